@@ -238,6 +238,7 @@ impl TestStateTtlAndRefresh {
             &metric_registry,
             Arc::clone(&notify_idle),
             &Handle::current(),
+            None,
         ));
         backend.add_policy(TtlPolicy::new(
             Arc::clone(&ttl_provider) as _,
@@ -287,6 +288,7 @@ impl TestStateLRUAndRefresh {
             &metric_registry,
             Arc::clone(&notify_idle),
             &Handle::current(),
+            None,
         ));
         let pool = Arc::new(ResourcePool::new(
             "my_pool",
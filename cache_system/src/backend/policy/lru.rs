@@ -146,6 +146,13 @@
 //! assert_eq!(pool.current(), RamSize(33));
 //! ```
 //!
+//! # Reload Cost
+//! Not all entries are equally expensive to reload once they were evicted. Catalog metadata can usually be re-fetched
+//! within milliseconds while object store data may take much longer. [`LruPolicy::new_with_reload_cost`] accepts a
+//! [`ReloadCostEstimator`] that assigns every entry a reload cost. For eviction purposes the entry is then treated as if
+//! it was used `reload_cost` later than it actually was, so cheap entries are evicted before expensive ones of the same
+//! age. [`LruPolicy::new`] uses a reload cost of zero, which results in plain LRU behavior.
+//!
 //! # Internals
 //! Here we describe the internals of the LRU cache system.
 //!
@@ -216,7 +223,7 @@
 //! State is held in the following structures:
 //!
 //! - `LruPolicyInner`: Holds [`CallbackHandle`] as well as an [`AddressableHeap`] to
-//!   memorize when entries were used for the last time.
+//!   memorize when entries were used for the last time (shifted by their reload cost).
 //! - `ResourcePoolInner`: Holds a reference to all pool members as well as the current consumption.
 //!
 //! All other structures and traits "only" act as glue.
@@ -279,6 +286,7 @@ use std::{
     hash::Hash,
     marker::PhantomData,
     sync::Arc,
+    time::Duration,
 };
 
 use iox_time::Time;
@@ -287,7 +295,9 @@ use parking_lot::{Mutex, MutexGuard};
 
 use crate::{
     addressable_heap::AddressableHeap,
-    resource_consumption::{Resource, ResourceEstimator},
+    resource_consumption::{
+        FunctionReloadCostEstimator, ReloadCostEstimator, Resource, ResourceEstimator,
+    },
 };
 
 use super::{CallbackHandle, ChangeRequest, Subscriber};
@@ -513,7 +523,8 @@ where
     V: Clone + Debug + Send + 'static,
     S: Resource,
 {
-    last_used: AddressableHeap<K, S, Time>,
+    /// "Last used" timestamp, shifted by the reload cost, as well as consumption and reload cost of every entry.
+    last_used: AddressableHeap<K, (S, Duration), Time>,
     metric_count: U64Gauge,
     metric_usage: U64Gauge,
     metric_evicted: U64Counter,
@@ -532,6 +543,7 @@ where
     inner: Arc<Mutex<LruPolicyInner<K, V, S>>>,
     pool: Arc<ResourcePool<S>>,
     resource_estimator: Arc<dyn ResourceEstimator<K = K, V = V, S = S>>,
+    reload_cost_estimator: Arc<dyn ReloadCostEstimator<K = K, V = V>>,
 }
 
 impl<K, V, S> LruPolicy<K, V, S>
@@ -552,6 +564,28 @@ where
         pool: Arc<ResourcePool<S>>,
         id: &'static str,
         resource_estimator: Arc<dyn ResourceEstimator<K = K, V = V, S = S>>,
+    ) -> impl FnOnce(CallbackHandle<K, V>) -> Self {
+        Self::new_with_reload_cost(
+            pool,
+            id,
+            resource_estimator,
+            Arc::new(FunctionReloadCostEstimator::<K, V>::new(|_k, _v| {
+                Duration::ZERO
+            })),
+        )
+    }
+
+    /// Create new backend w/o any known keys that weighs entries by their reload cost.
+    ///
+    /// See [module-level docs](self) for details on how the reload cost affects eviction.
+    ///
+    /// # Panic
+    /// Same as [`new`](Self::new).
+    pub fn new_with_reload_cost(
+        pool: Arc<ResourcePool<S>>,
+        id: &'static str,
+        resource_estimator: Arc<dyn ResourceEstimator<K = K, V = V, S = S>>,
+        reload_cost_estimator: Arc<dyn ReloadCostEstimator<K = K, V = V>>,
     ) -> impl FnOnce(CallbackHandle<K, V>) -> Self {
         let metric_count = pool
             .metric_registry
@@ -599,6 +633,7 @@ where
                 inner,
                 pool,
                 resource_estimator,
+                reload_cost_estimator,
             }
         }
     }
@@ -628,8 +663,12 @@ where
         let mut inner = self.inner.lock();
 
         // update "last used"
-        if let Some((consumption, _last_used)) = inner.last_used.remove(k) {
-            inner.last_used.insert(k.clone(), consumption, now);
+        if let Some(((consumption, reload_cost), _last_used)) = inner.last_used.remove(k) {
+            inner.last_used.insert(
+                k.clone(),
+                (consumption, reload_cost),
+                shift_by_reload_cost(now, reload_cost),
+            );
         }
 
         vec![]
@@ -643,6 +682,7 @@ where
     ) -> Vec<ChangeRequest<'static, Self::K, Self::V>> {
         // determine all attributes before getting any locks
         let consumption = self.resource_estimator.consumption(&k, &v);
+        let reload_cost = self.reload_cost_estimator.reload_cost(&k, &v);
 
         // get locks
         let mut pool = self.pool.inner.lock();
@@ -655,7 +695,7 @@ where
         // maybe clean from pool
         {
             let mut inner = self.inner.lock();
-            if let Some((consumption, _last_used)) = inner.last_used.remove(&k) {
+            if let Some(((consumption, _reload_cost), _last_used)) = inner.last_used.remove(&k) {
                 pool.remove(consumption);
                 inner.metric_count.dec(1);
                 inner.metric_usage.dec(consumption.into());
@@ -669,7 +709,11 @@ where
 
         // add new entry to inner backend AFTER adding it to the pool, so we are never overcommitting resources.
         let mut inner = self.inner.lock();
-        inner.last_used.insert(k, consumption, now);
+        inner.last_used.insert(
+            k,
+            (consumption, reload_cost),
+            shift_by_reload_cost(now, reload_cost),
+        );
         inner.metric_count.inc(1);
         inner.metric_usage.inc(consumption.into());
 
@@ -679,7 +723,7 @@ where
     fn remove(&mut self, k: &Self::K, _now: Time) -> Vec<ChangeRequest<'static, Self::K, Self::V>> {
         let mut inner = self.inner.lock();
 
-        if let Some((consumption, _last_used)) = inner.last_used.remove(k) {
+        if let Some(((consumption, _reload_cost), _last_used)) = inner.last_used.remove(k) {
             // only lock pool after we are sure that there is anything to do prevent lock contention
             let mut pool = self.pool.inner.lock();
 
@@ -745,7 +789,7 @@ trait PoolMemberGuard: Debug {
     type S;
 
     /// Check if this member has anything that could be removed. If so, return the "last used" timestamp of the oldest
-    /// entry.
+    /// entry, shifted by its reload cost.
     fn could_remove(&self) -> Option<Time>;
 
     /// Remove oldest entry and return consumption of the removed entry and an opaque [`ChangeRequest`].
@@ -791,7 +835,7 @@ where
     fn remove_oldest(&mut self) -> (Self::S, Box<dyn Any>) {
         let inner = self.inner.as_mut().expect("not yet finalized");
 
-        let (k, s, _t) = inner.last_used.pop().expect("nothing to remove");
+        let (k, (s, _reload_cost), _t) = inner.last_used.pop().expect("nothing to remove");
         inner.metric_count.dec(1);
         inner.metric_usage.dec(s.into());
         inner.metric_evicted.inc(1);
@@ -814,6 +858,11 @@ where
     }
 }
 
+/// Shift "last used" timestamp by reload cost so that expensive entries are evicted later.
+fn shift_by_reload_cost(now: Time, reload_cost: Duration) -> Time {
+    now.checked_add(reload_cost).unwrap_or(now)
+}
+
 fn downcast_change_requests<K, V>(requests: Vec<Box<dyn Any>>) -> Vec<ChangeRequest<'static, K, V>>
 where
     K: Clone + Eq + Hash + Ord + Debug + Send + 'static,
@@ -1231,6 +1280,73 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_reload_cost() {
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let pool = Arc::new(ResourcePool::new(
+            "pool",
+            TestSize(6),
+            Arc::new(metric::Registry::new()),
+        ));
+        let resource_estimator = Arc::new(TestResourceEstimator {});
+
+        // cheap member
+        let mut backend1 =
+            PolicyBackend::new(Box::new(HashMap::new()), Arc::clone(&time_provider) as _);
+        backend1.add_policy(LruPolicy::new(
+            Arc::clone(&pool),
+            "id1",
+            Arc::clone(&resource_estimator) as _,
+        ));
+
+        // expensive member
+        let mut backend2 =
+            PolicyBackend::new(Box::new(HashMap::new()), Arc::clone(&time_provider) as _);
+        backend2.add_policy(LruPolicy::new_with_reload_cost(
+            Arc::clone(&pool),
+            "id2",
+            Arc::clone(&resource_estimator) as _,
+            Arc::new(FunctionReloadCostEstimator::new(
+                |_k: &String, _v: &usize| Duration::from_millis(10),
+            )),
+        ));
+
+        backend2.set(String::from("a"), 2usize);
+
+        time_provider.inc(Duration::from_millis(1));
+        backend1.set(String::from("b"), 2usize);
+
+        time_provider.inc(Duration::from_millis(1));
+        backend1.set(String::from("c"), 2usize);
+        assert_eq!(pool.current().0, 6);
+
+        // "a" is the least recently used entry but is expensive to reload, so "b" gets evicted instead
+        time_provider.inc(Duration::from_millis(1));
+        backend1.set(String::from("d"), 2usize);
+        assert_eq!(pool.current().0, 6);
+        assert_inner_backend(
+            &mut backend1,
+            [(String::from("c"), 2), (String::from("d"), 2)],
+        );
+        assert_inner_backend(&mut backend2, [(String::from("a"), 2)]);
+
+        // once the cheap entries were used recently enough, the expensive entry is evicted
+        time_provider.inc(Duration::from_millis(20));
+        assert_eq!(backend1.get(&String::from("c")), Some(2usize));
+        assert_eq!(backend1.get(&String::from("d")), Some(2usize));
+        backend1.set(String::from("e"), 2usize);
+        assert_eq!(pool.current().0, 6);
+        assert_inner_backend(
+            &mut backend1,
+            [
+                (String::from("c"), 2),
+                (String::from("d"), 2),
+                (String::from("e"), 2),
+            ],
+        );
+        assert_inner_backend(&mut backend2, []);
+    }
+
     #[test]
     fn test_oversized_entries() {
         let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
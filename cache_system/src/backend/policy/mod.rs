@@ -18,6 +18,7 @@ use super::CacheBackend;
 pub mod lru;
 pub mod refresh;
 pub mod remove_if;
+pub mod snapshot;
 pub mod ttl;
 
 #[cfg(test)]
@@ -4,7 +4,7 @@ use std::{
 };
 
 use futures::{future::BoxFuture, stream::FuturesUnordered, FutureExt, StreamExt};
-use iox_time::Time;
+use iox_time::{Time, TimeProvider};
 use metric::U64Counter;
 use parking_lot::Mutex;
 use tokio::{
@@ -117,7 +117,24 @@ impl<K, V> RefreshDurationProvider for OptionalValueRefreshDurationProvider<K, V
     }
 }
 
+/// Configuration of refresh-ahead, see [`RefreshPolicy::new_with_refresh_ahead`].
+#[derive(Debug, Clone)]
+pub struct RefreshAheadConfig {
+    /// Time provider used to wait for the refresh time.
+    pub time_provider: Arc<dyn TimeProvider>,
+
+    /// Number of accesses to a key (between it being set and its refresh time) after which the
+    /// key is considered hot.
+    pub min_accesses: u64,
+}
+
 /// Cache policy that implements refreshing.
+///
+/// By default, a key is refreshed when it is accessed after its refresh time. With refresh-ahead
+/// (see [`new_with_refresh_ahead`](Self::new_with_refresh_ahead)), hot keys are additionally
+/// refreshed exactly at their refresh time, even if they are not accessed after that. This way
+/// frequently used keys are reloaded before they expire (e.g. due to a TTL policy) and readers do
+/// not have to wait for the loader.
 #[derive(Debug)]
 pub struct RefreshPolicy<K, V>
 where
@@ -128,6 +145,8 @@ where
     loader: Arc<dyn Loader<K = K, V = V, Extra = ()>>,
     callback_handle: Arc<Mutex<CallbackHandle<K, V>>>,
     metric_refreshed: U64Counter,
+    metric_refreshed_ahead: U64Counter,
+    refresh_ahead: Option<RefreshAheadConfig>,
     background_worker: JoinHandle<()>,
     timings: Arc<Mutex<HashMap<K, RefreshState>>>,
     tx_refresh_tasks: UnboundedSender<BoxFuture<'static, ()>>,
@@ -155,6 +174,33 @@ where
             metric_registry,
             idle_notify,
             handle,
+            None,
+        )
+    }
+
+    /// Create new refresh policy that also refreshes hot keys ahead of time.
+    ///
+    /// A key is hot if it was accessed at least [`RefreshAheadConfig::min_accesses`] times
+    /// between being set and its refresh time. The access count is reset every time the key is
+    /// set, so a key must stay hot to be refreshed again.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new_with_refresh_ahead(
+        refresh_duration_provider: Arc<dyn RefreshDurationProvider<K = K, V = V>>,
+        loader: Arc<dyn Loader<K = K, V = V, Extra = ()>>,
+        name: &'static str,
+        metric_registry: &metric::Registry,
+        handle: &Handle,
+        refresh_ahead: RefreshAheadConfig,
+    ) -> impl FnOnce(CallbackHandle<K, V>) -> Self {
+        let idle_notify = Arc::new(Notify::new());
+        Self::new_inner(
+            refresh_duration_provider,
+            loader,
+            name,
+            metric_registry,
+            idle_notify,
+            handle,
+            Some(refresh_ahead),
         )
     }
 
@@ -166,10 +212,17 @@ where
         metric_registry: &metric::Registry,
         idle_notify: Arc<Notify>,
         handle: &Handle,
+        refresh_ahead: Option<RefreshAheadConfig>,
     ) -> impl FnOnce(CallbackHandle<K, V>) -> Self {
         let metric_refreshed = metric_registry
             .register_metric::<U64Counter>("cache_refresh", "Number of cache refresh operations.")
             .recorder(&[("name", name)]);
+        let metric_refreshed_ahead = metric_registry
+            .register_metric::<U64Counter>(
+                "cache_refresh_ahead",
+                "Number of cache refresh operations that were started ahead of time for hot keys.",
+            )
+            .recorder(&[("name", name)]);
 
         // clone handle for callback
         let handle = handle.clone();
@@ -236,6 +289,8 @@ where
                 loader,
                 callback_handle,
                 metric_refreshed,
+                metric_refreshed_ahead,
+                refresh_ahead,
                 background_worker,
                 timings,
                 tx_refresh_tasks,
@@ -245,15 +300,41 @@ where
 
     /// Start refresh task for given key and return cancelation token for the task.
     ///
+    /// If `at` is given, the task waits until this time before it starts loading (refresh-ahead).
+    ///
     /// You shall store the given token in [`RefreshState`].
     #[must_use]
-    fn refresh(&self, k: K) -> CancellationToken {
+    fn refresh(&self, k: K, at: Option<Time>) -> CancellationToken {
         let loader = Arc::clone(&self.loader);
         let callback_handle = Arc::clone(&self.callback_handle);
         let cancelled = CancellationToken::default();
 
+        let wait = match at {
+            Some(at) => {
+                let time_provider = &self
+                    .refresh_ahead
+                    .as_ref()
+                    .expect("refresh-ahead configured")
+                    .time_provider;
+                let metric_refreshed_ahead = self.metric_refreshed_ahead.clone();
+                let sleep = time_provider.sleep_until(at);
+                Some(async move {
+                    sleep.await;
+                    metric_refreshed_ahead.inc(1);
+                })
+            }
+            None => {
+                self.metric_refreshed.inc(1);
+                None
+            }
+        };
+
         let cancelled_captured = cancelled.clone();
         let fut = async move {
+            if let Some(wait) = wait {
+                wait.await;
+            }
+
             // some `let`-dance so that rustc does not complain that `&K` is not `Send`
             let k_for_loader = k.clone();
             let v = loader.load(k_for_loader, ()).await;
@@ -287,7 +368,6 @@ where
             .map_err(|_| ())
             .expect("background worker alive");
 
-        self.metric_refreshed.inc(1);
         cancelled
     }
 }
@@ -317,11 +397,18 @@ where
         if let Some(RefreshState {
             t,
             running_refresh: running_refresh @ None,
+            accesses,
         }) = timings.get_mut(k)
         {
             // Is it time to refresh?
             if *t <= now {
-                *running_refresh = Some(self.refresh(k.clone()));
+                *running_refresh = Some(self.refresh(k.clone(), None));
+            } else if let Some(refresh_ahead) = &self.refresh_ahead {
+                // Is the key hot enough to be refreshed ahead of time?
+                *accesses += 1;
+                if *accesses >= refresh_ahead.min_accesses {
+                    *running_refresh = Some(self.refresh(k.clone(), Some(*t)));
+                }
             }
         }
 
@@ -343,6 +430,7 @@ where
             let state = RefreshState {
                 t,
                 running_refresh: None,
+                accesses: 0,
             };
 
             timings.insert(k, state);
@@ -377,6 +465,9 @@ struct RefreshState {
     ///
     /// This token will be triggered on [`drop`](Drop::drop).
     running_refresh: Option<CancellationToken>,
+
+    /// Number of accesses since the entry was set, only tracked for refresh-ahead.
+    accesses: u64,
 }
 
 impl Drop for RefreshState {
@@ -923,6 +1014,92 @@ mod tests {
         assert_eq!(backend.get(&1), Some(String::from("b")));
     }
 
+    #[tokio::test]
+    async fn test_refresh_ahead() {
+        let TestState {
+            mut backend,
+            refresh_duration_provider,
+            time_provider,
+            loader,
+            metric_registry,
+            notify_idle,
+            ..
+        } = TestState::new_with_refresh_ahead();
+
+        loader.mock_next(1, String::from("foo"));
+        for v in ["a", "b", "foo"] {
+            refresh_duration_provider.set_refresh_in(
+                1,
+                String::from(v),
+                Some(Duration::from_secs(1)),
+            );
+            refresh_duration_provider.set_refresh_in(
+                2,
+                String::from(v),
+                Some(Duration::from_secs(1)),
+            );
+        }
+        backend.set(1, String::from("a"));
+        backend.set(2, String::from("b"));
+
+        // key 1 is hot, key 2 is not
+        assert_eq!(backend.get(&1), Some(String::from("a")));
+        assert_eq!(backend.get(&1), Some(String::from("a")));
+        assert_eq!(backend.get(&2), Some(String::from("b")));
+        notify_idle.notified_with_timeout().await;
+        assert_eq!(get_refresh_ahead_metric(&metric_registry), 0);
+
+        // hot key is refreshed without being accessed again, the cold one is left alone
+        time_provider.inc(Duration::from_secs(1));
+        notify_idle.notified_with_timeout().await;
+        assert_eq!(get_refresh_ahead_metric(&metric_registry), 1);
+        assert_eq!(get_refresh_metric(&metric_registry), 0);
+        assert_eq!(backend.get(&1), Some(String::from("foo")));
+
+        // access count was reset by the refresh, so one access is not enough to be hot again
+        time_provider.inc(Duration::from_secs(1));
+        notify_idle.not_notified().await;
+        assert_eq!(get_refresh_ahead_metric(&metric_registry), 1);
+
+        // cold key is still refreshed lazily
+        loader.mock_next(2, String::from("foo"));
+        assert_eq!(backend.get(&2), Some(String::from("b")));
+        notify_idle.notified_with_timeout().await;
+        assert_eq!(get_refresh_metric(&metric_registry), 1);
+        assert_eq!(backend.get(&2), Some(String::from("foo")));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_ahead_cancelled_by_set() {
+        let TestState {
+            mut backend,
+            refresh_duration_provider,
+            time_provider,
+            metric_registry,
+            notify_idle,
+            ..
+        } = TestState::new_with_refresh_ahead();
+
+        refresh_duration_provider.set_refresh_in(
+            1,
+            String::from("a"),
+            Some(Duration::from_secs(1)),
+        );
+        refresh_duration_provider.set_refresh_in(1, String::from("b"), None);
+        backend.set(1, String::from("a"));
+        assert_eq!(backend.get(&1), Some(String::from("a")));
+        assert_eq!(backend.get(&1), Some(String::from("a")));
+
+        // a manual update cancels the scheduled refresh
+        backend.set(1, String::from("b"));
+        notify_idle.notified_with_timeout().await;
+
+        time_provider.inc(Duration::from_secs(1));
+        notify_idle.not_notified().await;
+        assert_eq!(get_refresh_ahead_metric(&metric_registry), 0);
+        assert_eq!(backend.get(&1), Some(String::from("b")));
+    }
+
     #[tokio::test]
     async fn test_generic_backend() {
         use crate::backend::test_util::test_generic;
@@ -957,6 +1134,15 @@ mod tests {
 
     impl TestState {
         fn new() -> Self {
+            Self::new_inner(false)
+        }
+
+        /// Refresh-ahead with a threshold of 2 accesses.
+        fn new_with_refresh_ahead() -> Self {
+            Self::new_inner(true)
+        }
+
+        fn new_inner(refresh_ahead: bool) -> Self {
             let refresh_duration_provider = Arc::new(TestRefreshDurationProvider::new());
             let time_provider = Arc::new(MockProvider::new(Time::MIN));
             let metric_registry = metric::Registry::new();
@@ -974,6 +1160,10 @@ mod tests {
                 &metric_registry,
                 Arc::clone(&notify_idle),
                 &Handle::current(),
+                refresh_ahead.then(|| RefreshAheadConfig {
+                    time_provider: Arc::clone(&time_provider) as _,
+                    min_accesses: 2,
+                }),
             ));
 
             Self {
@@ -988,10 +1178,18 @@ mod tests {
     }
 
     fn get_refresh_metric(metric_registry: &metric::Registry) -> u64 {
+        get_counter(metric_registry, "cache_refresh")
+    }
+
+    fn get_refresh_ahead_metric(metric_registry: &metric::Registry) -> u64 {
+        get_counter(metric_registry, "cache_refresh_ahead")
+    }
+
+    fn get_counter(metric_registry: &metric::Registry, name: &'static str) -> u64 {
         let mut reporter = RawReporter::default();
         metric_registry.report(&mut reporter);
         let observation = reporter
-            .metric("cache_refresh")
+            .metric(name)
             .unwrap()
             .observation(&[("name", "my_cache")])
             .unwrap();
@@ -0,0 +1,154 @@
+//! Policy that tracks cache entries for snapshots.
+use parking_lot::Mutex;
+use std::{collections::HashMap, fmt::Debug, hash::Hash, path::Path, sync::Arc};
+
+use crate::snapshot::{write_snapshot, CacheSerde};
+
+use super::{CallbackHandle, ChangeRequest, Subscriber};
+
+/// Tracks all cache entries so that they can be written to a [snapshot](crate::snapshot).
+///
+/// Entries are cloned when they are set, so this policy should only be used for caches with cheaply clonable values
+/// (e.g. values wrapped into an [`Arc`]).
+#[derive(Debug)]
+pub struct SnapshotPolicy<K, V>
+where
+    K: Clone + Eq + Debug + Hash + Ord + Send + 'static,
+    V: Clone + Debug + Send + 'static,
+{
+    entries: Arc<Mutex<HashMap<K, V>>>,
+}
+
+impl<K, V> SnapshotPolicy<K, V>
+where
+    K: Clone + Eq + Debug + Hash + Ord + Send + 'static,
+    V: Clone + Debug + Send + 'static,
+{
+    /// Create new policy.
+    ///
+    /// This returns the policy constructor which shall be pass to [`PolicyBackend::add_policy`] and handle that can
+    /// be used to write snapshots.
+    ///
+    /// # Panic
+    /// The policy constructor will panic if the inner backend is not empty.
+    ///
+    /// [`PolicyBackend::add_policy`]: super::PolicyBackend::add_policy
+    pub fn create_constructor_and_handle() -> (
+        impl FnOnce(CallbackHandle<K, V>) -> Self,
+        SnapshotHandle<K, V>,
+    ) {
+        let entries: Arc<Mutex<HashMap<K, V>>> = Default::default();
+        let handle = SnapshotHandle {
+            entries: Arc::clone(&entries),
+        };
+
+        let policy_constructor = move |mut callback_handle: CallbackHandle<K, V>| {
+            callback_handle.execute_requests(vec![ChangeRequest::ensure_empty()]);
+            Self { entries }
+        };
+
+        (policy_constructor, handle)
+    }
+}
+
+impl<K, V> Subscriber for SnapshotPolicy<K, V>
+where
+    K: Clone + Eq + Debug + Hash + Ord + Send + 'static,
+    V: Clone + Debug + Send + 'static,
+{
+    type K = K;
+    type V = V;
+
+    fn set(
+        &mut self,
+        k: Self::K,
+        v: Self::V,
+        _now: iox_time::Time,
+    ) -> Vec<ChangeRequest<'static, Self::K, Self::V>> {
+        self.entries.lock().insert(k, v);
+        vec![]
+    }
+
+    fn remove(
+        &mut self,
+        k: &Self::K,
+        _now: iox_time::Time,
+    ) -> Vec<ChangeRequest<'static, Self::K, Self::V>> {
+        self.entries.lock().remove(k);
+        vec![]
+    }
+}
+
+/// Handle created by [`SnapshotPolicy`] that can be used to write snapshots.
+///
+/// The handle can be cloned freely. All clones will refer to the same underlying backend.
+#[derive(Debug, Clone)]
+pub struct SnapshotHandle<K, V>
+where
+    K: Clone + Eq + Debug + Hash + Ord + Send + 'static,
+    V: Clone + Debug + Send + 'static,
+{
+    entries: Arc<Mutex<HashMap<K, V>>>,
+}
+
+impl<K, V> SnapshotHandle<K, V>
+where
+    K: CacheSerde + Clone + Eq + Debug + Hash + Ord + Send + 'static,
+    V: CacheSerde + Clone + Debug + Send + 'static,
+{
+    /// Write all current cache entries to a snapshot file at `path`.
+    ///
+    /// Returns the number of written entries.
+    pub fn write(&self, path: &Path) -> std::io::Result<usize> {
+        // clone entries so that we do not block the cache while writing to disk
+        let entries = self.entries.lock().clone();
+        write_snapshot(path, &entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iox_time::{MockProvider, Time};
+
+    use crate::{backend::policy::PolicyBackend, snapshot::Snapshot};
+
+    use super::*;
+
+    #[test]
+    fn test_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snapshot");
+
+        let time_provider = Arc::new(MockProvider::new(Time::MIN));
+        let mut backend =
+            PolicyBackend::new(Box::new(HashMap::<u64, String>::new()), time_provider);
+        let (constructor, handle) = SnapshotPolicy::create_constructor_and_handle();
+        backend.add_policy(constructor);
+
+        backend.set(1, String::from("a"));
+        backend.set(2, String::from("b"));
+        backend.set(1, String::from("c"));
+        backend.set(3, String::from("d"));
+        backend.remove(&3);
+
+        assert_eq!(handle.write(&path).unwrap(), 2);
+
+        let snapshot = Snapshot::<u64, String>::read(&path);
+        assert_eq!(snapshot.take(&1), Some(String::from("c")));
+        assert_eq!(snapshot.take(&2), Some(String::from("b")));
+        assert!(snapshot.is_empty());
+    }
+
+    #[test]
+    fn test_generic_backend() {
+        use crate::backend::test_util::test_generic;
+
+        test_generic(|| {
+            let time_provider = Arc::new(MockProvider::new(Time::MIN));
+            let mut backend = PolicyBackend::new(Box::new(HashMap::new()), time_provider);
+            let (constructor, _handle) = SnapshotPolicy::create_constructor_and_handle();
+            backend.add_policy(constructor);
+            backend
+        });
+    }
+}
@@ -1,5 +1,12 @@
 //! Time-to-live handling.
-use std::{fmt::Debug, hash::Hash, marker::PhantomData, sync::Arc, time::Duration};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    sync::Arc,
+    time::Duration,
+};
 
 use iox_time::Time;
 use metric::U64Counter;
@@ -112,6 +119,11 @@ impl<K, V> TtlProvider for OptionalValueTtlProvider<K, V> {
 /// Every method ([`get`](Subscriber::get), [`set`](Subscriber::set), [`remove`](Subscriber::remove)) causes the
 /// cache to check for expired keys. This may lead to certain delays, esp. when dropping the contained values takes a
 /// long time.
+///
+/// # Jitter
+/// Entries that are loaded together (e.g. right after startup) would also expire together and then
+/// cause a burst of reloads. A policy created via [`new_with_jitter`](Self::new_with_jitter)
+/// prolongs every TTL by a pseudo-random fraction of itself to spread the expiration out.
 #[derive(Debug)]
 pub struct TtlPolicy<K, V>
 where
@@ -119,6 +131,7 @@ where
     V: Clone + Debug + Send + 'static,
 {
     ttl_provider: Arc<dyn TtlProvider<K = K, V = V>>,
+    jitter: f64,
     expiration: AddressableHeap<K, (), Time>,
     metric_expired: U64Counter,
 }
@@ -134,6 +147,26 @@ where
         name: &'static str,
         metric_registry: &metric::Registry,
     ) -> impl FnOnce(CallbackHandle<K, V>) -> Self {
+        Self::new_with_jitter(ttl_provider, 0.0, name, metric_registry)
+    }
+
+    /// Create new TTL policy that prolongs every TTL by up to `jitter` times the TTL.
+    ///
+    /// E.g. a jitter of `0.1` turns a TTL of 10 minutes into a TTL between 10 and 11 minutes.
+    ///
+    /// # Panic
+    /// Panics if `jitter` is not within `[0, 1]`.
+    pub fn new_with_jitter(
+        ttl_provider: Arc<dyn TtlProvider<K = K, V = V>>,
+        jitter: f64,
+        name: &'static str,
+        metric_registry: &metric::Registry,
+    ) -> impl FnOnce(CallbackHandle<K, V>) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&jitter),
+            "jitter must be within [0, 1] but is {jitter}"
+        );
+
         let metric_expired = metric_registry
             .register_metric::<U64Counter>(
                 "cache_ttl_expired",
@@ -146,6 +179,7 @@ where
 
             Self {
                 ttl_provider,
+                jitter,
                 expiration: Default::default(),
                 metric_expired,
             }
@@ -168,6 +202,24 @@ where
 
         requests
     }
+
+    /// Prolong `ttl` by a fraction of up to `jitter` of itself.
+    ///
+    /// The fraction is derived from the key and the current time, so entries that are set at the
+    /// same time get different TTLs, and so does the same key when it is set again later.
+    fn jittered(&self, ttl: Duration, k: &K, now: Time) -> Duration {
+        if self.jitter == 0.0 || ttl.is_zero() {
+            return ttl;
+        }
+
+        let mut hasher = DefaultHasher::new();
+        k.hash(&mut hasher);
+        now.hash(&mut hasher);
+        let fraction = (hasher.finish() as f64 / u64::MAX as f64) * self.jitter;
+
+        ttl.checked_add(ttl.mul_f64(fraction))
+            .unwrap_or(Duration::MAX)
+    }
 }
 
 impl<K, V> Subscriber for TtlPolicy<K, V>
@@ -191,6 +243,7 @@ where
         let mut requests = self.evict_expired(now);
 
         if let Some(ttl) = self.ttl_provider.expires_in(&k, &v) {
+            let ttl = self.jittered(ttl, &k, now);
             if ttl.is_zero() {
                 requests.push(ChangeRequest::remove(k.clone()));
             }
@@ -360,6 +413,62 @@ mod tests {
         assert_eq!(get_expired_metric(&metric_registry), 1);
     }
 
+    #[test]
+    #[should_panic(expected = "jitter must be within [0, 1] but is 1.5")]
+    fn test_panic_invalid_jitter() {
+        let ttl_provider = Arc::new(TestTtlProvider::new());
+        let metric_registry = metric::Registry::new();
+
+        let time_provider = Arc::new(MockProvider::new(Time::MIN));
+        let mut backend = PolicyBackend::new(Box::new(HashMap::<u8, String>::new()), time_provider);
+        backend.add_policy(TtlPolicy::new_with_jitter(
+            Arc::clone(&ttl_provider) as _,
+            1.5,
+            "my_cache",
+            &metric_registry,
+        ));
+    }
+
+    #[test]
+    fn test_jitter_spreads_expiration() {
+        let ttl_provider = Arc::new(TestTtlProvider::new());
+        let metric_registry = metric::Registry::new();
+
+        let time_provider = Arc::new(MockProvider::new(Time::MIN));
+        let mut backend = PolicyBackend::new(
+            Box::new(HashMap::<u8, String>::new()),
+            Arc::clone(&time_provider) as _,
+        );
+        backend.add_policy(TtlPolicy::new_with_jitter(
+            Arc::clone(&ttl_provider) as _,
+            0.5,
+            "my_cache",
+            &metric_registry,
+        ));
+
+        for k in 0..100 {
+            ttl_provider.set_expires_in(k, String::from("a"), Some(Duration::from_secs(100)));
+            backend.set(k, String::from("a"));
+        }
+
+        // nothing expires before the TTL
+        time_provider.inc(Duration::from_millis(99_999));
+        assert_eq!(get_expired_metric(&metric_registry), 0);
+        assert!((0..100).all(|k| backend.get(&k).is_some()));
+
+        // the entries expire at different times
+        time_provider.inc(Duration::from_secs(25));
+        backend.get(&0);
+        let expired = get_expired_metric(&metric_registry);
+        assert!(expired > 0);
+        assert!(expired < 100);
+
+        // ...but all of them within the jitter
+        time_provider.inc(Duration::from_secs(26));
+        backend.get(&0);
+        assert_eq!(get_expired_metric(&metric_registry), 100);
+    }
+
     #[test]
     fn test_overflow_expire() {
         let ttl_provider = Arc::new(TestTtlProvider::new());
@@ -6,9 +6,17 @@ use futures::{
     future::{BoxFuture, Shared},
     FutureExt, TryFutureExt,
 };
+use metric::{U64Counter, U64Gauge};
 use observability_deps::tracing::debug;
 use parking_lot::Mutex;
-use std::{collections::HashMap, fmt::Debug, sync::Arc};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 use tokio::{
     sync::oneshot::{error::RecvError, Sender},
     task::JoinHandle,
@@ -16,6 +24,61 @@ use tokio::{
 
 use super::{Cache, CacheGetStatus, CachePeekStatus};
 
+/// Metrics about the deduplication of concurrent loads.
+#[derive(Debug, Default)]
+struct DriverMetrics {
+    /// Requests that joined an already running load instead of starting a new one.
+    coalesced: U64Counter,
+
+    /// Requests that currently wait for a running load.
+    waiting: U64Gauge,
+
+    /// Requests that were cancelled while waiting for a running load. The load itself continues.
+    waiter_cancelled: U64Counter,
+
+    /// Loads that were cancelled before their result could be stored, e.g. because the value was side-loaded via
+    /// [`Cache::set`] or because the cache was dropped.
+    load_cancelled: U64Counter,
+}
+
+impl DriverMetrics {
+    fn new(name: &'static str, metric_registry: &metric::Registry) -> Self {
+        let attributes = &[("name", name)];
+
+        let coalesced = metric_registry
+            .register_metric::<U64Counter>(
+                "cache_load_coalesced",
+                "Number of cache requests that joined an already running load.",
+            )
+            .recorder(attributes);
+        let waiting = metric_registry
+            .register_metric::<U64Gauge>(
+                "cache_load_waiting",
+                "Number of cache requests that currently wait for a running load.",
+            )
+            .recorder(attributes);
+        let waiter_cancelled = metric_registry
+            .register_metric::<U64Counter>(
+                "cache_load_waiter_cancelled",
+                "Number of cache requests that were cancelled while waiting for a running load.",
+            )
+            .recorder(attributes);
+        let load_cancelled = metric_registry
+            .register_metric::<U64Counter>(
+                "cache_load_cancelled",
+                "Number of cache loads that were cancelled before their result could be stored.",
+            )
+            .recorder(attributes);
+
+        Self {
+            coalesced,
+            waiting,
+            waiter_cancelled,
+            load_cancelled,
+        }
+    }
+}
+
 /// Combine a [`CacheBackend`] and a [`Loader`] into a single [`Cache`]
 #[derive(Debug)]
 pub struct CacheDriver<B, GetExtra>
@@ -25,6 +88,7 @@ where
 {
     state: Arc<Mutex<CacheState<B>>>,
     loader: Arc<dyn Loader<K = B::K, V = B::V, Extra = GetExtra>>,
+    metrics: Arc<DriverMetrics>,
 }
 
 impl<B, GetExtra> CacheDriver<B, GetExtra>
@@ -34,6 +98,24 @@ where
 {
     /// Create new, empty cache with given loader function.
     pub fn new(loader: Arc<dyn Loader<K = B::K, V = B::V, Extra = GetExtra>>, backend: B) -> Self {
+        Self::new_inner(loader, backend, DriverMetrics::default())
+    }
+
+    /// Create new, empty cache with given loader function that reports metrics about load deduplication.
+    pub fn new_with_metrics(
+        loader: Arc<dyn Loader<K = B::K, V = B::V, Extra = GetExtra>>,
+        backend: B,
+        name: &'static str,
+        metric_registry: &metric::Registry,
+    ) -> Self {
+        Self::new_inner(loader, backend, DriverMetrics::new(name, metric_registry))
+    }
+
+    fn new_inner(
+        loader: Arc<dyn Loader<K = B::K, V = B::V, Extra = GetExtra>>,
+        backend: B,
+        metrics: DriverMetrics,
+    ) -> Self {
         Self {
             state: Arc::new(Mutex::new(CacheState {
                 cached_entries: backend,
@@ -41,8 +123,25 @@ where
                 tag_counter: 0,
             })),
             loader,
+            metrics: Arc::new(metrics),
         }
     }
+
+    /// Number of loads that are currently running.
+    pub fn running_loads(&self) -> usize {
+        self.state.lock().running_queries.len()
+    }
+
+    /// Number of requests that currently wait for the running load of the given key.
+    ///
+    /// Returns `None` if there is no load running for this key.
+    pub fn waiters(&self, k: &B::K) -> Option<usize> {
+        self.state
+            .lock()
+            .running_queries
+            .get(k)
+            .map(|running_query| running_query.waiters.load(Ordering::SeqCst))
+    }
 }
 
 #[async_trait]
@@ -63,7 +162,7 @@ where
     ) -> (Self::V, CacheGetStatus) {
         // place state locking into its own scope so it doesn't leak into the generator (async
         // function)
-        let (receiver, status) = {
+        let (receiver, waiter, status) = {
             let mut state = self.state.lock();
 
             // check if the entry has already been cached
@@ -73,8 +172,10 @@ where
 
             // check if there is already a query for this key running
             if let Some(running_query) = state.running_queries.get(&k) {
+                self.metrics.coalesced.inc(1);
                 (
                     running_query.recv.clone(),
+                    Waiter::new(&running_query.waiters, &self.metrics),
                     CacheGetStatus::MissAlreadyLoading,
                 )
             } else {
@@ -95,6 +196,7 @@ where
                 // this very request is cancelled
                 let state_captured = Arc::clone(&self.state);
                 let loader = Arc::clone(&self.loader);
+                let metrics = Arc::clone(&self.metrics);
                 let k_captured = k.clone();
                 let handle = tokio::spawn(async move {
                     let loader_fut = async move {
@@ -129,6 +231,7 @@ where
                                     // data get side-loaded via `Cache::set`. In this case, we do
                                     // NOT modify the state because there would be a lock-gap. The
                                     // `set` function will do that for us instead.
+                                    metrics.load_cancelled.inc(1);
                                     v
                                 }
                                 Err(_) => {
@@ -148,6 +251,8 @@ where
                     tx_main.send(v).ok();
                 });
 
+                let waiters = Arc::new(AtomicUsize::new(0));
+                let waiter = Waiter::new(&waiters, &self.metrics);
                 state.running_queries.insert(
                     k,
                     RunningQuery {
@@ -155,13 +260,15 @@ where
                         set: tx_set,
                         join_handle: handle,
                         tag,
+                        waiters,
                     },
                 );
-                (receiver, CacheGetStatus::Miss)
+                (receiver, waiter, CacheGetStatus::Miss)
             }
         };
 
         let v = retrieve_from_shared(receiver).await;
+        waiter.finish();
 
         (v, status)
     }
@@ -173,7 +280,7 @@ where
     ) -> Option<(Self::V, CachePeekStatus)> {
         // place state locking into its own scope so it doesn't leak into the generator (async
         // function)
-        let (receiver, status) = {
+        let (receiver, waiter, status) = {
             let mut state = self.state.lock();
 
             // check if the entry has already been cached
@@ -183,8 +290,10 @@ where
 
             // check if there is already a query for this key running
             if let Some(running_query) = state.running_queries.get(&k) {
+                self.metrics.coalesced.inc(1);
                 (
                     running_query.recv.clone(),
+                    Waiter::new(&running_query.waiters, &self.metrics),
                     CachePeekStatus::MissAlreadyLoading,
                 )
             } else {
@@ -193,10 +302,15 @@ where
         };
 
         let v = retrieve_from_shared(receiver).await;
+        waiter.finish();
 
         Some((v, status))
     }
 
+    fn get_if_cached(&self, k: &Self::K) -> Option<Self::V> {
+        self.state.lock().cached_entries.get(k)
+    }
+
     async fn set(&self, k: Self::K, v: Self::V) {
         let maybe_join_handle = {
             let mut state = self.state.lock();
@@ -239,6 +353,7 @@ where
             // cancel the contained future which in turn will drop the sender of the oneshot
             // channel. The receivers will be notified.
             running_query.join_handle.abort();
+            self.metrics.load_cancelled.inc(1);
         }
     }
 }
@@ -320,6 +435,46 @@ where
     }
 }
 
+/// Tracks a request that waits for a running load.
+///
+/// If the request is cancelled (i.e. this guard is dropped without being [finished](Self::finish)), this is recorded
+/// as a cancelled waiter. The load itself continues to run.
+struct Waiter {
+    waiters: Arc<AtomicUsize>,
+    metrics: Arc<DriverMetrics>,
+    finished: bool,
+}
+
+impl Waiter {
+    fn new(waiters: &Arc<AtomicUsize>, metrics: &Arc<DriverMetrics>) -> Self {
+        waiters.fetch_add(1, Ordering::SeqCst);
+        metrics.waiting.inc(1);
+
+        Self {
+            waiters: Arc::clone(waiters),
+            metrics: Arc::clone(metrics),
+            finished: false,
+        }
+    }
+
+    /// Mark request as finished.
+    fn finish(mut self) {
+        self.finished = true;
+    }
+}
+
+impl Drop for Waiter {
+    fn drop(&mut self) {
+        self.waiters.fetch_sub(1, Ordering::SeqCst);
+        self.metrics.waiting.dec(1);
+
+        // a panicking loader is not a cancellation
+        if !self.finished && !std::thread::panicking() {
+            self.metrics.waiter_cancelled.inc(1);
+        }
+    }
+}
+
 /// A [`tokio::sync::oneshot::Receiver`] that can be cloned.
 ///
 /// The types are:
@@ -362,6 +517,9 @@ struct RunningQuery<V> {
     /// Tag so that queries for the same key (e.g. when starting, side-loading, starting again) can
     /// be told apart.
     tag: u64,
+
+    /// Number of requests that wait for this query.
+    waiters: Arc<AtomicUsize>,
 }
 
 /// Inner cache state that is usually guarded by a lock.
@@ -386,7 +544,12 @@ where
 mod tests {
     use std::sync::Arc;
 
-    use crate::cache::test_util::{run_test_generic, TestAdapter, TestLoader};
+    use metric::{Attributes, Metric};
+    use tokio::sync::Barrier;
+
+    use crate::cache::test_util::{
+        run_test_generic, AbortAndWaitExt, EnsurePendingExt, TestAdapter, TestLoader,
+    };
 
     use super::*;
 
@@ -395,6 +558,97 @@ mod tests {
         run_test_generic(MyTestAdapter).await;
     }
 
+    #[tokio::test]
+    async fn test_deduplication_metrics() {
+        let metric_registry = metric::Registry::new();
+        let loader = Arc::new(TestLoader::default());
+        let cache = Arc::new(CacheDriver::new_with_metrics(
+            Arc::clone(&loader) as _,
+            HashMap::new(),
+            "my_cache",
+            &metric_registry,
+        ));
+
+        loader.block();
+        let handle_1 = spawn_pending_get(&cache, 1).await;
+        let handle_2 = spawn_pending_get(&cache, 1).await;
+        let handle_3 = spawn_pending_get(&cache, 1).await;
+        assert_eq!(cache.running_loads(), 1);
+        assert_eq!(cache.waiters(&1), Some(3));
+        assert_eq!(cache.waiters(&2), None);
+        assert_eq!(get_counter(&metric_registry, "cache_load_coalesced"), 2);
+        assert_eq!(get_gauge(&metric_registry, "cache_load_waiting"), 3);
+
+        // value is loading but not cached yet
+        assert_eq!(cache.get_if_cached(&1), None);
+
+        // cancelling a waiter does not cancel the load
+        handle_1.abort_and_wait().await;
+        assert_eq!(cache.waiters(&1), Some(2));
+        assert_eq!(get_gauge(&metric_registry, "cache_load_waiting"), 2);
+        assert_eq!(
+            get_counter(&metric_registry, "cache_load_waiter_cancelled"),
+            1
+        );
+
+        assert_eq!(loader.unblock(), 1);
+        assert_eq!(handle_2.await.unwrap(), String::from("1_true"));
+        assert_eq!(handle_3.await.unwrap(), String::from("1_true"));
+        assert_eq!(cache.running_loads(), 0);
+        assert_eq!(cache.waiters(&1), None);
+        assert_eq!(get_gauge(&metric_registry, "cache_load_waiting"), 0);
+        assert_eq!(cache.get_if_cached(&1), Some(String::from("1_true")));
+        assert_eq!(loader.loaded(), vec![1]);
+
+        // side-loading cancels the running load
+        loader.block();
+        let handle = spawn_pending_get(&cache, 2).await;
+        cache.set(2, String::from("foo")).await;
+        assert_eq!(handle.await.unwrap(), String::from("foo"));
+        assert_eq!(get_counter(&metric_registry, "cache_load_cancelled"), 1);
+        assert_eq!(
+            get_counter(&metric_registry, "cache_load_waiter_cancelled"),
+            1
+        );
+        assert_eq!(get_counter(&metric_registry, "cache_load_coalesced"), 2);
+    }
+
+    /// Spawn GET request and wait until it is pending.
+    async fn spawn_pending_get(
+        cache: &Arc<CacheDriver<HashMap<u8, String>, bool>>,
+        k: u8,
+    ) -> JoinHandle<String> {
+        let barrier = Arc::new(Barrier::new(2));
+        let barrier_captured = Arc::clone(&barrier);
+        let cache_captured = Arc::clone(cache);
+        let handle = tokio::spawn(async move {
+            cache_captured
+                .get(k, true)
+                .ensure_pending(barrier_captured)
+                .await
+        });
+        barrier.wait().await;
+        handle
+    }
+
+    fn get_counter(metric_registry: &metric::Registry, name: &'static str) -> u64 {
+        metric_registry
+            .get_instrument::<Metric<U64Counter>>(name)
+            .expect("failed to read metric")
+            .get_observer(&Attributes::from(&[("name", "my_cache")]))
+            .expect("failed to get observer")
+            .fetch()
+    }
+
+    fn get_gauge(metric_registry: &metric::Registry, name: &'static str) -> u64 {
+        metric_registry
+            .get_instrument::<Metric<U64Gauge>>(name)
+            .expect("failed to read metric")
+            .get_observer(&Attributes::from(&[("name", "my_cache")]))
+            .expect("failed to get observer")
+            .fetch()
+    }
+
     struct MyTestAdapter;
 
     impl TestAdapter for MyTestAdapter {
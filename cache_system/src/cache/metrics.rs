@@ -146,6 +146,10 @@ where
         res
     }
 
+    fn get_if_cached(&self, k: &Self::K) -> Option<Self::V> {
+        self.inner.get_if_cached(k)
+    }
+
     async fn set(&self, k: Self::K, v: Self::V) {
         self.inner.set(k, v).await;
         self.metrics.metric_set.inc(1);
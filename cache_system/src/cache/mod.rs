@@ -125,6 +125,12 @@ pub trait Cache: Debug + Send + Sync + 'static {
         extra: Self::PeekExtra,
     ) -> Option<(Self::V, CachePeekStatus)>;
 
+    /// Get value from cache if it is already stored.
+    ///
+    /// In contrast to [`peek`](Self::peek) this will NOT wait for a loading task that is already in progress. It also
+    /// does not start a new loading task.
+    fn get_if_cached(&self, k: &Self::K) -> Option<Self::V>;
+
     /// Side-load an entry into the cache.
     ///
     /// This will also complete a currently running request for this key.
@@ -161,6 +167,10 @@ where
         self.as_ref().peek_with_status(k, extra).await
     }
 
+    fn get_if_cached(&self, k: &Self::K) -> Option<Self::V> {
+        self.as_ref().get_if_cached(k)
+    }
+
     async fn set(&self, k: Self::K, v: Self::V) {
         self.as_ref().set(k, v).await
     }
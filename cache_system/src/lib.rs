@@ -14,3 +14,4 @@ pub mod backend;
 pub mod cache;
 pub mod loader;
 pub mod resource_consumption;
+pub mod snapshot;
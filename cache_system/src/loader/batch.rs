@@ -0,0 +1,243 @@
+//! Coalesce concurrent loads into batches.
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use std::{hash::Hash, sync::Arc, time::Duration};
+use tokio::{runtime::Handle, sync::oneshot};
+
+use super::Loader;
+
+/// Loader that can load many keys at once, e.g. using a single catalog query.
+///
+/// Use [`BatchLoaderAdapter`] to use it as a regular [`Loader`].
+#[async_trait]
+pub trait BatchLoader: std::fmt::Debug + Send + Sync + 'static {
+    /// Cache key.
+    type K: Hash + Send + 'static;
+
+    /// Extra data needed when loading a missing entry. Specify `()` if not needed.
+    type Extra: Send + 'static;
+
+    /// Cache value.
+    type V: Send + 'static;
+
+    /// Load values for the given keys, using the extra data if needed.
+    ///
+    /// The result MUST contain exactly one value per key, in the same order as the input.
+    async fn load_batch(&self, keys: Vec<(Self::K, Self::Extra)>) -> Vec<Self::V>;
+}
+
+/// Pending load that waits for its batch.
+type PendingLoad<K, V, Extra> = (K, Extra, oneshot::Sender<V>);
+
+/// Adapter that turns a [`BatchLoader`] into a [`Loader`].
+///
+/// Loads are not issued right away but collected for up to `window`. Then all collected keys are loaded by a single
+/// [`BatchLoader::load_batch`] call. A batch is issued early if it reaches `max_batch_size` keys.
+///
+/// This trades a small amount of latency for the first miss against far fewer backend calls when many keys are missed
+/// at the same time, e.g. after a cold start.
+#[derive(Debug)]
+pub struct BatchLoaderAdapter<L>
+where
+    L: BatchLoader,
+{
+    inner: Arc<Inner<L>>,
+}
+
+struct Inner<L>
+where
+    L: BatchLoader,
+{
+    loader: L,
+    window: Duration,
+    max_batch_size: usize,
+    handle: Handle,
+    pending: Mutex<Vec<PendingLoad<L::K, L::V, L::Extra>>>,
+}
+
+impl<L> std::fmt::Debug for Inner<L>
+where
+    L: BatchLoader,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Inner")
+            .field("loader", &self.loader)
+            .field("window", &self.window)
+            .field("max_batch_size", &self.max_batch_size)
+            .field("pending", &self.pending.lock().len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<L> BatchLoaderAdapter<L>
+where
+    L: BatchLoader,
+{
+    /// Create new adapter.
+    ///
+    /// # Panic
+    /// Panics if `max_batch_size` is zero.
+    pub fn new(loader: L, window: Duration, max_batch_size: usize, handle: &Handle) -> Self {
+        assert!(
+            max_batch_size > 0,
+            "max_batch_size must be greater than zero"
+        );
+
+        Self {
+            inner: Arc::new(Inner {
+                loader,
+                window,
+                max_batch_size,
+                handle: handle.clone(),
+                pending: Mutex::new(vec![]),
+            }),
+        }
+    }
+}
+
+impl<L> Inner<L>
+where
+    L: BatchLoader,
+{
+    /// Issue batch load for all pending keys in a background task.
+    fn flush(self: &Arc<Self>, batch: Vec<PendingLoad<L::K, L::V, L::Extra>>) {
+        if batch.is_empty() {
+            return;
+        }
+
+        let this = Arc::clone(self);
+        self.handle.spawn(async move {
+            let (keys, senders): (Vec<_>, Vec<_>) = batch
+                .into_iter()
+                .map(|(k, extra, tx)| ((k, extra), tx))
+                .unzip();
+            let n_keys = keys.len();
+
+            let values = this.loader.load_batch(keys).await;
+            assert_eq!(
+                values.len(),
+                n_keys,
+                "batch loader must return one value per key"
+            );
+
+            for (tx, v) in senders.into_iter().zip(values) {
+                // the waiting load might have been cancelled
+                tx.send(v).ok();
+            }
+        });
+    }
+}
+
+#[async_trait]
+impl<L> Loader for BatchLoaderAdapter<L>
+where
+    L: BatchLoader,
+{
+    type K = L::K;
+    type V = L::V;
+    type Extra = L::Extra;
+
+    async fn load(&self, k: Self::K, extra: Self::Extra) -> Self::V {
+        let (tx, rx) = oneshot::channel();
+
+        let (first, full_batch) = {
+            let mut pending = self.inner.pending.lock();
+            pending.push((k, extra, tx));
+            let first = pending.len() == 1;
+            let full_batch =
+                (pending.len() >= self.inner.max_batch_size).then(|| std::mem::take(&mut *pending));
+            (first, full_batch)
+        };
+
+        if let Some(batch) = full_batch {
+            self.inner.flush(batch);
+        } else if first {
+            // first key of a new batch => flush it when the window is over
+            let inner = Arc::clone(&self.inner);
+            self.inner.handle.spawn(async move {
+                tokio::time::sleep(inner.window).await;
+                let batch = std::mem::take(&mut *inner.pending.lock());
+                inner.flush(batch);
+            });
+        }
+
+        rx.await.expect("batch load task panicked")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_window() {
+        let loader = TestBatchLoader::default();
+        let adapter =
+            BatchLoaderAdapter::new(loader, Duration::from_millis(10), 100, &Handle::current());
+
+        let (a, b, c) = tokio::join!(adapter.load(1, true), adapter.load(2, false), async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            adapter.load(3, true).await
+        });
+        assert_eq!(a, String::from("1_true"));
+        assert_eq!(b, String::from("2_false"));
+        assert_eq!(c, String::from("3_true"));
+
+        // first two keys arrived within the window, the third one was too late
+        assert_eq!(adapter.inner.loader.batch_sizes(), vec![2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_max_batch_size() {
+        let loader = TestBatchLoader::default();
+        let adapter =
+            BatchLoaderAdapter::new(loader, Duration::from_secs(3600), 2, &Handle::current());
+
+        // batch is flushed as soon as it is full, without waiting for the (very long) window
+        let (a, b) = tokio::time::timeout(Duration::from_secs(1), async {
+            tokio::join!(adapter.load(1, true), adapter.load(2, false))
+        })
+        .await
+        .unwrap();
+        assert_eq!(a, String::from("1_true"));
+        assert_eq!(b, String::from("2_false"));
+        assert_eq!(adapter.inner.loader.batch_sizes(), vec![2]);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "max_batch_size must be greater than zero")]
+    async fn test_panic_zero_batch_size() {
+        BatchLoaderAdapter::new(
+            TestBatchLoader::default(),
+            Duration::from_millis(10),
+            0,
+            &Handle::current(),
+        );
+    }
+
+    #[derive(Debug, Default)]
+    struct TestBatchLoader {
+        batch_sizes: Mutex<Vec<usize>>,
+    }
+
+    impl TestBatchLoader {
+        fn batch_sizes(&self) -> Vec<usize> {
+            self.batch_sizes.lock().clone()
+        }
+    }
+
+    #[async_trait]
+    impl BatchLoader for TestBatchLoader {
+        type K = u8;
+        type V = String;
+        type Extra = bool;
+
+        async fn load_batch(&self, keys: Vec<(Self::K, Self::Extra)>) -> Vec<Self::V> {
+            self.batch_sizes.lock().push(keys.len());
+
+            keys.into_iter()
+                .map(|(k, extra)| format!("{k}_{extra}"))
+                .collect()
+        }
+    }
+}
@@ -2,7 +2,9 @@
 use async_trait::async_trait;
 use std::{future::Future, hash::Hash, marker::PhantomData};
 
+pub mod batch;
 pub mod metrics;
+pub mod snapshot;
 
 /// Loader for missing [`Cache`](crate::cache::Cache) entries.
 #[async_trait]
@@ -0,0 +1,86 @@
+//! Rehydrate cache entries from a snapshot.
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::snapshot::Snapshot;
+
+use super::Loader;
+
+/// Loader that first serves entries from a [`Snapshot`] and only falls back to the inner loader for keys that are not
+/// part of the snapshot (or were already rehydrated).
+pub struct SnapshotLoader<L>
+where
+    L: Loader,
+    L::K: Eq,
+{
+    inner: L,
+    snapshot: Arc<Snapshot<L::K, L::V>>,
+}
+
+impl<L> SnapshotLoader<L>
+where
+    L: Loader,
+    L::K: Eq,
+{
+    /// Create new loader.
+    pub fn new(inner: L, snapshot: Arc<Snapshot<L::K, L::V>>) -> Self {
+        Self { inner, snapshot }
+    }
+}
+
+impl<L> std::fmt::Debug for SnapshotLoader<L>
+where
+    L: Loader,
+    L::K: Eq,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SnapshotLoader")
+            .field("inner", &self.inner)
+            .field("snapshot_len", &self.snapshot.len())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<L> Loader for SnapshotLoader<L>
+where
+    L: Loader,
+    L::K: Eq,
+{
+    type K = L::K;
+    type V = L::V;
+    type Extra = L::Extra;
+
+    async fn load(&self, k: Self::K, extra: Self::Extra) -> Self::V {
+        if let Some(v) = self.snapshot.take(&k) {
+            return v;
+        }
+
+        self.inner.load(k, extra).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::loader::FunctionLoader;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rehydrate() {
+        let snapshot = Arc::new(Snapshot::from(HashMap::from([(1u8, String::from("snap"))])));
+        let loader = SnapshotLoader::new(
+            FunctionLoader::new(|k: u8, _extra: ()| async move { format!("load_{k}") }),
+            Arc::clone(&snapshot),
+        );
+
+        assert_eq!(loader.load(1, ()).await, String::from("snap"));
+        assert!(snapshot.is_empty());
+
+        // every snapshot entry is only used once
+        assert_eq!(loader.load(1, ()).await, String::from("load_1"));
+        assert_eq!(loader.load(2, ()).await, String::from("load_2"));
+    }
+}
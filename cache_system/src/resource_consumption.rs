@@ -2,6 +2,7 @@
 use std::{
     fmt::Debug,
     ops::{Add, Sub},
+    time::Duration,
 };
 
 /// Strongly-typed resource consumption.
@@ -89,6 +90,73 @@ where
     }
 }
 
+/// An estimator of how expensive it is to reload a given key-value pair after it was evicted.
+///
+/// The cost is expressed as a [`Duration`] so that it can be weighed against the "last used" timestamp of an entry.
+/// Entries that are cheap to reload (e.g. catalog metadata) should report a small cost, entries that are expensive to
+/// reload (e.g. object store data) should report a larger one.
+pub trait ReloadCostEstimator: Debug + Send + Sync + 'static {
+    /// Cache key.
+    type K;
+
+    /// Cached value.
+    type V;
+
+    /// Estimate reload cost of given key-value pair.
+    fn reload_cost(&self, k: &Self::K, v: &Self::V) -> Duration;
+}
+
+type BoxedReloadCostFn<K, V> = Box<dyn (Fn(&K, &V) -> Duration) + Send + Sync>;
+
+/// A simple function-based [`ReloadCostEstimator`].
+pub struct FunctionReloadCostEstimator<K, V>
+where
+    K: 'static,
+    V: 'static,
+{
+    estimator: BoxedReloadCostFn<K, V>,
+}
+
+impl<K, V> FunctionReloadCostEstimator<K, V>
+where
+    K: 'static,
+    V: 'static,
+{
+    /// Create new reload cost estimator from given function.
+    pub fn new<F>(f: F) -> Self
+    where
+        F: Fn(&K, &V) -> Duration + Send + Sync + 'static,
+    {
+        Self {
+            estimator: Box::new(f),
+        }
+    }
+}
+
+impl<K, V> std::fmt::Debug for FunctionReloadCostEstimator<K, V>
+where
+    K: 'static,
+    V: 'static,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FunctionReloadCostEstimator")
+            .finish_non_exhaustive()
+    }
+}
+
+impl<K, V> ReloadCostEstimator for FunctionReloadCostEstimator<K, V>
+where
+    K: 'static,
+    V: 'static,
+{
+    type K = K;
+    type V = V;
+
+    fn reload_cost(&self, k: &Self::K, v: &Self::V) -> Duration {
+        (self.estimator)(k, v)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -100,6 +168,14 @@ mod tests {
         assert_eq!(estimator.consumption(&3, &2), TestSize(32));
     }
 
+    #[test]
+    fn test_function_reload_cost_estimator() {
+        let estimator = FunctionReloadCostEstimator::new(|k: &u8, v: &u16| {
+            Duration::from_millis((*k as u64) * 10 + (*v as u64))
+        });
+        assert_eq!(estimator.reload_cost(&3, &2), Duration::from_millis(32));
+    }
+
     #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
     struct TestSize(usize);
 
@@ -0,0 +1,367 @@
+//! Persist cache contents across restarts.
+//!
+//! On shutdown, the entries of a cache can be written to a local snapshot file, see
+//! [`SnapshotPolicy`](crate::backend::policy::snapshot::SnapshotPolicy) and [`write_snapshot`]. On startup, the
+//! snapshot is read back via [`Snapshot::read`]. Entries are NOT inserted into the cache eagerly. Instead they are
+//! handed out by [`SnapshotLoader`](crate::loader::snapshot::SnapshotLoader) when the cache would otherwise load them
+//! ("lazy rehydration"), so all cache policies (e.g. TTL, refresh, LRU) apply to rehydrated entries as usual.
+//!
+//! Snapshots are only an optimization: missing or corrupt snapshot files result in an empty snapshot.
+use observability_deps::tracing::{debug, info, warn};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Magic bytes at the start of every snapshot file.
+const MAGIC: &[u8] = b"IOXCACHE";
+
+/// Version of the snapshot file format.
+const VERSION: u8 = 1;
+
+/// Serialization of cache keys and values for [snapshots](self).
+pub trait CacheSerde: Sized {
+    /// Append serialized form of `self` to `buf`.
+    fn serialize(&self, buf: &mut Vec<u8>);
+
+    /// Deserialize from `data`.
+    ///
+    /// Returns `None` if the data is invalid.
+    fn deserialize(data: &[u8]) -> Option<Self>;
+}
+
+impl CacheSerde for Vec<u8> {
+    fn serialize(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self);
+    }
+
+    fn deserialize(data: &[u8]) -> Option<Self> {
+        Some(data.to_vec())
+    }
+}
+
+impl CacheSerde for String {
+    fn serialize(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self.as_bytes());
+    }
+
+    fn deserialize(data: &[u8]) -> Option<Self> {
+        String::from_utf8(data.to_vec()).ok()
+    }
+}
+
+impl CacheSerde for Arc<str> {
+    fn serialize(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self.as_bytes());
+    }
+
+    fn deserialize(data: &[u8]) -> Option<Self> {
+        std::str::from_utf8(data).ok().map(Arc::from)
+    }
+}
+
+macro_rules! impl_int {
+    ($t:ty) => {
+        impl CacheSerde for $t {
+            fn serialize(&self, buf: &mut Vec<u8>) {
+                buf.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn deserialize(data: &[u8]) -> Option<Self> {
+                Some(Self::from_le_bytes(data.try_into().ok()?))
+            }
+        }
+    };
+}
+
+impl_int!(i64);
+impl_int!(u64);
+
+impl CacheSerde for usize {
+    fn serialize(&self, buf: &mut Vec<u8>) {
+        (*self as u64).serialize(buf)
+    }
+
+    fn deserialize(data: &[u8]) -> Option<Self> {
+        u64::deserialize(data)?.try_into().ok()
+    }
+}
+
+impl<T> CacheSerde for Option<T>
+where
+    T: CacheSerde,
+{
+    fn serialize(&self, buf: &mut Vec<u8>) {
+        match self {
+            None => buf.push(0),
+            Some(v) => {
+                buf.push(1);
+                v.serialize(buf);
+            }
+        }
+    }
+
+    fn deserialize(data: &[u8]) -> Option<Self> {
+        match data.split_first()? {
+            (0, []) => Some(None),
+            (1, rest) => Some(Some(T::deserialize(rest)?)),
+            _ => None,
+        }
+    }
+}
+
+/// Write snapshot of the given entries to `path`.
+///
+/// The file is first written under a temporary name and then renamed, so a crash never leaves a partially written
+/// snapshot behind.
+///
+/// Returns the number of written entries.
+pub fn write_snapshot<'a, K, V, I>(path: &Path, entries: I) -> std::io::Result<usize>
+where
+    K: CacheSerde + 'a,
+    V: CacheSerde + 'a,
+    I: IntoIterator<Item = (&'a K, &'a V)>,
+{
+    let mut buf = MAGIC.to_vec();
+    buf.push(VERSION);
+
+    let mut n_entries = 0;
+    let mut scratch = vec![];
+    for (k, v) in entries {
+        scratch.clear();
+        k.serialize(&mut scratch);
+        push_field(&mut buf, &scratch)?;
+
+        scratch.clear();
+        v.serialize(&mut scratch);
+        push_field(&mut buf, &scratch)?;
+
+        n_entries += 1;
+    }
+
+    let mut tmp = PathBuf::from(path);
+    tmp.set_extension("tmp");
+    {
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(&buf)?;
+        file.sync_all()?;
+    }
+    std::fs::rename(&tmp, path)?;
+
+    info!(path=%path.display(), n_entries, bytes=buf.len(), "wrote cache snapshot");
+
+    Ok(n_entries)
+}
+
+/// Append length-prefixed field to `buf`.
+fn push_field(buf: &mut Vec<u8>, field: &[u8]) -> std::io::Result<()> {
+    let len = u32::try_from(field.len()).map_err(|_| {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, "cache entry too large")
+    })?;
+    buf.extend_from_slice(&len.to_le_bytes());
+    buf.extend_from_slice(field);
+    Ok(())
+}
+
+/// Cache entries read from a snapshot file that were not rehydrated yet.
+#[derive(Debug)]
+pub struct Snapshot<K, V>
+where
+    K: Eq + Hash,
+{
+    entries: Mutex<HashMap<K, V>>,
+}
+
+impl<K, V> Snapshot<K, V>
+where
+    K: CacheSerde + Eq + Hash,
+    V: CacheSerde,
+{
+    /// Read snapshot from `path`.
+    ///
+    /// The file is removed afterwards, so that a later crash does not rehydrate outdated data. A missing or corrupt
+    /// file results in an empty snapshot.
+    pub fn read(path: &Path) -> Self {
+        let entries = match std::fs::read(path) {
+            Ok(data) => {
+                if let Err(e) = std::fs::remove_file(path) {
+                    warn!(%e, path=%path.display(), "cannot remove cache snapshot");
+                }
+
+                match parse(&data) {
+                    Some(entries) => {
+                        info!(path=%path.display(), n_entries=entries.len(), "read cache snapshot");
+                        entries
+                    }
+                    None => {
+                        warn!(path=%path.display(), "ignoring corrupt cache snapshot");
+                        HashMap::new()
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                debug!(path=%path.display(), "no cache snapshot found");
+                HashMap::new()
+            }
+            Err(e) => {
+                warn!(%e, path=%path.display(), "cannot read cache snapshot");
+                HashMap::new()
+            }
+        };
+
+        Self::from(entries)
+    }
+}
+
+impl<K, V> Snapshot<K, V>
+where
+    K: Eq + Hash,
+{
+    /// Remove entry from the snapshot and return it.
+    ///
+    /// Every entry is only handed out once, later requests must be served by the actual loader.
+    pub fn take(&self, k: &K) -> Option<V> {
+        self.entries.lock().remove(k)
+    }
+
+    /// Number of entries that were not taken yet.
+    pub fn len(&self) -> usize {
+        self.entries.lock().len()
+    }
+
+    /// Checks if all entries were taken.
+    pub fn is_empty(&self) -> bool {
+        self.entries.lock().is_empty()
+    }
+}
+
+impl<K, V> From<HashMap<K, V>> for Snapshot<K, V>
+where
+    K: Eq + Hash,
+{
+    fn from(entries: HashMap<K, V>) -> Self {
+        Self {
+            entries: Mutex::new(entries),
+        }
+    }
+}
+
+/// Parse snapshot file content.
+fn parse<K, V>(data: &[u8]) -> Option<HashMap<K, V>>
+where
+    K: CacheSerde + Eq + Hash,
+    V: CacheSerde,
+{
+    let mut data = data.strip_prefix(MAGIC)?;
+    let (version, rest) = data.split_first()?;
+    if *version != VERSION {
+        return None;
+    }
+    data = rest;
+
+    fn next_field<'a>(data: &mut &'a [u8]) -> Option<&'a [u8]> {
+        if data.len() < 4 {
+            return None;
+        }
+        let (len, rest) = data.split_at(4);
+        let len = u32::from_le_bytes(len.try_into().expect("4 bytes")) as usize;
+        if rest.len() < len {
+            return None;
+        }
+        let (field, rest) = rest.split_at(len);
+        *data = rest;
+        Some(field)
+    }
+
+    let mut entries = HashMap::new();
+    while !data.is_empty() {
+        let k = K::deserialize(next_field(&mut data)?)?;
+        let v = V::deserialize(next_field(&mut data)?)?;
+        entries.insert(k, v);
+    }
+    Some(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serde_roundtrip() {
+        assert_roundtrip(vec![1u8, 2, 3]);
+        assert_roundtrip(String::from("foo"));
+        assert_roundtrip(Arc::<str>::from("bar"));
+        assert_roundtrip(-1i64);
+        assert_roundtrip(u64::MAX);
+        assert_roundtrip(42usize);
+        assert_roundtrip(Some(42usize));
+        assert_roundtrip(Option::<usize>::None);
+        assert_roundtrip(Some(Some(String::from("foo"))));
+
+        assert_eq!(u64::deserialize(&[1, 2]), None);
+        assert_eq!(String::deserialize(&[0xff]), None);
+        assert_eq!(Option::<u64>::deserialize(&[2]), None);
+        assert_eq!(Option::<u64>::deserialize(&[0, 1]), None);
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snapshot");
+
+        let entries = HashMap::from([
+            (String::from("a"), Some(1u64)),
+            (String::from("b"), None),
+            (String::from(""), Some(3u64)),
+        ]);
+        assert_eq!(write_snapshot(&path, &entries).unwrap(), 3);
+
+        let snapshot = Snapshot::<String, Option<u64>>::read(&path);
+        assert_eq!(snapshot.len(), 3);
+        assert!(!path.exists());
+
+        assert_eq!(snapshot.take(&String::from("a")), Some(Some(1)));
+        assert_eq!(snapshot.take(&String::from("a")), None);
+        assert_eq!(snapshot.take(&String::from("b")), Some(None));
+        assert_eq!(snapshot.take(&String::from("")), Some(Some(3)));
+        assert!(snapshot.is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_missing_or_corrupt() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("snapshot");
+
+        let snapshot = Snapshot::<String, u64>::read(&path);
+        assert!(snapshot.is_empty());
+
+        let entries = HashMap::from([(String::from("a"), 1u64)]);
+        write_snapshot(&path, &entries).unwrap();
+        let data = std::fs::read(&path).unwrap();
+
+        // truncated
+        std::fs::write(&path, &data[..data.len() - 1]).unwrap();
+        assert!(Snapshot::<String, u64>::read(&path).is_empty());
+
+        // wrong value type
+        std::fs::write(&path, &data).unwrap();
+        assert!(Snapshot::<String, Option<u64>>::read(&path).is_empty());
+
+        // wrong magic
+        std::fs::write(&path, b"foo").unwrap();
+        assert!(Snapshot::<String, u64>::read(&path).is_empty());
+    }
+
+    fn assert_roundtrip<T>(v: T)
+    where
+        T: CacheSerde + PartialEq + std::fmt::Debug,
+    {
+        let mut buf = vec![];
+        v.serialize(&mut buf);
+        assert_eq!(T::deserialize(&buf), Some(v));
+    }
+}
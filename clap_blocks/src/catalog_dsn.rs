@@ -4,6 +4,7 @@ use iox_catalog::{
     interface::Catalog,
     mem::MemCatalog,
     postgres::{PostgresCatalog, PostgresConnectionOptions},
+    replica::ReadReplicaCatalog,
 };
 use observability_deps::tracing::*;
 use snafu::{OptionExt, ResultExt, Snafu};
@@ -60,6 +61,18 @@ pub struct CatalogDsnConfig {
     #[clap(long = "--catalog-dsn", env = "INFLUXDB_IOX_CATALOG_DSN", action)]
     pub dsn: Option<String>,
 
+    /// Postgres connection string of a read replica of the catalog.
+    ///
+    /// If set, query-only components (the querier and the garbage collector in dry-run mode) serve
+    /// their non-transactional catalog reads from this replica instead of the primary given by
+    /// `--catalog-dsn`. All other components ignore this option.
+    #[clap(
+        long = "--catalog-replica-dsn",
+        env = "INFLUXDB_IOX_CATALOG_REPLICA_DSN",
+        action
+    )]
+    pub replica_dsn: Option<String>,
+
     /// Maximum number of connections allowed to the catalog at any one time.
     #[clap(
         long = "--catalog-max-connections",
@@ -128,6 +141,7 @@ impl CatalogDsnConfig {
         Self {
             catalog_type_: CatalogType::Memory,
             dsn: None,
+            replica_dsn: None,
             max_catalog_connections: PostgresConnectionOptions::DEFAULT_MAX_CONNS,
             postgres_schema_name: PostgresConnectionOptions::DEFAULT_SCHEMA_NAME.to_string(),
             connect_timeout: PostgresConnectionOptions::DEFAULT_CONNECT_TIMEOUT,
@@ -143,6 +157,7 @@ impl CatalogDsnConfig {
         Self {
             catalog_type_: CatalogType::Postgres,
             dsn: Some(dsn),
+            replica_dsn: None,
             max_catalog_connections: PostgresConnectionOptions::DEFAULT_MAX_CONNS,
             postgres_schema_name,
             connect_timeout: PostgresConnectionOptions::DEFAULT_CONNECT_TIMEOUT,
@@ -159,24 +174,8 @@ impl CatalogDsnConfig {
     ) -> Result<Arc<dyn Catalog>, Error> {
         let catalog = match self.catalog_type_ {
             CatalogType::Postgres => {
-                let options = PostgresConnectionOptions {
-                    app_name: app_name.to_string(),
-                    schema_name: self.postgres_schema_name.clone(),
-                    dsn: self
-                        .dsn
-                        .as_ref()
-                        .context(ConnectionStringRequiredSnafu)?
-                        .clone(),
-                    max_conns: self.max_catalog_connections,
-                    connect_timeout: self.connect_timeout,
-                    idle_timeout: self.idle_timeout,
-                    hotswap_poll_interval: self.hotswap_poll_interval,
-                };
-                Arc::new(
-                    PostgresCatalog::connect(options, metrics)
-                        .await
-                        .context(CatalogSnafu)?,
-                ) as Arc<dyn Catalog>
+                let dsn = self.dsn.as_ref().context(ConnectionStringRequiredSnafu)?;
+                self.connect_postgres(app_name, dsn, metrics).await?
             }
             CatalogType::Memory => {
                 let mem = MemCatalog::new(metrics);
@@ -193,4 +192,51 @@ impl CatalogDsnConfig {
 
         Ok(catalog)
     }
+
+    /// Get config-dependent catalog for query-only components.
+    ///
+    /// If a read replica is configured via `--catalog-replica-dsn`, the returned catalog serves
+    /// non-transactional reads from the replica (see [`ReadReplicaCatalog`]). Otherwise this is the
+    /// same as [`get_catalog`](Self::get_catalog).
+    pub async fn get_read_catalog(
+        &self,
+        app_name: &'static str,
+        metrics: Arc<metric::Registry>,
+    ) -> Result<Arc<dyn Catalog>, Error> {
+        let primary = self.get_catalog(app_name, Arc::clone(&metrics)).await?;
+
+        match (self.catalog_type_, &self.replica_dsn) {
+            (CatalogType::Postgres, Some(replica_dsn)) => {
+                info!("Catalog: reading from Postgres replica");
+                let replica = self
+                    .connect_postgres(app_name, replica_dsn, metrics)
+                    .await?;
+                Ok(Arc::new(ReadReplicaCatalog::new(primary, replica)))
+            }
+            _ => Ok(primary),
+        }
+    }
+
+    async fn connect_postgres(
+        &self,
+        app_name: &'static str,
+        dsn: &str,
+        metrics: Arc<metric::Registry>,
+    ) -> Result<Arc<dyn Catalog>, Error> {
+        let options = PostgresConnectionOptions {
+            app_name: app_name.to_string(),
+            schema_name: self.postgres_schema_name.clone(),
+            dsn: dsn.to_string(),
+            max_conns: self.max_catalog_connections,
+            connect_timeout: self.connect_timeout,
+            idle_timeout: self.idle_timeout,
+            hotswap_poll_interval: self.hotswap_poll_interval,
+        };
+
+        Ok(Arc::new(
+            PostgresCatalog::connect(options, metrics)
+                .await
+                .context(CatalogSnafu)?,
+        ))
+    }
 }
@@ -2,6 +2,8 @@
 
 #![cfg_attr(rustfmt, rustfmt_skip)] // https://github.com/rust-lang/rustfmt/issues/5489
 
+use std::{path::PathBuf, time::Duration};
+
 /// Create compactor configuration that can have different defaults. The `run compactor`
 /// server/service needs different defaults than the `compactor run-once` command, and this macro
 /// enables sharing of the parts of the configs that are the same without duplicating the code.
@@ -26,21 +28,36 @@ macro_rules! gen_compactor_config {
             )]
             pub topic: String,
 
+            /// Compact all shards of the write buffer topic instead of a fixed shard index range.
+            ///
+            /// The shard list is read from the catalog every time compaction candidates are
+            /// selected, so shards that are added to the topic are picked up without a restart.
+            #[clap(
+                long = "--compaction-discover-shards",
+                env = "INFLUXDB_IOX_COMPACTION_DISCOVER_SHARDS",
+                action
+            )]
+            pub discover_shards: bool,
+
             /// Write buffer shard index to start (inclusive) range with
             #[clap(
                 long = "--shard-index-range-start",
                 env = "INFLUXDB_IOX_SHARD_INDEX_RANGE_START",
+                required_unless_present = "discover_shards",
+                conflicts_with = "discover_shards",
                 action
             )]
-            pub shard_index_range_start: i32,
+            pub shard_index_range_start: Option<i32>,
 
             /// Write buffer shard index to end (inclusive) range with
             #[clap(
                 long = "--shard-index-range-end",
                 env = "INFLUXDB_IOX_SHARD_INDEX_RANGE_END",
+                required_unless_present = "discover_shards",
+                conflicts_with = "discover_shards",
                 action
             )]
-            pub shard_index_range_end: i32,
+            pub shard_index_range_end: Option<i32>,
 
             /// Desired max size of compacted parquet files.
             /// It is a target desired value, rather than a guarantee.
@@ -84,6 +101,37 @@ macro_rules! gen_compactor_config {
             )]
             pub split_percentage: u16,
 
+            /// Split compacted files at multiples of this interval since the epoch (e.g. `1d`
+            /// for UTC day boundaries), so that no output file spans a boundary.
+            ///
+            /// Replaces the percentage-based split of `--compaction-split-percentage`. Files
+            /// between two boundaries are still split by `--compaction-max-desired-size-bytes`.
+            /// Aligned files can be dropped entirely by retention and are pruned more
+            /// effectively by time range queries. If not set, files are split by size only.
+            #[clap(
+                long = "--compaction-split-interval",
+                env = "INFLUXDB_IOX_COMPACTION_SPLIT_INTERVAL",
+                value_parser = humantime::parse_duration,
+            )]
+            pub split_interval: Option<Duration>,
+
+            /// Target file sizes of the compaction levels above level 1, starting with level 2,
+            /// e.g. `1073741824,10737418240` for two more levels.
+            ///
+            /// Cold compaction compacts the files of a level into the next level once they add up
+            /// to the target file size of the next level, so that every level holds fewer, larger
+            /// files than the one below. Sizes must increase with the level and be larger than
+            /// `--compaction-max-desired-size-bytes`. At most 3 levels above level 1 are
+            /// supported. If not set, compacted files stay at level 1.
+            #[clap(
+                long = "--compaction-upper-level-file-sizes-bytes",
+                env = "INFLUXDB_IOX_COMPACTION_UPPER_LEVEL_FILE_SIZES_BYTES",
+                multiple_values = true,
+                use_value_delimiter = true,
+                action
+            )]
+            pub upper_level_file_sizes_bytes: Vec<u64>,
+
             /// The compactor will limit the number of simultaneous cold partition compaction jobs
             /// based on the size of the input files to be compacted. This number should be less
             /// than 1/10th of the available memory to ensure compactions have enough space to run.
@@ -181,6 +229,143 @@ macro_rules! gen_compactor_config {
                 action
             )]
             pub memory_budget_bytes: u64,
+
+            /// Write a JSON report of every compaction run (inputs, outputs, durations and the
+            /// configuration in effect) next to the compacted files in the object store. The
+            /// reports are referenced from the catalog for later analysis.
+            #[clap(
+                long = "--compaction-write-reports",
+                env = "INFLUXDB_IOX_COMPACTION_WRITE_REPORTS",
+                action
+            )]
+            pub write_reports: bool,
+
+            /// How long the hot compaction scheduler pauses before checking for more work when a
+            /// cycle found no hot partitions to compact.
+            ///
+            /// Hot and cold partitions are compacted by independent schedulers, so that long cold
+            /// compactions don't delay the compaction of recently written data.
+            #[clap(
+                long = "--compaction-hot-tick-interval",
+                env = "INFLUXDB_IOX_COMPACTION_HOT_TICK_INTERVAL",
+                default_value = "1s",
+                value_parser = humantime::parse_duration,
+            )]
+            pub hot_tick_interval: Duration,
+
+            /// How long the cold compaction scheduler pauses before checking for more work when a
+            /// cycle found no cold partitions to compact.
+            #[clap(
+                long = "--compaction-cold-tick-interval",
+                env = "INFLUXDB_IOX_COMPACTION_COLD_TICK_INTERVAL",
+                default_value = "1s",
+                value_parser = humantime::parse_duration,
+            )]
+            pub cold_tick_interval: Duration,
+
+            /// Max number of hot partitions that are compacted concurrently, in addition to the
+            /// limit given by the memory budget. 0 means no additional limit.
+            #[clap(
+                long = "--compaction-hot-max-concurrent-partitions",
+                env = "INFLUXDB_IOX_COMPACTION_HOT_MAX_CONCURRENT_PARTITIONS",
+                default_value = "0",
+                action
+            )]
+            pub hot_max_concurrent_partitions: usize,
+
+            /// Max number of cold partitions that are compacted concurrently, in addition to the
+            /// limit given by the cold concurrent size. 0 means no additional limit.
+            #[clap(
+                long = "--compaction-cold-max-concurrent-partitions",
+                env = "INFLUXDB_IOX_COMPACTION_COLD_MAX_CONCURRENT_PARTITIONS",
+                default_value = "0",
+                action
+            )]
+            pub cold_max_concurrent_partitions: usize,
+
+            /// Number of threads of a dedicated executor for cold compaction and background
+            /// rewrites. If not set, cold compaction shares the executor of hot compaction.
+            #[clap(
+                long = "--compaction-cold-exec-thread-count",
+                env = "INFLUXDB_IOX_COMPACTION_COLD_EXEC_THREAD_COUNT",
+                action
+            )]
+            pub cold_exec_thread_count: Option<usize>,
+
+            /// Memory available to the operators of cold compactions and rewrites that can spill
+            /// to disk (e.g. sorts), in bytes.
+            ///
+            /// Such operators write intermediate results to disk (see `--compaction-spill-dir`)
+            /// instead of exceeding this limit. Setting this implies a dedicated executor for cold
+            /// compaction (see `--compaction-cold-exec-thread-count`). Set to zero for no limit,
+            /// in which case nothing is spilled.
+            #[clap(
+                long = "--compaction-cold-exec-mem-pool-bytes",
+                env = "INFLUXDB_IOX_COMPACTION_COLD_EXEC_MEM_POOL_BYTES",
+                default_value = "0",
+                action
+            )]
+            pub cold_exec_mem_pool_bytes: usize,
+
+            /// Directory for the files spilled by cold compactions and rewrites.
+            ///
+            /// Every compaction spills into a directory of its own, which is removed when the
+            /// compaction is done. Directories left behind by a crash are removed on startup, so
+            /// this directory must not be shared with other compactors. If not specified, the
+            /// temporary directory of the OS is used and no quota applies.
+            #[clap(
+                long = "--compaction-spill-dir",
+                env = "INFLUXDB_IOX_COMPACTION_SPILL_DIR",
+                action
+            )]
+            pub spill_dir: Option<PathBuf>,
+
+            /// Maximum size of the spill files of a single compaction, in bytes. Compactions that
+            /// exceed it are cancelled. Only applies with `--compaction-spill-dir`.
+            #[clap(
+                long = "--compaction-spill-quota-bytes",
+                env = "INFLUXDB_IOX_COMPACTION_SPILL_QUOTA_BYTES",
+                default_value = "10737418240",
+                action
+            )]
+            pub spill_quota_bytes: u64,
+
+            /// How often the compaction debt of every shard is computed and published as the
+            /// `compactor_debt_files` and `compactor_debt_bytes` metrics.
+            ///
+            /// The debt consists of old level 0 files (see `--compaction-debt-level-0-age`) and
+            /// files that overlap with other files of their partition, i.e. need to be
+            /// deduplicated. It can drive autoscaling of compactors. Set to zero to disable.
+            #[clap(
+                long = "--compaction-debt-interval",
+                env = "INFLUXDB_IOX_COMPACTION_DEBT_INTERVAL",
+                default_value = "1m",
+                value_parser = humantime::parse_duration,
+            )]
+            pub debt_interval: Duration,
+
+            /// Level 0 files created longer ago than this count as compaction debt.
+            #[clap(
+                long = "--compaction-debt-level-0-age",
+                env = "INFLUXDB_IOX_COMPACTION_DEBT_LEVEL_0_AGE",
+                default_value = "1h",
+                value_parser = humantime::parse_duration,
+            )]
+            pub debt_level_0_age: Duration,
+
+            /// Hot compaction skips partitions that an ingester started persisting less than this
+            /// long ago and is still persisting, so that it doesn't compact files that are about to
+            /// be joined by another one.
+            ///
+            /// Partitions are skipped at most this long, even if an ingester crashed while
+            /// persisting them. Set to zero to compact partitions regardless.
+            #[clap(
+                long = "--compaction-hot-persist-grace-period",
+                env = "INFLUXDB_IOX_COMPACTION_HOT_PERSIST_GRACE_PERIOD",
+                default_value = "10m",
+                value_parser = humantime::parse_duration,
+            )]
+            pub hot_persist_grace_period: Duration,
         }
     };
 }
@@ -195,11 +380,14 @@ impl CompactorOnceConfig {
     pub fn into_compactor_config(self) -> CompactorConfig {
         CompactorConfig {
             topic: self.topic,
+            discover_shards: self.discover_shards,
             shard_index_range_start: self.shard_index_range_start,
             shard_index_range_end: self.shard_index_range_end,
             max_desired_file_size_bytes: self.max_desired_file_size_bytes,
             percentage_max_file_size: self.percentage_max_file_size,
             split_percentage: self.split_percentage,
+            split_interval: self.split_interval,
+            upper_level_file_sizes_bytes: self.upper_level_file_sizes_bytes,
             max_cold_concurrent_size_bytes: self.max_cold_concurrent_size_bytes,
             max_number_partitions_per_shard: self.max_number_partitions_per_shard,
             min_number_recent_ingested_files_per_partition: self
@@ -208,6 +396,18 @@ impl CompactorOnceConfig {
             cold_input_file_count_threshold: self.cold_input_file_count_threshold,
             hot_multiple: self.hot_multiple,
             memory_budget_bytes: self.memory_budget_bytes,
+            write_reports: self.write_reports,
+            hot_tick_interval: self.hot_tick_interval,
+            cold_tick_interval: self.cold_tick_interval,
+            hot_max_concurrent_partitions: self.hot_max_concurrent_partitions,
+            cold_max_concurrent_partitions: self.cold_max_concurrent_partitions,
+            cold_exec_thread_count: self.cold_exec_thread_count,
+            cold_exec_mem_pool_bytes: self.cold_exec_mem_pool_bytes,
+            spill_dir: self.spill_dir,
+            spill_quota_bytes: self.spill_quota_bytes,
+            debt_interval: self.debt_interval,
+            debt_level_0_age: self.debt_level_0_age,
+            hot_persist_grace_period: self.hot_persist_grace_period,
         }
     }
 }
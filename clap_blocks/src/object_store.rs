@@ -1,5 +1,6 @@
 //! CLI handling for object store config (via CLI arguments and environment variables).
 
+use data_types::ParquetPathLayout;
 use futures::TryStreamExt;
 use object_store::memory::InMemory;
 use object_store::path::Path;
@@ -211,6 +212,23 @@ pub struct ObjectStoreConfig {
         action
     )]
     pub object_store_connection_limit: NonZeroUsize,
+
+    /// Layout of the object store paths of written parquet files.
+    ///
+    /// Possible values (case insensitive):
+    ///
+    /// * v1 (default): `<namespace_id>/<table_id>/<shard_id>/<partition_id>/<uuid>.parquet`
+    /// * v2: `ns/<namespace_id>/<table_id>/<partition_id>/<uuid>.parquet`, which allows
+    ///    per-namespace object store lifecycle rules.
+    ///
+    /// Files of both layouts are always readable, so the layout can be changed at any time.
+    #[clap(
+        long = "--parquet-path-layout",
+        env = "INFLUXDB_IOX_PARQUET_PATH_LAYOUT",
+        default_value = "v1",
+        value_parser
+    )]
+    pub parquet_path_layout: ParquetPathLayout,
 }
 
 impl ObjectStoreConfig {
@@ -237,6 +255,7 @@ impl ObjectStoreConfig {
             google_service_account: Default::default(),
             object_store,
             object_store_connection_limit: NonZeroUsize::new(16).unwrap(),
+            parquet_path_layout: Default::default(),
         }
     }
 }
@@ -611,4 +630,16 @@ mod tests {
             data-dir"
         );
     }
+
+    #[test]
+    fn parquet_path_layout() {
+        let config = ObjectStoreConfig::try_parse_from(&["server"]).unwrap();
+        assert_eq!(config.parquet_path_layout, ParquetPathLayout::V1);
+
+        let config =
+            ObjectStoreConfig::try_parse_from(&["server", "--parquet-path-layout", "v2"]).unwrap();
+        assert_eq!(config.parquet_path_layout, ParquetPathLayout::V2);
+
+        ObjectStoreConfig::try_parse_from(&["server", "--parquet-path-layout", "v3"]).unwrap_err();
+    }
 }
@@ -2,7 +2,13 @@
 use data_types::{IngesterMapping, ShardIndex};
 use serde::Deserialize;
 use snafu::{ResultExt, Snafu};
-use std::{collections::HashMap, fs, io, path::PathBuf, sync::Arc};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
 #[derive(Debug, Snafu)]
 #[allow(missing_docs)]
@@ -207,6 +213,63 @@ pub struct QuerierConfig {
     )]
     pub ram_pool_data_bytes: usize,
 
+    /// Size of the chunks that the object store cache splits files into, in bytes.
+    ///
+    /// Range requests only load and cache the chunks they touch, so smaller chunks waste less of
+    /// the RAM cache on partially read files while larger chunks need fewer object store requests.
+    #[clap(
+        long = "--object-store-cache-chunk-size-bytes",
+        env = "INFLUXDB_IOX_OBJECT_STORE_CACHE_CHUNK_SIZE_BYTES",
+        default_value = "1048576",  // 1MB
+        action
+    )]
+    pub object_store_cache_chunk_size_bytes: usize,
+
+    /// Directory on local disk used as a second tier of the object store cache.
+    ///
+    /// Chunks loaded from the object store are also written to this directory, so they don't
+    /// need to be downloaded again after they were evicted from the RAM cache or after a restart.
+    /// If not specified, only the RAM cache is used.
+    #[clap(
+        long = "--object-store-cache-disk-path",
+        env = "INFLUXDB_IOX_OBJECT_STORE_CACHE_DISK_PATH",
+        action
+    )]
+    pub object_store_cache_disk_path: Option<PathBuf>,
+
+    /// Maximum size of the local-disk tier of the object store cache in bytes.
+    #[clap(
+        long = "--object-store-cache-disk-bytes",
+        env = "INFLUXDB_IOX_OBJECT_STORE_CACHE_DISK_BYTES",
+        default_value = "10737418240",  // 10GB
+        action
+    )]
+    pub object_store_cache_disk_bytes: usize,
+
+    /// Interval at which cached objects are checked against the object store.
+    ///
+    /// Objects that were deleted (e.g. by the garbage collector or manually by an operator) are
+    /// removed from the object store cache. Set to zero to disable the check.
+    #[clap(
+        long = "--object-store-cache-reconciliation-interval",
+        env = "INFLUXDB_IOX_OBJECT_STORE_CACHE_RECONCILIATION_INTERVAL",
+        default_value = "10m",
+        value_parser = humantime::parse_duration,
+    )]
+    pub object_store_cache_reconciliation_interval: Duration,
+
+    /// Duration to keep "not found" results in the object store cache.
+    ///
+    /// Objects that are written after they were first requested become visible once this
+    /// duration has passed. Set to zero to not cache "not found" results at all.
+    #[clap(
+        long = "--object-store-cache-not-found-ttl",
+        env = "INFLUXDB_IOX_OBJECT_STORE_CACHE_NOT_FOUND_TTL",
+        default_value = "10s",
+        value_parser = humantime::parse_duration,
+    )]
+    pub object_store_cache_not_found_ttl: Duration,
+
     /// Limit the number of concurrent queries.
     #[clap(
         long = "--max-concurrent-queries",
@@ -228,6 +291,250 @@ pub struct QuerierConfig {
         action
     )]
     pub max_table_query_bytes: usize,
+
+    /// Check the deduplicated output of every table scan for remaining primary key duplicates.
+    ///
+    /// Offending keys and the chunks (parquet files) that may have contributed them are logged
+    /// as warnings, queries are not failed. This keeps every primary key of a scan in memory and
+    /// should only be enabled to debug deduplication issues.
+    #[clap(
+        long = "--dedup-validation",
+        env = "INFLUXDB_IOX_DEDUP_VALIDATION",
+        action
+    )]
+    pub dedup_validation: bool,
+
+    /// Number of consecutive failed requests to an ingester after which its circuit breaker
+    /// opens.
+    ///
+    /// While the circuit is open, no requests are sent to that ingester and queries are answered
+    /// from persisted data only. Such results are flagged as potentially incomplete. Set to zero
+    /// to disable the circuit breaker, in which case ingester failures fail the query.
+    #[clap(
+        long = "--ingester-circuit-breaker-threshold",
+        env = "INFLUXDB_IOX_INGESTER_CIRCUIT_BREAKER_THRESHOLD",
+        default_value = "0",
+        action
+    )]
+    pub ingester_circuit_breaker_threshold: u64,
+
+    /// Duration an open ingester circuit stays open before a probe request is sent.
+    #[clap(
+        long = "--ingester-circuit-breaker-open-duration",
+        env = "INFLUXDB_IOX_INGESTER_CIRCUIT_BREAKER_OPEN_DURATION",
+        default_value = "30s",
+        value_parser = humantime::parse_duration,
+    )]
+    pub ingester_circuit_breaker_open_duration: Duration,
+
+    /// Ingester requests that take longer than this count as failed for the circuit breaker.
+    ///
+    /// Only used if the circuit breaker is enabled. Set to zero to not limit request duration.
+    #[clap(
+        long = "--ingester-latency-budget",
+        env = "INFLUXDB_IOX_INGESTER_LATENCY_BUDGET",
+        default_value = "0s",
+        value_parser = humantime::parse_duration,
+    )]
+    pub ingester_latency_budget: Duration,
+
+    /// Memory pool shared by all running queries, in bytes.
+    ///
+    /// Every table scan estimates its memory usage from the statistics of the scanned chunks and
+    /// reserves that amount until the query finishes. Scans that do not fit into the remaining
+    /// pool wait for other queries to finish, scans larger than the entire pool are rejected.
+    /// Set to zero to disable the admission control.
+    #[clap(
+        long = "--query-memory-pool-bytes",
+        env = "INFLUXDB_IOX_QUERY_MEMORY_POOL_BYTES",
+        default_value = "0",
+        action
+    )]
+    pub query_memory_pool_bytes: usize,
+
+    /// Maximum duration a query waits for memory from the query memory pool before it is
+    /// rejected.
+    #[clap(
+        long = "--query-admission-queue-timeout",
+        env = "INFLUXDB_IOX_QUERY_ADMISSION_QUEUE_TIMEOUT",
+        default_value = "10s",
+        value_parser = humantime::parse_duration,
+    )]
+    pub query_admission_queue_timeout: Duration,
+
+    /// Timeout for queries that do not request one.
+    ///
+    /// Queries exceeding their timeout are cancelled and fail with a `DEADLINE_EXCEEDED` error.
+    /// Set to zero to let such queries run indefinitely.
+    #[clap(
+        long = "--query-timeout",
+        env = "INFLUXDB_IOX_QUERY_TIMEOUT",
+        default_value = "0s",
+        value_parser = humantime::parse_duration,
+    )]
+    pub query_timeout: Duration,
+
+    /// Maximum timeout a client may request for a query.
+    ///
+    /// Requested timeouts above this are capped. Set to zero to accept any timeout.
+    #[clap(
+        long = "--max-query-timeout",
+        env = "INFLUXDB_IOX_MAX_QUERY_TIMEOUT",
+        default_value = "0s",
+        value_parser = humantime::parse_duration,
+    )]
+    pub max_query_timeout: Duration,
+
+    /// How often the per-namespace query timeouts are re-read from the catalog.
+    ///
+    /// A namespace timeout applies to queries of the namespace that do not request one and
+    /// replaces `--query-timeout` for them. Set to zero to ignore the namespace timeouts.
+    #[clap(
+        long = "--query-timeout-refresh-interval",
+        env = "INFLUXDB_IOX_QUERY_TIMEOUT_REFRESH_INTERVAL",
+        default_value = "1m",
+        value_parser = humantime::parse_duration,
+    )]
+    pub query_timeout_refresh_interval: Duration,
+
+    /// Memory available to query execution operators that can spill to disk (e.g. sorts), in
+    /// bytes.
+    ///
+    /// Such operators write intermediate results to disk instead of exceeding this limit. Set to
+    /// zero for no limit, in which case nothing is spilled.
+    #[clap(
+        long = "--exec-mem-pool-bytes",
+        env = "INFLUXDB_IOX_EXEC_MEM_POOL_BYTES",
+        default_value = "0",
+        action
+    )]
+    pub exec_mem_pool_bytes: usize,
+
+    /// Directory for files spilled during query execution.
+    ///
+    /// If not specified, the temporary directory of the OS is used.
+    #[clap(long = "--exec-spill-dir", env = "INFLUXDB_IOX_EXEC_SPILL_DIR", action)]
+    pub exec_spill_dir: Option<PathBuf>,
+
+    /// Minimum number of overlapping chunks that are deduplicated with a single sort.
+    ///
+    /// By default, overlapping chunks are sorted individually and merged, which needs memory for
+    /// all chunks at once. From this number of chunks on, all chunks are sorted together
+    /// instead, which can spill to disk (see `--exec-mem-pool-bytes`). Set to zero to always
+    /// merge.
+    #[clap(
+        long = "--external-dedup-min-chunks",
+        env = "INFLUXDB_IOX_EXTERNAL_DEDUP_MIN_CHUNKS",
+        default_value = "0",
+        action
+    )]
+    pub external_dedup_min_chunks: usize,
+
+    /// Answer time-bucketed `min`/`max`/`count` aggregations (e.g. `GROUP BY date_bin(...)`)
+    /// from parquet row group statistics instead of scanning the data where possible.
+    ///
+    /// This reads the footer of every queried parquet file during planning.
+    #[clap(
+        long = "--aggregate-pushdown",
+        env = "INFLUXDB_IOX_AGGREGATE_PUSHDOWN",
+        action
+    )]
+    pub aggregate_pushdown: bool,
+
+    /// How often the per-namespace query rate limits are re-read from the catalog.
+    ///
+    /// Queries exceeding the rate limit of their namespace are rejected with a
+    /// `RESOURCE_EXHAUSTED` error. Set to zero to disable query rate limiting.
+    #[clap(
+        long = "--query-rate-limit-refresh-interval",
+        env = "INFLUXDB_IOX_QUERY_RATE_LIMIT_REFRESH_INTERVAL",
+        default_value = "1m",
+        value_parser = humantime::parse_duration,
+    )]
+    pub query_rate_limit_refresh_interval: Duration,
+
+    /// Maximum number of queries of a single namespace that run at the same time.
+    ///
+    /// Further queries of the namespace wait for a slot, so that a single namespace cannot
+    /// occupy all of the `--max-concurrent-queries`. Set to zero to disable the per-namespace
+    /// limits.
+    #[clap(
+        long = "--max-concurrent-queries-per-namespace",
+        env = "INFLUXDB_IOX_MAX_CONCURRENT_QUERIES_PER_NAMESPACE",
+        default_value = "0",
+        action
+    )]
+    pub max_concurrent_queries_per_namespace: usize,
+
+    /// Maximum number of queries of a single namespace that wait for a slot.
+    ///
+    /// Further queries are rejected with a `RESOURCE_EXHAUSTED` error. Only used if
+    /// `--max-concurrent-queries-per-namespace` is set.
+    #[clap(
+        long = "--max-queued-queries-per-namespace",
+        env = "INFLUXDB_IOX_MAX_QUEUED_QUERIES_PER_NAMESPACE",
+        default_value = "100",
+        action
+    )]
+    pub max_queued_queries_per_namespace: usize,
+
+    /// How often the access statistics of the queried tables are written to the catalog.
+    ///
+    /// The statistics record when and how often every table was queried, so that rarely read
+    /// data can be compacted first and moved to colder storage tiers. Set to zero to disable
+    /// tracking.
+    #[clap(
+        long = "--access-stats-flush-interval",
+        env = "INFLUXDB_IOX_ACCESS_STATS_FLUSH_INTERVAL",
+        default_value = "1m",
+        value_parser = humantime::parse_duration,
+    )]
+    pub access_stats_flush_interval: Duration,
+
+    /// Tables queried within this window before startup get their namespace schema and partition
+    /// sort keys pre-loaded into the catalog cache.
+    ///
+    /// The recently queried tables are read from the table access statistics (see
+    /// `--access-stats-flush-interval`). Set to zero to disable the warm-up.
+    #[clap(
+        long = "--cache-warm-up-window",
+        env = "INFLUXDB_IOX_CACHE_WARM_UP_WINDOW",
+        default_value = "1h",
+        value_parser = humantime::parse_duration,
+    )]
+    pub cache_warm_up_window: Duration,
+
+    /// Maximum number of tables whose caches are warmed up on startup, most recently queried
+    /// first.
+    #[clap(
+        long = "--cache-warm-up-max-tables",
+        env = "INFLUXDB_IOX_CACHE_WARM_UP_MAX_TABLES",
+        default_value = "1000",
+        action
+    )]
+    pub cache_warm_up_max_tables: usize,
+
+    /// Maximum number of cached SQL query results.
+    ///
+    /// Results of queries that only read persisted data are cached and served again for identical
+    /// queries until new parquet files or deletes affect the queried tables. Set to zero to
+    /// disable the cache.
+    #[clap(
+        long = "--result-cache-max-entries",
+        env = "INFLUXDB_IOX_RESULT_CACHE_MAX_ENTRIES",
+        default_value = "0",
+        action
+    )]
+    pub result_cache_max_entries: usize,
+
+    /// Maximum size of a single cached SQL query result, in bytes. Larger results are not cached.
+    #[clap(
+        long = "--result-cache-max-entry-bytes",
+        env = "INFLUXDB_IOX_RESULT_CACHE_MAX_ENTRY_BYTES",
+        default_value = "10485760",
+        action
+    )]
+    pub result_cache_max_entry_bytes: usize,
 }
 
 impl QuerierConfig {
@@ -272,6 +579,32 @@ impl QuerierConfig {
         self.ram_pool_data_bytes
     }
 
+    /// Size of the chunks that the object store cache splits files into, in bytes.
+    pub fn object_store_cache_chunk_size_bytes(&self) -> usize {
+        self.object_store_cache_chunk_size_bytes
+    }
+
+    /// Directory of the local-disk tier of the object store cache, if enabled.
+    pub fn object_store_cache_disk_path(&self) -> Option<&Path> {
+        self.object_store_cache_disk_path.as_deref()
+    }
+
+    /// Maximum size of the local-disk tier of the object store cache in bytes.
+    pub fn object_store_cache_disk_bytes(&self) -> usize {
+        self.object_store_cache_disk_bytes
+    }
+
+    /// Interval at which cached objects are checked against the object store, `None` if
+    /// disabled.
+    pub fn object_store_cache_reconciliation_interval(&self) -> Option<Duration> {
+        Some(self.object_store_cache_reconciliation_interval).filter(|d| !d.is_zero())
+    }
+
+    /// Duration to keep "not found" results in the object store cache.
+    pub fn object_store_cache_not_found_ttl(&self) -> Duration {
+        self.object_store_cache_not_found_ttl
+    }
+
     /// Number of queries allowed to run concurrently
     pub fn max_concurrent_queries(&self) -> usize {
         self.max_concurrent_queries
@@ -282,6 +615,112 @@ impl QuerierConfig {
     pub fn max_table_query_bytes(&self) -> usize {
         self.max_table_query_bytes
     }
+
+    /// Check the output of table scans for primary key duplicates.
+    pub fn dedup_validation(&self) -> bool {
+        self.dedup_validation
+    }
+
+    /// Failure threshold of the ingester circuit breaker, `None` if disabled.
+    pub fn ingester_circuit_breaker_threshold(&self) -> Option<u64> {
+        Some(self.ingester_circuit_breaker_threshold).filter(|t| *t > 0)
+    }
+
+    /// Duration an open ingester circuit stays open.
+    pub fn ingester_circuit_breaker_open_duration(&self) -> Duration {
+        self.ingester_circuit_breaker_open_duration
+    }
+
+    /// Maximum duration of a single ingester request, `None` if unlimited.
+    pub fn ingester_latency_budget(&self) -> Option<Duration> {
+        Some(self.ingester_latency_budget).filter(|d| !d.is_zero())
+    }
+
+    /// Size of the memory pool shared by all queries, `None` if admission control is disabled.
+    pub fn query_memory_pool_bytes(&self) -> Option<usize> {
+        Some(self.query_memory_pool_bytes).filter(|b| *b > 0)
+    }
+
+    /// Maximum duration a query waits for memory from the pool.
+    pub fn query_admission_queue_timeout(&self) -> Duration {
+        self.query_admission_queue_timeout
+    }
+
+    /// Timeout for queries that do not request one, `None` if unlimited.
+    pub fn query_timeout(&self) -> Option<Duration> {
+        Some(self.query_timeout).filter(|d| !d.is_zero())
+    }
+
+    /// Maximum timeout a client may request, `None` if unlimited.
+    pub fn max_query_timeout(&self) -> Option<Duration> {
+        Some(self.max_query_timeout).filter(|d| !d.is_zero())
+    }
+
+    /// Refresh interval of the per-namespace query timeouts, `None` if they are ignored.
+    pub fn query_timeout_refresh_interval(&self) -> Option<Duration> {
+        Some(self.query_timeout_refresh_interval).filter(|d| !d.is_zero())
+    }
+
+    /// Memory available to spilling query operators, `None` if unlimited.
+    pub fn exec_mem_pool_bytes(&self) -> Option<usize> {
+        Some(self.exec_mem_pool_bytes).filter(|b| *b > 0)
+    }
+
+    /// Directory for spill files, `None` for the OS default.
+    pub fn exec_spill_dir(&self) -> Option<&Path> {
+        self.exec_spill_dir.as_deref()
+    }
+
+    /// Minimum number of overlapping chunks that are deduplicated with a single sort, `None` if
+    /// disabled.
+    pub fn external_dedup_min_chunks(&self) -> Option<usize> {
+        Some(self.external_dedup_min_chunks).filter(|n| *n > 0)
+    }
+
+    /// Answer aggregations from parquet row group statistics where possible.
+    pub fn aggregate_pushdown(&self) -> bool {
+        self.aggregate_pushdown
+    }
+
+    /// Refresh interval of the query rate limits, `None` if rate limiting is disabled.
+    pub fn query_rate_limit_refresh_interval(&self) -> Option<Duration> {
+        Some(self.query_rate_limit_refresh_interval).filter(|d| !d.is_zero())
+    }
+
+    /// Maximum number of concurrent queries per namespace, `None` if unlimited.
+    pub fn max_concurrent_queries_per_namespace(&self) -> Option<usize> {
+        Some(self.max_concurrent_queries_per_namespace).filter(|n| *n > 0)
+    }
+
+    /// Maximum number of queued queries per namespace.
+    pub fn max_queued_queries_per_namespace(&self) -> usize {
+        self.max_queued_queries_per_namespace
+    }
+
+    /// Flush interval of the table access statistics, `None` if tracking is disabled.
+    pub fn access_stats_flush_interval(&self) -> Option<Duration> {
+        Some(self.access_stats_flush_interval).filter(|d| !d.is_zero())
+    }
+
+    /// Window of recently queried tables to warm up the cache for, `None` if disabled.
+    pub fn cache_warm_up_window(&self) -> Option<Duration> {
+        Some(self.cache_warm_up_window).filter(|d| !d.is_zero())
+    }
+
+    /// Maximum number of tables to warm up the cache for.
+    pub fn cache_warm_up_max_tables(&self) -> usize {
+        self.cache_warm_up_max_tables
+    }
+
+    /// Maximum number of cached query results, `None` if the result cache is disabled.
+    pub fn result_cache_max_entries(&self) -> Option<usize> {
+        Some(self.result_cache_max_entries).filter(|n| *n > 0)
+    }
+
+    /// Maximum size of a single cached query result, in bytes.
+    pub fn result_cache_max_entry_bytes(&self) -> usize {
+        self.result_cache_max_entry_bytes
+    }
 }
 
 fn deserialize_shard_ingester_map(
@@ -420,6 +859,197 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_ingester_circuit_breaker() {
+        let actual = QuerierConfig::try_parse_from(["my_binary"]).unwrap();
+        assert_eq!(actual.ingester_circuit_breaker_threshold(), None);
+        assert_eq!(actual.ingester_latency_budget(), None);
+
+        let actual = QuerierConfig::try_parse_from([
+            "my_binary",
+            "--ingester-circuit-breaker-threshold",
+            "3",
+            "--ingester-circuit-breaker-open-duration",
+            "1m",
+            "--ingester-latency-budget",
+            "5s",
+        ])
+        .unwrap();
+        assert_eq!(actual.ingester_circuit_breaker_threshold(), Some(3));
+        assert_eq!(
+            actual.ingester_circuit_breaker_open_duration(),
+            Duration::from_secs(60)
+        );
+        assert_eq!(
+            actual.ingester_latency_budget(),
+            Some(Duration::from_secs(5))
+        );
+    }
+
+    #[test]
+    fn test_query_admission() {
+        let actual = QuerierConfig::try_parse_from(["my_binary"]).unwrap();
+        assert_eq!(actual.query_memory_pool_bytes(), None);
+
+        let actual = QuerierConfig::try_parse_from([
+            "my_binary",
+            "--query-memory-pool-bytes",
+            "1024",
+            "--query-admission-queue-timeout",
+            "1m",
+        ])
+        .unwrap();
+        assert_eq!(actual.query_memory_pool_bytes(), Some(1024));
+        assert_eq!(
+            actual.query_admission_queue_timeout(),
+            Duration::from_secs(60)
+        );
+    }
+
+    #[test]
+    fn test_query_timeout() {
+        let actual = QuerierConfig::try_parse_from(["my_binary"]).unwrap();
+        assert_eq!(actual.query_timeout(), None);
+        assert_eq!(actual.max_query_timeout(), None);
+        assert_eq!(
+            actual.query_timeout_refresh_interval(),
+            Some(Duration::from_secs(60))
+        );
+
+        let actual = QuerierConfig::try_parse_from([
+            "my_binary",
+            "--query-timeout",
+            "30s",
+            "--max-query-timeout",
+            "5m",
+            "--query-timeout-refresh-interval",
+            "0s",
+        ])
+        .unwrap();
+        assert_eq!(actual.query_timeout(), Some(Duration::from_secs(30)));
+        assert_eq!(actual.max_query_timeout(), Some(Duration::from_secs(300)));
+        assert_eq!(actual.query_timeout_refresh_interval(), None);
+    }
+
+    #[test]
+    fn test_query_rate_limit() {
+        let actual = QuerierConfig::try_parse_from(["my_binary"]).unwrap();
+        assert_eq!(
+            actual.query_rate_limit_refresh_interval(),
+            Some(Duration::from_secs(60))
+        );
+
+        let actual = QuerierConfig::try_parse_from([
+            "my_binary",
+            "--query-rate-limit-refresh-interval",
+            "0s",
+        ])
+        .unwrap();
+        assert_eq!(actual.query_rate_limit_refresh_interval(), None);
+    }
+
+    #[test]
+    fn test_namespace_limits() {
+        let actual = QuerierConfig::try_parse_from(["my_binary"]).unwrap();
+        assert_eq!(actual.max_concurrent_queries_per_namespace(), None);
+        assert_eq!(actual.max_queued_queries_per_namespace(), 100);
+
+        let actual = QuerierConfig::try_parse_from([
+            "my_binary",
+            "--max-concurrent-queries-per-namespace",
+            "2",
+            "--max-queued-queries-per-namespace",
+            "5",
+        ])
+        .unwrap();
+        assert_eq!(actual.max_concurrent_queries_per_namespace(), Some(2));
+        assert_eq!(actual.max_queued_queries_per_namespace(), 5);
+    }
+
+    #[test]
+    fn test_access_stats() {
+        let actual = QuerierConfig::try_parse_from(["my_binary"]).unwrap();
+        assert_eq!(
+            actual.access_stats_flush_interval(),
+            Some(Duration::from_secs(60))
+        );
+
+        let actual =
+            QuerierConfig::try_parse_from(["my_binary", "--access-stats-flush-interval", "0s"])
+                .unwrap();
+        assert_eq!(actual.access_stats_flush_interval(), None);
+    }
+
+    #[test]
+    fn test_cache_warm_up() {
+        let actual = QuerierConfig::try_parse_from(["my_binary"]).unwrap();
+        assert_eq!(
+            actual.cache_warm_up_window(),
+            Some(Duration::from_secs(3_600))
+        );
+        assert_eq!(actual.cache_warm_up_max_tables(), 1_000);
+
+        let actual = QuerierConfig::try_parse_from([
+            "my_binary",
+            "--cache-warm-up-window",
+            "0s",
+            "--cache-warm-up-max-tables",
+            "10",
+        ])
+        .unwrap();
+        assert_eq!(actual.cache_warm_up_window(), None);
+        assert_eq!(actual.cache_warm_up_max_tables(), 10);
+    }
+
+    #[test]
+    fn test_result_cache() {
+        let actual = QuerierConfig::try_parse_from(["my_binary"]).unwrap();
+        assert_eq!(actual.result_cache_max_entries(), None);
+        assert_eq!(actual.result_cache_max_entry_bytes(), 10 * 1024 * 1024);
+
+        let actual = QuerierConfig::try_parse_from([
+            "my_binary",
+            "--result-cache-max-entries",
+            "100",
+            "--result-cache-max-entry-bytes",
+            "1024",
+        ])
+        .unwrap();
+        assert_eq!(actual.result_cache_max_entries(), Some(100));
+        assert_eq!(actual.result_cache_max_entry_bytes(), 1024);
+    }
+
+    #[test]
+    fn test_spill() {
+        let actual = QuerierConfig::try_parse_from(["my_binary"]).unwrap();
+        assert_eq!(actual.exec_mem_pool_bytes(), None);
+        assert_eq!(actual.exec_spill_dir(), None);
+        assert_eq!(actual.external_dedup_min_chunks(), None);
+
+        let actual = QuerierConfig::try_parse_from([
+            "my_binary",
+            "--exec-mem-pool-bytes",
+            "1024",
+            "--exec-spill-dir",
+            "/tmp/spill",
+            "--external-dedup-min-chunks",
+            "10",
+        ])
+        .unwrap();
+        assert_eq!(actual.exec_mem_pool_bytes(), Some(1024));
+        assert_eq!(actual.exec_spill_dir(), Some(Path::new("/tmp/spill")));
+        assert_eq!(actual.external_dedup_min_chunks(), Some(10));
+    }
+
+    #[test]
+    fn test_aggregate_pushdown() {
+        let actual = QuerierConfig::try_parse_from(["my_binary"]).unwrap();
+        assert!(!actual.aggregate_pushdown());
+
+        let actual = QuerierConfig::try_parse_from(["my_binary", "--aggregate-pushdown"]).unwrap();
+        assert!(actual.aggregate_pushdown());
+    }
+
     #[test]
     fn supply_json_value() {
         let actual = QuerierConfig::try_parse_from([
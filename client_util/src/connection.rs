@@ -16,6 +16,10 @@ pub const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PK
 pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
 /// The default request timeout
 pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// The default interval of TCP keepalive probes
+pub const DEFAULT_TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+/// The default timeout for HTTP/2 keepalive pings
+pub const DEFAULT_KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(20);
 
 /// Errors returned by the ConnectionBuilder
 #[derive(Debug, Error)]
@@ -74,6 +78,11 @@ pub struct Builder {
     headers: Vec<(HeaderName, HeaderValue)>,
     connect_timeout: Duration,
     timeout: Duration,
+    tcp_keepalive: Option<Duration>,
+    http2_keep_alive_interval: Option<Duration>,
+    keep_alive_timeout: Duration,
+    keep_alive_while_idle: bool,
+    pool_size: usize,
 }
 
 impl std::default::Default for Builder {
@@ -83,6 +92,11 @@ impl std::default::Default for Builder {
             connect_timeout: DEFAULT_CONNECT_TIMEOUT,
             timeout: DEFAULT_TIMEOUT,
             headers: Default::default(),
+            tcp_keepalive: Some(DEFAULT_TCP_KEEPALIVE),
+            http2_keep_alive_interval: None,
+            keep_alive_timeout: DEFAULT_KEEP_ALIVE_TIMEOUT,
+            keep_alive_while_idle: false,
+            pool_size: 1,
         }
     }
 }
@@ -94,16 +108,26 @@ impl Builder {
     }
 
     /// Construct the [`Connection`] instance using the specified base URL.
+    ///
+    /// With a [`pool_size`](Self::pool_size) of one, the connection is established before this
+    /// returns. Pooled connections are established lazily, so connection errors only surface on
+    /// the first request.
     pub async fn build<D>(self, dst: D) -> Result<Connection>
     where
         D: TryInto<Uri, Error = InvalidUri> + Send,
     {
         let endpoint = self.create_endpoint(dst)?;
-        let channel = endpoint.connect().await?;
+        let channel = if self.pool_size > 1 {
+            Channel::balance_list(std::iter::repeat(endpoint).take(self.pool_size))
+        } else {
+            endpoint.connect().await?
+        };
         Ok(self.compose_middleware(channel))
     }
 
     /// Construct the [`Connection`] instance using the specified base URL and custom connector.
+    ///
+    /// This always uses a single connection, the [`pool_size`](Self::pool_size) is ignored.
     pub async fn build_with_connector<D, C>(self, dst: D, connector: C) -> Result<Connection>
     where
         D: TryInto<Uri, Error = InvalidUri> + Send,
@@ -124,7 +148,15 @@ impl Builder {
         let endpoint = Endpoint::from(dst.try_into()?)
             .user_agent(&self.user_agent)?
             .connect_timeout(self.connect_timeout)
-            .timeout(self.timeout);
+            .timeout(self.timeout)
+            .tcp_keepalive(self.tcp_keepalive)
+            .keep_alive_timeout(self.keep_alive_timeout)
+            .keep_alive_while_idle(self.keep_alive_while_idle);
+
+        let endpoint = match self.http2_keep_alive_interval {
+            Some(interval) => endpoint.http2_keep_alive_interval(interval),
+            None => endpoint,
+        };
         Ok(endpoint)
     }
 
@@ -175,6 +207,63 @@ impl Builder {
     pub fn timeout(self, timeout: Duration) -> Self {
         Self { timeout, ..self }
     }
+
+    /// Sets the interval of TCP keepalive probes, `None` disables them.
+    ///
+    /// Defaults to [`DEFAULT_TCP_KEEPALIVE`].
+    pub fn tcp_keepalive(self, tcp_keepalive: Option<Duration>) -> Self {
+        Self {
+            tcp_keepalive,
+            ..self
+        }
+    }
+
+    /// Sends HTTP/2 keepalive pings at the given interval.
+    ///
+    /// Idle connections are often dropped by load balancers and proxies without notice, which
+    /// makes the first request after an idle period fail. Regular pings keep the connection open
+    /// and detect dead connections early. Disabled by default.
+    pub fn http2_keep_alive_interval(self, interval: Duration) -> Self {
+        Self {
+            http2_keep_alive_interval: Some(interval),
+            ..self
+        }
+    }
+
+    /// Sets how long to wait for the acknowledgement of a keepalive ping before the connection
+    /// is closed.
+    ///
+    /// Only has an effect if [`http2_keep_alive_interval`](Self::http2_keep_alive_interval) is
+    /// set. Defaults to [`DEFAULT_KEEP_ALIVE_TIMEOUT`].
+    pub fn keep_alive_timeout(self, timeout: Duration) -> Self {
+        Self {
+            keep_alive_timeout: timeout,
+            ..self
+        }
+    }
+
+    /// Sets whether keepalive pings are also sent while there are no requests in flight.
+    ///
+    /// Only has an effect if [`http2_keep_alive_interval`](Self::http2_keep_alive_interval) is
+    /// set. Defaults to `false`.
+    pub fn keep_alive_while_idle(self, enabled: bool) -> Self {
+        Self {
+            keep_alive_while_idle: enabled,
+            ..self
+        }
+    }
+
+    /// Sets the number of connections to open to the server.
+    ///
+    /// Requests are balanced across all connections, which avoids head-of-line blocking on a
+    /// single HTTP/2 connection for clients with many concurrent requests. Defaults to one.
+    ///
+    /// # Panics
+    /// Panics if `pool_size` is zero.
+    pub fn pool_size(self, pool_size: usize) -> Self {
+        assert!(pool_size > 0, "pool size must be at least one");
+        Self { pool_size, ..self }
+    }
 }
 
 #[cfg(test)]
@@ -187,4 +276,26 @@ mod tests {
         fn assert_clone<T: Clone>(_t: T) {}
         assert_clone(Builder::default())
     }
+
+    #[tokio::test]
+    async fn test_pooled_connections_are_lazy() {
+        // nothing listens on this port, but pooled connections are only established on the first
+        // request
+        let connection = Builder::default()
+            .pool_size(2)
+            .http2_keep_alive_interval(Duration::from_secs(10))
+            .keep_alive_while_idle(true)
+            .build("http://127.0.0.1:1")
+            .await;
+        assert!(connection.is_ok());
+
+        let connection = Builder::default().build("http://127.0.0.1:1").await;
+        assert!(connection.is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "pool size must be at least one")]
+    fn test_pool_size_zero() {
+        Builder::default().pool_size(0);
+    }
 }
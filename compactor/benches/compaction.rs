@@ -0,0 +1,219 @@
+//! End-to-end benchmarks of the compactor.
+//!
+//! Every scenario creates a partition with cold level 0 files in an in-memory catalog and object
+//! store and runs one compaction cycle over it. Besides the criterion timings, the number of
+//! allocations of one compaction cycle is reported for every scenario.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use backoff::BackoffConfig;
+use compactor::{
+    compact::Compactor,
+    handler::{run_compactor_once, CompactorConfig, SchedulerConfig},
+};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use data_types::ColumnType;
+use iox_query::exec::Executor;
+use iox_tests::util::{TestCatalog, TestParquetFileBuilder};
+use iox_time::SystemProvider;
+use parquet_file::storage::ParquetStorage;
+use tokio::runtime::Runtime;
+
+/// Allocator that counts the allocations of the benchmark process.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size() as u64, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Shape of the data of a compaction scenario.
+#[derive(Debug, Clone, Copy)]
+struct Scenario {
+    name: &'static str,
+    files: usize,
+    rows_per_file: usize,
+    fields: usize,
+    /// Whether all files cover the same time range (and the same series) instead of consecutive
+    /// time ranges.
+    overlapping: bool,
+}
+
+const SCENARIOS: &[Scenario] = &[
+    Scenario {
+        name: "small_files",
+        files: 100,
+        rows_per_file: 10,
+        fields: 2,
+        overlapping: false,
+    },
+    Scenario {
+        name: "wide_table",
+        files: 4,
+        rows_per_file: 1_000,
+        fields: 200,
+        overlapping: false,
+    },
+    Scenario {
+        name: "heavy_overlap",
+        files: 20,
+        rows_per_file: 1_000,
+        fields: 2,
+        overlapping: true,
+    },
+];
+
+impl Scenario {
+    fn rows(&self) -> u64 {
+        (self.files * self.rows_per_file) as u64
+    }
+
+    /// Line protocol and time range of the `file`th file.
+    fn line_protocol(&self, file: usize) -> (String, i64, i64) {
+        let offset = if self.overlapping {
+            0
+        } else {
+            file * self.rows_per_file
+        };
+
+        let lines: Vec<_> = (0..self.rows_per_file)
+            .map(|row| {
+                let fields: Vec<_> = (0..self.fields)
+                    .map(|field| format!("f{}={}i", field, file * row + field))
+                    .collect();
+                format!(
+                    "bench,tag1=t{},tag2=t{} {} {}",
+                    row % 10,
+                    row % 7,
+                    fields.join(","),
+                    offset + row
+                )
+            })
+            .collect();
+
+        let min_time = offset as i64;
+        let max_time = (offset + self.rows_per_file - 1) as i64;
+        (lines.join("\n"), min_time, max_time)
+    }
+
+    /// Create the catalog, object store and files of the scenario and a compactor working on it.
+    async fn setup(&self) -> Arc<Compactor> {
+        let catalog = TestCatalog::new();
+        let namespace = catalog.create_namespace("bench").await;
+        let shard = namespace.create_shard(1).await;
+        let table = namespace.create_table("bench").await;
+        table.create_column("tag1", ColumnType::Tag).await;
+        table.create_column("tag2", ColumnType::Tag).await;
+        table.create_column("time", ColumnType::Time).await;
+        for field in 0..self.fields {
+            table
+                .create_column(&format!("f{}", field), ColumnType::I64)
+                .await;
+        }
+        let partition = table.with_shard(&shard).create_partition("bench").await;
+
+        for file in 0..self.files {
+            let (lp, min_time, max_time) = self.line_protocol(file);
+            let builder = TestParquetFileBuilder::default()
+                .with_line_protocol(&lp)
+                .with_min_time(min_time)
+                .with_max_time(max_time)
+                .with_max_seq(file as i64 + 1);
+            partition.create_parquet_file(builder).await;
+        }
+
+        let config = CompactorConfig::new(
+            100 * 1024 * 1024, // max_desired_file_size_bytes
+            80,                // percentage_max_file_size
+            80,                // split_percentage
+            u64::MAX,          // max_cold_concurrent_size_bytes
+            1,                 // max_number_partitions_per_shard
+            1,                 // min_number_recent_ingested_files_per_partition
+            u64::MAX,          // cold_input_size_threshold_bytes
+            usize::MAX,        // cold_input_file_count_threshold
+            1,                 // hot_multiple
+            u64::MAX,          // memory_budget_bytes
+            false,             // write_compaction_reports
+            SchedulerConfig::default(),
+            SchedulerConfig::default(),
+        );
+
+        Arc::new(Compactor::new(
+            vec![shard.shard.id],
+            Arc::clone(&catalog.catalog),
+            ParquetStorage::new(Arc::clone(&catalog.object_store)),
+            Arc::new(Executor::new(1)),
+            Arc::new(SystemProvider::new()),
+            BackoffConfig::default(),
+            config,
+            Arc::new(metric::Registry::new()),
+        ))
+    }
+}
+
+fn runtime() -> Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_io()
+        .enable_time()
+        .build()
+        .unwrap()
+}
+
+/// Run one compaction cycle of `scenario` and print the allocations it performed.
+fn report_allocations(rt: &Runtime, scenario: &Scenario) {
+    let compactor = rt.block_on(scenario.setup());
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let allocated_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    rt.block_on(run_compactor_once(compactor));
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    let allocated_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed) - allocated_bytes;
+
+    println!(
+        "compaction/{}: {} allocations, {} bytes allocated per compaction cycle",
+        scenario.name, allocations, allocated_bytes
+    );
+}
+
+fn compaction_benchmarks(c: &mut Criterion) {
+    let rt = runtime();
+    let mut group = c.benchmark_group("compaction");
+    group.sample_size(10);
+
+    for scenario in SCENARIOS {
+        report_allocations(&rt, scenario);
+
+        group.throughput(Throughput::Elements(scenario.rows()));
+        group.bench_function(scenario.name, |b| {
+            b.iter_batched(
+                || rt.block_on(scenario.setup()),
+                |compactor| rt.block_on(run_compactor_once(compactor)),
+                BatchSize::PerIteration,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, compaction_benchmarks);
+criterion_main!(benches);
@@ -1,10 +1,16 @@
 //! Data Points for the lifecycle of the Compactor
 
-use crate::handler::CompactorConfig;
+use crate::{
+    cost::{CostPhase, CostTracker},
+    handler::CompactorConfig,
+    parquet_file_filtering::{CandidateScorer, WriteAmplificationScorer},
+    spill::SpillDir,
+    state::CompactorState,
+};
 use backoff::BackoffConfig;
 use data_types::{
-    ColumnTypeCount, Namespace, NamespaceId, PartitionId, PartitionKey, PartitionParam, ShardId,
-    Table, TableId, TableSchema,
+    ColumnTypeCount, Namespace, NamespaceId, PartitionId, PartitionKey, PartitionParam,
+    SequenceNumber, ShardId, Table, TableAccessStats, TableId, TableSchema, Timestamp,
 };
 use iox_catalog::interface::{get_schema_by_id, Catalog};
 use iox_query::exec::Executor;
@@ -13,7 +19,8 @@ use metric::{
     Attributes, DurationHistogram, DurationHistogramOptions, Metric, U64Gauge, U64Histogram,
     U64HistogramOptions, DURATION_MAX,
 };
-use observability_deps::tracing::debug;
+use observability_deps::tracing::{debug, info};
+use parking_lot::Mutex;
 use parquet_file::storage::ParquetStorage;
 use schema::sort::SortKey;
 use snafu::{OptionExt, ResultExt, Snafu};
@@ -36,6 +43,11 @@ pub enum Error {
         source: iox_catalog::interface::Error,
     },
 
+    #[snafu(display("Error querying table access statistics {}", source))]
+    QueryingTableAccessStats {
+        source: iox_catalog::interface::Error,
+    },
+
     #[snafu(display("Error querying column {}", source))]
     QueryingColumn {
         source: iox_catalog::interface::Error,
@@ -65,6 +77,16 @@ pub enum Error {
         shard_id: ShardId,
     },
 
+    #[snafu(display(
+        "Error getting the partitions that are being persisted for shard {}. {}",
+        shard_id,
+        source
+    ))]
+    PersistingPartitions {
+        source: iox_catalog::interface::Error,
+        shard_id: ShardId,
+    },
+
     #[snafu(display(
         "Error getting the most level 0 file partitions for shard {}. {}",
         shard_id,
@@ -74,16 +96,52 @@ pub enum Error {
         source: iox_catalog::interface::Error,
         shard_id: ShardId,
     },
+
+    #[snafu(display("Error querying topic {}", source))]
+    QueryingTopic {
+        source: iox_catalog::interface::Error,
+    },
+
+    #[snafu(display("Could not find topic {}", topic_name))]
+    TopicNotFound { topic_name: String },
+
+    #[snafu(display("Error listing shards of topic {}: {}", topic_name, source))]
+    ListingShards {
+        source: iox_catalog::interface::Error,
+        topic_name: String,
+    },
 }
 
 /// A specialized `Error` for Compactor Data errors
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// Shards that a compactor is responsible for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShardAssignment {
+    /// A fixed list of shards.
+    Static(Vec<ShardId>),
+
+    /// All shards of the topic with the given name.
+    ///
+    /// The shard list is read from the catalog every time compaction candidates are selected, so
+    /// shards that are added to the topic are picked up without restarting the compactor.
+    Topic(String),
+}
+
+impl From<Vec<ShardId>> for ShardAssignment {
+    fn from(shards: Vec<ShardId>) -> Self {
+        Self::Static(shards)
+    }
+}
+
 /// Data points needed to run a compactor
 #[derive(Debug)]
 pub struct Compactor {
     /// Shards assigned to this compactor
-    shards: Vec<ShardId>,
+    shard_assignment: ShardAssignment,
+
+    /// Shards found during the last discovery, only used for [`ShardAssignment::Topic`].
+    discovered_shards: Mutex<Vec<ShardId>>,
 
     /// Object store for reading and persistence of parquet files
     pub(crate) store: ParquetStorage,
@@ -91,9 +149,20 @@ pub struct Compactor {
     /// The global catalog for schema, parquet files and tombstones
     pub(crate) catalog: Arc<dyn Catalog>,
 
+    /// Instrumented catalog and object store per phase, see [`CostTracker`]
+    pub(crate) cost: CostTracker,
+
     /// Executor for running queries, compacting, and persisting
     pub(crate) exec: Arc<Executor>,
 
+    /// Executor for compacting cold partitions and rewriting files. Same as `exec` unless
+    /// [`with_cold_executor`](Self::with_cold_executor) was used.
+    pub(crate) cold_exec: Arc<Executor>,
+
+    /// Directory for the spill files of compactions on `cold_exec`, see
+    /// [`with_spill_dir`](Self::with_spill_dir).
+    pub(crate) spill_dir: Option<Arc<SpillDir>>,
+
     /// Time provider for all activities in this compactor
     pub time_provider: Arc<dyn TimeProvider>,
 
@@ -103,6 +172,12 @@ pub struct Compactor {
     /// Configuration options for the compactor
     pub(crate) config: CompactorConfig,
 
+    /// Ranks the hot compaction candidates, see [`CandidateScorer`]
+    pub(crate) candidate_scorer: Arc<dyn CandidateScorer>,
+
+    /// Current activity, see [`CompactorState`]
+    pub(crate) state: CompactorState,
+
     /// Gauge for the number of compaction partition candidates before filtering
     compaction_candidate_gauge: Metric<U64Gauge>,
 
@@ -143,13 +218,21 @@ pub struct Compactor {
     ///  . Whether there is a big difference between each cycle or not
     ///  . How well this process  is parallelized
     pub(crate) compaction_cycle_duration: Metric<DurationHistogram>,
+
+    /// Gauge for the number of files the compactor has not caught up with yet, per shard and kind
+    /// of [debt](crate::debt::CompactionDebt).
+    pub(crate) compaction_debt_files: Metric<U64Gauge>,
+
+    /// Gauge for the total size of the files the compactor has not caught up with yet, per shard
+    /// and kind of [debt](crate::debt::CompactionDebt).
+    pub(crate) compaction_debt_bytes: Metric<U64Gauge>,
 }
 
 impl Compactor {
     /// Initialize the Compactor Data
     #[allow(clippy::too_many_arguments)]
     pub fn new(
-        shards: Vec<ShardId>,
+        shard_assignment: impl Into<ShardAssignment>,
         catalog: Arc<dyn Catalog>,
         store: ParquetStorage,
         exec: Arc<Executor>,
@@ -224,14 +307,32 @@ impl Compactor {
                 || duration_histogram_options,
             );
 
+        let compaction_debt_files = registry.register_metric(
+            "compactor_debt_files",
+            "Number of files that still need to be compacted",
+        );
+        let compaction_debt_bytes = registry.register_metric(
+            "compactor_debt_bytes",
+            "Total size of the files that still need to be compacted",
+        );
+
+        let cost = CostTracker::new(&catalog, &store, &registry);
+        let state = CompactorState::new(Arc::clone(&time_provider));
+
         Self {
-            shards,
+            shard_assignment: shard_assignment.into(),
+            discovered_shards: Default::default(),
             catalog,
+            cost,
             store,
+            cold_exec: Arc::clone(&exec),
+            spill_dir: None,
             exec,
             time_provider,
             backoff_config,
             config,
+            candidate_scorer: Arc::new(WriteAmplificationScorer),
+            state,
             compaction_candidate_gauge,
             parquet_file_candidate_gauge,
             parquet_file_candidate_bytes,
@@ -240,9 +341,85 @@ impl Compactor {
             candidate_selection_duration,
             partitions_extra_info_reading_duration,
             compaction_cycle_duration,
+            compaction_debt_files,
+            compaction_debt_bytes,
+        }
+    }
+
+    /// Use `cold_exec` to compact cold partitions, so that they don't compete with hot
+    /// partitions for the threads of the main executor.
+    pub fn with_cold_executor(mut self, cold_exec: Arc<Executor>) -> Self {
+        self.cold_exec = cold_exec;
+        self
+    }
+
+    /// Let compactions of cold partitions and rewrites spill into `spill_dir` if they exceed the
+    /// memory pool of the cold executor, instead of failing.
+    pub fn with_spill_dir(mut self, spill_dir: SpillDir) -> Self {
+        self.spill_dir = Some(Arc::new(spill_dir));
+        self
+    }
+
+    /// Use `candidate_scorer` to decide which hot partitions are compacted first, instead of the
+    /// default [`WriteAmplificationScorer`].
+    pub fn with_candidate_scorer(mut self, candidate_scorer: Arc<dyn CandidateScorer>) -> Self {
+        self.candidate_scorer = candidate_scorer;
+        self
+    }
+
+    /// The configuration to include in compaction reports, or `None` if reports are disabled.
+    pub(crate) fn report_config(&self) -> Option<CompactorConfig> {
+        if self.config.write_compaction_reports() {
+            Some(self.config)
+        } else {
+            None
         }
     }
 
+    /// Shards this compactor is currently responsible for.
+    ///
+    /// For [`ShardAssignment::Topic`] the shards are looked up in the catalog.
+    pub async fn shards(&self) -> Result<Vec<ShardId>> {
+        let topic_name = match &self.shard_assignment {
+            ShardAssignment::Static(shards) => return Ok(shards.clone()),
+            ShardAssignment::Topic(topic_name) => topic_name,
+        };
+
+        let mut repos = self
+            .cost
+            .catalog(CostPhase::CandidateSelection)
+            .repositories()
+            .await;
+        let topic = repos
+            .topics()
+            .get_by_name(topic_name)
+            .await
+            .context(QueryingTopicSnafu)?
+            .context(TopicNotFoundSnafu { topic_name })?;
+        let mut shards: Vec<_> = repos
+            .shards()
+            .list_by_topic(&topic)
+            .await
+            .context(ListingShardsSnafu { topic_name })?
+            .into_iter()
+            .map(|shard| shard.id)
+            .collect();
+        shards.sort();
+
+        let mut discovered_shards = self.discovered_shards.lock();
+        if *discovered_shards != shards {
+            info!(
+                %topic_name,
+                ?shards,
+                previous_shards=?*discovered_shards,
+                "compactor shards changed",
+            );
+            *discovered_shards = shards.clone();
+        }
+
+        Ok(shards)
+    }
+
     /// Return a list of the most recent highest ingested throughput partitions.
     /// The highest throughput partitions are prioritized as follows:
     ///  1. If there are partitions with new ingested files within the last 4 hours, pick them.
@@ -255,6 +432,9 @@ impl Compactor {
     /// * In all cases above, for each shard, N partitions with the most new ingested files
     ///   will be selected and the return list will include at most, P = N * S, partitions where S
     ///   is the number of shards this compactor handles.
+    /// * If a [persist grace period](CompactorConfig::hot_persist_grace_period) is configured,
+    ///   partitions that an ingester is persisting are skipped, so that a longer window may be
+    ///   searched for other partitions.
     pub async fn hot_partitions_to_compact(
         &self,
         // Max number of the most recent highest ingested throughput partitions
@@ -264,15 +444,39 @@ impl Compactor {
         // to prioritize partitions
         min_recent_ingested_files: usize,
     ) -> Result<Vec<PartitionParam>> {
-        let mut candidates = Vec::with_capacity(self.shards.len() * max_num_partitions_per_shard);
-        let mut repos = self.catalog.repositories().await;
-
-        for shard_id in &self.shards {
+        let shards = self.shards().await?;
+        let mut candidates = Vec::with_capacity(shards.len() * max_num_partitions_per_shard);
+        let mut repos = self
+            .cost
+            .catalog(CostPhase::CandidateSelection)
+            .repositories()
+            .await;
+
+        for shard_id in &shards {
             let attributes = Attributes::from([
                 ("shard_id", format!("{}", *shard_id).into()),
                 ("partition_type", "hot".into()),
             ]);
 
+            // Partitions that an ingester is persisting get another file soon, compacting them
+            // now would have to be repeated.
+            let persisting: HashSet<_> = match self.config.hot_persist_grace_period() {
+                Some(grace_period) => {
+                    let started_after =
+                        Timestamp::new((self.time_provider.now() - grace_period).timestamp_nanos());
+                    repos
+                        .partitions()
+                        .list_persisting(*shard_id, started_after)
+                        .await
+                        .context(PersistingPartitionsSnafu {
+                            shard_id: *shard_id,
+                        })?
+                        .into_iter()
+                        .collect()
+                }
+                None => HashSet::new(),
+            };
+
             // Get the most recent highest ingested throughput partitions within
             // the last 10 minutes. If nothing, increase to 30m minutes, 60 minutes,
             // 4 * 60 minutes, 24 * 60 minutes
@@ -291,6 +495,19 @@ impl Compactor {
                         shard_id: *shard_id,
                     })?;
 
+                if !persisting.is_empty() {
+                    let num_found = partitions.len();
+                    partitions.retain(|p| !persisting.contains(&p.partition_id));
+                    if partitions.len() < num_found {
+                        debug!(
+                            shard_id = shard_id.get(),
+                            num_minutes,
+                            n = num_found - partitions.len(),
+                            "skipped partitions that are being persisted"
+                        );
+                    }
+                }
+
                 if !partitions.is_empty() {
                     debug!(
                         shard_id = shard_id.get(),
@@ -327,10 +544,15 @@ impl Compactor {
         // Max number of cold partitions per shard we want to compact
         max_num_partitions_per_shard: usize,
     ) -> Result<Vec<PartitionParam>> {
-        let mut candidates = Vec::with_capacity(self.shards.len() * max_num_partitions_per_shard);
-        let mut repos = self.catalog.repositories().await;
-
-        for shard_id in &self.shards {
+        let shards = self.shards().await?;
+        let mut candidates = Vec::with_capacity(shards.len() * max_num_partitions_per_shard);
+        let mut repos = self
+            .cost
+            .catalog(CostPhase::CandidateSelection)
+            .repositories()
+            .await;
+
+        for shard_id in &shards {
             let attributes = Attributes::from([
                 ("shard_id", format!("{}", *shard_id).into()),
                 ("partition_type", "cold".into()),
@@ -366,7 +588,11 @@ impl Compactor {
         partitions: &[PartitionParam],
     ) -> Result<HashMap<TableId, Vec<ColumnTypeCount>>> {
         use std::collections::hash_map::Entry::Vacant; // this can be moved to the imports at the top if you want
-        let mut repos = self.catalog.repositories().await;
+        let mut repos = self
+            .cost
+            .catalog(CostPhase::PartitionInfo)
+            .repositories()
+            .await;
 
         let mut result = HashMap::with_capacity(partitions.len());
 
@@ -390,7 +616,11 @@ impl Compactor {
         &self,
         partitions: &[PartitionParam],
     ) -> Result<VecDeque<PartitionCompactionCandidateWithInfo>> {
-        let mut repos = self.catalog.repositories().await;
+        let mut repos = self
+            .cost
+            .catalog(CostPhase::PartitionInfo)
+            .repositories()
+            .await;
 
         let table_ids: HashSet<_> = partitions.iter().map(|p| p.table_id).collect();
         let namespace_ids: HashSet<_> = partitions.iter().map(|p| p.namespace_id).collect();
@@ -425,7 +655,12 @@ impl Compactor {
                 .get(&table.name)
                 .context(TableNotFoundSnafu { table_id: id })?
                 .clone();
-            tables.insert(id, (Arc::new(table), Arc::new(schema)));
+            let access_stats = repos
+                .table_access_stats()
+                .get_by_table_id(id)
+                .await
+                .context(QueryingTableAccessStatsSnafu)?;
+            tables.insert(id, (Arc::new(table), Arc::new(schema), access_stats));
         }
 
         let mut parts = HashMap::with_capacity(partitions.len());
@@ -444,7 +679,8 @@ impl Compactor {
         Ok(partitions
             .iter()
             .map(|p| {
-                let (table, table_schema) = tables.get(&p.table_id).expect("just queried");
+                let (table, table_schema, access_stats) =
+                    tables.get(&p.table_id).expect("just queried");
                 let part = parts.get(&p.partition_id).expect("just queried");
 
                 PartitionCompactionCandidateWithInfo {
@@ -456,12 +692,24 @@ impl Compactor {
                     candidate: *p,
                     sort_key: part.sort_key(),
                     partition_key: part.partition_key.clone(),
+                    table_access_stats: *access_stats,
+                    compaction_cursor: part.compaction_cursor,
                 }
             })
             .collect::<VecDeque<_>>())
     }
 }
 
+/// Order compaction candidates so that the partitions of rarely read tables come first.
+///
+/// Partitions of tables that were never queried come first, followed by the partitions of the
+/// least recently queried tables. The order of partitions of the same table is kept.
+pub(crate) fn rarely_read_first(candidates: &mut VecDeque<PartitionCompactionCandidateWithInfo>) {
+    candidates
+        .make_contiguous()
+        .sort_by_key(|c| c.table_access_stats.map(|stats| stats.last_queried_at));
+}
+
 /// [`PartitionParam`] with some information about its table and namespace.
 #[derive(Debug, Clone)]
 pub struct PartitionCompactionCandidateWithInfo {
@@ -482,6 +730,13 @@ pub struct PartitionCompactionCandidateWithInfo {
 
     /// partition_key
     pub partition_key: PartitionKey,
+
+    /// Access statistics of the table, `None` if the table was never queried
+    pub table_access_stats: Option<TableAccessStats>,
+
+    /// Compaction cursor of the partition, see
+    /// [`Partition::compaction_cursor`](data_types::Partition::compaction_cursor)
+    pub compaction_cursor: Option<SequenceNumber>,
 }
 
 impl PartitionCompactionCandidateWithInfo {
@@ -509,12 +764,13 @@ impl PartitionCompactionCandidateWithInfo {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::handler::SchedulerConfig;
     use data_types::{
-        ColumnId, ColumnSet, CompactionLevel, ParquetFileParams, SequenceNumber, ShardIndex,
-        Timestamp,
+        ColumnId, ColumnSet, ColumnStatsSet, CompactionLevel, ParquetFileParams, SequenceNumber,
+        ShardIndex, Timestamp,
     };
     use iox_tests::util::TestCatalog;
-    use iox_time::SystemProvider;
+    use iox_time::{MockProvider, SystemProvider};
     use std::time::Duration;
     use uuid::Uuid;
 
@@ -637,6 +893,7 @@ mod tests {
             compaction_level: CompactionLevel::Initial, // level of file of new writes
             created_at: time_now,
             column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::default(),
         };
 
         // Note: The order of the test cases below is important and should not be changed
@@ -783,6 +1040,123 @@ mod tests {
             partitions_with_info[1].sort_key,
             another_partition.sort_key()
         ); // this sort key is Some(tag1, time)
+        assert_eq!(partitions_with_info[0].table_access_stats, None);
+        assert_eq!(partitions_with_info[1].table_access_stats, None);
+
+        // Once queried, the partitions of the other table are prioritized
+        let stats = catalog
+            .catalog
+            .repositories()
+            .await
+            .table_access_stats()
+            .record(table.id, Timestamp::new(1), 1)
+            .await
+            .unwrap();
+        let mut partitions_with_info = compactor.add_info_to_partitions(&candidates).await.unwrap();
+        assert_eq!(partitions_with_info[0].table_access_stats, Some(stats));
+        assert_eq!(partitions_with_info[1].table_access_stats, None);
+        rarely_read_first(&mut partitions_with_info);
+        assert_eq!(partitions_with_info[0].id(), another_partition.id);
+        assert_eq!(partitions_with_info[1].id(), partition3.id);
+    }
+
+    #[tokio::test]
+    async fn test_hot_partitions_to_compact_skips_persisting() {
+        let catalog = TestCatalog::new();
+        let namespace = catalog.create_namespace("ns").await;
+        let shard = namespace.create_shard(1).await;
+        let table = namespace.create_table("table").await;
+        let table_shard = table.with_shard(&shard);
+        let partition1 = table_shard.create_partition("one").await;
+        let partition2 = table_shard.create_partition("two").await;
+
+        let now = SystemProvider::new().now();
+        let p1 = ParquetFileParams {
+            shard_id: shard.shard.id,
+            namespace_id: namespace.namespace.id,
+            table_id: table.table.id,
+            partition_id: partition1.partition.id,
+            object_store_id: Uuid::new_v4(),
+            max_sequence_number: SequenceNumber::new(100),
+            min_time: Timestamp::new(1),
+            max_time: Timestamp::new(5),
+            file_size_bytes: 1337,
+            row_count: 0,
+            compaction_level: CompactionLevel::Initial,
+            created_at: Timestamp::new(now.timestamp_nanos()),
+            column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::default(),
+        };
+        let mut repos = catalog.catalog.repositories().await;
+        for partition_id in [partition1.partition.id, partition2.partition.id] {
+            repos
+                .parquet_files()
+                .create(ParquetFileParams {
+                    object_store_id: Uuid::new_v4(),
+                    partition_id,
+                    ..p1.clone()
+                })
+                .await
+                .unwrap();
+        }
+
+        let config = make_compactor_config()
+            .with_hot_persist_grace_period(Some(Duration::from_secs(10 * 60)));
+        let make_compactor = |time_provider: Arc<dyn TimeProvider>, config| {
+            Compactor::new(
+                vec![shard.shard.id],
+                Arc::clone(&catalog.catalog),
+                ParquetStorage::new(Arc::clone(&catalog.object_store)),
+                Arc::new(Executor::new(1)),
+                time_provider,
+                BackoffConfig::default(),
+                config,
+                Arc::new(metric::Registry::new()),
+            )
+        };
+        let compactor = make_compactor(Arc::new(SystemProvider::new()), config);
+        let partition_ids = |candidates: Vec<PartitionParam>| {
+            let mut ids: Vec<_> = candidates.into_iter().map(|p| p.partition_id).collect();
+            ids.sort();
+            ids
+        };
+
+        let candidates = compactor.hot_partitions_to_compact(2, 1).await.unwrap();
+        assert_eq!(
+            partition_ids(candidates),
+            vec![partition1.partition.id, partition2.partition.id]
+        );
+
+        // an ingester persists partition1
+        repos
+            .partitions()
+            .start_persisting(partition1.partition.id)
+            .await
+            .unwrap();
+        let candidates = compactor.hot_partitions_to_compact(2, 1).await.unwrap();
+        assert_eq!(partition_ids(candidates), vec![partition2.partition.id]);
+
+        // without a grace period, partitions are compacted regardless
+        let regardless = make_compactor(Arc::new(SystemProvider::new()), make_compactor_config());
+        let candidates = regardless.hot_partitions_to_compact(2, 1).await.unwrap();
+        assert_eq!(partition_ids(candidates).len(), 2);
+
+        // marks older than the grace period are ignored, e.g. if the ingester crashed
+        let later = make_compactor(
+            Arc::new(MockProvider::new(now + Duration::from_secs(60 * 60))),
+            config,
+        );
+        let candidates = later.hot_partitions_to_compact(2, 1).await.unwrap();
+        assert_eq!(partition_ids(candidates).len(), 2);
+
+        // the ingester is done
+        repos
+            .partitions()
+            .finish_persisting(partition1.partition.id)
+            .await
+            .unwrap();
+        let candidates = compactor.hot_partitions_to_compact(2, 1).await.unwrap();
+        assert_eq!(partition_ids(candidates).len(), 2);
     }
 
     fn make_compactor_config() -> CompactorConfig {
@@ -807,6 +1181,9 @@ mod tests {
             cold_input_file_count_threshold,
             hot_multiple,
             memory_budget_bytes,
+            false,
+            SchedulerConfig::default(),
+            SchedulerConfig::default(),
         )
     }
 
@@ -930,6 +1307,7 @@ mod tests {
             compaction_level: CompactionLevel::Initial, // level of file of new writes
             created_at: time_38_hour_ago,               // create cold files by default
             column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::default(),
         };
 
         // Note: The order of the test cases below is important and should not be changed
@@ -1091,4 +1469,68 @@ mod tests {
         assert_eq!(candidates[2].partition_id, another_partition.id);
         assert_eq!(candidates[2].shard_id, another_shard.id);
     }
+
+    #[tokio::test]
+    async fn test_shard_discovery() {
+        let catalog = TestCatalog::new();
+
+        let mut txn = catalog.catalog.start_transaction().await.unwrap();
+        let topic = txn.topics().create_or_get("foo").await.unwrap();
+        let shard1 = txn
+            .shards()
+            .create_or_get(&topic, ShardIndex::new(1))
+            .await
+            .unwrap();
+        let other_topic = txn.topics().create_or_get("bar").await.unwrap();
+        txn.shards()
+            .create_or_get(&other_topic, ShardIndex::new(1))
+            .await
+            .unwrap();
+        txn.commit().await.unwrap();
+
+        let compactor = Compactor::new(
+            ShardAssignment::Topic("foo".into()),
+            Arc::clone(&catalog.catalog),
+            ParquetStorage::new(Arc::clone(&catalog.object_store)),
+            Arc::new(Executor::new(1)),
+            Arc::new(SystemProvider::new()),
+            BackoffConfig::default(),
+            make_compactor_config(),
+            Arc::new(metric::Registry::new()),
+        );
+        assert_eq!(compactor.shards().await.unwrap(), vec![shard1.id]);
+
+        // new shards of the topic are picked up
+        let shard2 = catalog
+            .catalog
+            .repositories()
+            .await
+            .shards()
+            .create_or_get(&topic, ShardIndex::new(2))
+            .await
+            .unwrap();
+        assert_eq!(
+            compactor.shards().await.unwrap(),
+            vec![shard1.id, shard2.id]
+        );
+        assert!(compactor
+            .cold_partitions_to_compact(1)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // unknown topic
+        let compactor = Compactor::new(
+            ShardAssignment::Topic("baz".into()),
+            Arc::clone(&catalog.catalog),
+            ParquetStorage::new(Arc::clone(&catalog.object_store)),
+            Arc::new(Executor::new(1)),
+            Arc::new(SystemProvider::new()),
+            BackoffConfig::default(),
+            make_compactor_config(),
+            Arc::new(metric::Registry::new()),
+        );
+        let err = compactor.shards().await.unwrap_err();
+        assert!(matches!(err, Error::TopicNotFound { .. }), "{}", err);
+    }
 }
@@ -12,8 +12,12 @@ use thiserror::Error;
 
 use crate::{
     compact::{Compactor, PartitionCompactionCandidateWithInfo},
-    parquet_file_filtering::{filter_hot_parquet_files, FilterResult, FilteredFiles},
+    cost::CostPhase,
+    parquet_file_filtering::{
+        filter_hot_parquet_files, CompactionBenefit, FilterResult, FilteredFiles,
+    },
     parquet_file_lookup,
+    state::CompactionKind,
 };
 
 #[derive(Debug, Error)]
@@ -79,14 +83,22 @@ pub async fn compact_hot_partitions(compactor: Arc<Compactor>) -> usize {
         duration.record(delta);
     }
 
+    compactor
+        .state
+        .start_cycle(CompactionKind::Hot, candidates.iter().map(|c| c.shard_id()));
+
     let n_candidates = candidates.len();
     if n_candidates == 0 {
         debug!("no hot compaction candidates found");
+        compactor.state.finish_cycle(CompactionKind::Hot, 0);
         return 0;
     } else {
         debug!(n_candidates, "found hot compaction candidates");
     }
 
+    // Compact the partitions that benefit the most first
+    let candidates = order_by_benefit(&compactor, candidates).await;
+
     let start_time = compactor.time_provider.now();
 
     compact_hot_partition_candidates(
@@ -106,10 +118,70 @@ pub async fn compact_hot_partitions(compactor: Arc<Compactor>) -> usize {
         let duration = compactor.compaction_cycle_duration.recorder(hot_attributes);
         duration.record(delta);
     }
+    compactor
+        .state
+        .finish_cycle(CompactionKind::Hot, n_candidates);
 
     n_candidates
 }
 
+/// Order the candidates by descending score of the compactor's
+/// [`CandidateScorer`](crate::parquet_file_filtering::CandidateScorer). Candidates whose files
+/// cannot be listed come last.
+async fn order_by_benefit(
+    compactor: &Compactor,
+    candidates: VecDeque<PartitionCompactionCandidateWithInfo>,
+) -> VecDeque<PartitionCompactionCandidateWithInfo> {
+    let small_file_threshold_bytes = (compactor.config.max_desired_file_size_bytes()
+        * compactor.config.percentage_max_file_size() as u64
+        / 100) as i64;
+
+    let scores = futures::future::join_all(
+        candidates
+            .iter()
+            .map(|partition| score_candidate(compactor, partition, small_file_threshold_bytes)),
+    )
+    .await;
+
+    let mut scored: Vec<_> = scores.into_iter().zip(candidates).collect();
+    // stable, so candidates with the same score keep their order
+    scored.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    scored.into_iter().map(|(_, partition)| partition).collect()
+}
+
+/// Score of compacting `partition`, `f64::NEG_INFINITY` if its files cannot be listed.
+async fn score_candidate(
+    compactor: &Compactor,
+    partition: &PartitionCompactionCandidateWithInfo,
+    small_file_threshold_bytes: i64,
+) -> f64 {
+    let partition_id = partition.id();
+    let files = parquet_file_lookup::ParquetFilesForCompaction::for_partition(
+        compactor.cost.catalog(CostPhase::CandidateSelection),
+        partition_id,
+        partition.compaction_cursor,
+    )
+    .await;
+
+    match files {
+        Ok(files) => {
+            let benefit = CompactionBenefit::new(&files, small_file_threshold_bytes);
+            let score = compactor.candidate_scorer.score(&benefit);
+            debug!(
+                ?partition_id,
+                ?benefit,
+                score,
+                "scored hot compaction candidate"
+            );
+            score
+        }
+        Err(e) => {
+            warn!(%e, ?partition_id, "error scoring hot compaction candidate");
+            f64::NEG_INFINITY
+        }
+    }
+}
+
 // For a given list of hot partition candidates and a memory budget, compute memory needed to compact each one
 // and compact as many of them in parallel as possible until all candidates are compacted
 //
@@ -128,6 +200,10 @@ async fn compact_hot_partition_candidates<F, Fut>(
     Fut: futures::Future<Output = ()> + Send,
 {
     let mut remaining_budget_bytes = compactor.config.memory_budget_bytes();
+    let max_concurrent_partitions = compactor
+        .config
+        .hot_scheduler()
+        .limit_concurrency(usize::MAX);
     let mut parallel_compacting_candidates = Vec::with_capacity(candidates.len());
     let mut num_remaining_candidates = candidates.len();
     let mut count = 0;
@@ -160,6 +236,11 @@ async fn compact_hot_partition_candidates<F, Fut>(
                     ?table_id,
                     "hot compaction is skipped due to missing column types of its table"
                 );
+                compactor.state.record_skipped(
+                    CompactionKind::Hot,
+                    partition_id,
+                    "missing column types of its table",
+                );
                 // todo: add this partition and its info into a new catalog table
                 // https://github.com/influxdata/influxdb_iox/issues/5458
                 None
@@ -170,8 +251,9 @@ async fn compact_hot_partition_candidates<F, Fut>(
                 // Get parquet_file info for this partition
                 let parquet_files_for_compaction =
                     parquet_file_lookup::ParquetFilesForCompaction::for_partition(
-                        Arc::clone(&compactor.catalog),
+                        compactor.cost.catalog(CostPhase::Compaction),
                         partition_id,
+                        partition.compaction_cursor,
                     )
                     .await;
                 match parquet_files_for_compaction {
@@ -182,6 +264,11 @@ async fn compact_hot_partition_candidates<F, Fut>(
                             ?partition_id,
                             "hot compaction failed due to error in reading parquet files"
                         );
+                        compactor.state.record_skipped(
+                            CompactionKind::Hot,
+                            partition_id,
+                            format!("error reading parquet files: {}", e),
+                        );
                         None
                     }
                     Ok(parquet_files_for_compaction) => {
@@ -213,6 +300,11 @@ async fn compact_hot_partition_candidates<F, Fut>(
                         ?table_id,
                         "hot compaction is skipped due to error in estimating compacting memory"
                     );
+                    compactor.state.record_skipped(
+                        CompactionKind::Hot,
+                        partition_id,
+                        "error estimating compacting memory",
+                    );
                     // todo: add this partition and its info into a new catalog table
                     // https://github.com/influxdata/influxdb_iox/issues/5458
                 }
@@ -228,6 +320,14 @@ async fn compact_hot_partition_candidates<F, Fut>(
                             ?table_id,
                             "hot compaction is skipped due to over memory budget"
                         );
+                        compactor.state.record_skipped(
+                            CompactionKind::Hot,
+                            partition_id,
+                            format!(
+                                "over memory budget: needs {} bytes",
+                                to_compact.budget_bytes()
+                            ),
+                        );
                         // todo: add this partition and its info into a new catalog table
                         // https://github.com/influxdata/influxdb_iox/issues/5458
                     }
@@ -240,9 +340,11 @@ async fn compact_hot_partition_candidates<F, Fut>(
         }
 
         // --------------------------------------------------------------------
-        // 4. Almost hitting max budget (only 10% left) or no more candidates or went over all remaining candidates,
+        // 4. Almost hitting max budget (only 10% left) or max concurrent partitions or no more candidates
+        //    or went over all remaining candidates,
         if (!parallel_compacting_candidates.is_empty())
             && ((remaining_budget_bytes <= (compactor.config.memory_budget_bytes() / 10) as u64)
+                || (parallel_compacting_candidates.len() >= max_concurrent_partitions)
                 || (candidates.is_empty())
                 || (count == num_remaining_candidates))
         {
@@ -279,6 +381,11 @@ async fn compact_hot_partitions_in_parallel(
             match compaction_result {
                 Err(e) => {
                     warn!(?e, ?partition_id, "hot compaction failed");
+                    comp.state.record_skipped(
+                        CompactionKind::Hot,
+                        partition_id,
+                        format!("compaction failed: {}", e),
+                    );
                 }
                 Ok(_) => {
                     debug!(?partition_id, "hot compaction complete");
@@ -301,8 +408,9 @@ async fn compact_hot_partitions_in_parallel(
 mod tests {
     use super::*;
     use crate::{
-        compact::Compactor, compact_hot_partitions::compact_hot_partition_candidates,
-        handler::CompactorConfig,
+        compact::Compactor,
+        compact_hot_partitions::compact_hot_partition_candidates,
+        handler::{CompactorConfig, SchedulerConfig},
     };
     use backoff::BackoffConfig;
     use data_types::{ColumnType, ColumnTypeCount, CompactionLevel};
@@ -652,6 +760,158 @@ mod tests {
         assert_eq!(g3_candidate1_pf_ids, vec![6, 5]);
     }
 
+    #[tokio::test]
+    async fn test_compact_hot_partition_candidates_max_concurrent_partitions() {
+        test_helpers::maybe_start_logging();
+
+        let TestSetup {
+            compactor,
+            mock_compactor,
+            shard,
+            table,
+            ..
+        } = test_setup_with_hot_scheduler(SchedulerConfig {
+            max_concurrent_partitions: 2,
+            ..Default::default()
+        })
+        .await;
+
+        let hot_time_one_hour_ago =
+            (compactor.time_provider.now() - Duration::from_secs(60 * 60)).timestamp_nanos();
+
+        // 3 partitions of 4,500 bytes each, all of them fit into the budget of 13,500
+        let mut partitions = vec![];
+        for name in ["one", "two", "three"] {
+            let partition = table.with_shard(&shard).create_partition(name).await;
+
+            let l0 = TestParquetFileBuilder::default()
+                .with_min_time(1)
+                .with_max_time(5)
+                .with_row_count(2)
+                .with_compaction_level(CompactionLevel::Initial)
+                .with_creation_time(hot_time_one_hour_ago);
+            partition.create_parquet_file_catalog_record(l0).await;
+
+            let l1 = TestParquetFileBuilder::default()
+                .with_min_time(4)
+                .with_max_time(6)
+                .with_row_count(2)
+                .with_compaction_level(CompactionLevel::FileNonOverlapped)
+                .with_creation_time(hot_time_one_hour_ago);
+            partition.create_parquet_file_catalog_record(l1).await;
+
+            partitions.push(partition);
+        }
+
+        let candidates = compactor
+            .hot_partitions_to_compact(
+                compactor.config.max_number_partitions_per_shard(),
+                compactor
+                    .config
+                    .min_number_recent_ingested_files_per_partition(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(candidates.len(), 3);
+
+        let table_columns = compactor.table_columns(&candidates).await.unwrap();
+        let candidates = compactor.add_info_to_partitions(&candidates).await.unwrap();
+        let mut sorted_candidates = candidates.into_iter().collect::<Vec<_>>();
+        sorted_candidates.sort_by_key(|c| c.candidate.partition_id);
+        let sorted_candidates = sorted_candidates.into_iter().collect::<VecDeque<_>>();
+
+        compact_hot_partition_candidates(
+            Arc::clone(&compactor),
+            mock_compactor.compaction_function(),
+            sorted_candidates,
+            table_columns,
+        )
+        .await;
+
+        // At most 2 partitions are compacted in parallel
+        let compaction_groups: Vec<Vec<_>> = mock_compactor
+            .results()
+            .iter()
+            .map(|group| group.iter().map(|c| c.partition.id()).collect())
+            .collect();
+        assert_eq!(
+            compaction_groups,
+            vec![
+                vec![partitions[0].partition.id, partitions[1].partition.id],
+                vec![partitions[2].partition.id],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_order_by_benefit() {
+        test_helpers::maybe_start_logging();
+
+        let TestSetup {
+            compactor,
+            shard,
+            table,
+            ..
+        } = test_setup().await;
+
+        let hot_time_one_hour_ago =
+            (compactor.time_provider.now() - Duration::from_secs(60 * 60)).timestamp_nanos();
+
+        // P1: a single L0 file that only needs to be upgraded
+        let partition1 = table.with_shard(&shard).create_partition("one").await;
+        let pf1_1 = TestParquetFileBuilder::default()
+            .with_min_time(1)
+            .with_max_time(5)
+            .with_compaction_level(CompactionLevel::Initial)
+            .with_creation_time(hot_time_one_hour_ago);
+        partition1.create_parquet_file_catalog_record(pf1_1).await;
+
+        // P2: overlapping L0 and L1 files
+        let partition2 = table.with_shard(&shard).create_partition("two").await;
+        let pf2_1 = TestParquetFileBuilder::default()
+            .with_min_time(1)
+            .with_max_time(5)
+            .with_compaction_level(CompactionLevel::Initial)
+            .with_creation_time(hot_time_one_hour_ago);
+        partition2.create_parquet_file_catalog_record(pf2_1).await;
+        let pf2_2 = TestParquetFileBuilder::default()
+            .with_min_time(4)
+            .with_max_time(8)
+            .with_compaction_level(CompactionLevel::Initial)
+            .with_creation_time(hot_time_one_hour_ago);
+        partition2.create_parquet_file_catalog_record(pf2_2).await;
+        let pf2_3 = TestParquetFileBuilder::default()
+            .with_min_time(6)
+            .with_max_time(10)
+            .with_compaction_level(CompactionLevel::FileNonOverlapped)
+            .with_creation_time(hot_time_one_hour_ago);
+        partition2.create_parquet_file_catalog_record(pf2_3).await;
+
+        let candidates = compactor
+            .hot_partitions_to_compact(
+                compactor.config.max_number_partitions_per_shard(),
+                compactor
+                    .config
+                    .min_number_recent_ingested_files_per_partition(),
+            )
+            .await
+            .unwrap();
+        let candidates = compactor.add_info_to_partitions(&candidates).await.unwrap();
+        let mut sorted_candidates = candidates.into_iter().collect::<Vec<_>>();
+        sorted_candidates.sort_by_key(|c| c.candidate.partition_id);
+        let sorted_candidates = sorted_candidates.into_iter().collect::<VecDeque<_>>();
+
+        let ordered: Vec<_> = order_by_benefit(&compactor, sorted_candidates)
+            .await
+            .iter()
+            .map(|c| c.id())
+            .collect();
+        assert_eq!(
+            ordered,
+            vec![partition2.partition.id, partition1.partition.id]
+        );
+    }
+
     #[derive(Default)]
     struct MockCompactor {
         compaction_groups: Arc<Mutex<Vec<Vec<FilteredFiles>>>>,
@@ -693,7 +953,7 @@ mod tests {
         }
     }
 
-    fn make_compactor_config() -> CompactorConfig {
+    fn make_compactor_config(hot_scheduler: SchedulerConfig) -> CompactorConfig {
         let max_desired_file_size_bytes = 100_000_000;
         let percentage_max_file_size = 90;
         let split_percentage = 100;
@@ -715,6 +975,9 @@ mod tests {
             cold_input_file_count_threshold,
             hot_multiple,
             memory_budget_bytes,
+            false,
+            hot_scheduler,
+            SchedulerConfig::default(),
         )
     }
 
@@ -727,6 +990,10 @@ mod tests {
     }
 
     async fn test_setup() -> TestSetup {
+        test_setup_with_hot_scheduler(SchedulerConfig::default()).await
+    }
+
+    async fn test_setup_with_hot_scheduler(hot_scheduler: SchedulerConfig) -> TestSetup {
         let catalog = TestCatalog::new();
         let namespace = catalog
             .create_namespace("namespace_hot_partitions_to_compact")
@@ -749,7 +1016,7 @@ mod tests {
         // Create a compactor
         // Compactor budget : 13,500
         let time_provider = Arc::new(SystemProvider::new());
-        let config = make_compactor_config();
+        let config = make_compactor_config(hot_scheduler);
         let compactor = Arc::new(Compactor::new(
             vec![shard.shard.id],
             Arc::clone(&catalog.catalog),
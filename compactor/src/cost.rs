@@ -0,0 +1,561 @@
+//! Tracking of the infrastructure cost of compaction.
+//!
+//! Every [`CostPhase`] of a compaction run gets its own instrumented view of the catalog and the
+//! object store. These count catalog requests and transactions as well as object store requests
+//! and transferred bytes, so the cost of compaction can be attributed to the individual phases
+//! and changes of the access patterns show up in the metrics.
+//!
+//! Every repository access (e.g. `repos.parquet_files()`) is counted as one catalog request.
+//! Requests within a transaction are not counted individually, only the transaction itself.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{stream::BoxStream, StreamExt};
+use iox_catalog::interface::{
+    Catalog, ColumnRepo, CompactionReportRepo, Error as CatalogError, NamespaceRepo,
+    ParquetFileRepo, PartitionRepo, ProcessedTombstoneRepo, QueryPoolRepo, RepoCollection,
+    ShardRepo, TableAccessStatsRepo, TableRepo, TombstoneRepo, TopicMetadataRepo, Transaction,
+};
+use iox_time::TimeProvider;
+use metric::U64Counter;
+use object_store::{
+    path::Path, DynObjectStore, GetResult, ListResult, MultipartId, ObjectMeta, ObjectStore,
+    Result as ObjectStoreResult,
+};
+use parquet_file::storage::ParquetStorage;
+use std::{ops::Range, sync::Arc};
+use tokio::io::AsyncWrite;
+
+/// Phase of a compaction run that catalog and object store accesses are attributed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CostPhase {
+    /// Discovery of shards and selection of the partitions to compact.
+    CandidateSelection,
+
+    /// Reading additional information (e.g. schema, sort key) of the selected partitions.
+    PartitionInfo,
+
+    /// Compaction of the selected partitions, including the lookup of their files.
+    Compaction,
+
+    /// Rewrites of existing files, see [`rewrite`](crate::rewrite).
+    Rewrite,
+}
+
+impl CostPhase {
+    /// All phases.
+    pub const ALL: [Self; 4] = [
+        Self::CandidateSelection,
+        Self::PartitionInfo,
+        Self::Compaction,
+        Self::Rewrite,
+    ];
+
+    /// Name of the phase, used as metric attribute.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::CandidateSelection => "candidate_selection",
+            Self::PartitionInfo => "partition_info",
+            Self::Compaction => "compaction",
+            Self::Rewrite => "rewrite",
+        }
+    }
+}
+
+/// Accumulated cost of compaction, see [`CostTracker::summary`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CostSummary {
+    /// Number of catalog requests outside of transactions.
+    pub catalog_requests: u64,
+
+    /// Number of catalog transactions.
+    pub catalog_transactions: u64,
+
+    /// Number of object store requests.
+    pub object_store_requests: u64,
+
+    /// Number of bytes read from the object store.
+    pub object_store_bytes_read: u64,
+
+    /// Number of bytes written to the object store.
+    pub object_store_bytes_written: u64,
+}
+
+impl CostSummary {
+    /// Cost that was accumulated since `earlier` was taken.
+    pub fn since(&self, earlier: &Self) -> Self {
+        Self {
+            catalog_requests: self
+                .catalog_requests
+                .saturating_sub(earlier.catalog_requests),
+            catalog_transactions: self
+                .catalog_transactions
+                .saturating_sub(earlier.catalog_transactions),
+            object_store_requests: self
+                .object_store_requests
+                .saturating_sub(earlier.object_store_requests),
+            object_store_bytes_read: self
+                .object_store_bytes_read
+                .saturating_sub(earlier.object_store_bytes_read),
+            object_store_bytes_written: self
+                .object_store_bytes_written
+                .saturating_sub(earlier.object_store_bytes_written),
+        }
+    }
+}
+
+/// Counters of a single phase.
+#[derive(Debug, Clone)]
+struct PhaseCounters {
+    catalog_requests: U64Counter,
+    catalog_transactions: U64Counter,
+    object_store_get: U64Counter,
+    object_store_put: U64Counter,
+    object_store_other: U64Counter,
+    bytes_read: U64Counter,
+    bytes_written: U64Counter,
+}
+
+impl PhaseCounters {
+    fn new(phase: CostPhase, registry: &metric::Registry) -> Self {
+        let catalog_requests = registry.register_metric::<U64Counter>(
+            "compactor_catalog_requests",
+            "Number of catalog requests outside of transactions issued by the compactor",
+        );
+        let catalog_transactions = registry.register_metric::<U64Counter>(
+            "compactor_catalog_transactions",
+            "Number of catalog transactions started by the compactor",
+        );
+        let object_store_requests = registry.register_metric::<U64Counter>(
+            "compactor_object_store_requests",
+            "Number of object store requests issued by the compactor",
+        );
+        let object_store_bytes = registry.register_metric::<U64Counter>(
+            "compactor_object_store_transfer_bytes",
+            "Number of bytes the compactor transferred to/from the object store",
+        );
+
+        let phase = phase.as_str();
+        Self {
+            catalog_requests: catalog_requests.recorder(&[("phase", phase)]),
+            catalog_transactions: catalog_transactions.recorder(&[("phase", phase)]),
+            object_store_get: object_store_requests.recorder(&[("phase", phase), ("op", "get")]),
+            object_store_put: object_store_requests.recorder(&[("phase", phase), ("op", "put")]),
+            object_store_other: object_store_requests
+                .recorder(&[("phase", phase), ("op", "other")]),
+            bytes_read: object_store_bytes.recorder(&[("phase", phase), ("op", "get")]),
+            bytes_written: object_store_bytes.recorder(&[("phase", phase), ("op", "put")]),
+        }
+    }
+}
+
+/// Instrumented catalog and object store for every [`CostPhase`].
+#[derive(Debug)]
+pub struct CostTracker {
+    phases: Vec<(CostPhase, PhaseCounters, Arc<dyn Catalog>, ParquetStorage)>,
+}
+
+impl CostTracker {
+    /// Create instrumented views of `catalog` and `store` for all phases.
+    pub fn new(
+        catalog: &Arc<dyn Catalog>,
+        store: &ParquetStorage,
+        registry: &metric::Registry,
+    ) -> Self {
+        let phases = CostPhase::ALL
+            .into_iter()
+            .map(|phase| {
+                let counters = PhaseCounters::new(phase, registry);
+                let catalog = Arc::new(CostTrackingCatalog {
+                    inner: Arc::clone(catalog),
+                    counters: counters.clone(),
+                }) as Arc<dyn Catalog>;
+                let store = store.clone().map_object_store(|inner| {
+                    Arc::new(CostTrackingObjectStore {
+                        inner,
+                        counters: counters.clone(),
+                    }) as Arc<DynObjectStore>
+                });
+                (phase, counters, catalog, store)
+            })
+            .collect();
+
+        Self { phases }
+    }
+
+    /// Catalog whose accesses are attributed to `phase`.
+    pub fn catalog(&self, phase: CostPhase) -> Arc<dyn Catalog> {
+        Arc::clone(&self.get(phase).2)
+    }
+
+    /// Object store whose accesses are attributed to `phase`.
+    pub fn store(&self, phase: CostPhase) -> ParquetStorage {
+        self.get(phase).3.clone()
+    }
+
+    /// Total cost of all phases since the compactor was started.
+    pub fn summary(&self) -> CostSummary {
+        self.phases
+            .iter()
+            .fold(CostSummary::default(), |acc, (_, counters, _, _)| {
+                CostSummary {
+                    catalog_requests: acc.catalog_requests + counters.catalog_requests.fetch(),
+                    catalog_transactions: acc.catalog_transactions
+                        + counters.catalog_transactions.fetch(),
+                    object_store_requests: acc.object_store_requests
+                        + counters.object_store_get.fetch()
+                        + counters.object_store_put.fetch()
+                        + counters.object_store_other.fetch(),
+                    object_store_bytes_read: acc.object_store_bytes_read
+                        + counters.bytes_read.fetch(),
+                    object_store_bytes_written: acc.object_store_bytes_written
+                        + counters.bytes_written.fetch(),
+                }
+            })
+    }
+
+    fn get(
+        &self,
+        phase: CostPhase,
+    ) -> &(CostPhase, PhaseCounters, Arc<dyn Catalog>, ParquetStorage) {
+        self.phases
+            .iter()
+            .find(|(p, _, _, _)| *p == phase)
+            .expect("all phases are initialized")
+    }
+}
+
+/// Catalog that counts requests and transactions.
+#[derive(Debug)]
+struct CostTrackingCatalog {
+    inner: Arc<dyn Catalog>,
+    counters: PhaseCounters,
+}
+
+#[async_trait]
+impl Catalog for CostTrackingCatalog {
+    async fn setup(&self) -> Result<(), CatalogError> {
+        self.inner.setup().await
+    }
+
+    async fn start_transaction(&self) -> Result<Box<dyn Transaction>, CatalogError> {
+        self.counters.catalog_transactions.inc(1);
+        self.inner.start_transaction().await
+    }
+
+    async fn repositories(&self) -> Box<dyn RepoCollection> {
+        Box::new(CostTrackingRepos {
+            inner: self.inner.repositories().await,
+            requests: self.counters.catalog_requests.clone(),
+        })
+    }
+
+    fn metrics(&self) -> Arc<metric::Registry> {
+        self.inner.metrics()
+    }
+
+    fn time_provider(&self) -> Arc<dyn TimeProvider> {
+        self.inner.time_provider()
+    }
+}
+
+/// Repositories that count every repository access as one request.
+#[derive(Debug)]
+struct CostTrackingRepos {
+    inner: Box<dyn RepoCollection>,
+    requests: U64Counter,
+}
+
+impl RepoCollection for CostTrackingRepos {
+    fn topics(&mut self) -> &mut dyn TopicMetadataRepo {
+        self.requests.inc(1);
+        self.inner.topics()
+    }
+
+    fn query_pools(&mut self) -> &mut dyn QueryPoolRepo {
+        self.requests.inc(1);
+        self.inner.query_pools()
+    }
+
+    fn namespaces(&mut self) -> &mut dyn NamespaceRepo {
+        self.requests.inc(1);
+        self.inner.namespaces()
+    }
+
+    fn tables(&mut self) -> &mut dyn TableRepo {
+        self.requests.inc(1);
+        self.inner.tables()
+    }
+
+    fn columns(&mut self) -> &mut dyn ColumnRepo {
+        self.requests.inc(1);
+        self.inner.columns()
+    }
+
+    fn shards(&mut self) -> &mut dyn ShardRepo {
+        self.requests.inc(1);
+        self.inner.shards()
+    }
+
+    fn partitions(&mut self) -> &mut dyn PartitionRepo {
+        self.requests.inc(1);
+        self.inner.partitions()
+    }
+
+    fn tombstones(&mut self) -> &mut dyn TombstoneRepo {
+        self.requests.inc(1);
+        self.inner.tombstones()
+    }
+
+    fn parquet_files(&mut self) -> &mut dyn ParquetFileRepo {
+        self.requests.inc(1);
+        self.inner.parquet_files()
+    }
+
+    fn processed_tombstones(&mut self) -> &mut dyn ProcessedTombstoneRepo {
+        self.requests.inc(1);
+        self.inner.processed_tombstones()
+    }
+
+    fn compaction_reports(&mut self) -> &mut dyn CompactionReportRepo {
+        self.requests.inc(1);
+        self.inner.compaction_reports()
+    }
+
+    fn table_access_stats(&mut self) -> &mut dyn TableAccessStatsRepo {
+        self.requests.inc(1);
+        self.inner.table_access_stats()
+    }
+}
+
+/// Object store that counts requests and transferred bytes.
+///
+/// Bytes written through multipart uploads are not counted.
+#[derive(Debug)]
+struct CostTrackingObjectStore {
+    inner: Arc<DynObjectStore>,
+    counters: PhaseCounters,
+}
+
+impl std::fmt::Display for CostTrackingObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CostTrackingObjectStore({})", self.inner)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for CostTrackingObjectStore {
+    async fn put(&self, location: &Path, bytes: Bytes) -> ObjectStoreResult<()> {
+        self.counters.object_store_put.inc(1);
+        let size = bytes.len();
+        let res = self.inner.put(location, bytes).await;
+        if res.is_ok() {
+            self.counters.bytes_written.inc(size as u64);
+        }
+        res
+    }
+
+    async fn put_multipart(
+        &self,
+        location: &Path,
+    ) -> ObjectStoreResult<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+        self.counters.object_store_put.inc(1);
+        self.inner.put_multipart(location).await
+    }
+
+    async fn abort_multipart(
+        &self,
+        location: &Path,
+        multipart_id: &MultipartId,
+    ) -> ObjectStoreResult<()> {
+        self.counters.object_store_other.inc(1);
+        self.inner.abort_multipart(location, multipart_id).await
+    }
+
+    async fn get(&self, location: &Path) -> ObjectStoreResult<GetResult> {
+        self.counters.object_store_get.inc(1);
+        match self.inner.get(location).await? {
+            GetResult::File(file, path) => {
+                if let Ok(m) = file.metadata() {
+                    self.counters.bytes_read.inc(m.len());
+                }
+                Ok(GetResult::File(file, path))
+            }
+            GetResult::Stream(s) => {
+                let bytes_read = self.counters.bytes_read.clone();
+                Ok(GetResult::Stream(
+                    s.map(move |res| {
+                        if let Ok(bytes) = &res {
+                            bytes_read.inc(bytes.len() as u64);
+                        }
+                        res
+                    })
+                    .boxed(),
+                ))
+            }
+        }
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> ObjectStoreResult<Bytes> {
+        self.counters.object_store_get.inc(1);
+        let bytes = self.inner.get_range(location, range).await?;
+        self.counters.bytes_read.inc(bytes.len() as u64);
+        Ok(bytes)
+    }
+
+    async fn head(&self, location: &Path) -> ObjectStoreResult<ObjectMeta> {
+        self.counters.object_store_other.inc(1);
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> ObjectStoreResult<()> {
+        self.counters.object_store_other.inc(1);
+        self.inner.delete(location).await
+    }
+
+    async fn list(
+        &self,
+        prefix: Option<&Path>,
+    ) -> ObjectStoreResult<BoxStream<'_, ObjectStoreResult<ObjectMeta>>> {
+        self.counters.object_store_other.inc(1);
+        self.inner.list(prefix).await
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> ObjectStoreResult<ListResult> {
+        self.counters.object_store_other.inc(1);
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> ObjectStoreResult<()> {
+        self.counters.object_store_other.inc(1);
+        self.inner.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> ObjectStoreResult<()> {
+        self.counters.object_store_other.inc(1);
+        self.inner.copy_if_not_exists(from, to).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iox_catalog::mem::MemCatalog;
+    use metric::{Attributes, Metric};
+
+    #[tokio::test]
+    async fn test_cost_tracker() {
+        let registry = metric::Registry::new();
+        let catalog: Arc<dyn Catalog> =
+            Arc::new(MemCatalog::new(Arc::new(metric::Registry::new())));
+        let object_store: Arc<DynObjectStore> = Arc::new(object_store::memory::InMemory::new());
+        let tracker = CostTracker::new(
+            &catalog,
+            &ParquetStorage::new(Arc::clone(&object_store)),
+            &registry,
+        );
+        assert_eq!(tracker.summary(), CostSummary::default());
+
+        // catalog accesses
+        let phase_catalog = tracker.catalog(CostPhase::CandidateSelection);
+        let mut repos = phase_catalog.repositories().await;
+        repos.topics().create_or_get("foo").await.unwrap();
+        repos.shards().list().await.unwrap();
+        drop(repos);
+        let txn = tracker
+            .catalog(CostPhase::Compaction)
+            .start_transaction()
+            .await
+            .unwrap();
+        txn.abort().await.unwrap();
+
+        assert_eq!(
+            get_counter(
+                &registry,
+                "compactor_catalog_requests",
+                &[("phase", "candidate_selection")]
+            ),
+            2
+        );
+        assert_eq!(
+            get_counter(
+                &registry,
+                "compactor_catalog_requests",
+                &[("phase", "compaction")]
+            ),
+            0
+        );
+        assert_eq!(
+            get_counter(
+                &registry,
+                "compactor_catalog_transactions",
+                &[("phase", "compaction")]
+            ),
+            1
+        );
+
+        // object store accesses
+        let before = tracker.summary();
+        let store = tracker.store(CostPhase::Rewrite);
+        let path = Path::from("foo");
+        let phase_object_store = store.object_store();
+        phase_object_store
+            .put(&path, Bytes::from_static(b"hello"))
+            .await
+            .unwrap();
+        let data = phase_object_store
+            .get(&path)
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        assert_eq!(data.as_ref(), b"hello");
+        phase_object_store.get_range(&path, 1..3).await.unwrap();
+        phase_object_store.head(&path).await.unwrap();
+
+        assert_eq!(
+            get_counter(
+                &registry,
+                "compactor_object_store_requests",
+                &[("phase", "rewrite"), ("op", "get")]
+            ),
+            2
+        );
+        assert_eq!(
+            get_counter(
+                &registry,
+                "compactor_object_store_transfer_bytes",
+                &[("phase", "rewrite"), ("op", "get")]
+            ),
+            7
+        );
+        assert_eq!(
+            tracker.summary().since(&before),
+            CostSummary {
+                catalog_requests: 0,
+                catalog_transactions: 0,
+                object_store_requests: 4,
+                object_store_bytes_read: 7,
+                object_store_bytes_written: 5,
+            }
+        );
+
+        // the underlying store is not instrumented
+        object_store.head(&path).await.unwrap();
+        assert_eq!(tracker.summary().since(&before).object_store_requests, 4);
+    }
+
+    fn get_counter<const N: usize>(
+        registry: &metric::Registry,
+        name: &'static str,
+        attributes: &[(&'static str, &'static str); N],
+    ) -> u64 {
+        registry
+            .get_instrument::<Metric<U64Counter>>(name)
+            .unwrap()
+            .get_observer(&Attributes::from(attributes))
+            .unwrap()
+            .fetch()
+    }
+}
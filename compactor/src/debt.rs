@@ -0,0 +1,327 @@
+//! Compaction debt, i.e. the work the compactor has not caught up with yet.
+//!
+//! The debt is periodically computed per shard and published as gauges, so that compactors can be
+//! scaled by the amount of outstanding work instead of by CPU usage.
+
+use crate::{compact::Compactor, cost::CostPhase};
+use data_types::{CompactionLevel, ParquetFile, PartitionId, ShardId, TablePartition, Timestamp};
+use metric::Attributes;
+use observability_deps::tracing::{debug, warn};
+use snafu::{ResultExt, Snafu};
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+use tokio_util::sync::CancellationToken;
+
+#[derive(Debug, Snafu)]
+#[allow(missing_copy_implementations, missing_docs)]
+pub enum Error {
+    #[snafu(display("Error listing shards {}", source))]
+    ListingShards { source: crate::compact::Error },
+
+    #[snafu(display("Error listing level 0 files of shard {}: {}", shard_id, source))]
+    ListingLevel0Files {
+        shard_id: ShardId,
+        source: iox_catalog::interface::Error,
+    },
+
+    #[snafu(display(
+        "Error listing level 1 files of partition {}: {}",
+        partition_id,
+        source
+    ))]
+    ListingLevel1Files {
+        partition_id: PartitionId,
+        source: iox_catalog::interface::Error,
+    },
+}
+
+/// A specialized `Error` for compaction debt errors
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Compaction debt of a shard.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionDebt {
+    /// Number of level 0 files that were created before the configured age threshold.
+    pub old_level_0_files: u64,
+
+    /// Total size of the old level 0 files.
+    pub old_level_0_bytes: u64,
+
+    /// Number of files whose time range overlaps with another file of their partition, i.e. files
+    /// that need to be deduplicated by compaction.
+    pub overlapping_files: u64,
+
+    /// Total size of the overlapping files.
+    pub overlapping_bytes: u64,
+}
+
+/// Compute the compaction debt of `shard_id`.
+///
+/// Level 0 files created more than `level_0_age` ago count as old.
+pub async fn compute_debt(
+    compactor: &Compactor,
+    shard_id: ShardId,
+    level_0_age: Duration,
+) -> Result<CompactionDebt> {
+    let older_than = compactor
+        .time_provider
+        .now()
+        .checked_sub(level_0_age)
+        .map(|t| Timestamp::new(t.timestamp_nanos()));
+
+    let mut repos = compactor
+        .cost
+        .catalog(CostPhase::CandidateSelection)
+        .repositories()
+        .await;
+    let level_0 = repos
+        .parquet_files()
+        .level_0(shard_id)
+        .await
+        .context(ListingLevel0FilesSnafu { shard_id })?;
+
+    let mut debt = CompactionDebt::default();
+    let mut partitions: BTreeMap<TablePartition, Vec<ParquetFile>> = BTreeMap::new();
+    for file in level_0 {
+        if older_than.map_or(false, |older_than| file.created_at < older_than) {
+            debt.old_level_0_files += 1;
+            debt.old_level_0_bytes += file.file_size_bytes as u64;
+        }
+
+        partitions
+            .entry(TablePartition::new(
+                file.shard_id,
+                file.table_id,
+                file.partition_id,
+            ))
+            .or_default()
+            .push(file);
+    }
+
+    for (table_partition, mut files) in partitions {
+        let min_time = files.iter().map(|f| f.min_time).min().expect("not empty");
+        let max_time = files.iter().map(|f| f.max_time).max().expect("not empty");
+        let level_1 = repos
+            .parquet_files()
+            .level_1(table_partition, min_time, max_time)
+            .await
+            .context(ListingLevel1FilesSnafu {
+                partition_id: table_partition.partition_id,
+            })?;
+        files.extend(level_1);
+
+        for file in overlapping(&files) {
+            debt.overlapping_files += 1;
+            debt.overlapping_bytes += file.file_size_bytes as u64;
+        }
+    }
+
+    Ok(debt)
+}
+
+/// Files of `files` whose time range overlaps with at least one other file.
+///
+/// Level 1 files never overlap each other, so every overlap involves a level 0 file.
+fn overlapping(files: &[ParquetFile]) -> Vec<&ParquetFile> {
+    let mut sorted: Vec<_> = files.iter().collect();
+    sorted.sort_by_key(|f| (f.min_time, f.id));
+
+    let mut overlapping = HashSet::new();
+    for (i, a) in sorted.iter().enumerate() {
+        for b in &sorted[i + 1..] {
+            if b.min_time > a.max_time {
+                break;
+            }
+            if a.compaction_level == CompactionLevel::Initial
+                || b.compaction_level == CompactionLevel::Initial
+            {
+                overlapping.insert(a.id);
+                overlapping.insert(b.id);
+            }
+        }
+    }
+
+    sorted
+        .into_iter()
+        .filter(|f| overlapping.contains(&f.id))
+        .collect()
+}
+
+/// Compute the compaction debt of all shards of the compactor every `interval` and publish it as
+/// gauges, until `shutdown` is triggered.
+pub(crate) async fn run_debt_metrics(
+    compactor: Arc<Compactor>,
+    interval: Duration,
+    level_0_age: Duration,
+    shutdown: CancellationToken,
+) {
+    while !shutdown.is_cancelled() {
+        if let Err(e) = update_debt_metrics(&compactor, level_0_age).await {
+            warn!(%e, "cannot compute compaction debt");
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {},
+            _ = shutdown.cancelled() => {},
+        }
+    }
+}
+
+async fn update_debt_metrics(compactor: &Compactor, level_0_age: Duration) -> Result<()> {
+    let shards = compactor.shards().await.context(ListingShardsSnafu)?;
+
+    for shard_id in shards {
+        let debt = compute_debt(compactor, shard_id, level_0_age).await?;
+        debug!(%shard_id, ?debt, "compaction debt");
+
+        let shard_id = format!("{}", shard_id);
+        for (kind, files, bytes) in [
+            (
+                "old_level_0",
+                debt.old_level_0_files,
+                debt.old_level_0_bytes,
+            ),
+            (
+                "overlapping",
+                debt.overlapping_files,
+                debt.overlapping_bytes,
+            ),
+        ] {
+            let attributes =
+                Attributes::from([("shard_id", shard_id.clone().into()), ("kind", kind.into())]);
+            compactor
+                .compaction_debt_files
+                .recorder(attributes.clone())
+                .set(files);
+            compactor
+                .compaction_debt_bytes
+                .recorder(attributes)
+                .set(bytes);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handler::{CompactorConfig, SchedulerConfig};
+    use backoff::BackoffConfig;
+    use iox_query::exec::Executor;
+    use iox_tests::util::{TestCatalog, TestParquetFileBuilder};
+    use parquet_file::storage::ParquetStorage;
+
+    fn make_compactor(catalog: &TestCatalog, shards: Vec<ShardId>) -> Compactor {
+        let config = CompactorConfig::new(
+            100 * 1024 * 1024, // max_desired_file_size_bytes
+            30,                // percentage_max_file_size
+            80,                // split_percentage
+            90_000,            // max_cold_concurrent_size_bytes
+            1,                 // max_number_partitions_per_shard
+            1,                 // min_number_recent_ingested_files_per_partition
+            600 * 1024 * 1024, // cold_input_size_threshold_bytes
+            100,               // cold_input_file_count_threshold
+            4,                 // hot_multiple
+            100_000_000,       // memory_budget_bytes
+            false,             // write_compaction_reports
+            SchedulerConfig::default(),
+            SchedulerConfig::default(),
+        );
+
+        Compactor::new(
+            shards,
+            Arc::clone(&catalog.catalog),
+            ParquetStorage::new(Arc::clone(&catalog.object_store)),
+            Arc::new(Executor::new(1)),
+            catalog.time_provider(),
+            BackoffConfig::default(),
+            config,
+            Arc::clone(&catalog.metric_registry),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_compute_debt() {
+        let catalog = TestCatalog::new();
+        let ns = catalog.create_namespace("ns").await;
+        let shard = ns.create_shard(1).await;
+        let table = ns.create_table("table").await;
+        let partition1 = table.with_shard(&shard).create_partition("one").await;
+        let partition2 = table.with_shard(&shard).create_partition("two").await;
+        let compactor = make_compactor(&catalog, vec![shard.shard.id]);
+        let level_0_age = Duration::from_secs(3_600);
+
+        // nothing to compact
+        let debt = compute_debt(&compactor, shard.shard.id, level_0_age)
+            .await
+            .unwrap();
+        assert_eq!(debt, CompactionDebt::default());
+
+        // partition 1: an old L0 file overlapping with an L1 file, and an L1 file without overlap
+        catalog
+            .mock_time_provider()
+            .inc(Duration::from_secs(2 * 3_600));
+        let now = catalog.time_provider().now().timestamp_nanos();
+        for (min_time, max_time, level, creation_time, size) in [
+            (10, 20, CompactionLevel::Initial, 1, 100),
+            (15, 30, CompactionLevel::FileNonOverlapped, 1, 1_000),
+            (40, 50, CompactionLevel::FileNonOverlapped, 1, 10_000),
+        ] {
+            partition1
+                .create_parquet_file_catalog_record(
+                    TestParquetFileBuilder::default()
+                        .with_min_time(min_time)
+                        .with_max_time(max_time)
+                        .with_compaction_level(level)
+                        .with_creation_time(creation_time)
+                        .with_file_size_bytes(size),
+                )
+                .await;
+        }
+
+        // partition 2: two recent L0 files overlapping each other and one without overlap
+        for (min_time, max_time, size) in [(10, 20, 1), (20, 30, 2), (40, 50, 4)] {
+            partition2
+                .create_parquet_file_catalog_record(
+                    TestParquetFileBuilder::default()
+                        .with_min_time(min_time)
+                        .with_max_time(max_time)
+                        .with_creation_time(now)
+                        .with_file_size_bytes(size),
+                )
+                .await;
+        }
+
+        let debt = compute_debt(&compactor, shard.shard.id, level_0_age)
+            .await
+            .unwrap();
+        assert_eq!(
+            debt,
+            CompactionDebt {
+                old_level_0_files: 1,
+                old_level_0_bytes: 100,
+                overlapping_files: 4,
+                overlapping_bytes: 100 + 1_000 + 1 + 2,
+            }
+        );
+
+        // metrics are published per shard
+        update_debt_metrics(&compactor, level_0_age).await.unwrap();
+        let attributes = Attributes::from([
+            ("shard_id", format!("{}", shard.shard.id).into()),
+            ("kind", "overlapping".into()),
+        ]);
+        assert_eq!(
+            compactor
+                .compaction_debt_bytes
+                .get_observer(&attributes)
+                .unwrap()
+                .fetch(),
+            1_103
+        );
+    }
+}
@@ -1,14 +1,17 @@
 //! Clean up parquet files from object storage and their associated entries in the catalog that are
 //! no longer needed because they've been compacted and they're old enough to no longer be used by
 //! any queriers.
+//!
+//! Before enabling the (destructive) cleanup in a new environment, [`GarbageCollector::report`]
+//! can be used to see what a cleanup would delete.
 
-use data_types::Timestamp;
+use data_types::{ParquetFile, Timestamp};
 use iox_catalog::interface::Catalog;
 use iox_time::TimeProvider;
-use object_store::DynObjectStore;
-use parquet_file::ParquetFilePath;
+use object_store::{path::Path, DynObjectStore};
+use parquet_file::{ParquetFilePath, ParquetPathLayout};
 use snafu::{ResultExt, Snafu};
-use std::sync::Arc;
+use std::{fmt::Write, sync::Arc};
 
 #[derive(Debug, Snafu)]
 #[allow(missing_copy_implementations, missing_docs)]
@@ -20,6 +23,17 @@ pub enum Error {
 
     #[snafu(display("Error(s) while deleting object store files: {:#?}", sources))]
     DeletingObjectStoreFiles { sources: Vec<object_store::Error> },
+
+    #[snafu(display("Error while listing catalog records {}", source))]
+    ListingCatalogRecords {
+        source: iox_catalog::interface::Error,
+    },
+
+    #[snafu(display("Error while writing report to {}: {}", path, source))]
+    WritingReport {
+        path: Path,
+        source: object_store::Error,
+    },
 }
 
 /// A specialized `Result` for garbage collection errors
@@ -65,17 +79,24 @@ impl GarbageCollector {
         let mut object_store_errors = Vec::with_capacity(deleted_catalog_records.len());
 
         for catalog_record in deleted_catalog_records {
-            let path = ParquetFilePath::new(
-                catalog_record.namespace_id,
-                catalog_record.table_id,
-                catalog_record.shard_id,
-                catalog_record.partition_id,
-                catalog_record.object_store_id,
-            );
-            let path = path.object_store_path();
-
-            if let Err(e) = self.object_store.delete(&path).await {
-                object_store_errors.push(e);
+            // the file may have been written with any path layout
+            let path = ParquetFilePath::from(&catalog_record);
+            let mut not_found = vec![];
+            for layout in [ParquetPathLayout::V1, ParquetPathLayout::V2] {
+                match self
+                    .object_store
+                    .delete(&path.object_store_path_with_layout(layout))
+                    .await
+                {
+                    Ok(()) => {}
+                    Err(e @ object_store::Error::NotFound { .. }) => not_found.push(e),
+                    Err(e) => object_store_errors.push(e),
+                }
+            }
+
+            // only a missing file is an error, not a missing path of the other layout
+            if not_found.len() == 2 {
+                object_store_errors.extend(not_found.into_iter().take(1));
             }
         }
 
@@ -88,14 +109,126 @@ impl GarbageCollector {
             .fail()
         }
     }
+
+    /// Dry run of [`cleanup`](Self::cleanup): list the files a cleanup with the same `older_than`
+    /// would delete, without deleting anything.
+    pub async fn report(&self, older_than: Timestamp) -> Result<GarbageCollectorReport> {
+        let candidates = self
+            .catalog
+            .repositories()
+            .await
+            .parquet_files()
+            .list_old(older_than)
+            .await
+            .context(ListingCatalogRecordsSnafu)?
+            .iter()
+            .map(|catalog_record| Candidate {
+                path: object_store_path(catalog_record),
+                file_size_bytes: catalog_record.file_size_bytes,
+                // `list_old` only returns files that are marked for deletion
+                to_delete: catalog_record.to_delete.unwrap_or(older_than),
+            })
+            .collect();
+
+        Ok(GarbageCollectorReport {
+            now: Timestamp::new(self.time_provider.now().timestamp_nanos()),
+            candidates,
+        })
+    }
+
+    /// Write `report` as CSV to `path` in the object store, e.g. to keep it next to the data it
+    /// describes.
+    pub async fn write_report(&self, report: &GarbageCollectorReport, path: &Path) -> Result<()> {
+        self.object_store
+            .put(path, report.to_csv().into())
+            .await
+            .context(WritingReportSnafu { path: path.clone() })
+    }
+}
+
+/// An object that a garbage collection would delete, see [`GarbageCollector::report`].
+///
+/// The path is given in the default [`ParquetPathLayout`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    /// Location of the object in the object store
+    pub path: Path,
+    /// Size of the object, as recorded in the catalog
+    pub file_size_bytes: i64,
+    /// When the file was marked for deletion
+    pub to_delete: Timestamp,
+}
+
+/// The objects that a garbage collection would delete, see [`GarbageCollector::report`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GarbageCollectorReport {
+    /// When the report was created, for computing the age of the candidates
+    pub now: Timestamp,
+    /// The objects that would be deleted
+    pub candidates: Vec<Candidate>,
+}
+
+impl GarbageCollectorReport {
+    /// Number of objects that would be deleted
+    pub fn count(&self) -> usize {
+        self.candidates.len()
+    }
+
+    /// Total size of the objects that would be deleted
+    pub fn total_bytes(&self) -> i64 {
+        self.candidates.iter().map(|c| c.file_size_bytes).sum()
+    }
+
+    /// The candidate that was marked for deletion first
+    pub fn oldest(&self) -> Option<&Candidate> {
+        self.candidates.iter().min_by_key(|c| c.to_delete)
+    }
+
+    /// The candidate that was marked for deletion last
+    pub fn newest(&self) -> Option<&Candidate> {
+        self.candidates.iter().max_by_key(|c| c.to_delete)
+    }
+
+    /// Age of `candidate`, i.e. the time since it was marked for deletion, in seconds
+    pub fn age_secs(&self, candidate: &Candidate) -> i64 {
+        (self.now.get() - candidate.to_delete.get()) / 1_000_000_000
+    }
+
+    /// Render the report as CSV with one row per candidate.
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("path,file_size_bytes,to_delete,age_secs\n");
+        for candidate in &self.candidates {
+            writeln!(
+                out,
+                "{},{},{},{}",
+                candidate.path,
+                candidate.file_size_bytes,
+                candidate.to_delete.get(),
+                self.age_secs(candidate)
+            )
+            .unwrap();
+        }
+        out
+    }
+}
+
+fn object_store_path(catalog_record: &ParquetFile) -> Path {
+    ParquetFilePath::new(
+        catalog_record.namespace_id,
+        catalog_record.table_id,
+        catalog_record.shard_id,
+        catalog_record.partition_id,
+        catalog_record.object_store_id,
+    )
+    .object_store_path()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use data_types::{
-        ColumnId, ColumnSet, CompactionLevel, ParquetFile, ParquetFileParams, SequenceNumber,
-        ShardIndex,
+        ColumnId, ColumnSet, ColumnStatsSet, CompactionLevel, ParquetFile, ParquetFileParams,
+        SequenceNumber, ShardIndex,
     };
     use futures::{StreamExt, TryStreamExt};
     use iox_tests::util::TestCatalog;
@@ -187,6 +320,7 @@ mod tests {
             created_at: Timestamp::new(1),
             compaction_level: CompactionLevel::Initial,
             column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::default(),
         };
         let parquet_file = txn
             .parquet_files()
@@ -268,6 +402,7 @@ mod tests {
             created_at: Timestamp::new(1),
             compaction_level: CompactionLevel::Initial,
             column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::default(),
         };
         let parquet_file = txn
             .parquet_files()
@@ -353,6 +488,7 @@ mod tests {
             created_at: Timestamp::new(1),
             compaction_level: CompactionLevel::Initial,
             column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::default(),
         };
         let parquet_file = txn
             .parquet_files()
@@ -384,4 +520,121 @@ mod tests {
         let mut list = catalog.object_store.list(None).await.unwrap();
         assert!(list.next().await.is_none());
     }
+
+    #[tokio::test]
+    async fn report_old_enough_files_without_deleting() {
+        let catalog = TestCatalog::new();
+        let gc = GarbageCollector::new(
+            Arc::clone(&catalog.catalog),
+            Arc::clone(&catalog.object_store),
+        );
+        let older_than =
+            Timestamp::new((gc.time_provider.now() + Duration::from_secs(100)).timestamp_nanos());
+
+        // nothing to report
+        let report = gc.report(older_than).await.unwrap();
+        assert_eq!(report.count(), 0);
+        assert_eq!(report.total_bytes(), 0);
+        assert!(report.oldest().is_none());
+        assert_eq!(report.to_csv(), "path,file_size_bytes,to_delete,age_secs\n");
+
+        let mut txn = catalog.catalog.start_transaction().await.unwrap();
+        let topic = txn.topics().create_or_get("foo").await.unwrap();
+        let pool = txn.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = txn
+            .namespaces()
+            .create("gc_report_old_enough_files", "inf", topic.id, pool.id)
+            .await
+            .unwrap();
+        let table = txn
+            .tables()
+            .create_or_get("test_table", namespace.id)
+            .await
+            .unwrap();
+        let shard = txn
+            .shards()
+            .create_or_get(&topic, ShardIndex::new(1))
+            .await
+            .unwrap();
+        let partition = txn
+            .partitions()
+            .create_or_get("one".into(), shard.id, table.id)
+            .await
+            .unwrap();
+
+        let mut parquet_files = vec![];
+        for file_size_bytes in [1337, 42, 7] {
+            let parquet_file_params = ParquetFileParams {
+                shard_id: shard.id,
+                namespace_id: namespace.id,
+                table_id: partition.table_id,
+                partition_id: partition.id,
+                object_store_id: Uuid::new_v4(),
+                max_sequence_number: SequenceNumber::new(140),
+                min_time: Timestamp::new(1),
+                max_time: Timestamp::new(10),
+                file_size_bytes,
+                row_count: 0,
+                created_at: Timestamp::new(1),
+                compaction_level: CompactionLevel::Initial,
+                column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+                column_stats: ColumnStatsSet::default(),
+            };
+            let parquet_file = txn
+                .parquet_files()
+                .create(parquet_file_params)
+                .await
+                .unwrap();
+            put_object_store_file(&parquet_file, Arc::clone(&catalog.object_store)).await;
+            parquet_files.push(parquet_file);
+        }
+
+        // the last file is not marked for deletion
+        for parquet_file in &parquet_files[..2] {
+            txn.parquet_files()
+                .flag_for_delete(parquet_file.id)
+                .await
+                .unwrap();
+        }
+
+        txn.commit().await.unwrap();
+
+        let report = gc.report(older_than).await.unwrap();
+        assert_eq!(report.count(), 2);
+        assert_eq!(report.total_bytes(), 1337 + 42);
+        assert!(report.oldest().unwrap().to_delete <= report.newest().unwrap().to_delete);
+
+        // the report is written to the object store, without deleting anything
+        let report_path = Path::from("gc_report.csv");
+        gc.write_report(&report, &report_path).await.unwrap();
+        let csv = catalog
+            .object_store
+            .get(&report_path)
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let csv = std::str::from_utf8(&csv).unwrap();
+        assert_eq!(csv, report.to_csv());
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with(&object_store_path(&parquet_files[0]).to_string()));
+        assert!(lines[1].contains(",1337,"));
+
+        assert_eq!(
+            catalog
+                .catalog
+                .repositories()
+                .await
+                .parquet_files()
+                .count()
+                .await
+                .unwrap(),
+            3
+        );
+        let list = catalog.object_store.list(None).await.unwrap();
+        let obj_store_paths: Vec<_> = list.try_collect().await.unwrap();
+        assert_eq!(obj_store_paths.len(), 4);
+    }
 }
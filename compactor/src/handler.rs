@@ -2,13 +2,15 @@
 
 use async_trait::async_trait;
 use backoff::Backoff;
+use data_types::{CompactionLevel, PartitionId, PartitionParam, TableId};
 use futures::{
     future::{BoxFuture, Shared},
-    FutureExt, StreamExt, TryFutureExt,
+    Future, FutureExt, StreamExt, TryFutureExt,
 };
 use iox_query::exec::Executor;
 use metric::Attributes;
 use observability_deps::tracing::*;
+use serde::Serialize;
 use std::sync::Arc;
 
 use thiserror::Error;
@@ -18,15 +20,56 @@ use tokio::{
 };
 use tokio_util::sync::CancellationToken;
 
-use crate::{compact::Compactor, compact_hot_partitions};
+use crate::{
+    compact::{self, Compactor},
+    compact_hot_partitions,
+    cost::CostPhase,
+    debt,
+    rewrite::{self, RewriteSummary},
+    state::{CompactionKind, StateSnapshot},
+};
 
 #[derive(Debug, Error)]
 #[allow(missing_copy_implementations, missing_docs)]
-pub enum Error {}
+pub enum Error {
+    #[error("Error rewriting table: {0}")]
+    Rewrite(#[from] rewrite::Error),
+
+    #[error("Error querying the catalog: {0}")]
+    Catalog(#[from] iox_catalog::interface::Error),
+
+    #[error("Could not find partition {0:?}")]
+    PartitionNotFound(PartitionId),
+
+    #[error("Could not find table {0:?}")]
+    TableNotFound(TableId),
+
+    #[error("Partition {0:?} does not belong to a shard of this compactor")]
+    ShardNotAssigned(PartitionId),
 
-/// The [`CompactorHandler`] does nothing at this point
+    #[error("Error gathering partition information: {0}")]
+    PartitionInfo(#[from] compact::Error),
+
+    #[error("Error compacting partition {partition_id:?}: {message}")]
+    Compaction {
+        partition_id: PartitionId,
+        message: String,
+    },
+}
+
+/// The [`CompactorHandler`] runs compaction in the background and serves on-demand compaction
+/// requests.
 #[async_trait]
 pub trait CompactorHandler: Send + Sync {
+    /// Rewrite all files of the given table, see [`rewrite_table`](rewrite::rewrite_table).
+    async fn rewrite_table(&self, table_id: TableId) -> Result<RewriteSummary, Error>;
+
+    /// Compact the given partition right away, see [`compact_partition`].
+    async fn compact_partition(&self, partition_id: PartitionId) -> Result<(), Error>;
+
+    /// Current activity of the compactor, see [`CompactorState`](crate::state::CompactorState).
+    fn state(&self) -> StateSnapshot;
+
     /// Wait until the handler finished  to shutdown.
     ///
     /// Use [`shutdown`](Self::shutdown) to trigger a shutdown.
@@ -48,17 +91,22 @@ fn shared_handle(handle: JoinHandle<()>) -> SharedJoinHandle {
 #[derive(Debug)]
 pub struct CompactorHandlerImpl {
     /// Data to compact
-    #[allow(dead_code)]
     compactor_data: Arc<Compactor>,
 
     /// A token that is used to trigger shutdown of the background worker
     shutdown: CancellationToken,
 
-    /// Runner to check for compaction work and kick it off
-    runner_handle: SharedJoinHandle,
+    /// Runner to check for hot compaction work and kick it off
+    hot_runner_handle: SharedJoinHandle,
+
+    /// Runner to check for cold compaction work and kick it off
+    cold_runner_handle: SharedJoinHandle,
+
+    /// Runner to publish the compaction debt, if enabled
+    debt_runner_handle: Option<SharedJoinHandle>,
 
-    /// Executor, required for clean shutdown.
-    exec: Arc<Executor>,
+    /// Executors, required for clean shutdown.
+    execs: Vec<Arc<Executor>>,
 }
 
 impl CompactorHandlerImpl {
@@ -67,26 +115,51 @@ impl CompactorHandlerImpl {
         let compactor_data = Arc::new(compactor);
 
         let shutdown = CancellationToken::new();
-        let runner_handle = tokio::task::spawn(run_compactor(
+        let hot_runner_handle = shared_handle(tokio::task::spawn(run_hot_compactor(
             Arc::clone(&compactor_data),
             shutdown.child_token(),
-        ));
-        let runner_handle = shared_handle(runner_handle);
+        )));
+        let cold_runner_handle = shared_handle(tokio::task::spawn(run_cold_compactor(
+            Arc::clone(&compactor_data),
+            shutdown.child_token(),
+        )));
+        let debt_runner_handle = compactor_data.config.debt_interval().map(|interval| {
+            shared_handle(tokio::task::spawn(debt::run_debt_metrics(
+                Arc::clone(&compactor_data),
+                interval,
+                compactor_data.config.debt_level_0_age(),
+                shutdown.child_token(),
+            )))
+        });
         info!("compactor started with config {:?}", compactor_data.config);
 
-        let exec = Arc::clone(&compactor_data.exec);
+        let mut execs = vec![Arc::clone(&compactor_data.exec)];
+        if !Arc::ptr_eq(&compactor_data.exec, &compactor_data.cold_exec) {
+            execs.push(Arc::clone(&compactor_data.cold_exec));
+        }
 
         Self {
             compactor_data,
             shutdown,
-            runner_handle,
-            exec,
+            hot_runner_handle,
+            cold_runner_handle,
+            debt_runner_handle,
+            execs,
         }
     }
 }
 
+/// Number of compaction levels above level 1 that the compactor can be configured with, see
+/// [`CompactorConfig::with_upper_level_file_sizes_bytes`].
+pub const MAX_UPPER_LEVELS: usize =
+    CompactionLevel::MAX as usize - CompactionLevel::FileNonOverlapped as usize;
+
+/// Default age after which level 0 files count as compaction debt, see
+/// [`CompactorConfig::with_debt_metrics`].
+pub const DEFAULT_DEBT_LEVEL_0_AGE: Duration = Duration::from_secs(60 * 60);
+
 /// The configuration options for the compactor.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize)]
 pub struct CompactorConfig {
     /// Desired max size of compacted parquet files
     /// It is a target desired value than a guarantee
@@ -147,6 +220,66 @@ pub struct CompactorConfig {
     /// How many candidates compacted concurrently are also decided using this estimation and
     /// budget.
     memory_budget_bytes: u64,
+
+    /// Write a [compaction report](crate::report::CompactionRunReport) for every compaction run.
+    write_compaction_reports: bool,
+
+    /// Scheduling of hot partition compaction
+    hot_scheduler: SchedulerConfig,
+
+    /// Scheduling of cold partition compaction
+    cold_scheduler: SchedulerConfig,
+
+    /// If set, compacted files are split at multiples of this interval since the epoch instead
+    /// of by `split_percentage`.
+    split_interval: Option<Duration>,
+
+    /// Target file sizes of the compaction levels above level 1, starting with level 2. Levels
+    /// with a target size of 0 are not used.
+    upper_level_file_sizes_bytes: [u64; MAX_UPPER_LEVELS],
+
+    /// If set, the [compaction debt](crate::debt) is published as metrics at this interval.
+    debt_interval: Option<Duration>,
+
+    /// Level 0 files older than this count as compaction debt.
+    debt_level_0_age: Duration,
+
+    /// If set, hot compaction skips partitions that an ingester started persisting less than
+    /// this long ago.
+    hot_persist_grace_period: Option<Duration>,
+}
+
+/// How the compaction of one kind of partitions (hot or cold) is scheduled.
+///
+/// Hot and cold partitions are compacted by independent background loops, so that long cold
+/// compactions cannot delay the compaction of recently written data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SchedulerConfig {
+    /// How long to pause before checking for more work again if a cycle found no work
+    pub tick_interval: Duration,
+
+    /// Max number of partitions compacted concurrently, in addition to the limits given by the
+    /// memory budget (hot) and the cold concurrent size (cold). 0 means no additional limit.
+    pub max_concurrent_partitions: usize,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            tick_interval: PAUSE_BETWEEN_NO_WORK,
+            max_concurrent_partitions: 0,
+        }
+    }
+}
+
+impl SchedulerConfig {
+    /// Limit `n` partitions to the max number of concurrently compacted partitions, if any
+    pub(crate) fn limit_concurrency(&self, n: usize) -> usize {
+        match self.max_concurrent_partitions {
+            0 => n,
+            max => n.min(max),
+        }
+    }
 }
 
 impl CompactorConfig {
@@ -163,6 +296,9 @@ impl CompactorConfig {
         cold_input_file_count_threshold: usize,
         hot_multiple: usize,
         memory_budget_bytes: u64,
+        write_compaction_reports: bool,
+        hot_scheduler: SchedulerConfig,
+        cold_scheduler: SchedulerConfig,
     ) -> Self {
         assert!(split_percentage > 0 && split_percentage <= 100);
 
@@ -177,6 +313,80 @@ impl CompactorConfig {
             cold_input_file_count_threshold,
             memory_budget_bytes,
             hot_multiple,
+            write_compaction_reports,
+            hot_scheduler,
+            cold_scheduler,
+            split_interval: None,
+            upper_level_file_sizes_bytes: [0; MAX_UPPER_LEVELS],
+            debt_interval: None,
+            debt_level_0_age: DEFAULT_DEBT_LEVEL_0_AGE,
+            hot_persist_grace_period: None,
+        }
+    }
+
+    /// Split compacted files at multiples of `split_interval` since the epoch (e.g. at UTC day
+    /// boundaries), so that no compacted file spans a boundary. `None` splits by size only.
+    pub fn with_split_interval(self, split_interval: Option<Duration>) -> Self {
+        assert!(split_interval.map_or(true, |i| !i.is_zero()));
+
+        Self {
+            split_interval,
+            ..self
+        }
+    }
+
+    /// Compact level 1 files into up to [`MAX_UPPER_LEVELS`] more levels with the given target
+    /// file sizes, starting with level 2. The files of a level are compacted into the next level
+    /// once they add up to its target file size, so that every level holds fewer, larger files
+    /// than the one below. Empty keeps all compacted files at level 1.
+    pub fn with_upper_level_file_sizes_bytes(self, file_sizes_bytes: &[u64]) -> Self {
+        assert!(
+            file_sizes_bytes.len() <= MAX_UPPER_LEVELS,
+            "at most {} levels above level 1 are supported",
+            MAX_UPPER_LEVELS
+        );
+
+        let mut upper_level_file_sizes_bytes = [0; MAX_UPPER_LEVELS];
+        let mut previous = self.max_desired_file_size_bytes;
+        for (target, size) in upper_level_file_sizes_bytes
+            .iter_mut()
+            .zip(file_sizes_bytes)
+        {
+            assert!(
+                *size > previous,
+                "target file sizes must increase with the compaction level"
+            );
+            *target = *size;
+            previous = *size;
+        }
+
+        Self {
+            upper_level_file_sizes_bytes,
+            ..self
+        }
+    }
+
+    /// Compute the [compaction debt](crate::debt) of every shard each `interval` and publish it
+    /// as metrics. Level 0 files older than `level_0_age` count as debt. `None` disables the
+    /// computation.
+    pub fn with_debt_metrics(self, interval: Option<Duration>, level_0_age: Duration) -> Self {
+        assert!(interval.map_or(true, |i| !i.is_zero()));
+
+        Self {
+            debt_interval: interval,
+            debt_level_0_age: level_0_age,
+            ..self
+        }
+    }
+
+    /// Skip hot partitions that an ingester is persisting, unless it started persisting them at
+    /// least `grace_period` ago (e.g. because it crashed). `None` compacts them regardless.
+    pub fn with_hot_persist_grace_period(self, grace_period: Option<Duration>) -> Self {
+        assert!(grace_period.map_or(true, |p| !p.is_zero()));
+
+        Self {
+            hot_persist_grace_period: grace_period,
+            ..self
         }
     }
 
@@ -195,6 +405,35 @@ impl CompactorConfig {
         self.split_percentage
     }
 
+    /// Interval at whose multiples compacted files are split, if any
+    pub fn split_interval(&self) -> Option<Duration> {
+        self.split_interval
+    }
+
+    /// Target file sizes of the compaction levels above level 1, starting with level 2. Empty if
+    /// compacted files stay at level 1.
+    pub fn upper_level_file_sizes_bytes(&self) -> &[u64] {
+        let num_levels = self
+            .upper_level_file_sizes_bytes
+            .iter()
+            .take_while(|size| **size > 0)
+            .count();
+        &self.upper_level_file_sizes_bytes[..num_levels]
+    }
+
+    /// Target size of the files compacted into `level`, or `None` if no files are compacted into
+    /// this level
+    pub fn level_file_size_bytes(&self, level: CompactionLevel) -> Option<u64> {
+        match level {
+            CompactionLevel::Initial => None,
+            CompactionLevel::FileNonOverlapped => Some(self.max_desired_file_size_bytes),
+            level => self
+                .upper_level_file_sizes_bytes()
+                .get(level as usize - CompactionLevel::Level2 as usize)
+                .copied(),
+        }
+    }
+
     /// Max number of partitions per shard we want to compact per cycle
     pub fn max_number_partitions_per_shard(&self) -> usize {
         self.max_number_partitions_per_shard
@@ -226,36 +465,142 @@ impl CompactorConfig {
     pub fn memory_budget_bytes(&self) -> u64 {
         self.memory_budget_bytes
     }
+
+    /// Whether to write a report for every compaction run
+    pub fn write_compaction_reports(&self) -> bool {
+        self.write_compaction_reports
+    }
+
+    /// Scheduling of hot partition compaction
+    pub fn hot_scheduler(&self) -> SchedulerConfig {
+        self.hot_scheduler
+    }
+
+    /// Scheduling of cold partition compaction
+    pub fn cold_scheduler(&self) -> SchedulerConfig {
+        self.cold_scheduler
+    }
+
+    /// Interval at which the compaction debt is published, `None` if disabled.
+    pub fn debt_interval(&self) -> Option<Duration> {
+        self.debt_interval
+    }
+
+    /// Age after which level 0 files count as compaction debt.
+    pub fn debt_level_0_age(&self) -> Duration {
+        self.debt_level_0_age
+    }
+
+    /// How long hot compaction skips partitions that an ingester is persisting, `None` if it
+    /// doesn't skip them.
+    pub fn hot_persist_grace_period(&self) -> Option<Duration> {
+        self.hot_persist_grace_period
+    }
 }
 
 /// How long to pause before checking for more work again if there was
 /// no work to do
 const PAUSE_BETWEEN_NO_WORK: Duration = Duration::from_secs(1);
 
-/// Checks for candidate partitions to compact and spawns tokio tasks to compact as many
+/// Checks for hot candidate partitions to compact and spawns tokio tasks to compact as many
 /// as the configuration will allow. Once those are done it rechecks the catalog for the
 /// next top partitions to compact.
-async fn run_compactor(compactor: Arc<Compactor>, shutdown: CancellationToken) {
+async fn run_hot_compactor(compactor: Arc<Compactor>, shutdown: CancellationToken) {
+    let tick_interval = compactor.config.hot_scheduler().tick_interval;
+
+    while !shutdown.is_cancelled() {
+        debug!("hot compactor main loop tick.");
+
+        let compacted_partitions = run_compactor_cycle(&compactor, |compactor| async move {
+            compact_hot_partitions::compact_hot_partitions(compactor).await
+        })
+        .await;
+
+        if compacted_partitions == 0 {
+            // pause to avoid a busy loop when the catalog is polled
+            pause(tick_interval, &shutdown).await;
+        }
+    }
+}
+
+/// Checks for cold candidate partitions to compact and spawns tokio tasks to compact as many
+/// as the configuration will allow, then makes progress on column type migrations. Once
+/// those are done it rechecks the catalog for the next partitions to compact.
+async fn run_cold_compactor(compactor: Arc<Compactor>, shutdown: CancellationToken) {
+    let tick_interval = compactor.config.cold_scheduler().tick_interval;
+
     while !shutdown.is_cancelled() {
-        debug!("compactor main loop tick.");
+        debug!("cold compactor main loop tick.");
+
+        let compacted_partitions = run_compactor_cycle(&compactor, |compactor| async move {
+            compact_cold_partitions(Arc::clone(&compactor)).await
+                + migrate_column_types(&compactor).await
+        })
+        .await;
+
+        if compacted_partitions == 0 {
+            // pause to avoid a busy loop when the catalog is polled
+            pause(tick_interval, &shutdown).await;
+        }
+    }
+}
+
+/// Sleep for `duration`, or until `shutdown` is triggered.
+async fn pause(duration: Duration, shutdown: &CancellationToken) {
+    tokio::select! {
+        _ = tokio::time::sleep(duration) => {},
+        _ = shutdown.cancelled() => {},
+    }
+}
 
-        run_compactor_once(Arc::clone(&compactor)).await;
+/// Run one compaction cycle and log its cost if any partitions were compacted.
+async fn run_compactor_cycle<F, Fut>(compactor: &Arc<Compactor>, cycle: F) -> usize
+where
+    F: FnOnce(Arc<Compactor>) -> Fut,
+    Fut: Future<Output = usize>,
+{
+    let cost_before = compactor.cost.summary();
+
+    let compacted_partitions = cycle(Arc::clone(compactor)).await;
+
+    let cost = compactor.cost.summary().since(&cost_before);
+    if compacted_partitions > 0 {
+        info!(
+            compacted_partitions,
+            catalog_requests = cost.catalog_requests,
+            catalog_transactions = cost.catalog_transactions,
+            object_store_requests = cost.object_store_requests,
+            object_store_bytes_read = cost.object_store_bytes_read,
+            object_store_bytes_written = cost.object_store_bytes_written,
+            "compaction run cost"
+        );
     }
+
+    compacted_partitions
 }
 
 /// Checks for candidate partitions to compact and spawns tokio tasks to compact as many
 /// as the configuration will allow.
+///
+/// Unlike the background loops of the [`CompactorHandlerImpl`], this interleaves hot and cold
+/// compaction: hot partitions are compacted up to
+/// [`hot_multiple`](CompactorConfig::hot_multiple) times before the cold partitions are.
 pub async fn run_compactor_once(compactor: Arc<Compactor>) {
-    let mut compacted_partitions = 0;
-    for _ in 0..compactor.config.hot_multiple {
-        compacted_partitions +=
-            compact_hot_partitions::compact_hot_partitions(Arc::clone(&compactor)).await;
-        if compacted_partitions == 0 {
-            // Not found hot candidates, should move to compact cold partitions
-            break;
+    let compacted_partitions = run_compactor_cycle(&compactor, |compactor| async move {
+        let mut compacted_partitions = 0;
+        for _ in 0..compactor.config.hot_multiple {
+            compacted_partitions +=
+                compact_hot_partitions::compact_hot_partitions(Arc::clone(&compactor)).await;
+            if compacted_partitions == 0 {
+                // Not found hot candidates, should move to compact cold partitions
+                break;
+            }
         }
-    }
-    compacted_partitions += compact_cold_partitions(Arc::clone(&compactor)).await;
+        compacted_partitions += compact_cold_partitions(Arc::clone(&compactor)).await;
+        compacted_partitions += migrate_column_types(&compactor).await;
+        compacted_partitions
+    })
+    .await;
 
     if compacted_partitions == 0 {
         // sleep for a second to avoid a busy loop when the catalog is polled
@@ -263,6 +608,27 @@ pub async fn run_compactor_once(compactor: Arc<Compactor>) {
     }
 }
 
+/// Make progress on column type migrations by rewriting outdated files. Returns the number of
+/// rewritten files.
+async fn migrate_column_types(compactor: &Compactor) -> usize {
+    match rewrite::migrate_column_types(compactor).await {
+        Ok(summary) => {
+            if summary.files_rewritten > 0 || summary.migrations_completed > 0 {
+                info!(
+                    files_rewritten = summary.files_rewritten,
+                    migrations_completed = summary.migrations_completed,
+                    "column type migration progress"
+                );
+            }
+            summary.files_rewritten
+        }
+        Err(e) => {
+            warn!(%e, "error migrating column types");
+            0
+        }
+    }
+}
+
 async fn compact_cold_partitions(compactor: Arc<Compactor>) -> usize {
     let cold_attributes = Attributes::from(&[("partition_type", "cold")]);
     // Select cold partition candidates
@@ -305,9 +671,20 @@ async fn compact_cold_partitions(compactor: Arc<Compactor>) -> usize {
         duration.record(delta);
     }
 
+    // Compact the partitions of rarely read tables first, so that they reach their final layout
+    // before they are moved to colder storage tiers.
+    let mut candidates = candidates;
+    crate::compact::rarely_read_first(&mut candidates);
+
+    compactor.state.start_cycle(
+        CompactionKind::Cold,
+        candidates.iter().map(|c| c.shard_id()),
+    );
+
     let n_candidates = candidates.len();
     if n_candidates == 0 {
         debug!("no cold compaction candidates found");
+        compactor.state.finish_cycle(CompactionKind::Cold, 0);
         return 0;
     } else {
         debug!(n_candidates, "found cold compaction candidates");
@@ -326,6 +703,10 @@ async fn compact_cold_partitions(compactor: Arc<Compactor>) -> usize {
     let num_parallel_partitions = (compactor.config.max_cold_concurrent_size_bytes
         / compactor.config.cold_input_size_threshold_bytes)
         as usize;
+    let num_parallel_partitions = compactor
+        .config
+        .cold_scheduler()
+        .limit_concurrency(num_parallel_partitions);
 
     futures::stream::iter(candidates)
         .map(|p| {
@@ -338,6 +719,11 @@ async fn compact_cold_partitions(compactor: Arc<Compactor>) -> usize {
                 match compaction_result {
                     Err(e) => {
                         warn!(?e, ?partition_id, "cold compaction failed");
+                        comp.state.record_skipped(
+                            CompactionKind::Cold,
+                            partition_id,
+                            format!("compaction failed: {}", e),
+                        );
                     }
                     Ok(_) => {
                         debug!(?partition_id, "cold compaction complete");
@@ -372,23 +758,103 @@ async fn compact_cold_partitions(compactor: Arc<Compactor>) -> usize {
             .recorder(cold_attributes);
         duration.record(delta);
     }
+    compactor
+        .state
+        .finish_cycle(CompactionKind::Cold, n_candidates);
 
     n_candidates
 }
 
+/// Compact all files of the given partition like a cold partition, independent of the regular
+/// candidate selection.
+///
+/// This is meant for operators, e.g. to speed up queries of a partition with many small files
+/// right away. The partition must belong to a shard of this compactor, so that it is not compacted
+/// by two compactors at the same time.
+pub async fn compact_partition(
+    compactor: &Compactor,
+    partition_id: PartitionId,
+) -> Result<(), Error> {
+    let mut repos = compactor
+        .cost
+        .catalog(CostPhase::CandidateSelection)
+        .repositories()
+        .await;
+    let partition = repos
+        .partitions()
+        .get_by_id(partition_id)
+        .await?
+        .ok_or(Error::PartitionNotFound(partition_id))?;
+    let table = repos
+        .tables()
+        .get_by_id(partition.table_id)
+        .await?
+        .ok_or(Error::TableNotFound(partition.table_id))?;
+    drop(repos);
+
+    if !compactor.shards().await?.contains(&partition.shard_id) {
+        return Err(Error::ShardNotAssigned(partition_id));
+    }
+
+    let candidate = compactor
+        .add_info_to_partitions(&[PartitionParam {
+            partition_id,
+            shard_id: partition.shard_id,
+            namespace_id: table.namespace_id,
+            table_id: table.id,
+        }])
+        .await?
+        .pop_front()
+        .expect("partition info for the candidate");
+
+    info!(?partition_id, "compacting partition on request");
+    crate::compact_cold_partition(compactor, candidate)
+        .await
+        .map_err(|e| Error::Compaction {
+            partition_id,
+            message: e.to_string(),
+        })
+}
+
 #[async_trait]
 impl CompactorHandler for CompactorHandlerImpl {
+    async fn rewrite_table(&self, table_id: TableId) -> Result<RewriteSummary, Error> {
+        Ok(rewrite::rewrite_table(&self.compactor_data, table_id).await?)
+    }
+
+    async fn compact_partition(&self, partition_id: PartitionId) -> Result<(), Error> {
+        compact_partition(&self.compactor_data, partition_id).await
+    }
+
+    fn state(&self) -> StateSnapshot {
+        self.compactor_data.state.snapshot()
+    }
+
     async fn join(&self) {
-        self.runner_handle
+        self.hot_runner_handle
+            .clone()
+            .await
+            .expect("hot compactor task failed");
+        self.cold_runner_handle
             .clone()
             .await
-            .expect("compactor task failed");
-        self.exec.join().await;
+            .expect("cold compactor task failed");
+        if let Some(debt_runner_handle) = &self.debt_runner_handle {
+            debt_runner_handle
+                .clone()
+                .await
+                .expect("compaction debt task failed");
+        }
+        for exec in &self.execs {
+            exec.join().await;
+        }
     }
 
     fn shutdown(&self) {
         self.shutdown.cancel();
-        self.exec.shutdown();
+        for exec in &self.execs {
+            exec.shutdown();
+        }
     }
 }
 
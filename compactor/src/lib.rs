@@ -12,16 +12,26 @@
 
 pub mod compact;
 pub(crate) mod compact_hot_partitions;
+pub mod cost;
+pub mod debt;
 pub mod garbage_collector;
 pub mod handler;
 pub(crate) mod parquet_file_combining;
-pub(crate) mod parquet_file_filtering;
+pub mod parquet_file_filtering;
 pub(crate) mod parquet_file_lookup;
 pub mod query;
+pub mod report;
+pub mod rewrite;
 pub mod server;
+pub mod spill;
+pub mod state;
 pub mod utils;
 
-use crate::compact::{Compactor, PartitionCompactionCandidateWithInfo};
+use crate::{
+    compact::{Compactor, PartitionCompactionCandidateWithInfo},
+    cost::CostPhase,
+    state::{CompactionKind, CompactionPhase},
+};
 use data_types::CompactionLevel;
 use metric::Attributes;
 use parquet_file_filtering::FilteredFiles;
@@ -56,18 +66,27 @@ pub(crate) async fn compact_hot_partition(
 
     let partition = to_compact.partition;
     let shard_id = partition.shard_id();
+    let _running = compactor.state.start_compaction(
+        partition.id(),
+        CompactionKind::Hot,
+        CompactionPhase::Compacting,
+    );
 
     let compact_result = parquet_file_combining::compact_parquet_files(
         to_compact.files,
         partition,
-        Arc::clone(&compactor.catalog),
-        compactor.store.clone(),
+        compactor.cost.catalog(CostPhase::Compaction),
+        compactor.cost.store(CostPhase::Compaction),
         Arc::clone(&compactor.exec),
+        None,
         Arc::clone(&compactor.time_provider),
         &compactor.compaction_input_file_bytes,
         compactor.config.max_desired_file_size_bytes(),
         compactor.config.percentage_max_file_size(),
         compactor.config.split_percentage(),
+        compactor.config.split_interval(),
+        CompactionLevel::FileNonOverlapped,
+        compactor.report_config(),
     )
     .await
     .context(CombiningSnafu);
@@ -95,11 +114,17 @@ pub(crate) async fn compact_cold_partition(
 ) -> Result<(), Error> {
     let start_time = compactor.time_provider.now();
     let shard_id = partition.shard_id();
+    let running = compactor.state.start_compaction(
+        partition.id(),
+        CompactionKind::Cold,
+        CompactionPhase::SelectingFiles,
+    );
 
     let parquet_files_for_compaction =
         parquet_file_lookup::ParquetFilesForCompaction::for_partition(
-            Arc::clone(&compactor.catalog),
+            compactor.cost.catalog(CostPhase::Compaction),
             partition.id(),
+            partition.compaction_cursor,
         )
         .await
         .context(LookupSnafu)?;
@@ -111,11 +136,16 @@ pub(crate) async fn compact_cold_partition(
         &compactor.parquet_file_candidate_gauge,
         &compactor.parquet_file_candidate_bytes,
     );
+    running.set_phase(CompactionPhase::Compacting);
 
     let compact_result =
         if to_compact.len() == 1 && to_compact[0].compaction_level == CompactionLevel::Initial {
             // upgrade the one l0 file to l1, don't run compaction
-            let mut repos = compactor.catalog.repositories().await;
+            let mut repos = compactor
+                .cost
+                .catalog(CostPhase::Compaction)
+                .repositories()
+                .await;
 
             repos
                 .parquet_files()
@@ -126,20 +156,30 @@ pub(crate) async fn compact_cold_partition(
         } else {
             parquet_file_combining::compact_parquet_files(
                 to_compact,
-                partition,
-                Arc::clone(&compactor.catalog),
-                compactor.store.clone(),
-                Arc::clone(&compactor.exec),
+                partition.clone(),
+                compactor.cost.catalog(CostPhase::Compaction),
+                compactor.cost.store(CostPhase::Compaction),
+                Arc::clone(&compactor.cold_exec),
+                compactor.spill_dir.as_deref(),
                 Arc::clone(&compactor.time_provider),
                 &compactor.compaction_input_file_bytes,
                 compactor.config.max_desired_file_size_bytes(),
                 compactor.config.percentage_max_file_size(),
                 compactor.config.split_percentage(),
+                compactor.config.split_interval(),
+                CompactionLevel::FileNonOverlapped,
+                compactor.report_config(),
             )
             .await
             .context(CombiningSnafu)
         };
 
+    // Once the level 0 files are compacted, the levels above may be due for compaction
+    let compact_result = match compact_result {
+        Ok(()) => compact_upper_levels(compactor, partition).await,
+        Err(e) => Err(e),
+    };
+
     let attributes = Attributes::from([
         ("shard_id", format!("{}", shard_id).into()),
         ("partition_type", "cold".into()),
@@ -156,10 +196,66 @@ pub(crate) async fn compact_cold_partition(
     compact_result
 }
 
+/// Compact the files of one partition into the levels above level 1 while a level is due, i.e.
+/// while the files of a level add up to the target file size of the next level.
+async fn compact_upper_levels(
+    compactor: &Compactor,
+    partition: PartitionCompactionCandidateWithInfo,
+) -> Result<(), Error> {
+    let upper_level_file_sizes_bytes = compactor.config.upper_level_file_sizes_bytes();
+
+    // Every round compacts the lowest level that is due, which may make the next one due
+    for _ in 0..upper_level_file_sizes_bytes.len() {
+        let parquet_files_for_compaction =
+            parquet_file_lookup::ParquetFilesForCompaction::for_partition(
+                compactor.cost.catalog(CostPhase::Compaction),
+                partition.id(),
+                partition.compaction_cursor,
+            )
+            .await
+            .context(LookupSnafu)?;
+
+        let (target_level, to_compact) =
+            match parquet_file_filtering::filter_upper_level_parquet_files(
+                &parquet_files_for_compaction,
+                upper_level_file_sizes_bytes,
+                compactor.config.cold_input_size_threshold_bytes(),
+            ) {
+                Some(due) => due,
+                None => break,
+            };
+        let max_desired_file_size_bytes = compactor
+            .config
+            .level_file_size_bytes(target_level)
+            .expect("files are only compacted into configured levels");
+
+        parquet_file_combining::compact_parquet_files(
+            to_compact,
+            partition.clone(),
+            compactor.cost.catalog(CostPhase::Compaction),
+            compactor.cost.store(CostPhase::Compaction),
+            Arc::clone(&compactor.cold_exec),
+            compactor.spill_dir.as_deref(),
+            Arc::clone(&compactor.time_provider),
+            &compactor.compaction_input_file_bytes,
+            max_desired_file_size_bytes,
+            compactor.config.percentage_max_file_size(),
+            compactor.config.split_percentage(),
+            compactor.config.split_interval(),
+            target_level,
+            compactor.report_config(),
+        )
+        .await
+        .context(CombiningSnafu)?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::handler::CompactorConfig;
+    use crate::handler::{CompactorConfig, SchedulerConfig};
     use arrow::record_batch::RecordBatch;
     use arrow_util::assert_batches_sorted_eq;
     use backoff::BackoffConfig;
@@ -347,6 +443,7 @@ mod tests {
             parquet_file_lookup::ParquetFilesForCompaction::for_partition(
                 Arc::clone(&compactor.catalog),
                 c.id(),
+                c.compaction_cursor,
             )
             .await
             .unwrap();
@@ -815,6 +912,9 @@ mod tests {
             cold_input_file_count_threshold,
             hot_multiple,
             memory_budget_bytes,
+            false,
+            SchedulerConfig::default(),
+            SchedulerConfig::default(),
         )
     }
 }
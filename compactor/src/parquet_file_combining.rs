@@ -1,6 +1,14 @@
-use crate::{compact::PartitionCompactionCandidateWithInfo, query::QueryableParquetChunk};
+use crate::{
+    compact::PartitionCompactionCandidateWithInfo,
+    handler::CompactorConfig,
+    query::QueryableParquetChunk,
+    report::{self, CompactionRunReport, ReportFile, ReportPath, REPORT_FORMAT_VERSION},
+    spill::SpillDir,
+    utils::compaction_key,
+};
 use data_types::{
-    CompactionLevel, ParquetFile, ParquetFileId, ParquetFileParams, PartitionId, TableSchema,
+    CompactionLevel, ParquetFile, ParquetFileId, ParquetFileParams, PartitionId, SequenceNumber,
+    TableSchema, Timestamp,
 };
 use datafusion::error::DataFusionError;
 use futures::{stream::FuturesOrdered, StreamExt, TryStreamExt};
@@ -10,28 +18,33 @@ use iox_query::{
     frontend::reorg::ReorgPlanner,
     QueryChunk,
 };
-use iox_time::TimeProvider;
+use iox_time::{Time, TimeProvider};
 use metric::{Attributes, Metric, U64Histogram};
 use observability_deps::tracing::*;
 use parquet_file::{
     chunk::ParquetChunk,
     metadata::IoxMetadata,
     serialize::CodecError,
-    storage::{ParquetStorage, UploadError},
+    storage::{ParquetStorage, ReadError, UploadError},
+    ParquetFilePath,
 };
 use schema::{sort::SortKey, Schema};
 use snafu::{ensure, ResultExt, Snafu};
 use std::{
     cmp::{max, min},
     collections::BTreeMap,
-    future,
+    future::{self, Future},
+    pin::Pin,
     sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
 };
+use tokio::task::{JoinError, JoinHandle};
 use uuid::Uuid;
 
 #[derive(Debug, Snafu)]
 #[allow(missing_copy_implementations, missing_docs)]
-pub(crate) enum Error {
+pub enum Error {
     #[snafu(display(
         "Must specify at least 2 files to compact for {}, got {num_files}", partition_id.get()
     ))]
@@ -54,11 +67,38 @@ pub(crate) enum Error {
     #[snafu(display("Error executing parquet write task  {}", source))]
     ExecuteParquetTask { source: tokio::task::JoinError },
 
+    #[snafu(display(
+        "Could not create spill directory for partition {}: {source}", partition_id.get()
+    ))]
+    SpillDirectory {
+        partition_id: PartitionId,
+        source: std::io::Error,
+    },
+
+    #[snafu(display(
+        "Compaction of partition {} cancelled, its spill files ({spill_bytes} bytes) exceed the \
+         quota of {quota_bytes} bytes",
+        partition_id.get()
+    ))]
+    SpillQuotaExceeded {
+        partition_id: PartitionId,
+        spill_bytes: u64,
+        quota_bytes: u64,
+    },
+
     #[snafu(display("Could not serialize and persist record batches {}", source))]
     Persist {
         source: parquet_file::storage::UploadError,
     },
 
+    #[snafu(display(
+        "Could not check the schema of the files of partition {}: {source}", partition_id.get()
+    ))]
+    CheckSchema {
+        partition_id: PartitionId,
+        source: parquet_file::storage::ReadError,
+    },
+
     #[snafu(display("Could not update catalog for partition {}: {source}", partition_id.get()))]
     Catalog {
         partition_id: PartitionId,
@@ -77,6 +117,9 @@ pub(crate) async fn compact_parquet_files(
     store: ParquetStorage,
     // Executor for running queries, compacting, and persisting
     exec: Arc<Executor>,
+    // If set, operators that exceed the memory pool of `exec` spill into a directory of this
+    // compaction below this one
+    spill_dir: Option<&SpillDir>,
     time_provider: Arc<dyn TimeProvider>,
     // Histogram for the sizes of the files compacted
     compaction_input_file_bytes: &Metric<U64Histogram>,
@@ -93,7 +136,17 @@ pub(crate) async fn compact_parquet_files(
     // When data is between a "small" and "large" amount, split the compacted files at roughly this
     // percentage in the earlier compacted file, and the remainder .in the later compacted file.
     split_percentage: u16,
+    // If set, split the compacted files at multiples of this interval since the epoch instead of
+    // by `split_percentage`. Files between two boundaries that are "large" are still split.
+    split_interval: Option<Duration>,
+    // Compaction level of the output files. Regular compaction produces
+    // `CompactionLevel::FileNonOverlapped` files, compaction into the levels above produces files of
+    // the next level, rewrites keep the level of their input.
+    target_level: CompactionLevel,
+    // If set, write a compaction report that includes this config snapshot.
+    report_config: Option<CompactorConfig>,
 ) -> Result<(), Error> {
+    let start_time = time_provider.now();
     let partition_id = partition.id();
 
     let num_files = files.len();
@@ -105,6 +158,44 @@ pub(crate) async fn compact_parquet_files(
         }
     );
 
+    // Files that cannot be read with the schema of the catalog would fail the entire compaction,
+    // leave them alone and compact the remaining files.
+    let (mut files, skipped_files) = check_schemas(files, &partition, &store)
+        .await
+        .context(CheckSchemaSnafu { partition_id })?;
+    if files.is_empty() {
+        warn!(
+            ?partition_id,
+            num_skipped = skipped_files.len(),
+            "no files with a compatible schema left to compact"
+        );
+        return Ok(());
+    }
+    let num_files = files.len();
+
+    // Plan the compaction of the same files the same way, regardless of the order in which they
+    // were selected.
+    files.sort_by_key(|f| f.id);
+
+    // Collect all the parquet file IDs, to be able to set their catalog records to be
+    // deleted. These should already be unique, no need to dedupe.
+    let original_parquet_file_ids: Vec<_> = files.iter().map(|f| f.id).collect();
+
+    // The result of this compaction may have been committed already, e.g. if the compactor
+    // crashed before it could record that. Don't create the output files again.
+    let compaction_key = compaction_key(&original_parquet_file_ids, target_level);
+    if is_committed(catalog.as_ref(), partition_id, &compaction_key)
+        .await
+        .context(CatalogSnafu { partition_id })?
+    {
+        info!(
+            ?partition_id,
+            %compaction_key,
+            "compaction already committed, skipping"
+        );
+        return Ok(());
+    }
+
     // Save all file sizes for recording metrics if this compaction succeeds.
     let file_sizes: Vec<_> = files.iter().map(|f| f.file_size_bytes).collect();
     // Find the total size of all files, to be used to determine if the result should be one file
@@ -112,8 +203,27 @@ pub(crate) async fn compact_parquet_files(
     let total_size: i64 = file_sizes.iter().sum();
     let total_size = total_size as u64;
 
+    // Level 0 files are compacted in the order of their max sequence numbers, so all level 0
+    // files up to the highest one compacted here are done. Rewrites keep files at level 0. The
+    // cursor must not move past skipped level 0 files, they would never be listed again.
+    let skipped_cursor = skipped_files
+        .iter()
+        .filter(|f| f.compaction_level == CompactionLevel::Initial)
+        .map(|f| f.max_sequence_number)
+        .min();
+    let compaction_cursor = if target_level == CompactionLevel::Initial {
+        None
+    } else {
+        files
+            .iter()
+            .filter(|f| f.compaction_level == CompactionLevel::Initial)
+            .map(|f| f.max_sequence_number)
+            .filter(|n| skipped_cursor.map_or(true, |skipped| *n < skipped))
+            .max()
+    };
+
     // Compute the number of files per compaction level for logging
-    let mut num_files_by_level = BTreeMap::new();
+    let mut num_files_by_level: BTreeMap<_, usize> = BTreeMap::new();
     for compaction_level in files.iter().map(|f| f.compaction_level) {
         *num_files_by_level.entry(compaction_level).or_default() += 1;
     }
@@ -123,14 +233,22 @@ pub(crate) async fn compact_parquet_files(
     let num_level_1 = num_files_by_level
         .get(&CompactionLevel::FileNonOverlapped)
         .unwrap_or(&0);
+    let num_upper_levels = num_files - num_level_0 - num_level_1;
     debug!(
         ?partition_id,
-        num_files, num_level_0, num_level_1, "compact files to stream"
+        num_files,
+        num_level_0,
+        num_level_1,
+        num_upper_levels,
+        ?target_level,
+        "compact files to stream"
     );
 
-    // Collect all the parquet file IDs, to be able to set their catalog records to be
-    // deleted. These should already be unique, no need to dedupe.
-    let original_parquet_file_ids: Vec<_> = files.iter().map(|f| f.id).collect();
+    let report_inputs: Vec<_> = files.iter().map(ReportFile::from).collect();
+
+    // Files that neither overlap in time nor differ in their columns may be concatenated.
+    let concat_paths =
+        can_concat(&files).then(|| files.iter().map(ParquetFilePath::from).collect::<Vec<_>>());
 
     // Convert the input files into QueryableParquetChunk for making query plan
     let query_chunks: Vec<_> = files
@@ -184,18 +302,34 @@ pub(crate) async fn compact_parquet_files(
     let (small_cutoff_bytes, large_cutoff_bytes) =
         cutoff_bytes(max_desired_file_size_bytes, percentage_max_file_size);
 
-    let ctx = exec.new_context(ExecutorType::Reorg);
-    let plan = if total_size <= small_cutoff_bytes {
-        // Compact everything into one file
-        ReorgPlanner::new(ctx.child_ctx("ReorgPlanner"))
-            .compact_plan(Arc::clone(&merged_schema), query_chunks, sort_key.clone())
-            .context(CompactLogicalPlanSnafu)?
-    } else {
-        let split_times = if small_cutoff_bytes < total_size && total_size <= large_cutoff_bytes {
-            // Split compaction into two files, the earlier of split_percentage amount of
-            // max_desired_file_size_bytes, the later of the rest
+    let split_times = match split_interval {
+        Some(split_interval) => {
+            let mut split_times =
+                crate::utils::compute_aligned_split_times(min_time, max_time, split_interval);
+            if total_size > large_cutoff_bytes {
+                // the data between two boundaries may still be too large for a single file
+                split_times.extend(
+                    crate::utils::compute_split_time(
+                        min_time,
+                        max_time,
+                        total_size,
+                        max_desired_file_size_bytes,
+                    )
+                    .into_iter()
+                    .filter(|t| *t < max_time),
+                );
+                split_times.sort_unstable();
+                split_times.dedup();
+            }
+            split_times
+        }
+        None if total_size <= small_cutoff_bytes => vec![],
+        None if total_size <= large_cutoff_bytes => {
+            // Split compaction into two files, the earlier of split_percentage
+            // amount of max_desired_file_size_bytes, the later of the rest
             vec![min_time + ((max_time - min_time) * split_percentage as i64) / 100]
-        } else {
+        }
+        None => {
             // Split compaction into multiple files
             crate::utils::compute_split_time(
                 min_time,
@@ -203,144 +337,265 @@ pub(crate) async fn compact_parquet_files(
                 total_size,
                 max_desired_file_size_bytes,
             )
-        };
-
-        if split_times.is_empty() || (split_times.len() == 1 && split_times[0] == max_time) {
-            // The split times might not have actually split anything, so in this case, compact
-            // everything into one file
-            ReorgPlanner::new(ctx.child_ctx("ReorgPlanner"))
-                .compact_plan(Arc::clone(&merged_schema), query_chunks, sort_key.clone())
-                .context(CompactLogicalPlanSnafu)?
-        } else {
-            // split compact query plan
-            ReorgPlanner::new(ctx.child_ctx("ReorgPlanner"))
-                .split_plan(
-                    Arc::clone(&merged_schema),
-                    query_chunks,
-                    sort_key.clone(),
-                    split_times,
-                )
-                .context(CompactLogicalPlanSnafu)?
         }
     };
-
-    let ctx = exec.new_context(ExecutorType::Reorg);
-    let physical_plan = ctx
-        .create_physical_plan(&plan)
-        .await
-        .context(CompactPhysicalPlanSnafu)?;
+    // The split times might not actually split anything, in which case everything is compacted
+    // into one file
+    let split = !(split_times.is_empty() || (split_times.len() == 1 && split_times[0] == max_time));
 
     let partition = Arc::new(partition);
 
-    // Run to collect each stream of the plan
-    let stream_count = physical_plan.output_partitioning().partition_count();
-
-    debug!("running plan with {} streams", stream_count);
-
-    // These streams *must* to run in parallel otherwise a deadlock
-    // can occur. Since there is a merge in the plan, in order to make
-    // progress on one stream there must be (potential space) on the
-    // other streams.
-    //
-    // https://github.com/influxdata/influxdb_iox/issues/4306
-    // https://github.com/influxdata/influxdb_iox/issues/4324
-    let compacted_parquet_files = (0..stream_count)
-        .map(|i| {
-            // Prepare variables to pass to the closure
+    // Non-overlapping files may be combined without decoding their data, falling back to a
+    // regular compaction if that is not possible.
+    let concatenated = match concat_paths {
+        Some(paths) if !split && total_size <= small_cutoff_bytes => {
+            let meta = compacted_file_meta(
+                &partition,
+                time_provider.now(),
+                max_sequence_number,
+                target_level,
+                &sort_key,
+            );
+            try_concat_parquet_files(&store, &paths, &meta, &partition).await
+        }
+        _ => None,
+    };
+
+    let (plan_end_time, compacted_parquet_files) = match concatenated {
+        Some(parquet_file) => (time_provider.now(), vec![parquet_file]),
+        None => {
             let ctx = exec.new_context(ExecutorType::Reorg);
-            let physical_plan = Arc::clone(&physical_plan);
-            let store = store.clone();
-            let time_provider = Arc::clone(&time_provider);
-            let sort_key = sort_key.clone();
-            let partition = Arc::clone(&partition);
-            // run as a separate tokio task so files can be written
-            // concurrently.
-            tokio::task::spawn(async move {
-                trace!(partition = i, "executing datafusion partition");
-                let data = ctx
-                    .execute_stream_partitioned(physical_plan, i)
-                    .await
-                    .context(ExecuteCompactPlanSnafu)?;
-                trace!(partition = i, "built result stream for partition");
-
-                let meta = IoxMetadata {
-                    object_store_id: Uuid::new_v4(),
-                    creation_timestamp: time_provider.now(),
-                    shard_id: partition.shard_id(),
-                    namespace_id: partition.namespace_id(),
-                    namespace_name: partition.namespace.name.clone().into(),
-                    table_id: partition.table.id,
-                    table_name: partition.table.name.clone().into(),
-                    partition_id,
-                    partition_key: partition.partition_key.clone(),
-                    max_sequence_number,
-                    compaction_level: CompactionLevel::FileNonOverlapped,
-                    sort_key: Some(sort_key.clone()),
-                };
-
-                debug!(
-                    ?partition_id,
-                    "executing and uploading compaction StreamSplitExec"
-                );
+            let plan = if split {
+                // split compact query plan
+                ReorgPlanner::new(ctx.child_ctx("ReorgPlanner"))
+                    .split_plan(
+                        Arc::clone(&merged_schema),
+                        query_chunks,
+                        sort_key.clone(),
+                        split_times,
+                    )
+                    .context(CompactLogicalPlanSnafu)?
+            } else {
+                // Compact everything into one file
+                ReorgPlanner::new(ctx.child_ctx("ReorgPlanner"))
+                    .compact_plan(Arc::clone(&merged_schema), query_chunks, sort_key.clone())
+                    .context(CompactLogicalPlanSnafu)?
+            };
+
+            let ctx = exec.new_context(ExecutorType::Reorg);
+            let physical_plan = ctx
+                .create_physical_plan(&plan)
+                .await
+                .context(CompactPhysicalPlanSnafu)?;
+            let plan_end_time = time_provider.now();
+
+            // Run to collect each stream of the plan
+            let stream_count = physical_plan.output_partitioning().partition_count();
+
+            debug!("running plan with {} streams", stream_count);
+
+            // These streams *must* to run in parallel otherwise a deadlock
+            // can occur. Since there is a merge in the plan, in order to make
+            // progress on one stream there must be (potential space) on the
+            // other streams.
+            //
+            // https://github.com/influxdata/influxdb_iox/issues/4306
+            // https://github.com/influxdata/influxdb_iox/issues/4324
+            //
+            // The spill files of the streams go to a directory of this compaction, which is
+            // removed once the compaction is done.
+            let spill_job = spill_dir
+                .map(SpillDir::job)
+                .transpose()
+                .context(SpillDirectorySnafu { partition_id })?;
+            let contexts = (0..stream_count)
+                .map(|_| match &spill_job {
+                    Some(job) => exec.new_context_with_spill_dir(ExecutorType::Reorg, job.path()),
+                    None => Ok(exec.new_context(ExecutorType::Reorg)),
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .context(ExecuteCompactPlanSnafu)?;
+            let execute = contexts
+                .into_iter()
+                .enumerate()
+                .map(|(i, ctx)| {
+                    // Prepare variables to pass to the closure
+                    let physical_plan = Arc::clone(&physical_plan);
+                    let store = store.clone();
+                    let time_provider = Arc::clone(&time_provider);
+                    let sort_key = sort_key.clone();
+                    let partition = Arc::clone(&partition);
+                    // run as a separate tokio task so files can be written
+                    // concurrently.
+                    AbortOnDrop(tokio::task::spawn(async move {
+                        trace!(partition = i, "executing datafusion partition");
+                        let data = ctx
+                            .execute_stream_partitioned(physical_plan, i)
+                            .await
+                            .context(ExecuteCompactPlanSnafu)?;
+                        trace!(partition = i, "built result stream for partition");
+
+                        let meta = compacted_file_meta(
+                            &partition,
+                            time_provider.now(),
+                            max_sequence_number,
+                            target_level,
+                            &sort_key,
+                        );
 
-                let object_store_id = meta.object_store_id;
-                info!(?partition_id, %object_store_id, "streaming exec to object store");
-
-                // Stream the record batches from the compaction exec, serialize
-                // them, and directly upload the resulting Parquet files to
-                // object storage.
-                let (parquet_meta, file_size) = match store.upload(data, &meta).await {
-                    Ok(v) => v,
-                    Err(UploadError::Serialise(CodecError::NoRows)) => {
-                        // This MAY be a bug.
-                        //
-                        // This also may happen legitimately, though very, very
-                        // rarely. See test_empty_parquet_file_panic for an
-                        // explanation.
-                        warn!(
+                        debug!(
                             ?partition_id,
-                            %object_store_id,
-                            "SplitExec produced an empty result stream"
+                            "executing and uploading compaction StreamSplitExec"
                         );
-                        return Ok(None);
+
+                        let object_store_id = meta.object_store_id;
+                        info!(?partition_id, %object_store_id, "streaming exec to object store");
+
+                        // Stream the record batches from the compaction exec, serialize
+                        // them, and directly upload the resulting Parquet files to
+                        // object storage.
+                        let (parquet_meta, file_size) = match store.upload(data, &meta).await {
+                            Ok(v) => v,
+                            Err(UploadError::Serialise(CodecError::NoRows)) => {
+                                // This MAY be a bug.
+                                //
+                                // This also may happen legitimately, though very, very
+                                // rarely. See test_empty_parquet_file_panic for an
+                                // explanation.
+                                warn!(
+                                    ?partition_id,
+                                    %object_store_id,
+                                    "SplitExec produced an empty result stream"
+                                );
+                                return Ok(None);
+                            }
+                            Err(e) => return Err(Error::Persist { source: e }),
+                        };
+
+                        debug!(?partition_id, %object_store_id, "file uploaded to object store");
+
+                        let parquet_file =
+                            meta.to_parquet_file(partition_id, file_size, &parquet_meta, |name| {
+                                partition
+                                    .table_schema
+                                    .columns
+                                    .get(name)
+                                    .expect("unknown column")
+                                    .id
+                            });
+
+                        Ok(Some(parquet_file))
+                    }))
+                })
+                // NB: FuturesOrdered allows the futures to run in parallel
+                .collect::<FuturesOrdered<_>>()
+                // Check for errors in the task
+                .map(|t| t.context(ExecuteParquetTaskSnafu)?)
+                // Discard the streams that resulted in empty output / no file uploaded
+                // to the object store.
+                .try_filter_map(|v| future::ready(Ok(v)))
+                // Collect all the persisted parquet files together.
+                .try_collect::<Vec<_>>();
+
+            // Dropping `execute` aborts the tasks, which stops their streams from spilling.
+            let compacted_parquet_files = match &spill_job {
+                Some(job) => tokio::select! {
+                    res = execute => res?,
+                    spill_bytes = job.quota_exceeded() => {
+                        return SpillQuotaExceededSnafu {
+                            partition_id,
+                            spill_bytes,
+                            quota_bytes: spill_dir.map(SpillDir::quota_bytes).unwrap_or_default(),
+                        }
+                        .fail();
                     }
-                    Err(e) => return Err(Error::Persist { source: e }),
-                };
-
-                debug!(?partition_id, %object_store_id, "file uploaded to object store");
-
-                let parquet_file =
-                    meta.to_parquet_file(partition_id, file_size, &parquet_meta, |name| {
-                        partition
-                            .table_schema
-                            .columns
-                            .get(name)
-                            .expect("unknown column")
-                            .id
-                    });
-
-                Ok(Some(parquet_file))
-            })
-        })
-        // NB: FuturesOrdered allows the futures to run in parallel
-        .collect::<FuturesOrdered<_>>()
-        // Check for errors in the task
-        .map(|t| t.context(ExecuteParquetTaskSnafu)?)
-        // Discard the streams that resulted in empty output / no file uploaded
-        // to the object store.
-        .try_filter_map(|v| future::ready(Ok(v)))
-        // Collect all the persisted parquet files together.
-        .try_collect::<Vec<_>>()
-        .await?;
-
-    update_catalog(
+                },
+                None => execute.await?,
+            };
+
+            (plan_end_time, compacted_parquet_files)
+        }
+    };
+    let execute_end_time = time_provider.now();
+
+    // A report that cannot be written must not fail the compaction, it is just not referenced
+    // from the catalog.
+    let report = match report_config {
+        Some(config) => {
+            let report = CompactionRunReport {
+                format_version: REPORT_FORMAT_VERSION,
+                compactor_version: env!("CARGO_PKG_VERSION"),
+                shard_id: partition.shard_id().get(),
+                namespace_id: partition.namespace_id().get(),
+                namespace_name: partition.namespace.name.clone(),
+                table_id: partition.table.id.get(),
+                table_name: partition.table.name.clone(),
+                partition_id: partition_id.get(),
+                partition_key: partition.partition_key.to_string(),
+                sort_key: Some(sort_key.to_columns().map(ToString::to_string).collect()),
+                target_level: target_level as i16,
+                started_at: start_time.to_rfc3339(),
+                plan_duration_secs: report::secs_between(start_time, plan_end_time),
+                execute_duration_secs: report::secs_between(plan_end_time, execute_end_time),
+                input_bytes: total_size as i64,
+                output_bytes: compacted_parquet_files
+                    .iter()
+                    .map(|f| f.file_size_bytes)
+                    .sum(),
+                inputs: report_inputs,
+                outputs: compacted_parquet_files
+                    .iter()
+                    .map(ReportFile::from)
+                    .collect(),
+                config,
+            };
+
+            let object_store_id = Uuid::new_v4();
+            let path = ReportPath::new(
+                partition.namespace_id(),
+                partition.table_id(),
+                partition.shard_id(),
+                partition_id,
+                object_store_id,
+            );
+            match report::write_report(&store, &path, &report).await {
+                Ok(()) => {
+                    debug!(?partition_id, %object_store_id, "compaction report written");
+                    Some((
+                        object_store_id,
+                        Timestamp::new(execute_end_time.timestamp_nanos()),
+                    ))
+                }
+                Err(e) => {
+                    warn!(?partition_id, %e, "could not write compaction report");
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+
+    let committed = update_catalog(
         catalog,
         partition_id,
         compacted_parquet_files,
         &original_parquet_file_ids,
+        &compaction_key,
+        compaction_cursor,
+        report,
     )
     .await
     .context(CatalogSnafu { partition_id })?;
+    if !committed {
+        // a concurrent or earlier attempt won, the files written by this one are orphaned and
+        // removed by the garbage collector
+        info!(
+            ?partition_id,
+            %compaction_key,
+            "compaction committed by another attempt, discarding result"
+        );
+        return Ok(());
+    }
 
     info!(?partition_id, "compaction complete");
 
@@ -353,14 +608,52 @@ pub(crate) async fn compact_parquet_files(
     Ok(())
 }
 
-/// Convert ParquetFile to a QueryableParquetChunk
-fn to_queryable_parquet_chunk(
-    file: ParquetFile,
-    store: ParquetStorage,
-    table_name: String,
-    table_schema: &TableSchema,
-    partition_sort_key: Option<SortKey>,
-) -> QueryableParquetChunk {
+/// Split `files` into the files that can be read with the schema the catalog has for their
+/// columns and the ones that cannot, e.g. because a column was written with a type that differs
+/// from the catalog. Columns whose type was migrated after the file was written are cast on read
+/// and are fine, see [`InfluxFieldType::can_migrate_to`](schema::InfluxFieldType::can_migrate_to).
+///
+/// Only the footers of the files are fetched. A warning is logged for every incompatible file.
+async fn check_schemas(
+    files: Vec<ParquetFile>,
+    partition: &PartitionCompactionCandidateWithInfo,
+    store: &ParquetStorage,
+) -> Result<(Vec<ParquetFile>, Vec<ParquetFile>), ReadError> {
+    let checks: Vec<_> = files
+        .iter()
+        .map(|file| {
+            let schema = file_schema(file, &partition.table_schema);
+            let path = ParquetFilePath::from(file);
+            async move { store.check_schema(schema.as_arrow().as_ref(), &path).await }
+        })
+        .collect::<FuturesOrdered<_>>()
+        .collect()
+        .await;
+
+    let mut compatible = Vec::with_capacity(files.len());
+    let mut incompatible = vec![];
+    for (file, check) in files.into_iter().zip(checks) {
+        match check {
+            Ok(()) => compatible.push(file),
+            Err(e @ ReadError::SchemaMismatch { .. }) => {
+                warn!(
+                    partition_id=?file.partition_id,
+                    parquet_file_id=?file.id,
+                    parquet_file_object_store_id=%file.object_store_id,
+                    %e,
+                    "skipping parquet file with a schema incompatible to the catalog"
+                );
+                incompatible.push(file);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok((compatible, incompatible))
+}
+
+/// The schema of the columns of `file`, as given by the catalog.
+fn file_schema(file: &ParquetFile, table_schema: &TableSchema) -> Schema {
     let column_id_lookup = table_schema.column_id_map();
     let selection: Vec<_> = file
         .column_set
@@ -371,9 +664,38 @@ fn to_queryable_parquet_chunk(
         .clone()
         .try_into()
         .expect("table schema is broken");
-    let schema = table_schema
+    table_schema
         .select_by_names(&selection)
-        .expect("schema in-sync");
+        .expect("schema in-sync")
+}
+
+/// A [`JoinHandle`] that is aborted on drop.
+#[derive(Debug)]
+struct AbortOnDrop<T>(JoinHandle<T>);
+
+impl<T> Future for AbortOnDrop<T> {
+    type Output = Result<T, JoinError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0).poll(cx)
+    }
+}
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Convert ParquetFile to a QueryableParquetChunk
+fn to_queryable_parquet_chunk(
+    file: ParquetFile,
+    store: ParquetStorage,
+    table_name: String,
+    table_schema: &TableSchema,
+    partition_sort_key: Option<SortKey>,
+) -> QueryableParquetChunk {
+    let schema = file_schema(&file, table_schema);
     let pk = schema.primary_key();
     let sort_key = partition_sort_key.as_ref().map(|sk| sk.filter_to(&pk));
     let file = Arc::new(file);
@@ -416,6 +738,86 @@ fn to_queryable_parquet_chunk(
     )
 }
 
+/// Build the IOx metadata of a compacted file.
+fn compacted_file_meta(
+    partition: &PartitionCompactionCandidateWithInfo,
+    creation_timestamp: Time,
+    max_sequence_number: SequenceNumber,
+    compaction_level: CompactionLevel,
+    sort_key: &SortKey,
+) -> IoxMetadata {
+    IoxMetadata {
+        object_store_id: Uuid::new_v4(),
+        creation_timestamp,
+        shard_id: partition.shard_id(),
+        namespace_id: partition.namespace_id(),
+        namespace_name: partition.namespace.name.clone().into(),
+        table_id: partition.table.id,
+        table_name: partition.table.name.clone().into(),
+        partition_id: partition.id(),
+        partition_key: partition.partition_key.clone(),
+        max_sequence_number,
+        compaction_level,
+        sort_key: Some(sort_key.clone()),
+    }
+}
+
+/// Returns true if the catalog information of `files` permits combining them by concatenating
+/// their row groups: there are at least 2 files, all with the same columns, and their time
+/// ranges do not overlap.
+///
+/// The file contents are checked again when concatenating, see [`parquet_file::concat`].
+fn can_concat(files: &[ParquetFile]) -> bool {
+    if files.len() < 2 || files.iter().any(|f| f.column_set != files[0].column_set) {
+        return false;
+    }
+
+    let mut ranges: Vec<_> = files.iter().map(|f| (f.min_time, f.max_time)).collect();
+    ranges.sort();
+    ranges.windows(2).all(|pair| pair[0].1 < pair[1].0)
+}
+
+/// Combine the files at `paths` into one new file described by `meta` by concatenating their row
+/// groups.
+///
+/// Returns `None` if the files cannot be concatenated, in which case they must be compacted
+/// regularly.
+async fn try_concat_parquet_files(
+    store: &ParquetStorage,
+    paths: &[ParquetFilePath],
+    meta: &IoxMetadata,
+    partition: &PartitionCompactionCandidateWithInfo,
+) -> Option<ParquetFileParams> {
+    let partition_id = partition.id();
+    let object_store_id = meta.object_store_id;
+
+    let (parquet_meta, file_size) = match store.concat(paths, meta).await {
+        Ok(v) => v,
+        Err(e) => {
+            debug!(?partition_id, %e, "cannot concatenate files, compacting them");
+            return None;
+        }
+    };
+
+    info!(
+        ?partition_id,
+        %object_store_id,
+        n_files = paths.len(),
+        "concatenated files without re-encoding"
+    );
+
+    Some(
+        meta.to_parquet_file(partition_id, file_size, &parquet_meta, |name| {
+            partition
+                .table_schema
+                .columns
+                .get(name)
+                .expect("unknown column")
+                .id
+        }),
+    )
+}
+
 fn cutoff_bytes(max_desired_file_size_bytes: u64, percentage_max_file_size: u16) -> (u64, u64) {
     (
         (max_desired_file_size_bytes * percentage_max_file_size as u64) / 100,
@@ -425,7 +827,7 @@ fn cutoff_bytes(max_desired_file_size_bytes: u64, percentage_max_file_size: u16)
 
 #[derive(Debug, Snafu)]
 #[allow(missing_copy_implementations, missing_docs)]
-pub(crate) enum CatalogUpdateError {
+pub enum CatalogUpdateError {
     #[snafu(display("Error while starting catalog transaction {}", source))]
     Transaction {
         source: iox_catalog::interface::Error,
@@ -445,6 +847,39 @@ pub(crate) enum CatalogUpdateError {
     FlagForDelete {
         source: iox_catalog::interface::Error,
     },
+
+    #[snafu(display("Error while recording the compaction report {}", source))]
+    Report {
+        source: iox_catalog::interface::Error,
+    },
+
+    #[snafu(display("Error while advancing the compaction cursor {}", source))]
+    CompactionCursor {
+        source: iox_catalog::interface::Error,
+    },
+
+    #[snafu(display("Error while looking up or recording the compaction key {}", source))]
+    CompactionKey {
+        source: iox_catalog::interface::Error,
+    },
+}
+
+/// Returns true if the catalog has output files of the compaction with the idempotency key
+/// `compaction_key`, see [`compaction_key`](crate::utils::compaction_key).
+async fn is_committed(
+    catalog: &dyn Catalog,
+    partition_id: PartitionId,
+    compaction_key: &str,
+) -> Result<bool, CatalogUpdateError> {
+    let committed = catalog
+        .repositories()
+        .await
+        .parquet_files()
+        .list_ids_by_compaction_key(partition_id, compaction_key)
+        .await
+        .context(CompactionKeySnafu)?;
+
+    Ok(!committed.is_empty())
 }
 
 async fn update_catalog(
@@ -452,25 +887,47 @@ async fn update_catalog(
     partition_id: PartitionId,
     compacted_parquet_files: Vec<ParquetFileParams>,
     original_parquet_file_ids: &[ParquetFileId],
-) -> Result<(), CatalogUpdateError> {
+    // Idempotency key of this compaction, recorded with the new files
+    compaction_key: &str,
+    // New compaction cursor of the partition, if level 0 files were compacted
+    compaction_cursor: Option<SequenceNumber>,
+    // Object store ID and creation time of the compaction report, if one was written
+    report: Option<(Uuid, Timestamp)>,
+) -> Result<bool, CatalogUpdateError> {
     let mut txn = catalog
         .start_transaction()
         .await
         .context(TransactionSnafu)?;
 
-    // Create the new parquet file in the catalog first
-    for parquet_file in compacted_parquet_files {
+    // Check again within the transaction, another attempt may have committed in the meantime
+    let committed = txn
+        .parquet_files()
+        .list_ids_by_compaction_key(partition_id, compaction_key)
+        .await
+        .context(CompactionKeySnafu)?;
+    if !committed.is_empty() {
+        txn.abort().await.context(TransactionSnafu)?;
+        return Ok(false);
+    }
+
+    // Create the new parquet files in the catalog first
+    for parquet_file in &compacted_parquet_files {
         debug!(
             ?partition_id,
             %parquet_file.object_store_id,
             "updating catalog"
         );
-
-        txn.parquet_files()
-            .create(parquet_file)
-            .await
-            .context(UpdateSnafu)?;
     }
+    let created = txn
+        .parquet_files()
+        .create_parquet_files(compacted_parquet_files)
+        .await
+        .context(UpdateSnafu)?;
+    let created_ids: Vec<_> = created.iter().map(|f| f.id).collect();
+    txn.parquet_files()
+        .set_compaction_key(&created_ids, compaction_key)
+        .await
+        .context(CompactionKeySnafu)?;
 
     // Mark input files for deletion
     for &original_parquet_file_id in original_parquet_file_ids {
@@ -480,18 +937,35 @@ async fn update_catalog(
             .context(FlagForDeleteSnafu)?;
     }
 
-    txn.commit().await.context(TransactionCommitSnafu)
+    if let Some(compaction_cursor) = compaction_cursor {
+        txn.partitions()
+            .update_compaction_cursor(partition_id, compaction_cursor)
+            .await
+            .context(CompactionCursorSnafu)?;
+    }
+
+    if let Some((object_store_id, created_at)) = report {
+        txn.compaction_reports()
+            .create(partition_id, object_store_id, created_at)
+            .await
+            .context(ReportSnafu)?;
+    }
+
+    txn.commit().await.context(TransactionCommitSnafu)?;
+
+    Ok(true)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::handler::SchedulerConfig;
     use arrow::record_batch::RecordBatch;
-    use arrow_util::assert_batches_sorted_eq;
+    use arrow_util::{assert_batches_eq, assert_batches_sorted_eq};
     use data_types::{ColumnType, PartitionParam, ShardId};
-    use iox_tests::util::{TestCatalog, TestParquetFileBuilder, TestTable};
+    use iox_tests::util::{TestCatalog, TestParquetFileBuilder, TestPartition, TestTable};
     use metric::U64HistogramOptions;
-    use parquet_file::ParquetFilePath;
+    use parquet_file::metadata::IoxParquetMetaData;
     use test_helpers::assert_error;
 
     #[test]
@@ -517,6 +991,7 @@ mod tests {
     struct TestSetup {
         catalog: Arc<TestCatalog>,
         table: Arc<TestTable>,
+        partition: Arc<TestPartition>,
         candidate_partition: PartitionCompactionCandidateWithInfo,
         parquet_files: Vec<ParquetFile>,
     }
@@ -555,6 +1030,8 @@ mod tests {
             },
             sort_key: partition.partition.sort_key(),
             partition_key: partition.partition.partition_key.clone(),
+            table_access_stats: None,
+            compaction_cursor: None,
         };
 
         let lp = vec![
@@ -637,6 +1114,7 @@ mod tests {
         TestSetup {
             catalog,
             table,
+            partition,
             candidate_partition,
             parquet_files,
         }
@@ -680,11 +1158,15 @@ mod tests {
             Arc::clone(&catalog.catalog),
             ParquetStorage::new(Arc::clone(&catalog.object_store)),
             Arc::clone(&catalog.exec),
+            None,
             Arc::clone(&catalog.time_provider) as Arc<dyn TimeProvider>,
             &compaction_input_file_bytes,
             DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
             DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
             DEFAULT_SPLIT_PERCENTAGE,
+            None,
+            CompactionLevel::FileNonOverlapped,
+            None,
         )
         .await;
         assert_error!(result, Error::NotEnoughParquetFiles { num_files: 0, .. });
@@ -720,11 +1202,15 @@ mod tests {
             Arc::clone(&catalog.catalog),
             ParquetStorage::new(Arc::clone(&catalog.object_store)),
             Arc::clone(&catalog.exec),
+            None,
             Arc::clone(&catalog.time_provider) as Arc<dyn TimeProvider>,
             &compaction_input_file_bytes,
             DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
             DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
             DEFAULT_SPLIT_PERCENTAGE,
+            None,
+            CompactionLevel::FileNonOverlapped,
+            None,
         )
         .await
         .unwrap();
@@ -762,6 +1248,60 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn retried_compaction_is_not_committed_again() {
+        test_helpers::maybe_start_logging();
+
+        let TestSetup {
+            catalog,
+            candidate_partition,
+            mut parquet_files,
+            ..
+        } = test_setup().await;
+        let table_id = candidate_partition.table_id();
+        let compaction_input_file_bytes = metrics();
+        let shard_id = candidate_partition.shard_id();
+
+        let to_compact = vec![parquet_files.remove(1), parquet_files.remove(0)];
+        let (test_catalog, metric) = (&catalog, &compaction_input_file_bytes);
+        let compact = move |files: Vec<ParquetFile>| {
+            compact_parquet_files(
+                files,
+                candidate_partition.clone(),
+                Arc::clone(&test_catalog.catalog),
+                ParquetStorage::new(Arc::clone(&test_catalog.object_store)),
+                Arc::clone(&test_catalog.exec),
+                None,
+                Arc::clone(&test_catalog.time_provider) as Arc<dyn TimeProvider>,
+                metric,
+                DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
+                DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
+                DEFAULT_SPLIT_PERCENTAGE,
+                None,
+                CompactionLevel::FileNonOverlapped,
+                None,
+            )
+        };
+        compact(to_compact.clone()).await.unwrap();
+        let files = catalog.list_by_table_not_to_delete(table_id).await;
+        let num_objects = catalog.object_store.list(None).await.unwrap().count().await;
+
+        // a retry, with the inputs in another order, finds the committed result and neither
+        // writes nor commits anything
+        compact(to_compact.into_iter().rev().collect())
+            .await
+            .unwrap();
+        assert_eq!(catalog.list_by_table_not_to_delete(table_id).await, files);
+        assert_eq!(
+            catalog.object_store.list(None).await.unwrap().count().await,
+            num_objects
+        );
+        assert_eq!(
+            extract_byte_metrics(&compaction_input_file_bytes, shard_id).sample_count,
+            2
+        );
+    }
+
     #[tokio::test]
     async fn small_files_get_compacted_into_one() {
         test_helpers::maybe_start_logging();
@@ -769,6 +1309,7 @@ mod tests {
         let TestSetup {
             catalog,
             table,
+            partition,
             candidate_partition,
             parquet_files,
         } = test_setup().await;
@@ -781,11 +1322,15 @@ mod tests {
             Arc::clone(&catalog.catalog),
             ParquetStorage::new(Arc::clone(&catalog.object_store)),
             Arc::clone(&catalog.exec),
+            None,
             Arc::clone(&catalog.time_provider) as Arc<dyn TimeProvider>,
             &compaction_input_file_bytes,
             DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
             DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
             DEFAULT_SPLIT_PERCENTAGE,
+            None,
+            CompactionLevel::FileNonOverlapped,
+            None,
         )
         .await
         .unwrap();
@@ -812,6 +1357,18 @@ mod tests {
             ]
         );
 
+        // The compaction cursor was advanced to the highest compacted level 0 file
+        let partition = catalog
+            .catalog
+            .repositories()
+            .await
+            .partitions()
+            .get_by_id(partition.partition.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(partition.compaction_cursor, Some(SequenceNumber::new(2)));
+
         // Verify the metrics
         assert_eq!(
             extract_byte_metrics(&compaction_input_file_bytes, shard_id),
@@ -855,6 +1412,7 @@ mod tests {
             table,
             candidate_partition,
             parquet_files,
+            ..
         } = test_setup().await;
         let compaction_input_file_bytes = metrics();
         let shard_id = candidate_partition.shard_id();
@@ -865,11 +1423,15 @@ mod tests {
             Arc::clone(&catalog.catalog),
             ParquetStorage::new(Arc::clone(&catalog.object_store)),
             Arc::clone(&catalog.exec),
+            None,
             Arc::clone(&catalog.time_provider) as Arc<dyn TimeProvider>,
             &compaction_input_file_bytes,
             DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
             DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
             DEFAULT_SPLIT_PERCENTAGE,
+            None,
+            CompactionLevel::FileNonOverlapped,
+            None,
         )
         .await
         .unwrap();
@@ -951,6 +1513,7 @@ mod tests {
             table,
             candidate_partition,
             parquet_files,
+            ..
         } = test_setup().await;
         let compaction_input_file_bytes = metrics();
         let shard_id = candidate_partition.shard_id();
@@ -967,11 +1530,15 @@ mod tests {
             Arc::clone(&catalog.catalog),
             ParquetStorage::new(Arc::clone(&catalog.object_store)),
             Arc::clone(&catalog.exec),
+            None,
             Arc::clone(&catalog.time_provider) as Arc<dyn TimeProvider>,
             &compaction_input_file_bytes,
             DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
             DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
             split_percentage,
+            None,
+            CompactionLevel::FileNonOverlapped,
+            None,
         )
         .await
         .unwrap();
@@ -1040,6 +1607,7 @@ mod tests {
             table,
             candidate_partition,
             parquet_files,
+            ..
         } = test_setup().await;
         let compaction_input_file_bytes = metrics();
         let shard_id = candidate_partition.shard_id();
@@ -1050,11 +1618,15 @@ mod tests {
             Arc::clone(&catalog.catalog),
             ParquetStorage::new(Arc::clone(&catalog.object_store)),
             Arc::clone(&catalog.exec),
+            None,
             Arc::clone(&catalog.time_provider) as Arc<dyn TimeProvider>,
             &compaction_input_file_bytes,
             DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
             DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
             DEFAULT_SPLIT_PERCENTAGE,
+            None,
+            CompactionLevel::FileNonOverlapped,
+            None,
         )
         .await
         .unwrap();
@@ -1140,6 +1712,383 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn compaction_report_is_written_and_referenced() {
+        test_helpers::maybe_start_logging();
+
+        let TestSetup {
+            catalog,
+            candidate_partition,
+            parquet_files,
+            ..
+        } = test_setup().await;
+        let compaction_input_file_bytes = metrics();
+        let partition_id = candidate_partition.id();
+        let namespace_id = candidate_partition.namespace_id();
+        let table_id = candidate_partition.table_id();
+        let shard_id = candidate_partition.shard_id();
+
+        let config = CompactorConfig::new(
+            DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
+            DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
+            DEFAULT_SPLIT_PERCENTAGE,
+            90_000,
+            1,
+            1,
+            600 * 1024 * 1024,
+            100,
+            4,
+            100_000_000,
+            true,
+            SchedulerConfig::default(),
+            SchedulerConfig::default(),
+        );
+
+        compact_parquet_files(
+            parquet_files.into_iter().take(4).collect(),
+            candidate_partition,
+            Arc::clone(&catalog.catalog),
+            ParquetStorage::new(Arc::clone(&catalog.object_store)),
+            Arc::clone(&catalog.exec),
+            None,
+            Arc::clone(&catalog.time_provider) as Arc<dyn TimeProvider>,
+            &compaction_input_file_bytes,
+            DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
+            DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
+            DEFAULT_SPLIT_PERCENTAGE,
+            None,
+            CompactionLevel::FileNonOverlapped,
+            Some(config),
+        )
+        .await
+        .unwrap();
+
+        // The report is referenced from the catalog
+        let reports = catalog
+            .catalog
+            .repositories()
+            .await
+            .compaction_reports()
+            .list_by_partition(partition_id)
+            .await
+            .unwrap();
+        assert_eq!(reports.len(), 1);
+
+        // ... and stored next to the compacted files
+        let path = ReportPath::new(
+            namespace_id,
+            table_id,
+            shard_id,
+            partition_id,
+            reports[0].object_store_id,
+        );
+        let data = catalog
+            .object_store
+            .get(&path.object_store_path())
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&data).unwrap();
+
+        assert_eq!(report["format_version"], REPORT_FORMAT_VERSION);
+        assert_eq!(report["partition_id"], partition_id.get());
+        assert_eq!(report["target_level"], 1);
+        assert_eq!(report["inputs"].as_array().unwrap().len(), 4);
+        assert_eq!(report["outputs"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            report["outputs"][0]["object_store_id"],
+            catalog
+                .list_by_table_not_to_delete(table_id)
+                .await
+                .into_iter()
+                // the compacted file, see `small_files_get_compacted_into_one`
+                .find(|f| f.id.get() == 7)
+                .unwrap()
+                .object_store_id
+                .to_string()
+        );
+        assert_eq!(
+            report["config"]["max_desired_file_size_bytes"],
+            DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES
+        );
+        assert_eq!(report["config"]["write_compaction_reports"], true);
+    }
+
+    #[tokio::test]
+    async fn non_overlapping_files_get_concatenated() {
+        test_helpers::maybe_start_logging();
+
+        let TestSetup {
+            catalog,
+            table,
+            partition,
+            candidate_partition,
+            ..
+        } = test_setup().await;
+
+        // files with the same columns and tag values that do not overlap in time
+        let mut parquet_files = vec![];
+        for (lp, time) in [
+            ("table,tag1=VT field_int=10i 20000", 20000),
+            ("table,tag1=VT field_int=20i 10000", 10000),
+            ("table,tag1=VT field_int=30i 30000", 30000),
+        ] {
+            let builder = TestParquetFileBuilder::default()
+                .with_line_protocol(lp)
+                .with_min_time(time)
+                .with_max_time(time);
+            parquet_files.push(partition.create_parquet_file(builder).await.parquet_file);
+        }
+        let ids: Vec<_> = parquet_files.iter().map(|f| f.id).collect();
+
+        compact_parquet_files(
+            parquet_files,
+            candidate_partition,
+            Arc::clone(&catalog.catalog),
+            ParquetStorage::new(Arc::clone(&catalog.object_store)),
+            Arc::clone(&catalog.exec),
+            None,
+            Arc::clone(&catalog.time_provider) as Arc<dyn TimeProvider>,
+            &metrics(),
+            DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
+            DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
+            DEFAULT_SPLIT_PERCENTAGE,
+            None,
+            CompactionLevel::FileNonOverlapped,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let mut files: Vec<_> = catalog
+            .list_by_table_not_to_delete(table.table.id)
+            .await
+            .into_iter()
+            .filter(|f| f.compaction_level == CompactionLevel::FileNonOverlapped)
+            .filter(|f| !ids.contains(&f.id))
+            .collect();
+        assert_eq!(files.len(), 1);
+        let file = files.pop().unwrap();
+        assert_eq!(file.row_count, 3);
+        assert_eq!(file.min_time, Timestamp::new(10000));
+        assert_eq!(file.max_time, Timestamp::new(30000));
+
+        // the row groups of the inputs were copied rather than re-encoded
+        let data = catalog
+            .object_store
+            .get(&ParquetFilePath::from(&file).object_store_path())
+            .await
+            .unwrap()
+            .bytes()
+            .await
+            .unwrap();
+        let parquet_meta = IoxParquetMetaData::from_file_bytes(data)
+            .unwrap()
+            .unwrap()
+            .decode()
+            .unwrap();
+        assert_eq!(parquet_meta.parquet_row_group_metadata().len(), 3);
+
+        let batches = read_parquet_file(&table, file).await;
+        assert_batches_eq!(
+            &[
+                "+-----------+------+-----------------------------+",
+                "| field_int | tag1 | time                        |",
+                "+-----------+------+-----------------------------+",
+                "| 20        | VT   | 1970-01-01T00:00:00.000010Z |",
+                "| 10        | VT   | 1970-01-01T00:00:00.000020Z |",
+                "| 30        | VT   | 1970-01-01T00:00:00.000030Z |",
+                "+-----------+------+-----------------------------+",
+            ],
+            &batches
+        );
+    }
+
+    #[tokio::test]
+    async fn small_files_get_split_at_interval_boundaries() {
+        test_helpers::maybe_start_logging();
+
+        let TestSetup {
+            catalog,
+            table,
+            partition,
+            candidate_partition,
+            ..
+        } = test_setup().await;
+
+        let mut parquet_files = vec![];
+        for (lp, time) in [
+            ("table,tag1=VT field_int=10i 20000", 20000),
+            ("table,tag1=VT field_int=20i 10000", 10000),
+            ("table,tag1=VT field_int=30i 30000", 30000),
+        ] {
+            let builder = TestParquetFileBuilder::default()
+                .with_line_protocol(lp)
+                .with_min_time(time)
+                .with_max_time(time);
+            parquet_files.push(partition.create_parquet_file(builder).await.parquet_file);
+        }
+        let ids: Vec<_> = parquet_files.iter().map(|f| f.id).collect();
+
+        // boundaries at 15000 and 30000, even though the data is "small"
+        compact_parquet_files(
+            parquet_files,
+            candidate_partition,
+            Arc::clone(&catalog.catalog),
+            ParquetStorage::new(Arc::clone(&catalog.object_store)),
+            Arc::clone(&catalog.exec),
+            None,
+            Arc::clone(&catalog.time_provider) as Arc<dyn TimeProvider>,
+            &metrics(),
+            DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
+            DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
+            DEFAULT_SPLIT_PERCENTAGE,
+            Some(Duration::from_nanos(15000)),
+            CompactionLevel::FileNonOverlapped,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let mut files: Vec<_> = catalog
+            .list_by_table_not_to_delete(table.table.id)
+            .await
+            .into_iter()
+            .filter(|f| f.compaction_level == CompactionLevel::FileNonOverlapped)
+            .filter(|f| !ids.contains(&f.id))
+            .map(|f| (f.min_time.get(), f.max_time.get(), f.row_count))
+            .collect();
+        files.sort_unstable();
+        assert_eq!(
+            files,
+            vec![(10000, 10000, 1), (20000, 20000, 1), (30000, 30000, 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn files_with_incompatible_schema_are_skipped() {
+        test_helpers::maybe_start_logging();
+
+        let TestSetup {
+            catalog,
+            table,
+            partition,
+            candidate_partition,
+            ..
+        } = test_setup().await;
+
+        // the second file has a float `field_int` while the catalog says integer
+        let mut parquet_files = vec![];
+        for (lp, max_seq) in [
+            ("table,tag1=VT field_int=10i 10000", 10),
+            ("table,tag1=VT field_int=1.5 20000", 11),
+            ("table,tag1=VT field_int=30i 30000", 12),
+        ] {
+            let builder = TestParquetFileBuilder::default()
+                .with_line_protocol(lp)
+                .with_max_seq(max_seq);
+            parquet_files.push(partition.create_parquet_file(builder).await.parquet_file);
+        }
+        let ids: Vec<_> = parquet_files.iter().map(|f| f.id).collect();
+
+        compact_parquet_files(
+            parquet_files,
+            candidate_partition,
+            Arc::clone(&catalog.catalog),
+            ParquetStorage::new(Arc::clone(&catalog.object_store)),
+            Arc::clone(&catalog.exec),
+            None,
+            Arc::clone(&catalog.time_provider) as Arc<dyn TimeProvider>,
+            &metrics(),
+            DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
+            DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
+            DEFAULT_SPLIT_PERCENTAGE,
+            None,
+            CompactionLevel::FileNonOverlapped,
+            None,
+        )
+        .await
+        .unwrap();
+
+        // only the incompatible file is left, untouched
+        let files = catalog.list_by_table_not_to_delete(table.table.id).await;
+        let remaining: Vec<_> = files.iter().filter(|f| ids.contains(&f.id)).collect();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, ids[1]);
+        assert_eq!(remaining[0].compaction_level, CompactionLevel::Initial);
+
+        // the compaction cursor stays below the skipped file
+        let partition = catalog
+            .catalog
+            .repositories()
+            .await
+            .partitions()
+            .get_by_id(partition.partition.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(partition.compaction_cursor, Some(SequenceNumber::new(10)));
+
+        let mut compacted: Vec<_> = files
+            .into_iter()
+            .filter(|f| f.compaction_level == CompactionLevel::FileNonOverlapped)
+            .filter(|f| f.max_sequence_number == SequenceNumber::new(12))
+            .collect();
+        assert_eq!(compacted.len(), 1);
+        let batches = read_parquet_file(&table, compacted.pop().unwrap()).await;
+        assert_batches_sorted_eq!(
+            &[
+                "+-----------+------+-----------------------------+",
+                "| field_int | tag1 | time                        |",
+                "+-----------+------+-----------------------------+",
+                "| 10        | VT   | 1970-01-01T00:00:00.000010Z |",
+                "| 30        | VT   | 1970-01-01T00:00:00.000030Z |",
+                "+-----------+------+-----------------------------+",
+            ],
+            &batches
+        );
+    }
+
+    #[tokio::test]
+    async fn spill_directory_is_removed_after_compaction() {
+        test_helpers::maybe_start_logging();
+
+        let TestSetup {
+            catalog,
+            table,
+            candidate_partition,
+            parquet_files,
+            ..
+        } = test_setup().await;
+        let root = test_helpers::tmp_dir().unwrap();
+        let spill_dir = SpillDir::new(root.path(), 1024 * 1024, &metric::Registry::new()).unwrap();
+
+        compact_parquet_files(
+            parquet_files.into_iter().take(4).collect(),
+            candidate_partition,
+            Arc::clone(&catalog.catalog),
+            ParquetStorage::new(Arc::clone(&catalog.object_store)),
+            Arc::clone(&catalog.exec),
+            Some(&spill_dir),
+            Arc::clone(&catalog.time_provider) as Arc<dyn TimeProvider>,
+            &metrics(),
+            DEFAULT_MAX_DESIRED_FILE_SIZE_BYTES,
+            DEFAULT_PERCENTAGE_MAX_FILE_SIZE,
+            DEFAULT_SPLIT_PERCENTAGE,
+            None,
+            CompactionLevel::FileNonOverlapped,
+            None,
+        )
+        .await
+        .unwrap();
+
+        let files = catalog.list_by_table_not_to_delete(table.table.id).await;
+        assert_eq!(files.len(), 3);
+        assert_eq!(std::fs::read_dir(root.path()).unwrap().count(), 0);
+    }
+
     async fn read_parquet_file(table: &Arc<TestTable>, file: ParquetFile) -> Vec<RecordBatch> {
         let storage = ParquetStorage::new(table.catalog.object_store());
 
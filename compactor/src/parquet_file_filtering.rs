@@ -4,9 +4,10 @@
 use crate::{
     compact::PartitionCompactionCandidateWithInfo, parquet_file_lookup::ParquetFilesForCompaction,
 };
-use data_types::{ColumnType, ColumnTypeCount, ParquetFile};
+use data_types::{ColumnType, ColumnTypeCount, CompactionLevel, ParquetFile};
 use metric::{Attributes, Metric, U64Gauge, U64Histogram};
 use observability_deps::tracing::*;
+use std::fmt::Debug;
 
 const AVERAGE_TAG_VALUE_LENGTH: i64 = 200;
 const STRING_LENGTH: i64 = 1000;
@@ -132,6 +133,7 @@ pub(crate) fn filter_hot_parquet_files(
     let ParquetFilesForCompaction {
         level_0,
         level_1: mut remaining_level_1,
+        ..
     } = parquet_files_for_compaction;
 
     if level_0.is_empty() {
@@ -299,6 +301,7 @@ pub(crate) fn filter_cold_parquet_files(
     let ParquetFilesForCompaction {
         level_0,
         level_1: mut remaining_level_1,
+        ..
     } = parquet_files_for_compaction;
 
     if level_0.is_empty() {
@@ -409,6 +412,176 @@ pub(crate) fn filter_cold_parquet_files(
     files_to_return
 }
 
+/// Given the files of a partition, find the lowest compaction level above level 0 whose files add
+/// up to at least the target file size of the next level, and select a subset of files that:
+///
+/// - Has the files of that level with the earliest min times, until the total size of all selected
+///   files exceeds `max_bytes` (but at least one)
+/// - Has all files of the next level that overlap in time with the selected files
+///
+/// Returns the level to compact the selected files into, or `None` if no level is due for
+/// compaction.
+pub(crate) fn filter_upper_level_parquet_files(
+    // Files of all levels for one partition
+    parquet_files_for_compaction: &ParquetFilesForCompaction,
+    // Target file size of every level above level 1, starting with level 2
+    upper_level_file_sizes_bytes: &[u64],
+    // Stop selecting files when the total size of all files selected so far exceeds this value
+    max_bytes: u64,
+) -> Option<(CompactionLevel, Vec<ParquetFile>)> {
+    let mut level = CompactionLevel::FileNonOverlapped;
+    for target_file_size_bytes in upper_level_file_sizes_bytes {
+        let next_level = level.next()?;
+        let files = parquet_files_for_compaction.files_at(level);
+        let level_bytes = files.iter().map(|f| f.file_size_bytes).sum::<i64>() as u64;
+        if level_bytes < *target_file_size_bytes {
+            level = next_level;
+            continue;
+        }
+
+        let mut files: Vec<_> = files.iter().collect();
+        files.sort_by_key(|f| f.min_time);
+        let next_level_files = parquet_files_for_compaction.files_at(next_level);
+
+        // Extend the selected time range file by file. Files of the next level that overlap the
+        // range have to be compacted along, so that the next level does not overlap itself.
+        let mut selected: Vec<&ParquetFile> = Vec::with_capacity(files.len());
+        let mut selected_bytes = 0;
+        let mut overlapping: Vec<&ParquetFile> = vec![];
+        for file in files {
+            let min_time = selected.first().map_or(file.min_time, |f| f.min_time);
+            let max_time = selected
+                .iter()
+                .map(|f| f.max_time)
+                .max()
+                .map_or(file.max_time, |t| t.max(file.max_time));
+            let next_overlapping: Vec<_> = next_level_files
+                .iter()
+                .filter(|f| f.min_time <= max_time && f.max_time >= min_time)
+                .collect();
+            let next_overlapping_bytes = next_overlapping
+                .iter()
+                .map(|f| f.file_size_bytes)
+                .sum::<i64>() as u64;
+
+            let total_bytes = selected_bytes + file.file_size_bytes as u64 + next_overlapping_bytes;
+            if !selected.is_empty() && total_bytes > max_bytes {
+                break;
+            }
+
+            selected_bytes += file.file_size_bytes as u64;
+            selected.push(file);
+            overlapping = next_overlapping;
+        }
+
+        info!(
+            partition_id = selected[0].partition_id.get(),
+            level = level as i16,
+            num_files_considering = parquet_files_for_compaction.files_at(level).len(),
+            num_files_compacting = selected.len(),
+            num_next_level_files_compacting = overlapping.len(),
+            "filtered Parquet files for compaction into the next level",
+        );
+
+        let files = overlapping.into_iter().chain(selected).cloned().collect();
+        return Some((next_level, files));
+    }
+
+    None
+}
+
+/// Estimated benefit and cost of compacting a partition, used to rank the compaction candidates
+/// with a [`CandidateScorer`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CompactionBenefit {
+    /// Number of level 0 files that overlap in time with another level 0 or level 1 file. Every
+    /// overlap has to be deduplicated by all queries reading the partition.
+    pub overlapping_files: usize,
+
+    /// Number of files smaller than the small file threshold. Small files are rewritten again
+    /// and again when they are compacted with newer files.
+    pub small_files: usize,
+
+    /// Bytes of level 0 files that overlap no other file. These files are upgraded to level 1
+    /// without being rewritten.
+    pub upgradable_bytes: i64,
+
+    /// Bytes of the level 0 files and the level 1 files they overlap with, i.e. the bytes that
+    /// are read and written to compact the partition.
+    pub total_bytes: i64,
+}
+
+impl CompactionBenefit {
+    /// Estimate the benefit of compacting `files`. Files smaller than
+    /// `small_file_threshold_bytes` count as small files.
+    pub(crate) fn new(files: &ParquetFilesForCompaction, small_file_threshold_bytes: i64) -> Self {
+        let mut benefit = Self::default();
+
+        for (i, level_0) in files.level_0.iter().enumerate() {
+            let overlapping_level_1: Vec<_> = files
+                .level_1
+                .iter()
+                .filter(|level_1| overlaps_in_time(level_0, level_1))
+                .collect();
+            let overlaps_level_0 = files
+                .level_0
+                .iter()
+                .enumerate()
+                .any(|(j, other)| i != j && overlaps_in_time(level_0, other));
+
+            if overlaps_level_0 || !overlapping_level_1.is_empty() {
+                benefit.overlapping_files += 1;
+            } else {
+                benefit.upgradable_bytes += level_0.file_size_bytes;
+            }
+            benefit.total_bytes += level_0.file_size_bytes;
+        }
+
+        let level_1_to_compact = files.level_1.iter().filter(|level_1| {
+            files
+                .level_0
+                .iter()
+                .any(|level_0| overlaps_in_time(level_0, level_1))
+        });
+        for level_1 in level_1_to_compact {
+            benefit.total_bytes += level_1.file_size_bytes;
+        }
+
+        benefit.small_files = files
+            .level_0
+            .iter()
+            .chain(&files.level_1)
+            .filter(|f| f.file_size_bytes < small_file_threshold_bytes)
+            .count();
+
+        benefit
+    }
+}
+
+/// Ranks compaction candidates, candidates with higher scores are compacted first.
+pub trait CandidateScorer: Debug + Send + Sync {
+    /// Score of compacting a partition with the given estimated benefit.
+    fn score(&self, benefit: &CompactionBenefit) -> f64;
+}
+
+/// Scores candidates by the write amplification they save per byte compacted: the more
+/// overlapping and small files and the more bytes that can be upgraded without rewriting them,
+/// the higher the score; the more bytes that have to be rewritten, the lower the score.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WriteAmplificationScorer;
+
+impl CandidateScorer for WriteAmplificationScorer {
+    fn score(&self, benefit: &CompactionBenefit) -> f64 {
+        const MB: f64 = (1024 * 1024) as f64;
+
+        let files_saved = (benefit.overlapping_files + benefit.small_files) as f64;
+        let bytes_upgraded = benefit.upgradable_bytes as f64 / MB;
+        let bytes_rewritten = (benefit.total_bytes - benefit.upgradable_bytes) as f64 / MB;
+
+        (files_saved + bytes_upgraded) / (1.0 + bytes_rewritten)
+    }
+}
+
 fn overlaps_in_time(a: &ParquetFile, b: &ParquetFile) -> bool {
     (a.min_time <= b.min_time && a.max_time >= b.min_time)
         || (a.min_time > b.min_time && a.min_time <= b.max_time)
@@ -488,9 +661,9 @@ fn record_byte_metrics(
 mod tests {
     use super::*;
     use data_types::{
-        ColumnSet, CompactionLevel, Namespace, NamespaceId, ParquetFileId, PartitionId,
-        PartitionParam, QueryPoolId, SequenceNumber, ShardId, Table, TableId, TableSchema,
-        Timestamp, TopicId,
+        ColumnSet, ColumnStatsSet, CompactionLevel, Namespace, NamespaceId, ParquetFileId,
+        PartitionId, PartitionParam, QueryPoolId, SequenceNumber, ShardId, Table, TableId,
+        TableSchema, Timestamp, TopicId,
     };
     use metric::{ObservationBucket, U64HistogramOptions};
     use std::{collections::BTreeMap, sync::Arc};
@@ -499,6 +672,97 @@ mod tests {
     const BUCKET_500_KB: u64 = 500 * 1024;
     const BUCKET_1_MB: u64 = 1024 * 1024;
 
+    #[test]
+    fn test_compaction_benefit() {
+        let files = ParquetFilesForCompaction {
+            level_0: vec![
+                // overlaps with the next level 0 file
+                ParquetFileBuilder::level_0()
+                    .id(1)
+                    .min_time(1)
+                    .max_time(3)
+                    .file_size_bytes(10)
+                    .build(),
+                ParquetFileBuilder::level_0()
+                    .id(2)
+                    .min_time(2)
+                    .max_time(4)
+                    .file_size_bytes(20)
+                    .build(),
+                // overlaps with the first level 1 file
+                ParquetFileBuilder::level_0()
+                    .id(3)
+                    .min_time(10)
+                    .max_time(12)
+                    .file_size_bytes(300)
+                    .build(),
+                // overlaps nothing
+                ParquetFileBuilder::level_0()
+                    .id(4)
+                    .min_time(20)
+                    .max_time(22)
+                    .file_size_bytes(400)
+                    .build(),
+            ],
+            level_1: vec![
+                ParquetFileBuilder::level_1()
+                    .id(5)
+                    .min_time(11)
+                    .max_time(15)
+                    .file_size_bytes(500)
+                    .build(),
+                // not compacted
+                ParquetFileBuilder::level_1()
+                    .id(6)
+                    .min_time(30)
+                    .max_time(35)
+                    .file_size_bytes(600)
+                    .build(),
+            ],
+            upper_levels: BTreeMap::new(),
+        };
+
+        assert_eq!(
+            CompactionBenefit::new(&files, 100),
+            CompactionBenefit {
+                overlapping_files: 3,
+                small_files: 2,
+                upgradable_bytes: 400,
+                total_bytes: 10 + 20 + 300 + 400 + 500,
+            }
+        );
+    }
+
+    #[test]
+    fn test_write_amplification_scorer() {
+        let scorer = WriteAmplificationScorer;
+        let mb = 1024 * 1024;
+
+        // nothing to gain
+        assert_eq!(scorer.score(&CompactionBenefit::default()), 0.0);
+
+        // the same number of overlaps is worth more if less bytes have to be rewritten
+        let small = CompactionBenefit {
+            overlapping_files: 2,
+            total_bytes: mb,
+            ..Default::default()
+        };
+        let large = CompactionBenefit {
+            overlapping_files: 2,
+            total_bytes: 100 * mb,
+            ..Default::default()
+        };
+        assert!(scorer.score(&small) > scorer.score(&large));
+
+        // upgrading files is cheap
+        let upgrade = CompactionBenefit {
+            upgradable_bytes: 100 * mb,
+            total_bytes: 100 * mb,
+            ..Default::default()
+        };
+        assert!(scorer.score(&upgrade) > scorer.score(&large));
+    }
+
     #[test]
     fn test_overlaps_in_time() {
         assert_overlap((1, 3), (2, 4));
@@ -630,6 +894,7 @@ mod tests {
             let parquet_files_for_compaction = ParquetFilesForCompaction {
                 level_0: vec![],
                 level_1: vec![],
+                upper_levels: BTreeMap::new(),
             };
             let (files_metric, bytes_metric) = metrics();
 
@@ -657,6 +922,7 @@ mod tests {
             let parquet_files_for_compaction = ParquetFilesForCompaction {
                 level_0: vec![ParquetFileBuilder::level_0().id(1).build()],
                 level_1: vec![],
+                upper_levels: BTreeMap::new(),
             };
             let (files_metric, bytes_metric) = metrics();
 
@@ -683,6 +949,7 @@ mod tests {
             let parquet_files_for_compaction = ParquetFilesForCompaction {
                 level_0: vec![ParquetFileBuilder::level_0().id(1).build()],
                 level_1: vec![],
+                upper_levels: BTreeMap::new(),
             };
             let (files_metric, bytes_metric) = metrics();
 
@@ -740,6 +1007,7 @@ mod tests {
                         .max_time(500)
                         .build(),
                 ],
+                upper_levels: BTreeMap::new(),
             };
             let (files_metric, bytes_metric) = metrics();
 
@@ -844,6 +1112,7 @@ mod tests {
                         .file_size_bytes(10)
                         .build(),
                 ],
+                upper_levels: BTreeMap::new(),
             };
 
             // total needed budget for one file with a tag, a time and 11 rows = 1176
@@ -925,6 +1194,7 @@ mod tests {
             let parquet_files_for_compaction = ParquetFilesForCompaction {
                 level_0: vec![],
                 level_1: vec![],
+                upper_levels: BTreeMap::new(),
             };
             let (files_metric, bytes_metric) = metrics();
 
@@ -944,6 +1214,7 @@ mod tests {
             let parquet_files_for_compaction = ParquetFilesForCompaction {
                 level_0: vec![ParquetFileBuilder::level_0().id(1).build()],
                 level_1: vec![],
+                upper_levels: BTreeMap::new(),
             };
             let (files_metric, bytes_metric) = metrics();
 
@@ -964,6 +1235,7 @@ mod tests {
             let parquet_files_for_compaction = ParquetFilesForCompaction {
                 level_0: vec![ParquetFileBuilder::level_0().id(1).build()],
                 level_1: vec![],
+                upper_levels: BTreeMap::new(),
             };
             let (files_metric, bytes_metric) = metrics();
 
@@ -1000,6 +1272,7 @@ mod tests {
                         .max_time(500)
                         .build(),
                 ],
+                upper_levels: BTreeMap::new(),
             };
             let (files_metric, bytes_metric) = metrics();
 
@@ -1043,6 +1316,7 @@ mod tests {
                         .max_time(500)
                         .build(),
                 ],
+                upper_levels: BTreeMap::new(),
             };
             let (files_metric, bytes_metric) = metrics();
 
@@ -1107,6 +1381,7 @@ mod tests {
                         .file_size_bytes(10)
                         .build(),
                 ],
+                upper_levels: BTreeMap::new(),
             };
 
             // all level 0 files & no level 1 files get returned
@@ -1216,6 +1491,7 @@ mod tests {
                         .file_size_bytes(10)
                         .build(),
                 ],
+                upper_levels: BTreeMap::new(),
             };
 
             // Max size 0; only the first level 0 file and its overlapping level 1 files get
@@ -1346,6 +1622,115 @@ mod tests {
         }
     }
 
+    mod upper_levels {
+        use super::*;
+
+        const DEFAULT_MAX_BYTES: u64 = 1024 * 1024 * 10;
+
+        fn files() -> ParquetFilesForCompaction {
+            ParquetFilesForCompaction {
+                level_0: vec![ParquetFileBuilder::level_0().id(1).build()],
+                level_1: vec![
+                    ParquetFileBuilder::level_1()
+                        .id(101)
+                        .min_time(10)
+                        .max_time(19)
+                        .file_size_bytes(40)
+                        .build(),
+                    ParquetFileBuilder::level_1()
+                        .id(102)
+                        .min_time(20)
+                        .max_time(29)
+                        .file_size_bytes(40)
+                        .build(),
+                    ParquetFileBuilder::level_1()
+                        .id(103)
+                        .min_time(0)
+                        .max_time(9)
+                        .file_size_bytes(40)
+                        .build(),
+                ],
+                upper_levels: BTreeMap::from([(
+                    CompactionLevel::Level2,
+                    vec![
+                        // overlaps with level 1 files
+                        ParquetFileBuilder::level_1()
+                            .compaction_level(CompactionLevel::Level2)
+                            .id(201)
+                            .min_time(5)
+                            .max_time(15)
+                            .file_size_bytes(100)
+                            .build(),
+                        // no overlaps
+                        ParquetFileBuilder::level_1()
+                            .compaction_level(CompactionLevel::Level2)
+                            .id(202)
+                            .min_time(50)
+                            .max_time(60)
+                            .file_size_bytes(100)
+                            .build(),
+                    ],
+                )]),
+            }
+        }
+
+        fn ids(files: &[ParquetFile]) -> Vec<i64> {
+            files.iter().map(|f| f.id.get()).collect()
+        }
+
+        #[test]
+        fn no_upper_levels_returns_none() {
+            assert_eq!(
+                filter_upper_level_parquet_files(&files(), &[], DEFAULT_MAX_BYTES),
+                None
+            );
+        }
+
+        #[test]
+        fn small_level_1_returns_none() {
+            assert_eq!(
+                filter_upper_level_parquet_files(&files(), &[121], DEFAULT_MAX_BYTES),
+                None
+            );
+        }
+
+        #[test]
+        fn level_1_files_and_their_level_2_overlaps() {
+            let (level, files) =
+                filter_upper_level_parquet_files(&files(), &[120], DEFAULT_MAX_BYTES).unwrap();
+
+            assert_eq!(level, CompactionLevel::Level2);
+            assert_eq!(ids(&files), [201, 103, 101, 102]);
+        }
+
+        #[test]
+        fn max_bytes_limits_level_1_files() {
+            // the earliest level 1 file and its overlap are always included
+            let (level, files) = filter_upper_level_parquet_files(&files(), &[120], 0).unwrap();
+            assert_eq!(level, CompactionLevel::Level2);
+            assert_eq!(ids(&files), [201, 103]);
+
+            let (level, files) = filter_upper_level_parquet_files(&files(), &[120], 180).unwrap();
+            assert_eq!(level, CompactionLevel::Level2);
+            assert_eq!(ids(&files), [201, 103, 101]);
+        }
+
+        #[test]
+        fn level_2_files_into_level_3() {
+            // level 1 is not due, but level 2 is
+            let (level, files) =
+                filter_upper_level_parquet_files(&files(), &[150, 200], DEFAULT_MAX_BYTES).unwrap();
+
+            assert_eq!(level, CompactionLevel::Level3);
+            assert_eq!(ids(&files), [201, 202]);
+
+            assert_eq!(
+                filter_upper_level_parquet_files(&files(), &[150, 201], DEFAULT_MAX_BYTES),
+                None
+            );
+        }
+    }
+
     /// Create ParquetFile instances for testing. Only sets fields relevant to the filtering; other
     /// fields are set to arbitrary and possibly invalid values. For example, by default, all
     /// ParquetFile instances created by this function will have the same ParquetFileId, which is
@@ -1382,6 +1767,11 @@ mod tests {
             }
         }
 
+        fn compaction_level(mut self, compaction_level: CompactionLevel) -> Self {
+            self.compaction_level = compaction_level;
+            self
+        }
+
         fn id(mut self, id: i64) -> Self {
             self.id = id;
             self
@@ -1427,6 +1817,7 @@ mod tests {
                 compaction_level,
                 created_at: Timestamp::new(12),
                 column_set: ColumnSet::new(std::iter::empty()),
+                column_stats: ColumnStatsSet::default(),
             }
         }
 
@@ -1455,6 +1846,9 @@ mod tests {
                     query_pool_id: QueryPoolId::new(1),
                     max_tables: 100,
                     max_columns_per_table: 100,
+                    write_rate_limit: None,
+                    query_rate_limit: None,
+                    query_timeout_ms: None,
                 }),
                 table_schema: Arc::new(TableSchema {
                     id: p.table_id,
@@ -1462,6 +1856,8 @@ mod tests {
                 }),
                 sort_key: None,
                 partition_key: "partition_key".into(),
+                table_access_stats: None,
+                compaction_cursor: None,
             }
         }
     }
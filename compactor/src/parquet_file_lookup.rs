@@ -1,11 +1,11 @@
 //! Logic for finding relevant Parquet files in the catalog to be considered during a compaction
 //! operation.
 
-use data_types::{CompactionLevel, ParquetFile, PartitionId};
+use data_types::{CompactionLevel, ParquetFile, PartitionId, SequenceNumber};
 use iox_catalog::interface::Catalog;
 use observability_deps::tracing::*;
 use snafu::{ResultExt, Snafu};
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 
 #[derive(Debug, Snafu)]
 #[allow(missing_copy_implementations, missing_docs)]
@@ -21,6 +21,10 @@ pub(crate) enum PartitionFilesFromPartitionError {
     },
 }
 
+/// Maximum number of files listed from the catalog at once, so that partitions with a huge
+/// number of files don't result in huge result sets and statement timeouts.
+const PAGE_SIZE: usize = 10_000;
+
 /// Collection of Parquet files relevant to compacting a partition. Separated by compaction level.
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct ParquetFilesForCompaction {
@@ -30,42 +34,94 @@ pub(crate) struct ParquetFilesForCompaction {
 
     /// Parquet files for a partition with `CompactionLevel::FileNonOverlapped`. Arbitrary order.
     pub(crate) level_1: Vec<ParquetFile>,
+
+    /// Parquet files for a partition with a compaction level above
+    /// `CompactionLevel::FileNonOverlapped`, by level. Arbitrary order within a level. Levels
+    /// without files are not listed.
+    pub(crate) upper_levels: BTreeMap<CompactionLevel, Vec<ParquetFile>>,
 }
 
 impl ParquetFilesForCompaction {
     /// Given a catalog and a partition ID, find the Parquet files in the catalog relevant to a
     /// compaction operation.
+    ///
+    /// Level 0 files at or below the `compaction_cursor` of the partition have been compacted
+    /// already and are not listed.
     pub(crate) async fn for_partition(
         catalog: Arc<dyn Catalog>,
         partition_id: PartitionId,
+        compaction_cursor: Option<SequenceNumber>,
+    ) -> Result<Self, PartitionFilesFromPartitionError> {
+        Self::for_partition_with_page_size(catalog, partition_id, compaction_cursor, PAGE_SIZE)
+            .await
+    }
+
+    async fn for_partition_with_page_size(
+        catalog: Arc<dyn Catalog>,
+        partition_id: PartitionId,
+        compaction_cursor: Option<SequenceNumber>,
+        page_size: usize,
     ) -> Result<Self, PartitionFilesFromPartitionError> {
         info!(
             partition_id = partition_id.get(),
+            ?compaction_cursor,
             "finding parquet files for compaction"
         );
 
-        // List all valid (not soft deleted) files of the partition
-        let parquet_files = catalog
-            .repositories()
-            .await
-            .parquet_files()
-            .list_by_partition_not_to_delete(partition_id)
-            .await
-            .context(ListParquetFilesSnafu { partition_id })?;
-
-        let mut level_0 = Vec::with_capacity(parquet_files.len());
-        let mut level_1 = Vec::with_capacity(parquet_files.len());
+        let mut level_0 = vec![];
+        let mut level_1 = vec![];
+        let mut upper_levels: BTreeMap<_, Vec<_>> = BTreeMap::new();
+
+        // List all valid (not soft deleted) files of the partition, page by page
+        let mut repos = catalog.repositories().await;
+        let mut after = None;
+        loop {
+            let parquet_files = repos
+                .parquet_files()
+                .list_by_partition_not_to_delete_paged(
+                    partition_id,
+                    compaction_cursor,
+                    after,
+                    page_size,
+                )
+                .await
+                .context(ListParquetFilesSnafu { partition_id })?;
+            let num_files = parquet_files.len();
+            after = parquet_files.last().map(|pf| pf.id);
+
+            for parquet_file in parquet_files {
+                match parquet_file.compaction_level {
+                    CompactionLevel::Initial => level_0.push(parquet_file),
+                    CompactionLevel::FileNonOverlapped => level_1.push(parquet_file),
+                    level => upper_levels.entry(level).or_default().push(parquet_file),
+                }
+            }
 
-        for parquet_file in parquet_files {
-            match parquet_file.compaction_level {
-                CompactionLevel::Initial => level_0.push(parquet_file),
-                CompactionLevel::FileNonOverlapped => level_1.push(parquet_file),
+            if num_files < page_size {
+                break;
             }
         }
 
         level_0.sort_by_key(|pf| pf.max_sequence_number);
 
-        Ok(Self { level_0, level_1 })
+        Ok(Self {
+            level_0,
+            level_1,
+            upper_levels,
+        })
+    }
+
+    /// Parquet files of the given compaction level.
+    pub(crate) fn files_at(&self, level: CompactionLevel) -> &[ParquetFile] {
+        match level {
+            CompactionLevel::Initial => &self.level_0,
+            CompactionLevel::FileNonOverlapped => &self.level_1,
+            level => self
+                .upper_levels
+                .get(&level)
+                .map(Vec::as_slice)
+                .unwrap_or_default(),
+        }
     }
 }
 
@@ -162,6 +218,7 @@ mod tests {
         let parquet_files_for_compaction = ParquetFilesForCompaction::for_partition(
             Arc::clone(&catalog.catalog),
             partition.partition.id,
+            None,
         )
         .await
         .unwrap();
@@ -193,6 +250,7 @@ mod tests {
         let parquet_files_for_compaction = ParquetFilesForCompaction::for_partition(
             Arc::clone(&catalog.catalog),
             partition.partition.id,
+            None,
         )
         .await
         .unwrap();
@@ -225,6 +283,7 @@ mod tests {
         let parquet_files_for_compaction = ParquetFilesForCompaction::for_partition(
             Arc::clone(&catalog.catalog),
             partition.partition.id,
+            None,
         )
         .await
         .unwrap();
@@ -263,6 +322,7 @@ mod tests {
         let parquet_files_for_compaction = ParquetFilesForCompaction::for_partition(
             Arc::clone(&catalog.catalog),
             partition.partition.id,
+            None,
         )
         .await
         .unwrap();
@@ -302,6 +362,7 @@ mod tests {
         let parquet_files_for_compaction = ParquetFilesForCompaction::for_partition(
             Arc::clone(&catalog.catalog),
             partition.partition.id,
+            None,
         )
         .await
         .unwrap();
@@ -313,4 +374,154 @@ mod tests {
 
         assert_eq!(parquet_files_for_compaction.level_1, vec![l1.parquet_file]);
     }
+
+    #[tokio::test]
+    async fn level_0_files_at_or_below_cursor_are_skipped() {
+        test_helpers::maybe_start_logging();
+        let TestSetup {
+            catalog, partition, ..
+        } = test_setup().await;
+
+        // Create level 0 files with max seq = 50 and 100
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol(ARBITRARY_LINE_PROTOCOL)
+            .with_compaction_level(CompactionLevel::Initial)
+            .with_max_seq(50);
+        let l0_max_seq_50 = partition.create_parquet_file(builder).await;
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol(ARBITRARY_LINE_PROTOCOL)
+            .with_compaction_level(CompactionLevel::Initial)
+            .with_max_seq(100);
+        let l0_max_seq_100 = partition.create_parquet_file(builder).await;
+
+        // Create a level 1 file, with a max seq below the cursor
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol(ARBITRARY_LINE_PROTOCOL)
+            .with_compaction_level(CompactionLevel::FileNonOverlapped)
+            .with_max_seq(10);
+        let l1 = partition.create_parquet_file(builder).await;
+
+        let parquet_files_for_compaction = ParquetFilesForCompaction::for_partition(
+            Arc::clone(&catalog.catalog),
+            partition.partition.id,
+            Some(SequenceNumber::new(50)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            parquet_files_for_compaction.level_0,
+            vec![l0_max_seq_100.parquet_file.clone()]
+        );
+        assert_eq!(
+            parquet_files_for_compaction.level_1,
+            vec![l1.parquet_file.clone()]
+        );
+
+        let parquet_files_for_compaction = ParquetFilesForCompaction::for_partition(
+            Arc::clone(&catalog.catalog),
+            partition.partition.id,
+            Some(SequenceNumber::new(49)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            parquet_files_for_compaction.level_0,
+            vec![l0_max_seq_50.parquet_file, l0_max_seq_100.parquet_file]
+        );
+        assert_eq!(parquet_files_for_compaction.level_1, vec![l1.parquet_file]);
+    }
+
+    #[tokio::test]
+    async fn upper_level_files_are_grouped_by_level() {
+        test_helpers::maybe_start_logging();
+        let TestSetup {
+            catalog, partition, ..
+        } = test_setup().await;
+
+        let mut files = vec![];
+        for level in [
+            CompactionLevel::Level2,
+            CompactionLevel::FileNonOverlapped,
+            CompactionLevel::Level4,
+            CompactionLevel::Level2,
+        ] {
+            let builder = TestParquetFileBuilder::default()
+                .with_line_protocol(ARBITRARY_LINE_PROTOCOL)
+                .with_compaction_level(level);
+            files.push(partition.create_parquet_file(builder).await.parquet_file);
+        }
+
+        let parquet_files_for_compaction = ParquetFilesForCompaction::for_partition(
+            Arc::clone(&catalog.catalog),
+            partition.partition.id,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert!(parquet_files_for_compaction.level_0.is_empty());
+        assert_eq!(parquet_files_for_compaction.level_1, vec![files[1].clone()]);
+        assert_eq!(
+            parquet_files_for_compaction.files_at(CompactionLevel::Level2),
+            &[files[0].clone(), files[3].clone()]
+        );
+        assert!(parquet_files_for_compaction
+            .files_at(CompactionLevel::Level3)
+            .is_empty());
+        assert_eq!(
+            parquet_files_for_compaction.files_at(CompactionLevel::Level4),
+            &[files[2].clone()]
+        );
+        assert_eq!(
+            parquet_files_for_compaction
+                .upper_levels
+                .keys()
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![CompactionLevel::Level2, CompactionLevel::Level4]
+        );
+    }
+
+    #[tokio::test]
+    async fn files_are_listed_in_pages() {
+        test_helpers::maybe_start_logging();
+        let TestSetup {
+            catalog, partition, ..
+        } = test_setup().await;
+
+        let mut level_0 = vec![];
+        for max_seq in [30, 10, 20] {
+            let builder = TestParquetFileBuilder::default()
+                .with_line_protocol(ARBITRARY_LINE_PROTOCOL)
+                .with_compaction_level(CompactionLevel::Initial)
+                .with_max_seq(max_seq);
+            level_0.push(partition.create_parquet_file(builder).await.parquet_file);
+        }
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol(ARBITRARY_LINE_PROTOCOL)
+            .with_compaction_level(CompactionLevel::FileNonOverlapped);
+        let l1 = partition.create_parquet_file(builder).await;
+        level_0.sort_by_key(|pf| pf.max_sequence_number);
+
+        // page sizes that divide the number of files and those that don't
+        for page_size in [1, 2, 4, 5] {
+            let parquet_files_for_compaction =
+                ParquetFilesForCompaction::for_partition_with_page_size(
+                    Arc::clone(&catalog.catalog),
+                    partition.partition.id,
+                    None,
+                    page_size,
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(parquet_files_for_compaction.level_0, level_0);
+            assert_eq!(
+                parquet_files_for_compaction.level_1,
+                vec![l1.parquet_file.clone()]
+            );
+        }
+    }
 }
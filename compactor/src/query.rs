@@ -223,11 +223,16 @@ impl QueryChunk for QueryableParquetChunk {
         trace!(?selection, "selection");
 
         self.data
-            .read_filter(predicate, selection)
+            .read_filter(predicate, &self.delete_predicates, selection)
             .context(ReadParquetSnafu)
             .map_err(|e| Box::new(e) as _)
     }
 
+    /// Deleted rows are removed while the parquet file is scanned
+    fn applies_delete_predicates(&self) -> bool {
+        true
+    }
+
     /// Returns chunk type
     fn chunk_type(&self) -> &str {
         "QueryableParquetChunk"
@@ -237,7 +242,8 @@ impl QueryChunk for QueryableParquetChunk {
     fn order(&self) -> ChunkOrder {
         match self.compaction_level {
             CompactionLevel::Initial => ChunkOrder::new(self.max_sequence_number.get()),
-            CompactionLevel::FileNonOverlapped => ChunkOrder::new(0),
+            // Files of higher levels hold older data, which is overwritten by lower levels
+            level => ChunkOrder::new(CompactionLevel::FileNonOverlapped as i64 - level as i64),
         }
     }
 
@@ -311,4 +317,11 @@ mod tests {
 
         assert_eq!(chunk.order(), ChunkOrder::new(0));
     }
+
+    #[tokio::test]
+    async fn chunk_order_is_below_level_1_when_compaction_level_2() {
+        let chunk = test_setup(CompactionLevel::Level2, 2).await;
+
+        assert_eq!(chunk.order(), ChunkOrder::new(-1));
+    }
 }
@@ -0,0 +1,262 @@
+//! Structured reports of single compaction runs.
+//!
+//! If enabled via [`CompactorConfig::write_compaction_reports`], every compaction run writes a
+//! small JSON document next to the Parquet files of the compacted partition. The document
+//! describes the inputs and outputs of the run, how long it took and the configuration that led
+//! to it. It is referenced from the catalog (see
+//! [`CompactionReportRepo`](iox_catalog::interface::CompactionReportRepo)) in the same transaction
+//! that swaps the compacted files, so that any compaction decision can be analysed after the fact.
+
+use crate::handler::CompactorConfig;
+use bytes::Bytes;
+use data_types::{NamespaceId, ParquetFile, ParquetFileParams, PartitionId, ShardId, TableId};
+use iox_time::Time;
+use object_store::path::Path;
+use parquet_file::storage::ParquetStorage;
+use serde::Serialize;
+use snafu::{ResultExt, Snafu};
+use uuid::Uuid;
+
+/// Version of the report format, bumped on incompatible changes.
+pub const REPORT_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Snafu)]
+#[allow(missing_copy_implementations, missing_docs)]
+pub enum Error {
+    #[snafu(display("Could not serialize compaction report: {}", source))]
+    Serialize { source: serde_json::Error },
+
+    #[snafu(display("Could not write compaction report to object store: {}", source))]
+    Write { source: object_store::Error },
+}
+
+/// A specialized `Result` for report errors
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Report of a single compaction run of one partition.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompactionRunReport {
+    /// Version of the report format, see [`REPORT_FORMAT_VERSION`].
+    pub format_version: u32,
+
+    /// Version of the compactor that wrote this report.
+    pub compactor_version: &'static str,
+
+    /// The shard of the compacted partition.
+    pub shard_id: i64,
+
+    /// The namespace of the compacted partition.
+    pub namespace_id: i64,
+
+    /// Name of the namespace.
+    pub namespace_name: String,
+
+    /// The table of the compacted partition.
+    pub table_id: i64,
+
+    /// Name of the table.
+    pub table_name: String,
+
+    /// The compacted partition.
+    pub partition_id: i64,
+
+    /// Key of the partition.
+    pub partition_key: String,
+
+    /// Sort key of the partition that the outputs are sorted by.
+    pub sort_key: Option<Vec<String>>,
+
+    /// Compaction level of the output files.
+    pub target_level: i16,
+
+    /// Start of the run (RFC 3339).
+    pub started_at: String,
+
+    /// Time spent planning the compaction, in seconds.
+    pub plan_duration_secs: f64,
+
+    /// Time spent executing the plan and uploading the outputs, in seconds.
+    pub execute_duration_secs: f64,
+
+    /// Total size of the input files in bytes.
+    pub input_bytes: i64,
+
+    /// Total size of the output files in bytes.
+    pub output_bytes: i64,
+
+    /// The files that were compacted.
+    pub inputs: Vec<ReportFile>,
+
+    /// The files that were created.
+    pub outputs: Vec<ReportFile>,
+
+    /// The compactor configuration at the time of the run.
+    pub config: CompactorConfig,
+}
+
+impl CompactionRunReport {
+    /// Serialize the report as JSON.
+    pub fn to_json(&self) -> Result<Vec<u8>> {
+        serde_json::to_vec_pretty(self).context(SerializeSnafu)
+    }
+}
+
+/// A single input or output file of a compaction run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReportFile {
+    /// Catalog ID of the file, not known yet for outputs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+
+    /// The uuid used in the object store path of the file.
+    pub object_store_id: String,
+
+    /// Compaction level of the file.
+    pub compaction_level: i16,
+
+    /// File size in bytes.
+    pub file_size_bytes: i64,
+
+    /// Number of rows in the file.
+    pub row_count: i64,
+
+    /// Min timestamp of the data in the file.
+    pub min_time: i64,
+
+    /// Max timestamp of the data in the file.
+    pub max_time: i64,
+
+    /// Max sequence number of the data in the file.
+    pub max_sequence_number: i64,
+}
+
+impl From<&ParquetFile> for ReportFile {
+    fn from(f: &ParquetFile) -> Self {
+        Self {
+            id: Some(f.id.get()),
+            object_store_id: f.object_store_id.to_string(),
+            compaction_level: f.compaction_level as i16,
+            file_size_bytes: f.file_size_bytes,
+            row_count: f.row_count,
+            min_time: f.min_time.get(),
+            max_time: f.max_time.get(),
+            max_sequence_number: f.max_sequence_number.get(),
+        }
+    }
+}
+
+impl From<&ParquetFileParams> for ReportFile {
+    fn from(f: &ParquetFileParams) -> Self {
+        Self {
+            id: None,
+            object_store_id: f.object_store_id.to_string(),
+            compaction_level: f.compaction_level as i16,
+            file_size_bytes: f.file_size_bytes,
+            row_count: f.row_count,
+            min_time: f.min_time.get(),
+            max_time: f.max_time.get(),
+            max_sequence_number: f.max_sequence_number.get(),
+        }
+    }
+}
+
+/// Location of a compaction report, next to the Parquet files of its partition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReportPath {
+    namespace_id: NamespaceId,
+    table_id: TableId,
+    shard_id: ShardId,
+    partition_id: PartitionId,
+    object_store_id: Uuid,
+}
+
+impl ReportPath {
+    /// Create report path relevant for the storage layout.
+    pub fn new(
+        namespace_id: NamespaceId,
+        table_id: TableId,
+        shard_id: ShardId,
+        partition_id: PartitionId,
+        object_store_id: Uuid,
+    ) -> Self {
+        Self {
+            namespace_id,
+            table_id,
+            shard_id,
+            partition_id,
+            object_store_id,
+        }
+    }
+
+    /// Get object-store path.
+    pub fn object_store_path(&self) -> Path {
+        let Self {
+            namespace_id,
+            table_id,
+            shard_id,
+            partition_id,
+            object_store_id,
+        } = self;
+
+        Path::from_iter([
+            namespace_id.to_string().as_str(),
+            table_id.to_string().as_str(),
+            shard_id.to_string().as_str(),
+            partition_id.to_string().as_str(),
+            &format!("{}.compaction.json", object_store_id),
+        ])
+    }
+}
+
+/// Write `report` to `path` in the object store.
+pub(crate) async fn write_report(
+    store: &ParquetStorage,
+    path: &ReportPath,
+    report: &CompactionRunReport,
+) -> Result<()> {
+    let json = report.to_json()?;
+    store
+        .object_store()
+        .put(&path.object_store_path(), Bytes::from(json))
+        .await
+        .context(WriteSnafu)
+}
+
+/// Convert a duration measured between two [`Time`]s into seconds.
+pub(crate) fn secs_between(start: Time, end: Time) -> f64 {
+    end.checked_duration_since(start)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_report_path() {
+        let uuid = Uuid::nil();
+        let path = ReportPath::new(
+            NamespaceId::new(1),
+            TableId::new(2),
+            ShardId::new(3),
+            PartitionId::new(4),
+            uuid,
+        );
+        assert_eq!(
+            path.object_store_path().to_string(),
+            format!("1/2/3/4/{}.compaction.json", uuid)
+        );
+    }
+
+    #[test]
+    fn test_secs_between() {
+        let start = Time::from_timestamp_nanos(0);
+        let end = start + Duration::from_millis(1500);
+        assert_eq!(secs_between(start, end), 1.5);
+
+        // clock went backwards
+        assert_eq!(secs_between(end, start), 0.0);
+    }
+}
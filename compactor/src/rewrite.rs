@@ -0,0 +1,512 @@
+//! Rewrite all parquet files of a table, independent of the regular compaction thresholds.
+//!
+//! This is used to migrate existing files after the partition sort key, the table schema (e.g.
+//! deleted columns or [column type migrations](data_types::ColumnTypeMigration)) or the parquet
+//! writer settings changed. Every file is re-encoded on its own and keeps its compaction level, so
+//! the overlap guarantees of the different levels are not affected by a rewrite.
+
+use crate::{
+    compact::Compactor,
+    cost::CostPhase,
+    parquet_file_combining,
+    state::{CompactionKind, CompactionPhase},
+};
+use data_types::{ParquetFile, PartitionId, PartitionParam, ShardId, TableId, Timestamp};
+use observability_deps::tracing::*;
+use snafu::{OptionExt, ResultExt, Snafu};
+use std::sync::Arc;
+
+#[derive(Debug, Snafu)]
+#[allow(missing_copy_implementations, missing_docs)]
+pub enum Error {
+    #[snafu(display("Error querying table {}", source))]
+    QueryingTable {
+        source: iox_catalog::interface::Error,
+    },
+
+    #[snafu(display("Could not find table {:?}", table_id))]
+    TableNotFound { table_id: TableId },
+
+    #[snafu(display("Error listing partitions of table {:?}: {}", table_id, source))]
+    ListingPartitions {
+        table_id: TableId,
+        source: iox_catalog::interface::Error,
+    },
+
+    #[snafu(display("Error gathering partition information: {}", source))]
+    PartitionInfo { source: crate::compact::Error },
+
+    #[snafu(display("Error listing shards: {}", source))]
+    ListingShards { source: crate::compact::Error },
+
+    #[snafu(display("Error listing column type migrations: {}", source))]
+    ListingMigrations {
+        source: iox_catalog::interface::Error,
+    },
+
+    #[snafu(display("Error completing column type migration: {}", source))]
+    CompletingMigration {
+        source: iox_catalog::interface::Error,
+    },
+
+    #[snafu(display("Error listing parquet files of table {:?}: {}", table_id, source))]
+    ListingTableFiles {
+        table_id: TableId,
+        source: iox_catalog::interface::Error,
+    },
+
+    #[snafu(display(
+        "Error listing parquet files of partition {:?}: {}",
+        partition_id,
+        source
+    ))]
+    ListingFiles {
+        partition_id: PartitionId,
+        source: iox_catalog::interface::Error,
+    },
+
+    #[snafu(display(
+        "Error rewriting parquet file of partition {:?}: {}",
+        partition_id,
+        source
+    ))]
+    Rewriting {
+        partition_id: PartitionId,
+        source: parquet_file_combining::Error,
+    },
+}
+
+/// A specialized `Error` for rewrite errors
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// Summary of a [`rewrite_table`] run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RewriteSummary {
+    /// Number of partitions that had at least one file rewritten.
+    pub partitions_rewritten: usize,
+
+    /// Number of parquet files that were rewritten.
+    pub files_rewritten: usize,
+}
+
+/// Rewrite all parquet files of the given table that are not marked for deletion.
+///
+/// Each file is re-encoded using the current partition sort key, the current table schema (columns
+/// that no longer exist in the catalog are dropped) and the current writer settings. The rewritten
+/// file replaces the original one in a single catalog transaction and keeps its compaction level.
+///
+/// Partitions without a sort key are skipped because they have never been persisted.
+pub async fn rewrite_table(compactor: &Compactor, table_id: TableId) -> Result<RewriteSummary> {
+    rewrite_table_files(compactor, table_id, None, |_| true).await
+}
+
+/// Summary of a [`migrate_column_types`] run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MigrationSummary {
+    /// Number of parquet files that were rewritten to the new column types.
+    pub files_rewritten: usize,
+
+    /// Number of column type migrations that were completed.
+    pub migrations_completed: usize,
+}
+
+/// Make progress on all active [column type migrations](data_types::ColumnTypeMigration).
+///
+/// Files of the shards assigned to this compactor that were created before a migration started
+/// are rewritten, which casts the migrated columns to their new type. A migration is completed
+/// once no file of its table that was created before the migration is left (in any shard).
+pub async fn migrate_column_types(compactor: &Compactor) -> Result<MigrationSummary> {
+    let migrations = compactor
+        .cost
+        .catalog(CostPhase::Rewrite)
+        .repositories()
+        .await
+        .columns()
+        .list_active_type_migrations()
+        .await
+        .context(ListingMigrationsSnafu)?;
+    if migrations.is_empty() {
+        return Ok(MigrationSummary::default());
+    }
+
+    let shards = compactor.shards().await.context(ListingShardsSnafu)?;
+
+    let mut summary = MigrationSummary::default();
+    for migration in migrations {
+        let table_id = migration.table_id;
+        let is_outdated = |f: &ParquetFile| f.created_at < migration.created_at;
+
+        let rewrite_summary =
+            rewrite_table_files(compactor, table_id, Some(&shards), is_outdated).await?;
+        summary.files_rewritten += rewrite_summary.files_rewritten;
+
+        let mut repos = compactor
+            .cost
+            .catalog(CostPhase::Rewrite)
+            .repositories()
+            .await;
+        let files_remaining = repos
+            .parquet_files()
+            .list_by_table_not_to_delete(table_id)
+            .await
+            .context(ListingTableFilesSnafu { table_id })?
+            .iter()
+            .filter(|f| is_outdated(f))
+            .count();
+        if files_remaining > 0 {
+            debug!(
+                migration_id=%migration.id,
+                files_remaining,
+                "column type migration still in progress"
+            );
+            continue;
+        }
+
+        let completed_at = Timestamp::new(compactor.time_provider.now().timestamp_nanos());
+        repos
+            .columns()
+            .complete_type_migration(migration.id, completed_at)
+            .await
+            .context(CompletingMigrationSnafu)?;
+        info!(
+            migration_id=%migration.id,
+            ?table_id,
+            column_id=?migration.column_id,
+            "completed column type migration"
+        );
+        summary.migrations_completed += 1;
+    }
+
+    Ok(summary)
+}
+
+/// Rewrite the files of the given table (optionally restricted to the given shards) that are not
+/// marked for deletion and match `filter`.
+async fn rewrite_table_files<F>(
+    compactor: &Compactor,
+    table_id: TableId,
+    shards: Option<&[ShardId]>,
+    filter: F,
+) -> Result<RewriteSummary>
+where
+    F: Fn(&ParquetFile) -> bool,
+{
+    let partitions = {
+        let mut repos = compactor
+            .cost
+            .catalog(CostPhase::Rewrite)
+            .repositories()
+            .await;
+
+        let table = repos
+            .tables()
+            .get_by_id(table_id)
+            .await
+            .context(QueryingTableSnafu)?
+            .context(TableNotFoundSnafu { table_id })?;
+
+        repos
+            .partitions()
+            .list_by_table_id(table_id)
+            .await
+            .context(ListingPartitionsSnafu { table_id })?
+            .into_iter()
+            .filter(|p| shards.map(|s| s.contains(&p.shard_id)).unwrap_or(true))
+            .map(|p| PartitionParam {
+                partition_id: p.id,
+                shard_id: p.shard_id,
+                namespace_id: table.namespace_id,
+                table_id,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let partitions = compactor
+        .add_info_to_partitions(&partitions)
+        .await
+        .context(PartitionInfoSnafu)?;
+
+    let mut summary = RewriteSummary::default();
+    for partition in partitions {
+        let partition_id = partition.id();
+
+        if partition.sort_key.is_none() {
+            debug!(?partition_id, "partition has no sort key, skipping rewrite");
+            continue;
+        }
+
+        let running = compactor.state.start_compaction(
+            partition_id,
+            CompactionKind::Rewrite,
+            CompactionPhase::SelectingFiles,
+        );
+        let files: Vec<_> = compactor
+            .cost
+            .catalog(CostPhase::Rewrite)
+            .repositories()
+            .await
+            .parquet_files()
+            .list_by_partition_not_to_delete(partition_id)
+            .await
+            .context(ListingFilesSnafu { partition_id })?
+            .into_iter()
+            .filter(|f| filter(f))
+            .collect();
+        if files.is_empty() {
+            continue;
+        }
+
+        let n_files = files.len();
+        running.set_phase(CompactionPhase::Compacting);
+        for file in files {
+            let target_level = file.compaction_level;
+
+            parquet_file_combining::compact_parquet_files(
+                vec![file],
+                partition.clone(),
+                compactor.cost.catalog(CostPhase::Rewrite),
+                compactor.cost.store(CostPhase::Rewrite),
+                Arc::clone(&compactor.cold_exec),
+                compactor.spill_dir.as_deref(),
+                Arc::clone(&compactor.time_provider),
+                &compactor.compaction_input_file_bytes,
+                compactor.config.max_desired_file_size_bytes(),
+                compactor.config.percentage_max_file_size(),
+                compactor.config.split_percentage(),
+                compactor.config.split_interval(),
+                target_level,
+                compactor.report_config(),
+            )
+            .await
+            .context(RewritingSnafu { partition_id })?;
+        }
+
+        info!(?partition_id, n_files, "rewrote partition");
+        summary.partitions_rewritten += 1;
+        summary.files_rewritten += n_files;
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handler::{CompactorConfig, SchedulerConfig};
+    use backoff::BackoffConfig;
+    use data_types::{ColumnType, CompactionLevel};
+    use iox_query::exec::Executor;
+    use iox_tests::util::{TestCatalog, TestParquetFileBuilder};
+    use iox_time::SystemProvider;
+    use parquet_file::storage::ParquetStorage;
+    use schema::sort::SortKey;
+    use test_helpers::assert_error;
+
+    fn make_compactor(catalog: &TestCatalog, shards: Vec<data_types::ShardId>) -> Compactor {
+        let config = CompactorConfig::new(
+            100 * 1024 * 1024, // max_desired_file_size_bytes
+            30,                // percentage_max_file_size
+            80,                // split_percentage
+            90_000,            // max_cold_concurrent_size_bytes
+            1,                 // max_number_partitions_per_shard
+            1,                 // min_number_recent_ingested_files_per_partition
+            600 * 1024 * 1024, // cold_input_size_threshold_bytes
+            100,               // cold_input_file_count_threshold
+            4,                 // hot_multiple
+            100_000_000,       // memory_budget_bytes
+            false,             // write_compaction_reports
+            SchedulerConfig::default(),
+            SchedulerConfig::default(),
+        );
+
+        Compactor::new(
+            shards,
+            Arc::clone(&catalog.catalog),
+            ParquetStorage::new(Arc::clone(&catalog.object_store)),
+            Arc::new(Executor::new(1)),
+            Arc::new(SystemProvider::new()),
+            BackoffConfig::default(),
+            config,
+            Arc::new(metric::Registry::new()),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_rewrite_table() {
+        test_helpers::maybe_start_logging();
+
+        let catalog = TestCatalog::new();
+        let ns = catalog.create_namespace("ns").await;
+        let shard = ns.create_shard(1).await;
+        let table = ns.create_table("table").await;
+        table.create_column("field_int", ColumnType::I64).await;
+        table.create_column("tag1", ColumnType::Tag).await;
+        table.create_column("tag2", ColumnType::Tag).await;
+        table.create_column("time", ColumnType::Time).await;
+
+        let partition = table
+            .with_shard(&shard)
+            .create_partition("2022-07-13")
+            .await
+            .update_sort_key(SortKey::from_columns(["tag1", "tag2", "time"]))
+            .await;
+
+        // a partition that was never persisted is skipped
+        table
+            .with_shard(&shard)
+            .create_partition("2022-07-14")
+            .await;
+
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol(
+                "table,tag1=WA,tag2=a field_int=1i 10\ntable,tag1=VT,tag2=b field_int=2i 20",
+            )
+            .with_max_seq(1);
+        let level_0 = partition.create_parquet_file(builder).await.parquet_file;
+
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol("table,tag1=OR,tag2=c field_int=3i 30")
+            .with_max_seq(2)
+            .with_compaction_level(CompactionLevel::FileNonOverlapped);
+        let level_1 = partition.create_parquet_file(builder).await.parquet_file;
+
+        // change the sort key, existing files are now sorted by an outdated key
+        let partition = partition
+            .update_sort_key(SortKey::from_columns(["tag2", "tag1", "time"]))
+            .await;
+
+        let compactor = make_compactor(&catalog, vec![shard.shard.id]);
+        let summary = rewrite_table(&compactor, table.table.id).await.unwrap();
+        assert_eq!(
+            summary,
+            RewriteSummary {
+                partitions_rewritten: 1,
+                files_rewritten: 2,
+            }
+        );
+
+        let mut files = catalog
+            .catalog
+            .repositories()
+            .await
+            .parquet_files()
+            .list_by_partition_not_to_delete(partition.partition.id)
+            .await
+            .unwrap();
+        files.sort_by_key(|f| f.max_sequence_number);
+        assert_eq!(files.len(), 2);
+
+        // original files are replaced but keep their level and data range
+        for (new, old) in files.iter().zip([&level_0, &level_1]) {
+            assert_ne!(new.id, old.id);
+            assert_ne!(new.object_store_id, old.object_store_id);
+            assert_eq!(new.compaction_level, old.compaction_level);
+            assert_eq!(new.max_sequence_number, old.max_sequence_number);
+            assert_eq!(new.min_time, old.min_time);
+            assert_eq!(new.max_time, old.max_time);
+            assert_eq!(new.row_count, old.row_count);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_migrate_column_types() {
+        test_helpers::maybe_start_logging();
+
+        let catalog = TestCatalog::new();
+        let ns = catalog.create_namespace("ns").await;
+        let shard1 = ns.create_shard(1).await;
+        let shard2 = ns.create_shard(2).await;
+        let table = ns.create_table("table").await;
+        let field = table.create_column("field_int", ColumnType::I64).await;
+        table.create_column("tag1", ColumnType::Tag).await;
+        table.create_column("time", ColumnType::Time).await;
+
+        let partition1 = table
+            .with_shard(&shard1)
+            .create_partition("2022-07-13")
+            .await
+            .update_sort_key(SortKey::from_columns(["tag1", "time"]))
+            .await;
+        let partition2 = table
+            .with_shard(&shard2)
+            .create_partition("2022-07-13")
+            .await
+            .update_sort_key(SortKey::from_columns(["tag1", "time"]))
+            .await;
+
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol("table,tag1=WA field_int=1i 10\ntable,tag1=VT field_int=2i 20")
+            .with_max_seq(1);
+        partition1.create_parquet_file(builder).await;
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol("table,tag1=OR field_int=3i 30")
+            .with_max_seq(2);
+        partition2.create_parquet_file(builder).await;
+
+        // nothing to do without migrations
+        let compactor1 = make_compactor(&catalog, vec![shard1.shard.id]);
+        let summary = migrate_column_types(&compactor1).await.unwrap();
+        assert_eq!(summary, MigrationSummary::default());
+
+        let migration = catalog
+            .catalog
+            .repositories()
+            .await
+            .columns()
+            .start_type_migration(field.column.id, ColumnType::F64, Timestamp::new(10))
+            .await
+            .unwrap();
+
+        // only files of the assigned shards are rewritten, the migration stays active until all
+        // shards are done
+        let summary = migrate_column_types(&compactor1).await.unwrap();
+        assert_eq!(
+            summary,
+            MigrationSummary {
+                files_rewritten: 1,
+                migrations_completed: 0,
+            }
+        );
+        let summary = migrate_column_types(&compactor1).await.unwrap();
+        assert_eq!(summary, MigrationSummary::default());
+
+        let compactor2 = make_compactor(&catalog, vec![shard2.shard.id]);
+        let summary = migrate_column_types(&compactor2).await.unwrap();
+        assert_eq!(
+            summary,
+            MigrationSummary {
+                files_rewritten: 1,
+                migrations_completed: 1,
+            }
+        );
+
+        let mut repos = catalog.catalog.repositories().await;
+        let migrations = repos
+            .columns()
+            .list_type_migrations_by_table_id(table.table.id)
+            .await
+            .unwrap();
+        assert_eq!(migrations.len(), 1);
+        assert_eq!(migrations[0].id, migration.id);
+        assert!(!migrations[0].is_active());
+
+        let files = repos
+            .parquet_files()
+            .list_by_table_not_to_delete(table.table.id)
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files.iter().all(|f| f.created_at > migration.created_at));
+    }
+
+    #[tokio::test]
+    async fn test_rewrite_table_not_found() {
+        let catalog = TestCatalog::new();
+        let ns = catalog.create_namespace("ns").await;
+        let shard = ns.create_shard(1).await;
+
+        let compactor = make_compactor(&catalog, vec![shard.shard.id]);
+        let res = rewrite_table(&compactor, TableId::new(42)).await;
+        assert_error!(res, Error::TableNotFound { .. });
+    }
+}
@@ -2,23 +2,32 @@
 
 use std::sync::Arc;
 
-use crate::handler::CompactorHandler;
+use self::grpc::GrpcDelegate;
+use crate::{handler::CompactorHandler, state::StateSnapshot};
 use std::fmt::Debug;
 
+pub mod grpc;
+
 /// The [`CompactorServer`] manages the lifecycle and contains all state for a
 /// `compactor` server instance.
 #[derive(Debug, Default)]
 pub struct CompactorServer<C: CompactorHandler> {
     metrics: Arc<metric::Registry>,
 
+    grpc: GrpcDelegate<C>,
+
     handler: Arc<C>,
 }
 
 impl<C: CompactorHandler> CompactorServer<C> {
-    /// Initialise a new [`CompactorServer`] using the provided HTTP and gRPC
+    /// Initialise a new [`CompactorServer`] using the provided gRPC
     /// handlers.
-    pub fn new(metrics: Arc<metric::Registry>, handler: Arc<C>) -> Self {
-        Self { metrics, handler }
+    pub fn new(metrics: Arc<metric::Registry>, grpc: GrpcDelegate<C>, handler: Arc<C>) -> Self {
+        Self {
+            metrics,
+            grpc,
+            handler,
+        }
     }
 
     /// Return the [`metric::Registry`] used by the router.
@@ -26,6 +35,11 @@ impl<C: CompactorHandler> CompactorServer<C> {
         Arc::clone(&self.metrics)
     }
 
+    /// Current activity of the compactor.
+    pub fn state(&self) -> StateSnapshot {
+        self.handler.state()
+    }
+
     /// Join shutdown worker.
     pub async fn join(&self) {
         self.handler.join().await;
@@ -36,3 +50,10 @@ impl<C: CompactorHandler> CompactorServer<C> {
         self.handler.shutdown();
     }
 }
+
+impl<C: CompactorHandler + Debug> CompactorServer<C> {
+    /// Get a reference to the compactor grpc delegate.
+    pub fn grpc(&self) -> &GrpcDelegate<C> {
+        &self.grpc
+    }
+}
@@ -0,0 +1,299 @@
+//! gRPC service implementations for `compactor`.
+
+use crate::{
+    handler::{self, CompactorHandler},
+    rewrite,
+    state::{CompactionKind, CompactionPhase, StateSnapshot},
+};
+use data_types::{PartitionId, TableId};
+use generated_types::influxdata::iox::compactor::v1::{
+    self as proto,
+    compaction_service_server::{CompactionService, CompactionServiceServer},
+};
+use std::sync::Arc;
+use tonic::{Request, Response};
+
+/// This type is responsible for managing all gRPC services exposed by
+/// `compactor`.
+#[derive(Debug, Default)]
+pub struct GrpcDelegate<C: CompactorHandler> {
+    compactor_handler: Arc<C>,
+}
+
+impl<C: CompactorHandler + 'static> GrpcDelegate<C> {
+    /// Initialise a new [`GrpcDelegate`] passing valid requests to the
+    /// specified `compactor_handler`.
+    pub fn new(compactor_handler: Arc<C>) -> Self {
+        Self { compactor_handler }
+    }
+
+    /// Acquire a Compaction gRPC service implementation.
+    pub fn compaction_service(&self) -> CompactionServiceServer<impl CompactionService> {
+        CompactionServiceServer::new(CompactionServiceImpl::new(
+            Arc::clone(&self.compactor_handler) as _,
+        ))
+    }
+}
+
+/// Implementation of the compaction service
+struct CompactionServiceImpl {
+    handler: Arc<dyn CompactorHandler + 'static>,
+}
+
+impl CompactionServiceImpl {
+    pub fn new(handler: Arc<dyn CompactorHandler + 'static>) -> Self {
+        Self { handler }
+    }
+}
+
+#[tonic::async_trait]
+impl CompactionService for CompactionServiceImpl {
+    async fn rewrite_table(
+        &self,
+        request: Request<proto::RewriteTableRequest>,
+    ) -> Result<Response<proto::RewriteTableResponse>, tonic::Status> {
+        let proto::RewriteTableRequest { table_id } = request.into_inner();
+
+        let summary = self
+            .handler
+            .rewrite_table(TableId::new(table_id))
+            .await
+            .map_err(|e| match e {
+                handler::Error::Rewrite(rewrite::Error::TableNotFound { .. }) => {
+                    tonic::Status::not_found(e.to_string())
+                }
+                e => tonic::Status::internal(e.to_string()),
+            })?;
+
+        Ok(Response::new(proto::RewriteTableResponse {
+            partitions_rewritten: summary.partitions_rewritten as i64,
+            files_rewritten: summary.files_rewritten as i64,
+        }))
+    }
+
+    async fn compact_partition(
+        &self,
+        request: Request<proto::CompactPartitionRequest>,
+    ) -> Result<Response<proto::CompactPartitionResponse>, tonic::Status> {
+        let proto::CompactPartitionRequest { partition_id } = request.into_inner();
+
+        self.handler
+            .compact_partition(PartitionId::new(partition_id))
+            .await
+            .map_err(|e| match e {
+                handler::Error::PartitionNotFound(_) | handler::Error::TableNotFound(_) => {
+                    tonic::Status::not_found(e.to_string())
+                }
+                handler::Error::ShardNotAssigned(_) => {
+                    tonic::Status::failed_precondition(e.to_string())
+                }
+                e => tonic::Status::internal(e.to_string()),
+            })?;
+
+        Ok(Response::new(proto::CompactPartitionResponse {}))
+    }
+
+    async fn get_state(
+        &self,
+        _request: Request<proto::GetStateRequest>,
+    ) -> Result<Response<proto::GetStateResponse>, tonic::Status> {
+        Ok(Response::new(state_to_proto(self.handler.state())))
+    }
+}
+
+fn state_to_proto(state: StateSnapshot) -> proto::GetStateResponse {
+    let StateSnapshot {
+        running,
+        last_cycles,
+    } = state;
+
+    proto::GetStateResponse {
+        running: running
+            .into_iter()
+            .map(|c| proto::RunningCompaction {
+                partition_id: c.partition_id,
+                kind: kind_to_proto(c.kind).into(),
+                phase: match c.phase {
+                    CompactionPhase::SelectingFiles => "selecting_files",
+                    CompactionPhase::Compacting => "compacting",
+                }
+                .to_string(),
+                elapsed_secs: c.elapsed_secs,
+            })
+            .collect(),
+        last_cycles: last_cycles
+            .into_iter()
+            .map(|c| proto::CompactionCycle {
+                kind: kind_to_proto(c.kind).into(),
+                started_at: c.started_at,
+                finished_at: c.finished_at,
+                candidates_per_shard: c
+                    .candidates_per_shard
+                    .into_iter()
+                    .map(|(shard_id, n)| (shard_id, n as u64))
+                    .collect(),
+                compacted_partitions: c.compacted_partitions.map(|n| n as u64),
+                skipped_partitions: c
+                    .skipped_partitions
+                    .into_iter()
+                    .map(|p| proto::SkippedPartition {
+                        partition_id: p.partition_id,
+                        reason: p.reason,
+                    })
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+fn kind_to_proto(kind: CompactionKind) -> proto::CompactionKind {
+    match kind {
+        CompactionKind::Hot => proto::CompactionKind::Hot,
+        CompactionKind::Cold => proto::CompactionKind::Cold,
+        CompactionKind::Rewrite => proto::CompactionKind::Rewrite,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        rewrite::RewriteSummary,
+        state::{CycleSummary, RunningCompactionSnapshot, SkippedPartition},
+    };
+    use async_trait::async_trait;
+    use std::collections::{BTreeMap, HashMap};
+
+    #[derive(Debug)]
+    struct MockCompactorHandler;
+
+    #[async_trait]
+    impl CompactorHandler for MockCompactorHandler {
+        async fn rewrite_table(&self, table_id: TableId) -> Result<RewriteSummary, handler::Error> {
+            if table_id.get() == 1 {
+                Ok(RewriteSummary {
+                    partitions_rewritten: 2,
+                    files_rewritten: 3,
+                })
+            } else {
+                Err(rewrite::Error::TableNotFound { table_id }.into())
+            }
+        }
+
+        async fn compact_partition(&self, partition_id: PartitionId) -> Result<(), handler::Error> {
+            match partition_id.get() {
+                1 => Ok(()),
+                2 => Err(handler::Error::ShardNotAssigned(partition_id)),
+                _ => Err(handler::Error::PartitionNotFound(partition_id)),
+            }
+        }
+
+        fn state(&self) -> StateSnapshot {
+            StateSnapshot {
+                running: vec![RunningCompactionSnapshot {
+                    partition_id: 1,
+                    kind: CompactionKind::Cold,
+                    phase: CompactionPhase::Compacting,
+                    elapsed_secs: 1.5,
+                }],
+                last_cycles: vec![CycleSummary {
+                    kind: CompactionKind::Hot,
+                    started_at: "1970-01-01T00:00:00+00:00".to_string(),
+                    finished_at: None,
+                    candidates_per_shard: BTreeMap::from([(1, 2)]),
+                    compacted_partitions: None,
+                    skipped_partitions: vec![SkippedPartition {
+                        partition_id: 3,
+                        reason: "over budget".to_string(),
+                    }],
+                }],
+            }
+        }
+
+        async fn join(&self) {}
+
+        fn shutdown(&self) {}
+    }
+
+    #[tokio::test]
+    async fn test_rewrite_table() {
+        let service = CompactionServiceImpl::new(Arc::new(MockCompactorHandler));
+
+        let response = service
+            .rewrite_table(Request::new(proto::RewriteTableRequest { table_id: 1 }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(
+            response,
+            proto::RewriteTableResponse {
+                partitions_rewritten: 2,
+                files_rewritten: 3,
+            }
+        );
+
+        let status = service
+            .rewrite_table(Request::new(proto::RewriteTableRequest { table_id: 2 }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_compact_partition() {
+        let service = CompactionServiceImpl::new(Arc::new(MockCompactorHandler));
+
+        service
+            .compact_partition(Request::new(proto::CompactPartitionRequest {
+                partition_id: 1,
+            }))
+            .await
+            .unwrap();
+
+        for (partition_id, code) in [
+            (2, tonic::Code::FailedPrecondition),
+            (3, tonic::Code::NotFound),
+        ] {
+            let status = service
+                .compact_partition(Request::new(proto::CompactPartitionRequest {
+                    partition_id,
+                }))
+                .await
+                .unwrap_err();
+            assert_eq!(status.code(), code);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_state() {
+        let service = CompactionServiceImpl::new(Arc::new(MockCompactorHandler));
+
+        let response = service
+            .get_state(Request::new(proto::GetStateRequest {}))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(
+            response,
+            proto::GetStateResponse {
+                running: vec![proto::RunningCompaction {
+                    partition_id: 1,
+                    kind: proto::CompactionKind::Cold.into(),
+                    phase: "compacting".to_string(),
+                    elapsed_secs: 1.5,
+                }],
+                last_cycles: vec![proto::CompactionCycle {
+                    kind: proto::CompactionKind::Hot.into(),
+                    started_at: "1970-01-01T00:00:00+00:00".to_string(),
+                    finished_at: None,
+                    candidates_per_shard: HashMap::from([(1, 2)]),
+                    compacted_partitions: None,
+                    skipped_partitions: vec![proto::SkippedPartition {
+                        partition_id: 3,
+                        reason: "over budget".to_string(),
+                    }],
+                }],
+            }
+        );
+    }
+}
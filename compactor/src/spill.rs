@@ -0,0 +1,227 @@
+//! Temporary disk space for compactions.
+//!
+//! Sorts of large compactions may not fit into the memory pool of the executor, in which case
+//! DataFusion spills them to disk. With a [`SpillDir`] every compaction writes its spill files
+//! into a directory of its own below a configured root directory. That directory is removed once
+//! the compaction is done, whether it succeeded or not, and directories left behind by a crashed
+//! compactor are removed on startup. A compaction whose spill files grow beyond the
+//! per-compaction quota is cancelled.
+
+use metric::{Metric, U64Counter, U64Histogram, U64HistogramOptions};
+use observability_deps::tracing::*;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use uuid::Uuid;
+
+/// Prefix of the directories of the individual compactions.
+const JOB_DIR_PREFIX: &str = "compaction-";
+
+/// How often the size of the spill files of a running compaction is checked.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Root directory for the spill files of compactions, see the [module docs](self).
+#[derive(Debug)]
+pub struct SpillDir {
+    root: PathBuf,
+    quota_bytes: u64,
+
+    /// Peak size of the spill files of every compaction that spilled
+    spill_bytes: U64Histogram,
+
+    /// Number of compactions cancelled because they exceeded the quota
+    quota_exceeded: U64Counter,
+}
+
+impl SpillDir {
+    /// Write spill files below `root`, allowing every compaction up to `quota_bytes`.
+    ///
+    /// `root` is created if it doesn't exist. Compaction directories left behind by a previous
+    /// run are removed, so `root` must not be shared between compactors.
+    pub fn new(
+        root: impl Into<PathBuf>,
+        quota_bytes: u64,
+        registry: &metric::Registry,
+    ) -> io::Result<Self> {
+        let root = root.into();
+        fs::create_dir_all(&root)?;
+
+        let mut removed = 0;
+        for entry in fs::read_dir(&root)? {
+            let entry = entry?;
+            if entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with(JOB_DIR_PREFIX)
+            {
+                fs::remove_dir_all(entry.path())?;
+                removed += 1;
+            }
+        }
+        if removed > 0 {
+            info!(?root, removed, "removed stale compaction spill directories");
+        }
+
+        let spill_bytes: Metric<U64Histogram> = registry.register_metric_with_options(
+            "compactor_spill_bytes",
+            "Peak size of the spill files of compactions that spilled to disk",
+            || {
+                U64HistogramOptions::new([
+                    100 * 1024 * 1024,        // 100 MB
+                    1024 * 1024 * 1024,       // 1 GB
+                    10 * 1024 * 1024 * 1024,  // 10 GB
+                    100 * 1024 * 1024 * 1024, // 100 GB
+                    u64::MAX,                 // Inf
+                ])
+            },
+        );
+        let quota_exceeded = registry.register_metric::<U64Counter>(
+            "compactor_spill_quota_exceeded",
+            "Number of compactions cancelled because their spill files exceeded the quota",
+        );
+
+        Ok(Self {
+            root,
+            quota_bytes,
+            spill_bytes: spill_bytes.recorder(&[]),
+            quota_exceeded: quota_exceeded.recorder(&[]),
+        })
+    }
+
+    /// Maximum size of the spill files of a single compaction.
+    pub fn quota_bytes(&self) -> u64 {
+        self.quota_bytes
+    }
+
+    /// Create the spill directory of a new compaction.
+    pub(crate) fn job(&self) -> io::Result<SpillJob<'_>> {
+        let path = self
+            .root
+            .join(format!("{JOB_DIR_PREFIX}{}", Uuid::new_v4()));
+        fs::create_dir(&path)?;
+
+        Ok(SpillJob {
+            dir: self,
+            path,
+            peak_bytes: AtomicU64::new(0),
+        })
+    }
+}
+
+/// Spill directory of a single compaction, removed on drop.
+#[derive(Debug)]
+pub(crate) struct SpillJob<'a> {
+    dir: &'a SpillDir,
+    path: PathBuf,
+    peak_bytes: AtomicU64,
+}
+
+impl SpillJob<'_> {
+    /// Directory for the spill files of this compaction.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Resolves once the spill files of this compaction exceed the quota, returning their size.
+    pub(crate) async fn quota_exceeded(&self) -> u64 {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            // files come and go while this runs, just try again later
+            let bytes = match dir_size(&self.path) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    debug!(path=?self.path, %e, "could not determine size of spill files");
+                    continue;
+                }
+            };
+            self.peak_bytes.fetch_max(bytes, Ordering::Relaxed);
+
+            if bytes > self.dir.quota_bytes {
+                self.dir.quota_exceeded.inc(1);
+                return bytes;
+            }
+        }
+    }
+}
+
+impl Drop for SpillJob<'_> {
+    fn drop(&mut self) {
+        let peak_bytes = self.peak_bytes.load(Ordering::Relaxed);
+        if peak_bytes > 0 {
+            self.dir.spill_bytes.record(peak_bytes);
+        }
+
+        if let Err(e) = fs::remove_dir_all(&self.path) {
+            warn!(path=?self.path, %e, "could not remove compaction spill directory");
+        }
+    }
+}
+
+/// Total size of the files in `path` and its subdirectories.
+fn dir_size(path: &Path) -> io::Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metric::Attributes;
+
+    #[tokio::test]
+    async fn test_spill_dir() {
+        let root = test_helpers::tmp_dir().unwrap();
+        let registry = metric::Registry::new();
+
+        // left behind by a crashed compactor
+        let stale = root.path().join(format!("{JOB_DIR_PREFIX}stale"));
+        fs::create_dir(&stale).unwrap();
+        fs::write(stale.join("spill"), b"data").unwrap();
+        let unrelated = root.path().join("unrelated");
+        fs::write(&unrelated, b"data").unwrap();
+
+        let dir = SpillDir::new(root.path(), 10, &registry).unwrap();
+        assert!(!stale.exists());
+        assert!(unrelated.exists());
+
+        let job = dir.job().unwrap();
+        let path = job.path().to_owned();
+        fs::create_dir(path.join("datafusion")).unwrap();
+        fs::write(path.join("datafusion").join("spill"), [0; 11]).unwrap();
+        assert_eq!(job.quota_exceeded().await, 11);
+
+        drop(job);
+        assert!(!path.exists());
+
+        let spill_bytes = registry
+            .get_instrument::<Metric<U64Histogram>>("compactor_spill_bytes")
+            .unwrap()
+            .get_observer(&Attributes::from(&[]))
+            .unwrap()
+            .fetch();
+        assert_eq!(spill_bytes.sample_count(), 1);
+        assert_eq!(spill_bytes.total, 11);
+
+        let quota_exceeded = registry
+            .get_instrument::<Metric<U64Counter>>("compactor_spill_quota_exceeded")
+            .unwrap()
+            .get_observer(&Attributes::from(&[]))
+            .unwrap()
+            .fetch();
+        assert_eq!(quota_exceeded, 1);
+    }
+}
@@ -0,0 +1,319 @@
+//! Current activity of a compactor.
+//!
+//! The [`CompactorState`] tracks the compactions that are currently running and summarizes the
+//! last hot and cold compaction cycles. A [`StateSnapshot`] of it is served as JSON by the
+//! compactor's HTTP `/state` endpoint, so that operators can see what a compactor is doing without
+//! digging through logs and aggregate metrics.
+
+use data_types::{PartitionId, ShardId};
+use iox_time::{Time, TimeProvider};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// What kind of compaction is running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompactionKind {
+    /// Compaction of a hot partition
+    Hot,
+
+    /// Compaction of a cold partition
+    Cold,
+
+    /// Rewrite of files, e.g. for a column type migration
+    Rewrite,
+}
+
+/// Phase of a running compaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompactionPhase {
+    /// Listing and selecting the files to compact
+    SelectingFiles,
+
+    /// Reading, compacting and writing the files
+    Compacting,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct RunningCompaction {
+    partition_id: PartitionId,
+    kind: CompactionKind,
+    phase: CompactionPhase,
+    started_at: Time,
+}
+
+/// Activity of a compactor, see the [module documentation](self).
+#[derive(Debug)]
+pub struct CompactorState {
+    time_provider: Arc<dyn TimeProvider>,
+    next_id: AtomicU64,
+    running: Mutex<BTreeMap<u64, RunningCompaction>>,
+    cycles: Mutex<BTreeMap<CompactionKind, CycleSummary>>,
+}
+
+impl CompactorState {
+    /// Create state without any activity.
+    pub fn new(time_provider: Arc<dyn TimeProvider>) -> Self {
+        Self {
+            time_provider,
+            next_id: AtomicU64::new(0),
+            running: Default::default(),
+            cycles: Default::default(),
+        }
+    }
+
+    /// Record the start of a compaction of `partition_id`. The compaction is running until the
+    /// returned guard is dropped.
+    pub fn start_compaction(
+        &self,
+        partition_id: PartitionId,
+        kind: CompactionKind,
+        phase: CompactionPhase,
+    ) -> RunningCompactionGuard<'_> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.running.lock().insert(
+            id,
+            RunningCompaction {
+                partition_id,
+                kind,
+                phase,
+                started_at: self.time_provider.now(),
+            },
+        );
+
+        RunningCompactionGuard { state: self, id }
+    }
+
+    /// Record the start of a compaction cycle of `kind` with the given candidates per shard.
+    /// This replaces the summary of the previous cycle of the same kind.
+    pub fn start_cycle(&self, kind: CompactionKind, candidates: impl IntoIterator<Item = ShardId>) {
+        let mut candidates_per_shard = BTreeMap::new();
+        for shard_id in candidates {
+            *candidates_per_shard.entry(shard_id.get()).or_default() += 1;
+        }
+
+        self.cycles.lock().insert(
+            kind,
+            CycleSummary {
+                kind,
+                started_at: self.time_provider.now().to_rfc3339(),
+                finished_at: None,
+                candidates_per_shard,
+                compacted_partitions: None,
+                skipped_partitions: vec![],
+            },
+        );
+    }
+
+    /// Record that `partition_id` is skipped in the current cycle of `kind`.
+    pub fn record_skipped(
+        &self,
+        kind: CompactionKind,
+        partition_id: PartitionId,
+        reason: impl Into<String>,
+    ) {
+        if let Some(cycle) = self.cycles.lock().get_mut(&kind) {
+            cycle.skipped_partitions.push(SkippedPartition {
+                partition_id: partition_id.get(),
+                reason: reason.into(),
+            });
+        }
+    }
+
+    /// Record the end of the current cycle of `kind`.
+    pub fn finish_cycle(&self, kind: CompactionKind, compacted_partitions: usize) {
+        if let Some(cycle) = self.cycles.lock().get_mut(&kind) {
+            cycle.finished_at = Some(self.time_provider.now().to_rfc3339());
+            cycle.compacted_partitions = Some(compacted_partitions);
+        }
+    }
+
+    /// The current activity.
+    pub fn snapshot(&self) -> StateSnapshot {
+        let now = self.time_provider.now();
+
+        let running = self
+            .running
+            .lock()
+            .values()
+            .map(|c| RunningCompactionSnapshot {
+                partition_id: c.partition_id.get(),
+                kind: c.kind,
+                phase: c.phase,
+                elapsed_secs: now
+                    .checked_duration_since(c.started_at)
+                    .unwrap_or_default()
+                    .as_secs_f64(),
+            })
+            .collect();
+        let last_cycles = self.cycles.lock().values().cloned().collect();
+
+        StateSnapshot {
+            running,
+            last_cycles,
+        }
+    }
+}
+
+/// A running compaction, see [`CompactorState::start_compaction`].
+#[derive(Debug)]
+pub struct RunningCompactionGuard<'a> {
+    state: &'a CompactorState,
+    id: u64,
+}
+
+impl RunningCompactionGuard<'_> {
+    /// Record that the compaction moved on to `phase`.
+    pub fn set_phase(&self, phase: CompactionPhase) {
+        if let Some(c) = self.state.running.lock().get_mut(&self.id) {
+            c.phase = phase;
+        }
+    }
+}
+
+impl Drop for RunningCompactionGuard<'_> {
+    fn drop(&mut self) {
+        self.state.running.lock().remove(&self.id);
+    }
+}
+
+/// The activity of a compactor at one point in time.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StateSnapshot {
+    /// Compactions that are currently running, in the order they were started.
+    pub running: Vec<RunningCompactionSnapshot>,
+
+    /// The last cycle of every kind of compaction, which may still be running.
+    pub last_cycles: Vec<CycleSummary>,
+}
+
+/// A compaction that is currently running.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunningCompactionSnapshot {
+    /// The compacted partition.
+    pub partition_id: i64,
+
+    /// Kind of compaction.
+    pub kind: CompactionKind,
+
+    /// Current phase.
+    pub phase: CompactionPhase,
+
+    /// Time since the compaction started, in seconds.
+    pub elapsed_secs: f64,
+}
+
+/// Summary of a compaction cycle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CycleSummary {
+    /// Kind of compaction.
+    pub kind: CompactionKind,
+
+    /// Start of the cycle (RFC 3339).
+    pub started_at: String,
+
+    /// End of the cycle (RFC 3339), `None` while the cycle is running.
+    pub finished_at: Option<String>,
+
+    /// Number of compaction candidates per shard ID.
+    pub candidates_per_shard: BTreeMap<i64, usize>,
+
+    /// Number of compacted partitions, `None` while the cycle is running.
+    pub compacted_partitions: Option<usize>,
+
+    /// Candidates that were not compacted.
+    pub skipped_partitions: Vec<SkippedPartition>,
+}
+
+/// A compaction candidate that was not compacted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkippedPartition {
+    /// The skipped partition.
+    pub partition_id: i64,
+
+    /// Why it was skipped.
+    pub reason: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iox_time::MockProvider;
+    use std::time::Duration;
+
+    #[test]
+    fn test_state() {
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let state = CompactorState::new(Arc::clone(&time_provider) as _);
+        assert_eq!(
+            state.snapshot(),
+            StateSnapshot {
+                running: vec![],
+                last_cycles: vec![],
+            }
+        );
+
+        state.start_cycle(
+            CompactionKind::Hot,
+            [ShardId::new(1), ShardId::new(2), ShardId::new(1)],
+        );
+        state.record_skipped(CompactionKind::Hot, PartitionId::new(3), "over budget");
+        let guard = state.start_compaction(
+            PartitionId::new(4),
+            CompactionKind::Hot,
+            CompactionPhase::SelectingFiles,
+        );
+        time_provider.inc(Duration::from_secs(2));
+        guard.set_phase(CompactionPhase::Compacting);
+
+        let snapshot = state.snapshot();
+        assert_eq!(
+            snapshot.running,
+            vec![RunningCompactionSnapshot {
+                partition_id: 4,
+                kind: CompactionKind::Hot,
+                phase: CompactionPhase::Compacting,
+                elapsed_secs: 2.0,
+            }]
+        );
+        assert_eq!(
+            snapshot.last_cycles,
+            vec![CycleSummary {
+                kind: CompactionKind::Hot,
+                started_at: "1970-01-01T00:00:00+00:00".to_string(),
+                finished_at: None,
+                candidates_per_shard: BTreeMap::from([(1, 2), (2, 1)]),
+                compacted_partitions: None,
+                skipped_partitions: vec![SkippedPartition {
+                    partition_id: 3,
+                    reason: "over budget".to_string(),
+                }],
+            }]
+        );
+
+        drop(guard);
+        state.finish_cycle(CompactionKind::Hot, 1);
+
+        let snapshot = state.snapshot();
+        assert!(snapshot.running.is_empty());
+        assert_eq!(
+            snapshot.last_cycles[0].finished_at.as_deref(),
+            Some("1970-01-01T00:00:02+00:00")
+        );
+        assert_eq!(snapshot.last_cycles[0].compacted_partitions, Some(1));
+
+        // a new cycle replaces the summary of the last one
+        state.start_cycle(CompactionKind::Hot, []);
+        let snapshot = state.snapshot();
+        assert_eq!(snapshot.last_cycles.len(), 1);
+        assert!(snapshot.last_cycles[0].skipped_partitions.is_empty());
+    }
+}
@@ -1,13 +1,17 @@
 //! Helpers of the Compactor
 
 use crate::query::QueryableParquetChunk;
-use data_types::{ParquetFile, ParquetFileId, TableSchema, Timestamp, Tombstone, TombstoneId};
+use data_types::{
+    CompactionLevel, ParquetFile, ParquetFileId, TableSchema, Timestamp, Tombstone, TombstoneId,
+};
 use observability_deps::tracing::*;
 use parquet_file::{chunk::ParquetChunk, storage::ParquetStorage};
 use schema::{sort::SortKey, Schema};
+use sha2::{Digest, Sha256};
 use std::{
     collections::{BTreeMap, HashSet},
     sync::Arc,
+    time::Duration,
 };
 
 /// Wrapper of group of parquet files with their min time and total size
@@ -210,10 +214,141 @@ pub(crate) fn compute_split_time(
     split_times
 }
 
+/// Return the split times that separate the time range [min_time, max_time] at multiples of
+/// `interval` since the epoch, e.g. at UTC day boundaries for an interval of one day.
+///
+/// A split plan puts rows up to and including a split time into the earlier file, so the split
+/// times are the last nanosecond before every boundary within the time range.
+/// Example:
+///  . Input
+///      min_time = 5
+///      max_time = 25
+///      interval = 10ns
+///
+///  . Output = [9, 19], which results in files covering [5, 9], [10, 19] and [20, 25]
+pub(crate) fn compute_aligned_split_times(
+    min_time: i64,
+    max_time: i64,
+    interval: Duration,
+) -> Vec<i64> {
+    let interval = i64::try_from(interval.as_nanos())
+        .unwrap_or(i64::MAX)
+        .max(1);
+
+    let mut split_times = vec![];
+    // first boundary after min_time
+    let mut boundary = min_time
+        .div_euclid(interval)
+        .saturating_add(1)
+        .saturating_mul(interval);
+    while boundary <= max_time {
+        split_times.push(boundary - 1);
+        boundary = match boundary.checked_add(interval) {
+            Some(boundary) => boundary,
+            None => break,
+        };
+    }
+
+    split_times
+}
+
+/// Version of the [compaction keys](compaction_key), to be increased whenever their derivation
+/// changes.
+const COMPACTION_KEY_VERSION: u8 = 1;
+
+/// Return the idempotency key of the compaction of the files with the IDs `input_file_ids` into
+/// files of `target_level`.
+///
+/// The key is stored with the output files in the catalog. It only depends on the set of input
+/// files and the target level, so a compaction that is retried after its result was committed,
+/// e.g. because the compactor crashed before it could record that, finds its key and does not
+/// create the output files again.
+pub(crate) fn compaction_key(
+    input_file_ids: &[ParquetFileId],
+    target_level: CompactionLevel,
+) -> String {
+    let mut ids: Vec<_> = input_file_ids.iter().map(|id| id.get()).collect();
+    ids.sort_unstable();
+    ids.dedup();
+
+    let mut hasher = Sha256::new();
+    hasher.update((target_level as i16).to_be_bytes());
+    for id in ids {
+        hasher.update(id.to_be_bytes());
+    }
+
+    format!("v{}-{:x}", COMPACTION_KEY_VERSION, hasher.finalize())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_compaction_key() {
+        let ids = |ids: &[i64]| {
+            ids.iter()
+                .copied()
+                .map(ParquetFileId::new)
+                .collect::<Vec<_>>()
+        };
+
+        let key = compaction_key(&ids(&[1, 2, 3]), CompactionLevel::FileNonOverlapped);
+        assert!(key.starts_with("v1-"), "{key}");
+        assert_eq!(key.len(), 3 + 64);
+
+        // independent of the order of the input files
+        assert_eq!(
+            compaction_key(&ids(&[3, 1, 2]), CompactionLevel::FileNonOverlapped),
+            key
+        );
+        assert_eq!(
+            compaction_key(&ids(&[1, 2, 3, 2]), CompactionLevel::FileNonOverlapped),
+            key
+        );
+
+        // but not of the input files or the target level
+        assert_ne!(
+            compaction_key(&ids(&[1, 2]), CompactionLevel::FileNonOverlapped),
+            key
+        );
+        assert_ne!(
+            compaction_key(&ids(&[1, 2, 4]), CompactionLevel::FileNonOverlapped),
+            key
+        );
+        assert_ne!(
+            compaction_key(&ids(&[1, 2, 3]), CompactionLevel::Level2),
+            key
+        );
+    }
+
+    #[test]
+    fn test_compute_aligned_split_times() {
+        let interval = Duration::from_nanos(10);
+
+        assert_eq!(compute_aligned_split_times(5, 25, interval), vec![9, 19]);
+
+        // a boundary at min_time does not split, one at max_time does
+        assert_eq!(compute_aligned_split_times(10, 20, interval), vec![19]);
+
+        // within one interval
+        assert!(compute_aligned_split_times(11, 19, interval).is_empty());
+        assert!(compute_aligned_split_times(7, 7, interval).is_empty());
+
+        // negative times
+        assert_eq!(compute_aligned_split_times(-15, 1, interval), vec![-11, -1]);
+
+        // UTC days
+        let day = 24 * 60 * 60 * 1_000_000_000_i64;
+        assert_eq!(
+            compute_aligned_split_times(day + 1, 3 * day, Duration::from_secs(24 * 60 * 60)),
+            vec![2 * day - 1, 3 * day - 1]
+        );
+
+        // huge intervals don't overflow
+        assert!(compute_aligned_split_times(1, i64::MAX - 1, Duration::MAX).is_empty());
+    }
+
     #[test]
     fn test_compute_split_time() {
         let min_time = 1;
@@ -18,7 +18,7 @@ use schema::{
     builder::SchemaBuilder, sort::SortKey, InfluxColumnType, InfluxFieldType, Schema,
     TIME_COLUMN_NAME,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use snafu::{ResultExt, Snafu};
 use sqlx::postgres::PgHasArrayType;
 use std::{
@@ -41,6 +41,25 @@ pub enum CompactionLevel {
     Initial = 0,
     /// Level of files persisted by a Compactor that do not overlap with non-level-0 files.
     FileNonOverlapped = 1,
+    /// Level of files compacted from level 1 files. Only used if the Compactor is configured
+    /// with more than two levels.
+    Level2 = 2,
+    /// Level of files compacted from level 2 files. Only used if the Compactor is configured
+    /// with more than three levels.
+    Level3 = 3,
+    /// Level of files compacted from level 3 files. Only used if the Compactor is configured
+    /// with five levels.
+    Level4 = 4,
+}
+
+impl CompactionLevel {
+    /// The highest compaction level.
+    pub const MAX: Self = Self::Level4;
+
+    /// The level that files of this level are compacted into, if any.
+    pub fn next(self) -> Option<Self> {
+        Self::try_from(self as i32 + 1).ok()
+    }
 }
 
 impl TryFrom<i32> for CompactionLevel {
@@ -50,6 +69,9 @@ impl TryFrom<i32> for CompactionLevel {
         match value {
             x if x == Self::Initial as i32 => Ok(Self::Initial),
             x if x == Self::FileNonOverlapped as i32 => Ok(Self::FileNonOverlapped),
+            x if x == Self::Level2 as i32 => Ok(Self::Level2),
+            x if x == Self::Level3 as i32 => Ok(Self::Level3),
+            x if x == Self::Level4 as i32 => Ok(Self::Level4),
             _ => Err("invalid compaction level value".into()),
         }
     }
@@ -134,8 +156,11 @@ impl std::fmt::Display for TableId {
 }
 
 /// Unique ID for a `Column`
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, sqlx::Type)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, sqlx::Type, Serialize, Deserialize,
+)]
 #[sqlx(transparent)]
+#[serde(transparent)]
 pub struct ColumnId(i64);
 
 #[allow(missing_docs)]
@@ -154,6 +179,27 @@ impl PgHasArrayType for ColumnId {
     }
 }
 
+/// Unique ID for a `ColumnTypeMigration`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, sqlx::Type)]
+#[sqlx(transparent)]
+pub struct ColumnTypeMigrationId(i64);
+
+#[allow(missing_docs)]
+impl ColumnTypeMigrationId {
+    pub fn new(v: i64) -> Self {
+        Self(v)
+    }
+    pub fn get(&self) -> i64 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for ColumnTypeMigrationId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// Unique ID for a `Shard`, assigned by the catalog. Joins to other catalog tables to uniquely
 /// identify shards independently of the underlying write buffer implementation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, sqlx::Type)]
@@ -387,6 +433,27 @@ impl std::fmt::Display for ParquetFileId {
     }
 }
 
+/// Unique ID for a `CompactionReport`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, sqlx::Type)]
+#[sqlx(transparent)]
+pub struct CompactionReportId(i64);
+
+#[allow(missing_docs)]
+impl CompactionReportId {
+    pub fn new(v: i64) -> Self {
+        Self(v)
+    }
+    pub fn get(&self) -> i64 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for CompactionReportId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// Data object for a topic. When Kafka is used as the write buffer, this is the Kafka topic name
 /// plus a catalog-assigned ID.
 #[derive(Debug, Clone, Eq, PartialEq, sqlx::FromRow)]
@@ -425,6 +492,13 @@ pub struct Namespace {
     pub max_tables: i32,
     /// The maximum number of columns per table in this namespace
     pub max_columns_per_table: i32,
+    /// The maximum number of writes per second to this namespace. `None` means unlimited.
+    pub write_rate_limit: Option<i32>,
+    /// The maximum number of queries per second against this namespace. `None` means unlimited.
+    pub query_rate_limit: Option<i32>,
+    /// The timeout of queries against this namespace that do not request one, in milliseconds.
+    /// `None` means the querier's default applies.
+    pub query_timeout_ms: Option<i64>,
 }
 
 /// Schema collection for a namespace. This is an in-memory object useful for a schema
@@ -557,6 +631,37 @@ impl Column {
     }
 }
 
+/// Data object for a change of a column's type.
+///
+/// When the migration starts, the column type in the catalog is switched to `to_type`. Parquet
+/// files created before `created_at` may still contain the column as `from_type`; they are cast
+/// on read and rewritten by the compactor over time. Once no such file is left, the migration is
+/// marked as completed.
+#[derive(Debug, Clone, sqlx::FromRow, Eq, PartialEq)]
+pub struct ColumnTypeMigration {
+    /// the id of the migration
+    pub id: ColumnTypeMigrationId,
+    /// the table id the column is in
+    pub table_id: TableId,
+    /// the column that is migrated
+    pub column_id: ColumnId,
+    /// the logical type of the column before the migration
+    pub from_type: i16,
+    /// the logical type of the column after the migration
+    pub to_type: i16,
+    /// when the migration was started
+    pub created_at: Timestamp,
+    /// when all files were rewritten to the new type, `None` while the migration is active
+    pub completed_at: Option<Timestamp>,
+}
+
+impl ColumnTypeMigration {
+    /// returns true if the migration still has files to rewrite
+    pub fn is_active(&self) -> bool {
+        self.completed_at.is_none()
+    }
+}
+
 /// The column id and its type for a column
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct ColumnSchema {
@@ -627,6 +732,18 @@ impl ColumnType {
             Self::Tag => "tag",
         }
     }
+
+    /// Returns true if a column of this type can be migrated to `target`, see
+    /// [`InfluxFieldType::can_migrate_to`]. Only field columns can be migrated.
+    pub fn can_migrate_to(&self, target: Self) -> bool {
+        match (
+            InfluxColumnType::from(*self),
+            InfluxColumnType::from(target),
+        ) {
+            (InfluxColumnType::Field(from), InfluxColumnType::Field(to)) => from.can_migrate_to(to),
+            _ => false,
+        }
+    }
 }
 
 impl std::fmt::Display for ColumnType {
@@ -835,6 +952,18 @@ pub struct Partition {
     /// is legal. Howver, updating to `A,C,D,B` is not because the
     /// relative order of B and C have been reversed.
     pub sort_key: Vec<String>,
+    /// Version of the `sort_key`, incremented by every update of the sort key.
+    ///
+    /// Writers that derive a new sort key from the one they read (e.g. to add new columns)
+    /// should use this version for a conditional update so that concurrent updates cannot
+    /// silently overwrite each other.
+    pub sort_key_version: i64,
+    /// The highest max sequence number of the level 0 files of this partition that were
+    /// compacted, `None` if the partition was never compacted.
+    ///
+    /// Level 0 files are compacted in the order of their max sequence numbers, so all level 0
+    /// files at or below the cursor have been compacted and need not be listed again.
+    pub compaction_cursor: Option<SequenceNumber>,
 }
 
 impl Partition {
@@ -857,6 +986,36 @@ pub struct PartitionInfo {
     pub table_name: String,
 }
 
+/// The data of a partition that was visible to a query.
+///
+/// A query can be repeated on the same data by pinning it to the snapshots of all partitions it
+/// has seen.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PartitionSnapshot {
+    /// Max sequence number of the persisted data, `None` if nothing was persisted.
+    pub persisted_max_sequence_number: Option<SequenceNumber>,
+
+    /// Max sequence number of all (persisted and unpersisted) data.
+    pub max_sequence_number: SequenceNumber,
+}
+
+impl PartitionSnapshot {
+    /// Returns `true` if all data of the snapshot was persisted.
+    pub fn is_persisted(&self) -> bool {
+        self.persisted_max_sequence_number == Some(self.max_sequence_number)
+    }
+
+    /// Combine two snapshots of the same partition, e.g. taken from the catalog and an ingester.
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            persisted_max_sequence_number: self
+                .persisted_max_sequence_number
+                .max(other.persisted_max_sequence_number),
+            max_sequence_number: self.max_sequence_number.max(other.max_sequence_number),
+        }
+    }
+}
+
 /// Data for a partition  chosen from its parquet files
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, sqlx::FromRow)]
 pub struct PartitionParam {
@@ -963,6 +1122,167 @@ impl Deref for ColumnSet {
     }
 }
 
+/// Statistics of a column of a parquet file.
+///
+/// These are recorded in the catalog, so that files can be pruned without fetching their footers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnStats {
+    /// the column
+    pub column_id: ColumnId,
+    /// the number of null values, if known
+    pub null_count: Option<u64>,
+    /// the smallest value, only recorded for tag and string field columns
+    pub min: Option<String>,
+    /// the largest value, only recorded for tag and string field columns
+    pub max: Option<String>,
+}
+
+impl ColumnStats {
+    /// Estimate the memory consumption of this object and its contents
+    pub fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+            + self.min.as_ref().map(|s| s.capacity()).unwrap_or_default()
+            + self.max.as_ref().map(|s| s.capacity()).unwrap_or_default()
+    }
+}
+
+/// Statistics of the columns of a parquet file, see [`ColumnStats`].
+///
+/// Files created before the statistics were recorded have an empty set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ColumnStatsSet(Vec<ColumnStats>);
+
+impl ColumnStatsSet {
+    /// Create new set of column statistics.
+    ///
+    /// # Panic
+    /// Panics when the passed statistics contain the same column twice.
+    pub fn new<I>(stats: I) -> Self
+    where
+        I: IntoIterator<Item = ColumnStats>,
+    {
+        let mut stats: Vec<ColumnStats> = stats.into_iter().collect();
+        stats.sort_by_key(|s| s.column_id);
+
+        let len_pre_dedup = stats.len();
+        stats.dedup_by_key(|s| s.column_id);
+        let len_post_dedup = stats.len();
+        assert_eq!(len_pre_dedup, len_post_dedup, "set contains duplicates");
+
+        stats.shrink_to_fit();
+
+        Self(stats)
+    }
+
+    /// Statistics of the given column, if recorded.
+    pub fn get(&self, column_id: ColumnId) -> Option<&ColumnStats> {
+        self.0
+            .binary_search_by_key(&column_id, |s| s.column_id)
+            .ok()
+            .map(|idx| &self.0[idx])
+    }
+
+    /// Estimate the memory consumption of this object and its contents
+    pub fn size(&self) -> usize {
+        std::mem::size_of_val(self)
+            + self.0.iter().map(|s| s.size()).sum::<usize>()
+            + (self.0.capacity() - self.0.len()) * std::mem::size_of::<ColumnStats>()
+    }
+}
+
+impl Deref for ColumnStatsSet {
+    type Target = [ColumnStats];
+
+    fn deref(&self) -> &Self::Target {
+        self.0.deref()
+    }
+}
+
+impl sqlx::Type<sqlx::Postgres> for ColumnStatsSet {
+    fn type_info() -> sqlx::postgres::PgTypeInfo {
+        // Store this type as JSONB
+        <sqlx::types::Json<Self> as sqlx::Type<sqlx::Postgres>>::type_info()
+    }
+}
+
+impl PgHasArrayType for ColumnStatsSet {
+    fn array_type_info() -> sqlx::postgres::PgTypeInfo {
+        <sqlx::types::Json<Self> as PgHasArrayType>::array_type_info()
+    }
+}
+
+impl sqlx::Encode<'_, sqlx::Postgres> for ColumnStatsSet {
+    fn encode_by_ref(
+        &self,
+        buf: &mut <sqlx::Postgres as sqlx::database::HasArguments<'_>>::ArgumentBuffer,
+    ) -> sqlx::encode::IsNull {
+        <sqlx::types::Json<&Self> as sqlx::Encode<sqlx::Postgres>>::encode(
+            sqlx::types::Json(self),
+            buf,
+        )
+    }
+}
+
+impl sqlx::Decode<'_, sqlx::Postgres> for ColumnStatsSet {
+    fn decode(
+        value: <sqlx::Postgres as sqlx::database::HasValueRef<'_>>::ValueRef,
+    ) -> Result<Self, Box<dyn std::error::Error + 'static + Send + Sync>> {
+        Ok(<sqlx::types::Json<Self> as sqlx::Decode<sqlx::Postgres>>::decode(value)?.0)
+    }
+}
+
+/// Layout of the object store paths of parquet files.
+///
+/// Files are written with a single layout, but readers resolve the paths of both layouts, so the
+/// layout of a running cluster can be changed without moving existing files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum ParquetPathLayout {
+    /// `<namespace_id>/<table_id>/<shard_id>/<partition_id>/<object_store_id>.parquet`
+    #[default]
+    V1,
+
+    /// `ns/<namespace_id>/<table_id>/<partition_id>/<object_store_id>.parquet`
+    ///
+    /// The files of a namespace, table or partition share a prefix that contains no other objects
+    /// (independent of the shard), so object store lifecycle rules can be applied per namespace.
+    V2,
+}
+
+impl ParquetPathLayout {
+    /// The other layout, which readers fall back to.
+    pub fn other(&self) -> Self {
+        match self {
+            Self::V1 => Self::V2,
+            Self::V2 => Self::V1,
+        }
+    }
+}
+
+impl std::fmt::Display for ParquetPathLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::V1 => write!(f, "v1"),
+            Self::V2 => write!(f, "v2"),
+        }
+    }
+}
+
+impl std::str::FromStr for ParquetPathLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "v1" => Ok(Self::V1),
+            "v2" => Ok(Self::V2),
+            _ => Err(format!(
+                "unknown parquet path layout '{}', expected 'v1' or 'v2'",
+                s
+            )),
+        }
+    }
+}
+
 /// Data for a parquet file reference that has been inserted in the catalog.
 #[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
 pub struct ParquetFile {
@@ -1003,6 +1323,8 @@ pub struct ParquetFile {
     ///      a Compactor and does not overlap with other files except level 0 ones. Eventually,
     ///      cold partitions (partitions that no longer needs to get compacted) will only include
     ///      one or many level-1 files
+    ///  * 2 and above (`CompactionLevel::Level2`, ...): files compacted from the level below by a
+    ///      Compactor configured with more levels. Each level holds fewer, larger files.
     pub compaction_level: CompactionLevel,
     /// the creation time of the parquet file
     pub created_at: Timestamp,
@@ -1021,6 +1343,8 @@ pub struct ParquetFile {
     /// The columns that are present in the table-wide schema are sorted according to the partition
     /// sort key. The occur in the parquet file according to this order.
     pub column_set: ColumnSet,
+    /// Statistics of the columns within this parquet file.
+    pub column_stats: ColumnStatsSet,
 }
 
 impl ParquetFile {
@@ -1028,6 +1352,8 @@ impl ParquetFile {
     pub fn size(&self) -> usize {
         std::mem::size_of_val(self) + self.column_set.size()
             - std::mem::size_of_val(&self.column_set)
+            + self.column_stats.size()
+            - std::mem::size_of_val(&self.column_stats)
     }
 }
 
@@ -1060,6 +1386,8 @@ pub struct ParquetFileParams {
     pub created_at: Timestamp,
     /// columns in this file.
     pub column_set: ColumnSet,
+    /// statistics of the columns in this file.
+    pub column_stats: ColumnStatsSet,
 }
 
 /// Data for a processed tombstone reference in the catalog.
@@ -1071,6 +1399,36 @@ pub struct ProcessedTombstone {
     pub parquet_file_id: ParquetFileId,
 }
 
+/// Data for a compaction report reference in the catalog.
+///
+/// The report itself is a JSON document in the object store, written next to the Parquet files of
+/// the partition.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct CompactionReport {
+    /// the id of the report in the catalog
+    pub id: CompactionReportId,
+    /// the partition that was compacted
+    pub partition_id: PartitionId,
+    /// the uuid used in the object store path for this report
+    pub object_store_id: Uuid,
+    /// the creation time of the report
+    pub created_at: Timestamp,
+}
+
+/// Data object for the access statistics of a table, maintained by the queriers.
+///
+/// Rarely read tables are candidates for more aggressive compaction and for moving their data to
+/// cheaper storage tiers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct TableAccessStats {
+    /// the table that was queried
+    pub table_id: TableId,
+    /// the time of the most recent query against the table
+    pub last_queried_at: Timestamp,
+    /// the number of queries against the table since it was first queried
+    pub query_count: i64,
+}
+
 /// ID of a chunk.
 ///
 /// This ID is unique within a single partition.
@@ -1305,6 +1663,18 @@ pub enum Op {
 
     /// Inequality (`!=`).
     Ne,
+
+    /// Less than (`<`).
+    Lt,
+
+    /// Less than or equal (`<=`).
+    Le,
+
+    /// Greater than (`>`).
+    Gt,
+
+    /// Greater than or equal (`>=`).
+    Ge,
 }
 
 impl std::fmt::Display for Op {
@@ -1312,6 +1682,10 @@ impl std::fmt::Display for Op {
         match self {
             Self::Eq => write!(f, "="),
             Self::Ne => write!(f, "!="),
+            Self::Lt => write!(f, "<"),
+            Self::Le => write!(f, "<="),
+            Self::Gt => write!(f, ">"),
+            Self::Ge => write!(f, ">="),
         }
     }
 }
@@ -2250,6 +2624,20 @@ mod tests {
     use ordered_float::OrderedFloat;
     use test_helpers::assert_contains;
 
+    #[test]
+    fn test_compaction_level_next() {
+        assert_eq!(
+            CompactionLevel::Initial.next(),
+            Some(CompactionLevel::FileNonOverlapped)
+        );
+        assert_eq!(
+            CompactionLevel::FileNonOverlapped.next(),
+            Some(CompactionLevel::Level2)
+        );
+        assert_eq!(CompactionLevel::MAX.next(), None);
+        assert!(CompactionLevel::try_from(CompactionLevel::MAX as i32 + 1).is_err());
+    }
+
     #[test]
     fn test_chunk_id_new() {
         // `ChunkId::new()` create new random ID
@@ -2301,9 +2689,32 @@ mod tests {
                     op: Op::Ne,
                     scalar: Scalar::I64(2),
                 },
+                DeleteExpr {
+                    column: String::from("col3"),
+                    op: Op::Lt,
+                    scalar: Scalar::I64(3),
+                },
+                DeleteExpr {
+                    column: String::from("col4"),
+                    op: Op::Le,
+                    scalar: Scalar::I64(4),
+                },
+                DeleteExpr {
+                    column: String::from("col5"),
+                    op: Op::Gt,
+                    scalar: Scalar::I64(5),
+                },
+                DeleteExpr {
+                    column: String::from("col6"),
+                    op: Op::Ge,
+                    scalar: Scalar::I64(6),
+                },
             ],
         };
-        assert_eq!(&pred.expr_sql_string(), r#""col1"=1 AND "col2"!=2"#);
+        assert_eq!(
+            &pred.expr_sql_string(),
+            r#""col1"=1 AND "col2"!=2 AND "col3"<3 AND "col4"<=4 AND "col5">5 AND "col6">=6"#
+        );
     }
 
     #[test]
@@ -3349,6 +3760,35 @@ mod tests {
         ColumnSet::new([ColumnId::new(1), ColumnId::new(2), ColumnId::new(1)]);
     }
 
+    #[test]
+    fn test_column_stats_set() {
+        let stats = |id, min: &str| ColumnStats {
+            column_id: ColumnId::new(id),
+            null_count: Some(0),
+            min: Some(min.to_string()),
+            max: Some(min.to_string()),
+        };
+        let set = ColumnStatsSet::new([stats(3, "c"), stats(1, "a")]);
+        assert_eq!(
+            set.iter().map(|s| s.column_id.get()).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+        assert_eq!(set.get(ColumnId::new(3)), Some(&stats(3, "c")));
+        assert_eq!(set.get(ColumnId::new(2)), None);
+    }
+
+    #[test]
+    #[should_panic = "set contains duplicates"]
+    fn test_column_stats_set_duplicates() {
+        let stats = |id| ColumnStats {
+            column_id: ColumnId::new(id),
+            null_count: None,
+            min: None,
+            max: None,
+        };
+        ColumnStatsSet::new([stats(1), stats(2), stats(1)]);
+    }
+
     #[test]
     fn test_timestamprange_start_after_end() {
         let tr = TimestampRange::new(2, 1);
@@ -0,0 +1,172 @@
+//! Cancel query execution once a wall-clock deadline passes.
+
+use std::{
+    fmt::Display,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use datafusion::{
+    arrow::{
+        datatypes::SchemaRef,
+        error::{ArrowError, Result as ArrowResult},
+        record_batch::RecordBatch,
+    },
+    physical_plan::{RecordBatchStream, SendableRecordBatchStream},
+};
+use futures::{FutureExt, Stream, StreamExt};
+use tokio::time::{Instant, Sleep};
+
+/// The deadline of a [`DeadlineStream`] passed.
+///
+/// Returned as [`ArrowError::ExternalError`], use [`DeadlineExceeded::from_error`] to detect it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeadlineExceeded {
+    /// The timeout the deadline was derived from.
+    pub timeout: Duration,
+}
+
+impl DeadlineExceeded {
+    /// Returns the [`DeadlineExceeded`] error if `err` is one.
+    pub fn from_error(err: &ArrowError) -> Option<Self> {
+        match err {
+            ArrowError::ExternalError(e) => e.downcast_ref::<Self>().copied(),
+            _ => None,
+        }
+    }
+}
+
+impl Display for DeadlineExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Query exceeded its timeout of {:?}", self.timeout)
+    }
+}
+
+impl std::error::Error for DeadlineExceeded {}
+
+/// Stream that fails with [`DeadlineExceeded`] if its inner stream does not finish before a
+/// deadline.
+///
+/// Once the deadline passed, the inner stream is dropped, which cancels all work it drives.
+pub struct DeadlineStream {
+    schema: SchemaRef,
+    inner: Option<SendableRecordBatchStream>,
+    sleep: Pin<Box<Sleep>>,
+    timeout: Duration,
+}
+
+impl DeadlineStream {
+    /// Create stream that fails if `inner` does not finish within `timeout` from now.
+    pub fn new(inner: SendableRecordBatchStream, timeout: Duration) -> Self {
+        Self::new_with_deadline(inner, Instant::now() + timeout, timeout)
+    }
+
+    /// Create stream that fails if `inner` does not finish before `deadline`, which was derived
+    /// from `timeout` (used for the error message).
+    pub fn new_with_deadline(
+        inner: SendableRecordBatchStream,
+        deadline: Instant,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            schema: inner.schema(),
+            inner: Some(inner),
+            sleep: Box::pin(tokio::time::sleep_until(deadline)),
+            timeout,
+        }
+    }
+}
+
+impl std::fmt::Debug for DeadlineStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeadlineStream")
+            .field("schema", &self.schema)
+            .field("done", &self.inner.is_none())
+            .field("deadline", &self.sleep.deadline())
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl Stream for DeadlineStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let inner = match this.inner.as_mut() {
+            Some(inner) => inner,
+            None => return Poll::Ready(None),
+        };
+
+        if this.sleep.poll_unpin(cx).is_ready() {
+            // cancel the inner stream
+            this.inner = None;
+            let err = DeadlineExceeded {
+                timeout: this.timeout,
+            };
+            return Poll::Ready(Some(Err(ArrowError::ExternalError(Box::new(err)))));
+        }
+
+        let res = inner.poll_next_unpin(cx);
+        if let Poll::Ready(None) = res {
+            this.inner = None;
+        }
+        res
+    }
+}
+
+impl RecordBatchStream for DeadlineStream {
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::array::Int64Array;
+
+    use super::*;
+    use crate::{stream_from_batch, AdapterStream};
+
+    fn batch() -> RecordBatch {
+        RecordBatch::try_from_iter(vec![("x", Arc::new(Int64Array::from(vec![1])) as _)]).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_finishes_in_time() {
+        let mut stream = DeadlineStream::new(stream_from_batch(batch()), Duration::from_secs(60));
+
+        assert_eq!(stream.next().await.unwrap().unwrap(), batch());
+        assert!(stream.next().await.is_none());
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_deadline_exceeded() {
+        // a stream that never finishes
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        let task = crate::watch::WatchedTask::new(
+            async move {
+                tx.send(Ok(batch())).await.ok();
+                futures::future::pending::<()>().await;
+                Ok(())
+            },
+            Vec::<tokio::sync::mpsc::Sender<_>>::new(),
+            "pending",
+        );
+        let inner = AdapterStream::adapt(batch().schema(), rx, task);
+
+        let timeout = Duration::from_millis(10);
+        let mut stream = DeadlineStream::new(inner, timeout);
+
+        assert_eq!(stream.next().await.unwrap().unwrap(), batch());
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert_eq!(
+            DeadlineExceeded::from_error(&err),
+            Some(DeadlineExceeded { timeout })
+        );
+        assert!(stream.next().await.is_none());
+    }
+}
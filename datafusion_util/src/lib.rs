@@ -1,6 +1,8 @@
 #![deny(rustdoc::broken_intra_doc_links, rustdoc::bare_urls, rust_2018_idioms)]
 #![allow(clippy::clone_on_ref_ptr)]
 
+pub mod deadline;
+pub mod memory;
 pub mod sender;
 pub mod watch;
 
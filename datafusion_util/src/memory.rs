@@ -0,0 +1,262 @@
+//! Bound the memory of record batches that streams buffer ahead of their consumer.
+//!
+//! Operators that merge many streams (e.g. the sorted parquet files of a partition) keep all of
+//! them open at the same time. If every input reads ahead on its own, nothing bounds the memory
+//! of the batches that were read but not yet consumed. A [`MemoryTrackedRecordBatchStream`] reads
+//! ahead in a background task and accounts for its buffered batches against a [`MemoryPool`]
+//! shared by all inputs. Once the pool is exhausted, reading ahead pends until memory is
+//! released by consuming batches.
+//!
+//! A stream with an empty buffer may always read one batch, even if the pool is exhausted.
+//! Otherwise a consumer that needs a batch of every input (like a merge) could wait forever for
+//! an input that waits for memory held by the others. The memory used is therefore bounded by
+//! the pool limit plus one batch per stream.
+
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use datafusion::{
+    arrow::{datatypes::SchemaRef, error::Result as ArrowResult, record_batch::RecordBatch},
+    physical_plan::{RecordBatchStream, SendableRecordBatchStream},
+};
+use futures::{Stream, StreamExt};
+use tokio::sync::{mpsc::UnboundedSender, Notify};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+use crate::watch::WatchedTask;
+
+/// Memory shared by multiple [`MemoryTrackedRecordBatchStream`]s.
+#[derive(Debug)]
+pub struct MemoryPool {
+    limit: usize,
+    used: Mutex<usize>,
+    released: Notify,
+}
+
+impl MemoryPool {
+    /// Create pool that hands out up to `limit` bytes.
+    pub fn new(limit: usize) -> Arc<Self> {
+        Arc::new(Self {
+            limit,
+            used: Mutex::new(0),
+            released: Notify::new(),
+        })
+    }
+
+    /// Maximum number of bytes handed out, see the [module documentation](self) for exceptions.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Number of bytes currently reserved.
+    pub fn used(&self) -> usize {
+        *self.used.lock().expect("not poisoned")
+    }
+
+    /// Reserve `bytes`, waiting for other reservations to be released if the pool is exhausted.
+    ///
+    /// If `force` is set, the bytes are reserved right away, even if that exceeds the limit.
+    pub async fn reserve(self: &Arc<Self>, bytes: usize, force: bool) -> MemoryReservation {
+        loop {
+            // register for notifications before checking, so that no release is missed
+            let released = self.released.notified();
+
+            {
+                let mut used = self.used.lock().expect("not poisoned");
+                if force || *used + bytes <= self.limit {
+                    *used += bytes;
+                    return MemoryReservation {
+                        pool: Arc::clone(self),
+                        bytes,
+                    };
+                }
+            }
+
+            released.await;
+        }
+    }
+}
+
+/// Bytes reserved in a [`MemoryPool`], released on drop.
+#[derive(Debug)]
+pub struct MemoryReservation {
+    pool: Arc<MemoryPool>,
+    bytes: usize,
+}
+
+impl MemoryReservation {
+    /// Number of reserved bytes.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+impl Drop for MemoryReservation {
+    fn drop(&mut self) {
+        *self.pool.used.lock().expect("not poisoned") -= self.bytes;
+        self.pool.released.notify_waiters();
+    }
+}
+
+/// Stream that reads ahead of its consumer within the bounds of a [`MemoryPool`], see the
+/// [module documentation](self).
+#[derive(Debug)]
+pub struct MemoryTrackedRecordBatchStream {
+    schema: SchemaRef,
+    inner: UnboundedReceiverStream<ArrowResult<RecordBatch>>,
+
+    /// Reservations of the buffered batches, in the order of the batches.
+    reservations: Arc<Mutex<VecDeque<MemoryReservation>>>,
+
+    /// Task reading the input.
+    #[allow(dead_code)]
+    task: Arc<WatchedTask>,
+}
+
+impl MemoryTrackedRecordBatchStream {
+    /// Read `input` ahead, accounting buffered batches against `pool`.
+    ///
+    /// Not called `new` because it returns a pinned reference rather than the object itself.
+    pub fn adapt(
+        input: SendableRecordBatchStream,
+        pool: Arc<MemoryPool>,
+    ) -> SendableRecordBatchStream {
+        let schema = input.schema();
+        let reservations: Arc<Mutex<VecDeque<MemoryReservation>>> = Default::default();
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let fut = read_ahead(input, pool, Arc::clone(&reservations), tx.clone());
+        let task = WatchedTask::new(fut, vec![tx], "memory tracked stream");
+
+        Box::pin(Self {
+            schema,
+            inner: UnboundedReceiverStream::new(rx),
+            reservations,
+            task,
+        })
+    }
+}
+
+async fn read_ahead(
+    mut input: SendableRecordBatchStream,
+    pool: Arc<MemoryPool>,
+    reservations: Arc<Mutex<VecDeque<MemoryReservation>>>,
+    tx: UnboundedSender<ArrowResult<RecordBatch>>,
+) -> ArrowResult<()> {
+    while let Some(batch) = input.next().await {
+        let batch = batch?;
+
+        let bytes = batch
+            .columns()
+            .iter()
+            .map(|c| c.get_array_memory_size())
+            .sum();
+        let buffer_empty = reservations.lock().expect("not poisoned").is_empty();
+        let reservation = pool.reserve(bytes, buffer_empty).await;
+
+        // register the reservation before sending, so that the receiver always finds it
+        reservations
+            .lock()
+            .expect("not poisoned")
+            .push_back(reservation);
+        if tx.send(Ok(batch)).is_err() {
+            // receiver hung up
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+impl Stream for MemoryTrackedRecordBatchStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let res = self.inner.poll_next_unpin(cx);
+        if let Poll::Ready(Some(Ok(_))) = &res {
+            // the batch leaves the buffer
+            self.reservations
+                .lock()
+                .expect("not poisoned")
+                .pop_front()
+                .expect("reservation for every buffered batch");
+        }
+        res
+    }
+}
+
+impl RecordBatchStream for MemoryTrackedRecordBatchStream {
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use datafusion::arrow::array::Int64Array;
+
+    use super::*;
+    use crate::stream_from_batches;
+
+    fn batch() -> RecordBatch {
+        RecordBatch::try_from_iter(vec![("x", Arc::new(Int64Array::from(vec![1, 2, 3])) as _)])
+            .unwrap()
+    }
+
+    fn batch_size() -> usize {
+        batch()
+            .columns()
+            .iter()
+            .map(|c| c.get_array_memory_size())
+            .sum()
+    }
+
+    async fn settle() {
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_ahead_within_limit() {
+        let pool = MemoryPool::new(2 * batch_size());
+        let input = stream_from_batches((0..5).map(|_| Arc::new(batch())).collect());
+        let mut stream = MemoryTrackedRecordBatchStream::adapt(input, Arc::clone(&pool));
+
+        // reads ahead until the pool is exhausted
+        settle().await;
+        assert_eq!(pool.used(), 2 * batch_size());
+
+        // consuming a batch lets the stream read the next one
+        stream.next().await.unwrap().unwrap();
+        settle().await;
+        assert_eq!(pool.used(), 2 * batch_size());
+
+        let rest: Vec<_> = stream.collect().await;
+        assert_eq!(rest.len(), 4);
+        assert_eq!(pool.used(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_progress_with_exhausted_pool() {
+        let pool = MemoryPool::new(batch_size());
+        let _other = pool.reserve(batch_size(), false).await;
+
+        let input = stream_from_batches((0..3).map(|_| Arc::new(batch())).collect());
+        let mut stream = MemoryTrackedRecordBatchStream::adapt(input, Arc::clone(&pool));
+
+        // a stream with an empty buffer may always read one batch
+        settle().await;
+        assert_eq!(pool.used(), 2 * batch_size());
+
+        for _ in 0..3 {
+            stream.next().await.unwrap().unwrap();
+        }
+        assert!(stream.next().await.is_none());
+        assert_eq!(pool.used(), batch_size());
+    }
+}
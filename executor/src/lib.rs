@@ -14,7 +14,10 @@
 use parking_lot::Mutex;
 use pin_project::{pin_project, pinned_drop};
 use std::{pin::Pin, sync::Arc};
-use tokio::sync::oneshot::{error::RecvError, Receiver};
+use tokio::{
+    runtime::Handle,
+    sync::oneshot::{error::RecvError, Receiver},
+};
 use tokio_util::sync::CancellationToken;
 
 use futures::{
@@ -24,6 +27,9 @@ use futures::{
 
 use observability_deps::tracing::warn;
 
+mod metrics;
+pub use metrics::register_runtime_metrics;
+
 /// Task that can be added to the executor-internal queue.
 ///
 /// Every task within the executor is represented by a [`Job`] that can be polled by the API user.
@@ -117,6 +123,9 @@ struct State {
 
     /// The inner thread that can be used to join during drop.
     thread: Option<std::thread::JoinHandle<()>>,
+
+    /// Handle of the tokio runtime.
+    handle: Handle,
 }
 
 // IMPORTANT: Implement `Drop` for `State`, NOT for `DedicatedExecutor`, because the executor can be cloned and clones
@@ -170,6 +179,7 @@ impl DedicatedExecutor {
 
         let (tx_tasks, rx_tasks) = std::sync::mpsc::channel::<Task>();
         let (tx_shutdown, rx_shutdown) = tokio::sync::oneshot::channel();
+        let (tx_handle, rx_handle) = std::sync::mpsc::channel();
 
         let thread = std::thread::spawn(move || {
             let runtime = tokio::runtime::Builder::new_multi_thread()
@@ -179,6 +189,9 @@ impl DedicatedExecutor {
                 .on_thread_start(move || set_current_thread_priority(WORKER_PRIORITY))
                 .build()
                 .expect("Creating tokio runtime");
+            tx_handle
+                .send(runtime.handle().clone())
+                .expect("executor still being created");
 
             runtime.block_on(async move {
                 // Dropping the tokio runtime only waits for tasks to yield not to complete
@@ -204,11 +217,14 @@ impl DedicatedExecutor {
             })
         });
 
+        let handle = rx_handle.recv().expect("Creating tokio runtime");
+
         let state = State {
             requests: Some(tx_tasks),
             task_refs: Arc::new(()),
             completed_shutdown: rx_shutdown.map_err(Arc::new).boxed().shared(),
             thread: Some(thread),
+            handle,
         };
 
         Self {
@@ -264,6 +280,12 @@ impl DedicatedExecutor {
         Arc::strong_count(&state.task_refs).saturating_sub(1)
     }
 
+    /// Register [metrics](register_runtime_metrics) for the tokio runtime of this executor.
+    pub fn register_metrics(&self, runtime_name: &'static str, metric_registry: &metric::Registry) {
+        let handle = self.state.lock().handle.clone();
+        register_runtime_metrics(runtime_name, &handle, metric_registry);
+    }
+
     /// signals shutdown of this executor and any Clones
     pub fn shutdown(&self) {
         // hang up the channel which will cause the dedicated thread
@@ -0,0 +1,235 @@
+//! Metrics for tokio runtimes.
+//!
+//! Scheduling saturation of a runtime (i.e. all workers are busy and tasks wait in the queues) is not visible from
+//! the outside and easily mistaken for slow IO. The metrics registered here make it observable:
+//!
+//! - **`tokio_runtime_schedule_delay`:** Histogram of the time between spawning a probe task and its first poll. This
+//!   is always available.
+//! - **Runtime internals:** Number of workers, worker busy time, poll counts and queue depths. These are only available
+//!   if the binary is built with `--cfg tokio_unstable` (which is the case for release builds).
+use std::time::{Duration, Instant};
+
+use metric::{Attributes, DurationHistogram};
+use tokio::runtime::Handle;
+
+/// Interval at which the schedule delay is probed.
+const PROBE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Register metrics for the tokio runtime behind `handle`.
+///
+/// All metrics carry a `runtime` attribute set to `runtime_name`. This spawns a background task on the runtime that
+/// runs until the runtime is shut down.
+pub fn register_runtime_metrics(
+    runtime_name: &'static str,
+    handle: &Handle,
+    metric_registry: &metric::Registry,
+) {
+    let attributes = Attributes::from(&[("runtime", runtime_name)]);
+
+    let schedule_delay = metric_registry
+        .register_metric::<DurationHistogram>(
+            "tokio_runtime_schedule_delay",
+            "Time between spawning a task on the tokio runtime and its first poll",
+        )
+        .recorder(attributes.clone());
+    handle.spawn(async move {
+        let mut interval = tokio::time::interval(PROBE_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let start = Instant::now();
+            match tokio::spawn(async move { start.elapsed() }).await {
+                Ok(delay) => schedule_delay.record(delay),
+                // runtime is shutting down
+                Err(_) => return,
+            }
+        }
+    });
+
+    #[cfg(tokio_unstable)]
+    unstable::register(attributes, handle, metric_registry);
+}
+
+/// Metrics that require `--cfg tokio_unstable`.
+#[cfg(tokio_unstable)]
+mod unstable {
+    use std::{any::Any, collections::BTreeMap, sync::Arc};
+
+    use metric::{Attributes, MetricKind, Observation, Reporter};
+    use parking_lot::Mutex;
+    use tokio::runtime::{Handle, RuntimeMetrics};
+
+    /// Computes an observation from the runtime metrics.
+    type ObserveFn = fn(&RuntimeMetrics) -> Observation;
+
+    /// Sum of a per-worker metric.
+    fn sum_workers<F>(metrics: &RuntimeMetrics, f: F) -> u64
+    where
+        F: Fn(usize) -> u64,
+    {
+        (0..metrics.num_workers()).map(f).sum()
+    }
+
+    const INSTRUMENTS: &[(&str, &str, MetricKind, ObserveFn)] = &[
+        (
+            "tokio_runtime_workers",
+            "Number of worker threads of the tokio runtime",
+            MetricKind::U64Gauge,
+            |m| Observation::U64Gauge(m.num_workers() as u64),
+        ),
+        (
+            "tokio_runtime_worker_busy_duration",
+            "Total time the workers of the tokio runtime were busy, divide its rate by the number of workers to get \
+            the busy ratio",
+            MetricKind::DurationCounter,
+            |m| {
+                Observation::DurationCounter(
+                    (0..m.num_workers())
+                        .map(|worker| m.worker_total_busy_duration(worker))
+                        .sum(),
+                )
+            },
+        ),
+        (
+            "tokio_runtime_worker_polls",
+            "Number of task polls performed by the workers of the tokio runtime",
+            MetricKind::U64Counter,
+            |m| Observation::U64Counter(sum_workers(m, |worker| m.worker_poll_count(worker))),
+        ),
+        (
+            "tokio_runtime_worker_steals",
+            "Number of tasks stolen by the workers of the tokio runtime from each other",
+            MetricKind::U64Counter,
+            |m| Observation::U64Counter(sum_workers(m, |worker| m.worker_steal_count(worker))),
+        ),
+        (
+            "tokio_runtime_injection_queue_depth",
+            "Number of tasks in the global queue of the tokio runtime",
+            MetricKind::U64Gauge,
+            |m| Observation::U64Gauge(m.injection_queue_depth() as u64),
+        ),
+        (
+            "tokio_runtime_local_queue_depth",
+            "Number of tasks in the local queues of all workers of the tokio runtime",
+            MetricKind::U64Gauge,
+            |m| {
+                Observation::U64Gauge(sum_workers(m, |worker| {
+                    m.worker_local_queue_depth(worker) as u64
+                }))
+            },
+        ),
+    ];
+
+    pub(super) fn register(
+        attributes: Attributes,
+        handle: &Handle,
+        metric_registry: &metric::Registry,
+    ) {
+        for &(name, description, kind, observe) in INSTRUMENTS {
+            let instrument = metric_registry.register_instrument(name, || RuntimeInstrument {
+                name,
+                description,
+                kind,
+                observe,
+                runtimes: Default::default(),
+            });
+            instrument
+                .runtimes
+                .lock()
+                .insert(attributes.clone(), handle.clone());
+        }
+    }
+
+    /// A [`metric::Instrument`] that reports a single metric for all registered runtimes.
+    #[derive(Clone)]
+    struct RuntimeInstrument {
+        name: &'static str,
+        description: &'static str,
+        kind: MetricKind,
+        observe: ObserveFn,
+
+        /// Registered runtimes, sorted by attributes because the observations must be reported in that order.
+        runtimes: Arc<Mutex<BTreeMap<Attributes, Handle>>>,
+    }
+
+    impl std::fmt::Debug for RuntimeInstrument {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("RuntimeInstrument")
+                .field("name", &self.name)
+                .finish_non_exhaustive()
+        }
+    }
+
+    impl metric::Instrument for RuntimeInstrument {
+        fn report(&self, reporter: &mut dyn Reporter) {
+            reporter.start_metric(self.name, self.description, self.kind);
+            for (attributes, handle) in self.runtimes.lock().iter() {
+                reporter.report_observation(attributes, (self.observe)(&handle.metrics()));
+            }
+            reporter.finish_metric();
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use metric::{Metric, Observation, RawReporter};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_schedule_delay() {
+        let metric_registry = metric::Registry::new();
+        register_runtime_metrics("test", &Handle::current(), &metric_registry);
+
+        let histogram = metric_registry
+            .get_instrument::<Metric<DurationHistogram>>("tokio_runtime_schedule_delay")
+            .unwrap()
+            .get_observer(&Attributes::from(&[("runtime", "test")]))
+            .unwrap()
+            .clone();
+
+        // the first probe runs right away
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while histogram.fetch().sample_count() == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let mut reporter = RawReporter::default();
+        metric_registry.report(&mut reporter);
+        let observation = reporter
+            .metric("tokio_runtime_schedule_delay")
+            .unwrap()
+            .observation(&[("runtime", "test")])
+            .unwrap();
+        assert!(matches!(observation, Observation::DurationHistogram(_)));
+    }
+
+    #[cfg(tokio_unstable)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_runtime_internals() {
+        let metric_registry = metric::Registry::new();
+        register_runtime_metrics("a", &Handle::current(), &metric_registry);
+        register_runtime_metrics("b", &Handle::current(), &metric_registry);
+
+        let mut reporter = RawReporter::default();
+        metric_registry.report(&mut reporter);
+        for runtime in ["a", "b"] {
+            assert_eq!(
+                reporter
+                    .metric("tokio_runtime_workers")
+                    .unwrap()
+                    .observation(&[("runtime", runtime)])
+                    .unwrap(),
+                &Observation::U64Gauge(2)
+            );
+        }
+    }
+}
@@ -1,10 +1,12 @@
 use chrono::{DateTime, Utc};
 use iox_catalog::interface::{Catalog, ParquetFileRepo};
+use metric::U64Counter;
 use object_store::ObjectMeta;
 use observability_deps::tracing::*;
 use snafu::prelude::*;
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 use tokio::sync::mpsc;
+use uuid::Uuid;
 
 #[derive(Debug, Snafu)]
 #[allow(missing_docs)]
@@ -12,10 +14,10 @@ pub enum Error {
     #[snafu(display("Expected a file name"))]
     FileNameMissing,
 
-    #[snafu(display("The catalog could not be queried for {object_store_id}"))]
-    GetFile {
+    #[snafu(display("The catalog could not be queried for {count} object store ids"))]
+    GetFiles {
         source: iox_catalog::interface::Error,
-        object_store_id: uuid::Uuid,
+        count: usize,
     },
 
     #[snafu(display("The deleter task exited unexpectedly"))]
@@ -26,9 +28,54 @@ pub enum Error {
 
 pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
 
+/// Metrics of the checker stage.
+#[derive(Debug)]
+pub(crate) struct Metrics {
+    /// Objects scheduled for deletion.
+    scheduled: U64Counter,
+
+    /// Bytes of the objects scheduled for deletion.
+    scheduled_bytes: U64Counter,
+
+    /// Objects that are kept.
+    kept: U64Counter,
+
+    /// Catalog queries issued.
+    catalog_queries: U64Counter,
+}
+
+impl Metrics {
+    pub(crate) fn new(metric_registry: &metric::Registry) -> Self {
+        let checked = metric_registry.register_metric::<U64Counter>(
+            "gc_checker_objects",
+            "Number of object store items checked by the garbage collector",
+        );
+        let checked_bytes = metric_registry.register_metric::<U64Counter>(
+            "gc_checker_object_bytes",
+            "Size of the object store items checked by the garbage collector",
+        );
+        let catalog_queries = metric_registry
+            .register_metric::<U64Counter>(
+                "gc_checker_catalog_queries",
+                "Number of catalog queries issued by the garbage collector",
+            )
+            .recorder(&[]);
+
+        Self {
+            scheduled: checked.recorder(&[("decision", "delete")]),
+            scheduled_bytes: checked_bytes.recorder(&[("decision", "delete")]),
+            kept: checked.recorder(&[("decision", "keep")]),
+            catalog_queries,
+        }
+    }
+}
+
 pub(crate) async fn perform(
     catalog: Arc<dyn Catalog>,
     cutoff: DateTime<Utc>,
+    orphans_only: bool,
+    batch_size: usize,
+    metrics: Metrics,
     mut items: mpsc::Receiver<ObjectMeta>,
     deleter: mpsc::Sender<ObjectMeta>,
 ) -> Result<()> {
@@ -36,7 +83,24 @@ pub(crate) async fn perform(
     let parquet_files = repositories.parquet_files();
 
     while let Some(item) = items.recv().await {
-        if should_delete(&item, cutoff, parquet_files).await? {
+        // Check everything that's already queued up with a single catalog query
+        let mut batch = vec![item];
+        while batch.len() < batch_size {
+            match items.try_recv() {
+                Ok(item) => batch.push(item),
+                Err(_) => break,
+            }
+        }
+
+        let batch_len = batch.len() as u64;
+        let to_delete = check_batch(batch, cutoff, orphans_only, parquet_files, &metrics).await?;
+        metrics.scheduled.inc(to_delete.len() as u64);
+        metrics
+            .scheduled_bytes
+            .inc(to_delete.iter().map(|item| item.size as u64).sum());
+        metrics.kept.inc(batch_len - to_delete.len() as u64);
+
+        for item in to_delete {
             deleter.send(item).await.context(DeleterExitedSnafu)?;
         }
     }
@@ -44,11 +108,82 @@ pub(crate) async fn perform(
     Ok(())
 }
 
-async fn should_delete(
-    item: &ObjectMeta,
+/// How to proceed with an object store item.
+enum Decision {
+    Keep,
+    Delete,
+    /// The item is a parquet file, delete it unless the catalog references it.
+    Lookup(Uuid),
+}
+
+/// Return the items that should be deleted.
+async fn check_batch(
+    items: Vec<ObjectMeta>,
     cutoff: DateTime<Utc>,
+    orphans_only: bool,
     parquet_files: &mut dyn ParquetFileRepo,
-) -> Result<bool> {
+    metrics: &Metrics,
+) -> Result<Vec<ObjectMeta>> {
+    let decisions = items
+        .iter()
+        .map(|item| decide(item, cutoff, orphans_only))
+        .collect::<Result<Vec<_>>>()?;
+
+    let object_store_ids: Vec<_> = decisions
+        .iter()
+        .filter_map(|decision| match decision {
+            Decision::Lookup(uuid) => Some(*uuid),
+            _ => None,
+        })
+        .collect();
+    let in_catalog: HashSet<_> = if object_store_ids.is_empty() {
+        HashSet::new()
+    } else {
+        metrics.catalog_queries.inc(1);
+        parquet_files
+            .existing_object_store_ids(&object_store_ids)
+            .await
+            .context(GetFilesSnafu {
+                count: object_store_ids.len(),
+            })?
+            .into_iter()
+            .collect()
+    };
+
+    Ok(items
+        .into_iter()
+        .zip(decisions)
+        .filter(|(item, decision)| match decision {
+            Decision::Keep => false,
+            Decision::Delete => true,
+            Decision::Lookup(uuid) if in_catalog.contains(uuid) => {
+                // We have a reference to this file; do not delete
+                info!(
+                    location = %item.location,
+                    deleting = false,
+                    reason = "exists in catalog",
+                    "Ignoring object",
+                );
+                false
+            }
+            Decision::Lookup(_) => {
+                info!(
+                    location = %item.location,
+                    deleting = true,
+                    reason = "not in catalog",
+                    "Scheduling file for deletion",
+                );
+                true
+            }
+        })
+        .map(|(item, _)| item)
+        .collect())
+}
+
+/// Decide about an item without consulting the catalog.
+///
+/// With `orphans_only`, only parquet files can be deleted; all other items are kept.
+fn decide(item: &ObjectMeta, cutoff: DateTime<Utc>, orphans_only: bool) -> Result<Decision> {
     if cutoff < item.last_modified {
         info!(
             location = %item.location,
@@ -59,44 +194,43 @@ async fn should_delete(
             "Ignoring object",
         );
         // Not old enough; do not delete
-        return Ok(false);
+        return Ok(Decision::Keep);
     }
 
     let file_name = item.location.parts().last().context(FileNameMissingSnafu)?;
 
     if let Some(uuid) = file_name.as_ref().strip_suffix(".parquet") {
         if let Ok(object_store_id) = uuid.parse() {
-            let parquet_file = parquet_files
-                .get_by_object_store_id(object_store_id)
-                .await
-                .context(GetFileSnafu { object_store_id })?;
+            return Ok(Decision::Lookup(object_store_id));
+        }
 
-            if parquet_file.is_some() {
-                // We have a reference to this file; do not delete
-                info!(
-                    location = %item.location,
-                    deleting = false,
-                    reason = "exists in catalog",
-                    "Ignoring object",
-                );
-                return Ok(false);
-            } else {
-                info!(
-                    location = %item.location,
-                    deleting = true,
-                    reason = "not in catalog",
-                    "Scheduling file for deletion",
-                );
-            }
-        } else {
+        if orphans_only {
             info!(
                 location = %item.location,
-                deleting = true,
+                deleting = false,
                 uuid,
                 reason = "not a valid UUID",
-                "Scheduling file for deletion",
+                "Ignoring object",
             );
+            return Ok(Decision::Keep);
         }
+
+        info!(
+            location = %item.location,
+            deleting = true,
+            uuid,
+            reason = "not a valid UUID",
+            "Scheduling file for deletion",
+        );
+    } else if orphans_only {
+        info!(
+            location = %item.location,
+            deleting = false,
+            file_name = %file_name.as_ref(),
+            reason = "not a .parquet file",
+            "Ignoring object",
+        );
+        return Ok(Decision::Keep);
     } else {
         info!(
             location = %item.location,
@@ -107,7 +241,7 @@ async fn should_delete(
         );
     }
 
-    Ok(true)
+    Ok(Decision::Delete)
 }
 
 #[cfg(test)]
@@ -115,8 +249,8 @@ mod tests {
     use super::*;
     use chrono::TimeZone;
     use data_types::{
-        ColumnId, ColumnSet, CompactionLevel, NamespaceId, ParquetFile, ParquetFileParams,
-        PartitionId, SequenceNumber, ShardId, ShardIndex, TableId, Timestamp,
+        ColumnId, ColumnSet, ColumnStatsSet, CompactionLevel, NamespaceId, ParquetFile,
+        ParquetFileParams, PartitionId, SequenceNumber, ShardId, ShardIndex, TableId, Timestamp,
     };
     use iox_catalog::{interface::Catalog, mem::MemCatalog};
     use object_store::path::Path;
@@ -170,6 +304,7 @@ mod tests {
             compaction_level: CompactionLevel::Initial,
             created_at: Timestamp::new(1),
             column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::default(),
         };
 
         let parquet_file = repos
@@ -181,6 +316,17 @@ mod tests {
         (catalog, parquet_file)
     }
 
+    async fn should_delete(
+        item: &ObjectMeta,
+        cutoff: DateTime<Utc>,
+        parquet_files: &mut dyn ParquetFileRepo,
+    ) -> Result<bool> {
+        let metrics = Metrics::new(&metric::Registry::new());
+        let to_delete =
+            check_batch(vec![item.clone()], cutoff, false, parquet_files, &metrics).await?;
+        Ok(!to_delete.is_empty())
+    }
+
     #[tokio::test]
     async fn dont_delete_new_file_in_catalog() {
         let (catalog, file_in_catalog) = test_catalog().await;
@@ -328,4 +474,90 @@ mod tests {
 
         assert!(should_delete(&item, cutoff, parquet_files).await.unwrap());
     }
+
+    #[tokio::test]
+    async fn check_batch_with_one_catalog_query() {
+        let (catalog, file_in_catalog) = test_catalog().await;
+        let mut repositories = catalog.repositories().await;
+        let parquet_files = repositories.parquet_files();
+
+        let in_catalog = ParquetFilePath::new(
+            file_in_catalog.namespace_id,
+            file_in_catalog.table_id,
+            file_in_catalog.shard_id,
+            file_in_catalog.partition_id,
+            file_in_catalog.object_store_id,
+        )
+        .object_store_path();
+        let not_in_catalog = ParquetFilePath::new(
+            NamespaceId::new(1),
+            TableId::new(2),
+            ShardId::new(3),
+            PartitionId::new(4),
+            Uuid::new_v4(),
+        )
+        .object_store_path();
+
+        let cutoff = *NEWER_TIME;
+        let items: Vec<_> = [
+            in_catalog,
+            not_in_catalog.clone(),
+            Path::from("not-parquet"),
+        ]
+        .into_iter()
+        .map(|location| ObjectMeta {
+            location,
+            last_modified: *OLDER_TIME,
+            size: 0,
+        })
+        .collect();
+
+        let metric_registry = metric::Registry::new();
+        let metrics = Metrics::new(&metric_registry);
+        let to_delete = check_batch(items, cutoff, false, parquet_files, &metrics)
+            .await
+            .unwrap();
+
+        let to_delete: Vec<_> = to_delete.into_iter().map(|item| item.location).collect();
+        assert_eq!(to_delete, vec![not_in_catalog, Path::from("not-parquet")]);
+        assert_eq!(metrics.catalog_queries.fetch(), 1);
+    }
+
+    #[tokio::test]
+    async fn orphans_only_keeps_objects_that_are_not_parquet_files() {
+        let metric_registry = Arc::new(metric::Registry::new());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(Arc::clone(&metric_registry)));
+        let mut repositories = catalog.repositories().await;
+        let parquet_files = repositories.parquet_files();
+
+        let orphan = ParquetFilePath::new(
+            NamespaceId::new(1),
+            TableId::new(2),
+            ShardId::new(3),
+            PartitionId::new(4),
+            Uuid::new_v4(),
+        )
+        .object_store_path();
+
+        let items: Vec<_> = [
+            orphan.clone(),
+            Path::from("1/2/3/4/report.compaction.json"),
+            Path::from("not-a-uuid.parquet"),
+        ]
+        .into_iter()
+        .map(|location| ObjectMeta {
+            location,
+            last_modified: *OLDER_TIME,
+            size: 10,
+        })
+        .collect();
+
+        let metrics = Metrics::new(&metric::Registry::new());
+        let to_delete = check_batch(items, *NEWER_TIME, true, parquet_files, &metrics)
+            .await
+            .unwrap();
+
+        let to_delete: Vec<_> = to_delete.into_iter().map(|item| item.location).collect();
+        assert_eq!(to_delete, vec![orphan]);
+    }
 }
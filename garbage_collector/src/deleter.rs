@@ -1,16 +1,44 @@
 use futures::{StreamExt, TryStreamExt};
+use metric::U64Counter;
 use object_store::{DynObjectStore, ObjectMeta};
 use observability_deps::tracing::info;
 use snafu::prelude::*;
 use std::sync::Arc;
 use tokio::sync::mpsc;
 
+/// Metrics of the deleter stage.
+#[derive(Debug)]
+pub(crate) struct Metrics {
+    /// Objects deleted from the object store.
+    deleted: U64Counter,
+
+    /// Objects that would have been deleted without the dry run.
+    skipped: U64Counter,
+}
+
+impl Metrics {
+    pub(crate) fn new(metric_registry: &metric::Registry) -> Self {
+        let objects = metric_registry.register_metric::<U64Counter>(
+            "gc_deleter_objects",
+            "Number of object store items deleted by the garbage collector",
+        );
+
+        Self {
+            deleted: objects.recorder(&[("result", "deleted")]),
+            skipped: objects.recorder(&[("result", "dry_run")]),
+        }
+    }
+}
+
 pub(crate) async fn perform(
     object_store: Arc<DynObjectStore>,
     dry_run: bool,
     concurrent_deletes: usize,
+    metrics: Metrics,
     items: mpsc::Receiver<ObjectMeta>,
 ) -> Result<()> {
+    let metrics = &metrics;
+
     tokio_stream::wrappers::ReceiverStream::new(items)
         .map(|item| {
             let object_store = Arc::clone(&object_store);
@@ -19,13 +47,18 @@ pub(crate) async fn perform(
                 let path = item.location;
                 if dry_run {
                     info!(?path, "Not deleting due to dry run");
+                    metrics.skipped.inc(1);
                     Ok(())
                 } else {
                     info!("Deleting {path}");
-                    object_store
+                    let result = object_store
                         .delete(&path)
                         .await
-                        .context(DeletingSnafu { path })
+                        .context(DeletingSnafu { path });
+                    if result.is_ok() {
+                        metrics.deleted.inc(1);
+                    }
+                    result
                 }
             }
         })
@@ -19,7 +19,7 @@ use chrono::{DateTime, Utc};
 use chrono_english::{parse_date_string, Dialect};
 use clap::Parser;
 use iox_catalog::interface::Catalog;
-use object_store::DynObjectStore;
+use object_store::{path::Path, DynObjectStore};
 use observability_deps::tracing::*;
 use snafu::prelude::*;
 use std::{fmt::Debug, sync::Arc};
@@ -32,8 +32,6 @@ mod deleter;
 /// Logic for listing all files in object storage.
 mod lister;
 
-const BUFFER_SIZE: usize = 1000;
-
 /// Run the tasks that clean up old object store files that don't appear in the catalog.
 pub async fn main(config: Config) -> Result<()> {
     GarbageCollector::start(config)?.join().await
@@ -60,6 +58,7 @@ impl GarbageCollector {
             object_store,
             sub_config,
             catalog,
+            metric_registry,
         } = config;
 
         let dry_run = sub_config.dry_run;
@@ -67,20 +66,37 @@ impl GarbageCollector {
         info!(
             cutoff_arg = %sub_config.cutoff,
             cutoff_parsed = %cutoff,
+            prefix = ?sub_config.prefix,
+            orphans_only = sub_config.orphans_only,
             "GarbageCollector starting"
         );
 
         let (shutdown_tx, shutdown_rx) = broadcast::channel(1);
 
-        let (tx1, rx1) = mpsc::channel(BUFFER_SIZE);
-        let (tx2, rx2) = mpsc::channel(BUFFER_SIZE);
+        let (tx1, rx1) = mpsc::channel(sub_config.queue_depth.max(1));
+        let (tx2, rx2) = mpsc::channel(sub_config.queue_depth.max(1));
 
-        let lister = tokio::spawn(lister::perform(shutdown_rx, Arc::clone(&object_store), tx1));
-        let checker = tokio::spawn(checker::perform(catalog, cutoff, rx1, tx2));
+        let lister = tokio::spawn(lister::perform(
+            shutdown_rx,
+            Arc::clone(&object_store),
+            sub_config.prefix.as_deref().map(Path::from),
+            lister::listed_counter(&metric_registry),
+            tx1,
+        ));
+        let checker = tokio::spawn(checker::perform(
+            catalog,
+            cutoff,
+            sub_config.orphans_only,
+            sub_config.check_batch_size,
+            checker::Metrics::new(&metric_registry),
+            rx1,
+            tx2,
+        ));
         let deleter = tokio::spawn(deleter::perform(
             object_store,
             dry_run,
             sub_config.concurrent_deletes,
+            deleter::Metrics::new(&metric_registry),
             rx2,
         ));
 
@@ -130,6 +146,9 @@ pub struct Config {
 
     /// The garbage collector specific configuration
     pub sub_config: SubConfig,
+
+    /// The registry for the metrics of the individual stages
+    pub metric_registry: Arc<metric::Registry>,
 }
 
 impl Debug for Config {
@@ -160,12 +179,41 @@ pub struct SubConfig {
     )]
     cutoff: String,
 
+    /// Only list the object store items under this prefix, e.g. when the bucket is shared with
+    /// data other than IOx files.
+    #[clap(long, env = "INFLUXDB_IOX_GC_PREFIX")]
+    prefix: Option<String>,
+
+    /// Only delete orphaned parquet files, i.e. parquet files without a record in the catalog
+    /// that are older than the cutoff. Items that are not parquet files (such as compaction
+    /// reports) are kept.
+    ///
+    /// Combine with `--dry-run` to report orphaned files, e.g. those leaked by failed uploads
+    /// and interrupted compactions, without deleting them.
+    #[clap(long, env = "INFLUXDB_IOX_GC_ORPHANS_ONLY")]
+    orphans_only: bool,
+
     /// Number of concurrent object store deletion tasks
     #[clap(long, default_value_t = 5, env = "INFLUXDB_IOX_GC_CONCURRENT_DELETES")]
     concurrent_deletes: usize,
+
+    /// Number of object store items that can be queued between two stages (listing, checking
+    /// against the catalog and deleting) before the earlier stage waits for the later one.
+    #[clap(long, default_value_t = 1000, env = "INFLUXDB_IOX_GC_QUEUE_DEPTH")]
+    queue_depth: usize,
+
+    /// Maximum number of object store items that are checked against the catalog with a single
+    /// query.
+    #[clap(long, default_value_t = 100, env = "INFLUXDB_IOX_GC_CHECK_BATCH_SIZE")]
+    check_batch_size: usize,
 }
 
 impl SubConfig {
+    /// Whether files are only reported instead of being deleted.
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
     fn cutoff(&self) -> Result<DateTime<Utc>> {
         let argument = &self.cutoff;
         parse_date_string(argument, Utc::now(), Dialect::Us)
@@ -211,6 +259,7 @@ mod tests {
         object_store::{make_object_store, ObjectStoreConfig},
     };
     use filetime::FileTime;
+    use metric::{Attributes, Metric, U64Counter};
     use std::{fs, iter, path::PathBuf};
     use tempfile::TempDir;
 
@@ -221,6 +270,7 @@ mod tests {
         let setup = OldFileSetup::new();
 
         let config = build_config(setup.data_dir_arg(), []).await;
+        let metric_registry = Arc::clone(&config.metric_registry);
         main(config).await.unwrap();
 
         assert!(
@@ -228,6 +278,30 @@ mod tests {
             "The path {} should have been deleted",
             setup.file_path.as_path().display(),
         );
+
+        let counter = |name, attributes: Attributes| {
+            metric_registry
+                .get_instrument::<Metric<U64Counter>>(name)
+                .unwrap()
+                .get_observer(&attributes)
+                .unwrap()
+                .fetch()
+        };
+        assert_eq!(counter("gc_lister_objects", Attributes::from(&[])), 1);
+        assert_eq!(
+            counter(
+                "gc_checker_objects",
+                Attributes::from(&[("decision", "delete")])
+            ),
+            1
+        );
+        assert_eq!(
+            counter(
+                "gc_deleter_objects",
+                Attributes::from(&[("result", "deleted")])
+            ),
+            1
+        );
     }
 
     #[tokio::test]
@@ -247,6 +321,52 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn orphans_only_preserves_files_that_are_not_parquet_files() {
+        let setup = OldFileSetup::new();
+
+        let config = build_config(setup.data_dir_arg(), ["--orphans-only"]).await;
+        main(config).await.unwrap();
+
+        assert!(
+            setup.file_path.exists(),
+            "The path {} should not have been deleted",
+            setup.file_path.as_path().display(),
+        );
+    }
+
+    #[tokio::test]
+    async fn only_lists_files_under_the_prefix() {
+        let setup = OldFileSetup::new();
+        let dir = setup.data_dir.path().join("some-dir");
+        fs::create_dir(&dir).unwrap();
+        let file_under_prefix = dir.join("some-old-file");
+        fs::write(&file_under_prefix, "dummy content").unwrap();
+        filetime::set_file_mtime(&file_under_prefix, OldFileSetup::APRIL_9_2018).unwrap();
+
+        let config = build_config(setup.data_dir_arg(), ["--prefix", "some-dir"]).await;
+        let metric_registry = Arc::clone(&config.metric_registry);
+        main(config).await.unwrap();
+
+        assert!(
+            !file_under_prefix.exists(),
+            "The path {} should have been deleted",
+            file_under_prefix.as_path().display(),
+        );
+        assert!(
+            setup.file_path.exists(),
+            "The path {} should not have been deleted",
+            setup.file_path.as_path().display(),
+        );
+        let listed = metric_registry
+            .get_instrument::<Metric<U64Counter>>("gc_lister_objects")
+            .unwrap()
+            .get_observer(&Attributes::from(&[]))
+            .unwrap()
+            .fetch();
+        assert_eq!(listed, 1);
+    }
+
     async fn build_config(data_dir: &str, args: impl IntoIterator<Item = &str> + Send) -> Config {
         let sub_config = SubConfig::parse_from(iter::once("dummy-program-name").chain(args));
         let object_store = object_store(data_dir);
@@ -256,6 +376,7 @@ mod tests {
             object_store,
             catalog,
             sub_config,
+            metric_registry: Default::default(),
         }
     }
 
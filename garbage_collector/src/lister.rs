@@ -1,5 +1,6 @@
 use futures::prelude::*;
-use object_store::{DynObjectStore, ObjectMeta};
+use metric::U64Counter;
+use object_store::{path::Path, DynObjectStore, ObjectMeta};
 use observability_deps::tracing::*;
 use snafu::prelude::*;
 use std::sync::Arc;
@@ -11,9 +12,14 @@ use tokio::{
 pub(crate) async fn perform(
     mut shutdown: broadcast::Receiver<()>,
     object_store: Arc<DynObjectStore>,
+    prefix: Option<Path>,
+    listed: U64Counter,
     checker: mpsc::Sender<ObjectMeta>,
 ) -> Result<()> {
-    let mut items = object_store.list(None).await.context(ListingSnafu)?;
+    let mut items = object_store
+        .list(prefix.as_ref())
+        .await
+        .context(ListingSnafu)?;
 
     loop {
         select! {
@@ -25,6 +31,7 @@ pub(crate) async fn perform(
                     Some(item) => {
                         let item = item.context(MalformedSnafu)?;
                         debug!(location = %item.location, "Object store item");
+                        listed.inc(1);
                         checker.send(item).await?;
                     }
                     None => {
@@ -54,4 +61,14 @@ pub enum Error {
     },
 }
 
+/// Register the counter of listed object store items.
+pub(crate) fn listed_counter(metric_registry: &metric::Registry) -> U64Counter {
+    metric_registry
+        .register_metric::<U64Counter>(
+            "gc_lister_objects",
+            "Number of object store items listed by the garbage collector",
+        )
+        .recorder(&[])
+}
+
 pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
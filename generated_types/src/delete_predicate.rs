@@ -101,6 +101,10 @@ impl TryFrom<proto::Op> for Op {
             proto::Op::Unspecified => Err(FieldViolation::required("")),
             proto::Op::Eq => Ok(Self::Eq),
             proto::Op::Ne => Ok(Self::Ne),
+            proto::Op::Lt => Ok(Self::Lt),
+            proto::Op::Le => Ok(Self::Le),
+            proto::Op::Gt => Ok(Self::Gt),
+            proto::Op::Ge => Ok(Self::Ge),
         }
     }
 }
@@ -110,6 +114,10 @@ impl From<Op> for proto::Op {
         match value {
             Op::Eq => Self::Eq,
             Op::Ne => Self::Ne,
+            Op::Lt => Self::Lt,
+            Op::Le => Self::Le,
+            Op::Gt => Self::Gt,
+            Op::Ge => Self::Ge,
         }
     }
 }
@@ -147,5 +155,15 @@ mod tests {
             op: Op::Eq,
             scalar: Scalar::String("foo".to_string()),
         });
+        round_trip(DeleteExpr {
+            column: "cost".to_string(),
+            op: Op::Gt,
+            scalar: Scalar::I64(100),
+        });
+        round_trip(DeleteExpr {
+            column: "cost".to_string(),
+            op: Op::Le,
+            scalar: Scalar::F64((1.5).into()),
+        });
     }
 }
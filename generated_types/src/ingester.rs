@@ -1,11 +1,12 @@
 use crate::{google::FieldViolation, influxdata::iox::ingester::v1 as proto};
-use data_types::TimestampRange;
+use data_types::{PartitionId, PartitionSnapshot, SequenceNumber, TimestampRange};
 use datafusion::{
     common::DataFusionError, datafusion_proto::bytes::Serializeable, logical_plan::Expr,
 };
 use predicate::{Predicate, ValueExpr};
 use prost::Message;
 use snafu::{ResultExt, Snafu};
+use std::collections::BTreeMap;
 
 fn expr_to_bytes_violation(field: impl Into<String>, e: DataFusionError) -> FieldViolation {
     FieldViolation {
@@ -32,6 +33,8 @@ pub struct IngesterQueryRequest {
     pub columns: Vec<String>,
     /// Predicate for filtering
     pub predicate: Option<Predicate>,
+    /// Only return the data of these partition snapshots, if set
+    pub snapshot: Option<BTreeMap<PartitionId, PartitionSnapshot>>,
 }
 
 impl IngesterQueryRequest {
@@ -48,6 +51,16 @@ impl IngesterQueryRequest {
             table,
             columns,
             predicate,
+            snapshot: None,
+        }
+    }
+
+    /// Only return the data of the given partition snapshots. Partitions without a snapshot are
+    /// skipped.
+    pub fn with_snapshot(self, snapshot: BTreeMap<PartitionId, PartitionSnapshot>) -> Self {
+        Self {
+            snapshot: Some(snapshot),
+            ..self
         }
     }
 }
@@ -61,11 +74,32 @@ impl TryFrom<proto::IngesterQueryRequest> for IngesterQueryRequest {
             table,
             columns,
             predicate,
+            snapshot,
         } = proto;
 
         let predicate = predicate.map(TryInto::try_into).transpose()?;
+        let snapshot = snapshot.map(|snapshot| {
+            snapshot
+                .partitions
+                .into_iter()
+                .map(|p| {
+                    (
+                        PartitionId::new(p.partition_id),
+                        PartitionSnapshot {
+                            persisted_max_sequence_number: p
+                                .persisted_max_sequence_number
+                                .map(SequenceNumber::new),
+                            max_sequence_number: SequenceNumber::new(p.max_sequence_number),
+                        },
+                    )
+                })
+                .collect()
+        });
 
-        Ok(Self::new(namespace, table, columns, predicate))
+        Ok(Self {
+            snapshot,
+            ..Self::new(namespace, table, columns, predicate)
+        })
     }
 }
 
@@ -78,6 +112,7 @@ impl TryFrom<IngesterQueryRequest> for proto::IngesterQueryRequest {
             table,
             columns,
             predicate,
+            snapshot,
         } = query;
 
         Ok(Self {
@@ -85,6 +120,18 @@ impl TryFrom<IngesterQueryRequest> for proto::IngesterQueryRequest {
             table,
             columns,
             predicate: predicate.map(TryInto::try_into).transpose()?,
+            snapshot: snapshot.map(|snapshot| proto::QuerySnapshot {
+                partitions: snapshot
+                    .into_iter()
+                    .map(|(partition_id, p)| proto::PartitionSnapshot {
+                        partition_id: partition_id.get(),
+                        persisted_max_sequence_number: p
+                            .persisted_max_sequence_number
+                            .map(|s| s.get()),
+                        max_sequence_number: p.max_sequence_number.get(),
+                    })
+                    .collect(),
+            }),
         })
     }
 }
@@ -250,6 +297,27 @@ mod tests {
         let rust_query_converted: IngesterQueryRequest = proto_query.try_into().unwrap();
 
         assert_eq!(rust_query, rust_query_converted);
+
+        // with snapshot
+        let rust_query = rust_query.with_snapshot(BTreeMap::from([
+            (
+                PartitionId::new(1),
+                PartitionSnapshot {
+                    persisted_max_sequence_number: None,
+                    max_sequence_number: SequenceNumber::new(3),
+                },
+            ),
+            (
+                PartitionId::new(2),
+                PartitionSnapshot {
+                    persisted_max_sequence_number: Some(SequenceNumber::new(10)),
+                    max_sequence_number: SequenceNumber::new(12),
+                },
+            ),
+        ]));
+        let proto_query: proto::IngesterQueryRequest = rust_query.clone().try_into().unwrap();
+        let rust_query_converted: IngesterQueryRequest = proto_query.try_into().unwrap();
+        assert_eq!(rust_query, rust_query_converted);
     }
 
     #[test]
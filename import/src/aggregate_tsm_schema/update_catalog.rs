@@ -298,7 +298,11 @@ where
                 let sort_key = sort_key.to_columns().collect::<Vec<_>>();
                 repos
                     .partitions()
-                    .update_sort_key(partition.id, &sort_key)
+                    .update_sort_key_if_version(
+                        partition.id,
+                        &sort_key,
+                        partition.sort_key_version,
+                    )
                     .await
                     .map_err(UpdateCatalogError::CatalogError)?;
             }
@@ -1317,6 +1321,8 @@ mod tests {
             // N.B. empty sort key at this point; will return as None from the getter and will be
             // computed
             sort_key: Vec::new(),
+            sort_key_version: 0,
+            compaction_cursor: None,
         };
         let sort_key = get_sort_key(&partition, &m).1.unwrap();
         let sort_key = sort_key.to_columns().collect::<Vec<_>>();
@@ -1364,6 +1370,8 @@ mod tests {
             partition_key: PartitionKey::from("2022-06-21"),
             // N.B. sort key is already what it will computed to; here we're testing the `adjust_sort_key_columns` code path
             sort_key: vec!["host".to_string(), "arch".to_string(), "time".to_string()],
+            sort_key_version: 0,
+            compaction_cursor: None,
         };
         // ensure sort key is unchanged
         let _maybe_updated_sk = get_sort_key(&partition, &m).1;
@@ -1410,6 +1418,8 @@ mod tests {
             partition_key: PartitionKey::from("2022-06-21"),
             // N.B. is missing host so will need updating
             sort_key: vec!["arch".to_string(), "time".to_string()],
+            sort_key_version: 0,
+            compaction_cursor: None,
         };
         let sort_key = get_sort_key(&partition, &m).1.unwrap();
         let sort_key = sort_key.to_columns().collect::<Vec<_>>();
@@ -1458,6 +1468,8 @@ mod tests {
             partition_key: PartitionKey::from("2022-06-21"),
             // N.B. is missing arch so will need updating
             sort_key: vec!["host".to_string(), "time".to_string()],
+            sort_key_version: 0,
+            compaction_cursor: None,
         };
         let sort_key = get_sort_key(&partition, &m).1.unwrap();
         let sort_key = sort_key.to_columns().collect::<Vec<_>>();
@@ -0,0 +1,492 @@
+//! # Parse clauses common to many InfluxQL statements
+//!
+//! This includes measurement names and the `ON`, `FROM`, `WHERE`, `ORDER BY`, `LIMIT`, `OFFSET`,
+//! `SLIMIT` and `SOFFSET` clauses.
+
+#![allow(dead_code)]
+
+use crate::expression::{conditional_expression, Expr};
+use crate::identifier::{identifier, Identifier};
+use crate::keywords::keyword;
+use crate::literal::unsigned_integer;
+use crate::string::{regex, Regex};
+use nom::branch::alt;
+use nom::bytes::complete::tag_no_case;
+use nom::character::complete::{char, multispace0, multispace1, satisfy};
+use nom::combinator::{cut, map, not, opt, value, verify};
+use nom::multi::separated_list1;
+use nom::sequence::{pair, preceded, terminated, tuple};
+use nom::IResult;
+use std::fmt::{Display, Formatter};
+
+/// The name of a measurement, or a regular expression matching measurement names.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MeasurementName {
+    /// A measurement name
+    Name(Identifier),
+
+    /// A regular expression matching measurement names
+    Regex(Regex),
+}
+
+impl Display for MeasurementName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Name(v) => write!(f, "{}", v)?,
+            Self::Regex(v) => write!(f, "{}", v)?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a measurement name, which is either an identifier or a regular expression.
+fn measurement_name(i: &str) -> IResult<&str, MeasurementName> {
+    alt((
+        map(identifier, MeasurementName::Name),
+        map(regex, MeasurementName::Regex),
+    ))(i)
+}
+
+/// A measurement name, optionally qualified by a database and retention policy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QualifiedMeasurementName {
+    /// The database, such as `telegraf` in `telegraf.autogen.cpu`
+    pub database: Option<Identifier>,
+
+    /// The retention policy, such as `autogen` in `telegraf.autogen.cpu`
+    pub retention_policy: Option<Identifier>,
+
+    /// The measurement name or regular expression
+    pub name: MeasurementName,
+}
+
+impl Display for QualifiedMeasurementName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (&self.database, &self.retention_policy) {
+            (Some(db), Some(rp)) => write!(f, "{}.{}.{}", db, rp, self.name)?,
+            (Some(db), None) => write!(f, "{}..{}", db, self.name)?,
+            (None, Some(rp)) => write!(f, "{}.{}", rp, self.name)?,
+            (None, None) => write!(f, "{}", self.name)?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a qualified measurement name.
+///
+/// ```text
+/// qualified_measurement_name ::= measurement_name
+///                              | policy_name "." measurement_name
+///                              | db_name "." policy_name? "." measurement_name
+/// ```
+pub fn qualified_measurement_name(i: &str) -> IResult<&str, QualifiedMeasurementName> {
+    alt((
+        // db_name "." policy_name? "." measurement_name
+        map(
+            tuple((
+                terminated(identifier, char('.')),
+                terminated(opt(identifier), char('.')),
+                measurement_name,
+            )),
+            |(db, rp, name)| QualifiedMeasurementName {
+                database: Some(db),
+                retention_policy: rp,
+                name,
+            },
+        ),
+        // policy_name "." measurement_name
+        map(
+            pair(terminated(identifier, char('.')), measurement_name),
+            |(rp, name)| QualifiedMeasurementName {
+                database: None,
+                retention_policy: Some(rp),
+                name,
+            },
+        ),
+        map(measurement_name, |name| QualifiedMeasurementName {
+            database: None,
+            retention_policy: None,
+            name,
+        }),
+    ))(i)
+}
+
+/// Parse an `ON` clause, returning the name of the database.
+///
+/// ```text
+/// on_clause ::= "ON" identifier
+/// ```
+pub fn on_clause(i: &str) -> IResult<&str, Identifier> {
+    preceded(
+        pair(multispace0, keyword("ON")),
+        cut(preceded(multispace1, identifier)),
+    )(i)
+}
+
+/// Parse a `FROM` clause.
+///
+/// ```text
+/// from_clause ::= "FROM" qualified_measurement_name ( "," qualified_measurement_name )*
+/// ```
+pub fn from_clause(i: &str) -> IResult<&str, Vec<QualifiedMeasurementName>> {
+    preceded(
+        pair(multispace0, keyword("FROM")),
+        cut(separated_list1(
+            preceded(multispace0, char(',')),
+            preceded(multispace0, qualified_measurement_name),
+        )),
+    )(i)
+}
+
+/// Parse a `WHERE` clause, returning its conditional expression.
+///
+/// ```text
+/// where_clause ::= "WHERE" expr
+/// ```
+pub fn where_clause(i: &str) -> IResult<&str, Expr> {
+    preceded(
+        pair(multispace0, keyword("WHERE")),
+        cut(conditional_expression),
+    )(i)
+}
+
+/// Parse the `FROM` and `WHERE` clauses of a statement that requires at least one of them, such
+/// as `DELETE` and `DROP SERIES`.
+///
+/// ```text
+/// from_or_where_clauses ::= from_clause | where_clause | from_clause where_clause
+/// ```
+pub fn from_or_where_clauses(
+    i: &str,
+) -> IResult<&str, (Option<Vec<QualifiedMeasurementName>>, Option<Expr>)> {
+    verify(
+        pair(opt(from_clause), opt(where_clause)),
+        |(from, condition): &(Option<Vec<QualifiedMeasurementName>>, Option<Expr>)| {
+            from.is_some() || condition.is_some()
+        },
+    )(i)
+}
+
+/// The sort order of an `ORDER BY` clause.
+///
+/// InfluxQL only supports ordering by time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrderByClause {
+    /// Ascending time order, which is the default
+    Ascending,
+
+    /// Descending time order
+    Descending,
+}
+
+impl Display for OrderByClause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ascending => f.write_str("ORDER BY TIME ASC")?,
+            Self::Descending => f.write_str("ORDER BY TIME DESC")?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse an `ORDER BY` clause.
+///
+/// ```text
+/// order_by_clause ::= "ORDER" "BY" ( "TIME" ( "ASC" | "DESC" )? | "ASC" | "DESC" )
+/// ```
+pub fn order_by_clause(i: &str) -> IResult<&str, OrderByClause> {
+    let order = || {
+        preceded(
+            multispace1,
+            alt((
+                value(OrderByClause::Ascending, keyword("ASC")),
+                value(OrderByClause::Descending, keyword("DESC")),
+            )),
+        )
+    };
+
+    preceded(
+        tuple((
+            multispace0,
+            keyword("ORDER"),
+            multispace1,
+            cut(keyword("BY")),
+        )),
+        cut(alt((
+            // "TIME" ( "ASC" | "DESC" )?
+            map(
+                preceded(
+                    pair(multispace1, terminated(tag_no_case("TIME"), not_ident_char)),
+                    opt(order()),
+                ),
+                |order| order.unwrap_or(OrderByClause::Ascending),
+            ),
+            order(),
+        ))),
+    )(i)
+}
+
+/// Succeeds if the input does not continue with a character that is valid within an unquoted
+/// identifier.
+fn not_ident_char(i: &str) -> IResult<&str, ()> {
+    not(satisfy(|c| c.is_ascii_alphanumeric() || c == '_'))(i)
+}
+
+/// Parse a `LIMIT` clause.
+///
+/// ```text
+/// limit_clause ::= "LIMIT" unsigned_integer
+/// ```
+pub fn limit_clause(i: &str) -> IResult<&str, u64> {
+    unsigned_clause("LIMIT")(i)
+}
+
+/// Parse an `OFFSET` clause.
+///
+/// ```text
+/// offset_clause ::= "OFFSET" unsigned_integer
+/// ```
+pub fn offset_clause(i: &str) -> IResult<&str, u64> {
+    unsigned_clause("OFFSET")(i)
+}
+
+/// Parse an `SLIMIT` clause.
+///
+/// ```text
+/// slimit_clause ::= "SLIMIT" unsigned_integer
+/// ```
+pub fn slimit_clause(i: &str) -> IResult<&str, u64> {
+    unsigned_clause("SLIMIT")(i)
+}
+
+/// Parse an `SOFFSET` clause.
+///
+/// ```text
+/// soffset_clause ::= "SOFFSET" unsigned_integer
+/// ```
+pub fn soffset_clause(i: &str) -> IResult<&str, u64> {
+    unsigned_clause("SOFFSET")(i)
+}
+
+/// Parse a clause consisting of the keyword `name` followed by an unsigned integer.
+fn unsigned_clause<'a>(name: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, u64> {
+    preceded(
+        pair(multispace0, keyword(name)),
+        cut(preceded(multispace1, unsigned_integer)),
+    )
+}
+
+/// Writes `items` to `f`, separated by commas.
+pub fn write_list<T: Display>(f: &mut Formatter<'_>, items: &[T]) -> std::fmt::Result {
+    for (idx, item) in items.iter().enumerate() {
+        if idx > 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "{}", item)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assert_failure;
+
+    #[test]
+    fn test_qualified_measurement_name() {
+        let (_, got) = qualified_measurement_name("cpu").unwrap();
+        assert_eq!(
+            got,
+            QualifiedMeasurementName {
+                database: None,
+                retention_policy: None,
+                name: MeasurementName::Name(Identifier::Unquoted("cpu".into())),
+            }
+        );
+
+        let (_, got) = qualified_measurement_name("autogen./cpu|mem/").unwrap();
+        assert_eq!(
+            got,
+            QualifiedMeasurementName {
+                database: None,
+                retention_policy: Some(Identifier::Unquoted("autogen".into())),
+                name: MeasurementName::Regex("cpu|mem".into()),
+            }
+        );
+
+        let (_, got) = qualified_measurement_name(r#"telegraf.autogen."cpu load""#).unwrap();
+        assert_eq!(
+            got,
+            QualifiedMeasurementName {
+                database: Some(Identifier::Unquoted("telegraf".into())),
+                retention_policy: Some(Identifier::Unquoted("autogen".into())),
+                name: MeasurementName::Name(Identifier::Quoted("cpu load".into())),
+            }
+        );
+
+        let (_, got) = qualified_measurement_name("telegraf..cpu").unwrap();
+        assert_eq!(
+            got,
+            QualifiedMeasurementName {
+                database: Some(Identifier::Unquoted("telegraf".into())),
+                retention_policy: None,
+                name: MeasurementName::Name(Identifier::Unquoted("cpu".into())),
+            }
+        );
+
+        // Fallible cases
+
+        // keywords must be quoted
+        qualified_measurement_name("where").unwrap_err();
+    }
+
+    #[test]
+    fn test_display_qualified_measurement_name() {
+        for input in [
+            "cpu",
+            "/cpu|mem/",
+            "autogen.cpu",
+            "telegraf.autogen.cpu",
+            "telegraf..cpu",
+            r#""my db".autogen./c\/pu/"#,
+        ] {
+            let (_, got) = qualified_measurement_name(input).unwrap();
+            assert_eq!(got.to_string(), input);
+        }
+    }
+
+    #[test]
+    fn test_on_clause() {
+        let (rem, got) = on_clause(" ON telegraf FROM cpu").unwrap();
+        assert_eq!(got, Identifier::Unquoted("telegraf".into()));
+        assert_eq!(rem, " FROM cpu");
+
+        let (_, got) = on_clause(r#"on "my db""#).unwrap();
+        assert_eq!(got, Identifier::Quoted("my db".into()));
+
+        // Fallible cases
+
+        // missing database
+        assert_failure!(on_clause("ON "));
+        assert_failure!(on_clause("ON FROM"));
+
+        // not an ON clause
+        on_clause("ONE").unwrap_err();
+    }
+
+    #[test]
+    fn test_from_clause() {
+        let (rem, got) = from_clause(" FROM cpu, telegraf..mem, /^disk/ WHERE").unwrap();
+        assert_eq!(
+            got.iter().map(|m| m.to_string()).collect::<Vec<_>>(),
+            vec!["cpu", "telegraf..mem", "/^disk/"]
+        );
+        assert_eq!(rem, " WHERE");
+
+        // Fallible cases
+
+        // missing measurement
+        assert_failure!(from_clause("FROM"));
+        assert_failure!(from_clause("FROM WHERE"));
+    }
+
+    #[test]
+    fn test_where_clause() {
+        let (rem, got) = where_clause(" WHERE foo = 'bar' LIMIT 1").unwrap();
+        assert_eq!(got.to_string(), "foo = 'bar'");
+        assert_eq!(rem, " LIMIT 1");
+
+        // Fallible cases
+
+        // missing expression
+        assert_failure!(where_clause("WHERE "));
+
+        // not a WHERE clause
+        where_clause("WHEREAS foo").unwrap_err();
+    }
+
+    #[test]
+    fn test_from_or_where_clauses() {
+        let (rem, (from, condition)) = from_or_where_clauses(" FROM cpu WHERE host = 'a'").unwrap();
+        assert_eq!(from.unwrap().len(), 1);
+        assert_eq!(condition.unwrap().to_string(), "host = 'a'");
+        assert!(rem.is_empty());
+
+        let (_, (from, condition)) = from_or_where_clauses(" FROM cpu").unwrap();
+        assert!(from.is_some());
+        assert!(condition.is_none());
+
+        let (_, (from, condition)) = from_or_where_clauses(" WHERE time < 10").unwrap();
+        assert!(from.is_none());
+        assert!(condition.is_some());
+
+        // Fallible cases
+
+        // neither clause
+        from_or_where_clauses("").unwrap_err();
+        from_or_where_clauses(" LIMIT 1").unwrap_err();
+
+        // invalid clauses
+        assert_failure!(from_or_where_clauses(" FROM"));
+        assert_failure!(from_or_where_clauses(" FROM cpu WHERE"));
+    }
+
+    #[test]
+    fn test_order_by_clause() {
+        let (_, got) = order_by_clause("ORDER BY TIME").unwrap();
+        assert_eq!(got, OrderByClause::Ascending);
+
+        let (_, got) = order_by_clause(" order by time desc").unwrap();
+        assert_eq!(got, OrderByClause::Descending);
+
+        let (_, got) = order_by_clause("ORDER BY time ASC").unwrap();
+        assert_eq!(got, OrderByClause::Ascending);
+
+        let (_, got) = order_by_clause("ORDER BY DESC").unwrap();
+        assert_eq!(got, OrderByClause::Descending);
+
+        let (rem, _) = order_by_clause("ORDER BY time LIMIT 1").unwrap();
+        assert_eq!(rem, " LIMIT 1");
+
+        assert_eq!(OrderByClause::Descending.to_string(), "ORDER BY TIME DESC");
+
+        // Fallible cases
+
+        // only time is supported
+        assert_failure!(order_by_clause("ORDER BY foo"));
+        assert_failure!(order_by_clause("ORDER BY timestamp"));
+
+        // missing BY
+        assert_failure!(order_by_clause("ORDER time"));
+    }
+
+    #[test]
+    fn test_limit_offset_clauses() {
+        let (_, got) = limit_clause("LIMIT 10").unwrap();
+        assert_eq!(got, 10);
+
+        let (rem, got) = offset_clause(" offset 20 SLIMIT 1").unwrap();
+        assert_eq!(got, 20);
+        assert_eq!(rem, " SLIMIT 1");
+
+        let (_, got) = slimit_clause("SLIMIT 3").unwrap();
+        assert_eq!(got, 3);
+
+        let (_, got) = soffset_clause("SOFFSET 4").unwrap();
+        assert_eq!(got, 4);
+
+        // Fallible cases
+
+        // missing or invalid value
+        assert_failure!(limit_clause("LIMIT"));
+        assert_failure!(limit_clause("LIMIT -1"));
+        assert_failure!(offset_clause("OFFSET foo"));
+
+        // SLIMIT is not LIMIT
+        limit_clause("SLIMIT 1").unwrap_err();
+    }
+}
@@ -0,0 +1,116 @@
+//! # Parse an InfluxQL [DELETE] statement
+//!
+//! [DELETE]: https://docs.influxdata.com/influxdb/v1.8/query_language/manage-database/#delete-series-with-delete
+
+#![allow(dead_code)]
+
+use crate::common::{from_or_where_clauses, write_list, QualifiedMeasurementName};
+use crate::expression::Expr;
+use crate::keywords::keyword;
+use nom::character::complete::multispace0;
+use nom::combinator::cut;
+use nom::sequence::preceded;
+use nom::IResult;
+use std::fmt::{Display, Formatter};
+
+/// A parsed `DELETE` statement.
+///
+/// At least one of `from` and `condition` is set.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeleteStatement {
+    /// The measurements of the `FROM` clause, all measurements if not set.
+    pub from: Option<Vec<QualifiedMeasurementName>>,
+
+    /// The conditional expression of the `WHERE` clause, which selects the points to delete by
+    /// their tags and time.
+    pub condition: Option<Expr>,
+}
+
+impl Display for DeleteStatement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("DELETE")?;
+
+        if let Some(from) = &self.from {
+            f.write_str(" FROM ")?;
+            write_list(f, from)?;
+        }
+
+        if let Some(condition) = &self.condition {
+            write!(f, " WHERE {}", condition)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a `DELETE` statement.
+///
+/// ```text
+/// delete_statement ::= "DELETE" from_or_where_clauses
+/// ```
+pub fn delete_statement(i: &str) -> IResult<&str, DeleteStatement> {
+    let (i, (from, condition)) = preceded(
+        preceded(multispace0, keyword("DELETE")),
+        // a statement starting with DELETE must be a complete DELETE statement
+        cut(from_or_where_clauses),
+    )(i)?;
+
+    Ok((i, DeleteStatement { from, condition }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assert_failure;
+
+    #[test]
+    fn test_delete_statement() {
+        let (rem, got) = delete_statement("DELETE FROM cpu").unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(got.from.unwrap().len(), 1);
+        assert!(got.condition.is_none());
+
+        let (_, got) = delete_statement("delete where time < '2022-01-01T00:00:00Z'").unwrap();
+        assert!(got.from.is_none());
+        assert_eq!(
+            got.condition.unwrap().to_string(),
+            "time < '2022-01-01T00:00:00Z'"
+        );
+
+        let (rem, got) =
+            delete_statement("  DELETE FROM cpu, /^disk/ WHERE host = 'a' AND time > 10; SHOW")
+                .unwrap();
+        assert_eq!(rem, "; SHOW");
+        assert_eq!(got.from.unwrap().len(), 2);
+        assert_eq!(
+            got.condition.unwrap().to_string(),
+            "host = 'a' AND time > 10"
+        );
+
+        // Fallible cases
+
+        // not a DELETE statement
+        delete_statement("DELETES FROM cpu").unwrap_err();
+        delete_statement("DROP SERIES FROM cpu").unwrap_err();
+
+        // neither FROM nor WHERE clause
+        assert_failure!(delete_statement("DELETE"));
+        assert_failure!(delete_statement("DELETE LIMIT 1"));
+
+        // invalid clauses
+        assert_failure!(delete_statement("DELETE FROM"));
+        assert_failure!(delete_statement("DELETE FROM cpu WHERE"));
+    }
+
+    #[test]
+    fn test_display_delete_statement() {
+        for input in [
+            "DELETE FROM cpu",
+            "DELETE WHERE time < 10",
+            "DELETE FROM telegraf.autogen.cpu, /^disk/ WHERE host = 'a'",
+        ] {
+            let (_, got) = delete_statement(input).unwrap();
+            assert_eq!(got.to_string(), input);
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! # Parse the InfluxQL `DROP` statements
+//!
+//! Dispatches to the parsers of the individual `DROP` statements. Only `DROP SERIES` is
+//! supported.
+
+#![allow(dead_code)]
+
+use crate::drop_series::{drop_series, DropSeriesStatement};
+use crate::keywords::keyword;
+use nom::character::complete::{multispace0, multispace1};
+use nom::combinator::{cut, map};
+use nom::sequence::{pair, preceded};
+use nom::IResult;
+use std::fmt::{Display, Formatter};
+
+/// A parsed `DROP` statement.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DropStatement {
+    /// `DROP SERIES`
+    Series(DropSeriesStatement),
+}
+
+impl Display for DropStatement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Series(s) => write!(f, "{}", s)?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a `DROP` statement.
+///
+/// ```text
+/// drop_statement ::= "DROP" drop_series
+/// ```
+pub fn drop_statement(i: &str) -> IResult<&str, DropStatement> {
+    preceded(
+        pair(preceded(multispace0, keyword("DROP")), multispace1),
+        // a statement starting with DROP must be a complete and supported DROP statement
+        cut(map(drop_series, DropStatement::Series)),
+    )(i)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assert_failure;
+
+    #[test]
+    fn test_drop_statement() {
+        let (_, got) = drop_statement("DROP SERIES FROM cpu").unwrap();
+        assert!(matches!(got, DropStatement::Series(_)));
+
+        let (rem, got) = drop_statement("  drop\nseries where host = 'a'; SHOW").unwrap();
+        assert!(matches!(got, DropStatement::Series(_)));
+        assert_eq!(rem, "; SHOW");
+
+        // Fallible cases
+
+        // not a DROP statement
+        drop_statement("DELETE FROM cpu").unwrap_err();
+
+        // unsupported DROP statement
+        assert_failure!(drop_statement("DROP MEASUREMENT cpu"));
+        assert_failure!(drop_statement("DROP DATABASE telegraf"));
+
+        // errors of the individual statements are propagated
+        assert_failure!(drop_statement("DROP SERIES"));
+    }
+
+    #[test]
+    fn test_display_drop_statement() {
+        let input = "DROP SERIES FROM cpu WHERE host = 'a'";
+        let (_, got) = drop_statement(input).unwrap();
+        assert_eq!(got.to_string(), input);
+    }
+}
@@ -0,0 +1,101 @@
+//! # Parse an InfluxQL [DROP SERIES] statement
+//!
+//! [DROP SERIES]: https://docs.influxdata.com/influxdb/v1.8/query_language/manage-database/#drop-series-from-the-index-with-drop-series
+
+#![allow(dead_code)]
+
+use crate::common::{from_or_where_clauses, write_list, QualifiedMeasurementName};
+use crate::expression::Expr;
+use crate::keywords::keyword;
+use nom::combinator::cut;
+use nom::sequence::preceded;
+use nom::IResult;
+use std::fmt::{Display, Formatter};
+
+/// A parsed `DROP SERIES` statement.
+///
+/// At least one of `from` and `condition` is set.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DropSeriesStatement {
+    /// The measurements of the `FROM` clause, all measurements if not set.
+    pub from: Option<Vec<QualifiedMeasurementName>>,
+
+    /// The conditional expression of the `WHERE` clause, which selects the series to drop by
+    /// their tags.
+    pub condition: Option<Expr>,
+}
+
+impl Display for DropSeriesStatement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("DROP SERIES")?;
+
+        if let Some(from) = &self.from {
+            f.write_str(" FROM ")?;
+            write_list(f, from)?;
+        }
+
+        if let Some(condition) = &self.condition {
+            write!(f, " WHERE {}", condition)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a `DROP SERIES` statement, starting after the `DROP` keyword.
+///
+/// ```text
+/// drop_series ::= "SERIES" from_or_where_clauses
+/// ```
+pub fn drop_series(i: &str) -> IResult<&str, DropSeriesStatement> {
+    let (i, (from, condition)) = preceded(keyword("SERIES"), cut(from_or_where_clauses))(i)?;
+
+    Ok((i, DropSeriesStatement { from, condition }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assert_failure;
+
+    #[test]
+    fn test_drop_series() {
+        let (rem, got) = drop_series("SERIES FROM cpu").unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(got.from.unwrap().len(), 1);
+        assert!(got.condition.is_none());
+
+        let (_, got) = drop_series("series where host = 'a'").unwrap();
+        assert!(got.from.is_none());
+        assert_eq!(got.condition.unwrap().to_string(), "host = 'a'");
+
+        let (_, got) = drop_series("SERIES FROM cpu, mem WHERE host = 'a'").unwrap();
+        assert_eq!(got.from.unwrap().len(), 2);
+        assert!(got.condition.is_some());
+
+        // Fallible cases
+
+        // not DROP SERIES
+        drop_series("MEASUREMENT cpu").unwrap_err();
+        drop_series("SERIESFROM cpu").unwrap_err();
+
+        // neither FROM nor WHERE clause
+        assert_failure!(drop_series("SERIES"));
+
+        // invalid clauses
+        assert_failure!(drop_series("SERIES FROM"));
+        assert_failure!(drop_series("SERIES WHERE"));
+    }
+
+    #[test]
+    fn test_display_drop_series() {
+        for input in [
+            "DROP SERIES FROM cpu",
+            "DROP SERIES WHERE host = 'a'",
+            "DROP SERIES FROM autogen.cpu, /^disk/ WHERE host = 'a'",
+        ] {
+            let (_, got) = drop_series(&input["DROP ".len()..]).unwrap();
+            assert_eq!(got.to_string(), input);
+        }
+    }
+}
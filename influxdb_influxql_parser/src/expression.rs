@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 
+use crate::keywords::keyword;
 use crate::literal::literal_regex;
 use crate::{
     identifier::{identifier, Identifier},
@@ -9,9 +10,9 @@ use crate::{
 use nom::branch::alt;
 use nom::bytes::complete::{tag, tag_no_case};
 use nom::character::complete::{char, multispace0};
-use nom::combinator::{cut, map, value};
-use nom::multi::many0;
-use nom::sequence::{delimited, preceded, tuple};
+use nom::combinator::{cut, map, opt, value};
+use nom::multi::{many0, separated_list0};
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
 use nom::IResult;
 use std::fmt::{Display, Formatter, Write};
 
@@ -33,13 +34,38 @@ pub enum Expr {
     /// Binary operations, such as the
     /// conditional foo = 'bar' or the arithmetic 1 + 2 expressions.
     BinaryOp {
+        /// The left-hand side operand
         lhs: Box<Expr>,
+        /// The operator
         op: BinaryOperator,
+        /// The right-hand side operand
         rhs: Box<Expr>,
     },
 
     /// Nested expression, such as (foo = 'bar') or (1)
     Nested(Box<Expr>),
+
+    /// Function call, such as now() or count(foo)
+    Call {
+        /// The name of the function
+        name: String,
+        /// The arguments of the function
+        args: Vec<Expr>,
+    },
+
+    /// Wildcard, such as * or *::field, selecting all fields and / or tags
+    Wildcard(Option<WildcardType>),
+
+    /// Identifier with an explicit data type, such as foo::field or bar::float
+    VarRef {
+        /// The name of the column
+        name: Identifier,
+        /// The explicit data type of the column
+        data_type: VarRefDataType,
+    },
+
+    /// Distinct values of a field, such as DISTINCT foo
+    Distinct(Identifier),
 }
 
 impl From<Literal> for Expr {
@@ -75,6 +101,76 @@ impl Display for Expr {
             Self::UnaryOp(op, e) => write!(f, "{}{}", op, e)?,
             Self::BinaryOp { lhs, op, rhs } => write!(f, "{} {} {}", lhs, op, rhs)?,
             Self::Nested(e) => write!(f, "({})", e)?,
+            Self::Call { name, args } => {
+                write!(f, "{}(", name)?;
+                for (idx, arg) in args.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                f.write_char(')')?;
+            }
+            Self::Wildcard(None) => f.write_char('*')?,
+            Self::Wildcard(Some(typ)) => write!(f, "*::{}", typ)?,
+            Self::VarRef { name, data_type } => write!(f, "{}::{}", name, data_type)?,
+            Self::Distinct(v) => write!(f, "DISTINCT {}", v)?,
+        }
+
+        Ok(())
+    }
+}
+
+/// The kind of columns selected by a wildcard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WildcardType {
+    /// Select all tag columns, `*::tag`
+    Tag,
+
+    /// Select all field columns, `*::field`
+    Field,
+}
+
+impl Display for WildcardType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Tag => f.write_str("tag")?,
+            Self::Field => f.write_str("field")?,
+        }
+
+        Ok(())
+    }
+}
+
+/// The explicit data type of a [`Expr::VarRef`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VarRefDataType {
+    /// A float field, `::float`
+    Float,
+    /// An integer field, `::integer`
+    Integer,
+    /// An unsigned integer field, `::unsigned`
+    Unsigned,
+    /// A string field, `::string`
+    String,
+    /// A boolean field, `::boolean`
+    Boolean,
+    /// A tag, `::tag`
+    Tag,
+    /// A field of any type, `::field`
+    Field,
+}
+
+impl Display for VarRefDataType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Float => f.write_str("float")?,
+            Self::Integer => f.write_str("integer")?,
+            Self::Unsigned => f.write_str("unsigned")?,
+            Self::String => f.write_str("string")?,
+            Self::Boolean => f.write_str("boolean")?,
+            Self::Tag => f.write_str("tag")?,
+            Self::Field => f.write_str("field")?,
         }
 
         Ok(())
@@ -84,7 +180,9 @@ impl Display for Expr {
 /// An InfluxQL unary operator.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UnaryOperator {
+    /// `+`
     Plus,
+    /// `-`
     Minus,
 }
 
@@ -102,25 +200,44 @@ impl Display for UnaryOperator {
 /// An InfluxQL binary operators.
 #[derive(Clone, Debug, Copy, PartialEq, Eq)]
 pub enum BinaryOperator {
-    Add,        // +
-    Sub,        // -
-    Mul,        // *
-    Div,        // /
-    Mod,        // %
-    BitwiseAnd, // &
-    BitwiseOr,  // |
-    BitwiseXor, // ^
-    Eq,         // =
-    NotEq,      // !=
-    EqRegex,    // =~
-    NotEqRegex, // !~
-    Lt,         // <
-    LtEq,       // <=
-    Gt,         // >
-    GtEq,       // >=
-    In,         // IN
-    And,        // AND
-    Or,         // OR
+    /// `+`
+    Add,
+    /// `-`
+    Sub,
+    /// `*`
+    Mul,
+    /// `/`
+    Div,
+    /// `%`
+    Mod,
+    /// `&`
+    BitwiseAnd,
+    /// `|`
+    BitwiseOr,
+    /// `^`
+    BitwiseXor,
+    /// `=`
+    Eq,
+    /// `!=`
+    NotEq,
+    /// `=~`
+    EqRegex,
+    /// `!~`
+    NotEqRegex,
+    /// `<`
+    Lt,
+    /// `<=`
+    LtEq,
+    /// `>`
+    Gt,
+    /// `>=`
+    GtEq,
+    /// `IN`
+    In,
+    /// `AND`
+    And,
+    /// `OR`
+    Or,
 }
 
 impl Display for BinaryOperator {
@@ -175,14 +292,98 @@ fn parens(i: &str) -> IResult<&str, Expr> {
     )(i)
 }
 
-/// Parse an operand expression, such as a literal, identifier or bind parameter.
+/// Parse the parenthesised argument list of a function call.
+///
+/// Besides expressions, arguments may be regular expressions, such as `mean(/^usage/)`.
+fn call_args(i: &str) -> IResult<&str, Vec<Expr>> {
+    preceded(
+        preceded(multispace0, char('(')),
+        // an identifier followed by an opening parenthesis must be a complete function call
+        cut(terminated(
+            separated_list0(
+                preceded(multispace0, char(',')),
+                alt((
+                    map(preceded(multispace0, literal_regex), Expr::Literal),
+                    conditional_expression,
+                )),
+            ),
+            preceded(multispace0, char(')')),
+        )),
+    )(i)
+}
+
+/// Parse a function call, such as `now()` or `count(foo)`.
+fn call(i: &str) -> IResult<&str, Expr> {
+    map(pair(identifier, call_args), |(name, args)| {
+        let name = match name {
+            Identifier::Unquoted(s) | Identifier::Quoted(s) => s,
+        };
+        Expr::Call { name, args }
+    })(i)
+}
+
+/// Parse the `DISTINCT` keyword, either as the modifier `DISTINCT foo` or the function call
+/// `distinct(foo)`.
+fn distinct(i: &str) -> IResult<&str, Expr> {
+    let (i, name) = keyword("DISTINCT")(i)?;
+    alt((
+        map(call_args, |args| Expr::Call {
+            name: name.to_owned(),
+            args,
+        }),
+        map(cut(preceded(multispace0, identifier)), Expr::Distinct),
+    ))(i)
+}
+
+/// Parse a wildcard, such as `*`, `*::field` or `*::tag`.
+fn wildcard(i: &str) -> IResult<&str, Option<WildcardType>> {
+    preceded(
+        char('*'),
+        opt(preceded(
+            tag("::"),
+            cut(alt((
+                value(WildcardType::Field, tag_no_case("field")),
+                value(WildcardType::Tag, tag_no_case("tag")),
+            ))),
+        )),
+    )(i)
+}
+
+/// Parse the explicit data type of an identifier, such as `float` in `foo::float`.
+fn var_ref_data_type(i: &str) -> IResult<&str, VarRefDataType> {
+    alt((
+        value(VarRefDataType::Float, tag_no_case("float")),
+        value(VarRefDataType::Integer, tag_no_case("integer")),
+        value(VarRefDataType::Unsigned, tag_no_case("unsigned")),
+        value(VarRefDataType::String, tag_no_case("string")),
+        value(VarRefDataType::Boolean, tag_no_case("boolean")),
+        value(VarRefDataType::Tag, tag_no_case("tag")),
+        value(VarRefDataType::Field, tag_no_case("field")),
+    ))(i)
+}
+
+/// Parse an identifier with an optional explicit data type, such as `foo` or `foo::field`.
+fn var_ref(i: &str) -> IResult<&str, Expr> {
+    map(
+        pair(identifier, opt(preceded(tag("::"), cut(var_ref_data_type)))),
+        |(name, data_type)| match data_type {
+            Some(data_type) => Expr::VarRef { name, data_type },
+            None => Expr::Identifier(name),
+        },
+    )(i)
+}
+
+/// Parse an operand expression, such as a literal, function call, identifier or bind parameter.
 fn operand(i: &str) -> IResult<&str, Expr> {
     preceded(
         multispace0,
         alt((
             map(literal, Expr::Literal),
-            map(identifier, Expr::Identifier),
+            distinct,
+            call,
+            var_ref,
             map(parameter, Expr::BindParameter),
+            map(wildcard, Expr::Wildcard),
         )),
     )(i)
 }
@@ -275,10 +476,7 @@ fn conditional(i: &str) -> IResult<&str, Expr> {
 fn conjunction(i: &str) -> IResult<&str, Expr> {
     let (input, f1) = conditional(i)?;
     let (input, exprs) = many0(tuple((
-        value(
-            BinaryOperator::And,
-            preceded(multispace0, tag_no_case("and")),
-        ),
+        value(BinaryOperator::And, preceded(multispace0, keyword("AND"))),
         cut(conditional),
     )))(input)?;
     Ok((input, reduce_expr(f1, exprs)))
@@ -288,7 +486,7 @@ fn conjunction(i: &str) -> IResult<&str, Expr> {
 fn disjunction(i: &str) -> IResult<&str, Expr> {
     let (input, f1) = conjunction(i)?;
     let (input, exprs) = many0(tuple((
-        value(BinaryOperator::Or, preceded(multispace0, tag_no_case("or"))),
+        value(BinaryOperator::Or, preceded(multispace0, keyword("OR"))),
         cut(conjunction),
     )))(input)?;
     Ok((input, reduce_expr(f1, exprs)))
@@ -489,6 +687,136 @@ mod test {
         assert_failure!(conditional_expression("5 AND and OR 5"));
     }
 
+    #[test]
+    fn test_call() {
+        let (_, got) = conditional_expression("now()").unwrap();
+        assert_eq!(
+            got,
+            Expr::Call {
+                name: "now".into(),
+                args: vec![]
+            }
+        );
+
+        let (_, got) = conditional_expression("time > now() - 1h").unwrap();
+        assert_eq!(
+            got,
+            *binary_op!(
+                ident!("time"),
+                Gt,
+                binary_op!(
+                    Expr::Call {
+                        name: "now".into(),
+                        args: vec![]
+                    },
+                    Sub,
+                    Expr::Literal(crate::literal::Duration::from(3_600_000_000_000).into())
+                )
+            )
+        );
+
+        let (_, got) = conditional_expression("foo( 1 , bar ) + 2").unwrap();
+        assert_eq!(
+            got,
+            *binary_op!(
+                Expr::Call {
+                    name: "foo".into(),
+                    args: vec![1.into(), ident!("bar")]
+                },
+                Add,
+                2
+            )
+        );
+
+        // Fallible cases
+
+        // missing closing parenthesis
+        assert_failure!(conditional_expression("foo < now("));
+
+        // incomplete argument list
+        assert_failure!(conditional_expression("foo(1,)"));
+    }
+
+    #[test]
+    fn test_wildcard_var_ref_distinct() {
+        let (_, got) = conditional_expression("*").unwrap();
+        assert_eq!(got, Expr::Wildcard(None));
+
+        let (_, got) = conditional_expression("*::field").unwrap();
+        assert_eq!(got, Expr::Wildcard(Some(WildcardType::Field)));
+
+        let (_, got) = conditional_expression("*::TAG").unwrap();
+        assert_eq!(got, Expr::Wildcard(Some(WildcardType::Tag)));
+
+        let (_, got) = conditional_expression("foo::float").unwrap();
+        assert_eq!(
+            got,
+            Expr::VarRef {
+                name: Identifier::Unquoted("foo".into()),
+                data_type: VarRefDataType::Float
+            }
+        );
+
+        let (_, got) = conditional_expression("count(*)").unwrap();
+        assert_eq!(
+            got,
+            Expr::Call {
+                name: "count".into(),
+                args: vec![Expr::Wildcard(None)]
+            }
+        );
+
+        let (_, got) = conditional_expression("mean(/^usage/)").unwrap();
+        assert_eq!(
+            got,
+            Expr::Call {
+                name: "mean".into(),
+                args: vec![regex!("^usage")]
+            }
+        );
+
+        let (_, got) = conditional_expression("DISTINCT foo").unwrap();
+        assert_eq!(got, Expr::Distinct(Identifier::Unquoted("foo".into())));
+
+        let (_, got) = conditional_expression("count(distinct foo)").unwrap();
+        assert_eq!(
+            got,
+            Expr::Call {
+                name: "count".into(),
+                args: vec![Expr::Distinct(Identifier::Unquoted("foo".into()))]
+            }
+        );
+
+        let (_, got) = conditional_expression("distinct(foo)").unwrap();
+        assert_eq!(
+            got,
+            Expr::Call {
+                name: "distinct".into(),
+                args: vec![ident!("foo")]
+            }
+        );
+
+        // Fallible cases
+
+        // invalid data types
+        assert_failure!(conditional_expression("*::float"));
+        assert_failure!(conditional_expression("foo::bar"));
+
+        // DISTINCT requires an identifier
+        assert_failure!(conditional_expression("DISTINCT 5"));
+    }
+
+    #[test]
+    fn test_keyword_boundaries() {
+        // operators must not match the prefix of a keyword
+        let (got, _) = conditional_expression("foo = 'a' ORDER BY time").unwrap();
+        assert_eq!(got, " ORDER BY time");
+
+        let (got, e) = conditional_expression("foo = 'a' OR(bar = 1)").unwrap();
+        assert!(got.is_empty());
+        assert_eq!(e.to_string(), "foo = 'a' OR (bar = 1)");
+    }
+
     #[test]
     fn test_regex() {
         let (_, got) = conditional_expression("foo =~ /(a > b)/").unwrap();
@@ -497,6 +825,14 @@ mod test {
         let (_, got) = conditional_expression("foo !~ /bar/").unwrap();
         assert_eq!(got, *binary_op!(ident!("foo"), NotEqRegex, regex!("bar")));
 
+        // escape sequences are preserved for the regular expression engine
+        let (_, got) = conditional_expression(r#"host =~ /^server\d+\/\\$/"#).unwrap();
+        assert_eq!(
+            got,
+            *binary_op!(ident!("host"), EqRegex, regex!(r#"^server\d+/\\$"#))
+        );
+        assert_eq!(got.to_string(), r#"host =~ /^server\d+\/\\$/"#);
+
         // Fallible cases
 
         // Expects a regex literal after regex conditional operators
@@ -562,6 +898,25 @@ mod test {
         let got = format!("{}", e);
         assert_eq!(got, "-6h30m");
 
+        // time arithmetic keeps the units of the durations
+        let (_, e) = conditional_expression("time > now() - 1h AND time < now()+1w").unwrap();
+        let got = format!("{}", e);
+        assert_eq!(got, "time > now() - 1h AND time < now() + 1w");
+
+        // function calls
+        let (_, e) = conditional_expression("now( ) - foo(1,bar)").unwrap();
+        let got = format!("{}", e);
+        assert_eq!(got, "now() - foo(1, bar)");
+
+        // wildcards, data types and DISTINCT
+        let (_, e) = conditional_expression("count( DISTINCT  foo ) + *::tag").unwrap();
+        let got = format!("{}", e);
+        assert_eq!(got, "count(DISTINCT foo) + *::tag");
+
+        let (_, e) = conditional_expression("mean(foo::integer) + max(/bar/)").unwrap();
+        let got = format!("{}", e);
+        assert_eq!(got, "mean(foo::integer) + max(/bar/)");
+
         // can't parse literal regular expressions as part of an arithmetic expression
         assert_failure!(conditional_expression(r#""foo" + /^(no|match)$/"#));
     }
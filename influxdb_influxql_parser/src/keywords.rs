@@ -127,6 +127,12 @@ fn keyword_show_to_write(i: &str) -> IResult<&str, &str> {
     ))(i)
 }
 
+/// Matches the specified keyword, case insensitively, ensuring it is followed by a character that
+/// terminates a keyword.
+pub fn keyword<'a>(keyword: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    terminated(tag_no_case(keyword), keyword_follow_char)
+}
+
 // Matches any InfluxQL reserved keyword.
 pub fn sql_keyword(i: &str) -> IResult<&str, &str> {
     // NOTE that the alt function takes a tuple with a maximum arity of 21, hence
@@ -233,4 +239,24 @@ mod test {
 
         sql_keyword("NOT_A_KEYWORD").unwrap_err();
     }
+
+    #[test]
+    fn test_keyword() {
+        let (rem, got) = keyword("SELECT")("select foo").unwrap();
+        assert_eq!(got, "select");
+        assert_eq!(rem, " foo");
+
+        let (rem, _) = keyword("FILL")("FILL(none)").unwrap();
+        assert_eq!(rem, "(none)");
+
+        keyword("SELECT")("SELECT").unwrap();
+
+        // ┌─────────────────────────────┐
+        // │       Fallible tests        │
+        // └─────────────────────────────┘
+
+        // must be followed by a terminating character
+        keyword("OR")("ORDER BY").unwrap_err();
+        keyword("SELECT")("SELECTED").unwrap_err();
+    }
 }
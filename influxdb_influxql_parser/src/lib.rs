@@ -11,12 +11,25 @@
     clippy::use_self,
     clippy::clone_on_ref_ptr
 )]
-mod expression;
-mod identifier;
+pub mod common;
+pub mod delete;
+pub mod drop;
+pub mod drop_series;
+pub mod expression;
+pub mod identifier;
 mod keywords;
-mod literal;
-mod parameter;
-mod string;
+pub mod literal;
+pub mod parameter;
+pub mod retention_policy;
+pub mod select;
+pub mod show;
+pub mod show_field_keys;
+pub mod show_measurements;
+pub mod show_tag_keys;
+pub mod show_tag_values;
+pub mod statement;
+pub mod string;
+pub mod time_range;
 
 #[cfg(test)]
 mod test_util;
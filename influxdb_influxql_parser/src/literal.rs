@@ -5,7 +5,7 @@ use crate::write_escaped;
 use nom::branch::alt;
 use nom::bytes::complete::{tag, tag_no_case};
 use nom::character::complete::digit1;
-use nom::combinator::{map, map_res, recognize, value};
+use nom::combinator::{map, map_opt, map_res, recognize, value};
 use nom::multi::fold_many1;
 use nom::sequence::{pair, separated_pair};
 use nom::IResult;
@@ -26,7 +26,7 @@ const NANOS_PER_DAY: i64 = 24 * NANOS_PER_HOUR;
 /// Number of nanoseconds in a week.
 const NANOS_PER_WEEK: i64 = 7 * NANOS_PER_DAY;
 
-// Primitive InfluxQL literal values, such as strings and regular expressions.
+/// Primitive InfluxQL literal values, such as strings and regular expressions.
 #[derive(Clone, Debug, PartialEq)]
 pub enum Literal {
     /// Unsigned integer literal.
@@ -110,7 +110,7 @@ impl Display for Literal {
 /// ```text
 /// INTEGER ::= [0-9]+
 /// ```
-fn integer(i: &str) -> IResult<&str, i64> {
+pub fn integer(i: &str) -> IResult<&str, i64> {
     map_res(digit1, |s: &str| s.parse())(i)
 }
 
@@ -121,7 +121,7 @@ fn integer(i: &str) -> IResult<&str, i64> {
 /// ```text
 /// INTEGER ::= [0-9]+
 /// ```
-fn unsigned_integer(i: &str) -> IResult<&str, u64> {
+pub fn unsigned_integer(i: &str) -> IResult<&str, u64> {
     map_res(digit1, |s: &str| s.parse())(i)
 }
 
@@ -133,7 +133,7 @@ fn unsigned_integer(i: &str) -> IResult<&str, u64> {
 /// float   ::= INTEGER "." INTEGER
 /// INTEGER ::= [0-9]+
 /// ```
-fn float(i: &str) -> IResult<&str, f64> {
+pub fn float(i: &str) -> IResult<&str, f64> {
     map_res(
         recognize(separated_pair(digit1, tag("."), digit1)),
         |s: &str| s.parse(),
@@ -170,6 +170,13 @@ impl From<i64> for Duration {
     }
 }
 
+impl Duration {
+    /// Returns the duration in nanoseconds.
+    pub fn as_nanos(&self) -> i64 {
+        self.0
+    }
+}
+
 static DIVISORS: [(i64, &str); 8] = [
     (NANOS_PER_WEEK, "w"),
     (NANOS_PER_DAY, "d"),
@@ -183,17 +190,22 @@ static DIVISORS: [(i64, &str); 8] = [
 
 impl Display for Duration {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self.0 {
-            0 => f.write_str("0s")?,
-            mut i => {
-                // only return the divisors that are > self
-                for (div, unit) in DIVISORS.iter().filter(|(div, _)| self.0 > *div) {
-                    let units = i / div;
-                    if units > 0 {
-                        write!(f, "{}{}", units, unit)?;
-                        i -= units * div;
-                    }
-                }
+        if self.0 == 0 {
+            return f.write_str("0s");
+        }
+
+        if self.0 < 0 {
+            f.write_char('-')?;
+        }
+
+        // unsigned, as the magnitude of i64::MIN does not fit into an i64
+        let mut i = self.0.unsigned_abs();
+        for (div, unit) in DIVISORS {
+            let div = div as u64;
+            let units = i / div;
+            if units > 0 {
+                write!(f, "{}{}", units, unit)?;
+                i -= units * div;
             }
         }
 
@@ -202,42 +214,56 @@ impl Display for Duration {
 }
 
 /// Parse the input for a InfluxQL duration fragment and returns the value in nanoseconds.
+///
+/// Fails if the value does not fit into 64 bits.
 fn single_duration(i: &str) -> IResult<&str, i64> {
     use DurationUnit::*;
 
-    map(
+    map_opt(
         pair(
             integer,
             alt((
-                value(Nanosecond, tag("ns")),   // nanoseconds
+                value(Nanosecond, tag("ns")),  // nanoseconds
                 value(Microsecond, tag("µs")), // microseconds
-                value(Microsecond, tag("us")),  // microseconds
-                value(Millisecond, tag("ms")),  // milliseconds
-                value(Second, tag("s")),        // seconds
-                value(Minute, tag("m")),        // minutes
-                value(Hour, tag("h")),          // hours
-                value(Day, tag("d")),           // days
-                value(Week, tag("w")),          // weeks
+                value(Microsecond, tag("us")), // microseconds
+                value(Millisecond, tag("ms")), // milliseconds
+                value(Second, tag("s")),       // seconds
+                value(Minute, tag("m")),       // minutes
+                value(Hour, tag("h")),         // hours
+                value(Day, tag("d")),          // days
+                value(Week, tag("w")),         // weeks
             )),
         ),
         |(v, unit)| match unit {
-            Nanosecond => v,
-            Microsecond => v * NANOS_PER_MICRO,
-            Millisecond => v * NANOS_PER_MILLI,
-            Second => v * NANOS_PER_SEC,
-            Minute => v * NANOS_PER_MIN,
-            Hour => v * NANOS_PER_HOUR,
-            Day => v * NANOS_PER_DAY,
-            Week => v * NANOS_PER_WEEK,
+            Nanosecond => Some(v),
+            Microsecond => v.checked_mul(NANOS_PER_MICRO),
+            Millisecond => v.checked_mul(NANOS_PER_MILLI),
+            Second => v.checked_mul(NANOS_PER_SEC),
+            Minute => v.checked_mul(NANOS_PER_MIN),
+            Hour => v.checked_mul(NANOS_PER_HOUR),
+            Day => v.checked_mul(NANOS_PER_DAY),
+            Week => v.checked_mul(NANOS_PER_WEEK),
         },
     )(i)
 }
 
 /// Parse the input for an InfluxQL duration and returns the value in nanoseconds.
-fn duration(i: &str) -> IResult<&str, Duration> {
-    map(
-        fold_many1(single_duration, || 0, |acc, fragment| acc + fragment),
-        Duration,
+///
+/// A duration is a sequence of fragments, such as `1h30m`, whose values are summed. Fails if the
+/// sum does not fit into 64 bits.
+///
+/// ```text
+/// duration      ::= ( INTEGER duration_unit )+
+/// duration_unit ::= "ns" | "us" | "µs" | "ms" | "s" | "m" | "h" | "d" | "w"
+/// ```
+pub fn duration(i: &str) -> IResult<&str, Duration> {
+    map_opt(
+        fold_many1(
+            single_duration,
+            || Some(0_i64),
+            |acc, fragment| acc?.checked_add(fragment),
+        ),
+        |v| v.map(Duration),
     )(i)
 }
 
@@ -381,6 +407,29 @@ mod test {
             got,
             Duration(10 * NANOS_PER_HOUR + 3 * NANOS_PER_MIN + 2 * NANOS_PER_SEC)
         );
+
+        for (input, want) in [
+            ("10s", 10 * NANOS_PER_SEC),
+            ("5m", 5 * NANOS_PER_MIN),
+            ("2h", 2 * NANOS_PER_HOUR),
+            ("7d", 7 * NANOS_PER_DAY),
+            ("1w", NANOS_PER_WEEK),
+        ] {
+            let (rem, got) = duration(input).unwrap();
+            assert!(rem.is_empty());
+            assert_eq!(got.as_nanos(), want);
+        }
+
+        // Fallible cases
+
+        // missing unit
+        duration("10").unwrap_err();
+
+        // overflows a single fragment
+        duration("9223372036854775807w").unwrap_err();
+
+        // overflows the sum of the fragments
+        duration("9223372036854775807ns1ns").unwrap_err();
     }
 
     #[test]
@@ -409,5 +458,17 @@ mod test {
         );
         let got = format!("{}", d);
         assert_eq!(got, "20w6d13h11m10s9ms8us500ns");
+
+        // exact multiples of a unit
+        for input in ["1w", "1d", "1h", "1m", "1s", "1ms", "1us", "1ns", "2w1d"] {
+            let (_, d) = duration(input).unwrap();
+            assert_eq!(d.to_string(), input);
+        }
+
+        let d = Duration(-(NANOS_PER_HOUR + 30 * NANOS_PER_MIN));
+        assert_eq!(d.to_string(), "-1h30m");
+
+        let d = Duration(i64::MIN);
+        assert_eq!(d.to_string(), "-15250w1d23h47m16s854ms775us808ns");
     }
 }
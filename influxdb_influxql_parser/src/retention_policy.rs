@@ -0,0 +1,477 @@
+//! # Parse the InfluxQL [CREATE RETENTION POLICY] and [ALTER RETENTION POLICY] statements
+//!
+//! [CREATE RETENTION POLICY]: https://docs.influxdata.com/influxdb/v1.8/query_language/manage-database/#create-retention-policies-with-create-retention-policy
+//! [ALTER RETENTION POLICY]: https://docs.influxdata.com/influxdb/v1.8/query_language/manage-database/#modify-retention-policies-with-alter-retention-policy
+
+#![allow(dead_code)]
+
+use crate::common::on_clause;
+use crate::identifier::{identifier, Identifier};
+use crate::keywords::keyword;
+use crate::literal::{duration, unsigned_integer, Duration};
+use nom::branch::alt;
+use nom::character::complete::{multispace0, multispace1};
+use nom::combinator::{cut, map, opt, value, verify};
+use nom::error::{Error, ErrorKind};
+use nom::sequence::{pair, preceded, tuple};
+use nom::IResult;
+use std::fmt::{Display, Formatter};
+
+/// How long a retention policy keeps data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetentionDuration {
+    /// Data is kept for the given duration.
+    ///
+    /// As in InfluxDB 1.x, a duration of zero keeps data forever.
+    Finite(Duration),
+
+    /// `INF`, data is kept forever.
+    Infinite,
+}
+
+impl RetentionDuration {
+    /// Returns how long data is kept, `None` if it is kept forever.
+    pub fn as_std_duration(&self) -> Option<std::time::Duration> {
+        match self {
+            Self::Finite(d) if d.as_nanos() > 0 => {
+                Some(std::time::Duration::from_nanos(d.as_nanos() as u64))
+            }
+            Self::Finite(_) | Self::Infinite => None,
+        }
+    }
+}
+
+impl Display for RetentionDuration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Finite(d) => write!(f, "{}", d),
+            Self::Infinite => f.write_str("INF"),
+        }
+    }
+}
+
+/// A parsed `CREATE RETENTION POLICY` statement.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CreateRetentionPolicyStatement {
+    /// The name of the retention policy.
+    pub name: Identifier,
+
+    /// The database of the `ON` clause.
+    pub database: Identifier,
+
+    /// How long the retention policy keeps data.
+    pub duration: RetentionDuration,
+
+    /// The number of copies of the data.
+    pub replication: u64,
+
+    /// The time range covered by a shard group, defaulting to a value derived from `duration`.
+    pub shard_duration: Option<Duration>,
+
+    /// Whether the retention policy becomes the default policy of the database.
+    pub default: bool,
+}
+
+impl Display for CreateRetentionPolicyStatement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "CREATE RETENTION POLICY {} ON {} DURATION {} REPLICATION {}",
+            self.name, self.database, self.duration, self.replication
+        )?;
+
+        if let Some(shard_duration) = self.shard_duration {
+            write!(f, " SHARD DURATION {}", shard_duration)?;
+        }
+
+        if self.default {
+            f.write_str(" DEFAULT")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A parsed `ALTER RETENTION POLICY` statement.
+///
+/// At least one of the options is set, the others keep their current value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AlterRetentionPolicyStatement {
+    /// The name of the retention policy.
+    pub name: Identifier,
+
+    /// The database of the `ON` clause.
+    pub database: Identifier,
+
+    /// How long the retention policy keeps data.
+    pub duration: Option<RetentionDuration>,
+
+    /// The number of copies of the data.
+    pub replication: Option<u64>,
+
+    /// The time range covered by a shard group.
+    pub shard_duration: Option<Duration>,
+
+    /// Whether the retention policy becomes the default policy of the database.
+    pub default: bool,
+}
+
+impl Display for AlterRetentionPolicyStatement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ALTER RETENTION POLICY {} ON {}",
+            self.name, self.database
+        )?;
+
+        if let Some(duration) = self.duration {
+            write!(f, " DURATION {}", duration)?;
+        }
+
+        if let Some(replication) = self.replication {
+            write!(f, " REPLICATION {}", replication)?;
+        }
+
+        if let Some(shard_duration) = self.shard_duration {
+            write!(f, " SHARD DURATION {}", shard_duration)?;
+        }
+
+        if self.default {
+            f.write_str(" DEFAULT")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a `CREATE RETENTION POLICY` statement.
+///
+/// ```text
+/// create_retention_policy ::= "CREATE" "RETENTION" "POLICY" identifier on_clause duration_option
+///                             replication_option shard_duration_option? "DEFAULT"?
+/// ```
+pub fn create_retention_policy_statement(i: &str) -> IResult<&str, CreateRetentionPolicyStatement> {
+    let (i, _) = retention_policy_keywords("CREATE")(i)?;
+
+    // a statement starting with CREATE RETENTION POLICY must be a complete statement
+    let (i, (name, database, duration, replication, shard_duration, default)) = cut(tuple((
+        preceded(multispace1, identifier),
+        on_clause,
+        duration_option,
+        replication_option,
+        opt(shard_duration_option),
+        opt(default_option),
+    )))(i)?;
+
+    Ok((
+        i,
+        CreateRetentionPolicyStatement {
+            name,
+            database,
+            duration,
+            replication,
+            shard_duration,
+            default: default.is_some(),
+        },
+    ))
+}
+
+/// An option of an `ALTER RETENTION POLICY` statement.
+#[derive(Clone, Copy)]
+enum PolicyOption {
+    Duration(RetentionDuration),
+    Replication(u64),
+    ShardDuration(Duration),
+    Default,
+}
+
+/// Parse an `ALTER RETENTION POLICY` statement.
+///
+/// ```text
+/// alter_retention_policy ::= "ALTER" "RETENTION" "POLICY" identifier on_clause policy_option+
+/// policy_option          ::= duration_option | replication_option | shard_duration_option
+///                          | "DEFAULT"
+/// ```
+///
+/// Every option may be given at most once, in any order.
+pub fn alter_retention_policy_statement(i: &str) -> IResult<&str, AlterRetentionPolicyStatement> {
+    let (i, _) = retention_policy_keywords("ALTER")(i)?;
+
+    // a statement starting with ALTER RETENTION POLICY must be a complete statement
+    let (mut i, (name, database)) = cut(pair(preceded(multispace1, identifier), on_clause))(i)?;
+
+    let mut statement = AlterRetentionPolicyStatement {
+        name,
+        database,
+        duration: None,
+        replication: None,
+        shard_duration: None,
+        default: false,
+    };
+    let mut num_options = 0;
+    while let (rem, Some(option)) = opt(alt((
+        map(duration_option, PolicyOption::Duration),
+        map(replication_option, PolicyOption::Replication),
+        map(shard_duration_option, PolicyOption::ShardDuration),
+        value(PolicyOption::Default, default_option),
+    )))(i)?
+    {
+        let duplicate = match option {
+            PolicyOption::Duration(d) => statement.duration.replace(d).is_some(),
+            PolicyOption::Replication(n) => statement.replication.replace(n).is_some(),
+            PolicyOption::ShardDuration(d) => statement.shard_duration.replace(d).is_some(),
+            PolicyOption::Default => std::mem::replace(&mut statement.default, true),
+        };
+        if duplicate {
+            return Err(nom::Err::Failure(Error::new(i, ErrorKind::Verify)));
+        }
+
+        i = rem;
+        num_options += 1;
+    }
+
+    if num_options == 0 {
+        return Err(nom::Err::Failure(Error::new(i, ErrorKind::Many1)));
+    }
+
+    Ok((i, statement))
+}
+
+/// Parse the keywords `<first> RETENTION POLICY` that start a statement.
+fn retention_policy_keywords<'a>(
+    first: &'static str,
+) -> impl FnMut(&'a str) -> IResult<&'a str, ()> {
+    value(
+        (),
+        tuple((
+            multispace0,
+            keyword(first),
+            multispace1,
+            keyword("RETENTION"),
+            multispace1,
+            keyword("POLICY"),
+        )),
+    )
+}
+
+/// Parse how long a retention policy keeps data.
+///
+/// ```text
+/// retention_duration ::= duration | "INF"
+/// ```
+fn retention_duration(i: &str) -> IResult<&str, RetentionDuration> {
+    alt((
+        value(RetentionDuration::Infinite, keyword("INF")),
+        map(duration, RetentionDuration::Finite),
+    ))(i)
+}
+
+/// Parse a `DURATION` option.
+///
+/// ```text
+/// duration_option ::= "DURATION" retention_duration
+/// ```
+fn duration_option(i: &str) -> IResult<&str, RetentionDuration> {
+    preceded(
+        pair(multispace0, keyword("DURATION")),
+        cut(preceded(multispace1, retention_duration)),
+    )(i)
+}
+
+/// Parse a `REPLICATION` option, which must be at least 1.
+///
+/// ```text
+/// replication_option ::= "REPLICATION" INTEGER
+/// ```
+fn replication_option(i: &str) -> IResult<&str, u64> {
+    preceded(
+        pair(multispace0, keyword("REPLICATION")),
+        cut(preceded(
+            multispace1,
+            verify(unsigned_integer, |n: &u64| *n >= 1),
+        )),
+    )(i)
+}
+
+/// Parse a `SHARD DURATION` option.
+///
+/// ```text
+/// shard_duration_option ::= "SHARD" "DURATION" duration
+/// ```
+fn shard_duration_option(i: &str) -> IResult<&str, Duration> {
+    preceded(
+        tuple((multispace0, keyword("SHARD"), multispace1)),
+        cut(preceded(pair(keyword("DURATION"), multispace1), duration)),
+    )(i)
+}
+
+/// Parse the `DEFAULT` option.
+fn default_option(i: &str) -> IResult<&str, ()> {
+    value((), pair(multispace0, keyword("DEFAULT")))(i)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assert_failure;
+
+    const NANOS_PER_HOUR: i64 = 60 * 60 * 1_000_000_000;
+
+    #[test]
+    fn test_retention_duration() {
+        let (_, got) = retention_duration("INF").unwrap();
+        assert_eq!(got, RetentionDuration::Infinite);
+        assert_eq!(got.as_std_duration(), None);
+
+        let (_, got) = retention_duration("1d12h").unwrap();
+        assert_eq!(
+            got,
+            RetentionDuration::Finite(Duration::from(36 * NANOS_PER_HOUR))
+        );
+        assert_eq!(
+            got.as_std_duration(),
+            Some(std::time::Duration::from_secs(36 * 60 * 60))
+        );
+
+        // zero keeps data forever
+        let (_, got) = retention_duration("0s").unwrap();
+        assert_eq!(got.as_std_duration(), None);
+
+        // Fallible cases
+        retention_duration("infinite").unwrap_err();
+        retention_duration("1").unwrap_err();
+    }
+
+    #[test]
+    fn test_create_retention_policy() {
+        let (rem, got) = create_retention_policy_statement(
+            "CREATE RETENTION POLICY one_day ON telegraf DURATION 1d REPLICATION 1",
+        )
+        .unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(
+            got,
+            CreateRetentionPolicyStatement {
+                name: Identifier::Unquoted("one_day".into()),
+                database: Identifier::Unquoted("telegraf".into()),
+                duration: RetentionDuration::Finite(Duration::from(24 * NANOS_PER_HOUR)),
+                replication: 1,
+                shard_duration: None,
+                default: false,
+            }
+        );
+
+        let (rem, got) = create_retention_policy_statement(
+            "create retention policy \"forever\" on \"my db\" duration inf replication 3 \
+             shard duration 2h default; SHOW",
+        )
+        .unwrap();
+        assert_eq!(rem, "; SHOW");
+        assert_eq!(got.name, Identifier::Quoted("forever".into()));
+        assert_eq!(got.database, Identifier::Quoted("my db".into()));
+        assert_eq!(got.duration, RetentionDuration::Infinite);
+        assert_eq!(got.replication, 3);
+        assert_eq!(got.shard_duration, Some(Duration::from(2 * NANOS_PER_HOUR)));
+        assert!(got.default);
+
+        // Fallible cases
+
+        // not a CREATE RETENTION POLICY statement
+        create_retention_policy_statement("CREATE DATABASE telegraf").unwrap_err();
+        create_retention_policy_statement("ALTER RETENTION POLICY rp ON db DEFAULT").unwrap_err();
+
+        // missing or invalid parts
+        assert_failure!(create_retention_policy_statement(
+            "CREATE RETENTION POLICY rp DURATION 1d REPLICATION 1"
+        ));
+        assert_failure!(create_retention_policy_statement(
+            "CREATE RETENTION POLICY rp ON db REPLICATION 1"
+        ));
+        assert_failure!(create_retention_policy_statement(
+            "CREATE RETENTION POLICY rp ON db DURATION 1d"
+        ));
+        assert_failure!(create_retention_policy_statement(
+            "CREATE RETENTION POLICY rp ON db DURATION 1x REPLICATION 1"
+        ));
+        assert_failure!(create_retention_policy_statement(
+            "CREATE RETENTION POLICY rp ON db DURATION 1d REPLICATION 0"
+        ));
+        assert_failure!(create_retention_policy_statement(
+            "CREATE RETENTION POLICY rp ON db DURATION 1d REPLICATION 1 SHARD DURATION INF"
+        ));
+    }
+
+    #[test]
+    fn test_alter_retention_policy() {
+        let (rem, got) =
+            alter_retention_policy_statement("ALTER RETENTION POLICY rp ON db DURATION 2w")
+                .unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(
+            got,
+            AlterRetentionPolicyStatement {
+                name: Identifier::Unquoted("rp".into()),
+                database: Identifier::Unquoted("db".into()),
+                duration: Some(RetentionDuration::Finite(Duration::from(
+                    14 * 24 * NANOS_PER_HOUR
+                ))),
+                replication: None,
+                shard_duration: None,
+                default: false,
+            }
+        );
+
+        // options in any order
+        let (_, got) = alter_retention_policy_statement(
+            "alter retention policy rp on db default shard duration 1h replication 2 duration inf",
+        )
+        .unwrap();
+        assert_eq!(got.duration, Some(RetentionDuration::Infinite));
+        assert_eq!(got.replication, Some(2));
+        assert_eq!(got.shard_duration, Some(Duration::from(NANOS_PER_HOUR)));
+        assert!(got.default);
+
+        // Fallible cases
+
+        // not an ALTER RETENTION POLICY statement
+        alter_retention_policy_statement("ALTER DATABASE telegraf").unwrap_err();
+
+        // no options
+        assert_failure!(alter_retention_policy_statement(
+            "ALTER RETENTION POLICY rp ON db"
+        ));
+
+        // duplicate options
+        assert_failure!(alter_retention_policy_statement(
+            "ALTER RETENTION POLICY rp ON db DURATION 1d DURATION 2d"
+        ));
+        assert_failure!(alter_retention_policy_statement(
+            "ALTER RETENTION POLICY rp ON db DEFAULT REPLICATION 1 DEFAULT"
+        ));
+
+        // invalid options
+        assert_failure!(alter_retention_policy_statement(
+            "ALTER RETENTION POLICY rp ON db REPLICATION x"
+        ));
+    }
+
+    #[test]
+    fn test_display_retention_policy_statements() {
+        for input in [
+            "CREATE RETENTION POLICY rp ON db DURATION 1d REPLICATION 1",
+            "CREATE RETENTION POLICY \"my rp\" ON db DURATION INF REPLICATION 3 \
+             SHARD DURATION 1w DEFAULT",
+        ] {
+            let (_, got) = create_retention_policy_statement(input).unwrap();
+            assert_eq!(got.to_string(), input);
+        }
+
+        for input in [
+            "ALTER RETENTION POLICY rp ON db DEFAULT",
+            "ALTER RETENTION POLICY rp ON db DURATION 1h30m REPLICATION 2 SHARD DURATION 1h",
+        ] {
+            let (_, got) = alter_retention_policy_statement(input).unwrap();
+            assert_eq!(got.to_string(), input);
+        }
+    }
+}
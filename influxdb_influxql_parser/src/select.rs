@@ -0,0 +1,573 @@
+//! # Parse an InfluxQL [SELECT] statement
+//!
+//! [SELECT]: https://docs.influxdata.com/influxdb/v1.8/query_language/explore-data/#the-basic-select-statement
+
+#![allow(dead_code)]
+
+use crate::common::{
+    from_clause, limit_clause, offset_clause, order_by_clause, slimit_clause, soffset_clause,
+    where_clause, write_list, OrderByClause, QualifiedMeasurementName,
+};
+use crate::expression::{conditional_expression, Expr};
+use crate::identifier::{identifier, Identifier};
+use crate::keywords::keyword;
+use crate::literal::{float, integer, literal_regex};
+use crate::string::{regex, Regex};
+use nom::branch::alt;
+use nom::bytes::complete::tag_no_case;
+use nom::character::complete::{char, multispace0, multispace1};
+use nom::combinator::{cut, map, opt, value};
+use nom::multi::separated_list1;
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+use nom::IResult;
+use std::fmt::{Display, Formatter, Write};
+
+/// A parsed InfluxQL `SELECT` statement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelectStatement {
+    /// The projection of the statement.
+    pub fields: Vec<Field>,
+
+    /// The measurements to select from.
+    pub from: Vec<QualifiedMeasurementName>,
+
+    /// The conditional expression of the `WHERE` clause.
+    pub condition: Option<Expr>,
+
+    /// The dimensions of the `GROUP BY` clause.
+    pub group_by: Option<Vec<Dimension>>,
+
+    /// The `FILL` clause, specifying how to fill empty time intervals.
+    pub fill: Option<FillClause>,
+
+    /// The time order of the result.
+    pub order_by: Option<OrderByClause>,
+
+    /// The maximum number of rows per series.
+    pub limit: Option<u64>,
+
+    /// The number of rows to skip per series.
+    pub offset: Option<u64>,
+
+    /// The maximum number of series.
+    pub series_limit: Option<u64>,
+
+    /// The number of series to skip.
+    pub series_offset: Option<u64>,
+}
+
+impl Display for SelectStatement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SELECT ")?;
+        write_list(f, &self.fields)?;
+        f.write_str(" FROM ")?;
+        write_list(f, &self.from)?;
+
+        if let Some(condition) = &self.condition {
+            write!(f, " WHERE {}", condition)?;
+        }
+
+        if let Some(group_by) = &self.group_by {
+            f.write_str(" GROUP BY ")?;
+            write_list(f, group_by)?;
+        }
+
+        if let Some(fill) = &self.fill {
+            write!(f, " {}", fill)?;
+        }
+
+        if let Some(order_by) = &self.order_by {
+            write!(f, " {}", order_by)?;
+        }
+
+        if let Some(limit) = self.limit {
+            write!(f, " LIMIT {}", limit)?;
+        }
+
+        if let Some(offset) = self.offset {
+            write!(f, " OFFSET {}", offset)?;
+        }
+
+        if let Some(series_limit) = self.series_limit {
+            write!(f, " SLIMIT {}", series_limit)?;
+        }
+
+        if let Some(series_offset) = self.series_offset {
+            write!(f, " SOFFSET {}", series_offset)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A single projection of a `SELECT` statement, such as `mean(usage) AS mean_usage`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    /// The projected expression.
+    pub expr: Expr,
+
+    /// The name of the projection in the result.
+    pub alias: Option<Identifier>,
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.expr)?;
+        if let Some(alias) = &self.alias {
+            write!(f, " AS {}", alias)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A dimension of a `GROUP BY` clause.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Dimension {
+    /// Group by time intervals, such as `time(10m)` or `time(10m, 5m)`
+    Time {
+        /// The duration of each interval.
+        interval: Expr,
+
+        /// The offset of the interval boundaries.
+        offset: Option<Expr>,
+    },
+
+    /// Group by a tag
+    Tag(Identifier),
+
+    /// Group by all tags matching a regular expression
+    Regex(Regex),
+
+    /// Group by all tags
+    Wildcard,
+}
+
+impl Display for Dimension {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Time {
+                interval,
+                offset: None,
+            } => write!(f, "time({})", interval)?,
+            Self::Time {
+                interval,
+                offset: Some(offset),
+            } => write!(f, "time({}, {})", interval, offset)?,
+            Self::Tag(v) => write!(f, "{}", v)?,
+            Self::Regex(v) => write!(f, "{}", v)?,
+            Self::Wildcard => f.write_char('*')?,
+        }
+
+        Ok(())
+    }
+}
+
+/// A numeric value of a `FILL` clause.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Number {
+    /// An integer value
+    Integer(i64),
+
+    /// A floating point value
+    Float(f64),
+}
+
+impl Display for Number {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Integer(v) => write!(f, "{}", v)?,
+            Self::Float(v) => write!(f, "{}", v)?,
+        }
+
+        Ok(())
+    }
+}
+
+/// How empty time intervals of a `GROUP BY time(...)` query are filled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FillClause {
+    /// Report `null` for empty intervals, which is the default
+    Null,
+
+    /// Omit empty intervals
+    None,
+
+    /// Report the value of the previous interval
+    Previous,
+
+    /// Interpolate linearly between the surrounding intervals
+    Linear,
+
+    /// Report the given value
+    Value(Number),
+}
+
+impl Display for FillClause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Null => f.write_str("fill(null)")?,
+            Self::None => f.write_str("fill(none)")?,
+            Self::Previous => f.write_str("fill(previous)")?,
+            Self::Linear => f.write_str("fill(linear)")?,
+            Self::Value(v) => write!(f, "fill({})", v)?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a single projection.
+///
+/// ```text
+/// field ::= ( expr | regex ) ( "AS" identifier )?
+/// ```
+fn field(i: &str) -> IResult<&str, Field> {
+    map(
+        pair(
+            alt((
+                map(preceded(multispace0, literal_regex), Expr::Literal),
+                conditional_expression,
+            )),
+            opt(preceded(
+                pair(multispace1, keyword("AS")),
+                cut(preceded(multispace1, identifier)),
+            )),
+        ),
+        |(expr, alias)| Field { expr, alias },
+    )(i)
+}
+
+/// Parse the comma-separated list of projections.
+fn field_list(i: &str) -> IResult<&str, Vec<Field>> {
+    separated_list1(preceded(multispace0, char(',')), field)(i)
+}
+
+/// Parse a `time(interval[, offset])` dimension.
+fn time_dimension(i: &str) -> IResult<&str, Dimension> {
+    preceded(
+        terminated(tag_no_case("time"), pair(multispace0, char('('))),
+        // `time` followed by an opening parenthesis must be a complete time dimension
+        cut(map(
+            terminated(
+                pair(
+                    conditional_expression,
+                    opt(preceded(
+                        preceded(multispace0, char(',')),
+                        conditional_expression,
+                    )),
+                ),
+                preceded(multispace0, char(')')),
+            ),
+            |(interval, offset)| Dimension::Time { interval, offset },
+        )),
+    )(i)
+}
+
+/// Parse a single `GROUP BY` dimension.
+fn dimension(i: &str) -> IResult<&str, Dimension> {
+    preceded(
+        multispace0,
+        alt((
+            time_dimension,
+            value(Dimension::Wildcard, char('*')),
+            map(regex, Dimension::Regex),
+            map(identifier, Dimension::Tag),
+        )),
+    )(i)
+}
+
+/// Parse the `GROUP BY` clause.
+///
+/// ```text
+/// group_by_clause ::= "GROUP" "BY" dimension ( "," dimension )*
+/// ```
+fn group_by_clause(i: &str) -> IResult<&str, Vec<Dimension>> {
+    preceded(
+        tuple((
+            multispace0,
+            keyword("GROUP"),
+            multispace1,
+            cut(keyword("BY")),
+        )),
+        cut(separated_list1(preceded(multispace0, char(',')), dimension)),
+    )(i)
+}
+
+/// Parse a possibly negative number.
+fn number(i: &str) -> IResult<&str, Number> {
+    let (i, negative) = opt(char('-'))(i)?;
+    let sign = if negative.is_some() { -1 } else { 1 };
+
+    alt((
+        map(float, move |v| Number::Float(sign as f64 * v)),
+        map(integer, move |v| Number::Integer(sign * v)),
+    ))(i)
+}
+
+/// Parse the `FILL` clause.
+///
+/// ```text
+/// fill_clause ::= "FILL" "(" ( "NULL" | "NONE" | "PREVIOUS" | "LINEAR" | number ) ")"
+/// ```
+fn fill_clause(i: &str) -> IResult<&str, FillClause> {
+    preceded(
+        pair(multispace0, keyword("FILL")),
+        cut(delimited(
+            preceded(multispace0, char('(')),
+            preceded(
+                multispace0,
+                alt((
+                    value(FillClause::Null, tag_no_case("null")),
+                    value(FillClause::None, tag_no_case("none")),
+                    value(FillClause::Previous, tag_no_case("previous")),
+                    value(FillClause::Linear, tag_no_case("linear")),
+                    map(number, FillClause::Value),
+                )),
+            ),
+            preceded(multispace0, char(')')),
+        )),
+    )(i)
+}
+
+/// Parse a `SELECT` statement.
+///
+/// ```text
+/// select_statement ::= "SELECT" field ( "," field )* from_clause where_clause?
+///                      group_by_clause? fill_clause? order_by_clause? limit_clause?
+///                      offset_clause? slimit_clause? soffset_clause?
+/// ```
+pub fn select_statement(i: &str) -> IResult<&str, SelectStatement> {
+    let (i, _) = preceded(multispace0, keyword("SELECT"))(i)?;
+
+    // a statement starting with SELECT must be a complete SELECT statement
+    let (i, fields) = cut(field_list)(i)?;
+    let (i, from) = cut(from_clause)(i)?;
+    let (i, condition) = opt(where_clause)(i)?;
+    let (i, group_by) = opt(group_by_clause)(i)?;
+    let (i, fill) = opt(fill_clause)(i)?;
+    let (i, order_by) = opt(order_by_clause)(i)?;
+    let (i, limit) = opt(limit_clause)(i)?;
+    let (i, offset) = opt(offset_clause)(i)?;
+    let (i, series_limit) = opt(slimit_clause)(i)?;
+    let (i, series_offset) = opt(soffset_clause)(i)?;
+
+    Ok((
+        i,
+        SelectStatement {
+            fields,
+            from,
+            condition,
+            group_by,
+            fill,
+            order_by,
+            limit,
+            offset,
+            series_limit,
+            series_offset,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assert_failure;
+    use crate::common::MeasurementName;
+    use crate::literal::Duration;
+
+    /// Constructs an unquoted [Identifier].
+    macro_rules! ident {
+        ($EXPR: expr) => {
+            Identifier::Unquoted($EXPR.into())
+        };
+    }
+
+    #[test]
+    fn test_select_statement() {
+        let (rem, got) = select_statement(
+            "SELECT mean(usage) AS mean_usage, host FROM telegraf.autogen.cpu \
+             WHERE time > now() - 2h GROUP BY time(10m), host fill(none) ORDER BY time DESC \
+             LIMIT 10 OFFSET 5 SLIMIT 2 SOFFSET 1",
+        )
+        .unwrap();
+        assert!(rem.is_empty());
+
+        assert_eq!(
+            got.fields,
+            vec![
+                Field {
+                    expr: Expr::Call {
+                        name: "mean".into(),
+                        args: vec![Expr::Identifier(ident!("usage"))],
+                    },
+                    alias: Some(ident!("mean_usage")),
+                },
+                Field {
+                    expr: Expr::Identifier(ident!("host")),
+                    alias: None,
+                },
+            ]
+        );
+        assert_eq!(
+            got.from,
+            vec![QualifiedMeasurementName {
+                database: Some(ident!("telegraf")),
+                retention_policy: Some(ident!("autogen")),
+                name: MeasurementName::Name(ident!("cpu")),
+            }]
+        );
+        assert_eq!(got.condition.unwrap().to_string(), "time > now() - 2h");
+        assert_eq!(
+            got.group_by.unwrap(),
+            vec![
+                Dimension::Time {
+                    interval: Expr::Literal(Duration::from(600_000_000_000).into()),
+                    offset: None,
+                },
+                Dimension::Tag(ident!("host")),
+            ]
+        );
+        assert_eq!(got.fill, Some(FillClause::None));
+        assert_eq!(got.order_by, Some(OrderByClause::Descending));
+        assert_eq!(got.limit, Some(10));
+        assert_eq!(got.offset, Some(5));
+        assert_eq!(got.series_limit, Some(2));
+        assert_eq!(got.series_offset, Some(1));
+
+        // minimal statement
+        let (_, got) = select_statement("select * from cpu").unwrap();
+        assert_eq!(
+            got,
+            SelectStatement {
+                fields: vec![Field {
+                    expr: Expr::Wildcard(None),
+                    alias: None,
+                }],
+                from: vec![QualifiedMeasurementName {
+                    database: None,
+                    retention_policy: None,
+                    name: MeasurementName::Name(ident!("cpu")),
+                }],
+                condition: None,
+                group_by: None,
+                fill: None,
+                order_by: None,
+                limit: None,
+                offset: None,
+                series_limit: None,
+                series_offset: None,
+            }
+        );
+
+        // remaining input is returned
+        let (rem, _) = select_statement("SELECT a FROM cpu; SELECT b FROM mem").unwrap();
+        assert_eq!(rem, "; SELECT b FROM mem");
+
+        // Fallible cases
+
+        // not a SELECT statement
+        select_statement("SHOW MEASUREMENTS").unwrap_err();
+
+        // missing projection or FROM clause
+        assert_failure!(select_statement("SELECT FROM cpu"));
+        assert_failure!(select_statement("SELECT a"));
+        assert_failure!(select_statement("SELECT a FROM"));
+
+        // invalid clauses
+        assert_failure!(select_statement("SELECT a FROM cpu WHERE"));
+        assert_failure!(select_statement("SELECT a FROM cpu GROUP host"));
+        assert_failure!(select_statement("SELECT a FROM cpu GROUP BY time(10m"));
+        assert_failure!(select_statement("SELECT a FROM cpu fill(foo)"));
+        assert_failure!(select_statement("SELECT a FROM cpu ORDER BY host"));
+        assert_failure!(select_statement("SELECT a FROM cpu LIMIT x"));
+        assert_failure!(select_statement("SELECT a AS FROM cpu"));
+    }
+
+    #[test]
+    fn test_field() {
+        let (_, got) = field("/^usage/").unwrap();
+        assert_eq!(
+            got,
+            Field {
+                expr: Expr::Literal(crate::literal::Literal::Regex("^usage".into())),
+                alias: None,
+            }
+        );
+
+        let (_, got) = field(r#"value::field AS "my value""#).unwrap();
+        assert_eq!(got.alias, Some(Identifier::Quoted("my value".into())));
+        assert_eq!(got.expr.to_string(), "value::field");
+
+        let (rem, got) = field("DISTINCT host FROM cpu").unwrap();
+        assert_eq!(got.expr, Expr::Distinct(ident!("host")));
+        assert_eq!(rem, " FROM cpu");
+    }
+
+    #[test]
+    fn test_dimension() {
+        let (_, got) = dimension("time(10m, -5m)").unwrap();
+        assert_eq!(got.to_string(), "time(10m, -5m)");
+
+        let (_, got) = dimension("*").unwrap();
+        assert_eq!(got, Dimension::Wildcard);
+
+        let (_, got) = dimension("/^host/").unwrap();
+        assert_eq!(got, Dimension::Regex("^host".into()));
+
+        // a tag named time
+        let (_, got) = dimension("time").unwrap();
+        assert_eq!(got, Dimension::Tag(ident!("time")));
+
+        let (_, got) = dimension(r#""region""#).unwrap();
+        assert_eq!(got, Dimension::Tag(Identifier::Quoted("region".into())));
+    }
+
+    #[test]
+    fn test_fill_clause() {
+        let (_, got) = fill_clause("fill(null)").unwrap();
+        assert_eq!(got, FillClause::Null);
+
+        let (_, got) = fill_clause(" FILL( previous )").unwrap();
+        assert_eq!(got, FillClause::Previous);
+
+        let (_, got) = fill_clause("fill(linear)").unwrap();
+        assert_eq!(got, FillClause::Linear);
+
+        let (_, got) = fill_clause("fill(-10)").unwrap();
+        assert_eq!(got, FillClause::Value(Number::Integer(-10)));
+
+        let (_, got) = fill_clause("fill(0.5)").unwrap();
+        assert_eq!(got, FillClause::Value(Number::Float(0.5)));
+
+        // Fallible cases
+
+        assert_failure!(fill_clause("fill()"));
+        assert_failure!(fill_clause("fill(none"));
+        fill_clause("filling(none)").unwrap_err();
+    }
+
+    #[test]
+    fn test_display_select_statement() {
+        for input in [
+            "SELECT * FROM cpu",
+            "SELECT *::field, host::tag FROM cpu, /^disk/",
+            "SELECT mean(usage) AS mean_usage FROM telegraf..cpu WHERE host = 'a' AND time >= now() - 2h GROUP BY time(5m, 30s), host fill(-1.5)",
+            "SELECT count(DISTINCT host) FROM autogen.cpu GROUP BY * fill(previous) ORDER BY TIME DESC LIMIT 1 OFFSET 2 SLIMIT 3 SOFFSET 4",
+            "SELECT /^usage/, \"quoted name\" AS \"alias\" FROM cpu WHERE region =~ /us-.*/ GROUP BY /^ho/",
+        ] {
+            let (_, got) = select_statement(input).unwrap();
+            assert_eq!(got.to_string(), input);
+        }
+
+        // formatting is normalized
+        let (_, got) = select_statement(
+            "select  mean( usage ) as m from cpu group by time( 10m ) order by time",
+        )
+        .unwrap();
+        assert_eq!(
+            got.to_string(),
+            "SELECT mean(usage) AS m FROM cpu GROUP BY time(10m) ORDER BY TIME ASC"
+        );
+    }
+}
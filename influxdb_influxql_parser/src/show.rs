@@ -0,0 +1,115 @@
+//! # Parse the InfluxQL `SHOW` statements
+//!
+//! Dispatches to the parsers of the individual schema exploration statements.
+
+#![allow(dead_code)]
+
+use crate::keywords::keyword;
+use crate::show_field_keys::{show_field_keys, ShowFieldKeysStatement};
+use crate::show_measurements::{show_measurements, ShowMeasurementsStatement};
+use crate::show_tag_keys::{show_tag_keys, ShowTagKeysStatement};
+use crate::show_tag_values::{show_tag_values, ShowTagValuesStatement};
+use nom::branch::alt;
+use nom::character::complete::{multispace0, multispace1};
+use nom::combinator::{cut, map};
+use nom::sequence::{pair, preceded};
+use nom::IResult;
+use std::fmt::{Display, Formatter};
+
+/// A parsed `SHOW` statement.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ShowStatement {
+    /// `SHOW MEASUREMENTS`
+    Measurements(ShowMeasurementsStatement),
+
+    /// `SHOW TAG KEYS`
+    TagKeys(ShowTagKeysStatement),
+
+    /// `SHOW TAG VALUES`
+    TagValues(ShowTagValuesStatement),
+
+    /// `SHOW FIELD KEYS`
+    FieldKeys(ShowFieldKeysStatement),
+}
+
+impl Display for ShowStatement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Measurements(s) => write!(f, "{}", s)?,
+            Self::TagKeys(s) => write!(f, "{}", s)?,
+            Self::TagValues(s) => write!(f, "{}", s)?,
+            Self::FieldKeys(s) => write!(f, "{}", s)?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a `SHOW` statement.
+///
+/// ```text
+/// show_statement ::= "SHOW" ( show_measurements | show_tag_keys | show_tag_values
+///                           | show_field_keys )
+/// ```
+pub fn show_statement(i: &str) -> IResult<&str, ShowStatement> {
+    preceded(
+        pair(preceded(multispace0, keyword("SHOW")), multispace1),
+        // a statement starting with SHOW must be a complete and supported SHOW statement
+        cut(alt((
+            map(show_measurements, ShowStatement::Measurements),
+            map(show_tag_keys, ShowStatement::TagKeys),
+            map(show_tag_values, ShowStatement::TagValues),
+            map(show_field_keys, ShowStatement::FieldKeys),
+        ))),
+    )(i)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assert_failure;
+
+    #[test]
+    fn test_show_statement() {
+        let (_, got) = show_statement("SHOW MEASUREMENTS").unwrap();
+        assert!(matches!(got, ShowStatement::Measurements(_)));
+
+        let (_, got) = show_statement("SHOW TAG KEYS").unwrap();
+        assert!(matches!(got, ShowStatement::TagKeys(_)));
+
+        let (_, got) = show_statement("show tag values with key = host").unwrap();
+        assert!(matches!(got, ShowStatement::TagValues(_)));
+
+        let (_, got) = show_statement("  SHOW\nFIELD KEYS").unwrap();
+        assert!(matches!(got, ShowStatement::FieldKeys(_)));
+
+        // remaining input is returned
+        let (rem, _) = show_statement("SHOW FIELD KEYS; SHOW TAG KEYS").unwrap();
+        assert_eq!(rem, "; SHOW TAG KEYS");
+
+        // Fallible cases
+
+        // not a SHOW statement
+        show_statement("SELECT * FROM cpu").unwrap_err();
+
+        // unsupported SHOW statement
+        assert_failure!(show_statement("SHOW DATABASES"));
+        assert_failure!(show_statement("SHOW TAG"));
+
+        // errors of the individual statements are propagated
+        assert_failure!(show_statement("SHOW TAG VALUES"));
+    }
+
+    #[test]
+    fn test_display_show_statement() {
+        for input in [
+            "SHOW MEASUREMENTS WITH MEASUREMENT =~ /^cpu/ LIMIT 1",
+            "SHOW TAG KEYS ON telegraf FROM cpu WHERE host = 'a' LIMIT 1 OFFSET 2",
+            "SHOW TAG VALUES FROM cpu WITH KEY IN (host, region) LIMIT 1",
+            "SHOW FIELD KEYS FROM cpu OFFSET 2",
+        ] {
+            let (_, got) = show_statement(input).unwrap();
+            assert_eq!(got.to_string(), input);
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! # Parse an InfluxQL [SHOW FIELD KEYS] statement
+//!
+//! [SHOW FIELD KEYS]: https://docs.influxdata.com/influxdb/v1.8/query_language/explore-schema/#show-field-keys
+
+#![allow(dead_code)]
+
+use crate::common::{
+    from_clause, limit_clause, offset_clause, on_clause, write_list, QualifiedMeasurementName,
+};
+use crate::identifier::Identifier;
+use crate::keywords::keyword;
+use nom::character::complete::multispace1;
+use nom::combinator::opt;
+use nom::sequence::{pair, preceded};
+use nom::IResult;
+use std::fmt::{Display, Formatter};
+
+/// A parsed `SHOW FIELD KEYS` statement.
+///
+/// Unlike the other `SHOW` statements, `SHOW FIELD KEYS` does not support a `WHERE` clause.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShowFieldKeysStatement {
+    /// The database of the `ON` clause, defaulting to the database of the request.
+    pub database: Option<Identifier>,
+
+    /// The measurements of the `FROM` clause.
+    pub from: Option<Vec<QualifiedMeasurementName>>,
+
+    /// The maximum number of rows per measurement.
+    pub limit: Option<u64>,
+
+    /// The number of rows to skip per measurement.
+    pub offset: Option<u64>,
+}
+
+impl Display for ShowFieldKeysStatement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SHOW FIELD KEYS")?;
+
+        if let Some(database) = &self.database {
+            write!(f, " ON {}", database)?;
+        }
+
+        if let Some(from) = &self.from {
+            f.write_str(" FROM ")?;
+            write_list(f, from)?;
+        }
+
+        if let Some(limit) = self.limit {
+            write!(f, " LIMIT {}", limit)?;
+        }
+
+        if let Some(offset) = self.offset {
+            write!(f, " OFFSET {}", offset)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a `SHOW FIELD KEYS` statement, starting after the `SHOW` keyword.
+///
+/// ```text
+/// show_field_keys ::= "FIELD" "KEYS" on_clause? from_clause? limit_clause? offset_clause?
+/// ```
+pub fn show_field_keys(i: &str) -> IResult<&str, ShowFieldKeysStatement> {
+    let (i, _) = pair(keyword("FIELD"), preceded(multispace1, keyword("KEYS")))(i)?;
+    let (i, database) = opt(on_clause)(i)?;
+    let (i, from) = opt(from_clause)(i)?;
+    let (i, limit) = opt(limit_clause)(i)?;
+    let (i, offset) = opt(offset_clause)(i)?;
+
+    Ok((
+        i,
+        ShowFieldKeysStatement {
+            database,
+            from,
+            limit,
+            offset,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assert_failure;
+
+    #[test]
+    fn test_show_field_keys() {
+        let (rem, got) = show_field_keys("FIELD KEYS").unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(
+            got,
+            ShowFieldKeysStatement {
+                database: None,
+                from: None,
+                limit: None,
+                offset: None,
+            }
+        );
+
+        let (_, got) =
+            show_field_keys("field keys on telegraf from cpu limit 10 offset 5").unwrap();
+        assert_eq!(got.database, Some(Identifier::Unquoted("telegraf".into())));
+        assert_eq!(got.from.unwrap().len(), 1);
+        assert_eq!(got.limit, Some(10));
+        assert_eq!(got.offset, Some(5));
+
+        // a WHERE clause is not consumed
+        let (rem, _) = show_field_keys("FIELD KEYS WHERE a = 1").unwrap();
+        assert_eq!(rem, " WHERE a = 1");
+
+        // Fallible cases
+
+        // not SHOW FIELD KEYS
+        show_field_keys("FIELDS").unwrap_err();
+        show_field_keys("TAG KEYS").unwrap_err();
+
+        // invalid clauses
+        assert_failure!(show_field_keys("FIELD KEYS ON"));
+        assert_failure!(show_field_keys("FIELD KEYS FROM"));
+        assert_failure!(show_field_keys("FIELD KEYS OFFSET"));
+    }
+
+    #[test]
+    fn test_display_show_field_keys() {
+        for input in [
+            "SHOW FIELD KEYS",
+            "SHOW FIELD KEYS ON telegraf",
+            "SHOW FIELD KEYS ON telegraf FROM autogen.cpu, /^disk/ LIMIT 1 OFFSET 2",
+        ] {
+            let (_, got) = show_field_keys(&input["SHOW ".len()..]).unwrap();
+            assert_eq!(got.to_string(), input);
+        }
+    }
+}
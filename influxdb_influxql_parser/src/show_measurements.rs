@@ -0,0 +1,213 @@
+//! # Parse an InfluxQL [SHOW MEASUREMENTS] statement
+//!
+//! [SHOW MEASUREMENTS]: https://docs.influxdata.com/influxdb/v1.8/query_language/explore-schema/#show-measurements
+
+#![allow(dead_code)]
+
+use crate::common::{limit_clause, offset_clause, on_clause, where_clause};
+use crate::expression::Expr;
+use crate::identifier::{identifier, Identifier};
+use crate::keywords::keyword;
+use crate::string::{regex, Regex};
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::{char, multispace0, multispace1};
+use nom::combinator::{cut, map, opt};
+use nom::sequence::{pair, preceded, tuple};
+use nom::IResult;
+use std::fmt::{Display, Formatter};
+
+/// A parsed `SHOW MEASUREMENTS` statement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShowMeasurementsStatement {
+    /// The database of the `ON` clause, defaulting to the database of the request.
+    pub database: Option<Identifier>,
+
+    /// The measurements of the `WITH MEASUREMENT` clause, all measurements if not set.
+    pub with_measurement: Option<WithMeasurementClause>,
+
+    /// The conditional expression of the `WHERE` clause.
+    pub condition: Option<Expr>,
+
+    /// The maximum number of rows.
+    pub limit: Option<u64>,
+
+    /// The number of rows to skip.
+    pub offset: Option<u64>,
+}
+
+impl Display for ShowMeasurementsStatement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SHOW MEASUREMENTS")?;
+
+        if let Some(database) = &self.database {
+            write!(f, " ON {}", database)?;
+        }
+
+        if let Some(with_measurement) = &self.with_measurement {
+            write!(f, " {}", with_measurement)?;
+        }
+
+        if let Some(condition) = &self.condition {
+            write!(f, " WHERE {}", condition)?;
+        }
+
+        if let Some(limit) = self.limit {
+            write!(f, " LIMIT {}", limit)?;
+        }
+
+        if let Some(offset) = self.offset {
+            write!(f, " OFFSET {}", offset)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The `WITH MEASUREMENT` clause of a `SHOW MEASUREMENTS` statement, selecting the measurements.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WithMeasurementClause {
+    /// Select a single measurement, `WITH MEASUREMENT = name`
+    Eq(Identifier),
+
+    /// Select all measurements matching a regular expression, `WITH MEASUREMENT =~ /regex/`
+    EqRegex(Regex),
+}
+
+impl Display for WithMeasurementClause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("WITH MEASUREMENT ")?;
+
+        match self {
+            Self::Eq(v) => write!(f, "= {}", v)?,
+            Self::EqRegex(v) => write!(f, "=~ {}", v)?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse the `WITH MEASUREMENT` clause.
+///
+/// ```text
+/// with_measurement_clause ::= "WITH" "MEASUREMENT" ( "=" identifier | "=~" regex )
+/// ```
+fn with_measurement_clause(i: &str) -> IResult<&str, WithMeasurementClause> {
+    preceded(
+        tuple((
+            multispace0,
+            keyword("WITH"),
+            multispace1,
+            cut(keyword("MEASUREMENT")),
+        )),
+        cut(preceded(
+            multispace0,
+            alt((
+                // the regular expression operator must be tried before `=`
+                map(
+                    preceded(pair(tag("=~"), multispace0), regex),
+                    WithMeasurementClause::EqRegex,
+                ),
+                map(
+                    preceded(pair(char('='), multispace0), identifier),
+                    WithMeasurementClause::Eq,
+                ),
+            )),
+        )),
+    )(i)
+}
+
+/// Parse a `SHOW MEASUREMENTS` statement, starting after the `SHOW` keyword.
+///
+/// ```text
+/// show_measurements ::= "MEASUREMENTS" on_clause? with_measurement_clause? where_clause?
+///                       limit_clause? offset_clause?
+/// ```
+pub fn show_measurements(i: &str) -> IResult<&str, ShowMeasurementsStatement> {
+    let (i, _) = keyword("MEASUREMENTS")(i)?;
+    let (i, database) = opt(on_clause)(i)?;
+    let (i, with_measurement) = opt(with_measurement_clause)(i)?;
+    let (i, condition) = opt(where_clause)(i)?;
+    let (i, limit) = opt(limit_clause)(i)?;
+    let (i, offset) = opt(offset_clause)(i)?;
+
+    Ok((
+        i,
+        ShowMeasurementsStatement {
+            database,
+            with_measurement,
+            condition,
+            limit,
+            offset,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assert_failure;
+
+    #[test]
+    fn test_show_measurements() {
+        let (rem, got) = show_measurements("MEASUREMENTS").unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(
+            got,
+            ShowMeasurementsStatement {
+                database: None,
+                with_measurement: None,
+                condition: None,
+                limit: None,
+                offset: None,
+            }
+        );
+
+        let (_, got) = show_measurements(
+            "measurements on db with measurement =~ /^cpu/ where host = 'a' limit 10 offset 5",
+        )
+        .unwrap();
+        assert_eq!(got.database, Some(Identifier::Unquoted("db".into())));
+        assert_eq!(
+            got.with_measurement,
+            Some(WithMeasurementClause::EqRegex("^cpu".into()))
+        );
+        assert_eq!(got.condition.unwrap().to_string(), "host = 'a'");
+        assert_eq!(got.limit, Some(10));
+        assert_eq!(got.offset, Some(5));
+
+        let (_, got) = show_measurements("MEASUREMENTS WITH MEASUREMENT = \"my cpu\"").unwrap();
+        assert_eq!(
+            got.with_measurement,
+            Some(WithMeasurementClause::Eq(Identifier::Quoted(
+                "my cpu".into()
+            )))
+        );
+
+        // Fallible cases
+
+        // not SHOW MEASUREMENTS
+        show_measurements("MEASUREMENT").unwrap_err();
+        show_measurements("TAG KEYS").unwrap_err();
+
+        // invalid clauses
+        assert_failure!(show_measurements("MEASUREMENTS ON"));
+        assert_failure!(show_measurements("MEASUREMENTS WITH KEY = host"));
+        assert_failure!(show_measurements("MEASUREMENTS WITH MEASUREMENT != cpu"));
+        assert_failure!(show_measurements("MEASUREMENTS WITH MEASUREMENT =~ cpu"));
+        assert_failure!(show_measurements("MEASUREMENTS WHERE"));
+        assert_failure!(show_measurements("MEASUREMENTS LIMIT x"));
+    }
+
+    #[test]
+    fn test_display_show_measurements() {
+        for input in [
+            "SHOW MEASUREMENTS",
+            "SHOW MEASUREMENTS ON telegraf WITH MEASUREMENT =~ /^cpu\\/[0-9]/ LIMIT 1 OFFSET 2",
+            "SHOW MEASUREMENTS WITH MEASUREMENT = cpu WHERE host = 'a'",
+        ] {
+            let (_, got) = show_measurements(&input["SHOW ".len()..]).unwrap();
+            assert_eq!(got.to_string(), input);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! # Parse an InfluxQL [SHOW TAG KEYS] statement
+//!
+//! [SHOW TAG KEYS]: https://docs.influxdata.com/influxdb/v1.8/query_language/explore-schema/#show-tag-keys
+
+#![allow(dead_code)]
+
+use crate::common::{
+    from_clause, limit_clause, offset_clause, on_clause, where_clause, write_list,
+    QualifiedMeasurementName,
+};
+use crate::expression::Expr;
+use crate::identifier::Identifier;
+use crate::keywords::keyword;
+use nom::character::complete::multispace1;
+use nom::combinator::opt;
+use nom::sequence::{pair, preceded};
+use nom::IResult;
+use std::fmt::{Display, Formatter};
+
+/// A parsed `SHOW TAG KEYS` statement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShowTagKeysStatement {
+    /// The database of the `ON` clause, defaulting to the database of the request.
+    pub database: Option<Identifier>,
+
+    /// The measurements of the `FROM` clause.
+    pub from: Option<Vec<QualifiedMeasurementName>>,
+
+    /// The conditional expression of the `WHERE` clause.
+    pub condition: Option<Expr>,
+
+    /// The maximum number of rows per measurement.
+    pub limit: Option<u64>,
+
+    /// The number of rows to skip per measurement.
+    pub offset: Option<u64>,
+}
+
+impl Display for ShowTagKeysStatement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SHOW TAG KEYS")?;
+
+        if let Some(database) = &self.database {
+            write!(f, " ON {}", database)?;
+        }
+
+        if let Some(from) = &self.from {
+            f.write_str(" FROM ")?;
+            write_list(f, from)?;
+        }
+
+        if let Some(condition) = &self.condition {
+            write!(f, " WHERE {}", condition)?;
+        }
+
+        if let Some(limit) = self.limit {
+            write!(f, " LIMIT {}", limit)?;
+        }
+
+        if let Some(offset) = self.offset {
+            write!(f, " OFFSET {}", offset)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse a `SHOW TAG KEYS` statement, starting after the `SHOW` keyword.
+///
+/// ```text
+/// show_tag_keys ::= "TAG" "KEYS" on_clause? from_clause? where_clause? limit_clause?
+///                   offset_clause?
+/// ```
+pub fn show_tag_keys(i: &str) -> IResult<&str, ShowTagKeysStatement> {
+    let (i, _) = pair(keyword("TAG"), preceded(multispace1, keyword("KEYS")))(i)?;
+    let (i, database) = opt(on_clause)(i)?;
+    let (i, from) = opt(from_clause)(i)?;
+    let (i, condition) = opt(where_clause)(i)?;
+    let (i, limit) = opt(limit_clause)(i)?;
+    let (i, offset) = opt(offset_clause)(i)?;
+
+    Ok((
+        i,
+        ShowTagKeysStatement {
+            database,
+            from,
+            condition,
+            limit,
+            offset,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assert_failure;
+
+    #[test]
+    fn test_show_tag_keys() {
+        let (rem, got) = show_tag_keys("TAG KEYS").unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(
+            got,
+            ShowTagKeysStatement {
+                database: None,
+                from: None,
+                condition: None,
+                limit: None,
+                offset: None,
+            }
+        );
+
+        let (_, got) = show_tag_keys(
+            "tag keys on telegraf from cpu, /^disk/ where host = 'server01' limit 10 offset 5",
+        )
+        .unwrap();
+        assert_eq!(got.database, Some(Identifier::Unquoted("telegraf".into())));
+        assert_eq!(got.from.unwrap().len(), 2);
+        assert_eq!(got.condition.unwrap().to_string(), "host = 'server01'");
+        assert_eq!(got.limit, Some(10));
+        assert_eq!(got.offset, Some(5));
+
+        // Fallible cases
+
+        // not SHOW TAG KEYS
+        show_tag_keys("TAG VALUES").unwrap_err();
+        show_tag_keys("TAGS KEYS").unwrap_err();
+
+        // invalid clauses
+        assert_failure!(show_tag_keys("TAG KEYS ON"));
+        assert_failure!(show_tag_keys("TAG KEYS FROM"));
+        assert_failure!(show_tag_keys("TAG KEYS WHERE"));
+        assert_failure!(show_tag_keys("TAG KEYS LIMIT x"));
+    }
+
+    #[test]
+    fn test_display_show_tag_keys() {
+        for input in [
+            "SHOW TAG KEYS",
+            "SHOW TAG KEYS ON telegraf",
+            "SHOW TAG KEYS FROM autogen.cpu, /^disk/",
+            "SHOW TAG KEYS ON \"my db\" FROM cpu WHERE host = 'a' LIMIT 1 OFFSET 2",
+        ] {
+            let (_, got) = show_tag_keys(&input["SHOW ".len()..]).unwrap();
+            assert_eq!(got.to_string(), input);
+        }
+    }
+}
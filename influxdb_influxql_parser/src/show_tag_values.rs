@@ -0,0 +1,288 @@
+//! # Parse an InfluxQL [SHOW TAG VALUES] statement
+//!
+//! [SHOW TAG VALUES]: https://docs.influxdata.com/influxdb/v1.8/query_language/explore-schema/#show-tag-values
+
+#![allow(dead_code)]
+
+use crate::common::{
+    from_clause, limit_clause, offset_clause, on_clause, where_clause, write_list,
+    QualifiedMeasurementName,
+};
+use crate::expression::Expr;
+use crate::identifier::{identifier, Identifier};
+use crate::keywords::keyword;
+use crate::string::{regex, Regex};
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::{char, multispace0, multispace1};
+use nom::combinator::{cut, map, opt};
+use nom::multi::separated_list1;
+use nom::sequence::{delimited, pair, preceded, tuple};
+use nom::IResult;
+use std::fmt::{Display, Formatter};
+
+/// A parsed `SHOW TAG VALUES` statement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShowTagValuesStatement {
+    /// The database of the `ON` clause, defaulting to the database of the request.
+    pub database: Option<Identifier>,
+
+    /// The measurements of the `FROM` clause.
+    pub from: Option<Vec<QualifiedMeasurementName>>,
+
+    /// The tag keys to return the values of.
+    pub with_key: WithKeyClause,
+
+    /// The conditional expression of the `WHERE` clause.
+    pub condition: Option<Expr>,
+
+    /// The maximum number of rows per measurement.
+    pub limit: Option<u64>,
+
+    /// The number of rows to skip per measurement.
+    pub offset: Option<u64>,
+}
+
+impl Display for ShowTagValuesStatement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SHOW TAG VALUES")?;
+
+        if let Some(database) = &self.database {
+            write!(f, " ON {}", database)?;
+        }
+
+        if let Some(from) = &self.from {
+            f.write_str(" FROM ")?;
+            write_list(f, from)?;
+        }
+
+        write!(f, " {}", self.with_key)?;
+
+        if let Some(condition) = &self.condition {
+            write!(f, " WHERE {}", condition)?;
+        }
+
+        if let Some(limit) = self.limit {
+            write!(f, " LIMIT {}", limit)?;
+        }
+
+        if let Some(offset) = self.offset {
+            write!(f, " OFFSET {}", offset)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The `WITH KEY` clause of a `SHOW TAG VALUES` statement, selecting the tag keys.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WithKeyClause {
+    /// Select a single tag key, `WITH KEY = key`
+    Eq(Identifier),
+
+    /// Select all tag keys except one, `WITH KEY != key`
+    NotEq(Identifier),
+
+    /// Select all tag keys matching a regular expression, `WITH KEY =~ /regex/`
+    EqRegex(Regex),
+
+    /// Select all tag keys not matching a regular expression, `WITH KEY !~ /regex/`
+    NotEqRegex(Regex),
+
+    /// Select a list of tag keys, `WITH KEY IN (key1, key2)`
+    In(Vec<Identifier>),
+}
+
+impl Display for WithKeyClause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("WITH KEY ")?;
+
+        match self {
+            Self::Eq(v) => write!(f, "= {}", v)?,
+            Self::NotEq(v) => write!(f, "!= {}", v)?,
+            Self::EqRegex(v) => write!(f, "=~ {}", v)?,
+            Self::NotEqRegex(v) => write!(f, "!~ {}", v)?,
+            Self::In(list) => {
+                f.write_str("IN (")?;
+                write_list(f, list)?;
+                f.write_str(")")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse the `WITH KEY` clause.
+///
+/// ```text
+/// with_key_clause ::= "WITH" "KEY" ( ( "=" | "!=" ) identifier
+///                                  | ( "=~" | "!~" ) regex
+///                                  | "IN" "(" identifier ( "," identifier )* ")" )
+/// ```
+fn with_key_clause(i: &str) -> IResult<&str, WithKeyClause> {
+    preceded(
+        tuple((
+            multispace0,
+            keyword("WITH"),
+            multispace1,
+            cut(keyword("KEY")),
+        )),
+        cut(preceded(
+            multispace0,
+            alt((
+                // regular expression operators must be tried before `=`
+                map(
+                    preceded(pair(tag("=~"), multispace0), regex),
+                    WithKeyClause::EqRegex,
+                ),
+                map(
+                    preceded(pair(tag("!~"), multispace0), regex),
+                    WithKeyClause::NotEqRegex,
+                ),
+                map(
+                    preceded(pair(tag("!="), multispace0), identifier),
+                    WithKeyClause::NotEq,
+                ),
+                map(
+                    preceded(pair(char('='), multispace0), identifier),
+                    WithKeyClause::Eq,
+                ),
+                map(
+                    preceded(
+                        pair(keyword("IN"), multispace0),
+                        delimited(
+                            char('('),
+                            separated_list1(
+                                preceded(multispace0, char(',')),
+                                preceded(multispace0, identifier),
+                            ),
+                            preceded(multispace0, char(')')),
+                        ),
+                    ),
+                    WithKeyClause::In,
+                ),
+            )),
+        )),
+    )(i)
+}
+
+/// Parse a `SHOW TAG VALUES` statement, starting after the `SHOW` keyword.
+///
+/// ```text
+/// show_tag_values ::= "TAG" "VALUES" on_clause? from_clause? with_key_clause where_clause?
+///                     limit_clause? offset_clause?
+/// ```
+pub fn show_tag_values(i: &str) -> IResult<&str, ShowTagValuesStatement> {
+    let (i, _) = pair(keyword("TAG"), preceded(multispace1, keyword("VALUES")))(i)?;
+    let (i, database) = opt(on_clause)(i)?;
+    let (i, from) = opt(from_clause)(i)?;
+    // the WITH KEY clause is mandatory
+    let (i, with_key) = cut(with_key_clause)(i)?;
+    let (i, condition) = opt(where_clause)(i)?;
+    let (i, limit) = opt(limit_clause)(i)?;
+    let (i, offset) = opt(offset_clause)(i)?;
+
+    Ok((
+        i,
+        ShowTagValuesStatement {
+            database,
+            from,
+            with_key,
+            condition,
+            limit,
+            offset,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assert_failure;
+
+    /// Constructs an unquoted [Identifier].
+    macro_rules! ident {
+        ($EXPR: expr) => {
+            Identifier::Unquoted($EXPR.into())
+        };
+    }
+
+    #[test]
+    fn test_show_tag_values() {
+        let (rem, got) = show_tag_values("TAG VALUES WITH KEY = host").unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(
+            got,
+            ShowTagValuesStatement {
+                database: None,
+                from: None,
+                with_key: WithKeyClause::Eq(ident!("host")),
+                condition: None,
+                limit: None,
+                offset: None,
+            }
+        );
+
+        let (_, got) = show_tag_values(
+            "tag values on telegraf from cpu with key in (host, region) where host =~ /^server/ limit 10 offset 5",
+        )
+        .unwrap();
+        assert_eq!(got.database, Some(ident!("telegraf")));
+        assert_eq!(got.from.unwrap().len(), 1);
+        assert_eq!(
+            got.with_key,
+            WithKeyClause::In(vec![ident!("host"), ident!("region")])
+        );
+        assert_eq!(got.condition.unwrap().to_string(), "host =~ /^server/");
+        assert_eq!(got.limit, Some(10));
+        assert_eq!(got.offset, Some(5));
+
+        // Fallible cases
+
+        // not SHOW TAG VALUES
+        show_tag_values("TAG KEYS").unwrap_err();
+
+        // missing or invalid WITH KEY clause
+        assert_failure!(show_tag_values("TAG VALUES"));
+        assert_failure!(show_tag_values("TAG VALUES FROM cpu"));
+        assert_failure!(show_tag_values("TAG VALUES WITH host"));
+        assert_failure!(show_tag_values("TAG VALUES WITH KEY host"));
+        assert_failure!(show_tag_values("TAG VALUES WITH KEY = /host/"));
+        assert_failure!(show_tag_values("TAG VALUES WITH KEY =~ host"));
+        assert_failure!(show_tag_values("TAG VALUES WITH KEY IN ()"));
+        assert_failure!(show_tag_values("TAG VALUES WITH KEY IN (host"));
+    }
+
+    #[test]
+    fn test_with_key_clause() {
+        let (_, got) = with_key_clause(" WITH KEY != host").unwrap();
+        assert_eq!(got, WithKeyClause::NotEq(ident!("host")));
+
+        let (_, got) = with_key_clause("WITH KEY =~ /^ho/").unwrap();
+        assert_eq!(got, WithKeyClause::EqRegex("^ho".into()));
+
+        let (_, got) = with_key_clause("WITH KEY !~/^ho/").unwrap();
+        assert_eq!(got, WithKeyClause::NotEqRegex("^ho".into()));
+
+        let (_, got) = with_key_clause(r#"WITH KEY="my tag""#).unwrap();
+        assert_eq!(got, WithKeyClause::Eq(Identifier::Quoted("my tag".into())));
+
+        let (_, got) = with_key_clause("with key in( a ,b )").unwrap();
+        assert_eq!(got, WithKeyClause::In(vec![ident!("a"), ident!("b")]));
+    }
+
+    #[test]
+    fn test_display_show_tag_values() {
+        for input in [
+            "SHOW TAG VALUES WITH KEY = host",
+            "SHOW TAG VALUES ON telegraf WITH KEY != host",
+            "SHOW TAG VALUES FROM cpu, /^disk/ WITH KEY =~ /^ho/",
+            "SHOW TAG VALUES WITH KEY !~ /^ho/ WHERE region = 'us' LIMIT 1 OFFSET 2",
+            "SHOW TAG VALUES ON \"my db\" FROM autogen.cpu WITH KEY IN (host, \"my tag\")",
+        ] {
+            let (_, got) = show_tag_values(&input["SHOW ".len()..]).unwrap();
+            assert_eq!(got.to_string(), input);
+        }
+    }
+}
@@ -0,0 +1,281 @@
+//! # Parse InfluxQL statements
+//!
+//! Dispatches to the parsers of the individual statements and splits a batch of statements,
+//! separated by semicolons, into its statements.
+
+use crate::delete::{delete_statement, DeleteStatement};
+use crate::drop::{drop_statement, DropStatement};
+use crate::retention_policy::{
+    alter_retention_policy_statement, create_retention_policy_statement,
+    AlterRetentionPolicyStatement, CreateRetentionPolicyStatement,
+};
+use crate::select::{select_statement, SelectStatement};
+use crate::show::{show_statement, ShowStatement};
+use nom::branch::alt;
+use nom::character::complete::multispace0;
+use nom::combinator::{eof, map};
+use nom::sequence::terminated;
+use nom::IResult;
+use std::fmt::{Display, Formatter};
+
+/// A parsed InfluxQL statement.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Statement {
+    /// A `SELECT` statement.
+    Select(Box<SelectStatement>),
+
+    /// A `SHOW` statement.
+    Show(ShowStatement),
+
+    /// A `DELETE` statement.
+    Delete(DeleteStatement),
+
+    /// A `DROP` statement.
+    Drop(DropStatement),
+
+    /// A `CREATE RETENTION POLICY` statement.
+    CreateRetentionPolicy(CreateRetentionPolicyStatement),
+
+    /// An `ALTER RETENTION POLICY` statement.
+    AlterRetentionPolicy(AlterRetentionPolicyStatement),
+}
+
+impl Display for Statement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Select(s) => write!(f, "{}", s),
+            Self::Show(s) => write!(f, "{}", s),
+            Self::Delete(s) => write!(f, "{}", s),
+            Self::Drop(s) => write!(f, "{}", s),
+            Self::CreateRetentionPolicy(s) => write!(f, "{}", s),
+            Self::AlterRetentionPolicy(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Parse a single statement.
+///
+/// ```text
+/// statement ::= select_statement | show_statement | delete_statement | drop_statement
+///             | create_retention_policy | alter_retention_policy
+/// ```
+pub fn statement(i: &str) -> IResult<&str, Statement> {
+    alt((
+        map(select_statement, |s| Statement::Select(Box::new(s))),
+        map(show_statement, Statement::Show),
+        map(delete_statement, Statement::Delete),
+        map(drop_statement, Statement::Drop),
+        map(
+            create_retention_policy_statement,
+            Statement::CreateRetentionPolicy,
+        ),
+        map(
+            alter_retention_policy_statement,
+            Statement::AlterRetentionPolicy,
+        ),
+    ))(i)
+}
+
+/// The error of a statement that could not be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// Description of the error.
+    pub message: String,
+
+    /// Byte offset of the error within the statement.
+    pub pos: usize,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at pos {}", self.message, self.pos)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A statement of a batch of statements, see [`parse_statements`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedStatement<'a> {
+    /// The text of the statement, without surrounding whitespace and the terminating semicolon.
+    pub source: &'a str,
+
+    /// Byte offset of the statement within the batch.
+    pub offset: usize,
+
+    /// The parsed statement or the reason why it could not be parsed.
+    pub result: Result<Statement, ParseError>,
+}
+
+/// Parse a batch of statements separated by semicolons.
+///
+/// Every statement is parsed on its own, so an invalid statement does not prevent the following
+/// statements from being parsed. Empty statements are skipped.
+pub fn parse_statements(input: &str) -> Vec<ParsedStatement<'_>> {
+    split_statements(input)
+        .into_iter()
+        .map(|(offset, source)| ParsedStatement {
+            source,
+            offset,
+            result: parse_statement(source),
+        })
+        .collect()
+}
+
+/// Parse `source` as one complete statement.
+fn parse_statement(source: &str) -> Result<Statement, ParseError> {
+    let error = |rem: &str, message: String| ParseError {
+        message,
+        pos: source.len() - rem.len(),
+    };
+
+    match terminated(statement, terminated(multispace0, eof))(source) {
+        Ok((_, statement)) => Ok(statement),
+        // a statement was recognised, but its remainder is invalid
+        Err(nom::Err::Failure(e)) => Err(error(e.input, "invalid statement".to_string())),
+        Err(nom::Err::Error(e)) if e.input.len() < source.trim_start().len() => {
+            Err(error(e.input, "unexpected input".to_string()))
+        }
+        Err(nom::Err::Error(e)) => Err(error(
+            e.input,
+            "expected SELECT, SHOW, DELETE, DROP SERIES, CREATE RETENTION POLICY or \
+             ALTER RETENTION POLICY statement"
+                .to_string(),
+        )),
+        Err(nom::Err::Incomplete(_)) => Err(error("", "unexpected end of input".to_string())),
+    }
+}
+
+/// Split `input` at the semicolons that are not part of a quoted string or identifier.
+///
+/// Returns the byte offset and text of every non-empty statement, without surrounding whitespace.
+fn split_statements(input: &str) -> Vec<(usize, &str)> {
+    let mut statements = vec![];
+    let mut push = |start: usize, end: usize| {
+        let text = &input[start..end];
+        let trimmed = text.trim_start();
+        let offset = start + text.len() - trimmed.len();
+        let trimmed = trimmed.trim_end();
+        if !trimmed.is_empty() {
+            statements.push((offset, trimmed));
+        }
+    };
+
+    let mut start = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for (pos, c) in input.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '\'' || c == '"' => quote = Some(c),
+            None if c == ';' => {
+                push(start, pos);
+                start = pos + 1;
+            }
+            None => {}
+        }
+    }
+    push(start, input.len());
+
+    statements
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_statement() {
+        let (_, got) = statement("SELECT value FROM cpu").unwrap();
+        assert!(matches!(got, Statement::Select(_)));
+
+        let (_, got) = statement("SHOW TAG KEYS").unwrap();
+        assert!(matches!(got, Statement::Show(_)));
+
+        let (_, got) = statement("DELETE FROM cpu WHERE time < 10").unwrap();
+        assert!(matches!(got, Statement::Delete(_)));
+
+        let (_, got) = statement("DROP SERIES FROM cpu").unwrap();
+        assert!(matches!(got, Statement::Drop(_)));
+
+        let (_, got) =
+            statement("CREATE RETENTION POLICY rp ON db DURATION 1d REPLICATION 1").unwrap();
+        assert!(matches!(got, Statement::CreateRetentionPolicy(_)));
+
+        let (_, got) = statement("ALTER RETENTION POLICY rp ON db DEFAULT").unwrap();
+        assert!(matches!(got, Statement::AlterRetentionPolicy(_)));
+
+        // Fallible cases
+        statement("DROP MEASUREMENT cpu").unwrap_err();
+        statement("CREATE DATABASE telegraf").unwrap_err();
+    }
+
+    #[test]
+    fn test_display_statement() {
+        for input in [
+            "SELECT value FROM cpu WHERE host = 'a'",
+            "SHOW FIELD KEYS",
+            "DELETE FROM cpu WHERE host = 'a'",
+            "DROP SERIES WHERE host = 'a'",
+            "CREATE RETENTION POLICY rp ON db DURATION INF REPLICATION 1 DEFAULT",
+            "ALTER RETENTION POLICY rp ON db DURATION 30d",
+        ] {
+            let (_, got) = statement(input).unwrap();
+            assert_eq!(got.to_string(), input);
+        }
+    }
+
+    #[test]
+    fn test_parse_statements() {
+        let got = parse_statements(
+            "SELECT value FROM cpu;\n  SHOW TAG KEYS ;; SELECT ';' FROM \"a;b\"\nSHOW TAG KEYS",
+        );
+        let got: Vec<_> = got
+            .iter()
+            .map(|s| (s.offset, s.source, s.result.is_ok()))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                (0, "SELECT value FROM cpu", true),
+                (25, "SHOW TAG KEYS", true),
+                (42, "SELECT ';' FROM \"a;b\"\nSHOW TAG KEYS", false),
+            ]
+        );
+
+        assert!(parse_statements("").is_empty());
+        assert!(parse_statements(" ; \n;").is_empty());
+    }
+
+    #[test]
+    fn test_parse_statements_errors() {
+        let got = parse_statements(
+            "SELECT FROM cpu; CREATE DATABASE cpu; SHOW TAG KEYS x; DROP MEASUREMENT cpu",
+        );
+        assert_eq!(got.len(), 4);
+
+        let err = got[0].result.as_ref().unwrap_err();
+        assert_eq!(err.message, "invalid statement");
+
+        let err = got[1].result.as_ref().unwrap_err();
+        assert_eq!(
+            err.message,
+            "expected SELECT, SHOW, DELETE, DROP SERIES, CREATE RETENTION POLICY or ALTER \
+             RETENTION POLICY statement"
+        );
+        assert_eq!(err.pos, 0);
+
+        let err = got[2].result.as_ref().unwrap_err();
+        assert_eq!(err.message, "unexpected input");
+        assert_eq!(err.pos, 14);
+        assert_eq!(err.to_string(), "unexpected input at pos 14");
+
+        // only DROP SERIES is supported
+        let err = got[3].result.as_ref().unwrap_err();
+        assert_eq!(err.message, "invalid statement");
+        assert_eq!(err.pos, 5);
+    }
+}
@@ -10,7 +10,7 @@ use nom::branch::alt;
 use nom::bytes::complete::{is_not, tag};
 use nom::character::complete::char;
 use nom::combinator::{map, value, verify};
-use nom::error::Error;
+use nom::error::{Error, ErrorKind};
 use nom::multi::fold_many0;
 use nom::sequence::{delimited, preceded};
 use nom::{IResult, Parser};
@@ -94,31 +94,31 @@ where
 
 /// Parse regular expression literal characters.
 ///
-/// Consumes i until reaching and escaped delimiter ("\/"), newline or eof.
+/// Consumes i until reaching an escaped delimiter ("\/"), an unescaped delimiter, newline or eof.
+/// Any other escape sequence, including an escaped backslash, is consumed verbatim, so that it is
+/// interpreted by the regular expression engine, which matches the InfluxQL 1.x scanner.
 fn regex_literal(i: &str) -> IResult<&str, &str> {
-    let mut remaining = &i[..i.len()];
-    let mut consumed = &i[..0];
-
-    loop {
-        // match everything except `\`, `/` or `\n`
-        let (_, match_i) = is_not("\\/\n")(remaining)?;
-        consumed = &i[..(consumed.len() + match_i.len())];
-        remaining = &i[consumed.len()..];
-
-        // Try and consume '\' followed by a '/'
-        if let Ok((remaining_i, _)) = char::<_, Error<&str>>('\\')(remaining) {
-            if char::<_, Error<&str>>('/')(remaining_i).is_ok() {
-                // We're escaping a '/' (a regex delimiter), so finish and let
-                // the outer parser match and unescape
-                return Ok((remaining, consumed));
-            }
-            // Skip the '/' and continue consuming
-            consumed = &i[..consumed.len() + 1];
-            remaining = &i[consumed.len()..];
-        } else {
-            return Ok((remaining, consumed));
+    let mut chars = i.char_indices();
+    let end = loop {
+        match chars.next() {
+            None => break i.len(),
+            Some((pos, '/' | '\n')) => break pos,
+            Some((pos, '\\')) => match chars.next() {
+                // An escaped newline is invalid and an escaped delimiter ("\/") is
+                // matched and unescaped by the outer parser
+                None | Some((_, '/' | '\n')) => break pos,
+                Some(_) => {}
+            },
+            Some(_) => {}
         }
+    };
+
+    if end == 0 {
+        // Like `is_not`, fail if nothing was consumed
+        return Err(nom::Err::Error(Error::new(i, ErrorKind::IsNot)));
     }
+
+    Ok((&i[end..], &i[..end]))
 }
 
 /// An unescaped regular expression.
@@ -134,6 +134,13 @@ impl Display for Regex {
     }
 }
 
+impl Regex {
+    /// Returns the unescaped pattern of the regular expression.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
 impl From<String> for Regex {
     fn from(v: String) -> Self {
         Self(v)
@@ -243,6 +250,23 @@ mod test {
         let (_, got) = regex(r#"/hello\n/"#).unwrap();
         assert_eq!(got, "hello\\n".into());
 
+        // escape sequences at the start of the regex
+        let (_, got) = regex(r#"/\d+/"#).unwrap();
+        assert_eq!(got, r#"\d+"#.into());
+
+        // escaped backslash before the closing delimiter
+        let (rem, got) = regex(r#"/a\\/"#).unwrap();
+        assert!(rem.is_empty());
+        assert_eq!(got, r#"a\\"#.into());
+
+        // escaped backslash followed by an escaped delimiter
+        let (_, got) = regex(r#"/a\\\//"#).unwrap();
+        assert_eq!(got, r#"a\\/"#.into());
+
+        // unicode
+        let (_, got) = regex("/^\u{1f47d}\\s/").unwrap();
+        assert_eq!(got, "^\u{1f47d}\\s".into());
+
         // Empty regex
         let (_, got) = regex("//").unwrap();
         assert_eq!(got, "".into());
@@ -258,5 +282,22 @@ mod test {
         // Single backslash fails, which matches Go implementation
         // See: https://go.dev/play/p/_8J1v5-382G
         regex(r#"/\/"#).unwrap_err();
+
+        // Escaped newline
+        regex("/hello\\\nworld/").unwrap_err();
+    }
+
+    #[test]
+    fn test_regex_display() {
+        for input in [
+            r#"/hello/"#,
+            r#"/\d+/"#,
+            r#"/this\/is\/a\/path/"#,
+            r#"/a\\/"#,
+            r#"/a\\\//"#,
+        ] {
+            let (_, got) = regex(input).unwrap();
+            assert_eq!(got.to_string(), input);
+        }
     }
 }
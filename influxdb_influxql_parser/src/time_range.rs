@@ -0,0 +1,366 @@
+//! # Extract the time range implied by an InfluxQL `WHERE` clause
+//!
+//! The time range is used to restrict the data that needs to be read for a query, and by query
+//! guards that reject queries spanning too much time.
+
+#![allow(dead_code)]
+
+use crate::expression::{BinaryOperator, Expr, UnaryOperator};
+use crate::identifier::Identifier;
+use crate::literal::Literal;
+use std::fmt::{Display, Formatter};
+
+/// A range of time in nanoseconds since the Unix epoch.
+///
+/// The lower bound is inclusive and the upper bound is exclusive, i.e. `[min, max)`. `None`
+/// means the range is unbounded in that direction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeRange {
+    /// Inclusive lower bound.
+    pub min: Option<i64>,
+
+    /// Exclusive upper bound.
+    pub max: Option<i64>,
+}
+
+impl TimeRange {
+    /// Create a new range.
+    pub fn new(min: Option<i64>, max: Option<i64>) -> Self {
+        Self { min, max }
+    }
+
+    /// Create a range that is unbounded in both directions.
+    pub fn unbounded() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if the range does not restrict time at all.
+    pub fn is_unbounded(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+
+    /// Returns `true` if no timestamp lies within the range.
+    pub fn is_empty(&self) -> bool {
+        matches!((self.min, self.max), (Some(min), Some(max)) if min >= max)
+    }
+
+    /// Returns the range of timestamps that lie within both `self` and `other`.
+    pub fn intersect(self, other: Self) -> Self {
+        Self {
+            min: match (self.min, other.min) {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                (a, b) => a.or(b),
+            },
+            max: match (self.max, other.max) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+        }
+    }
+
+    /// Returns the smallest range that covers both `self` and `other`.
+    pub fn union(self, other: Self) -> Self {
+        if self.is_empty() {
+            return other;
+        }
+        if other.is_empty() {
+            return self;
+        }
+
+        Self {
+            min: self.min.zip(other.min).map(|(a, b)| a.min(b)),
+            max: self.max.zip(other.max).map(|(a, b)| a.max(b)),
+        }
+    }
+}
+
+/// Errors returned by [`time_range`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimeRangeError {
+    /// The expression compared against `time` cannot be evaluated to a timestamp.
+    InvalidTimeExpr(String),
+
+    /// A string literal compared against `time` is not a valid RFC3339 timestamp.
+    InvalidTimestamp(String),
+
+    /// Evaluating the expression compared against `time` overflowed.
+    Overflow(String),
+}
+
+impl Display for TimeRangeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidTimeExpr(e) => write!(f, "invalid time expression: {}", e),
+            Self::InvalidTimestamp(s) => write!(f, "invalid timestamp: '{}'", s),
+            Self::Overflow(e) => write!(f, "time expression overflows: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TimeRangeError {}
+
+/// Extract the time range implied by the `WHERE` clause `cond`.
+///
+/// `now()` evaluates to `now`, in nanoseconds since the Unix epoch.
+///
+/// The result is conservative: every row that matches `cond` lies within the returned range, but
+/// the range may also contain rows that do not match. Predicates that do not restrict `time`
+/// (e.g. tag predicates or `time != ...`) leave the range unbounded, and an `OR` results in a
+/// range that covers both sides.
+pub fn time_range(cond: &Expr, now: i64) -> Result<TimeRange, TimeRangeError> {
+    match cond {
+        Expr::Nested(e) => time_range(e, now),
+        Expr::BinaryOp {
+            lhs,
+            op: BinaryOperator::And,
+            rhs,
+        } => Ok(time_range(lhs, now)?.intersect(time_range(rhs, now)?)),
+        Expr::BinaryOp {
+            lhs,
+            op: BinaryOperator::Or,
+            rhs,
+        } => Ok(time_range(lhs, now)?.union(time_range(rhs, now)?)),
+        Expr::BinaryOp { lhs, op, rhs } => {
+            let (op, other) = if is_time(lhs) {
+                (*op, rhs)
+            } else if is_time(rhs) {
+                match flip(*op) {
+                    Some(op) => (op, lhs),
+                    None => return Ok(TimeRange::unbounded()),
+                }
+            } else {
+                return Ok(TimeRange::unbounded());
+            };
+
+            let (min, max) = match op {
+                BinaryOperator::Eq => {
+                    let t = eval_time(other, now)?;
+                    (Some(t), Some(succ(t, other)?))
+                }
+                BinaryOperator::Gt => (Some(succ(eval_time(other, now)?, other)?), None),
+                BinaryOperator::GtEq => (Some(eval_time(other, now)?), None),
+                BinaryOperator::Lt => (None, Some(eval_time(other, now)?)),
+                BinaryOperator::LtEq => (None, Some(succ(eval_time(other, now)?, other)?)),
+                BinaryOperator::NotEq => {
+                    // does not restrict the range, but the expression must still be valid
+                    eval_time(other, now)?;
+                    (None, None)
+                }
+                _ => (None, None),
+            };
+
+            Ok(TimeRange::new(min, max))
+        }
+        _ => Ok(TimeRange::unbounded()),
+    }
+}
+
+/// Returns `true` if `expr` refers to the `time` column.
+fn is_time(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Identifier(Identifier::Unquoted(s) | Identifier::Quoted(s))
+            if s.eq_ignore_ascii_case("time")
+    )
+}
+
+/// Flip a comparison operator so that its operands can be swapped, i.e. `a op b` is equivalent
+/// to `b flip(op) a`.
+///
+/// Returns `None` if `op` is not a comparison.
+fn flip(op: BinaryOperator) -> Option<BinaryOperator> {
+    match op {
+        BinaryOperator::Eq => Some(BinaryOperator::Eq),
+        BinaryOperator::NotEq => Some(BinaryOperator::NotEq),
+        BinaryOperator::Lt => Some(BinaryOperator::Gt),
+        BinaryOperator::LtEq => Some(BinaryOperator::GtEq),
+        BinaryOperator::Gt => Some(BinaryOperator::Lt),
+        BinaryOperator::GtEq => Some(BinaryOperator::LtEq),
+        _ => None,
+    }
+}
+
+/// Returns the timestamp following `t`.
+fn succ(t: i64, expr: &Expr) -> Result<i64, TimeRangeError> {
+    t.checked_add(1)
+        .ok_or_else(|| TimeRangeError::Overflow(expr.to_string()))
+}
+
+/// Evaluate an expression that is compared against `time` to a timestamp in nanoseconds since
+/// the Unix epoch.
+///
+/// `now()` evaluates to `now`. Supported are integer literals in nanoseconds, durations, RFC3339
+/// string literals and their sums and differences, such as `now() - 1h`.
+pub fn eval_time(expr: &Expr, now: i64) -> Result<i64, TimeRangeError> {
+    let overflow = || TimeRangeError::Overflow(expr.to_string());
+
+    match expr {
+        Expr::Literal(Literal::Unsigned(v)) => i64::try_from(*v).map_err(|_| overflow()),
+        Expr::Literal(Literal::Duration(v)) => Ok(v.as_nanos()),
+        Expr::Literal(Literal::String(s)) => parse_timestamp(s),
+        Expr::Call { name, args } if name.eq_ignore_ascii_case("now") && args.is_empty() => Ok(now),
+        Expr::Nested(e) => eval_time(e, now),
+        Expr::UnaryOp(UnaryOperator::Plus, e) => eval_time(e, now),
+        Expr::UnaryOp(UnaryOperator::Minus, e) => {
+            eval_time(e, now)?.checked_neg().ok_or_else(overflow)
+        }
+        Expr::BinaryOp {
+            lhs,
+            op: BinaryOperator::Add,
+            rhs,
+        } => eval_time(lhs, now)?
+            .checked_add(eval_time(rhs, now)?)
+            .ok_or_else(overflow),
+        Expr::BinaryOp {
+            lhs,
+            op: BinaryOperator::Sub,
+            rhs,
+        } => eval_time(lhs, now)?
+            .checked_sub(eval_time(rhs, now)?)
+            .ok_or_else(overflow),
+        _ => Err(TimeRangeError::InvalidTimeExpr(expr.to_string())),
+    }
+}
+
+/// Parse an RFC3339 timestamp to nanoseconds since the Unix epoch.
+fn parse_timestamp(s: &str) -> Result<i64, TimeRangeError> {
+    let t = chrono::DateTime::parse_from_rfc3339(s)
+        .map_err(|_| TimeRangeError::InvalidTimestamp(s.to_string()))?;
+
+    // `timestamp_nanos` panics on overflow
+    t.timestamp()
+        .checked_mul(1_000_000_000)
+        .and_then(|secs| secs.checked_add(i64::from(t.timestamp_subsec_nanos())))
+        .ok_or_else(|| TimeRangeError::Overflow(format!("'{}'", s)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::expression::conditional_expression;
+
+    /// 2022-09-01T00:00:00Z
+    const NOW: i64 = 1_661_990_400_000_000_000;
+
+    const HOUR: i64 = 3_600_000_000_000;
+
+    fn range(cond: &str) -> Result<TimeRange, TimeRangeError> {
+        let (rem, expr) = conditional_expression(cond).unwrap();
+        assert!(rem.is_empty(), "unparsed input: {}", rem);
+        time_range(&expr, NOW)
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert_eq!(range("time >= 10").unwrap(), TimeRange::new(Some(10), None));
+        assert_eq!(range("time > 10").unwrap(), TimeRange::new(Some(11), None));
+        assert_eq!(range("time <= 10").unwrap(), TimeRange::new(None, Some(11)));
+        assert_eq!(range("time < 10").unwrap(), TimeRange::new(None, Some(10)));
+        assert_eq!(
+            range("time = 10").unwrap(),
+            TimeRange::new(Some(10), Some(11))
+        );
+        assert!(range("time != 10").unwrap().is_unbounded());
+
+        // time on the right-hand side
+        assert_eq!(range("10 < time").unwrap(), TimeRange::new(Some(11), None));
+        assert_eq!(range("10 >= time").unwrap(), TimeRange::new(None, Some(11)));
+
+        // quoted and upper case identifiers
+        assert_eq!(
+            range(r#""time" >= 10"#).unwrap(),
+            TimeRange::new(Some(10), None)
+        );
+        assert_eq!(range("TIME >= 10").unwrap(), TimeRange::new(Some(10), None));
+    }
+
+    #[test]
+    fn test_time_expressions() {
+        assert_eq!(
+            range("time >= now() - 1h").unwrap(),
+            TimeRange::new(Some(NOW - HOUR), None)
+        );
+        assert_eq!(
+            range("time < now() + (2h - 30m)").unwrap(),
+            TimeRange::new(None, Some(NOW + HOUR + HOUR / 2))
+        );
+        assert_eq!(
+            range("time >= -1h").unwrap(),
+            TimeRange::new(Some(-HOUR), None)
+        );
+        assert_eq!(
+            range("time >= '2022-09-01T00:00:00Z' AND time < '2022-09-01T01:00:00.5+01:00'")
+                .unwrap(),
+            TimeRange::new(Some(NOW), Some(NOW + 500_000_000))
+        );
+    }
+
+    #[test]
+    fn test_logical_operators() {
+        assert_eq!(
+            range("time >= 10 AND time < 20").unwrap(),
+            TimeRange::new(Some(10), Some(20))
+        );
+        assert_eq!(
+            range("time >= 10 AND time >= 15 AND time < 20 AND time < 30").unwrap(),
+            TimeRange::new(Some(15), Some(20))
+        );
+        assert!(range("time >= 20 AND time < 10").unwrap().is_empty());
+
+        // OR covers both sides
+        assert_eq!(
+            range("(time >= 10 AND time < 20) OR (time >= 30 AND time < 40)").unwrap(),
+            TimeRange::new(Some(10), Some(40))
+        );
+        assert_eq!(
+            range("time < 10 OR time >= 30").unwrap(),
+            TimeRange::unbounded()
+        );
+
+        // empty side of an OR is ignored
+        assert_eq!(
+            range("(time >= 20 AND time < 10) OR time = 30").unwrap(),
+            TimeRange::new(Some(30), Some(31))
+        );
+
+        // predicates that do not restrict time
+        assert_eq!(
+            range("foo = 'bar' AND time >= 10").unwrap(),
+            TimeRange::new(Some(10), None)
+        );
+        assert!(range("foo = 'bar' OR time >= 10").unwrap().is_unbounded());
+        assert!(range("foo =~ /bar/").unwrap().is_unbounded());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            range("time > foo").unwrap_err(),
+            TimeRangeError::InvalidTimeExpr("foo".into())
+        );
+        assert_eq!(
+            range("time > now(1)").unwrap_err(),
+            TimeRangeError::InvalidTimeExpr("now(1)".into())
+        );
+        assert_eq!(
+            range("time > 1.5").unwrap_err(),
+            TimeRangeError::InvalidTimeExpr("1.5".into())
+        );
+        assert_eq!(
+            range("time != 'yesterday'").unwrap_err(),
+            TimeRangeError::InvalidTimestamp("yesterday".into())
+        );
+        assert_eq!(
+            range("time < 18446744073709551615").unwrap_err(),
+            TimeRangeError::Overflow("18446744073709551615".into())
+        );
+        assert_eq!(
+            range("time > 9223372036854775807").unwrap_err(),
+            TimeRangeError::Overflow("9223372036854775807".into())
+        );
+
+        // errors are reported for both sides of logical operators
+        range("time >= 10 OR time > foo").unwrap_err();
+        range("time > foo AND time >= 10").unwrap_err();
+    }
+}
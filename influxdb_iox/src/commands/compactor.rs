@@ -3,6 +3,11 @@ use clap_blocks::{
     compactor::CompactorOnceConfig,
     object_store::{make_object_store, ObjectStoreConfig},
 };
+use futures::Future;
+use influxdb_iox_client::{
+    compactor::{generated_types::CompactionKind, Client as CompactorClient},
+    connection::Connection,
+};
 use iox_query::exec::Executor;
 use iox_time::{SystemProvider, TimeProvider};
 use ioxd_compactor::build_compactor_from_config;
@@ -39,9 +44,29 @@ pub enum Command {
         )]
         query_exec_thread_count: usize,
     },
+
+    /// Compact all files of a partition right away.
+    ///
+    /// Talks to the compactor at `--host`, which must be assigned to the shard of the partition.
+    CompactPartition {
+        /// The ID of the partition to compact
+        #[clap(action)]
+        partition_id: i64,
+    },
+
+    /// List the partitions that were skipped in the last compaction cycles of the compactor at
+    /// `--host`, with the reason why they were skipped.
+    ListSkipped,
+
+    /// Show the running compactions and the last compaction cycles of the compactor at `--host`.
+    Status,
 }
 
-pub async fn command(config: Config) -> Result<()> {
+pub async fn command<C, CFut>(connection: C, config: Config) -> Result<()>
+where
+    C: Send + FnOnce() -> CFut,
+    CFut: Send + Future<Output = Connection>,
+{
     match config.command {
         Command::RunOnce {
             object_store_config,
@@ -67,12 +92,14 @@ pub async fn command(config: Config) -> Result<()> {
             ));
 
             let exec = Arc::new(Executor::new(query_exec_thread_count));
+            exec.register_metrics(&metric_registry);
             let time_provider = Arc::new(SystemProvider::new());
 
             let compactor = build_compactor_from_config(
                 compactor_config,
                 catalog,
                 object_store,
+                object_store_config.parquet_path_layout,
                 exec,
                 time_provider,
                 metric_registry,
@@ -82,6 +109,36 @@ pub async fn command(config: Config) -> Result<()> {
 
             compactor::handler::run_compactor_once(compactor).await;
         }
+        Command::CompactPartition { partition_id } => {
+            let mut client = CompactorClient::new(connection().await);
+            client.compact_partition(partition_id).await?;
+            println!("Compacted partition {}", partition_id);
+        }
+        Command::ListSkipped => {
+            let mut client = CompactorClient::new(connection().await);
+            let state = client.get_state().await?;
+
+            let skipped: Vec<_> = state
+                .last_cycles
+                .iter()
+                .flat_map(|cycle| {
+                    let kind = kind_name(cycle.kind);
+                    cycle.skipped_partitions.iter().map(move |p| {
+                        serde_json::json!({
+                            "kind": kind,
+                            "partitionId": p.partition_id,
+                            "reason": p.reason,
+                        })
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&skipped)?);
+        }
+        Command::Status => {
+            let mut client = CompactorClient::new(connection().await);
+            let state = client.get_state().await?;
+            println!("{}", serde_json::to_string_pretty(&state)?);
+        }
     }
 
     Ok(())
@@ -101,6 +158,25 @@ pub enum Error {
 
     #[snafu(context(false))]
     Compacting { source: ioxd_compactor::Error },
+
+    #[snafu(context(false))]
+    #[snafu(display("Error talking to the compactor: {}", source))]
+    Client {
+        source: influxdb_iox_client::error::Error,
+    },
+
+    #[snafu(context(false))]
+    #[snafu(display("JSON Serialization error: {}", source))]
+    Serde { source: serde_json::Error },
+}
+
+fn kind_name(kind: i32) -> &'static str {
+    match CompactionKind::from_i32(kind) {
+        Some(CompactionKind::Hot) => "hot",
+        Some(CompactionKind::Cold) => "cold",
+        Some(CompactionKind::Rewrite) => "rewrite",
+        Some(CompactionKind::Unspecified) | None => "unknown",
+    }
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,122 @@
+//! This module implements the `influxql` CLI command
+
+use influxdb_influxql_parser::statement::parse_statements;
+use serde_json::json;
+use std::{
+    io::{Read, Write},
+    path::PathBuf,
+};
+use thiserror::Error;
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Error reading {path:?}: {source}")]
+    ReadError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Error writing report: {0}")]
+    WriteError(#[from] std::io::Error),
+
+    #[error("JSON Serialization error: {0}")]
+    Serde(#[from] serde_json::Error),
+
+    #[error("{failed} of {total} statements could not be parsed")]
+    InvalidStatements { failed: usize, total: usize },
+}
+
+/// Various commands for InfluxQL inspection
+#[derive(Debug, clap::Parser)]
+pub struct Config {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+/// Parse all statements of a file and report the result of every statement as a JSON line
+#[derive(Debug, clap::Parser)]
+struct DumpFile {
+    /// The file with the statements, separated by semicolons. Reads from stdin if `-`.
+    #[clap(action, default_value = "-")]
+    file: PathBuf,
+}
+
+/// All possible subcommands for influxql
+#[derive(Debug, clap::Parser)]
+enum Command {
+    /// Parse a file or stdin with many statements
+    DumpFile(DumpFile),
+}
+
+pub fn command(config: Config) -> Result<(), Error> {
+    match config.command {
+        Command::DumpFile(command) => dump_file(command),
+    }
+}
+
+fn dump_file(command: DumpFile) -> Result<(), Error> {
+    let path = command.file;
+    let mut input = String::new();
+    let read = if path.as_os_str() == "-" {
+        std::io::stdin().read_to_string(&mut input)
+    } else {
+        std::fs::File::open(&path).and_then(|mut f| f.read_to_string(&mut input))
+    };
+    read.map_err(|source| Error::ReadError { path, source })?;
+
+    let statements = parse_statements(&input);
+    let total = statements.len();
+    let mut failed = 0;
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    for statement in statements {
+        let (line, column) = line_column(&input, statement.offset);
+        let report = match &statement.result {
+            Ok(parsed) => json!({
+                "line": line,
+                "column": column,
+                "statement": statement.source,
+                "ok": true,
+                "normalized": parsed.to_string(),
+                "ast": format!("{:?}", parsed),
+            }),
+            Err(e) => {
+                failed += 1;
+                let (error_line, error_column) = line_column(&input, statement.offset + e.pos);
+                json!({
+                    "line": line,
+                    "column": column,
+                    "statement": statement.source,
+                    "ok": false,
+                    "error": {
+                        "message": e.message,
+                        "line": error_line,
+                        "column": error_column,
+                    },
+                })
+            }
+        };
+        writeln!(out, "{}", serde_json::to_string(&report)?)?;
+    }
+
+    if failed > 0 {
+        return Err(Error::InvalidStatements { failed, total });
+    }
+
+    Ok(())
+}
+
+/// Returns the 1-based line and column of the byte `offset` within `input`.
+fn line_column(input: &str, offset: usize) -> (usize, usize) {
+    let before = &input[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .map(|l| l.chars().count())
+        .unwrap_or_default()
+        + 1;
+    (line, column)
+}
@@ -2,6 +2,7 @@ use futures::Future;
 use influxdb_iox_client::connection::Connection;
 use snafu::prelude::*;
 
+mod influxql;
 mod namespace;
 mod print_cpu;
 mod schema;
@@ -15,6 +16,10 @@ pub enum Error {
     #[snafu(context(false))]
     #[snafu(display("Error in namespace subcommand: {}", source))]
     NamespaceError { source: namespace::Error },
+
+    #[snafu(context(false))]
+    #[snafu(display("Error in influxql subcommand: {}", source))]
+    InfluxqlError { source: influxql::Error },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -36,6 +41,9 @@ enum Command {
 
     /// Interrogate the schema of a namespace
     Schema(schema::Config),
+
+    /// Validate InfluxQL statements
+    Influxql(influxql::Config),
 }
 
 pub async fn command<C, CFut>(connection: C, config: Config) -> Result<()>
@@ -53,6 +61,7 @@ where
             let connection = connection().await;
             schema::command(connection, config).await?
         }
+        Command::Influxql(config) => influxql::command(config)?,
     }
 
     Ok(())
@@ -1,9 +1,18 @@
+use arrow::{error::ArrowError, record_batch::RecordBatch};
 use influxdb_iox_client::{
     connection::Connection,
-    flight::{self, generated_types::ReadInfo},
+    flight::{
+        self,
+        generated_types::{read_info, ReadInfo},
+    },
     format::QueryOutputFormat,
 };
-use std::str::FromStr;
+use parquet_file::serialize::{to_parquet_without_metadata, CodecError};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -13,24 +22,82 @@ pub enum Error {
 
     #[error("Error querying: {0}")]
     Query(#[from] influxdb_iox_client::flight::Error),
+
+    #[error("Query returned more than {0} rows, output was truncated")]
+    TooManyRows(usize),
+
+    #[error("Error writing parquet file: {0}")]
+    Parquet(#[from] CodecError),
+
+    #[error("Error creating output file {path:?}: {source}")]
+    CreateOutput {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("The parquet format requires an output file (--output)")]
+    OutputRequired,
+
+    #[error("--output is only supported by the parquet format")]
+    OutputNotSupported,
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
-/// Query the data with SQL
+/// Language of a query
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ArgEnum)]
+pub enum QueryType {
+    Sql,
+    #[clap(name = "influxql")]
+    InfluxQL,
+}
+
+impl From<QueryType> for read_info::QueryType {
+    fn from(query_type: QueryType) -> Self {
+        match query_type {
+            QueryType::Sql => Self::Sql,
+            QueryType::InfluxQL => Self::Influxql,
+        }
+    }
+}
+
+/// Query the data with SQL or InfluxQL
 #[derive(Debug, clap::Parser)]
 pub struct Config {
     /// The IOx namespace to query
     #[clap(action)]
     namespace: String,
 
-    /// The query to run, in SQL format
+    /// The query to run, in the language given by `--query-type`
     #[clap(action)]
     query: String,
 
-    /// Optional format ('pretty', 'json', or 'csv')
+    /// Optional query language ('sql' or 'influxql')
+    #[clap(
+        arg_enum,
+        long = "--query-type",
+        default_value = "sql",
+        ignore_case = true,
+        action
+    )]
+    query_type: QueryType,
+
+    /// Optional format ('pretty', 'json', 'csv' or 'parquet')
     #[clap(short, long, default_value = "pretty", action)]
     format: String,
+
+    /// File to write the results to, required by (and only supported by) the 'parquet' format
+    #[clap(long, action)]
+    output: Option<PathBuf>,
+
+    /// Optional query timeout, e.g. '30s'. Defaults to the timeout configured on the server.
+    #[clap(long, value_parser = humantime::parse_duration)]
+    timeout: Option<Duration>,
+
+    /// Optional maximum number of rows to output. Queries returning more rows are cancelled
+    /// after printing the first rows.
+    #[clap(long, action)]
+    max_rows: Option<usize>,
 }
 
 pub async fn command(connection: Connection, config: Config) -> Result<()> {
@@ -38,28 +105,182 @@ pub async fn command(connection: Connection, config: Config) -> Result<()> {
     let Config {
         namespace,
         format,
+        output,
         query,
+        query_type,
+        timeout,
+        max_rows,
     } = config;
 
-    let format = QueryOutputFormat::from_str(&format)?;
+    let output = Output::try_new(&format, output)?;
 
-    let mut query_results = client
+    let query_results = client
         .perform_query(ReadInfo {
             namespace_name: namespace,
             sql_query: query,
+            timeout_millis: timeout
+                .map(|timeout| timeout.as_millis().try_into().unwrap_or(u64::MAX))
+                .unwrap_or_default(),
+            params: vec![],
+            query_type: read_info::QueryType::from(query_type).into(),
+            snapshot_token: String::new(),
+            partition_count: 0,
+            partition: 0,
         })
         .await?;
 
-    // It might be nice to do some sort of streaming write
-    // rather than buffering the whole thing.
-    let mut batches = vec![];
-    while let Some(data) = query_results.next().await? {
-        batches.push(data);
+    let mut results = LimitedResults {
+        query_results,
+        max_rows,
+        rows: 0,
+        truncated: false,
+    };
+
+    match output {
+        Output::Parquet(path) => write_parquet(&mut results, &path).await?,
+        Output::Text(format) if format.supports_streaming() => {
+            // write every batch as soon as it arrives, so that the result doesn't have to fit
+            // into memory
+            let mut writer = format.streaming_writer(std::io::stdout())?;
+            while let Some(batch) = results.next().await? {
+                writer.write(&batch)?;
+            }
+            writer.finish()?;
+            println!();
+        }
+        Output::Text(format) => {
+            let mut batches = vec![];
+            while let Some(batch) = results.next().await? {
+                batches.push(batch);
+            }
+
+            let formatted_result = format.format(&batches)?;
+
+            println!("{}", formatted_result);
+        }
     }
 
-    let formatted_result = format.format(&batches)?;
+    match max_rows {
+        Some(max_rows) if results.truncated => Err(Error::TooManyRows(max_rows)),
+        _ => Ok(()),
+    }
+}
 
-    println!("{}", formatted_result);
+/// Where and how the query results are written.
+#[derive(Debug)]
+enum Output {
+    /// Formatted as text to stdout.
+    Text(QueryOutputFormat),
+
+    /// Parquet file at the given path, preserving the data types of the columns.
+    Parquet(PathBuf),
+}
 
-    Ok(())
+impl Output {
+    fn try_new(format: &str, output: Option<PathBuf>) -> Result<Self> {
+        if format.eq_ignore_ascii_case("parquet") {
+            return output.map(Self::Parquet).ok_or(Error::OutputRequired);
+        }
+
+        let format = QueryOutputFormat::from_str(format)?;
+        match output {
+            Some(_) => Err(Error::OutputNotSupported),
+            None => Ok(Self::Text(format)),
+        }
+    }
+}
+
+/// Write the query results to a parquet file at `path`.
+///
+/// The file is removed if the query fails.
+async fn write_parquet(results: &mut LimitedResults, path: &Path) -> Result<()> {
+    let file = std::fs::File::create(path).map_err(|source| Error::CreateOutput {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    let batches = futures::stream::unfold(results, |results| async move {
+        let batch = results
+            .next()
+            .await
+            .transpose()?
+            .map_err(|e| ArrowError::ExternalError(Box::new(e)));
+        Some((batch, results))
+    });
+
+    match to_parquet_without_metadata(batches, file).await {
+        Ok(meta) => {
+            println!("Wrote {} rows to {}", meta.num_rows, path.display());
+            Ok(())
+        }
+        Err(e) => {
+            std::fs::remove_file(path).ok();
+            Err(e.into())
+        }
+    }
+}
+
+/// Returns the record batches of a query, up to a maximum number of rows.
+struct LimitedResults {
+    query_results: flight::PerformQuery,
+    max_rows: Option<usize>,
+    rows: usize,
+    truncated: bool,
+}
+
+impl LimitedResults {
+    async fn next(&mut self) -> Result<Option<RecordBatch>> {
+        if self.truncated {
+            return Ok(None);
+        }
+
+        let batch = match self.query_results.next().await? {
+            Some(batch) => batch,
+            None => return Ok(None),
+        };
+        self.rows += batch.num_rows();
+
+        match self.max_rows {
+            Some(max_rows) if self.rows > max_rows => {
+                self.truncated = true;
+
+                // the remaining results are not needed, stop computing them on the server
+                self.query_results.cancel().await.ok();
+
+                let keep = batch.num_rows() - (self.rows - max_rows);
+                Ok(Some(batch.slice(0, keep)))
+            }
+            _ => Ok(Some(batch)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output() {
+        assert!(matches!(
+            Output::try_new("csv", None).unwrap(),
+            Output::Text(QueryOutputFormat::Csv)
+        ));
+        assert!(matches!(
+            Output::try_new("Parquet", Some(PathBuf::from("out.parquet"))).unwrap(),
+            Output::Parquet(path) if path == Path::new("out.parquet")
+        ));
+
+        assert!(matches!(
+            Output::try_new("parquet", None).unwrap_err(),
+            Error::OutputRequired
+        ));
+        assert!(matches!(
+            Output::try_new("json", Some(PathBuf::from("out.json"))).unwrap_err(),
+            Error::OutputNotSupported
+        ));
+        assert!(matches!(
+            Output::try_new("xml", None).unwrap_err(),
+            Error::Formatting(_)
+        ));
+    }
 }
@@ -80,6 +80,7 @@ pub async fn command(connection: Connection, config: Config) -> Result<()> {
         columns,
         predicate,
         namespace,
+        snapshot: None,
     };
 
     let mut query_results = client.perform_query(request).await?;
@@ -4,9 +4,9 @@ use bytes::Bytes;
 use clap_blocks::object_store::{make_object_store, ObjectStoreType};
 use clap_blocks::{catalog_dsn::CatalogDsnConfig, object_store::ObjectStoreConfig};
 use data_types::{
-    ColumnId, ColumnSet, ColumnType, NamespaceId, NamespaceSchema as CatalogNamespaceSchema,
-    ParquetFile as CatalogParquetFile, ParquetFileParams, PartitionId, SequenceNumber, ShardId,
-    ShardIndex, TableId, Timestamp,
+    ColumnId, ColumnSet, ColumnStatsSet, ColumnType, NamespaceId,
+    NamespaceSchema as CatalogNamespaceSchema, ParquetFile as CatalogParquetFile,
+    ParquetFileParams, PartitionId, SequenceNumber, ShardId, ShardIndex, TableId, Timestamp,
 };
 use futures::future::join_all;
 use influxdb_iox_client::{
@@ -225,9 +225,9 @@ pub async fn command(connection: Connection, config: Config) -> Result<(), Error
     }
 }
 
-const TOPIC_NAME: &str = "iox_shared";
-const SHARD_INDEX: ShardIndex = ShardIndex::new(0);
-const QUERY_POOL: &str = "iox_shared";
+pub(super) const TOPIC_NAME: &str = "iox_shared";
+pub(super) const SHARD_INDEX: ShardIndex = ShardIndex::new(0);
+pub(super) const QUERY_POOL: &str = "iox_shared";
 
 // loads the protobuf namespace schema returned from a remote IOx server into the passed in
 // catalog. It does this based on namespace, table, and column names, not IDs. It also inserts
@@ -363,6 +363,7 @@ async fn load_parquet_files(
                         .expect("compaction level should be valid"),
                     created_at: Timestamp::new(p.created_at),
                     column_set: ColumnSet::new(p.column_set.into_iter().map(ColumnId::new)),
+                    column_stats: ColumnStatsSet::default(),
                 };
 
                 repos.parquet_files().create(params).await?
@@ -601,6 +602,7 @@ mod tests {
             compaction_level: CompactionLevel::Initial,
             created_at,
             column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::default(),
         }];
         assert_eq!(expected, files);
     }
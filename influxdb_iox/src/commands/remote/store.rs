@@ -1,11 +1,35 @@
 //! This module implements the `remote store` CLI subcommand
 
+use bytes::Bytes;
+use clap_blocks::{
+    catalog_dsn::CatalogDsnConfig,
+    object_store::{make_object_store, ObjectStoreConfig, ObjectStoreType},
+};
+use data_types::{ColumnType, Namespace};
 use futures::StreamExt;
-use influxdb_iox_client::{connection::Connection, store};
+use influxdb_iox_client::{
+    catalog::{self, generated_types::ParquetFile},
+    connection::Connection,
+    schema, store,
+};
+use iox_catalog::interface::{Catalog, RepoCollection};
+use object_store::DynObjectStore;
+use parquet_file::{
+    metadata::{IoxMetadata, IoxParquetMetaData},
+    ParquetFilePath,
+};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use thiserror::Error;
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
 
+use super::partition::{QUERY_POOL, SHARD_INDEX, TOPIC_NAME};
+use crate::commands::storage::parse_range;
+
 #[allow(clippy::enum_variant_names)]
 #[derive(Debug, Error)]
 pub enum Error {
@@ -17,6 +41,45 @@ pub enum Error {
 
     #[error("Writing file: {0}")]
     FileError(#[from] std::io::Error),
+
+    #[error("Table {0} not found")]
+    TableNotFound(String),
+
+    #[error("Catalog DSN error: {0}")]
+    CatalogDsn(#[from] clap_blocks::catalog_dsn::Error),
+
+    #[error("Cannot parse object store config: {0}")]
+    ObjectStoreParsing(#[from] clap_blocks::object_store::ParseError),
+
+    #[error("Catalog error: {0}")]
+    Catalog(#[from] iox_catalog::interface::Error),
+
+    #[error("Object store error: {0}")]
+    ObjectStore(#[from] object_store::Error),
+
+    #[error("Cannot read parquet metadata of {path:?}: {source}")]
+    ParquetMetadata {
+        path: PathBuf,
+        source: parquet_file::metadata::Error,
+    },
+
+    #[error("File {0:?} has no parquet metadata")]
+    NoParquetMetadata(PathBuf),
+
+    #[error("Schema of {path:?} is incompatible with the catalog: {source}")]
+    IncompatibleSchema {
+        path: PathBuf,
+        source: iox_catalog::interface::Error,
+    },
+
+    #[error("Column {column} of {path:?} has no IOx column type")]
+    UnknownColumnType { path: PathBuf, column: String },
+
+    #[error(
+        "The object store is configured to store files in memory which is \
+        unlikely to be useful - try passing --object-store=file"
+    )]
+    SillyObjectStoreConfig,
 }
 
 /// Object store commands
@@ -38,25 +101,460 @@ struct Get {
     file_name: String,
 }
 
+/// Export the parquet files of a table that overlap a time range into a local directory
+///
+/// Besides the parquet files, a `manifest.json` with the catalog records of the files is written.
+#[derive(Debug, clap::Parser)]
+struct Export {
+    /// The namespace of the table
+    #[clap(long, action)]
+    namespace: String,
+
+    /// The table name
+    #[clap(long, action)]
+    table: String,
+
+    /// The start time (inclusive) of the time range, in nanoseconds since the epoch or in RFC3339
+    /// format
+    #[clap(
+        long,
+        default_value = "-9223372036854775806",
+        value_parser = parse_range,
+    )]
+    start: i64,
+
+    /// The end time (exclusive) of the time range, in nanoseconds since the epoch or in RFC3339
+    /// format
+    #[clap(
+        long,
+        default_value = "9223372036854775806",
+        value_parser = parse_range,
+    )]
+    end: i64,
+
+    /// The directory to write the files to, it is created if it doesn't exist
+    #[clap(long, action)]
+    output_dir: PathBuf,
+}
+
+/// Import local parquet files into the local catalog and object store
+///
+/// The files must contain IOx metadata, e.g. because they were exported with `remote store
+/// export`. Namespaces, tables, columns and partitions are created as needed, based on the names
+/// in the metadata. Files that are already in the catalog are skipped.
+#[derive(Debug, clap::Parser)]
+struct Import {
+    #[clap(flatten)]
+    catalog_dsn: CatalogDsnConfig,
+
+    #[clap(flatten)]
+    object_store: ObjectStoreConfig,
+
+    /// The parquet files to import. For directories, all `.parquet` files in them are imported
+    #[clap(action, required = true)]
+    paths: Vec<PathBuf>,
+}
+
 /// All possible subcommands for partition
 #[derive(Debug, clap::Parser)]
 enum Command {
     Get(Get),
+    Export(Export),
+    Import(Box<Import>),
 }
 
 pub async fn command(connection: Connection, config: Config) -> Result<(), Error> {
     match config.command {
         Command::Get(get) => {
             let mut client = store::Client::new(connection);
-            let mut response = client.get_parquet_file_by_object_store_id(get.uuid).await?;
-            let mut file = File::create(&get.file_name).await?;
-            while let Some(res) = response.next().await {
-                let res = res.unwrap();
-                let _ = file.write(&res.data).await?;
-            }
+            download(&mut client, get.uuid, Path::new(&get.file_name)).await?;
             println!("wrote data to {}", get.file_name);
 
             Ok(())
         }
+        Command::Export(export) => {
+            let mut schema_client = schema::Client::new(connection.clone());
+            let schema = schema_client.get_schema(&export.namespace).await?;
+            let table_id = schema
+                .tables
+                .get(&export.table)
+                .map(|t| t.id)
+                .ok_or_else(|| Error::TableNotFound(export.table.clone()))?;
+
+            let mut catalog_client = catalog::Client::new(connection.clone());
+            let partitions = catalog_client.get_partitions_by_table_id(table_id).await?;
+
+            tokio::fs::create_dir_all(&export.output_dir).await?;
+
+            let mut store_client = store::Client::new(connection);
+            let mut files = vec![];
+            for partition in partitions {
+                let parquet_files = catalog_client
+                    .get_parquet_files_by_partition_id(partition.id)
+                    .await?;
+
+                for parquet_file in parquet_files {
+                    if !should_export(&parquet_file, export.start, export.end) {
+                        continue;
+                    }
+
+                    let file_name = format!("{}.parquet", parquet_file.object_store_id);
+                    println!("getting file {} from remote", parquet_file.object_store_id);
+                    download(
+                        &mut store_client,
+                        parquet_file.object_store_id.clone(),
+                        &export.output_dir.join(&file_name),
+                    )
+                    .await?;
+
+                    files.push(serde_json::json!({
+                        "file_name": file_name,
+                        "partition_key": partition.key,
+                        "parquet_file": parquet_file,
+                    }));
+                }
+            }
+
+            let manifest = serde_json::json!({
+                "namespace": export.namespace,
+                "table": export.table,
+                "start": export.start,
+                "end": export.end,
+                "files": files,
+            });
+            let manifest_path = export.output_dir.join("manifest.json");
+            tokio::fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?).await?;
+
+            println!(
+                "exported {} files to {}",
+                files.len(),
+                export.output_dir.display()
+            );
+
+            Ok(())
+        }
+        Command::Import(import) => {
+            match &import.object_store.object_store {
+                None | Some(ObjectStoreType::Memory | ObjectStoreType::MemoryThrottled) => {
+                    return Err(Error::SillyObjectStoreConfig);
+                }
+                _ => {}
+            }
+
+            let metrics = Arc::new(metric::Registry::new());
+            let catalog = import.catalog_dsn.get_catalog("cli", metrics).await?;
+            let object_store = make_object_store(&import.object_store)?;
+
+            let mut files = vec![];
+            for path in import.paths {
+                files.extend(parquet_files(path).await?);
+            }
+
+            let mut imported = 0;
+            for path in files {
+                if import_file(&catalog, &object_store, &path).await? {
+                    imported += 1;
+                }
+            }
+            println!("imported {} files", imported);
+
+            Ok(())
+        }
+    }
+}
+
+/// Returns `path` if it is a file, or all `.parquet` files in it if it is a directory.
+async fn parquet_files(path: PathBuf) -> Result<Vec<PathBuf>, Error> {
+    if !tokio::fs::metadata(&path).await?.is_dir() {
+        return Ok(vec![path]);
+    }
+
+    let mut files = vec![];
+    let mut entries = tokio::fs::read_dir(&path).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path
+            .extension()
+            .map(|ext| ext == "parquet")
+            .unwrap_or(false)
+        {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+/// Import the parquet file at `path` into the catalog and object store.
+///
+/// Returns false if the file was already in the catalog.
+async fn import_file(
+    catalog: &Arc<dyn Catalog>,
+    object_store: &Arc<DynObjectStore>,
+    path: &Path,
+) -> Result<bool, Error> {
+    let data = Bytes::from(tokio::fs::read(path).await?);
+    let metadata_error = |source| Error::ParquetMetadata {
+        path: path.to_path_buf(),
+        source,
+    };
+    let parquet_metadata = IoxParquetMetaData::from_file_bytes(data.clone())
+        .map_err(metadata_error)?
+        .ok_or_else(|| Error::NoParquetMetadata(path.to_path_buf()))?;
+    let decoded = parquet_metadata.decode().map_err(metadata_error)?;
+    let iox_metadata = decoded.read_iox_metadata_new().map_err(metadata_error)?;
+    let schema = decoded.read_schema().map_err(metadata_error)?;
+    // `IoxMetadata::to_parquet_file` requires the statistics
+    decoded.read_statistics(&schema).map_err(metadata_error)?;
+
+    let mut repos = catalog.repositories().await;
+    if repos
+        .parquet_files()
+        .get_by_object_store_id(iox_metadata.object_store_id)
+        .await?
+        .is_some()
+    {
+        println!(
+            "skipping file {} already in the catalog",
+            iox_metadata.object_store_id
+        );
+        return Ok(false);
+    }
+
+    let namespace = namespace(repos.as_mut(), &iox_metadata.namespace_name).await?;
+    let table = repos
+        .tables()
+        .create_or_get(&iox_metadata.table_name, namespace.id)
+        .await?;
+
+    // creating the columns validates that their types match the existing columns
+    let mut column_ids = HashMap::with_capacity(schema.len());
+    for (influx_column_type, field) in schema.iter() {
+        let column_type = influx_column_type.ok_or_else(|| Error::UnknownColumnType {
+            path: path.to_path_buf(),
+            column: field.name().to_string(),
+        })?;
+        let column = repos
+            .columns()
+            .create_or_get(field.name(), table.id, ColumnType::from(column_type))
+            .await
+            .map_err(|source| match source {
+                e @ iox_catalog::interface::Error::ColumnTypeMismatch { .. } => {
+                    Error::IncompatibleSchema {
+                        path: path.to_path_buf(),
+                        source: e,
+                    }
+                }
+                e => Error::Catalog(e),
+            })?;
+        column_ids.insert(column.name, column.id);
+    }
+
+    let topic = repos.topics().create_or_get(TOPIC_NAME).await?;
+    let shard = repos.shards().create_or_get(&topic, SHARD_INDEX).await?;
+    let partition = repos
+        .partitions()
+        .create_or_get(iox_metadata.partition_key.clone(), shard.id, table.id)
+        .await?;
+
+    // the IDs in the file are the ones of the catalog the file was exported from
+    let iox_metadata = IoxMetadata {
+        namespace_id: namespace.id,
+        shard_id: shard.id,
+        table_id: table.id,
+        partition_id: partition.id,
+        ..iox_metadata
+    };
+
+    let object_store_path = ParquetFilePath::from(&iox_metadata).object_store_path();
+    let file_size = data.len();
+    object_store.put(&object_store_path, data).await?;
+
+    let params = iox_metadata.to_parquet_file(partition.id, file_size, &parquet_metadata, |name| {
+        column_ids[name]
+    });
+    repos.parquet_files().create(params).await?;
+
+    println!(
+        "imported file {} into table {} of namespace {}",
+        iox_metadata.object_store_id, table.name, namespace.name
+    );
+
+    Ok(true)
+}
+
+/// Get the namespace with the given name, or create it with the topic and query pool used for
+/// pulled partitions.
+async fn namespace(repos: &mut dyn RepoCollection, name: &str) -> Result<Namespace, Error> {
+    if let Some(namespace) = repos.namespaces().get_by_name(name).await? {
+        return Ok(namespace);
+    }
+
+    let topic = repos.topics().create_or_get(TOPIC_NAME).await?;
+    let query_pool = repos.query_pools().create_or_get(QUERY_POOL).await?;
+    let namespace = repos
+        .namespaces()
+        .create(name, "inf", topic.id, query_pool.id)
+        .await?;
+    println!("namespace {} created in local catalog", namespace.name);
+
+    Ok(namespace)
+}
+
+/// Returns true if `parquet_file` is not marked for deletion and contains data in the time range
+/// from `start` (inclusive) to `end` (exclusive).
+fn should_export(parquet_file: &ParquetFile, start: i64, end: i64) -> bool {
+    parquet_file.to_delete == 0 && parquet_file.max_time >= start && parquet_file.min_time < end
+}
+
+/// Download the parquet file with the object store id `uuid` to `path`.
+async fn download(client: &mut store::Client, uuid: String, path: &Path) -> Result<(), Error> {
+    let mut response = client.get_parquet_file_by_object_store_id(uuid).await?;
+    let mut file = File::create(path).await?;
+    while let Some(res) = response.next().await {
+        let res = res.map_err(influxdb_iox_client::error::Error::from)?;
+        file.write_all(&res.data).await?;
+    }
+    file.flush().await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::{
+        array::{ArrayRef, DictionaryArray, Float64Array, Int64Array, TimestampNanosecondArray},
+        datatypes::Int32Type,
+        record_batch::RecordBatch,
+    };
+    use data_types::{CompactionLevel, NamespaceId, PartitionId, SequenceNumber, ShardId, TableId};
+    use iox_catalog::mem::MemCatalog;
+    use iox_time::Time;
+    use schema::{builder::SchemaBuilder, InfluxFieldType};
+    use uuid::Uuid;
+
+    /// Write a parquet file with IOx metadata, as exported from another catalog, to `dir`.
+    async fn write_file(dir: &Path, val: ArrayRef) -> (PathBuf, Uuid) {
+        let field_type = match val.data_type() {
+            arrow::datatypes::DataType::Int64 => InfluxFieldType::Integer,
+            _ => InfluxFieldType::Float,
+        };
+        let schema = SchemaBuilder::new()
+            .tag("tag")
+            .influx_field("val", field_type)
+            .timestamp()
+            .build()
+            .unwrap();
+        let batch = RecordBatch::try_new(
+            schema.as_arrow(),
+            vec![
+                Arc::new(
+                    vec!["a"]
+                        .into_iter()
+                        .collect::<DictionaryArray<Int32Type>>(),
+                ),
+                val,
+                Arc::new(TimestampNanosecondArray::from(vec![10])),
+            ],
+        )
+        .unwrap();
+
+        let meta = IoxMetadata {
+            object_store_id: Uuid::new_v4(),
+            creation_timestamp: Time::from_timestamp_nanos(42),
+            namespace_id: NamespaceId::new(100),
+            namespace_name: "ns".into(),
+            shard_id: ShardId::new(100),
+            table_id: TableId::new(100),
+            table_name: "t".into(),
+            partition_id: PartitionId::new(100),
+            partition_key: "p".into(),
+            max_sequence_number: SequenceNumber::new(11),
+            compaction_level: CompactionLevel::FileNonOverlapped,
+            sort_key: None,
+        };
+        let (bytes, _) =
+            parquet_file::serialize::to_parquet_bytes(futures::stream::iter([Ok(batch)]), &meta)
+                .await
+                .unwrap();
+
+        let path = dir.join(format!("{}.parquet", meta.object_store_id));
+        std::fs::write(&path, bytes).unwrap();
+        (path, meta.object_store_id)
+    }
+
+    #[tokio::test]
+    async fn test_import_file() {
+        let metrics = Arc::new(metric::Registry::new());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(metrics));
+        let object_store: Arc<DynObjectStore> = Arc::new(object_store::memory::InMemory::new());
+        let dir = tempfile::tempdir().unwrap();
+
+        let (path, uuid) = write_file(dir.path(), Arc::new(Int64Array::from(vec![1]))).await;
+        assert!(import_file(&catalog, &object_store, &path).await.unwrap());
+
+        // the file is registered with the IDs of the local catalog
+        let mut repos = catalog.repositories().await;
+        let parquet_file = repos
+            .parquet_files()
+            .get_by_object_store_id(uuid)
+            .await
+            .unwrap()
+            .unwrap();
+        let namespace = repos.namespaces().get_by_name("ns").await.unwrap().unwrap();
+        assert_eq!(parquet_file.namespace_id, namespace.id);
+        assert_ne!(parquet_file.partition_id, PartitionId::new(100));
+        assert_eq!(parquet_file.row_count, 1);
+        assert_eq!(parquet_file.min_time.get(), 10);
+        assert_eq!(parquet_file.column_set.len(), 3);
+        drop(repos);
+
+        let object_store_path = ParquetFilePath::from(&parquet_file).object_store_path();
+        object_store.get(&object_store_path).await.unwrap();
+
+        // importing the same file again is a no-op
+        assert!(!import_file(&catalog, &object_store, &path).await.unwrap());
+
+        // the type of `val` conflicts with the catalog
+        let (path, _) = write_file(dir.path(), Arc::new(Float64Array::from(vec![1.0]))).await;
+        let err = import_file(&catalog, &object_store, &path)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::IncompatibleSchema { .. }), "{}", err);
+
+        // files without IOx metadata are rejected
+        let path = dir.path().join("empty.parquet");
+        std::fs::write(&path, b"").unwrap();
+        assert!(import_file(&catalog, &object_store, &path).await.is_err());
+
+        // directories are expanded to the parquet files in them
+        std::fs::write(dir.path().join("manifest.json"), b"{}").unwrap();
+        assert_eq!(
+            parquet_files(dir.path().to_path_buf()).await.unwrap().len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_should_export() {
+        let file = ParquetFile {
+            min_time: 10,
+            max_time: 20,
+            ..Default::default()
+        };
+
+        assert!(should_export(&file, 0, 100));
+        assert!(should_export(&file, 20, 100));
+        assert!(should_export(&file, 0, 11));
+        assert!(!should_export(&file, 21, 100));
+        assert!(!should_export(&file, 0, 10));
+
+        let file = ParquetFile {
+            to_delete: 1,
+            ..file
+        };
+        assert!(!should_export(&file, 0, 100));
     }
 }
@@ -24,7 +24,7 @@ use ioxd_querier::{create_querier_server_type, QuerierServerTypeArgs};
 use ioxd_router::create_router_server_type;
 use object_store::DynObjectStore;
 use observability_deps::tracing::*;
-use std::{path::PathBuf, sync::Arc};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 use thiserror::Error;
 use trace_exporters::TracingConfig;
 use trogging::cli::LoggingConfig;
@@ -415,11 +415,14 @@ impl Config {
         // parameters are redundant with ingester's
         let compactor_config = CompactorConfig {
             topic: QUERY_POOL_NAME.to_string(),
-            shard_index_range_start,
-            shard_index_range_end,
+            discover_shards: false,
+            shard_index_range_start: Some(shard_index_range_start),
+            shard_index_range_end: Some(shard_index_range_end),
             max_desired_file_size_bytes: 30_000,
             percentage_max_file_size: 30,
             split_percentage: 80,
+            split_interval: None,
+            upper_level_file_sizes_bytes: vec![],
             max_cold_concurrent_size_bytes: 90_000,
             max_number_partitions_per_shard: 1,
             min_number_recent_ingested_files_per_partition: 1,
@@ -427,6 +430,18 @@ impl Config {
             cold_input_file_count_threshold: 100,
             hot_multiple: 4,
             memory_budget_bytes: 300_000,
+            write_reports: false,
+            hot_tick_interval: Duration::from_secs(1),
+            cold_tick_interval: Duration::from_secs(1),
+            hot_max_concurrent_partitions: 0,
+            cold_max_concurrent_partitions: 0,
+            cold_exec_thread_count: None,
+            cold_exec_mem_pool_bytes: 0,
+            spill_dir: None,
+            spill_quota_bytes: 10 * 1024 * 1024 * 1024,
+            debt_interval: Duration::from_secs(60),
+            debt_level_0_age: Duration::from_secs(60 * 60),
+            hot_persist_grace_period: Duration::from_secs(10 * 60),
         };
 
         let querier_config = QuerierConfig {
@@ -435,8 +450,34 @@ impl Config {
             shard_to_ingesters: None,      // will be ignored
             ram_pool_metadata_bytes: querier_ram_pool_metadata_bytes,
             ram_pool_data_bytes: querier_ram_pool_data_bytes,
+            object_store_cache_chunk_size_bytes: 1_048_576, // 1MB
+            object_store_cache_disk_path: None,
+            object_store_cache_disk_bytes: 10_737_418_240, // 10GB
+            object_store_cache_reconciliation_interval: Duration::from_secs(600),
+            object_store_cache_not_found_ttl: Duration::from_secs(10),
             max_concurrent_queries: querier_max_concurrent_queries,
             max_table_query_bytes: querier_max_table_query_bytes,
+            dedup_validation: false,
+            ingester_circuit_breaker_threshold: 0,
+            ingester_circuit_breaker_open_duration: Duration::from_secs(30),
+            ingester_latency_budget: Duration::ZERO,
+            query_memory_pool_bytes: 0,
+            query_admission_queue_timeout: Duration::from_secs(10),
+            query_timeout: Duration::ZERO,
+            max_query_timeout: Duration::ZERO,
+            query_timeout_refresh_interval: Duration::from_secs(60),
+            exec_mem_pool_bytes: 0,
+            exec_spill_dir: None,
+            external_dedup_min_chunks: 0,
+            aggregate_pushdown: false,
+            query_rate_limit_refresh_interval: Duration::from_secs(60),
+            max_concurrent_queries_per_namespace: 0,
+            max_queued_queries_per_namespace: 100,
+            access_stats_flush_interval: Duration::from_secs(60),
+            cache_warm_up_window: Duration::from_secs(3_600),
+            cache_warm_up_max_tables: 1_000,
+            result_cache_max_entries: 0,
+            result_cache_max_entry_bytes: 10_485_760, // 10MB
         };
 
         SpecializedConfig {
@@ -505,6 +546,7 @@ pub async fn command(config: Config) -> Result<()> {
     let object_store: Arc<DynObjectStore> =
         make_object_store(router_run_config.object_store_config())
             .map_err(Error::ObjectStoreParsing)?;
+    let parquet_path_layout = router_run_config.object_store_config().parquet_path_layout;
 
     let time_provider: Arc<dyn TimeProvider> = Arc::new(SystemProvider::new());
 
@@ -516,6 +558,7 @@ pub async fn command(config: Config) -> Result<()> {
     let num_threads = num_cpus::get();
     info!(%num_threads, "Creating shared query executor");
     let exec = Arc::new(Executor::new(num_threads));
+    exec.register_metrics(&metrics);
 
     info!("starting router");
     let router = create_router_server_type(
@@ -525,7 +568,8 @@ pub async fn command(config: Config) -> Result<()> {
         Arc::clone(&object_store),
         &write_buffer_config,
         QUERY_POOL_NAME,
-        1_000, // max 1,000 concurrent HTTP requests
+        1_000,                   // max 1,000 concurrent HTTP requests
+        Duration::from_secs(60), // refresh rate limits every minute
     )
     .await?;
 
@@ -535,6 +579,7 @@ pub async fn command(config: Config) -> Result<()> {
         Arc::clone(&metrics),
         Arc::clone(&catalog),
         Arc::clone(&object_store),
+        parquet_path_layout,
         Arc::clone(&exec),
         &write_buffer_config,
         ingester_config,
@@ -547,6 +592,7 @@ pub async fn command(config: Config) -> Result<()> {
         Arc::clone(&metrics),
         Arc::clone(&catalog),
         Arc::clone(&object_store),
+        parquet_path_layout,
         Arc::clone(&exec),
         Arc::clone(&time_provider),
         compactor_config,
@@ -569,6 +615,7 @@ pub async fn command(config: Config) -> Result<()> {
         metric_registry: Arc::clone(&metrics),
         catalog,
         object_store,
+        parquet_path_layout,
         exec,
         time_provider,
         ingester_addresses,
@@ -95,6 +95,7 @@ pub async fn command(config: Config) -> Result<(), Error> {
     ));
 
     let exec = Arc::new(Executor::new(config.query_exec_thread_count));
+    exec.register_metrics(&metric_registry);
     let time_provider = Arc::new(SystemProvider::new());
 
     let server_type = create_compactor_server_type(
@@ -102,6 +103,7 @@ pub async fn command(config: Config) -> Result<(), Error> {
         Arc::clone(&metric_registry),
         catalog,
         object_store,
+        config.run_config.object_store_config().parquet_path_layout,
         exec,
         time_provider,
         config.compactor_config,
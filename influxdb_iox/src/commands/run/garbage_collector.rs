@@ -31,10 +31,18 @@ pub async fn command(config: Config) -> Result<()> {
     let time_provider = Arc::new(SystemProvider::new());
     let metric_registry: Arc<metric::Registry> = Default::default();
 
-    let catalog = config
-        .catalog_dsn
-        .get_catalog("garbage-collector", Arc::clone(&metric_registry))
-        .await?;
+    // A dry run doesn't delete anything, so it can tolerate reading from a lagging replica.
+    let catalog = if config.sub_config.dry_run() {
+        config
+            .catalog_dsn
+            .get_read_catalog("garbage-collector", Arc::clone(&metric_registry))
+            .await?
+    } else {
+        config
+            .catalog_dsn
+            .get_catalog("garbage-collector", Arc::clone(&metric_registry))
+            .await?
+    };
 
     let object_store = make_object_store(config.run_config.object_store_config())?;
 
@@ -50,12 +58,13 @@ pub async fn command(config: Config) -> Result<()> {
     info!("starting garbage-collector");
 
     let server_type = Arc::new({
+        let metric_registry = Arc::clone(&metric_registry);
         let config = gc::Config {
             object_store,
             catalog,
             sub_config,
+            metric_registry: Arc::clone(&metric_registry),
         };
-        let metric_registry = Arc::clone(&metric_registry);
 
         gc::Server::start(metric_registry, config)
     });
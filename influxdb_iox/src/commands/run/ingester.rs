@@ -102,6 +102,7 @@ pub async fn command(config: Config) -> Result<()> {
         Arc::clone(&metric_registry),
         catalog,
         object_store,
+        config.run_config.object_store_config().parquet_path_layout,
         exec,
         &config.write_buffer_config,
         config.ingester_config,
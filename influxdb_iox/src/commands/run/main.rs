@@ -116,6 +116,23 @@ pub async fn main(
             .register_instrument("jemalloc_metrics", jemalloc::JemallocMetrics::new);
     }
 
+    // Register tokio metrics for the IO runtime, services might share a registry
+    let mut runtime_metric_registries: Vec<Arc<metric::Registry>> = vec![];
+    for service in &services {
+        let metric_registry = service.server_type.metric_registry();
+        if !runtime_metric_registries
+            .iter()
+            .any(|r| Arc::ptr_eq(r, &metric_registry))
+        {
+            executor::register_runtime_metrics(
+                "io",
+                &tokio::runtime::Handle::current(),
+                &metric_registry,
+            );
+            runtime_metric_registries.push(metric_registry);
+        }
+    }
+
     // Construct a token to trigger clean shutdown
     let frontend_shutdown = CancellationToken::new();
 
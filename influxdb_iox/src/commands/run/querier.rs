@@ -5,7 +5,7 @@ use clap_blocks::{
     catalog_dsn::CatalogDsnConfig, object_store::make_object_store, querier::QuerierConfig,
     run_config::RunConfig,
 };
-use iox_query::exec::Executor;
+use iox_query::exec::{Executor, ExecutorConfig};
 use iox_time::{SystemProvider, TimeProvider};
 use ioxd_common::{
     server_type::{CommonServerState, CommonServerStateError},
@@ -76,7 +76,7 @@ pub async fn command(config: Config) -> Result<(), Error> {
 
     let catalog = config
         .catalog_dsn
-        .get_catalog("querier", Arc::clone(&metric_registry))
+        .get_read_catalog("querier", Arc::clone(&metric_registry))
         .await?;
 
     let object_store = make_object_store(config.run_config.object_store_config())
@@ -97,13 +97,25 @@ pub async fn command(config: Config) -> Result<(), Error> {
     let ingester_addresses = config.querier_config.ingester_addresses()?;
     info!(?ingester_addresses, "using ingester addresses");
 
-    let exec = Arc::new(Executor::new(num_threads));
+    let exec = Arc::new(Executor::new_with_config(ExecutorConfig {
+        num_threads,
+        target_query_partitions: num_threads,
+        mem_pool_size: config.querier_config.exec_mem_pool_bytes(),
+        spill_dir: config
+            .querier_config
+            .exec_spill_dir()
+            .map(|path| path.to_owned()),
+        external_dedup_min_chunks: config.querier_config.external_dedup_min_chunks(),
+        aggregate_pushdown: config.querier_config.aggregate_pushdown(),
+    }));
+    exec.register_metrics(&metric_registry);
 
     let server_type = create_querier_server_type(QuerierServerTypeArgs {
         common_state: &common_state,
         metric_registry: Arc::clone(&metric_registry),
         catalog,
         object_store,
+        parquet_path_layout: config.run_config.object_store_config().parquet_path_layout,
         exec,
         time_provider,
         ingester_addresses,
@@ -14,7 +14,7 @@ use ioxd_router::create_router_server_type;
 use object_store::DynObjectStore;
 use object_store_metrics::ObjectStoreMetrics;
 use observability_deps::tracing::*;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -87,6 +87,16 @@ pub struct Config {
         action
     )]
     pub(crate) http_request_limit: usize,
+
+    /// How often the per-namespace write rate limits are re-read from the
+    /// catalog.
+    #[clap(
+        long = "--rate-limit-refresh-interval",
+        env = "INFLUXDB_IOX_RATE_LIMIT_REFRESH_INTERVAL",
+        default_value = "1m",
+        value_parser = humantime::parse_duration,
+    )]
+    pub(crate) rate_limit_refresh_interval: Duration,
 }
 
 pub async fn command(config: Config) -> Result<()> {
@@ -116,6 +126,7 @@ pub async fn command(config: Config) -> Result<()> {
         &config.write_buffer_config,
         &config.query_pool_name,
         config.http_request_limit,
+        config.rate_limit_refresh_interval,
     )
     .await?;
 
@@ -9,7 +9,10 @@ use datafusion::{
     datasource::MemTable,
     prelude::{SessionConfig, SessionContext},
 };
-use influxdb_iox_client::{connection::Connection, flight::generated_types::ReadInfo};
+use influxdb_iox_client::{
+    connection::Connection,
+    flight::generated_types::{read_info::QueryType, ReadInfo},
+};
 use observability_deps::tracing::{debug, info};
 use snafu::{ResultExt, Snafu};
 use std::{collections::HashMap, sync::Arc, time::Instant};
@@ -123,6 +126,12 @@ async fn load_remote_system_tables(
                         .perform_query(ReadInfo {
                             namespace_name: db_name.clone(),
                             sql_query: sql,
+                            timeout_millis: 0,
+                            params: vec![],
+                            query_type: QueryType::Sql.into(),
+                            snapshot_token: String::new(),
+                            partition_count: 0,
+                            partition: 0,
                         })
                         .await
                         .context(RunningRemoteQuerySnafu)?;
@@ -11,7 +11,9 @@ use snafu::{ResultExt, Snafu};
 use super::repl_command::ReplCommand;
 
 use influxdb_iox_client::{
-    connection::Connection, flight::generated_types::ReadInfo, format::QueryOutputFormat,
+    connection::Connection,
+    flight::generated_types::{read_info::QueryType, ReadInfo},
+    format::QueryOutputFormat,
 };
 
 #[derive(Debug, Snafu)]
@@ -432,6 +434,12 @@ async fn scrape_query(
         .perform_query(ReadInfo {
             namespace_name: db_name.to_string(),
             sql_query: query.to_string(),
+            timeout_millis: 0,
+            params: vec![],
+            query_type: QueryType::Sql.into(),
+            snapshot_token: String::new(),
+            partition_count: 0,
+            partition: 0,
         })
         .await
         .context(RunningRemoteQuerySnafu)?;
@@ -92,7 +92,7 @@ pub struct Config {
 // Attempts to parse either a stringified `i64` value. or alternatively parse an
 // RFC3339 formatted timestamp into an `i64` value representing nanoseconds
 // since the epoch.
-fn parse_range(s: &str) -> Result<i64, ParseError> {
+pub(crate) fn parse_range(s: &str) -> Result<i64, ParseError> {
     match s.parse::<i64>() {
         Ok(v) => Ok(v),
         Err(_) => {
@@ -138,6 +138,17 @@ struct Config {
     )]
     rpc_timeout: Duration,
 
+    /// Send HTTP/2 keepalive pings to the server at this interval.
+    ///
+    /// Prevents idle connections from being dropped by proxies and load balancers. Disabled by
+    /// default.
+    #[clap(long, global = true, value_parser = humantime::parse_duration)]
+    rpc_keepalive_interval: Option<Duration>,
+
+    /// Number of connections to open to the server, requests are balanced across all of them.
+    #[clap(long, global = true, default_value = "1", action)]
+    rpc_pool_size: usize,
+
     /// Automatically generate an uber-trace-id header for CLI requests
     ///
     /// The generated trace ID will be emitted at the beginning of the response.
@@ -208,13 +219,20 @@ fn main() -> Result<(), std::io::Error> {
         let headers = config.header;
         let log_verbose_count = config.all_in_one_config.logging_config.log_verbose_count;
         let rpc_timeout = config.rpc_timeout;
+        let rpc_keepalive_interval = config.rpc_keepalive_interval;
+        let rpc_pool_size = config.rpc_pool_size;
 
         let connection = || async move {
             let mut builder = headers.into_iter().fold(Builder::default(), |builder, kv| {
                 builder.header(kv.key, kv.value)
             });
 
-            builder = builder.timeout(rpc_timeout);
+            builder = builder.timeout(rpc_timeout).pool_size(rpc_pool_size);
+            if let Some(interval) = rpc_keepalive_interval {
+                builder = builder
+                    .http2_keep_alive_interval(interval)
+                    .keep_alive_while_idle(true);
+            }
 
             if config.gen_trace_id {
                 let key = http::header::HeaderName::from_str(
@@ -297,7 +315,7 @@ fn main() -> Result<(), std::io::Error> {
             }
             Some(Command::Compactor(config)) => {
                 let _tracing_guard = handle_init_logs(init_simple_logs(log_verbose_count));
-                if let Err(e) = commands::compactor::command(*config).await {
+                if let Err(e) = commands::compactor::command(connection, *config).await {
                     eprintln!("{}", e);
                     std::process::exit(ReturnCode::Failure as _)
                 }
@@ -179,6 +179,68 @@ async fn remote_partition_and_get_from_store_and_pull() {
                             predicate::str::contains("wrote file")
                                 .and(predicate::str::contains(id)),
                         );
+
+                    // Export the files of the table that overlap a time range
+                    let dir = tempfile::tempdir().expect("could not get temporary directory");
+
+                    Command::cargo_bin("influxdb_iox")
+                        .unwrap()
+                        .arg("-h")
+                        .arg(&router_addr)
+                        .arg("remote")
+                        .arg("store")
+                        .arg("export")
+                        .arg("--namespace")
+                        .arg(&namespace)
+                        .arg("--table")
+                        .arg("my_awesome_table")
+                        .arg("--start")
+                        .arg("0")
+                        .arg("--end")
+                        .arg("1970-01-01T00:00:01Z")
+                        .arg("--output-dir")
+                        .arg(dir.path())
+                        .assert()
+                        .success()
+                        .stdout(predicate::str::contains("exported 1 files"));
+
+                    assert!(dir.path().join(format!("{}.parquet", id)).exists());
+                    let manifest: Value = serde_json::from_slice(
+                        &std::fs::read(dir.path().join("manifest.json")).unwrap(),
+                    )
+                    .unwrap();
+                    let files = manifest.get("files").unwrap().as_array().unwrap();
+                    assert_eq!(files.len(), 1);
+                    assert_eq!(
+                        files[0]
+                            .get("parquet_file")
+                            .unwrap()
+                            .get("objectStoreId")
+                            .unwrap(),
+                        id
+                    );
+
+                    // No file overlaps a later time range
+                    let dir = tempfile::tempdir().expect("could not get temporary directory");
+
+                    Command::cargo_bin("influxdb_iox")
+                        .unwrap()
+                        .arg("-h")
+                        .arg(&router_addr)
+                        .arg("remote")
+                        .arg("store")
+                        .arg("export")
+                        .arg("--namespace")
+                        .arg(&namespace)
+                        .arg("--table")
+                        .arg("my_awesome_table")
+                        .arg("--start")
+                        .arg("1970-01-01T00:00:01Z")
+                        .arg("--output-dir")
+                        .arg(dir.path())
+                        .assert()
+                        .success()
+                        .stdout(predicate::str::contains("exported 0 files"));
                 }
                 .boxed()
             })),
@@ -532,3 +594,72 @@ async fn query_ingester() {
     .run()
     .await
 }
+
+/// Test the query CLI command with InfluxQL queries
+#[tokio::test]
+async fn query_influxql() {
+    test_helpers::maybe_start_logging();
+    let database_url = maybe_skip_integration!();
+
+    let mut cluster = MiniCluster::create_shared(database_url).await;
+
+    StepTest::new(
+        &mut cluster,
+        vec![
+            Step::WriteLineProtocol(String::from(
+                "my_awesome_table3,tag1=A,tag2=B val=42i 123456",
+            )),
+            Step::WaitForReadable,
+            Step::Custom(Box::new(|state: &mut StepTestState| {
+                async {
+                    let querier_addr = state.cluster().querier().querier_grpc_base().to_string();
+
+                    let expected = [
+                        "+--------------------------------+-----+",
+                        "| time                           | val |",
+                        "+--------------------------------+-----+",
+                        "| 1970-01-01T00:00:00.000123456Z | 42  |",
+                        "+--------------------------------+-----+",
+                    ]
+                    .join("\n");
+
+                    Command::cargo_bin("influxdb_iox")
+                        .unwrap()
+                        .arg("-h")
+                        .arg(&querier_addr)
+                        .arg("query")
+                        .arg("--query-type")
+                        .arg("influxql")
+                        .arg(state.cluster().namespace())
+                        .arg("SELECT val FROM my_awesome_table3")
+                        .assert()
+                        .success()
+                        .stdout(predicate::str::contains(&expected));
+                }
+                .boxed()
+            })),
+            Step::Custom(Box::new(|state: &mut StepTestState| {
+                async {
+                    let querier_addr = state.cluster().querier().querier_grpc_base().to_string();
+
+                    // SQL syntax is not valid InfluxQL
+                    Command::cargo_bin("influxdb_iox")
+                        .unwrap()
+                        .arg("-h")
+                        .arg(&querier_addr)
+                        .arg("query")
+                        .arg("--query-type")
+                        .arg("influxql")
+                        .arg(state.cluster().namespace())
+                        .arg("SELECT * FROM my_awesome_table3 LIMIT 1 OFFSET 1 ORDER BY val")
+                        .assert()
+                        .failure()
+                        .stderr(predicate::str::contains("Error while planning query"));
+                }
+                .boxed()
+            })),
+        ],
+    )
+    .run()
+    .await
+}
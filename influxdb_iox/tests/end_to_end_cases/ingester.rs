@@ -46,13 +46,19 @@ async fn ingester_flight_api() {
     let (msg, app_metadata) = performed_query.next().await.unwrap().unwrap();
     msg.unwrap_none();
     let partition_id = app_metadata.partition_id;
+    let max_sequence_number = app_metadata
+        .status
+        .as_ref()
+        .and_then(|status| status.max_sequence_number);
+    assert!(max_sequence_number.is_some());
     assert_eq!(
         app_metadata,
         IngesterQueryResponseMetadata {
             partition_id,
             status: Some(PartitionStatus {
                 parquet_max_sequence_number: None,
-                tombstone_max_sequence_number: None
+                tombstone_max_sequence_number: None,
+                max_sequence_number,
             })
         },
     );
@@ -1,8 +1,13 @@
 pub(crate) mod influxrpc;
 mod multi_ingester;
 
+use arrow_util::assert_batches_sorted_eq;
 use assert_cmd::Command;
 use futures::FutureExt;
+use influxdb_iox_client::flight::{
+    generated_types::{read_info::QueryType, ReadInfo},
+    row::DeserializeRow,
+};
 use predicates::prelude::*;
 use test_helpers_end_to_end::{
     maybe_skip_integration, try_run_query, MiniCluster, Step, StepTest, StepTestState, TestConfig,
@@ -45,6 +50,145 @@ async fn basic_ingester() {
     .await
 }
 
+#[tokio::test]
+async fn basic_typed_query() {
+    test_helpers::maybe_start_logging();
+    let database_url = maybe_skip_integration!();
+
+    let table_name = "the_table";
+
+    // Set up the cluster  ====================================
+    let mut cluster = MiniCluster::create_shared(database_url).await;
+
+    #[derive(Debug, PartialEq, DeserializeRow)]
+    struct Row {
+        tag1: String,
+        tag2: Option<String>,
+        #[iox(rename = "val")]
+        value: i64,
+        time: i64,
+    }
+
+    StepTest::new(
+        &mut cluster,
+        vec![
+            Step::WriteLineProtocol(format!(
+                "{},tag1=A,tag2=B val=42i 123456\n\
+                 {},tag1=A val=43i 123457",
+                table_name, table_name
+            )),
+            Step::WaitForReadable,
+            Step::Custom(Box::new(move |state: &mut StepTestState| {
+                async move {
+                    let mut client = influxdb_iox_client::flight::Client::new(
+                        state.cluster().querier().querier_grpc_connection(),
+                    );
+                    let rows: Vec<Row> = client
+                        .query_typed(ReadInfo {
+                            namespace_name: state.cluster().namespace().to_string(),
+                            sql_query: format!("select * from {} order by time", table_name),
+                            timeout_millis: 0,
+                            params: vec![],
+                            query_type: QueryType::Sql.into(),
+                            snapshot_token: String::new(),
+                            partition_count: 0,
+                            partition: 0,
+                        })
+                        .await
+                        .unwrap();
+
+                    assert_eq!(
+                        rows,
+                        vec![
+                            Row {
+                                tag1: "A".to_string(),
+                                tag2: Some("B".to_string()),
+                                value: 42,
+                                time: 123456,
+                            },
+                            Row {
+                                tag1: "A".to_string(),
+                                tag2: None,
+                                value: 43,
+                                time: 123457,
+                            },
+                        ]
+                    );
+                }
+                .boxed()
+            })),
+        ],
+    )
+    .run()
+    .await
+}
+
+#[tokio::test]
+async fn partitioned_query() {
+    test_helpers::maybe_start_logging();
+    let database_url = maybe_skip_integration!();
+
+    let table_name = "the_table";
+
+    // Set up the cluster  ====================================
+    let mut cluster = MiniCluster::create_shared(database_url).await;
+
+    StepTest::new(
+        &mut cluster,
+        vec![
+            Step::WriteLineProtocol(format!(
+                "{},tag1=A val=1i 100\n\
+                 {},tag1=B val=2i 200\n\
+                 {},tag1=C val=3i 300",
+                table_name, table_name, table_name
+            )),
+            Step::WaitForReadable,
+            Step::Custom(Box::new(move |state: &mut StepTestState| {
+                async move {
+                    let mut client = influxdb_iox_client::flight::Client::new(
+                        state.cluster().querier().querier_grpc_connection(),
+                    );
+                    let request = ReadInfo {
+                        namespace_name: state.cluster().namespace().to_string(),
+                        sql_query: format!("select tag1, val from {}", table_name),
+                        timeout_millis: 0,
+                        params: vec![],
+                        query_type: QueryType::Sql.into(),
+                        snapshot_token: String::new(),
+                        partition_count: 0,
+                        partition: 0,
+                    };
+
+                    let partitions = client.partition_query(request.clone(), 4).await.unwrap();
+                    assert!(!partitions.is_empty() && partitions.len() <= 4);
+                    assert!(partitions.iter().all(|p| !p.snapshot_token.is_empty()));
+
+                    let batches = client
+                        .perform_partitioned_query(request, 4)
+                        .await
+                        .unwrap()
+                        .collect()
+                        .await
+                        .unwrap();
+                    let expected = [
+                        "+------+-----+",
+                        "| tag1 | val |",
+                        "+------+-----+",
+                        "| A    | 1   |",
+                        "| B    | 2   |",
+                        "| C    | 3   |",
+                        "+------+-----+",
+                    ];
+                    assert_batches_sorted_eq!(&expected, &batches);
+                }
+                .boxed()
+            })),
+        ],
+    )
+    .run()
+    .await
+}
+
 #[tokio::test]
 async fn basic_on_parquet() {
     test_helpers::maybe_start_logging();
@@ -1,5 +1,6 @@
+use futures::FutureExt;
 use influxdb_iox_client::write::generated_types::{column, Column, TableBatch};
-use test_helpers_end_to_end::{maybe_skip_integration, MiniCluster, Step, StepTest};
+use test_helpers_end_to_end::{maybe_skip_integration, MiniCluster, Step, StepTest, StepTestState};
 
 #[tokio::test]
 async fn write_via_grpc() {
@@ -99,3 +100,65 @@ async fn write_via_grpc() {
     .run()
     .await
 }
+
+#[tokio::test]
+async fn write_via_buffered_grpc() {
+    test_helpers::maybe_start_logging();
+    let database_url = maybe_skip_integration!();
+
+    let table_name = "the_table";
+
+    // Set up the cluster  ====================================
+    let mut cluster = MiniCluster::create_shared(database_url).await;
+
+    StepTest::new(
+        &mut cluster,
+        vec![
+            Step::Custom(Box::new(move |state: &mut StepTestState| {
+                async move {
+                    let mut writer = influxdb_iox_client::write::Client::new(
+                        state.cluster().router().router_grpc_connection(),
+                    )
+                    .buffered(state.cluster().namespace());
+
+                    // lines are buffered...
+                    let written = writer
+                        .write_lp(format!("{},tag1=A val=1i 100", table_name))
+                        .await
+                        .unwrap();
+                    assert_eq!(written, 0);
+                    let written = writer
+                        .write_lp(format!("{},tag1=B val=2i 200", table_name))
+                        .await
+                        .unwrap();
+                    assert_eq!(written, 0);
+
+                    // ...invalid lines are rejected without poisoning the batch...
+                    writer.write_lp("not line protocol").await.unwrap_err();
+
+                    // ...and written in one gzip-compressed request on flush
+                    assert_eq!(writer.flush().await.unwrap(), 2);
+                    assert_eq!(writer.buffered_bytes(), 0);
+                }
+                .boxed()
+            })),
+            // write through the regular API to get a write token to wait for
+            Step::WriteLineProtocol(format!("{},tag1=C val=3i 300", table_name)),
+            Step::WaitForReadable,
+            Step::Query {
+                sql: format!("select * from {}", table_name),
+                expected: vec![
+                    "+------+--------------------------------+-----+",
+                    "| tag1 | time                           | val |",
+                    "+------+--------------------------------+-----+",
+                    "| A    | 1970-01-01T00:00:00.000000100Z | 1   |",
+                    "| B    | 1970-01-01T00:00:00.000000200Z | 2   |",
+                    "| C    | 1970-01-01T00:00:00.000000300Z | 3   |",
+                    "+------+--------------------------------+-----+",
+                ],
+            },
+        ],
+    )
+    .run()
+    .await
+}
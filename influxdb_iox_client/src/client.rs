@@ -1,6 +1,9 @@
 /// Errors for the client
 pub mod error;
 
+/// Client for compactor API
+pub mod compactor;
+
 /// Client for health checking API
 pub mod health;
 
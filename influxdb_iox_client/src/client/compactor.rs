@@ -0,0 +1,50 @@
+use self::generated_types::{compaction_service_client::CompactionServiceClient, *};
+
+use crate::connection::Connection;
+use crate::error::Error;
+
+/// Re-export generated_types
+pub mod generated_types {
+    pub use generated_types::influxdata::iox::compactor::v1::*;
+}
+
+/// A basic client for operating a compactor.
+#[derive(Debug, Clone)]
+pub struct Client {
+    inner: CompactionServiceClient<Connection>,
+}
+
+impl Client {
+    /// Creates a new client with the provided connection
+    pub fn new(channel: Connection) -> Self {
+        Self {
+            inner: CompactionServiceClient::new(channel),
+        }
+    }
+
+    /// Compact all files of the given partition right away
+    pub async fn compact_partition(&mut self, partition_id: i64) -> Result<(), Error> {
+        self.inner
+            .compact_partition(CompactPartitionRequest { partition_id })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Rewrite all parquet files of the given table
+    pub async fn rewrite_table(&mut self, table_id: i64) -> Result<RewriteTableResponse, Error> {
+        let response = self
+            .inner
+            .rewrite_table(RewriteTableRequest { table_id })
+            .await?;
+
+        Ok(response.into_inner())
+    }
+
+    /// Get the current activity of the compactor
+    pub async fn get_state(&mut self) -> Result<GetStateResponse, Error> {
+        let response = self.inner.get_state(GetStateRequest {}).await?;
+
+        Ok(response.into_inner())
+    }
+}
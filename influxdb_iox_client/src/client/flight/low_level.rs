@@ -24,7 +24,10 @@ use ::generated_types::influxdata::iox::{
 use futures_util::stream;
 use futures_util::stream::StreamExt;
 use prost::Message;
-use tonic::Streaming;
+use tonic::{
+    metadata::{AsciiMetadataValue, MetadataMap},
+    Streaming,
+};
 
 use arrow::{
     array::ArrayRef,
@@ -33,7 +36,8 @@ use arrow::{
     record_batch::RecordBatch,
 };
 use arrow_flight::{
-    flight_service_client::FlightServiceClient, utils::flight_data_to_arrow_batch, FlightData,
+    flight_descriptor::DescriptorType, flight_service_client::FlightServiceClient,
+    utils::flight_data_to_arrow_batch, Action, FlightData, FlightDescriptor, FlightInfo,
     HandshakeRequest, Ticket,
 };
 
@@ -41,6 +45,12 @@ use super::Error;
 use crate::connection::Connection;
 use rand::Rng;
 
+/// Name of the response header that carries the ID the server assigned to a query.
+pub const QUERY_ID_HEADER: &str = "iox-query-id";
+
+/// Type of the Flight action that cancels a running query.
+pub const CANCEL_QUERY_ACTION: &str = "CancelQuery";
+
 /// Metadata that can be send during flight requests.
 pub trait ClientMetadata: Message {
     /// Response metadata.
@@ -61,12 +71,16 @@ impl ClientMetadata for IngesterQueryRequest {
 /// The type parameter `T` -- which must implement [`ClientMetadata`] describes the request and response metadata that
 /// is send and received during the flight request. The request is encoded as protobuf and send as the Flight "ticket",
 /// the response is received via the so called "app metadata".
-#[derive(Debug)]
+///
+/// Clones share the underlying connection, so that they can be used to perform queries
+/// concurrently.
+#[derive(Debug, Clone)]
 pub struct Client<T>
 where
     T: ClientMetadata,
 {
     inner: FlightServiceClient<Connection>,
+    headers: MetadataMap,
     _phantom: PhantomData<T>,
 }
 
@@ -78,16 +92,48 @@ where
     pub fn new(channel: Connection) -> Self {
         Self {
             inner: FlightServiceClient::new(channel),
+            headers: MetadataMap::new(),
             _phantom: PhantomData::default(),
         }
     }
 
+    /// Add a header (gRPC metadata entry) that is sent with every query request.
+    pub fn add_header(&mut self, key: &'static str, value: &str) -> Result<(), Error> {
+        let value: AsciiMetadataValue = value.parse()?;
+        self.headers.insert(key, value);
+        Ok(())
+    }
+
     /// Query the given database with the given SQL query, and return a
     /// [`PerformQuery`] instance that streams low-level message results.
     pub async fn perform_query(&mut self, request: T) -> Result<PerformQuery<T::Response>, Error> {
         PerformQuery::<T::Response>::new(self, request).await
     }
 
+    /// Ask the server how to fetch the results of `request`, which is sent as command of the
+    /// flight descriptor.
+    ///
+    /// Every endpoint of the returned [`FlightInfo`] carries a ticket that can be passed to
+    /// [`perform_query`](Self::perform_query) after decoding it into a `T`.
+    pub async fn get_flight_info(&mut self, request: T) -> Result<FlightInfo, Error> {
+        let descriptor = FlightDescriptor {
+            r#type: DescriptorType::Cmd as i32,
+            cmd: request.encode_to_vec(),
+            path: vec![],
+        };
+        let mut request = tonic::Request::new(descriptor);
+        *request.metadata_mut() = self.headers.clone();
+
+        Ok(self.inner.get_flight_info(request).await?.into_inner())
+    }
+
+    /// Cancel the running query with the given ID, see [`PerformQuery::query_id`].
+    ///
+    /// This aborts the query execution on the server, the stream of the query ends with an error.
+    pub async fn cancel_query(&mut self, query_id: &str) -> Result<(), Error> {
+        cancel_query(&mut self.inner, &self.headers, query_id).await
+    }
+
     /// Perform a handshake with the server, as defined by the Arrow Flight API.
     pub async fn handshake(&mut self) -> Result<(), Error> {
         let request = HandshakeRequest {
@@ -112,6 +158,23 @@ where
     }
 }
 
+async fn cancel_query(
+    client: &mut FlightServiceClient<Connection>,
+    headers: &MetadataMap,
+    query_id: &str,
+) -> Result<(), Error> {
+    let mut request = tonic::Request::new(Action {
+        r#type: CANCEL_QUERY_ACTION.to_string(),
+        body: query_id.as_bytes().to_vec(),
+    });
+    *request.metadata_mut() = headers.clone();
+
+    let mut response = client.do_action(request).await?.into_inner();
+    while response.message().await?.is_some() {}
+
+    Ok(())
+}
+
 #[derive(Debug)]
 struct PerformQueryState {
     schema: Arc<Schema>,
@@ -162,11 +225,18 @@ impl LowLevelMessage {
 /// A struct that manages the stream of Arrow `RecordBatch` results from an
 /// Arrow Flight query. Created by calling the `perform_query` method on a
 /// Flight [`Client`].
+///
+/// The results are streamed: the server only computes the next batch once the previous one was
+/// received, so a slow consumer slows down the query instead of buffering its results. Dropping
+/// this struct or calling [`cancel`](Self::cancel) aborts the query on the server.
 #[derive(Debug)]
 pub struct PerformQuery<T>
 where
     T: Default + Message,
 {
+    client: FlightServiceClient<Connection>,
+    headers: MetadataMap,
+    query_id: Option<String>,
     response: Streaming<FlightData>,
     state: Option<PerformQueryState>,
     _phantom: PhantomData<T>,
@@ -185,15 +255,39 @@ where
         let t = Ticket {
             ticket: bytes.to_vec(),
         };
-        let response = flight.inner.do_get(t).await?.into_inner();
+        let mut request = tonic::Request::new(t);
+        *request.metadata_mut() = flight.headers.clone();
+        let response = flight.inner.do_get(request).await?;
+        let query_id = response
+            .metadata()
+            .get(QUERY_ID_HEADER)
+            .and_then(|query_id| query_id.to_str().ok())
+            .map(|query_id| query_id.to_string());
 
         Ok(Self {
+            client: flight.inner.clone(),
+            headers: flight.headers.clone(),
+            query_id,
             state: None,
-            response,
+            response: response.into_inner(),
             _phantom: Default::default(),
         })
     }
 
+    /// ID that the server assigned to this query, or `None` if the server did not send one.
+    pub fn query_id(&self) -> Option<&str> {
+        self.query_id.as_deref()
+    }
+
+    /// Cancel this query on the server.
+    ///
+    /// Results that were already received may still be returned by [`next`](Self::next), after
+    /// that the stream ends with a "cancelled" error.
+    pub async fn cancel(&mut self) -> Result<(), Error> {
+        let query_id = self.query_id.as_deref().ok_or(Error::NoQueryId)?;
+        cancel_query(&mut self.client, &self.headers, query_id).await
+    }
+
     /// Returns next low-level message, or `None` if there are no further results available.
     pub async fn next(&mut self) -> Result<Option<(LowLevelMessage, T)>, Error> {
         let Self {
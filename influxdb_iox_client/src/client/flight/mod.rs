@@ -1,4 +1,10 @@
 use ::generated_types::influxdata::iox::querier::v1::{AppMetadata, ReadInfo};
+use futures_util::{
+    future::try_join_all,
+    stream::{self, BoxStream},
+    StreamExt,
+};
+use prost::Message;
 use thiserror::Error;
 
 use arrow::{
@@ -17,9 +23,13 @@ pub mod generated_types {
 }
 
 pub mod low_level;
-pub use low_level::{Client as LowLevelClient, PerformQuery as LowLevelPerformQuery};
+pub mod row;
+pub use low_level::{
+    Client as LowLevelClient, PerformQuery as LowLevelPerformQuery, CANCEL_QUERY_ACTION,
+    QUERY_ID_HEADER,
+};
 
-use self::low_level::LowLevelMessage;
+use self::{low_level::LowLevelMessage, row::DeserializeRow};
 
 /// Error responses when querying an IOx database using the Arrow Flight gRPC
 /// API.
@@ -68,6 +78,22 @@ pub enum Error {
     /// Unexpected schema change.
     #[error("Unexpected schema change")]
     UnexpectedSchemaChange,
+
+    /// Invalid header value.
+    #[error(transparent)]
+    InvalidHeaderValue(#[from] tonic::metadata::errors::InvalidMetadataValue),
+
+    /// The server did not assign an ID to the query, so it cannot be cancelled.
+    #[error("Query has no ID")]
+    NoQueryId,
+
+    /// The query result could not be deserialized into the requested type.
+    #[error(transparent)]
+    Deserialize(#[from] row::DeserializeError),
+
+    /// The server returned a flight endpoint without a ticket.
+    #[error("Flight endpoint without ticket")]
+    NoTicket,
 }
 
 /// An IOx Arrow Flight gRPC API client.
@@ -85,7 +111,7 @@ pub enum Error {
 ///     connection::Builder,
 ///     flight::{
 ///         Client,
-///         generated_types::ReadInfo,
+///         generated_types::{read_info::QueryType, ReadInfo},
 ///     },
 /// };
 ///
@@ -100,6 +126,12 @@ pub enum Error {
 ///     .perform_query(ReadInfo {
 ///         namespace_name: "my_database".to_string(),
 ///         sql_query: "select * from cpu_load".to_string(),
+///         timeout_millis: 0,
+///         params: vec![],
+///         query_type: QueryType::Sql.into(),
+///         snapshot_token: String::new(),
+///         partition_count: 0,
+///         partition: 0,
 ///     })
 ///     .await
 ///     .expect("query request should work");
@@ -111,7 +143,11 @@ pub enum Error {
 /// }
 /// # }
 /// ```
-#[derive(Debug)]
+///
+/// # Partitioned Queries
+/// Large results can be fetched over several concurrent streams, see
+/// [`perform_partitioned_query`](Client::perform_partitioned_query).
+#[derive(Debug, Clone)]
 pub struct Client {
     inner: LowLevelClient<ReadInfo>,
 }
@@ -130,6 +166,72 @@ impl Client {
         PerformQuery::new(self, request).await
     }
 
+    /// Query the given database and deserialize all result rows into `T`, see the
+    /// [`row`] module.
+    pub async fn query_typed<T: DeserializeRow>(
+        &mut self,
+        request: ReadInfo,
+    ) -> Result<Vec<T>, Error> {
+        let mut query = self.perform_query(request).await?;
+
+        let mut rows = vec![];
+        while let Some(batch) = query.next().await? {
+            rows.extend(T::deserialize_batch(&batch)?);
+        }
+
+        Ok(rows)
+    }
+
+    /// Ask the server to split `request` into at most `max_partitions` partitions (as many as the
+    /// server sees fit if zero).
+    ///
+    /// Returns one request per partition. Together they return the same rows as `request`, in no
+    /// particular order, because all of them are pinned to the same snapshot of the data. They can
+    /// be performed concurrently, also over different connections to the same querier.
+    pub async fn partition_query(
+        &mut self,
+        request: ReadInfo,
+        max_partitions: u32,
+    ) -> Result<Vec<ReadInfo>, Error> {
+        let request = ReadInfo {
+            partition_count: max_partitions,
+            partition: 0,
+            ..request
+        };
+        let flight_info = self.inner.get_flight_info(request).await?;
+
+        flight_info
+            .endpoint
+            .into_iter()
+            .map(|endpoint| {
+                let ticket = endpoint.ticket.ok_or(Error::NoTicket)?;
+                Ok(ReadInfo::decode(ticket.ticket.as_slice())?)
+            })
+            .collect()
+    }
+
+    /// Query the given database split into at most `max_partitions` partitions (see
+    /// [`partition_query`](Self::partition_query)) and fetch all of them concurrently.
+    pub async fn perform_partitioned_query(
+        &mut self,
+        request: ReadInfo,
+        max_partitions: u32,
+    ) -> Result<PartitionedQuery, Error> {
+        let partitions = self.partition_query(request, max_partitions).await?;
+        let queries = try_join_all(partitions.into_iter().map(|partition| {
+            let mut client = self.clone();
+            async move { client.perform_query(partition).await }
+        }))
+        .await?;
+
+        Ok(PartitionedQuery::new(queries))
+    }
+
+    /// Cancel the running query with the given ID, see [`PerformQuery::query_id`].
+    pub async fn cancel_query(&mut self, query_id: &str) -> Result<(), Error> {
+        self.inner.cancel_query(query_id).await
+    }
+
     /// Perform a handshake with the server, as defined by the Arrow Flight API.
     pub async fn handshake(&mut self) -> Result<(), Error> {
         self.inner.handshake().await
@@ -139,33 +241,66 @@ impl Client {
 /// A struct that manages the stream of Arrow `RecordBatch` results from an
 /// Arrow Flight query. Created by calling the `perform_query` method on a
 /// Flight [`Client`].
+///
+/// The results are streamed with backpressure, see [`LowLevelPerformQuery`].
 #[derive(Debug)]
 pub struct PerformQuery {
     inner: LowLevelPerformQuery<AppMetadata>,
     got_schema: bool,
+    query_id: Option<String>,
+    snapshot_token: Option<String>,
 }
 
 impl PerformQuery {
     pub(crate) async fn new(flight: &mut Client, request: ReadInfo) -> Result<Self, Error> {
         let inner = flight.inner.perform_query(request).await?;
+        let query_id = inner.query_id().map(|query_id| query_id.to_string());
 
         Ok(Self {
             inner,
             got_schema: false,
+            query_id,
+            snapshot_token: None,
         })
     }
 
+    /// ID that the server assigned to this query.
+    ///
+    /// This is `None` if the server did not send an ID.
+    pub fn query_id(&self) -> Option<&str> {
+        self.query_id.as_deref()
+    }
+
+    /// Token of the data this query saw.
+    ///
+    /// Pass it as `snapshot_token` of a [`ReadInfo`] to repeat the query on the same data. This
+    /// is `None` until the schema was received or if the server did not send a token.
+    pub fn snapshot_token(&self) -> Option<&str> {
+        self.snapshot_token.as_deref()
+    }
+
+    /// Cancel this query on the server, see [`LowLevelPerformQuery::cancel`].
+    pub async fn cancel(&mut self) -> Result<(), Error> {
+        self.inner.cancel().await
+    }
+
     /// Returns the next `RecordBatch` available for this query, or `None` if
     /// there are no further results available.
     pub async fn next(&mut self) -> Result<Option<RecordBatch>, Error> {
         loop {
             match self.inner.next().await? {
                 None => return Ok(None),
-                Some((LowLevelMessage::Schema(_), _)) => {
+                Some((LowLevelMessage::Schema(_), app_metadata)) => {
                     if self.got_schema {
                         return Err(Error::UnexpectedSchemaChange);
                     }
                     self.got_schema = true;
+                    if !app_metadata.query_id.is_empty() {
+                        self.query_id = Some(app_metadata.query_id);
+                    }
+                    if !app_metadata.snapshot_token.is_empty() {
+                        self.snapshot_token = Some(app_metadata.snapshot_token);
+                    }
                 }
                 Some((LowLevelMessage::RecordBatch(batch), _)) => return Ok(Some(batch)),
                 Some((LowLevelMessage::None, _)) => (),
@@ -183,3 +318,66 @@ impl PerformQuery {
         Ok(batches)
     }
 }
+
+/// Results of a query that is split into partitions, created by calling the
+/// [`perform_partitioned_query`](Client::perform_partitioned_query) method on a Flight [`Client`].
+///
+/// All partitions are streamed concurrently, `RecordBatch`es are returned in the order in which
+/// they arrive. Dropping this struct aborts all partitions on the server.
+pub struct PartitionedQuery {
+    query_ids: Vec<String>,
+    batches: BoxStream<'static, Result<RecordBatch, Error>>,
+}
+
+impl std::fmt::Debug for PartitionedQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PartitionedQuery")
+            .field("query_ids", &self.query_ids)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartitionedQuery {
+    fn new(queries: Vec<PerformQuery>) -> Self {
+        let query_ids = queries
+            .iter()
+            .filter_map(|query| query.query_id().map(|query_id| query_id.to_string()))
+            .collect();
+
+        let batches = stream::select_all(queries.into_iter().map(|query| {
+            stream::unfold(Some(query), |query| async move {
+                let mut query = query?;
+                match query.next().await {
+                    Ok(Some(batch)) => Some((Ok(batch), Some(query))),
+                    Ok(None) => None,
+                    // the stream of a partition ends with its first error
+                    Err(e) => Some((Err(e), None)),
+                }
+            })
+            .boxed()
+        }))
+        .boxed();
+
+        Self { query_ids, batches }
+    }
+
+    /// IDs that the server assigned to the queries of the partitions.
+    pub fn query_ids(&self) -> &[String] {
+        &self.query_ids
+    }
+
+    /// Returns the next `RecordBatch` of any partition, or `None` if all partitions are done.
+    pub async fn next(&mut self) -> Result<Option<RecordBatch>, Error> {
+        self.batches.next().await.transpose()
+    }
+
+    /// Collect and return all `RecordBatch`es of all partitions into a `Vec`
+    pub async fn collect(&mut self) -> Result<Vec<RecordBatch>, Error> {
+        let mut batches = Vec::new();
+        while let Some(data) = self.next().await? {
+            batches.push(data);
+        }
+
+        Ok(batches)
+    }
+}
@@ -0,0 +1,283 @@
+//! Deserialization of query results into user-defined structs.
+//!
+//! Implement [`DeserializeRow`] for a struct, usually via `#[derive(DeserializeRow)]`, and use
+//! [`Client::query_typed`](super::Client::query_typed) to receive the rows of a query as instances
+//! of that struct:
+//!
+//! ```rust
+//! use influxdb_iox_client::flight::row::DeserializeRow;
+//!
+//! #[derive(Debug, DeserializeRow)]
+//! struct Cpu {
+//!     host: String,
+//!     #[iox(rename = "usage_user")]
+//!     user: f64,
+//!     usage_system: Option<f64>,
+//!     /// Nanoseconds since the epoch
+//!     time: i64,
+//! }
+//! ```
+//!
+//! Fields are read from the column of the same name (or the name given by
+//! `#[iox(rename = "...")]`). See [`FromColumn`] for the supported field types; nullable columns
+//! must be read into `Option` fields.
+
+use arrow::{
+    array::{
+        Array, ArrayRef, BooleanArray, DictionaryArray, Float64Array, Int64Array, StringArray,
+        TimestampNanosecondArray, UInt64Array,
+    },
+    datatypes::{DataType, Int32Type},
+};
+use thiserror::Error;
+
+pub use arrow::record_batch::RecordBatch;
+pub use influxdb_iox_client_derive::DeserializeRow;
+
+/// Errors deserializing rows.
+#[derive(Debug, Error)]
+pub enum DeserializeError {
+    /// The query result has no column for a field.
+    #[error("Column '{0}' not found in query result")]
+    MissingColumn(String),
+
+    /// The type of a column cannot be read into the type of its field.
+    #[error("Column '{column}' of type {data_type} cannot be read as {target}")]
+    UnsupportedType {
+        /// Column name
+        column: String,
+        /// Arrow type of the column
+        data_type: DataType,
+        /// Rust type of the field
+        target: &'static str,
+    },
+
+    /// A column is null in a row, but its field is not an `Option`.
+    #[error("Column '{column}' is null in row {row}, use an Option field for nullable columns")]
+    UnexpectedNull {
+        /// Column name
+        column: String,
+        /// Row within the record batch
+        row: usize,
+    },
+}
+
+/// A type whose instances can be read from the rows of a [`RecordBatch`].
+///
+/// Usually derived, see the [module documentation](self).
+pub trait DeserializeRow: Sized {
+    /// Read all rows of `batch`.
+    fn deserialize_batch(batch: &RecordBatch) -> Result<Vec<Self>, DeserializeError>;
+}
+
+/// A type that can be read from a single value of a column.
+///
+/// Supported are:
+///
+/// - `i64` from `Int64` and nanosecond `Timestamp` columns (e.g. `time`)
+/// - `u64` from `UInt64` columns
+/// - `f64` from `Float64` columns
+/// - `bool` from `Boolean` columns
+/// - `String` from `Utf8` and dictionary-encoded `Utf8` columns (e.g. tags)
+/// - `Option<T>` for all of the above, which is `None` for null values
+pub trait FromColumn: Sized {
+    /// Read the value at `row` of `array`, which is the column with the name `column`.
+    fn from_column(column: &str, array: &dyn Array, row: usize) -> Result<Self, DeserializeError>;
+}
+
+/// Look up the column with the name `name` in `batch`.
+pub fn column<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a ArrayRef, DeserializeError> {
+    batch
+        .schema()
+        .index_of(name)
+        .map(|idx| batch.column(idx))
+        .map_err(|_| DeserializeError::MissingColumn(name.to_string()))
+}
+
+impl<T: FromColumn> FromColumn for Option<T> {
+    fn from_column(column: &str, array: &dyn Array, row: usize) -> Result<Self, DeserializeError> {
+        if array.is_null(row) {
+            Ok(None)
+        } else {
+            T::from_column(column, array, row).map(Some)
+        }
+    }
+}
+
+impl FromColumn for i64 {
+    fn from_column(column: &str, array: &dyn Array, row: usize) -> Result<Self, DeserializeError> {
+        check_not_null(column, array, row)?;
+        let any = array.as_any();
+        if let Some(array) = any.downcast_ref::<Int64Array>() {
+            Ok(array.value(row))
+        } else if let Some(array) = any.downcast_ref::<TimestampNanosecondArray>() {
+            Ok(array.value(row))
+        } else {
+            Err(unsupported(column, array, "i64"))
+        }
+    }
+}
+
+impl FromColumn for u64 {
+    fn from_column(column: &str, array: &dyn Array, row: usize) -> Result<Self, DeserializeError> {
+        check_not_null(column, array, row)?;
+        array
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .map(|array| array.value(row))
+            .ok_or_else(|| unsupported(column, array, "u64"))
+    }
+}
+
+impl FromColumn for f64 {
+    fn from_column(column: &str, array: &dyn Array, row: usize) -> Result<Self, DeserializeError> {
+        check_not_null(column, array, row)?;
+        array
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .map(|array| array.value(row))
+            .ok_or_else(|| unsupported(column, array, "f64"))
+    }
+}
+
+impl FromColumn for bool {
+    fn from_column(column: &str, array: &dyn Array, row: usize) -> Result<Self, DeserializeError> {
+        check_not_null(column, array, row)?;
+        array
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .map(|array| array.value(row))
+            .ok_or_else(|| unsupported(column, array, "bool"))
+    }
+}
+
+impl FromColumn for String {
+    fn from_column(column: &str, array: &dyn Array, row: usize) -> Result<Self, DeserializeError> {
+        check_not_null(column, array, row)?;
+        let any = array.as_any();
+        if let Some(array) = any.downcast_ref::<StringArray>() {
+            return Ok(array.value(row).to_string());
+        }
+
+        if let Some(dictionary) = any.downcast_ref::<DictionaryArray<Int32Type>>() {
+            if let Some(values) = dictionary.values().as_any().downcast_ref::<StringArray>() {
+                let key = dictionary.keys().value(row) as usize;
+                return Ok(values.value(key).to_string());
+            }
+        }
+
+        Err(unsupported(column, array, "String"))
+    }
+}
+
+fn check_not_null(column: &str, array: &dyn Array, row: usize) -> Result<(), DeserializeError> {
+    if array.is_null(row) {
+        return Err(DeserializeError::UnexpectedNull {
+            column: column.to_string(),
+            row,
+        });
+    }
+    Ok(())
+}
+
+fn unsupported(column: &str, array: &dyn Array, target: &'static str) -> DeserializeError {
+    DeserializeError::UnsupportedType {
+        column: column.to_string(),
+        data_type: array.data_type().clone(),
+        target,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, DeserializeRow)]
+    struct Row {
+        host: String,
+        region: Option<String>,
+        #[iox(rename = "usage_user")]
+        user: f64,
+        count: Option<u64>,
+        active: bool,
+        time: i64,
+    }
+
+    fn batch() -> RecordBatch {
+        let host: DictionaryArray<Int32Type> = vec!["a", "b"].into_iter().collect();
+        RecordBatch::try_from_iter(vec![
+            ("host", Arc::new(host) as ArrayRef),
+            (
+                "region",
+                Arc::new(StringArray::from(vec![Some("west"), None])),
+            ),
+            ("usage_user", Arc::new(Float64Array::from(vec![1.5, 2.5]))),
+            ("count", Arc::new(UInt64Array::from(vec![None, Some(3)]))),
+            ("active", Arc::new(BooleanArray::from(vec![true, false]))),
+            (
+                "time",
+                Arc::new(TimestampNanosecondArray::from(vec![100, 200])),
+            ),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let rows = Row::deserialize_batch(&batch()).unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                Row {
+                    host: "a".to_string(),
+                    region: Some("west".to_string()),
+                    user: 1.5,
+                    count: None,
+                    active: true,
+                    time: 100,
+                },
+                Row {
+                    host: "b".to_string(),
+                    region: None,
+                    user: 2.5,
+                    count: Some(3),
+                    active: false,
+                    time: 200,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        #[derive(Debug, DeserializeRow)]
+        struct Missing {
+            #[allow(dead_code)]
+            missing: i64,
+        }
+        let err = Missing::deserialize_batch(&batch()).unwrap_err();
+        assert!(matches!(err, DeserializeError::MissingColumn(c) if c == "missing"));
+
+        #[derive(Debug, DeserializeRow)]
+        struct WrongType {
+            #[allow(dead_code)]
+            host: f64,
+        }
+        let err = WrongType::deserialize_batch(&batch()).unwrap_err();
+        assert!(
+            matches!(err, DeserializeError::UnsupportedType { column, target: "f64", .. } if column == "host")
+        );
+
+        #[derive(Debug, DeserializeRow)]
+        struct NotNullable {
+            #[allow(dead_code)]
+            region: String,
+        }
+        let err = NotNullable::deserialize_batch(&batch()).unwrap_err();
+        assert!(
+            matches!(err, DeserializeError::UnexpectedNull { column, row: 1 } if column == "region")
+        );
+    }
+}
@@ -34,4 +34,42 @@ impl Client {
 
         Ok(response.into_inner().schema.unwrap_field("schema")?)
     }
+
+    /// Start changing the type of a field column.
+    pub async fn start_column_type_migration(
+        &mut self,
+        namespace: &str,
+        table: &str,
+        column: &str,
+        column_type: column_schema::ColumnType,
+    ) -> Result<ColumnTypeMigration, Error> {
+        let response = self
+            .inner
+            .start_column_type_migration(StartColumnTypeMigrationRequest {
+                namespace: namespace.to_string(),
+                table: table.to_string(),
+                column: column.to_string(),
+                column_type: column_type as i32,
+            })
+            .await?;
+
+        Ok(response.into_inner().migration.unwrap_field("migration")?)
+    }
+
+    /// Get the column type migrations of a table and their progress.
+    pub async fn get_column_type_migrations(
+        &mut self,
+        namespace: &str,
+        table: &str,
+    ) -> Result<Vec<ColumnTypeMigration>, Error> {
+        let response = self
+            .inner
+            .get_column_type_migrations(GetColumnTypeMigrationsRequest {
+                namespace: namespace.to_string(),
+                table: table.to_string(),
+            })
+            .await?;
+
+        Ok(response.into_inner().migrations)
+    }
 }
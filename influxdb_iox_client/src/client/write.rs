@@ -7,6 +7,9 @@ use self::generated_types::write_service_client::WriteServiceClient;
 
 use crate::connection::Connection;
 use crate::error::Error;
+#[cfg(feature = "write_lp")]
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tonic::codec::CompressionEncoding;
 
 /// An IOx Write API client.
 ///
@@ -45,6 +48,21 @@ impl Client {
         }
     }
 
+    /// Compress all write requests with gzip.
+    ///
+    /// The server must accept gzip-compressed requests, which IOx routers do.
+    pub fn with_gzip(self) -> Self {
+        Self {
+            inner: self.inner.send_compressed(CompressionEncoding::Gzip),
+        }
+    }
+
+    /// Create a [`BufferedWriter`] that writes to namespace `db_name` using this client.
+    #[cfg(feature = "write_lp")]
+    pub fn buffered(self, db_name: impl Into<String>) -> BufferedWriter {
+        BufferedWriter::new(self, db_name)
+    }
+
     /// Write the [LineProtocol] formatted data in `lp_data` to
     /// database `name`. Lines without a timestamp will be assigned `default_time`
     ///
@@ -84,3 +102,212 @@ impl Client {
         Ok(self.inner.write(write_request).await?)
     }
 }
+
+/// Writes line protocol in batches, see [`Client::buffered`].
+///
+/// Lines are buffered until the buffer exceeds [`max_batch_bytes`](Self::with_max_batch_bytes)
+/// or its oldest line is older than [`max_batch_age`](Self::with_max_batch_age). Both thresholds
+/// are checked whenever lines are written, so call [`flush`](Self::flush) after the last write
+/// (and periodically if writes are sparse).
+///
+/// Payloads are gzip-compressed and writes that fail with a transient error (e.g. the router
+/// being unavailable or rate limiting) are retried with exponential backoff.
+///
+/// ```no_run
+/// #[tokio::main]
+/// # async fn main() {
+/// use influxdb_iox_client::{
+///     write::Client,
+///     connection::Builder,
+/// };
+///
+/// let mut connection = Builder::default()
+///     .build("http://127.0.0.1:8082")
+///     .await
+///     .unwrap();
+///
+/// let mut writer = Client::new(connection).buffered("bananas");
+///
+/// for i in 0..10_000 {
+///     writer
+///         .write_lp(format!("cpu,region=west user={i} {i}"))
+///         .await
+///         .expect("failed to write to IOx");
+/// }
+/// writer.flush().await.expect("failed to write to IOx");
+/// # }
+/// ```
+#[cfg(feature = "write_lp")]
+#[derive(Debug)]
+pub struct BufferedWriter {
+    client: Client,
+    db_name: String,
+    max_batch_bytes: usize,
+    max_batch_age: Duration,
+    max_retries: usize,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+
+    /// Buffered lines, separated by newlines.
+    buffer: String,
+
+    /// When the first line in `buffer` was written and the timestamp (in nanoseconds since the
+    /// epoch) assigned to buffered lines without a timestamp.
+    batch_start: Option<(Instant, i64)>,
+}
+
+#[cfg(feature = "write_lp")]
+impl BufferedWriter {
+    /// Default for [`with_max_batch_bytes`](Self::with_max_batch_bytes).
+    pub const DEFAULT_MAX_BATCH_BYTES: usize = 1024 * 1024;
+
+    /// Default for [`with_max_batch_age`](Self::with_max_batch_age).
+    pub const DEFAULT_MAX_BATCH_AGE: Duration = Duration::from_secs(1);
+
+    /// Default for [`with_max_retries`](Self::with_max_retries).
+    pub const DEFAULT_MAX_RETRIES: usize = 5;
+
+    fn new(client: Client, db_name: impl Into<String>) -> Self {
+        Self {
+            client: client.with_gzip(),
+            db_name: db_name.into(),
+            max_batch_bytes: Self::DEFAULT_MAX_BATCH_BYTES,
+            max_batch_age: Self::DEFAULT_MAX_BATCH_AGE,
+            max_retries: Self::DEFAULT_MAX_RETRIES,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            buffer: String::new(),
+            batch_start: None,
+        }
+    }
+
+    /// Flush once the buffered line protocol exceeds `max_batch_bytes`.
+    pub fn with_max_batch_bytes(self, max_batch_bytes: usize) -> Self {
+        Self {
+            max_batch_bytes,
+            ..self
+        }
+    }
+
+    /// Flush once the oldest buffered line is older than `max_batch_age`.
+    pub fn with_max_batch_age(self, max_batch_age: Duration) -> Self {
+        Self {
+            max_batch_age,
+            ..self
+        }
+    }
+
+    /// Retry failed writes at most `max_retries` times.
+    pub fn with_max_retries(self, max_retries: usize) -> Self {
+        Self {
+            max_retries,
+            ..self
+        }
+    }
+
+    /// Back off between `initial_backoff` and `max_backoff` between retries.
+    pub fn with_backoff(self, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            initial_backoff,
+            max_backoff,
+            ..self
+        }
+    }
+
+    /// Number of bytes of line protocol that are currently buffered.
+    pub fn buffered_bytes(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Buffer the [LineProtocol] formatted data in `lp_data` and flush the buffer if it exceeds
+    /// one of the thresholds.
+    ///
+    /// Lines without a timestamp are assigned the time at which the first line of their batch
+    /// was buffered. Invalid line protocol is rejected right away and nothing of `lp_data` is
+    /// buffered in that case.
+    ///
+    /// Returns the number of lines that were written to the database by this call, i.e. 0 if
+    /// the lines were only buffered.
+    ///
+    /// [LineProtocol]: https://docs.influxdata.com/influxdb/v2.0/reference/syntax/line-protocol/#data-types-and-format
+    pub async fn write_lp(&mut self, lp_data: impl AsRef<str> + Send) -> Result<usize, Error> {
+        let lp_data = lp_data.as_ref().trim();
+        if lp_data.is_empty() {
+            return Ok(0);
+        }
+
+        // validate here so that invalid lines do not poison the entire batch
+        mutable_batch_lp::lines_to_batches(lp_data, 0).map_err(|e| Error::Client(Box::new(e)))?;
+
+        if !self.buffer.is_empty() {
+            self.buffer.push('\n');
+        }
+        self.buffer.push_str(lp_data);
+        let (started_at, _) = *self.batch_start.get_or_insert_with(|| {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos();
+            (Instant::now(), i64::try_from(now).unwrap_or(i64::MAX))
+        });
+
+        if self.buffer.len() >= self.max_batch_bytes || started_at.elapsed() >= self.max_batch_age {
+            self.flush().await
+        } else {
+            Ok(0)
+        }
+    }
+
+    /// Write all buffered lines to the database.
+    ///
+    /// Returns the number of written lines. If the write fails even after retrying, the lines
+    /// stay buffered and are written by the next flush.
+    pub async fn flush(&mut self) -> Result<usize, Error> {
+        let default_time = match self.batch_start {
+            Some((_, default_time)) => default_time,
+            None => return Ok(0),
+        };
+
+        let mut backoff = self.initial_backoff;
+        let mut retries = 0;
+        loop {
+            match self
+                .client
+                .write_lp(&self.db_name, &self.buffer, default_time)
+                .await
+            {
+                Ok(lines) => {
+                    self.buffer.clear();
+                    self.batch_start = None;
+                    return Ok(lines);
+                }
+                Err(e) if is_transient(&e) && retries < self.max_retries => {
+                    retries += 1;
+                    tokio::time::sleep(jitter(backoff)).await;
+                    backoff = (backoff * 2).min(self.max_backoff);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Returns `true` if a write that failed with `e` may succeed when retried.
+#[cfg(feature = "write_lp")]
+fn is_transient(e: &Error) -> bool {
+    matches!(
+        e,
+        Error::Unavailable(_)
+            | Error::ResourceExhausted(_)
+            | Error::DeadlineExceeded(_)
+            | Error::Aborted(_)
+    )
+}
+
+/// Randomize `backoff` by +/- 50% so that writers do not retry in lockstep.
+#[cfg(feature = "write_lp")]
+fn jitter(backoff: Duration) -> Duration {
+    use rand::Rng;
+
+    backoff.mul_f64(rand::thread_rng().gen_range(0.5..1.5))
+}
@@ -1,11 +1,15 @@
 //! Output formatting utilities for Arrow record batches
 
-use std::{fmt::Display, str::FromStr};
+use std::{fmt::Display, io::Write, str::FromStr};
 
 use thiserror::Error;
 
 use arrow::{
-    self, csv::WriterBuilder, error::ArrowError, json::ArrayWriter, record_batch::RecordBatch,
+    self,
+    csv::{self, WriterBuilder},
+    error::ArrowError,
+    json::ArrayWriter,
+    record_batch::RecordBatch,
 };
 
 /// Error type for results formatting
@@ -34,6 +38,10 @@ pub enum Error {
     /// Error converting JSON output to utf-8
     #[error("Error converting JSON output to UTF-8: {}", .0)]
     JsonUtf8(std::string::FromUtf8Error),
+
+    /// The format can not be written one batch at a time
+    #[error("Format {} does not support streaming", .0)]
+    NotStreamable(QueryOutputFormat),
 }
 type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -125,6 +133,79 @@ impl QueryOutputFormat {
     }
 }
 
+impl QueryOutputFormat {
+    /// Returns true if this format can be written one batch at a time, see
+    /// [`streaming_writer`](Self::streaming_writer).
+    ///
+    /// The pretty format needs all batches to compute the column widths.
+    pub fn supports_streaming(&self) -> bool {
+        match self {
+            Self::Pretty => false,
+            Self::Csv | Self::Json => true,
+        }
+    }
+
+    /// Create a [`StreamingWriter`] that writes [`RecordBatch`]es to `writer` in this format as
+    /// they arrive, instead of buffering all of them like [`format`](Self::format).
+    ///
+    /// The output is the same as the one of [`format`](Self::format) for all batches.
+    pub fn streaming_writer<W: Write>(&self, writer: W) -> Result<StreamingWriter<W>> {
+        let inner = match self {
+            Self::Pretty => return Err(Error::NotStreamable(*self)),
+            Self::Csv => {
+                StreamingWriterInner::Csv(WriterBuilder::new().has_headers(true).build(writer))
+            }
+            Self::Json => StreamingWriterInner::Json(ArrayWriter::new(writer)),
+        };
+
+        Ok(StreamingWriter { inner })
+    }
+}
+
+/// Writes [`RecordBatch`]es in a streamable [`QueryOutputFormat`] one at a time.
+///
+/// Created by [`QueryOutputFormat::streaming_writer`].
+pub struct StreamingWriter<W: Write> {
+    inner: StreamingWriterInner<W>,
+}
+
+enum StreamingWriterInner<W: Write> {
+    Csv(csv::Writer<W>),
+    Json(ArrayWriter<W>),
+}
+
+impl<W: Write> std::fmt::Debug for StreamingWriter<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let format = match &self.inner {
+            StreamingWriterInner::Csv(_) => QueryOutputFormat::Csv,
+            StreamingWriterInner::Json(_) => QueryOutputFormat::Json,
+        };
+        f.debug_struct("StreamingWriter")
+            .field("format", &format)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<W: Write> StreamingWriter<W> {
+    /// Write a single batch.
+    pub fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        match &mut self.inner {
+            StreamingWriterInner::Csv(writer) => writer.write(batch).map_err(Error::CsvArrow),
+            StreamingWriterInner::Json(writer) => writer
+                .write_batches(std::slice::from_ref(batch))
+                .map_err(Error::JsonArrow),
+        }
+    }
+
+    /// Finish the output after the last batch was written.
+    pub fn finish(self) -> Result<()> {
+        match self.inner {
+            StreamingWriterInner::Csv(_) => Ok(()),
+            StreamingWriterInner::Json(mut writer) => writer.finish().map_err(Error::JsonArrow),
+        }
+    }
+}
+
 fn batches_to_pretty(batches: &[RecordBatch]) -> Result<String> {
     arrow_util::display::pretty_format_batches(batches).map_err(Error::PrettyArrow)
 }
@@ -197,6 +278,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_streaming_writer() {
+        use arrow::array::{ArrayRef, Int64Array, StringArray};
+        use std::sync::Arc;
+
+        let batches: Vec<_> = (0..3)
+            .map(|i| {
+                RecordBatch::try_from_iter(vec![
+                    ("n", Arc::new(Int64Array::from(vec![i, i + 10])) as ArrayRef),
+                    ("s", Arc::new(StringArray::from(vec!["a", "b"])) as ArrayRef),
+                ])
+                .unwrap()
+            })
+            .collect();
+
+        for format in [QueryOutputFormat::Csv, QueryOutputFormat::Json] {
+            assert!(format.supports_streaming());
+
+            let mut bytes = vec![];
+            let mut writer = format.streaming_writer(&mut bytes).unwrap();
+            for batch in &batches {
+                writer.write(batch).unwrap();
+            }
+            writer.finish().unwrap();
+
+            assert_eq!(
+                String::from_utf8(bytes).unwrap(),
+                format.format(&batches).unwrap()
+            );
+        }
+
+        assert!(!QueryOutputFormat::Pretty.supports_streaming());
+        assert_eq!(
+            QueryOutputFormat::Pretty
+                .streaming_writer(vec![])
+                .unwrap_err()
+                .to_string(),
+            "Format pretty does not support streaming"
+        );
+    }
+
     #[test]
     fn test_from_roundtrip() {
         assert_eq!(
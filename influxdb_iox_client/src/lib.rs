@@ -15,6 +15,9 @@
 )]
 #![allow(clippy::missing_docs_in_private_items)]
 
+// Allow the code generated by `influxdb_iox_client_derive` to refer to this crate.
+extern crate self as influxdb_iox_client;
+
 pub use generated_types::{google, protobuf_type_url, protobuf_type_url_eq};
 
 pub use client::*;
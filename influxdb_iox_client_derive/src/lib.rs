@@ -0,0 +1,126 @@
+//! Derive macros for the InfluxDB IOx API client.
+//!
+//! Use them through the re-exports of `influxdb_iox_client`, since the generated code refers to
+//! that crate.
+#![deny(rustdoc::broken_intra_doc_links, rustdoc::bare_urls, rust_2018_idioms)]
+#![warn(
+    missing_docs,
+    clippy::todo,
+    clippy::dbg_macro,
+    clippy::clone_on_ref_ptr
+)]
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Lit, Meta, NestedMeta};
+
+/// Derive `influxdb_iox_client::flight::row::DeserializeRow` for a struct with named fields.
+///
+/// Every field is read from the column with the same name. Use `#[iox(rename = "...")]` to read
+/// a field from a column with a different name.
+#[proc_macro_derive(DeserializeRow, attributes(iox))]
+pub fn derive_deserialize_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "DeserializeRow can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "DeserializeRow can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut lookups = vec![];
+    let mut initializers = vec![];
+    for (i, field) in fields.iter().enumerate() {
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        let column = column_name(field)?.unwrap_or_else(|| ident.to_string());
+        let array = format_ident!("column_{}", i);
+
+        lookups.push(quote! {
+            let #array = ::influxdb_iox_client::flight::row::column(batch, #column)?;
+        });
+        initializers.push(quote! {
+            #ident: <#ty as ::influxdb_iox_client::flight::row::FromColumn>::from_column(
+                #column,
+                #array.as_ref(),
+                row,
+            )?
+        });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::influxdb_iox_client::flight::row::DeserializeRow for #name #ty_generics
+        #where_clause
+        {
+            fn deserialize_batch(
+                batch: &::influxdb_iox_client::flight::row::RecordBatch,
+            ) -> ::std::result::Result<
+                ::std::vec::Vec<Self>,
+                ::influxdb_iox_client::flight::row::DeserializeError,
+            > {
+                #(#lookups)*
+
+                (0..batch.num_rows())
+                    .map(|row| ::std::result::Result::Ok(Self { #(#initializers),* }))
+                    .collect()
+            }
+        }
+    })
+}
+
+/// The column name of `#[iox(rename = "...")]`, if present.
+fn column_name(field: &syn::Field) -> syn::Result<Option<String>> {
+    let mut name = None;
+
+    for attr in field.attrs.iter().filter(|attr| attr.path.is_ident("iox")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(syn::Error::new_spanned(meta, "expected #[iox(...)]")),
+        };
+
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("rename") => {
+                    match nv.lit {
+                        Lit::Str(s) => name = Some(s.value()),
+                        lit => {
+                            return Err(syn::Error::new_spanned(
+                                lit,
+                                "expected column name as string literal",
+                            ))
+                        }
+                    }
+                }
+                other => {
+                    return Err(syn::Error::new_spanned(
+                        other,
+                        "unknown attribute, expected `rename = \"...\"`",
+                    ))
+                }
+            }
+        }
+    }
+
+    Ok(name)
+}
@@ -238,6 +238,8 @@ mod tests {
                 table_id: TableId::new(table_id),
                 partition_key: partition_key.into(),
                 sort_key: vec![],
+                sort_key_version: 0,
+                compaction_cursor: None,
             },
         };
 
@@ -308,6 +310,8 @@ mod tests {
                 table_id: TableId::new(table_id),
                 partition_key: partition_key.into(),
                 sort_key: vec![],
+                sort_key_version: 0,
+                compaction_cursor: None,
             },
         };
 
@@ -405,6 +409,8 @@ mod tests {
                 partition_key: partition_key.into(),
                 // NO SORT KEY from the catalog here, first persisting batch
                 sort_key: vec![],
+                sort_key_version: 0,
+                compaction_cursor: None,
             },
         };
 
@@ -505,6 +511,8 @@ mod tests {
                 // SPECIFY A SORT KEY HERE to simulate a sort key being stored in the catalog
                 // this is NOT what the computed sort key would be based on this data's cardinality
                 sort_key: vec!["tag3".to_string(), "tag1".to_string(), "time".to_string()],
+                sort_key_version: 0,
+                compaction_cursor: None,
             },
         };
 
@@ -606,6 +614,8 @@ mod tests {
                 // this is NOT what the computed sort key would be based on this data's cardinality
                 // The new column, tag1, should get added just before the time column
                 sort_key: vec!["tag3".to_string(), "time".to_string()],
+                sort_key_version: 0,
+                compaction_cursor: None,
             },
         };
 
@@ -715,6 +725,8 @@ mod tests {
                     "tag4".to_string(),
                     "time".to_string(),
                 ],
+                sort_key_version: 0,
+                compaction_cursor: None,
             },
         };
 
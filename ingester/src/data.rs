@@ -24,9 +24,9 @@ use mutable_batch::MutableBatch;
 use object_store::DynObjectStore;
 use observability_deps::tracing::{debug, warn};
 use parking_lot::RwLock;
-use parquet_file::storage::ParquetStorage;
+use parquet_file::{storage::ParquetStorage, ParquetPathLayout};
 use predicate::Predicate;
-use schema::selection::Selection;
+use schema::{selection::Selection, sort::adjust_sort_key_columns};
 use snafu::{OptionExt, ResultExt, Snafu};
 use std::{
     collections::{btree_map::Entry, BTreeMap},
@@ -134,6 +134,14 @@ impl IngesterData {
         }
     }
 
+    /// Write parquet files with the given path layout.
+    pub fn with_parquet_path_layout(self, parquet_path_layout: ParquetPathLayout) -> Self {
+        Self {
+            store: self.store.with_path_layout(parquet_path_layout),
+            ..self
+        }
+    }
+
     /// Executor for running queries and compacting and persisting
     pub(crate) fn exec(&self) -> &Arc<Executor> {
         &self.exec
@@ -200,6 +208,24 @@ impl IngesterData {
         }
         progresses
     }
+
+    /// Record in the catalog whether this ingester is persisting data of the partition, so that
+    /// compactors leave it alone until the new file is in the catalog.
+    ///
+    /// This is best effort: a failure only means that a compactor might compact the partition
+    /// concurrently, which is what happened before these marks existed.
+    async fn mark_persisting(&self, partition_id: PartitionId, persisting: bool) {
+        let mut repos = self.catalog.repositories().await;
+        let res = if persisting {
+            repos.partitions().start_persisting(partition_id).await
+        } else {
+            repos.partitions().finish_persisting(partition_id).await
+        };
+
+        if let Err(e) = res {
+            warn!(?partition_id, persisting, %e, "could not mark partition as persisting");
+        }
+    }
 }
 
 /// The Persister has a function to persist a given partition ID and to update the
@@ -273,6 +299,8 @@ impl Persister for IngesterData {
         let persisting_batch = namespace.snapshot_to_persisting(&partition_info).await;
 
         if let Some(persisting_batch) = persisting_batch {
+            self.mark_persisting(partition_id, true).await;
+
             // do the CPU intensive work of compaction, de-duplication and sorting
             let compacted_stream = match compact_persisting_batch(
                 Arc::new(SystemProvider::new()),
@@ -291,6 +319,7 @@ impl Persister for IngesterData {
                 Ok(Some(r)) => r,
                 Ok(None) => {
                     warn!("persist called with no data");
+                    self.mark_persisting(partition_id, false).await;
                     return;
                 }
             };
@@ -319,20 +348,45 @@ impl Persister for IngesterData {
             // catalog. If the order is reversed, the querier or
             // compactor may see a parquet file with an inconsistent
             // sort key. https://github.com/influxdata/influxdb_iox/issues/5090
+            //
+            // The sort key is updated with a compare-and-swap so that concurrent updates (e.g. by
+            // the compactor) are not overwritten. On conflict, the columns added by this persist
+            // are merged into the sort key that is now in the catalog and the update is retried.
             if let Some(new_sort_key) = sort_key_update {
                 let sort_key = new_sort_key.to_columns().collect::<Vec<_>>();
                 Backoff::new(&self.backoff_config)
                     .retry_all_errors("update_sort_key", || async {
                         let mut repos = self.catalog.repositories().await;
+                        let partition = repos.partitions().get_by_id(partition_id).await?.ok_or(
+                            iox_catalog::interface::Error::PartitionNotFound { id: partition_id },
+                        )?;
+
+                        let sort_key = match partition.sort_key() {
+                            Some(catalog_sort_key) => {
+                                match adjust_sort_key_columns(&catalog_sort_key, &sort_key).1 {
+                                    Some(merged) => merged,
+                                    // catalog already contains all columns
+                                    None => return Ok(()),
+                                }
+                            }
+                            None => new_sort_key.clone(),
+                        };
+                        let sort_key = sort_key.to_columns().collect::<Vec<_>>();
+
                         let partition = repos
                             .partitions()
-                            .update_sort_key(partition_id, &sort_key)
+                            .update_sort_key_if_version(
+                                partition_id,
+                                &sort_key,
+                                partition.sort_key_version,
+                            )
                             .await?;
 
                         debug!(
                             partition_id=?partition.id,
                             table_id=?partition.table_id,
                             sort_key=?partition.sort_key,
+                            sort_key_version=partition.sort_key_version,
                             "Updated sort key in catalog"
                         );
                         // compiler insisted on getting told the type of the error :shrug:
@@ -362,6 +416,7 @@ impl Persister for IngesterData {
                 })
                 .await
                 .expect("retry forever");
+            self.mark_persisting(partition_id, false).await;
 
             // Record metrics
             let attributes = Attributes::from([(
@@ -987,19 +1042,36 @@ impl TableData {
         Ok(())
     }
 
-    pub fn unpersisted_partition_data(&self) -> Vec<UnpersistedPartitionData> {
+    /// Return the unpersisted data of all partitions.
+    ///
+    /// The buffer of every partition is snapshotted first, so that writes that arrive after this
+    /// call end up in new snapshots. This way no snapshot straddles the returned
+    /// [`PartitionStatus::max_sequence_number`], which is required to pin later queries to it.
+    pub fn unpersisted_partition_data(&mut self) -> Vec<UnpersistedPartitionData> {
+        let tombstone_max_sequence_number = self.tombstone_max_sequence_number;
         self.partition_data
-            .values()
-            .map(|p| UnpersistedPartitionData {
-                partition_id: p.id,
-                non_persisted: p
-                    .get_non_persisting_data()
-                    .expect("get_non_persisting should always work"),
-                persisting: p.get_persisting_data(),
-                partition_status: PartitionStatus {
-                    parquet_max_sequence_number: p.data.max_persisted_sequence_number,
-                    tombstone_max_sequence_number: self.tombstone_max_sequence_number,
-                },
+            .values_mut()
+            .map(|p| {
+                let non_persisted = p.snapshot().expect("snapshot should always work");
+                let persisting = p.get_persisting_data();
+
+                let max_sequence_number = non_persisted
+                    .iter()
+                    .chain(persisting.iter().flat_map(|b| b.data.iter()))
+                    .map(|b| b.max_sequence_number)
+                    .chain(p.data.max_persisted_sequence_number)
+                    .max();
+
+                UnpersistedPartitionData {
+                    partition_id: p.id,
+                    non_persisted,
+                    persisting,
+                    partition_status: PartitionStatus {
+                        parquet_max_sequence_number: p.data.max_persisted_sequence_number,
+                        tombstone_max_sequence_number,
+                        max_sequence_number,
+                    },
+                }
             })
             .collect()
     }
@@ -1090,17 +1162,11 @@ impl PartitionData {
 
     /// Snapshot whatever is in the buffer and return a new vec of the
     /// arc cloned snapshots
-    #[allow(dead_code)] // Used in tests
     pub fn snapshot(&mut self) -> Result<Vec<Arc<SnapshotBatch>>> {
         self.data.snapshot().context(SnapshotSnafu)?;
         Ok(self.data.snapshots.to_vec())
     }
 
-    /// Return non persisting data
-    pub fn get_non_persisting_data(&self) -> Result<Vec<Arc<SnapshotBatch>>> {
-        self.data.buffer_and_snapshots()
-    }
-
     /// Return persisting data
     pub fn get_persisting_data(&self) -> Option<QueryableBatch> {
         self.data.get_persisting_data()
@@ -1338,21 +1404,6 @@ impl DataBuffer {
         }
     }
 
-    /// Returns all existing snapshots plus data in the buffer
-    /// This only read data. Data in the buffer will be kept in the buffer
-    pub fn buffer_and_snapshots(&self) -> Result<Vec<Arc<SnapshotBatch>>> {
-        // Existing snapshots
-        let mut snapshots = self.snapshots.clone();
-
-        // copy the buffer to a snapshot
-        let buffer_snapshot = self
-            .copy_buffer_to_snapshot()
-            .context(BufferToSnapshotSnafu)?;
-        snapshots.extend(buffer_snapshot);
-
-        Ok(snapshots)
-    }
-
     /// Snapshots the buffer and moves snapshots over to the `PersistingBatch`.
     ///
     /// # Panic
@@ -1543,6 +1594,11 @@ pub struct PartitionStatus {
 
     /// Max sequence number for a tombstone
     pub tombstone_max_sequence_number: Option<SequenceNumber>,
+
+    /// Max sequence number of all data (persisted or not) that was returned for this partition.
+    ///
+    /// A query pinned to this sequence number sees the same data of this partition.
+    pub max_sequence_number: Option<SequenceNumber>,
 }
 
 /// Stream of snapshots.
@@ -1698,15 +1754,15 @@ mod tests {
     use arrow_util::assert_batches_sorted_eq;
     use assert_matches::assert_matches;
     use data_types::{
-        ColumnId, ColumnSet, CompactionLevel, NamespaceSchema, NonEmptyString, ParquetFileParams,
-        Sequence, TimestampRange,
+        ColumnId, ColumnSet, ColumnStatsSet, CompactionLevel, NamespaceSchema, NonEmptyString,
+        ParquetFileParams, Sequence, TimestampRange,
     };
     use datafusion::physical_plan::RecordBatchStream;
     use dml::{DmlDelete, DmlMeta, DmlWrite};
     use futures::TryStreamExt;
     use iox_catalog::{mem::MemCatalog, validate_or_insert_schema};
     use iox_time::Time;
-    use metric::{MetricObserver, Observation};
+    use metric::{DurationHistogram, MetricObserver, Observation};
     use mutable_batch_lp::{lines_to_batches, test_helpers::lp_to_mutable_batch};
     use object_store::memory::InMemory;
     use std::{
@@ -2197,6 +2253,25 @@ mod tests {
             .unwrap();
         assert_eq!(partition_info.partition.sort_key, vec!["time"]);
 
+        // verify the partition was marked as persisting while writing the file, and the mark was
+        // removed afterwards
+        for op in ["partition_start_persisting", "partition_finish_persisting"] {
+            let calls = metrics
+                .get_instrument::<Metric<DurationHistogram>>("catalog_op_duration")
+                .unwrap()
+                .get_observer(&Attributes::from(&[("op", op), ("result", "success")]))
+                .unwrap()
+                .fetch()
+                .sample_count();
+            assert_eq!(calls, 1, "{op}");
+        }
+        let persisting = repos
+            .partitions()
+            .list_persisting(shard1.id, Timestamp::new(0))
+            .await
+            .unwrap();
+        assert!(persisting.is_empty());
+
         let mem_table = n.table_data("mem").unwrap();
         let mem_table = mem_table.read().await;
 
@@ -2692,6 +2767,7 @@ mod tests {
             compaction_level: CompactionLevel::Initial,
             created_at: Timestamp::new(1),
             column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::default(),
         };
         repos
             .parquet_files()
@@ -2948,6 +3024,7 @@ mod tests {
                 PartitionStatus {
                     parquet_max_sequence_number: None,
                     tombstone_max_sequence_number: Some(SequenceNumber::new(1)),
+                    max_sequence_number: None,
                 },
             )),
             Err(ArrowError::IoError("some io error".into())),
@@ -2957,6 +3034,7 @@ mod tests {
                 PartitionStatus {
                     parquet_max_sequence_number: None,
                     tombstone_max_sequence_number: None,
+                    max_sequence_number: None,
                 },
             )),
         ])));
@@ -2968,6 +3046,7 @@ mod tests {
                 status: PartitionStatus {
                     parquet_max_sequence_number: None,
                     tombstone_max_sequence_number: Some(SequenceNumber::new(1)),
+                    max_sequence_number: None,
                 },
             }),
             Ok(FlatIngesterQueryResponse::StartSnapshot { schema: schema_1 }),
@@ -2984,6 +3063,7 @@ mod tests {
                 status: PartitionStatus {
                     parquet_max_sequence_number: None,
                     tombstone_max_sequence_number: None,
+                    max_sequence_number: None,
                 },
             }),
         ];
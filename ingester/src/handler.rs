@@ -12,7 +12,7 @@ use crate::{
 };
 use async_trait::async_trait;
 use backoff::BackoffConfig;
-use data_types::{Shard, ShardIndex, TopicMetadata};
+use data_types::{ParquetPathLayout, Shard, ShardIndex, TopicMetadata};
 use futures::{
     future::{BoxFuture, Shared},
     stream::FuturesUnordered,
@@ -127,6 +127,7 @@ impl IngestHandlerImpl {
         shard_states: BTreeMap<ShardIndex, Shard>,
         catalog: Arc<dyn Catalog>,
         object_store: Arc<DynObjectStore>,
+        parquet_path_layout: ParquetPathLayout,
         write_buffer: Arc<dyn WriteBufferReading>,
         exec: Arc<Executor>,
         metric_registry: Arc<metric::Registry>,
@@ -141,14 +142,17 @@ impl IngestHandlerImpl {
                 ShardData::new(s.shard_index, Arc::clone(&metric_registry)),
             );
         }
-        let data = Arc::new(IngesterData::new(
-            object_store,
-            catalog,
-            shards,
-            exec,
-            BackoffConfig::default(),
-            Arc::clone(&metric_registry),
-        ));
+        let data = Arc::new(
+            IngesterData::new(
+                object_store,
+                catalog,
+                shards,
+                exec,
+                BackoffConfig::default(),
+                Arc::clone(&metric_registry),
+            )
+            .with_parquet_path_layout(parquet_path_layout),
+        );
 
         let ingester_data = Arc::clone(&data);
         let topic_name = topic.name.clone();
@@ -680,6 +684,7 @@ mod tests {
             shard_states,
             Arc::clone(&catalog),
             object_store,
+            Default::default(),
             reading,
             Arc::new(Executor::new(1)),
             Arc::clone(&metrics),
@@ -884,6 +889,7 @@ mod tests {
             table: "cpu".to_string(),
             columns: vec!["asdf".to_string()],
             predicate: None,
+            snapshot: None,
         };
 
         let res = ingester.ingester.query(request.clone()).await.unwrap_err();
@@ -953,6 +959,7 @@ mod tests {
                 shard_states,
                 Arc::clone(&catalog),
                 object_store,
+                Default::default(),
                 reading,
                 Arc::new(Executor::new(1)),
                 Arc::clone(&metrics),
@@ -1,10 +1,11 @@
 //! Handle all requests from Querier
 
 use crate::data::{
-    IngesterData, IngesterQueryPartition, IngesterQueryResponse, QueryableBatch,
+    IngesterData, IngesterQueryPartition, IngesterQueryResponse, QueryableBatch, SnapshotBatch,
     UnpersistedPartitionData,
 };
 use arrow::error::ArrowError;
+use data_types::{PartitionId, SequenceNumber};
 use datafusion::{
     error::DataFusionError, logical_plan::LogicalPlanBuilder,
     physical_plan::SendableRecordBatchStream,
@@ -74,6 +75,12 @@ pub enum Error {
 
     #[snafu(display("Concurrent query request limit exceeded"))]
     RequestLimit,
+
+    #[snafu(display(
+        "Query snapshot of partition {} expired, data of the snapshot cannot be separated from newer data",
+        partition_id
+    ))]
+    SnapshotExpired { partition_id: PartitionId },
 }
 
 /// A specialized `Error` for Ingester's Query errors
@@ -111,7 +118,7 @@ pub async fn prepare_data_to_querier(
         };
 
         let mut unpersisted_partition_data = {
-            let table_data = table_data.read().await;
+            let mut table_data = table_data.write().await;
             table_data.unpersisted_partition_data()
         };
         debug!(?unpersisted_partition_data);
@@ -133,6 +140,29 @@ pub async fn prepare_data_to_querier(
         },
     );
 
+    // Queries pinned to a snapshot only see the partitions and data of that snapshot. This is
+    // checked before streaming any data so that an expired snapshot fails the entire request.
+    if let Some(snapshot) = &request.snapshot {
+        unpersisted_partitions.retain(|p| snapshot.contains_key(&p.partition_id));
+        for partition in &mut unpersisted_partitions {
+            let partition_id = partition.partition_id;
+            let max_sequence_number = snapshot[&partition_id].max_sequence_number;
+
+            partition.non_persisted = filter_snapshot_batches(
+                partition_id,
+                std::mem::take(&mut partition.non_persisted),
+                max_sequence_number,
+            )?;
+            if let Some(persisting) = &mut partition.persisting {
+                persisting.data = filter_snapshot_batches(
+                    partition_id,
+                    std::mem::take(&mut persisting.data),
+                    max_sequence_number,
+                )?;
+            }
+        }
+    }
+
     let ingest_data = Arc::clone(ingest_data);
     let request = Arc::clone(request);
     let partitions = futures::stream::iter(unpersisted_partitions).then(move |partition| {
@@ -202,6 +232,26 @@ async fn prepare_data_to_querier_for_partition(
     .map(Some)
 }
 
+/// Keep the batches that only contain data up to `max_sequence_number`.
+///
+/// Fails if a batch contains data of both sides of `max_sequence_number`.
+fn filter_snapshot_batches(
+    partition_id: PartitionId,
+    batches: Vec<Arc<SnapshotBatch>>,
+    max_sequence_number: SequenceNumber,
+) -> Result<Vec<Arc<SnapshotBatch>>> {
+    let mut result = Vec::with_capacity(batches.len());
+    for batch in batches {
+        if batch.max_sequence_number <= max_sequence_number {
+            result.push(batch);
+        } else if batch.min_sequence_number <= max_sequence_number {
+            return SnapshotExpiredSnafu { partition_id }.fail();
+        }
+    }
+
+    Ok(result)
+}
+
 /// Query a given Queryable Batch, applying selection and filters as appropriate
 /// Return stream of record batches
 pub(crate) async fn query(
@@ -286,7 +336,8 @@ mod tests {
         test_util::{
             create_one_record_batch_with_influxtype_no_duplicates, create_tombstone,
             make_ingester_data, make_ingester_data_with_tombstones, make_queryable_batch,
-            make_queryable_batch_with_deletes, DataLocation, TEST_NAMESPACE, TEST_TABLE,
+            make_queryable_batch_with_deletes, make_snapshot_batch, DataLocation, TEST_NAMESPACE,
+            TEST_TABLE,
         },
     };
     use arrow::record_batch::RecordBatch;
@@ -628,6 +679,30 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_filter_snapshot_batches() {
+        let batch = Arc::clone(&create_one_record_batch_with_influxtype_no_duplicates().await[0]);
+        let snapshot = |min: i64, max: i64| {
+            Arc::new(make_snapshot_batch(
+                Arc::clone(&batch),
+                SequenceNumber::new(min),
+                SequenceNumber::new(max),
+            ))
+        };
+        let partition_id = PartitionId::new(1);
+
+        // batches after the snapshot are dropped
+        let batches = vec![snapshot(1, 3), snapshot(4, 5), snapshot(6, 8)];
+        let filtered =
+            filter_snapshot_batches(partition_id, batches.clone(), SequenceNumber::new(5)).unwrap();
+        assert_eq!(filtered, batches[..2].to_vec());
+
+        // batches that straddle the snapshot cannot be split
+        let err =
+            filter_snapshot_batches(partition_id, batches, SequenceNumber::new(7)).unwrap_err();
+        assert_matches!(err, Error::SnapshotExpired { partition_id: p } if p == partition_id);
+    }
+
     async fn ingester_response_to_record_batches(
         response: IngesterQueryResponse,
     ) -> Vec<RecordBatch> {
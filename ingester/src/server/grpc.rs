@@ -10,13 +10,18 @@ use arrow_flight::{
     Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightInfo,
     HandshakeRequest, HandshakeResponse, IpcMessage, PutResult, SchemaAsIpc, SchemaResult, Ticket,
 };
+use data_types::PartitionId;
 use flatbuffers::FlatBufferBuilder;
 use futures::Stream;
-use generated_types::influxdata::iox::ingester::v1::{
-    self as proto,
-    write_info_service_server::{WriteInfoService, WriteInfoServiceServer},
+use generated_types::{
+    influxdata::iox::ingester::v1::{
+        self as proto,
+        write_info_service_server::{WriteInfoService, WriteInfoServiceServer},
+    },
+    ingester::IngesterQueryRequest,
 };
-use observability_deps::tracing::{info, warn};
+use iox_query::QUERY_ID_HEADER;
+use observability_deps::tracing::{debug, info, warn};
 use pin_project::pin_project;
 use prost::Message;
 use snafu::{ResultExt, Snafu};
@@ -149,6 +154,12 @@ pub enum Error {
         table_name: String,
     },
 
+    #[snafu(display(
+        "Query snapshot of partition {} expired, data of the snapshot cannot be separated from newer data",
+        partition_id
+    ))]
+    SnapshotExpired { partition_id: PartitionId },
+
     #[snafu(display("Error while streaming query results: {}", source))]
     QueryStream { source: ArrowError },
 
@@ -167,7 +178,8 @@ impl From<Error> for tonic::Status {
             | Error::InvalidQuery { .. }
             | Error::Query { .. }
             | Error::NamespaceNotFound { .. }
-            | Error::TableNotFound { .. } => {
+            | Error::TableNotFound { .. }
+            | Error::SnapshotExpired { .. } => {
                 // TODO(edd): this should be `debug`. Keeping at info whilst IOx still in early
                 // development
                 info!(?err, msg)
@@ -194,6 +206,7 @@ impl Error {
             Self::NamespaceNotFound { .. } | Self::TableNotFound { .. } => {
                 Status::not_found(self.to_string())
             }
+            Self::SnapshotExpired { .. } => Status::failed_precondition(self.to_string()),
         }
     }
 }
@@ -256,6 +269,13 @@ impl<I: IngestHandler + Send + Sync + 'static> Flight for FlightService<I> {
         request: Request<Ticket>,
     ) -> Result<Response<Self::DoGetStream>, tonic::Status> {
         let _span_ctx: Option<SpanContext> = request.extensions().get().cloned();
+        // ID assigned by the querier, used to correlate logs across components
+        let query_id = request
+            .metadata()
+            .get(QUERY_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_owned();
         let ticket = request.into_inner();
 
         let proto_query_request =
@@ -263,7 +283,14 @@ impl<I: IngestHandler + Send + Sync + 'static> Flight for FlightService<I> {
                 ticket: ticket.ticket,
             })?;
 
-        let query_request = proto_query_request.try_into().context(InvalidQuerySnafu)?;
+        let query_request: IngesterQueryRequest =
+            proto_query_request.try_into().context(InvalidQuerySnafu)?;
+        debug!(
+            %query_id,
+            namespace=%query_request.namespace,
+            table=%query_request.table,
+            "ingester flight do_get",
+        );
 
         self.maybe_panic_in_flight_do_get();
 
@@ -282,9 +309,15 @@ impl<I: IngestHandler + Send + Sync + 'static> Flight for FlightService<I> {
                         namespace_name,
                         table_name,
                     },
-                    _ => Error::Query {
-                        source: Box::new(e),
-                    },
+                    crate::querier_handler::Error::SnapshotExpired { partition_id } => {
+                        Error::SnapshotExpired { partition_id }
+                    }
+                    _ => {
+                        warn!(%query_id, %e, "ingester query failed");
+                        Error::Query {
+                            source: Box::new(e),
+                        }
+                    }
                 })?;
 
         let output = GetStream::new(query_response.flatten());
@@ -408,6 +441,7 @@ impl Stream for GetStream {
                             tombstone_max_sequence_number: status
                                 .tombstone_max_sequence_number
                                 .map(|x| x.get()),
+                            max_sequence_number: status.max_sequence_number.map(|x| x.get()),
                         }),
                     };
                     prost::Message::encode(&app_metadata, &mut bytes)
@@ -486,6 +520,7 @@ mod tests {
                     status: PartitionStatus {
                         parquet_max_sequence_number: None,
                         tombstone_max_sequence_number: None,
+                        max_sequence_number: None,
                     },
                 }),
                 Ok(FlatIngesterQueryResponse::StartSnapshot { schema }),
@@ -499,6 +534,7 @@ mod tests {
                         status: Some(proto::PartitionStatus {
                             parquet_max_sequence_number: None,
                             tombstone_max_sequence_number: None,
+                            max_sequence_number: None,
                         }),
                     },
                 }),
@@ -524,6 +560,7 @@ mod tests {
                     status: PartitionStatus {
                         parquet_max_sequence_number: None,
                         tombstone_max_sequence_number: None,
+                        max_sequence_number: None,
                     },
                 }),
                 Err(ArrowError::IoError("foo".into())),
@@ -532,6 +569,7 @@ mod tests {
                     status: PartitionStatus {
                         parquet_max_sequence_number: None,
                         tombstone_max_sequence_number: None,
+                        max_sequence_number: None,
                     },
                 }),
             ],
@@ -543,6 +581,7 @@ mod tests {
                         status: Some(proto::PartitionStatus {
                             parquet_max_sequence_number: None,
                             tombstone_max_sequence_number: None,
+                            max_sequence_number: None,
                         }),
                     },
                 }),
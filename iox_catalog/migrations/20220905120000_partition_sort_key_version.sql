@@ -0,0 +1,3 @@
+-- Version counter for optimistic concurrency control of sort key updates.
+-- Incremented on every sort key change, see `PartitionRepo::update_sort_key_if_version`.
+ALTER TABLE IF EXISTS partition ADD COLUMN IF NOT EXISTS sort_key_version BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,17 @@
+-- Tracks changes of a column's type, see `ColumnRepo::start_type_migration`.
+CREATE TABLE IF NOT EXISTS column_type_migration (
+    id BIGINT GENERATED ALWAYS AS IDENTITY,
+    table_id BIGINT NOT NULL,
+    column_id BIGINT NOT NULL,
+    from_type SMALLINT NOT NULL,
+    to_type SMALLINT NOT NULL,
+    created_at BIGINT NOT NULL,
+    completed_at BIGINT,
+    PRIMARY KEY (id),
+    CONSTRAINT column_type_migration_table_fkey FOREIGN KEY (table_id) REFERENCES table_name (id),
+    CONSTRAINT column_type_migration_column_fkey FOREIGN KEY (column_id) REFERENCES column_name (id)
+);
+
+-- At most one active migration per column.
+CREATE UNIQUE INDEX IF NOT EXISTS column_type_migration_active_idx
+    ON column_type_migration (column_id) WHERE completed_at IS NULL;
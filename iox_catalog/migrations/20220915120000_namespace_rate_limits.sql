@@ -0,0 +1,9 @@
+ALTER TABLE
+  IF EXISTS namespace
+ADD
+  COLUMN write_rate_limit INT;
+
+ALTER TABLE
+  IF EXISTS namespace
+ADD
+  COLUMN query_rate_limit INT;
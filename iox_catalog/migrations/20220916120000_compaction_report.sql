@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS compaction_report (
+    id BIGINT GENERATED ALWAYS AS IDENTITY,
+    partition_id BIGINT NOT NULL,
+    object_store_id UUID NOT NULL,
+    created_at BIGINT NOT NULL,
+    PRIMARY KEY (id)
+);
+
+ALTER TABLE
+    IF EXISTS compaction_report
+ADD
+    FOREIGN KEY (partition_id) REFERENCES partition (id) MATCH SIMPLE ON UPDATE NO ACTION ON DELETE NO ACTION NOT VALID;
+
+CREATE INDEX IF NOT EXISTS compaction_report_partition_idx ON compaction_report (partition_id);
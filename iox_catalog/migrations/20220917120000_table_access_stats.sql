@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS table_access_stats (
+    table_id BIGINT NOT NULL,
+    last_queried_at BIGINT NOT NULL,
+    query_count BIGINT NOT NULL,
+    PRIMARY KEY (table_id)
+);
+
+ALTER TABLE
+    IF EXISTS table_access_stats
+ADD
+    FOREIGN KEY (table_id) REFERENCES table_name (id) MATCH SIMPLE ON UPDATE NO ACTION ON DELETE NO ACTION NOT VALID;
@@ -0,0 +1,3 @@
+-- Highest max sequence number of the level 0 files compacted so far, NULL if never compacted.
+-- Only ever moves forward, see `PartitionRepo::update_compaction_cursor`.
+ALTER TABLE IF EXISTS partition ADD COLUMN IF NOT EXISTS compaction_cursor BIGINT;
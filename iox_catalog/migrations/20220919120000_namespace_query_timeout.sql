@@ -0,0 +1,4 @@
+ALTER TABLE
+  IF EXISTS namespace
+ADD
+  COLUMN query_timeout_ms BIGINT;
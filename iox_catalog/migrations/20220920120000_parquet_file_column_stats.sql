@@ -0,0 +1,4 @@
+ALTER TABLE
+  IF EXISTS parquet_file
+ADD
+  COLUMN column_stats JSONB NOT NULL DEFAULT '[]';
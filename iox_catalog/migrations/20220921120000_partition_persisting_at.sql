@@ -0,0 +1,5 @@
+-- Time an ingester started persisting data of the partition, NULL if it is not persisting.
+-- Compactors skip recently marked partitions, see `PartitionRepo::list_persisting`.
+ALTER TABLE IF EXISTS partition ADD COLUMN IF NOT EXISTS persisting_at BIGINT;
+CREATE INDEX IF NOT EXISTS partition_persisting_at_idx ON partition (shard_id, persisting_at)
+    WHERE persisting_at IS NOT NULL;
@@ -0,0 +1,5 @@
+-- Idempotency key of the compaction that created the file, NULL for files not created by a compaction.
+-- A compaction that is retried after its result was committed finds its key and skips the commit.
+ALTER TABLE IF EXISTS parquet_file ADD COLUMN IF NOT EXISTS compaction_key TEXT;
+CREATE INDEX IF NOT EXISTS parquet_file_compaction_key_idx ON parquet_file (partition_id, compaction_key)
+    WHERE compaction_key IS NOT NULL;
@@ -2,11 +2,12 @@
 
 use async_trait::async_trait;
 use data_types::{
-    Column, ColumnSchema, ColumnType, ColumnTypeCount, Namespace, NamespaceId, NamespaceSchema,
-    ParquetFile, ParquetFileId, ParquetFileParams, Partition, PartitionId, PartitionInfo,
-    PartitionKey, PartitionParam, ProcessedTombstone, QueryPool, QueryPoolId, SequenceNumber,
-    Shard, ShardId, ShardIndex, Table, TableId, TablePartition, TableSchema, Timestamp, Tombstone,
-    TombstoneId, TopicId, TopicMetadata,
+    Column, ColumnId, ColumnSchema, ColumnType, ColumnTypeCount, ColumnTypeMigration,
+    ColumnTypeMigrationId, CompactionReport, Namespace, NamespaceId, NamespaceSchema, ParquetFile,
+    ParquetFileId, ParquetFileParams, Partition, PartitionId, PartitionInfo, PartitionKey,
+    PartitionParam, ProcessedTombstone, QueryPool, QueryPoolId, SequenceNumber, Shard, ShardId,
+    ShardIndex, Table, TableAccessStats, TableId, TablePartition, TableSchema, Timestamp,
+    Tombstone, TombstoneId, TopicId, TopicMetadata,
 };
 use iox_time::TimeProvider;
 use snafu::{OptionExt, Snafu};
@@ -56,6 +57,34 @@ pub enum Error {
     #[snafu(display("partition {} not found", id))]
     PartitionNotFound { id: PartitionId },
 
+    #[snafu(display("column {} not found", id))]
+    ColumnNotFound { id: ColumnId },
+
+    #[snafu(display("column {} can't be migrated from type {} to {}", name, existing, new))]
+    ColumnTypeMigrationUnsupported {
+        name: String,
+        existing: String,
+        new: String,
+    },
+
+    #[snafu(display("column {} already has an active type migration", id))]
+    ColumnTypeMigrationActive { id: ColumnId },
+
+    #[snafu(display("column type migration {} not found", id))]
+    ColumnTypeMigrationNotFound { id: ColumnTypeMigrationId },
+
+    #[snafu(display(
+        "sort key of partition {} was concurrently updated: expected version {} but found {}",
+        id,
+        expected_version,
+        actual_version,
+    ))]
+    SortKeyVersionConflict {
+        id: PartitionId,
+        expected_version: i64,
+        actual_version: i64,
+    },
+
     #[snafu(display(
         "couldn't create column {} in table {}; limit reached on namespace",
         column_name,
@@ -134,6 +163,12 @@ pub trait Catalog: Send + Sync + Debug {
     async fn start_transaction(&self) -> Result<Box<dyn Transaction>, Error>;
 
     /// Accesses the repositories without a transaction scope.
+    ///
+    /// Together with [`metrics`](Self::metrics) and [`time_provider`](Self::time_provider), this is
+    /// the read half of the catalog that query-only components rely on, while
+    /// [`setup`](Self::setup) and [`start_transaction`](Self::start_transaction) form the write half.
+    /// See [`ReadReplicaCatalog`](crate::replica::ReadReplicaCatalog) for a catalog serving the two
+    /// halves from different databases.
     async fn repositories(&self) -> Box<dyn RepoCollection>;
 
     /// Gets metric registry associated with this catalog.
@@ -246,6 +281,12 @@ pub trait RepoCollection: Send + Sync + Debug {
 
     /// Repository for [processed tombstones](data_types::ProcessedTombstone).
     fn processed_tombstones(&mut self) -> &mut dyn ProcessedTombstoneRepo;
+
+    /// Repository for [compaction reports](data_types::CompactionReport).
+    fn compaction_reports(&mut self) -> &mut dyn CompactionReportRepo;
+
+    /// Repository for [table access statistics](data_types::TableAccessStats).
+    fn table_access_stats(&mut self) -> &mut dyn TableAccessStatsRepo;
 }
 
 /// Functions for working with topics in the catalog.
@@ -292,6 +333,30 @@ pub trait NamespaceRepo: Send + Sync {
 
     /// Update the limit on the number of columns that can exist per table in a given namespace.
     async fn update_column_limit(&mut self, name: &str, new_max: i32) -> Result<Namespace>;
+
+    /// Update the limit on the number of writes per second to a given namespace. `None` removes
+    /// the limit.
+    async fn update_write_rate_limit(
+        &mut self,
+        name: &str,
+        new_max: Option<i32>,
+    ) -> Result<Namespace>;
+
+    /// Update the limit on the number of queries per second against a given namespace. `None`
+    /// removes the limit.
+    async fn update_query_rate_limit(
+        &mut self,
+        name: &str,
+        new_max: Option<i32>,
+    ) -> Result<Namespace>;
+
+    /// Update the default timeout (in milliseconds) of queries against a given namespace. `None`
+    /// removes the namespace default.
+    async fn update_query_timeout(
+        &mut self,
+        name: &str,
+        new_timeout_ms: Option<i64>,
+    ) -> Result<Namespace>;
 }
 
 /// Functions for working with tables in the catalog
@@ -385,6 +450,39 @@ pub trait ColumnRepo: Send + Sync {
         &mut self,
         table_id: TableId,
     ) -> Result<Vec<ColumnTypeCount>>;
+
+    /// Start changing the type of a field column to `column_type`.
+    ///
+    /// This updates the column type and records a [`ColumnTypeMigration`]. Parquet files
+    /// created before `created_at` still hold the old type; readers cast them to the new type
+    /// and the compactor rewrites them until the migration is
+    /// [completed](Self::complete_type_migration).
+    ///
+    /// Returns [`Error::ColumnTypeMigrationUnsupported`] if the type change is not supported
+    /// (see [`ColumnType::can_migrate_to`]) and [`Error::ColumnTypeMigrationActive`] if the
+    /// column is still being migrated.
+    async fn start_type_migration(
+        &mut self,
+        column_id: ColumnId,
+        column_type: ColumnType,
+        created_at: Timestamp,
+    ) -> Result<ColumnTypeMigration>;
+
+    /// List all type migrations (active and completed) for the given table ID.
+    async fn list_type_migrations_by_table_id(
+        &mut self,
+        table_id: TableId,
+    ) -> Result<Vec<ColumnTypeMigration>>;
+
+    /// List all type migrations that are not completed yet.
+    async fn list_active_type_migrations(&mut self) -> Result<Vec<ColumnTypeMigration>>;
+
+    /// Mark a type migration as completed, i.e. no parquet file with the old type is left.
+    async fn complete_type_migration(
+        &mut self,
+        id: ColumnTypeMigrationId,
+        completed_at: Timestamp,
+    ) -> Result<ColumnTypeMigration>;
 }
 
 /// Functions for working with shards in the catalog
@@ -433,6 +531,11 @@ pub trait PartitionRepo: Send + Sync {
     /// get partition by ID
     async fn get_by_id(&mut self, partition_id: PartitionId) -> Result<Option<Partition>>;
 
+    /// get multiple partitions by ID in a single query
+    ///
+    /// Unknown IDs are ignored, the order of the result is undefined.
+    async fn get_by_ids(&mut self, partition_ids: &[PartitionId]) -> Result<Vec<Partition>>;
+
     /// return partitions for a given shard
     async fn list_by_shard(&mut self, shard_id: ShardId) -> Result<Vec<Partition>>;
 
@@ -450,11 +553,56 @@ pub trait PartitionRepo: Send + Sync {
     ) -> Result<Option<PartitionInfo>>;
 
     /// Update the sort key for the partition
+    ///
+    /// This unconditionally overwrites the sort key. Writers that derive the new sort key from
+    /// the existing one should use [`update_sort_key_if_version`](Self::update_sort_key_if_version)
+    /// instead.
     async fn update_sort_key(
         &mut self,
         partition_id: PartitionId,
         sort_key: &[&str],
     ) -> Result<Partition>;
+
+    /// Update the sort key for the partition if its
+    /// [`sort_key_version`](Partition::sort_key_version) still equals `expected_version`
+    /// (compare-and-swap).
+    ///
+    /// Returns [`Error::SortKeyVersionConflict`] if the sort key was changed by someone else in
+    /// the meantime. This error is retryable: re-read the partition, merge the changes into the
+    /// new sort key and try again with the new version.
+    async fn update_sort_key_if_version(
+        &mut self,
+        partition_id: PartitionId,
+        sort_key: &[&str],
+        expected_version: i64,
+    ) -> Result<Partition>;
+
+    /// Advance the [`compaction_cursor`](Partition::compaction_cursor) of the partition to
+    /// `max_sequence_number`. The cursor never moves backwards, a lower value is ignored.
+    async fn update_compaction_cursor(
+        &mut self,
+        partition_id: PartitionId,
+        max_sequence_number: SequenceNumber,
+    ) -> Result<()>;
+
+    /// Record that an ingester started persisting data of the partition.
+    ///
+    /// Compactors skip such partitions for a while, see [`list_persisting`](Self::list_persisting).
+    async fn start_persisting(&mut self, partition_id: PartitionId) -> Result<()>;
+
+    /// Record that the ingester finished persisting data of the partition.
+    async fn finish_persisting(&mut self, partition_id: PartitionId) -> Result<()>;
+
+    /// Return the partitions of the shard that an ingester started persisting at or after
+    /// `started_after` and did not finish persisting yet.
+    ///
+    /// Older marks are ignored, so that an ingester that crashed while persisting does not keep
+    /// the partition from being compacted.
+    async fn list_persisting(
+        &mut self,
+        shard_id: ShardId,
+        started_after: Timestamp,
+    ) -> Result<Vec<PartitionId>>;
 }
 
 /// Functions for working with tombstones in the catalog
@@ -515,6 +663,15 @@ pub trait ParquetFileRepo: Send + Sync {
     /// create the parquet file
     async fn create(&mut self, parquet_file_params: ParquetFileParams) -> Result<ParquetFile>;
 
+    /// Create parquet files for all of `parquet_file_params` at once.
+    ///
+    /// Either all or none of the files are created, even outside of a transaction. Fails with
+    /// [`FileExists`](Error::FileExists) if a file with one of the object store ids exists already.
+    async fn create_parquet_files(
+        &mut self,
+        parquet_file_params: Vec<ParquetFileParams>,
+    ) -> Result<Vec<ParquetFile>>;
+
     /// Flag the parquet file for deletion
     async fn flag_for_delete(&mut self, id: ParquetFileId) -> Result<()>;
 
@@ -543,6 +700,10 @@ pub trait ParquetFileRepo: Send + Sync {
     /// Returns the deleted records.
     async fn delete_old(&mut self, older_than: Timestamp) -> Result<Vec<ParquetFile>>;
 
+    /// List all parquet files that were marked to be deleted earlier than the specified time,
+    /// i.e. the records [`delete_old`](Self::delete_old) would delete, without deleting them.
+    async fn list_old(&mut self, older_than: Timestamp) -> Result<Vec<ParquetFile>>;
+
     /// List parquet files for a given shard with compaction level 0 and other criteria that
     /// define a file as a candidate for compaction
     async fn level_0(&mut self, shard_id: ShardId) -> Result<Vec<ParquetFile>>;
@@ -582,6 +743,20 @@ pub trait ParquetFileRepo: Send + Sync {
         partition_id: PartitionId,
     ) -> Result<Vec<ParquetFile>>;
 
+    /// List a page of the parquet files for a given partition that are NOT marked as
+    /// [`to_delete`](ParquetFile::to_delete), ordered by ascending ID.
+    ///
+    /// Returns at most `limit` files with an ID greater than `after`, i.e. the next page starts
+    /// after the ID of the last file of this page. If `compaction_cursor` is set, level 0 files at
+    /// or below it are skipped, see [`Partition::compaction_cursor`](data_types::Partition).
+    async fn list_by_partition_not_to_delete_paged(
+        &mut self,
+        partition_id: PartitionId,
+        compaction_cursor: Option<SequenceNumber>,
+        after: Option<ParquetFileId>,
+        limit: usize,
+    ) -> Result<Vec<ParquetFile>>;
+
     /// Update the compaction level of the specified parquet files to
     /// `CompactionLevel::FileNonOverlapped`
     /// Returns the IDs of the files that were successfully updated.
@@ -623,6 +798,27 @@ pub trait ParquetFileRepo: Send + Sync {
         &mut self,
         object_store_id: Uuid,
     ) -> Result<Option<ParquetFile>>;
+
+    /// Return the subset of the given object store ids that are referenced by a parquet file,
+    /// regardless of whether the file is marked as [`to_delete`](ParquetFile::to_delete).
+    async fn existing_object_store_ids(&mut self, object_store_ids: &[Uuid]) -> Result<Vec<Uuid>>;
+
+    /// Record that the given parquet files were created by the compaction with the idempotency
+    /// key `compaction_key`.
+    async fn set_compaction_key(
+        &mut self,
+        parquet_file_ids: &[ParquetFileId],
+        compaction_key: &str,
+    ) -> Result<()>;
+
+    /// Return the IDs of the parquet files of the partition that were created by the compaction
+    /// with the idempotency key `compaction_key`, regardless of whether they are marked as
+    /// [`to_delete`](ParquetFile::to_delete).
+    async fn list_ids_by_compaction_key(
+        &mut self,
+        partition_id: PartitionId,
+        compaction_key: &str,
+    ) -> Result<Vec<ParquetFileId>>;
 }
 
 /// Functions for working with processed tombstone pointers in the catalog
@@ -649,6 +845,58 @@ pub trait ProcessedTombstoneRepo: Send + Sync {
     async fn count_by_tombstone_id(&mut self, tombstone_id: TombstoneId) -> Result<i64>;
 }
 
+/// Functions for working with compaction report pointers in the catalog
+#[async_trait]
+pub trait CompactionReportRepo: Send + Sync {
+    /// Record a compaction report of the given partition that was written to the object store
+    /// under `object_store_id`.
+    async fn create(
+        &mut self,
+        partition_id: PartitionId,
+        object_store_id: Uuid,
+        created_at: Timestamp,
+    ) -> Result<CompactionReport>;
+
+    /// List all compaction reports of the given partition, oldest first.
+    async fn list_by_partition(
+        &mut self,
+        partition_id: PartitionId,
+    ) -> Result<Vec<CompactionReport>>;
+}
+
+/// Functions for working with table access statistics in the catalog
+#[async_trait]
+pub trait TableAccessStatsRepo: Send + Sync {
+    /// Record `query_count` queries against the given table, the most recent one at
+    /// `last_queried_at`.
+    ///
+    /// The count is added to the existing statistics of the table, and the last query time never
+    /// moves backwards.
+    async fn record(
+        &mut self,
+        table_id: TableId,
+        last_queried_at: Timestamp,
+        query_count: i64,
+    ) -> Result<TableAccessStats>;
+
+    /// Get the access statistics of the given table, if it was ever queried.
+    async fn get_by_table_id(&mut self, table_id: TableId) -> Result<Option<TableAccessStats>>;
+
+    /// List the access statistics of all queried tables in the given namespace.
+    async fn list_by_namespace(
+        &mut self,
+        namespace_id: NamespaceId,
+    ) -> Result<Vec<TableAccessStats>>;
+
+    /// List the access statistics of the tables last queried at or after `since`, most recently
+    /// queried first. At most `limit` tables are listed.
+    async fn list_queried_since(
+        &mut self,
+        since: Timestamp,
+        limit: usize,
+    ) -> Result<Vec<TableAccessStats>>;
+}
+
 /// Gets the namespace schema including all tables and columns.
 pub async fn get_schema_by_id<R>(id: NamespaceId, repos: &mut R) -> Result<NamespaceSchema>
 where
@@ -851,7 +1099,7 @@ pub(crate) mod test_helpers {
 
     use super::*;
     use ::test_helpers::{assert_contains, tracing::TracingCapture};
-    use data_types::{ColumnId, ColumnSet, CompactionLevel};
+    use data_types::{ColumnSet, ColumnStats, ColumnStatsSet, CompactionLevel};
     use metric::{Attributes, DurationHistogram, Metric};
     use std::{
         ops::{Add, DerefMut},
@@ -866,17 +1114,22 @@ pub(crate) mod test_helpers {
         test_namespace(Arc::clone(&catalog)).await;
         test_table(Arc::clone(&catalog)).await;
         test_column(Arc::clone(&catalog)).await;
+        test_column_type_migration(Arc::clone(&catalog)).await;
         test_shards(Arc::clone(&catalog)).await;
         test_partition(Arc::clone(&catalog)).await;
         test_tombstone(Arc::clone(&catalog)).await;
         test_tombstones_by_parquet_file(Arc::clone(&catalog)).await;
         test_parquet_file(Arc::clone(&catalog)).await;
+        test_create_parquet_files(Arc::clone(&catalog)).await;
         test_parquet_file_compaction_level_0(Arc::clone(&catalog)).await;
         test_parquet_file_compaction_level_1(Arc::clone(&catalog)).await;
         test_most_level_0_files_partitions(Arc::clone(&catalog)).await;
         test_recent_highest_throughput_partitions(Arc::clone(&catalog)).await;
         test_update_to_compaction_level_1(Arc::clone(&catalog)).await;
         test_processed_tombstones(Arc::clone(&catalog)).await;
+        test_compaction_reports(Arc::clone(&catalog)).await;
+        test_compaction_keys(Arc::clone(&catalog)).await;
+        test_table_access_stats(Arc::clone(&catalog)).await;
         test_list_by_partiton_not_to_delete(Arc::clone(&catalog)).await;
         test_txn_isolation(Arc::clone(&catalog)).await;
         test_txn_drop(Arc::clone(&catalog)).await;
@@ -892,6 +1145,8 @@ pub(crate) mod test_helpers {
         assert_metric_hit(&*metrics, "partition_create_or_get");
         assert_metric_hit(&*metrics, "tombstone_create_or_get");
         assert_metric_hit(&*metrics, "parquet_create");
+        assert_metric_hit(&*metrics, "compaction_report_create");
+        assert_metric_hit(&*metrics, "table_access_stats_record");
     }
 
     async fn test_setup(catalog: Arc<dyn Catalog>) {
@@ -1003,6 +1258,66 @@ pub(crate) mod test_helpers {
             .await
             .expect("namespace should be updateable");
         assert_eq!(NEW_COLUMN_LIMIT, modified.max_columns_per_table);
+
+        // rate limits are unset by default
+        assert_eq!(modified.write_rate_limit, None);
+        assert_eq!(modified.query_rate_limit, None);
+
+        let modified = repos
+            .namespaces()
+            .update_write_rate_limit(namespace_name, Some(100))
+            .await
+            .expect("namespace should be updateable");
+        assert_eq!(modified.write_rate_limit, Some(100));
+        assert_eq!(modified.query_rate_limit, None);
+
+        let modified = repos
+            .namespaces()
+            .update_query_rate_limit(namespace_name, Some(10))
+            .await
+            .expect("namespace should be updateable");
+        assert_eq!(modified.write_rate_limit, Some(100));
+        assert_eq!(modified.query_rate_limit, Some(10));
+
+        let modified = repos
+            .namespaces()
+            .update_write_rate_limit(namespace_name, None)
+            .await
+            .expect("namespace should be updateable");
+        assert_eq!(modified.write_rate_limit, None);
+        assert_eq!(modified.query_rate_limit, Some(10));
+
+        let err = repos
+            .namespaces()
+            .update_query_rate_limit("does_not_exist", Some(1))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::NamespaceNotFoundByName { .. }));
+
+        // query timeout is unset by default
+        assert_eq!(modified.query_timeout_ms, None);
+
+        let modified = repos
+            .namespaces()
+            .update_query_timeout(namespace_name, Some(30_000))
+            .await
+            .expect("namespace should be updateable");
+        assert_eq!(modified.query_timeout_ms, Some(30_000));
+        assert_eq!(modified.query_rate_limit, Some(10));
+
+        let modified = repos
+            .namespaces()
+            .update_query_timeout(namespace_name, None)
+            .await
+            .expect("namespace should be updateable");
+        assert_eq!(modified.query_timeout_ms, None);
+
+        let err = repos
+            .namespaces()
+            .update_query_timeout("does_not_exist", Some(1))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::NamespaceNotFoundByName { .. }));
     }
 
     async fn test_table(catalog: Arc<dyn Catalog>) {
@@ -1323,6 +1638,152 @@ pub(crate) mod test_helpers {
         ));
     }
 
+    async fn test_column_type_migration(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("foo").await.unwrap();
+        let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = repos
+            .namespaces()
+            .create(
+                "namespace_column_type_migration_test",
+                "inf",
+                topic.id,
+                pool.id,
+            )
+            .await
+            .unwrap();
+        let table = repos
+            .tables()
+            .create_or_get("test_table", namespace.id)
+            .await
+            .unwrap();
+        let field = repos
+            .columns()
+            .create_or_get("field", table.id, ColumnType::I64)
+            .await
+            .unwrap();
+        let tag = repos
+            .columns()
+            .create_or_get("tag", table.id, ColumnType::Tag)
+            .await
+            .unwrap();
+
+        // unsupported migrations are rejected
+        let err = repos
+            .columns()
+            .start_type_migration(tag.id, ColumnType::String, Timestamp::new(1))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::ColumnTypeMigrationUnsupported { .. }),
+            "{err:?}"
+        );
+        let err = repos
+            .columns()
+            .start_type_migration(field.id, ColumnType::Bool, Timestamp::new(1))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::ColumnTypeMigrationUnsupported { .. }),
+            "{err:?}"
+        );
+        let err = repos
+            .columns()
+            .start_type_migration(ColumnId::new(i64::MAX), ColumnType::F64, Timestamp::new(1))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::ColumnNotFound { .. }), "{err:?}");
+
+        // start a migration
+        let migration = repos
+            .columns()
+            .start_type_migration(field.id, ColumnType::F64, Timestamp::new(1))
+            .await
+            .unwrap();
+        assert_eq!(migration.table_id, table.id);
+        assert_eq!(migration.column_id, field.id);
+        assert_eq!(migration.from_type, ColumnType::I64 as i16);
+        assert_eq!(migration.to_type, ColumnType::F64 as i16);
+        assert_eq!(migration.created_at, Timestamp::new(1));
+        assert!(migration.is_active());
+
+        // the column has the new type now
+        let columns = repos.columns().list_by_table_id(table.id).await.unwrap();
+        let field_after = columns.iter().find(|c| c.id == field.id).unwrap();
+        assert_eq!(field_after.column_type, ColumnType::F64 as i16);
+        repos
+            .columns()
+            .create_or_get("field", table.id, ColumnType::F64)
+            .await
+            .unwrap();
+
+        // only one active migration per column
+        let err = repos
+            .columns()
+            .start_type_migration(field.id, ColumnType::F64, Timestamp::new(2))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::ColumnTypeMigrationActive { .. }),
+            "{err:?}"
+        );
+
+        let active = repos.columns().list_active_type_migrations().await.unwrap();
+        assert_eq!(active, vec![migration.clone()]);
+
+        // complete the migration
+        let completed = repos
+            .columns()
+            .complete_type_migration(migration.id, Timestamp::new(3))
+            .await
+            .unwrap();
+        assert_eq!(completed.completed_at, Some(Timestamp::new(3)));
+        assert!(!completed.is_active());
+        assert!(repos
+            .columns()
+            .list_active_type_migrations()
+            .await
+            .unwrap()
+            .is_empty());
+
+        // another column of the table can be migrated
+        let field2 = repos
+            .columns()
+            .create_or_get("field2", table.id, ColumnType::U64)
+            .await
+            .unwrap();
+        let migration2 = repos
+            .columns()
+            .start_type_migration(field2.id, ColumnType::F64, Timestamp::new(4))
+            .await
+            .unwrap();
+        assert_eq!(migration2.from_type, ColumnType::U64 as i16);
+
+        let migrations = repos
+            .columns()
+            .list_type_migrations_by_table_id(table.id)
+            .await
+            .unwrap();
+        assert_eq!(migrations, vec![completed, migration2.clone()]);
+
+        // clean up so other tests do not see the active migration
+        repos
+            .columns()
+            .complete_type_migration(migration2.id, Timestamp::new(5))
+            .await
+            .unwrap();
+
+        let err = repos
+            .columns()
+            .complete_type_migration(ColumnTypeMigrationId::new(i64::MAX), Timestamp::new(1))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::ColumnTypeMigrationNotFound { .. }),
+            "{err:?}"
+        );
+    }
+
     async fn test_shards(catalog: Arc<dyn Catalog>) {
         let mut repos = catalog.repositories().await;
         let topic = repos.topics().create_or_get("shard_test").await.unwrap();
@@ -1443,6 +1904,24 @@ pub(crate) mod test_helpers {
             .unwrap()
             .is_none());
 
+        let mut batch = repos
+            .partitions()
+            .get_by_ids(&[
+                other_partition.id,
+                PartitionId::new(i64::MAX),
+                *created.keys().next().unwrap(),
+            ])
+            .await
+            .unwrap();
+        batch.sort_by_key(|p| p.id);
+        let mut expected = vec![
+            other_partition.clone(),
+            created.values().next().unwrap().clone(),
+        ];
+        expected.sort_by_key(|p| p.id);
+        assert_eq!(batch, expected);
+        assert!(repos.partitions().get_by_ids(&[]).await.unwrap().is_empty());
+
         // List them and assert they match
         let listed = repos
             .partitions()
@@ -1555,25 +2034,167 @@ pub(crate) mod test_helpers {
             updated_other_partition.sort_key,
             vec!["tag2", "tag1", "tag3 , with comma", "time"]
         );
-    }
+        // every update bumps the version
+        assert_eq!(other_partition.sort_key_version, 0);
+        assert_eq!(updated_other_partition.sort_key_version, 2);
 
-    async fn test_tombstone(catalog: Arc<dyn Catalog>) {
-        let mut repos = catalog.repositories().await;
-        let topic = repos.topics().create_or_get("foo").await.unwrap();
-        let pool = repos.query_pools().create_or_get("foo").await.unwrap();
-        let namespace = repos
-            .namespaces()
-            .create("namespace_tombstone_test", "inf", topic.id, pool.id)
+        // test conditional update with the current version
+        let updated_other_partition = repos
+            .partitions()
+            .update_sort_key_if_version(
+                other_partition.id,
+                &["tag2", "tag1", "tag3 , with comma", "tag4", "time"],
+                2,
+            )
             .await
             .unwrap();
-        let table = repos
-            .tables()
-            .create_or_get("test_table", namespace.id)
+        assert_eq!(
+            updated_other_partition.sort_key,
+            vec!["tag2", "tag1", "tag3 , with comma", "tag4", "time"]
+        );
+        assert_eq!(updated_other_partition.sort_key_version, 3);
+
+        // test conditional update with an outdated version
+        let err = repos
+            .partitions()
+            .update_sort_key_if_version(other_partition.id, &["tag5", "time"], 2)
             .await
-            .unwrap();
-        let other_table = repos
-            .tables()
-            .create_or_get("other", namespace.id)
+            .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                Error::SortKeyVersionConflict {
+                    expected_version: 2,
+                    actual_version: 3,
+                    ..
+                }
+            ),
+            "{err:?}"
+        );
+        let unchanged_other_partition = repos
+            .partitions()
+            .get_by_id(other_partition.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(unchanged_other_partition, updated_other_partition);
+
+        // test conditional update of an unknown partition
+        let err = repos
+            .partitions()
+            .update_sort_key_if_version(PartitionId::new(i64::MAX), &["time"], 0)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::PartitionNotFound { .. }), "{err:?}");
+
+        // test compaction cursor, which only moves forward
+        assert_eq!(other_partition.compaction_cursor, None);
+        for (max_sequence_number, expected) in [(10, 10), (20, 20), (15, 20)] {
+            repos
+                .partitions()
+                .update_compaction_cursor(
+                    other_partition.id,
+                    SequenceNumber::new(max_sequence_number),
+                )
+                .await
+                .unwrap();
+            let partition = repos
+                .partitions()
+                .get_by_id(other_partition.id)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                partition.compaction_cursor,
+                Some(SequenceNumber::new(expected))
+            );
+        }
+        let err = repos
+            .partitions()
+            .update_compaction_cursor(PartitionId::new(i64::MAX), SequenceNumber::new(1))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::PartitionNotFound { .. }), "{err:?}");
+
+        // test persisting marks
+        let before = Timestamp::new(catalog.time_provider().now().timestamp_nanos());
+        let persisting = repos
+            .partitions()
+            .list_persisting(shard.id, before)
+            .await
+            .unwrap();
+        assert!(persisting.is_empty());
+
+        let mut ids: Vec<_> = created.keys().copied().collect();
+        for id in &ids {
+            repos.partitions().start_persisting(*id).await.unwrap();
+        }
+        repos
+            .partitions()
+            .start_persisting(other_partition.id)
+            .await
+            .unwrap();
+        let persisting = repos
+            .partitions()
+            .list_persisting(shard.id, before)
+            .await
+            .unwrap();
+        ids.sort();
+        assert_eq!(persisting, ids);
+
+        // marks older than `started_after` are ignored
+        let persisting = repos
+            .partitions()
+            .list_persisting(shard.id, Timestamp::new(i64::MAX))
+            .await
+            .unwrap();
+        assert!(persisting.is_empty());
+
+        repos.partitions().finish_persisting(ids[0]).await.unwrap();
+        let persisting = repos
+            .partitions()
+            .list_persisting(shard.id, before)
+            .await
+            .unwrap();
+        assert_eq!(persisting, ids[1..]);
+        let persisting = repos
+            .partitions()
+            .list_persisting(other_shard.id, before)
+            .await
+            .unwrap();
+        assert_eq!(persisting, vec![other_partition.id]);
+
+        let err = repos
+            .partitions()
+            .start_persisting(PartitionId::new(i64::MAX))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::PartitionNotFound { .. }), "{err:?}");
+        let err = repos
+            .partitions()
+            .finish_persisting(PartitionId::new(i64::MAX))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::PartitionNotFound { .. }), "{err:?}");
+    }
+
+    async fn test_tombstone(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("foo").await.unwrap();
+        let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = repos
+            .namespaces()
+            .create("namespace_tombstone_test", "inf", topic.id, pool.id)
+            .await
+            .unwrap();
+        let table = repos
+            .tables()
+            .create_or_get("test_table", namespace.id)
+            .await
+            .unwrap();
+        let other_table = repos
+            .tables()
+            .create_or_get("other", namespace.id)
             .await
             .unwrap();
         let shard = repos
@@ -1778,6 +2399,7 @@ pub(crate) mod test_helpers {
             compaction_level: CompactionLevel::Initial,
             created_at: Timestamp::new(1),
             column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::default(),
         };
         let parquet_file = repos
             .parquet_files()
@@ -1988,6 +2610,12 @@ pub(crate) mod test_helpers {
             compaction_level: CompactionLevel::Initial,
             created_at: Timestamp::new(1),
             column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::new([ColumnStats {
+                column_id: ColumnId::new(1),
+                null_count: Some(0),
+                min: Some("east".to_string()),
+                max: Some("west".to_string()),
+            }]),
         };
         let parquet_file = repos
             .parquet_files()
@@ -2003,6 +2631,21 @@ pub(crate) mod test_helpers {
             .unwrap();
         assert_eq!(parquet_file, pfg.unwrap());
 
+        // verify we can check the existence of many object store ids at once
+        let missing_object_store_id = Uuid::new_v4();
+        let existing = repos
+            .parquet_files()
+            .existing_object_store_ids(&[missing_object_store_id, parquet_file.object_store_id])
+            .await
+            .unwrap();
+        assert_eq!(existing, vec![parquet_file.object_store_id]);
+        let existing = repos
+            .parquet_files()
+            .existing_object_store_ids(&[])
+            .await
+            .unwrap();
+        assert!(existing.is_empty());
+
         // verify that trying to create a file with the same UUID throws an error
         let err = repos
             .parquet_files()
@@ -2077,6 +2720,19 @@ pub(crate) mod test_helpers {
         assert!(deleted_files.is_empty());
         assert!(repos.parquet_files().exist(parquet_file.id).await.unwrap());
 
+        let old_files = repos
+            .parquet_files()
+            .list_old(before_deleted)
+            .await
+            .unwrap();
+        assert!(old_files.is_empty());
+
+        // File is listed, but not deleted, if it was marked to be deleted before the specified
+        // time
+        let old_files = repos.parquet_files().list_old(older_than).await.unwrap();
+        assert_eq!(old_files, vec![marked_deleted.clone()]);
+        assert!(repos.parquet_files().exist(parquet_file.id).await.unwrap());
+
         // File is deleted if it was marked to be deleted before the specified time
         let deleted_files = repos.parquet_files().delete_old(older_than).await.unwrap();
         assert_eq!(deleted_files.len(), 1);
@@ -2355,6 +3011,135 @@ pub(crate) mod test_helpers {
         assert_eq!(count, 1);
     }
 
+    async fn test_create_parquet_files(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("foo").await.unwrap();
+        let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = repos
+            .namespaces()
+            .create(
+                "namespace_create_parquet_files_test",
+                "inf",
+                topic.id,
+                pool.id,
+            )
+            .await
+            .unwrap();
+        let table = repos
+            .tables()
+            .create_or_get("test_table", namespace.id)
+            .await
+            .unwrap();
+        let shard = repos
+            .shards()
+            .create_or_get(&topic, ShardIndex::new(2000))
+            .await
+            .unwrap();
+        let partition = repos
+            .partitions()
+            .create_or_get("one".into(), shard.id, table.id)
+            .await
+            .unwrap();
+
+        let parquet_file_params = ParquetFileParams {
+            shard_id: shard.id,
+            namespace_id: namespace.id,
+            table_id: partition.table_id,
+            partition_id: partition.id,
+            object_store_id: Uuid::new_v4(),
+            max_sequence_number: SequenceNumber::new(140),
+            min_time: Timestamp::new(1),
+            max_time: Timestamp::new(10),
+            file_size_bytes: 1337,
+            row_count: 0,
+            compaction_level: CompactionLevel::Initial,
+            created_at: Timestamp::new(1),
+            column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::new([ColumnStats {
+                column_id: ColumnId::new(1),
+                null_count: Some(0),
+                min: Some("east".to_string()),
+                max: Some("west".to_string()),
+            }]),
+        };
+        let parquet_file = repos
+            .parquet_files()
+            .create(parquet_file_params.clone())
+            .await
+            .unwrap();
+
+        let many_params: Vec<_> = (0..2)
+            .map(|_| ParquetFileParams {
+                object_store_id: Uuid::new_v4(),
+                ..parquet_file_params.clone()
+            })
+            .collect();
+        let many_ids: Vec<_> = many_params.iter().map(|p| p.object_store_id).collect();
+
+        // nothing is created if one of the files exists already
+        let err = repos
+            .parquet_files()
+            .create_parquet_files(
+                many_params
+                    .iter()
+                    .cloned()
+                    .chain(std::iter::once(parquet_file_params))
+                    .collect(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::FileExists { object_store_id } if object_store_id == parquet_file.object_store_id
+        ));
+        let existing = repos
+            .parquet_files()
+            .existing_object_store_ids(&many_ids)
+            .await
+            .unwrap();
+        assert!(existing.is_empty());
+
+        // nothing is created if the same file is passed twice
+        let err = repos
+            .parquet_files()
+            .create_parquet_files(vec![many_params[0].clone(), many_params[0].clone()])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::FileExists { .. }));
+        let existing = repos
+            .parquet_files()
+            .existing_object_store_ids(&many_ids)
+            .await
+            .unwrap();
+        assert!(existing.is_empty());
+
+        let many_files = repos
+            .parquet_files()
+            .create_parquet_files(many_params)
+            .await
+            .unwrap();
+        let mut created_ids: Vec<_> = many_files.iter().map(|f| f.object_store_id).collect();
+        created_ids.sort();
+        let mut expected = many_ids.clone();
+        expected.sort();
+        assert_eq!(created_ids, expected);
+        assert!(many_files.iter().all(|f| f.to_delete.is_none()));
+
+        let files = repos
+            .parquet_files()
+            .list_by_partition_not_to_delete(partition.id)
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 3);
+
+        assert!(repos
+            .parquet_files()
+            .create_parquet_files(vec![])
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
     async fn test_parquet_file_compaction_level_0(catalog: Arc<dyn Catalog>) {
         let mut repos = catalog.repositories().await;
         let topic = repos.topics().create_or_get("foo").await.unwrap();
@@ -2408,6 +3193,7 @@ pub(crate) mod test_helpers {
             compaction_level: CompactionLevel::Initial,
             created_at: Timestamp::new(1),
             column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::default(),
         };
 
         let parquet_file = repos
@@ -2537,6 +3323,7 @@ pub(crate) mod test_helpers {
             compaction_level: CompactionLevel::Initial,
             created_at: Timestamp::new(1),
             column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::default(),
         };
         let parquet_file = repos
             .parquet_files()
@@ -2764,6 +3551,7 @@ pub(crate) mod test_helpers {
             compaction_level: CompactionLevel::Initial,
             created_at: time_38_hour_ago,
             column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::default(),
         };
         let delete_l0_file = repos
             .parquet_files()
@@ -2998,6 +3786,7 @@ pub(crate) mod test_helpers {
             compaction_level: CompactionLevel::Initial,
             created_at: time_now,
             column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::default(),
         };
         let delete_l0_file = repos
             .parquet_files()
@@ -3211,6 +4000,7 @@ pub(crate) mod test_helpers {
             compaction_level: CompactionLevel::Initial,
             created_at: Timestamp::new(1),
             column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::default(),
         };
 
         let parquet_file = repos
@@ -3265,6 +4055,67 @@ pub(crate) mod test_helpers {
             .await
             .unwrap();
         assert_eq!(files, vec![parquet_file.clone(), level1_file.clone()]);
+
+        // level 0 files at or below the compaction cursor are skipped, level 1 files are not
+        let newer_file_params = ParquetFileParams {
+            object_store_id: Uuid::new_v4(),
+            max_sequence_number: SequenceNumber::new(200),
+            ..parquet_file_params.clone()
+        };
+        let newer_file = repos
+            .parquet_files()
+            .create(newer_file_params)
+            .await
+            .unwrap();
+        let files = repos
+            .parquet_files()
+            .list_by_partition_not_to_delete_paged(
+                partition.id,
+                Some(SequenceNumber::new(140)),
+                None,
+                10,
+            )
+            .await
+            .unwrap();
+        assert_eq!(files, vec![level1_file.clone(), newer_file.clone()]);
+        let files = repos
+            .parquet_files()
+            .list_by_partition_not_to_delete_paged(
+                partition.id,
+                Some(SequenceNumber::new(139)),
+                None,
+                10,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            files,
+            vec![
+                parquet_file.clone(),
+                level1_file.clone(),
+                newer_file.clone()
+            ]
+        );
+
+        // pages are ordered by ID and start after the last file of the previous page
+        let mut pages = vec![];
+        let mut after = None;
+        loop {
+            let page = repos
+                .parquet_files()
+                .list_by_partition_not_to_delete_paged(partition.id, None, after, 2)
+                .await
+                .unwrap();
+            match page.last() {
+                Some(last) => after = Some(last.id),
+                None => break,
+            }
+            pages.push(page);
+        }
+        assert_eq!(
+            pages,
+            vec![vec![parquet_file, level1_file], vec![newer_file]]
+        );
     }
 
     async fn test_update_to_compaction_level_1(catalog: Arc<dyn Catalog>) {
@@ -3317,6 +4168,7 @@ pub(crate) mod test_helpers {
             compaction_level: CompactionLevel::Initial,
             created_at: Timestamp::new(1),
             column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::default(),
         };
         let parquet_file = repos
             .parquet_files()
@@ -3433,6 +4285,7 @@ pub(crate) mod test_helpers {
             compaction_level: CompactionLevel::Initial,
             created_at: Timestamp::new(1),
             column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::default(),
         };
         let p1 = repos
             .parquet_files()
@@ -3566,6 +4419,372 @@ pub(crate) mod test_helpers {
         assert_eq!(count, 0);
     }
 
+    async fn test_compaction_reports(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("foo").await.unwrap();
+        let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = repos
+            .namespaces()
+            .create("namespace_compaction_report_test", "inf", topic.id, pool.id)
+            .await
+            .unwrap();
+        let table = repos
+            .tables()
+            .create_or_get("test_table", namespace.id)
+            .await
+            .unwrap();
+        let shard = repos
+            .shards()
+            .create_or_get(&topic, ShardIndex::new(1))
+            .await
+            .unwrap();
+        let partition = repos
+            .partitions()
+            .create_or_get("one".into(), shard.id, table.id)
+            .await
+            .unwrap();
+        let other_partition = repos
+            .partitions()
+            .create_or_get("two".into(), shard.id, table.id)
+            .await
+            .unwrap();
+
+        let reports = repos
+            .compaction_reports()
+            .list_by_partition(partition.id)
+            .await
+            .unwrap();
+        assert!(reports.is_empty());
+
+        let r1 = repos
+            .compaction_reports()
+            .create(partition.id, Uuid::new_v4(), Timestamp::new(1))
+            .await
+            .unwrap();
+        assert_eq!(r1.partition_id, partition.id);
+        assert_eq!(r1.created_at, Timestamp::new(1));
+        let r2 = repos
+            .compaction_reports()
+            .create(partition.id, Uuid::new_v4(), Timestamp::new(2))
+            .await
+            .unwrap();
+        assert_ne!(r1.id, r2.id);
+        let r3 = repos
+            .compaction_reports()
+            .create(other_partition.id, Uuid::new_v4(), Timestamp::new(3))
+            .await
+            .unwrap();
+
+        let reports = repos
+            .compaction_reports()
+            .list_by_partition(partition.id)
+            .await
+            .unwrap();
+        assert_eq!(reports, vec![r1, r2]);
+
+        let reports = repos
+            .compaction_reports()
+            .list_by_partition(other_partition.id)
+            .await
+            .unwrap();
+        assert_eq!(reports, vec![r3]);
+
+        // the partition must exist
+        let err = repos
+            .compaction_reports()
+            .create(
+                PartitionId::new(i64::MAX),
+                Uuid::new_v4(),
+                Timestamp::new(4),
+            )
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                Error::PartitionNotFound { .. } | Error::ForeignKeyViolation { .. }
+            ),
+            "{err:?}"
+        );
+    }
+
+    async fn test_compaction_keys(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("foo").await.unwrap();
+        let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = repos
+            .namespaces()
+            .create("namespace_compaction_key_test", "inf", topic.id, pool.id)
+            .await
+            .unwrap();
+        let table = repos
+            .tables()
+            .create_or_get("test_table", namespace.id)
+            .await
+            .unwrap();
+        let shard = repos
+            .shards()
+            .create_or_get(&topic, ShardIndex::new(1))
+            .await
+            .unwrap();
+        let partition = repos
+            .partitions()
+            .create_or_get("one".into(), shard.id, table.id)
+            .await
+            .unwrap();
+        let other_partition = repos
+            .partitions()
+            .create_or_get("two".into(), shard.id, table.id)
+            .await
+            .unwrap();
+
+        let params = ParquetFileParams {
+            shard_id: shard.id,
+            namespace_id: namespace.id,
+            table_id: table.id,
+            partition_id: partition.id,
+            object_store_id: Uuid::new_v4(),
+            max_sequence_number: SequenceNumber::new(10),
+            min_time: Timestamp::new(1),
+            max_time: Timestamp::new(10),
+            file_size_bytes: 1337,
+            row_count: 0,
+            compaction_level: CompactionLevel::FileNonOverlapped,
+            created_at: Timestamp::new(1),
+            column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+            column_stats: ColumnStatsSet::default(),
+        };
+        let files = repos
+            .parquet_files()
+            .create_parquet_files(vec![
+                params.clone(),
+                ParquetFileParams {
+                    object_store_id: Uuid::new_v4(),
+                    ..params.clone()
+                },
+                ParquetFileParams {
+                    object_store_id: Uuid::new_v4(),
+                    ..params.clone()
+                },
+            ])
+            .await
+            .unwrap();
+        let other_file = repos
+            .parquet_files()
+            .create(ParquetFileParams {
+                object_store_id: Uuid::new_v4(),
+                partition_id: other_partition.id,
+                ..params
+            })
+            .await
+            .unwrap();
+
+        let ids = repos
+            .parquet_files()
+            .list_ids_by_compaction_key(partition.id, "k1")
+            .await
+            .unwrap();
+        assert!(ids.is_empty());
+
+        repos
+            .parquet_files()
+            .set_compaction_key(&[files[0].id, files[1].id], "k1")
+            .await
+            .unwrap();
+        repos
+            .parquet_files()
+            .set_compaction_key(&[files[2].id], "k2")
+            .await
+            .unwrap();
+        repos
+            .parquet_files()
+            .set_compaction_key(&[other_file.id], "k1")
+            .await
+            .unwrap();
+
+        let ids = repos
+            .parquet_files()
+            .list_ids_by_compaction_key(partition.id, "k1")
+            .await
+            .unwrap();
+        assert_eq!(ids, vec![files[0].id, files[1].id]);
+        let ids = repos
+            .parquet_files()
+            .list_ids_by_compaction_key(other_partition.id, "k1")
+            .await
+            .unwrap();
+        assert_eq!(ids, vec![other_file.id]);
+
+        // files marked for deletion keep their key
+        repos
+            .parquet_files()
+            .flag_for_delete(files[2].id)
+            .await
+            .unwrap();
+        let ids = repos
+            .parquet_files()
+            .list_ids_by_compaction_key(partition.id, "k2")
+            .await
+            .unwrap();
+        assert_eq!(ids, vec![files[2].id]);
+    }
+
+    async fn test_table_access_stats(catalog: Arc<dyn Catalog>) {
+        let mut repos = catalog.repositories().await;
+        let topic = repos.topics().create_or_get("foo").await.unwrap();
+        let pool = repos.query_pools().create_or_get("foo").await.unwrap();
+        let namespace = repos
+            .namespaces()
+            .create(
+                "namespace_table_access_stats_test",
+                "inf",
+                topic.id,
+                pool.id,
+            )
+            .await
+            .unwrap();
+        let other_namespace = repos
+            .namespaces()
+            .create(
+                "namespace_table_access_stats_test2",
+                "inf",
+                topic.id,
+                pool.id,
+            )
+            .await
+            .unwrap();
+        let table = repos
+            .tables()
+            .create_or_get("test_table", namespace.id)
+            .await
+            .unwrap();
+        let other_table = repos
+            .tables()
+            .create_or_get("test_table2", namespace.id)
+            .await
+            .unwrap();
+        let other_namespace_table = repos
+            .tables()
+            .create_or_get("test_table", other_namespace.id)
+            .await
+            .unwrap();
+
+        let stats = repos
+            .table_access_stats()
+            .get_by_table_id(table.id)
+            .await
+            .unwrap();
+        assert!(stats.is_none());
+
+        let stats = repos
+            .table_access_stats()
+            .record(table.id, Timestamp::new(10), 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            stats,
+            TableAccessStats {
+                table_id: table.id,
+                last_queried_at: Timestamp::new(10),
+                query_count: 2,
+            }
+        );
+
+        // counts add up and the last query time never moves backwards
+        let stats = repos
+            .table_access_stats()
+            .record(table.id, Timestamp::new(5), 3)
+            .await
+            .unwrap();
+        assert_eq!(stats.last_queried_at, Timestamp::new(10));
+        assert_eq!(stats.query_count, 5);
+        let stats = repos
+            .table_access_stats()
+            .record(table.id, Timestamp::new(20), 1)
+            .await
+            .unwrap();
+        assert_eq!(stats.last_queried_at, Timestamp::new(20));
+        assert_eq!(stats.query_count, 6);
+        assert_eq!(
+            repos
+                .table_access_stats()
+                .get_by_table_id(table.id)
+                .await
+                .unwrap(),
+            Some(stats)
+        );
+
+        let other_stats = repos
+            .table_access_stats()
+            .record(other_table.id, Timestamp::new(1), 1)
+            .await
+            .unwrap();
+        let other_namespace_stats = repos
+            .table_access_stats()
+            .record(other_namespace_table.id, Timestamp::new(1), 1)
+            .await
+            .unwrap();
+
+        let mut listed = repos
+            .table_access_stats()
+            .list_by_namespace(namespace.id)
+            .await
+            .unwrap();
+        listed.sort_by_key(|s| s.table_id);
+        assert_eq!(listed, vec![stats, other_stats]);
+
+        let listed = repos
+            .table_access_stats()
+            .list_by_namespace(other_namespace.id)
+            .await
+            .unwrap();
+        assert_eq!(listed, vec![other_namespace_stats]);
+
+        let table_ids = [table.id, other_table.id, other_namespace_table.id];
+        let queried_since = |listed: Vec<TableAccessStats>| {
+            listed
+                .into_iter()
+                .filter(|s| table_ids.contains(&s.table_id))
+                .map(|s| s.table_id)
+                .collect::<Vec<_>>()
+        };
+        let listed = repos
+            .table_access_stats()
+            .list_queried_since(Timestamp::new(1), 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            queried_since(listed),
+            vec![table.id, other_table.id, other_namespace_table.id]
+        );
+        let listed = repos
+            .table_access_stats()
+            .list_queried_since(Timestamp::new(2), 10)
+            .await
+            .unwrap();
+        assert_eq!(queried_since(listed), vec![table.id]);
+        let listed = repos
+            .table_access_stats()
+            .list_queried_since(Timestamp::new(1), 1)
+            .await
+            .unwrap();
+        assert_eq!(listed.len(), 1);
+
+        // the table must exist
+        let err = repos
+            .table_access_stats()
+            .record(TableId::new(i64::MAX), Timestamp::new(1), 1)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                Error::TableNotFound { .. } | Error::ForeignKeyViolation { .. }
+            ),
+            "{err:?}"
+        );
+    }
+
     async fn test_txn_isolation(catalog: Arc<dyn Catalog>) {
         let barrier = Arc::new(tokio::sync::Barrier::new(2));
 
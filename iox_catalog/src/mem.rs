@@ -3,20 +3,22 @@
 
 use crate::{
     interface::{
-        sealed::TransactionFinalize, Catalog, ColumnRepo, ColumnUpsertRequest, Error,
-        NamespaceRepo, ParquetFileRepo, PartitionRepo, ProcessedTombstoneRepo, QueryPoolRepo,
-        RepoCollection, Result, ShardRepo, TablePersistInfo, TableRepo, TombstoneRepo,
-        TopicMetadataRepo, Transaction,
+        sealed::TransactionFinalize, Catalog, ColumnRepo, ColumnUpsertRequest,
+        CompactionReportRepo, Error, NamespaceRepo, ParquetFileRepo, PartitionRepo,
+        ProcessedTombstoneRepo, QueryPoolRepo, RepoCollection, Result, ShardRepo,
+        TableAccessStatsRepo, TablePersistInfo, TableRepo, TombstoneRepo, TopicMetadataRepo,
+        Transaction,
     },
     metrics::MetricDecorator,
 };
 use async_trait::async_trait;
 use data_types::{
-    Column, ColumnId, ColumnType, ColumnTypeCount, CompactionLevel, Namespace, NamespaceId,
-    ParquetFile, ParquetFileId, ParquetFileParams, Partition, PartitionId, PartitionInfo,
-    PartitionKey, PartitionParam, ProcessedTombstone, QueryPool, QueryPoolId, SequenceNumber,
-    Shard, ShardId, ShardIndex, Table, TableId, TablePartition, Timestamp, Tombstone, TombstoneId,
-    TopicId, TopicMetadata,
+    Column, ColumnId, ColumnType, ColumnTypeCount, ColumnTypeMigration, ColumnTypeMigrationId,
+    CompactionLevel, CompactionReport, CompactionReportId, Namespace, NamespaceId, ParquetFile,
+    ParquetFileId, ParquetFileParams, Partition, PartitionId, PartitionInfo, PartitionKey,
+    PartitionParam, ProcessedTombstone, QueryPool, QueryPoolId, SequenceNumber, Shard, ShardId,
+    ShardIndex, Table, TableAccessStats, TableId, TablePartition, Timestamp, Tombstone,
+    TombstoneId, TopicId, TopicMetadata,
 };
 use iox_time::{SystemProvider, TimeProvider};
 use observability_deps::tracing::warn;
@@ -62,11 +64,18 @@ struct MemCollections {
     namespaces: Vec<Namespace>,
     tables: Vec<Table>,
     columns: Vec<Column>,
+    column_type_migrations: Vec<ColumnTypeMigration>,
     shards: Vec<Shard>,
     partitions: Vec<Partition>,
     tombstones: Vec<Tombstone>,
     parquet_files: Vec<ParquetFile>,
     processed_tombstones: Vec<ProcessedTombstone>,
+    compaction_reports: Vec<CompactionReport>,
+    table_access_stats: Vec<TableAccessStats>,
+    /// Partitions that an ingester is persisting, with the time it started.
+    persisting_partitions: HashMap<PartitionId, Timestamp>,
+    /// Idempotency keys of the compactions that created parquet files.
+    compaction_keys: HashMap<ParquetFileId, String>,
 }
 
 #[derive(Debug)]
@@ -227,6 +236,14 @@ impl RepoCollection for MemTxn {
     fn processed_tombstones(&mut self) -> &mut dyn ProcessedTombstoneRepo {
         self
     }
+
+    fn compaction_reports(&mut self) -> &mut dyn CompactionReportRepo {
+        self
+    }
+
+    fn table_access_stats(&mut self) -> &mut dyn TableAccessStatsRepo {
+        self
+    }
 }
 
 #[async_trait]
@@ -303,6 +320,9 @@ impl NamespaceRepo for MemTxn {
             retention_duration: Some(retention_duration.to_string()),
             max_tables: 10000,
             max_columns_per_table: 1000,
+            write_rate_limit: None,
+            query_rate_limit: None,
+            query_timeout_ms: None,
         };
         stage.namespaces.push(namespace);
         Ok(stage.namespaces.last().unwrap().clone())
@@ -351,6 +371,57 @@ impl NamespaceRepo for MemTxn {
             }),
         }
     }
+
+    async fn update_write_rate_limit(
+        &mut self,
+        name: &str,
+        new_max: Option<i32>,
+    ) -> Result<Namespace> {
+        let stage = self.stage();
+        match stage.namespaces.iter_mut().find(|n| n.name == name) {
+            Some(n) => {
+                n.write_rate_limit = new_max;
+                Ok(n.clone())
+            }
+            None => Err(Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    async fn update_query_rate_limit(
+        &mut self,
+        name: &str,
+        new_max: Option<i32>,
+    ) -> Result<Namespace> {
+        let stage = self.stage();
+        match stage.namespaces.iter_mut().find(|n| n.name == name) {
+            Some(n) => {
+                n.query_rate_limit = new_max;
+                Ok(n.clone())
+            }
+            None => Err(Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            }),
+        }
+    }
+
+    async fn update_query_timeout(
+        &mut self,
+        name: &str,
+        new_timeout_ms: Option<i64>,
+    ) -> Result<Namespace> {
+        let stage = self.stage();
+        match stage.namespaces.iter_mut().find(|n| n.name == name) {
+            Some(n) => {
+                n.query_timeout_ms = new_timeout_ms;
+                Ok(n.clone())
+            }
+            None => Err(Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            }),
+        }
+    }
 }
 
 #[async_trait]
@@ -632,6 +703,96 @@ impl ColumnRepo for MemTxn {
 
         Ok(column_type_counts)
     }
+
+    async fn start_type_migration(
+        &mut self,
+        column_id: ColumnId,
+        column_type: ColumnType,
+        created_at: Timestamp,
+    ) -> Result<ColumnTypeMigration> {
+        let stage = self.stage();
+
+        if stage
+            .column_type_migrations
+            .iter()
+            .any(|m| m.column_id == column_id && m.is_active())
+        {
+            return Err(Error::ColumnTypeMigrationActive { id: column_id });
+        }
+
+        let column = stage
+            .columns
+            .iter_mut()
+            .find(|c| c.id == column_id)
+            .ok_or(Error::ColumnNotFound { id: column_id })?;
+        let existing =
+            ColumnType::try_from(column.column_type).map_err(|_| Error::UnknownColumnType {
+                data_type: column.column_type,
+                name: column.name.clone(),
+            })?;
+        if !existing.can_migrate_to(column_type) {
+            return Err(Error::ColumnTypeMigrationUnsupported {
+                name: column.name.clone(),
+                existing: existing.to_string(),
+                new: column_type.to_string(),
+            });
+        }
+        column.column_type = column_type as i16;
+
+        let migration = ColumnTypeMigration {
+            id: ColumnTypeMigrationId::new(stage.column_type_migrations.len() as i64 + 1),
+            table_id: column.table_id,
+            column_id,
+            from_type: existing as i16,
+            to_type: column_type as i16,
+            created_at,
+            completed_at: None,
+        };
+        stage.column_type_migrations.push(migration.clone());
+
+        Ok(migration)
+    }
+
+    async fn list_type_migrations_by_table_id(
+        &mut self,
+        table_id: TableId,
+    ) -> Result<Vec<ColumnTypeMigration>> {
+        let stage = self.stage();
+
+        Ok(stage
+            .column_type_migrations
+            .iter()
+            .filter(|m| m.table_id == table_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn list_active_type_migrations(&mut self) -> Result<Vec<ColumnTypeMigration>> {
+        let stage = self.stage();
+
+        Ok(stage
+            .column_type_migrations
+            .iter()
+            .filter(|m| m.is_active())
+            .cloned()
+            .collect())
+    }
+
+    async fn complete_type_migration(
+        &mut self,
+        id: ColumnTypeMigrationId,
+        completed_at: Timestamp,
+    ) -> Result<ColumnTypeMigration> {
+        let stage = self.stage();
+
+        match stage.column_type_migrations.iter_mut().find(|m| m.id == id) {
+            Some(m) => {
+                m.completed_at.get_or_insert(completed_at);
+                Ok(m.clone())
+            }
+            None => Err(Error::ColumnTypeMigrationNotFound { id }),
+        }
+    }
 }
 
 #[async_trait]
@@ -734,6 +895,8 @@ impl PartitionRepo for MemTxn {
                         table_id,
                         partition_key: key,
                         sort_key: vec![],
+                        sort_key_version: 0,
+                        compaction_cursor: None,
                     };
                     stage.partitions.push(p);
                     stage.partitions.last().unwrap()
@@ -753,6 +916,17 @@ impl PartitionRepo for MemTxn {
             .cloned())
     }
 
+    async fn get_by_ids(&mut self, partition_ids: &[PartitionId]) -> Result<Vec<Partition>> {
+        let stage = self.stage();
+
+        Ok(stage
+            .partitions
+            .iter()
+            .filter(|p| partition_ids.contains(&p.id))
+            .cloned()
+            .collect())
+    }
+
     async fn list_by_shard(&mut self, shard_id: ShardId) -> Result<Vec<Partition>> {
         let stage = self.stage();
 
@@ -840,11 +1014,97 @@ impl PartitionRepo for MemTxn {
         match stage.partitions.iter_mut().find(|p| p.id == partition_id) {
             Some(p) => {
                 p.sort_key = sort_key.iter().map(|s| s.to_string()).collect();
+                p.sort_key_version += 1;
                 Ok(p.clone())
             }
             None => Err(Error::PartitionNotFound { id: partition_id }),
         }
     }
+
+    async fn update_sort_key_if_version(
+        &mut self,
+        partition_id: PartitionId,
+        sort_key: &[&str],
+        expected_version: i64,
+    ) -> Result<Partition> {
+        let stage = self.stage();
+        match stage.partitions.iter_mut().find(|p| p.id == partition_id) {
+            Some(p) if p.sort_key_version != expected_version => {
+                Err(Error::SortKeyVersionConflict {
+                    id: partition_id,
+                    expected_version,
+                    actual_version: p.sort_key_version,
+                })
+            }
+            Some(p) => {
+                p.sort_key = sort_key.iter().map(|s| s.to_string()).collect();
+                p.sort_key_version += 1;
+                Ok(p.clone())
+            }
+            None => Err(Error::PartitionNotFound { id: partition_id }),
+        }
+    }
+
+    async fn update_compaction_cursor(
+        &mut self,
+        partition_id: PartitionId,
+        max_sequence_number: SequenceNumber,
+    ) -> Result<()> {
+        let stage = self.stage();
+        match stage.partitions.iter_mut().find(|p| p.id == partition_id) {
+            Some(p) => {
+                p.compaction_cursor = p.compaction_cursor.max(Some(max_sequence_number));
+                Ok(())
+            }
+            None => Err(Error::PartitionNotFound { id: partition_id }),
+        }
+    }
+
+    async fn start_persisting(&mut self, partition_id: PartitionId) -> Result<()> {
+        let started_at = Timestamp::new(self.time_provider.now().timestamp_nanos());
+
+        let stage = self.stage();
+        if !stage.partitions.iter().any(|p| p.id == partition_id) {
+            return Err(Error::PartitionNotFound { id: partition_id });
+        }
+        stage.persisting_partitions.insert(partition_id, started_at);
+
+        Ok(())
+    }
+
+    async fn finish_persisting(&mut self, partition_id: PartitionId) -> Result<()> {
+        let stage = self.stage();
+        if !stage.partitions.iter().any(|p| p.id == partition_id) {
+            return Err(Error::PartitionNotFound { id: partition_id });
+        }
+        stage.persisting_partitions.remove(&partition_id);
+
+        Ok(())
+    }
+
+    async fn list_persisting(
+        &mut self,
+        shard_id: ShardId,
+        started_after: Timestamp,
+    ) -> Result<Vec<PartitionId>> {
+        let stage = self.stage();
+
+        let mut partitions: Vec<_> = stage
+            .partitions
+            .iter()
+            .filter(|p| {
+                p.shard_id == shard_id
+                    && stage
+                        .persisting_partitions
+                        .get(&p.id)
+                        .map_or(false, |started_at| *started_at >= started_after)
+            })
+            .map(|p| p.id)
+            .collect();
+        partitions.sort();
+
+        Ok(partitions)
+    }
 }
 
 #[async_trait]
@@ -994,6 +1254,7 @@ impl ParquetFileRepo for MemTxn {
             compaction_level,
             created_at,
             column_set,
+            column_stats,
         } = parquet_file_params;
 
         if stage
@@ -1020,12 +1281,39 @@ impl ParquetFileRepo for MemTxn {
             compaction_level,
             created_at,
             column_set,
+            column_stats,
         };
         stage.parquet_files.push(parquet_file);
 
         Ok(stage.parquet_files.last().unwrap().clone())
     }
 
+    async fn create_parquet_files(
+        &mut self,
+        parquet_file_params: Vec<ParquetFileParams>,
+    ) -> Result<Vec<ParquetFile>> {
+        // check all files upfront, so that either all or none are created
+        let stage = self.stage();
+        let mut object_store_ids = HashSet::with_capacity(parquet_file_params.len());
+        for params in &parquet_file_params {
+            let object_store_id = params.object_store_id;
+            if !object_store_ids.insert(object_store_id)
+                || stage
+                    .parquet_files
+                    .iter()
+                    .any(|f| f.object_store_id == object_store_id)
+            {
+                return Err(Error::FileExists { object_store_id });
+            }
+        }
+
+        let mut parquet_files = Vec::with_capacity(parquet_file_params.len());
+        for params in parquet_file_params {
+            parquet_files.push(ParquetFileRepo::create(self, params).await?);
+        }
+        Ok(parquet_files)
+    }
+
     async fn flag_for_delete(&mut self, id: ParquetFileId) -> Result<()> {
         let marked_at = Timestamp::new(self.time_provider.now().timestamp_nanos());
         let stage = self.stage();
@@ -1098,6 +1386,17 @@ impl ParquetFileRepo for MemTxn {
         Ok(delete)
     }
 
+    async fn list_old(&mut self, older_than: Timestamp) -> Result<Vec<ParquetFile>> {
+        let stage = self.stage();
+
+        Ok(stage
+            .parquet_files
+            .iter()
+            .filter(|f| matches!(f.to_delete, Some(marked_deleted) if marked_deleted < older_than))
+            .cloned()
+            .collect())
+    }
+
     async fn level_0(&mut self, shard_id: ShardId) -> Result<Vec<ParquetFile>> {
         let stage = self.stage();
 
@@ -1273,6 +1572,35 @@ impl ParquetFileRepo for MemTxn {
             .collect())
     }
 
+    async fn list_by_partition_not_to_delete_paged(
+        &mut self,
+        partition_id: PartitionId,
+        compaction_cursor: Option<SequenceNumber>,
+        after: Option<ParquetFileId>,
+        limit: usize,
+    ) -> Result<Vec<ParquetFile>> {
+        let stage = self.stage();
+
+        let mut parquet_files: Vec<_> = stage
+            .parquet_files
+            .iter()
+            .filter(|f| {
+                f.partition_id == partition_id
+                    && f.to_delete.is_none()
+                    && after.map_or(true, |after| f.id > after)
+                    && compaction_cursor.map_or(true, |compaction_cursor| {
+                        f.compaction_level != CompactionLevel::Initial
+                            || f.max_sequence_number > compaction_cursor
+                    })
+            })
+            .cloned()
+            .collect();
+        parquet_files.sort_by_key(|f| f.id);
+        parquet_files.truncate(limit);
+
+        Ok(parquet_files)
+    }
+
     async fn update_to_level_1(
         &mut self,
         parquet_file_ids: &[ParquetFileId],
@@ -1374,6 +1702,60 @@ impl ParquetFileRepo for MemTxn {
             .find(|f| f.object_store_id.eq(&object_store_id))
             .cloned())
     }
+
+    async fn existing_object_store_ids(&mut self, object_store_ids: &[Uuid]) -> Result<Vec<Uuid>> {
+        let stage = self.stage();
+
+        Ok(object_store_ids
+            .iter()
+            .filter(|id| {
+                stage
+                    .parquet_files
+                    .iter()
+                    .any(|f| f.object_store_id == **id)
+            })
+            .copied()
+            .collect())
+    }
+
+    async fn set_compaction_key(
+        &mut self,
+        parquet_file_ids: &[ParquetFileId],
+        compaction_key: &str,
+    ) -> Result<()> {
+        let stage = self.stage();
+
+        for id in parquet_file_ids {
+            if stage.parquet_files.iter().any(|f| f.id == *id) {
+                stage
+                    .compaction_keys
+                    .insert(*id, compaction_key.to_string());
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn list_ids_by_compaction_key(
+        &mut self,
+        partition_id: PartitionId,
+        compaction_key: &str,
+    ) -> Result<Vec<ParquetFileId>> {
+        let stage = self.stage();
+
+        let mut ids: Vec<_> = stage
+            .parquet_files
+            .iter()
+            .filter(|f| {
+                f.partition_id == partition_id
+                    && stage.compaction_keys.get(&f.id).map(String::as_str) == Some(compaction_key)
+            })
+            .map(|f| f.id)
+            .collect();
+        ids.sort();
+
+        Ok(ids)
+    }
 }
 
 #[async_trait]
@@ -1457,6 +1839,135 @@ impl ProcessedTombstoneRepo for MemTxn {
     }
 }
 
+#[async_trait]
+impl CompactionReportRepo for MemTxn {
+    async fn create(
+        &mut self,
+        partition_id: PartitionId,
+        object_store_id: Uuid,
+        created_at: Timestamp,
+    ) -> Result<CompactionReport> {
+        let stage = self.stage();
+
+        if !stage.partitions.iter().any(|p| p.id == partition_id) {
+            return Err(Error::PartitionNotFound { id: partition_id });
+        }
+
+        let report = CompactionReport {
+            id: CompactionReportId::new(stage.compaction_reports.len() as i64 + 1),
+            partition_id,
+            object_store_id,
+            created_at,
+        };
+        stage.compaction_reports.push(report);
+
+        Ok(report)
+    }
+
+    async fn list_by_partition(
+        &mut self,
+        partition_id: PartitionId,
+    ) -> Result<Vec<CompactionReport>> {
+        let stage = self.stage();
+
+        Ok(stage
+            .compaction_reports
+            .iter()
+            .filter(|r| r.partition_id == partition_id)
+            .copied()
+            .collect())
+    }
+}
+
+#[async_trait]
+impl TableAccessStatsRepo for MemTxn {
+    async fn record(
+        &mut self,
+        table_id: TableId,
+        last_queried_at: Timestamp,
+        query_count: i64,
+    ) -> Result<TableAccessStats> {
+        let stage = self.stage();
+
+        if !stage.tables.iter().any(|t| t.id == table_id) {
+            return Err(Error::TableNotFound { id: table_id });
+        }
+
+        let stats = match stage
+            .table_access_stats
+            .iter_mut()
+            .find(|s| s.table_id == table_id)
+        {
+            Some(stats) => {
+                stats.last_queried_at = stats.last_queried_at.max(last_queried_at);
+                stats.query_count += query_count;
+                *stats
+            }
+            None => {
+                let stats = TableAccessStats {
+                    table_id,
+                    last_queried_at,
+                    query_count,
+                };
+                stage.table_access_stats.push(stats);
+                stats
+            }
+        };
+
+        Ok(stats)
+    }
+
+    async fn get_by_table_id(&mut self, table_id: TableId) -> Result<Option<TableAccessStats>> {
+        let stage = self.stage();
+
+        Ok(stage
+            .table_access_stats
+            .iter()
+            .find(|s| s.table_id == table_id)
+            .copied())
+    }
+
+    async fn list_by_namespace(
+        &mut self,
+        namespace_id: NamespaceId,
+    ) -> Result<Vec<TableAccessStats>> {
+        let stage = self.stage();
+
+        let table_ids: HashSet<_> = stage
+            .tables
+            .iter()
+            .filter(|t| t.namespace_id == namespace_id)
+            .map(|t| t.id)
+            .collect();
+
+        Ok(stage
+            .table_access_stats
+            .iter()
+            .filter(|s| table_ids.contains(&s.table_id))
+            .copied()
+            .collect())
+    }
+
+    async fn list_queried_since(
+        &mut self,
+        since: Timestamp,
+        limit: usize,
+    ) -> Result<Vec<TableAccessStats>> {
+        let stage = self.stage();
+
+        let mut stats: Vec<_> = stage
+            .table_access_stats
+            .iter()
+            .filter(|s| s.last_queried_at >= since)
+            .copied()
+            .collect();
+        stats.sort_by_key(|s| (std::cmp::Reverse(s.last_queried_at), s.table_id));
+        stats.truncate(limit);
+
+        Ok(stats)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,15 +1,17 @@
 //! Metric instrumentation for catalog implementations.
 
 use crate::interface::{
-    sealed::TransactionFinalize, ColumnRepo, ColumnUpsertRequest, NamespaceRepo, ParquetFileRepo,
-    PartitionRepo, ProcessedTombstoneRepo, QueryPoolRepo, RepoCollection, Result, ShardRepo,
-    TablePersistInfo, TableRepo, TombstoneRepo, TopicMetadataRepo,
+    sealed::TransactionFinalize, ColumnRepo, ColumnUpsertRequest, CompactionReportRepo,
+    NamespaceRepo, ParquetFileRepo, PartitionRepo, ProcessedTombstoneRepo, QueryPoolRepo,
+    RepoCollection, Result, ShardRepo, TableAccessStatsRepo, TablePersistInfo, TableRepo,
+    TombstoneRepo, TopicMetadataRepo,
 };
 use async_trait::async_trait;
 use data_types::{
-    Column, ColumnType, ColumnTypeCount, Namespace, NamespaceId, ParquetFile, ParquetFileId,
-    ParquetFileParams, Partition, PartitionId, PartitionInfo, PartitionKey, PartitionParam,
-    ProcessedTombstone, QueryPool, QueryPoolId, SequenceNumber, Shard, ShardId, ShardIndex, Table,
+    Column, ColumnId, ColumnType, ColumnTypeCount, ColumnTypeMigration, ColumnTypeMigrationId,
+    CompactionReport, Namespace, NamespaceId, ParquetFile, ParquetFileId, ParquetFileParams,
+    Partition, PartitionId, PartitionInfo, PartitionKey, PartitionParam, ProcessedTombstone,
+    QueryPool, QueryPoolId, SequenceNumber, Shard, ShardId, ShardIndex, Table, TableAccessStats,
     TableId, TablePartition, Timestamp, Tombstone, TombstoneId, TopicId, TopicMetadata,
 };
 use iox_time::{SystemProvider, TimeProvider};
@@ -53,6 +55,8 @@ where
         + TombstoneRepo
         + ProcessedTombstoneRepo
         + ParquetFileRepo
+        + CompactionReportRepo
+        + TableAccessStatsRepo
         + Debug,
     P: TimeProvider,
 {
@@ -95,6 +99,14 @@ where
     fn processed_tombstones(&mut self) -> &mut dyn ProcessedTombstoneRepo {
         self
     }
+
+    fn compaction_reports(&mut self) -> &mut dyn CompactionReportRepo {
+        self
+    }
+
+    fn table_access_stats(&mut self) -> &mut dyn TableAccessStatsRepo {
+        self
+    }
 }
 
 #[async_trait]
@@ -198,6 +210,9 @@ decorate!(
         "namespace_get_by_name" = get_by_name(&mut self, name: &str) -> Result<Option<Namespace>>;
         "namespace_update_table_limit" = update_table_limit(&mut self, name: &str, new_max: i32) -> Result<Namespace>;
         "namespace_update_column_limit" = update_column_limit(&mut self, name: &str, new_max: i32) -> Result<Namespace>;
+        "namespace_update_write_rate_limit" = update_write_rate_limit(&mut self, name: &str, new_max: Option<i32>) -> Result<Namespace>;
+        "namespace_update_query_rate_limit" = update_query_rate_limit(&mut self, name: &str, new_max: Option<i32>) -> Result<Namespace>;
+        "namespace_update_query_timeout" = update_query_timeout(&mut self, name: &str, new_timeout_ms: Option<i64>) -> Result<Namespace>;
     ]
 );
 
@@ -222,6 +237,10 @@ decorate!(
         "column_create_or_get_many" = create_or_get_many(&mut self, columns: &[ColumnUpsertRequest<'_>]) -> Result<Vec<Column>>;
         "column_list" = list(&mut self) -> Result<Vec<Column>>;
         "column_list_type_count_by_table_id" = list_type_count_by_table_id(&mut self, table_id: TableId) -> Result<Vec<ColumnTypeCount>>;
+        "column_start_type_migration" = start_type_migration(&mut self, column_id: ColumnId, column_type: ColumnType, created_at: Timestamp) -> Result<ColumnTypeMigration>;
+        "column_list_type_migrations_by_table_id" = list_type_migrations_by_table_id(&mut self, table_id: TableId) -> Result<Vec<ColumnTypeMigration>>;
+        "column_list_active_type_migrations" = list_active_type_migrations(&mut self) -> Result<Vec<ColumnTypeMigration>>;
+        "column_complete_type_migration" = complete_type_migration(&mut self, id: ColumnTypeMigrationId, completed_at: Timestamp) -> Result<ColumnTypeMigration>;
     ]
 );
 
@@ -241,11 +260,17 @@ decorate!(
     methods = [
         "partition_create_or_get" = create_or_get(&mut self, key: PartitionKey, shard_id: ShardId, table_id: TableId) -> Result<Partition>;
         "partition_get_by_id" = get_by_id(&mut self, partition_id: PartitionId) -> Result<Option<Partition>>;
+        "partition_get_by_ids" = get_by_ids(&mut self, partition_ids: &[PartitionId]) -> Result<Vec<Partition>>;
         "partition_list_by_shard" = list_by_shard(&mut self, shard_id: ShardId) -> Result<Vec<Partition>>;
         "partition_list_by_namespace" = list_by_namespace(&mut self, namespace_id: NamespaceId) -> Result<Vec<Partition>>;
         "partition_list_by_table_id" = list_by_table_id(&mut self, table_id: TableId) -> Result<Vec<Partition>>;
         "partition_partition_info_by_id" = partition_info_by_id(&mut self, partition_id: PartitionId) -> Result<Option<PartitionInfo>>;
         "partition_update_sort_key" = update_sort_key(&mut self, partition_id: PartitionId, sort_key: &[&str]) -> Result<Partition>;
+        "partition_update_sort_key_if_version" = update_sort_key_if_version(&mut self, partition_id: PartitionId, sort_key: &[&str], expected_version: i64) -> Result<Partition>;
+        "partition_update_compaction_cursor" = update_compaction_cursor(&mut self, partition_id: PartitionId, max_sequence_number: SequenceNumber) -> Result<()>;
+        "partition_start_persisting" = start_persisting(&mut self, partition_id: PartitionId) -> Result<()>;
+        "partition_finish_persisting" = finish_persisting(&mut self, partition_id: PartitionId) -> Result<()>;
+        "partition_list_persisting" = list_persisting(&mut self, shard_id: ShardId, started_after: Timestamp) -> Result<Vec<PartitionId>>;
     ]
 );
 
@@ -266,12 +291,15 @@ decorate!(
     impl_trait = ParquetFileRepo,
     methods = [
         "parquet_create" = create( &mut self, parquet_file_params: ParquetFileParams) -> Result<ParquetFile>;
+        "parquet_create_parquet_files" = create_parquet_files(&mut self, parquet_file_params: Vec<ParquetFileParams>) -> Result<Vec<ParquetFile>>;
         "parquet_flag_for_delete" = flag_for_delete(&mut self, id: ParquetFileId) -> Result<()>;
         "parquet_list_by_shard_greater_than" = list_by_shard_greater_than(&mut self, shard_id: ShardId, sequence_number: SequenceNumber) -> Result<Vec<ParquetFile>>;
         "parquet_list_by_namespace_not_to_delete" = list_by_namespace_not_to_delete(&mut self, namespace_id: NamespaceId) -> Result<Vec<ParquetFile>>;
         "parquet_list_by_table_not_to_delete" = list_by_table_not_to_delete(&mut self, table_id: TableId) -> Result<Vec<ParquetFile>>;
         "parquet_delete_old" = delete_old(&mut self, older_than: Timestamp) -> Result<Vec<ParquetFile>>;
+        "parquet_list_old" = list_old(&mut self, older_than: Timestamp) -> Result<Vec<ParquetFile>>;
         "parquet_list_by_partition_not_to_delete" = list_by_partition_not_to_delete(&mut self, partition_id: PartitionId) -> Result<Vec<ParquetFile>>;
+        "parquet_list_by_partition_not_to_delete_paged" = list_by_partition_not_to_delete_paged(&mut self, partition_id: PartitionId, compaction_cursor: Option<SequenceNumber>, after: Option<ParquetFileId>, limit: usize) -> Result<Vec<ParquetFile>>;
         "parquet_level_0" = level_0(&mut self, shard_id: ShardId) -> Result<Vec<ParquetFile>>;
         "parquet_level_1" = level_1(&mut self, table_partition: TablePartition, min_time: Timestamp, max_time: Timestamp) -> Result<Vec<ParquetFile>>;
         "parquet_update_to_level_1" = update_to_level_1(&mut self, parquet_file_ids: &[ParquetFileId]) -> Result<Vec<ParquetFileId>>;
@@ -280,6 +308,9 @@ decorate!(
         "parquet_count_by_overlaps_with_level_0" = count_by_overlaps_with_level_0(&mut self, table_id: TableId, shard_id: ShardId, min_time: Timestamp, max_time: Timestamp, sequence_number: SequenceNumber) -> Result<i64>;
         "parquet_count_by_overlaps_with_level_1" = count_by_overlaps_with_level_1(&mut self, table_id: TableId, shard_id: ShardId, min_time: Timestamp, max_time: Timestamp) -> Result<i64>;
         "parquet_get_by_object_store_id" = get_by_object_store_id(&mut self, object_store_id: Uuid) -> Result<Option<ParquetFile>>;
+        "parquet_existing_object_store_ids" = existing_object_store_ids(&mut self, object_store_ids: &[Uuid]) -> Result<Vec<Uuid>>;
+        "parquet_set_compaction_key" = set_compaction_key(&mut self, parquet_file_ids: &[ParquetFileId], compaction_key: &str) -> Result<()>;
+        "parquet_list_ids_by_compaction_key" = list_ids_by_compaction_key(&mut self, partition_id: PartitionId, compaction_key: &str) -> Result<Vec<ParquetFileId>>;
         "recent_highest_throughput_partitions" = recent_highest_throughput_partitions(&mut self, shard_id: ShardId, num_hours: u32, min_num_files: usize, num_partitions: usize) -> Result<Vec<PartitionParam>>;
         "most_level_0_files_partitions" =  most_level_0_files_partitions(&mut self, shard_id: ShardId, older_than_num_hours: u32, num_partitions: usize) -> Result<Vec<PartitionParam>>;
     ]
@@ -294,3 +325,21 @@ decorate!(
         "processed_tombstone_count_by_tombstone_id" = count_by_tombstone_id(&mut self, tombstone_id: TombstoneId) -> Result<i64>;
     ]
 );
+
+decorate!(
+    impl_trait = CompactionReportRepo,
+    methods = [
+        "compaction_report_create" = create(&mut self, partition_id: PartitionId, object_store_id: Uuid, created_at: Timestamp) -> Result<CompactionReport>;
+        "compaction_report_list_by_partition" = list_by_partition(&mut self, partition_id: PartitionId) -> Result<Vec<CompactionReport>>;
+    ]
+);
+
+decorate!(
+    impl_trait = TableAccessStatsRepo,
+    methods = [
+        "table_access_stats_record" = record(&mut self, table_id: TableId, last_queried_at: Timestamp, query_count: i64) -> Result<TableAccessStats>;
+        "table_access_stats_get_by_table_id" = get_by_table_id(&mut self, table_id: TableId) -> Result<Option<TableAccessStats>>;
+        "table_access_stats_list_by_namespace" = list_by_namespace(&mut self, namespace_id: NamespaceId) -> Result<Vec<TableAccessStats>>;
+        "table_access_stats_list_queried_since" = list_queried_since(&mut self, since: Timestamp, limit: usize) -> Result<Vec<TableAccessStats>>;
+    ]
+);
@@ -2,19 +2,21 @@
 
 use crate::{
     interface::{
-        sealed::TransactionFinalize, Catalog, ColumnRepo, ColumnUpsertRequest, Error,
-        NamespaceRepo, ParquetFileRepo, PartitionRepo, ProcessedTombstoneRepo, QueryPoolRepo,
-        RepoCollection, Result, ShardRepo, TablePersistInfo, TableRepo, TombstoneRepo,
-        TopicMetadataRepo, Transaction,
+        sealed::TransactionFinalize, Catalog, ColumnRepo, ColumnUpsertRequest,
+        CompactionReportRepo, Error, NamespaceRepo, ParquetFileRepo, PartitionRepo,
+        ProcessedTombstoneRepo, QueryPoolRepo, RepoCollection, Result, ShardRepo,
+        TableAccessStatsRepo, TablePersistInfo, TableRepo, TombstoneRepo, TopicMetadataRepo,
+        Transaction,
     },
     metrics::MetricDecorator,
 };
 use async_trait::async_trait;
 use data_types::{
-    Column, ColumnType, ColumnTypeCount, CompactionLevel, Namespace, NamespaceId, ParquetFile,
-    ParquetFileId, ParquetFileParams, Partition, PartitionId, PartitionInfo, PartitionKey,
-    PartitionParam, ProcessedTombstone, QueryPool, QueryPoolId, SequenceNumber, Shard, ShardId,
-    ShardIndex, Table, TableId, TablePartition, Timestamp, Tombstone, TombstoneId, TopicId,
+    Column, ColumnId, ColumnType, ColumnTypeCount, ColumnTypeMigration, ColumnTypeMigrationId,
+    CompactionLevel, CompactionReport, Namespace, NamespaceId, ParquetFile, ParquetFileId,
+    ParquetFileParams, Partition, PartitionId, PartitionInfo, PartitionKey, PartitionParam,
+    ProcessedTombstone, QueryPool, QueryPoolId, SequenceNumber, Shard, ShardId, ShardIndex, Table,
+    TableAccessStats, TableId, TablePartition, Timestamp, Tombstone, TombstoneId, TopicId,
     TopicMetadata,
 };
 use iox_time::{SystemProvider, TimeProvider};
@@ -23,7 +25,7 @@ use sqlx::{
     migrate::Migrator, postgres::PgPoolOptions, types::Uuid, Acquire, Executor, Postgres, Row,
 };
 use sqlx_hotswap_pool::HotSwapPool;
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashSet, sync::Arc, time::Duration};
 
 static MIGRATOR: Migrator = sqlx::migrate!();
 
@@ -500,6 +502,14 @@ impl RepoCollection for PostgresTxn {
     fn processed_tombstones(&mut self) -> &mut dyn ProcessedTombstoneRepo {
         self
     }
+
+    fn compaction_reports(&mut self) -> &mut dyn CompactionReportRepo {
+        self
+    }
+
+    fn table_access_stats(&mut self) -> &mut dyn TableAccessStatsRepo {
+        self
+    }
 }
 
 #[async_trait]
@@ -705,6 +715,90 @@ RETURNING *;
 
         Ok(namespace)
     }
+
+    async fn update_write_rate_limit(
+        &mut self,
+        name: &str,
+        new_max: Option<i32>,
+    ) -> Result<Namespace> {
+        let rec = sqlx::query_as::<_, Namespace>(
+            r#"
+UPDATE namespace
+SET write_rate_limit = $1
+WHERE name = $2
+RETURNING *;
+        "#,
+        )
+        .bind(&new_max)
+        .bind(&name)
+        .fetch_one(&mut self.inner)
+        .await;
+
+        let namespace = rec.map_err(|e| match e {
+            sqlx::Error::RowNotFound => Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            },
+            _ => Error::SqlxError { source: e },
+        })?;
+
+        Ok(namespace)
+    }
+
+    async fn update_query_rate_limit(
+        &mut self,
+        name: &str,
+        new_max: Option<i32>,
+    ) -> Result<Namespace> {
+        let rec = sqlx::query_as::<_, Namespace>(
+            r#"
+UPDATE namespace
+SET query_rate_limit = $1
+WHERE name = $2
+RETURNING *;
+        "#,
+        )
+        .bind(&new_max)
+        .bind(&name)
+        .fetch_one(&mut self.inner)
+        .await;
+
+        let namespace = rec.map_err(|e| match e {
+            sqlx::Error::RowNotFound => Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            },
+            _ => Error::SqlxError { source: e },
+        })?;
+
+        Ok(namespace)
+    }
+
+    async fn update_query_timeout(
+        &mut self,
+        name: &str,
+        new_timeout_ms: Option<i64>,
+    ) -> Result<Namespace> {
+        let rec = sqlx::query_as::<_, Namespace>(
+            r#"
+UPDATE namespace
+SET query_timeout_ms = $1
+WHERE name = $2
+RETURNING *;
+        "#,
+        )
+        .bind(&new_timeout_ms)
+        .bind(&name)
+        .fetch_one(&mut self.inner)
+        .await;
+
+        let namespace = rec.map_err(|e| match e {
+            sqlx::Error::RowNotFound => Error::NamespaceNotFoundByName {
+                name: name.to_string(),
+            },
+            _ => Error::SqlxError { source: e },
+        })?;
+
+        Ok(namespace)
+    }
 }
 
 #[async_trait]
@@ -1024,6 +1118,136 @@ select column_type as col_type, count(1) from column_name where table_id = $1 gr
         .await
         .map_err(|e| Error::SqlxError { source: e })
     }
+
+    async fn start_type_migration(
+        &mut self,
+        column_id: ColumnId,
+        column_type: ColumnType,
+        created_at: Timestamp,
+    ) -> Result<ColumnTypeMigration> {
+        let active = sqlx::query_scalar::<_, i64>(
+            r#"
+SELECT count(*) FROM column_type_migration WHERE column_id = $1 AND completed_at IS NULL;
+            "#,
+        )
+        .bind(&column_id) // $1
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+        if active > 0 {
+            return Err(Error::ColumnTypeMigrationActive { id: column_id });
+        }
+
+        let column = sqlx::query_as::<_, Column>(
+            r#"
+SELECT * FROM column_name WHERE id = $1;
+            "#,
+        )
+        .bind(&column_id) // $1
+        .fetch_optional(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?
+        .ok_or(Error::ColumnNotFound { id: column_id })?;
+
+        let existing =
+            ColumnType::try_from(column.column_type).map_err(|_| Error::UnknownColumnType {
+                data_type: column.column_type,
+                name: column.name.clone(),
+            })?;
+        if !existing.can_migrate_to(column_type) {
+            return Err(Error::ColumnTypeMigrationUnsupported {
+                name: column.name,
+                existing: existing.to_string(),
+                new: column_type.to_string(),
+            });
+        }
+
+        // Update the column and record the migration in a single statement so that both
+        // happen or neither. The partial unique index `column_type_migration_active_idx`
+        // rejects a second active migration for the same column.
+        let rec = sqlx::query_as::<_, ColumnTypeMigration>(
+            r#"
+WITH updated AS (
+    UPDATE column_name
+    SET column_type = $2
+    WHERE id = $1 AND column_type = $3
+    RETURNING id, table_id
+)
+INSERT INTO column_type_migration ( table_id, column_id, from_type, to_type, created_at )
+SELECT table_id, id, $3, $2, $4 FROM updated
+RETURNING *;
+            "#,
+        )
+        .bind(&column_id) // $1
+        .bind(column_type as i16) // $2
+        .bind(existing as i16) // $3
+        .bind(&created_at) // $4
+        .fetch_one(&mut self.inner)
+        .await;
+
+        match rec {
+            Ok(migration) => Ok(migration),
+            // the column type was changed concurrently
+            Err(sqlx::Error::RowNotFound) => {
+                Err(Error::ColumnTypeMigrationActive { id: column_id })
+            }
+            Err(e) if is_unique_violation(&e) => {
+                Err(Error::ColumnTypeMigrationActive { id: column_id })
+            }
+            Err(e) => Err(Error::SqlxError { source: e }),
+        }
+    }
+
+    async fn list_type_migrations_by_table_id(
+        &mut self,
+        table_id: TableId,
+    ) -> Result<Vec<ColumnTypeMigration>> {
+        sqlx::query_as::<_, ColumnTypeMigration>(
+            r#"
+SELECT * FROM column_type_migration WHERE table_id = $1 ORDER BY id;
+            "#,
+        )
+        .bind(&table_id) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list_active_type_migrations(&mut self) -> Result<Vec<ColumnTypeMigration>> {
+        sqlx::query_as::<_, ColumnTypeMigration>(
+            r#"
+SELECT * FROM column_type_migration WHERE completed_at IS NULL ORDER BY id;
+            "#,
+        )
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn complete_type_migration(
+        &mut self,
+        id: ColumnTypeMigrationId,
+        completed_at: Timestamp,
+    ) -> Result<ColumnTypeMigration> {
+        let rec = sqlx::query_as::<_, ColumnTypeMigration>(
+            r#"
+UPDATE column_type_migration
+SET completed_at = COALESCE(completed_at, $2)
+WHERE id = $1
+RETURNING *;
+            "#,
+        )
+        .bind(&id) // $1
+        .bind(&completed_at) // $2
+        .fetch_one(&mut self.inner)
+        .await;
+
+        match rec {
+            Ok(migration) => Ok(migration),
+            Err(sqlx::Error::RowNotFound) => Err(Error::ColumnTypeMigrationNotFound { id }),
+            Err(e) => Err(Error::SqlxError { source: e }),
+        }
+    }
 }
 
 #[async_trait]
@@ -1184,6 +1408,16 @@ RETURNING *;
         Ok(Some(partition))
     }
 
+    async fn get_by_ids(&mut self, partition_ids: &[PartitionId]) -> Result<Vec<Partition>> {
+        let ids: Vec<_> = partition_ids.iter().map(|p| p.get()).collect();
+
+        sqlx::query_as::<_, Partition>(r#"SELECT * FROM partition WHERE id = ANY($1);"#)
+            .bind(&ids[..]) // $1
+            .fetch_all(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })
+    }
+
     async fn list_by_shard(&mut self, shard_id: ShardId) -> Result<Vec<Partition>> {
         sqlx::query_as::<_, Partition>(r#"SELECT * FROM partition WHERE shard_id = $1;"#)
             .bind(&shard_id) // $1
@@ -1247,6 +1481,8 @@ WHERE partition.id = $1;
             table_id: info.get("table_id"),
             partition_key: info.get("partition_key"),
             sort_key: info.get("sort_key"),
+            sort_key_version: info.get("sort_key_version"),
+            compaction_cursor: info.get("compaction_cursor"),
         };
 
         Ok(Some(PartitionInfo {
@@ -1264,7 +1500,7 @@ WHERE partition.id = $1;
         let rec = sqlx::query_as::<_, Partition>(
             r#"
 UPDATE partition
-SET sort_key = $1
+SET sort_key = $1, sort_key_version = sort_key_version + 1
 WHERE id = $2
 RETURNING *;
         "#,
@@ -1288,6 +1524,139 @@ RETURNING *;
 
         Ok(partition)
     }
+
+    async fn update_sort_key_if_version(
+        &mut self,
+        partition_id: PartitionId,
+        sort_key: &[&str],
+        expected_version: i64,
+    ) -> Result<Partition> {
+        let rec = sqlx::query_as::<_, Partition>(
+            r#"
+UPDATE partition
+SET sort_key = $1, sort_key_version = sort_key_version + 1
+WHERE id = $2 AND sort_key_version = $3
+RETURNING *;
+        "#,
+        )
+        .bind(&sort_key) // $1
+        .bind(&partition_id) // $2
+        .bind(&expected_version) // $3
+        .fetch_one(&mut self.inner)
+        .await;
+
+        let partition = match rec {
+            Ok(partition) => partition,
+            Err(sqlx::Error::RowNotFound) => {
+                // either the partition does not exist or the version does not match
+                let actual_version = sqlx::query_scalar::<_, i64>(
+                    r#"SELECT sort_key_version FROM partition WHERE id = $1;"#,
+                )
+                .bind(&partition_id) // $1
+                .fetch_optional(&mut self.inner)
+                .await
+                .map_err(|e| Error::SqlxError { source: e })?;
+
+                return Err(match actual_version {
+                    Some(actual_version) => Error::SortKeyVersionConflict {
+                        id: partition_id,
+                        expected_version,
+                        actual_version,
+                    },
+                    None => Error::PartitionNotFound { id: partition_id },
+                });
+            }
+            Err(e) => return Err(Error::SqlxError { source: e }),
+        };
+
+        debug!(
+            ?partition_id,
+            input_sort_key=?sort_key,
+            expected_version,
+            partition_after_catalog_update=?partition,
+            "Partition after conditionally updating sort key"
+        );
+
+        Ok(partition)
+    }
+
+    async fn update_compaction_cursor(
+        &mut self,
+        partition_id: PartitionId,
+        max_sequence_number: SequenceNumber,
+    ) -> Result<()> {
+        // GREATEST ignores NULL, i.e. a partition that was never compacted
+        let rec = sqlx::query(
+            r#"
+UPDATE partition
+SET compaction_cursor = GREATEST(compaction_cursor, $1)
+WHERE id = $2;
+        "#,
+        )
+        .bind(&max_sequence_number) // $1
+        .bind(&partition_id) // $2
+        .execute(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })?;
+
+        if rec.rows_affected() == 0 {
+            return Err(Error::PartitionNotFound { id: partition_id });
+        }
+
+        Ok(())
+    }
+
+    async fn start_persisting(&mut self, partition_id: PartitionId) -> Result<()> {
+        let started_at = Timestamp::new(self.time_provider.now().timestamp_nanos());
+
+        let rec = sqlx::query(r#"UPDATE partition SET persisting_at = $1 WHERE id = $2;"#)
+            .bind(&started_at) // $1
+            .bind(&partition_id) // $2
+            .execute(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })?;
+
+        if rec.rows_affected() == 0 {
+            return Err(Error::PartitionNotFound { id: partition_id });
+        }
+
+        Ok(())
+    }
+
+    async fn finish_persisting(&mut self, partition_id: PartitionId) -> Result<()> {
+        let rec = sqlx::query(r#"UPDATE partition SET persisting_at = NULL WHERE id = $1;"#)
+            .bind(&partition_id) // $1
+            .execute(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })?;
+
+        if rec.rows_affected() == 0 {
+            return Err(Error::PartitionNotFound { id: partition_id });
+        }
+
+        Ok(())
+    }
+
+    async fn list_persisting(
+        &mut self,
+        shard_id: ShardId,
+        started_after: Timestamp,
+    ) -> Result<Vec<PartitionId>> {
+        sqlx::query_scalar::<_, PartitionId>(
+            r#"
+SELECT id
+FROM partition
+WHERE shard_id = $1
+  AND persisting_at >= $2
+ORDER BY id;
+        "#,
+        )
+        .bind(&shard_id) // $1
+        .bind(&started_after) // $2
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
 }
 
 #[async_trait]
@@ -1509,6 +1878,7 @@ impl ParquetFileRepo for PostgresTxn {
             compaction_level,
             created_at,
             column_set,
+            column_stats,
         } = parquet_file_params;
 
         let rec = sqlx::query_as::<_, ParquetFile>(
@@ -1516,8 +1886,8 @@ impl ParquetFileRepo for PostgresTxn {
 INSERT INTO parquet_file (
     shard_id, table_id, partition_id, object_store_id,
     max_sequence_number, min_time, max_time, file_size_bytes,
-    row_count, compaction_level, created_at, namespace_id, column_set )
-VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13 )
+    row_count, compaction_level, created_at, namespace_id, column_set, column_stats )
+VALUES ( $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14 )
 RETURNING *;
         "#,
         )
@@ -1534,6 +1904,7 @@ RETURNING *;
         .bind(created_at) // $11
         .bind(namespace_id) // $12
         .bind(column_set) // $13
+        .bind(column_stats) // $14
         .fetch_one(&mut self.inner)
         .await
         .map_err(|e| {
@@ -1549,6 +1920,102 @@ RETURNING *;
         Ok(rec)
     }
 
+    async fn create_parquet_files(
+        &mut self,
+        parquet_file_params: Vec<ParquetFileParams>,
+    ) -> Result<Vec<ParquetFile>> {
+        let object_store_ids: Vec<_> = parquet_file_params
+            .iter()
+            .map(|p| p.object_store_id)
+            .collect();
+        let mut seen = HashSet::with_capacity(object_store_ids.len());
+        if let Some(&object_store_id) = object_store_ids.iter().find(|id| !seen.insert(**id)) {
+            return Err(Error::FileExists { object_store_id });
+        }
+        let existing = self.existing_object_store_ids(&object_store_ids).await?;
+        if let Some(&object_store_id) = existing.first() {
+            return Err(Error::FileExists { object_store_id });
+        }
+
+        let mut v_shard_id = Vec::with_capacity(parquet_file_params.len());
+        let mut v_table_id = Vec::with_capacity(parquet_file_params.len());
+        let mut v_partition_id = Vec::with_capacity(parquet_file_params.len());
+        let mut v_max_sequence_number = Vec::with_capacity(parquet_file_params.len());
+        let mut v_min_time = Vec::with_capacity(parquet_file_params.len());
+        let mut v_max_time = Vec::with_capacity(parquet_file_params.len());
+        let mut v_file_size_bytes = Vec::with_capacity(parquet_file_params.len());
+        let mut v_row_count = Vec::with_capacity(parquet_file_params.len());
+        let mut v_compaction_level = Vec::with_capacity(parquet_file_params.len());
+        let mut v_created_at = Vec::with_capacity(parquet_file_params.len());
+        let mut v_namespace_id = Vec::with_capacity(parquet_file_params.len());
+        let mut v_column_set = Vec::with_capacity(parquet_file_params.len());
+        let mut v_column_stats = Vec::with_capacity(parquet_file_params.len());
+        for p in &parquet_file_params {
+            v_shard_id.push(p.shard_id.get());
+            v_table_id.push(p.table_id.get());
+            v_partition_id.push(p.partition_id.get());
+            v_max_sequence_number.push(p.max_sequence_number.get());
+            v_min_time.push(p.min_time.get());
+            v_max_time.push(p.max_time.get());
+            v_file_size_bytes.push(p.file_size_bytes);
+            v_row_count.push(p.row_count);
+            v_compaction_level.push(p.compaction_level as i16);
+            v_created_at.push(p.created_at.get());
+            v_namespace_id.push(p.namespace_id.get());
+            // UNNEST flattens multi-dimensional arrays, so pass the column sets as array literals
+            v_column_set.push(format!(
+                "{{{}}}",
+                p.column_set
+                    .iter()
+                    .map(|id| id.get().to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ));
+            v_column_stats.push(p.column_stats.clone());
+        }
+
+        // a single statement, so that either all or none of the files are created
+        sqlx::query_as::<_, ParquetFile>(
+            r#"
+INSERT INTO parquet_file (
+    shard_id, table_id, partition_id, object_store_id,
+    max_sequence_number, min_time, max_time, file_size_bytes,
+    row_count, compaction_level, created_at, namespace_id, column_set, column_stats )
+SELECT shard_id, table_id, partition_id, object_store_id,
+       max_sequence_number, min_time, max_time, file_size_bytes,
+       row_count, compaction_level, created_at, namespace_id, column_set::INT8[], column_stats
+FROM UNNEST($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+    AS a(shard_id, table_id, partition_id, object_store_id,
+         max_sequence_number, min_time, max_time, file_size_bytes,
+         row_count, compaction_level, created_at, namespace_id, column_set, column_stats)
+RETURNING *;
+        "#,
+        )
+        .bind(&v_shard_id) // $1
+        .bind(&v_table_id) // $2
+        .bind(&v_partition_id) // $3
+        .bind(&object_store_ids) // $4
+        .bind(&v_max_sequence_number) // $5
+        .bind(&v_min_time) // $6
+        .bind(&v_max_time) // $7
+        .bind(&v_file_size_bytes) // $8
+        .bind(&v_row_count) // $9
+        .bind(&v_compaction_level) // $10
+        .bind(&v_created_at) // $11
+        .bind(&v_namespace_id) // $12
+        .bind(&v_column_set) // $13
+        .bind(&v_column_stats) // $14
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })
+    }
+
     async fn flag_for_delete(&mut self, id: ParquetFileId) -> Result<()> {
         let marked_at = Timestamp::new(self.time_provider.now().timestamp_nanos());
 
@@ -1573,7 +2040,7 @@ RETURNING *;
             r#"
 SELECT id, shard_id, namespace_id, table_id, partition_id, object_store_id,
        max_sequence_number, min_time, max_time, to_delete, file_size_bytes,
-       row_count, compaction_level, created_at, column_set
+       row_count, compaction_level, created_at, column_set, column_stats
 FROM parquet_file
 WHERE shard_id = $1
   AND max_sequence_number > $2
@@ -1599,7 +2066,8 @@ SELECT parquet_file.id, parquet_file.shard_id, parquet_file.namespace_id,
        parquet_file.table_id, parquet_file.partition_id, parquet_file.object_store_id,
        parquet_file.max_sequence_number, parquet_file.min_time,
        parquet_file.max_time, parquet_file.to_delete, parquet_file.file_size_bytes,
-       parquet_file.row_count, parquet_file.compaction_level, parquet_file.created_at, parquet_file.column_set
+       parquet_file.row_count, parquet_file.compaction_level, parquet_file.created_at, parquet_file.column_set,
+       parquet_file.column_stats
 FROM parquet_file
 INNER JOIN table_name on table_name.id = parquet_file.table_id
 WHERE table_name.namespace_id = $1
@@ -1619,7 +2087,7 @@ WHERE table_name.namespace_id = $1
             r#"
 SELECT id, shard_id, namespace_id, table_id, partition_id, object_store_id,
        max_sequence_number, min_time, max_time, to_delete, file_size_bytes,
-       row_count, compaction_level, created_at, column_set
+       row_count, compaction_level, created_at, column_set, column_stats
 FROM parquet_file
 WHERE table_id = $1 AND to_delete IS NULL;
              "#,
@@ -1644,6 +2112,24 @@ RETURNING *;
         .map_err(|e| Error::SqlxError { source: e })
     }
 
+    async fn list_old(&mut self, older_than: Timestamp) -> Result<Vec<ParquetFile>> {
+        // Deliberately doesn't use `SELECT *` to avoid the performance hit of fetching the large
+        // `parquet_metadata` column!!
+        sqlx::query_as::<_, ParquetFile>(
+            r#"
+SELECT id, shard_id, namespace_id, table_id, partition_id, object_store_id,
+       max_sequence_number, min_time, max_time, to_delete, file_size_bytes,
+       row_count, compaction_level, created_at, column_set, column_stats
+FROM parquet_file
+WHERE to_delete < $1;
+             "#,
+        )
+        .bind(&older_than) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
     async fn level_0(&mut self, shard_id: ShardId) -> Result<Vec<ParquetFile>> {
         // this intentionally limits the returned files to 10,000 as it is used to make
         // a decision on the highest priority partitions. If compaction has never been
@@ -1654,7 +2140,7 @@ RETURNING *;
             r#"
 SELECT id, shard_id, namespace_id, table_id, partition_id, object_store_id,
        max_sequence_number, min_time, max_time, to_delete, file_size_bytes,
-       row_count, compaction_level, created_at, column_set
+       row_count, compaction_level, created_at, column_set, column_stats
 FROM parquet_file
 WHERE parquet_file.shard_id = $1
   AND parquet_file.compaction_level = 0
@@ -1680,7 +2166,7 @@ WHERE parquet_file.shard_id = $1
             r#"
 SELECT id, shard_id, namespace_id, table_id, partition_id, object_store_id,
        max_sequence_number, min_time, max_time, to_delete, file_size_bytes,
-       row_count, compaction_level, created_at, column_set
+       row_count, compaction_level, created_at, column_set, column_stats
 FROM parquet_file
 WHERE parquet_file.shard_id = $1
   AND parquet_file.table_id = $2
@@ -1787,7 +2273,7 @@ LIMIT $3;
             r#"
 SELECT id, shard_id, namespace_id, table_id, partition_id, object_store_id,
        max_sequence_number, min_time, max_time, to_delete, file_size_bytes,
-       row_count, compaction_level, created_at, column_set
+       row_count, compaction_level, created_at, column_set, column_stats
 FROM parquet_file
 WHERE parquet_file.partition_id = $1
   AND parquet_file.to_delete IS NULL;
@@ -1799,6 +2285,41 @@ WHERE parquet_file.partition_id = $1
         .map_err(|e| Error::SqlxError { source: e })
     }
 
+    async fn list_by_partition_not_to_delete_paged(
+        &mut self,
+        partition_id: PartitionId,
+        compaction_cursor: Option<SequenceNumber>,
+        after: Option<ParquetFileId>,
+        limit: usize,
+    ) -> Result<Vec<ParquetFile>> {
+        // Deliberately doesn't use `SELECT *` to avoid the performance hit of fetching the large
+        // `parquet_metadata` column!!
+        sqlx::query_as::<_, ParquetFile>(
+            r#"
+SELECT id, shard_id, namespace_id, table_id, partition_id, object_store_id,
+       max_sequence_number, min_time, max_time, to_delete, file_size_bytes,
+       row_count, compaction_level, created_at, column_set, column_stats
+FROM parquet_file
+WHERE parquet_file.partition_id = $1
+  AND parquet_file.to_delete IS NULL
+  AND ($3::BIGINT IS NULL
+       OR parquet_file.compaction_level != $2
+       OR parquet_file.max_sequence_number > $3)
+  AND ($4::BIGINT IS NULL OR parquet_file.id > $4)
+ORDER BY parquet_file.id
+LIMIT $5;
+        "#,
+        )
+        .bind(&partition_id) // $1
+        .bind(CompactionLevel::Initial) // $2
+        .bind(&compaction_cursor) // $3
+        .bind(&after) // $4
+        .bind(limit as i64) // $5
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
     async fn update_to_level_1(
         &mut self,
         parquet_file_ids: &[ParquetFileId],
@@ -1919,7 +2440,7 @@ WHERE table_id = $1
             r#"
 SELECT id, shard_id, namespace_id, table_id, partition_id, object_store_id,
        max_sequence_number, min_time, max_time, to_delete, file_size_bytes,
-       row_count, compaction_level, created_at, column_set
+       row_count, compaction_level, created_at, column_set, column_stats
 FROM parquet_file
 WHERE object_store_id = $1;
              "#,
@@ -1936,6 +2457,59 @@ WHERE object_store_id = $1;
 
         Ok(Some(parquet_file))
     }
+
+    async fn existing_object_store_ids(&mut self, object_store_ids: &[Uuid]) -> Result<Vec<Uuid>> {
+        sqlx::query_scalar::<_, Uuid>(
+            r#"
+SELECT object_store_id
+FROM parquet_file
+WHERE object_store_id = ANY($1);
+             "#,
+        )
+        .bind(object_store_ids) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn set_compaction_key(
+        &mut self,
+        parquet_file_ids: &[ParquetFileId],
+        compaction_key: &str,
+    ) -> Result<()> {
+        // If I try to do `.bind(parquet_file_ids)` directly, I get a compile error from sqlx.
+        // See https://github.com/launchbadge/sqlx/issues/1744
+        let ids: Vec<_> = parquet_file_ids.iter().map(|p| p.get()).collect();
+        let _ = sqlx::query(r#"UPDATE parquet_file SET compaction_key = $1 WHERE id = ANY($2);"#)
+            .bind(compaction_key) // $1
+            .bind(&ids[..]) // $2
+            .execute(&mut self.inner)
+            .await
+            .map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(())
+    }
+
+    async fn list_ids_by_compaction_key(
+        &mut self,
+        partition_id: PartitionId,
+        compaction_key: &str,
+    ) -> Result<Vec<ParquetFileId>> {
+        sqlx::query_scalar::<_, ParquetFileId>(
+            r#"
+SELECT id
+FROM parquet_file
+WHERE partition_id = $1
+  AND compaction_key = $2
+ORDER BY id;
+             "#,
+        )
+        .bind(&partition_id) // $1
+        .bind(compaction_key) // $2
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
 }
 
 #[async_trait]
@@ -2015,6 +2589,149 @@ WHERE parquet_file_id = $1
     }
 }
 
+#[async_trait]
+impl CompactionReportRepo for PostgresTxn {
+    async fn create(
+        &mut self,
+        partition_id: PartitionId,
+        object_store_id: Uuid,
+        created_at: Timestamp,
+    ) -> Result<CompactionReport> {
+        sqlx::query_as::<_, CompactionReport>(
+            r#"
+INSERT INTO compaction_report ( partition_id, object_store_id, created_at )
+VALUES ( $1, $2, $3 )
+RETURNING *;
+        "#,
+        )
+        .bind(partition_id) // $1
+        .bind(object_store_id) // $2
+        .bind(created_at) // $3
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })
+    }
+
+    async fn list_by_partition(
+        &mut self,
+        partition_id: PartitionId,
+    ) -> Result<Vec<CompactionReport>> {
+        sqlx::query_as::<_, CompactionReport>(
+            r#"
+SELECT *
+FROM compaction_report
+WHERE partition_id = $1
+ORDER BY id;
+        "#,
+        )
+        .bind(&partition_id) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+}
+
+#[async_trait]
+impl TableAccessStatsRepo for PostgresTxn {
+    async fn record(
+        &mut self,
+        table_id: TableId,
+        last_queried_at: Timestamp,
+        query_count: i64,
+    ) -> Result<TableAccessStats> {
+        sqlx::query_as::<_, TableAccessStats>(
+            r#"
+INSERT INTO table_access_stats ( table_id, last_queried_at, query_count )
+VALUES ( $1, $2, $3 )
+ON CONFLICT ON CONSTRAINT table_access_stats_pkey
+DO UPDATE SET
+    last_queried_at = GREATEST(table_access_stats.last_queried_at, EXCLUDED.last_queried_at),
+    query_count = table_access_stats.query_count + EXCLUDED.query_count
+RETURNING *;
+        "#,
+        )
+        .bind(table_id) // $1
+        .bind(last_queried_at) // $2
+        .bind(query_count) // $3
+        .fetch_one(&mut self.inner)
+        .await
+        .map_err(|e| {
+            if is_fk_violation(&e) {
+                Error::ForeignKeyViolation { source: e }
+            } else {
+                Error::SqlxError { source: e }
+            }
+        })
+    }
+
+    async fn get_by_table_id(&mut self, table_id: TableId) -> Result<Option<TableAccessStats>> {
+        let rec = sqlx::query_as::<_, TableAccessStats>(
+            r#"
+SELECT *
+FROM table_access_stats
+WHERE table_id = $1;
+        "#,
+        )
+        .bind(&table_id) // $1
+        .fetch_one(&mut self.inner)
+        .await;
+
+        if let Err(sqlx::Error::RowNotFound) = rec {
+            return Ok(None);
+        }
+
+        let stats = rec.map_err(|e| Error::SqlxError { source: e })?;
+
+        Ok(Some(stats))
+    }
+
+    async fn list_by_namespace(
+        &mut self,
+        namespace_id: NamespaceId,
+    ) -> Result<Vec<TableAccessStats>> {
+        sqlx::query_as::<_, TableAccessStats>(
+            r#"
+SELECT table_access_stats.*
+FROM table_access_stats
+INNER JOIN table_name ON table_name.id = table_access_stats.table_id
+WHERE table_name.namespace_id = $1
+ORDER BY table_access_stats.table_id;
+        "#,
+        )
+        .bind(&namespace_id) // $1
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+
+    async fn list_queried_since(
+        &mut self,
+        since: Timestamp,
+        limit: usize,
+    ) -> Result<Vec<TableAccessStats>> {
+        sqlx::query_as::<_, TableAccessStats>(
+            r#"
+SELECT *
+FROM table_access_stats
+WHERE last_queried_at >= $1
+ORDER BY last_queried_at DESC, table_id
+LIMIT $2;
+        "#,
+        )
+        .bind(&since) // $1
+        .bind(limit as i64) // $2
+        .fetch_all(&mut self.inner)
+        .await
+        .map_err(|e| Error::SqlxError { source: e })
+    }
+}
+
 /// The error code returned by Postgres for a unique constraint violation.
 ///
 /// See <https://www.postgresql.org/docs/9.2/errcodes-appendix.html>
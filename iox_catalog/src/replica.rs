@@ -0,0 +1,110 @@
+//! A catalog that splits its interface between a primary and a read replica.
+
+use crate::interface::{Catalog, Error, RepoCollection, Transaction};
+use async_trait::async_trait;
+use iox_time::TimeProvider;
+use std::sync::Arc;
+
+/// A [`Catalog`] that serves non-transactional reads from a read replica.
+///
+/// The catalog interface is split into two halves:
+///
+/// - **write half:** [`setup`](Catalog::setup) and [`start_transaction`](Catalog::start_transaction)
+///   are served by the primary.
+/// - **read half:** [`repositories`](Catalog::repositories) is served by the replica.
+///
+/// This offloads heavy list queries from the primary. A replica lags behind the primary, so reads
+/// may observe slightly stale data, and it rejects mutations. Hence this catalog must only be used
+/// by query-only components that never write through [`repositories`](Catalog::repositories).
+#[derive(Debug)]
+pub struct ReadReplicaCatalog {
+    primary: Arc<dyn Catalog>,
+    replica: Arc<dyn Catalog>,
+}
+
+impl ReadReplicaCatalog {
+    /// Create a new catalog that writes to `primary` and reads from `replica`.
+    pub fn new(primary: Arc<dyn Catalog>, replica: Arc<dyn Catalog>) -> Self {
+        Self { primary, replica }
+    }
+}
+
+#[async_trait]
+impl Catalog for ReadReplicaCatalog {
+    async fn setup(&self) -> Result<(), Error> {
+        // replicas follow the schema of the primary
+        self.primary.setup().await
+    }
+
+    async fn start_transaction(&self) -> Result<Box<dyn Transaction>, Error> {
+        self.primary.start_transaction().await
+    }
+
+    async fn repositories(&self) -> Box<dyn RepoCollection> {
+        self.replica.repositories().await
+    }
+
+    fn metrics(&self) -> Arc<metric::Registry> {
+        self.primary.metrics()
+    }
+
+    fn time_provider(&self) -> Arc<dyn TimeProvider> {
+        self.primary.time_provider()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem::MemCatalog;
+
+    #[tokio::test]
+    async fn test_read_replica_catalog() {
+        let metrics = Arc::new(metric::Registry::default());
+        let primary: Arc<dyn Catalog> = Arc::new(MemCatalog::new(Arc::clone(&metrics)));
+        let replica: Arc<dyn Catalog> = Arc::new(MemCatalog::new(Arc::clone(&metrics)));
+        let catalog = ReadReplicaCatalog::new(Arc::clone(&primary), Arc::clone(&replica));
+
+        // transactions write to the primary
+        let mut txn = catalog.start_transaction().await.unwrap();
+        txn.topics().create_or_get("primary").await.unwrap();
+        txn.commit().await.unwrap();
+
+        // non-transactional reads are served by the replica
+        replica
+            .repositories()
+            .await
+            .topics()
+            .create_or_get("replica")
+            .await
+            .unwrap();
+
+        let mut repos = catalog.repositories().await;
+        assert!(repos
+            .topics()
+            .get_by_name("primary")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(repos
+            .topics()
+            .get_by_name("replica")
+            .await
+            .unwrap()
+            .is_some());
+
+        let mut repos = primary.repositories().await;
+        assert!(repos
+            .topics()
+            .get_by_name("primary")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(repos
+            .topics()
+            .get_by_name("replica")
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -1,6 +1,7 @@
 //! This module handles the manipulation / execution of storage
 //! plans. This is currently implemented using DataFusion, and this
 //! interface abstracts away many of the details
+pub(crate) mod analyze;
 pub(crate) mod context;
 pub mod field;
 pub mod fieldlist;
@@ -14,12 +15,17 @@ pub use context::{DEFAULT_CATALOG, DEFAULT_SCHEMA};
 use executor::DedicatedExecutor;
 use trace::span::{SpanExt, SpanRecorder};
 
-use std::sync::Arc;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use datafusion::{
     self,
+    error::DataFusionError,
     execution::{
         context::SessionState,
+        disk_manager::{DiskManager, DiskManagerConfig},
         runtime_env::{RuntimeConfig, RuntimeEnv},
     },
     logical_plan::{normalize_col, plan::Extension, Expr, LogicalPlan},
@@ -39,6 +45,23 @@ pub struct ExecutorConfig {
 
     /// Target parallelism for query execution
     pub target_query_partitions: usize,
+
+    /// Memory available to DataFusion operators, in bytes.
+    ///
+    /// Operators that can spill (e.g. sorts) write to disk instead of exceeding this limit. `None`
+    /// means unlimited, in which case nothing is spilled.
+    pub mem_pool_size: Option<usize>,
+
+    /// Directory for spill files. Uses the OS temporary directory if `None`.
+    pub spill_dir: Option<PathBuf>,
+
+    /// Minimum number of overlapping chunks that are deduplicated with a single sort that can
+    /// spill, see [`IOxSessionConfig::with_external_dedup_min_chunks`].
+    pub external_dedup_min_chunks: Option<usize>,
+
+    /// Answer time-bucketed aggregations from parquet row group statistics where possible, see
+    /// [`IOxSessionConfig::with_aggregate_pushdown`].
+    pub aggregate_pushdown: bool,
 }
 
 /// Handles executing DataFusion plans, and marshalling the results into rust
@@ -78,6 +101,10 @@ impl Executor {
         Self::new_with_config(ExecutorConfig {
             num_threads,
             target_query_partitions: num_threads,
+            mem_pool_size: None,
+            spill_dir: None,
+            external_dedup_min_chunks: None,
+            aggregate_pushdown: false,
         })
     }
 
@@ -85,7 +112,14 @@ impl Executor {
         let query_exec = DedicatedExecutor::new("IOx Query Executor Thread", config.num_threads);
         let reorg_exec = DedicatedExecutor::new("IOx Reorg Executor Thread", config.num_threads);
 
-        let runtime_config = RuntimeConfig::new();
+        let mut runtime_config = RuntimeConfig::new();
+        if let Some(mem_pool_size) = config.mem_pool_size {
+            runtime_config = runtime_config.with_memory_limit(mem_pool_size, 1.0);
+        }
+        if let Some(spill_dir) = &config.spill_dir {
+            runtime_config = runtime_config
+                .with_disk_manager(DiskManagerConfig::NewSpecified(vec![spill_dir.clone()]));
+        }
         let runtime = Arc::new(RuntimeEnv::new(runtime_config).expect("creating runtime"));
 
         Self {
@@ -100,9 +134,25 @@ impl Executor {
     ///
     /// Note that this context (and all its clones) will be shut down once `Executor` is dropped.
     pub fn new_execution_config(&self, executor_type: ExecutorType) -> IOxSessionConfig {
+        self.execution_config(executor_type, Arc::clone(&self.runtime))
+    }
+
+    fn execution_config(
+        &self,
+        executor_type: ExecutorType,
+        runtime: Arc<RuntimeEnv>,
+    ) -> IOxSessionConfig {
         let exec = self.executor(executor_type).clone();
-        IOxSessionConfig::new(exec, Arc::clone(&self.runtime))
+        IOxSessionConfig::new(exec, runtime)
             .with_target_partitions(self.config.target_query_partitions)
+            .with_external_dedup_min_chunks(self.config.external_dedup_min_chunks)
+            .with_aggregate_pushdown(self.config.aggregate_pushdown)
+    }
+
+    /// Returns true if contexts of this executor answer aggregations from row group statistics
+    /// where possible, see [`ExecutorConfig::aggregate_pushdown`].
+    pub fn aggregate_pushdown(&self) -> bool {
+        self.config.aggregate_pushdown
     }
 
     /// Get IOx context from DataFusion state.
@@ -124,6 +174,30 @@ impl Executor {
         self.new_execution_config(executor_type).build()
     }
 
+    /// Create a new execution context like [`new_context`](Self::new_context) whose operators
+    /// write their spill files into `spill_dir` instead of [`ExecutorConfig::spill_dir`].
+    ///
+    /// Memory is still accounted for in the memory pool shared by all contexts of this executor.
+    pub fn new_context_with_spill_dir(
+        &self,
+        executor_type: ExecutorType,
+        spill_dir: &Path,
+    ) -> Result<IOxSessionContext, DataFusionError> {
+        let disk_manager =
+            DiskManager::try_new(DiskManagerConfig::NewSpecified(vec![spill_dir.to_owned()]))?;
+        let runtime = Arc::new(RuntimeEnv {
+            memory_manager: Arc::clone(&self.runtime.memory_manager),
+            disk_manager,
+            object_store_registry: Arc::clone(&self.runtime.object_store_registry),
+        });
+        Ok(self.execution_config(executor_type, runtime).build())
+    }
+
+    /// Number of threads per thread pool.
+    pub fn num_threads(&self) -> usize {
+        self.config.num_threads
+    }
+
     /// Return the execution pool  of the specified type
     fn executor(&self, executor_type: ExecutorType) -> &DedicatedExecutor {
         match executor_type {
@@ -132,6 +206,12 @@ impl Executor {
         }
     }
 
+    /// Register tokio runtime metrics for both thread pools.
+    pub fn register_metrics(&self, metric_registry: &metric::Registry) {
+        self.query_exec.register_metrics("query", metric_registry);
+        self.reorg_exec.register_metrics("reorg", metric_registry);
+    }
+
     /// Initializes shutdown.
     pub fn shutdown(&self) {
         self.query_exec.shutdown();
@@ -457,6 +537,71 @@ mod tests {
         exec.join().await;
     }
 
+    #[tokio::test]
+    async fn approximate_aggregates_are_registered() {
+        let batch = RecordBatch::try_from_iter_with_nullable(vec![
+            ("tag", to_string_array(&["a", "b", "a", "c"]), true),
+            (
+                "v",
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4])) as ArrayRef,
+                true,
+            ),
+        ])
+        .expect("created new record batch");
+        let table = MemTable::try_new(batch.schema(), vec![vec![batch]]).unwrap();
+
+        let exec = Executor::new(1);
+        let ctx = exec.new_context(ExecutorType::Query);
+        ctx.inner().register_table("t", Arc::new(table)).unwrap();
+        let plan = ctx
+            .prepare_sql(
+                "SELECT approx_count_distinct(tag) AS tags, approx_percentile(v, 0.5) AS median \
+                 FROM t",
+            )
+            .await
+            .unwrap();
+        let results = ctx.collect(plan).await.unwrap();
+
+        datafusion::assert_batches_eq!(
+            [
+                "+------+--------+",
+                "| tags | median |",
+                "+------+--------+",
+                "| 3    | 2.5    |",
+                "+------+--------+",
+            ],
+            &results
+        );
+
+        exec.join().await;
+    }
+
+    #[tokio::test]
+    async fn context_with_spill_dir() {
+        let spill_dir = test_helpers::tmp_dir().unwrap();
+        let exec = Executor::new(1);
+
+        let ctx = exec
+            .new_context_with_spill_dir(ExecutorType::Reorg, spill_dir.path())
+            .unwrap();
+        let results = ctx
+            .collect(ctx.prepare_sql("SELECT 1 AS a").await.unwrap())
+            .await
+            .unwrap();
+        datafusion::assert_batches_eq!(["+---+", "| a |", "+---+", "| 1 |", "+---+"], &results);
+
+        // spill files are removed with the context
+        drop(ctx);
+        assert_eq!(std::fs::read_dir(spill_dir.path()).unwrap().count(), 0);
+
+        let err = exec
+            .new_context_with_spill_dir(ExecutorType::Reorg, &spill_dir.path().join("missing"))
+            .unwrap_err();
+        assert!(matches!(err, DataFusionError::IoError(_)), "{}", err);
+
+        exec.join().await;
+    }
+
     /// return a set for testing
     fn to_set(strs: &[&str]) -> StringSetRef {
         StringSetRef::new(strs.iter().map(|s| s.to_string()).collect::<StringSet>())
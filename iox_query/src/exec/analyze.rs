@@ -0,0 +1,355 @@
+//! This module contains the IOx implementation of `EXPLAIN ANALYZE`.
+//!
+//! [`IOxAnalyzeExec`] executes its input to completion, discards the output and instead returns
+//! the metrics of every operator of the input plan, e.g. the scanned chunks by type (parquet
+//! files vs. ingester data), the rows removed by deduplication and the chunks pruned while
+//! planning the query.
+
+use std::{any::Any, fmt, sync::Arc};
+
+use arrow::{
+    array::StringArray,
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    error::{ArrowError, Result as ArrowResult},
+    record_batch::RecordBatch,
+};
+use datafusion::{
+    error::{DataFusionError, Result},
+    execution::context::TaskContext,
+    physical_plan::{
+        expressions::PhysicalSortExpr,
+        metrics::{MetricValue, MetricsSet},
+        DisplayFormatType, Distribution, ExecutionPlan, Partitioning, SendableRecordBatchStream,
+        Statistics,
+    },
+};
+use datafusion_util::{watch::WatchedTask, AdapterStream};
+use futures::TryStreamExt;
+use tokio::sync::mpsc;
+
+use crate::QueryPruningStats;
+
+/// Executes `input` and returns one row with the metrics of every operator of `input`, followed
+/// by one row with the pruning statistics of every scanned table.
+#[derive(Debug)]
+pub(crate) struct IOxAnalyzeExec {
+    input: Arc<dyn ExecutionPlan>,
+    pruning_stats: Option<Arc<QueryPruningStats>>,
+    schema: SchemaRef,
+}
+
+impl IOxAnalyzeExec {
+    pub(crate) fn new(
+        input: Arc<dyn ExecutionPlan>,
+        pruning_stats: Option<Arc<QueryPruningStats>>,
+    ) -> Self {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("operator", DataType::Utf8, false),
+            Field::new("metrics", DataType::Utf8, false),
+        ]));
+
+        Self {
+            input,
+            pruning_stats,
+            schema,
+        }
+    }
+}
+
+impl ExecutionPlan for IOxAnalyzeExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+
+    fn required_child_distribution(&self) -> Distribution {
+        Distribution::UnspecifiedDistribution
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(Self {
+                input: Arc::clone(&children[0]),
+                pruning_stats: self.pruning_stats.clone(),
+                schema: Arc::clone(&self.schema),
+            })),
+            _ => Err(DataFusionError::Internal(
+                "IOxAnalyzeExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        if partition != 0 {
+            return Err(DataFusionError::Internal(format!(
+                "IOxAnalyzeExec invalid partition. Expected 0, got {}",
+                partition
+            )));
+        }
+
+        let (tx, rx) = mpsc::channel(1);
+        let fut = analyze(
+            Arc::clone(&self.input),
+            context,
+            self.pruning_stats.clone(),
+            Arc::clone(&self.schema),
+            tx.clone(),
+        );
+        let handle = WatchedTask::new(fut, vec![tx], "analyze");
+
+        Ok(AdapterStream::adapt(self.schema(), rx, handle))
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default => write!(f, "IOxAnalyzeExec"),
+        }
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        None
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+}
+
+async fn analyze(
+    input: Arc<dyn ExecutionPlan>,
+    context: Arc<TaskContext>,
+    pruning_stats: Option<Arc<QueryPruningStats>>,
+    schema: SchemaRef,
+    tx: mpsc::Sender<ArrowResult<RecordBatch>>,
+) -> ArrowResult<()> {
+    // run all partitions to completion, only the metrics are of interest
+    let streams = (0..input.output_partitioning().partition_count())
+        .map(|partition| input.execute(partition, Arc::clone(&context)))
+        .collect::<Result<Vec<_>>>()
+        .map_err(|e| ArrowError::ExternalError(Box::new(e)))?;
+    futures::future::try_join_all(
+        streams
+            .into_iter()
+            .map(|stream| stream.try_for_each(|_batch| futures::future::ready(Ok(())))),
+    )
+    .await?;
+
+    let mut operators = vec![];
+    let mut metrics = vec![];
+    describe_plan(input.as_ref(), 0, &mut operators, &mut metrics);
+
+    for (table_name, stats) in pruning_stats.iter().flat_map(|stats| stats.tables()) {
+        operators.push(format!("ChunkPruning: table_name={}", table_name));
+        metrics.push(format!(
+            "chunks_kept={}, chunks_pruned={}, rows_pruned={}",
+            stats.chunks_kept, stats.chunks_pruned, stats.rows_pruned
+        ));
+    }
+
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(StringArray::from_iter_values(operators)),
+            Arc::new(StringArray::from_iter_values(metrics)),
+        ],
+    )?;
+
+    // the receiver may have been dropped if the query was cancelled
+    tx.send(Ok(batch)).await.ok();
+
+    Ok(())
+}
+
+/// Describe `plan` and its children (depth first), indented by their depth.
+fn describe_plan(
+    plan: &dyn ExecutionPlan,
+    depth: usize,
+    operators: &mut Vec<String>,
+    metrics: &mut Vec<String>,
+) {
+    operators.push(format!(
+        "{:indent$}{}",
+        "",
+        OneLine(plan),
+        indent = depth * 2
+    ));
+    metrics.push(plan.metrics().map(format_metrics).unwrap_or_default());
+
+    for child in plan.children() {
+        describe_plan(child.as_ref(), depth + 1, operators, metrics);
+    }
+}
+
+/// Aggregate the metrics of all partitions by name and labels, and render them sorted by name.
+///
+/// Unlike the DataFusion `EXPLAIN ANALYZE`, metrics with different labels (e.g. the scanned
+/// chunks by type) are kept apart.
+fn format_metrics(metrics: MetricsSet) -> String {
+    let mut aggregated: Vec<(String, MetricValue)> = vec![];
+
+    for metric in metrics.iter() {
+        let value = metric.value();
+        if matches!(
+            value,
+            MetricValue::StartTimestamp(_) | MetricValue::EndTimestamp(_)
+        ) {
+            continue;
+        }
+
+        let labels: Vec<_> = metric
+            .labels()
+            .iter()
+            .map(|label| format!("{}={}", label.name(), label.value()))
+            .collect();
+        let key = if labels.is_empty() {
+            value.name().to_string()
+        } else {
+            format!("{}{{{}}}", value.name(), labels.join(","))
+        };
+
+        match aggregated.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, acc)) => acc.aggregate(value),
+            None => {
+                let mut acc = value.new_empty();
+                acc.aggregate(value);
+                aggregated.push((key, acc));
+            }
+        }
+    }
+
+    aggregated.sort_by(|(a, _), (b, _)| a.cmp(b));
+    aggregated
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Displays a single operator without its children.
+struct OneLine<'a>(&'a dyn ExecutionPlan);
+
+impl fmt::Display for OneLine<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt_as(DisplayFormatType::Default, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        exec::{Executor, ExecutorType, IOxSessionContext},
+        frontend::sql::SqlQueryPlanner,
+        provider::IOxReadFilterNode,
+        test::TestChunk,
+        QueryChunk, QueryId,
+    };
+    use arrow::array::Array;
+    use predicate::Predicate;
+
+    fn column(batch: &RecordBatch, i: usize) -> Vec<String> {
+        let array = batch
+            .column(i)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        (0..array.len())
+            .map(|row| array.value(row).to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_analyze() {
+        let chunks: Vec<Arc<dyn QueryChunk>> = (1..=2)
+            .map(|id| {
+                Arc::new(
+                    TestChunk::new("t")
+                        .with_id(id)
+                        .with_tag_column("tag")
+                        .with_time_column()
+                        .with_one_row_of_data(),
+                ) as _
+            })
+            .collect();
+        let scan = Arc::new(IOxReadFilterNode::new(
+            IOxSessionContext::with_testing(),
+            Arc::from("t"),
+            chunks[0].schema(),
+            chunks,
+            Predicate::default(),
+        ));
+
+        let pruning_stats = Arc::new(QueryPruningStats::new());
+        pruning_stats.record_pruned("t", 3, 30);
+        pruning_stats.record_kept("t", 2);
+
+        let plan = Arc::new(IOxAnalyzeExec::new(scan, Some(pruning_stats)));
+        let batches = IOxSessionContext::with_testing()
+            .collect(plan)
+            .await
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+
+        let operators = column(&batches[0], 0);
+        assert_eq!(
+            operators,
+            vec![
+                "IOxReadFilterNode: table_name=t, chunks=2 predicate=Predicate",
+                "ChunkPruning: table_name=t",
+            ]
+        );
+
+        let metrics = column(&batches[0], 1);
+        assert!(
+            metrics[0].contains("chunks{chunk_type=Test Chunk}=2"),
+            "{}",
+            metrics[0]
+        );
+        assert!(metrics[0].contains("output_rows=2"), "{}", metrics[0]);
+        assert_eq!(metrics[1], "chunks_kept=2, chunks_pruned=3, rows_pruned=30");
+    }
+
+    #[tokio::test]
+    async fn test_explain_analyze_sql() {
+        let exec = Executor::new(1);
+        let ctx = exec
+            .new_context(ExecutorType::Query)
+            .with_query_id(QueryId::new());
+
+        let plan = SqlQueryPlanner::new()
+            .query("EXPLAIN ANALYZE SELECT 1", &[], &ctx)
+            .await
+            .unwrap();
+        assert!(plan.as_any().is::<IOxAnalyzeExec>());
+
+        let batches = ctx.collect(plan).await.unwrap();
+        let operators = column(&batches[0], 0);
+        assert!(
+            operators[0].starts_with("ProjectionExec"),
+            "{:?}",
+            operators
+        );
+    }
+}
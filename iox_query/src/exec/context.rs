@@ -14,7 +14,7 @@ use datafusion::{
         context::{QueryPlanner, SessionState, TaskContext},
         runtime_env::RuntimeEnv,
     },
-    logical_plan::{LogicalPlan, UserDefinedLogicalNode},
+    logical_plan::{FunctionRegistry, LogicalPlan, UserDefinedLogicalNode},
     physical_plan::{
         coalesce_partitions::CoalescePartitionsExec,
         displayable,
@@ -25,6 +25,7 @@ use datafusion::{
 };
 use futures::TryStreamExt;
 use observability_deps::tracing::debug;
+use query_functions::{APPROX_COUNT_DISTINCT_UDAF_NAME, APPROX_PERCENTILE_UDAF_NAME};
 use trace::{
     ctx::SpanContext,
     span::{MetaValue, Span, SpanExt, SpanRecorder},
@@ -43,10 +44,14 @@ use crate::exec::{
     stringset::{IntoStringSet, StringSetRef},
 };
 
-use crate::plan::{
-    fieldlist::FieldListPlan,
-    seriesset::{SeriesSetPlan, SeriesSetPlans},
-    stringset::StringSetPlan,
+use crate::{
+    physical_optimizer::AggregatePushdown,
+    plan::{
+        fieldlist::FieldListPlan,
+        seriesset::{SeriesSetPlan, SeriesSetPlans},
+        stringset::StringSetPlan,
+    },
+    QueryCompleteness, QueryId, QueryMemoryReservation, QueryPruningStats, QuerySnapshot,
 };
 
 // Reuse DataFusion error and Result types for this module
@@ -168,6 +173,10 @@ pub struct IOxSessionConfig {
 
     /// Span context from which to create spans for this query
     span_ctx: Option<SpanContext>,
+
+    /// Answer aggregations from row group statistics, see
+    /// [`with_aggregate_pushdown`](Self::with_aggregate_pushdown).
+    aggregate_pushdown: bool,
 }
 
 impl fmt::Debug for IOxSessionConfig {
@@ -176,6 +185,12 @@ impl fmt::Debug for IOxSessionConfig {
     }
 }
 
+/// Deduplication settings of a session, see [`IOxSessionConfig::with_external_dedup_min_chunks`].
+#[derive(Debug, Clone, Copy)]
+struct ExternalDedupConfig {
+    min_chunks: usize,
+}
+
 const BATCH_SIZE: usize = 8 * 1024;
 const COALESCE_BATCH_SIZE: usize = BATCH_SIZE / 2;
 
@@ -198,6 +213,7 @@ impl IOxSessionConfig {
             runtime,
             default_catalog: None,
             span_ctx: None,
+            aggregate_pushdown: false,
         }
     }
 
@@ -209,6 +225,29 @@ impl IOxSessionConfig {
         self
     }
 
+    /// Deduplicate sets of at least `min_chunks` overlapping chunks with a single sort instead of
+    /// merging individually sorted chunks.
+    ///
+    /// The single sort can spill to disk if the executor limits its memory, so the memory used for
+    /// deduplication no longer grows with the number of overlapping chunks. `None` disables this.
+    pub fn with_external_dedup_min_chunks(mut self, min_chunks: Option<usize>) -> Self {
+        if let Some(min_chunks) = min_chunks {
+            self.session_config = self
+                .session_config
+                .with_extension(Arc::new(ExternalDedupConfig { min_chunks }));
+        }
+        self
+    }
+
+    /// Answer time-bucketed `min`/`max`/`count` aggregations from the row group statistics of
+    /// the chunks instead of scanning them where possible, see [`AggregatePushdown`].
+    pub fn with_aggregate_pushdown(self, aggregate_pushdown: bool) -> Self {
+        Self {
+            aggregate_pushdown,
+            ..self
+        }
+    }
+
     /// Set the default catalog provider
     pub fn with_default_catalog(self, catalog: Arc<dyn CatalogProvider>) -> Self {
         Self {
@@ -224,8 +263,19 @@ impl IOxSessionConfig {
 
     /// Create an ExecutionContext suitable for executing DataFusion plans
     pub fn build(self) -> IOxSessionContext {
-        let state = SessionState::with_config_rt(self.session_config, self.runtime)
+        let mut state = SessionState::with_config_rt(self.session_config, self.runtime)
             .with_query_planner(Arc::new(IOxQueryPlanner {}));
+        if self.aggregate_pushdown {
+            state = state.add_physical_optimizer_rule(Arc::new(AggregatePushdown::new()));
+        }
+
+        // make the IOx aggregate functions available to SQL
+        for name in [APPROX_COUNT_DISTINCT_UDAF_NAME, APPROX_PERCENTILE_UDAF_NAME] {
+            let udaf = query_functions::registry()
+                .udaf(name)
+                .expect("IOx aggregate function registered");
+            state.aggregate_functions.insert(name.to_string(), udaf);
+        }
 
         let inner = SessionContext::with_state(state);
 
@@ -264,12 +314,16 @@ pub struct IOxSessionContext {
 
     /// Span context from which to create spans for this query
     recorder: SpanRecorder,
+
+    /// ID of the query this context was created for, if any.
+    query_id: Option<QueryId>,
 }
 
 impl fmt::Debug for IOxSessionContext {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("IOxSessionContext")
             .field("inner", &"<DataFusion ExecutionContext>")
+            .field("query_id", &self.query_id)
             .finish()
     }
 }
@@ -284,6 +338,7 @@ impl IOxSessionContext {
             inner: SessionContext::default(),
             exec: None,
             recorder: SpanRecorder::default(),
+            query_id: None,
         }
     }
 
@@ -306,7 +361,105 @@ impl IOxSessionContext {
             inner,
             exec,
             recorder,
+            query_id: None,
+        }
+    }
+
+    /// Assign the ID of the query this context is used for.
+    ///
+    /// The ID is recorded on the span of this context and made available to DataFusion (e.g. table
+    /// providers) via [`SessionContextIOxExt::query_id`]. This also starts tracking the
+    /// [completeness](Self::query_completeness) of the query results and the
+    /// [memory reserved](Self::query_memory_reservation) by the query, the
+    /// [chunks pruned](Self::query_pruning_stats) while planning it and the
+    /// [data snapshot](Self::query_snapshot) it sees.
+    pub fn with_query_id(mut self, query_id: QueryId) -> Self {
+        {
+            let mut state = self.inner.state.write();
+            state.config = state
+                .config
+                .clone()
+                .with_extension(Arc::new(query_id))
+                .with_extension(Arc::new(QueryCompleteness::new()))
+                .with_extension(Arc::new(QueryMemoryReservation::new()))
+                .with_extension(Arc::new(QueryPruningStats::new()))
+                .with_extension(Arc::new(QuerySnapshot::new()));
+        }
+        self.recorder.set_metadata("query_id", query_id.to_string());
+        self.query_id = Some(query_id);
+        self
+    }
+
+    /// ID of the query this context is used for, if any.
+    pub fn query_id(&self) -> Option<QueryId> {
+        self.query_id
+    }
+
+    /// Completeness of the results of the query this context is used for, if any.
+    ///
+    /// See [`SessionContextIOxExt::query_completeness`].
+    pub fn query_completeness(&self) -> Option<Arc<QueryCompleteness>> {
+        self.inner
+            .state
+            .read()
+            .config
+            .get_extension::<QueryCompleteness>()
+    }
+
+    /// Memory reserved by the query this context is used for, if any.
+    ///
+    /// See [`SessionContextIOxExt::query_memory_reservation`].
+    pub fn query_memory_reservation(&self) -> Option<Arc<QueryMemoryReservation>> {
+        self.inner
+            .state
+            .read()
+            .config
+            .get_extension::<QueryMemoryReservation>()
+    }
+
+    /// Chunks pruned while planning the query this context is used for, if any.
+    ///
+    /// See [`SessionContextIOxExt::query_pruning_stats`].
+    pub fn query_pruning_stats(&self) -> Option<Arc<QueryPruningStats>> {
+        self.inner
+            .state
+            .read()
+            .config
+            .get_extension::<QueryPruningStats>()
+    }
+
+    /// Pin the query this context is used for to the data of `snapshot`, see [`QuerySnapshot`].
+    ///
+    /// This must be called after [`with_query_id`](Self::with_query_id).
+    pub fn with_query_snapshot(self, snapshot: QuerySnapshot) -> Self {
+        {
+            let mut state = self.inner.state.write();
+            state.config = state.config.clone().with_extension(Arc::new(snapshot));
         }
+        self
+    }
+
+    /// Snapshot of the data seen by the query this context is used for, if any.
+    ///
+    /// See [`SessionContextIOxExt::query_snapshot`].
+    pub fn query_snapshot(&self) -> Option<Arc<QuerySnapshot>> {
+        self.inner
+            .state
+            .read()
+            .config
+            .get_extension::<QuerySnapshot>()
+    }
+
+    /// Minimum number of overlapping chunks that are deduplicated with a single (spilling) sort.
+    ///
+    /// See [`IOxSessionConfig::with_external_dedup_min_chunks`].
+    pub fn external_dedup_min_chunks(&self) -> Option<usize> {
+        self.inner
+            .state
+            .read()
+            .config
+            .get_extension::<ExternalDedupConfig>()
+            .map(|config| config.min_chunks)
     }
 
     /// returns a reference to the inner datafusion execution context
@@ -318,10 +471,17 @@ impl IOxSessionContext {
     /// tables referenced in the SQL have been registered with this context
     pub async fn prepare_sql(&self, sql: &str) -> Result<Arc<dyn ExecutionPlan>> {
         let ctx = self.child_ctx("prepare_sql");
+        let logical_plan = ctx.sql_to_logical_plan(sql)?;
+        ctx.create_physical_plan(&logical_plan).await
+    }
+
+    /// Plan (but do not optimize) a SQL statement. This assumes that any tables referenced in
+    /// the SQL have been registered with this context
+    pub fn sql_to_logical_plan(&self, sql: &str) -> Result<LogicalPlan> {
         debug!(text=%sql, "planning SQL query");
-        let logical_plan = ctx.inner.create_logical_plan(sql)?;
+        let logical_plan = self.inner.create_logical_plan(sql)?;
         debug!(plan=%logical_plan.display_graphviz(), "logical plan");
-        ctx.create_physical_plan(&logical_plan).await
+        Ok(logical_plan)
     }
 
     /// Prepare (optimize + plan) a pre-created [`LogicalPlan`] for execution
@@ -602,11 +762,13 @@ impl IOxSessionContext {
 
     /// Returns a IOxSessionContext with a SpanRecorder that is a child of the current
     pub fn child_ctx(&self, name: &'static str) -> Self {
-        Self::new(
+        let mut ctx = Self::new(
             self.inner.clone(),
             self.exec.clone(),
             self.recorder.child(name),
-        )
+        );
+        ctx.query_id = self.query_id;
+        ctx
     }
 
     /// Record an event on the span recorder
@@ -637,6 +799,31 @@ pub trait SessionContextIOxExt {
 
     /// Get span context
     fn span_ctx(&self) -> Option<SpanContext>;
+
+    /// Get ID of the query, see [`IOxSessionContext::with_query_id`].
+    fn query_id(&self) -> Option<QueryId>;
+
+    /// Get completeness tracker of the query, see [`IOxSessionContext::with_query_id`].
+    ///
+    /// Data sources that can only provide part of the requested data should mark the query as
+    /// incomplete instead of failing it.
+    fn query_completeness(&self) -> Option<Arc<QueryCompleteness>>;
+
+    /// Get memory reservation holder of the query, see [`IOxSessionContext::with_query_id`].
+    ///
+    /// Reservations attached to it are released once the query is finished.
+    fn query_memory_reservation(&self) -> Option<Arc<QueryMemoryReservation>>;
+
+    /// Get pruning statistics of the query, see [`IOxSessionContext::with_query_id`].
+    ///
+    /// Data sources that prune chunks while planning report them here.
+    fn query_pruning_stats(&self) -> Option<Arc<QueryPruningStats>>;
+
+    /// Get data snapshot of the query, see [`IOxSessionContext::with_query_id`].
+    ///
+    /// Data sources record the partitions they read here and only read the pinned partition
+    /// snapshots if the query is pinned.
+    fn query_snapshot(&self) -> Option<Arc<QuerySnapshot>>;
 }
 
 impl SessionContextIOxExt for SessionState {
@@ -651,4 +838,26 @@ impl SessionContextIOxExt for SessionState {
             .get_extension::<Option<Span>>()
             .and_then(|span| span.as_ref().as_ref().map(|span| span.ctx.clone()))
     }
+
+    fn query_id(&self) -> Option<QueryId> {
+        self.config
+            .get_extension::<QueryId>()
+            .map(|query_id| *query_id)
+    }
+
+    fn query_completeness(&self) -> Option<Arc<QueryCompleteness>> {
+        self.config.get_extension::<QueryCompleteness>()
+    }
+
+    fn query_memory_reservation(&self) -> Option<Arc<QueryMemoryReservation>> {
+        self.config.get_extension::<QueryMemoryReservation>()
+    }
+
+    fn query_pruning_stats(&self) -> Option<Arc<QueryPruningStats>> {
+        self.config.get_extension::<QueryPruningStats>()
+    }
+
+    fn query_snapshot(&self) -> Option<Arc<QuerySnapshot>> {
+        self.config.get_extension::<QuerySnapshot>()
+    }
 }
@@ -0,0 +1,1115 @@
+//! Query frontend for InfluxQL queries.
+//!
+//! A parsed InfluxQL `SELECT` statement is lowered into a DataFusion [`LogicalPlan`] against the
+//! tables registered with the session:
+//!
+//! * the measurement of the `FROM` clause is the table of the same name
+//! * tags are the dictionary encoded tag columns, fields are the field columns
+//! * `time` is always the first column of the result
+//! * `GROUP BY time(interval[, offset])` buckets the rows into time windows, which are labelled
+//!   by their start
+//! * `GROUP BY` tags are returned as columns following `time`, and the result is sorted by them
+//!
+//! Empty time windows are omitted from the result, i.e. only `fill(none)` is supported.
+//! Statements that use features that are not supported yet, such as multiple measurements,
+//! regular expression measurements, `SLIMIT` or non-aggregate functions, are rejected.
+//!
+//! `SHOW MEASUREMENTS` and `SHOW TAG KEYS` statements are answered from the schemas of the
+//! registered tables, without a `WHERE` clause only.
+
+use std::{collections::HashSet, sync::Arc};
+
+use arrow::{
+    array::StringArray,
+    datatypes::{DataType, Field, Schema as ArrowSchema},
+    record_batch::RecordBatch,
+};
+use chrono::Utc;
+use datafusion::{
+    error::{DataFusionError, Result},
+    logical_plan::{
+        lit, lit_timestamp_nano, Column, Expr, LogicalPlan, LogicalPlanBuilder, Operator,
+    },
+    physical_plan::ExecutionPlan,
+    scalar::ScalarValue,
+};
+use influxdb_influxql_parser::{
+    common::{MeasurementName, OrderByClause},
+    expression::{BinaryOperator, Expr as InfluxQLExpr, UnaryOperator, WildcardType},
+    identifier::Identifier,
+    literal::Literal,
+    select::{select_statement, Dimension, FillClause, SelectStatement},
+    show::ShowStatement,
+    show_measurements::{ShowMeasurementsStatement, WithMeasurementClause},
+    show_tag_keys::ShowTagKeysStatement,
+    statement::Statement,
+    time_range::{eval_time, time_range},
+};
+use query_functions::{
+    group_by::{Aggregate, WindowDuration},
+    make_window_bound_expr, regex_match_expr, regex_not_match_expr,
+    selectors::{selector_first, selector_last, SelectorOutput},
+};
+use schema::{InfluxColumnType, Schema, TIME_COLUMN_NAME, TIME_DATA_TYPE};
+
+use crate::{exec::IOxSessionContext, util::make_scan_plan, DEFAULT_CATALOG, DEFAULT_SCHEMA};
+
+/// Column holding the measurement of every row of a `SHOW TAG KEYS` plan.
+const MEASUREMENT_COLUMN_NAME: &str = "iox::measurement";
+
+/// A planned InfluxQL statement.
+///
+/// InfluxQL returns the rows of a statement grouped into series, each of which has a name and
+/// tags. The rows of a series are adjacent in the output of the plan.
+#[derive(Debug)]
+pub struct InfluxQLStatementPlan {
+    /// The physical plan of the statement.
+    pub plan: Arc<dyn ExecutionPlan>,
+
+    /// The name of every series, unless [`name_column`](Self::name_column) is set.
+    pub series_name: String,
+
+    /// The column holding the series name of every row.
+    pub name_column: Option<String>,
+
+    /// The columns holding the series tags of every row.
+    pub tag_columns: Vec<String>,
+}
+
+/// This struct can create plans for running InfluxQL queries against databases
+#[derive(Debug, Default)]
+pub struct InfluxQLQueryPlanner {}
+
+impl InfluxQLQueryPlanner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Plan an InfluxQL query against the tables registered with `ctx`, and return a DataFusion
+    /// physical execution plan that runs on the query executor.
+    pub async fn query(
+        &self,
+        query: &str,
+        ctx: &IOxSessionContext,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let plan = self.logical_plan(query, Utc::now().timestamp_nanos(), ctx)?;
+        ctx.create_physical_plan(&plan).await
+    }
+
+    /// Lower a single InfluxQL `SELECT` statement into a DataFusion logical plan.
+    ///
+    /// `now()` evaluates to `now`, in nanoseconds since the Unix epoch.
+    pub fn logical_plan(
+        &self,
+        query: &str,
+        now: i64,
+        ctx: &IOxSessionContext,
+    ) -> Result<LogicalPlan> {
+        let select = parse_select(query)?;
+        SelectPlanner::try_new(&select, now, ctx)?.plan()
+    }
+
+    /// Plan a single parsed InfluxQL statement against the tables registered with `ctx`.
+    ///
+    /// `SELECT`, `SHOW MEASUREMENTS` and `SHOW TAG KEYS` statements are supported.
+    pub async fn statement(
+        &self,
+        statement: &Statement,
+        ctx: &IOxSessionContext,
+    ) -> Result<InfluxQLStatementPlan> {
+        let (plan, series_name, name_column, tag_columns) = match statement {
+            Statement::Select(select) => {
+                let planner = SelectPlanner::try_new(select, Utc::now().timestamp_nanos(), ctx)?;
+                let (_, tags) = planner.dimensions()?;
+                let plan = planner.plan()?;
+                (plan, planner.measurement.to_string(), None, tags)
+            }
+            Statement::Show(ShowStatement::Measurements(show)) => (
+                show_measurements_plan(show, ctx)?,
+                "measurements".to_string(),
+                None,
+                vec![],
+            ),
+            Statement::Show(ShowStatement::TagKeys(show)) => (
+                show_tag_keys_plan(show, ctx)?,
+                String::new(),
+                Some(MEASUREMENT_COLUMN_NAME.to_string()),
+                vec![],
+            ),
+            Statement::Show(ShowStatement::TagValues(_)) => {
+                return not_implemented("SHOW TAG VALUES")
+            }
+            Statement::Show(ShowStatement::FieldKeys(_)) => {
+                return not_implemented("SHOW FIELD KEYS")
+            }
+            Statement::Delete(_) => return not_implemented("DELETE"),
+            Statement::Drop(_) => return not_implemented("DROP SERIES"),
+            Statement::CreateRetentionPolicy(_) => {
+                return not_implemented("CREATE RETENTION POLICY")
+            }
+            Statement::AlterRetentionPolicy(_) => return not_implemented("ALTER RETENTION POLICY"),
+        };
+
+        Ok(InfluxQLStatementPlan {
+            plan: ctx.create_physical_plan(&plan).await?,
+            series_name,
+            name_column,
+            tag_columns,
+        })
+    }
+}
+
+/// Plan a `SHOW MEASUREMENTS` statement, which returns the measurement names in the `name`
+/// column.
+fn show_measurements_plan(
+    show: &ShowMeasurementsStatement,
+    ctx: &IOxSessionContext,
+) -> Result<LogicalPlan> {
+    if show.condition.is_some() {
+        return not_implemented("SHOW MEASUREMENTS with WHERE clause");
+    }
+
+    let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+        "name",
+        DataType::Utf8,
+        false,
+    )]));
+    let names = StringArray::from(measurement_names(ctx)?);
+    let batch = RecordBatch::try_new(schema, vec![Arc::new(names)])?;
+
+    let mut builder = LogicalPlanBuilder::from(make_scan_plan(batch)?);
+    match &show.with_measurement {
+        Some(WithMeasurementClause::Eq(name)) => {
+            builder = builder.filter(column("name").eq(lit(ident_name(name))))?;
+        }
+        Some(WithMeasurementClause::EqRegex(regex)) => {
+            builder =
+                builder.filter(regex_match_expr(column("name"), regex.as_str().to_string()))?;
+        }
+        None => {}
+    }
+    builder = builder.sort(vec![sort(column("name"), true)])?;
+
+    if show.limit.is_some() || show.offset.is_some() {
+        let skip = show.offset.unwrap_or(0) as usize;
+        let fetch = show.limit.map(|limit| limit as usize);
+        builder = builder.limit(skip, fetch)?;
+    }
+
+    builder.build()
+}
+
+/// Plan a `SHOW TAG KEYS` statement, which returns the tag keys in the `tagKey` column and their
+/// measurement in the [`MEASUREMENT_COLUMN_NAME`] column.
+fn show_tag_keys_plan(show: &ShowTagKeysStatement, ctx: &IOxSessionContext) -> Result<LogicalPlan> {
+    if show.condition.is_some() {
+        return not_implemented("SHOW TAG KEYS with WHERE clause");
+    }
+
+    // LIMIT and OFFSET apply to the tag keys of every measurement
+    let mut measurements = vec![];
+    let mut tag_keys = vec![];
+    for measurement in measurement_names(ctx)? {
+        let (_, schema) = measurement_scan(&measurement, ctx)?;
+        let mut keys = schema
+            .tags_iter()
+            .map(|f| f.name().clone())
+            .collect::<Vec<_>>();
+        keys.sort();
+
+        let keys = keys
+            .into_iter()
+            .skip(show.offset.unwrap_or(0) as usize)
+            .take(show.limit.map_or(usize::MAX, |limit| limit as usize));
+        for key in keys {
+            measurements.push(measurement.clone());
+            tag_keys.push(key);
+        }
+    }
+
+    let schema = Arc::new(ArrowSchema::new(vec![
+        Field::new(MEASUREMENT_COLUMN_NAME, DataType::Utf8, false),
+        Field::new("tagKey", DataType::Utf8, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema,
+        vec![
+            Arc::new(StringArray::from(measurements)),
+            Arc::new(StringArray::from(tag_keys)),
+        ],
+    )?;
+
+    let mut builder = LogicalPlanBuilder::from(make_scan_plan(batch)?);
+    if let Some(from) = &show.from {
+        // The database and retention policy are ignored, as the session is bound to a
+        // namespace.
+        let filter = from
+            .iter()
+            .map(|measurement| match &measurement.name {
+                MeasurementName::Name(name) => {
+                    column(MEASUREMENT_COLUMN_NAME).eq(lit(ident_name(name)))
+                }
+                MeasurementName::Regex(regex) => {
+                    regex_match_expr(column(MEASUREMENT_COLUMN_NAME), regex.as_str().to_string())
+                }
+            })
+            .reduce(Expr::or)
+            .expect("FROM clause has at least one measurement");
+        builder = builder.filter(filter)?;
+    }
+
+    builder
+        .sort(vec![
+            sort(column(MEASUREMENT_COLUMN_NAME), true),
+            sort(column("tagKey"), true),
+        ])?
+        .build()
+}
+
+/// The names of the tables registered with `ctx`, in ascending order.
+fn measurement_names(ctx: &IOxSessionContext) -> Result<Vec<String>> {
+    let schema = ctx
+        .inner()
+        .catalog(DEFAULT_CATALOG)
+        .and_then(|catalog| catalog.schema(DEFAULT_SCHEMA))
+        .ok_or_else(|| {
+            DataFusionError::Plan(format!(
+                "schema {}.{} not found",
+                DEFAULT_CATALOG, DEFAULT_SCHEMA
+            ))
+        })?;
+
+    let mut names = schema.table_names();
+    names.sort();
+    Ok(names)
+}
+
+/// The scan of the table `measurement` and its IOx schema.
+fn measurement_scan(measurement: &str, ctx: &IOxSessionContext) -> Result<(LogicalPlan, Schema)> {
+    let scan = ctx.inner().table(measurement)?.to_logical_plan()?;
+    let arrow_schema: ArrowSchema = scan.schema().as_ref().into();
+    let schema = Schema::try_from(Arc::new(arrow_schema)).map_err(|e| {
+        DataFusionError::Plan(format!(
+            "invalid schema of measurement {}: {}",
+            measurement, e
+        ))
+    })?;
+
+    Ok((scan, schema))
+}
+
+/// Parse `query`, which must be a single `SELECT` statement.
+fn parse_select(query: &str) -> Result<SelectStatement> {
+    let (rem, select) = select_statement(query)
+        .map_err(|e| DataFusionError::Plan(format!("invalid InfluxQL SELECT statement: {}", e)))?;
+
+    let rem = rem.trim_start();
+    let rem = rem.strip_prefix(';').unwrap_or(rem).trim();
+    if !rem.is_empty() {
+        return Err(DataFusionError::Plan(format!(
+            "unexpected input after SELECT statement: '{}'",
+            rem
+        )));
+    }
+
+    Ok(select)
+}
+
+/// Aggregates of an aggregate query, named by their InfluxQL representation.
+#[derive(Debug, Default)]
+struct Aggregates {
+    exprs: Vec<Expr>,
+    names: HashSet<String>,
+}
+
+/// Lowers a single `SELECT` statement.
+#[derive(Debug)]
+struct SelectPlanner<'a> {
+    select: &'a SelectStatement,
+    measurement: &'a str,
+    now: i64,
+    scan: LogicalPlan,
+    schema: Schema,
+}
+
+impl<'a> SelectPlanner<'a> {
+    fn try_new(select: &'a SelectStatement, now: i64, ctx: &IOxSessionContext) -> Result<Self> {
+        // The database and retention policy are ignored, as the session is bound to a
+        // namespace.
+        let measurement = match select.from.as_slice() {
+            [measurement] => measurement,
+            _ => return not_implemented("SELECT from multiple measurements"),
+        };
+        let table_name = match &measurement.name {
+            MeasurementName::Name(name) => ident_name(name),
+            MeasurementName::Regex(_) => return not_implemented("regular expression measurements"),
+        };
+
+        let (scan, schema) = measurement_scan(table_name, ctx)?;
+
+        Ok(Self {
+            select,
+            measurement: table_name,
+            now,
+            scan,
+            schema,
+        })
+    }
+
+    /// Create the plan, which looks like:
+    ///
+    /// ```text
+    ///   Limit [optional]
+    ///     Sort(tags, time)
+    ///       Projection(time, tags, fields)
+    ///         Aggregate(gby: tags, window start; agg: aggregates) [optional]
+    ///           Filter(condition) [optional]
+    ///             Scan
+    /// ```
+    fn plan(&self) -> Result<LogicalPlan> {
+        let select = self.select;
+
+        if select.series_limit.is_some() || select.series_offset.is_some() {
+            return not_implemented("SLIMIT and SOFFSET");
+        }
+        if !matches!(select.fill, None | Some(FillClause::None)) {
+            return not_implemented("FILL other than fill(none)");
+        }
+
+        let (window, tags) = self.dimensions()?;
+
+        let mut builder = LogicalPlanBuilder::from(self.scan.clone());
+        if let Some(condition) = &select.condition {
+            builder = builder.filter(self.condition(condition)?)?;
+        }
+
+        let is_aggregate = select.fields.iter().any(|f| contains_call(&f.expr));
+        if window.is_some() && !is_aggregate {
+            return Err(DataFusionError::Plan(
+                "GROUP BY time() requires an aggregate function".to_string(),
+            ));
+        }
+
+        let mut aggregates = is_aggregate.then(Aggregates::default);
+        let fields = self.fields(&tags, aggregates.as_mut())?;
+
+        let time = match (aggregates, window) {
+            (None, _) => column(TIME_COLUMN_NAME),
+            (Some(aggregates), Some((interval, offset))) => {
+                let group_exprs = tags
+                    .iter()
+                    .map(|tag| column(tag))
+                    .chain(std::iter::once(
+                        window_start(interval, offset).alias(TIME_COLUMN_NAME),
+                    ))
+                    .collect::<Vec<_>>();
+                builder = builder.aggregate(group_exprs, aggregates.exprs)?;
+                column(TIME_COLUMN_NAME)
+            }
+            (Some(aggregates), None) => {
+                let group_exprs = tags.iter().map(|tag| column(tag)).collect::<Vec<_>>();
+                builder = builder.aggregate(group_exprs, aggregates.exprs)?;
+
+                // a single aggregate over the queried time range is labelled by its start
+                let range = match &select.condition {
+                    Some(condition) => time_range(condition, self.now)
+                        .map_err(|e| DataFusionError::Plan(e.to_string()))?,
+                    None => Default::default(),
+                };
+                lit_timestamp_nano(range.min.unwrap_or(0)).alias(TIME_COLUMN_NAME)
+            }
+        };
+
+        let projection = std::iter::once(time)
+            .chain(tags.iter().map(|tag| column(tag)))
+            .chain(fields)
+            .collect::<Vec<_>>();
+        builder = builder.project(projection)?;
+
+        let ascending = !matches!(select.order_by, Some(OrderByClause::Descending));
+        let sort_exprs = tags
+            .iter()
+            .map(|tag| sort(column(tag), true))
+            .chain(std::iter::once(sort(column(TIME_COLUMN_NAME), ascending)))
+            .collect::<Vec<_>>();
+        builder = builder.sort(sort_exprs)?;
+
+        if select.limit.is_some() || select.offset.is_some() {
+            // InfluxQL limits the rows per series
+            if !tags.is_empty() {
+                return not_implemented("LIMIT and OFFSET with GROUP BY tags");
+            }
+
+            let skip = select.offset.unwrap_or(0) as usize;
+            let fetch = select.limit.map(|limit| limit as usize);
+            builder = builder.limit(skip, fetch)?;
+        }
+
+        builder.build()
+    }
+
+    /// Returns the time window as `(interval, offset)` in nanoseconds, if any, and the tags of
+    /// the `GROUP BY` clause.
+    #[allow(clippy::type_complexity)]
+    fn dimensions(&self) -> Result<(Option<(i64, i64)>, Vec<String>)> {
+        let mut window = None;
+        let mut tags = vec![];
+
+        for dimension in self.select.group_by.iter().flatten() {
+            match dimension {
+                Dimension::Time { interval, offset } => {
+                    if window.is_some() {
+                        return Err(DataFusionError::Plan(
+                            "multiple GROUP BY time() dimensions".to_string(),
+                        ));
+                    }
+
+                    let interval = duration(interval)?;
+                    if interval <= 0 {
+                        return Err(DataFusionError::Plan(
+                            "GROUP BY time() interval must be positive".to_string(),
+                        ));
+                    }
+                    let offset = offset.as_ref().map(duration).transpose()?.unwrap_or(0);
+                    window = Some((interval, offset));
+                }
+                Dimension::Tag(tag) => tags.push(ident_name(tag).to_string()),
+                Dimension::Wildcard => {
+                    tags.extend(self.schema.tags_iter().map(|f| f.name().clone()));
+                }
+                Dimension::Regex(_) => return not_implemented("GROUP BY regular expression"),
+            }
+        }
+
+        // InfluxQL sorts the series by tag name
+        tags.sort();
+        tags.dedup();
+
+        Ok((window, tags))
+    }
+
+    /// Lower the projections, aliased by their name in the result.
+    ///
+    /// The aggregates of an aggregate query are added to `aggregates`.
+    fn fields(
+        &self,
+        tags: &[String],
+        mut aggregates: Option<&mut Aggregates>,
+    ) -> Result<Vec<Expr>> {
+        let mut names = HashSet::new();
+        let mut exprs = vec![];
+
+        for field in &self.select.fields {
+            if is_time(&field.expr) {
+                // time is always the first column
+                continue;
+            }
+
+            if let InfluxQLExpr::Wildcard(typ) = &field.expr {
+                if aggregates.is_some() {
+                    return not_implemented("wildcards in aggregate queries");
+                }
+
+                let mut columns = self
+                    .schema
+                    .iter()
+                    .filter(|(column_type, f)| {
+                        let selected = match (typ, column_type) {
+                            (None, Some(InfluxColumnType::Tag | InfluxColumnType::Field(_))) => {
+                                true
+                            }
+                            (Some(WildcardType::Tag), Some(InfluxColumnType::Tag)) => true,
+                            (Some(WildcardType::Field), Some(InfluxColumnType::Field(_))) => true,
+                            _ => false,
+                        };
+                        selected && !tags.contains(f.name())
+                    })
+                    .map(|(_, f)| f.name().clone())
+                    .collect::<Vec<_>>();
+                columns.sort();
+
+                for name in columns {
+                    let alias = unique_name(&mut names, name.clone());
+                    exprs.push(column(&name).alias(&alias));
+                }
+                continue;
+            }
+
+            let expr = self.expr(&field.expr, aggregates.as_deref_mut())?;
+            let name = match &field.alias {
+                Some(alias) => ident_name(alias).to_string(),
+                None => field_name(&field.expr),
+            };
+            let alias = unique_name(&mut names, name);
+            exprs.push(expr.alias(&alias));
+        }
+
+        if exprs.is_empty() {
+            return Err(DataFusionError::Plan(
+                "at least one non-time field must be queried".to_string(),
+            ));
+        }
+
+        Ok(exprs)
+    }
+
+    /// Lower the `WHERE` clause.
+    fn condition(&self, expr: &InfluxQLExpr) -> Result<Expr> {
+        match expr {
+            InfluxQLExpr::Nested(e) => self.condition(e),
+            InfluxQLExpr::BinaryOp {
+                lhs,
+                op: BinaryOperator::And,
+                rhs,
+            } => Ok(self.condition(lhs)?.and(self.condition(rhs)?)),
+            InfluxQLExpr::BinaryOp {
+                lhs,
+                op: BinaryOperator::Or,
+                rhs,
+            } => Ok(self.condition(lhs)?.or(self.condition(rhs)?)),
+            InfluxQLExpr::BinaryOp { lhs, op, rhs } if is_time(lhs) || is_time(rhs) => {
+                let (op, other) = if is_time(lhs) {
+                    (*op, rhs)
+                } else {
+                    (flip(*op)?, lhs)
+                };
+                let t =
+                    eval_time(other, self.now).map_err(|e| DataFusionError::Plan(e.to_string()))?;
+
+                Ok(binary_expr(
+                    column(TIME_COLUMN_NAME),
+                    comparison_operator(op)?,
+                    lit_timestamp_nano(t),
+                ))
+            }
+            InfluxQLExpr::BinaryOp {
+                lhs,
+                op: op @ (BinaryOperator::EqRegex | BinaryOperator::NotEqRegex),
+                rhs,
+            } => {
+                let pattern = match rhs.as_ref() {
+                    InfluxQLExpr::Literal(Literal::Regex(re)) => re.as_str().to_string(),
+                    _ => {
+                        return Err(DataFusionError::Plan(format!(
+                            "expected regular expression: {}",
+                            rhs
+                        )))
+                    }
+                };
+                let input = self.expr(lhs, None)?;
+
+                Ok(match op {
+                    BinaryOperator::EqRegex => regex_match_expr(input, pattern),
+                    _ => regex_not_match_expr(input, pattern),
+                })
+            }
+            InfluxQLExpr::BinaryOp { lhs, op, rhs } => Ok(binary_expr(
+                self.expr(lhs, None)?,
+                comparison_operator(*op)?,
+                self.expr(rhs, None)?,
+            )),
+            _ => Err(DataFusionError::Plan(format!(
+                "unsupported condition: {}",
+                expr
+            ))),
+        }
+    }
+
+    /// Lower a scalar expression.
+    ///
+    /// If `aggregates` is given, columns may only be referenced by aggregate functions, which
+    /// are added to `aggregates` and replaced by a reference to their result.
+    fn expr(&self, expr: &InfluxQLExpr, mut aggregates: Option<&mut Aggregates>) -> Result<Expr> {
+        match expr {
+            InfluxQLExpr::Identifier(name) | InfluxQLExpr::VarRef { name, .. } => {
+                if aggregates.is_some() {
+                    return Err(DataFusionError::Plan(
+                        "mixing aggregate and non-aggregate queries is not supported".to_string(),
+                    ));
+                }
+
+                let name = ident_name(name);
+                Ok(match self.schema.find_index_of(name) {
+                    Some(_) => column(name),
+                    // unknown columns are null
+                    None => lit(ScalarValue::Null),
+                })
+            }
+            InfluxQLExpr::Literal(v) => literal(v),
+            InfluxQLExpr::Nested(e) => self.expr(e, aggregates),
+            InfluxQLExpr::UnaryOp(UnaryOperator::Plus, e) => self.expr(e, aggregates),
+            InfluxQLExpr::UnaryOp(UnaryOperator::Minus, e) => {
+                Ok(Expr::Negative(Box::new(self.expr(e, aggregates)?)))
+            }
+            InfluxQLExpr::BinaryOp { lhs, op, rhs } => {
+                let op = match op {
+                    BinaryOperator::Add => Operator::Plus,
+                    BinaryOperator::Sub => Operator::Minus,
+                    BinaryOperator::Mul => Operator::Multiply,
+                    BinaryOperator::Div => Operator::Divide,
+                    BinaryOperator::Mod => Operator::Modulo,
+                    _ => return not_implemented(format!("operator {}", op)),
+                };
+                let lhs = self.expr(lhs, aggregates.as_deref_mut())?;
+                let rhs = self.expr(rhs, aggregates)?;
+                Ok(binary_expr(lhs, op, rhs))
+            }
+            InfluxQLExpr::Call { name, args } => match aggregates {
+                Some(aggregates) => {
+                    let agg_name = expr.to_string();
+                    if aggregates.names.insert(agg_name.clone()) {
+                        aggregates
+                            .exprs
+                            .push(self.aggregate(name, args)?.alias(&agg_name));
+                    }
+                    Ok(column(&agg_name))
+                }
+                None => not_implemented(format!("function {}()", name)),
+            },
+            InfluxQLExpr::Wildcard(_) => Err(DataFusionError::Plan(
+                "wildcards must be queried on their own".to_string(),
+            )),
+            InfluxQLExpr::Distinct(_) => not_implemented("DISTINCT"),
+            InfluxQLExpr::BindParameter(_) => not_implemented("bind parameters"),
+        }
+    }
+
+    /// Lower an aggregate function.
+    fn aggregate(&self, name: &str, args: &[InfluxQLExpr]) -> Result<Expr> {
+        let field = match args {
+            [InfluxQLExpr::Identifier(field) | InfluxQLExpr::VarRef { name: field, .. }] => {
+                ident_name(field)
+            }
+            _ => {
+                return Err(DataFusionError::Plan(format!(
+                    "expected a single field argument to {}()",
+                    name
+                )))
+            }
+        };
+        let data_type = match self
+            .schema
+            .find_index_of(field)
+            .map(|i| self.schema.field(i))
+        {
+            Some((Some(InfluxColumnType::Field(_)), f)) => f.data_type(),
+            _ => {
+                return Err(DataFusionError::Plan(format!(
+                    "{} is not a field of the measurement",
+                    field
+                )))
+            }
+        };
+        let input = column(field);
+
+        let agg = match name.to_ascii_lowercase().as_str() {
+            "count" => Aggregate::Count,
+            "sum" => Aggregate::Sum,
+            "mean" => Aggregate::Mean,
+            "min" => Aggregate::Min,
+            "max" => Aggregate::Max,
+            "first" => {
+                return Ok(selector_first(data_type, SelectorOutput::Value)
+                    .call(vec![input, column(TIME_COLUMN_NAME)]))
+            }
+            "last" => {
+                return Ok(selector_last(data_type, SelectorOutput::Value)
+                    .call(vec![input, column(TIME_COLUMN_NAME)]))
+            }
+            _ => return not_implemented(format!("function {}()", name)),
+        };
+
+        agg.to_datafusion_expr(input)
+            .map_err(|e| DataFusionError::Plan(e.to_string()))
+    }
+}
+
+fn not_implemented<T>(feature: impl Into<String>) -> Result<T> {
+    Err(DataFusionError::NotImplemented(format!(
+        "{} in InfluxQL",
+        feature.into()
+    )))
+}
+
+fn ident_name(ident: &Identifier) -> &str {
+    match ident {
+        Identifier::Unquoted(s) | Identifier::Quoted(s) => s,
+    }
+}
+
+/// Reference a column by its unqualified name, which may contain dots.
+fn column(name: &str) -> Expr {
+    Expr::Column(Column::from_name(name))
+}
+
+fn binary_expr(left: Expr, op: Operator, right: Expr) -> Expr {
+    Expr::BinaryExpr {
+        left: Box::new(left),
+        op,
+        right: Box::new(right),
+    }
+}
+
+fn sort(expr: Expr, asc: bool) -> Expr {
+    Expr::Sort {
+        expr: Box::new(expr),
+        asc,
+        nulls_first: true,
+    }
+}
+
+/// The start of the time window of each row.
+fn window_start(interval: i64, offset: i64) -> Expr {
+    // window bounds are labelled by their end, InfluxQL labels them by their start
+    let stop = make_window_bound_expr(
+        column(TIME_COLUMN_NAME),
+        WindowDuration::from_nanoseconds(interval),
+        WindowDuration::from_nanoseconds(offset),
+    );
+    let start = binary_expr(
+        Expr::Cast {
+            expr: Box::new(stop),
+            data_type: DataType::Int64,
+        },
+        Operator::Minus,
+        lit(interval),
+    );
+
+    Expr::Cast {
+        expr: Box::new(start),
+        data_type: TIME_DATA_TYPE(),
+    }
+}
+
+/// Returns `true` if `expr` refers to the `time` column.
+fn is_time(expr: &InfluxQLExpr) -> bool {
+    matches!(
+        expr,
+        InfluxQLExpr::Identifier(ident) if ident_name(ident).eq_ignore_ascii_case(TIME_COLUMN_NAME)
+    )
+}
+
+/// Returns `true` if `expr` contains a function call.
+fn contains_call(expr: &InfluxQLExpr) -> bool {
+    match expr {
+        InfluxQLExpr::Call { .. } => true,
+        InfluxQLExpr::Nested(e) | InfluxQLExpr::UnaryOp(_, e) => contains_call(e),
+        InfluxQLExpr::BinaryOp { lhs, rhs, .. } => contains_call(lhs) || contains_call(rhs),
+        _ => false,
+    }
+}
+
+/// The name of a projection without alias in the result.
+fn field_name(expr: &InfluxQLExpr) -> String {
+    match expr {
+        InfluxQLExpr::Identifier(name) | InfluxQLExpr::VarRef { name, .. } => {
+            ident_name(name).to_string()
+        }
+        InfluxQLExpr::Call { name, .. } => name.to_string(),
+        InfluxQLExpr::Nested(e) | InfluxQLExpr::UnaryOp(_, e) => field_name(e),
+        InfluxQLExpr::BinaryOp { lhs, rhs, .. } => {
+            format!("{}_{}", field_name(lhs), field_name(rhs))
+        }
+        _ => expr.to_string(),
+    }
+}
+
+/// Make `name` unique by adding a numeric suffix, like InfluxQL does for duplicate names.
+fn unique_name(names: &mut HashSet<String>, name: String) -> String {
+    let mut unique = name.clone();
+    let mut n = 1;
+    while !names.insert(unique.clone()) {
+        unique = format!("{}_{}", name, n);
+        n += 1;
+    }
+    unique
+}
+
+/// The value of a duration literal in nanoseconds.
+fn duration(expr: &InfluxQLExpr) -> Result<i64> {
+    match expr {
+        InfluxQLExpr::Literal(Literal::Duration(v)) => Ok(v.as_nanos()),
+        _ => Err(DataFusionError::Plan(format!(
+            "expected duration literal: {}",
+            expr
+        ))),
+    }
+}
+
+fn literal(v: &Literal) -> Result<Expr> {
+    Ok(match v {
+        Literal::Unsigned(v) => match i64::try_from(*v) {
+            Ok(v) => lit(v),
+            Err(_) => lit(*v),
+        },
+        Literal::Float(v) => lit(*v),
+        Literal::String(v) => lit(v.clone()),
+        Literal::Boolean(v) => lit(*v),
+        Literal::Duration(v) => lit(v.as_nanos()),
+        Literal::Regex(_) => {
+            return Err(DataFusionError::Plan(format!(
+                "unexpected regular expression: {}",
+                v
+            )))
+        }
+    })
+}
+
+fn comparison_operator(op: BinaryOperator) -> Result<Operator> {
+    Ok(match op {
+        BinaryOperator::Eq => Operator::Eq,
+        BinaryOperator::NotEq => Operator::NotEq,
+        BinaryOperator::Lt => Operator::Lt,
+        BinaryOperator::LtEq => Operator::LtEq,
+        BinaryOperator::Gt => Operator::Gt,
+        BinaryOperator::GtEq => Operator::GtEq,
+        _ => return not_implemented(format!("operator {} in conditions", op)),
+    })
+}
+
+/// Flip a comparison operator so that its operands can be swapped.
+fn flip(op: BinaryOperator) -> Result<BinaryOperator> {
+    Ok(match op {
+        BinaryOperator::Lt => BinaryOperator::Gt,
+        BinaryOperator::LtEq => BinaryOperator::GtEq,
+        BinaryOperator::Gt => BinaryOperator::Lt,
+        BinaryOperator::GtEq => BinaryOperator::LtEq,
+        BinaryOperator::Eq | BinaryOperator::NotEq => op,
+        _ => return not_implemented(format!("operator {} on time", op)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::record_batch::RecordBatch;
+    use arrow_util::assert_batches_eq;
+
+    use super::*;
+    use crate::{provider::ProviderBuilder, test::TestChunk, QueryChunk};
+
+    /// A context with the table `h2o`:
+    ///
+    /// ```text
+    /// +------+-----------+-----------------------------+
+    /// | tag1 | field_int | time                        |
+    /// +------+-----------+-----------------------------+
+    /// | WA   | 1000      | 1970-01-01T00:00:00.000008Z |
+    /// | VT   | 10        | 1970-01-01T00:00:00.000010Z |
+    /// | UT   | 70        | 1970-01-01T00:00:00.000020Z |
+    /// +------+-----------+-----------------------------+
+    /// ```
+    fn ctx() -> IOxSessionContext {
+        let chunk = Arc::new(
+            TestChunk::new("h2o")
+                .with_time_column()
+                .with_tag_column("tag1")
+                .with_i64_field_column("field_int")
+                .with_three_rows_of_data(),
+        ) as Arc<dyn QueryChunk>;
+
+        let ctx = IOxSessionContext::with_testing();
+        let provider = ProviderBuilder::new("h2o", chunk.schema(), ctx.child_ctx("provider"))
+            .add_chunk(chunk)
+            .build()
+            .unwrap();
+        ctx.inner()
+            .register_table("h2o", Arc::new(provider))
+            .unwrap();
+        ctx
+    }
+
+    async fn run(query: &str) -> Vec<RecordBatch> {
+        let ctx = ctx();
+        let plan = InfluxQLQueryPlanner::new()
+            .query(query, &ctx)
+            .await
+            .unwrap();
+        ctx.collect(plan).await.unwrap()
+    }
+
+    async fn run_statement(statement: &str) -> (InfluxQLStatementPlan, Vec<RecordBatch>) {
+        let ctx = ctx();
+        let (_, statement) = influxdb_influxql_parser::statement::statement(statement).unwrap();
+        let plan = InfluxQLQueryPlanner::new()
+            .statement(&statement, &ctx)
+            .await
+            .unwrap();
+        let batches = ctx.collect(Arc::clone(&plan.plan)).await.unwrap();
+        (plan, batches)
+    }
+
+    fn plan_err(query: &str) -> String {
+        InfluxQLQueryPlanner::new()
+            .logical_plan(query, 0, &ctx())
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_raw() {
+        let got = run("SELECT field_int FROM h2o WHERE tag1 = 'VT' OR field_int > 100").await;
+        assert_batches_eq!(
+            [
+                "+-----------------------------+-----------+",
+                "| time                        | field_int |",
+                "+-----------------------------+-----------+",
+                "| 1970-01-01T00:00:00.000008Z | 1000      |",
+                "| 1970-01-01T00:00:00.000010Z | 10        |",
+                "+-----------------------------+-----------+",
+            ],
+            &got
+        );
+
+        let got =
+            run("SELECT * FROM h2o WHERE time >= 10us AND tag1 =~ /T$/ ORDER BY time DESC").await;
+        assert_batches_eq!(
+            [
+                "+-----------------------------+-----------+------+",
+                "| time                        | field_int | tag1 |",
+                "+-----------------------------+-----------+------+",
+                "| 1970-01-01T00:00:00.000020Z | 70        | UT   |",
+                "| 1970-01-01T00:00:00.000010Z | 10        | VT   |",
+                "+-----------------------------+-----------+------+",
+            ],
+            &got
+        );
+
+        let got = run("SELECT field_int * 2 AS double, foo FROM h2o LIMIT 1 OFFSET 1;").await;
+        assert_batches_eq!(
+            [
+                "+-----------------------------+--------+-----+",
+                "| time                        | double | foo |",
+                "+-----------------------------+--------+-----+",
+                "| 1970-01-01T00:00:00.000010Z | 20     |     |",
+                "+-----------------------------+--------+-----+",
+            ],
+            &got
+        );
+    }
+
+    #[tokio::test]
+    async fn test_aggregate() {
+        let got =
+            run("SELECT sum(field_int), count(field_int) AS n FROM h2o GROUP BY time(10us)").await;
+        assert_batches_eq!(
+            [
+                "+-----------------------------+------+---+",
+                "| time                        | sum  | n |",
+                "+-----------------------------+------+---+",
+                "| 1970-01-01T00:00:00Z        | 1000 | 1 |",
+                "| 1970-01-01T00:00:00.000010Z | 10   | 1 |",
+                "| 1970-01-01T00:00:00.000020Z | 70   | 1 |",
+                "+-----------------------------+------+---+",
+            ],
+            &got
+        );
+
+        let got =
+            run("SELECT max(field_int) - min(field_int) AS spread FROM h2o WHERE time >= 5us")
+                .await;
+        assert_batches_eq!(
+            [
+                "+-----------------------------+--------+",
+                "| time                        | spread |",
+                "+-----------------------------+--------+",
+                "| 1970-01-01T00:00:00.000005Z | 990    |",
+                "+-----------------------------+--------+",
+            ],
+            &got
+        );
+
+        let got = run("SELECT last(field_int) FROM h2o GROUP BY tag1").await;
+        assert_batches_eq!(
+            [
+                "+----------------------+------+------+",
+                "| time                 | tag1 | last |",
+                "+----------------------+------+------+",
+                "| 1970-01-01T00:00:00Z | UT   | 70   |",
+                "| 1970-01-01T00:00:00Z | VT   | 10   |",
+                "| 1970-01-01T00:00:00Z | WA   | 1000 |",
+                "+----------------------+------+------+",
+            ],
+            &got
+        );
+    }
+
+    #[tokio::test]
+    async fn test_statement() {
+        let (plan, got) = run_statement("SELECT max(field_int) FROM h2o GROUP BY tag1").await;
+        assert_eq!(plan.series_name, "h2o");
+        assert_eq!(plan.name_column, None);
+        assert_eq!(plan.tag_columns, vec!["tag1".to_string()]);
+        assert_eq!(got.iter().map(|b| b.num_rows()).sum::<usize>(), 3);
+
+        let (plan, got) = run_statement("SHOW MEASUREMENTS").await;
+        assert_eq!(plan.series_name, "measurements");
+        assert_batches_eq!(
+            ["+------+", "| name |", "+------+", "| h2o  |", "+------+"],
+            &got
+        );
+
+        let (_, got) = run_statement("SHOW MEASUREMENTS WITH MEASUREMENT =~ /^cpu/").await;
+        assert_eq!(got.iter().map(|b| b.num_rows()).sum::<usize>(), 0);
+
+        let (plan, got) = run_statement("SHOW TAG KEYS FROM /^h2/").await;
+        assert_eq!(plan.name_column.as_deref(), Some(MEASUREMENT_COLUMN_NAME));
+        assert!(plan.tag_columns.is_empty());
+        assert_batches_eq!(
+            [
+                "+------------------+--------+",
+                "| iox::measurement | tagKey |",
+                "+------------------+--------+",
+                "| h2o              | tag1   |",
+                "+------------------+--------+",
+            ],
+            &got
+        );
+
+        let (_, got) = run_statement("SHOW TAG KEYS OFFSET 1").await;
+        assert_eq!(got.iter().map(|b| b.num_rows()).sum::<usize>(), 0);
+
+        let ctx = ctx();
+        for (statement, err) in [
+            (
+                "SHOW TAG KEYS WHERE tag1 = 'VT'",
+                "This feature is not implemented: SHOW TAG KEYS with WHERE clause in InfluxQL",
+            ),
+            (
+                "SHOW FIELD KEYS",
+                "This feature is not implemented: SHOW FIELD KEYS in InfluxQL",
+            ),
+        ] {
+            let (_, statement) = influxdb_influxql_parser::statement::statement(statement).unwrap();
+            let got = InfluxQLQueryPlanner::new()
+                .statement(&statement, &ctx)
+                .await
+                .unwrap_err();
+            assert_eq!(got.to_string(), err);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            plan_err("SELECT field_int, sum(field_int) FROM h2o"),
+            "Error during planning: mixing aggregate and non-aggregate queries is not supported"
+        );
+        assert_eq!(
+            plan_err("SELECT field_int FROM h2o GROUP BY time(1m)"),
+            "Error during planning: GROUP BY time() requires an aggregate function"
+        );
+        assert_eq!(
+            plan_err("SELECT field_int FROM h2o, cpu"),
+            "This feature is not implemented: SELECT from multiple measurements in InfluxQL"
+        );
+        assert_eq!(
+            plan_err("SELECT sum(field_int) FROM h2o GROUP BY time(1m) fill(previous)"),
+            "This feature is not implemented: FILL other than fill(none) in InfluxQL"
+        );
+        assert_eq!(
+            plan_err("SELECT field_int FROM h2o; SELECT field_int FROM h2o"),
+            "Error during planning: unexpected input after SELECT statement: 'SELECT field_int FROM h2o'"
+        );
+        assert!(plan_err("SELECT field_int FROM cpu").contains("cpu"));
+    }
+}
@@ -1,24 +1,288 @@
-use std::sync::Arc;
+pub mod rewrite;
 
-use crate::exec::context::IOxSessionContext;
-use datafusion::{error::Result, physical_plan::ExecutionPlan};
+use std::{fmt::Write, sync::Arc};
+
+use crate::exec::{analyze::IOxAnalyzeExec, context::IOxSessionContext};
+use datafusion::{
+    error::{DataFusionError, Result},
+    logical_plan::LogicalPlan,
+    physical_plan::ExecutionPlan,
+};
+use rewrite::{SqlRewriteRule, SqlRewriteRules};
+
+/// The value of a bind parameter of a SQL query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamValue {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    UInteger(u64),
+    Float(f64),
+    String(String),
+}
+
+/// A bind parameter of a SQL query.
+///
+/// Every parameter can be referenced by its 1-based position in the parameter list (`$1`, `$2`,
+/// ...) and, if it has a name, by its name (`$name`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryParam {
+    pub name: Option<String>,
+    pub value: ParamValue,
+}
+
+impl QueryParam {
+    /// A parameter that is only referenced by its position.
+    pub fn positional(value: ParamValue) -> Self {
+        Self { name: None, value }
+    }
+
+    /// A parameter that is referenced by `name` (or its position).
+    pub fn named(name: impl Into<String>, value: ParamValue) -> Self {
+        Self {
+            name: Some(name.into()),
+            value,
+        }
+    }
+}
 
 /// This struct can create plans for running SQL queries against databases
 #[derive(Debug, Default)]
-pub struct SqlQueryPlanner {}
+pub struct SqlQueryPlanner {
+    rules: SqlRewriteRules,
+}
 
 impl SqlQueryPlanner {
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Register a rule that rewrites the planned queries, see [`SqlRewriteRules`].
+    pub fn with_rule(mut self, rule: Arc<dyn SqlRewriteRule>) -> Self {
+        self.rules = self.rules.with_rule(rule);
+        self
+    }
+
     /// Plan a SQL query against the catalogs registered with `ctx`, and return a
     /// DataFusion physical execution plan that runs on the query executor.
+    ///
+    /// The placeholders (`$1`, `$name`) of the query are bound to `params`, see [`QueryParam`].
+    ///
+    /// `EXPLAIN ANALYZE` queries execute the query and return the metrics of every operator,
+    /// including the scanned chunks by type and the chunks pruned while planning.
     pub async fn query(
         &self,
         query: &str,
+        params: &[QueryParam],
         ctx: &IOxSessionContext,
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        ctx.prepare_sql(query).await
+        let bound;
+        let query = if params.is_empty() {
+            query
+        } else {
+            bound = bind_params(query, params)?;
+            &bound
+        };
+
+        let plan = ctx.sql_to_logical_plan(query)?;
+        let plan = if self.rules.is_empty() {
+            plan
+        } else {
+            self.rules.rewrite_plan(&plan)?
+        };
+
+        // `EXPLAIN ANALYZE` reports IOx-specific metrics, see [`IOxAnalyzeExec`]
+        if let LogicalPlan::Analyze(analyze) = &plan {
+            let input = ctx.create_physical_plan(&analyze.input).await?;
+            return Ok(Arc::new(IOxAnalyzeExec::new(
+                input,
+                ctx.query_pruning_stats(),
+            )));
+        }
+
+        ctx.create_physical_plan(&plan).await
+    }
+}
+
+/// Replace the placeholders of `query` by SQL literals of the values of `params`.
+///
+/// Placeholders within string literals, quoted identifiers and comments are left untouched.
+/// Values are rendered as single literal tokens, so a value can never change the structure of
+/// the query.
+fn bind_params(query: &str, params: &[QueryParam]) -> Result<String> {
+    let mut out = String::with_capacity(query.len());
+    let mut chars = query.char_indices().peekable();
+
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                // a doubled quote is an escaped quote, i.e. the end and the start of a literal
+                let end = query[pos + 1..]
+                    .find(c)
+                    .map(|i| pos + 1 + i + 1)
+                    .unwrap_or(query.len());
+                out.push_str(&query[pos..end]);
+                skip_to(&mut chars, end);
+            }
+            '-' if query[pos..].starts_with("--") => {
+                let end = query[pos..]
+                    .find('\n')
+                    .map(|i| pos + i)
+                    .unwrap_or(query.len());
+                out.push_str(&query[pos..end]);
+                skip_to(&mut chars, end);
+            }
+            '/' if query[pos..].starts_with("/*") => {
+                let end = query[pos + 2..]
+                    .find("*/")
+                    .map(|i| pos + 2 + i + 2)
+                    .unwrap_or(query.len());
+                out.push_str(&query[pos..end]);
+                skip_to(&mut chars, end);
+            }
+            '$' => {
+                let start = pos + 1;
+                let mut end = start;
+                while let Some((i, c)) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || *c == '_') {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+
+                let name = &query[start..end];
+                if name.is_empty() {
+                    out.push('$');
+                    continue;
+                }
+                let param = lookup_param(name, params).ok_or_else(|| {
+                    DataFusionError::Plan(format!("No value for query parameter ${}", name))
+                })?;
+                write_literal(&mut out, &param.value)?;
+            }
+            c => out.push(c),
+        }
+    }
+
+    Ok(out)
+}
+
+/// Advance `chars` to the byte offset `end`.
+fn skip_to(chars: &mut std::iter::Peekable<std::str::CharIndices<'_>>, end: usize) {
+    while chars.next_if(|(i, _)| *i < end).is_some() {}
+}
+
+fn lookup_param<'a>(name: &str, params: &'a [QueryParam]) -> Option<&'a QueryParam> {
+    if name.bytes().all(|b| b.is_ascii_digit()) {
+        let position: usize = name.parse().ok()?;
+        return position.checked_sub(1).and_then(|i| params.get(i));
+    }
+
+    params
+        .iter()
+        .find(|param| param.name.as_deref() == Some(name))
+}
+
+fn write_literal(out: &mut String, value: &ParamValue) -> Result<()> {
+    match value {
+        ParamValue::Null => out.push_str("NULL"),
+        ParamValue::Boolean(v) => out.push_str(if *v { "TRUE" } else { "FALSE" }),
+        // parenthesize negative numbers so that a preceding `-` doesn't start a comment
+        ParamValue::Integer(v) if *v < 0 => write!(out, "({})", v).unwrap(),
+        ParamValue::Integer(v) => write!(out, "{}", v).unwrap(),
+        ParamValue::UInteger(v) => write!(out, "{}", v).unwrap(),
+        ParamValue::Float(v) if !v.is_finite() => {
+            return Err(DataFusionError::Plan(format!(
+                "Unsupported query parameter value: {}",
+                v
+            )))
+        }
+        ParamValue::Float(v) => {
+            // always write a decimal point so that the literal is a float, but never an exponent
+            let mut literal = v.to_string();
+            if !literal.contains('.') {
+                literal.push_str(".0");
+            }
+            if v.is_sign_negative() {
+                write!(out, "({})", literal).unwrap()
+            } else {
+                out.push_str(&literal)
+            }
+        }
+        ParamValue::String(v) => {
+            out.push('\'');
+            out.push_str(&v.replace('\'', "''"));
+            out.push('\'');
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bind_params() {
+        let params = [
+            QueryParam::positional(ParamValue::Integer(-3)),
+            QueryParam::named("host", ParamValue::String("it's".to_string())),
+            QueryParam::named("usage", ParamValue::Float(1.0)),
+            QueryParam::positional(ParamValue::Boolean(true)),
+            QueryParam::positional(ParamValue::Null),
+        ];
+
+        let got = bind_params(
+            "SELECT * FROM cpu WHERE x = $1 AND host = $host AND usage > $usage AND b = $4 \
+             AND n IS $5 AND h2 = $2",
+            &params,
+        )
+        .unwrap();
+        assert_eq!(
+            got,
+            "SELECT * FROM cpu WHERE x = (-3) AND host = 'it''s' AND usage > 1.0 AND b = TRUE \
+             AND n IS NULL AND h2 = 'it''s'"
+        );
+
+        // placeholders in literals, quoted identifiers and comments are not bound
+        let query = "SELECT '$1', \"$host\" -- $1\nFROM cpu /* $usage */ WHERE x = 'a''$1'";
+        assert_eq!(bind_params(query, &params).unwrap(), query);
+
+        // a lone `$` is not a placeholder
+        assert_eq!(
+            bind_params("SELECT '$' || $", &params).unwrap(),
+            "SELECT '$' || $"
+        );
+    }
+
+    #[test]
+    fn test_bind_params_errors() {
+        let params = [QueryParam::named("host", ParamValue::Float(f64::NAN))];
+
+        let err = bind_params("SELECT $2", &params).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: No value for query parameter $2"
+        );
+
+        let err = bind_params("SELECT $0", &params).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: No value for query parameter $0"
+        );
+
+        let err = bind_params("SELECT $region", &params).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: No value for query parameter $region"
+        );
+
+        let err = bind_params("SELECT $host", &params).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Unsupported query parameter value: NaN"
+        );
     }
 }
@@ -0,0 +1,160 @@
+//! Pluggable rewrites of planned SQL queries.
+//!
+//! A [`SqlRewriteRule`] rewrites single expressions. [`SqlRewriteRules`] applies the registered
+//! rules to every expression of a logical plan: the expressions of all plan nodes (projections,
+//! `WHERE` and `HAVING` filters, aggregates, joins, sorts, ...) as well as the plans of `FROM`
+//! subqueries, CTEs and subquery expressions (`EXISTS`, `IN`, scalar subqueries).
+
+use std::{fmt::Debug, sync::Arc};
+
+use datafusion::{
+    error::Result,
+    logical_expr::{utils::from_plan, Subquery},
+    logical_plan::{Expr, ExprRewritable, ExprRewriter, LogicalPlan},
+};
+
+/// A rewrite of the expressions of a SQL query, see [`SqlRewriteRules`].
+pub trait SqlRewriteRule: Debug + Send + Sync {
+    /// Name of the rule, for debugging.
+    fn name(&self) -> &str;
+
+    /// Rewrite a single expression.
+    ///
+    /// This is called bottom-up, i.e. the children of `expr` have been rewritten already. The
+    /// rewritten expression must have the same output name and data type as `expr`, because
+    /// the schemas of the plan nodes are not recomputed.
+    fn rewrite_expr(&self, expr: Expr) -> Result<Expr>;
+}
+
+/// The rewrite rules that are applied to planned SQL queries.
+#[derive(Debug, Default, Clone)]
+pub struct SqlRewriteRules {
+    rules: Vec<Arc<dyn SqlRewriteRule>>,
+}
+
+impl SqlRewriteRules {
+    /// Register `rule`. Rules are applied in the order of their registration.
+    pub fn with_rule(mut self, rule: Arc<dyn SqlRewriteRule>) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Returns true if no rules are registered.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Apply all rules to all expressions of `plan`.
+    pub fn rewrite_plan(&self, plan: &LogicalPlan) -> Result<LogicalPlan> {
+        let inputs = plan
+            .inputs()
+            .into_iter()
+            .map(|input| self.rewrite_plan(input))
+            .collect::<Result<Vec<_>>>()?;
+
+        let exprs = plan
+            .expressions()
+            .into_iter()
+            .map(|expr| expr.rewrite(&mut RuleRewriter { rules: self }))
+            .collect::<Result<Vec<_>>>()?;
+
+        from_plan(plan, &exprs, &inputs)
+    }
+
+    fn rewrite_subquery(&self, subquery: Subquery) -> Result<Subquery> {
+        Ok(Subquery {
+            subquery: Arc::new(self.rewrite_plan(&subquery.subquery)?),
+        })
+    }
+}
+
+/// Applies the rules to an expression and all its sub-expressions.
+struct RuleRewriter<'a> {
+    rules: &'a SqlRewriteRules,
+}
+
+impl ExprRewriter for RuleRewriter<'_> {
+    fn mutate(&mut self, expr: Expr) -> Result<Expr> {
+        // subqueries are plans of their own, that the expression traversal doesn't descend into
+        let expr = match expr {
+            Expr::Exists { subquery, negated } => Expr::Exists {
+                subquery: self.rules.rewrite_subquery(subquery)?,
+                negated,
+            },
+            Expr::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => Expr::InSubquery {
+                expr,
+                subquery: self.rules.rewrite_subquery(subquery)?,
+                negated,
+            },
+            Expr::ScalarSubquery(subquery) => {
+                Expr::ScalarSubquery(self.rules.rewrite_subquery(subquery)?)
+            }
+            expr => expr,
+        };
+
+        self.rules
+            .rules
+            .iter()
+            .try_fold(expr, |expr, rule| rule.rewrite_expr(expr))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        exec::{Executor, ExecutorType},
+        frontend::sql::SqlQueryPlanner,
+    };
+    use arrow_util::assert_batches_eq;
+    use datafusion::scalar::ScalarValue;
+
+    /// Replaces the literal `1` by `10`.
+    #[derive(Debug)]
+    struct ReplaceOne;
+
+    impl SqlRewriteRule for ReplaceOne {
+        fn name(&self) -> &str {
+            "replace_one"
+        }
+
+        fn rewrite_expr(&self, expr: Expr) -> Result<Expr> {
+            Ok(match expr {
+                Expr::Literal(ScalarValue::Int64(Some(1))) => {
+                    Expr::Literal(ScalarValue::Int64(Some(10)))
+                }
+                expr => expr,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rewrite_all_expressions() {
+        let exec = Executor::new(1);
+        let ctx = exec.new_context(ExecutorType::Query);
+
+        // the literal appears in a CTE, a FROM subquery, WHERE and HAVING, so every one of them
+        // must be rewritten for the query to return a row
+        let sql = "WITH cte AS (SELECT 1 AS a) \
+                   SELECT a FROM (SELECT a FROM cte WHERE a = 1) AS t \
+                   GROUP BY a HAVING max(a) = 1";
+
+        let planner = SqlQueryPlanner::new().with_rule(Arc::new(ReplaceOne));
+        let plan = planner.query(sql, &[], &ctx).await.unwrap();
+        let batches = ctx.collect(plan).await.unwrap();
+
+        let expected = vec!["+----+", "| a  |", "+----+", "| 10 |", "+----+"];
+        assert_batches_eq!(expected, &batches);
+
+        // without the rule, the query is unchanged
+        let plan = SqlQueryPlanner::new().query(sql, &[], &ctx).await.unwrap();
+        let batches = ctx.collect(plan).await.unwrap();
+
+        let expected = vec!["+---+", "| a |", "+---+", "| 1 |", "+---+"];
+        assert_batches_eq!(expected, &batches);
+    }
+}
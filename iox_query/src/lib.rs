@@ -12,7 +12,7 @@ use async_trait::async_trait;
 use data_types::{
     ChunkId, ChunkOrder, DeletePredicate, InfluxDbType, PartitionId, TableSummary, TimestampMinMax,
 };
-use datafusion::physical_plan::SendableRecordBatchStream;
+use datafusion::physical_plan::{ExecutionPlan, SendableRecordBatchStream};
 use exec::{stringset::StringSet, IOxSessionContext};
 use hashbrown::HashMap;
 use observability_deps::tracing::{debug, trace};
@@ -26,15 +26,27 @@ use std::{any::Any, collections::BTreeSet, fmt::Debug, iter::FromIterator, sync:
 
 pub mod exec;
 pub mod frontend;
+pub mod physical_optimizer;
 pub mod plan;
 pub mod provider;
 pub mod pruning;
+pub mod query_completeness;
+pub mod query_id;
+pub mod query_memory;
+pub mod query_pruning;
+pub mod query_snapshot;
+pub mod result_cache;
 pub mod statistics;
 pub mod util;
 
 pub use exec::context::{DEFAULT_CATALOG, DEFAULT_SCHEMA};
 pub use frontend::common::ScanPlanBuilder;
+pub use query_completeness::QueryCompleteness;
 pub use query_functions::group_by::{Aggregate, WindowDuration};
+pub use query_id::{QueryId, QUERY_ID_HEADER};
+pub use query_memory::QueryMemoryReservation;
+pub use query_pruning::QueryPruningStats;
+pub use query_snapshot::{QuerySnapshot, SnapshotExpired, SnapshotToken};
 
 /// Trait for an object (designed to be a Chunk) which can provide
 /// metadata
@@ -61,6 +73,14 @@ pub trait QueryChunkMeta {
     /// return a reference to delete predicates of the chunk
     fn delete_predicates(&self) -> &[Arc<DeletePredicate>];
 
+    /// Return the statistics of every row group of the chunk, if known.
+    ///
+    /// Unlike [`summary`](Self::summary), these cover disjoint parts of the chunk data and can be
+    /// used to answer aggregations without scanning the chunk.
+    fn row_group_statistics(&self) -> Option<Arc<Vec<TableSummary>>> {
+        None
+    }
+
     /// return true if the chunk has delete predicates
     fn has_delete_predicates(&self) -> bool {
         !self.delete_predicates().is_empty()
@@ -171,6 +191,13 @@ pub trait QueryDatabase: QueryDatabaseMeta + Debug + Send + Sync {
     ///
     /// This is required until <https://github.com/rust-lang/rust/issues/65991> is fixed.
     fn as_meta(&self) -> &dyn QueryDatabaseMeta;
+
+    /// Returns a plan that serves the results of `plan` from the result cache of this database.
+    ///
+    /// Databases without a result cache return `plan` unchanged, see [`result_cache`].
+    fn cached_plan(&self, plan: Arc<dyn ExecutionPlan>) -> Arc<dyn ExecutionPlan> {
+        plan
+    }
 }
 
 /// Error type for [`QueryChunk`] operations.
@@ -248,12 +275,27 @@ pub trait QueryChunk: QueryChunkMeta + Debug + Send + Sync + 'static {
         selection: Selection<'_>,
     ) -> Result<SendableRecordBatchStream, QueryChunkError>;
 
+    /// Returns true if [`read_filter`](Self::read_filter) already removes the rows deleted by
+    /// the [delete predicates](QueryChunkMeta::delete_predicates) of this chunk, so that the
+    /// query plan doesn't need a separate filter for them. The columns of the delete predicates
+    /// are always part of the selection of such reads.
+    fn applies_delete_predicates(&self) -> bool {
+        false
+    }
+
     /// Returns chunk type. Useful in tests and debug logs.
     fn chunk_type(&self) -> &str;
 
     /// Order of this chunk relative to other overlapping chunks.
     fn order(&self) -> ChunkOrder;
 
+    /// Returns true if the data of this chunk never changes, i.e. every chunk with the same ID and
+    /// delete predicates returns the same data. Only results of queries that exclusively read
+    /// immutable chunks are cached, see [`result_cache`].
+    fn is_immutable(&self) -> bool {
+        false
+    }
+
     /// Return backend as [`Any`] which can be used to downcast to a specific implementation.
     fn as_any(&self) -> &dyn Any;
 }
@@ -292,6 +334,10 @@ where
     fn timestamp_min_max(&self) -> Option<TimestampMinMax> {
         self.as_ref().timestamp_min_max()
     }
+
+    fn row_group_statistics(&self) -> Option<Arc<Vec<TableSummary>>> {
+        self.as_ref().row_group_statistics()
+    }
 }
 
 /// Implement ChunkMeta for Arc<dyn QueryChunk>
@@ -325,6 +371,10 @@ impl QueryChunkMeta for Arc<dyn QueryChunk> {
     fn timestamp_min_max(&self) -> Option<TimestampMinMax> {
         self.as_ref().timestamp_min_max()
     }
+
+    fn row_group_statistics(&self) -> Option<Arc<Vec<TableSummary>>> {
+        self.as_ref().row_group_statistics()
+    }
 }
 
 /// return true if all the chunks include statistics
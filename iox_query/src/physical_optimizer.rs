@@ -0,0 +1,752 @@
+//! IOx-specific rules of the DataFusion physical optimizer.
+//!
+//! # Aggregate pushdown
+//!
+//! Dashboards typically show coarse rollups like
+//!
+//! ```sql
+//! SELECT date_bin(INTERVAL '1 hour', time, TIMESTAMP '1970-01-01T00:00:00Z') AS hour,
+//!        count(*), max(usage)
+//! FROM cpu
+//! WHERE time >= '2022-10-01T00:00:00Z'
+//! GROUP BY hour
+//! ```
+//!
+//! Answering these by scanning the raw data is wasteful: if all rows of a parquet row group fall
+//! into the same bucket, the row group statistics already contain the partial aggregate of that
+//! bucket. [`AggregatePushdown`] replaces the scan of such chunks by the aggregate states computed
+//! from their [row group statistics](crate::QueryChunkMeta::row_group_statistics). Chunks that
+//! cannot be answered from their statistics are scanned as before.
+//!
+//! The rule applies to partial aggregations that
+//!
+//! - group by a single `date_bin` or `date_trunc` of the time column,
+//! - only compute `min`, `max` and `count`: parquet statistics do not contain sums, so `sum` and
+//!   `avg` always scan, and
+//! - read chunks that neither need deduplication nor have delete predicates, optionally through
+//!   filters that restrict the time range.
+//!
+//! A chunk is answered from its statistics if for every row group the earliest and the latest
+//! timestamp fall into the same bucket and pass the time filters. Since bucketing and time range
+//! filters are monotonic in time, all rows of such a row group are then in the same bucket and
+//! pass the filters.
+use std::sync::Arc;
+
+use arrow::{
+    array::{new_null_array, ArrayRef, BooleanArray},
+    compute::cast,
+    datatypes::{DataType, Field, Schema, SchemaRef},
+    record_batch::RecordBatch,
+};
+use data_types::{StatValues, Statistics, TableSummary};
+use datafusion::{
+    error::Result,
+    execution::context::SessionConfig,
+    logical_plan::Operator,
+    physical_optimizer::optimizer::PhysicalOptimizerRule,
+    physical_plan::{
+        aggregates::{AggregateExec, AggregateMode},
+        coalesce_batches::CoalesceBatchesExec,
+        coalesce_partitions::CoalescePartitionsExec,
+        empty::EmptyExec,
+        expressions::{BinaryExpr, Column, Count, Literal, Max, Min},
+        filter::FilterExec,
+        functions::ScalarFunctionExpr,
+        memory::MemoryExec,
+        projection::ProjectionExec,
+        repartition::RepartitionExec,
+        union::UnionExec,
+        AggregateExpr, ExecutionPlan, PhysicalExpr,
+    },
+    scalar::ScalarValue,
+};
+use observability_deps::tracing::debug;
+use schema::TIME_COLUMN_NAME;
+
+use crate::{
+    provider::IOxReadFilterNode,
+    statistics::{max_to_scalar, min_to_scalar},
+    QueryChunk,
+};
+
+/// Names of the time bucketing functions that are answered from statistics.
+const TIME_BUCKET_FUNCTIONS: &[&str] = &["datebin", "datetrunc"];
+
+/// Answers time-bucketed aggregations from row group statistics, see the
+/// [module documentation](self).
+#[derive(Debug, Default)]
+pub struct AggregatePushdown {}
+
+impl AggregatePushdown {
+    /// Create the rule.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl PhysicalOptimizerRule for AggregatePushdown {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        _config: &SessionConfig,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(optimize_plan(&plan, false)?.unwrap_or(plan))
+    }
+
+    fn name(&self) -> &str {
+        "iox_aggregate_pushdown"
+    }
+}
+
+/// Rewrite all eligible partial aggregations within `plan`, returns `None` if nothing changed.
+///
+/// `merges_partitions` is true if the parent of `plan` accepts any number of input partitions,
+/// so that `plan` may change its number of output partitions.
+fn optimize_plan(
+    plan: &Arc<dyn ExecutionPlan>,
+    merges_partitions: bool,
+) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+    if let Some(new_plan) = pushdown(plan, merges_partitions)? {
+        return Ok(Some(new_plan));
+    }
+
+    let merges = plan.as_any().is::<RepartitionExec>()
+        || plan.as_any().is::<CoalescePartitionsExec>()
+        || plan.as_any().is::<UnionExec>();
+    let children = plan.children();
+    let mut changed = false;
+    let mut new_children = Vec::with_capacity(children.len());
+    for child in children {
+        match optimize_plan(&child, merges)? {
+            Some(new_child) => {
+                changed = true;
+                new_children.push(new_child);
+            }
+            None => new_children.push(child),
+        }
+    }
+
+    if changed {
+        Ok(Some(Arc::clone(plan).with_new_children(new_children)?))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Answer `plan` partially from statistics if it is an eligible partial aggregation.
+fn pushdown(
+    plan: &Arc<dyn ExecutionPlan>,
+    merges_partitions: bool,
+) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+    let aggregate = match plan.as_any().downcast_ref::<AggregateExec>() {
+        Some(aggregate) if matches!(aggregate.mode(), AggregateMode::Partial) => aggregate,
+        _ => return Ok(None),
+    };
+
+    // the union of the reduced aggregation and the statistics has one more partition
+    let n_partitions = plan.output_partitioning().partition_count();
+    if !merges_partitions && n_partitions != 1 {
+        return Ok(None);
+    }
+
+    let group_by = aggregate.group_expr();
+    let group_expr = match group_by.expr() {
+        [(expr, _name)] if group_by.null_expr().is_empty() && is_time_bucket(expr.as_ref()) => {
+            Arc::clone(expr)
+        }
+        _ => return Ok(None),
+    };
+    let aggregates = match aggregate
+        .aggr_expr()
+        .iter()
+        .map(PushdownAggregate::try_new)
+        .collect::<Option<Vec<_>>>()
+    {
+        Some(aggregates) => aggregates,
+        None => return Ok(None),
+    };
+
+    let mut pushdown = Pushdown {
+        group_expr,
+        input_schema: aggregate.input_schema(),
+        aggregates,
+        filters: vec![],
+        rows: vec![],
+        n_chunks: 0,
+    };
+    let new_input = match pushdown.reduce(aggregate.input(), false)? {
+        Some(new_input) => new_input,
+        None => return Ok(None),
+    };
+
+    let schema = plan.schema();
+    let batch = match pushdown.batch(&schema) {
+        Some(batch) => batch,
+        None => {
+            debug!("aggregate states from statistics do not match the aggregate schema");
+            return Ok(None);
+        }
+    };
+    debug!(
+        n_chunks = pushdown.n_chunks,
+        n_row_groups = pushdown.rows.len(),
+        "answering aggregation from row group statistics"
+    );
+
+    let union = Arc::new(UnionExec::new(vec![
+        Arc::clone(plan).with_new_children(vec![new_input])?,
+        Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None)?),
+    ]));
+    if merges_partitions {
+        Ok(Some(union))
+    } else {
+        Ok(Some(Arc::new(CoalescePartitionsExec::new(union))))
+    }
+}
+
+/// Returns true if `expr` buckets the time column, e.g. `date_bin(INTERVAL '1 hour', time)`.
+fn is_time_bucket(expr: &dyn PhysicalExpr) -> bool {
+    let function = match expr.as_any().downcast_ref::<ScalarFunctionExpr>() {
+        Some(function) => function,
+        None => return false,
+    };
+
+    TIME_BUCKET_FUNCTIONS.contains(&function.name())
+        && function
+            .args()
+            .iter()
+            .any(|arg| is_time_column(arg.as_ref()))
+        && function
+            .args()
+            .iter()
+            .all(|arg| is_time_column(arg.as_ref()) || arg.as_any().is::<Literal>())
+}
+
+/// Returns true if `expr` is a conjunction of comparisons of the time column with literals, i.e.
+/// it is true for a contiguous time range.
+fn is_time_range(expr: &dyn PhysicalExpr) -> bool {
+    let binary = match expr.as_any().downcast_ref::<BinaryExpr>() {
+        Some(binary) => binary,
+        None => return false,
+    };
+
+    match binary.op() {
+        Operator::And => {
+            is_time_range(binary.left().as_ref()) && is_time_range(binary.right().as_ref())
+        }
+        Operator::Eq | Operator::Lt | Operator::LtEq | Operator::Gt | Operator::GtEq => {
+            let (left, right) = (binary.left().as_any(), binary.right().as_any());
+            (is_time_column(binary.left().as_ref()) && right.is::<Literal>())
+                || (left.is::<Literal>() && is_time_column(binary.right().as_ref()))
+        }
+        _ => false,
+    }
+}
+
+fn is_time_column(expr: &dyn PhysicalExpr) -> bool {
+    expr.as_any()
+        .downcast_ref::<Column>()
+        .map_or(false, |column| column.name() == TIME_COLUMN_NAME)
+}
+
+/// Aggregate functions that can be answered from statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AggregateKind {
+    Min,
+    Max,
+    Count,
+}
+
+/// An aggregate of the partial aggregation, e.g. `max(usage)`.
+#[derive(Debug)]
+struct PushdownAggregate {
+    kind: AggregateKind,
+
+    /// Aggregated column, `None` for `count(*)`.
+    column: Option<String>,
+
+    /// Type of the aggregate state.
+    data_type: DataType,
+}
+
+impl PushdownAggregate {
+    fn try_new(aggregate: &Arc<dyn AggregateExpr>) -> Option<Self> {
+        let any = aggregate.as_any();
+        let kind = if any.is::<Min>() {
+            AggregateKind::Min
+        } else if any.is::<Max>() {
+            AggregateKind::Max
+        } else if any.is::<Count>() {
+            AggregateKind::Count
+        } else {
+            return None;
+        };
+
+        let data_type = match aggregate.state_fields().ok()?.as_slice() {
+            [field] => field.data_type().clone(),
+            _ => return None,
+        };
+
+        let column = match aggregate.expressions().as_slice() {
+            [expr] => {
+                if let Some(column) = expr.as_any().downcast_ref::<Column>() {
+                    Some(column.name().to_string())
+                } else {
+                    // `count(*)` counts a non-null literal
+                    match expr.as_any().downcast_ref::<Literal>() {
+                        Some(literal)
+                            if kind == AggregateKind::Count && !literal.value().is_null() =>
+                        {
+                            None
+                        }
+                        _ => return None,
+                    }
+                }
+            }
+            _ => return None,
+        };
+
+        Some(Self {
+            kind,
+            column,
+            data_type,
+        })
+    }
+
+    /// Aggregate state of a row group of `chunk`, `None` if the statistics do not suffice.
+    fn state(&self, chunk: &dyn QueryChunk, row_group: &TableSummary) -> Option<ScalarValue> {
+        let n_rows = row_group.column(TIME_COLUMN_NAME)?.total_count();
+        let column = match &self.column {
+            Some(column) => column,
+            None => return Some(ScalarValue::Int64(Some(n_rows as i64))),
+        };
+
+        let summary = match row_group.column(column) {
+            Some(summary) => summary,
+            // the scan fills columns that the chunk does not have with nulls
+            None if chunk.schema().find_index_of(column).is_none() => {
+                return match self.kind {
+                    AggregateKind::Count => Some(ScalarValue::Int64(Some(0))),
+                    AggregateKind::Min | AggregateKind::Max => {
+                        ScalarValue::try_from(&self.data_type).ok()
+                    }
+                }
+            }
+            None => return None,
+        };
+
+        let null_count = summary.stats.null_count()?;
+        let total_count = summary.total_count();
+        match self.kind {
+            AggregateKind::Count => Some(ScalarValue::Int64(Some(
+                total_count.checked_sub(null_count)? as i64,
+            ))),
+            _ if null_count == total_count => ScalarValue::try_from(&self.data_type).ok(),
+            AggregateKind::Min => min_to_scalar(&summary.influxdb_type, &summary.stats),
+            AggregateKind::Max => max_to_scalar(&summary.influxdb_type, &summary.stats),
+        }
+    }
+}
+
+/// State of the rewrite of a single partial aggregation.
+#[derive(Debug)]
+struct Pushdown {
+    /// Time bucket expression of the aggregation.
+    group_expr: Arc<dyn PhysicalExpr>,
+
+    /// Input schema of the aggregation.
+    input_schema: SchemaRef,
+
+    /// Aggregates of the aggregation.
+    aggregates: Vec<PushdownAggregate>,
+
+    /// Filters between the aggregation and the currently visited node, along with their input
+    /// schemas.
+    filters: Vec<(Arc<dyn PhysicalExpr>, SchemaRef)>,
+
+    /// One row of the partial aggregation output per row group answered from statistics.
+    rows: Vec<Vec<ScalarValue>>,
+
+    /// Number of chunks answered from statistics.
+    n_chunks: usize,
+}
+
+impl Pushdown {
+    /// Remove the chunks that can be answered from statistics from `plan` and record their
+    /// aggregate states. Returns `None` if no chunk was removed.
+    ///
+    /// `filtered` is true if a filter is applied between the aggregation and `plan`.
+    fn reduce(
+        &mut self,
+        plan: &Arc<dyn ExecutionPlan>,
+        filtered: bool,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        let any = plan.as_any();
+
+        if let Some(node) = any.downcast_ref::<IOxReadFilterNode>() {
+            // Without a filter above the scan, the predicate of the scan must be applied exactly.
+            // It is not taken into account here, so only scans without predicate are eligible.
+            if !filtered && !node.predicate().is_empty() {
+                return Ok(None);
+            }
+
+            let mut rest = vec![];
+            let mut n_removed = 0;
+            for chunk in node.chunks() {
+                match self.chunk_rows(chunk.as_ref()) {
+                    Some(mut rows) => {
+                        self.rows.append(&mut rows);
+                        n_removed += 1;
+                    }
+                    None => rest.push(Arc::clone(chunk)),
+                }
+            }
+            if n_removed == 0 {
+                return Ok(None);
+            }
+            self.n_chunks += n_removed;
+
+            // scans with zero partitions are not supported by all plan nodes
+            return Ok(Some(if rest.is_empty() {
+                Arc::new(EmptyExec::new(false, plan.schema()))
+            } else {
+                Arc::new(node.with_chunks(rest))
+            }));
+        }
+
+        if let Some(filter) = any.downcast_ref::<FilterExec>() {
+            if !is_time_range(filter.predicate().as_ref()) {
+                return Ok(None);
+            }
+
+            self.filters
+                .push((Arc::clone(filter.predicate()), filter.input().schema()));
+            let res = self.reduce_children(plan, true);
+            self.filters.pop();
+            return res;
+        }
+
+        if let Some(projection) = any.downcast_ref::<ProjectionExec>() {
+            // columns are matched by name, so they must not be renamed
+            let only_columns = projection.expr().iter().all(|(expr, name)| {
+                expr.as_any()
+                    .downcast_ref::<Column>()
+                    .map_or(false, |column| column.name() == name)
+            });
+            if !only_columns {
+                return Ok(None);
+            }
+            return self.reduce_children(plan, filtered);
+        }
+
+        // nodes that pass all rows through unchanged
+        if any.is::<UnionExec>()
+            || any.is::<RepartitionExec>()
+            || any.is::<CoalesceBatchesExec>()
+            || any.is::<CoalescePartitionsExec>()
+        {
+            return self.reduce_children(plan, filtered);
+        }
+
+        // anything else (e.g. deduplication) is scanned as usual
+        Ok(None)
+    }
+
+    fn reduce_children(
+        &mut self,
+        plan: &Arc<dyn ExecutionPlan>,
+        filtered: bool,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        let children = plan.children();
+        let mut changed = false;
+        let mut new_children = Vec::with_capacity(children.len());
+        for child in children {
+            match self.reduce(&child, filtered)? {
+                Some(new_child) => {
+                    changed = true;
+                    new_children.push(new_child);
+                }
+                None => new_children.push(child),
+            }
+        }
+
+        if changed {
+            Ok(Some(Arc::clone(plan).with_new_children(new_children)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Rows of the partial aggregation output for all row groups of `chunk`, `None` if the chunk
+    /// must be scanned.
+    fn chunk_rows(&self, chunk: &dyn QueryChunk) -> Option<Vec<Vec<ScalarValue>>> {
+        if chunk.may_contain_pk_duplicates() || chunk.has_delete_predicates() {
+            return None;
+        }
+
+        chunk
+            .row_group_statistics()?
+            .iter()
+            .map(|row_group| self.row_group_row(chunk, row_group))
+            .collect()
+    }
+
+    fn row_group_row(
+        &self,
+        chunk: &dyn QueryChunk,
+        row_group: &TableSummary,
+    ) -> Option<Vec<ScalarValue>> {
+        let (min, max) = match &row_group.column(TIME_COLUMN_NAME)?.stats {
+            Statistics::I64(StatValues {
+                min: Some(min),
+                max: Some(max),
+                ..
+            }) => (*min, *max),
+            _ => return None,
+        };
+
+        for (filter, schema) in &self.filters {
+            let passed = evaluate(filter.as_ref(), schema, min, max)?;
+            let passed = passed.as_any().downcast_ref::<BooleanArray>()?;
+            if passed.null_count() > 0 || !passed.value(0) || !passed.value(1) {
+                return None;
+            }
+        }
+
+        let buckets = evaluate(self.group_expr.as_ref(), &self.input_schema, min, max)?;
+        let bucket = ScalarValue::try_from_array(&buckets, 0).ok()?;
+        if bucket.is_null() || bucket != ScalarValue::try_from_array(&buckets, 1).ok()? {
+            return None;
+        }
+
+        let mut row = Vec::with_capacity(self.aggregates.len() + 1);
+        row.push(bucket);
+        for aggregate in &self.aggregates {
+            row.push(aggregate.state(chunk, row_group)?);
+        }
+        Some(row)
+    }
+
+    /// Output of the partial aggregation for the row groups answered from statistics.
+    fn batch(&self, schema: &SchemaRef) -> Option<RecordBatch> {
+        let columns = schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(idx, field)| {
+                let array = if self.rows.is_empty() {
+                    new_null_array(field.data_type(), 0)
+                } else {
+                    ScalarValue::iter_to_array(self.rows.iter().map(|row| row[idx].clone())).ok()?
+                };
+                if array.data_type() == field.data_type() {
+                    Some(array)
+                } else {
+                    cast(&array, field.data_type()).ok()
+                }
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        RecordBatch::try_new(Arc::clone(schema), columns).ok()
+    }
+}
+
+/// Evaluate `expr` against two rows of `schema` that only contain the timestamps `min` and `max`,
+/// all other columns are null.
+fn evaluate(expr: &dyn PhysicalExpr, schema: &Schema, min: i64, max: i64) -> Option<ArrayRef> {
+    let mut fields = Vec::with_capacity(schema.fields().len());
+    let mut columns = Vec::with_capacity(schema.fields().len());
+    for field in schema.fields() {
+        let column = if field.name() == TIME_COLUMN_NAME {
+            let times = ScalarValue::iter_to_array([
+                ScalarValue::TimestampNanosecond(Some(min), None),
+                ScalarValue::TimestampNanosecond(Some(max), None),
+            ])
+            .ok()?;
+            cast(&times, field.data_type()).ok()?
+        } else {
+            new_null_array(field.data_type(), 2)
+        };
+        fields.push(Field::new(field.name(), field.data_type().clone(), true));
+        columns.push(column);
+    }
+
+    let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).ok()?;
+    Some(expr.evaluate(&batch).ok()?.into_array(2))
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_util::assert_batches_sorted_eq;
+    use data_types::{ColumnSummary, InfluxDbType};
+    use datafusion::physical_plan::displayable;
+
+    use super::*;
+    use crate::{
+        exec::{Executor, ExecutorConfig, ExecutorType},
+        provider::ProviderBuilder,
+        test::TestChunk,
+        QueryChunkMeta,
+    };
+
+    const HOUR: i64 = 3_600_000_000_000;
+
+    const QUERY: &str = "SELECT \
+        date_bin(INTERVAL '1 hour', time, TIMESTAMP '1970-01-01T00:00:00Z') AS hour, \
+        count(*) AS n, min(field_int) AS lo, max(field_int) AS hi \
+        FROM t WHERE time >= TIMESTAMP '1970-01-01T00:00:00Z' GROUP BY hour";
+
+    #[tokio::test]
+    async fn test_aggregate_pushdown() {
+        test_helpers::maybe_start_logging();
+
+        // all rows are in the first hour
+        let chunk1 =
+            Arc::new(chunk(1).with_row_group_statistics(vec![row_group(50, 7000, 5, 1000, 5)]));
+        // the second row group spans two hours
+        let chunk2 = Arc::new(chunk(2).with_row_group_statistics(vec![
+            row_group(50, 7000, 5, 1000, 5),
+            row_group(HOUR - 1, HOUR, 1, 2, 2),
+        ]));
+        // no statistics
+        let chunk3 = Arc::new(chunk(3));
+        let chunks = || {
+            [&chunk1, &chunk2, &chunk3]
+                .into_iter()
+                .map(|c| Arc::clone(c) as Arc<dyn QueryChunk>)
+                .collect::<Vec<_>>()
+        };
+
+        let expected = vec![
+            "+----------------------+----+----+------+",
+            "| hour                 | n  | lo | hi   |",
+            "+----------------------+----+----+------+",
+            "| 1970-01-01T00:00:00Z | 15 | 5  | 1000 |",
+            "+----------------------+----+----+------+",
+        ];
+
+        // without pushdown, all chunks are scanned
+        let (plan_str, batches) = run_query(false, chunks(), QUERY).await;
+        assert!(!plan_str.contains("MemoryExec"), "{}", plan_str);
+        assert_batches_sorted_eq!(&expected, &batches);
+
+        // with pushdown, the first chunk is answered from its statistics
+        let n_reads = |chunk: &Arc<TestChunk>| chunk.predicates().len();
+        let before = [n_reads(&chunk1), n_reads(&chunk2), n_reads(&chunk3)];
+        let (plan_str, batches) = run_query(true, chunks(), QUERY).await;
+        assert!(plan_str.contains("MemoryExec"), "{}", plan_str);
+        assert!(plan_str.contains("chunks=2"), "{}", plan_str);
+        assert_batches_sorted_eq!(&expected, &batches);
+        assert_eq!(n_reads(&chunk1), before[0]);
+        assert!(n_reads(&chunk2) > before[1]);
+        assert!(n_reads(&chunk3) > before[2]);
+
+        // chunks that may contain duplicates are always scanned
+        let chunk4 = Arc::new(
+            chunk(4)
+                .with_may_contain_pk_duplicates(true)
+                .with_row_group_statistics(vec![row_group(50, 7000, 5, 1000, 5)]),
+        ) as Arc<dyn QueryChunk>;
+        let (plan_str, _batches) = run_query(true, vec![chunk4], QUERY).await;
+        assert!(!plan_str.contains("MemoryExec"), "{}", plan_str);
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_pushdown_unsupported() {
+        test_helpers::maybe_start_logging();
+
+        for sql in [
+            // sums are not part of the statistics
+            "SELECT date_bin(INTERVAL '1 hour', time, TIMESTAMP '1970-01-01T00:00:00Z') AS hour, \
+                sum(field_int) FROM t GROUP BY hour",
+            // not a time range
+            "SELECT date_bin(INTERVAL '1 hour', time, TIMESTAMP '1970-01-01T00:00:00Z') AS hour, \
+                count(*) FROM t WHERE field_int > 10 GROUP BY hour",
+            // not grouped by time
+            "SELECT field_int, count(*) FROM t GROUP BY field_int",
+        ] {
+            let chunk =
+                Arc::new(chunk(1).with_row_group_statistics(vec![row_group(50, 7000, 5, 1000, 5)]))
+                    as Arc<dyn QueryChunk>;
+            let (plan_str, _batches) = run_query(true, vec![chunk], sql).await;
+            assert!(!plan_str.contains("MemoryExec"), "{}\n{}", sql, plan_str);
+        }
+    }
+
+    /// Chunk with five rows in the first hour, see [`TestChunk::with_five_rows_of_data`].
+    fn chunk(id: u128) -> TestChunk {
+        TestChunk::new("t")
+            .with_id(id)
+            // chunks of different partitions do not need to be deduplicated
+            .with_partition_id(id as i64)
+            .with_time_column()
+            .with_i64_field_column("field_int")
+            .with_five_rows_of_data()
+    }
+
+    fn row_group(min_time: i64, max_time: i64, min: i64, max: i64, count: u64) -> TableSummary {
+        TableSummary {
+            columns: vec![
+                column_summary(
+                    TIME_COLUMN_NAME,
+                    InfluxDbType::Timestamp,
+                    min_time,
+                    max_time,
+                    count,
+                ),
+                column_summary("field_int", InfluxDbType::Field, min, max, count),
+            ],
+        }
+    }
+
+    fn column_summary(
+        name: &str,
+        influxdb_type: InfluxDbType,
+        min: i64,
+        max: i64,
+        count: u64,
+    ) -> ColumnSummary {
+        ColumnSummary {
+            name: name.to_string(),
+            influxdb_type: Some(influxdb_type),
+            stats: Statistics::I64(StatValues {
+                min: Some(min),
+                max: Some(max),
+                total_count: count,
+                null_count: Some(0),
+                distinct_count: None,
+            }),
+        }
+    }
+
+    async fn run_query(
+        aggregate_pushdown: bool,
+        chunks: Vec<Arc<dyn QueryChunk>>,
+        sql: &str,
+    ) -> (String, Vec<RecordBatch>) {
+        let exec = Executor::new_with_config(ExecutorConfig {
+            num_threads: 1,
+            target_query_partitions: 2,
+            mem_pool_size: None,
+            spill_dir: None,
+            external_dedup_min_chunks: None,
+            aggregate_pushdown,
+        });
+        let ctx = exec.new_context(ExecutorType::Query);
+
+        let mut builder = ProviderBuilder::new("t", chunks[0].schema(), ctx.child_ctx("provider"));
+        for chunk in chunks {
+            builder = builder.add_chunk(chunk);
+        }
+        ctx.inner()
+            .register_table("t", Arc::new(builder.build().unwrap()))
+            .unwrap();
+
+        let plan = ctx.prepare_sql(sql).await.unwrap();
+        let plan_str = format!("{}", displayable(plan.as_ref()).indent());
+        let batches = ctx.collect(plan).await.unwrap();
+
+        exec.join().await;
+        (plan_str, batches)
+    }
+}
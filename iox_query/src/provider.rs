@@ -4,7 +4,8 @@ use async_trait::async_trait;
 use hashbrown::HashMap;
 use std::sync::Arc;
 
-use arrow::{datatypes::SchemaRef as ArrowSchemaRef, error::ArrowError};
+use arrow::{compute::SortOptions, datatypes::SchemaRef as ArrowSchemaRef, error::ArrowError};
+use data_types::DeletePredicate;
 use datafusion::{
     datasource::TableProvider,
     error::{DataFusionError, Result as DataFusionResult},
@@ -12,16 +13,18 @@ use datafusion::{
     logical_expr::{TableProviderFilterPushDown, TableType},
     logical_plan::Expr,
     physical_plan::{
-        expressions::{col as physical_col, PhysicalSortExpr},
+        coalesce_partitions::CoalescePartitionsExec,
+        expressions::{col as physical_col, Literal, PhysicalSortExpr},
         filter::FilterExec,
         projection::ProjectionExec,
         sorts::{sort::SortExec, sort_preserving_merge::SortPreservingMergeExec},
         union::UnionExec,
         ExecutionPlan,
     },
+    scalar::ScalarValue,
 };
 use observability_deps::tracing::{debug, trace, warn};
-use predicate::Predicate;
+use predicate::{delete_predicate::delete_predicates_to_filter, Predicate};
 use schema::{
     interner::SchemaInterner, merge::SchemaMerger, sort::SortKey, InfluxColumnType, Schema,
 };
@@ -36,14 +39,20 @@ use crate::{
 use snafu::{ResultExt, Snafu};
 
 mod adapter;
+mod dedup_validation;
 mod deduplicate;
 pub mod overlap;
 mod physical;
+use self::dedup_validation::{ChunkInfo, DedupValidationExec};
 use self::overlap::group_potential_duplicates;
 pub(crate) use deduplicate::DeduplicateExec;
 pub use deduplicate::RecordBatchDeduplicator;
 pub(crate) use physical::IOxReadFilterNode;
 
+/// Column that the external deduplication plan uses to order rows of different chunks that have the
+/// same primary key, see [`Deduplicater::build_external_deduplicate_plan_for_overlapped_chunks`].
+const CHUNK_ORDER_COLUMN: &str = "__chunk_order";
+
 #[derive(Debug, Snafu)]
 pub enum Error {
     #[snafu(display(
@@ -122,6 +131,7 @@ pub struct ProviderBuilder {
     schema: Arc<Schema>,
     chunks: Vec<Arc<dyn QueryChunk>>,
     output_sort_key: Option<SortKey>,
+    validate_dedup: bool,
 
     // execution context used for tracing
     ctx: IOxSessionContext,
@@ -134,6 +144,7 @@ impl ProviderBuilder {
             schema,
             chunks: Vec::new(),
             output_sort_key: None,
+            validate_dedup: false,
             ctx,
         }
     }
@@ -146,6 +157,17 @@ impl ProviderBuilder {
         }
     }
 
+    /// Check the scan output for primary key duplicates that survived deduplication.
+    ///
+    /// Offending keys are logged together with the chunks that may have contributed them, the
+    /// query output is not modified. This is expensive and meant for debugging only.
+    pub fn with_dedup_validation(self, validate_dedup: bool) -> Self {
+        Self {
+            validate_dedup,
+            ..self
+        }
+    }
+
     /// Add a new chunk to this provider
     pub fn add_chunk(mut self, chunk: Arc<dyn QueryChunk>) -> Self {
         self.chunks.push(chunk);
@@ -159,6 +181,7 @@ impl ProviderBuilder {
             table_name: self.table_name,
             chunks: self.chunks,
             output_sort_key: self.output_sort_key,
+            validate_dedup: self.validate_dedup,
             ctx: self.ctx,
         })
     }
@@ -177,6 +200,8 @@ pub struct ChunkTableProvider {
     chunks: Vec<Arc<dyn QueryChunk>>,
     /// The desired output sort key if any
     output_sort_key: Option<SortKey>,
+    /// Check the scan output for primary key duplicates
+    validate_dedup: bool,
 
     // execution context
     ctx: IOxSessionContext,
@@ -242,13 +267,41 @@ impl TableProvider for ChunkTableProvider {
         let mut deduplicate = Deduplicater::new(self.ctx.child_ctx("deduplicator"));
         let plan = deduplicate.build_scan_plan(
             Arc::clone(&self.table_name),
-            scan_schema,
-            chunks,
+            Arc::clone(&scan_schema),
+            chunks.clone(),
             predicate,
             self.output_sort_key.clone(),
         )?;
 
-        Ok(plan)
+        if !self.validate_dedup {
+            return Ok(plan);
+        }
+
+        // duplicates can only be detected if the entire primary key is part of the output
+        let key_columns: Vec<String> = self
+            .iox_schema
+            .primary_key()
+            .into_iter()
+            .map(|name| name.to_string())
+            .collect();
+        if key_columns
+            .iter()
+            .any(|name| scan_schema.find_index_of(name).is_none())
+        {
+            debug!(table_name=%self.table_name, "primary key not fully projected, skipping dedup validation");
+            return Ok(plan);
+        }
+
+        let chunks = chunks
+            .iter()
+            .map(|chunk| ChunkInfo::new(chunk.as_ref()))
+            .collect();
+        Ok(Arc::new(DedupValidationExec::new(
+            plan,
+            Arc::clone(&self.table_name),
+            key_columns,
+            chunks,
+        )))
     }
 
     /// Filter pushdown specification
@@ -279,6 +332,9 @@ pub(crate) struct Deduplicater {
     /// schema interner
     schema_interner: SchemaInterner,
 
+    /// Minimum number of overlapping chunks that are deduplicated with a single sort
+    external_dedup_min_chunks: Option<usize>,
+
     // execution context
     ctx: IOxSessionContext,
 }
@@ -290,6 +346,7 @@ impl Deduplicater {
             in_chunk_duplicates_chunks: vec![],
             no_duplicates_chunks: vec![],
             schema_interner: Default::default(),
+            external_dedup_min_chunks: ctx.external_dedup_min_chunks(),
             ctx,
         }
     }
@@ -445,16 +502,33 @@ impl Deduplicater {
                     "Sort keys while building build_deduplicate_plan_for_overlapped_chunks"
                 );
 
-                plans.push(Self::build_deduplicate_plan_for_overlapped_chunks(
-                    self.ctx
-                        .child_ctx("build_deduplicate_plan_for_overlapped_chunks"),
-                    Arc::clone(&table_name),
-                    Arc::clone(&output_schema),
-                    overlapped_chunks,
-                    predicate.clone(),
-                    &chunks_dedup_sort_key,
-                    &mut self.schema_interner,
-                )?);
+                let external = self
+                    .external_dedup_min_chunks
+                    .map(|min_chunks| overlapped_chunks.len() >= min_chunks)
+                    .unwrap_or_default();
+                if external {
+                    plans.push(Self::build_external_deduplicate_plan_for_overlapped_chunks(
+                        self.ctx
+                            .child_ctx("build_external_deduplicate_plan_for_overlapped_chunks"),
+                        Arc::clone(&table_name),
+                        Arc::clone(&output_schema),
+                        overlapped_chunks,
+                        predicate.clone(),
+                        &chunks_dedup_sort_key,
+                        &mut self.schema_interner,
+                    )?);
+                } else {
+                    plans.push(Self::build_deduplicate_plan_for_overlapped_chunks(
+                        self.ctx
+                            .child_ctx("build_deduplicate_plan_for_overlapped_chunks"),
+                        Arc::clone(&table_name),
+                        Arc::clone(&output_schema),
+                        overlapped_chunks,
+                        predicate.clone(),
+                        &chunks_dedup_sort_key,
+                        &mut self.schema_interner,
+                    )?);
+                }
             }
 
             // Build a plan for each chunk which may have duplicates,
@@ -781,6 +855,149 @@ impl Deduplicater {
         Self::add_projection_node_if_needed(output_schema, plan)
     }
 
+    /// Return deduplicate plan for the given overlapped chunks that sorts all chunks at once
+    ///
+    /// In contrast to [`build_deduplicate_plan_for_overlapped_chunks`](Self::build_deduplicate_plan_for_overlapped_chunks),
+    /// the chunks are not sorted individually and merged, which requires all chunks to be
+    /// sorted in memory at the same time. Instead, the unsorted chunks are streamed into a single
+    /// SortExec that spills to disk if the memory of the executor is limited. Rows with the same
+    /// primary key are ordered by the chunk they come from, so that rows of newer chunks still
+    /// win during deduplication.
+    ///
+    /// ```text
+    ///               ┌─────────────────┐
+    ///               │ ProjectionExec  │
+    ///               │  (optional)     │
+    ///               └─────────────────┘
+    ///                        ▲
+    ///                        │
+    ///               ┌─────────────────┐
+    ///               │ DeduplicateExec │
+    ///               └─────────────────┘
+    ///                        ▲
+    ///                        │
+    ///               ┌─────────────────┐
+    ///               │    SortExec     │  <-- sorts on sort key + chunk order
+    ///               └─────────────────┘
+    ///                        ▲
+    ///                        │
+    ///          ┌──────────────────────────┐
+    ///          │  CoalescePartitionsExec  │
+    ///          └──────────────────────────┘
+    ///                        ▲
+    ///                        │
+    ///            ┌───────────────────────┐
+    ///            │       UnionExec       │
+    ///            └───────────────────────┘
+    ///                       ▲
+    ///                       │
+    ///           ┌───────────┴───────────┐
+    ///           │                       │
+    ///  ┌─────────────────┐        ┌─────────────────┐
+    ///  │ ProjectionExec  │ ...    │ ProjectionExec  │  <-- adds chunk order
+    ///  └─────────────────┘        └─────────────────┘
+    ///           ▲                          ▲
+    ///           │          ...             │
+    ///           │                          │
+    ///  ┌─────────────────┐        ┌─────────────────┐
+    ///  │IOxReadFilterNode│        │IOxReadFilterNode│
+    ///  │    (Chunk 1)    │ ...    │    (Chunk n)    │
+    ///  └─────────────────┘        └─────────────────┘
+    ///```
+    fn build_external_deduplicate_plan_for_overlapped_chunks(
+        ctx: IOxSessionContext,
+        table_name: Arc<str>,
+        output_schema: Arc<Schema>,
+        chunks: Vec<Arc<dyn QueryChunk>>, // These chunks are identified overlapped
+        predicate: Predicate,
+        output_sort_key: &SortKey,
+        schema_interner: &mut SchemaInterner,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        // Same chunk order as the merge-based plan, see `build_deduplicate_plan_for_overlapped_chunks`
+        let chunks = {
+            let mut chunks = chunks;
+            chunks.sort_unstable_by_key(|c| (c.order(), c.id()));
+            chunks
+        };
+
+        let pk_schema = Self::compute_pk_schema(&chunks, schema_interner);
+        let input_schema = Self::compute_input_schema(&output_schema, &pk_schema, schema_interner);
+
+        debug!(
+            ?output_schema,
+            ?pk_schema,
+            ?input_schema,
+            num_chunks = chunks.len(),
+            "creating external deduplicate plan for overlapped chunks"
+        );
+
+        // Scan each chunk without sorting it and tag its rows with the position of the chunk
+        let chunk_plans = chunks
+            .iter()
+            .enumerate()
+            .map(|(chunk_order, chunk)| {
+                let plan = Self::build_sort_plan_for_read_filter(
+                    ctx.child_ctx("build_sort_plan_for_read_filter"),
+                    Arc::clone(&table_name),
+                    Arc::clone(&input_schema),
+                    Arc::clone(chunk),
+                    predicate.clone(),
+                    None,
+                    schema_interner,
+                )?;
+                Self::add_chunk_order_node(plan, chunk_order as u64)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // Sort all chunks at once
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(CoalescePartitionsExec::new(Arc::new(
+            UnionExec::new(chunk_plans),
+        )));
+        let dedup_sort_exprs = arrow_sort_key_exprs(output_sort_key, &plan.schema());
+        let mut sort_exprs = dedup_sort_exprs.clone();
+        sort_exprs.push(PhysicalSortExpr {
+            expr: physical_col(CHUNK_ORDER_COLUMN, &plan.schema())
+                .context(InternalSelectExprSnafu)?,
+            options: SortOptions {
+                descending: false,
+                nulls_first: false,
+            },
+        });
+        let plan = Arc::new(SortExec::try_new(sort_exprs, plan).context(InternalSortSnafu)?);
+
+        // Deduplicate on the sort key only, rows of the last chunk win
+        let plan = Self::add_deduplicate_node(dedup_sort_exprs, plan);
+
+        // select back to the requested output schema, this also drops the chunk order
+        Self::add_projection_node_if_needed(output_schema, plan)
+    }
+
+    /// Add the [`CHUNK_ORDER_COLUMN`] with the given value to the output of `input`
+    fn add_chunk_order_node(
+        input: Arc<dyn ExecutionPlan>,
+        chunk_order: u64,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let input_schema = input.schema();
+
+        let mut select_exprs = input_schema
+            .fields()
+            .iter()
+            .map(|f| {
+                let field_name = f.name();
+                let physical_expr =
+                    physical_col(field_name, &input_schema).context(InternalSelectExprSnafu)?;
+                Ok((physical_expr, field_name.to_string()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        select_exprs.push((
+            Arc::new(Literal::new(ScalarValue::UInt64(Some(chunk_order)))) as _,
+            CHUNK_ORDER_COLUMN.to_string(),
+        ));
+
+        let plan = ProjectionExec::try_new(select_exprs, input).context(InternalProjectionSnafu)?;
+        Ok(Arc::new(plan))
+    }
+
     /// Return deduplicate plan for a given chunk with duplicates
     /// The plan will look like this
     /// ```text
@@ -1010,15 +1227,15 @@ impl Deduplicater {
             predicate,
         ));
 
-        // Add Filter operator, FilterExec, if the chunk has delete predicates
-        let del_preds = chunk.delete_predicates();
-        let del_preds: Vec<Arc<Predicate>> = del_preds
-            .iter()
-            .map(|pred| Arc::new(pred.as_ref().clone().into()))
-            .collect();
-
+        // Add Filter operator, FilterExec, if the chunk has delete predicates that are not
+        // applied while reading the chunk
+        let del_preds: &[Arc<DeletePredicate>] = if chunk.applies_delete_predicates() {
+            &[]
+        } else {
+            chunk.delete_predicates()
+        };
         trace!(?del_preds, "Chunk delete predicates");
-        let negated_del_expr_val = Predicate::negated_expr(&del_preds[..]);
+        let negated_del_expr_val = delete_predicates_to_filter(del_preds);
         if let Some(negated_del_expr) = negated_del_expr_val {
             debug!(?negated_del_expr, "Logical negated expressions");
 
@@ -1255,7 +1472,10 @@ impl Deduplicater {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::test::{raw_data, TestChunk};
+    use crate::{
+        exec::{Executor, ExecutorConfig, ExecutorType},
+        test::{raw_data, TestChunk},
+    };
     use arrow::datatypes::DataType;
     use arrow_util::{assert_batches_eq, assert_batches_sorted_eq};
     use datafusion::physical_plan::displayable;
@@ -2557,6 +2777,93 @@ mod test {
         assert_batches_eq!(&expected, &batch);
     }
 
+    #[tokio::test]
+    async fn scan_plan_with_two_overlapped_chunks_with_duplicates_external() {
+        test_helpers::maybe_start_logging();
+
+        // test overlapped chunks
+        let chunk1 = Arc::new(
+            TestChunk::new("t")
+                .with_time_column_with_full_stats(
+                    Some(5),
+                    Some(7000),
+                    10,
+                    Some(NonZeroU64::new(7).unwrap()),
+                )
+                .with_tag_column_with_full_stats(
+                    "tag1",
+                    Some("AL"),
+                    Some("MT"),
+                    10,
+                    Some(NonZeroU64::new(3).unwrap()),
+                )
+                .with_i64_field_column("field_int")
+                .with_ten_rows_of_data_some_duplicates(),
+        ) as Arc<dyn QueryChunk>;
+
+        let chunk2 = Arc::new(
+            TestChunk::new("t")
+                .with_time_column_with_full_stats(
+                    Some(5),
+                    Some(7000),
+                    5,
+                    Some(NonZeroU64::new(5).unwrap()),
+                )
+                .with_tag_column_with_full_stats(
+                    "tag1",
+                    Some("AL"),
+                    Some("MT"),
+                    5,
+                    Some(NonZeroU64::new(3).unwrap()),
+                )
+                .with_i64_field_column("field_int")
+                .with_five_rows_of_data(),
+        ) as Arc<dyn QueryChunk>;
+
+        // Datafusion schema of the chunk
+        let schema = chunk1.schema();
+        let chunks = vec![chunk1, chunk2];
+
+        let exec = Executor::new_with_config(ExecutorConfig {
+            num_threads: 1,
+            target_query_partitions: 1,
+            mem_pool_size: None,
+            spill_dir: None,
+            external_dedup_min_chunks: Some(2),
+            aggregate_pushdown: false,
+        });
+        let mut deduplicator = Deduplicater::new(exec.new_context(ExecutorType::Query));
+        let plan = deduplicator
+            .build_scan_plan(Arc::from("t"), schema, chunks, Predicate::default(), None)
+            .unwrap();
+
+        // Both chunks are sorted at once instead of being merged
+        let plan_str = format!("{}", displayable(plan.as_ref()).indent());
+        assert!(plan_str.contains("CoalescePartitionsExec"));
+        assert!(!plan_str.contains("SortPreservingMergeExec"));
+        assert!(plan_str.contains(CHUNK_ORDER_COLUMN));
+
+        let batch = test_collect(plan).await;
+        // Same result as the merge-based plan, see `scan_plan_with_two_overlapped_chunks_with_duplicates`
+        let expected = vec![
+            "+-----------+------+--------------------------------+",
+            "| field_int | tag1 | time                           |",
+            "+-----------+------+--------------------------------+",
+            "| 100       | AL   | 1970-01-01T00:00:00.000000050Z |",
+            "| 70        | CT   | 1970-01-01T00:00:00.000000100Z |",
+            "| 70        | CT   | 1970-01-01T00:00:00.000000500Z |",
+            "| 30        | MT   | 1970-01-01T00:00:00.000000005Z |",
+            "| 1000      | MT   | 1970-01-01T00:00:00.000001Z    |",
+            "| 1000      | MT   | 1970-01-01T00:00:00.000002Z    |",
+            "| 5         | MT   | 1970-01-01T00:00:00.000005Z    |",
+            "| 10        | MT   | 1970-01-01T00:00:00.000007Z    |",
+            "+-----------+------+--------------------------------+",
+        ];
+        assert_batches_eq!(&expected, &batch);
+
+        exec.join().await;
+    }
+
     #[tokio::test]
     async fn non_sorted_scan_plan_with_four_chunks() {
         test_helpers::maybe_start_logging();
@@ -3267,6 +3574,49 @@ mod test {
         assert_eq!(count, 3);
     }
 
+    #[tokio::test]
+    async fn scan_plan_with_dedup_validation() {
+        test_helpers::maybe_start_logging();
+
+        let chunk = Arc::new(
+            TestChunk::new("t")
+                .with_id(1)
+                .with_time_column()
+                .with_tag_column("tag1")
+                .with_i64_field_column("field_int")
+                .with_five_rows_of_data(),
+        ) as Arc<dyn QueryChunk>;
+
+        let ctx = IOxSessionContext::with_testing();
+        let provider = ProviderBuilder::new("t", chunk.schema(), ctx.child_ctx("provider"))
+            .add_chunk(chunk)
+            .with_dedup_validation(true)
+            .build()
+            .unwrap();
+        ctx.inner().register_table("t", Arc::new(provider)).unwrap();
+
+        let plan = ctx.prepare_sql("select * from t").await.unwrap();
+        let plan_str = format!("{}", displayable(plan.as_ref()).indent());
+        assert!(
+            plan_str.contains("DedupValidationExec: [tag1,time]"),
+            "unexpected plan:\n{}",
+            plan_str
+        );
+
+        // validation output is the same as without validation
+        let batches = ctx.collect(plan).await.unwrap();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 5);
+
+        // validation is skipped if the primary key is not part of the output
+        let plan = ctx.prepare_sql("select field_int from t").await.unwrap();
+        let plan_str = format!("{}", displayable(plan.as_ref()).indent());
+        assert!(
+            !plan_str.contains("DedupValidationExec"),
+            "unexpected plan:\n{}",
+            plan_str
+        );
+    }
+
     fn chunk_ids(group: &[Arc<dyn QueryChunk>]) -> String {
         let ids = group
             .iter()
@@ -0,0 +1,421 @@
+//! Implementation of the DedupValidationExec operator (checks that the output of a scan has no
+//! primary key duplicates left)
+use std::{
+    collections::HashSet,
+    fmt,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use arrow::{
+    array::{Array, ArrayRef, TimestampNanosecondArray},
+    datatypes::SchemaRef,
+    error::Result as ArrowResult,
+    record_batch::RecordBatch,
+    util::display::array_value_to_string,
+};
+use data_types::{ChunkId, ChunkOrder, TimestampMinMax};
+use datafusion::{
+    error::Result,
+    execution::context::TaskContext,
+    physical_plan::{
+        expressions::PhysicalSortExpr,
+        metrics::{self, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet},
+        DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
+        SendableRecordBatchStream, Statistics,
+    },
+};
+use futures::{Stream, StreamExt};
+use observability_deps::tracing::warn;
+use parking_lot::Mutex;
+use schema::TIME_COLUMN_NAME;
+
+use crate::QueryChunk;
+
+/// Maximum number of offending keys that are reported per output partition.
+const MAX_REPORTED_KEYS: usize = 10;
+
+/// Description of a chunk that was scanned, used to report which chunks (and therefore which
+/// parquet files) may have contributed a duplicate.
+#[derive(Debug, Clone)]
+pub(crate) struct ChunkInfo {
+    id: ChunkId,
+    chunk_type: String,
+    order: ChunkOrder,
+    time_range: Option<TimestampMinMax>,
+}
+
+impl ChunkInfo {
+    pub(crate) fn new(chunk: &dyn QueryChunk) -> Self {
+        Self {
+            id: chunk.id(),
+            chunk_type: chunk.chunk_type().to_string(),
+            order: chunk.order(),
+            time_range: chunk.summary().and_then(|s| s.time_range()),
+        }
+    }
+
+    /// Returns true if this chunk may contain a row with the given timestamp.
+    fn may_contain(&self, time: Option<i64>) -> bool {
+        match (self.time_range, time) {
+            (Some(range), Some(time)) => range.min <= time && time <= range.max,
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for ChunkInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{} ({:?})", self.chunk_type, self.id, self.order)
+    }
+}
+
+/// # DedupValidationExec
+///
+/// This operator passes its input through unchanged, but checks that no two output rows share
+/// the same primary key. The output of a table scan is expected to be fully deduplicated, so any
+/// duplicate found here is a bug in the deduplication logic (e.g. wrongly grouped overlapping
+/// chunks).
+///
+/// Offending keys, together with the chunks that may have contributed them (based on their time
+/// range), are logged as a warning. The query itself never fails because of a duplicate.
+///
+/// This operator remembers every key it has seen across all input partitions, so it is
+/// expensive and intended for debugging only.
+#[derive(Debug)]
+pub(crate) struct DedupValidationExec {
+    input: Arc<dyn ExecutionPlan>,
+    table_name: Arc<str>,
+    key_columns: Vec<String>,
+    chunks: Arc<Vec<ChunkInfo>>,
+    seen: Arc<Mutex<HashSet<Vec<Option<String>>>>>,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl DedupValidationExec {
+    pub(crate) fn new(
+        input: Arc<dyn ExecutionPlan>,
+        table_name: Arc<str>,
+        key_columns: Vec<String>,
+        chunks: Vec<ChunkInfo>,
+    ) -> Self {
+        Self {
+            input,
+            table_name,
+            key_columns,
+            chunks: Arc::new(chunks),
+            seen: Default::default(),
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+}
+
+impl ExecutionPlan for DedupValidationExec {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.input.output_ordering()
+    }
+
+    fn maintains_input_order(&self) -> bool {
+        true
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        assert_eq!(children.len(), 1);
+        Ok(Arc::new(Self::new(
+            Arc::clone(&children[0]),
+            Arc::clone(&self.table_name),
+            self.key_columns.clone(),
+            self.chunks.as_ref().clone(),
+        )))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let input = self.input.execute(partition, context)?;
+
+        Ok(Box::pin(DedupValidationStream {
+            input,
+            table_name: Arc::clone(&self.table_name),
+            key_columns: self.key_columns.clone(),
+            chunks: Arc::clone(&self.chunks),
+            seen: Arc::clone(&self.seen),
+            num_duplicates: MetricBuilder::new(&self.metrics)
+                .counter("num_duplicate_keys", partition),
+            duplicates: vec![],
+            reported: false,
+        }))
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                write!(f, "DedupValidationExec: [{}]", self.key_columns.join(","))
+            }
+        }
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.input.statistics()
+    }
+}
+
+/// Duplicate key found in the output.
+#[derive(Debug)]
+struct Duplicate {
+    key: Vec<Option<String>>,
+    time: Option<i64>,
+}
+
+struct DedupValidationStream {
+    input: SendableRecordBatchStream,
+    table_name: Arc<str>,
+    key_columns: Vec<String>,
+    chunks: Arc<Vec<ChunkInfo>>,
+    seen: Arc<Mutex<HashSet<Vec<Option<String>>>>>,
+    num_duplicates: metrics::Count,
+    /// The first [`MAX_REPORTED_KEYS`] duplicates found by this stream.
+    duplicates: Vec<Duplicate>,
+    reported: bool,
+}
+
+impl DedupValidationStream {
+    fn check(&mut self, batch: &RecordBatch) {
+        let schema = batch.schema();
+        let columns: Vec<ArrayRef> = self
+            .key_columns
+            .iter()
+            .filter_map(|name| schema.index_of(name).ok())
+            .map(|idx| Arc::clone(batch.column(idx)))
+            .collect();
+        if columns.len() != self.key_columns.len() {
+            // not all key columns are part of the output, nothing to validate
+            return;
+        }
+        let times = schema.index_of(TIME_COLUMN_NAME).ok().and_then(|idx| {
+            batch
+                .column(idx)
+                .as_any()
+                .downcast_ref::<TimestampNanosecondArray>()
+        });
+
+        let mut seen = self.seen.lock();
+        for row in 0..batch.num_rows() {
+            let key = columns
+                .iter()
+                .map(|array| {
+                    if array.is_null(row) {
+                        None
+                    } else {
+                        array_value_to_string(array, row).ok()
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            if !seen.contains(&key) {
+                seen.insert(key);
+                continue;
+            }
+
+            self.num_duplicates.add(1);
+            if self.duplicates.len() < MAX_REPORTED_KEYS {
+                let time = times
+                    .filter(|times| times.is_valid(row))
+                    .map(|times| times.value(row));
+                self.duplicates.push(Duplicate { key, time });
+            }
+        }
+    }
+
+    /// Log the duplicates found by this stream, if any.
+    fn report(&mut self) {
+        if self.reported {
+            return;
+        }
+        self.reported = true;
+
+        let total = self.num_duplicates.value();
+        if total == 0 {
+            return;
+        }
+
+        for Duplicate { key, time } in &self.duplicates {
+            let key = self
+                .key_columns
+                .iter()
+                .zip(key)
+                .map(|(name, value)| format!("{}={}", name, value.as_deref().unwrap_or("NULL")))
+                .collect::<Vec<_>>()
+                .join(",");
+            let chunks = self
+                .chunks
+                .iter()
+                .filter(|chunk| chunk.may_contain(*time))
+                .map(|chunk| chunk.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            warn!(
+                table_name=%self.table_name,
+                %key,
+                %chunks,
+                "duplicate primary key in deduplicated scan output",
+            );
+        }
+
+        warn!(
+            table_name=%self.table_name,
+            total,
+            reported=self.duplicates.len(),
+            "deduplication validation failed",
+        );
+    }
+}
+
+impl RecordBatchStream for DedupValidationStream {
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+}
+
+impl Stream for DedupValidationStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let res = self.input.poll_next_unpin(cx);
+        match &res {
+            Poll::Ready(Some(Ok(batch))) => self.check(batch),
+            Poll::Ready(None) => self.report(),
+            _ => {}
+        }
+        res
+    }
+}
+
+impl Drop for DedupValidationStream {
+    fn drop(&mut self) {
+        // queries with a limit might not consume the stream until the end
+        self.report();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::TestChunk;
+    use arrow::array::{Int64Array, StringArray};
+    use datafusion::physical_plan::memory::MemoryExec;
+    use datafusion_util::test_collect;
+
+    fn batch(tags: Vec<&str>, times: Vec<i64>) -> RecordBatch {
+        let fields = Int64Array::from(vec![1; tags.len()]);
+        RecordBatch::try_from_iter(vec![
+            ("tag", Arc::new(StringArray::from(tags)) as ArrayRef),
+            ("f", Arc::new(fields) as ArrayRef),
+            (
+                TIME_COLUMN_NAME,
+                Arc::new(TimestampNanosecondArray::from(times)) as ArrayRef,
+            ),
+        ])
+        .unwrap()
+    }
+
+    fn make_exec(partitions: Vec<Vec<RecordBatch>>) -> Arc<DedupValidationExec> {
+        let schema = partitions[0][0].schema();
+        let input = Arc::new(MemoryExec::try_new(&partitions, schema, None).unwrap());
+        let chunk = TestChunk::new("t")
+            .with_id(1)
+            .with_time_column_with_stats(Some(1), Some(5));
+
+        Arc::new(DedupValidationExec::new(
+            input,
+            Arc::from("t"),
+            vec!["tag".to_string(), TIME_COLUMN_NAME.to_string()],
+            vec![ChunkInfo::new(&chunk)],
+        ))
+    }
+
+    fn num_duplicates(exec: &DedupValidationExec) -> usize {
+        exec.metrics()
+            .unwrap()
+            .iter()
+            .filter(|m| m.value().name() == "num_duplicate_keys")
+            .map(|m| m.value().as_usize())
+            .sum()
+    }
+
+    #[tokio::test]
+    async fn test_no_duplicates() {
+        let exec = make_exec(vec![vec![
+            batch(vec!["a", "a", "b"], vec![1, 2, 1]),
+            batch(vec!["b"], vec![2]),
+        ]]);
+
+        let output = test_collect(Arc::clone(&exec) as _).await;
+        assert_eq!(output.iter().map(|b| b.num_rows()).sum::<usize>(), 4);
+        assert_eq!(num_duplicates(&exec), 0);
+    }
+
+    #[tokio::test]
+    async fn test_duplicates_are_passed_through() {
+        test_helpers::maybe_start_logging();
+
+        // duplicates within a batch, across batches and across partitions
+        let exec = make_exec(vec![
+            vec![
+                batch(vec!["a", "a", "b"], vec![1, 1, 1]),
+                batch(vec!["b"], vec![1]),
+            ],
+            vec![batch(vec!["a", "c"], vec![1, 1])],
+        ]);
+
+        let output = test_collect(Arc::clone(&exec) as _).await;
+
+        // query output is not modified
+        assert_eq!(output.iter().map(|b| b.num_rows()).sum::<usize>(), 6);
+        assert_eq!(num_duplicates(&exec), 3);
+    }
+
+    #[test]
+    fn test_chunk_info() {
+        let chunk = TestChunk::new("t")
+            .with_id(1)
+            .with_time_column_with_stats(Some(10), Some(20));
+        let info = ChunkInfo::new(&chunk);
+
+        assert!(info.may_contain(Some(10)));
+        assert!(info.may_contain(Some(20)));
+        assert!(!info.may_contain(Some(21)));
+        assert!(info.may_contain(None));
+
+        let chunk = TestChunk::new("t").with_id(2);
+        assert!(ChunkInfo::new(&chunk).may_contain(Some(21)));
+    }
+}
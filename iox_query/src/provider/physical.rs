@@ -9,7 +9,7 @@ use datafusion::{
     execution::context::TaskContext,
     physical_plan::{
         expressions::PhysicalSortExpr,
-        metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet},
+        metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet},
         DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream, Statistics,
     },
 };
@@ -54,6 +54,28 @@ impl IOxReadFilterNode {
             metrics: ExecutionPlanMetricsSet::new(),
         }
     }
+
+    /// The chunks read by this node.
+    pub fn chunks(&self) -> &[Arc<dyn QueryChunk>] {
+        &self.chunks
+    }
+
+    /// The predicate applied while reading the chunks.
+    pub fn predicate(&self) -> &Predicate {
+        &self.predicate
+    }
+
+    /// Create a node that reads `chunks` instead of the chunks of this node, keeping everything
+    /// else.
+    pub fn with_chunks(&self, chunks: Vec<Arc<dyn QueryChunk>>) -> Self {
+        Self::new(
+            self.ctx.child_ctx("with_chunks"),
+            Arc::clone(&self.table_name),
+            Arc::clone(&self.iox_schema),
+            chunks,
+            self.predicate.clone(),
+        )
+    }
 }
 
 impl ExecutionPlan for IOxReadFilterNode {
@@ -117,6 +139,23 @@ impl ExecutionPlan for IOxReadFilterNode {
 
         let chunk = Arc::clone(&self.chunks[partition]);
 
+        // report the scanned chunks by type (e.g. parquet files vs. ingester data), see
+        // `EXPLAIN ANALYZE`
+        let chunk_type = chunk.chunk_type().to_string();
+        MetricBuilder::new(&self.metrics)
+            .with_new_label("chunk_type", chunk_type.clone())
+            .counter("chunks", partition)
+            .add(1);
+        MetricBuilder::new(&self.metrics)
+            .with_new_label("chunk_type", chunk_type)
+            .counter("chunk_rows", partition)
+            .add(
+                chunk
+                    .summary()
+                    .map(|summary| summary.total_count() as usize)
+                    .unwrap_or_default(),
+            );
+
         let chunk_table_schema = chunk.schema();
 
         // The output selection is all the columns in the schema.
@@ -0,0 +1,55 @@
+//! Tracking of queries that may return incomplete results.
+
+use parking_lot::Mutex;
+
+/// Records whether the results of a single query may be incomplete.
+///
+/// Components that answer a query from a degraded set of data sources (e.g. the querier serving
+/// only persisted data while the ingesters are unavailable) mark the query as incomplete, so
+/// that this can be reported back to the client.
+#[derive(Debug, Default)]
+pub struct QueryCompleteness {
+    reasons: Mutex<Vec<String>>,
+}
+
+impl QueryCompleteness {
+    /// Create tracker for a query that is complete so far.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark query results as potentially incomplete.
+    pub fn mark_incomplete(&self, reason: impl Into<String>) {
+        self.reasons.lock().push(reason.into());
+    }
+
+    /// Returns `true` if nobody marked the query results as incomplete.
+    pub fn is_complete(&self) -> bool {
+        self.reasons.lock().is_empty()
+    }
+
+    /// Reasons why the query results may be incomplete, in the order they were reported.
+    pub fn reasons(&self) -> Vec<String> {
+        self.reasons.lock().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completeness() {
+        let completeness = QueryCompleteness::new();
+        assert!(completeness.is_complete());
+        assert!(completeness.reasons().is_empty());
+
+        completeness.mark_incomplete("foo");
+        completeness.mark_incomplete(String::from("bar"));
+        assert!(!completeness.is_complete());
+        assert_eq!(
+            completeness.reasons(),
+            vec![String::from("foo"), String::from("bar")]
+        );
+    }
+}
@@ -0,0 +1,63 @@
+//! Unique identifier of a single query.
+
+use std::{fmt::Display, str::FromStr};
+use uuid::Uuid;
+
+/// Name of the gRPC metadata entry / HTTP header that carries the [`QueryId`].
+///
+/// The querier returns it to clients and passes it on to the ingesters it talks to, so that all
+/// server-side artifacts of a query (logs, traces, query log entries) can be correlated.
+pub const QUERY_ID_HEADER: &str = "iox-query-id";
+
+/// Unique identifier of a single query.
+///
+/// It is assigned when the query enters the system and stays the same across all components that
+/// are involved in answering it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct QueryId(Uuid);
+
+impl QueryId {
+    /// Create a new, random query ID.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+
+    /// Get the inner UUID.
+    pub fn get(&self) -> Uuid {
+        self.0
+    }
+}
+
+impl From<Uuid> for QueryId {
+    fn from(uuid: Uuid) -> Self {
+        Self(uuid)
+    }
+}
+
+impl Display for QueryId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for QueryId {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(Uuid::parse_str(s)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let id = QueryId::new();
+        assert_ne!(id, QueryId::new());
+        assert_eq!(id.to_string().parse::<QueryId>().unwrap(), id);
+        "foo".parse::<QueryId>().unwrap_err();
+    }
+}
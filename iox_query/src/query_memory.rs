@@ -0,0 +1,77 @@
+//! Tracking of memory reserved by a single query.
+
+use std::fmt::Debug;
+
+use parking_lot::Mutex;
+
+/// Holds the memory reservations of a single query.
+///
+/// Components that admit a query based on its (estimated) memory usage attach their reservation
+/// here. The reservations are released once the query is finished, i.e. when the query context
+/// and all execution state derived from it are dropped.
+#[derive(Debug, Default)]
+pub struct QueryMemoryReservation {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    bytes: usize,
+    guards: Vec<Box<dyn Debug + Send + Sync>>,
+}
+
+impl QueryMemoryReservation {
+    /// Create holder for a query that has not reserved any memory yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attach a reservation of `bytes` to the query.
+    ///
+    /// The `guard` is dropped (and with that the reservation released) when the query finishes.
+    pub fn add(&self, bytes: usize, guard: impl Debug + Send + Sync + 'static) {
+        let mut inner = self.inner.lock();
+        inner.bytes = inner.bytes.saturating_add(bytes);
+        inner.guards.push(Box::new(guard));
+    }
+
+    /// Total number of bytes reserved by the query so far.
+    pub fn bytes(&self) -> usize {
+        self.inner.lock().bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_reservation() {
+        #[derive(Debug)]
+        struct Guard(Arc<AtomicBool>);
+
+        impl Drop for Guard {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let released = Arc::new(AtomicBool::new(false));
+
+        let reservation = QueryMemoryReservation::new();
+        assert_eq!(reservation.bytes(), 0);
+
+        reservation.add(10, Guard(Arc::clone(&released)));
+        reservation.add(5, ());
+        assert_eq!(reservation.bytes(), 15);
+        assert!(!released.load(Ordering::SeqCst));
+
+        drop(reservation);
+        assert!(released.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,98 @@
+//! Tracking of the chunks pruned while planning a single query.
+
+use std::collections::BTreeMap;
+
+use parking_lot::Mutex;
+
+/// Pruning statistics of a single table scan.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TablePruningStats {
+    /// Number of chunks that were pruned.
+    pub chunks_pruned: u64,
+
+    /// Number of rows of the pruned chunks.
+    pub rows_pruned: u64,
+
+    /// Number of chunks that were kept.
+    pub chunks_kept: u64,
+}
+
+/// Records how many chunks were pruned for the tables of a single query.
+///
+/// Pruning happens while the query is planned, so the plan itself does not know about the pruned
+/// chunks. Data sources report them here, so that they can be shown by `EXPLAIN ANALYZE`.
+#[derive(Debug, Default)]
+pub struct QueryPruningStats {
+    tables: Mutex<BTreeMap<String, TablePruningStats>>,
+}
+
+impl QueryPruningStats {
+    /// Create tracker for a query that did not prune any chunks yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `chunks` chunks with `rows` rows in total of `table_name` were pruned.
+    pub fn record_pruned(&self, table_name: &str, chunks: u64, rows: u64) {
+        let mut tables = self.tables.lock();
+        let stats = tables.entry(table_name.to_string()).or_default();
+        stats.chunks_pruned += chunks;
+        stats.rows_pruned += rows;
+    }
+
+    /// Record that `chunks` chunks of `table_name` were kept.
+    pub fn record_kept(&self, table_name: &str, chunks: u64) {
+        let mut tables = self.tables.lock();
+        tables
+            .entry(table_name.to_string())
+            .or_default()
+            .chunks_kept += chunks;
+    }
+
+    /// Statistics of all tables, ordered by table name.
+    pub fn tables(&self) -> Vec<(String, TablePruningStats)> {
+        self.tables
+            .lock()
+            .iter()
+            .map(|(table_name, stats)| (table_name.clone(), *stats))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pruning_stats() {
+        let stats = QueryPruningStats::new();
+        assert!(stats.tables().is_empty());
+
+        stats.record_pruned("b", 2, 10);
+        stats.record_kept("b", 1);
+        stats.record_pruned("b", 1, 5);
+        stats.record_kept("a", 3);
+
+        assert_eq!(
+            stats.tables(),
+            vec![
+                (
+                    String::from("a"),
+                    TablePruningStats {
+                        chunks_pruned: 0,
+                        rows_pruned: 0,
+                        chunks_kept: 3,
+                    }
+                ),
+                (
+                    String::from("b"),
+                    TablePruningStats {
+                        chunks_pruned: 3,
+                        rows_pruned: 15,
+                        chunks_kept: 1,
+                    }
+                ),
+            ]
+        );
+    }
+}
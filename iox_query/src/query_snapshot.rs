@@ -0,0 +1,277 @@
+//! Snapshot of the data a query has seen, so that the query can be repeated on the same data.
+//!
+//! While a query is planned, data sources record the [`PartitionSnapshot`] of every partition
+//! they considered. The combined snapshot is returned to the client as an opaque token (see
+//! [`QuerySnapshot::token`]). Re-issuing a query with that token pins it to the recorded
+//! snapshots: data written after the snapshot is ignored, which makes results repeatable (e.g.
+//! for paginated exports).
+//!
+//! A pinned query fails if the data of the snapshot cannot be separated from newer data anymore,
+//! e.g. because it was compacted together with newer data.
+//!
+//! Note that snapshots only cover written data: deletes that were issued after the snapshot was
+//! taken are still applied to pinned queries.
+
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
+
+use data_types::{PartitionId, PartitionSnapshot, SequenceNumber};
+use parking_lot::Mutex;
+use snafu::{OptionExt, ResultExt, Snafu};
+
+/// Version prefix of the token format.
+const TOKEN_VERSION: &str = "v1";
+
+#[derive(Debug, Snafu)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[snafu(display("Invalid query snapshot token: {}", reason))]
+    InvalidToken { reason: String },
+
+    #[snafu(display("Invalid number in query snapshot token: {}", source))]
+    InvalidNumber { source: std::num::ParseIntError },
+}
+
+/// The data of the snapshot a query is pinned to is not available anymore.
+///
+/// Data sources return this (possibly wrapped into other errors) so that services can tell clients
+/// to re-issue the query without a snapshot token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotExpired {
+    /// Why the snapshot expired.
+    pub reason: String,
+}
+
+impl SnapshotExpired {
+    /// Returns `true` if `err` or any of its sources is a [`SnapshotExpired`] error.
+    pub fn is_cause_of(err: &(dyn std::error::Error + 'static)) -> bool {
+        let mut current = Some(err);
+        while let Some(err) = current {
+            if err.is::<Self>() {
+                return true;
+            }
+            current = err.source();
+        }
+        false
+    }
+}
+
+impl Display for SnapshotExpired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Query snapshot expired: {}", self.reason)
+    }
+}
+
+impl std::error::Error for SnapshotExpired {}
+
+/// Snapshot of the partitions seen by a single query, see the [module documentation](self).
+#[derive(Debug, Default)]
+pub struct QuerySnapshot {
+    /// Snapshots the query is pinned to, if any.
+    pinned: Option<SnapshotToken>,
+
+    /// Snapshots recorded while planning the query.
+    observed: Mutex<BTreeMap<PartitionId, PartitionSnapshot>>,
+}
+
+impl QuerySnapshot {
+    /// Create snapshot for a query that sees the latest data.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create snapshot for a query that only sees the data of `token`.
+    pub fn pinned(token: SnapshotToken) -> Self {
+        Self {
+            pinned: Some(token),
+            observed: Default::default(),
+        }
+    }
+
+    /// The partition snapshots the query is pinned to, if any.
+    ///
+    /// Partitions that are not part of a pinned snapshot had no data when the snapshot was taken.
+    pub fn pinned_partitions(&self) -> Option<&BTreeMap<PartitionId, PartitionSnapshot>> {
+        self.pinned.as_ref().map(|token| &token.partitions)
+    }
+
+    /// Record that the query saw `snapshot` of `partition_id`.
+    ///
+    /// Snapshots of the same partition (e.g. from the catalog and the ingester) are merged.
+    pub fn record(&self, partition_id: PartitionId, snapshot: PartitionSnapshot) {
+        let mut observed = self.observed.lock();
+        let merged = match observed.get(&partition_id) {
+            Some(existing) => existing.merge(snapshot),
+            None => snapshot,
+        };
+        observed.insert(partition_id, merged);
+    }
+
+    /// Token that pins a repeated query to the data of this query.
+    ///
+    /// For pinned queries, this is the token the query was pinned to.
+    pub fn token(&self) -> SnapshotToken {
+        match &self.pinned {
+            Some(token) => token.clone(),
+            None => SnapshotToken {
+                partitions: self.observed.lock().clone(),
+            },
+        }
+    }
+}
+
+/// Serializable form of a [`QuerySnapshot`].
+///
+/// The string representation is opaque to clients and only guaranteed to be understood by
+/// servers of the same version.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapshotToken {
+    partitions: BTreeMap<PartitionId, PartitionSnapshot>,
+}
+
+impl SnapshotToken {
+    /// Create token from the snapshots of all partitions.
+    pub fn new(partitions: BTreeMap<PartitionId, PartitionSnapshot>) -> Self {
+        Self { partitions }
+    }
+
+    /// The partition snapshots.
+    pub fn partitions(&self) -> &BTreeMap<PartitionId, PartitionSnapshot> {
+        &self.partitions
+    }
+}
+
+impl Display for SnapshotToken {
+    /// Formats the token as `v1:<partition>=<persisted>/<max>,...`, where a partition without
+    /// persisted data has a persisted sequence number of `-`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:", TOKEN_VERSION)?;
+        for (i, (partition_id, snapshot)) in self.partitions.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}=", partition_id.get())?;
+            match snapshot.persisted_max_sequence_number {
+                Some(persisted) => write!(f, "{}", persisted.get())?,
+                None => write!(f, "-")?,
+            }
+            write!(f, "/{}", snapshot.max_sequence_number.get())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for SnapshotToken {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let entries = s
+            .strip_prefix(TOKEN_VERSION)
+            .and_then(|s| s.strip_prefix(':'))
+            .context(InvalidTokenSnafu {
+                reason: "unknown version",
+            })?;
+
+        let mut partitions = BTreeMap::new();
+        for entry in entries.split(',').filter(|entry| !entry.is_empty()) {
+            let (partition_id, sequence_numbers) =
+                entry.split_once('=').context(InvalidTokenSnafu {
+                    reason: format!("invalid partition entry '{}'", entry),
+                })?;
+            let (persisted, max) = sequence_numbers
+                .split_once('/')
+                .context(InvalidTokenSnafu {
+                    reason: format!("invalid partition entry '{}'", entry),
+                })?;
+
+            let partition_id = PartitionId::new(partition_id.parse().context(InvalidNumberSnafu)?);
+            let persisted_max_sequence_number = match persisted {
+                "-" => None,
+                persisted => Some(SequenceNumber::new(
+                    persisted.parse().context(InvalidNumberSnafu)?,
+                )),
+            };
+            let max_sequence_number = SequenceNumber::new(max.parse().context(InvalidNumberSnafu)?);
+
+            partitions.insert(
+                partition_id,
+                PartitionSnapshot {
+                    persisted_max_sequence_number,
+                    max_sequence_number,
+                },
+            );
+        }
+
+        Ok(Self { partitions })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(persisted: Option<i64>, max: i64) -> PartitionSnapshot {
+        PartitionSnapshot {
+            persisted_max_sequence_number: persisted.map(SequenceNumber::new),
+            max_sequence_number: SequenceNumber::new(max),
+        }
+    }
+
+    #[test]
+    fn test_record() {
+        let query_snapshot = QuerySnapshot::new();
+        assert!(query_snapshot.pinned_partitions().is_none());
+        assert_eq!(query_snapshot.token().to_string(), "v1:");
+
+        query_snapshot.record(PartitionId::new(2), snapshot(Some(10), 10));
+        query_snapshot.record(PartitionId::new(1), snapshot(None, 3));
+        query_snapshot.record(PartitionId::new(2), snapshot(Some(8), 12));
+
+        let token = query_snapshot.token();
+        assert_eq!(token.to_string(), "v1:1=-/3,2=10/12");
+
+        // a pinned query returns the token it was pinned to
+        let pinned = QuerySnapshot::pinned(token.clone());
+        pinned.record(PartitionId::new(3), snapshot(Some(1), 1));
+        assert_eq!(pinned.pinned_partitions(), Some(token.partitions()));
+        assert_eq!(pinned.token(), token);
+    }
+
+    #[test]
+    fn test_snapshot_expired_is_cause_of() {
+        #[derive(Debug, Snafu)]
+        enum Wrapper {
+            #[snafu(display("wrapped: {}", source))]
+            Wrapped { source: SnapshotExpired },
+
+            #[snafu(display("other"))]
+            Other,
+        }
+
+        let expired = SnapshotExpired {
+            reason: "compacted".to_string(),
+        };
+        assert!(SnapshotExpired::is_cause_of(&expired));
+        assert!(SnapshotExpired::is_cause_of(&Wrapper::Wrapped {
+            source: expired
+        }));
+        assert!(!SnapshotExpired::is_cause_of(&Wrapper::Other));
+    }
+
+    #[test]
+    fn test_token_roundtrip() {
+        let token = SnapshotToken::new(BTreeMap::from([
+            (PartitionId::new(1), snapshot(None, 3)),
+            (PartitionId::new(2), snapshot(Some(10), 12)),
+        ]));
+        assert_eq!(token.to_string().parse::<SnapshotToken>().unwrap(), token);
+        assert_eq!(
+            "v1:".parse::<SnapshotToken>().unwrap(),
+            SnapshotToken::default()
+        );
+
+        for invalid in ["", "v2:1=-/3", "v1:1", "v1:1=3", "v1:x=-/3", "v1:1=-/y"] {
+            invalid.parse::<SnapshotToken>().unwrap_err();
+        }
+    }
+}
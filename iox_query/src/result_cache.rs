@@ -0,0 +1,569 @@
+//! Cache of the results of recent queries.
+//!
+//! Dashboards re-issue identical queries every few seconds, while the data they read rarely
+//! changes that often. [`QueryResultCache`] stores the record batches of recent queries, keyed by
+//! the namespace and the (normalized) physical plan of the query. Every entry records a snapshot
+//! of the chunks the query read: their IDs, orders (i.e. the max sequence numbers of the persisted
+//! data) and the number of their delete predicates. A cached result is only served if planning the
+//! same query again yields the same snapshot, so new parquet files, compactions and deletes
+//! invalidate it.
+//!
+//! Only plans that exclusively read [immutable](QueryChunk::is_immutable) chunks are cached. Data
+//! that has not been persisted yet (e.g. ingester data) can change at any time, so queries that
+//! read it are executed as usual.
+
+use std::{any::Any, collections::HashMap, fmt, sync::Arc, task::Poll};
+
+use arrow::{datatypes::SchemaRef, error::Result as ArrowResult, record_batch::RecordBatch};
+use data_types::{ChunkId, ChunkOrder};
+use datafusion::{
+    error::Result,
+    execution::context::TaskContext,
+    physical_plan::{
+        coalesce_partitions::CoalescePartitionsExec, displayable, empty::EmptyExec,
+        expressions::PhysicalSortExpr, memory::MemoryExec, DisplayFormatType, ExecutionPlan,
+        Partitioning, RecordBatchStream, SendableRecordBatchStream, Statistics,
+    },
+};
+use futures::{Stream, StreamExt};
+use metric::U64Counter;
+use observability_deps::tracing::{debug, warn};
+use parking_lot::Mutex;
+
+use crate::{provider::IOxReadFilterNode, QueryChunk};
+
+/// Limits of a [`QueryResultCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryResultCacheConfig {
+    /// Maximum number of cached query results. The least recently used result is evicted first.
+    pub max_entries: usize,
+
+    /// Maximum size of a single cached query result, in bytes. Larger results are not cached.
+    pub max_entry_bytes: usize,
+}
+
+/// Cache of the results of recent queries, see the [module documentation](self).
+#[derive(Debug)]
+pub struct QueryResultCache {
+    config: QueryResultCacheConfig,
+    state: Mutex<CacheState>,
+    metrics: CacheMetrics,
+}
+
+impl QueryResultCache {
+    /// Create an empty cache.
+    pub fn new(config: QueryResultCacheConfig, metric_registry: &metric::Registry) -> Self {
+        Self {
+            config,
+            state: Default::default(),
+            metrics: CacheMetrics::new(metric_registry),
+        }
+    }
+
+    /// Returns a plan that produces the results of `plan` of a query against `namespace`.
+    ///
+    /// If the results of an identical query over the same chunks are cached, the returned plan
+    /// serves them from memory. Otherwise the returned plan executes `plan` and caches its
+    /// results once they were produced completely.
+    pub fn cached_plan(
+        self: &Arc<Self>,
+        namespace: &str,
+        plan: Arc<dyn ExecutionPlan>,
+    ) -> Arc<dyn ExecutionPlan> {
+        let snapshot = match ChunkSnapshot::of_plan(plan.as_ref()) {
+            Some(snapshot) if plan.output_partitioning().partition_count() > 0 => snapshot,
+            _ => {
+                self.metrics.uncacheable.inc(1);
+                return plan;
+            }
+        };
+
+        let key = CacheKey {
+            namespace: namespace.to_string(),
+            plan: displayable(plan.as_ref()).indent().to_string(),
+        };
+
+        let mut state = self.state.lock();
+        match state.get(&key) {
+            Some(entry) if entry.snapshot == snapshot => {
+                debug!(%namespace, "query result cache hit");
+                self.metrics.hit.inc(1);
+                match MemoryExec::try_new(&[entry.batches.clone()], plan.schema(), None) {
+                    Ok(exec) => return Arc::new(exec),
+                    Err(e) => {
+                        warn!(%e, "cannot serve cached query result");
+                        state.remove(&key);
+                    }
+                }
+            }
+            Some(_) => {
+                debug!(%namespace, "query result cache entry is stale");
+                self.metrics.stale.inc(1);
+                state.remove(&key);
+            }
+            None => {
+                self.metrics.miss.inc(1);
+            }
+        }
+        drop(state);
+
+        let input = if plan.output_partitioning().partition_count() > 1 {
+            Arc::new(CoalescePartitionsExec::new(plan)) as _
+        } else {
+            plan
+        };
+
+        Arc::new(ResultCacheExec {
+            input,
+            cache: Arc::clone(self),
+            key,
+            snapshot,
+        })
+    }
+
+    /// Number of cached query results.
+    pub fn len(&self) -> usize {
+        self.state.lock().entries.len()
+    }
+
+    /// Returns true if no query results are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn insert(&self, key: CacheKey, snapshot: Vec<ChunkSnapshot>, batches: Vec<RecordBatch>) {
+        let mut state = self.state.lock();
+        state.insert(
+            key,
+            CacheEntry {
+                snapshot,
+                batches,
+                last_used: 0,
+            },
+        );
+        state.evict(self.config.max_entries);
+    }
+}
+
+#[derive(Debug)]
+struct CacheMetrics {
+    hit: U64Counter,
+    miss: U64Counter,
+    stale: U64Counter,
+    uncacheable: U64Counter,
+}
+
+impl CacheMetrics {
+    fn new(metric_registry: &metric::Registry) -> Self {
+        let lookups = metric_registry.register_metric::<U64Counter>(
+            "query_result_cache_lookups",
+            "Number of query result cache lookups, by result",
+        );
+
+        Self {
+            hit: lookups.recorder(&[("result", "hit")]),
+            miss: lookups.recorder(&[("result", "miss")]),
+            stale: lookups.recorder(&[("result", "stale")]),
+            uncacheable: lookups.recorder(&[("result", "uncacheable")]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    namespace: String,
+
+    /// The rendered physical plan, which contains all (simplified) expressions of the query.
+    plan: String,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    snapshot: Vec<ChunkSnapshot>,
+    batches: Vec<RecordBatch>,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<CacheKey, CacheEntry>,
+
+    /// Logical clock for the least-recently-used eviction.
+    clock: u64,
+}
+
+impl CacheState {
+    fn get(&mut self, key: &CacheKey) -> Option<&CacheEntry> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.entries.get_mut(key).map(|entry| {
+            entry.last_used = clock;
+            &*entry
+        })
+    }
+
+    fn insert(&mut self, key: CacheKey, mut entry: CacheEntry) {
+        self.clock += 1;
+        entry.last_used = self.clock;
+        self.entries.insert(key, entry);
+    }
+
+    fn remove(&mut self, key: &CacheKey) {
+        self.entries.remove(key);
+    }
+
+    /// Evict the least recently used entries until at most `max_entries` are left.
+    fn evict(&mut self, max_entries: usize) {
+        while self.entries.len() > max_entries {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+                .expect("cache is not empty");
+            self.entries.remove(&oldest);
+        }
+    }
+}
+
+/// The state of a chunk that a query read.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct ChunkSnapshot {
+    table_name: String,
+    id: ChunkId,
+    order: ChunkOrder,
+    delete_predicates: usize,
+}
+
+impl ChunkSnapshot {
+    /// Returns the sorted snapshots of all chunks read by `plan`, or `None` if `plan` reads
+    /// mutable chunks or data from other sources than chunks.
+    fn of_plan(plan: &dyn ExecutionPlan) -> Option<Vec<Self>> {
+        let mut snapshot = vec![];
+        Self::collect(plan, &mut snapshot)?;
+        snapshot.sort();
+        Some(snapshot)
+    }
+
+    fn collect(plan: &dyn ExecutionPlan, snapshot: &mut Vec<Self>) -> Option<()> {
+        let children = plan.children();
+        if !children.is_empty() {
+            return children
+                .iter()
+                .try_for_each(|child| Self::collect(child.as_ref(), snapshot));
+        }
+
+        if plan.as_any().is::<EmptyExec>() {
+            return Some(());
+        }
+
+        let node = plan.as_any().downcast_ref::<IOxReadFilterNode>()?;
+        for chunk in node.chunks() {
+            if !chunk.is_immutable() {
+                return None;
+            }
+            snapshot.push(Self::new(chunk.as_ref()));
+        }
+
+        Some(())
+    }
+
+    fn new(chunk: &dyn QueryChunk) -> Self {
+        Self {
+            table_name: chunk.table_name().to_string(),
+            id: chunk.id(),
+            order: chunk.order(),
+            delete_predicates: chunk.delete_predicates().len(),
+        }
+    }
+}
+
+/// Executes a plan and caches its results once they were produced completely.
+#[derive(Debug)]
+struct ResultCacheExec {
+    /// The plan of the query, with a single partition.
+    input: Arc<dyn ExecutionPlan>,
+    cache: Arc<QueryResultCache>,
+    key: CacheKey,
+    snapshot: Vec<ChunkSnapshot>,
+}
+
+impl ExecutionPlan for ResultCacheExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.input.output_ordering()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![Arc::clone(&self.input)]
+    }
+
+    fn with_new_children(
+        self: Arc<Self>,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        assert_eq!(children.len(), 1, "ResultCacheExec has exactly one input");
+
+        Ok(Arc::new(Self {
+            input: Arc::clone(&children[0]),
+            cache: Arc::clone(&self.cache),
+            key: self.key.clone(),
+            snapshot: self.snapshot.clone(),
+        }))
+    }
+
+    fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let inner = self.input.execute(partition, context)?;
+
+        Ok(Box::pin(RecordingStream {
+            inner,
+            recording: Some(Recording {
+                cache: Arc::clone(&self.cache),
+                key: self.key.clone(),
+                snapshot: self.snapshot.clone(),
+                batches: vec![],
+                bytes: 0,
+            }),
+        }))
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default => write!(f, "ResultCacheExec"),
+        }
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.input.statistics()
+    }
+}
+
+/// The results of a query that are recorded for the cache.
+#[derive(Debug)]
+struct Recording {
+    cache: Arc<QueryResultCache>,
+    key: CacheKey,
+    snapshot: Vec<ChunkSnapshot>,
+    batches: Vec<RecordBatch>,
+    bytes: usize,
+}
+
+impl Recording {
+    /// Record `batch`. Returns false if the results are too large to be cached.
+    fn push(&mut self, batch: &RecordBatch) -> bool {
+        self.bytes += batch
+            .columns()
+            .iter()
+            .map(|array| array.get_array_memory_size())
+            .sum::<usize>();
+        self.batches.push(batch.clone());
+        self.bytes <= self.cache.config.max_entry_bytes
+    }
+
+    fn finish(self) {
+        self.cache.insert(self.key, self.snapshot, self.batches);
+    }
+}
+
+/// Passes the batches of a stream through and records them.
+///
+/// The recording is only cached if the stream ends without an error, so partial results (e.g.
+/// of cancelled queries) are never cached.
+struct RecordingStream {
+    inner: SendableRecordBatchStream,
+    recording: Option<Recording>,
+}
+
+impl Stream for RecordingStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let res = futures::ready!(self.inner.poll_next_unpin(cx));
+        match &res {
+            Some(Ok(batch)) => {
+                if let Some(recording) = self.recording.as_mut() {
+                    if !recording.push(batch) {
+                        self.recording = None;
+                    }
+                }
+            }
+            Some(Err(_)) => {
+                self.recording = None;
+            }
+            None => {
+                if let Some(recording) = self.recording.take() {
+                    recording.finish();
+                }
+            }
+        }
+        Poll::Ready(res)
+    }
+}
+
+impl RecordBatchStream for RecordingStream {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{exec::IOxSessionContext, test::TestChunk};
+    use arrow_util::assert_batches_eq;
+    use metric::{Attributes, Metric};
+    use predicate::Predicate;
+
+    fn chunk(id: u128) -> Arc<dyn QueryChunk> {
+        Arc::new(
+            TestChunk::new("t")
+                .with_id(id)
+                .with_tag_column("tag")
+                .with_time_column()
+                .with_one_row_of_data(),
+        )
+    }
+
+    fn scan(chunks: Vec<Arc<dyn QueryChunk>>) -> Arc<dyn ExecutionPlan> {
+        Arc::new(IOxReadFilterNode::new(
+            IOxSessionContext::with_testing(),
+            Arc::from("t"),
+            chunks[0].schema(),
+            chunks,
+            Predicate::default(),
+        ))
+    }
+
+    fn lookups(registry: &metric::Registry, result: &'static str) -> u64 {
+        registry
+            .get_instrument::<Metric<U64Counter>>("query_result_cache_lookups")
+            .unwrap()
+            .get_observer(&Attributes::from(&[("result", result)]))
+            .unwrap()
+            .fetch()
+    }
+
+    #[tokio::test]
+    async fn test_cache() {
+        let registry = metric::Registry::new();
+        let cache = Arc::new(QueryResultCache::new(
+            QueryResultCacheConfig {
+                max_entries: 10,
+                max_entry_bytes: usize::MAX,
+            },
+            &registry,
+        ));
+        let ctx = IOxSessionContext::with_testing();
+        let expected = vec![
+            "+-----+-----------------------------+",
+            "| tag | time                        |",
+            "+-----+-----------------------------+",
+            "| MA  | 1970-01-01T00:00:00.000001Z |",
+            "| MA  | 1970-01-01T00:00:00.000001Z |",
+            "+-----+-----------------------------+",
+        ];
+
+        // first query executes the plan and fills the cache
+        let plan = cache.cached_plan("ns", scan(vec![chunk(1), chunk(2)]));
+        assert!(plan.as_any().is::<ResultCacheExec>());
+        assert!(cache.is_empty());
+        let batches = ctx.collect(plan).await.unwrap();
+        assert_batches_eq!(&expected, &batches);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(lookups(&registry, "miss"), 1);
+
+        // identical query over the same chunks is served from the cache
+        let plan = cache.cached_plan("ns", scan(vec![chunk(1), chunk(2)]));
+        assert!(plan.as_any().is::<MemoryExec>());
+        let batches = ctx.collect(plan).await.unwrap();
+        assert_batches_eq!(&expected, &batches);
+        assert_eq!(lookups(&registry, "hit"), 1);
+
+        // other namespaces have their own entries
+        let plan = cache.cached_plan("other", scan(vec![chunk(1), chunk(2)]));
+        assert!(plan.as_any().is::<ResultCacheExec>());
+        assert_eq!(lookups(&registry, "miss"), 2);
+
+        // a new chunk (e.g. a persisted or compacted file) invalidates the entry
+        let plan = cache.cached_plan("ns", scan(vec![chunk(1), chunk(3)]));
+        assert!(plan.as_any().is::<ResultCacheExec>());
+        assert_eq!(lookups(&registry, "stale"), 1);
+        assert!(cache.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_limits() {
+        let registry = metric::Registry::new();
+        let cache = Arc::new(QueryResultCache::new(
+            QueryResultCacheConfig {
+                max_entries: 1,
+                max_entry_bytes: usize::MAX,
+            },
+            &registry,
+        ));
+        let ctx = IOxSessionContext::with_testing();
+
+        // the least recently used entry is evicted
+        ctx.collect(cache.cached_plan("a", scan(vec![chunk(1)])))
+            .await
+            .unwrap();
+        ctx.collect(cache.cached_plan("b", scan(vec![chunk(1)])))
+            .await
+            .unwrap();
+        assert_eq!(cache.len(), 1);
+        let plan = cache.cached_plan("b", scan(vec![chunk(1)]));
+        assert!(plan.as_any().is::<MemoryExec>());
+        let plan = cache.cached_plan("a", scan(vec![chunk(1)]));
+        assert!(plan.as_any().is::<ResultCacheExec>());
+
+        // results that exceed the size limit are not cached
+        let cache = Arc::new(QueryResultCache::new(
+            QueryResultCacheConfig {
+                max_entries: 1,
+                max_entry_bytes: 1,
+            },
+            &registry,
+        ));
+        ctx.collect(cache.cached_plan("a", scan(vec![chunk(1)])))
+            .await
+            .unwrap();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_uncacheable() {
+        let registry = metric::Registry::new();
+        let cache = Arc::new(QueryResultCache::new(
+            QueryResultCacheConfig {
+                max_entries: 10,
+                max_entry_bytes: usize::MAX,
+            },
+            &registry,
+        ));
+
+        // plans that read other sources than chunks are not cached
+        let memory: Arc<dyn ExecutionPlan> =
+            Arc::new(MemoryExec::try_new(&[vec![]], chunk(1).schema().as_arrow(), None).unwrap());
+        let plan = cache.cached_plan("ns", Arc::clone(&memory));
+        assert!(Arc::ptr_eq(&plan, &memory));
+        assert_eq!(lookups(&registry, "uncacheable"), 1);
+    }
+}
@@ -220,6 +220,9 @@ pub struct TestChunk {
 
     /// Time range of the data
     timestamp_min_max: Option<TimestampMinMax>,
+
+    /// Return value for row_group_statistics()
+    row_group_statistics: Option<Arc<Vec<TableSummary>>>,
 }
 
 /// Implements a method for adding a column with default stats
@@ -299,6 +302,7 @@ impl TestChunk {
             partition_sort_key: None,
             timestamp_min_max: None,
             partition_id: None,
+            row_group_statistics: None,
         }
     }
 
@@ -459,6 +463,12 @@ impl TestChunk {
         self
     }
 
+    /// Set the statistics of the row groups of this chunk
+    pub fn with_row_group_statistics(mut self, row_group_statistics: Vec<TableSummary>) -> Self {
+        self.row_group_statistics = Some(Arc::new(row_group_statistics));
+        self
+    }
+
     impl_with_column!(with_i64_field_column, Int64);
     impl_with_column_no_stats!(with_i64_field_column_no_stats, Int64);
     impl_with_column_with_stats!(with_i64_field_column_with_stats, Int64, i64, I64);
@@ -985,6 +995,10 @@ impl QueryChunk for TestChunk {
         self.order
     }
 
+    fn is_immutable(&self) -> bool {
+        true
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -1022,6 +1036,10 @@ impl QueryChunkMeta for TestChunk {
     fn timestamp_min_max(&self) -> Option<TimestampMinMax> {
         self.timestamp_min_max
     }
+
+    fn row_group_statistics(&self) -> Option<Arc<Vec<TableSummary>>> {
+        self.row_group_statistics.clone()
+    }
 }
 
 /// Return the raw data from the list of chunks
@@ -0,0 +1,322 @@
+//! Golden-file ("snapshot") assertions.
+//!
+//! Complex behavioral tests often end up comparing against enormous inline string vectors (plans, catalog
+//! contents, compactor plans). This module lets such tests compare their output against a reviewed file on disk
+//! instead:
+//!
+//! ```text
+//! <crate>/tests/golden/<name>.golden
+//! ```
+//!
+//! On mismatch the actual output is written next to the expected file as `<name>.actual` and the test panics with
+//! the commands required to inspect and accept the change. Setting the environment variable
+//! [`UPDATE_GOLDEN_ENV`] (e.g. `IOX_UPDATE_GOLDEN=1 cargo test`) overwrites the golden files with the actual output
+//! instead of failing, which is also how new golden files are created.
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use data_types::ColumnType;
+use datafusion::{
+    logical_plan::LogicalPlan,
+    physical_plan::{displayable, ExecutionPlan},
+};
+use iox_catalog::interface::Catalog;
+use iox_query::exec::IOxSessionContext;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Environment variable that, when set, makes golden-file assertions update the expected files instead of failing.
+pub const UPDATE_GOLDEN_ENV: &str = "IOX_UPDATE_GOLDEN";
+
+/// File extension of reviewed golden files.
+const GOLDEN_EXTENSION: &str = "golden";
+
+/// File extension of files containing the actual output of a failed assertion.
+const ACTUAL_EXTENSION: &str = "actual";
+
+/// Matches UUIDs, e.g. object store IDs that are random for every test run.
+static REGEX_UUID: Lazy<Regex> = Lazy::new(|| {
+    Regex::new("[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}").expect("UUID regex")
+});
+
+/// Assert that `actual` matches the golden file `tests/golden/<name>.golden` of the crate that calls this macro.
+///
+/// `name` may contain `/` to group golden files into sub-directories.
+///
+/// See [module-level docs](crate::golden) for how to create and update golden files.
+#[macro_export]
+macro_rules! assert_golden {
+    ($NAME: expr, $ACTUAL: expr) => {
+        $crate::golden::assert_golden_file(
+            $crate::golden::golden_path(env!("CARGO_MANIFEST_DIR"), $NAME),
+            $ACTUAL,
+        )
+    };
+}
+
+/// Path of the golden file `name` for the crate located at `manifest_dir`.
+pub fn golden_path(manifest_dir: impl AsRef<Path>, name: &str) -> PathBuf {
+    let mut path = manifest_dir.as_ref().join("tests").join("golden");
+    path.extend(name.split('/'));
+    path.set_extension(GOLDEN_EXTENSION);
+    path
+}
+
+/// Assert that `actual` matches the content of the file at `expected_path`.
+///
+/// Trailing whitespace of the individual lines and the file is ignored so that golden files survive editors that
+/// strip them.
+///
+/// # Panic
+/// Panics if the content does not match and [`UPDATE_GOLDEN_ENV`] is not set.
+pub fn assert_golden_file(expected_path: impl AsRef<Path>, actual: impl AsRef<str>) {
+    let expected_path = expected_path.as_ref();
+    let actual = normalize_text(actual.as_ref());
+    let actual_path = expected_path.with_extension(ACTUAL_EXTENSION);
+
+    if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+        write_file(expected_path, &actual);
+        // a stale `.actual` file from a previous failure would only be confusing
+        std::fs::remove_file(&actual_path).ok();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(expected_path)
+        .map(|s| normalize_text(&s))
+        .ok();
+
+    if expected.as_deref() == Some(actual.as_str()) {
+        std::fs::remove_file(&actual_path).ok();
+        return;
+    }
+
+    write_file(&actual_path, &actual);
+
+    let reason = if expected.is_some() {
+        "does not match"
+    } else {
+        "does not exist"
+    };
+    panic!(
+        "Golden file {:?} {reason}.\n\
+         \n\
+         actual output:\n\
+         {actual}\n\
+         Possibly helpful commands:\n  \
+         # See diff\n  \
+         diff -du {:?} {:?}\n  \
+         # Update expected\n  \
+         cp -f {:?} {:?}\n  \
+         # Update all golden files\n  \
+         {UPDATE_GOLDEN_ENV}=1 cargo test\n",
+        expected_path, expected_path, actual_path, actual_path, expected_path,
+    );
+}
+
+/// Replace all UUIDs in `s` by a stable placeholder.
+///
+/// The same UUID is always mapped to the same placeholder (numbered by first occurrence), so relations between
+/// lines (e.g. the same file showing up in two plan nodes) are still visible in the golden output.
+pub fn normalize_uuids(s: &str) -> String {
+    let mut seen: Vec<String> = vec![];
+
+    REGEX_UUID
+        .replace_all(s, |caps: &regex::Captures<'_>| {
+            let uuid = caps[0].to_string();
+            let idx = match seen.iter().position(|other| other == &uuid) {
+                Some(idx) => idx,
+                None => {
+                    seen.push(uuid);
+                    seen.len() - 1
+                }
+            };
+            format!("<UUID-{idx}>")
+        })
+        .into_owned()
+}
+
+/// Render a physical plan in indented form, suitable for golden files.
+pub fn format_physical_plan(plan: &Arc<dyn ExecutionPlan>) -> String {
+    normalize_uuids(&displayable(plan.as_ref()).indent().to_string())
+}
+
+/// Render a logical plan in indented form, suitable for golden files.
+pub fn format_logical_plan(plan: &LogicalPlan) -> String {
+    normalize_uuids(&plan.display_indent().to_string())
+}
+
+/// Plan `sql` and render the resulting physical plan, suitable for golden files.
+pub async fn format_sql_plan(ctx: &IOxSessionContext, sql: &str) -> String {
+    let physical_plan = ctx.prepare_sql(sql).await.expect("planning SQL");
+    format_physical_plan(&physical_plan)
+}
+
+/// Render the content of the catalog in a stable, human-readable form, suitable for golden files.
+///
+/// The dump lists namespaces, tables (with their columns), partitions (with their sort keys) and all parquet files
+/// that are NOT marked for deletion. Object store IDs are omitted because they are random. Everything is ordered by
+/// catalog ID, so the output only depends on the order in which the test created the objects.
+pub async fn format_catalog_state(catalog: &dyn Catalog) -> String {
+    use std::fmt::Write;
+
+    let mut repos = catalog.repositories().await;
+    let mut out = String::new();
+
+    let mut namespaces = repos.namespaces().list().await.expect("list namespaces");
+    namespaces.sort_by_key(|ns| ns.id);
+    for namespace in namespaces {
+        writeln!(
+            out,
+            "namespace {} {:?} retention={:?}",
+            namespace.id.get(),
+            namespace.name,
+            namespace.retention_duration,
+        )
+        .unwrap();
+
+        let mut tables = repos
+            .tables()
+            .list_by_namespace_id(namespace.id)
+            .await
+            .expect("list tables");
+        tables.sort_by_key(|t| t.id);
+        for table in tables {
+            writeln!(out, "  table {} {:?}", table.id.get(), table.name).unwrap();
+
+            let mut columns = repos
+                .columns()
+                .list_by_table_id(table.id)
+                .await
+                .expect("list columns");
+            columns.sort_by_key(|c| c.id);
+            for column in columns {
+                writeln!(
+                    out,
+                    "    column {} {:?} {}",
+                    column.id.get(),
+                    column.name,
+                    ColumnType::try_from(column.column_type).expect("valid column type"),
+                )
+                .unwrap();
+            }
+
+            let mut partitions = repos
+                .partitions()
+                .list_by_table_id(table.id)
+                .await
+                .expect("list partitions");
+            partitions.sort_by_key(|p| p.id);
+            for partition in partitions {
+                writeln!(
+                    out,
+                    "    partition {} key={:?} shard={} sort_key={:?}",
+                    partition.id.get(),
+                    partition.partition_key.to_string(),
+                    partition.shard_id.get(),
+                    partition.sort_key,
+                )
+                .unwrap();
+
+                let mut files = repos
+                    .parquet_files()
+                    .list_by_partition_not_to_delete(partition.id)
+                    .await
+                    .expect("list parquet files");
+                files.sort_by_key(|f| f.id);
+                for file in files {
+                    writeln!(
+                        out,
+                        "      file {} level={} time=[{}, {}] max_seq={} rows={} bytes={}",
+                        file.id.get(),
+                        file.compaction_level as i16,
+                        file.min_time.get(),
+                        file.max_time.get(),
+                        file.max_sequence_number.get(),
+                        file.row_count,
+                        file.file_size_bytes,
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
+    out
+}
+
+fn normalize_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for line in s.trim_end().lines() {
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn write_file(path: &Path, content: &str) {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).expect("create golden directory");
+    }
+    std::fs::write(path, content).expect("write golden file");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_golden_path() {
+        assert_eq!(
+            golden_path("/foo/bar", "x/y"),
+            PathBuf::from("/foo/bar/tests/golden/x/y.golden"),
+        );
+    }
+
+    #[test]
+    fn test_normalize_uuids() {
+        let input = "a 00000000-0000-0000-0000-000000000001.parquet\n\
+                     b 0e4b3fa5-54b2-4ec8-9d6a-63bd5a1ac5ad.parquet\n\
+                     c 00000000-0000-0000-0000-000000000001.parquet";
+        let expected = "a <UUID-0>.parquet\n\
+                        b <UUID-1>.parquet\n\
+                        c <UUID-0>.parquet";
+        assert_eq!(normalize_uuids(input), expected);
+    }
+
+    #[test]
+    fn test_normalize_text() {
+        assert_eq!(normalize_text("a  \nb\n\n"), "a\nb\n");
+    }
+
+    #[test]
+    fn test_assert_golden_file_match() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("x.golden");
+        std::fs::write(&path, "foo\nbar   \n").unwrap();
+
+        assert_golden_file(&path, "foo\nbar");
+
+        assert!(!path.with_extension(ACTUAL_EXTENSION).exists());
+    }
+
+    #[test]
+    fn test_assert_golden_file_mismatch() {
+        if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+            // mismatches are accepted in update mode
+            return;
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("x.golden");
+        std::fs::write(&path, "foo\n").unwrap();
+
+        let path_captured = path.clone();
+        let res = std::panic::catch_unwind(move || assert_golden_file(&path_captured, "bar"));
+        assert!(res.is_err());
+
+        let actual = std::fs::read_to_string(path.with_extension(ACTUAL_EXTENSION)).unwrap();
+        assert_eq!(actual, "bar\n");
+    }
+}
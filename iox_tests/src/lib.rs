@@ -10,4 +10,5 @@
     clippy::clone_on_ref_ptr
 )]
 
+pub mod golden;
 pub mod util;
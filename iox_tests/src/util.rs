@@ -5,9 +5,10 @@ use arrow::{
     record_batch::RecordBatch,
 };
 use data_types::{
-    Column, ColumnSet, ColumnType, CompactionLevel, Namespace, NamespaceSchema, ParquetFile,
-    ParquetFileParams, Partition, PartitionId, QueryPool, SequenceNumber, Shard, ShardId,
-    ShardIndex, Table, TableId, TableSchema, Timestamp, Tombstone, TombstoneId, TopicMetadata,
+    Column, ColumnSet, ColumnStatsSet, ColumnType, CompactionLevel, Namespace, NamespaceSchema,
+    ParquetFile, ParquetFileParams, Partition, PartitionId, QueryPool, SequenceNumber, Shard,
+    ShardId, ShardIndex, Table, TableId, TableSchema, Timestamp, Tombstone, TombstoneId,
+    TopicMetadata,
 };
 use datafusion::physical_plan::metrics::Count;
 use iox_catalog::{
@@ -20,7 +21,10 @@ use mutable_batch_lp::test_helpers::lp_to_mutable_batch;
 use object_store::{memory::InMemory, DynObjectStore};
 use observability_deps::tracing::debug;
 use once_cell::sync::Lazy;
-use parquet_file::{metadata::IoxMetadata, storage::ParquetStorage};
+use parquet_file::{
+    metadata::{IoxMetadata, IoxParquetMetaData},
+    storage::ParquetStorage,
+};
 use schema::{
     selection::Selection,
     sort::{adjust_sort_key_columns, compute_sort_key, SortKey},
@@ -432,6 +436,40 @@ impl TestTableBoundShard {
             tombstone,
         })
     }
+
+    /// Create a tombstone that covers the time ranges of `files` and is newer than all of them,
+    /// i.e. that has to be applied to all of them.
+    pub async fn create_tombstone_for_files(
+        self: &Arc<Self>,
+        files: &[&TestParquetFile],
+        predicate: &str,
+    ) -> Arc<TestTombstone> {
+        assert!(!files.is_empty(), "tombstone must cover at least one file");
+        for file in files {
+            assert_eq!(file.parquet_file.table_id, self.table.table.id);
+            assert_eq!(file.parquet_file.shard_id, self.shard.shard.id);
+        }
+
+        let sequence_number = files
+            .iter()
+            .map(|f| f.parquet_file.max_sequence_number.get())
+            .max()
+            .unwrap()
+            + 1;
+        let min_time = files
+            .iter()
+            .map(|f| f.parquet_file.min_time.get())
+            .min()
+            .unwrap();
+        let max_time = files
+            .iter()
+            .map(|f| f.parquet_file.max_time.get())
+            .max()
+            .unwrap();
+
+        self.create_tombstone(sequence_number, min_time, max_time, predicate)
+            .await
+    }
 }
 
 /// A test catalog with specified namespace, shard, table, partition
@@ -489,6 +527,7 @@ impl TestPartition {
             to_delete,
             object_store_id,
             row_count,
+            ..
         } = builder;
 
         let record_batch = record_batch.expect("A record batch is required");
@@ -524,13 +563,29 @@ impl TestPartition {
             compaction_level: CompactionLevel::Initial,
             sort_key: Some(sort_key.clone()),
         };
-        let real_file_size_bytes = create_parquet_file(
+        let (parquet_metadata, real_file_size_bytes) = create_parquet_file(
             ParquetStorage::new(Arc::clone(&self.catalog.object_store)),
             &metadata,
             record_batch.clone(),
         )
         .await;
 
+        let table_catalog_schema = self.table.catalog_schema().await;
+        let column_stats = metadata
+            .to_parquet_file(
+                self.partition.id,
+                real_file_size_bytes,
+                &parquet_metadata,
+                |name| {
+                    table_catalog_schema
+                        .columns
+                        .get(name)
+                        .expect("Column registered")
+                        .id
+                },
+            )
+            .column_stats;
+
         let builder = TestParquetFileBuilder {
             record_batch: Some(record_batch),
             table: Some(table),
@@ -544,6 +599,7 @@ impl TestPartition {
             to_delete,
             object_store_id: Some(object_store_id),
             row_count: None, // will be computed from the record batch again
+            column_stats: Some(column_stats),
         };
 
         let result = self.create_parquet_file_catalog_record(builder).await;
@@ -569,6 +625,7 @@ impl TestPartition {
             to_delete,
             object_store_id,
             row_count,
+            column_stats,
             ..
         } = builder;
 
@@ -609,6 +666,7 @@ impl TestPartition {
             created_at: Timestamp::new(creation_time),
             compaction_level,
             column_set,
+            column_stats: column_stats.unwrap_or_default(),
         };
 
         let mut repos = self.catalog.catalog.repositories().await;
@@ -652,6 +710,7 @@ pub struct TestParquetFileBuilder {
     to_delete: bool,
     object_store_id: Option<Uuid>,
     row_count: Option<usize>,
+    column_stats: Option<ColumnStatsSet>,
 }
 
 impl Default for TestParquetFileBuilder {
@@ -669,6 +728,7 @@ impl Default for TestParquetFileBuilder {
             to_delete: false,
             object_store_id: None,
             row_count: None,
+            column_stats: None,
         }
     }
 }
@@ -751,6 +811,264 @@ impl TestParquetFileBuilder {
     }
 }
 
+/// How the time ranges of the files of a partition relate to each other, see
+/// [`TestScenarioBuilder::with_overlap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlapPattern {
+    /// The files cover consecutive time ranges that don't overlap.
+    None,
+    /// Every file overlaps with the second half of the time range of the previous file.
+    Partial,
+    /// All files cover the same time range.
+    Full,
+}
+
+/// A builder for scenarios of many tables, partitions and parquet files with generated line
+/// protocol.
+///
+/// Every table has a `tag1` tag, a `field_int` field and a `time` column. The files of a
+/// partition are created in order, with increasing max sequence numbers, and the partitions of a
+/// table cover consecutive time ranges.
+#[derive(Debug, Clone)]
+pub struct TestScenarioBuilder {
+    namespace: String,
+    shard_index: i32,
+    num_tables: usize,
+    num_partitions: usize,
+    num_files: usize,
+    rows_per_file: usize,
+    tag_cardinality: usize,
+    start_time: i64,
+    file_duration: i64,
+    overlap: OverlapPattern,
+    compaction_level: CompactionLevel,
+}
+
+impl Default for TestScenarioBuilder {
+    fn default() -> Self {
+        Self {
+            namespace: "ns".to_string(),
+            shard_index: 1,
+            num_tables: 1,
+            num_partitions: 1,
+            num_files: 1,
+            rows_per_file: 10,
+            tag_cardinality: 3,
+            start_time: 0,
+            file_duration: 1_000,
+            overlap: OverlapPattern::None,
+            compaction_level: CompactionLevel::Initial,
+        }
+    }
+}
+
+impl TestScenarioBuilder {
+    /// Specify the name of the namespace to create.
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        self.namespace = namespace.to_string();
+        self
+    }
+
+    /// Specify the index of the shard the partitions are created on.
+    pub fn with_shard_index(mut self, shard_index: i32) -> Self {
+        self.shard_index = shard_index;
+        self
+    }
+
+    /// Specify the number of tables, named `table_0`, `table_1`, ...
+    pub fn with_tables(mut self, num_tables: usize) -> Self {
+        self.num_tables = num_tables;
+        self
+    }
+
+    /// Specify the number of partitions per table, with keys `partition_0`, `partition_1`, ...
+    pub fn with_partitions(mut self, num_partitions: usize) -> Self {
+        self.num_partitions = num_partitions;
+        self
+    }
+
+    /// Specify the number of parquet files per partition.
+    pub fn with_files(mut self, num_files: usize) -> Self {
+        self.num_files = num_files;
+        self
+    }
+
+    /// Specify the number of rows per parquet file.
+    pub fn with_rows_per_file(mut self, rows_per_file: usize) -> Self {
+        self.rows_per_file = rows_per_file;
+        self
+    }
+
+    /// Specify the number of distinct values of the `tag1` tag.
+    pub fn with_tag_cardinality(mut self, tag_cardinality: usize) -> Self {
+        self.tag_cardinality = tag_cardinality;
+        self
+    }
+
+    /// Specify the time, in nanoseconds, of the first row of the first partition.
+    pub fn with_start_time(mut self, start_time: i64) -> Self {
+        self.start_time = start_time;
+        self
+    }
+
+    /// Specify the length, in nanoseconds, of the time range covered by each file. Must be at
+    /// least the number of rows per file, so that the rows have distinct times.
+    pub fn with_file_duration(mut self, file_duration: i64) -> Self {
+        self.file_duration = file_duration;
+        self
+    }
+
+    /// Specify how the time ranges of the files of a partition overlap.
+    pub fn with_overlap(mut self, overlap: OverlapPattern) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    /// Specify the compaction level of all files.
+    pub fn with_compaction_level(mut self, compaction_level: CompactionLevel) -> Self {
+        self.compaction_level = compaction_level;
+        self
+    }
+
+    /// Create the namespace, shard, tables, partitions and files in `catalog`.
+    pub async fn build(self, catalog: &Arc<TestCatalog>) -> TestScenario {
+        assert!(
+            self.rows_per_file > 0,
+            "Parquet file must have at least 1 row"
+        );
+        assert!(
+            self.file_duration >= self.rows_per_file as i64,
+            "File duration must be at least the number of rows per file"
+        );
+
+        let namespace = catalog.create_namespace(&self.namespace).await;
+        let shard = namespace.create_shard(self.shard_index).await;
+
+        // the time range of the files of a partition, relative to the start of the partition
+        let file_ranges: Vec<_> = (0..self.num_files as i64)
+            .map(|file| {
+                let offset = match self.overlap {
+                    OverlapPattern::None => file * self.file_duration,
+                    OverlapPattern::Partial => file * (self.file_duration / 2),
+                    OverlapPattern::Full => 0,
+                };
+                (offset, offset + self.file_duration - 1)
+            })
+            .collect();
+        let partition_duration = file_ranges
+            .iter()
+            .map(|(_, max)| max + 1)
+            .max()
+            .unwrap_or(0);
+
+        let mut tables = Vec::with_capacity(self.num_tables);
+        let mut partitions = Vec::with_capacity(self.num_tables * self.num_partitions);
+        let mut files = Vec::with_capacity(self.num_tables * self.num_partitions * self.num_files);
+        let mut max_seq = 0;
+        for table_index in 0..self.num_tables {
+            let table_name = format!("table_{}", table_index);
+            let table = namespace.create_table(&table_name).await;
+            table.create_column("tag1", ColumnType::Tag).await;
+            table.create_column("field_int", ColumnType::I64).await;
+            table.create_column("time", ColumnType::Time).await;
+
+            let table_shard = table.with_shard(&shard);
+            for partition_index in 0..self.num_partitions {
+                let partition = table_shard
+                    .create_partition(&format!("partition_{}", partition_index))
+                    .await;
+                let partition_start = self.start_time + partition_index as i64 * partition_duration;
+
+                for (min, max) in &file_ranges {
+                    max_seq += 1;
+                    let min_time = partition_start + min;
+                    let max_time = partition_start + max;
+                    let lp = generate_lp(
+                        &table_name,
+                        self.rows_per_file,
+                        self.tag_cardinality,
+                        min_time,
+                        max_time,
+                    );
+                    let builder = TestParquetFileBuilder::default()
+                        .with_line_protocol(&lp)
+                        .with_max_seq(max_seq)
+                        .with_min_time(min_time)
+                        .with_max_time(max_time)
+                        .with_compaction_level(self.compaction_level);
+                    files.push(partition.create_parquet_file(builder).await);
+                }
+
+                partitions.push(partition);
+            }
+
+            tables.push(table);
+        }
+
+        TestScenario {
+            namespace,
+            shard,
+            tables,
+            partitions,
+            files,
+        }
+    }
+}
+
+/// The catalog objects created by a [`TestScenarioBuilder`], in the order of their creation.
+#[allow(missing_docs)]
+pub struct TestScenario {
+    pub namespace: Arc<TestNamespace>,
+    pub shard: Arc<TestShard>,
+    pub tables: Vec<Arc<TestTable>>,
+    pub partitions: Vec<Arc<TestPartition>>,
+    pub files: Vec<TestParquetFile>,
+}
+
+impl TestScenario {
+    /// The files of `partition`, in the order of their creation.
+    pub fn files_of(&self, partition: &TestPartition) -> Vec<&TestParquetFile> {
+        self.files
+            .iter()
+            .filter(|f| f.partition.partition.id == partition.partition.id)
+            .collect()
+    }
+}
+
+/// Generate `rows` rows of line protocol for `table` with the times spread evenly over
+/// `min_time..=max_time` and `tag_cardinality` distinct values of the `tag1` tag.
+pub fn generate_lp(
+    table: &str,
+    rows: usize,
+    tag_cardinality: usize,
+    min_time: i64,
+    max_time: i64,
+) -> String {
+    let step = if rows > 1 {
+        (max_time - min_time) / (rows as i64 - 1)
+    } else {
+        0
+    };
+
+    (0..rows)
+        .map(|row| {
+            let time = if row + 1 == rows {
+                max_time
+            } else {
+                min_time + row as i64 * step
+            };
+            format!(
+                "{},tag1=v{} field_int={}i {}",
+                table,
+                row % tag_cardinality.max(1),
+                row,
+                time
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 async fn update_catalog_sort_key_if_needed(
     partitions_catalog: &mut dyn PartitionRepo,
     partition_id: PartitionId,
@@ -798,13 +1116,12 @@ async fn create_parquet_file(
     store: ParquetStorage,
     metadata: &IoxMetadata,
     record_batch: RecordBatch,
-) -> usize {
+) -> (IoxParquetMetaData, usize) {
     let stream = futures::stream::once(async { Ok(record_batch) });
-    let (_meta, file_size) = store
+    store
         .upload(stream, metadata)
         .await
-        .expect("persisting parquet file should succeed");
-    file_size
+        .expect("persisting parquet file should succeed")
 }
 
 /// A test parquet file of the catalog
@@ -843,6 +1160,14 @@ impl TestParquetFile {
         let table_schema: Schema = table_schema.clone().try_into().unwrap();
         Arc::new(table_schema.select_by_names(&selection).unwrap())
     }
+
+    /// Create a tombstone that covers the time range of this file and is newer than it.
+    pub async fn create_overlapping_tombstone(&self, predicate: &str) -> Arc<TestTombstone> {
+        self.table
+            .with_shard(&self.shard)
+            .create_tombstone_for_files(&[self], predicate)
+            .await
+    }
 }
 
 /// A catalog test tombstone
@@ -867,6 +1192,84 @@ impl TestTombstone {
             .await
             .unwrap();
     }
+
+    /// Return true if the tombstone was marked processed for `parquet_file`
+    pub async fn is_processed_by(&self, parquet_file: &TestParquetFile) -> bool {
+        let mut repos = self.catalog.catalog.repositories().await;
+
+        repos
+            .processed_tombstones()
+            .exist(parquet_file.parquet_file.id, self.tombstone.id)
+            .await
+            .unwrap()
+    }
+
+    /// Assert that the tombstone was marked processed for exactly the given files
+    pub async fn assert_processed_by(&self, parquet_files: &[&TestParquetFile]) {
+        for parquet_file in parquet_files {
+            assert!(
+                self.is_processed_by(parquet_file).await,
+                "tombstone {} not processed by parquet file {}",
+                self.tombstone.id.get(),
+                parquet_file.parquet_file.id.get(),
+            );
+        }
+
+        let count = self
+            .catalog
+            .count_processed_tombstones(self.tombstone.id)
+            .await;
+        assert_eq!(count, parquet_files.len() as i64);
+    }
+
+    /// Return true if the tombstone still exists in the catalog
+    pub async fn exists(&self) -> bool {
+        let mut repos = self.catalog.catalog.repositories().await;
+
+        repos
+            .tombstones()
+            .get_by_id(self.tombstone.id)
+            .await
+            .unwrap()
+            .is_some()
+    }
+
+    /// Return true if the tombstone can be removed, i.e. if it was marked processed by all the
+    /// non-deleted files it applies to: the level 0 files that overlap with it and are older, and
+    /// the level 1 files that overlap with it
+    pub async fn is_removable(&self) -> bool {
+        let mut repos = self.catalog.catalog.repositories().await;
+        let tombstone = &self.tombstone;
+
+        let level_0 = repos
+            .parquet_files()
+            .count_by_overlaps_with_level_0(
+                tombstone.table_id,
+                tombstone.shard_id,
+                tombstone.min_time,
+                tombstone.max_time,
+                tombstone.sequence_number,
+            )
+            .await
+            .unwrap();
+        let level_1 = repos
+            .parquet_files()
+            .count_by_overlaps_with_level_1(
+                tombstone.table_id,
+                tombstone.shard_id,
+                tombstone.min_time,
+                tombstone.max_time,
+            )
+            .await
+            .unwrap();
+        let processed = repos
+            .processed_tombstones()
+            .count_by_tombstone_id(tombstone.id)
+            .await
+            .unwrap();
+
+        level_0 + level_1 == processed
+    }
 }
 
 /// Return the current time
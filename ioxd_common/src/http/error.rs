@@ -1,4 +1,6 @@
-use hyper::{Body, Response, StatusCode};
+use std::time::Duration;
+
+use hyper::{header::RETRY_AFTER, Body, Response, StatusCode};
 use observability_deps::tracing::warn;
 
 /// Constants used in API error codes.
@@ -101,6 +103,9 @@ pub struct HttpApiError {
 
     /// Human-readable message.
     msg: String,
+
+    /// Hint for the client when to retry the request.
+    retry_after: Option<Duration>,
 }
 
 impl HttpApiError {
@@ -109,6 +114,17 @@ impl HttpApiError {
         Self {
             code: code.into(),
             msg: msg.into(),
+            retry_after: None,
+        }
+    }
+
+    /// Tell the client to retry the request after the given duration.
+    ///
+    /// This is sent as a `Retry-After` header, rounded up to full seconds.
+    pub fn with_retry_after(self, retry_after: Duration) -> Self {
+        Self {
+            retry_after: Some(retry_after),
+            ..self
         }
     }
 
@@ -125,10 +141,12 @@ impl HttpApiError {
 
     /// Generate response for this error.
     pub fn response(&self) -> Response<Body> {
-        Response::builder()
-            .status(self.code.status_code())
-            .body(self.body())
-            .unwrap()
+        let mut builder = Response::builder().status(self.code.status_code());
+        if let Some(retry_after) = self.retry_after {
+            let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            builder = builder.header(RETRY_AFTER, secs);
+        }
+        builder.body(self.body()).unwrap()
     }
 
     /// Check if the error is an internal server error.
@@ -1,13 +1,15 @@
 use async_trait::async_trait;
 use clap_blocks::compactor::CompactorConfig;
 use compactor::{
-    handler::{CompactorHandler, CompactorHandlerImpl},
-    server::CompactorServer,
+    compact::ShardAssignment,
+    handler::{CompactorHandler, CompactorHandlerImpl, SchedulerConfig},
+    server::{grpc::GrpcDelegate, CompactorServer},
+    spill::SpillDir,
 };
 use data_types::ShardIndex;
-use hyper::{Body, Request, Response};
+use hyper::{header::CONTENT_TYPE, Body, Method, Request, Response};
 use iox_catalog::interface::Catalog;
-use iox_query::exec::Executor;
+use iox_query::exec::{Executor, ExecutorConfig};
 use iox_time::TimeProvider;
 use ioxd_common::{
     add_service,
@@ -19,7 +21,7 @@ use ioxd_common::{
 };
 use metric::Registry;
 use object_store::DynObjectStore;
-use parquet_file::storage::ParquetStorage;
+use parquet_file::{storage::ParquetStorage, ParquetPathLayout};
 use std::{
     fmt::{Debug, Display},
     sync::Arc,
@@ -37,6 +39,12 @@ pub enum Error {
 
     #[error("shard_index_range_start must be <= shard_index_range_end")]
     ShardIndexRange,
+
+    #[error("shard_index_range_start and shard_index_range_end must be set")]
+    ShardIndexRangeMissing,
+
+    #[error("Cannot set up compaction spill directory: {0}")]
+    SpillDir(std::io::Error),
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -73,17 +81,31 @@ impl<C: CompactorHandler + std::fmt::Debug + 'static> ServerType for CompactorSe
         self.trace_collector.as_ref().map(Arc::clone)
     }
 
-    /// Just return "not found".
+    /// Serve the current activity of the compactor as JSON at `/state`, see
+    /// [`StateSnapshot`](compactor::state::StateSnapshot).
     async fn route_http_request(
         &self,
-        _req: Request<Body>,
+        req: Request<Body>,
     ) -> Result<Response<Body>, Box<dyn HttpApiErrorSource>> {
-        Err(Box::new(IoxHttpError::NotFound))
+        match (req.method(), req.uri().path()) {
+            (&Method::GET, "/state") => {
+                let body = serde_json::to_vec(&self.server.state()).map_err(|e| {
+                    Box::new(IoxHttpError::Serialization(e.to_string()))
+                        as Box<dyn HttpApiErrorSource>
+                })?;
+                Ok(Response::builder()
+                    .header(CONTENT_TYPE, "application/json")
+                    .body(Body::from(body))
+                    .unwrap())
+            }
+            _ => Err(Box::new(IoxHttpError::NotFound)),
+        }
     }
 
-    /// Provide a placeholder gRPC service.
+    /// Configure the gRPC services.
     async fn server_grpc(self: Arc<Self>, builder_input: RpcBuilderInput) -> Result<(), RpcError> {
         let builder = setup_builder!(builder_input, self);
+        add_service!(builder, self.server.grpc().compaction_service());
         serve_builder!(builder);
 
         Ok(())
@@ -98,16 +120,18 @@ impl<C: CompactorHandler + std::fmt::Debug + 'static> ServerType for CompactorSe
     }
 }
 
-/// Simple error struct, we're not really providing an HTTP interface for the compactor.
+/// Errors of the compactor's HTTP interface, which only serves the compactor state.
 #[derive(Debug)]
 pub enum IoxHttpError {
     NotFound,
+    Serialization(String),
 }
 
 impl IoxHttpError {
     fn status_code(&self) -> HttpApiErrorCode {
         match self {
             IoxHttpError::NotFound => HttpApiErrorCode::NotFound,
+            IoxHttpError::Serialization(_) => HttpApiErrorCode::InternalError,
         }
     }
 }
@@ -127,11 +151,13 @@ impl HttpApiErrorSource for IoxHttpError {
 }
 
 /// Instantiate a compactor server
+#[allow(clippy::too_many_arguments)]
 pub async fn create_compactor_server_type(
     common_state: &CommonServerState,
     metric_registry: Arc<metric::Registry>,
     catalog: Arc<dyn Catalog>,
     object_store: Arc<DynObjectStore>,
+    parquet_path_layout: ParquetPathLayout,
     exec: Arc<Executor>,
     time_provider: Arc<dyn TimeProvider>,
     compactor_config: CompactorConfig,
@@ -140,6 +166,7 @@ pub async fn create_compactor_server_type(
         compactor_config,
         catalog,
         object_store,
+        parquet_path_layout,
         exec,
         time_provider,
         Arc::clone(&metric_registry),
@@ -147,7 +174,8 @@ pub async fn create_compactor_server_type(
     .await?;
 
     let compactor_handler = Arc::new(CompactorHandlerImpl::new(compactor));
-    let compactor = CompactorServer::new(metric_registry, compactor_handler);
+    let grpc = GrpcDelegate::new(Arc::clone(&compactor_handler));
+    let compactor = CompactorServer::new(metric_registry, grpc, compactor_handler);
     Ok(Arc::new(CompactorServerType::new(compactor, common_state)))
 }
 
@@ -155,36 +183,77 @@ pub async fn build_compactor_from_config(
     compactor_config: CompactorConfig,
     catalog: Arc<dyn Catalog>,
     object_store: Arc<DynObjectStore>,
+    parquet_path_layout: ParquetPathLayout,
     exec: Arc<Executor>,
     time_provider: Arc<dyn TimeProvider>,
     metric_registry: Arc<Registry>,
 ) -> Result<compactor::compact::Compactor, Error> {
-    if compactor_config.shard_index_range_start > compactor_config.shard_index_range_end {
-        return Err(Error::ShardIndexRange);
-    }
-
     let mut txn = catalog.start_transaction().await?;
     let topic = txn
         .topics()
         .get_by_name(&compactor_config.topic)
         .await?
-        .ok_or(Error::TopicCatalogLookup {
-            topic_name: compactor_config.topic,
+        .ok_or_else(|| Error::TopicCatalogLookup {
+            topic_name: compactor_config.topic.clone(),
         })?;
 
-    let shard_indexes: Vec<_> = (compactor_config.shard_index_range_start
-        ..=compactor_config.shard_index_range_end)
-        .map(ShardIndex::new)
-        .collect();
+    let shard_assignment = if compactor_config.discover_shards {
+        ShardAssignment::Topic(compactor_config.topic)
+    } else {
+        let (start, end) = match (
+            compactor_config.shard_index_range_start,
+            compactor_config.shard_index_range_end,
+        ) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return Err(Error::ShardIndexRangeMissing),
+        };
+        if start > end {
+            return Err(Error::ShardIndexRange);
+        }
 
-    let mut shards = Vec::with_capacity(shard_indexes.len());
-    for k in shard_indexes {
-        let s = txn.shards().create_or_get(&topic, k).await?;
-        shards.push(s.id);
-    }
+        let mut shards = Vec::with_capacity((start..=end).count());
+        for k in (start..=end).map(ShardIndex::new) {
+            let s = txn.shards().create_or_get(&topic, k).await?;
+            shards.push(s.id);
+        }
+        ShardAssignment::Static(shards)
+    };
     txn.commit().await?;
 
-    let parquet_store = ParquetStorage::new(object_store);
+    let parquet_store = ParquetStorage::new(object_store).with_path_layout(parquet_path_layout);
+
+    let hot_scheduler = SchedulerConfig {
+        tick_interval: compactor_config.hot_tick_interval,
+        max_concurrent_partitions: compactor_config.hot_max_concurrent_partitions,
+    };
+    let cold_scheduler = SchedulerConfig {
+        tick_interval: compactor_config.cold_tick_interval,
+        max_concurrent_partitions: compactor_config.cold_max_concurrent_partitions,
+    };
+    // cold compaction gets its own thread pool if requested, so that it can't starve hot
+    // compaction. Its memory limit needs an executor of its own, too.
+    let cold_exec_mem_pool_bytes =
+        Some(compactor_config.cold_exec_mem_pool_bytes).filter(|b| *b > 0);
+    let cold_exec = (compactor_config.cold_exec_thread_count.is_some()
+        || cold_exec_mem_pool_bytes.is_some())
+    .then(|| {
+        let num_threads = compactor_config
+            .cold_exec_thread_count
+            .unwrap_or_else(|| exec.num_threads());
+        Arc::new(Executor::new_with_config(ExecutorConfig {
+            num_threads,
+            target_query_partitions: num_threads,
+            mem_pool_size: cold_exec_mem_pool_bytes,
+            spill_dir: None,
+            external_dedup_min_chunks: None,
+            aggregate_pushdown: false,
+        }))
+    });
+    let spill_dir = compactor_config
+        .spill_dir
+        .map(|root| SpillDir::new(root, compactor_config.spill_quota_bytes, &metric_registry))
+        .transpose()
+        .map_err(Error::SpillDir)?;
 
     let compactor_config = compactor::handler::CompactorConfig::new(
         compactor_config.max_desired_file_size_bytes,
@@ -197,10 +266,22 @@ pub async fn build_compactor_from_config(
         compactor_config.cold_input_file_count_threshold,
         compactor_config.hot_multiple,
         compactor_config.memory_budget_bytes,
+        compactor_config.write_reports,
+        hot_scheduler,
+        cold_scheduler,
+    )
+    .with_split_interval(compactor_config.split_interval)
+    .with_upper_level_file_sizes_bytes(&compactor_config.upper_level_file_sizes_bytes)
+    .with_debt_metrics(
+        Some(compactor_config.debt_interval).filter(|d| !d.is_zero()),
+        compactor_config.debt_level_0_age,
+    )
+    .with_hot_persist_grace_period(
+        Some(compactor_config.hot_persist_grace_period).filter(|p| !p.is_zero()),
     );
 
-    Ok(compactor::compact::Compactor::new(
-        shards,
+    let compactor = compactor::compact::Compactor::new(
+        shard_assignment,
         catalog,
         parquet_store,
         exec,
@@ -208,5 +289,15 @@ pub async fn build_compactor_from_config(
         backoff::BackoffConfig::default(),
         compactor_config,
         metric_registry,
-    ))
+    );
+
+    let compactor = match cold_exec {
+        Some(cold_exec) => compactor.with_cold_executor(cold_exec),
+        None => compactor,
+    };
+
+    Ok(match spill_dir {
+        Some(spill_dir) => compactor.with_spill_dir(spill_dir),
+        None => compactor,
+    })
 }
@@ -1,6 +1,6 @@
 use async_trait::async_trait;
 use clap_blocks::{ingester::IngesterConfig, write_buffer::WriteBufferConfig};
-use data_types::ShardIndex;
+use data_types::{ParquetPathLayout, ShardIndex};
 use hyper::{Body, Request, Response};
 use ingester::{
     handler::{IngestHandler, IngestHandlerImpl},
@@ -136,11 +136,13 @@ impl HttpApiErrorSource for IoxHttpError {
 }
 
 /// Instantiate an ingester server type
+#[allow(clippy::too_many_arguments)]
 pub async fn create_ingester_server_type(
     common_state: &CommonServerState,
     metric_registry: Arc<metric::Registry>,
     catalog: Arc<dyn Catalog>,
     object_store: Arc<DynObjectStore>,
+    parquet_path_layout: ParquetPathLayout,
     exec: Arc<Executor>,
     write_buffer_config: &WriteBufferConfig,
     ingester_config: IngesterConfig,
@@ -189,6 +191,7 @@ pub async fn create_ingester_server_type(
             shards,
             catalog,
             object_store,
+            parquet_path_layout,
             write_buffer,
             exec,
             Arc::clone(&metric_registry),
@@ -0,0 +1,548 @@
+//! HTTP query endpoint for the `querier`.
+//!
+//! This is a lightweight alternative to the Flight API for integrations that cannot speak gRPC / Arrow. Results are
+//! NOT buffered: every [`RecordBatch`] is encoded as soon as hyper asks for the next body chunk (chunked transfer
+//! encoding), so a slow client naturally applies backpressure to the query execution and the memory usage of the
+//! endpoint is bound by the size of a single batch.
+//!
+//! Two routes are supported:
+//!
+//! ```text
+//! GET  /api/v3/query?namespace=<name>&q=<sql>[&format=csv|jsonl]
+//! POST /api/v3/query?namespace=<name>[&format=csv|jsonl]    (SQL query as request body)
+//! ```
+//!
+//! If no `format` parameter is given, the output format is negotiated using the `Accept` header, see
+//! [`QueryOutputFormat::from_accept`].
+//!
+//! InfluxQL queries of InfluxDB 1.x clients are answered by the `/query` endpoint, see [`v1`].
+use std::{
+    pin::Pin,
+    str::Utf8Error,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use arrow::{error::ArrowError, record_batch::RecordBatch};
+use bytes::Bytes;
+use data_types::{DatabaseName, DatabaseNameError};
+use datafusion::{error::DataFusionError, physical_plan::SendableRecordBatchStream};
+use futures::{Stream, StreamExt};
+use hyper::{
+    header::{ACCEPT, CONTENT_TYPE},
+    Body, Method, Request, Response,
+};
+use iox_query::{exec::ExecutionContextProvider, QueryCompletedToken, QueryDatabase};
+use ioxd_common::http::{
+    error::{HttpApiError, HttpApiErrorCode, HttpApiErrorSource},
+    utils::{parse_body, ParseBodyError},
+};
+use observability_deps::tracing::{info, warn};
+use serde::Deserialize;
+use service_common::{planner::Planner, QueryDatabaseProvider, QueryQueueFull};
+use thiserror::Error;
+use trace::{ctx::SpanContext, span::SpanExt};
+use tracker::InstrumentedAsyncOwnedSemaphorePermit;
+
+mod v1;
+
+use v1::V1_QUERY_PATH;
+
+/// Path of the query endpoint.
+const QUERY_PATH: &str = "/api/v3/query";
+
+/// Errors returned by the `querier` HTTP request handler.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The requested path has no registered handler.
+    #[error("not found")]
+    NoHandler,
+
+    /// The query string could not be parsed.
+    #[error("invalid query string: {0}")]
+    InvalidQueryString(#[from] serde_urlencoded::de::Error),
+
+    /// The request did not contain a query.
+    #[error("no query provided")]
+    NoQuery,
+
+    /// The request did not name a database.
+    #[error("no database provided")]
+    NoDatabase,
+
+    /// The namespace name is invalid.
+    #[error("invalid namespace name: {0}")]
+    InvalidNamespaceName(#[from] DatabaseNameError),
+
+    /// The namespace does not exist.
+    #[error("namespace not found: {0}")]
+    NamespaceNotFound(String),
+
+    /// The requested output format is not supported.
+    #[error("unsupported output format: {0}")]
+    UnsupportedFormat(String),
+
+    /// The `Accept` header cannot be read.
+    #[error("invalid accept header: {0}")]
+    NonUtf8AcceptHeader(hyper::header::ToStrError),
+
+    /// The request body could not be read.
+    #[error("cannot read request body: {0}")]
+    ParseBody(#[from] ParseBodyError),
+
+    /// The request body content is not valid utf8.
+    #[error("body content is not valid utf8: {0}")]
+    NonUtf8Body(Utf8Error),
+
+    /// The query could not be planned.
+    #[error("error planning query: {0}")]
+    Planning(DataFusionError),
+
+    /// The query could not be executed.
+    #[error("error executing query: {0}")]
+    Execution(DataFusionError),
+
+    /// Too many queries of the namespace are already waiting.
+    #[error("namespace {0}: {1}")]
+    QueueFull(String, QueryQueueFull),
+}
+
+impl HttpApiErrorSource for Error {
+    fn to_http_api_error(&self) -> HttpApiError {
+        let code = match self {
+            Self::NoHandler | Self::NamespaceNotFound(_) => HttpApiErrorCode::NotFound,
+            Self::InvalidQueryString(_)
+            | Self::NoQuery
+            | Self::NoDatabase
+            | Self::InvalidNamespaceName(_)
+            | Self::NonUtf8AcceptHeader(_)
+            | Self::NonUtf8Body(_)
+            | Self::Planning(_) => HttpApiErrorCode::Invalid,
+            Self::UnsupportedFormat(_) => HttpApiErrorCode::UnsupportedMediaType,
+            Self::ParseBody(ParseBodyError::RequestSizeExceeded { .. }) => {
+                HttpApiErrorCode::RequestTooLarge
+            }
+            Self::ParseBody(_) => HttpApiErrorCode::Invalid,
+            Self::Execution(_) => HttpApiErrorCode::InternalError,
+            Self::QueueFull(_, _) => HttpApiErrorCode::TooManyRequests,
+        };
+
+        HttpApiError::new(code, self.to_string())
+    }
+}
+
+/// Output format of the HTTP query endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryOutputFormat {
+    /// Comma separated values with a header line.
+    Csv,
+
+    /// One JSON object per row and line (<https://jsonlines.org/>).
+    #[serde(alias = "ndjson")]
+    Jsonl,
+}
+
+impl QueryOutputFormat {
+    /// Value of the `Content-Type` header for responses in this format.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Jsonl => "application/x-ndjson",
+        }
+    }
+
+    /// Pick the output format based on the value of an `Accept` header.
+    ///
+    /// The media ranges are tried in the order of their quality values (order of appearance for equal qualities)
+    /// and the first supported one wins. A missing header or a wildcard results in JSON lines.
+    pub fn from_accept(accept: Option<&str>) -> Result<Self, Error> {
+        let accept = match accept {
+            Some(accept) if !accept.trim().is_empty() => accept,
+            _ => return Ok(Self::Jsonl),
+        };
+
+        let mut ranges: Vec<(&str, f32)> = accept
+            .split(',')
+            .map(|range| {
+                let mut parts = range.split(';').map(str::trim);
+                let media_type = parts.next().unwrap_or_default();
+                let quality = parts
+                    .find_map(|param| param.strip_prefix("q="))
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                (media_type, quality)
+            })
+            .filter(|(_, quality)| *quality > 0.0)
+            .collect();
+        // stable sort, so equal qualities keep the client's order
+        ranges.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        ranges
+            .into_iter()
+            .find_map(
+                |(media_type, _)| match media_type.to_ascii_lowercase().as_str() {
+                    "text/csv" | "text/*" => Some(Self::Csv),
+                    "application/x-ndjson"
+                    | "application/jsonl"
+                    | "application/json"
+                    | "application/*"
+                    | "*/*" => Some(Self::Jsonl),
+                    _ => None,
+                },
+            )
+            .ok_or_else(|| Error::UnsupportedFormat(accept.to_string()))
+    }
+}
+
+/// Query string of the query endpoint.
+#[derive(Debug, Deserialize)]
+struct QueryParams {
+    namespace: String,
+    q: Option<String>,
+    format: Option<QueryOutputFormat>,
+}
+
+/// This type is responsible for servicing requests to the `querier` HTTP endpoint.
+///
+/// Requests to some paths may be handled externally by the caller - the IOx server runner framework takes care of
+/// implementing the heath endpoint, metrics, pprof, etc.
+#[derive(Debug)]
+pub struct HttpDelegate<D> {
+    database: Arc<D>,
+    max_request_bytes: usize,
+}
+
+impl<D> HttpDelegate<D>
+where
+    D: QueryDatabaseProvider,
+{
+    /// Create new delegate that answers queries using `database`.
+    ///
+    /// Request bodies (i.e. SQL queries send via `POST`) are limited to `max_request_bytes`.
+    pub fn new(database: Arc<D>, max_request_bytes: usize) -> Self {
+        Self {
+            database,
+            max_request_bytes,
+        }
+    }
+
+    /// Routes `req` to the appropriate handler, if any, returning the handler response.
+    pub async fn route(&self, req: Request<Body>) -> Result<Response<Body>, Error> {
+        match (req.method(), req.uri().path()) {
+            (&Method::GET, QUERY_PATH) | (&Method::POST, QUERY_PATH) => self.query(req).await,
+            (&Method::GET, V1_QUERY_PATH) | (&Method::POST, V1_QUERY_PATH) => {
+                self.v1_query(req).await
+            }
+            _ => Err(Error::NoHandler),
+        }
+    }
+
+    async fn query(&self, req: Request<Body>) -> Result<Response<Body>, Error> {
+        let span_ctx: Option<SpanContext> = req.extensions().get().cloned();
+
+        let params: QueryParams =
+            serde_urlencoded::from_str(req.uri().query().unwrap_or_default())?;
+        let format = match params.format {
+            Some(format) => format,
+            None => {
+                let accept = req
+                    .headers()
+                    .get(ACCEPT)
+                    .map(|v| v.to_str().map_err(Error::NonUtf8AcceptHeader))
+                    .transpose()?;
+                QueryOutputFormat::from_accept(accept)?
+            }
+        };
+        let namespace = DatabaseName::new(params.namespace)?;
+
+        let sql = match (req.method(), params.q) {
+            (&Method::GET, Some(q)) => q,
+            (&Method::GET, None) => return Err(Error::NoQuery),
+            (_, _) => {
+                let body = parse_body(req, self.max_request_bytes).await?;
+                std::str::from_utf8(&body)
+                    .map_err(Error::NonUtf8Body)?
+                    .to_string()
+            }
+        };
+        if sql.trim().is_empty() {
+            return Err(Error::NoQuery);
+        }
+
+        let namespace_permit = self
+            .database
+            .acquire_namespace_semaphore(
+                &namespace,
+                span_ctx.child_span("namespace query semaphore"),
+            )
+            .await
+            .map_err(|e| Error::QueueFull(namespace.to_string(), e))?;
+        let permit = self
+            .database
+            .acquire_semaphore(span_ctx.child_span("query rate limit semaphore"))
+            .await;
+        info!(%namespace, %sql, ?format, "HTTP query");
+
+        let db = self
+            .database
+            .db(&namespace, span_ctx.child_span("get namespace"))
+            .await
+            .ok_or_else(|| Error::NamespaceNotFound(namespace.to_string()))?;
+
+        let ctx = db.new_query_context(span_ctx);
+        let query_completed_token = db.record_query(&ctx, "sql", Box::new(sql.clone()));
+
+        let physical_plan = Planner::new(&ctx)
+            .sql(sql, vec![])
+            .await
+            .map_err(Error::Planning)?;
+        let physical_plan = db.cached_plan(physical_plan);
+        let batches = ctx
+            .execute_stream(physical_plan)
+            .await
+            .map_err(Error::Execution)?;
+
+        let stream = QueryResponseStream {
+            batches,
+            format,
+            first: true,
+            done: false,
+            query_completed_token,
+            permit,
+            namespace_permit,
+        };
+
+        Ok(Response::builder()
+            .header(CONTENT_TYPE, format.content_type())
+            .body(Body::wrap_stream(stream))
+            .expect("valid response"))
+    }
+}
+
+/// Encodes a [`RecordBatch`] stream into the body of a HTTP response.
+///
+/// The query is marked as successful once the underlying stream is exhausted. The query permits are held until the
+/// response is fully sent (or the client disconnected).
+struct QueryResponseStream {
+    batches: SendableRecordBatchStream,
+    format: QueryOutputFormat,
+    first: bool,
+    done: bool,
+    query_completed_token: QueryCompletedToken,
+    #[allow(dead_code)]
+    permit: InstrumentedAsyncOwnedSemaphorePermit,
+    #[allow(dead_code)]
+    namespace_permit: Option<InstrumentedAsyncOwnedSemaphorePermit>,
+}
+
+impl QueryResponseStream {
+    fn encode(&mut self, batch: &RecordBatch) -> Result<Bytes, ArrowError> {
+        let mut buf = Vec::new();
+
+        match self.format {
+            QueryOutputFormat::Csv => {
+                arrow::csv::WriterBuilder::new()
+                    .has_headers(self.first)
+                    .build(&mut buf)
+                    .write(batch)?;
+            }
+            QueryOutputFormat::Jsonl => {
+                let mut writer = arrow::json::LineDelimitedWriter::new(&mut buf);
+                writer.write_batches(std::slice::from_ref(batch))?;
+                writer.finish()?;
+            }
+        }
+
+        self.first = false;
+        Ok(buf.into())
+    }
+}
+
+impl Stream for QueryResponseStream {
+    type Item = Result<Bytes, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+
+            match futures::ready!(this.batches.poll_next_unpin(cx)) {
+                Some(Ok(batch)) => {
+                    let res = this
+                        .encode(&batch)
+                        .map_err(|e| Error::Execution(DataFusionError::ArrowError(e)));
+                    match res {
+                        // don't emit empty chunks, they would terminate the chunked transfer encoding
+                        Ok(bytes) if bytes.is_empty() => continue,
+                        Ok(bytes) => return Poll::Ready(Some(Ok(bytes))),
+                        Err(e) => {
+                            warn!(%e, "error encoding HTTP query response");
+                            this.done = true;
+                            return Poll::Ready(Some(Err(e)));
+                        }
+                    }
+                }
+                Some(Err(e)) => {
+                    // The response head is already sent, so the only way to signal this error to the client is to
+                    // abort the body.
+                    warn!(%e, "error executing HTTP query");
+                    this.done = true;
+                    return Poll::Ready(Some(Err(Error::Execution(e))));
+                }
+                None => {
+                    this.done = true;
+                    this.query_completed_token.set_success();
+
+                    // CSV clients still expect a header line for empty results
+                    if this.first && this.format == QueryOutputFormat::Csv {
+                        let batch = RecordBatch::new_empty(this.batches.schema());
+                        if let Ok(bytes) = this.encode(&batch) {
+                            return Poll::Ready(Some(Ok(bytes)));
+                        }
+                    }
+
+                    return Poll::Ready(None);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use service_common::test_util::TestDatabaseStore;
+
+    use super::*;
+
+    #[test]
+    fn test_from_accept() {
+        assert_eq!(
+            QueryOutputFormat::from_accept(None).unwrap(),
+            QueryOutputFormat::Jsonl
+        );
+        assert_eq!(
+            QueryOutputFormat::from_accept(Some("*/*")).unwrap(),
+            QueryOutputFormat::Jsonl
+        );
+        assert_eq!(
+            QueryOutputFormat::from_accept(Some("text/csv")).unwrap(),
+            QueryOutputFormat::Csv
+        );
+        assert_eq!(
+            QueryOutputFormat::from_accept(Some("application/x-ndjson, text/csv")).unwrap(),
+            QueryOutputFormat::Jsonl
+        );
+        assert_eq!(
+            QueryOutputFormat::from_accept(Some("application/x-ndjson;q=0.5, text/csv")).unwrap(),
+            QueryOutputFormat::Csv
+        );
+        assert_eq!(
+            QueryOutputFormat::from_accept(Some("text/html, text/csv;q=0.1")).unwrap(),
+            QueryOutputFormat::Csv
+        );
+        assert!(matches!(
+            QueryOutputFormat::from_accept(Some("text/html")),
+            Err(Error::UnsupportedFormat(_))
+        ));
+        assert!(matches!(
+            QueryOutputFormat::from_accept(Some("text/csv;q=0")),
+            Err(Error::UnsupportedFormat(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_query_csv() {
+        let delegate = delegate().await;
+
+        let req = Request::builder()
+            .uri("https://bananas.example/api/v3/query?namespace=my_db&q=SELECT%201%20AS%20a")
+            .header(ACCEPT, "text/csv")
+            .body(Body::empty())
+            .unwrap();
+        let resp = delegate.route(req).await.unwrap();
+
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            "text/csv; charset=utf-8"
+        );
+        assert_eq!(body_to_string(resp).await, "a\n1\n");
+    }
+
+    #[tokio::test]
+    async fn test_query_jsonl_post() {
+        let delegate = delegate().await;
+
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("https://bananas.example/api/v3/query?namespace=my_db&format=jsonl")
+            .header(ACCEPT, "text/csv")
+            .body(Body::from("SELECT 1 AS a"))
+            .unwrap();
+        let resp = delegate.route(req).await.unwrap();
+
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            "application/x-ndjson"
+        );
+        assert_eq!(body_to_string(resp).await, "{\"a\":1}\n");
+    }
+
+    #[tokio::test]
+    async fn test_query_errors() {
+        let delegate = delegate().await;
+
+        let req = Request::builder()
+            .uri("https://bananas.example/api/v3/bananas")
+            .body(Body::empty())
+            .unwrap();
+        assert!(matches!(delegate.route(req).await, Err(Error::NoHandler)));
+
+        let req = Request::builder()
+            .uri("https://bananas.example/api/v3/query?namespace=my_db")
+            .body(Body::empty())
+            .unwrap();
+        assert!(matches!(delegate.route(req).await, Err(Error::NoQuery)));
+
+        let req = Request::builder()
+            .uri("https://bananas.example/api/v3/query?namespace=unknown&q=SELECT%201")
+            .body(Body::empty())
+            .unwrap();
+        assert!(matches!(
+            delegate.route(req).await,
+            Err(Error::NamespaceNotFound(_))
+        ));
+
+        let req = Request::builder()
+            .uri("https://bananas.example/api/v3/query?namespace=my_db&q=SELEC")
+            .body(Body::empty())
+            .unwrap();
+        assert!(matches!(delegate.route(req).await, Err(Error::Planning(_))));
+
+        let req = Request::builder()
+            .uri("https://bananas.example/api/v3/query?namespace=my_db&q=SELECT%201")
+            .header(ACCEPT, "text/html")
+            .body(Body::empty())
+            .unwrap();
+        let err = delegate.route(req).await.unwrap_err();
+        assert!(matches!(err, Error::UnsupportedFormat(_)));
+        assert_eq!(
+            err.to_http_api_error().response().status(),
+            hyper::StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+    }
+
+    async fn delegate() -> HttpDelegate<TestDatabaseStore> {
+        let database = Arc::new(TestDatabaseStore::new());
+        database.db_or_create("my_db").await;
+        HttpDelegate::new(database, 1024)
+    }
+
+    async fn body_to_string(resp: Response<Body>) -> String {
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+}
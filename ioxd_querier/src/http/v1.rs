@@ -0,0 +1,566 @@
+//! InfluxDB 1.x compatible InfluxQL query endpoint of the `querier`.
+//!
+//! ```text
+//! GET  /query?db=<name>&q=<influxql>[&epoch=ns|u|µ|ms|s|m|h][&pretty=true]
+//! POST /query?db=<name>[&epoch=...]    (parameters as form or InfluxQL query as request body)
+//! ```
+//!
+//! The response has the JSON structure of InfluxDB 1.x, so that 1.x clients such as Grafana's
+//! InfluxQL data source can query IOx directly. The database `db` is the namespace of the same
+//! name, the retention policy `rp` is ignored.
+//!
+//! A query may consist of several statements separated by semicolons, each of which gets its own
+//! result. Like 1.x, the whole query is rejected if any statement cannot be parsed, while errors
+//! planning or executing a statement are reported in the result of that statement. Unlike 1.x,
+//! results are not streamed: chunked responses are not supported and the result of every
+//! statement is buffered before the response is sent.
+use std::{collections::BTreeMap, sync::Arc};
+
+use arrow::{
+    array::{as_boolean_array, as_primitive_array, as_string_array, Array, ArrayRef},
+    compute::cast,
+    datatypes::{DataType, Float64Type, Int64Type, TimeUnit, TimestampNanosecondType, UInt64Type},
+    error::ArrowError,
+    record_batch::RecordBatch,
+    util::display::array_value_to_string,
+};
+use chrono::SecondsFormat;
+use data_types::DatabaseName;
+use datafusion::error::DataFusionError;
+use hyper::{header::CONTENT_TYPE, Body, Method, Request, Response, StatusCode};
+use influxdb_influxql_parser::statement::{parse_statements, Statement};
+use iox_query::{
+    exec::{ExecutionContextProvider, IOxSessionContext},
+    frontend::influxql::InfluxQLStatementPlan,
+    QueryDatabase,
+};
+use iox_time::Time;
+use ioxd_common::http::utils::parse_body;
+use observability_deps::tracing::info;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use service_common::{planner::Planner, QueryDatabaseProvider};
+use trace::{ctx::SpanContext, span::SpanExt};
+
+use super::{Error, HttpDelegate};
+
+/// Path of the 1.x query endpoint.
+pub(super) const V1_QUERY_PATH: &str = "/query";
+
+/// Precision of the timestamps in the response, RFC 3339 strings if not set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+enum Epoch {
+    #[serde(rename = "ns", alias = "n")]
+    Nanosecond,
+    #[serde(rename = "u", alias = "µ")]
+    Microsecond,
+    #[serde(rename = "ms")]
+    Millisecond,
+    #[serde(rename = "s")]
+    Second,
+    #[serde(rename = "m")]
+    Minute,
+    #[serde(rename = "h")]
+    Hour,
+}
+
+impl Epoch {
+    /// The number of nanoseconds per unit of this precision.
+    fn nanos(&self) -> i64 {
+        match self {
+            Self::Nanosecond => 1,
+            Self::Microsecond => 1_000,
+            Self::Millisecond => 1_000_000,
+            Self::Second => 1_000_000_000,
+            Self::Minute => 60 * 1_000_000_000,
+            Self::Hour => 60 * 60 * 1_000_000_000,
+        }
+    }
+}
+
+/// Parameters of the 1.x query endpoint, from the query string or a form request body.
+#[derive(Debug, Default, Deserialize)]
+struct V1QueryParams {
+    db: Option<String>,
+    q: Option<String>,
+    epoch: Option<Epoch>,
+    pretty: Option<bool>,
+}
+
+impl V1QueryParams {
+    /// Fill the parameters that are not set from `other`.
+    fn or(self, other: Self) -> Self {
+        Self {
+            db: self.db.or(other.db),
+            q: self.q.or(other.q),
+            epoch: self.epoch.or(other.epoch),
+            pretty: self.pretty.or(other.pretty),
+        }
+    }
+}
+
+/// Body of a 1.x query response.
+#[derive(Debug, Serialize)]
+struct QueryResponse {
+    results: Vec<StatementResult>,
+}
+
+/// Result of a single statement of a 1.x query.
+#[derive(Debug, Serialize)]
+struct StatementResult {
+    statement_id: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    series: Vec<Series>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl StatementResult {
+    fn error(statement_id: usize, error: impl ToString) -> Self {
+        Self {
+            statement_id,
+            series: vec![],
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// A series of the result of a statement.
+#[derive(Debug, PartialEq, Serialize)]
+struct Series {
+    name: String,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    tags: BTreeMap<String, String>,
+    columns: Vec<String>,
+    values: Vec<Vec<Value>>,
+}
+
+impl<D> HttpDelegate<D>
+where
+    D: QueryDatabaseProvider,
+{
+    pub(super) async fn v1_query(&self, req: Request<Body>) -> Result<Response<Body>, Error> {
+        let span_ctx: Option<SpanContext> = req.extensions().get().cloned();
+
+        let mut params: V1QueryParams =
+            serde_urlencoded::from_str(req.uri().query().unwrap_or_default())?;
+        if req.method() == Method::POST {
+            let is_form = req
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .map_or(false, |v| {
+                    v.starts_with("application/x-www-form-urlencoded")
+                });
+            let body = parse_body(req, self.max_request_bytes).await?;
+            if is_form {
+                params = params.or(serde_urlencoded::from_bytes(&body)?);
+            } else if !body.is_empty() {
+                let q = std::str::from_utf8(&body).map_err(Error::NonUtf8Body)?;
+                params.q = Some(q.to_string());
+            }
+        }
+
+        let query = params
+            .q
+            .filter(|q| !q.trim().is_empty())
+            .ok_or(Error::NoQuery)?;
+        let namespace = DatabaseName::new(params.db.ok_or(Error::NoDatabase)?)?;
+
+        let mut statements = vec![];
+        for parsed in parse_statements(&query) {
+            match parsed.result {
+                Ok(statement) => statements.push(statement),
+                Err(e) => {
+                    let body = serde_json::json!({
+                        "error": format!("error parsing query: {}: {}", parsed.source, e)
+                    });
+                    return Ok(json_response(StatusCode::BAD_REQUEST, &body, params.pretty));
+                }
+            }
+        }
+
+        let _namespace_permit = self
+            .database
+            .acquire_namespace_semaphore(
+                &namespace,
+                span_ctx.child_span("namespace query semaphore"),
+            )
+            .await
+            .map_err(|e| Error::QueueFull(namespace.to_string(), e))?;
+        let _permit = self
+            .database
+            .acquire_semaphore(span_ctx.child_span("query rate limit semaphore"))
+            .await;
+        info!(%namespace, %query, "HTTP InfluxQL query");
+
+        let db = match self
+            .database
+            .db(&namespace, span_ctx.child_span("get namespace"))
+            .await
+        {
+            Some(db) => db,
+            None => {
+                let results = (0..statements.len())
+                    .map(|id| {
+                        StatementResult::error(id, format!("database not found: {}", namespace))
+                    })
+                    .collect();
+                let body = QueryResponse { results };
+                return Ok(json_response(StatusCode::OK, &body, params.pretty));
+            }
+        };
+
+        let ctx = db.new_query_context(span_ctx);
+        let query_completed_token = db.record_query(&ctx, "influxql", Box::new(query.clone()));
+
+        let mut results = vec![];
+        for (statement_id, statement) in statements.into_iter().enumerate() {
+            let result = match execute(statement, &ctx, params.epoch).await {
+                Ok(series) => StatementResult {
+                    statement_id,
+                    series,
+                    error: None,
+                },
+                Err(e) => StatementResult::error(statement_id, e),
+            };
+            results.push(result);
+        }
+        if results.iter().all(|r| r.error.is_none()) {
+            query_completed_token.set_success();
+        }
+
+        let body = QueryResponse { results };
+        Ok(json_response(StatusCode::OK, &body, params.pretty))
+    }
+}
+
+/// Plan and execute a single statement, returning its result as series.
+async fn execute(
+    statement: Statement,
+    ctx: &IOxSessionContext,
+    epoch: Option<Epoch>,
+) -> Result<Vec<Series>, Error> {
+    let plan = Planner::new(ctx)
+        .influxql_statement(statement)
+        .await
+        .map_err(Error::Planning)?;
+    let batches = ctx
+        .collect(Arc::clone(&plan.plan))
+        .await
+        .map_err(Error::Execution)?;
+
+    series(&plan, &batches, epoch).map_err(|e| Error::Execution(DataFusionError::ArrowError(e)))
+}
+
+/// Split the rows of `batches`, the output of `plan`, into their series.
+fn series(
+    plan: &InfluxQLStatementPlan,
+    batches: &[RecordBatch],
+    epoch: Option<Epoch>,
+) -> Result<Vec<Series>, ArrowError> {
+    let mut series: Vec<Series> = vec![];
+
+    for batch in batches {
+        let schema = batch.schema();
+        let mut name_column = None;
+        let mut tag_columns = vec![];
+        let mut columns = vec![];
+        let mut values = vec![];
+        for (field, array) in schema.fields().iter().zip(batch.columns()) {
+            // dictionary encoded tags are returned as strings
+            let array = match array.data_type() {
+                DataType::Dictionary(_, _) => cast(array, &DataType::Utf8)?,
+                _ => Arc::clone(array),
+            };
+
+            if plan.name_column.as_ref() == Some(field.name()) {
+                name_column = Some(array);
+            } else if plan.tag_columns.contains(field.name()) {
+                tag_columns.push((field.name(), array));
+            } else {
+                columns.push(field.name().clone());
+                values.push(array);
+            }
+        }
+
+        for row in 0..batch.num_rows() {
+            let name = match &name_column {
+                Some(array) => string_value(array, row)?,
+                None => plan.series_name.clone(),
+            };
+            let tags = tag_columns
+                .iter()
+                .map(|(tag, array)| Ok((tag.to_string(), string_value(array, row)?)))
+                .collect::<Result<BTreeMap<_, _>, ArrowError>>()?;
+            let row_values = values
+                .iter()
+                .map(|array| json_value(array, row, epoch))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            // the rows of a series are adjacent
+            match series.last_mut() {
+                Some(last) if last.name == name && last.tags == tags => {
+                    last.values.push(row_values)
+                }
+                _ => series.push(Series {
+                    name,
+                    tags,
+                    columns: columns.clone(),
+                    values: vec![row_values],
+                }),
+            }
+        }
+    }
+
+    Ok(series)
+}
+
+/// The value of a series name or tag column as string, an empty string for null.
+fn string_value(array: &ArrayRef, row: usize) -> Result<String, ArrowError> {
+    if array.is_null(row) {
+        Ok(String::new())
+    } else {
+        array_value_to_string(array, row)
+    }
+}
+
+/// Convert a single value of `array` into JSON.
+fn json_value(array: &ArrayRef, row: usize, epoch: Option<Epoch>) -> Result<Value, ArrowError> {
+    if array.is_null(row) {
+        return Ok(Value::Null);
+    }
+
+    Ok(match array.data_type() {
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            let nanos = as_primitive_array::<TimestampNanosecondType>(array).value(row);
+            match epoch {
+                Some(epoch) => Value::from(nanos / epoch.nanos()),
+                None => Value::from(
+                    Time::from_timestamp_nanos(nanos)
+                        .date_time()
+                        .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                ),
+            }
+        }
+        DataType::Int64 => Value::from(as_primitive_array::<Int64Type>(array).value(row)),
+        DataType::UInt64 => Value::from(as_primitive_array::<UInt64Type>(array).value(row)),
+        // NaN and infinity become null
+        DataType::Float64 => Value::from(as_primitive_array::<Float64Type>(array).value(row)),
+        DataType::Boolean => Value::from(as_boolean_array(array).value(row)),
+        DataType::Utf8 => Value::from(as_string_array(array).value(row)),
+        _ => Value::from(array_value_to_string(array, row)?),
+    })
+}
+
+/// A JSON response with the given status.
+fn json_response(
+    status: StatusCode,
+    body: &impl Serialize,
+    pretty: Option<bool>,
+) -> Response<Body> {
+    let body = if pretty.unwrap_or_default() {
+        serde_json::to_vec_pretty(body)
+    } else {
+        serde_json::to_vec(body)
+    }
+    .expect("serializable response");
+
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .expect("valid response")
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::{
+        array::{DictionaryArray, Float64Array, StringArray, TimestampNanosecondArray},
+        datatypes::Int32Type,
+    };
+    use datafusion::physical_plan::empty::EmptyExec;
+    use serde_json::json;
+    use service_common::test_util::TestDatabaseStore;
+
+    use super::*;
+
+    #[test]
+    fn test_series() {
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "time",
+                Arc::new(TimestampNanosecondArray::from(vec![
+                    1_000_000_000,
+                    2_500_000_000,
+                    1_000_000_000,
+                ])) as ArrayRef,
+            ),
+            (
+                "host",
+                Arc::new(
+                    vec![Some("a"), Some("a"), None]
+                        .into_iter()
+                        .collect::<DictionaryArray<Int32Type>>(),
+                ) as ArrayRef,
+            ),
+            (
+                "usage",
+                Arc::new(Float64Array::from(vec![Some(1.5), None, Some(f64::NAN)])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let plan = InfluxQLStatementPlan {
+            plan: Arc::new(EmptyExec::new(false, batch.schema())),
+            series_name: "cpu".to_string(),
+            name_column: None,
+            tag_columns: vec!["host".to_string()],
+        };
+
+        let got = series(&plan, &[batch.clone()], None).unwrap();
+        assert_eq!(
+            serde_json::to_value(got).unwrap(),
+            json!([
+                {
+                    "name": "cpu",
+                    "tags": {"host": "a"},
+                    "columns": ["time", "usage"],
+                    "values": [["1970-01-01T00:00:01Z", 1.5], ["1970-01-01T00:00:02.500Z", null]],
+                },
+                {
+                    "name": "cpu",
+                    "tags": {"host": ""},
+                    "columns": ["time", "usage"],
+                    "values": [["1970-01-01T00:00:01Z", null]],
+                },
+            ])
+        );
+
+        let got = series(&plan, &[batch], Some(Epoch::Millisecond)).unwrap();
+        assert_eq!(
+            serde_json::to_value(&got[0].values).unwrap(),
+            json!([[1000, 1.5], [2500, null]])
+        );
+    }
+
+    #[test]
+    fn test_series_name_column() {
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "iox::measurement",
+                Arc::new(StringArray::from(vec!["cpu", "cpu", "mem"])) as ArrayRef,
+            ),
+            (
+                "tagKey",
+                Arc::new(StringArray::from(vec!["host", "region", "host"])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let plan = InfluxQLStatementPlan {
+            plan: Arc::new(EmptyExec::new(false, batch.schema())),
+            series_name: String::new(),
+            name_column: Some("iox::measurement".to_string()),
+            tag_columns: vec![],
+        };
+
+        // series continue across batches
+        let got = series(&plan, &[batch.slice(0, 1), batch.slice(1, 2)], None).unwrap();
+        assert_eq!(
+            serde_json::to_value(got).unwrap(),
+            json!([
+                {"name": "cpu", "columns": ["tagKey"], "values": [["host"], ["region"]]},
+                {"name": "mem", "columns": ["tagKey"], "values": [["host"]]},
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_v1_query() {
+        let delegate = delegate().await;
+
+        // errors of the individual statements are part of the result
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri("https://bananas.example/query?db=my_db")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Body::from("q=SELECT+value+FROM+cpu%3B+SHOW+FIELD+KEYS"))
+            .unwrap();
+        let resp = delegate.route(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        let got = body_to_json(resp).await;
+        let results = got["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["statement_id"], 0);
+        assert!(results[0]["error"].as_str().unwrap().contains("cpu"));
+        assert_eq!(results[1]["statement_id"], 1);
+        assert!(results[1]["error"]
+            .as_str()
+            .unwrap()
+            .contains("SHOW FIELD KEYS"));
+
+        let req = Request::builder()
+            .uri("https://bananas.example/query?db=unknown&q=SHOW%20MEASUREMENTS")
+            .body(Body::empty())
+            .unwrap();
+        let resp = delegate.route(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            body_to_json(resp).await,
+            json!({"results": [{"statement_id": 0, "error": "database not found: unknown"}]})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_v1_query_errors() {
+        let delegate = delegate().await;
+
+        // the whole query is rejected if a statement is invalid
+        let req = Request::builder()
+            .uri("https://bananas.example/query?db=my_db&q=SHOW%20MEASUREMENTS%3B%20SELEC")
+            .body(Body::empty())
+            .unwrap();
+        let resp = delegate.route(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let got = body_to_json(resp).await;
+        assert!(got["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("error parsing query: SELEC:"));
+
+        let req = Request::builder()
+            .uri("https://bananas.example/query?q=SHOW%20MEASUREMENTS")
+            .body(Body::empty())
+            .unwrap();
+        assert!(matches!(delegate.route(req).await, Err(Error::NoDatabase)));
+
+        let req = Request::builder()
+            .uri("https://bananas.example/query?db=my_db")
+            .body(Body::empty())
+            .unwrap();
+        assert!(matches!(delegate.route(req).await, Err(Error::NoQuery)));
+
+        let req = Request::builder()
+            .uri("https://bananas.example/query?db=my_db&q=SHOW%20MEASUREMENTS&epoch=d")
+            .body(Body::empty())
+            .unwrap();
+        assert!(matches!(
+            delegate.route(req).await,
+            Err(Error::InvalidQueryString(_))
+        ));
+    }
+
+    async fn delegate() -> HttpDelegate<TestDatabaseStore> {
+        let database = Arc::new(TestDatabaseStore::new());
+        database.db_or_create("my_db").await;
+        HttpDelegate::new(database, 1024)
+    }
+
+    async fn body_to_json(resp: Response<Body>) -> Value {
+        let body = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+}
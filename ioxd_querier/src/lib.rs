@@ -2,11 +2,11 @@ use async_trait::async_trait;
 use clap_blocks::querier::{IngesterAddresses, QuerierConfig};
 use hyper::{Body, Request, Response};
 use iox_catalog::interface::Catalog;
-use iox_query::exec::Executor;
+use iox_query::{exec::Executor, result_cache::QueryResultCacheConfig};
 use iox_time::TimeProvider;
 use ioxd_common::{
     add_service,
-    http::error::{HttpApiError, HttpApiErrorCode, HttpApiErrorSource},
+    http::error::HttpApiErrorSource,
     rpc::RpcBuilderInput,
     serve_builder,
     server_type::{CommonServerState, RpcError, ServerType},
@@ -14,24 +14,28 @@ use ioxd_common::{
 };
 use metric::Registry;
 use object_store::DynObjectStore;
-use parquet_file::storage::ParquetStorage;
-use querier::{
-    create_ingester_connections_by_shard, QuerierCatalogCache, QuerierDatabase, QuerierHandler,
-    QuerierHandlerImpl, QuerierServer,
+use parquet_file::{
+    storage::{ParquetStorage, ReadRetryConfig},
+    ParquetPathLayout,
 };
-use std::{
-    fmt::{Debug, Display},
-    sync::Arc,
+use querier::{
+    create_ingester_connections_by_shard, CacheWarmUpConfig, DiskTierConfig,
+    IngesterCircuitBreakerConfig, NamespaceSchedulerConfig, ObjectStoreCacheConfig,
+    QuerierCatalogCache, QuerierDatabase, QuerierHandler, QuerierHandlerImpl, QuerierServer,
+    QueryAdmissionConfig, QueryTimeoutConfig,
 };
+use std::{fmt::Debug, sync::Arc};
 use thiserror::Error;
 use tokio::runtime::Handle;
 use trace::TraceCollector;
 
+mod http;
 mod rpc;
 
 pub struct QuerierServerType<C: QuerierHandler> {
     database: Arc<QuerierDatabase>,
     server: QuerierServer<C>,
+    http: http::HttpDelegate<QuerierDatabase>,
     trace_collector: Option<Arc<dyn TraceCollector>>,
 }
 
@@ -47,9 +51,15 @@ impl<C: QuerierHandler> QuerierServerType<C> {
         database: Arc<QuerierDatabase>,
         common_state: &CommonServerState,
     ) -> Self {
+        let http = http::HttpDelegate::new(
+            Arc::clone(&database),
+            common_state.run_config().max_http_request_size,
+        );
+
         Self {
             server,
             database,
+            http,
             trace_collector: common_state.trace_collector(),
         }
     }
@@ -67,12 +77,12 @@ impl<C: QuerierHandler + std::fmt::Debug + 'static> ServerType for QuerierServer
         self.trace_collector.as_ref().map(Arc::clone)
     }
 
-    /// Just return "not found".
+    /// Dispatches `req` to the querier HTTP query endpoint.
     async fn route_http_request(
         &self,
-        _req: Request<Body>,
+        req: Request<Body>,
     ) -> Result<Response<Body>, Box<dyn HttpApiErrorSource>> {
-        Err(Box::new(IoxHttpError::NotFound))
+        self.http.route(req).await.map_err(|e| Box::new(e) as _)
     }
 
     /// Provide a placeholder gRPC service.
@@ -109,34 +119,6 @@ impl<C: QuerierHandler + std::fmt::Debug + 'static> ServerType for QuerierServer
     }
 }
 
-/// Simple error struct, we're not really providing an HTTP interface for the compactor.
-#[derive(Debug)]
-pub enum IoxHttpError {
-    NotFound,
-}
-
-impl IoxHttpError {
-    fn status_code(&self) -> HttpApiErrorCode {
-        match self {
-            IoxHttpError::NotFound => HttpApiErrorCode::NotFound,
-        }
-    }
-}
-
-impl Display for IoxHttpError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
-    }
-}
-
-impl std::error::Error for IoxHttpError {}
-
-impl HttpApiErrorSource for IoxHttpError {
-    fn to_http_api_error(&self) -> HttpApiError {
-        HttpApiError::new(self.status_code(), self.to_string())
-    }
-}
-
 /// Arguments required to create a [`ServerType`] for the querier.
 #[derive(Debug)]
 pub struct QuerierServerTypeArgs<'a> {
@@ -144,6 +126,7 @@ pub struct QuerierServerTypeArgs<'a> {
     pub metric_registry: Arc<metric::Registry>,
     pub catalog: Arc<dyn Catalog>,
     pub object_store: Arc<DynObjectStore>,
+    pub parquet_path_layout: ParquetPathLayout,
     pub exec: Arc<Executor>,
     pub time_provider: Arc<dyn TimeProvider>,
     pub ingester_addresses: IngesterAddresses,
@@ -170,8 +153,24 @@ pub async fn create_querier_server_type(
         Arc::clone(&args.catalog),
         args.time_provider,
         Arc::clone(&args.metric_registry),
+        args.object_store,
         args.querier_config.ram_pool_metadata_bytes(),
         args.querier_config.ram_pool_data_bytes(),
+        ObjectStoreCacheConfig {
+            chunk_size_bytes: args.querier_config.object_store_cache_chunk_size_bytes(),
+            disk: args
+                .querier_config
+                .object_store_cache_disk_path()
+                .map(|path| DiskTierConfig {
+                    path: path.to_owned(),
+                    max_bytes: args.querier_config.object_store_cache_disk_bytes(),
+                }),
+            reconciliation_interval: args
+                .querier_config
+                .object_store_cache_reconciliation_interval(),
+            not_found_ttl: Some(args.querier_config.object_store_cache_not_found_ttl()),
+            ..Default::default()
+        },
         &Handle::current(),
     ));
 
@@ -180,21 +179,73 @@ pub async fn create_querier_server_type(
         IngesterAddresses::ByShardIndex(map) => Some(create_ingester_connections_by_shard(
             map,
             Arc::clone(&catalog_cache),
+            args.querier_config
+                .ingester_circuit_breaker_threshold()
+                .map(|failure_threshold| IngesterCircuitBreakerConfig {
+                    failure_threshold,
+                    open_duration: args.querier_config.ingester_circuit_breaker_open_duration(),
+                    latency_budget: args.querier_config.ingester_latency_budget(),
+                }),
         )),
     };
 
-    let database = Arc::new(
-        QuerierDatabase::new(
-            catalog_cache,
-            Arc::clone(&args.metric_registry),
-            ParquetStorage::new(args.object_store),
-            args.exec,
-            ingester_connection,
-            args.querier_config.max_concurrent_queries(),
-            args.querier_config.max_table_query_bytes(),
-        )
-        .await?,
-    );
+    // queries are usually selective, so only fetch the parts of the files that are needed. A
+    // single flaky object store request should not fail the entire query.
+    let parquet_store = ParquetStorage::new(catalog_cache.object_store().object_store())
+        .with_partial_reads(true)
+        .with_read_retries(ReadRetryConfig::default(), &args.metric_registry)
+        .with_path_layout(args.parquet_path_layout);
+
+    let mut database = QuerierDatabase::new(
+        catalog_cache,
+        Arc::clone(&args.metric_registry),
+        parquet_store,
+        args.exec,
+        ingester_connection,
+        args.querier_config.max_concurrent_queries(),
+        args.querier_config.max_table_query_bytes(),
+        args.querier_config.dedup_validation(),
+    )
+    .await?
+    .with_query_timeout(QueryTimeoutConfig {
+        default: args.querier_config.query_timeout(),
+        max: args.querier_config.max_query_timeout(),
+    });
+    if let Some(pool_bytes) = args.querier_config.query_memory_pool_bytes() {
+        database = database.with_query_admission(QueryAdmissionConfig {
+            pool_bytes,
+            max_queue_duration: args.querier_config.query_admission_queue_timeout(),
+        });
+    }
+    if let Some(refresh_interval) = args.querier_config.query_timeout_refresh_interval() {
+        database = database.with_namespace_query_timeouts(refresh_interval);
+    }
+    if let Some(refresh_interval) = args.querier_config.query_rate_limit_refresh_interval() {
+        database = database.with_query_rate_limit(refresh_interval);
+    }
+    if let Some(max_concurrent_queries) = args.querier_config.max_concurrent_queries_per_namespace()
+    {
+        database = database.with_namespace_scheduler(NamespaceSchedulerConfig {
+            max_concurrent_queries,
+            max_queued_queries: args.querier_config.max_queued_queries_per_namespace(),
+        });
+    }
+    if let Some(flush_interval) = args.querier_config.access_stats_flush_interval() {
+        database = database.with_access_stats(flush_interval);
+    }
+    if let Some(window) = args.querier_config.cache_warm_up_window() {
+        database = database.with_cache_warm_up(CacheWarmUpConfig {
+            window,
+            max_tables: args.querier_config.cache_warm_up_max_tables(),
+        });
+    }
+    if let Some(max_entries) = args.querier_config.result_cache_max_entries() {
+        database = database.with_result_cache(QueryResultCacheConfig {
+            max_entries,
+            max_entry_bytes: args.querier_config.result_cache_max_entry_bytes(),
+        });
+    }
+    let database = Arc::new(database);
     let querier_handler = Arc::new(QuerierHandlerImpl::new(args.catalog, Arc::clone(&database)));
 
     let querier = QuerierServer::new(args.metric_registry, querier_handler);
@@ -71,6 +71,7 @@ mod tests {
             catalog.catalog(),
             catalog.time_provider(),
             catalog.metric_registry(),
+            catalog.object_store(),
             &Handle::current(),
         ));
         let db = Arc::new(
@@ -82,6 +83,7 @@ mod tests {
                 Some(create_ingester_connection_for_testing()),
                 QuerierDatabase::MAX_CONCURRENT_QUERIES_MAX,
                 usize::MAX,
+                false,
             )
             .await
             .unwrap(),
@@ -107,6 +109,7 @@ mod tests {
             catalog.catalog(),
             catalog.time_provider(),
             catalog.metric_registry(),
+            catalog.object_store(),
             &Handle::current(),
         ));
         let db = Arc::new(
@@ -118,6 +121,7 @@ mod tests {
                 Some(create_ingester_connection_for_testing()),
                 QuerierDatabase::MAX_CONCURRENT_QUERIES_MAX,
                 usize::MAX,
+                false,
             )
             .await
             .unwrap(),
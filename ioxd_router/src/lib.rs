@@ -4,6 +4,7 @@ use data_types::{DatabaseName, PartitionTemplate, TemplatePart};
 use hashbrown::HashMap;
 use hyper::{Body, Request, Response};
 use iox_catalog::interface::Catalog;
+use iox_time::SystemProvider;
 use ioxd_common::{
     add_service,
     http::error::{HttpApiError, HttpApiErrorSource},
@@ -19,8 +20,8 @@ use observability_deps::tracing::info;
 use router::{
     dml_handlers::{
         DmlHandler, DmlHandlerChainExt, FanOutAdaptor, InstrumentationDecorator,
-        NamespaceAutocreation, Partitioner, SchemaValidator, ShardedWriteBuffer,
-        WriteSummaryAdapter,
+        NamespaceAutocreation, NamespaceRateLimiter, Partitioner, SchemaValidator,
+        ShardedWriteBuffer, WriteSummaryAdapter,
     },
     namespace_cache::{
         metrics::InstrumentedCache, MemoryNamespaceCache, NamespaceCache, ShardedCache,
@@ -37,6 +38,7 @@ use std::{
     collections::BTreeSet,
     fmt::{Debug, Display},
     sync::Arc,
+    time::Duration,
 };
 use thiserror::Error;
 use tokio_util::sync::CancellationToken;
@@ -159,7 +161,11 @@ impl std::error::Error for IoxHttpErrorAdaptor {}
 
 impl HttpApiErrorSource for IoxHttpErrorAdaptor {
     fn to_http_api_error(&self) -> HttpApiError {
-        HttpApiError::new(self.0.as_status_code(), self.to_string())
+        let err = HttpApiError::new(self.0.as_status_code(), self.to_string());
+        match self.0.retry_after() {
+            Some(retry_after) => err.with_retry_after(retry_after),
+            None => err,
+        }
     }
 }
 
@@ -172,6 +178,7 @@ pub async fn create_router_server_type(
     write_buffer_config: &WriteBufferConfig,
     query_pool_name: &str,
     request_limit: usize,
+    rate_limit_refresh_interval: Duration,
 ) -> Result<Arc<dyn ServerType>> {
     // Initialise the sharded write buffer and instrument it with DML handler
     // metrics.
@@ -248,6 +255,16 @@ pub async fn create_router_server_type(
         });
     txn.commit().await?;
 
+    // Reject requests to namespaces exceeding their write rate limit before
+    // doing any other work.
+    let rate_limiter = NamespaceRateLimiter::new(
+        Arc::clone(&catalog),
+        Arc::new(SystemProvider::new()),
+        rate_limit_refresh_interval,
+        &*metrics,
+    );
+    let rate_limiter = InstrumentationDecorator::new("rate_limiter", &*metrics, rate_limiter);
+
     let ns_creator = NamespaceAutocreation::new(
         Arc::clone(&catalog),
         ns_cache,
@@ -261,9 +278,11 @@ pub async fn create_router_server_type(
     let parallel_write = WriteSummaryAdapter::new(FanOutAdaptor::new(write_buffer));
 
     // Build the chain of DML handlers that forms the request processing
-    // pipeline, starting with the namespace creator (for testing purposes) and
-    // write partitioner that yields a set of partitioned batches.
-    let handler_stack = ns_creator
+    // pipeline, starting with the rate limiter, the namespace creator (for
+    // testing purposes) and write partitioner that yields a set of partitioned
+    // batches.
+    let handler_stack = rate_limiter
+        .and_then(ns_creator)
         .and_then(schema_validator)
         .and_then(partitioner)
         // Once writes have been partitioned, they are processed in parallel.
@@ -2,7 +2,7 @@
 //! download & execute a scan.
 
 use crate::{storage::ParquetStorage, ParquetFilePath};
-use data_types::{ParquetFile, TimestampMinMax};
+use data_types::{DeletePredicate, ParquetFile, TimestampMinMax};
 use datafusion::physical_plan::SendableRecordBatchStream;
 use predicate::Predicate;
 use schema::{selection::Selection, Schema};
@@ -72,15 +72,18 @@ impl ParquetChunk {
         })
     }
 
-    /// Return stream of data read from parquet file
+    /// Return stream of data read from parquet file, without the rows deleted by
+    /// `delete_predicates`, see [`ParquetStorage::read_filter`].
     pub fn read_filter(
         &self,
         predicate: &Predicate,
+        delete_predicates: &[Arc<DeletePredicate>],
         selection: Selection<'_>,
     ) -> Result<SendableRecordBatchStream, crate::storage::ReadError> {
         let path: ParquetFilePath = self.parquet_file.as_ref().into();
         self.store.read_filter(
             predicate,
+            delete_predicates,
             selection,
             Arc::clone(&self.schema.as_arrow()),
             &path,
@@ -0,0 +1,628 @@
+//! Combine parquet files by concatenating their row groups, without decoding
+//! any data.
+//!
+//! Compacting files that were written one after another (e.g. by an ingester
+//! persisting an append-only workload) does not require any deduplication or
+//! sorting: the data of the files is disjoint and, if the files are ordered by
+//! time, already sorted. [`concat_parquet`] copies the serialized row groups of
+//! such files into a new file and only rebuilds the footer, which is orders of
+//! magnitude cheaper than decoding, merging and re-encoding the data.
+//!
+//! # Eligibility
+//!
+//! Files can only be concatenated if
+//!
+//! - they all have the same parquet schema and the same arrow/IOx schema
+//!   metadata,
+//! - they all have the same sort key as the output file and that sort key
+//!   orders `time` ascending,
+//! - their time ranges do not overlap, and
+//! - all sort key columns preceding `time` are constant, with the same value in
+//!   every file (this usually holds for the tags of a partition of a
+//!   low-cardinality table).
+//!
+//! Together these guarantee that the concatenated data is sorted by the sort
+//! key and free of duplicates. If any condition is violated, a
+//! [`ConcatError`] is returned and the caller must fall back to a regular
+//! compaction.
+
+use bytes::Bytes;
+use parquet_format::{ColumnChunk, FileMetaData, KeyValue, RowGroup};
+use schema::TIME_COLUMN_NAME;
+use thiserror::Error;
+use thrift::protocol::{TCompactInputProtocol, TCompactOutputProtocol, TOutputProtocol};
+
+use crate::metadata::{IoxMetadata, METADATA_KEY};
+
+/// Magic bytes at the start and end of every parquet file.
+const PARQUET_MAGIC: &[u8; 4] = b"PAR1";
+
+/// Size of the parquet file trailer: the 4-byte footer length and the magic
+/// bytes.
+const TRAILER_SIZE: usize = 8;
+
+/// Errors returned by [`concat_parquet`].
+#[derive(Debug, Error)]
+pub enum ConcatError {
+    /// No input files were given.
+    #[error("no parquet files to concatenate")]
+    NoFiles,
+
+    /// An input is not a valid parquet file.
+    #[error("input file {index} is not a valid parquet file: {reason}")]
+    InvalidFile {
+        /// Position of the file within the inputs.
+        index: usize,
+
+        /// What is wrong with the file.
+        reason: &'static str,
+    },
+
+    /// Decoding or encoding a parquet footer failed.
+    #[error("failed to (de)serialize parquet footer: {0}")]
+    Thrift(#[from] thrift::Error),
+
+    /// Decoding or encoding the IOx metadata failed.
+    #[error("failed to (de)serialize IOx metadata: {0}")]
+    Metadata(#[from] crate::metadata::Error),
+
+    /// The schema of an input differs from the schema of the first input.
+    #[error("schema of input file {index} differs from the first input")]
+    SchemaMismatch {
+        /// Position of the file within the inputs.
+        index: usize,
+    },
+
+    /// The sort key of an input differs from the sort key of the output.
+    #[error("sort key of input file {index} differs from the output sort key")]
+    SortKeyMismatch {
+        /// Position of the file within the inputs.
+        index: usize,
+    },
+
+    /// The output sort key does not order `time` ascending.
+    #[error("sort key does not order the time column ascending")]
+    UnsupportedSortKey,
+
+    /// The statistics required to prove that the output is sorted are missing.
+    #[error("input file {index} has no statistics for column '{column}'")]
+    MissingStatistics {
+        /// Position of the file within the inputs.
+        index: usize,
+
+        /// Column without statistics.
+        column: String,
+    },
+
+    /// The time ranges of two inputs overlap.
+    #[error("time ranges of the input files overlap")]
+    Overlapping,
+
+    /// A sort key column preceding `time` is not constant across all inputs,
+    /// so the concatenated data would not be sorted.
+    #[error("sort key column '{column}' is not constant across the input files")]
+    NotConstant {
+        /// Sort key column.
+        column: String,
+    },
+}
+
+/// The footer and data of an input file.
+#[derive(Debug)]
+struct Input {
+    /// Position of the file within the inputs.
+    index: usize,
+    data: Bytes,
+    footer: FileMetaData,
+    min_time: i64,
+    max_time: i64,
+}
+
+/// Concatenate the row groups of the parquet files `files` into a new parquet
+/// file carrying the IOx metadata `meta`.
+///
+/// The inputs may be given in any order, they are ordered by time. See the
+/// [module documentation](self) for the conditions the inputs must meet.
+///
+/// Returns the bytes of the new file along with its [`FileMetaData`], from
+/// which an [`IoxParquetMetaData`] can be derived.
+///
+/// [`IoxParquetMetaData`]: crate::metadata::IoxParquetMetaData
+pub fn concat_parquet(
+    files: &[Bytes],
+    meta: &IoxMetadata,
+) -> Result<(Vec<u8>, FileMetaData), ConcatError> {
+    if files.is_empty() {
+        return Err(ConcatError::NoFiles);
+    }
+
+    let sort_key = meta
+        .sort_key
+        .as_ref()
+        .ok_or(ConcatError::UnsupportedSortKey)?;
+    match sort_key.get(TIME_COLUMN_NAME) {
+        Some(column) if !column.options.descending => {}
+        _ => return Err(ConcatError::UnsupportedSortKey),
+    }
+    let prefix_columns: Vec<&str> = sort_key
+        .to_columns()
+        .take_while(|name| *name != TIME_COLUMN_NAME)
+        .collect();
+
+    let mut inputs = files
+        .iter()
+        .enumerate()
+        .map(|(index, data)| read_input(index, data.clone()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // check that the inputs are compatible with each other and the output
+    let first = &inputs[0].footer;
+    for input in &inputs {
+        let index = input.index;
+        if input.footer.schema != first.schema
+            || foreign_metadata(&input.footer) != foreign_metadata(first)
+        {
+            return Err(ConcatError::SchemaMismatch { index });
+        }
+
+        let input_meta = read_iox_metadata(&input.footer)?;
+        if input_meta.sort_key.as_ref() != Some(sort_key) {
+            return Err(ConcatError::SortKeyMismatch { index });
+        }
+    }
+
+    // check that the concatenated data is sorted
+    for column in prefix_columns {
+        let mut value = None;
+        for input in &inputs {
+            let file_value = constant_value(input.index, &input.footer, column)?;
+            match &value {
+                None => value = Some(file_value),
+                Some(value) if *value == file_value => {}
+                Some(_) => {
+                    return Err(ConcatError::NotConstant {
+                        column: column.to_string(),
+                    })
+                }
+            }
+        }
+    }
+
+    inputs.sort_by_key(|input| input.min_time);
+    if inputs
+        .windows(2)
+        .any(|pair| pair[0].max_time >= pair[1].min_time)
+    {
+        return Err(ConcatError::Overlapping);
+    }
+
+    // copy the row groups
+    let total_size = inputs.iter().map(|input| input.data.len()).sum();
+    let mut out = Vec::with_capacity(total_size);
+    out.extend_from_slice(PARQUET_MAGIC);
+
+    let mut row_groups = vec![];
+    for input in &inputs {
+        for row_group in &input.footer.row_groups {
+            row_groups.push(copy_row_group(
+                input.index,
+                &input.data,
+                row_group,
+                &mut out,
+            )?);
+        }
+    }
+    for (ordinal, row_group) in row_groups.iter_mut().enumerate() {
+        row_group.ordinal = i16::try_from(ordinal).ok();
+    }
+
+    // rebuild the footer
+    let first = &inputs[0].footer;
+    let mut key_value_metadata = foreign_metadata(first);
+    key_value_metadata.push(KeyValue {
+        key: METADATA_KEY.to_string(),
+        value: Some(base64::encode(meta.to_protobuf().map_err(|e| {
+            crate::metadata::Error::IoxMetadataBroken {
+                source: Box::new(e),
+            }
+        })?)),
+    });
+
+    let footer = FileMetaData {
+        version: first.version,
+        schema: first.schema.clone(),
+        num_rows: row_groups.iter().map(|row_group| row_group.num_rows).sum(),
+        row_groups,
+        key_value_metadata: Some(key_value_metadata),
+        created_by: first.created_by.clone(),
+        column_orders: first.column_orders.clone(),
+        encryption_algorithm: None,
+        footer_signing_key_metadata: None,
+    };
+
+    let footer_start = out.len();
+    {
+        let mut protocol = TCompactOutputProtocol::new(&mut out);
+        footer.write_to_out_protocol(&mut protocol)?;
+        protocol.flush()?;
+    }
+    let footer_len = (out.len() - footer_start) as u32;
+    out.extend_from_slice(&footer_len.to_le_bytes());
+    out.extend_from_slice(PARQUET_MAGIC);
+
+    Ok((out, footer))
+}
+
+/// Decode the footer of the input file `data` and determine its time range.
+fn read_input(index: usize, data: Bytes) -> Result<Input, ConcatError> {
+    let invalid = |reason| ConcatError::InvalidFile { index, reason };
+
+    if data.len() < PARQUET_MAGIC.len() + TRAILER_SIZE {
+        return Err(invalid("file too small"));
+    }
+    if &data[..4] != PARQUET_MAGIC || &data[data.len() - 4..] != PARQUET_MAGIC {
+        return Err(invalid("missing magic bytes"));
+    }
+
+    let trailer_start = data.len() - TRAILER_SIZE;
+    let footer_len = u32::from_le_bytes(data[trailer_start..trailer_start + 4].try_into().unwrap());
+    let footer_start = trailer_start
+        .checked_sub(footer_len as usize)
+        .filter(|start| *start >= PARQUET_MAGIC.len())
+        .ok_or_else(|| invalid("footer length out of bounds"))?;
+
+    let footer = {
+        let mut protocol = TCompactInputProtocol::new(&data[footer_start..trailer_start]);
+        FileMetaData::read_from_in_protocol(&mut protocol)?
+    };
+    if footer.encryption_algorithm.is_some() {
+        return Err(invalid("encrypted files are not supported"));
+    }
+
+    let mut min_time = i64::MAX;
+    let mut max_time = i64::MIN;
+    for row_group in &footer.row_groups {
+        let (min, max) = column_stats(row_group, TIME_COLUMN_NAME)
+            .and_then(|(min, max, _)| Some((decode_i64(min?)?, decode_i64(max?)?)))
+            .ok_or_else(|| ConcatError::MissingStatistics {
+                index,
+                column: TIME_COLUMN_NAME.to_string(),
+            })?;
+        min_time = min_time.min(min);
+        max_time = max_time.max(max);
+    }
+
+    Ok(Input {
+        index,
+        data,
+        footer,
+        min_time,
+        max_time,
+    })
+}
+
+/// Key-value metadata of the file, except for the IOx metadata.
+///
+/// This includes the arrow schema (with the IOx column types) which must be
+/// retained as-is in the output.
+fn foreign_metadata(footer: &FileMetaData) -> Vec<KeyValue> {
+    footer
+        .key_value_metadata
+        .iter()
+        .flatten()
+        .filter(|kv| kv.key != METADATA_KEY)
+        .cloned()
+        .collect()
+}
+
+/// Read the IOx metadata of an input file.
+fn read_iox_metadata(footer: &FileMetaData) -> Result<IoxMetadata, ConcatError> {
+    let proto_base64 = footer
+        .key_value_metadata
+        .iter()
+        .flatten()
+        .find(|kv| kv.key == METADATA_KEY)
+        .and_then(|kv| kv.value.as_ref())
+        .ok_or(crate::metadata::Error::IoxMetadataMissing {})?;
+    let proto_bytes =
+        base64::decode(proto_base64).map_err(|e| crate::metadata::Error::IoxMetadataBroken {
+            source: Box::new(e),
+        })?;
+
+    Ok(IoxMetadata::from_protobuf(&proto_bytes)?)
+}
+
+/// The single value of `column` in the file, `None` if the column only
+/// contains NULLs.
+///
+/// Returns an error if the column contains more than one distinct value.
+fn constant_value(
+    index: usize,
+    footer: &FileMetaData,
+    column: &str,
+) -> Result<Option<Vec<u8>>, ConcatError> {
+    let mut value: Option<Option<Vec<u8>>> = None;
+
+    for row_group in &footer.row_groups {
+        let (min, max, nulls) =
+            column_stats(row_group, column).ok_or_else(|| ConcatError::MissingStatistics {
+                index,
+                column: column.to_string(),
+            })?;
+
+        let row_group_value = match (min, max, nulls) {
+            (_, _, Some(nulls)) if nulls == row_group.num_rows => None,
+            (Some(min), Some(max), Some(0)) if min == max => Some(min.to_vec()),
+            _ => {
+                return Err(ConcatError::NotConstant {
+                    column: column.to_string(),
+                })
+            }
+        };
+
+        match &value {
+            None => value = Some(row_group_value),
+            Some(value) if *value == row_group_value => {}
+            Some(_) => {
+                return Err(ConcatError::NotConstant {
+                    column: column.to_string(),
+                })
+            }
+        }
+    }
+
+    // a file without row groups does not constrain the value
+    Ok(value.flatten())
+}
+
+/// Min, max and NULL count statistics of `column` in `row_group`.
+///
+/// Only the exact `min_value`/`max_value` statistics are used, the deprecated
+/// `min`/`max` fields may be wrong for byte arrays.
+///
+/// Returns `None` if the column does not exist or has no statistics.
+#[allow(clippy::type_complexity)]
+fn column_stats<'a>(
+    row_group: &'a RowGroup,
+    column: &str,
+) -> Option<(Option<&'a [u8]>, Option<&'a [u8]>, Option<i64>)> {
+    let stats = row_group
+        .columns
+        .iter()
+        .filter_map(|chunk| chunk.meta_data.as_ref())
+        .find(|meta| meta.path_in_schema.len() == 1 && meta.path_in_schema[0] == column)?
+        .statistics
+        .as_ref()?;
+
+    Some((
+        stats.min_value.as_deref(),
+        stats.max_value.as_deref(),
+        stats.null_count,
+    ))
+}
+
+fn decode_i64(bytes: &[u8]) -> Option<i64> {
+    Some(i64::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Append the column chunks of `row_group` from the input file `data` to `out`
+/// and return the row group metadata with adjusted offsets.
+fn copy_row_group(
+    index: usize,
+    data: &[u8],
+    row_group: &RowGroup,
+    out: &mut Vec<u8>,
+) -> Result<RowGroup, ConcatError> {
+    let invalid = |reason| ConcatError::InvalidFile { index, reason };
+
+    let row_group_start = out.len() as i64;
+    let mut columns = Vec::with_capacity(row_group.columns.len());
+    for chunk in &row_group.columns {
+        if chunk.file_path.is_some() {
+            return Err(invalid("column chunks in external files are not supported"));
+        }
+        let meta = chunk
+            .meta_data
+            .as_ref()
+            .ok_or_else(|| invalid("column chunk without metadata"))?;
+
+        // the dictionary page, if any, precedes the data pages
+        let start = meta
+            .dictionary_page_offset
+            .unwrap_or(meta.data_page_offset)
+            .min(meta.data_page_offset);
+        let end = start
+            .checked_add(meta.total_compressed_size)
+            .filter(|end| start >= 0 && *end as usize <= data.len())
+            .ok_or_else(|| invalid("column chunk out of bounds"))?;
+
+        let delta = out.len() as i64 - start;
+        out.extend_from_slice(&data[start as usize..end as usize]);
+
+        let mut meta = meta.clone();
+        meta.data_page_offset += delta;
+        meta.dictionary_page_offset = meta.dictionary_page_offset.map(|offset| offset + delta);
+        meta.index_page_offset = meta.index_page_offset.map(|offset| offset + delta);
+        // bloom filters and page indexes are stored outside of the column
+        // chunk and are not copied
+        meta.bloom_filter_offset = None;
+
+        columns.push(ColumnChunk {
+            file_path: None,
+            file_offset: chunk.file_offset + delta,
+            meta_data: Some(meta),
+            offset_index_offset: None,
+            offset_index_length: None,
+            column_index_offset: None,
+            column_index_length: None,
+            crypto_metadata: None,
+            encrypted_column_metadata: None,
+        });
+    }
+
+    Ok(RowGroup {
+        columns,
+        total_byte_size: row_group.total_byte_size,
+        num_rows: row_group.num_rows,
+        sorting_columns: row_group.sorting_columns.clone(),
+        file_offset: Some(row_group_start),
+        total_compressed_size: Some(out.len() as i64 - row_group_start),
+        ordinal: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{metadata::IoxParquetMetaData, serialize::to_parquet_bytes};
+    use arrow::{
+        array::{ArrayRef, DictionaryArray, Float64Array, TimestampNanosecondArray},
+        datatypes::Int32Type,
+        record_batch::RecordBatch,
+        util::pretty::pretty_format_batches,
+    };
+    use data_types::{CompactionLevel, NamespaceId, PartitionId, SequenceNumber, ShardId, TableId};
+    use datafusion::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use iox_time::Time;
+    use schema::{builder::SchemaBuilder, sort::SortKey};
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_concat() {
+        let meta = meta();
+        let file1 = file(&meta, "a", &[1, 2], &[1.0, 2.0]).await;
+        let file2 = file(&meta, "a", &[10, 20, 30], &[3.0, 4.0, 5.0]).await;
+
+        // inputs are ordered by time
+        let (data, footer) = concat_parquet(&[file2, file1], &meta).unwrap();
+        assert_eq!(footer.num_rows, 5);
+        assert_eq!(footer.row_groups.len(), 2);
+
+        let batches = read(data.clone());
+        assert_eq!(
+            pretty_format_batches(&batches).unwrap().to_string(),
+            [
+                "+-----+-----+-------------------------------+",
+                "| tag | val | time                          |",
+                "+-----+-----+-------------------------------+",
+                "| a   | 1   | 1970-01-01T00:00:00.000000001 |",
+                "| a   | 2   | 1970-01-01T00:00:00.000000002 |",
+                "| a   | 3   | 1970-01-01T00:00:00.000000010 |",
+                "| a   | 4   | 1970-01-01T00:00:00.000000020 |",
+                "| a   | 5   | 1970-01-01T00:00:00.000000030 |",
+                "+-----+-----+-------------------------------+",
+            ]
+            .join("\n")
+        );
+
+        // the IOx metadata of the output is readable
+        let parquet_meta = IoxParquetMetaData::from_file_bytes(Bytes::from(data))
+            .unwrap()
+            .unwrap()
+            .decode()
+            .unwrap();
+        assert_eq!(parquet_meta.row_count(), 5);
+        assert_eq!(parquet_meta.read_iox_metadata_new().unwrap(), meta);
+
+        // the returned footer matches the file
+        let parquet_meta = IoxParquetMetaData::try_from(footer)
+            .unwrap()
+            .decode()
+            .unwrap();
+        assert_eq!(parquet_meta.read_iox_metadata_new().unwrap(), meta);
+    }
+
+    #[tokio::test]
+    async fn test_not_eligible() {
+        let meta = meta();
+        let file1 = file(&meta, "a", &[1, 2], &[1.0, 2.0]).await;
+
+        assert!(matches!(
+            concat_parquet(&[], &meta),
+            Err(ConcatError::NoFiles)
+        ));
+
+        // overlapping time ranges
+        let file2 = file(&meta, "a", &[2, 3], &[1.0, 2.0]).await;
+        assert!(matches!(
+            concat_parquet(&[file1.clone(), file2], &meta),
+            Err(ConcatError::Overlapping)
+        ));
+
+        // different tag values would not be sorted
+        let file2 = file(&meta, "b", &[10, 20], &[1.0, 2.0]).await;
+        assert!(matches!(
+            concat_parquet(&[file1.clone(), file2], &meta),
+            Err(ConcatError::NotConstant { .. })
+        ));
+
+        // different sort key
+        let other_meta = IoxMetadata {
+            sort_key: Some(SortKey::from_columns(["time"])),
+            ..meta.clone()
+        };
+        let file2 = file(&other_meta, "a", &[10, 20], &[1.0, 2.0]).await;
+        assert!(matches!(
+            concat_parquet(&[file1.clone(), file2], &meta),
+            Err(ConcatError::SortKeyMismatch { index: 1 })
+        ));
+
+        // not a parquet file
+        assert!(matches!(
+            concat_parquet(&[file1, Bytes::from_static(b"foo")], &meta),
+            Err(ConcatError::InvalidFile { index: 1, .. })
+        ));
+    }
+
+    fn meta() -> IoxMetadata {
+        IoxMetadata {
+            object_store_id: Uuid::new_v4(),
+            creation_timestamp: Time::from_timestamp_nanos(42),
+            namespace_id: NamespaceId::new(1),
+            namespace_name: "bananas".into(),
+            shard_id: ShardId::new(2),
+            table_id: TableId::new(3),
+            table_name: "platanos".into(),
+            partition_id: PartitionId::new(4),
+            partition_key: "potato".into(),
+            max_sequence_number: SequenceNumber::new(11),
+            compaction_level: CompactionLevel::FileNonOverlapped,
+            sort_key: Some(SortKey::from_columns(["tag", "time"])),
+        }
+    }
+
+    async fn file(meta: &IoxMetadata, tag: &str, times: &[i64], vals: &[f64]) -> Bytes {
+        let schema = SchemaBuilder::new()
+            .tag("tag")
+            .influx_field("val", schema::InfluxFieldType::Float)
+            .timestamp()
+            .build()
+            .unwrap();
+
+        let tags: DictionaryArray<Int32Type> = times.iter().map(|_| Some(tag)).collect();
+        let batch = RecordBatch::try_new(
+            schema.as_arrow(),
+            vec![
+                Arc::new(tags) as ArrayRef,
+                Arc::new(Float64Array::from(vals.to_vec())),
+                Arc::new(TimestampNanosecondArray::from(times.to_vec())),
+            ],
+        )
+        .unwrap();
+
+        let (data, _) = to_parquet_bytes(futures::stream::iter([Ok(batch)]), meta)
+            .await
+            .unwrap();
+        Bytes::from(data)
+    }
+
+    fn read(data: Vec<u8>) -> Vec<RecordBatch> {
+        ParquetRecordBatchReaderBuilder::try_new(Bytes::from(data))
+            .unwrap()
+            .build()
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    }
+}
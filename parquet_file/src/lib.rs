@@ -16,6 +16,7 @@
 #![allow(clippy::missing_docs_in_private_items)]
 
 pub mod chunk;
+pub mod concat;
 pub mod metadata;
 pub mod serialize;
 pub mod storage;
@@ -24,6 +25,11 @@ use data_types::{NamespaceId, ParquetFile, PartitionId, ShardId, TableId};
 use object_store::path::Path;
 use uuid::Uuid;
 
+pub use data_types::ParquetPathLayout;
+
+/// First path segment of [`ParquetPathLayout::V2`] paths.
+const V2_PREFIX: &str = "ns";
+
 /// Location of a Parquet file within a database's object store.
 /// The exact format is an implementation detail and is subject to change.
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
@@ -53,8 +59,13 @@ impl ParquetFilePath {
         }
     }
 
-    /// Get object-store path.
+    /// Get object-store path in the default [`ParquetPathLayout`].
     pub fn object_store_path(&self) -> Path {
+        self.object_store_path_with_layout(ParquetPathLayout::default())
+    }
+
+    /// Get object-store path in the given `layout`.
+    pub fn object_store_path_with_layout(&self, layout: ParquetPathLayout) -> Path {
         let Self {
             namespace_id,
             table_id,
@@ -63,13 +74,23 @@ impl ParquetFilePath {
             object_store_id,
         } = self;
 
-        Path::from_iter([
-            namespace_id.to_string().as_str(),
-            table_id.to_string().as_str(),
-            shard_id.to_string().as_str(),
-            partition_id.to_string().as_str(),
-            &format!("{}.parquet", object_store_id),
-        ])
+        let file_name = format!("{}.parquet", object_store_id);
+        match layout {
+            ParquetPathLayout::V1 => Path::from_iter([
+                namespace_id.to_string().as_str(),
+                table_id.to_string().as_str(),
+                shard_id.to_string().as_str(),
+                partition_id.to_string().as_str(),
+                &file_name,
+            ]),
+            ParquetPathLayout::V2 => Path::from_iter([
+                V2_PREFIX,
+                namespace_id.to_string().as_str(),
+                table_id.to_string().as_str(),
+                partition_id.to_string().as_str(),
+                &file_name,
+            ]),
+        }
     }
 }
 
@@ -121,5 +142,20 @@ mod tests {
             path.to_string(),
             "1/2/3/4/00000000-0000-0000-0000-000000000000.parquet".to_string(),
         );
+
+        let path = pfp.object_store_path_with_layout(ParquetPathLayout::V2);
+        assert_eq!(
+            path.to_string(),
+            "ns/1/2/4/00000000-0000-0000-0000-000000000000.parquet".to_string(),
+        );
+    }
+
+    #[test]
+    fn parquet_path_layout_roundtrip() {
+        for layout in [ParquetPathLayout::V1, ParquetPathLayout::V2] {
+            assert_eq!(layout.to_string().parse::<ParquetPathLayout>(), Ok(layout));
+        }
+        assert_eq!("V2".parse::<ParquetPathLayout>(), Ok(ParquetPathLayout::V2));
+        "v3".parse::<ParquetPathLayout>().unwrap_err();
     }
 }
@@ -88,9 +88,9 @@
 //! [Thrift Compact Protocol]: https://github.com/apache/thrift/blob/master/doc/specs/thrift-compact-protocol.md
 use bytes::Bytes;
 use data_types::{
-    ColumnId, ColumnSet, ColumnSummary, CompactionLevel, InfluxDbType, NamespaceId,
-    ParquetFileParams, PartitionId, PartitionKey, SequenceNumber, ShardId, StatValues, Statistics,
-    TableId, Timestamp,
+    ColumnId, ColumnSet, ColumnStats, ColumnStatsSet, ColumnSummary, CompactionLevel, InfluxDbType,
+    NamespaceId, ParquetFileParams, PartitionId, PartitionKey, SequenceNumber, ShardId, StatValues,
+    Statistics, TableId, TableSummary, Timestamp,
 };
 use generated_types::influxdata::iox::ingester::v1 as proto;
 use iox_time::Time;
@@ -332,7 +332,7 @@ impl IoxMetadata {
     }
 
     /// Read from protobuf message
-    fn from_protobuf(data: &[u8]) -> Result<Self> {
+    pub(crate) fn from_protobuf(data: &[u8]) -> Result<Self> {
         // extract protobuf message from bytes
         let proto_msg = proto::IoxMetadata::decode(data)
             .map_err(|err| Box::new(err) as _)
@@ -432,6 +432,22 @@ impl IoxMetadata {
             .read_statistics(&*schema)
             .expect("invalid statistics");
         let columns: Vec<_> = stats.iter().map(|v| column_id_map(&v.name)).collect();
+
+        // Publish the null counts of all columns and the min/max values of the string columns
+        // (tags and string fields), so that files can be pruned by tag values without fetching
+        // their footers.
+        let column_stats = ColumnStatsSet::new(stats.iter().map(|v| {
+            let (min, max) = match &v.stats {
+                Statistics::String(s) => (s.min.clone(), s.max.clone()),
+                _ => (None, None),
+            };
+            ColumnStats {
+                column_id: column_id_map(&v.name),
+                null_count: v.stats.null_count(),
+                min,
+                max,
+            }
+        }));
         let time_summary = stats
             .into_iter()
             .find(|v| v.name == TIME_COLUMN_NAME)
@@ -464,6 +480,7 @@ impl IoxMetadata {
             row_count: row_count.try_into().expect("row count overflows i64"),
             created_at: Timestamp::new(self.creation_timestamp.timestamp_nanos()),
             column_set: ColumnSet::new(columns),
+            column_stats,
         }
     }
 
@@ -765,6 +782,25 @@ impl DecodedIoxParquetMetaData {
         Ok(column_summaries)
     }
 
+    /// Read IOx statistics of every row group from parquet metadata, in row group order.
+    ///
+    /// Unlike [`read_statistics`](Self::read_statistics), the statistics of the row groups are
+    /// not combined, so they can be used to answer queries about parts of the file.
+    pub fn read_row_group_statistics(&self, schema: &Schema) -> Result<Vec<TableSummary>> {
+        ensure!(!self.md.row_groups().is_empty(), NoRowGroupSnafu);
+
+        self.md
+            .row_groups()
+            .iter()
+            .enumerate()
+            .map(|(row_group_idx, row_group)| {
+                let columns =
+                    read_statistics_from_parquet_row_group(row_group, row_group_idx, schema)?;
+                Ok(TableSummary { columns })
+            })
+            .collect()
+    }
+
     /// Estimate the memory consumption of this object and its contents
     pub fn size(&self) -> usize {
         // This is likely a wild under count as it doesn't include
@@ -774,6 +810,12 @@ impl DecodedIoxParquetMetaData {
     }
 }
 
+impl From<ParquetMetaData> for DecodedIoxParquetMetaData {
+    fn from(md: ParquetMetaData) -> Self {
+        Self { md }
+    }
+}
+
 /// Read IOx statistics from parquet row group metadata.
 fn read_statistics_from_parquet_row_group(
     row_group: &ParquetRowGroupMetaData,
@@ -959,7 +1001,8 @@ fn extract_iox_statistics(
 mod tests {
     use super::*;
     use arrow::{
-        array::{ArrayRef, StringArray, TimestampNanosecondArray},
+        array::{ArrayRef, DictionaryArray, Float64Array, StringArray, TimestampNanosecondArray},
+        datatypes::Int32Type,
         record_batch::RecordBatch,
     };
     use data_types::CompactionLevel;
@@ -1073,6 +1116,91 @@ mod tests {
         // SchemaBuilder)
         let col_summary = decoded.read_statistics(&*schema).unwrap();
         assert!(!col_summary.is_empty());
+
+        // The file has a single row group, so its statistics equal the file statistics
+        let row_group_summaries = decoded.read_row_group_statistics(&*schema).unwrap();
+        assert_eq!(row_group_summaries.len(), 1);
+        assert_eq!(row_group_summaries[0].columns, col_summary);
+        assert_eq!(row_group_summaries[0].total_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_to_parquet_file_column_stats() {
+        let meta = IoxMetadata {
+            object_store_id: Default::default(),
+            creation_timestamp: Time::from_timestamp_nanos(42),
+            namespace_id: NamespaceId::new(1),
+            namespace_name: "bananas".into(),
+            shard_id: ShardId::new(2),
+            table_id: TableId::new(3),
+            table_name: "platanos".into(),
+            partition_id: PartitionId::new(4),
+            partition_key: "potato".into(),
+            max_sequence_number: SequenceNumber::new(11),
+            compaction_level: CompactionLevel::Initial,
+            sort_key: None,
+        };
+
+        let tags: ArrayRef = Arc::new(DictionaryArray::<Int32Type>::from_iter([
+            Some("west"),
+            None,
+            Some("east"),
+        ]));
+        let values: ArrayRef = Arc::new(Float64Array::from_iter([Some(1.0), Some(2.0), None]));
+        let timestamps = to_timestamp_array(&[1, 2, 3]);
+
+        let schema = SchemaBuilder::new()
+            .tag("region")
+            .influx_field("value", InfluxFieldType::Float)
+            .timestamp()
+            .build()
+            .expect("could not create schema")
+            .as_arrow();
+
+        let batch = RecordBatch::try_new(schema, vec![tags, values, timestamps]).unwrap();
+        let stream = futures::stream::iter([Ok(batch)]);
+
+        let (_bytes, file_meta) = crate::serialize::to_parquet_bytes(stream, &meta)
+            .await
+            .expect("should serialize");
+        let iox_parquet_meta = IoxParquetMetaData::try_from(file_meta).unwrap();
+
+        let params =
+            meta.to_parquet_file(
+                PartitionId::new(4),
+                100,
+                &iox_parquet_meta,
+                |name| match name {
+                    "region" => ColumnId::new(1),
+                    "value" => ColumnId::new(2),
+                    TIME_COLUMN_NAME => ColumnId::new(3),
+                    _ => panic!("unexpected column {name}"),
+                },
+            );
+
+        assert_eq!(
+            params.column_stats,
+            ColumnStatsSet::new([
+                ColumnStats {
+                    column_id: ColumnId::new(1),
+                    null_count: Some(1),
+                    min: Some("east".to_string()),
+                    max: Some("west".to_string()),
+                },
+                ColumnStats {
+                    column_id: ColumnId::new(2),
+                    null_count: Some(1),
+                    min: None,
+                    max: None,
+                },
+                ColumnStats {
+                    column_id: ColumnId::new(3),
+                    null_count: Some(0),
+                    min: None,
+                    max: None,
+                },
+            ])
+        );
     }
 
     fn to_timestamp_array(timestamps: &[i64]) -> ArrayRef {
@@ -9,7 +9,10 @@ use parquet::{
     arrow::ArrowWriter,
     basic::Compression,
     errors::ParquetError,
-    file::{metadata::KeyValue, properties::WriterProperties},
+    file::{
+        metadata::KeyValue,
+        properties::{WriterProperties, WriterPropertiesBuilder},
+    },
 };
 use thiserror::Error;
 
@@ -88,6 +91,42 @@ pub async fn to_parquet<S, W>(
     meta: &IoxMetadata,
     sink: W,
 ) -> Result<parquet_format::FileMetaData, CodecError>
+where
+    S: Stream<Item = Result<RecordBatch, ArrowError>> + Send,
+    W: Write + Send,
+{
+    // Serialize the IoxMetadata to the protobuf bytes.
+    let props = writer_props(meta)?;
+
+    encode(batches, props, sink).await
+}
+
+/// Encode `batches` into a parquet file in `W`, like [`to_parquet()`], but
+/// without any IOx metadata.
+///
+/// This is used to export arbitrary [`RecordBatch`]es, e.g. query results,
+/// with the same encoding (compression, row group size) as the parquet files
+/// of IOx.
+///
+/// # Errors
+///
+/// See [`to_parquet()`].
+pub async fn to_parquet_without_metadata<S, W>(
+    batches: S,
+    sink: W,
+) -> Result<parquet_format::FileMetaData, CodecError>
+where
+    S: Stream<Item = Result<RecordBatch, ArrowError>> + Send,
+    W: Write + Send,
+{
+    encode(batches, base_writer_props().build(), sink).await
+}
+
+async fn encode<S, W>(
+    batches: S,
+    props: WriterProperties,
+    sink: W,
+) -> Result<parquet_format::FileMetaData, CodecError>
 where
     S: Stream<Item = Result<RecordBatch, ArrowError>> + Send,
     W: Write + Send,
@@ -109,9 +148,6 @@ where
         .map(|v| v.schema())
         .ok_or(CodecError::SchemaPeek)?;
 
-    // Serialize the IoxMetadata to the protobuf bytes.
-    let props = writer_props(meta)?;
-
     // Construct the arrow serializer with the metadata as part of the parquet
     // file properties.
     let mut writer = ArrowWriter::try_new(sink, Arc::clone(&schema), Some(props))?;
@@ -174,17 +210,21 @@ where
 fn writer_props(meta: &IoxMetadata) -> Result<WriterProperties, prost::EncodeError> {
     let bytes = meta.to_protobuf()?;
 
-    let builder = WriterProperties::builder()
-        .set_key_value_metadata(Some(vec![KeyValue {
-            key: METADATA_KEY.to_string(),
-            value: Some(base64::encode(&bytes)),
-        }]))
-        .set_compression(Compression::ZSTD)
-        .set_max_row_group_size(ROW_GROUP_WRITE_SIZE);
+    let builder = base_writer_props().set_key_value_metadata(Some(vec![KeyValue {
+        key: METADATA_KEY.to_string(),
+        value: Some(base64::encode(&bytes)),
+    }]));
 
     Ok(builder.build())
 }
 
+/// The [`WriterProperties`] shared by all parquet files written by IOx.
+fn base_writer_props() -> WriterPropertiesBuilder {
+    WriterProperties::builder()
+        .set_compression(Compression::ZSTD)
+        .set_max_row_group_size(ROW_GROUP_WRITE_SIZE)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -249,6 +289,44 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_encode_stream_without_metadata() {
+        let batch = RecordBatch::try_from_iter([("a", to_string_array(&["value"]))]).unwrap();
+        let stream = futures::stream::iter([Ok(batch.clone())]);
+
+        let mut bytes = vec![];
+        let file_meta = to_parquet_without_metadata(stream, &mut bytes)
+            .await
+            .expect("should serialize");
+        assert_eq!(file_meta.num_rows, 1);
+
+        // the file has no IOx metadata
+        let bytes = Bytes::from(bytes);
+        let parquet_meta = IoxParquetMetaData::from_file_bytes(bytes.clone())
+            .expect("should decode")
+            .expect("should contain metadata")
+            .decode()
+            .expect("should decode parquet metadata");
+        assert!(parquet_meta.read_iox_metadata_new().is_err());
+
+        let arrow_reader = ParquetRecordBatchReaderBuilder::try_new(bytes)
+            .expect("should init builder")
+            .build()
+            .expect("should create reader");
+        let record_batches = arrow_reader
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(record_batches, vec![batch]);
+
+        // empty results are rejected
+        let stream = futures::stream::iter(Vec::<Result<RecordBatch, ArrowError>>::new());
+        let err = to_parquet_without_metadata(stream, vec![])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CodecError::NoRecordBatches));
+    }
+
     fn to_string_array(strs: &[&str]) -> ArrayRef {
         let array: StringArray = strs.iter().map(|s| Some(*s)).collect();
         Arc::new(array)
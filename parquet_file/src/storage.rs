@@ -2,29 +2,56 @@
 //! object store and reading it back.
 
 use crate::{
-    metadata::{IoxMetadata, IoxParquetMetaData},
+    concat::{concat_parquet, ConcatError},
+    metadata::{DecodedIoxParquetMetaData, IoxMetadata, IoxParquetMetaData, METADATA_KEY},
     serialize::{self, CodecError, ROW_GROUP_WRITE_SIZE},
-    ParquetFilePath,
+    ParquetFilePath, ParquetPathLayout,
 };
 use arrow::{
+    compute::{cast, lexsort_to_indices, take, SortColumn},
     datatypes::{Field, Schema, SchemaRef},
     error::{ArrowError, Result as ArrowResult},
     record_batch::RecordBatch,
 };
-use bytes::Bytes;
+use bytes::{Buf, Bytes};
+use data_types::{DeletePredicate, TableSummary, TimestampRange};
 use datafusion::{
-    parquet::arrow::{arrow_reader::ParquetRecordBatchReaderBuilder, ProjectionMask},
-    physical_plan::SendableRecordBatchStream,
+    error::DataFusionError,
+    execution::context::ExecutionProps,
+    logical_plan::{DFSchema, Expr},
+    parquet::{
+        arrow::{
+            arrow_reader::ParquetRecordBatchReaderBuilder, parquet_to_arrow_schema, ProjectionMask,
+        },
+        errors::ParquetError,
+        file::{
+            footer::parse_metadata,
+            metadata::ParquetMetaData,
+            reader::{ChunkReader, Length},
+            statistics::Statistics,
+        },
+    },
+    physical_expr::create_physical_expr,
+    physical_plan::{PhysicalExpr, SendableRecordBatchStream},
 };
-use datafusion_util::{watch::WatchedTask, AdapterStream};
-use futures::{Stream, TryStreamExt};
-use object_store::{DynObjectStore, GetResult};
+use datafusion_util::{batch_filter, watch::WatchedTask, AdapterStream};
+use futures::Stream;
+use object_store::DynObjectStore;
 use observability_deps::tracing::*;
-use predicate::Predicate;
-use schema::selection::{select_schema, Selection};
-use std::{collections::HashMap, num::TryFromIntError, sync::Arc, time::Duration};
+use predicate::{delete_predicate::delete_predicates_to_filter, Predicate};
+use schema::{
+    selection::{select_schema, Selection},
+    sort::{SortKey, SortKeyBuilder},
+    InfluxFieldType, TIME_COLUMN_NAME,
+};
+use std::{collections::HashMap, num::TryFromIntError, ops::Range, sync::Arc, time::Duration};
 use thiserror::Error;
-use tokio::io::AsyncReadExt;
+use uuid::Uuid;
+
+mod retry;
+
+use retry::ReadRetries;
+pub use retry::ReadRetryConfig;
 
 /// Parquet row group read size
 pub const ROW_GROUP_READ_SIZE: usize = 1024 * 1024;
@@ -33,6 +60,19 @@ pub const ROW_GROUP_READ_SIZE: usize = 1024 * 1024;
 // Skip clippy due to <https://github.com/rust-lang/rust-clippy/issues/8159>.
 #[allow(clippy::assertions_on_constants)]
 const _: () = assert!(ROW_GROUP_WRITE_SIZE % ROW_GROUP_READ_SIZE == 0);
+
+/// Number of bytes fetched from the end of a parquet file when reading the footer during partial reads.
+///
+/// This matches the read size that parquet-rs uses for the footer, so that a single request usually suffices to get
+/// the entire file metadata.
+const FOOTER_READ_SIZE: usize = 64 * 1024;
+
+/// Byte ranges that are separated by less than this many bytes are coalesced into a single request during partial
+/// reads.
+///
+/// Fetching a few unused bytes is much cheaper than issuing another object store request.
+const RANGE_COALESCE_GAP: usize = 1024 * 1024;
+
 /// Errors returned during a Parquet "put" operation, covering [`RecordBatch`]
 /// pull from the provided stream, encoding, and finally uploading the bytes to
 /// the object store.
@@ -82,6 +122,66 @@ pub enum ReadError {
     /// Malformed integer data for row count
     #[error("Malformed row count integer")]
     MalformedRowCount(#[from] TryFromIntError),
+
+    /// The object is too small to be a parquet file.
+    #[error("Object '{path}' is too small to be a parquet file: {size} bytes")]
+    TooSmall {
+        /// Path of the affected parquet file.
+        path: object_store::path::Path,
+
+        /// Object size in bytes.
+        size: usize,
+    },
+
+    /// The IOx schema or statistics cannot be read from the parquet metadata.
+    #[error("invalid IOx parquet metadata: {0}")]
+    Metadata(crate::metadata::Error),
+
+    /// The delete predicates cannot be evaluated against the selected columns.
+    #[error("Cannot apply delete predicates to file '{path}': {source}")]
+    DeletePredicate {
+        /// Path of the affected parquet file.
+        path: object_store::path::Path,
+
+        /// Source error
+        source: DataFusionError,
+    },
+}
+
+/// Errors returned by [`ParquetStorage::rewrite_sorted`].
+#[derive(Debug, Error)]
+pub enum RewriteError {
+    /// Fetching or decoding the existing file failed.
+    #[error("failed to read existing parquet file: {0}")]
+    Read(#[from] ReadError),
+
+    /// The existing file does not contain valid IOx metadata.
+    #[error("failed to read IOx metadata of existing parquet file: {0}")]
+    Metadata(crate::metadata::Error),
+
+    /// Re-sorting the data failed.
+    #[error("failed to sort data: {0}")]
+    Sort(#[source] ArrowError),
+
+    /// Writing the new file failed.
+    #[error("failed to write rewritten parquet file: {0}")]
+    Upload(#[from] UploadError),
+}
+
+/// Errors returned by [`ParquetStorage::concat`].
+#[derive(Debug, Error)]
+pub enum ConcatFilesError {
+    /// Fetching an input file failed.
+    #[error("failed to read input parquet file: {0}")]
+    Read(#[from] ReadError),
+
+    /// The input files cannot be concatenated.
+    #[error("cannot concatenate parquet files: {0}")]
+    Concat(#[from] ConcatError),
+
+    /// An error during Parquet metadata conversion of the concatenated file.
+    #[error("failed to construct IOx parquet metadata: {0}")]
+    Metadata(crate::metadata::Error),
 }
 
 /// The [`ParquetStorage`] type encapsulates [`RecordBatch`] persistence to an
@@ -98,13 +198,120 @@ pub enum ReadError {
 pub struct ParquetStorage {
     /// Underlying object store.
     object_store: Arc<DynObjectStore>,
+
+    /// Only fetch the byte ranges of the file that are required to answer a
+    /// read, see [`with_partial_reads`](Self::with_partial_reads).
+    partial_reads: bool,
+
+    /// Retries of transient object store errors during reads, see
+    /// [`with_read_retries`](Self::with_read_retries).
+    read_retries: ReadRetries,
+
+    /// Path layout of written files, see
+    /// [`with_path_layout`](Self::with_path_layout).
+    path_layout: ParquetPathLayout,
 }
 
 impl ParquetStorage {
     /// Initialise a new [`ParquetStorage`] using `object_store` as the
     /// persistence layer.
     pub fn new(object_store: Arc<DynObjectStore>) -> Self {
-        Self { object_store }
+        Self {
+            object_store,
+            partial_reads: false,
+            read_retries: ReadRetries::disabled(),
+            path_layout: ParquetPathLayout::default(),
+        }
+    }
+
+    /// Underlying object store.
+    pub fn object_store(&self) -> &Arc<DynObjectStore> {
+        &self.object_store
+    }
+
+    /// Replace the underlying object store by the result of `f`, e.g. to
+    /// instrument all requests. All other settings are kept.
+    pub fn map_object_store<F>(self, f: F) -> Self
+    where
+        F: FnOnce(Arc<DynObjectStore>) -> Arc<DynObjectStore>,
+    {
+        Self {
+            object_store: f(self.object_store),
+            ..self
+        }
+    }
+
+    /// Enable or disable partial reads.
+    ///
+    /// When enabled, reads fetch the parquet footer using ranged requests,
+    /// determine the row groups that can match the predicate and then only
+    /// fetch the byte ranges of the selected columns within these row groups.
+    ///
+    /// This drastically reduces the number of transferred bytes for selective
+    /// reads of large files at the cost of more (but smaller) object store
+    /// requests. Readers that consume entire files (e.g. the compactor) should
+    /// keep this disabled.
+    pub fn with_partial_reads(self, partial_reads: bool) -> Self {
+        Self {
+            partial_reads,
+            ..self
+        }
+    }
+
+    /// Retry object store requests of reads that fail with a transient error.
+    ///
+    /// By default, every object store error fails the read. With retries
+    /// enabled, failed requests are re-issued and downloads that break
+    /// mid-stream are resumed from the last received byte, so a single flaky
+    /// request does not fail an entire query.
+    pub fn with_read_retries(
+        self,
+        config: ReadRetryConfig,
+        metric_registry: &metric::Registry,
+    ) -> Self {
+        Self {
+            read_retries: ReadRetries::new(config, metric_registry),
+            ..self
+        }
+    }
+
+    /// Write files with the given path layout.
+    ///
+    /// Reads always resolve files of both layouts: the path of the
+    /// configured layout is tried first and the path of the other layout if
+    /// the file does not exist. This allows switching the layout without
+    /// moving existing files.
+    pub fn with_path_layout(self, path_layout: ParquetPathLayout) -> Self {
+        Self {
+            path_layout,
+            ..self
+        }
+    }
+
+    /// Path layout of written files.
+    pub fn path_layout(&self) -> ParquetPathLayout {
+        self.path_layout
+    }
+
+    /// Object store paths of `path` in the order reads try them.
+    fn read_paths(&self, path: &ParquetFilePath) -> [object_store::path::Path; 2] {
+        [
+            path.object_store_path_with_layout(self.path_layout),
+            path.object_store_path_with_layout(self.path_layout.other()),
+        ]
+    }
+
+    /// Fetch the entire file at `path`, resolving both path layouts.
+    async fn get_bytes(&self, path: &ParquetFilePath) -> Result<Bytes, ReadError> {
+        let [path, fallback_path] = self.read_paths(path);
+        let res = match self.object_store.get(&path).await {
+            Err(object_store::Error::NotFound { .. }) => {
+                self.object_store.get(&fallback_path).await
+            }
+            res => res,
+        };
+
+        Ok(res?.bytes().await?)
     }
 
     /// Push `batches`, a stream of [`RecordBatch`] instances, to object
@@ -141,23 +348,28 @@ impl ParquetStorage {
             "IoxParquetMetaData coverted from Row Group Metadata (aka FileMetaData)"
         );
 
-        // Derive the correct object store path from the metadata.
-        let path = ParquetFilePath::from(meta).object_store_path();
-
         let file_size = data.len();
-        let data = Bytes::from(data);
+        self.put(meta, Bytes::from(data)).await;
+
+        Ok((parquet_meta, file_size))
+    }
+
+    /// Store the encoded parquet file `data` at the path derived from `meta`.
+    ///
+    /// This method retries forever in the presence of object store errors.
+    async fn put(&self, meta: &IoxMetadata, data: Bytes) {
+        // Derive the correct object store path from the metadata.
+        let path = ParquetFilePath::from(meta).object_store_path_with_layout(self.path_layout);
 
         // Retry uploading the file endlessly.
         //
-        // This is abort-able by the user by dropping the upload() future.
+        // This is abort-able by the user by dropping the future.
         //
         // Cloning `data` is a ref count inc, rather than a data copy.
         while let Err(e) = self.object_store.put(&path, data.clone()).await {
             error!(error=%e, ?meta, "failed to upload parquet file to object storage");
             tokio::time::sleep(Duration::from_secs(1)).await;
         }
-
-        Ok((parquet_meta, file_size))
     }
 
     /// Pull the Parquet-encoded [`RecordBatch`] at the file path derived from
@@ -167,24 +379,45 @@ impl ParquetStorage {
     ///
     /// This impl fetches the associated Parquet file bytes from object storage,
     /// temporarily persisting them to a local temp file to feed to the arrow
-    /// reader.
+    /// reader. If [partial reads](Self::with_partial_reads) are enabled, only
+    /// the byte ranges of row groups that may match the `predicate`'s time
+    /// range and of the selected columns are fetched.
+    ///
+    /// The rows deleted by any of the `delete_predicates` are removed while
+    /// the file is scanned, so that no separate filter is needed afterwards.
+    /// All columns of the delete predicates, including the time column, must
+    /// be part of the `selection`.
+    ///
+    /// Files of both [path layouts](Self::with_path_layout) are found.
     ///
     /// No caching is performed by `read_filter()`, and each call to
     /// `read_filter()` will re-download the parquet file unless the underlying
     /// object store impl caches the fetched bytes.
     pub fn read_filter(
         &self,
-        _predicate: &Predicate,
+        predicate: &Predicate,
+        delete_predicates: &[Arc<DeletePredicate>],
         selection: Selection<'_>,
         schema: SchemaRef,
         path: &ParquetFilePath,
     ) -> Result<SendableRecordBatchStream, ReadError> {
-        let path = path.object_store_path();
+        let [path, fallback_path] = self.read_paths(path);
         trace!(path=?path, "fetching parquet data for filtered read");
 
         // Compute final (output) schema after selection
         let schema = select_schema(selection, &schema);
 
+        // Rows that are not deleted, evaluated against the selected columns
+        let delete_filter = match delete_predicates_to_filter(delete_predicates) {
+            Some(expr) => Some(physical_filter(expr, &schema).map_err(|source| {
+                ReadError::DeletePredicate {
+                    path: path.clone(),
+                    source,
+                }
+            })?),
+            None => None,
+        };
+
         let (tx, rx) = tokio::sync::mpsc::channel(2);
 
         // Run async dance here to make sure any error returned
@@ -193,10 +426,51 @@ impl ParquetStorage {
         let object_store = Arc::clone(&self.object_store);
         let schema_captured = Arc::clone(&schema);
         let tx_captured = tx.clone();
+        let partial_reads = self.partial_reads;
+        let read_retries = self.read_retries.clone();
+        let range = predicate.range;
         let fut = async move {
-            let download_result =
-                download_and_scan_parquet(schema_captured, path, object_store, tx_captured.clone())
-                    .await;
+            let download = |path| {
+                let schema = Arc::clone(&schema_captured);
+                let object_store = Arc::clone(&object_store);
+                let read_retries = read_retries.clone();
+                let delete_filter = delete_filter.clone();
+                let tx = tx_captured.clone();
+                async move {
+                    if partial_reads {
+                        fetch_ranges_and_scan_parquet(
+                            schema,
+                            path,
+                            object_store,
+                            read_retries,
+                            range,
+                            delete_filter,
+                            tx,
+                        )
+                        .await
+                    } else {
+                        download_and_scan_parquet(
+                            schema,
+                            path,
+                            object_store,
+                            read_retries,
+                            delete_filter,
+                            tx,
+                        )
+                        .await
+                    }
+                }
+            };
+
+            // nothing was sent yet if the file does not exist, so it is safe to try the other
+            // path layout
+            let download_result = match download(path).await {
+                Err(ReadError::ObjectStore(object_store::Error::NotFound { .. })) => {
+                    trace!(path=?fallback_path, "parquet file not found, trying other path layout");
+                    download(fallback_path).await
+                }
+                res => res,
+            };
 
             // If there was an error returned from download_and_scan_parquet send it back to the receiver.
             if let Err(e) = download_result {
@@ -222,10 +496,250 @@ impl ParquetStorage {
         schema: SchemaRef,
         path: &ParquetFilePath,
     ) -> Result<SendableRecordBatchStream, ReadError> {
-        self.read_filter(&Predicate::default(), Selection::All, schema, path)
+        self.read_filter(&Predicate::default(), &[], Selection::All, schema, path)
+    }
+
+    /// Read the IOx statistics of every row group of the parquet file at `path`, in row group
+    /// order.
+    ///
+    /// Only the footer of the file is fetched, using ranged requests. Files of both
+    /// [path layouts](Self::with_path_layout) are found.
+    pub async fn read_row_group_statistics(
+        &self,
+        path: &ParquetFilePath,
+    ) -> Result<Vec<TableSummary>, ReadError> {
+        let [path, fallback_path] = self.read_paths(path);
+        let object_store = self.object_store.as_ref();
+        let (metadata, _reader, _footer_start) =
+            match fetch_metadata(object_store, &self.read_retries, &path).await {
+                Err(ReadError::ObjectStore(object_store::Error::NotFound { .. })) => {
+                    fetch_metadata(object_store, &self.read_retries, &fallback_path).await?
+                }
+                res => res?,
+            };
+
+        let decoded = DecodedIoxParquetMetaData::from(metadata);
+        let schema = decoded.read_schema().map_err(ReadError::Metadata)?;
+        decoded
+            .read_row_group_statistics(&schema)
+            .map_err(ReadError::Metadata)
+    }
+
+    /// Check that the parquet file at `path` can be read with `schema`, i.e. that every column
+    /// of `schema` exists in the file with the same type or a type that is cast on read (see
+    /// [`InfluxFieldType::can_migrate_to`]).
+    ///
+    /// Only the footer of the file is fetched, using ranged requests. Returns
+    /// [`ReadError::SchemaMismatch`] if the file cannot be read with `schema`.
+    pub async fn check_schema(
+        &self,
+        schema: &Schema,
+        path: &ParquetFilePath,
+    ) -> Result<(), ReadError> {
+        let [path, fallback_path] = self.read_paths(path);
+        let object_store = self.object_store.as_ref();
+        let (metadata, path) = match fetch_metadata(object_store, &self.read_retries, &path).await {
+            Err(ReadError::ObjectStore(object_store::Error::NotFound { .. })) => {
+                let (metadata, _reader, _footer_start) =
+                    fetch_metadata(object_store, &self.read_retries, &fallback_path).await?;
+                (metadata, fallback_path)
+            }
+            res => (res?.0, path),
+        };
+
+        let file_metadata = metadata.file_metadata();
+        let file_schema = parquet_to_arrow_schema(
+            file_metadata.schema_descr(),
+            file_metadata.key_value_metadata(),
+        )?;
+        project_for_parquet_reader(&file_schema, schema)
+            .map(|_| ())
+            .map_err(|source| ReadError::SchemaMismatch { path, source })
+    }
+
+    /// Rewrite the existing parquet file at `path` so that its data is sorted
+    /// by `new_sort_key`.
+    ///
+    /// This is used when the sort key of a partition evolved after the file
+    /// was written (e.g. new tag columns were added): files with an outdated
+    /// sort key must otherwise be re-sorted every time they are queried or
+    /// compacted.
+    ///
+    /// The rewritten data is stored as a NEW object with a freshly generated
+    /// object store ID; all other [`IoxMetadata`] (including the creation
+    /// timestamp, so the rewrite does not make the partition look "hot") is
+    /// taken from the existing file. The sort key stored in the new file is
+    /// `new_sort_key` restricted to the columns of the file. Columns of
+    /// `new_sort_key` that do not exist in the file are ignored.
+    ///
+    /// The existing file is neither modified nor deleted; swapping the files
+    /// in the catalog is up to the caller.
+    ///
+    /// Returns the metadata of the new file along with its parquet metadata
+    /// and size in bytes.
+    ///
+    /// # Memory
+    ///
+    /// Sorting requires the entire file to be held in memory (twice, briefly).
+    /// This is in line with [`upload`](Self::upload), which buffers the
+    /// encoded file as well.
+    pub async fn rewrite_sorted(
+        &self,
+        path: &ParquetFilePath,
+        new_sort_key: &SortKey,
+    ) -> Result<(IoxMetadata, IoxParquetMetaData, usize), RewriteError> {
+        let data = self.get_bytes(path).await?;
+        let path = path.object_store_path_with_layout(self.path_layout);
+        debug!(
+            ?path,
+            ?new_sort_key,
+            "rewriting parquet file with new sort key"
+        );
+
+        let old_meta = IoxParquetMetaData::from_file_bytes(data.clone())
+            .map_err(RewriteError::Metadata)?
+            .ok_or_else(|| ReadError::TooSmall {
+                path: path.clone(),
+                size: 0,
+            })?
+            .decode()
+            .and_then(|md| md.read_iox_metadata_new())
+            .map_err(RewriteError::Metadata)?;
+
+        let builder = ParquetRecordBatchReaderBuilder::try_new(data).map_err(ReadError::from)?;
+        // Drop the IOx metadata of the existing file from the schema, the
+        // new file gets its own metadata attached during serialisation.
+        let schema = builder.schema();
+        let mut schema_metadata = schema.metadata().clone();
+        schema_metadata.remove(METADATA_KEY);
+        let schema = Arc::new(Schema::new_with_metadata(
+            schema.fields().clone(),
+            schema_metadata,
+        ));
+
+        let batches = builder
+            .with_batch_size(ROW_GROUP_READ_SIZE)
+            .build()
+            .map_err(ReadError::from)?
+            .map(|batch| {
+                let batch = batch?;
+                RecordBatch::try_new(Arc::clone(&schema), batch.columns().to_vec())
+            })
+            .collect::<ArrowResult<Vec<_>>>()
+            .map_err(RewriteError::Sort)?;
+        let batch = RecordBatch::concat(&schema, &batches).map_err(RewriteError::Sort)?;
+
+        let file_sort_key = sort_key_for_schema(new_sort_key, &schema);
+        let batch = sort_batch(batch, &file_sort_key).map_err(RewriteError::Sort)?;
+
+        let new_meta = IoxMetadata {
+            object_store_id: Uuid::new_v4(),
+            sort_key: Some(file_sort_key),
+            ..old_meta
+        };
+        let (parquet_meta, file_size) = self
+            .upload(futures::stream::iter([Ok(batch)]), &new_meta)
+            .await?;
+
+        debug!(
+            ?path,
+            new_object_store_id=%new_meta.object_store_id,
+            file_size,
+            "rewrote parquet file with new sort key"
+        );
+
+        Ok((new_meta, parquet_meta, file_size))
+    }
+
+    /// Combine the parquet files at `paths` into a single new file described by
+    /// `meta`, by concatenating their row groups without decoding any data.
+    ///
+    /// This is much cheaper than reading the files and writing their data with
+    /// [`upload`](Self::upload), but only possible if the files do not overlap
+    /// and their concatenation is sorted, see [`crate::concat`] for details.
+    /// If the files cannot be concatenated, [`ConcatFilesError::Concat`] is
+    /// returned and nothing is written.
+    ///
+    /// The input files are neither modified nor deleted.
+    ///
+    /// Returns the parquet metadata of the new file and its size in bytes.
+    ///
+    /// # Retries
+    ///
+    /// Like [`upload`](Self::upload), this method retries uploading the new
+    /// file forever in the presence of object store errors.
+    pub async fn concat(
+        &self,
+        paths: &[ParquetFilePath],
+        meta: &IoxMetadata,
+    ) -> Result<(IoxParquetMetaData, usize), ConcatFilesError> {
+        let mut files = Vec::with_capacity(paths.len());
+        for path in paths {
+            files.push(self.get_bytes(path).await?);
+        }
+
+        let (data, parquet_file_meta) = concat_parquet(&files, meta)?;
+        let parquet_meta =
+            IoxParquetMetaData::try_from(parquet_file_meta).map_err(ConcatFilesError::Metadata)?;
+
+        let file_size = data.len();
+        self.put(meta, Bytes::from(data)).await;
+
+        debug!(
+            n_files = paths.len(),
+            object_store_id=%meta.object_store_id,
+            file_size,
+            "concatenated parquet files"
+        );
+
+        Ok((parquet_meta, file_size))
     }
 }
 
+/// Restrict `sort_key` to the columns present in `schema`, keeping the sort
+/// options.
+fn sort_key_for_schema(sort_key: &SortKey, schema: &Schema) -> SortKey {
+    sort_key
+        .iter()
+        .filter(|(col, _options)| schema.field_with_name(col).is_ok())
+        .fold(
+            SortKeyBuilder::with_capacity(sort_key.len()),
+            |builder, (col, options)| builder.with_col_sort_opts(Arc::clone(col), *options),
+        )
+        .build()
+}
+
+/// Sort `batch` by the columns of `sort_key`, all of which must exist in the
+/// batch.
+fn sort_batch(batch: RecordBatch, sort_key: &SortKey) -> ArrowResult<RecordBatch> {
+    if sort_key.is_empty() || batch.num_rows() == 0 {
+        return Ok(batch);
+    }
+
+    let schema = batch.schema();
+    let sort_columns = sort_key
+        .iter()
+        .map(|(col, options)| {
+            let (idx, _field) = schema
+                .column_with_name(col)
+                .expect("sort key restricted to schema");
+            SortColumn {
+                values: Arc::clone(batch.column(idx)),
+                options: Some(*options),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let indices = lexsort_to_indices(&sort_columns, None)?;
+    let columns = batch
+        .columns()
+        .iter()
+        .map(|c| take(c.as_ref(), &indices, None))
+        .collect::<ArrowResult<Vec<_>>>()?;
+
+    RecordBatch::try_new(schema, columns)
+}
+
 /// Downloads the specified parquet file to a local temporary file
 /// and push the [`RecordBatch`] contents over `tx`, projecting the specified
 /// column indexes.
@@ -236,35 +750,142 @@ async fn download_and_scan_parquet(
     expected_schema: SchemaRef,
     path: object_store::path::Path,
     object_store: Arc<DynObjectStore>,
+    read_retries: ReadRetries,
+    delete_filter: Option<Arc<dyn PhysicalExpr>>,
     tx: tokio::sync::mpsc::Sender<ArrowResult<RecordBatch>>,
 ) -> Result<(), ReadError> {
     trace!(?path, "Start parquet download & scan");
 
-    let read_stream = object_store.get(&path).await?;
+    let data = read_retries.get(object_store.as_ref(), &path).await?;
 
-    let data = match read_stream {
-        GetResult::File(f, _) => {
-            trace!(?path, "Using file directly");
-            let mut f = tokio::fs::File::from_std(f);
-            let l = f.metadata().await?.len();
-            let mut buf = Vec::with_capacity(l as usize);
-            f.read_to_end(&mut buf).await?;
-            buf
-        }
-        GetResult::Stream(read_stream) => {
-            let chunks: Vec<_> = read_stream.try_collect().await?;
+    let builder = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(data))?;
 
-            let mut buf = Vec::with_capacity(chunks.iter().map(|c| c.len()).sum::<usize>());
-            for c in chunks {
-                buf.extend(c);
-            }
+    scan_parquet(builder, expected_schema, path, delete_filter, tx).await
+}
+
+/// Fetches the footer of the specified parquet file, determines the row groups
+/// that may contain data within `range` and then only fetches the byte ranges
+/// of the columns in `expected_schema` within these row groups. The resulting
+/// [`RecordBatch`] contents are pushed over `tx`.
+///
+/// All data is kept in memory, nothing is spilled to disk.
+async fn fetch_ranges_and_scan_parquet(
+    expected_schema: SchemaRef,
+    path: object_store::path::Path,
+    object_store: Arc<DynObjectStore>,
+    read_retries: ReadRetries,
+    range: Option<TimestampRange>,
+    delete_filter: Option<Arc<dyn PhysicalExpr>>,
+    tx: tokio::sync::mpsc::Sender<ArrowResult<RecordBatch>>,
+) -> Result<(), ReadError> {
+    trace!(?path, "Start parquet partial fetch & scan");
+
+    let object_store = object_store.as_ref();
+    let (metadata, mut reader, footer_start) =
+        fetch_metadata(object_store, &read_retries, &path).await?;
 
-            buf
+    // Check schema and calculate `file->expected` projections
+    let file_metadata = metadata.file_metadata();
+    let file_schema = parquet_to_arrow_schema(
+        file_metadata.schema_descr(),
+        file_metadata.key_value_metadata(),
+    )?;
+    let mask = match project_for_parquet_reader(&file_schema, &expected_schema) {
+        Ok((mask, _reorder_projection)) => mask,
+        Err(e) => {
+            return Err(ReadError::SchemaMismatch { path, source: e });
         }
     };
 
-    let builder = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(data))?;
+    let row_groups = prune_row_groups(&metadata, range);
+    let ranges = column_chunk_ranges(&metadata, &row_groups, &mask);
+    let ranges: Vec<_> = coalesce_ranges(ranges, RANGE_COALESCE_GAP)
+        .into_iter()
+        // no need to re-fetch what we already got
+        .filter(|r| r.start < footer_start)
+        .collect();
+    debug!(
+        ?path,
+        row_groups_total = metadata.num_row_groups(),
+        row_groups_selected = row_groups.len(),
+        bytes_fetched = ranges.iter().map(|r| r.len()).sum::<usize>(),
+        size,
+        "Parquet partial fetch",
+    );
+
+    let chunks = futures::future::try_join_all(
+        ranges
+            .iter()
+            .map(|r| read_retries.get_range(object_store, &path, r.clone())),
+    )
+    .await?;
+    for (r, chunk) in ranges.into_iter().zip(chunks) {
+        reader.insert(r.start as u64, chunk);
+    }
 
+    let builder = ParquetRecordBatchReaderBuilder::try_new(reader)?.with_row_groups(row_groups);
+
+    scan_parquet(builder, expected_schema, path, delete_filter, tx).await
+}
+
+/// Fetches the footer of the specified parquet file using ranged requests and parses the file
+/// metadata.
+///
+/// Returns the metadata, a reader that is backed by the fetched bytes and the offset at which
+/// the fetched bytes start.
+async fn fetch_metadata(
+    object_store: &DynObjectStore,
+    read_retries: &ReadRetries,
+    path: &object_store::path::Path,
+) -> Result<(ParquetMetaData, SparseChunkReader, usize), ReadError> {
+    let size = read_retries.head(object_store, path).await?.size;
+    // footer length (4 bytes) + magic (4 bytes)
+    if size < 8 {
+        return Err(ReadError::TooSmall {
+            path: path.clone(),
+            size,
+        });
+    }
+
+    // Fetch the footer. Usually the fixed-size read already contains the entire metadata, otherwise issue a second
+    // request for the missing bytes.
+    let mut footer_start = size.saturating_sub(FOOTER_READ_SIZE);
+    let mut footer = read_retries
+        .get_range(object_store, path, footer_start..size)
+        .await?;
+    let metadata_len = footer_metadata_len(&footer);
+    let metadata_start = size.saturating_sub(8 + metadata_len);
+    if metadata_start < footer_start {
+        let head = read_retries
+            .get_range(object_store, path, metadata_start..footer_start)
+            .await?;
+        let mut buf = Vec::with_capacity(head.len() + footer.len());
+        buf.extend_from_slice(&head);
+        buf.extend_from_slice(&footer);
+        footer = Bytes::from(buf);
+        footer_start = metadata_start;
+    }
+
+    let mut reader = SparseChunkReader::new(size as u64);
+    reader.insert(footer_start as u64, footer);
+    let metadata = parse_metadata(&reader)?;
+
+    Ok((metadata, reader, footer_start))
+}
+
+/// Scans the parquet file provided by `builder` and pushes the [`RecordBatch`]
+/// contents over `tx`, projecting to `expected_schema` and only keeping the
+/// rows that match `delete_filter`, if any.
+async fn scan_parquet<T>(
+    builder: ParquetRecordBatchReaderBuilder<T>,
+    expected_schema: SchemaRef,
+    path: object_store::path::Path,
+    delete_filter: Option<Arc<dyn PhysicalExpr>>,
+    tx: tokio::sync::mpsc::Sender<ArrowResult<RecordBatch>>,
+) -> Result<(), ReadError>
+where
+    T: ChunkReader + 'static,
+{
     // Check schema and calculate `file->expected` projections
     let file_schema = builder.schema();
     let (mask, reorder_projection) = match project_for_parquet_reader(file_schema, &expected_schema)
@@ -290,15 +911,35 @@ async fn download_and_scan_parquet(
         .build()?;
 
     for batch in record_batch_reader {
-        let batch = batch.map(|batch| {
+        let batch = batch.and_then(|batch| {
             // project to fix column order
             let batch = batch
                 .project(&reorder_projection)
                 .expect("bug in projection calculation");
 
+            // cast columns of files written before a field type migration
+            let columns = batch
+                .columns()
+                .iter()
+                .zip(expected_schema.fields())
+                .map(|(array, field)| {
+                    if array.data_type() == field.data_type() {
+                        Ok(Arc::clone(array))
+                    } else {
+                        cast(array, field.data_type())
+                    }
+                })
+                .collect::<ArrowResult<Vec<_>>>()?;
+
             // attach potential metadata
-            RecordBatch::try_new(Arc::clone(&expected_schema), batch.columns().to_vec())
-                .expect("bug in schema handling")
+            let batch = RecordBatch::try_new(Arc::clone(&expected_schema), columns)
+                .expect("bug in schema handling");
+
+            // remove deleted rows
+            match &delete_filter {
+                Some(delete_filter) => batch_filter(&batch, delete_filter),
+                None => Ok(batch),
+            }
         });
         if tx.send(batch).await.is_err() {
             debug!("Receiver hung up - exiting");
@@ -311,6 +952,150 @@ async fn download_and_scan_parquet(
     Ok(())
 }
 
+/// Build a physical expression of `expr` that can be evaluated against record batches of `schema`.
+fn physical_filter(
+    expr: Expr,
+    schema: &SchemaRef,
+) -> Result<Arc<dyn PhysicalExpr>, DataFusionError> {
+    let df_schema: DFSchema = schema.as_ref().clone().try_into()?;
+    create_physical_expr(&expr, &df_schema, schema, &ExecutionProps::new())
+}
+
+/// Extract the length of the thrift-encoded file metadata from the last 8 bytes of a parquet file.
+///
+/// The caller must ensure that `footer` contains at least 8 bytes.
+fn footer_metadata_len(footer: &[u8]) -> usize {
+    let len_bytes = &footer[footer.len() - 8..footer.len() - 4];
+    u32::from_le_bytes(len_bytes.try_into().expect("4 bytes")) as usize
+}
+
+/// Determine the row groups that may contain rows within the given time `range`.
+///
+/// Row groups without usable statistics for the time column are always selected.
+fn prune_row_groups(metadata: &ParquetMetaData, range: Option<TimestampRange>) -> Vec<usize> {
+    let all = (0..metadata.num_row_groups()).collect();
+    let range = match range {
+        Some(range) => range,
+        None => return all,
+    };
+    let time_idx = match metadata
+        .file_metadata()
+        .schema_descr()
+        .columns()
+        .iter()
+        .position(|c| c.name() == TIME_COLUMN_NAME)
+    {
+        Some(idx) => idx,
+        None => return all,
+    };
+
+    metadata
+        .row_groups()
+        .iter()
+        .enumerate()
+        .filter(|(_idx, rg)| match rg.column(time_idx).statistics() {
+            Some(Statistics::Int64(stats)) if stats.has_min_max_set() => {
+                // `range` has an exclusive end
+                *stats.min() < range.end() && *stats.max() >= range.start()
+            }
+            _ => true,
+        })
+        .map(|(idx, _rg)| idx)
+        .collect()
+}
+
+/// Byte ranges of all column chunks of the given `row_groups` that belong to the selected `root_columns`.
+fn column_chunk_ranges(
+    metadata: &ParquetMetaData,
+    row_groups: &[usize],
+    root_columns: &[usize],
+) -> Vec<Range<usize>> {
+    let schema_descr = metadata.file_metadata().schema_descr();
+    let leaves: Vec<_> = (0..schema_descr.num_columns())
+        .filter(|leaf| root_columns.contains(&schema_descr.get_column_root_idx(*leaf)))
+        .collect();
+
+    row_groups
+        .iter()
+        .flat_map(|rg| {
+            let rg = metadata.row_group(*rg);
+            leaves.iter().map(move |leaf| {
+                let (start, len) = rg.column(*leaf).byte_range();
+                start as usize..(start + len) as usize
+            })
+        })
+        .collect()
+}
+
+/// Sort ranges and merge those that overlap or are separated by at most `max_gap` bytes.
+fn coalesce_ranges(mut ranges: Vec<Range<usize>>, max_gap: usize) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|r| r.start);
+
+    let mut out: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for r in ranges {
+        match out.last_mut() {
+            Some(last) if r.start <= last.end + max_gap => {
+                last.end = last.end.max(r.end);
+            }
+            _ => out.push(r),
+        }
+    }
+    out
+}
+
+/// A [`ChunkReader`] that is only backed by the fetched parts of a parquet file.
+///
+/// Reads of byte ranges that were not fetched fail.
+#[derive(Debug)]
+struct SparseChunkReader {
+    /// Total size of the file.
+    len: u64,
+
+    /// Fetched chunks as `(start offset, data)`, may overlap.
+    chunks: Vec<(u64, Bytes)>,
+}
+
+impl SparseChunkReader {
+    fn new(len: u64) -> Self {
+        Self {
+            len,
+            chunks: vec![],
+        }
+    }
+
+    fn insert(&mut self, start: u64, data: Bytes) {
+        self.chunks.push((start, data));
+    }
+}
+
+impl Length for SparseChunkReader {
+    fn len(&self) -> u64 {
+        self.len
+    }
+}
+
+impl ChunkReader for SparseChunkReader {
+    type T = bytes::buf::Reader<Bytes>;
+
+    fn get_read(&self, start: u64, length: usize) -> Result<Self::T, ParquetError> {
+        let end = start + length as u64;
+        self.chunks
+            .iter()
+            .find(|(chunk_start, data)| {
+                *chunk_start <= start && end <= *chunk_start + data.len() as u64
+            })
+            .map(|(chunk_start, data)| {
+                let offset = (start - chunk_start) as usize;
+                data.slice(offset..offset + length).reader()
+            })
+            .ok_or_else(|| {
+                ParquetError::General(format!(
+                    "byte range {start}..{end} was not fetched from object store"
+                ))
+            })
+    }
+}
+
 /// Error during projecting parquet file data to an expected schema.
 #[derive(Debug, Error)]
 #[allow(clippy::large_enum_variant)]
@@ -330,6 +1115,14 @@ pub enum ProjectionError {
     },
 }
 
+/// Returns true if `file_field` was written before a type migration of `expected_field`, see
+/// [`InfluxFieldType::can_migrate_to`].
+fn is_field_type_migration(file_field: &Field, expected_field: &Field) -> bool {
+    file_field.name() == expected_field.name()
+        && file_field.is_nullable() == expected_field.is_nullable()
+        && InfluxFieldType::is_migration(file_field.data_type(), expected_field.data_type())
+}
+
 /// Calculate project for the parquet-rs reader.
 ///
 /// Expects the schema that was extracted from the actual parquet file and the desired output schema.
@@ -341,6 +1134,8 @@ pub enum ProjectionError {
 /// 2. A re-order mask that can be used to reorder the output batches to actually match the desired schema.
 ///
 /// Will fail the desired schema contains a column that is unknown or the field types in the two schemas do not match.
+/// Fields whose type was migrated after the file was written (e.g. from integer to float) match and must be cast
+/// after reading.
 fn project_for_parquet_reader(
     file_schema: &Schema,
     expected_schema: &Schema,
@@ -360,7 +1155,7 @@ fn project_for_parquet_reader(
             return Err(ProjectionError::UnknownField(field.name().clone()));
         };
         let file_field = file_schema.field(file_idx);
-        if field != file_field {
+        if field != file_field && !is_field_type_migration(file_field, field) {
             return Err(ProjectionError::FieldTypeMismatch {
                 expected: field.clone(),
                 actual: file_field.clone(),
@@ -393,10 +1188,14 @@ fn project_for_parquet_reader(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use arrow::array::{ArrayRef, Int64Array, StringArray};
+    use arrow::{
+        array::{ArrayRef, Float64Array, Int64Array, StringArray, TimestampNanosecondArray},
+        datatypes::DataType,
+    };
     use data_types::{CompactionLevel, NamespaceId, PartitionId, SequenceNumber, ShardId, TableId};
     use datafusion::common::DataFusionError;
     use iox_time::Time;
+    use predicate::delete_predicate::parse_delete_predicate;
     use std::collections::HashMap;
 
     #[tokio::test]
@@ -629,6 +1428,400 @@ mod tests {
         assert_roundtrip(file_batch, Selection::Some(&["a"]), schema, expected_batch).await;
     }
 
+    #[tokio::test]
+    async fn test_field_type_migration() {
+        let file_batch = RecordBatch::try_from_iter([
+            ("a", to_string_array(&["value"])),
+            ("b", to_int_array(&[1])),
+        ])
+        .unwrap();
+        let expected_batch = RecordBatch::try_from_iter([
+            ("a", to_string_array(&["value"])),
+            ("b", Arc::new(Float64Array::from(vec![1.0])) as ArrayRef),
+        ])
+        .unwrap();
+        let schema = expected_batch.schema();
+        assert_roundtrip(file_batch, Selection::All, schema, expected_batch).await;
+    }
+
+    #[tokio::test]
+    async fn test_check_schema() {
+        let batch = RecordBatch::try_from_iter([
+            ("a", to_string_array(&["value"])),
+            ("b", to_int_array(&[1])),
+        ])
+        .unwrap();
+
+        let object_store: Arc<DynObjectStore> = Arc::new(object_store::memory::InMemory::default());
+        let store = ParquetStorage::new(object_store);
+        let meta = meta();
+        upload(&store, &meta, batch.clone()).await;
+        let path: ParquetFilePath = (&meta).into();
+
+        // subsets and migrated types are fine
+        store.check_schema(&batch.schema(), &path).await.unwrap();
+        let schema = Schema::new(vec![Field::new("b", DataType::Float64, true)]);
+        store.check_schema(&schema, &path).await.unwrap();
+
+        let schema = Schema::new(vec![Field::new("b", DataType::Utf8, true)]);
+        let err = store.check_schema(&schema, &path).await.unwrap_err();
+        assert!(matches!(
+            err,
+            ReadError::SchemaMismatch {
+                source: ProjectionError::FieldTypeMismatch { .. },
+                ..
+            }
+        ));
+
+        let schema = Schema::new(vec![Field::new("c", DataType::Int64, true)]);
+        let err = store.check_schema(&schema, &path).await.unwrap_err();
+        assert!(matches!(
+            err,
+            ReadError::SchemaMismatch {
+                source: ProjectionError::UnknownField(_),
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_partial_reads_roundtrip() {
+        let batch = RecordBatch::try_from_iter([
+            ("a", to_string_array(&["value"])),
+            ("b", to_int_array(&[1])),
+            ("c", to_string_array(&["foo"])),
+            ("d", to_int_array(&[2])),
+        ])
+        .unwrap();
+        let schema = batch.schema();
+
+        let object_store: Arc<DynObjectStore> = Arc::new(object_store::memory::InMemory::default());
+        let store = ParquetStorage::new(object_store).with_partial_reads(true);
+        let meta = meta();
+        upload(&store, &meta, batch.clone()).await;
+
+        let actual_batch = download(&store, &meta, Selection::All, Arc::clone(&schema))
+            .await
+            .unwrap();
+        assert_eq!(actual_batch, batch);
+
+        let expected_batch = RecordBatch::try_from_iter([
+            ("d", to_int_array(&[2])),
+            ("c", to_string_array(&["foo"])),
+        ])
+        .unwrap();
+        let actual_batch = download(&store, &meta, Selection::Some(&["d", "c"]), schema)
+            .await
+            .unwrap();
+        assert_eq!(actual_batch, expected_batch);
+    }
+
+    #[tokio::test]
+    async fn test_path_layouts() {
+        let batch = RecordBatch::try_from_iter([("a", to_string_array(&["value"]))]).unwrap();
+        let schema = batch.schema();
+        let meta = meta();
+        let path = ParquetFilePath::from(&meta);
+
+        let object_store: Arc<DynObjectStore> = Arc::new(object_store::memory::InMemory::default());
+        let store_v1 = ParquetStorage::new(Arc::clone(&object_store));
+        let store_v2 = ParquetStorage::new(Arc::clone(&object_store))
+            .with_path_layout(ParquetPathLayout::V2)
+            .with_partial_reads(true);
+
+        // files are written with the configured layout
+        upload(&store_v2, &meta, batch.clone()).await;
+        object_store
+            .head(&path.object_store_path_with_layout(ParquetPathLayout::V2))
+            .await
+            .unwrap();
+        object_store
+            .head(&path.object_store_path_with_layout(ParquetPathLayout::V1))
+            .await
+            .unwrap_err();
+
+        // ...but found with either layout
+        for store in [&store_v1, &store_v2] {
+            let actual_batch = download(store, &meta, Selection::All, Arc::clone(&schema))
+                .await
+                .unwrap();
+            assert_eq!(actual_batch, batch);
+        }
+        let (new_meta, _, _) = store_v1
+            .rewrite_sorted(&path, &SortKey::from_columns(["a"]))
+            .await
+            .unwrap();
+        object_store
+            .head(
+                &ParquetFilePath::from(&new_meta)
+                    .object_store_path_with_layout(ParquetPathLayout::V1),
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_partial_reads_prune_row_groups() {
+        let batch = RecordBatch::try_from_iter([
+            ("a", to_string_array(&["value"])),
+            (TIME_COLUMN_NAME, to_int_array(&[10])),
+        ])
+        .unwrap();
+        let schema = batch.schema();
+
+        let object_store: Arc<DynObjectStore> = Arc::new(object_store::memory::InMemory::default());
+        let store = ParquetStorage::new(object_store).with_partial_reads(true);
+        let meta = meta();
+        upload(&store, &meta, batch.clone()).await;
+        let path: ParquetFilePath = (&meta).into();
+
+        // range covers the data
+        let predicate = Predicate::new().with_range(0, 11);
+        let rx = store
+            .read_filter(&predicate, &[], Selection::All, Arc::clone(&schema), &path)
+            .unwrap();
+        let batches = datafusion::physical_plan::common::collect(rx)
+            .await
+            .unwrap();
+        assert_eq!(batches, vec![batch]);
+
+        // range excludes the data (end is exclusive)
+        let predicate = Predicate::new().with_range(0, 10);
+        let rx = store
+            .read_filter(&predicate, &[], Selection::All, schema, &path)
+            .unwrap();
+        let batches = datafusion::physical_plan::common::collect(rx)
+            .await
+            .unwrap();
+        assert!(batches.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_filter_delete_predicates() {
+        let time_array =
+            |vals: &[i64]| Arc::new(TimestampNanosecondArray::from(vals.to_vec())) as ArrayRef;
+        let batch = RecordBatch::try_from_iter([
+            ("a", to_string_array(&["x", "y", "z"])),
+            ("b", to_int_array(&[1, 2, 3])),
+            (TIME_COLUMN_NAME, time_array(&[10, 20, 30])),
+        ])
+        .unwrap();
+        let schema = batch.schema();
+
+        for partial_reads in [false, true] {
+            let object_store: Arc<DynObjectStore> =
+                Arc::new(object_store::memory::InMemory::default());
+            let store = ParquetStorage::new(object_store).with_partial_reads(partial_reads);
+            let meta = meta();
+            upload(&store, &meta, batch.clone()).await;
+            let path: ParquetFilePath = (&meta).into();
+
+            // only the second row matches both the time range and the expression
+            let delete = Arc::new(parse_delete_predicate("0", "25", "b >= 2").unwrap());
+            let rx = store
+                .read_filter(
+                    &Predicate::default(),
+                    &[Arc::clone(&delete)],
+                    Selection::All,
+                    Arc::clone(&schema),
+                    &path,
+                )
+                .unwrap();
+            let batches = datafusion::physical_plan::common::collect(rx)
+                .await
+                .unwrap();
+            let expected_batch = RecordBatch::try_from_iter([
+                ("a", to_string_array(&["x", "z"])),
+                ("b", to_int_array(&[1, 3])),
+                (TIME_COLUMN_NAME, time_array(&[10, 30])),
+            ])
+            .unwrap();
+            assert_eq!(batches, vec![expected_batch]);
+
+            // the columns of the delete predicates must be selected
+            let res = store.read_filter(
+                &Predicate::default(),
+                &[delete],
+                Selection::Some(&["a", TIME_COLUMN_NAME]),
+                Arc::clone(&schema),
+                &path,
+            );
+            assert!(matches!(res, Err(ReadError::DeletePredicate { .. })));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_row_group_statistics() {
+        let schema = schema::builder::SchemaBuilder::new()
+            .influx_field("b", InfluxFieldType::Integer)
+            .timestamp()
+            .build()
+            .unwrap()
+            .as_arrow();
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                to_int_array(&[3, 1, 2]),
+                Arc::new(TimestampNanosecondArray::from(vec![10, 20, 30])),
+            ],
+        )
+        .unwrap();
+
+        let object_store: Arc<DynObjectStore> = Arc::new(object_store::memory::InMemory::default());
+        let store = ParquetStorage::new(object_store);
+        let meta = meta();
+        upload(&store, &meta, batch).await;
+        let path: ParquetFilePath = (&meta).into();
+
+        let summaries = store.read_row_group_statistics(&path).await.unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].total_count(), 3);
+        assert_eq!(
+            summaries[0].column("b").unwrap().stats,
+            data_types::Statistics::I64(data_types::StatValues {
+                min: Some(1),
+                max: Some(3),
+                total_count: 3,
+                null_count: Some(0),
+                distinct_count: None,
+            })
+        );
+        assert_eq!(
+            summaries[0].column(TIME_COLUMN_NAME).unwrap().stats,
+            data_types::Statistics::I64(data_types::StatValues {
+                min: Some(10),
+                max: Some(30),
+                total_count: 3,
+                null_count: Some(0),
+                distinct_count: None,
+            })
+        );
+
+        // missing files are reported as such
+        let other_meta = IoxMetadata {
+            object_store_id: Uuid::new_v4(),
+            ..meta
+        };
+        let err = store
+            .read_row_group_statistics(&(&other_meta).into())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            ReadError::ObjectStore(object_store::Error::NotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rewrite_sorted() {
+        let batch = RecordBatch::try_from_iter([
+            ("a", to_string_array(&["x", "x", "y", "y"])),
+            ("b", to_string_array(&["2", "1", "2", "1"])),
+            (TIME_COLUMN_NAME, to_int_array(&[1, 2, 3, 4])),
+        ])
+        .unwrap();
+        let schema = batch.schema();
+
+        let object_store: Arc<DynObjectStore> = Arc::new(object_store::memory::InMemory::default());
+        let store = ParquetStorage::new(object_store);
+        let meta = IoxMetadata {
+            sort_key: Some(SortKey::from_columns(["a", TIME_COLUMN_NAME])),
+            ..meta()
+        };
+        upload(&store, &meta, batch).await;
+
+        // "c" does not exist in the file
+        let new_sort_key = SortKey::from_columns(["b", "c", "a", TIME_COLUMN_NAME]);
+        let (new_meta, file_meta, file_size) = store
+            .rewrite_sorted(&(&meta).into(), &new_sort_key)
+            .await
+            .unwrap();
+
+        assert_ne!(new_meta.object_store_id, meta.object_store_id);
+        assert_eq!(
+            new_meta.sort_key,
+            Some(SortKey::from_columns(["b", "a", TIME_COLUMN_NAME]))
+        );
+        assert_eq!(
+            new_meta,
+            IoxMetadata {
+                object_store_id: new_meta.object_store_id,
+                sort_key: new_meta.sort_key.clone(),
+                ..meta.clone()
+            }
+        );
+        assert!(file_size > 0);
+        assert_eq!(
+            file_meta.decode().unwrap().read_iox_metadata_new().unwrap(),
+            new_meta
+        );
+
+        let expected = RecordBatch::try_from_iter([
+            ("a", to_string_array(&["x", "y", "x", "y"])),
+            ("b", to_string_array(&["1", "1", "2", "2"])),
+            (TIME_COLUMN_NAME, to_int_array(&[2, 4, 1, 3])),
+        ])
+        .unwrap();
+        let actual = download(&store, &new_meta, Selection::All, schema)
+            .await
+            .unwrap();
+        assert_eq!(actual, expected);
+
+        // the old file is left untouched
+        let old = download(&store, &meta, Selection::All, expected.schema())
+            .await
+            .unwrap();
+        assert_eq!(old.num_rows(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_rewrite_sorted_missing_file() {
+        let object_store: Arc<DynObjectStore> = Arc::new(object_store::memory::InMemory::default());
+        let store = ParquetStorage::new(object_store);
+
+        let err = store
+            .rewrite_sorted(&(&meta()).into(), &SortKey::from_columns(["a"]))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, RewriteError::Read(ReadError::ObjectStore(_))),
+            "{err:?}"
+        );
+    }
+
+    #[test]
+    fn test_coalesce_ranges() {
+        assert_eq!(coalesce_ranges(vec![], 0), vec![]);
+        assert_eq!(
+            coalesce_ranges(vec![10..20, 0..5, 5..8, 30..40, 15..25], 0),
+            vec![0..8, 10..25, 30..40],
+        );
+        assert_eq!(
+            coalesce_ranges(vec![10..20, 0..5, 30..40], 5),
+            vec![0..20, 30..40],
+        );
+    }
+
+    #[test]
+    fn test_sparse_chunk_reader() {
+        let mut reader = SparseChunkReader::new(100);
+        reader.insert(10, Bytes::from_static(b"0123456789"));
+        reader.insert(90, Bytes::from_static(b"abcdefghij"));
+
+        let mut buf = String::new();
+        std::io::Read::read_to_string(&mut reader.get_read(12, 3).unwrap(), &mut buf).unwrap();
+        assert_eq!(buf, "234");
+
+        let mut buf = String::new();
+        std::io::Read::read_to_string(&mut reader.get_read(90, 10).unwrap(), &mut buf).unwrap();
+        assert_eq!(buf, "abcdefghij");
+
+        // partially fetched
+        reader.get_read(15, 10).unwrap_err();
+        // not fetched at all
+        reader.get_read(50, 1).unwrap_err();
+    }
+
     #[test]
     fn test_project_for_parquet_reader() {
         assert_eq!(
@@ -778,7 +1971,13 @@ mod tests {
     ) -> Result<RecordBatch, DataFusionError> {
         let path: ParquetFilePath = meta.into();
         let rx = store
-            .read_filter(&Predicate::default(), selection, expected_schema, &path)
+            .read_filter(
+                &Predicate::default(),
+                &[],
+                selection,
+                expected_schema,
+                &path,
+            )
             .expect("should read record batches from object store");
         let schema = rx.schema();
         datafusion::physical_plan::common::collect(rx)
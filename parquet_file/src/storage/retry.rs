@@ -0,0 +1,424 @@
+//! Retries of transient object store errors during parquet reads.
+//!
+//! Object store requests occasionally fail with transient errors (e.g. connection resets or
+//! throttling). Instead of failing the entire query, such requests are retried a few times.
+//! Downloads that fail mid-stream are resumed from the last received byte using a ranged request,
+//! so data that was already transferred is not fetched again.
+
+use bytes::Bytes;
+use futures::TryStreamExt;
+use metric::U64Counter;
+use object_store::{path::Path, DynObjectStore, GetResult, ObjectMeta};
+use observability_deps::tracing::warn;
+use std::{future::Future, ops::Range, time::Duration};
+use tokio::io::AsyncReadExt;
+
+use super::ReadError;
+
+/// Configuration of read retries, see
+/// [`ParquetStorage::with_read_retries`](super::ParquetStorage::with_read_retries).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadRetryConfig {
+    /// Maximum number of retries of a single object store request.
+    pub max_retries: usize,
+
+    /// Backoff before the first retry. It is doubled for every further retry.
+    pub init_backoff: Duration,
+
+    /// Maximum backoff between two retries.
+    pub max_backoff: Duration,
+}
+
+impl Default for ReadRetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            init_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+/// Retry layer for the object store requests issued by parquet reads.
+#[derive(Debug, Clone)]
+pub(super) struct ReadRetries {
+    config: ReadRetryConfig,
+    retried: U64Counter,
+    gave_up: U64Counter,
+}
+
+impl ReadRetries {
+    /// Never retry.
+    pub(super) fn disabled() -> Self {
+        Self {
+            config: ReadRetryConfig {
+                max_retries: 0,
+                ..Default::default()
+            },
+            retried: Default::default(),
+            gave_up: Default::default(),
+        }
+    }
+
+    pub(super) fn new(config: ReadRetryConfig, metric_registry: &metric::Registry) -> Self {
+        let metric = metric_registry.register_metric::<U64Counter>(
+            "parquet_read_retry",
+            "Number of object store requests of parquet reads that failed with a transient error",
+        );
+
+        Self {
+            config,
+            retried: metric.recorder(&[("outcome", "retried")]),
+            gave_up: metric.recorder(&[("outcome", "gave_up")]),
+        }
+    }
+
+    /// Get object metadata.
+    pub(super) async fn head(
+        &self,
+        object_store: &DynObjectStore,
+        path: &Path,
+    ) -> Result<ObjectMeta, object_store::Error> {
+        self.retry(path, "head", || object_store.head(path)).await
+    }
+
+    /// Get byte range of an object.
+    pub(super) async fn get_range(
+        &self,
+        object_store: &DynObjectStore,
+        path: &Path,
+        range: Range<usize>,
+    ) -> Result<Bytes, object_store::Error> {
+        self.retry(path, "get_range", || {
+            object_store.get_range(path, range.clone())
+        })
+        .await
+    }
+
+    /// Get entire object.
+    ///
+    /// If the download fails mid-stream, the rest of the object is fetched with a ranged request
+    /// starting at the last received byte.
+    pub(super) async fn get(
+        &self,
+        object_store: &DynObjectStore,
+        path: &Path,
+    ) -> Result<Vec<u8>, ReadError> {
+        let mut retries = 0;
+        let read_stream = loop {
+            match object_store.get(path).await {
+                Ok(read_stream) => break read_stream,
+                Err(e) => self.backoff(e, path, "get", &mut retries).await?,
+            }
+        };
+
+        match read_stream {
+            GetResult::File(f, _) => {
+                let mut f = tokio::fs::File::from_std(f);
+                let l = f.metadata().await?.len();
+                let mut buf = Vec::with_capacity(l as usize);
+                f.read_to_end(&mut buf).await?;
+                Ok(buf)
+            }
+            GetResult::Stream(mut read_stream) => {
+                let mut buf = vec![];
+                loop {
+                    match read_stream.try_next().await {
+                        Ok(Some(chunk)) => buf.extend_from_slice(&chunk),
+                        Ok(None) => return Ok(buf),
+                        Err(e) => {
+                            self.backoff(e, path, "get", &mut retries).await?;
+
+                            let size = self.head(object_store, path).await?.size;
+                            if buf.len() < size {
+                                let rest =
+                                    self.get_range(object_store, path, buf.len()..size).await?;
+                                buf.extend_from_slice(&rest);
+                            }
+                            return Ok(buf);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Run `f` and retry transient errors.
+    async fn retry<F, Fut, T>(
+        &self,
+        path: &Path,
+        op: &'static str,
+        mut f: F,
+    ) -> Result<T, object_store::Error>
+    where
+        F: FnMut() -> Fut + Send,
+        Fut: Future<Output = Result<T, object_store::Error>> + Send,
+        T: Send,
+    {
+        let mut retries = 0;
+        loop {
+            match f().await {
+                Ok(v) => return Ok(v),
+                Err(e) => self.backoff(e, path, op, &mut retries).await?,
+            }
+        }
+    }
+
+    /// Check if the failed request shall be retried and wait before doing so.
+    ///
+    /// Returns the error if the request shall NOT be retried.
+    async fn backoff(
+        &self,
+        e: object_store::Error,
+        path: &Path,
+        op: &'static str,
+        retries: &mut usize,
+    ) -> Result<(), object_store::Error> {
+        if !is_transient(&e) {
+            return Err(e);
+        }
+        if *retries >= self.config.max_retries {
+            if self.config.max_retries > 0 {
+                self.gave_up.inc(1);
+            }
+            return Err(e);
+        }
+
+        let backoff = self
+            .config
+            .init_backoff
+            .saturating_mul(2u32.saturating_pow(*retries as u32))
+            .min(self.config.max_backoff);
+        *retries += 1;
+        self.retried.inc(1);
+        warn!(
+            %e,
+            %path,
+            op,
+            retry=*retries,
+            ?backoff,
+            "transient object store error during parquet read, retrying",
+        );
+        tokio::time::sleep(backoff).await;
+
+        Ok(())
+    }
+}
+
+/// Errors that are likely to go away when the request is retried.
+///
+/// All network and service errors of the object store implementations are reported as
+/// [`Generic`](object_store::Error::Generic), the other variants describe permanent conditions
+/// (e.g. a missing object).
+fn is_transient(e: &object_store::Error) -> bool {
+    matches!(e, object_store::Error::Generic { .. })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use futures::stream::BoxStream;
+    use metric::{Attributes, Metric};
+    use object_store::{memory::InMemory, ListResult, MultipartId, ObjectStore};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::AsyncWrite;
+
+    #[tokio::test]
+    async fn test_resume_stream() {
+        let store = FlakyStore::new().await;
+        store.stream_failures.store(1, Ordering::SeqCst);
+        let metric_registry = metric::Registry::new();
+        let retries = ReadRetries::new(test_config(), &metric_registry);
+
+        let data = retries.get(&store, &path()).await.unwrap();
+        assert_eq!(data, DATA);
+        assert_eq!(get_metric(&metric_registry, "retried"), 1);
+        assert_eq!(get_metric(&metric_registry, "gave_up"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_retry_range() {
+        let store = FlakyStore::new().await;
+        let metric_registry = metric::Registry::new();
+        let retries = ReadRetries::new(test_config(), &metric_registry);
+
+        store.range_failures.store(2, Ordering::SeqCst);
+        let data = retries.get_range(&store, &path(), 2..5).await.unwrap();
+        assert_eq!(data.as_ref(), &DATA[2..5]);
+        assert_eq!(get_metric(&metric_registry, "retried"), 2);
+        assert_eq!(get_metric(&metric_registry, "gave_up"), 0);
+
+        store.range_failures.store(3, Ordering::SeqCst);
+        retries.get_range(&store, &path(), 2..5).await.unwrap_err();
+        assert_eq!(get_metric(&metric_registry, "retried"), 4);
+        assert_eq!(get_metric(&metric_registry, "gave_up"), 1);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_error_not_retried() {
+        let store = FlakyStore::new().await;
+        let metric_registry = metric::Registry::new();
+        let retries = ReadRetries::new(test_config(), &metric_registry);
+
+        let err = retries
+            .get_range(&store, &Path::from("missing"), 0..1)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, object_store::Error::NotFound { .. }));
+        assert_eq!(get_metric(&metric_registry, "retried"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_disabled() {
+        let store = FlakyStore::new().await;
+        store.stream_failures.store(1, Ordering::SeqCst);
+
+        let err = ReadRetries::disabled()
+            .get(&store, &path())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ReadError::ObjectStore(_)));
+    }
+
+    const DATA: &[u8] = b"0123456789";
+
+    fn path() -> Path {
+        Path::from("foo")
+    }
+
+    fn test_config() -> ReadRetryConfig {
+        ReadRetryConfig {
+            max_retries: 2,
+            init_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+        }
+    }
+
+    fn get_metric(metric_registry: &metric::Registry, outcome: &'static str) -> u64 {
+        metric_registry
+            .get_instrument::<Metric<U64Counter>>("parquet_read_retry")
+            .unwrap()
+            .get_observer(&Attributes::from(&[("outcome", outcome)]))
+            .unwrap()
+            .fetch()
+    }
+
+    fn injected_error() -> object_store::Error {
+        object_store::Error::Generic {
+            store: "flaky",
+            source: "injected error".into(),
+        }
+    }
+
+    /// In-memory store that fails a configurable number of requests.
+    #[derive(Debug)]
+    struct FlakyStore {
+        inner: InMemory,
+
+        /// Number of `get` streams that fail after delivering half of the data.
+        stream_failures: AtomicUsize,
+
+        /// Number of `get_range` requests that fail.
+        range_failures: AtomicUsize,
+    }
+
+    impl FlakyStore {
+        async fn new() -> Self {
+            let inner = InMemory::new();
+            inner.put(&path(), Bytes::from(DATA)).await.unwrap();
+
+            Self {
+                inner,
+                stream_failures: AtomicUsize::new(0),
+                range_failures: AtomicUsize::new(0),
+            }
+        }
+
+        fn should_fail(counter: &AtomicUsize) -> bool {
+            counter
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+        }
+    }
+
+    impl std::fmt::Display for FlakyStore {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Flaky({})", self.inner)
+        }
+    }
+
+    #[async_trait]
+    impl ObjectStore for FlakyStore {
+        async fn put(&self, location: &Path, bytes: Bytes) -> object_store::Result<()> {
+            self.inner.put(location, bytes).await
+        }
+
+        async fn put_multipart(
+            &self,
+            location: &Path,
+        ) -> object_store::Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+            self.inner.put_multipart(location).await
+        }
+
+        async fn abort_multipart(
+            &self,
+            location: &Path,
+            multipart_id: &MultipartId,
+        ) -> object_store::Result<()> {
+            self.inner.abort_multipart(location, multipart_id).await
+        }
+
+        async fn get(&self, location: &Path) -> object_store::Result<GetResult> {
+            if !Self::should_fail(&self.stream_failures) {
+                return self.inner.get(location).await;
+            }
+
+            let data = self.inner.get(location).await?.bytes().await?;
+            let head = data.slice(..data.len() / 2);
+            let stream = futures::stream::iter([Ok(head), Err(injected_error())]);
+            Ok(GetResult::Stream(Box::pin(stream)))
+        }
+
+        async fn get_range(
+            &self,
+            location: &Path,
+            range: Range<usize>,
+        ) -> object_store::Result<Bytes> {
+            if Self::should_fail(&self.range_failures) {
+                return Err(injected_error());
+            }
+            self.inner.get_range(location, range).await
+        }
+
+        async fn head(&self, location: &Path) -> object_store::Result<ObjectMeta> {
+            self.inner.head(location).await
+        }
+
+        async fn delete(&self, location: &Path) -> object_store::Result<()> {
+            self.inner.delete(location).await
+        }
+
+        async fn list(
+            &self,
+            prefix: Option<&Path>,
+        ) -> object_store::Result<BoxStream<'_, object_store::Result<ObjectMeta>>> {
+            self.inner.list(prefix).await
+        }
+
+        async fn list_with_delimiter(
+            &self,
+            prefix: Option<&Path>,
+        ) -> object_store::Result<ListResult> {
+            self.inner.list_with_delimiter(prefix).await
+        }
+
+        async fn copy(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+            self.inner.copy(from, to).await
+        }
+
+        async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> object_store::Result<()> {
+            self.inner.copy_if_not_exists(from, to).await
+        }
+    }
+}
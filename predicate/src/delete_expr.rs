@@ -44,11 +44,9 @@ pub enum DataFusionToExprError {
 pub(crate) fn df_to_expr(
     expr: datafusion::logical_plan::Expr,
 ) -> Result<DeleteExpr, DataFusionToExprError> {
-    match expr {
+    match normalize(expr) {
         datafusion::logical_plan::Expr::BinaryExpr { left, op, right } => {
             let (column, scalar) = match (left.deref(), right.deref()) {
-                // The delete predicate parser currently only supports `<column><op><value>`, not `<value><op><column>`,
-                // however this could can easily be extended to support the latter case as well.
                 (
                     datafusion::logical_plan::Expr::Column(column),
                     datafusion::logical_plan::Expr::Literal(value),
@@ -76,10 +74,85 @@ pub(crate) fn df_to_expr(
     }
 }
 
+/// Flip a binary expression of the form `<value><op><column>` into the canonical form
+/// `<column><op><value>`, mirroring the operator (e.g. `1 < a` becomes `a > 1`).
+///
+/// Other expressions are returned unchanged.
+fn normalize(expr: datafusion::logical_plan::Expr) -> datafusion::logical_plan::Expr {
+    use datafusion::logical_plan::Expr;
+
+    match expr {
+        Expr::BinaryExpr { left, op, right }
+            if matches!(
+                (left.deref(), right.deref()),
+                (Expr::Literal(_), Expr::Column(_))
+            ) =>
+        {
+            match mirror_op(op) {
+                Some(op) => Expr::BinaryExpr {
+                    left: right,
+                    op,
+                    right: left,
+                },
+                None => Expr::BinaryExpr { left, op, right },
+            }
+        }
+        other => other,
+    }
+}
+
+/// The operator `op'` for which `a op b` is equivalent to `b op' a`, if any.
+fn mirror_op(op: datafusion::logical_plan::Operator) -> Option<datafusion::logical_plan::Operator> {
+    use datafusion::logical_plan::Operator;
+
+    match op {
+        Operator::Eq => Some(Operator::Eq),
+        Operator::NotEq => Some(Operator::NotEq),
+        Operator::Lt => Some(Operator::Gt),
+        Operator::LtEq => Some(Operator::GtEq),
+        Operator::Gt => Some(Operator::Lt),
+        Operator::GtEq => Some(Operator::LtEq),
+        _ => None,
+    }
+}
+
+/// Convert a DataFusion expression that is an `AND`-conjunction of `<column><op><value>`
+/// expressions into its [`DeleteExpr`]s.
+pub(crate) fn df_to_exprs(
+    expr: datafusion::logical_plan::Expr,
+) -> Result<Vec<DeleteExpr>, DataFusionToExprError> {
+    let mut exprs = vec![];
+    split_conjunction(expr, &mut exprs)?;
+    Ok(exprs)
+}
+
+fn split_conjunction(
+    expr: datafusion::logical_plan::Expr,
+    exprs: &mut Vec<DeleteExpr>,
+) -> Result<(), DataFusionToExprError> {
+    match expr {
+        datafusion::logical_plan::Expr::BinaryExpr {
+            left,
+            op: datafusion::logical_plan::Operator::And,
+            right,
+        } => {
+            split_conjunction(*left, exprs)?;
+            split_conjunction(*right, exprs)?;
+        }
+        other => exprs.push(df_to_expr(other)?),
+    }
+
+    Ok(())
+}
+
 pub(crate) fn op_to_df(op: Op) -> datafusion::logical_plan::Operator {
     match op {
         Op::Eq => datafusion::logical_plan::Operator::Eq,
         Op::Ne => datafusion::logical_plan::Operator::NotEq,
+        Op::Lt => datafusion::logical_plan::Operator::Lt,
+        Op::Le => datafusion::logical_plan::Operator::LtEq,
+        Op::Gt => datafusion::logical_plan::Operator::Gt,
+        Op::Ge => datafusion::logical_plan::Operator::GtEq,
     }
 }
 
@@ -96,6 +169,10 @@ pub(crate) fn df_to_op(op: datafusion::logical_plan::Operator) -> Result<Op, Dat
     match op {
         datafusion::logical_plan::Operator::Eq => Ok(Op::Eq),
         datafusion::logical_plan::Operator::NotEq => Ok(Op::Ne),
+        datafusion::logical_plan::Operator::Lt => Ok(Op::Lt),
+        datafusion::logical_plan::Operator::LtEq => Ok(Op::Le),
+        datafusion::logical_plan::Operator::Gt => Ok(Op::Gt),
+        datafusion::logical_plan::Operator::GtEq => Ok(Op::Ge),
         other => Err(DataFusionToOpError::UnsupportedOperator { op: other }),
     }
 }
@@ -172,6 +249,114 @@ mod tests {
             },
             r#""col"='foo'"#,
         );
+        assert_expr_works(
+            DeleteExpr {
+                column: "cost".to_string(),
+                op: Op::Lt,
+                scalar: Scalar::I64(100),
+            },
+            r#""cost"<100"#,
+        );
+        assert_expr_works(
+            DeleteExpr {
+                column: "cost".to_string(),
+                op: Op::Le,
+                scalar: Scalar::I64(100),
+            },
+            r#""cost"<=100"#,
+        );
+        assert_expr_works(
+            DeleteExpr {
+                column: "cost".to_string(),
+                op: Op::Gt,
+                scalar: Scalar::F64((1.5).into()),
+            },
+            r#""cost">1.5"#,
+        );
+        assert_expr_works(
+            DeleteExpr {
+                column: "cost".to_string(),
+                op: Op::Ge,
+                scalar: Scalar::I64(-1),
+            },
+            r#""cost">=-1"#,
+        );
+    }
+
+    #[test]
+    fn test_value_on_left() {
+        use datafusion::logical_plan::{col, lit, Expr, Operator};
+
+        let cases = [
+            (Operator::Eq, Op::Eq),
+            (Operator::NotEq, Op::Ne),
+            (Operator::Lt, Op::Gt),
+            (Operator::LtEq, Op::Ge),
+            (Operator::Gt, Op::Lt),
+            (Operator::GtEq, Op::Le),
+        ];
+        for (df_op, op) in cases {
+            let expr = Expr::BinaryExpr {
+                left: Box::new(lit(100i64)),
+                op: df_op,
+                right: Box::new(col("cost")),
+            };
+            assert_eq!(
+                df_to_expr(expr).unwrap(),
+                DeleteExpr {
+                    column: "cost".to_string(),
+                    op,
+                    scalar: Scalar::I64(100),
+                }
+            );
+        }
+
+        // operators that can't be mirrored are still rejected
+        let expr = Expr::BinaryExpr {
+            left: Box::new(lit("x")),
+            op: Operator::Like,
+            right: Box::new(col("foo")),
+        };
+        let res = df_to_expr(expr);
+        assert_contains!(res.unwrap_err().to_string(), "unsupported operants:");
+    }
+
+    #[test]
+    fn test_conjunction_roundtrip() {
+        let exprs = vec![
+            DeleteExpr {
+                column: "region".to_string(),
+                op: Op::Eq,
+                scalar: Scalar::String("us".to_string()),
+            },
+            DeleteExpr {
+                column: "cost".to_string(),
+                op: Op::Gt,
+                scalar: Scalar::I64(100),
+            },
+            DeleteExpr {
+                column: "cost".to_string(),
+                op: Op::Le,
+                scalar: Scalar::I64(200),
+            },
+        ];
+
+        let df_expr = exprs
+            .iter()
+            .cloned()
+            .map(expr_to_df)
+            .reduce(|a, b| a.and(b))
+            .unwrap();
+        assert_eq!(df_to_exprs(df_expr).unwrap(), exprs);
+    }
+
+    #[test]
+    fn test_unsupported_conjunction() {
+        let expr = datafusion::logical_plan::col("a")
+            .eq(datafusion::logical_plan::lit(1i64))
+            .or(datafusion::logical_plan::col("b").eq(datafusion::logical_plan::lit(2i64)));
+        let res = df_to_exprs(expr);
+        assert_contains!(res.unwrap_err().to_string(), "unsupported operants:");
     }
 
     fn assert_expr_works(expr: DeleteExpr, display: &str) {
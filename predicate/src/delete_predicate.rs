@@ -1,4 +1,4 @@
-use crate::delete_expr::{df_to_expr, expr_to_df};
+use crate::delete_expr::{df_to_exprs, expr_to_df};
 use chrono::DateTime;
 use data_types::{DeleteExpr, DeletePredicate, TimestampRange, Tombstone};
 use datafusion::logical_plan::{lit, Column, Expr, Operator};
@@ -33,7 +33,7 @@ pub enum Error {
     InvalidSemantics { value: String },
 
     /// Predicate include non supported expression
-    #[snafu(display("Delete predicate must be conjunctive expressions of binary 'column_name = literal' or 'column_name != literal': ({})", value))]
+    #[snafu(display("Delete predicate must be conjunctive expressions of binary 'column_name <op> literal' where op is one of =, !=, <, <=, >, >=: ({})", value))]
     NotSupportPredicate { value: String },
 
     #[snafu(display(r#"Unable to parse delete string '{}'"#, value))]
@@ -75,6 +75,19 @@ impl From<DeletePredicate> for crate::Predicate {
     }
 }
 
+/// Convert delete predicates into a filter expression that is true for the rows that are NOT
+/// deleted by any of them, see [`Predicate::negated_expr`](crate::Predicate::negated_expr).
+///
+/// Returns `None` if there are no delete predicates.
+pub fn delete_predicates_to_filter(delete_predicates: &[Arc<DeletePredicate>]) -> Option<Expr> {
+    let delete_predicates: Vec<Arc<crate::Predicate>> = delete_predicates
+        .iter()
+        .map(|pred| Arc::new(pred.as_ref().clone().into()))
+        .collect();
+
+    crate::Predicate::negated_expr(&delete_predicates)
+}
+
 /// Convert tombstones to delete predicates
 pub fn tombstones_to_delete_predicates(tombstones: &[Tombstone]) -> Vec<Arc<DeletePredicate>> {
     tombstones_to_delete_predicates_iter(tombstones).collect()
@@ -116,7 +129,8 @@ pub fn parse_delete_predicate(
 
 /// Parse the predicate and convert it into datafusion expression
 /// A delete predicate is a conjunctive expression of many
-/// binary expressions of 'colum = constant' or 'column != constant'
+/// binary expressions of 'column <op> constant', where op is one of
+/// `=`, `!=`, `<`, `<=`, `>`, `>=`
 ///
 fn parse_predicate(predicate: &str) -> Result<Vec<DeleteExpr>> {
     if predicate.is_empty() {
@@ -151,14 +165,11 @@ fn parse_predicate(predicate: &str) -> Result<Vec<DeleteExpr>> {
                     ..
                 }) => {
                     // split this expr into smaller binary if any
-                    let mut exprs = vec![];
-                    let split = split_members(&expr, &mut exprs);
-                    if !split {
-                        return Err(Error::NotSupportPredicate {
+                    sql_to_df(&expr)
+                        .and_then(|expr| df_to_exprs(expr).ok())
+                        .ok_or_else(|| Error::NotSupportPredicate {
                             value: predicate.to_string(),
-                        });
-                    }
-                    Ok(exprs)
+                        })
                 }
                 _ => Err(Error::InvalidSemantics {
                     value: predicate.to_string(),
@@ -168,13 +179,12 @@ fn parse_predicate(predicate: &str) -> Result<Vec<DeleteExpr>> {
     }
 }
 
-/// Recursively split all "AND" expressions into smaller ones
-/// Example: "A AND B AND C" => [A, B, C]
-/// Return false if not all of them are AND of binary expression of
-/// "column_name = literal" or "column_name != literal"
+/// Convert an "AND" conjunction of binary expressions into a DataFusion expression.
 ///
-/// The split expressions will be converted into data fusion expressions
-fn split_members(predicate: &SqlParserExpr, predicates: &mut Vec<DeleteExpr>) -> bool {
+/// Return `None` if not all of them are AND of binary expression of
+/// "column_name <op> literal" or "literal <op> column_name" where op is one of
+/// `=`, `!=`, `<`, `<=`, `>`, `>=`
+fn sql_to_df(predicate: &SqlParserExpr) -> Option<Expr> {
     // The below code built to be compatible with
     // https://github.com/influxdata/influxdb/blob/master/predicate/parser_test.go
     match predicate {
@@ -182,72 +192,70 @@ fn split_members(predicate: &SqlParserExpr, predicates: &mut Vec<DeleteExpr>) ->
             left,
             op: BinaryOperator::And,
             right,
-        } => {
-            if !split_members(left, predicates) {
-                return false;
-            }
-            if !split_members(right, predicates) {
-                return false;
-            }
-        }
+        } => Some(sql_to_df(left)?.and(sql_to_df(right)?)),
         SqlParserExpr::BinaryOp { left, op, right } => {
             // Verify Operator
             let op = match op {
                 BinaryOperator::Eq => Operator::Eq,
                 BinaryOperator::NotEq => Operator::NotEq,
-                _ => return false,
-            };
-
-            // verify if left is identifier (column name)
-            let column = match &**left {
-                SqlParserExpr::Identifier(Ident {
-                    value,
-                    quote_style: _, // all quotes are ignored as done in idpe
-                }) => Expr::Column(Column {
-                    relation: None,
-                    name: value.to_string(),
-                }),
-                _ => return false, // not a column name
+                BinaryOperator::Lt => Operator::Lt,
+                BinaryOperator::LtEq => Operator::LtEq,
+                BinaryOperator::Gt => Operator::Gt,
+                BinaryOperator::GtEq => Operator::GtEq,
+                _ => return None,
             };
 
-            // verify if right is a literal or an identifier (e.g column name)
-            let value = match &**right {
-                SqlParserExpr::Identifier(Ident {
-                    value,
-                    quote_style: _,
-                }) => lit(value.to_string()),
-                SqlParserExpr::Value(Value::DoubleQuotedString(value)) => lit(value.to_string()),
-                SqlParserExpr::Value(Value::SingleQuotedString(value)) => lit(value.to_string()),
-                SqlParserExpr::Value(Value::NationalStringLiteral(value)) => lit(value.to_string()),
-                SqlParserExpr::Value(Value::HexStringLiteral(value)) => lit(value.to_string()),
-                SqlParserExpr::Value(Value::Number(v, _)) => match v.parse::<i64>() {
-                    Ok(v) => lit(v),
-                    Err(_) => lit(v.parse::<f64>().unwrap()),
-                },
-                SqlParserExpr::Value(Value::Boolean(v)) => lit(*v),
-                _ => return false, // not a literal
+            // `<value><op><column>` is flipped into `<column><op><value>` by `df_to_exprs`
+            let (left, right) = if let SqlParserExpr::Value(_) = &**left {
+                (sql_to_literal(left)?, sql_to_column(right)?)
+            } else {
+                (sql_to_column(left)?, sql_to_literal(right)?)
             };
 
-            let expr = Expr::BinaryExpr {
-                left: Box::new(column),
+            Some(Expr::BinaryExpr {
+                left: Box::new(left),
                 op,
-                right: Box::new(value),
-            };
-            let expr: Result<DeleteExpr, _> = df_to_expr(expr);
-            match expr {
-                Ok(expr) => {
-                    predicates.push(expr);
-                }
-                Err(_) => {
-                    // cannot convert
-                    return false;
-                }
-            }
+                right: Box::new(right),
+            })
         }
-        _ => return false,
+        _ => None,
     }
+}
 
-    true
+/// Convert an identifier into a column
+fn sql_to_column(expr: &SqlParserExpr) -> Option<Expr> {
+    match expr {
+        SqlParserExpr::Identifier(Ident {
+            value,
+            quote_style: _, // all quotes are ignored as done in idpe
+        }) => Some(Expr::Column(Column {
+            relation: None,
+            name: value.to_string(),
+        })),
+        _ => None, // not a column name
+    }
+}
+
+/// Convert a literal or an identifier (e.g column name) into a literal
+fn sql_to_literal(expr: &SqlParserExpr) -> Option<Expr> {
+    let value = match expr {
+        SqlParserExpr::Identifier(Ident {
+            value,
+            quote_style: _,
+        }) => lit(value.to_string()),
+        SqlParserExpr::Value(Value::DoubleQuotedString(value)) => lit(value.to_string()),
+        SqlParserExpr::Value(Value::SingleQuotedString(value)) => lit(value.to_string()),
+        SqlParserExpr::Value(Value::NationalStringLiteral(value)) => lit(value.to_string()),
+        SqlParserExpr::Value(Value::HexStringLiteral(value)) => lit(value.to_string()),
+        SqlParserExpr::Value(Value::Number(v, _)) => match v.parse::<i64>() {
+            Ok(v) => lit(v),
+            Err(_) => lit(v.parse::<f64>().unwrap()),
+        },
+        SqlParserExpr::Value(Value::Boolean(v)) => lit(*v),
+        _ => return None, // not a literal
+    };
+
+    Some(value)
 }
 
 /// Parse a time and return its time in nanosecond
@@ -528,6 +536,55 @@ mod tests {
         assert_eq!(result, expected)
     }
 
+    #[test]
+    fn test_parse_predicate_range() {
+        let pred = r#"region='us' AND cost > 100 and cost <= 200.5 AND temp < 10 AND temp >= 1"#;
+        let result = parse_predicate(pred).unwrap();
+
+        let expected = vec![
+            DeleteExpr::new(
+                "region".to_string(),
+                Op::Eq,
+                Scalar::String("us".to_string()),
+            ),
+            DeleteExpr::new("cost".to_string(), Op::Gt, Scalar::I64(100)),
+            DeleteExpr::new("cost".to_string(), Op::Le, Scalar::F64((200.5).into())),
+            DeleteExpr::new("temp".to_string(), Op::Lt, Scalar::I64(10)),
+            DeleteExpr::new("temp".to_string(), Op::Ge, Scalar::I64(1)),
+        ];
+        assert_eq!(result, expected);
+
+        // the SQL representation of the predicate parses back to the same expressions
+        let delete_predicate = DeletePredicate {
+            range: TimestampRange::new(1, 2),
+            exprs: expected,
+        };
+        let result = parse_predicate(&delete_predicate.expr_sql_string()).unwrap();
+        assert_eq!(result, delete_predicate.exprs);
+    }
+
+    #[test]
+    fn test_parse_predicate_value_on_left() {
+        let pred = r#"'us' = region AND 100 < cost and 200.5 >= cost AND 'MA' != state"#;
+        let result = parse_predicate(pred).unwrap();
+
+        let expected = vec![
+            DeleteExpr::new(
+                "region".to_string(),
+                Op::Eq,
+                Scalar::String("us".to_string()),
+            ),
+            DeleteExpr::new("cost".to_string(), Op::Gt, Scalar::I64(100)),
+            DeleteExpr::new("cost".to_string(), Op::Le, Scalar::F64((200.5).into())),
+            DeleteExpr::new(
+                "state".to_string(),
+                Op::Ne,
+                Scalar::String("MA".to_string()),
+            ),
+        ];
+        assert_eq!(result, expected);
+    }
+
     #[test]
     fn test_parse_predicate_invalid() {
         let pred = r#"city= Boston Or cost !=100 and state != "MA""#; // OR
@@ -538,11 +595,11 @@ mod tests {
         let result = parse_predicate(pred);
         assert!(result.is_err());
 
-        let pred = r#"cost > 100"#; // >
+        let pred = r#"cost > 100 OR cost < 10"#; // OR
         let result = parse_predicate(pred);
         assert!(result.is_err());
 
-        let pred = r#"cost <= 100"#; // <
+        let pred = r#"100 < 200"#; // no column
         let result = parse_predicate(pred);
         assert!(result.is_err());
 
@@ -555,6 +612,19 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_delete_predicates_to_filter() {
+        assert!(delete_predicates_to_filter(&[]).is_none());
+
+        let pred = Arc::new(parse_delete_predicate("0", "200", "cost > 100").unwrap());
+        let filter = delete_predicates_to_filter(&[Arc::clone(&pred)]).unwrap();
+
+        let expected =
+            crate::Predicate::negated_expr(&[Arc::new(crate::Predicate::from((*pred).clone()))])
+                .unwrap();
+        assert_eq!(filter, expected);
+    }
+
     #[test]
     fn test_full_delete_pred() {
         let start = r#"1970-01-01T00:00:00Z"#; // this is nano 0
@@ -587,7 +657,7 @@ mod tests {
     fn test_full_delete_pred_invalid_pred() {
         let start = r#"100"#;
         let stop = r#"200"#;
-        let pred = r#"cost > 100"#;
+        let pred = r#"cost > 100 OR cost < 10"#;
 
         let result = parse_delete_predicate(start, stop, pred);
         assert!(result.is_err());
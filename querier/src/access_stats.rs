@@ -0,0 +1,264 @@
+//! Table access statistics.
+//!
+//! Every table scan records an access of its table. Accesses are aggregated in memory, so
+//! recording them is cheap, and periodically written to the catalog (see
+//! [`TableAccessStatsRepo`]). There they inform data tiering decisions, e.g. the compactor
+//! prioritizes rarely read tables.
+//!
+//! [`TableAccessStatsRepo`]: iox_catalog::interface::TableAccessStatsRepo
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use data_types::{TableId, Timestamp};
+use iox_catalog::interface::Catalog;
+use iox_time::{Time, TimeProvider};
+use metric::U64Counter;
+use observability_deps::tracing::{debug, warn};
+use parking_lot::Mutex;
+use tokio_util::sync::CancellationToken;
+
+/// Accesses of a table that were not written to the catalog yet.
+#[derive(Debug, Clone, Copy)]
+struct PendingAccess {
+    last_queried_at: Time,
+    query_count: u64,
+}
+
+/// Tracker for the access statistics of tables.
+#[derive(Debug)]
+pub struct TableAccessTracker {
+    catalog: Arc<dyn Catalog>,
+    time_provider: Arc<dyn TimeProvider>,
+    flush_interval: Duration,
+    pending: Mutex<HashMap<TableId, PendingAccess>>,
+    metric_flush_errors: U64Counter,
+}
+
+impl TableAccessTracker {
+    /// Create new tracker that writes the recorded accesses to the catalog every
+    /// `flush_interval`.
+    pub fn new(
+        catalog: Arc<dyn Catalog>,
+        time_provider: Arc<dyn TimeProvider>,
+        flush_interval: Duration,
+        metric_registry: &metric::Registry,
+    ) -> Self {
+        let metric_flush_errors = metric_registry
+            .register_metric::<U64Counter>(
+                "querier_table_access_stats_flush_errors",
+                "Number of failed attempts to write table access statistics to the catalog",
+            )
+            .recorder(&[]);
+
+        Self {
+            catalog,
+            time_provider,
+            flush_interval,
+            pending: Default::default(),
+            metric_flush_errors,
+        }
+    }
+
+    /// Record a query against the given table.
+    pub fn record(&self, table_id: TableId) {
+        let now = self.time_provider.now();
+
+        self.pending
+            .lock()
+            .entry(table_id)
+            .and_modify(|access| {
+                access.last_queried_at = access.last_queried_at.max(now);
+                access.query_count += 1;
+            })
+            .or_insert(PendingAccess {
+                last_queried_at: now,
+                query_count: 1,
+            });
+    }
+
+    /// Write the accesses recorded since the last flush to the catalog.
+    ///
+    /// If that fails, the accesses are kept and written with the next flush.
+    pub async fn flush(&self) {
+        let pending = std::mem::take(&mut *self.pending.lock());
+        if pending.is_empty() {
+            return;
+        }
+
+        match self.write(&pending).await {
+            Ok(()) => {
+                debug!(n_tables = pending.len(), "wrote table access statistics");
+            }
+            Err(e) => {
+                warn!(%e, n_tables = pending.len(), "cannot write table access statistics to catalog");
+                self.metric_flush_errors.inc(1);
+
+                let mut current = self.pending.lock();
+                for (table_id, access) in pending {
+                    current
+                        .entry(table_id)
+                        .and_modify(|current| {
+                            current.last_queried_at =
+                                current.last_queried_at.max(access.last_queried_at);
+                            current.query_count += access.query_count;
+                        })
+                        .or_insert(access);
+                }
+            }
+        }
+    }
+
+    async fn write(
+        &self,
+        pending: &HashMap<TableId, PendingAccess>,
+    ) -> Result<(), iox_catalog::interface::Error> {
+        // Use a transaction, so that the statistics are written to the primary even if the
+        // catalog serves reads from a replica.
+        let mut txn = self.catalog.start_transaction().await?;
+
+        for (table_id, access) in pending {
+            let res = txn
+                .table_access_stats()
+                .record(
+                    *table_id,
+                    Timestamp::new(access.last_queried_at.timestamp_nanos()),
+                    access.query_count as i64,
+                )
+                .await;
+
+            if let Err(e) = res {
+                txn.abort().await?;
+                return Err(e);
+            }
+        }
+
+        txn.commit().await
+    }
+
+    /// Flush the recorded accesses every flush interval until `shutdown` is triggered, then flush
+    /// one last time.
+    pub async fn run(self: Arc<Self>, shutdown: CancellationToken) {
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = tokio::time::sleep(self.flush_interval) => {}
+            }
+
+            self.flush().await;
+        }
+
+        self.flush().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use data_types::TableAccessStats;
+    use iox_tests::util::TestCatalog;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_flush() {
+        let catalog = TestCatalog::new();
+        let ns = catalog.create_namespace("ns").await;
+        let table1 = ns.create_table("table1").await;
+        let table2 = ns.create_table("table2").await;
+
+        let tracker = TableAccessTracker::new(
+            catalog.catalog(),
+            catalog.time_provider(),
+            Duration::from_secs(60),
+            &catalog.metric_registry(),
+        );
+
+        // nothing to write
+        tracker.flush().await;
+        assert_eq!(get_stats(&catalog, table1.table.id).await, None);
+
+        tracker.record(table1.table.id);
+        catalog.mock_time_provider().inc(Duration::from_secs(1));
+        let t1 = catalog.time_provider().now();
+        tracker.record(table1.table.id);
+        tracker.record(table2.table.id);
+
+        // accesses are only written on flush
+        assert_eq!(get_stats(&catalog, table1.table.id).await, None);
+
+        tracker.flush().await;
+        assert_eq!(
+            get_stats(&catalog, table1.table.id).await,
+            Some(TableAccessStats {
+                table_id: table1.table.id,
+                last_queried_at: Timestamp::new(t1.timestamp_nanos()),
+                query_count: 2,
+            })
+        );
+        assert_eq!(
+            get_stats(&catalog, table2.table.id).await,
+            Some(TableAccessStats {
+                table_id: table2.table.id,
+                last_queried_at: Timestamp::new(t1.timestamp_nanos()),
+                query_count: 1,
+            })
+        );
+
+        // subsequent flushes add to the statistics
+        catalog.mock_time_provider().inc(Duration::from_secs(1));
+        let t2 = catalog.time_provider().now();
+        tracker.record(table1.table.id);
+        tracker.flush().await;
+        tracker.flush().await;
+        assert_eq!(
+            get_stats(&catalog, table1.table.id).await,
+            Some(TableAccessStats {
+                table_id: table1.table.id,
+                last_queried_at: Timestamp::new(t2.timestamp_nanos()),
+                query_count: 3,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_flush_error_keeps_accesses() {
+        let catalog = TestCatalog::new();
+        let ns = catalog.create_namespace("ns").await;
+        let table = ns.create_table("table").await;
+
+        let tracker = TableAccessTracker::new(
+            catalog.catalog(),
+            catalog.time_provider(),
+            Duration::from_secs(60),
+            &catalog.metric_registry(),
+        );
+
+        // an unknown table fails the entire flush
+        let unknown = TableId::new(i64::MAX);
+        tracker.record(table.table.id);
+        tracker.record(unknown);
+        tracker.flush().await;
+        assert_eq!(get_stats(&catalog, table.table.id).await, None);
+
+        // the accesses are kept for the next flush
+        tracker.record(table.table.id);
+        tracker.pending.lock().remove(&unknown);
+        tracker.flush().await;
+        assert_eq!(
+            get_stats(&catalog, table.table.id)
+                .await
+                .unwrap()
+                .query_count,
+            2
+        );
+    }
+
+    async fn get_stats(catalog: &TestCatalog, table_id: TableId) -> Option<TableAccessStats> {
+        catalog
+            .catalog()
+            .repositories()
+            .await
+            .table_access_stats()
+            .get_by_table_id(table_id)
+            .await
+            .unwrap()
+    }
+}
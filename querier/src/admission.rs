@@ -0,0 +1,330 @@
+//! Memory-based admission control for queries.
+//!
+//! Every table scan estimates the memory it needs from the statistics of its chunks and reserves
+//! that amount from a pool shared by all queries. If the pool is exhausted, the scan waits for
+//! other queries to finish, up to [`QueryAdmissionConfig::max_queue_duration`]. Scans that would
+//! never fit into the pool are rejected right away.
+//!
+//! Reservations are attached to the [`QueryMemoryReservation`] of the query and released once
+//! the query finishes.
+use std::{sync::Arc, time::Duration};
+
+use iox_query::QueryMemoryReservation;
+use metric::{U64Counter, U64Gauge};
+use observability_deps::tracing::debug;
+use snafu::Snafu;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Granularity of the memory pool.
+///
+/// Tokio semaphores can only hand out `u32` permits at once, so the pool is accounted in KiB.
+const BYTES_PER_PERMIT: usize = 1024;
+
+#[derive(Debug, Snafu)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[snafu(display(
+        "Query needs an estimated {} bytes which exceeds the query memory pool of {} bytes",
+        needed_bytes,
+        pool_bytes
+    ))]
+    TooLarge {
+        needed_bytes: usize,
+        pool_bytes: usize,
+    },
+
+    #[snafu(display(
+        "Query memory pool exhausted, could not reserve {} bytes within {:?}",
+        needed_bytes,
+        max_queue_duration
+    ))]
+    PoolExhausted {
+        needed_bytes: usize,
+        max_queue_duration: Duration,
+    },
+}
+
+/// Configuration of the query admission control.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryAdmissionConfig {
+    /// Memory pool shared by all queries, in bytes.
+    pub pool_bytes: usize,
+
+    /// Maximum duration a query waits for memory to become available before it is rejected.
+    pub max_queue_duration: Duration,
+}
+
+/// Admission control that limits the estimated memory of all running queries.
+#[derive(Debug)]
+pub struct QueryAdmission {
+    config: QueryAdmissionConfig,
+    semaphore: Arc<Semaphore>,
+    pool_permits: usize,
+    metric_queued: U64Counter,
+    metric_rejected_too_large: U64Counter,
+    metric_rejected_timeout: U64Counter,
+    metric_reserved_bytes: U64Gauge,
+}
+
+impl QueryAdmission {
+    /// Create new admission control with an empty pool.
+    pub fn new(config: QueryAdmissionConfig, metric_registry: &metric::Registry) -> Self {
+        let pool_permits = (config.pool_bytes / BYTES_PER_PERMIT)
+            .min(Semaphore::MAX_PERMITS)
+            .min(u32::MAX as usize);
+
+        let metric_queued = metric_registry
+            .register_metric::<U64Counter>(
+                "querier_query_admission_queued",
+                "Number of table scans that had to wait for query memory to become available",
+            )
+            .recorder(&[]);
+        let metric_rejected = metric_registry.register_metric::<U64Counter>(
+            "querier_query_admission_rejected",
+            "Number of table scans that were rejected by the query memory admission control",
+        );
+        let metric_rejected_too_large = metric_rejected.recorder(&[("reason", "too_large")]);
+        let metric_rejected_timeout = metric_rejected.recorder(&[("reason", "timeout")]);
+        let metric_reserved_bytes = metric_registry
+            .register_metric::<U64Gauge>(
+                "querier_query_admission_reserved_bytes",
+                "Estimated query memory currently reserved by running queries",
+            )
+            .recorder(&[]);
+
+        Self {
+            config,
+            semaphore: Arc::new(Semaphore::new(pool_permits)),
+            pool_permits,
+            metric_queued,
+            metric_rejected_too_large,
+            metric_rejected_timeout,
+            metric_reserved_bytes,
+        }
+    }
+
+    /// Reserve `bytes` for the given query.
+    ///
+    /// Waits for memory to become available if the pool is currently exhausted. The reservation
+    /// is released once the query finishes. If `reservation` is `None` (i.e. the request is not
+    /// part of a tracked query), the memory is only checked against the pool size but not
+    /// reserved.
+    pub async fn admit(
+        &self,
+        bytes: usize,
+        reservation: Option<&QueryMemoryReservation>,
+    ) -> Result<(), Error> {
+        let permits = bytes_to_permits(bytes);
+        if permits > self.pool_permits {
+            self.metric_rejected_too_large.inc(1);
+            return Err(Error::TooLarge {
+                needed_bytes: bytes,
+                pool_bytes: self.config.pool_bytes,
+            });
+        }
+
+        let reservation = match reservation {
+            Some(reservation) => reservation,
+            None => return Ok(()),
+        };
+        if permits == 0 {
+            return Ok(());
+        }
+
+        // `permits` is bounded by `pool_permits` which fits into an u32
+        let permits_u32 = permits as u32;
+        let permit = match Arc::clone(&self.semaphore).try_acquire_many_owned(permits_u32) {
+            Ok(permit) => permit,
+            Err(_) => {
+                debug!(bytes, "query memory pool exhausted, queuing query");
+                self.metric_queued.inc(1);
+
+                let acquire = Arc::clone(&self.semaphore).acquire_many_owned(permits_u32);
+                match tokio::time::timeout(self.config.max_queue_duration, acquire).await {
+                    Ok(permit) => permit.expect("semaphore should not be closed by anyone"),
+                    Err(_) => {
+                        self.metric_rejected_timeout.inc(1);
+                        return Err(Error::PoolExhausted {
+                            needed_bytes: bytes,
+                            max_queue_duration: self.config.max_queue_duration,
+                        });
+                    }
+                }
+            }
+        };
+
+        let reserved_bytes = (permits * BYTES_PER_PERMIT) as u64;
+        self.metric_reserved_bytes.inc(reserved_bytes);
+        reservation.add(
+            bytes,
+            AdmissionPermit {
+                permit,
+                reserved_bytes,
+                metric_reserved_bytes: self.metric_reserved_bytes.clone(),
+            },
+        );
+
+        Ok(())
+    }
+}
+
+/// Memory reserved from the pool, released on drop.
+#[derive(Debug)]
+struct AdmissionPermit {
+    #[allow(dead_code)]
+    permit: OwnedSemaphorePermit,
+    reserved_bytes: u64,
+    metric_reserved_bytes: U64Gauge,
+}
+
+impl Drop for AdmissionPermit {
+    fn drop(&mut self) {
+        self.metric_reserved_bytes.dec(self.reserved_bytes);
+    }
+}
+
+fn bytes_to_permits(bytes: usize) -> usize {
+    bytes / BYTES_PER_PERMIT + usize::from(bytes % BYTES_PER_PERMIT != 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use metric::{Attributes, Metric};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_admission() {
+        let metric_registry = metric::Registry::new();
+        let admission = QueryAdmission::new(
+            QueryAdmissionConfig {
+                pool_bytes: 10 * BYTES_PER_PERMIT,
+                max_queue_duration: Duration::from_millis(10),
+            },
+            &metric_registry,
+        );
+
+        // too large for the pool, even without a reservation
+        assert!(matches!(
+            admission.admit(10 * BYTES_PER_PERMIT + 1, None).await,
+            Err(Error::TooLarge { .. })
+        ));
+        assert_eq!(get_rejected(&metric_registry, "too_large"), 1);
+
+        // untracked requests do not reserve anything
+        admission.admit(10 * BYTES_PER_PERMIT, None).await.unwrap();
+        assert_eq!(get_reserved_bytes(&metric_registry), 0);
+
+        // fill the pool
+        let query1 = QueryMemoryReservation::new();
+        admission
+            .admit(4 * BYTES_PER_PERMIT, Some(&query1))
+            .await
+            .unwrap();
+        admission.admit(1, Some(&query1)).await.unwrap();
+        assert_eq!(query1.bytes(), 4 * BYTES_PER_PERMIT + 1);
+        assert_eq!(
+            get_reserved_bytes(&metric_registry),
+            5 * BYTES_PER_PERMIT as u64
+        );
+
+        let query2 = QueryMemoryReservation::new();
+        admission
+            .admit(5 * BYTES_PER_PERMIT, Some(&query2))
+            .await
+            .unwrap();
+
+        // pool exhausted => queue and time out
+        let query3 = QueryMemoryReservation::new();
+        assert!(matches!(
+            admission.admit(BYTES_PER_PERMIT, Some(&query3)).await,
+            Err(Error::PoolExhausted { .. })
+        ));
+        assert_eq!(get_queued(&metric_registry), 1);
+        assert_eq!(get_rejected(&metric_registry, "timeout"), 1);
+
+        // finishing a query frees its memory
+        drop(query1);
+        assert_eq!(
+            get_reserved_bytes(&metric_registry),
+            5 * BYTES_PER_PERMIT as u64
+        );
+        admission
+            .admit(BYTES_PER_PERMIT, Some(&query3))
+            .await
+            .unwrap();
+        assert_eq!(get_queued(&metric_registry), 1);
+    }
+
+    #[tokio::test]
+    async fn test_queued_query_is_admitted() {
+        let metric_registry = metric::Registry::new();
+        let admission = Arc::new(QueryAdmission::new(
+            QueryAdmissionConfig {
+                pool_bytes: BYTES_PER_PERMIT,
+                max_queue_duration: Duration::from_secs(10),
+            },
+            &metric_registry,
+        ));
+
+        let query1 = QueryMemoryReservation::new();
+        admission
+            .admit(BYTES_PER_PERMIT, Some(&query1))
+            .await
+            .unwrap();
+
+        let admission_captured = Arc::clone(&admission);
+        let handle = tokio::spawn(async move {
+            let query2 = QueryMemoryReservation::new();
+            admission_captured
+                .admit(BYTES_PER_PERMIT, Some(&query2))
+                .await
+                .unwrap();
+            query2.bytes()
+        });
+
+        // wait until the second query is queued
+        while get_queued(&metric_registry) == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        drop(query1);
+        assert_eq!(handle.await.unwrap(), BYTES_PER_PERMIT);
+        assert_eq!(get_rejected(&metric_registry, "timeout"), 0);
+    }
+
+    #[test]
+    fn test_bytes_to_permits() {
+        assert_eq!(bytes_to_permits(0), 0);
+        assert_eq!(bytes_to_permits(1), 1);
+        assert_eq!(bytes_to_permits(BYTES_PER_PERMIT), 1);
+        assert_eq!(bytes_to_permits(BYTES_PER_PERMIT + 1), 2);
+    }
+
+    fn get_queued(metric_registry: &metric::Registry) -> u64 {
+        metric_registry
+            .get_instrument::<Metric<U64Counter>>("querier_query_admission_queued")
+            .unwrap()
+            .get_observer(&Attributes::from(&[]))
+            .unwrap()
+            .fetch()
+    }
+
+    fn get_rejected(metric_registry: &metric::Registry, reason: &'static str) -> u64 {
+        metric_registry
+            .get_instrument::<Metric<U64Counter>>("querier_query_admission_rejected")
+            .unwrap()
+            .get_observer(&Attributes::from(&[("reason", reason)]))
+            .unwrap()
+            .fetch()
+    }
+
+    fn get_reserved_bytes(metric_registry: &metric::Registry) -> u64 {
+        metric_registry
+            .get_instrument::<Metric<U64Gauge>>("querier_query_admission_reserved_bytes")
+            .unwrap()
+            .get_observer(&Attributes::from(&[]))
+            .unwrap()
+            .fetch()
+    }
+}
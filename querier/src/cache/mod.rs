@@ -1,4 +1,5 @@
 //! Caches used by the querier.
+use ::object_store::DynObjectStore;
 use backoff::BackoffConfig;
 use cache_system::backend::policy::lru::ResourcePool;
 use iox_catalog::interface::Catalog;
@@ -7,12 +8,19 @@ use std::sync::Arc;
 use tokio::runtime::Handle;
 
 use self::{
-    namespace::NamespaceCache, parquet_file::ParquetFileCache, partition::PartitionCache,
-    processed_tombstones::ProcessedTombstonesCache, projected_schema::ProjectedSchemaCache,
-    ram::RamSize, read_buffer::ReadBufferCache, tombstones::TombstoneCache,
+    namespace::NamespaceCache,
+    object_store::{ObjectStoreCache, ObjectStoreCacheConfig},
+    parquet_file::ParquetFileCache,
+    partition::PartitionCache,
+    processed_tombstones::ProcessedTombstonesCache,
+    projected_schema::ProjectedSchemaCache,
+    ram::RamSize,
+    read_buffer::ReadBufferCache,
+    tombstones::TombstoneCache,
 };
 
 pub mod namespace;
+pub mod object_store;
 pub mod parquet_file;
 pub mod partition;
 pub mod processed_tombstones;
@@ -22,7 +30,13 @@ pub mod read_buffer;
 pub mod tombstones;
 
 #[cfg(test)]
-mod test_util;
+pub(crate) mod test_util;
+
+/// Fraction by which the TTL of catalog cache entries is randomly prolonged.
+///
+/// This avoids that entries that were loaded together (e.g. during startup) all expire at the
+/// same time and hit the catalog with a burst of reloads.
+const TTL_JITTER: f64 = 0.1;
 
 /// Caches request to the [`Catalog`].
 #[derive(Debug)]
@@ -51,6 +65,9 @@ pub struct CatalogCache {
     /// Projected schema cache.
     projected_schema_cache: ProjectedSchemaCache,
 
+    /// Object store cache.
+    object_store_cache: ObjectStoreCache,
+
     /// Metric registry
     metric_registry: Arc<metric::Registry>,
 
@@ -60,20 +77,25 @@ pub struct CatalogCache {
 
 impl CatalogCache {
     /// Create empty cache.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         catalog: Arc<dyn Catalog>,
         time_provider: Arc<dyn TimeProvider>,
         metric_registry: Arc<metric::Registry>,
+        object_store: Arc<DynObjectStore>,
         ram_pool_metadata_bytes: usize,
         ram_pool_data_bytes: usize,
+        object_store_cache_config: ObjectStoreCacheConfig,
         handle: &Handle,
     ) -> Self {
         Self::new_internal(
             catalog,
             time_provider,
             metric_registry,
+            object_store,
             ram_pool_metadata_bytes,
             ram_pool_data_bytes,
+            object_store_cache_config,
             handle,
             false,
         )
@@ -86,30 +108,39 @@ impl CatalogCache {
         catalog: Arc<dyn Catalog>,
         time_provider: Arc<dyn TimeProvider>,
         metric_registry: Arc<metric::Registry>,
+        object_store: Arc<DynObjectStore>,
         handle: &Handle,
     ) -> Self {
         Self::new_internal(
             catalog,
             time_provider,
             metric_registry,
+            object_store,
             usize::MAX,
             usize::MAX,
+            ObjectStoreCacheConfig::default(),
             handle,
             true,
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn new_internal(
         catalog: Arc<dyn Catalog>,
         time_provider: Arc<dyn TimeProvider>,
         metric_registry: Arc<metric::Registry>,
+        object_store: Arc<DynObjectStore>,
         ram_pool_metadata_bytes: usize,
         ram_pool_data_bytes: usize,
+        object_store_cache_config: ObjectStoreCacheConfig,
         handle: &Handle,
         testing: bool,
     ) -> Self {
         let backoff_config = BackoffConfig::default();
 
+        // tests rely on entries expiring exactly after their TTL
+        let ttl_jitter = if testing { 0.0 } else { TTL_JITTER };
+
         let ram_pool_metadata = Arc::new(ResourcePool::new(
             "ram_metadata",
             RamSize(ram_pool_metadata_bytes),
@@ -127,6 +158,7 @@ impl CatalogCache {
             Arc::clone(&time_provider),
             &metric_registry,
             Arc::clone(&ram_pool_metadata),
+            handle,
             testing,
         );
         let namespace_cache = NamespaceCache::new(
@@ -135,6 +167,7 @@ impl CatalogCache {
             Arc::clone(&time_provider),
             &metric_registry,
             Arc::clone(&ram_pool_metadata),
+            ttl_jitter,
             handle,
             testing,
         );
@@ -144,6 +177,7 @@ impl CatalogCache {
             Arc::clone(&time_provider),
             &metric_registry,
             Arc::clone(&ram_pool_metadata),
+            ttl_jitter,
             testing,
         );
         let parquet_file_cache = ParquetFileCache::new(
@@ -163,12 +197,23 @@ impl CatalogCache {
             testing,
         );
         let read_buffer_cache = ReadBufferCache::new(
-            backoff_config,
+            backoff_config.clone(),
             Arc::clone(&time_provider),
             Arc::clone(&metric_registry),
             Arc::clone(&ram_pool_data),
             testing,
         );
+        let object_store_cache = ObjectStoreCache::new(
+            backoff_config,
+            object_store,
+            Arc::clone(&time_provider),
+            &metric_registry,
+            Arc::clone(&ram_pool_metadata),
+            Arc::clone(&ram_pool_data),
+            object_store_cache_config,
+            handle,
+            testing,
+        );
         let projected_schema_cache = ProjectedSchemaCache::new(
             Arc::clone(&time_provider),
             &metric_registry,
@@ -185,6 +230,7 @@ impl CatalogCache {
             tombstone_cache,
             read_buffer_cache,
             projected_schema_cache,
+            object_store_cache,
             metric_registry,
             time_provider,
         }
@@ -239,4 +285,9 @@ impl CatalogCache {
     pub(crate) fn projected_schema(&self) -> &ProjectedSchemaCache {
         &self.projected_schema_cache
     }
+
+    /// Object store cache.
+    pub fn object_store(&self) -> &ObjectStoreCache {
+        &self.object_store_cache
+    }
 }
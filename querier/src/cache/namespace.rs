@@ -4,7 +4,7 @@ use backoff::{Backoff, BackoffConfig};
 use cache_system::{
     backend::policy::{
         lru::{LruPolicy, ResourcePool},
-        refresh::{OptionalValueRefreshDurationProvider, RefreshPolicy},
+        refresh::{OptionalValueRefreshDurationProvider, RefreshAheadConfig, RefreshPolicy},
         remove_if::{RemoveIfHandle, RemoveIfPolicy},
         ttl::{OptionalValueTtlProvider, TtlPolicy},
         PolicyBackend,
@@ -46,6 +46,10 @@ pub const REFRESH_EXISTING: Duration = Duration::from_secs(10);
 pub const TTL_NON_EXISTING: Duration = Duration::from_nanos(2);
 pub const REFRESH_NON_EXISTING: Duration = Duration::from_nanos(1);
 
+/// Number of accesses within the refresh window after which a namespace is refreshed ahead of time,
+/// so that frequently queried namespaces never have to wait for the catalog.
+pub const REFRESH_AHEAD_MIN_ACCESSES: u64 = 5;
+
 const CACHE_ID: &str = "namespace";
 
 type CacheT = Box<
@@ -72,6 +76,7 @@ impl NamespaceCache {
         time_provider: Arc<dyn TimeProvider>,
         metric_registry: &metric::Registry,
         ram_pool: Arc<ResourcePool<RamSize>>,
+        ttl_jitter: f64,
         handle: &Handle,
         testing: bool,
     ) -> Self {
@@ -106,15 +111,16 @@ impl NamespaceCache {
         ));
 
         let mut backend = PolicyBackend::new(Box::new(HashMap::new()), Arc::clone(&time_provider));
-        backend.add_policy(TtlPolicy::new(
+        backend.add_policy(TtlPolicy::new_with_jitter(
             Arc::new(OptionalValueTtlProvider::new(
                 Some(TTL_NON_EXISTING),
                 Some(TTL_EXISTING),
             )),
+            ttl_jitter,
             CACHE_ID,
             metric_registry,
         ));
-        backend.add_policy(RefreshPolicy::new(
+        backend.add_policy(RefreshPolicy::new_with_refresh_ahead(
             Arc::new(OptionalValueRefreshDurationProvider::new(
                 Some(REFRESH_NON_EXISTING),
                 Some(REFRESH_EXISTING),
@@ -123,6 +129,10 @@ impl NamespaceCache {
             CACHE_ID,
             metric_registry,
             handle,
+            RefreshAheadConfig {
+                time_provider: Arc::clone(&time_provider),
+                min_accesses: REFRESH_AHEAD_MIN_ACCESSES,
+            },
         ));
 
         let (constructor, remove_if_handle) =
@@ -143,7 +153,7 @@ impl NamespaceCache {
             )),
         ));
 
-        let cache = CacheDriver::new(loader, backend);
+        let cache = CacheDriver::new_with_metrics(loader, backend, CACHE_ID, metric_registry);
         let cache = Box::new(CacheWithMetrics::new(
             cache,
             CACHE_ID,
@@ -297,6 +307,7 @@ mod tests {
             catalog.time_provider(),
             &catalog.metric_registry(),
             test_ram_pool(),
+            0.0,
             &Handle::current(),
             true,
         );
@@ -388,6 +399,7 @@ mod tests {
             catalog.time_provider(),
             &catalog.metric_registry(),
             test_ram_pool(),
+            0.0,
             &Handle::current(),
             true,
         );
@@ -411,6 +423,7 @@ mod tests {
             catalog.time_provider(),
             &catalog.metric_registry(),
             test_ram_pool(),
+            0.0,
             &Handle::current(),
             true,
         );
@@ -0,0 +1,1043 @@
+//! Cache for object store data.
+//!
+//! Objects are split into fixed-size chunks that are loaded and cached independently, so range
+//! requests (e.g. reading the footer or a few row groups of a large parquet file) only load and
+//! keep the chunks they actually touch instead of the whole object.
+//!
+//! Hot chunks are kept in the RAM pool. Since they are expensive to reload, they are weighted with
+//! [`CHUNK_RELOAD_COST`] so that cheap catalog metadata sharing the same pool is evicted first.
+//! Optionally, a second tier on local disk keeps chunks that
+//! were loaded from the object store, so they don't have to be downloaded again after they were
+//! evicted from RAM or after a restart. See [`DiskTierConfig`]. In this case the sizes of known
+//! objects are also written to a [snapshot](cache_system::snapshot) in the same directory on
+//! shutdown (see [`ObjectStoreCache::write_snapshot`]), so that a restarted querier does not need
+//! to issue a HEAD request for every object again.
+//!
+//! Cached data never expires on its own because parquet files are immutable. Only "not found"
+//! results expire after [`ObjectStoreCacheConfig::not_found_ttl`], so objects that are written
+//! after they were first requested become visible. Objects that were
+//! deleted (by the garbage collector or manually) can be removed via
+//! [`ObjectStoreCache::invalidate`] or by the periodic reconciliation, see
+//! [`ObjectStoreCacheConfig::reconciliation_interval`].
+
+use self::disk::DiskTier;
+use super::ram::RamSize;
+use async_trait::async_trait;
+use backoff::{Backoff, BackoffConfig};
+use bytes::{Bytes, BytesMut};
+use cache_system::{
+    backend::policy::{
+        lru::{LruPolicy, ResourcePool},
+        remove_if::{RemoveIfHandle, RemoveIfPolicy},
+        ttl::{OptionalValueTtlProvider, TtlPolicy},
+        PolicyBackend,
+    },
+    cache::{driver::CacheDriver, metrics::CacheWithMetrics, Cache},
+    loader::{metrics::MetricsLoader, FunctionLoader},
+    resource_consumption::{FunctionEstimator, FunctionReloadCostEstimator},
+    snapshot::{write_snapshot, Snapshot},
+};
+use futures::{stream::BoxStream, StreamExt};
+use iox_time::TimeProvider;
+use object_store::{
+    path::Path, DynObjectStore, Error, GetResult, ListResult, MultipartId, ObjectMeta, ObjectStore,
+    Result,
+};
+use observability_deps::tracing::{info, warn};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap, fmt::Display, mem, ops::Range, path::PathBuf, sync::Arc, time::Duration,
+};
+use tokio::{io::AsyncWrite, runtime::Handle};
+use trace::span::Span;
+
+mod disk;
+
+const CACHE_ID_SIZE: &str = "object_store_size";
+const CACHE_ID_CHUNK: &str = "object_store_chunk";
+
+/// File name of the size snapshot within the disk tier directory.
+const SIZE_SNAPSHOT_FILE: &str = "sizes.snapshot";
+
+/// Name used for errors created by the cache.
+const STORE_NAME: &str = "ObjectStoreCache";
+
+/// Default size of the chunks that objects are split into.
+pub const DEFAULT_CHUNK_SIZE_BYTES: usize = 1024 * 1024;
+
+/// Reload cost of a cached chunk.
+///
+/// Chunks require an object store request to be reloaded, which is a lot more expensive than
+/// re-fetching catalog metadata. For eviction purposes, chunks are treated as if they were used
+/// this much later than they actually were.
+pub const CHUNK_RELOAD_COST: Duration = Duration::from_secs(1);
+
+/// Default duration to keep "not found" results.
+pub const DEFAULT_NOT_FOUND_TTL: Duration = Duration::from_secs(10);
+
+/// Default value of [`ObjectStoreCacheConfig::not_found_ttl_jitter`].
+pub const DEFAULT_NOT_FOUND_TTL_JITTER: f64 = 0.1;
+
+/// Configuration of the [`ObjectStoreCache`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectStoreCacheConfig {
+    /// Size of the chunks that objects are split into.
+    pub chunk_size_bytes: usize,
+
+    /// Optional second tier on local disk.
+    pub disk: Option<DiskTierConfig>,
+
+    /// Interval at which all cached objects are checked against the underlying object store.
+    ///
+    /// Objects that no longer exist are removed from all cache tiers. `None` disables the
+    /// reconciliation.
+    pub reconciliation_interval: Option<Duration>,
+
+    /// Duration to keep "not found" results, `None` keeps them forever.
+    pub not_found_ttl: Option<Duration>,
+
+    /// Fraction of [`not_found_ttl`](Self::not_found_ttl) by which the expiration of "not found"
+    /// results is randomly delayed, so that they are not all re-checked at once.
+    pub not_found_ttl_jitter: f64,
+}
+
+impl Default for ObjectStoreCacheConfig {
+    fn default() -> Self {
+        Self {
+            chunk_size_bytes: DEFAULT_CHUNK_SIZE_BYTES,
+            disk: None,
+            reconciliation_interval: None,
+            not_found_ttl: Some(DEFAULT_NOT_FOUND_TTL),
+            not_found_ttl_jitter: DEFAULT_NOT_FOUND_TTL_JITTER,
+        }
+    }
+}
+
+/// Configuration of the local-disk tier of the [`ObjectStoreCache`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskTierConfig {
+    /// Directory that holds the cached chunks.
+    ///
+    /// It is created if it does not exist. Chunks that are already present (e.g. from before a
+    /// restart) are reused. The directory also holds the snapshot of known object sizes.
+    pub path: PathBuf,
+
+    /// Maximum number of bytes stored in the directory. Least recently used chunks are removed
+    /// to stay below this limit.
+    pub max_bytes: usize,
+}
+
+/// Size of an object, `None` if the object does not exist.
+type SizeCacheT = Box<
+    dyn Cache<
+        K = Path,
+        V = Option<usize>,
+        GetExtra = ((), Option<Span>),
+        PeekExtra = ((), Option<Span>),
+    >,
+>;
+
+/// Chunk of an object, keyed by path and chunk index. `None` if the object does not exist.
+///
+/// The extra data passed to GET is the size of the object, so the loader can clamp the last
+/// chunk.
+type ChunkCacheT = Box<
+    dyn Cache<
+        K = (Path, usize),
+        V = Option<Bytes>,
+        GetExtra = (usize, Option<Span>),
+        PeekExtra = ((), Option<Span>),
+    >,
+>;
+
+/// Cache for object store data.
+#[derive(Debug)]
+pub struct ObjectStoreCache {
+    object_store: Arc<CachedObjectStore>,
+}
+
+impl ObjectStoreCache {
+    /// Create new empty cache.
+    ///
+    /// If configured, the reconciliation task is spawned on the given runtime `handle`. It stops
+    /// once the cache and all object stores returned by [`object_store`](Self::object_store) are
+    /// dropped.
+    ///
+    /// # Panic
+    /// Panics if the chunk size is zero or if the disk tier cannot be set up.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        backoff_config: BackoffConfig,
+        object_store: Arc<DynObjectStore>,
+        time_provider: Arc<dyn TimeProvider>,
+        metric_registry: &metric::Registry,
+        ram_pool_metadata: Arc<ResourcePool<RamSize>>,
+        ram_pool_data: Arc<ResourcePool<RamSize>>,
+        config: ObjectStoreCacheConfig,
+        handle: &Handle,
+        testing: bool,
+    ) -> Self {
+        let ObjectStoreCacheConfig {
+            chunk_size_bytes,
+            disk,
+            reconciliation_interval,
+            not_found_ttl,
+            not_found_ttl_jitter,
+        } = config;
+        assert!(chunk_size_bytes > 0, "chunk size must be positive");
+
+        let size_snapshot_path = disk
+            .as_ref()
+            .map(|config| config.path.join(SIZE_SNAPSHOT_FILE));
+        let size_snapshot = Arc::new(match &size_snapshot_path {
+            Some(path) => Snapshot::read(path),
+            None => Snapshot::from(HashMap::new()),
+        });
+
+        let disk = disk.map(|config| {
+            let path = config.path.clone();
+            match DiskTier::new(config, metric_registry) {
+                Ok(disk) => Arc::new(disk),
+                Err(e) => panic!(
+                    "cannot set up object store disk cache at {}: {}",
+                    path.display(),
+                    e
+                ),
+            }
+        });
+
+        let known_sizes = Arc::new(Mutex::new(HashMap::new()));
+
+        let (size_cache, size_remove_if) = size_cache(
+            backoff_config.clone(),
+            Arc::clone(&object_store),
+            Arc::clone(&time_provider),
+            metric_registry,
+            ram_pool_metadata,
+            Arc::clone(&known_sizes),
+            size_snapshot,
+            not_found_ttl,
+            not_found_ttl_jitter,
+            testing,
+        );
+        let (chunk_cache, chunk_remove_if) = chunk_cache(
+            backoff_config,
+            Arc::clone(&object_store),
+            Arc::clone(&time_provider),
+            metric_registry,
+            ram_pool_data,
+            disk.clone(),
+            chunk_size_bytes,
+            not_found_ttl,
+            not_found_ttl_jitter,
+            testing,
+        );
+
+        let cached_store = Arc::new(CachedObjectStore {
+            inner: object_store,
+            size_cache,
+            size_remove_if,
+            chunk_cache,
+            chunk_remove_if,
+            disk,
+            known_sizes,
+            size_snapshot_path,
+            chunk_size_bytes,
+        });
+
+        if let Some(interval) = reconciliation_interval {
+            let store = Arc::downgrade(&cached_store);
+            handle.spawn(async move {
+                loop {
+                    time_provider.sleep(interval).await;
+
+                    let store = match store.upgrade() {
+                        Some(store) => store,
+                        None => return,
+                    };
+                    let n_invalidated = store.reconcile().await;
+                    if n_invalidated > 0 {
+                        info!(
+                            n_invalidated,
+                            "removed deleted objects from object store cache"
+                        );
+                    }
+                }
+            });
+        }
+
+        Self {
+            object_store: cached_store,
+        }
+    }
+
+    /// Remove all cached data of the given object from all cache tiers.
+    ///
+    /// This must be called when an object is deleted or overwritten, otherwise stale data might
+    /// be served. Note that reads that are already in flight may still re-populate the cache.
+    pub fn invalidate(&self, path: &Path) {
+        self.object_store.invalidate(path);
+    }
+
+    /// Check all cached objects against the underlying object store and invalidate the ones that
+    /// no longer exist.
+    ///
+    /// Returns the number of invalidated objects.
+    pub async fn reconcile(&self) -> usize {
+        self.object_store.reconcile().await
+    }
+
+    /// Get an object store that reads through this cache.
+    ///
+    /// Only read requests (`get`, `get_range`) are cached; `head` and listing requests are
+    /// forwarded to the underlying store. The returned store is read-only.
+    pub fn object_store(&self) -> Arc<DynObjectStore> {
+        Arc::clone(&self.object_store) as _
+    }
+
+    /// Write sizes of all known objects to the snapshot file, so they can be rehydrated after a
+    /// restart.
+    ///
+    /// This is a no-op if the disk tier is disabled. Errors are logged but otherwise ignored
+    /// because the snapshot is only an optimization.
+    pub fn write_snapshot(&self) {
+        self.object_store.write_snapshot();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn size_cache(
+    backoff_config: BackoffConfig,
+    object_store: Arc<DynObjectStore>,
+    time_provider: Arc<dyn TimeProvider>,
+    metric_registry: &metric::Registry,
+    ram_pool: Arc<ResourcePool<RamSize>>,
+    known_sizes: Arc<Mutex<HashMap<Path, usize>>>,
+    snapshot: Arc<Snapshot<String, usize>>,
+    not_found_ttl: Option<Duration>,
+    not_found_ttl_jitter: f64,
+    testing: bool,
+) -> (SizeCacheT, RemoveIfHandle<Path, Option<usize>>) {
+    let loader = FunctionLoader::new(move |path: Path, _extra: ()| {
+        let backoff_config = backoff_config.clone();
+        let object_store = Arc::clone(&object_store);
+        let known_sizes = Arc::clone(&known_sizes);
+        let snapshot = Arc::clone(&snapshot);
+
+        async move {
+            // rehydrate from snapshot, objects are immutable so the size is still correct
+            if let Some(size) = snapshot.take(&path.to_string()) {
+                known_sizes.lock().insert(path, size);
+                return Some(size);
+            }
+
+            let size = Backoff::new(&backoff_config)
+                .retry_all_errors("get object size from object store", || async {
+                    match object_store.head(&path).await {
+                        Ok(meta) => Ok(Some(meta.size)),
+                        Err(Error::NotFound { .. }) => Ok(None),
+                        Err(e) => Err(e),
+                    }
+                })
+                .await
+                .expect("retry forever");
+
+            if let Some(size) = size {
+                known_sizes.lock().insert(path, size);
+            }
+
+            size
+        }
+    });
+    let loader = Arc::new(MetricsLoader::new(
+        loader,
+        CACHE_ID_SIZE,
+        Arc::clone(&time_provider),
+        metric_registry,
+        testing,
+    ));
+
+    let mut backend = PolicyBackend::new(Box::new(HashMap::new()), Arc::clone(&time_provider));
+    backend.add_policy(TtlPolicy::new_with_jitter(
+        Arc::new(OptionalValueTtlProvider::new(not_found_ttl, None)),
+        not_found_ttl_jitter,
+        CACHE_ID_SIZE,
+        metric_registry,
+    ));
+    let (policy_constructor, remove_if_handle) =
+        RemoveIfPolicy::create_constructor_and_handle(CACHE_ID_SIZE, metric_registry);
+    backend.add_policy(policy_constructor);
+    backend.add_policy(LruPolicy::new(
+        ram_pool,
+        CACHE_ID_SIZE,
+        Arc::new(FunctionEstimator::new(|k: &Path, v: &Option<usize>| {
+            RamSize(mem::size_of_val(k) + k.as_ref().len() + mem::size_of_val(v))
+        })),
+    ));
+
+    let cache = CacheDriver::new_with_metrics(loader, backend, CACHE_ID_SIZE, metric_registry);
+    let cache = Box::new(CacheWithMetrics::new(
+        cache,
+        CACHE_ID_SIZE,
+        time_provider,
+        metric_registry,
+    ));
+
+    (cache, remove_if_handle)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn chunk_cache(
+    backoff_config: BackoffConfig,
+    object_store: Arc<DynObjectStore>,
+    time_provider: Arc<dyn TimeProvider>,
+    metric_registry: &metric::Registry,
+    ram_pool: Arc<ResourcePool<RamSize>>,
+    disk: Option<Arc<DiskTier>>,
+    chunk_size_bytes: usize,
+    not_found_ttl: Option<Duration>,
+    not_found_ttl_jitter: f64,
+    testing: bool,
+) -> (ChunkCacheT, RemoveIfHandle<(Path, usize), Option<Bytes>>) {
+    let loader = FunctionLoader::new(move |(path, index): (Path, usize), size: usize| {
+        let backoff_config = backoff_config.clone();
+        let object_store = Arc::clone(&object_store);
+        let disk = disk.clone();
+
+        let start = index * chunk_size_bytes;
+        let end = (start + chunk_size_bytes).min(size);
+
+        async move {
+            if let Some(disk) = &disk {
+                if let Some(data) = disk.get(&path, index).await {
+                    return Some(data);
+                }
+            }
+
+            let data = Backoff::new(&backoff_config)
+                .retry_all_errors("get object chunk from object store", || async {
+                    match object_store.get_range(&path, start..end).await {
+                        Ok(data) => Ok(Some(data)),
+                        Err(Error::NotFound { .. }) => Ok(None),
+                        Err(e) => Err(e),
+                    }
+                })
+                .await
+                .expect("retry forever");
+
+            if let (Some(disk), Some(data)) = (&disk, &data) {
+                disk.put(&path, index, data.clone()).await;
+            }
+
+            data
+        }
+    });
+    let loader = Arc::new(MetricsLoader::new(
+        loader,
+        CACHE_ID_CHUNK,
+        Arc::clone(&time_provider),
+        metric_registry,
+        testing,
+    ));
+
+    let mut backend = PolicyBackend::new(Box::new(HashMap::new()), Arc::clone(&time_provider));
+    backend.add_policy(TtlPolicy::new_with_jitter(
+        Arc::new(OptionalValueTtlProvider::new(not_found_ttl, None)),
+        not_found_ttl_jitter,
+        CACHE_ID_CHUNK,
+        metric_registry,
+    ));
+    let (policy_constructor, remove_if_handle) =
+        RemoveIfPolicy::create_constructor_and_handle(CACHE_ID_CHUNK, metric_registry);
+    backend.add_policy(policy_constructor);
+    backend.add_policy(LruPolicy::new_with_reload_cost(
+        ram_pool,
+        CACHE_ID_CHUNK,
+        Arc::new(FunctionEstimator::new(
+            |k: &(Path, usize), v: &Option<Bytes>| {
+                RamSize(
+                    mem::size_of_val(k)
+                        + k.0.as_ref().len()
+                        + mem::size_of_val(v)
+                        + v.as_ref().map(|data| data.len()).unwrap_or_default(),
+                )
+            },
+        )),
+        Arc::new(FunctionReloadCostEstimator::new(
+            |_k: &(Path, usize), v: &Option<Bytes>| {
+                if v.is_some() {
+                    CHUNK_RELOAD_COST
+                } else {
+                    Duration::ZERO
+                }
+            },
+        )),
+    ));
+
+    let cache = CacheDriver::new_with_metrics(loader, backend, CACHE_ID_CHUNK, metric_registry);
+    let cache = Box::new(CacheWithMetrics::new(
+        cache,
+        CACHE_ID_CHUNK,
+        time_provider,
+        metric_registry,
+    ));
+
+    (cache, remove_if_handle)
+}
+
+/// Read-only [`ObjectStore`] that serves reads from the chunk cache.
+#[derive(Debug)]
+struct CachedObjectStore {
+    inner: Arc<DynObjectStore>,
+    size_cache: SizeCacheT,
+    size_remove_if: RemoveIfHandle<Path, Option<usize>>,
+    chunk_cache: ChunkCacheT,
+    chunk_remove_if: RemoveIfHandle<(Path, usize), Option<Bytes>>,
+    disk: Option<Arc<DiskTier>>,
+
+    /// Sizes of all existing objects that were loaded, used to find the chunks of an object on
+    /// invalidation and to know which objects to check during reconciliation.
+    ///
+    /// Entries are only removed on invalidation, not when the cache entries are evicted.
+    known_sizes: Arc<Mutex<HashMap<Path, usize>>>,
+
+    /// Path of the size snapshot, `None` if the disk tier is disabled.
+    size_snapshot_path: Option<PathBuf>,
+
+    chunk_size_bytes: usize,
+}
+
+impl CachedObjectStore {
+    fn invalidate(&self, location: &Path) {
+        let size = self.known_sizes.lock().remove(location);
+        self.size_remove_if.remove_if(location, |_| true);
+
+        let n_chunks = match size {
+            Some(size) => (size + self.chunk_size_bytes - 1) / self.chunk_size_bytes,
+            None => return,
+        };
+        for index in 0..n_chunks {
+            self.chunk_remove_if
+                .remove_if(&(location.clone(), index), |_| true);
+            if let Some(disk) = &self.disk {
+                disk.remove(location, index);
+            }
+        }
+    }
+
+    fn write_snapshot(&self) {
+        let path = match &self.size_snapshot_path {
+            Some(path) => path,
+            None => return,
+        };
+
+        let sizes: HashMap<String, usize> = self
+            .known_sizes
+            .lock()
+            .iter()
+            .map(|(k, v)| (k.to_string(), *v))
+            .collect();
+        if let Err(e) = write_snapshot(path, &sizes) {
+            warn!(%e, path=%path.display(), "cannot write object store size snapshot");
+        }
+    }
+
+    async fn reconcile(&self) -> usize {
+        let paths: Vec<_> = self.known_sizes.lock().keys().cloned().collect();
+
+        let mut n_invalidated = 0;
+        for path in paths {
+            match self.inner.head(&path).await {
+                Ok(_) => {}
+                Err(Error::NotFound { .. }) => {
+                    self.invalidate(&path);
+                    n_invalidated += 1;
+                }
+                Err(e) => {
+                    warn!(
+                        %e,
+                        %path,
+                        "cannot check object during object store cache reconciliation"
+                    );
+                }
+            }
+        }
+        n_invalidated
+    }
+
+    async fn size(&self, location: &Path) -> Result<usize> {
+        self.size_cache
+            .get(location.clone(), ((), None))
+            .await
+            .ok_or_else(|| not_found(location))
+    }
+
+    async fn get_range_cached(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        let size = self.size(location).await?;
+        if range.start > range.end || range.end > size {
+            return Err(Error::Generic {
+                store: STORE_NAME,
+                source: format!(
+                    "Range {}..{} out of bounds for object {} of size {}",
+                    range.start, range.end, location, size
+                )
+                .into(),
+            });
+        }
+        if range.is_empty() {
+            return Ok(Bytes::new());
+        }
+
+        let first = range.start / self.chunk_size_bytes;
+        let last = (range.end - 1) / self.chunk_size_bytes;
+
+        let chunks = futures::future::try_join_all((first..=last).map(|index| async move {
+            self.chunk_cache
+                .get((location.clone(), index), (size, None))
+                .await
+                .ok_or_else(|| not_found(location))
+        }))
+        .await?;
+
+        // fast path: range within a single chunk, no copy required
+        if let [chunk] = chunks.as_slice() {
+            let offset = range.start - first * self.chunk_size_bytes;
+            return Ok(chunk.slice(offset..offset + range.len()));
+        }
+
+        let mut out = BytesMut::with_capacity(range.len());
+        for (index, chunk) in (first..=last).zip(chunks) {
+            let chunk_start = index * self.chunk_size_bytes;
+            let from = range.start.saturating_sub(chunk_start);
+            let to = (range.end - chunk_start).min(chunk.len());
+            out.extend_from_slice(&chunk[from..to]);
+        }
+        Ok(out.freeze())
+    }
+}
+
+impl Display for CachedObjectStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}({})", STORE_NAME, self.inner)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for CachedObjectStore {
+    async fn put(&self, _location: &Path, _bytes: Bytes) -> Result<()> {
+        Err(read_only("put"))
+    }
+
+    async fn put_multipart(
+        &self,
+        _location: &Path,
+    ) -> Result<(MultipartId, Box<dyn AsyncWrite + Unpin + Send>)> {
+        Err(read_only("put_multipart"))
+    }
+
+    async fn abort_multipart(&self, _location: &Path, _multipart_id: &MultipartId) -> Result<()> {
+        Err(read_only("abort_multipart"))
+    }
+
+    async fn get(&self, location: &Path) -> Result<GetResult> {
+        let size = self.size(location).await?;
+        let data = self.get_range_cached(location, 0..size).await?;
+        Ok(GetResult::Stream(
+            futures::stream::once(async move { Ok(data) }).boxed(),
+        ))
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.get_range_cached(location, range).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, _location: &Path) -> Result<()> {
+        Err(read_only("delete"))
+    }
+
+    async fn list(&self, prefix: Option<&Path>) -> Result<BoxStream<'_, Result<ObjectMeta>>> {
+        self.inner.list(prefix).await
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, _from: &Path, _to: &Path) -> Result<()> {
+        Err(read_only("copy"))
+    }
+
+    async fn copy_if_not_exists(&self, _from: &Path, _to: &Path) -> Result<()> {
+        Err(read_only("copy_if_not_exists"))
+    }
+}
+
+fn not_found(location: &Path) -> Error {
+    Error::NotFound {
+        path: location.to_string(),
+        source: format!("Object {} not found", location).into(),
+    }
+}
+
+fn read_only(op: &'static str) -> Error {
+    Error::Generic {
+        store: STORE_NAME,
+        source: format!("{} is not supported, the cache is read-only", op).into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::ram::test_util::test_ram_pool;
+    use assert_matches::assert_matches;
+    use iox_time::{MockProvider, SystemProvider, Time};
+    use object_store::memory::InMemory;
+
+    const CHUNK_SIZE: usize = 4;
+
+    fn make_cache(object_store: Arc<DynObjectStore>) -> ObjectStoreCache {
+        make_cache_with_disk(object_store, None)
+    }
+
+    fn make_cache_with_disk(
+        object_store: Arc<DynObjectStore>,
+        disk: Option<DiskTierConfig>,
+    ) -> ObjectStoreCache {
+        make_cache_with_config(
+            object_store,
+            Arc::new(SystemProvider::new()),
+            ObjectStoreCacheConfig {
+                chunk_size_bytes: CHUNK_SIZE,
+                disk,
+                reconciliation_interval: None,
+                not_found_ttl: None,
+                not_found_ttl_jitter: 0.0,
+            },
+        )
+    }
+
+    fn make_cache_with_config(
+        object_store: Arc<DynObjectStore>,
+        time_provider: Arc<dyn TimeProvider>,
+        config: ObjectStoreCacheConfig,
+    ) -> ObjectStoreCache {
+        ObjectStoreCache::new(
+            BackoffConfig::default(),
+            object_store,
+            time_provider,
+            &metric::Registry::new(),
+            test_ram_pool(),
+            test_ram_pool(),
+            config,
+            &Handle::current(),
+            true,
+        )
+    }
+
+    async fn setup() -> (Arc<DynObjectStore>, ObjectStoreCache, Path) {
+        let inner: Arc<DynObjectStore> = Arc::new(InMemory::new());
+        let path = Path::from("foo");
+        inner
+            .put(&path, Bytes::from_static(b"0123456789"))
+            .await
+            .unwrap();
+
+        let cache = make_cache(Arc::clone(&inner));
+        (inner, cache, path)
+    }
+
+    #[tokio::test]
+    async fn test_get_range() {
+        let (_inner, cache, path) = setup().await;
+        let store = cache.object_store();
+
+        // within a single chunk
+        assert_eq!(store.get_range(&path, 1..3).await.unwrap().as_ref(), b"12");
+
+        // spanning multiple chunks, including the truncated last chunk
+        assert_eq!(
+            store.get_range(&path, 3..10).await.unwrap().as_ref(),
+            b"3456789"
+        );
+
+        // exactly one chunk
+        assert_eq!(
+            store.get_range(&path, 4..8).await.unwrap().as_ref(),
+            b"4567"
+        );
+
+        // empty
+        assert!(store.get_range(&path, 5..5).await.unwrap().is_empty());
+
+        // out of bounds
+        store.get_range(&path, 5..11).await.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_get() {
+        let (_inner, cache, path) = setup().await;
+        let store = cache.object_store();
+
+        let data = store.get(&path).await.unwrap().bytes().await.unwrap();
+        assert_eq!(data.as_ref(), b"0123456789");
+    }
+
+    #[tokio::test]
+    async fn test_only_required_chunks_are_cached() {
+        let (inner, cache, path) = setup().await;
+        let store = cache.object_store();
+
+        // load first chunk
+        assert_eq!(store.get_range(&path, 0..2).await.unwrap().as_ref(), b"01");
+
+        // remove object from underlying store
+        inner.delete(&path).await.unwrap();
+
+        // first chunk is still served from cache
+        assert_eq!(store.get_range(&path, 1..4).await.unwrap().as_ref(), b"123");
+
+        // the last chunk was never loaded
+        let err = store.get_range(&path, 8..10).await.unwrap_err();
+        assert_matches!(err, Error::NotFound { .. });
+    }
+
+    #[tokio::test]
+    async fn test_not_found() {
+        let (_inner, cache, _path) = setup().await;
+        let store = cache.object_store();
+
+        let err = store.get_range(&Path::from("bar"), 0..1).await.unwrap_err();
+        assert_matches!(err, Error::NotFound { .. });
+    }
+
+    #[tokio::test]
+    async fn test_not_found_ttl() {
+        let inner: Arc<DynObjectStore> = Arc::new(InMemory::new());
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let ttl = Duration::from_secs(10);
+        let cache = make_cache_with_config(
+            Arc::clone(&inner),
+            Arc::clone(&time_provider) as _,
+            ObjectStoreCacheConfig {
+                chunk_size_bytes: CHUNK_SIZE,
+                disk: None,
+                reconciliation_interval: None,
+                not_found_ttl: Some(ttl),
+                not_found_ttl_jitter: 0.0,
+            },
+        );
+        let store = cache.object_store();
+        let path = Path::from("foo");
+
+        let err = store.get_range(&path, 0..2).await.unwrap_err();
+        assert_matches!(err, Error::NotFound { .. });
+
+        // object arrives late, "not found" is still cached
+        inner
+            .put(&path, Bytes::from_static(b"0123456789"))
+            .await
+            .unwrap();
+        time_provider.inc(ttl / 2);
+        let err = store.get_range(&path, 0..2).await.unwrap_err();
+        assert_matches!(err, Error::NotFound { .. });
+
+        // after the TTL the object becomes visible
+        time_provider.inc(ttl);
+        assert_eq!(store.get_range(&path, 0..2).await.unwrap().as_ref(), b"01");
+
+        // existing objects do not expire
+        inner.delete(&path).await.unwrap();
+        time_provider.inc(ttl * 10);
+        assert_eq!(store.get_range(&path, 0..2).await.unwrap().as_ref(), b"01");
+    }
+
+    #[tokio::test]
+    async fn test_read_only() {
+        let (_inner, cache, path) = setup().await;
+        let store = cache.object_store();
+
+        store.put(&path, Bytes::new()).await.unwrap_err();
+        store.delete(&path).await.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn test_disk_tier_survives_restart() {
+        let (inner, cache, path) = setup().await;
+        let dir = tempfile::tempdir().unwrap();
+        let disk = DiskTierConfig {
+            path: dir.path().to_owned(),
+            max_bytes: 1024,
+        };
+        drop(cache);
+
+        let cache = make_cache_with_disk(Arc::clone(&inner), Some(disk.clone()));
+        assert_eq!(
+            cache
+                .object_store()
+                .get_range(&path, 0..6)
+                .await
+                .unwrap()
+                .as_ref(),
+            b"012345"
+        );
+        drop(cache);
+
+        // overwrite object with data of the same size, so that only data served from the disk
+        // tier still shows the old content
+        inner
+            .put(&path, Bytes::from_static(b"abcdefghij"))
+            .await
+            .unwrap();
+
+        // new cache with empty RAM pool but the same disk tier
+        let cache = make_cache_with_disk(Arc::clone(&inner), Some(disk));
+        let store = cache.object_store();
+
+        // first two chunks come from disk
+        assert_eq!(
+            store.get_range(&path, 0..8).await.unwrap().as_ref(),
+            b"01234567"
+        );
+
+        // last chunk was never loaded
+        assert_eq!(store.get_range(&path, 8..10).await.unwrap().as_ref(), b"ij");
+    }
+
+    #[tokio::test]
+    async fn test_size_snapshot() {
+        let (inner, cache, path) = setup().await;
+        drop(cache);
+        let dir = tempfile::tempdir().unwrap();
+        let disk = DiskTierConfig {
+            path: dir.path().to_owned(),
+            max_bytes: 1024,
+        };
+        let snapshot_path = dir.path().join(SIZE_SNAPSHOT_FILE);
+
+        let cache = make_cache_with_disk(Arc::clone(&inner), Some(disk.clone()));
+        assert_eq!(
+            cache
+                .object_store()
+                .get(&path)
+                .await
+                .unwrap()
+                .bytes()
+                .await
+                .unwrap()
+                .as_ref(),
+            b"0123456789"
+        );
+        cache.write_snapshot();
+        drop(cache);
+        assert!(snapshot_path.exists());
+
+        // delete object, so that the size can only come from the snapshot and the data only from
+        // the disk tier
+        inner.delete(&path).await.unwrap();
+
+        let cache = make_cache_with_disk(Arc::clone(&inner), Some(disk));
+        assert!(!snapshot_path.exists());
+        assert_eq!(
+            cache
+                .object_store()
+                .get_range(&path, 2..10)
+                .await
+                .unwrap()
+                .as_ref(),
+            b"23456789"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalidate() {
+        let (inner, cache, path) = setup().await;
+        let dir = tempfile::tempdir().unwrap();
+        let cache = make_cache_with_disk(
+            Arc::clone(&inner),
+            Some(DiskTierConfig {
+                path: dir.path().to_owned(),
+                max_bytes: 1024,
+            }),
+        );
+        let store = cache.object_store();
+        assert_eq!(store.get_range(&path, 0..10).await.unwrap().len(), 10);
+
+        // overwrite object, cache still serves the old content
+        inner
+            .put(&path, Bytes::from_static(b"abcdefghij"))
+            .await
+            .unwrap();
+        assert_eq!(store.get_range(&path, 0..2).await.unwrap().as_ref(), b"01");
+
+        // all tiers are invalidated
+        cache.invalidate(&path);
+        assert_eq!(
+            store.get_range(&path, 0..10).await.unwrap().as_ref(),
+            b"abcdefghij"
+        );
+
+        // invalidating unknown objects is a no-op
+        cache.invalidate(&Path::from("bar"));
+    }
+
+    #[tokio::test]
+    async fn test_reconcile() {
+        let (inner, cache, path) = setup().await;
+        let store = cache.object_store();
+
+        let other = Path::from("bar");
+        inner.put(&other, Bytes::from_static(b"xyz")).await.unwrap();
+
+        assert_eq!(store.get_range(&path, 0..2).await.unwrap().as_ref(), b"01");
+        assert_eq!(store.get_range(&other, 0..2).await.unwrap().as_ref(), b"xy");
+        assert_eq!(cache.reconcile().await, 0);
+
+        inner.delete(&path).await.unwrap();
+        assert_eq!(cache.reconcile().await, 1);
+
+        let err = store.get_range(&path, 0..2).await.unwrap_err();
+        assert_matches!(err, Error::NotFound { .. });
+        assert_eq!(store.get_range(&other, 0..2).await.unwrap().as_ref(), b"xy");
+
+        // deleted objects are only reported once
+        assert_eq!(cache.reconcile().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_reconciliation_task() {
+        let (inner, _cache, path) = setup().await;
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let interval = Duration::from_secs(60);
+        let cache = make_cache_with_config(
+            Arc::clone(&inner),
+            Arc::clone(&time_provider) as _,
+            ObjectStoreCacheConfig {
+                chunk_size_bytes: CHUNK_SIZE,
+                disk: None,
+                reconciliation_interval: Some(interval),
+                not_found_ttl: None,
+                not_found_ttl_jitter: 0.0,
+            },
+        );
+        let store = cache.object_store();
+
+        assert_eq!(store.get_range(&path, 0..2).await.unwrap().as_ref(), b"01");
+        inner.delete(&path).await.unwrap();
+        assert_eq!(store.get_range(&path, 0..2).await.unwrap().as_ref(), b"01");
+
+        // the background task might not have started sleeping yet, so keep advancing the time
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while store.get_range(&path, 0..2).await.is_ok() {
+                time_provider.inc(interval);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("object was not invalidated");
+    }
+}
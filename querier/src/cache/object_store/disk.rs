@@ -0,0 +1,384 @@
+//! Local-disk tier of the object store cache.
+//!
+//! Every chunk is stored as a single file in a flat directory. Files are first written under a
+//! temporary name and then renamed, so a crash never leaves partially written chunks behind. On
+//! startup the directory is scanned and the existing chunks are reused, ordered by their
+//! modification time.
+use bytes::Bytes;
+use metric::U64Counter;
+use object_store::path::Path;
+use observability_deps::tracing::{info, warn};
+use parking_lot::Mutex;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    time::SystemTime,
+};
+use uuid::Uuid;
+
+use super::DiskTierConfig;
+
+/// Suffix of files holding a chunk.
+const CHUNK_SUFFIX: &str = ".chunk";
+
+/// Suffix of files that are still being written.
+const TMP_SUFFIX: &str = ".tmp";
+
+/// Local-disk tier with a size cap and LRU eviction.
+#[derive(Debug)]
+pub(super) struct DiskTier {
+    root: PathBuf,
+    max_bytes: usize,
+    state: Mutex<State>,
+    hits: U64Counter,
+    misses: U64Counter,
+}
+
+impl DiskTier {
+    /// Set up disk tier, reusing chunks that are already present in the configured directory.
+    pub(super) fn new(
+        config: DiskTierConfig,
+        metric_registry: &metric::Registry,
+    ) -> std::io::Result<Self> {
+        let DiskTierConfig {
+            path: root,
+            max_bytes,
+        } = config;
+
+        std::fs::create_dir_all(&root)?;
+
+        let mut found = vec![];
+        for entry in std::fs::read_dir(&root)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+
+            let name = match entry.file_name().into_string() {
+                Ok(name) => name,
+                Err(_) => continue,
+            };
+            if name.ends_with(TMP_SUFFIX) {
+                // leftover of an interrupted write
+                std::fs::remove_file(entry.path())?;
+            } else if name.ends_with(CHUNK_SUFFIX) {
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                found.push((modified, name, metadata.len() as usize));
+            }
+        }
+
+        // oldest first, so they are evicted first
+        found.sort();
+
+        let mut state = State::default();
+        for (_modified, name, size) in found {
+            state.insert(name, size);
+        }
+
+        // the size cap might have been lowered since the last run
+        let evicted = state.make_room(0, max_bytes);
+        for name in &evicted {
+            std::fs::remove_file(root.join(name))?;
+        }
+
+        info!(
+            path=%root.display(),
+            n_chunks=state.entries.len(),
+            used_bytes=state.used_bytes,
+            n_evicted=evicted.len(),
+            "object store disk cache ready",
+        );
+
+        let accesses = metric_registry.register_metric::<U64Counter>(
+            "cache_object_store_disk_access",
+            "Number of lookups in the local-disk tier of the object store cache",
+        );
+        let hits = accesses.recorder(&[("status", "hit")]);
+        let misses = accesses.recorder(&[("status", "miss")]);
+
+        Ok(Self {
+            root,
+            max_bytes,
+            state: Mutex::new(state),
+            hits,
+            misses,
+        })
+    }
+
+    /// Get chunk from disk.
+    ///
+    /// Returns `None` if the chunk is not stored or cannot be read.
+    pub(super) async fn get(&self, path: &Path, index: usize) -> Option<Bytes> {
+        let name = file_name(path, index);
+
+        if !self.state.lock().touch(&name) {
+            self.misses.inc(1);
+            return None;
+        }
+
+        let file = self.root.join(&name);
+        match tokio::task::spawn_blocking(move || std::fs::read(file))
+            .await
+            .expect("disk read task panicked")
+        {
+            Ok(data) => {
+                self.hits.inc(1);
+                Some(Bytes::from(data))
+            }
+            Err(e) => {
+                warn!(%e, %name, "cannot read chunk from object store disk cache");
+                self.state.lock().remove(&name);
+                self.misses.inc(1);
+                None
+            }
+        }
+    }
+
+    /// Store chunk on disk, evicting the least recently used chunks if required.
+    ///
+    /// Errors are logged and otherwise ignored, the disk tier is only an optimization.
+    pub(super) async fn put(&self, path: &Path, index: usize, data: Bytes) {
+        let size = data.len();
+        if size > self.max_bytes {
+            return;
+        }
+
+        let name = file_name(path, index);
+
+        // reserve space
+        let evicted = {
+            let mut state = self.state.lock();
+            if state.entries.contains_key(&name) {
+                return;
+            }
+            let evicted = state.make_room(size, self.max_bytes);
+            state.used_bytes += size;
+            evicted
+        };
+
+        let root = self.root.clone();
+        let name_captured = name.clone();
+        let res = tokio::task::spawn_blocking(move || {
+            for name in evicted {
+                if let Err(e) = std::fs::remove_file(root.join(&name)) {
+                    warn!(%e, %name, "cannot remove chunk from object store disk cache");
+                }
+            }
+
+            let tmp = root.join(format!("{}{}", Uuid::new_v4(), TMP_SUFFIX));
+            std::fs::write(&tmp, &data)?;
+            std::fs::rename(&tmp, root.join(&name_captured))
+        })
+        .await
+        .expect("disk write task panicked");
+
+        let mut state = self.state.lock();
+        state.used_bytes -= size;
+        match res {
+            Ok(()) => {
+                // a concurrent write of the same chunk may have won the race
+                if !state.entries.contains_key(&name) {
+                    state.insert(name, size);
+                }
+            }
+            Err(e) => {
+                warn!(%e, %name, "cannot write chunk to object store disk cache");
+            }
+        }
+    }
+
+    /// Remove chunk from disk, if it is stored.
+    pub(super) fn remove(&self, path: &Path, index: usize) {
+        let name = file_name(path, index);
+        if !self.state.lock().remove(&name) {
+            return;
+        }
+
+        if let Err(e) = std::fs::remove_file(self.root.join(&name)) {
+            warn!(%e, %name, "cannot remove chunk from object store disk cache");
+        }
+    }
+
+    /// Number of bytes currently stored on disk.
+    #[cfg(test)]
+    fn used_bytes(&self) -> usize {
+        self.state.lock().used_bytes
+    }
+}
+
+/// Bookkeeping of the chunks stored on disk.
+#[derive(Debug, Default)]
+struct State {
+    /// Size and last usage of every chunk, keyed by file name.
+    entries: HashMap<String, Entry>,
+
+    /// File names ordered by last usage.
+    lru: BTreeMap<u64, String>,
+
+    /// Logical clock used for the LRU order.
+    clock: u64,
+
+    /// Sum of all chunk sizes, including space reserved for running writes.
+    used_bytes: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    size: usize,
+    last_used: u64,
+}
+
+impl State {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn insert(&mut self, name: String, size: usize) {
+        let last_used = self.tick();
+        self.lru.insert(last_used, name.clone());
+        self.entries.insert(name, Entry { size, last_used });
+        self.used_bytes += size;
+    }
+
+    /// Remove entry. Returns `false` if the entry does not exist.
+    fn remove(&mut self, name: &str) -> bool {
+        match self.entries.remove(name) {
+            Some(entry) => {
+                self.lru.remove(&entry.last_used);
+                self.used_bytes -= entry.size;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Mark entry as used. Returns `false` if the entry does not exist.
+    fn touch(&mut self, name: &str) -> bool {
+        let last_used = self.tick();
+        match self.entries.get_mut(name) {
+            Some(entry) => {
+                self.lru.remove(&entry.last_used);
+                self.lru.insert(last_used, name.to_owned());
+                entry.last_used = last_used;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove least recently used entries until `additional` bytes fit into `max_bytes`.
+    ///
+    /// Returns the file names of the removed entries.
+    fn make_room(&mut self, additional: usize, max_bytes: usize) -> Vec<String> {
+        let mut evicted = vec![];
+        while self.used_bytes + additional > max_bytes {
+            let name = match self.lru.values().next() {
+                Some(name) => name.clone(),
+                None => break,
+            };
+            self.remove(&name);
+            evicted.push(name);
+        }
+        evicted
+    }
+}
+
+/// File name of the given chunk.
+///
+/// The object path is escaped so that it forms a single file name.
+fn file_name(path: &Path, index: usize) -> String {
+    let escaped = path.as_ref().replace('%', "%25").replace('/', "%2F");
+    format!("{}.{}{}", escaped, index, CHUNK_SUFFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_tier(root: &std::path::Path, max_bytes: usize) -> DiskTier {
+        DiskTier::new(
+            DiskTierConfig {
+                path: root.to_owned(),
+                max_bytes,
+            },
+            &metric::Registry::new(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(file_name(&Path::from("a/b%c"), 3), "a%2Fb%25c.3.chunk");
+    }
+
+    #[tokio::test]
+    async fn test_get_put() {
+        let dir = tempfile::tempdir().unwrap();
+        let tier = make_tier(dir.path(), 100);
+        let path = Path::from("a/b");
+
+        assert_eq!(tier.get(&path, 0).await, None);
+
+        tier.put(&path, 0, Bytes::from_static(b"foo")).await;
+        assert_eq!(tier.get(&path, 0).await, Some(Bytes::from_static(b"foo")));
+        assert_eq!(tier.get(&path, 1).await, None);
+        assert_eq!(tier.used_bytes(), 3);
+
+        tier.remove(&path, 0);
+        assert_eq!(tier.get(&path, 0).await, None);
+        assert_eq!(tier.used_bytes(), 0);
+        assert!(!dir.path().join(file_name(&path, 0)).exists());
+    }
+
+    #[tokio::test]
+    async fn test_eviction() {
+        let dir = tempfile::tempdir().unwrap();
+        let tier = make_tier(dir.path(), 10);
+        let path = Path::from("a");
+
+        tier.put(&path, 0, Bytes::from_static(b"0000")).await;
+        tier.put(&path, 1, Bytes::from_static(b"1111")).await;
+
+        // use chunk 0, so chunk 1 is the least recently used one
+        assert!(tier.get(&path, 0).await.is_some());
+
+        tier.put(&path, 2, Bytes::from_static(b"2222")).await;
+        assert!(tier.get(&path, 0).await.is_some());
+        assert_eq!(tier.get(&path, 1).await, None);
+        assert!(tier.get(&path, 2).await.is_some());
+        assert_eq!(tier.used_bytes(), 8);
+        assert!(!dir.path().join(file_name(&path, 1)).exists());
+
+        // too large
+        tier.put(&path, 3, Bytes::from(vec![0; 11])).await;
+        assert_eq!(tier.get(&path, 3).await, None);
+        assert_eq!(tier.used_bytes(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_recovery() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = Path::from("a");
+
+        let tier = make_tier(dir.path(), 100);
+        tier.put(&path, 0, Bytes::from_static(b"0000")).await;
+        tier.put(&path, 1, Bytes::from_static(b"1111")).await;
+        drop(tier);
+
+        // leftover of an interrupted write
+        let tmp = dir.path().join(format!("foo{}", TMP_SUFFIX));
+        std::fs::write(&tmp, b"xxx").unwrap();
+
+        let tier = make_tier(dir.path(), 100);
+        assert_eq!(tier.used_bytes(), 8);
+        assert_eq!(tier.get(&path, 1).await, Some(Bytes::from_static(b"1111")));
+        assert!(!tmp.exists());
+
+        // lowered size cap
+        drop(tier);
+        let tier = make_tier(dir.path(), 4);
+        assert_eq!(tier.used_bytes(), 4);
+    }
+}
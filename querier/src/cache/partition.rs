@@ -1,5 +1,6 @@
 //! Partition cache.
 
+use async_trait::async_trait;
 use backoff::{Backoff, BackoffConfig};
 use cache_system::{
     backend::policy::{
@@ -8,20 +9,33 @@ use cache_system::{
         PolicyBackend,
     },
     cache::{driver::CacheDriver, metrics::CacheWithMetrics, Cache},
-    loader::{metrics::MetricsLoader, FunctionLoader},
+    loader::{
+        batch::{BatchLoader, BatchLoaderAdapter},
+        metrics::MetricsLoader,
+    },
     resource_consumption::FunctionEstimator,
 };
 use data_types::{PartitionId, ShardId};
 use iox_catalog::interface::Catalog;
 use iox_time::TimeProvider;
 use schema::sort::SortKey;
-use std::{collections::HashMap, mem::size_of_val, sync::Arc};
+use std::{collections::HashMap, mem::size_of_val, sync::Arc, time::Duration};
+use tokio::runtime::Handle;
 use trace::span::Span;
 
 use super::ram::RamSize;
 
 const CACHE_ID: &str = "partition";
 
+/// Time to collect partition misses before they are loaded with a single catalog query.
+///
+/// Queries usually touch many partitions at once, so after a cold start this turns a storm of single-row catalog
+/// queries into a few bulk ones.
+pub const BATCH_WINDOW: Duration = Duration::from_millis(5);
+
+/// Maximum number of partitions loaded by a single catalog query.
+pub const MAX_BATCH_SIZE: usize = 1_000;
+
 type CacheT = Box<
     dyn Cache<
         K = PartitionId,
@@ -46,32 +60,18 @@ impl PartitionCache {
         time_provider: Arc<dyn TimeProvider>,
         metric_registry: &metric::Registry,
         ram_pool: Arc<ResourcePool<RamSize>>,
+        handle: &Handle,
         testing: bool,
     ) -> Self {
-        let loader = FunctionLoader::new(move |partition_id: PartitionId, _extra: ()| {
-            let catalog = Arc::clone(&catalog);
-            let backoff_config = backoff_config.clone();
-
-            async move {
-                let partition = Backoff::new(&backoff_config)
-                    .retry_all_errors("get partition_key", || async {
-                        catalog
-                            .repositories()
-                            .await
-                            .partitions()
-                            .get_by_id(partition_id)
-                            .await
-                    })
-                    .await
-                    .expect("retry forever")
-                    .expect("partition gone from catalog?!");
-
-                CachedPartition {
-                    shard_id: partition.shard_id,
-                    sort_key: Arc::new(partition.sort_key()),
-                }
-            }
-        });
+        let loader = BatchLoaderAdapter::new(
+            PartitionLoader {
+                catalog,
+                backoff_config,
+            },
+            BATCH_WINDOW,
+            MAX_BATCH_SIZE,
+            handle,
+        );
         let loader = Arc::new(MetricsLoader::new(
             loader,
             CACHE_ID,
@@ -92,7 +92,7 @@ impl PartitionCache {
             })),
         ));
 
-        let cache = CacheDriver::new(loader, backend);
+        let cache = CacheDriver::new_with_metrics(loader, backend, CACHE_ID, metric_registry);
         let cache = Box::new(CacheWithMetrics::new(
             cache,
             CACHE_ID,
@@ -134,6 +134,56 @@ impl PartitionCache {
     }
 }
 
+/// Loads partitions from the catalog, many at a time.
+#[derive(Debug)]
+struct PartitionLoader {
+    catalog: Arc<dyn Catalog>,
+    backoff_config: BackoffConfig,
+}
+
+#[async_trait]
+impl BatchLoader for PartitionLoader {
+    type K = PartitionId;
+    type V = CachedPartition;
+    type Extra = ();
+
+    async fn load_batch(&self, keys: Vec<(Self::K, Self::Extra)>) -> Vec<Self::V> {
+        let partition_ids: Vec<_> = keys
+            .iter()
+            .map(|(partition_id, _extra)| *partition_id)
+            .collect();
+
+        let partitions: HashMap<_, _> = Backoff::new(&self.backoff_config)
+            .retry_all_errors("get partitions", || async {
+                self.catalog
+                    .repositories()
+                    .await
+                    .partitions()
+                    .get_by_ids(&partition_ids)
+                    .await
+            })
+            .await
+            .expect("retry forever")
+            .into_iter()
+            .map(|p| (p.id, p))
+            .collect();
+
+        partition_ids
+            .iter()
+            .map(|partition_id| {
+                let partition = partitions
+                    .get(partition_id)
+                    .expect("partition gone from catalog?!");
+
+                CachedPartition {
+                    shard_id: partition.shard_id,
+                    sort_key: Arc::new(partition.sort_key()),
+                }
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug, Clone)]
 struct CachedPartition {
     shard_id: ShardId,
@@ -187,20 +237,21 @@ mod tests {
             catalog.time_provider(),
             &catalog.metric_registry(),
             test_ram_pool(),
+            &Handle::current(),
             true,
         );
 
         let id1 = cache.shard_id(p1.id, None).await;
         assert_eq!(id1, s1.shard.id);
-        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_id", 1);
+        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_ids", 1);
 
         let id2 = cache.shard_id(p2.id, None).await;
         assert_eq!(id2, s2.shard.id);
-        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_id", 2);
+        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_ids", 2);
 
         let id1 = cache.shard_id(p1.id, None).await;
         assert_eq!(id1, s1.shard.id);
-        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_id", 2);
+        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_ids", 2);
     }
 
     #[tokio::test]
@@ -230,20 +281,21 @@ mod tests {
             catalog.time_provider(),
             &catalog.metric_registry(),
             test_ram_pool(),
+            &Handle::current(),
             true,
         );
 
         let sort_key1 = cache.sort_key(p1.id, &Vec::new(), None).await;
         assert_eq!(sort_key1.as_ref(), &p1.sort_key());
-        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_id", 1);
+        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_ids", 1);
 
         let sort_key2 = cache.sort_key(p2.id, &Vec::new(), None).await;
         assert_eq!(sort_key2.as_ref(), &p2.sort_key());
-        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_id", 2);
+        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_ids", 2);
 
         let sort_key1 = cache.sort_key(p1.id, &Vec::new(), None).await;
         assert_eq!(sort_key1.as_ref(), &p1.sort_key());
-        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_id", 2);
+        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_ids", 2);
     }
 
     #[tokio::test]
@@ -279,20 +331,59 @@ mod tests {
             catalog.time_provider(),
             &catalog.metric_registry(),
             test_ram_pool(),
+            &Handle::current(),
             true,
         );
 
         cache.shard_id(p2.id, None).await;
         cache.sort_key(p3.id, &Vec::new(), None).await;
-        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_id", 2);
+        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_ids", 2);
 
         cache.shard_id(p1.id, None).await;
         cache.sort_key(p2.id, &Vec::new(), None).await;
-        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_id", 3);
+        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_ids", 3);
 
         cache.sort_key(p1.id, &Vec::new(), None).await;
         cache.shard_id(p2.id, None).await;
-        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_id", 3);
+        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_ids", 3);
+    }
+
+    #[tokio::test]
+    async fn test_batching() {
+        let catalog = TestCatalog::new();
+
+        let ns = catalog.create_namespace("ns").await;
+        let t = ns.create_table("table").await;
+        let s1 = ns.create_shard(1).await;
+        let s2 = ns.create_shard(2).await;
+        let p1 = t
+            .with_shard(&s1)
+            .create_partition("k1")
+            .await
+            .partition
+            .clone();
+        let p2 = t
+            .with_shard(&s2)
+            .create_partition("k2")
+            .await
+            .partition
+            .clone();
+
+        let cache = PartitionCache::new(
+            catalog.catalog(),
+            BackoffConfig::default(),
+            catalog.time_provider(),
+            &catalog.metric_registry(),
+            test_ram_pool(),
+            &Handle::current(),
+            true,
+        );
+
+        // concurrent misses are loaded by a single catalog query
+        let (id1, id2) = tokio::join!(cache.shard_id(p1.id, None), cache.shard_id(p2.id, None));
+        assert_eq!(id1, s1.shard.id);
+        assert_eq!(id2, s2.shard.id);
+        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_ids", 1);
     }
 
     #[tokio::test]
@@ -312,23 +403,24 @@ mod tests {
             catalog.time_provider(),
             &catalog.metric_registry(),
             test_ram_pool(),
+            &Handle::current(),
             true,
         );
 
         let sort_key = cache.sort_key(p_id, &Vec::new(), None).await;
         assert_eq!(sort_key.as_ref(), &p_sort_key);
-        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_id", 1);
+        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_ids", 1);
 
         // requesting nother will not expire
         assert!(p_sort_key.is_none());
         let sort_key = cache.sort_key(p_id, &Vec::new(), None).await;
         assert_eq!(sort_key.as_ref(), &p_sort_key);
-        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_id", 1);
+        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_ids", 1);
 
         // but requesting something will expire
         let sort_key = cache.sort_key(p_id, &["foo"], None).await;
         assert_eq!(sort_key.as_ref(), &p_sort_key);
-        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_id", 2);
+        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_ids", 2);
 
         // set sort key
         let p = p
@@ -339,18 +431,18 @@ mod tests {
         let p_sort_key = p.partition.sort_key();
         let sort_key = cache.sort_key(p_id, &["foo"], None).await;
         assert_eq!(sort_key.as_ref(), &p_sort_key);
-        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_id", 3);
+        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_ids", 3);
 
         // subsets and the full key don't expire
         for should_cover in [Vec::new(), vec!["foo"], vec!["bar"], vec!["foo", "bar"]] {
             let sort_key = cache.sort_key(p_id, &should_cover, None).await;
             assert_eq!(sort_key.as_ref(), &p_sort_key);
-            assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_id", 3);
+            assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_ids", 3);
         }
 
         // unknown columns expire
         let sort_key = cache.sort_key(p_id, &["foo", "x"], None).await;
         assert_eq!(sort_key.as_ref(), &p_sort_key);
-        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_id", 4);
+        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_ids", 4);
     }
 }
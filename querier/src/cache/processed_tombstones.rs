@@ -50,6 +50,7 @@ impl ProcessedTombstonesCache {
         time_provider: Arc<dyn TimeProvider>,
         metric_registry: &metric::Registry,
         ram_pool: Arc<ResourcePool<RamSize>>,
+        ttl_jitter: f64,
         testing: bool,
     ) -> Self {
         let loader = FunctionLoader::new(move |(parquet_file_id, tombstone_id), _extra: ()| {
@@ -79,8 +80,9 @@ impl ProcessedTombstonesCache {
         ));
 
         let mut backend = PolicyBackend::new(Box::new(HashMap::new()), Arc::clone(&time_provider));
-        backend.add_policy(TtlPolicy::new(
+        backend.add_policy(TtlPolicy::new_with_jitter(
             Arc::new(KeepExistsForever {}),
+            ttl_jitter,
             CACHE_ID,
             metric_registry,
         ));
@@ -92,7 +94,7 @@ impl ProcessedTombstonesCache {
             })),
         ));
 
-        let cache = CacheDriver::new(loader, backend);
+        let cache = CacheDriver::new_with_metrics(loader, backend, CACHE_ID, metric_registry);
         let cache = Box::new(CacheWithMetrics::new(
             cache,
             CACHE_ID,
@@ -174,6 +176,7 @@ mod tests {
             catalog.time_provider(),
             &catalog.metric_registry(),
             test_ram_pool(),
+            0.0,
             true,
         );
 
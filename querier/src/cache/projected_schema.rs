@@ -108,7 +108,7 @@ impl ProjectedSchemaCache {
             })),
         ));
 
-        let cache = CacheDriver::new(loader, backend);
+        let cache = CacheDriver::new_with_metrics(loader, backend, CACHE_ID, metric_registry);
         let cache = Box::new(CacheWithMetrics::new(
             cache,
             CACHE_ID,
@@ -1,6 +1,7 @@
 //! Cache Parquet file data in Read Buffer chunks.
 
 use super::ram::RamSize;
+use crate::chunk::projection::ProjectionMetrics;
 use backoff::{Backoff, BackoffConfig};
 use cache_system::{
     backend::policy::{
@@ -56,10 +57,13 @@ impl ReadBufferCache {
         testing: bool,
     ) -> Self {
         let metric_registry_captured = Arc::clone(&metric_registry);
+        // Loading decodes all columns of the file, regardless of the query that triggered it.
+        let projection_metrics = Arc::new(ProjectionMetrics::new(&metric_registry));
         let loader =
             FunctionLoader::new(move |_parquet_file_id, extra_fetch_info: ExtraFetchInfo| {
                 let backoff_config = backoff_config.clone();
                 let metric_registry = Arc::clone(&metric_registry_captured);
+                let projection_metrics = Arc::clone(&projection_metrics);
 
                 async move {
                     let rb_chunk = Backoff::new(&backoff_config)
@@ -79,6 +83,7 @@ impl ReadBufferCache {
                         })
                         .await
                         .expect("retry forever");
+                    projection_metrics.record_read_buffer_load(extra_fetch_info.schema.len());
 
                     Arc::new(rb_chunk)
                 }
@@ -105,7 +110,7 @@ impl ReadBufferCache {
             )),
         ));
 
-        let cache = CacheDriver::new(loader, backend);
+        let cache = CacheDriver::new_with_metrics(loader, backend, CACHE_ID, &metric_registry);
         let cache = Box::new(CacheWithMetrics::new(
             cache,
             CACHE_ID,
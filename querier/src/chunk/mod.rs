@@ -3,12 +3,13 @@
 use crate::cache::namespace::CachedTable;
 use crate::cache::CatalogCache;
 use data_types::{
-    ChunkId, ChunkOrder, CompactionLevel, DeletePredicate, ParquetFile, ParquetFileId, PartitionId,
-    SequenceNumber, ShardId, TableSummary, TimestampMinMax,
+    ChunkId, ChunkOrder, ColumnStats, CompactionLevel, DeletePredicate, ParquetFile, ParquetFileId,
+    PartitionId, SequenceNumber, ShardId, TableSummary, TimestampMinMax,
 };
 use iox_catalog::interface::Catalog;
+use observability_deps::tracing::warn;
 use parking_lot::RwLock;
-use parquet_file::{chunk::ParquetChunk, storage::ParquetStorage};
+use parquet_file::{chunk::ParquetChunk, storage::ParquetStorage, ParquetFilePath};
 use read_buffer::RBChunk;
 use schema::{sort::SortKey, Schema};
 use std::collections::HashSet;
@@ -16,8 +17,12 @@ use std::{collections::HashMap, sync::Arc};
 use trace::span::{Span, SpanRecorder};
 use uuid::Uuid;
 
-use self::util::create_basic_summary;
+use self::{
+    projection::ProjectionMetrics,
+    util::{add_column_stats, column_stats_by_name, create_basic_summary},
+};
 
+pub(crate) mod projection;
 mod query_access;
 pub(crate) mod util;
 
@@ -50,6 +55,9 @@ pub struct ChunkMeta {
 
     /// Compaction level of the parquet file of the chunk
     compaction_level: CompactionLevel,
+
+    /// Statistics of the columns of the parquet file, as recorded in the catalog.
+    column_stats: HashMap<Arc<str>, ColumnStats>,
 }
 
 impl ChunkMeta {
@@ -161,16 +169,21 @@ impl ChunkStage {
     }
 }
 
-impl From<Arc<ParquetChunk>> for ChunkStage {
-    fn from(parquet_chunk: Arc<ParquetChunk>) -> Self {
-        let table_summary = Arc::new(create_basic_summary(
+impl ChunkStage {
+    /// Parquet stage, using the column statistics of the catalog for the table summary.
+    fn new_parquet(
+        parquet_chunk: Arc<ParquetChunk>,
+        column_stats: &HashMap<Arc<str>, ColumnStats>,
+    ) -> Self {
+        let mut table_summary = create_basic_summary(
             parquet_chunk.rows() as u64,
             &parquet_chunk.schema(),
             parquet_chunk.timestamp_min_max(),
-        ));
+        );
+        add_column_stats(&mut table_summary, column_stats);
         Self::Parquet {
             parquet_chunk,
-            table_summary,
+            table_summary: Arc::new(table_summary),
         }
     }
 }
@@ -238,6 +251,12 @@ pub struct QuerierChunk {
 
     /// Load setting.
     load_setting: QuerierChunkLoadSetting,
+
+    /// Metrics to audit projection pushdown.
+    projection_metrics: Arc<ProjectionMetrics>,
+
+    /// Statistics of the row groups of the parquet file, if loaded.
+    row_group_statistics: Option<Arc<Vec<TableSummary>>>,
 }
 
 impl QuerierChunk {
@@ -249,6 +268,7 @@ impl QuerierChunk {
         catalog_cache: Arc<CatalogCache>,
         store: ParquetStorage,
         load_setting: QuerierChunkLoadSetting,
+        projection_metrics: Arc<ProjectionMetrics>,
         span: Option<Span>,
     ) -> Self {
         let span_recorder = SpanRecorder::new(span);
@@ -290,7 +310,7 @@ impl QuerierChunk {
         let stage: ChunkStage = if let Some(rb_chunk) = rb_chunk {
             rb_chunk.into()
         } else {
-            parquet_chunk.into()
+            ChunkStage::new_parquet(parquet_chunk, &meta.column_stats)
         };
 
         Self {
@@ -303,6 +323,8 @@ impl QuerierChunk {
             stage: Arc::new(RwLock::new(stage)),
             store,
             load_setting,
+            projection_metrics,
+            row_group_statistics: None,
         }
     }
 
@@ -314,6 +336,17 @@ impl QuerierChunk {
         }
     }
 
+    /// Set the statistics of the row groups of the parquet file.
+    pub fn with_row_group_statistics(
+        self,
+        row_group_statistics: Option<Arc<Vec<TableSummary>>>,
+    ) -> Self {
+        Self {
+            row_group_statistics,
+            ..self
+        }
+    }
+
     /// Get metadata attached to the given chunk.
     pub fn meta(&self) -> &ChunkMeta {
         self.meta.as_ref()
@@ -353,6 +386,12 @@ pub struct ChunkAdapter {
 
     /// Load settings for chunks
     load_settings: HashMap<ParquetFileId, QuerierChunkLoadSetting>,
+
+    /// Metrics to audit projection pushdown, shared by all chunks.
+    projection_metrics: Arc<ProjectionMetrics>,
+
+    /// Load the row group statistics of the parquet files of new chunks.
+    load_row_group_statistics: bool,
 }
 
 impl ChunkAdapter {
@@ -363,11 +402,28 @@ impl ChunkAdapter {
         metric_registry: Arc<metric::Registry>,
         load_settings: HashMap<ParquetFileId, QuerierChunkLoadSetting>,
     ) -> Self {
+        let projection_metrics = Arc::new(ProjectionMetrics::new(&metric_registry));
+
         Self {
             catalog_cache,
             store,
             metric_registry,
             load_settings,
+            projection_metrics,
+            load_row_group_statistics: false,
+        }
+    }
+
+    /// Load the row group statistics of the parquet files of new chunks, so that aggregations can
+    /// be answered from them (see [`AggregatePushdown`]).
+    ///
+    /// This fetches the footer of every parquet file that is queried.
+    ///
+    /// [`AggregatePushdown`]: iox_query::physical_optimizer::AggregatePushdown
+    pub fn with_row_group_statistics(self, load_row_group_statistics: bool) -> Self {
+        Self {
+            load_row_group_statistics,
+            ..self
         }
     }
 
@@ -403,6 +459,12 @@ impl ChunkAdapter {
             )
             .await?;
 
+        let row_group_statistics = if self.load_row_group_statistics {
+            self.row_group_statistics(&parquet_file).await
+        } else {
+            None
+        };
+
         let parquet_chunk = Arc::new(ParquetChunk::new(
             parquet_file,
             parts.schema,
@@ -422,12 +484,37 @@ impl ChunkAdapter {
                 Arc::clone(&self.catalog_cache),
                 self.store.clone(),
                 load_settings,
+                Arc::clone(&self.projection_metrics),
                 span_recorder.child_span("QuerierChunk::new"),
             )
-            .await,
+            .await
+            .with_row_group_statistics(row_group_statistics),
         )
     }
 
+    /// Read the row group statistics of `parquet_file`. Failures are logged, the chunk is then
+    /// scanned as usual.
+    async fn row_group_statistics(
+        &self,
+        parquet_file: &ParquetFile,
+    ) -> Option<Arc<Vec<TableSummary>>> {
+        match self
+            .store
+            .read_row_group_statistics(&ParquetFilePath::from(parquet_file))
+            .await
+        {
+            Ok(statistics) => Some(Arc::new(statistics)),
+            Err(e) => {
+                warn!(
+                    %e,
+                    parquet_file_id=%parquet_file.id,
+                    "cannot read row group statistics",
+                );
+                None
+            }
+        }
+    }
+
     async fn chunk_parts(
         &self,
         cached_table: &CachedTable,
@@ -514,6 +601,8 @@ impl ChunkAdapter {
 
         let order = ChunkOrder::new(parquet_file.max_sequence_number.get());
 
+        let column_stats = column_stats_by_name(&parquet_file, &cached_table.column_id_map);
+
         let meta = Arc::new(ChunkMeta {
             parquet_file_id: parquet_file.id,
             chunk_id,
@@ -524,6 +613,7 @@ impl ChunkAdapter {
             partition_id: parquet_file.partition_id,
             max_sequence_number: parquet_file.max_sequence_number,
             compaction_level: parquet_file.compaction_level,
+            column_stats,
         });
 
         Some(ChunkParts {
@@ -547,11 +637,11 @@ pub mod tests {
     use super::*;
     use arrow::{datatypes::DataType, record_batch::RecordBatch};
     use arrow_util::assert_batches_eq;
-    use data_types::{ColumnType, NamespaceSchema};
+    use data_types::{ColumnType, NamespaceSchema, StatValues, Statistics};
     use futures::StreamExt;
     use iox_query::{exec::IOxSessionContext, QueryChunk, QueryChunkMeta};
     use iox_tests::util::{TestCatalog, TestNamespace, TestParquetFileBuilder};
-    use metric::{Attributes, Observation, RawReporter};
+    use metric::{Attributes, Metric, Observation, RawReporter, U64Counter, U64Histogram};
     use schema::{builder::SchemaBuilder, selection::Selection, sort::SortKeyBuilder};
     use test_helpers::maybe_start_logging;
     use tokio::runtime::Handle;
@@ -636,6 +726,28 @@ pub mod tests {
         assert_eq!(catalog_metrics1, catalog_metrics2);
     }
 
+    #[tokio::test]
+    async fn test_parquet_chunk_summary_uses_catalog_column_stats() {
+        maybe_start_logging();
+        let test_data = TestData::new(QuerierChunkLoadSetting::ParquetOnly).await;
+        let namespace_schema = Arc::new(test_data.ns.schema().await);
+        let chunk = test_data.chunk(namespace_schema).await;
+
+        // tag values are known without reading the parquet file, so the chunk can be pruned
+        let summary = chunk.summary().unwrap();
+        let tag1 = summary.column("tag1").unwrap();
+        assert_eq!(
+            tag1.stats,
+            Statistics::String(StatValues {
+                min: Some(String::from("UT")),
+                max: Some(String::from("WA")),
+                total_count: 3,
+                null_count: Some(0),
+                distinct_count: None,
+            })
+        );
+    }
+
     #[tokio::test]
     async fn test_new_on_demand_chunk() {
         maybe_start_logging();
@@ -676,13 +788,81 @@ pub mod tests {
         assert_eq!(catalog_metrics1, catalog_metrics2);
     }
 
+    #[tokio::test]
+    async fn test_projection_metrics() {
+        maybe_start_logging();
+        let test_data = TestData::new(QuerierChunkLoadSetting::ParquetOnly).await;
+        let namespace_schema = Arc::new(test_data.ns.schema().await);
+        let chunk = test_data.chunk(namespace_schema).await;
+
+        // a projected read only decodes the selected columns that exist in the file
+        collect_read_filter_with_selection(&chunk, Selection::Some(&["tag1", "tag2", "time"]))
+            .await;
+        assert_eq!(
+            test_data.histogram("querier_chunk_columns_requested", &[]),
+            (1, 3)
+        );
+        assert_eq!(
+            test_data.histogram("querier_chunk_columns_decoded", &[("reason", "read")]),
+            (1, 2)
+        );
+        assert_eq!(test_data.unprojected_reads(), 0);
+
+        // an unprojected read decodes the entire file
+        collect_read_filter(&chunk).await;
+        assert_eq!(
+            test_data.histogram("querier_chunk_columns_requested", &[]),
+            (2, 6)
+        );
+        assert_eq!(
+            test_data.histogram("querier_chunk_columns_decoded", &[("reason", "read")]),
+            (2, 5)
+        );
+        assert_eq!(test_data.unprojected_reads(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_projection_metrics_read_buffer_load() {
+        maybe_start_logging();
+        let test_data = TestData::new(QuerierChunkLoadSetting::OnDemand).await;
+        let namespace_schema = Arc::new(test_data.ns.schema().await);
+        let chunk = test_data.chunk(namespace_schema).await;
+
+        // loading the read buffer decodes the entire file, even for a projected read
+        collect_read_filter_with_selection(&chunk, Selection::Some(&["time"])).await;
+        assert_eq!(chunk.chunk_type(), "read_buffer");
+        assert_eq!(
+            test_data.histogram("querier_chunk_columns_requested", &[]),
+            (1, 1)
+        );
+        assert_eq!(
+            test_data.histogram(
+                "querier_chunk_columns_decoded",
+                &[("reason", "read_buffer_load")]
+            ),
+            (1, 3)
+        );
+        assert_eq!(
+            test_data.histogram("querier_chunk_columns_decoded", &[("reason", "read")]),
+            (0, 0)
+        );
+    }
+
     /// collect data for the given chunk
     async fn collect_read_filter(chunk: &dyn QueryChunk) -> Vec<RecordBatch> {
+        collect_read_filter_with_selection(chunk, Selection::All).await
+    }
+
+    /// collect data for the given chunk and selection
+    async fn collect_read_filter_with_selection(
+        chunk: &dyn QueryChunk,
+        selection: Selection<'_>,
+    ) -> Vec<RecordBatch> {
         chunk
             .read_filter(
                 IOxSessionContext::with_testing(),
                 &Default::default(),
-                Selection::All,
+                selection,
             )
             .unwrap()
             .collect::<Vec<_>>()
@@ -732,6 +912,7 @@ pub mod tests {
                     catalog.catalog(),
                     catalog.time_provider(),
                     catalog.metric_registry(),
+                    catalog.object_store(),
                     &Handle::current(),
                 )),
                 ParquetStorage::new(catalog.object_store()),
@@ -761,6 +942,34 @@ pub mod tests {
                 .unwrap()
         }
 
+        /// get sample count and sum of the given histogram
+        fn histogram<const N: usize>(
+            &self,
+            name: &'static str,
+            attributes: &[(&'static str, &'static str); N],
+        ) -> (u64, u64) {
+            let observation = self
+                .catalog
+                .metric_registry
+                .get_instrument::<Metric<U64Histogram>>(name)
+                .expect("failed to read metric")
+                .get_observer(&Attributes::from(attributes))
+                .expect("failed to get observer")
+                .fetch();
+            (observation.sample_count(), observation.total)
+        }
+
+        /// get number of unprojected chunk reads
+        fn unprojected_reads(&self) -> u64 {
+            self.catalog
+                .metric_registry
+                .get_instrument::<Metric<U64Counter>>("querier_chunk_unprojected_reads")
+                .expect("failed to read metric")
+                .get_observer(&Attributes::from(&[]))
+                .expect("failed to get observer")
+                .fetch()
+        }
+
         /// get catalog access metrics from metric registry
         fn get_catalog_access_metrics(&self) -> Vec<(Attributes, u64)> {
             let mut reporter = RawReporter::default();
@@ -0,0 +1,85 @@
+//! Metrics to audit projection pushdown.
+//!
+//! Decoding parquet data is the dominating cost of most queries, so every chunk read should only
+//! decode the columns the query actually needs. These metrics compare the columns requested by the
+//! query plan with the columns decoded from parquet, so that regressions (e.g. a hot path that
+//! reads the entire file) show up in production.
+use metric::{Attributes, U64Counter, U64Histogram, U64HistogramOptions};
+
+/// Column-level metrics of chunk reads.
+#[derive(Debug, Clone)]
+pub struct ProjectionMetrics {
+    /// Number of columns requested per chunk read.
+    columns_requested: U64Histogram,
+
+    /// Number of columns decoded from parquet per chunk read.
+    columns_decoded_read: U64Histogram,
+
+    /// Number of columns decoded from parquet per read buffer load.
+    columns_decoded_read_buffer_load: U64Histogram,
+
+    /// Number of chunk reads without a projection.
+    unprojected_reads: U64Counter,
+}
+
+impl ProjectionMetrics {
+    /// Register metrics.
+    ///
+    /// This can be called multiple times for the same registry, the metrics are shared.
+    pub fn new(metric_registry: &metric::Registry) -> Self {
+        let columns_requested = metric_registry
+            .register_metric_with_options::<U64Histogram, _>(
+                "querier_chunk_columns_requested",
+                "Number of columns requested per chunk read",
+                buckets,
+            )
+            .recorder(&[]);
+
+        let columns_decoded = metric_registry.register_metric_with_options::<U64Histogram, _>(
+            "querier_chunk_columns_decoded",
+            "Number of columns decoded from parquet per chunk read or read buffer load",
+            buckets,
+        );
+        let columns_decoded_read =
+            columns_decoded.recorder(Attributes::from(&[("reason", "read")]));
+        let columns_decoded_read_buffer_load =
+            columns_decoded.recorder(Attributes::from(&[("reason", "read_buffer_load")]));
+
+        let unprojected_reads = metric_registry
+            .register_metric::<U64Counter>(
+                "querier_chunk_unprojected_reads",
+                "Number of chunk reads that did not restrict the columns to read",
+            )
+            .recorder(&[]);
+
+        Self {
+            columns_requested,
+            columns_decoded_read,
+            columns_decoded_read_buffer_load,
+            unprojected_reads,
+        }
+    }
+
+    /// Record a chunk read.
+    ///
+    /// `decoded` is `None` if the data was not decoded from parquet (e.g. because it was served
+    /// from the read buffer). `projected` is `false` if the read did not restrict the columns.
+    pub fn record_read(&self, requested: usize, decoded: Option<usize>, projected: bool) {
+        self.columns_requested.record(requested as u64);
+        if let Some(decoded) = decoded {
+            self.columns_decoded_read.record(decoded as u64);
+        }
+        if !projected {
+            self.unprojected_reads.inc(1);
+        }
+    }
+
+    /// Record that an entire parquet file with `decoded` columns was loaded into the read buffer.
+    pub fn record_read_buffer_load(&self, decoded: usize) {
+        self.columns_decoded_read_buffer_load.record(decoded as u64);
+    }
+}
+
+fn buckets() -> U64HistogramOptions {
+    U64HistogramOptions::new([1, 2, 4, 8, 16, 32, 64, 128, 256, u64::MAX])
+}
@@ -94,6 +94,10 @@ impl QueryChunkMeta for QuerierChunk {
     fn timestamp_min_max(&self) -> Option<TimestampMinMax> {
         Some(self.timestamp_min_max)
     }
+
+    fn row_group_statistics(&self) -> Option<Arc<Vec<TableSummary>>> {
+        self.row_group_statistics.clone()
+    }
 }
 
 impl QueryChunk for QuerierChunk {
@@ -254,6 +258,10 @@ impl QueryChunk for QuerierChunk {
         ctx.set_metadata("projection", format!("{}", selection));
 
         let output_schema = select_schema(selection, &self.schema.as_arrow());
+        let (columns_requested, projected) = match selection {
+            Selection::Some(cols) => (cols.len(), true),
+            Selection::All => (self.schema.len(), false),
+        };
 
         let load_setting = self.load_setting;
         let chunk_id = self.id();
@@ -263,6 +271,7 @@ impl QueryChunk for QuerierChunk {
         let store = self.store.clone();
         let schema = Arc::clone(&self.schema);
         let catalog_cache = Arc::clone(&self.catalog_cache);
+        let projection_metrics = Arc::clone(&self.projection_metrics);
         Ok(Box::pin(RecordBatchStreamAdapter::new(
             output_schema,
             futures::stream::once(async move {
@@ -295,9 +304,22 @@ impl QueryChunk for QuerierChunk {
                 let selection: HalfOwnedSelection<'_> = (&selection).into();
                 let selection: Selection<'_> = (&selection).into();
 
+                // Only parquet reads decode data, the read buffer is already decoded.
+                let columns_decoded = match &*stage {
+                    ChunkStage::Parquet { parquet_chunk, .. } => {
+                        parquet_chunk.column_names(selection).map(|cols| cols.len())
+                    }
+                    ChunkStage::ReadBuffer { .. } => None,
+                };
+                ctx.set_metadata("columns_requested", columns_requested as i64);
+                if let Some(columns_decoded) = columns_decoded {
+                    ctx.set_metadata("columns_decoded", columns_decoded as i64);
+                }
+                projection_metrics.record_read(columns_requested, columns_decoded, projected);
+
                 let stream_res: ArrowResult<SendableRecordBatchStream> = match &*stage {
                     ChunkStage::Parquet { parquet_chunk, .. } => Ok(parquet_chunk
-                        .read_filter(&pred_with_deleted_exprs, selection)
+                        .read_filter(&pred_with_deleted_exprs, &[], selection)
                         .context(ParquetFileChunkSnafu { chunk_id })?),
                     ChunkStage::ReadBuffer { rb_chunk, .. } => {
                         // Only apply pushdownable predicates
@@ -350,6 +372,11 @@ impl QueryChunk for QuerierChunk {
         self.meta().order()
     }
 
+    fn is_immutable(&self) -> bool {
+        // parquet files are never modified, only replaced by files with new IDs
+        true
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -1,7 +1,8 @@
 use data_types::{
-    ColumnSummary, InfluxDbType, StatValues, Statistics, TableSummary, TimestampMinMax,
+    ColumnStats, ColumnSummary, InfluxDbType, StatValues, Statistics, TableSummary, TimestampMinMax,
 };
 use schema::{InfluxColumnType, InfluxFieldType, Schema};
+use std::{collections::HashMap, sync::Arc};
 
 /// Create basic table summary.
 ///
@@ -82,6 +83,51 @@ pub fn create_basic_summary(
     TableSummary { columns }
 }
 
+/// Column statistics that the catalog records for a parquet file, keyed by column name.
+///
+/// Statistics of columns that are unknown to `column_id_map` are dropped.
+pub fn column_stats_by_name(
+    parquet_file: &ParquetFile,
+    column_id_map: &HashMap<ColumnId, Arc<str>>,
+) -> HashMap<Arc<str>, ColumnStats> {
+    parquet_file
+        .column_stats
+        .iter()
+        .filter_map(|stats| {
+            column_id_map
+                .get(&stats.column_id)
+                .map(|name| (Arc::clone(name), stats.clone()))
+        })
+        .collect()
+}
+
+/// Add the column statistics that the catalog records for a parquet file to a summary created by
+/// [`create_basic_summary`].
+///
+/// This adds the [null count](StatValues::null_count) of all columns and the
+/// [min](StatValues::min)/[max](StatValues::max) of tag and string field columns, so that the
+/// chunk can be pruned by tag values.
+pub fn add_column_stats(summary: &mut TableSummary, column_stats: &HashMap<Arc<str>, ColumnStats>) {
+    for column in &mut summary.columns {
+        let stats = match column_stats.get(column.name.as_str()) {
+            Some(stats) => stats,
+            None => continue,
+        };
+
+        match &mut column.stats {
+            Statistics::String(v) => {
+                v.min = stats.min.clone();
+                v.max = stats.max.clone();
+                v.null_count = stats.null_count;
+            }
+            Statistics::I64(v) => v.null_count = stats.null_count,
+            Statistics::U64(v) => v.null_count = stats.null_count,
+            Statistics::F64(v) => v.null_count = stats.null_count,
+            Statistics::Bool(v) => v.null_count = stats.null_count,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use schema::builder::SchemaBuilder;
@@ -190,6 +236,78 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_add_column_stats() {
+        let schema = SchemaBuilder::new()
+            .tag("tag")
+            .influx_field("field_float", InfluxFieldType::Float)
+            .timestamp()
+            .build()
+            .unwrap();
+        let mut summary = create_basic_summary(3, &schema, TimestampMinMax { min: 1, max: 2 });
+
+        let column_stats = HashMap::from([
+            (
+                Arc::from("tag"),
+                ColumnStats {
+                    column_id: ColumnId::new(1),
+                    null_count: Some(1),
+                    min: Some(String::from("a")),
+                    max: Some(String::from("b")),
+                },
+            ),
+            (
+                Arc::from("field_float"),
+                ColumnStats {
+                    column_id: ColumnId::new(2),
+                    null_count: Some(2),
+                    min: None,
+                    max: None,
+                },
+            ),
+        ]);
+        add_column_stats(&mut summary, &column_stats);
+
+        let expected = TableSummary {
+            columns: vec![
+                ColumnSummary {
+                    name: String::from("tag"),
+                    influxdb_type: Some(InfluxDbType::Tag),
+                    stats: Statistics::String(StatValues {
+                        min: Some(String::from("a")),
+                        max: Some(String::from("b")),
+                        total_count: 3,
+                        null_count: Some(1),
+                        distinct_count: None,
+                    }),
+                },
+                ColumnSummary {
+                    name: String::from("field_float"),
+                    influxdb_type: Some(InfluxDbType::Field),
+                    stats: Statistics::F64(StatValues {
+                        min: None,
+                        max: None,
+                        total_count: 3,
+                        null_count: Some(2),
+                        distinct_count: None,
+                    }),
+                },
+                ColumnSummary {
+                    name: String::from("time"),
+                    influxdb_type: Some(InfluxDbType::Timestamp),
+                    stats: Statistics::I64(StatValues {
+                        min: Some(1),
+                        max: Some(2),
+                        total_count: 3,
+                        null_count: None,
+                        distinct_count: None,
+                    }),
+                },
+            ],
+        };
+        assert_eq!(summary, expected);
+    }
+
     #[test]
     fn test_create_basic_summary() {
         let schema = full_schema();
@@ -1,22 +1,36 @@
 //! Database for the querier that contains all namespaces.
 
 use crate::{
-    cache::CatalogCache, chunk::ChunkAdapter, ingester::IngesterConnection,
-    namespace::QuerierNamespace, query_log::QueryLog, table::PruneMetrics,
+    access_stats::TableAccessTracker,
+    admission::{QueryAdmission, QueryAdmissionConfig},
+    cache::CatalogCache,
+    chunk::ChunkAdapter,
+    ingester::IngesterConnection,
+    namespace::QuerierNamespace,
+    namespace_timeout::NamespaceQueryTimeouts,
+    query_log::QueryLog,
+    rate_limit::QueryRateLimiter,
+    scheduler::{NamespaceScheduler, NamespaceSchedulerConfig},
+    table::PruneMetrics,
+    warm_up::{CacheWarmUp, CacheWarmUpConfig},
 };
 use async_trait::async_trait;
 use backoff::{Backoff, BackoffConfig};
 use data_types::{Namespace, ShardIndex};
 use iox_catalog::interface::Catalog;
-use iox_query::exec::Executor;
+use iox_query::{
+    exec::Executor,
+    result_cache::{QueryResultCache, QueryResultCacheConfig},
+};
 use parquet_file::storage::ParquetStorage;
-use service_common::QueryDatabaseProvider;
+use service_common::{QueryDatabaseProvider, QueryQueueFull};
 use sharder::JumpHash;
 use snafu::Snafu;
-use std::{collections::BTreeSet, sync::Arc};
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
 use trace::span::{Span, SpanRecorder};
 use tracker::{
     AsyncSemaphoreMetrics, InstrumentedAsyncOwnedSemaphorePermit, InstrumentedAsyncSemaphore,
+    RateLimited,
 };
 
 /// The number of entries to store in the circular query buffer log.
@@ -35,6 +49,36 @@ pub enum Error {
     NoShards,
 }
 
+/// Server-side query timeouts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueryTimeoutConfig {
+    /// Timeout for queries that do not request one. `None` means no timeout.
+    pub default: Option<Duration>,
+
+    /// Upper bound for the timeouts requested by clients. `None` means unbounded.
+    pub max: Option<Duration>,
+}
+
+impl QueryTimeoutConfig {
+    /// Timeout for a query, given the timeout requested by the client (if any).
+    pub fn timeout(&self, requested: Option<Duration>) -> Option<Duration> {
+        self.timeout_with_namespace_default(requested, None)
+    }
+
+    /// Timeout for a query, given the timeout requested by the client (if any) and the default
+    /// timeout of its namespace (if any), which takes precedence over [`default`](Self::default).
+    pub fn timeout_with_namespace_default(
+        &self,
+        requested: Option<Duration>,
+        namespace_default: Option<Duration>,
+    ) -> Option<Duration> {
+        match (requested.or(namespace_default).or(self.default), self.max) {
+            (Some(timeout), Some(max)) => Some(timeout.min(max)),
+            (timeout, max) => timeout.or(max),
+        }
+    }
+}
+
 /// Database for the querier.
 ///
 /// Contains all namespaces.
@@ -50,7 +94,6 @@ pub struct QuerierDatabase {
     chunk_adapter: Arc<ChunkAdapter>,
 
     /// Metric registry
-    #[allow(dead_code)]
     metric_registry: Arc<metric::Registry>,
 
     /// Executor for queries.
@@ -75,8 +118,35 @@ pub struct QuerierDatabase {
     /// Max combined chunk size for all chunks returned to the query subsystem by a single table.
     max_table_query_bytes: usize,
 
+    /// Check the output of table scans for primary key duplicates.
+    dedup_validation: bool,
+
     /// Chunk prune metrics.
     prune_metrics: Arc<PruneMetrics>,
+
+    /// Memory-based admission control for queries, if enabled.
+    query_admission: Option<Arc<QueryAdmission>>,
+
+    /// Server-side query timeouts.
+    query_timeout: QueryTimeoutConfig,
+
+    /// Per-namespace default query timeouts, if enabled.
+    namespace_query_timeouts: Option<Arc<NamespaceQueryTimeouts>>,
+
+    /// Per-namespace query rate limits, if enabled.
+    query_rate_limiter: Option<Arc<QueryRateLimiter>>,
+
+    /// Per-namespace query concurrency limits, if enabled.
+    namespace_scheduler: Option<Arc<NamespaceScheduler>>,
+
+    /// Tracker for table access statistics, if enabled.
+    access_stats: Option<Arc<TableAccessTracker>>,
+
+    /// Cache of the results of recent queries, if enabled.
+    result_cache: Option<Arc<QueryResultCache>>,
+
+    /// Cache warm-up for recently queried tables, if enabled.
+    cache_warm_up: Option<Arc<CacheWarmUp>>,
 }
 
 #[async_trait]
@@ -93,6 +163,35 @@ impl QueryDatabaseProvider for QuerierDatabase {
             .await
             .expect("Semaphore should not be closed by anyone")
     }
+
+    async fn query_timeout(&self, name: &str, requested: Option<Duration>) -> Option<Duration> {
+        let namespace_default = match (&self.namespace_query_timeouts, requested) {
+            // a requested timeout takes precedence, no need to look up the namespace
+            (Some(namespace_query_timeouts), None) => namespace_query_timeouts.get(name).await,
+            _ => None,
+        };
+
+        self.query_timeout
+            .timeout_with_namespace_default(requested, namespace_default)
+    }
+
+    async fn check_rate_limit(&self, name: &str) -> Result<(), RateLimited> {
+        match &self.query_rate_limiter {
+            Some(query_rate_limiter) => query_rate_limiter.check(name).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn acquire_namespace_semaphore(
+        &self,
+        name: &str,
+        span: Option<Span>,
+    ) -> Result<Option<InstrumentedAsyncOwnedSemaphorePermit>, QueryQueueFull> {
+        match &self.namespace_scheduler {
+            Some(namespace_scheduler) => namespace_scheduler.acquire(name, span).await.map(Some),
+            None => Ok(None),
+        }
+    }
 }
 
 impl QuerierDatabase {
@@ -104,6 +203,7 @@ impl QuerierDatabase {
     pub const MAX_CONCURRENT_QUERIES_MAX: usize = u16::MAX as usize;
 
     /// Create new database.
+    #[allow(clippy::too_many_arguments)]
     pub async fn new(
         catalog_cache: Arc<CatalogCache>,
         metric_registry: Arc<metric::Registry>,
@@ -112,6 +212,7 @@ impl QuerierDatabase {
         ingester_connection: Option<Arc<dyn IngesterConnection>>,
         max_concurrent_queries: usize,
         max_table_query_bytes: usize,
+        dedup_validation: bool,
     ) -> Result<Self, Error> {
         assert!(
             max_concurrent_queries <= Self::MAX_CONCURRENT_QUERIES_MAX,
@@ -122,12 +223,16 @@ impl QuerierDatabase {
 
         let backoff_config = BackoffConfig::default();
 
-        let chunk_adapter = Arc::new(ChunkAdapter::new(
-            Arc::clone(&catalog_cache),
-            store,
-            Arc::clone(&metric_registry),
-            Default::default(),
-        ));
+        // chunks can only be answered from their statistics if these are loaded
+        let chunk_adapter = Arc::new(
+            ChunkAdapter::new(
+                Arc::clone(&catalog_cache),
+                store,
+                Arc::clone(&metric_registry),
+                Default::default(),
+            )
+            .with_row_group_statistics(exec.aggregate_pushdown()),
+        );
         let query_log = Arc::new(QueryLog::new(QUERY_LOG_SIZE, catalog_cache.time_provider()));
         let semaphore_metrics = Arc::new(AsyncSemaphoreMetrics::new(
             &metric_registry,
@@ -153,10 +258,128 @@ impl QuerierDatabase {
             query_execution_semaphore,
             sharder,
             max_table_query_bytes,
+            dedup_validation,
             prune_metrics,
+            query_admission: None,
+            query_timeout: QueryTimeoutConfig::default(),
+            namespace_query_timeouts: None,
+            query_rate_limiter: None,
+            namespace_scheduler: None,
+            access_stats: None,
+            result_cache: None,
+            cache_warm_up: None,
         })
     }
 
+    /// Enforce timeouts for all queries.
+    pub fn with_query_timeout(self, query_timeout: QueryTimeoutConfig) -> Self {
+        Self {
+            query_timeout,
+            ..self
+        }
+    }
+
+    /// Apply the per-namespace default query timeouts stored in the catalog.
+    ///
+    /// The timeouts are re-read from the catalog every `refresh_interval`.
+    pub fn with_namespace_query_timeouts(self, refresh_interval: Duration) -> Self {
+        let namespace_query_timeouts = Arc::new(NamespaceQueryTimeouts::new(
+            self.catalog_cache.catalog(),
+            self.catalog_cache.time_provider(),
+            refresh_interval,
+        ));
+
+        Self {
+            namespace_query_timeouts: Some(namespace_query_timeouts),
+            ..self
+        }
+    }
+
+    /// Limit the estimated memory of all running queries.
+    ///
+    /// Table scans that would exceed the memory pool wait for other queries to finish or are
+    /// rejected, see [`QueryAdmission`].
+    pub fn with_query_admission(self, config: QueryAdmissionConfig) -> Self {
+        let query_admission = Arc::new(QueryAdmission::new(config, &self.metric_registry));
+
+        Self {
+            query_admission: Some(query_admission),
+            ..self
+        }
+    }
+
+    /// Enforce the per-namespace query rate limits stored in the catalog.
+    ///
+    /// The limits are re-read from the catalog every `refresh_interval`.
+    pub fn with_query_rate_limit(self, refresh_interval: Duration) -> Self {
+        let query_rate_limiter = Arc::new(QueryRateLimiter::new(
+            self.catalog_cache.catalog(),
+            self.catalog_cache.time_provider(),
+            refresh_interval,
+            &self.metric_registry,
+        ));
+
+        Self {
+            query_rate_limiter: Some(query_rate_limiter),
+            ..self
+        }
+    }
+
+    /// Limit the number of concurrent and queued queries per namespace, so that a single
+    /// namespace cannot occupy all query slots, see [`NamespaceScheduler`].
+    pub fn with_namespace_scheduler(self, config: NamespaceSchedulerConfig) -> Self {
+        let namespace_scheduler = Arc::new(NamespaceScheduler::new(
+            config,
+            Arc::clone(&self.metric_registry),
+        ));
+
+        Self {
+            namespace_scheduler: Some(namespace_scheduler),
+            ..self
+        }
+    }
+
+    /// Track when and how often tables are queried.
+    ///
+    /// The statistics are written to the catalog every `flush_interval` by a background worker of
+    /// the [`QuerierHandler`](crate::QuerierHandler).
+    pub fn with_access_stats(self, flush_interval: Duration) -> Self {
+        let access_stats = Arc::new(TableAccessTracker::new(
+            self.catalog_cache.catalog(),
+            self.catalog_cache.time_provider(),
+            flush_interval,
+            &self.metric_registry,
+        ));
+
+        Self {
+            access_stats: Some(access_stats),
+            ..self
+        }
+    }
+
+    /// Cache the results of recent queries, see [`QueryResultCache`].
+    pub fn with_result_cache(self, config: QueryResultCacheConfig) -> Self {
+        let result_cache = Arc::new(QueryResultCache::new(config, &self.metric_registry));
+
+        Self {
+            result_cache: Some(result_cache),
+            ..self
+        }
+    }
+
+    /// Pre-load the catalog cache for tables that were queried recently.
+    ///
+    /// The warm-up runs once as a background worker of the [`QuerierHandler`](crate::QuerierHandler)
+    /// and relies on the statistics written by [`with_access_stats`](Self::with_access_stats).
+    pub fn with_cache_warm_up(self, config: CacheWarmUpConfig) -> Self {
+        let cache_warm_up = Arc::new(CacheWarmUp::new(Arc::clone(&self.catalog_cache), config));
+
+        Self {
+            cache_warm_up: Some(cache_warm_up),
+            ..self
+        }
+    }
+
     /// Get namespace if it exists.
     ///
     /// This will await the internal namespace semaphore. Existence of namespaces is checked AFTER
@@ -183,7 +406,11 @@ impl QuerierDatabase {
             Arc::clone(&self.query_log),
             Arc::clone(&self.sharder),
             self.max_table_query_bytes,
+            self.dedup_validation,
             Arc::clone(&self.prune_metrics),
+            self.query_admission.clone(),
+            self.access_stats.clone(),
+            self.result_cache.clone(),
         )))
     }
 
@@ -207,6 +434,21 @@ impl QuerierDatabase {
     pub(crate) fn exec(&self) -> &Executor {
         &self.exec
     }
+
+    /// Catalog cache.
+    pub(crate) fn catalog_cache(&self) -> &Arc<CatalogCache> {
+        &self.catalog_cache
+    }
+
+    /// Tracker for table access statistics, if enabled.
+    pub(crate) fn access_stats(&self) -> Option<&Arc<TableAccessTracker>> {
+        self.access_stats.as_ref()
+    }
+
+    /// Cache warm-up, if enabled.
+    pub(crate) fn cache_warm_up(&self) -> Option<&Arc<CacheWarmUp>> {
+        self.cache_warm_up.as_ref()
+    }
 }
 
 pub async fn create_sharder(
@@ -257,6 +499,7 @@ mod tests {
             catalog.catalog(),
             catalog.time_provider(),
             catalog.metric_registry(),
+            catalog.object_store(),
             &Handle::current(),
         ));
         QuerierDatabase::new(
@@ -267,6 +510,7 @@ mod tests {
             Some(create_ingester_connection_for_testing()),
             QuerierDatabase::MAX_CONCURRENT_QUERIES_MAX.saturating_add(1),
             usize::MAX,
+            false,
         )
         .await
         .unwrap();
@@ -280,6 +524,7 @@ mod tests {
             catalog.catalog(),
             catalog.time_provider(),
             catalog.metric_registry(),
+            catalog.object_store(),
             &Handle::current(),
         ));
 
@@ -292,12 +537,61 @@ mod tests {
                 Some(create_ingester_connection_for_testing()),
                 QuerierDatabase::MAX_CONCURRENT_QUERIES_MAX,
                 usize::MAX,
+                false,
             )
             .await,
             Error::NoShards
         );
     }
 
+    #[test]
+    fn test_query_timeout() {
+        let secs = |s| Some(Duration::from_secs(s));
+
+        let config = QueryTimeoutConfig::default();
+        assert_eq!(config.timeout(None), None);
+        assert_eq!(config.timeout(secs(10)), secs(10));
+
+        let config = QueryTimeoutConfig {
+            default: secs(5),
+            max: None,
+        };
+        assert_eq!(config.timeout(None), secs(5));
+        assert_eq!(config.timeout(secs(10)), secs(10));
+
+        let config = QueryTimeoutConfig {
+            default: secs(5),
+            max: secs(7),
+        };
+        assert_eq!(config.timeout(None), secs(5));
+        assert_eq!(config.timeout(secs(1)), secs(1));
+        assert_eq!(config.timeout(secs(10)), secs(7));
+
+        let config = QueryTimeoutConfig {
+            default: None,
+            max: secs(7),
+        };
+        assert_eq!(config.timeout(None), secs(7));
+
+        // the namespace default replaces the server default, but not a requested timeout
+        let config = QueryTimeoutConfig {
+            default: secs(5),
+            max: secs(7),
+        };
+        assert_eq!(
+            config.timeout_with_namespace_default(None, secs(6)),
+            secs(6)
+        );
+        assert_eq!(
+            config.timeout_with_namespace_default(None, secs(60)),
+            secs(7)
+        );
+        assert_eq!(
+            config.timeout_with_namespace_default(secs(1), secs(6)),
+            secs(1)
+        );
+    }
+
     #[tokio::test]
     async fn test_namespace() {
         let catalog = TestCatalog::new();
@@ -308,6 +602,7 @@ mod tests {
             catalog.catalog(),
             catalog.time_provider(),
             catalog.metric_registry(),
+            catalog.object_store(),
             &Handle::current(),
         ));
         let db = QuerierDatabase::new(
@@ -318,6 +613,7 @@ mod tests {
             Some(create_ingester_connection_for_testing()),
             QuerierDatabase::MAX_CONCURRENT_QUERIES_MAX,
             usize::MAX,
+            false,
         )
         .await
         .unwrap();
@@ -338,6 +634,7 @@ mod tests {
             catalog.catalog(),
             catalog.time_provider(),
             catalog.metric_registry(),
+            catalog.object_store(),
             &Handle::current(),
         ));
         let db = QuerierDatabase::new(
@@ -348,6 +645,7 @@ mod tests {
             Some(create_ingester_connection_for_testing()),
             QuerierDatabase::MAX_CONCURRENT_QUERIES_MAX,
             usize::MAX,
+            false,
         )
         .await
         .unwrap();
@@ -42,7 +42,6 @@ pub trait QuerierHandler: Send + Sync {
 type SharedJoinHandle = Shared<BoxFuture<'static, Result<(), Arc<JoinError>>>>;
 
 /// Convert a [`JoinHandle`] into a [`SharedJoinHandle`].
-#[allow(dead_code)]
 fn shared_handle(handle: JoinHandle<()>) -> SharedJoinHandle {
     handle.map_err(Arc::new).boxed().shared()
 }
@@ -73,7 +72,22 @@ impl QuerierHandlerImpl {
         let shutdown = CancellationToken::new();
         let poison_cabinet = Arc::new(PoisonCabinet::new());
 
-        let join_handles = vec![];
+        let mut join_handles = vec![];
+        if let Some(access_stats) = database.access_stats() {
+            join_handles.push((
+                String::from("table access statistics"),
+                shared_handle(tokio::spawn(Arc::clone(access_stats).run(shutdown.clone()))),
+            ));
+        }
+        if let Some(cache_warm_up) = database.cache_warm_up() {
+            join_handles.push((
+                String::from("cache warm-up"),
+                shared_handle(tokio::spawn(
+                    Arc::clone(cache_warm_up).run(shutdown.clone()),
+                )),
+            ));
+        }
+
         Self {
             catalog,
             database,
@@ -114,6 +128,10 @@ impl QuerierHandler for QuerierHandlerImpl {
     fn shutdown(&self) {
         self.shutdown.cancel();
         self.database.exec().shutdown();
+        self.database
+            .catalog_cache()
+            .object_store()
+            .write_snapshot();
     }
 }
 
@@ -171,6 +189,7 @@ mod tests {
                 Arc::clone(&catalog),
                 time_provider,
                 Arc::clone(&metric_registry),
+                Arc::clone(&object_store) as _,
                 &Handle::current(),
             ));
             // QuerierDatabase::new returns an error if there are no shards in the catalog
@@ -195,6 +214,7 @@ mod tests {
                     Some(create_ingester_connection_for_testing()),
                     QuerierDatabase::MAX_CONCURRENT_QUERIES_MAX,
                     usize::MAX,
+                    false,
                 )
                 .await
                 .unwrap(),
@@ -0,0 +1,292 @@
+//! Circuit breaker for ingester requests.
+//!
+//! If an ingester repeatedly fails to answer (or takes longer than the configured latency
+//! budget), the circuit for that ingester opens and no further requests are sent to it for
+//! [`CircuitBreakerConfig::open_duration`]. Afterwards a single probe request is let through. If
+//! it succeeds the circuit closes again, otherwise it stays open for another period.
+//!
+//! While the circuit is open, queries are answered from persisted (parquet) data only and marked
+//! as potentially incomplete, see [`QueryCompleteness`](iox_query::QueryCompleteness).
+use std::{borrow::Cow, sync::Arc, time::Duration};
+
+use iox_time::{Time, TimeProvider};
+use metric::U64Counter;
+use observability_deps::tracing::{info, warn};
+use parking_lot::Mutex;
+
+/// Configuration of the ingester circuit breaker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// Number of consecutive failed requests after which the circuit opens.
+    pub failure_threshold: u64,
+
+    /// Duration the circuit stays open before a probe request is sent to the ingester.
+    pub open_duration: Duration,
+
+    /// Requests that take longer than this are cancelled and count as failed.
+    ///
+    /// `None` means that requests may take arbitrarily long.
+    pub latency_budget: Option<Duration>,
+}
+
+/// State of a single circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Requests are passed to the ingester.
+    Closed { consecutive_failures: u64 },
+
+    /// Requests are rejected until the given time.
+    Open { until: Time },
+
+    /// A single probe request was let through at the given time and we wait for its result.
+    HalfOpen { since: Time },
+}
+
+/// Circuit breaker for a single ingester.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    ingester_address: Arc<str>,
+    config: CircuitBreakerConfig,
+    time_provider: Arc<dyn TimeProvider>,
+    state: Mutex<State>,
+    metric_opened: U64Counter,
+    metric_rejected: U64Counter,
+}
+
+impl CircuitBreaker {
+    /// Create new, closed circuit breaker.
+    pub fn new(
+        ingester_address: Arc<str>,
+        config: CircuitBreakerConfig,
+        time_provider: Arc<dyn TimeProvider>,
+        metric_registry: &metric::Registry,
+    ) -> Self {
+        let attributes = [("ingester", Cow::from(ingester_address.as_ref().to_owned()))];
+        let metric_opened = metric_registry
+            .register_metric::<U64Counter>(
+                "ingester_circuit_breaker_opened",
+                "Number of times the circuit breaker for an ingester opened",
+            )
+            .recorder(attributes.clone());
+        let metric_rejected = metric_registry
+            .register_metric::<U64Counter>(
+                "ingester_circuit_breaker_rejected",
+                "Number of ingester requests that were not sent because the circuit was open",
+            )
+            .recorder(attributes);
+
+        Self {
+            ingester_address,
+            config,
+            time_provider,
+            state: Mutex::new(State::Closed {
+                consecutive_failures: 0,
+            }),
+            metric_opened,
+            metric_rejected,
+        }
+    }
+
+    /// Latency budget for a single request.
+    pub fn latency_budget(&self) -> Option<Duration> {
+        self.config.latency_budget
+    }
+
+    /// Check if a request may be sent to the ingester.
+    ///
+    /// If the circuit is open and the open duration has passed, this lets a single probe request
+    /// through. The result of every permitted request must be reported via
+    /// [`record_success`](Self::record_success) or [`record_failure`](Self::record_failure).
+    pub fn try_acquire(&self) -> bool {
+        let now = self.time_provider.now();
+        let mut state = self.state.lock();
+
+        let permitted = match *state {
+            State::Closed { .. } => true,
+            State::Open { until } => {
+                if now >= until {
+                    *state = State::HalfOpen { since: now };
+                    true
+                } else {
+                    false
+                }
+            }
+            State::HalfOpen { since } => {
+                // the probe might have been cancelled, so send another one eventually
+                if self.elapsed(since, now) >= self.config.open_duration {
+                    *state = State::HalfOpen { since: now };
+                    true
+                } else {
+                    false
+                }
+            }
+        };
+
+        if !permitted {
+            self.metric_rejected.inc(1);
+        }
+        permitted
+    }
+
+    /// Record a successful request. This closes the circuit.
+    pub fn record_success(&self) {
+        let mut state = self.state.lock();
+
+        if !matches!(*state, State::Closed { .. }) {
+            info!(
+                ingester_address = self.ingester_address.as_ref(),
+                "Ingester recovered, closing circuit"
+            );
+        }
+
+        *state = State::Closed {
+            consecutive_failures: 0,
+        };
+    }
+
+    /// Record a failed request.
+    ///
+    /// Returns `true` if the circuit is open afterwards.
+    pub fn record_failure(&self) -> bool {
+        let now = self.time_provider.now();
+        let mut state = self.state.lock();
+
+        let open = match *state {
+            State::Closed {
+                consecutive_failures,
+            } => {
+                let consecutive_failures = consecutive_failures + 1;
+                if consecutive_failures >= self.config.failure_threshold {
+                    true
+                } else {
+                    *state = State::Closed {
+                        consecutive_failures,
+                    };
+                    false
+                }
+            }
+            State::Open { .. } | State::HalfOpen { .. } => true,
+        };
+
+        if open {
+            if matches!(*state, State::Closed { .. }) {
+                warn!(
+                    ingester_address = self.ingester_address.as_ref(),
+                    open_duration = ?self.config.open_duration,
+                    "Ingester failed repeatedly, opening circuit"
+                );
+                self.metric_opened.inc(1);
+            }
+
+            *state = State::Open {
+                until: now.checked_add(self.config.open_duration).unwrap_or(now),
+            };
+        }
+
+        open
+    }
+
+    fn elapsed(&self, since: Time, now: Time) -> Duration {
+        now.checked_duration_since(since).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iox_time::MockProvider;
+    use metric::{Attributes, Metric};
+
+    use super::*;
+
+    #[test]
+    fn test_circuit_breaker() {
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let metric_registry = metric::Registry::new();
+        let breaker = CircuitBreaker::new(
+            Arc::from("addr1"),
+            CircuitBreakerConfig {
+                failure_threshold: 2,
+                open_duration: Duration::from_secs(10),
+                latency_budget: None,
+            },
+            Arc::clone(&time_provider) as _,
+            &metric_registry,
+        );
+
+        // failures below the threshold keep the circuit closed
+        assert!(breaker.try_acquire());
+        assert!(!breaker.record_failure());
+        assert!(breaker.try_acquire());
+        breaker.record_success();
+        assert!(breaker.try_acquire());
+        assert!(!breaker.record_failure());
+
+        // reaching the threshold opens the circuit
+        assert!(breaker.try_acquire());
+        assert!(breaker.record_failure());
+        assert!(!breaker.try_acquire());
+        assert_eq!(
+            get_metric(&metric_registry, "ingester_circuit_breaker_opened"),
+            1
+        );
+        assert_eq!(
+            get_metric(&metric_registry, "ingester_circuit_breaker_rejected"),
+            1
+        );
+
+        // after the open duration, a single probe is let through
+        time_provider.inc(Duration::from_secs(10));
+        assert!(breaker.try_acquire());
+        assert!(!breaker.try_acquire());
+
+        // failed probe keeps the circuit open
+        assert!(breaker.record_failure());
+        assert!(!breaker.try_acquire());
+        assert_eq!(
+            get_metric(&metric_registry, "ingester_circuit_breaker_opened"),
+            1
+        );
+
+        // successful probe closes it
+        time_provider.inc(Duration::from_secs(10));
+        assert!(breaker.try_acquire());
+        breaker.record_success();
+        assert!(breaker.try_acquire());
+        assert!(breaker.try_acquire());
+    }
+
+    #[test]
+    fn test_cancelled_probe() {
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let breaker = CircuitBreaker::new(
+            Arc::from("addr1"),
+            CircuitBreakerConfig {
+                failure_threshold: 1,
+                open_duration: Duration::from_secs(10),
+                latency_budget: None,
+            },
+            Arc::clone(&time_provider) as _,
+            &metric::Registry::new(),
+        );
+
+        assert!(breaker.record_failure());
+
+        time_provider.inc(Duration::from_secs(10));
+        assert!(breaker.try_acquire());
+
+        // probe never reports back
+        time_provider.inc(Duration::from_secs(5));
+        assert!(!breaker.try_acquire());
+        time_provider.inc(Duration::from_secs(5));
+        assert!(breaker.try_acquire());
+    }
+
+    fn get_metric(metric_registry: &metric::Registry, name: &'static str) -> u64 {
+        metric_registry
+            .get_instrument::<Metric<U64Counter>>(name)
+            .unwrap()
+            .get_observer(&Attributes::from(&[("ingester", "addr1")]))
+            .unwrap()
+            .fetch()
+    }
+}
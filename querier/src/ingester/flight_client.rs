@@ -5,6 +5,7 @@ use influxdb_iox_client::flight::{
     generated_types as proto,
     low_level::{Client as LowLevelFlightClient, LowLevelMessage, PerformQuery},
 };
+use iox_query::{QueryId, QUERY_ID_HEADER};
 use observability_deps::tracing::debug;
 use snafu::{ResultExt, Snafu};
 use std::{collections::HashMap, fmt::Debug, ops::DerefMut, sync::Arc};
@@ -41,10 +42,13 @@ pub enum Error {
 #[async_trait]
 pub trait FlightClient: Debug + Send + Sync + 'static {
     /// Send query to given ingester.
+    ///
+    /// The query ID, if any, is sent to the ingester as request header.
     async fn query(
         &self,
         ingester_address: Arc<str>,
         request: IngesterQueryRequest,
+        query_id: Option<QueryId>,
     ) -> Result<Box<dyn QueryData>, Error>;
 }
 
@@ -90,12 +94,18 @@ impl FlightClient for FlightClientImpl {
         &self,
         ingester_addr: Arc<str>,
         request: IngesterQueryRequest,
+        query_id: Option<QueryId>,
     ) -> Result<Box<dyn QueryData>, Error> {
         let connection = self.connect(Arc::clone(&ingester_addr)).await?;
 
         let mut client = LowLevelFlightClient::<proto::IngesterQueryRequest>::new(connection);
+        if let Some(query_id) = query_id {
+            client
+                .add_header(QUERY_ID_HEADER, &query_id.to_string())
+                .context(FlightSnafu)?;
+        }
 
-        debug!(%ingester_addr, ?request, "Sending request to ingester");
+        debug!(%ingester_addr, ?request, ?query_id, "Sending request to ingester");
         let request: proto::IngesterQueryRequest =
             request.try_into().context(CreatingRequestSnafu)?;
 
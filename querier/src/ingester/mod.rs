@@ -1,4 +1,5 @@
 use self::{
+    circuit_breaker::{CircuitBreaker, CircuitBreakerConfig},
     flight_client::{Error as FlightClientError, FlightClient, FlightClientImpl, FlightError},
     test_util::MockIngesterConnection,
 };
@@ -7,8 +8,8 @@ use arrow::{datatypes::DataType, error::ArrowError, record_batch::RecordBatch};
 use async_trait::async_trait;
 use client_util::connection;
 use data_types::{
-    ChunkId, ChunkOrder, IngesterMapping, PartitionId, SequenceNumber, ShardId, ShardIndex,
-    TableSummary, TimestampMinMax,
+    ChunkId, ChunkOrder, IngesterMapping, PartitionId, PartitionSnapshot, SequenceNumber, ShardId,
+    ShardIndex, TableSummary, TimestampMinMax,
 };
 use datafusion_util::MemoryStream;
 use futures::{stream::FuturesUnordered, TryStreamExt};
@@ -23,21 +24,23 @@ use influxdb_iox_client::flight::{
 use iox_query::{
     exec::{stringset::StringSet, IOxSessionContext},
     util::compute_timenanosecond_min_max,
-    QueryChunk, QueryChunkError, QueryChunkMeta,
+    QueryChunk, QueryChunkError, QueryChunkMeta, QueryId, SnapshotExpired,
 };
 use iox_time::{Time, TimeProvider};
 use metric::{DurationHistogram, Metric};
 use observability_deps::tracing::{debug, info, trace, warn};
 use predicate::Predicate;
-use schema::{selection::Selection, sort::SortKey, Schema};
+use schema::{selection::Selection, sort::SortKey, InfluxFieldType, Schema};
 use snafu::{ensure, OptionExt, ResultExt, Snafu};
 use std::{
     any::Any,
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     sync::Arc,
+    time::Duration,
 };
 use trace::span::{Span, SpanRecorder};
 
+pub(crate) mod circuit_breaker;
 pub(crate) mod flight_client;
 pub(crate) mod test_util;
 
@@ -82,6 +85,29 @@ pub enum Error {
         source: FlightClientError,
     },
 
+    #[snafu(display(
+        "Ingester '{}' cannot serve query snapshot: {}",
+        ingester_address,
+        source
+    ))]
+    SnapshotExpired {
+        ingester_address: String,
+        source: SnapshotExpired,
+    },
+
+    #[snafu(display(
+        "Ingester '{}' did not answer within latency budget of {:?}",
+        ingester_address,
+        budget
+    ))]
+    LatencyBudgetExceeded {
+        ingester_address: String,
+        budget: Duration,
+    },
+
+    #[snafu(display("Ingester '{}' is unavailable, circuit is open", ingester_address))]
+    IngesterUnavailable { ingester_address: String },
+
     #[snafu(display("Failed to connect to ingester '{}': {}", ingester_address, source))]
     Connecting {
         ingester_address: String,
@@ -133,17 +159,31 @@ pub enum Error {
     ShardNotMapped { shard_index: ShardIndex },
 }
 
+impl Error {
+    /// Returns `true` if this error indicates that the ingester itself failed to answer, as
+    /// opposed to e.g. an error decoding its answer.
+    fn is_ingester_failure(&self) -> bool {
+        matches!(
+            self,
+            Self::RemoteQuery { .. } | Self::Connecting { .. } | Self::LatencyBudgetExceeded { .. }
+        )
+    }
+}
+
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 /// Create a new set of connections given a map of shard indexes to Ingester configurations
 pub fn create_ingester_connections_by_shard(
     shard_to_ingesters: HashMap<ShardIndex, IngesterMapping>,
     catalog_cache: Arc<CatalogCache>,
+    circuit_breaker_config: Option<CircuitBreakerConfig>,
 ) -> Arc<dyn IngesterConnection> {
-    Arc::new(IngesterConnectionImpl::by_shard(
-        shard_to_ingesters,
-        catalog_cache,
-    ))
+    let connection = IngesterConnectionImpl::by_shard(shard_to_ingesters, catalog_cache);
+    let connection = match circuit_breaker_config {
+        Some(config) => connection.with_circuit_breaker(config),
+        None => connection,
+    };
+    Arc::new(connection)
 }
 
 /// Create a new ingester suitable for testing
@@ -157,6 +197,8 @@ pub fn create_ingester_connection_for_testing() -> Arc<dyn IngesterConnection> {
 pub trait IngesterConnection: std::fmt::Debug + Send + Sync + 'static {
     /// Returns all partitions ingester(s) know about for the specified table.
     ///
+    /// If `snapshot` is set, only the data of these partition snapshots is returned.
+    ///
     /// # Panics
     ///
     /// Panics if the list of shard_indexes is empty.
@@ -169,6 +211,8 @@ pub trait IngesterConnection: std::fmt::Debug + Send + Sync + 'static {
         columns: Vec<String>,
         predicate: &Predicate,
         expected_schema: Arc<Schema>,
+        query_id: Option<QueryId>,
+        snapshot: Option<&BTreeMap<PartitionId, PartitionSnapshot>>,
         span: Option<Span>,
     ) -> Result<Vec<IngesterPartition>>;
 
@@ -292,6 +336,7 @@ pub struct IngesterConnectionImpl {
     flight_client: Arc<dyn FlightClient>,
     catalog_cache: Arc<CatalogCache>,
     metrics: Arc<IngesterConnectionMetrics>,
+    circuit_breakers: HashMap<Arc<str>, Arc<CircuitBreaker>>,
 }
 
 impl IngesterConnectionImpl {
@@ -348,8 +393,33 @@ impl IngesterConnectionImpl {
             flight_client,
             catalog_cache,
             metrics,
+            circuit_breakers: HashMap::new(),
         }
     }
+
+    /// Guard every ingester with a circuit breaker.
+    ///
+    /// Once the circuit for an ingester is open, requests to it fail fast with
+    /// [`Error::IngesterUnavailable`] so that callers can fall back to persisted data only.
+    pub fn with_circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        let time_provider = self.catalog_cache.time_provider();
+        let metric_registry = self.catalog_cache.metric_registry();
+
+        self.circuit_breakers = self
+            .unique_ingester_addresses
+            .iter()
+            .map(|ingester_address| {
+                let circuit_breaker = CircuitBreaker::new(
+                    Arc::clone(ingester_address),
+                    config,
+                    Arc::clone(&time_provider),
+                    &metric_registry,
+                );
+                (Arc::clone(ingester_address), Arc::new(circuit_breaker))
+            })
+            .collect();
+        self
+    }
 }
 
 /// Struct that names all parameters to `execute`
@@ -363,6 +433,8 @@ struct GetPartitionForIngester<'a> {
     columns: Vec<String>,
     predicate: &'a Predicate,
     expected_schema: Arc<Schema>,
+    query_id: Option<QueryId>,
+    snapshot: Option<&'a BTreeMap<PartitionId, PartitionSnapshot>>,
 }
 
 /// Fetches the partitions for a single ingester
@@ -379,6 +451,8 @@ async fn execute(
         columns,
         predicate,
         expected_schema,
+        query_id,
+        snapshot,
     } = request;
 
     let ingester_query_request = IngesterQueryRequest {
@@ -386,10 +460,15 @@ async fn execute(
         table: table_name.to_string(),
         columns: columns.clone(),
         predicate: Some(predicate.clone()),
+        snapshot: snapshot.cloned(),
     };
 
     let query_res = flight_client
-        .query(Arc::clone(&ingester_address), ingester_query_request)
+        .query(
+            Arc::clone(&ingester_address),
+            ingester_query_request,
+            query_id,
+        )
         .await;
 
     if let Err(FlightClientError::Flight {
@@ -405,6 +484,14 @@ async fn execute(
             );
             return Ok(vec![]);
         }
+        if snapshot.is_some() && status.code() == tonic::Code::FailedPrecondition {
+            return Err(Error::SnapshotExpired {
+                ingester_address: ingester_address.to_string(),
+                source: SnapshotExpired {
+                    reason: status.message().to_string(),
+                },
+            });
+        }
     }
     let mut perform_query = query_res
         .context(RemoteQuerySnafu {
@@ -415,6 +502,7 @@ async fn execute(
             warn!(
                 e=%e,
                 ingester_address=ingester_address.as_ref(),
+                ?query_id,
                 namespace=namespace_name.as_ref(),
                 table=table_name.as_ref(),
                 columns=columns.join(",").as_str(),
@@ -588,7 +676,8 @@ impl IngesterStreamDecoder {
                         .tombstone_max_sequence_number
                         .map(SequenceNumber::new),
                     partition_sort_key,
-                );
+                )
+                .with_max_sequence_number(status.max_sequence_number.map(SequenceNumber::new));
                 self.current_partition = Some(partition);
             }
             LowLevelMessage::Schema(schema) => {
@@ -671,6 +760,8 @@ impl IngesterConnection for IngesterConnectionImpl {
         columns: Vec<String>,
         predicate: &Predicate,
         expected_schema: Arc<Schema>,
+        query_id: Option<QueryId>,
+        snapshot: Option<&BTreeMap<PartitionId, PartitionSnapshot>>,
         span: Option<Span>,
     ) -> Result<Vec<IngesterPartition>> {
         // If no shard indexes are specified, no ingester addresses can be found. This is a
@@ -683,7 +774,8 @@ impl IngesterConnection for IngesterConnectionImpl {
 
         let metrics = Arc::clone(&self.metrics);
 
-        let measured_ingester_request = |ingester_address| {
+        let measured_ingester_request = |ingester_address: Arc<str>| {
+            let circuit_breaker = self.circuit_breakers.get(&ingester_address).map(Arc::clone);
             let request = GetPartitionForIngester {
                 flight_client: Arc::clone(&self.flight_client),
                 catalog_cache: Arc::clone(&self.catalog_cache),
@@ -693,21 +785,73 @@ impl IngesterConnection for IngesterConnectionImpl {
                 columns: columns.clone(),
                 predicate,
                 expected_schema: Arc::clone(&expected_schema),
+                query_id,
+                snapshot,
             };
             let metrics = Arc::clone(&metrics);
 
             // wrap `execute` into an additional future so that we can measure the request time
             // INFO: create the measurement structure outside of the async block so cancellation is
             // always measured
-            let measure_me = ObserveIngesterRequest::new(request.clone(), metrics, &span_recorder);
+            // INFO: requests that are rejected by the circuit breaker are not measured
+            let permitted = circuit_breaker
+                .as_ref()
+                .map(|circuit_breaker| circuit_breaker.try_acquire())
+                .unwrap_or(true);
+            let measure_me = permitted
+                .then(|| ObserveIngesterRequest::new(request.clone(), metrics, &span_recorder));
             async move {
-                let res = execute(request.clone(), measure_me.span_recorder()).await;
+                let ingester_address = Arc::clone(&request.ingester_address);
+                let measure_me = match measure_me {
+                    Some(measure_me) => measure_me,
+                    None => {
+                        return IngesterUnavailableSnafu {
+                            ingester_address: ingester_address.as_ref(),
+                        }
+                        .fail()
+                    }
+                };
+
+                let fut = execute(request.clone(), measure_me.span_recorder());
+                let latency_budget = circuit_breaker
+                    .as_ref()
+                    .and_then(|circuit_breaker| circuit_breaker.latency_budget());
+                let res = match latency_budget {
+                    Some(budget) => tokio::time::timeout(budget, fut).await.unwrap_or_else(|_| {
+                        LatencyBudgetExceededSnafu {
+                            ingester_address: ingester_address.as_ref(),
+                            budget,
+                        }
+                        .fail()
+                    }),
+                    None => fut.await,
+                };
 
                 match &res {
                     Ok(_) => measure_me.set_ok(),
                     Err(_) => measure_me.set_err(),
                 }
 
+                if let Some(circuit_breaker) = &circuit_breaker {
+                    match &res {
+                        Ok(_) => circuit_breaker.record_success(),
+                        Err(e) if e.is_ingester_failure() => {
+                            if circuit_breaker.record_failure() {
+                                warn!(
+                                    e=%e,
+                                    ingester_address=ingester_address.as_ref(),
+                                    "Ingester request failed, circuit is open",
+                                );
+                                return IngesterUnavailableSnafu {
+                                    ingester_address: ingester_address.as_ref(),
+                                }
+                                .fail();
+                            }
+                        }
+                        Err(_) => {}
+                    }
+                }
+
                 res
             }
         };
@@ -822,6 +966,10 @@ pub struct IngesterPartition {
     /// persisted for this partition
     tombstone_max_sequence_number: Option<SequenceNumber>,
 
+    /// Maximum sequence number of all data (persisted or not) that the
+    /// ingester returned for this partition
+    max_sequence_number: Option<SequenceNumber>,
+
     /// Partition-wide sort key.
     partition_sort_key: Arc<Option<SortKey>>,
 
@@ -847,11 +995,20 @@ impl IngesterPartition {
             shard_id,
             parquet_max_sequence_number,
             tombstone_max_sequence_number,
+            max_sequence_number: None,
             partition_sort_key,
             chunks: vec![],
         }
     }
 
+    /// Set the maximum sequence number of all data of this partition.
+    pub fn with_max_sequence_number(self, max_sequence_number: Option<SequenceNumber>) -> Self {
+        Self {
+            max_sequence_number,
+            ..self
+        }
+    }
+
     /// Try to add a new chunk to this partition.
     pub(crate) fn try_add_chunk(
         mut self,
@@ -934,6 +1091,10 @@ impl IngesterPartition {
         self.tombstone_max_sequence_number
     }
 
+    pub(crate) fn max_sequence_number(&self) -> Option<SequenceNumber> {
+        self.max_sequence_number
+    }
+
     pub(crate) fn chunks(&self) -> &[IngesterChunk] {
         &self.chunks
     }
@@ -1124,6 +1285,17 @@ fn ensure_schema(batch: RecordBatch, expected_schema: &Schema) -> Result<RecordB
 
                     // check type
                     if desired_type != actual_type {
+                        // the ingester may still buffer data of a field that was migrated to
+                        // a new type
+                        if InfluxFieldType::is_migration(actual_type, desired_type) {
+                            return arrow::compute::cast(col, desired_type).context(
+                                ConvertingRecordBatchSnafu {
+                                    column_name: desired_field.name(),
+                                    data_type: desired_type.clone(),
+                                },
+                            );
+                        }
+
                         if let DataType::Dictionary(_key_type, value_type) = desired_type.clone() {
                             if value_type.as_ref() == actual_type {
                                 // convert
@@ -1206,6 +1378,41 @@ mod tests {
         assert_matches!(err, Error::RemoteQuery { .. });
     }
 
+    #[tokio::test]
+    async fn test_flight_circuit_breaker() {
+        let mock_flight_client = Arc::new(
+            MockFlightClient::new([
+                (
+                    "addr1",
+                    Err(FlightClientError::Flight {
+                        source: FlightError::GrpcError(tonic::Status::internal("cow exploded")),
+                    }),
+                ),
+                ("addr2", Ok(MockQueryData { results: vec![] })),
+            ])
+            .await,
+        );
+        let ingester_conn = mock_flight_client
+            .ingester_conn()
+            .await
+            .with_circuit_breaker(CircuitBreakerConfig {
+                failure_threshold: 1,
+                open_duration: Duration::from_secs(10),
+                latency_budget: None,
+            });
+
+        let err = get_partitions(&ingester_conn, &[1]).await.unwrap_err();
+        assert_matches!(err, Error::IngesterUnavailable { .. });
+
+        // circuit is open, so the ingester is not contacted again (the response is not mocked)
+        let err = get_partitions(&ingester_conn, &[1]).await.unwrap_err();
+        assert_matches!(err, Error::IngesterUnavailable { .. });
+
+        // other ingesters are unaffected
+        let partitions = get_partitions(&ingester_conn, &[2]).await.unwrap();
+        assert!(partitions.is_empty());
+    }
+
     #[tokio::test]
     async fn test_flight_not_found() {
         let mock_flight_client = Arc::new(
@@ -1277,6 +1484,7 @@ mod tests {
                             status: Some(PartitionStatus {
                                 parquet_max_sequence_number: None,
                                 tombstone_max_sequence_number: None,
+                                max_sequence_number: None,
                             }),
                         },
                     ))],
@@ -1333,6 +1541,7 @@ mod tests {
                                 status: Some(PartitionStatus {
                                     parquet_max_sequence_number: None,
                                     tombstone_max_sequence_number: None,
+                                    max_sequence_number: None,
                                 }),
                             },
                         )),
@@ -1343,6 +1552,7 @@ mod tests {
                                 status: Some(PartitionStatus {
                                     parquet_max_sequence_number: None,
                                     tombstone_max_sequence_number: None,
+                                    max_sequence_number: None,
                                 }),
                             },
                         )),
@@ -1353,6 +1563,7 @@ mod tests {
                                 status: Some(PartitionStatus {
                                     parquet_max_sequence_number: None,
                                     tombstone_max_sequence_number: None,
+                                    max_sequence_number: None,
                                 }),
                             },
                         )),
@@ -1433,6 +1644,7 @@ mod tests {
                                     status: Some(PartitionStatus {
                                         parquet_max_sequence_number: Some(11),
                                         tombstone_max_sequence_number: Some(12),
+                                        max_sequence_number: None,
                                     }),
                                 },
                             )),
@@ -1463,6 +1675,7 @@ mod tests {
                                     status: Some(PartitionStatus {
                                         parquet_max_sequence_number: Some(21),
                                         tombstone_max_sequence_number: Some(22),
+                                        max_sequence_number: None,
                                     }),
                                 },
                             )),
@@ -1488,6 +1701,7 @@ mod tests {
                                     status: Some(PartitionStatus {
                                         parquet_max_sequence_number: Some(31),
                                         tombstone_max_sequence_number: Some(32),
+                                        max_sequence_number: None,
                                     }),
                                 },
                             )),
@@ -1677,6 +1891,7 @@ mod tests {
                                     status: Some(PartitionStatus {
                                         parquet_max_sequence_number: Some(11),
                                         tombstone_max_sequence_number: Some(12),
+                                        max_sequence_number: None,
                                     }),
                                 },
                             )),
@@ -1748,6 +1963,8 @@ mod tests {
                 columns,
                 &Predicate::default(),
                 schema,
+                None,
+                None,
                 span,
             )
             .await
@@ -1843,6 +2060,7 @@ mod tests {
                     self.catalog.catalog(),
                     self.catalog.time_provider(),
                     self.catalog.metric_registry(),
+                    self.catalog.object_store(),
                     &Handle::current(),
                 )),
             )
@@ -1855,6 +2073,7 @@ mod tests {
             &self,
             ingester_address: Arc<str>,
             _request: IngesterQueryRequest,
+            _query_id: Option<QueryId>,
         ) -> Result<Box<dyn QueryData>, FlightClientError> {
             self.responses
                 .lock()
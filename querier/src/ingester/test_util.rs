@@ -1,9 +1,9 @@
 use super::IngesterConnection;
 use async_trait::async_trait;
-use data_types::ShardIndex;
+use data_types::{PartitionId, PartitionSnapshot, ShardIndex};
 use generated_types::influxdata::iox::ingester::v1::GetWriteInfoResponse;
 use parking_lot::Mutex;
-use std::{any::Any, sync::Arc};
+use std::{any::Any, collections::BTreeMap, sync::Arc};
 use trace::span::Span;
 
 /// IngesterConnection for testing
@@ -35,6 +35,8 @@ impl IngesterConnection for MockIngesterConnection {
         _columns: Vec<String>,
         _predicate: &predicate::Predicate,
         _expected_schema: Arc<schema::Schema>,
+        _query_id: Option<iox_query::QueryId>,
+        _snapshot: Option<&BTreeMap<PartitionId, PartitionSnapshot>>,
         _span: Option<Span>,
     ) -> super::Result<Vec<super::IngesterPartition>> {
         self.next_response
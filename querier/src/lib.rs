@@ -9,24 +9,36 @@
     clippy::clone_on_ref_ptr
 )]
 
+mod access_stats;
+mod admission;
 mod cache;
 mod chunk;
 mod database;
 mod handler;
 mod ingester;
 mod namespace;
+mod namespace_timeout;
 mod poison;
 mod query_log;
+mod rate_limit;
+mod scheduler;
 mod server;
 mod system_tables;
 mod table;
 mod tombstone;
+mod warm_up;
 
-pub use cache::CatalogCache as QuerierCatalogCache;
+pub use access_stats::TableAccessTracker;
+pub use admission::{Error as QueryAdmissionError, QueryAdmission, QueryAdmissionConfig};
+pub use cache::{
+    object_store::{DiskTierConfig, ObjectStoreCacheConfig},
+    CatalogCache as QuerierCatalogCache,
+};
 pub use chunk::QuerierChunkLoadSetting;
-pub use database::{Error as QuerierDatabaseError, QuerierDatabase};
+pub use database::{Error as QuerierDatabaseError, QuerierDatabase, QueryTimeoutConfig};
 pub use handler::{QuerierHandler, QuerierHandlerImpl};
 pub use ingester::{
+    circuit_breaker::CircuitBreakerConfig as IngesterCircuitBreakerConfig,
     create_ingester_connection_for_testing, create_ingester_connections_by_shard,
     flight_client::{
         Error as IngesterFlightClientError, FlightClient as IngesterFlightClient,
@@ -35,4 +47,7 @@ pub use ingester::{
     Error as IngesterError, IngesterConnection, IngesterConnectionImpl, IngesterPartition,
 };
 pub use namespace::QuerierNamespace;
+pub use rate_limit::QueryRateLimiter;
+pub use scheduler::{NamespaceScheduler, NamespaceSchedulerConfig};
 pub use server::QuerierServer;
+pub use warm_up::CacheWarmUpConfig;
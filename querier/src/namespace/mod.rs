@@ -1,6 +1,8 @@
 //! Namespace within the whole database.
 
 use crate::{
+    access_stats::TableAccessTracker,
+    admission::QueryAdmission,
     cache::{namespace::CachedNamespace, CatalogCache},
     chunk::ChunkAdapter,
     ingester::IngesterConnection,
@@ -9,7 +11,7 @@ use crate::{
     QuerierChunkLoadSetting,
 };
 use data_types::{NamespaceId, ParquetFileId, ShardIndex};
-use iox_query::exec::Executor;
+use iox_query::{exec::Executor, result_cache::QueryResultCache};
 use parquet_file::storage::ParquetStorage;
 use sharder::JumpHash;
 use std::{collections::HashMap, sync::Arc};
@@ -48,6 +50,9 @@ pub struct QuerierNamespace {
 
     /// Query log.
     query_log: Arc<QueryLog>,
+
+    /// Cache of the results of recent queries, if enabled.
+    result_cache: Option<Arc<QueryResultCache>>,
 }
 
 impl QuerierNamespace {
@@ -62,7 +67,11 @@ impl QuerierNamespace {
         query_log: Arc<QueryLog>,
         sharder: Arc<JumpHash<Arc<ShardIndex>>>,
         max_table_query_bytes: usize,
+        dedup_validation: bool,
         prune_metrics: Arc<PruneMetrics>,
+        query_admission: Option<Arc<QueryAdmission>>,
+        access_stats: Option<Arc<TableAccessTracker>>,
+        result_cache: Option<Arc<QueryResultCache>>,
     ) -> Self {
         let tables: HashMap<_, _> = ns
             .tables
@@ -78,7 +87,10 @@ impl QuerierNamespace {
                     chunk_adapter: Arc::clone(&chunk_adapter),
                     exec: Arc::clone(&exec),
                     max_query_bytes: max_table_query_bytes,
+                    dedup_validation,
                     prune_metrics: Arc::clone(&prune_metrics),
+                    query_admission: query_admission.clone(),
+                    access_stats: access_stats.clone(),
                 }));
 
                 (Arc::clone(table_name), table)
@@ -94,6 +106,7 @@ impl QuerierNamespace {
             exec,
             catalog_cache: Arc::clone(chunk_adapter.catalog_cache()),
             query_log,
+            result_cache,
         }
     }
 
@@ -130,7 +143,11 @@ impl QuerierNamespace {
             query_log,
             sharder,
             max_table_query_bytes,
+            false,
             prune_metrics,
+            None,
+            None,
+            None,
         )
     }
 
@@ -11,7 +11,9 @@ use data_types::NamespaceId;
 use datafusion::{
     catalog::{catalog::CatalogProvider, schema::SchemaProvider},
     datasource::TableProvider,
+    physical_plan::ExecutionPlan,
 };
+use iox_catalog::interface::Catalog;
 use iox_query::{
     exec::{ExecutionContextProvider, ExecutorType, IOxSessionContext},
     QueryChunk, QueryCompletedToken, QueryDatabase, QueryDatabaseError, QueryText, DEFAULT_SCHEMA,
@@ -56,6 +58,10 @@ impl QueryDatabase for QuerierNamespace {
         let mut chunks = table
             .chunks(
                 predicate,
+                ctx.query_id(),
+                ctx.query_completeness(),
+                ctx.query_pruning_stats(),
+                ctx.query_snapshot(),
                 ctx.span().map(|span| span.child("querier table chunks")),
             )
             .await?;
@@ -86,13 +92,20 @@ impl QueryDatabase for QuerierNamespace {
         // will be set.
         let query_log = Arc::clone(&self.query_log);
         let trace_id = ctx.span().map(|s| s.ctx.trace_id);
-        let entry = query_log.push(self.id, query_type, query_text, trace_id);
+        let entry = query_log.push(self.id, query_type, query_text, trace_id, ctx.query_id());
         QueryCompletedToken::new(move |success| query_log.set_completed(entry, success))
     }
 
     fn as_meta(&self) -> &dyn QueryDatabaseMeta {
         self
     }
+
+    fn cached_plan(&self, plan: Arc<dyn ExecutionPlan>) -> Arc<dyn ExecutionPlan> {
+        match &self.result_cache {
+            Some(result_cache) => result_cache.cached_plan(&self.name, plan),
+            None => plan,
+        }
+    }
 }
 
 pub struct QuerierCatalogProvider {
@@ -104,6 +117,9 @@ pub struct QuerierCatalogProvider {
 
     /// Query log.
     query_log: Arc<QueryLog>,
+
+    /// Catalog, for the catalog introspection tables in [`SYSTEM_SCHEMA`].
+    catalog: Arc<dyn Catalog>,
 }
 
 impl QuerierCatalogProvider {
@@ -112,6 +128,7 @@ impl QuerierCatalogProvider {
             namespace_id: namespace.id,
             tables: Arc::clone(&namespace.tables),
             query_log: Arc::clone(&namespace.query_log),
+            catalog: namespace.catalog_cache.catalog(),
         }
     }
 }
@@ -132,6 +149,7 @@ impl CatalogProvider for QuerierCatalogProvider {
             })),
             SYSTEM_SCHEMA => Some(Arc::new(SystemSchemaProvider::new(
                 Arc::clone(&self.query_log),
+                Arc::clone(&self.catalog),
                 self.namespace_id,
             ))),
             _ => None,
@@ -594,6 +612,80 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_prune_by_catalog_column_stats() {
+        test_helpers::maybe_start_logging();
+
+        let catalog = TestCatalog::new();
+
+        let ns = catalog.create_namespace("ns").await;
+        let table = ns.create_table("cpu").await;
+        let shard = ns.create_shard(1).await;
+        let partition = table.with_shard(&shard).create_partition("k").await;
+
+        table.create_column("host", ColumnType::Tag).await;
+        table.create_column("load", ColumnType::F64).await;
+        table.create_column("time", ColumnType::Time).await;
+
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol("cpu,host=a load=1 11\ncpu,host=b load=2 11")
+            .with_max_seq(2)
+            .with_min_time(11)
+            .with_max_time(11)
+            .with_file_size_bytes(100);
+        partition.create_parquet_file(builder).await;
+
+        let builder = TestParquetFileBuilder::default()
+            .with_line_protocol("cpu,host=x load=3 11\ncpu,host=z load=4 11")
+            .with_max_seq(4)
+            .with_min_time(11)
+            .with_max_time(11)
+            .with_file_size_bytes(200);
+        partition.create_parquet_file(builder).await;
+
+        let querier_namespace = Arc::new(querier_namespace(&ns).await);
+
+        assert_query(
+            &querier_namespace,
+            "SELECT * FROM cpu WHERE host = 'y'",
+            &["++", "++"],
+        )
+        .await;
+        assert_query(
+            &querier_namespace,
+            "SELECT * FROM cpu WHERE host >= 'x' ORDER BY host",
+            &[
+                "+------+------+--------------------------------+",
+                "| host | load | time                           |",
+                "+------+------+--------------------------------+",
+                "| x    | 3    | 1970-01-01T00:00:00.000000011Z |",
+                "| z    | 4    | 1970-01-01T00:00:00.000000011Z |",
+                "+------+------+--------------------------------+",
+            ],
+        )
+        .await;
+
+        // the first query pruned both files, the second one only the first file
+        let mut reporter = RawReporter::default();
+        catalog.metric_registry().report(&mut reporter);
+        assert_eq!(
+            reporter
+                .metric("query_pruner_catalog_stats_files")
+                .unwrap()
+                .observation(&[])
+                .unwrap(),
+            &Observation::U64Counter(3),
+        );
+        assert_eq!(
+            reporter
+                .metric("query_pruner_catalog_stats_bytes")
+                .unwrap()
+                .observation(&[])
+                .unwrap(),
+            &Observation::U64Counter(400),
+        );
+    }
+
     async fn assert_query(
         querier_namespace: &Arc<QuerierNamespace>,
         sql: &str,
@@ -648,7 +740,7 @@ mod tests {
         let planner = SqlQueryPlanner::default();
         let ctx = querier_namespace.new_query_context(span_ctx);
 
-        let physical_plan = planner.query(sql, &ctx).await.context(BuildSnafu)?;
+        let physical_plan = planner.query(sql, &[], &ctx).await.context(BuildSnafu)?;
 
         ctx.collect(physical_plan).await.context(RunSnafu)
     }
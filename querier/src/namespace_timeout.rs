@@ -0,0 +1,131 @@
+//! Per-namespace default query timeouts.
+//!
+//! The timeouts are stored in the catalog (see [`Namespace::query_timeout_ms`]) and re-read once
+//! they are older than the configured refresh interval.
+//!
+//! [`Namespace::query_timeout_ms`]: data_types::Namespace::query_timeout_ms
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use iox_catalog::interface::Catalog;
+use iox_time::{Time, TimeProvider};
+use observability_deps::tracing::warn;
+use parking_lot::Mutex;
+
+/// Query timeout of a namespace, as last read from the catalog.
+#[derive(Debug, Clone, Copy)]
+struct CachedTimeout {
+    timeout: Option<Duration>,
+    fetched_at: Time,
+}
+
+/// Default query timeouts, keyed by namespace.
+#[derive(Debug)]
+pub struct NamespaceQueryTimeouts {
+    catalog: Arc<dyn Catalog>,
+    time_provider: Arc<dyn TimeProvider>,
+    refresh_interval: Duration,
+    timeouts: Mutex<HashMap<Arc<str>, CachedTimeout>>,
+}
+
+impl NamespaceQueryTimeouts {
+    /// Create new lookup that re-reads the timeouts from the catalog every `refresh_interval`.
+    pub fn new(
+        catalog: Arc<dyn Catalog>,
+        time_provider: Arc<dyn TimeProvider>,
+        refresh_interval: Duration,
+    ) -> Self {
+        Self {
+            catalog,
+            time_provider,
+            refresh_interval,
+            timeouts: Default::default(),
+        }
+    }
+
+    /// Default query timeout of the given namespace.
+    ///
+    /// Returns `None` for namespaces without a timeout (or that do not exist).
+    pub async fn get(&self, namespace: &str) -> Option<Duration> {
+        let now = self.time_provider.now();
+
+        let cached = self.timeouts.lock().get(namespace).copied();
+        if let Some(cached) = cached {
+            let age = now
+                .checked_duration_since(cached.fetched_at)
+                .unwrap_or_default();
+            if age < self.refresh_interval {
+                return cached.timeout;
+            }
+        }
+
+        let mut repos = self.catalog.repositories().await;
+        let timeout = match repos.namespaces().get_by_name(namespace).await {
+            Ok(ns) => ns
+                .and_then(|ns| ns.query_timeout_ms)
+                .and_then(|ms| u64::try_from(ms).ok())
+                .filter(|ms| *ms > 0)
+                .map(Duration::from_millis),
+            Err(e) => {
+                // keep serving queries with the last known timeout
+                warn!(%e, namespace, "cannot read query timeout from catalog");
+                cached.and_then(|cached| cached.timeout)
+            }
+        };
+
+        self.timeouts.lock().insert(
+            Arc::from(namespace),
+            CachedTimeout {
+                timeout,
+                fetched_at: now,
+            },
+        );
+
+        timeout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iox_tests::util::TestCatalog;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_namespace_timeouts() {
+        let catalog = TestCatalog::new();
+        catalog.create_namespace("ns1").await;
+        catalog.create_namespace("ns2").await;
+
+        let timeouts = NamespaceQueryTimeouts::new(
+            catalog.catalog(),
+            catalog.time_provider(),
+            Duration::from_secs(60),
+        );
+        assert_eq!(timeouts.get("ns1").await, None);
+        assert_eq!(timeouts.get("unknown").await, None);
+
+        update_timeout(&catalog, "ns1", Some(1_500)).await;
+        update_timeout(&catalog, "ns2", Some(0)).await;
+
+        // the cached timeout is used until it expires
+        assert_eq!(timeouts.get("ns1").await, None);
+
+        catalog.mock_time_provider().inc(Duration::from_secs(60));
+        assert_eq!(
+            timeouts.get("ns1").await,
+            Some(Duration::from_millis(1_500))
+        );
+        assert_eq!(timeouts.get("ns2").await, None);
+    }
+
+    async fn update_timeout(catalog: &TestCatalog, namespace: &str, timeout_ms: Option<i64>) {
+        catalog
+            .catalog()
+            .repositories()
+            .await
+            .namespaces()
+            .update_query_timeout(namespace, timeout_ms)
+            .await
+            .unwrap();
+    }
+}
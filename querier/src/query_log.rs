@@ -1,7 +1,7 @@
 //! Ring buffer of queries that have been run with some brief information
 
 use data_types::NamespaceId;
-use iox_query::QueryText;
+use iox_query::{QueryId, QueryText};
 use iox_time::{Time, TimeProvider};
 use parking_lot::Mutex;
 use std::{
@@ -28,6 +28,9 @@ pub struct QueryLogEntry {
     /// The trace ID if any
     pub trace_id: Option<TraceId>,
 
+    /// The query ID if any
+    pub query_id: Option<QueryId>,
+
     /// Time at which the query was run
     pub issue_time: Time,
 
@@ -44,6 +47,7 @@ impl std::fmt::Debug for QueryLogEntry {
         f.debug_struct("QueryLogEntry")
             .field("query_type", &self.query_type)
             .field("query_text", &self.query_text.to_string())
+            .field("query_id", &self.query_id)
             .field("issue_time", &self.issue_time)
             .field("query_completed_duration", &self.query_completed_duration)
             .field("success", &self.success)
@@ -58,6 +62,7 @@ impl QueryLogEntry {
         query_type: String,
         query_text: QueryText,
         trace_id: Option<TraceId>,
+        query_id: Option<QueryId>,
         issue_time: Time,
     ) -> Self {
         Self {
@@ -65,6 +70,7 @@ impl QueryLogEntry {
             query_type,
             query_text,
             trace_id,
+            query_id,
             issue_time,
             query_completed_duration: UNCOMPLETED_DURATION.into(),
             success: atomic::AtomicBool::new(false),
@@ -124,12 +130,14 @@ impl QueryLog {
         query_type: impl Into<String>,
         query_text: QueryText,
         trace_id: Option<TraceId>,
+        query_id: Option<QueryId>,
     ) -> Arc<QueryLogEntry> {
         let entry = Arc::new(QueryLogEntry::new(
             namespace_id,
             query_type.into(),
             query_text,
             trace_id,
+            query_id,
             self.time_provider.now(),
         ));
 
@@ -175,6 +183,7 @@ mod test_super {
             "sql".into(),
             Box::new("SELECT 1"),
             None,
+            None,
             time_provider.now(),
         ));
         // query has not completed
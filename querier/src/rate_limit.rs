@@ -0,0 +1,183 @@
+//! Per-namespace query rate limiting.
+//!
+//! The limits are stored in the catalog (see [`Namespace::query_rate_limit`]) and re-read once
+//! they are older than the configured refresh interval. Every namespace has its own token bucket
+//! that allows bursts of up to one second worth of queries.
+//!
+//! [`Namespace::query_rate_limit`]: data_types::Namespace::query_rate_limit
+use std::{collections::HashMap, num::NonZeroU32, sync::Arc, time::Duration};
+
+use iox_catalog::interface::Catalog;
+use iox_time::{Time, TimeProvider};
+use metric::U64Counter;
+use observability_deps::tracing::{debug, warn};
+use parking_lot::Mutex;
+use tracker::{RateLimited, RateLimiter};
+
+/// Query rate limit of a namespace, as last read from the catalog.
+#[derive(Debug, Clone, Copy)]
+struct CachedLimit {
+    limit: Option<NonZeroU32>,
+    fetched_at: Time,
+}
+
+/// Rate limiter for queries, keyed by namespace.
+#[derive(Debug)]
+pub struct QueryRateLimiter {
+    catalog: Arc<dyn Catalog>,
+    time_provider: Arc<dyn TimeProvider>,
+    refresh_interval: Duration,
+    limits: Mutex<HashMap<Arc<str>, CachedLimit>>,
+    rate_limiter: RateLimiter,
+    metric_rejected: U64Counter,
+}
+
+impl QueryRateLimiter {
+    /// Create new rate limiter that re-reads the limits from the catalog every `refresh_interval`.
+    pub fn new(
+        catalog: Arc<dyn Catalog>,
+        time_provider: Arc<dyn TimeProvider>,
+        refresh_interval: Duration,
+        metric_registry: &metric::Registry,
+    ) -> Self {
+        let metric_rejected = metric_registry
+            .register_metric::<U64Counter>(
+                "querier_query_rate_limited",
+                "Number of queries that were rejected by the per-namespace query rate limit",
+            )
+            .recorder(&[]);
+
+        Self {
+            catalog,
+            rate_limiter: RateLimiter::new(Arc::clone(&time_provider)),
+            time_provider,
+            refresh_interval,
+            limits: Default::default(),
+            metric_rejected,
+        }
+    }
+
+    /// Check if another query against the given namespace is permitted.
+    ///
+    /// Namespaces without a limit (or that do not exist) are never rejected.
+    pub async fn check(&self, namespace: &str) -> Result<(), RateLimited> {
+        let limit = match self.limit(namespace).await {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+
+        self.rate_limiter
+            .try_acquire(namespace, limit)
+            .map_err(|e| {
+                debug!(namespace, retry_after=?e.retry_after, "query rate limited");
+                self.metric_rejected.inc(1);
+                e
+            })
+    }
+
+    async fn limit(&self, namespace: &str) -> Option<NonZeroU32> {
+        let now = self.time_provider.now();
+
+        let cached = self.limits.lock().get(namespace).copied();
+        if let Some(cached) = cached {
+            let age = now
+                .checked_duration_since(cached.fetched_at)
+                .unwrap_or_default();
+            if age < self.refresh_interval {
+                return cached.limit;
+            }
+        }
+
+        let mut repos = self.catalog.repositories().await;
+        let limit = match repos.namespaces().get_by_name(namespace).await {
+            Ok(ns) => ns
+                .and_then(|ns| ns.query_rate_limit)
+                .and_then(|limit| u32::try_from(limit).ok())
+                .and_then(NonZeroU32::new),
+            Err(e) => {
+                // keep serving queries with the last known limit
+                warn!(%e, namespace, "cannot read query rate limit from catalog");
+                cached.and_then(|cached| cached.limit)
+            }
+        };
+
+        self.limits.lock().insert(
+            Arc::from(namespace),
+            CachedLimit {
+                limit,
+                fetched_at: now,
+            },
+        );
+
+        limit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iox_tests::util::TestCatalog;
+    use metric::{Attributes, Metric};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let catalog = TestCatalog::new();
+        catalog.create_namespace("ns1").await;
+        catalog.create_namespace("ns2").await;
+
+        let limiter = QueryRateLimiter::new(
+            catalog.catalog(),
+            catalog.time_provider(),
+            Duration::from_secs(60),
+            &catalog.metric_registry(),
+        );
+
+        // no limits configured
+        for _ in 0..10 {
+            limiter.check("ns1").await.unwrap();
+            limiter.check("unknown").await.unwrap();
+        }
+
+        update_limit(&catalog, "ns1", Some(1)).await;
+        update_limit(&catalog, "ns2", Some(1)).await;
+
+        // the cached limit is used until it expires
+        limiter.check("ns1").await.unwrap();
+        limiter.check("ns1").await.unwrap();
+        assert_eq!(get_rejected(&catalog.metric_registry()), 0);
+
+        catalog.mock_time_provider().inc(Duration::from_secs(60));
+        limiter.check("ns1").await.unwrap();
+        let err = limiter.check("ns1").await.unwrap_err();
+        assert_eq!(err.retry_after, Duration::from_secs(1));
+        assert_eq!(get_rejected(&catalog.metric_registry()), 1);
+
+        // namespaces are limited independently
+        limiter.check("ns2").await.unwrap();
+        limiter.check("ns2").await.unwrap_err();
+
+        catalog.mock_time_provider().inc(Duration::from_secs(1));
+        limiter.check("ns1").await.unwrap();
+    }
+
+    async fn update_limit(catalog: &TestCatalog, namespace: &str, limit: Option<i32>) {
+        catalog
+            .catalog()
+            .repositories()
+            .await
+            .namespaces()
+            .update_query_rate_limit(namespace, limit)
+            .await
+            .unwrap();
+    }
+
+    fn get_rejected(metric_registry: &metric::Registry) -> u64 {
+        metric_registry
+            .get_instrument::<Metric<U64Counter>>("querier_query_rate_limited")
+            .unwrap()
+            .get_observer(&Attributes::from(&[]))
+            .unwrap()
+            .fetch()
+    }
+}
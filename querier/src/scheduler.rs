@@ -0,0 +1,210 @@
+//! Per-namespace query concurrency limits.
+//!
+//! All queries share the global query semaphore (see `--max-concurrent-queries`), which hands out
+//! permits in FIFO order. Without further limits, a single namespace issuing many heavy queries
+//! fills the queue of that semaphore and all other namespaces wait behind it.
+//!
+//! The [`NamespaceScheduler`] therefore limits the number of queries every namespace may run (or
+//! wait for the global semaphore) at once. Further queries of the namespace wait in a queue of
+//! their own, so that the global queue holds at most a few queries per namespace and namespaces
+//! are served in a round-robin fashion. Queries that would exceed the queue limit of their
+//! namespace are rejected right away.
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use metric::{Metric, U64Counter};
+use observability_deps::tracing::debug;
+use parking_lot::Mutex;
+use service_common::QueryQueueFull;
+use trace::span::Span;
+use tracker::{
+    AsyncSemaphoreMetrics, InstrumentedAsyncOwnedSemaphorePermit, InstrumentedAsyncSemaphore,
+};
+
+/// Configuration of the per-namespace query limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NamespaceSchedulerConfig {
+    /// Maximum number of queries of a single namespace that run at the same time.
+    pub max_concurrent_queries: usize,
+
+    /// Maximum number of queries of a single namespace that wait for a free slot.
+    pub max_queued_queries: usize,
+}
+
+/// Query slots of a single namespace.
+#[derive(Debug)]
+struct NamespaceSlots {
+    semaphore: Arc<InstrumentedAsyncSemaphore>,
+    queued: AtomicUsize,
+    metric_rejected: U64Counter,
+}
+
+/// Scheduler that limits the concurrent and queued queries per namespace, see the
+/// [module documentation](self).
+#[derive(Debug)]
+pub struct NamespaceScheduler {
+    config: NamespaceSchedulerConfig,
+    namespaces: Mutex<HashMap<Arc<str>, Arc<NamespaceSlots>>>,
+    metric_registry: Arc<metric::Registry>,
+    metric_rejected: Metric<U64Counter>,
+}
+
+impl NamespaceScheduler {
+    /// Create new scheduler.
+    ///
+    /// The time queries wait for a slot of their namespace is recorded by the
+    /// `iox_async_semaphore_acquire_duration` metric with the `namespace` attribute.
+    pub fn new(config: NamespaceSchedulerConfig, metric_registry: Arc<metric::Registry>) -> Self {
+        let metric_rejected = metric_registry.register_metric::<U64Counter>(
+            "querier_namespace_query_rejected",
+            "Number of queries that were rejected because the queue of their namespace was full",
+        );
+
+        Self {
+            config,
+            namespaces: Default::default(),
+            metric_registry,
+            metric_rejected,
+        }
+    }
+
+    /// Acquire a query slot of `namespace`, waiting for one to become available if necessary.
+    ///
+    /// The slot is released once the returned permit is dropped.
+    pub async fn acquire(
+        &self,
+        namespace: &str,
+        span: Option<Span>,
+    ) -> Result<InstrumentedAsyncOwnedSemaphorePermit, QueryQueueFull> {
+        let slots = self.slots(namespace);
+
+        // Count this query as queued while it waits for a slot. The check is racy (a slot may be
+        // released right after it), which is fine for a safety limit.
+        let queued = slots.queued.fetch_add(1, Ordering::AcqRel);
+        let _queued = QueuedGuard(&slots.queued);
+        if queued >= self.config.max_queued_queries && slots.semaphore.available_permits() == 0 {
+            debug!(
+                namespace,
+                queued, "namespace query queue full, rejecting query"
+            );
+            slots.metric_rejected.inc(1);
+            return Err(QueryQueueFull {
+                max_queued: self.config.max_queued_queries,
+            });
+        }
+
+        Ok(slots
+            .semaphore
+            .acquire_owned(span)
+            .await
+            .expect("semaphore should not be closed by anyone"))
+    }
+
+    fn slots(&self, namespace: &str) -> Arc<NamespaceSlots> {
+        let mut namespaces = self.namespaces.lock();
+        if let Some(slots) = namespaces.get(namespace) {
+            return Arc::clone(slots);
+        }
+
+        let namespace_attr = Cow::Owned(namespace.to_owned());
+        let semaphore_metrics = Arc::new(AsyncSemaphoreMetrics::new(
+            &self.metric_registry,
+            [
+                ("semaphore", Cow::Borrowed("namespace_query_execution")),
+                ("namespace", namespace_attr.clone()),
+            ],
+        ));
+        let slots = Arc::new(NamespaceSlots {
+            semaphore: Arc::new(
+                semaphore_metrics.new_semaphore(self.config.max_concurrent_queries),
+            ),
+            queued: AtomicUsize::new(0),
+            metric_rejected: self
+                .metric_rejected
+                .recorder([("namespace", namespace_attr)]),
+        });
+        namespaces.insert(Arc::from(namespace), Arc::clone(&slots));
+
+        slots
+    }
+}
+
+/// Decrements the number of queued queries of a namespace on drop, i.e. also when the query is
+/// cancelled while it waits.
+struct QueuedGuard<'a>(&'a AtomicUsize);
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use metric::{Attributes, DurationHistogram};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scheduler() {
+        let metric_registry = Arc::new(metric::Registry::new());
+        let scheduler = NamespaceScheduler::new(
+            NamespaceSchedulerConfig {
+                max_concurrent_queries: 1,
+                max_queued_queries: 1,
+            },
+            Arc::clone(&metric_registry),
+        );
+
+        let permit = scheduler.acquire("ns1", None).await.unwrap();
+
+        // the second query of the namespace waits for the first one
+        let mut waiting = Box::pin(scheduler.acquire("ns1", None));
+        assert!(
+            tokio::time::timeout(Duration::from_millis(10), &mut waiting)
+                .await
+                .is_err()
+        );
+
+        // ...and the queue of the namespace is now full
+        let err = scheduler.acquire("ns1", None).await.unwrap_err();
+        assert_eq!(err, QueryQueueFull { max_queued: 1 });
+
+        // other namespaces are not affected
+        let _other = scheduler.acquire("ns2", None).await.unwrap();
+
+        drop(permit);
+        let _permit = waiting.await.unwrap();
+
+        let rejected = metric_registry
+            .get_instrument::<Metric<U64Counter>>("querier_namespace_query_rejected")
+            .unwrap();
+        let rejected = |namespace: &'static str| {
+            rejected
+                .get_observer(&Attributes::from(&[("namespace", namespace)]))
+                .map(|observer| observer.fetch())
+                .unwrap_or_default()
+        };
+        assert_eq!(rejected("ns1"), 1);
+        assert_eq!(rejected("ns2"), 0);
+
+        let wait_duration = metric_registry
+            .get_instrument::<Metric<DurationHistogram>>("iox_async_semaphore_acquire_duration")
+            .unwrap()
+            .get_observer(&Attributes::from(&[
+                ("semaphore", "namespace_query_execution"),
+                ("namespace", "ns1"),
+            ]))
+            .unwrap()
+            .fetch();
+        assert_eq!(wait_duration.sample_count(), 2);
+    }
+}
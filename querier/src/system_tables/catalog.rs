@@ -0,0 +1,463 @@
+//! System tables that describe the layout of a namespace in the catalog: `system.parquet_files`,
+//! `system.partitions` and `system.columns`.
+//!
+//! The tables are read from the catalog whenever they are scanned (bypassing the querier caches),
+//! so they always reflect the current state of the namespace.
+use crate::system_tables::{BatchIterator, IoxSystemTable};
+use arrow::{
+    array::{ArrayRef, Int16Array, Int64Array, StringArray, TimestampNanosecondArray},
+    datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit},
+    error::{ArrowError, Result},
+    record_batch::RecordBatch,
+};
+use async_trait::async_trait;
+use data_types::{ColumnType, NamespaceId, Partition, PartitionId, TableId};
+use iox_catalog::interface::Catalog;
+use std::{collections::HashMap, sync::Arc};
+
+/// Implementation of the `system.parquet_files` table: all files of the namespace that are not
+/// marked for deletion.
+#[derive(Debug)]
+pub(super) struct ParquetFilesTable {
+    schema: SchemaRef,
+    catalog: Arc<dyn Catalog>,
+    namespace_id: NamespaceId,
+}
+
+impl ParquetFilesTable {
+    pub(super) fn new(catalog: Arc<dyn Catalog>, namespace_id: NamespaceId) -> Self {
+        Self {
+            schema: parquet_files_schema(),
+            catalog,
+            namespace_id,
+        }
+    }
+}
+
+#[async_trait]
+impl IoxSystemTable for ParquetFilesTable {
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+
+    async fn scan(&self, batch_size: usize) -> Result<BatchIterator> {
+        let mut repos = self.catalog.repositories().await;
+        let table_names =
+            table_names(repos.tables().list_by_namespace_id(self.namespace_id).await)?;
+        let partitions = partitions(
+            repos
+                .partitions()
+                .list_by_namespace(self.namespace_id)
+                .await,
+        )?;
+        let mut files = repos
+            .parquet_files()
+            .list_by_namespace_not_to_delete(self.namespace_id)
+            .await
+            .map_err(catalog_error)?;
+        files.sort_by_key(|f| f.id);
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from_iter_values(
+                files.iter().map(|f| f.id.get()),
+            )),
+            Arc::new(
+                files
+                    .iter()
+                    .map(|f| table_names.get(&f.table_id).map(String::as_str))
+                    .collect::<StringArray>(),
+            ),
+            Arc::new(Int64Array::from_iter_values(
+                files.iter().map(|f| f.partition_id.get()),
+            )),
+            Arc::new(
+                files
+                    .iter()
+                    .map(|f| {
+                        partitions
+                            .get(&f.partition_id)
+                            .map(|p| p.partition_key.to_string())
+                    })
+                    .collect::<StringArray>(),
+            ),
+            Arc::new(Int64Array::from_iter_values(
+                files.iter().map(|f| f.shard_id.get()),
+            )),
+            Arc::new(Int16Array::from_iter_values(
+                files.iter().map(|f| f.compaction_level as i16),
+            )),
+            Arc::new(TimestampNanosecondArray::from_iter_values(
+                files.iter().map(|f| f.min_time.get()),
+            )),
+            Arc::new(TimestampNanosecondArray::from_iter_values(
+                files.iter().map(|f| f.max_time.get()),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                files.iter().map(|f| f.row_count),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                files.iter().map(|f| f.file_size_bytes),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                files.iter().map(|f| f.max_sequence_number.get()),
+            )),
+            Arc::new(TimestampNanosecondArray::from_iter_values(
+                files.iter().map(|f| f.created_at.get()),
+            )),
+        ];
+
+        batches(self.schema(), columns, batch_size)
+    }
+}
+
+fn parquet_files_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("table_name", DataType::Utf8, true),
+        Field::new("partition_id", DataType::Int64, false),
+        Field::new("partition_key", DataType::Utf8, true),
+        Field::new("shard_id", DataType::Int64, false),
+        Field::new("compaction_level", DataType::Int16, false),
+        Field::new(
+            "min_time",
+            DataType::Timestamp(TimeUnit::Nanosecond, None),
+            false,
+        ),
+        Field::new(
+            "max_time",
+            DataType::Timestamp(TimeUnit::Nanosecond, None),
+            false,
+        ),
+        Field::new("row_count", DataType::Int64, false),
+        Field::new("file_size_bytes", DataType::Int64, false),
+        Field::new("max_sequence_number", DataType::Int64, false),
+        Field::new(
+            "created_at",
+            DataType::Timestamp(TimeUnit::Nanosecond, None),
+            false,
+        ),
+    ]))
+}
+
+/// Implementation of the `system.partitions` table: all partitions of the namespace with the
+/// number and size of their files that are not marked for deletion.
+#[derive(Debug)]
+pub(super) struct PartitionsTable {
+    schema: SchemaRef,
+    catalog: Arc<dyn Catalog>,
+    namespace_id: NamespaceId,
+}
+
+impl PartitionsTable {
+    pub(super) fn new(catalog: Arc<dyn Catalog>, namespace_id: NamespaceId) -> Self {
+        Self {
+            schema: partitions_schema(),
+            catalog,
+            namespace_id,
+        }
+    }
+}
+
+/// Number, size and rows of the files of a partition
+#[derive(Debug, Default, Clone, Copy)]
+struct FileStats {
+    count: i64,
+    size_bytes: i64,
+    row_count: i64,
+}
+
+#[async_trait]
+impl IoxSystemTable for PartitionsTable {
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+
+    async fn scan(&self, batch_size: usize) -> Result<BatchIterator> {
+        let mut repos = self.catalog.repositories().await;
+        let table_names =
+            table_names(repos.tables().list_by_namespace_id(self.namespace_id).await)?;
+        let mut partitions = repos
+            .partitions()
+            .list_by_namespace(self.namespace_id)
+            .await
+            .map_err(catalog_error)?;
+        partitions.sort_by_key(|p| p.id);
+
+        let mut file_stats: HashMap<PartitionId, FileStats> = HashMap::new();
+        for file in repos
+            .parquet_files()
+            .list_by_namespace_not_to_delete(self.namespace_id)
+            .await
+            .map_err(catalog_error)?
+        {
+            let stats = file_stats.entry(file.partition_id).or_default();
+            stats.count += 1;
+            stats.size_bytes += file.file_size_bytes;
+            stats.row_count += file.row_count;
+        }
+        let stats: Vec<_> = partitions
+            .iter()
+            .map(|p| file_stats.get(&p.id).copied().unwrap_or_default())
+            .collect();
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(Int64Array::from_iter_values(
+                partitions.iter().map(|p| p.id.get()),
+            )),
+            Arc::new(
+                partitions
+                    .iter()
+                    .map(|p| table_names.get(&p.table_id).map(String::as_str))
+                    .collect::<StringArray>(),
+            ),
+            Arc::new(StringArray::from_iter_values(
+                partitions.iter().map(|p| p.partition_key.to_string()),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                partitions.iter().map(|p| p.shard_id.get()),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                partitions.iter().map(|p| p.sort_key.join(",")),
+            )),
+            Arc::new(Int64Array::from_iter_values(stats.iter().map(|s| s.count))),
+            Arc::new(Int64Array::from_iter_values(
+                stats.iter().map(|s| s.size_bytes),
+            )),
+            Arc::new(Int64Array::from_iter_values(
+                stats.iter().map(|s| s.row_count),
+            )),
+        ];
+
+        batches(self.schema(), columns, batch_size)
+    }
+}
+
+fn partitions_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("table_name", DataType::Utf8, true),
+        Field::new("partition_key", DataType::Utf8, false),
+        Field::new("shard_id", DataType::Int64, false),
+        Field::new("sort_key", DataType::Utf8, false),
+        Field::new("file_count", DataType::Int64, false),
+        Field::new("file_size_bytes", DataType::Int64, false),
+        Field::new("row_count", DataType::Int64, false),
+    ]))
+}
+
+/// Implementation of the `system.columns` table: all columns of all tables of the namespace.
+#[derive(Debug)]
+pub(super) struct ColumnsTable {
+    schema: SchemaRef,
+    catalog: Arc<dyn Catalog>,
+    namespace_id: NamespaceId,
+}
+
+impl ColumnsTable {
+    pub(super) fn new(catalog: Arc<dyn Catalog>, namespace_id: NamespaceId) -> Self {
+        Self {
+            schema: columns_schema(),
+            catalog,
+            namespace_id,
+        }
+    }
+}
+
+#[async_trait]
+impl IoxSystemTable for ColumnsTable {
+    fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+
+    async fn scan(&self, batch_size: usize) -> Result<BatchIterator> {
+        let mut repos = self.catalog.repositories().await;
+        let table_names =
+            table_names(repos.tables().list_by_namespace_id(self.namespace_id).await)?;
+        let mut columns = repos
+            .columns()
+            .list_by_namespace_id(self.namespace_id)
+            .await
+            .map_err(catalog_error)?;
+        columns.sort_by_key(|c| c.id);
+
+        let arrays: Vec<ArrayRef> = vec![
+            Arc::new(
+                columns
+                    .iter()
+                    .map(|c| table_names.get(&c.table_id).map(String::as_str))
+                    .collect::<StringArray>(),
+            ),
+            Arc::new(Int64Array::from_iter_values(
+                columns.iter().map(|c| c.id.get()),
+            )),
+            Arc::new(StringArray::from_iter_values(
+                columns.iter().map(|c| c.name.as_str()),
+            )),
+            Arc::new(
+                columns
+                    .iter()
+                    .map(|c| ColumnType::try_from(c.column_type).ok().map(|t| t.as_str()))
+                    .collect::<StringArray>(),
+            ),
+        ];
+
+        batches(self.schema(), arrays, batch_size)
+    }
+}
+
+fn columns_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("table_name", DataType::Utf8, true),
+        Field::new("column_id", DataType::Int64, false),
+        Field::new("column_name", DataType::Utf8, false),
+        Field::new("column_type", DataType::Utf8, true),
+    ]))
+}
+
+fn catalog_error(e: iox_catalog::interface::Error) -> ArrowError {
+    ArrowError::ExternalError(Box::new(e))
+}
+
+/// Table names by table ID
+fn table_names(
+    tables: iox_catalog::interface::Result<Vec<data_types::Table>>,
+) -> Result<HashMap<TableId, String>> {
+    Ok(tables
+        .map_err(catalog_error)?
+        .into_iter()
+        .map(|t| (t.id, t.name))
+        .collect())
+}
+
+/// Partitions by partition ID
+fn partitions(
+    partitions: iox_catalog::interface::Result<Vec<Partition>>,
+) -> Result<HashMap<PartitionId, Partition>> {
+    Ok(partitions
+        .map_err(catalog_error)?
+        .into_iter()
+        .map(|p| (p.id, p))
+        .collect())
+}
+
+/// Split `columns` into batches of at most `batch_size` rows.
+fn batches(schema: SchemaRef, columns: Vec<ArrayRef>, batch_size: usize) -> Result<BatchIterator> {
+    let batch = RecordBatch::try_new(schema, columns)?;
+    let num_rows = batch.num_rows();
+
+    Ok(Box::new((0..num_rows).step_by(batch_size.max(1)).map(
+        move |offset| Ok(batch.slice(offset, batch_size.min(num_rows - offset))),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_util::assert_batches_eq;
+    use data_types::{ColumnType, CompactionLevel};
+    use iox_tests::util::{TestCatalog, TestParquetFileBuilder};
+
+    #[tokio::test]
+    async fn test_catalog_tables() {
+        let catalog = TestCatalog::new();
+        let ns = catalog.create_namespace("ns").await;
+        let shard = ns.create_shard(1).await;
+        let table = ns.create_table("cpu").await;
+        table.create_column("host", ColumnType::Tag).await;
+        table.create_column("time", ColumnType::Time).await;
+        let partition = table
+            .with_shard(&shard)
+            .create_partition_with_sort_key("a", &["host", "time"])
+            .await;
+        // a partition without files
+        table.with_shard(&shard).create_partition("b").await;
+
+        let builder = TestParquetFileBuilder::default()
+            .with_min_time(1)
+            .with_max_time(5)
+            .with_row_count(2)
+            .with_file_size_bytes(100)
+            .with_max_seq(1)
+            .with_compaction_level(CompactionLevel::Initial)
+            .with_creation_time(10);
+        partition.create_parquet_file_catalog_record(builder).await;
+        let builder = TestParquetFileBuilder::default()
+            .with_min_time(3)
+            .with_max_time(8)
+            .with_row_count(5)
+            .with_file_size_bytes(200)
+            .with_max_seq(2)
+            .with_compaction_level(CompactionLevel::FileNonOverlapped)
+            .with_creation_time(20);
+        partition.create_parquet_file_catalog_record(builder).await;
+
+        // a file of another namespace
+        let other = catalog.create_namespace("other").await;
+        other
+            .create_table("mem")
+            .await
+            .with_shard(&other.create_shard(1).await)
+            .create_partition("a")
+            .await
+            .create_parquet_file_catalog_record(TestParquetFileBuilder::default())
+            .await;
+
+        let table = ParquetFilesTable::new(catalog.catalog(), ns.namespace.id);
+        let batches = table
+            .scan(1)
+            .await
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(batches.len(), 2);
+        assert_batches_eq!(
+            &[
+                "+----+------------+--------------+---------------+----------+------------------+--------------------------------+--------------------------------+-----------+-----------------+---------------------+--------------------------------+",
+                "| id | table_name | partition_id | partition_key | shard_id | compaction_level | min_time                       | max_time                       | row_count | file_size_bytes | max_sequence_number | created_at                     |",
+                "+----+------------+--------------+---------------+----------+------------------+--------------------------------+--------------------------------+-----------+-----------------+---------------------+--------------------------------+",
+                "| 1  | cpu        | 1            | a             | 1        | 0                | 1970-01-01T00:00:00.000000001Z | 1970-01-01T00:00:00.000000005Z | 2         | 100             | 1                   | 1970-01-01T00:00:00.000000010Z |",
+                "| 2  | cpu        | 1            | a             | 1        | 1                | 1970-01-01T00:00:00.000000003Z | 1970-01-01T00:00:00.000000008Z | 5         | 200             | 2                   | 1970-01-01T00:00:00.000000020Z |",
+                "+----+------------+--------------+---------------+----------+------------------+--------------------------------+--------------------------------+-----------+-----------------+---------------------+--------------------------------+",
+            ],
+            &batches
+        );
+
+        let table = PartitionsTable::new(catalog.catalog(), ns.namespace.id);
+        let batches = table
+            .scan(10)
+            .await
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_batches_eq!(
+            &[
+                "+----+------------+---------------+----------+-----------+------------+-----------------+-----------+",
+                "| id | table_name | partition_key | shard_id | sort_key  | file_count | file_size_bytes | row_count |",
+                "+----+------------+---------------+----------+-----------+------------+-----------------+-----------+",
+                "| 1  | cpu        | a             | 1        | host,time | 2          | 300             | 7         |",
+                "| 2  | cpu        | b             | 1        |           | 0          | 0               | 0         |",
+                "+----+------------+---------------+----------+-----------+------------+-----------------+-----------+",
+            ],
+            &batches
+        );
+
+        let table = ColumnsTable::new(catalog.catalog(), ns.namespace.id);
+        let batches = table
+            .scan(10)
+            .await
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_batches_eq!(
+            &[
+                "+------------+-----------+-------------+-------------+",
+                "| table_name | column_id | column_name | column_type |",
+                "+------------+-----------+-------------+-------------+",
+                "| cpu        | 1         | host        | tag         |",
+                "| cpu        | 2         | time        | time        |",
+                "+------------+-----------+-------------+-------------+",
+            ],
+            &batches
+        );
+    }
+}
@@ -13,6 +13,8 @@ use datafusion::{
         SendableRecordBatchStream, Statistics,
     },
 };
+use futures::{stream::BoxStream, StreamExt, TryStreamExt};
+use iox_catalog::interface::Catalog;
 use std::{
     any::Any,
     pin::Pin,
@@ -20,25 +22,61 @@ use std::{
     task::{Context, Poll},
 };
 
+mod catalog;
 mod queries;
 
 pub const SYSTEM_SCHEMA: &str = "system";
 
 const QUERIES_TABLE: &str = "queries";
+const PARQUET_FILES_TABLE: &str = "parquet_files";
+const PARTITIONS_TABLE: &str = "partitions";
+const COLUMNS_TABLE: &str = "columns";
 
-const ALL_SYSTEM_TABLES: &[&str] = &[QUERIES_TABLE];
+const ALL_SYSTEM_TABLES: &[&str] = &[
+    QUERIES_TABLE,
+    PARQUET_FILES_TABLE,
+    PARTITIONS_TABLE,
+    COLUMNS_TABLE,
+];
 
 pub struct SystemSchemaProvider {
     queries: Arc<dyn TableProvider>,
+    parquet_files: Arc<dyn TableProvider>,
+    partitions: Arc<dyn TableProvider>,
+    columns: Arc<dyn TableProvider>,
 }
 
 impl SystemSchemaProvider {
-    pub fn new(query_log: Arc<QueryLog>, namespace_id: NamespaceId) -> Self {
+    pub fn new(
+        query_log: Arc<QueryLog>,
+        catalog: Arc<dyn Catalog>,
+        namespace_id: NamespaceId,
+    ) -> Self {
         let queries = Arc::new(SystemTableProvider {
             table: Arc::new(queries::QueriesTable::new(query_log, Some(namespace_id))),
         });
+        let parquet_files = Arc::new(SystemTableProvider {
+            table: Arc::new(catalog::ParquetFilesTable::new(
+                Arc::clone(&catalog),
+                namespace_id,
+            )),
+        });
+        let partitions = Arc::new(SystemTableProvider {
+            table: Arc::new(catalog::PartitionsTable::new(
+                Arc::clone(&catalog),
+                namespace_id,
+            )),
+        });
+        let columns = Arc::new(SystemTableProvider {
+            table: Arc::new(catalog::ColumnsTable::new(catalog, namespace_id)),
+        });
 
-        Self { queries }
+        Self {
+            queries,
+            parquet_files,
+            partitions,
+            columns,
+        }
     }
 }
 
@@ -57,6 +95,9 @@ impl SchemaProvider for SystemSchemaProvider {
     fn table(&self, name: &str) -> Option<Arc<dyn TableProvider>> {
         match name {
             QUERIES_TABLE => Some(Arc::clone(&self.queries)),
+            PARQUET_FILES_TABLE => Some(Arc::clone(&self.parquet_files)),
+            PARTITIONS_TABLE => Some(Arc::clone(&self.partitions)),
+            COLUMNS_TABLE => Some(Arc::clone(&self.columns)),
             _ => None,
         }
     }
@@ -71,12 +112,13 @@ impl SchemaProvider for SystemSchemaProvider {
 type BatchIterator = Box<dyn Iterator<Item = ArrowResult<RecordBatch>> + Send + Sync>;
 
 /// The minimal thing that a system table needs to implement
+#[async_trait]
 trait IoxSystemTable: Send + Sync {
     /// Produce the schema from this system table
     fn schema(&self) -> SchemaRef;
 
     /// Get the contents of the system table
-    fn scan(&self, batch_size: usize) -> ArrowResult<BatchIterator>;
+    async fn scan(&self, batch_size: usize) -> ArrowResult<BatchIterator>;
 }
 
 /// Adapter that makes any `IoxSystemTable` a DataFusion `TableProvider`
@@ -171,9 +213,16 @@ impl<T: IoxSystemTable + 'static> ExecutionPlan for SystemTableExecutionPlan<T>
     ) -> DataFusionResult<SendableRecordBatchStream> {
         let batch_size = context.session_config().batch_size();
 
+        // the contents are only read when the stream is polled
+        let table = Arc::clone(&self.table);
+        let batches = futures::stream::once(async move { table.scan(batch_size).await })
+            .map_ok(futures::stream::iter)
+            .try_flatten()
+            .boxed();
+
         Ok(Box::pin(SystemTableStream {
             projected_schema: Arc::clone(&self.projected_schema),
-            batches: self.table.scan(batch_size)?,
+            batches,
             projection: self.projection.clone(),
         }))
     }
@@ -186,7 +235,7 @@ impl<T: IoxSystemTable + 'static> ExecutionPlan for SystemTableExecutionPlan<T>
 struct SystemTableStream {
     projected_schema: SchemaRef,
     projection: Option<Vec<usize>>,
-    batches: BatchIterator,
+    batches: BoxStream<'static, ArrowResult<RecordBatch>>,
 }
 
 impl RecordBatchStream for SystemTableStream {
@@ -198,12 +247,14 @@ impl RecordBatchStream for SystemTableStream {
 impl futures::Stream for SystemTableStream {
     type Item = ArrowResult<RecordBatch>;
 
-    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Poll::Ready(self.batches.next().map(|maybe_batch| {
-            maybe_batch.and_then(|batch| match &self.projection {
-                Some(projection) => batch.project(projection),
-                None => Ok(batch),
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.batches.poll_next_unpin(cx).map(|maybe_batch| {
+            maybe_batch.map(|maybe_batch| {
+                maybe_batch.and_then(|batch| match &self.projection {
+                    Some(projection) => batch.project(projection),
+                    None => Ok(batch),
+                })
             })
-        }))
+        })
     }
 }
@@ -11,6 +11,7 @@ use arrow::{
     error::Result,
     record_batch::RecordBatch,
 };
+use async_trait::async_trait;
 use data_types::NamespaceId;
 use observability_deps::tracing::error;
 use std::{collections::VecDeque, sync::Arc};
@@ -33,12 +34,13 @@ impl QueriesTable {
     }
 }
 
+#[async_trait]
 impl IoxSystemTable for QueriesTable {
     fn schema(&self) -> SchemaRef {
         Arc::clone(&self.schema)
     }
 
-    fn scan(&self, batch_size: usize) -> Result<BatchIterator> {
+    async fn scan(&self, batch_size: usize) -> Result<BatchIterator> {
         let schema = self.schema();
 
         let mut entries = self.query_log.entries();
@@ -97,6 +99,7 @@ fn queries_schema(include_namespace_id: bool) -> SchemaRef {
         ),
         Field::new("success", DataType::Boolean, false),
         Field::new("trace_id", DataType::Utf8, true),
+        Field::new("query_id", DataType::Utf8, true),
     ]);
 
     Arc::new(Schema::new(columns))
@@ -177,6 +180,15 @@ fn from_query_log_entries(
             .collect::<StringArray>(),
     ));
 
+    columns.push(Arc::new(
+        entries
+            .iter()
+            .skip(offset)
+            .take(len)
+            .map(|e| e.query_id.map(|x| x.to_string()))
+            .collect::<StringArray>(),
+    ));
+
     RecordBatch::try_new(schema, columns)
 }
 
@@ -184,11 +196,13 @@ fn from_query_log_entries(
 mod tests {
     use super::*;
     use arrow_util::assert_batches_eq;
+    use iox_query::QueryId;
     use iox_time::{Time, TimeProvider};
     use trace::ctx::TraceId;
+    use uuid::Uuid;
 
-    #[test]
-    fn test_from_query_log() {
+    #[tokio::test]
+    async fn test_from_query_log() {
         let now = Time::from_rfc3339("1996-12-19T16:39:57+00:00").unwrap();
         let time_provider = Arc::new(iox_time::MockProvider::new(now));
 
@@ -199,29 +213,35 @@ mod tests {
             10,
             Arc::clone(&time_provider) as Arc<dyn TimeProvider>,
         ));
-        query_log.push(id1, "sql", Box::new("select * from foo"), None);
+        query_log.push(id1, "sql", Box::new("select * from foo"), None, None);
         time_provider.inc(std::time::Duration::from_secs(24 * 60 * 60));
-        let sql2_entry = query_log.push(id1, "sql", Box::new("select * from bar"), None);
+        let sql2_entry = query_log.push(id1, "sql", Box::new("select * from bar"), None, None);
         let read_filter_entry = query_log.push(
             id2,
             "read_filter",
             Box::new("json goop"),
             Some(TraceId::new(0x45fe).unwrap()),
+            Some(QueryId::from(Uuid::from_u128(0x1234))),
         );
 
         let table = QueriesTable::new(Arc::clone(&query_log), None);
 
         let expected = vec![
-            "+--------------+----------------------+-------------+-------------------+--------------------+---------+----------+",
-            "| namespace_id | issue_time           | query_type  | query_text        | completed_duration | success | trace_id |",
-            "+--------------+----------------------+-------------+-------------------+--------------------+---------+----------+",
-            "| 1            | 1996-12-19T16:39:57Z | sql         | select * from foo |                    | false   |          |",
-            "| 1            | 1996-12-20T16:39:57Z | sql         | select * from bar |                    | false   |          |",
-            "| 2            | 1996-12-20T16:39:57Z | read_filter | json goop         |                    | false   | 45fe     |",
-            "+--------------+----------------------+-------------+-------------------+--------------------+---------+----------+",
+            "+--------------+----------------------+-------------+-------------------+--------------------+---------+----------+--------------------------------------+",
+            "| namespace_id | issue_time           | query_type  | query_text        | completed_duration | success | trace_id | query_id                             |",
+            "+--------------+----------------------+-------------+-------------------+--------------------+---------+----------+--------------------------------------+",
+            "| 1            | 1996-12-19T16:39:57Z | sql         | select * from foo |                    | false   |          |                                      |",
+            "| 1            | 1996-12-20T16:39:57Z | sql         | select * from bar |                    | false   |          |                                      |",
+            "| 2            | 1996-12-20T16:39:57Z | read_filter | json goop         |                    | false   | 45fe     | 00000000-0000-0000-0000-000000001234 |",
+            "+--------------+----------------------+-------------+-------------------+--------------------+---------+----------+--------------------------------------+",
         ];
 
-        let entries = table.scan(3).unwrap().collect::<Result<Vec<_>>>().unwrap();
+        let entries = table
+            .scan(3)
+            .await
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
         assert_eq!(entries.len(), 1);
         assert_batches_eq!(&expected, &entries);
 
@@ -233,16 +253,21 @@ mod tests {
         read_filter_entry.set_completed(now, true);
 
         let expected = vec![
-            "+--------------+----------------------+-------------+-------------------+--------------------+---------+----------+",
-            "| namespace_id | issue_time           | query_type  | query_text        | completed_duration | success | trace_id |",
-            "+--------------+----------------------+-------------+-------------------+--------------------+---------+----------+",
-            "| 1            | 1996-12-19T16:39:57Z | sql         | select * from foo |                    | false   |          |",
-            "| 1            | 1996-12-20T16:39:57Z | sql         | select * from bar | 4s                 | false   |          |",
-            "| 2            | 1996-12-20T16:39:57Z | read_filter | json goop         | 4s                 | true    | 45fe     |",
-            "+--------------+----------------------+-------------+-------------------+--------------------+---------+----------+",
+            "+--------------+----------------------+-------------+-------------------+--------------------+---------+----------+--------------------------------------+",
+            "| namespace_id | issue_time           | query_type  | query_text        | completed_duration | success | trace_id | query_id                             |",
+            "+--------------+----------------------+-------------+-------------------+--------------------+---------+----------+--------------------------------------+",
+            "| 1            | 1996-12-19T16:39:57Z | sql         | select * from foo |                    | false   |          |                                      |",
+            "| 1            | 1996-12-20T16:39:57Z | sql         | select * from bar | 4s                 | false   |          |                                      |",
+            "| 2            | 1996-12-20T16:39:57Z | read_filter | json goop         | 4s                 | true    | 45fe     | 00000000-0000-0000-0000-000000001234 |",
+            "+--------------+----------------------+-------------+-------------------+--------------------+---------+----------+--------------------------------------+",
         ];
 
-        let entries = table.scan(2).unwrap().collect::<Result<Vec<_>>>().unwrap();
+        let entries = table
+            .scan(2)
+            .await
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
         assert_eq!(entries.len(), 2);
         assert_batches_eq!(&expected, &entries);
 
@@ -250,15 +275,20 @@ mod tests {
         let table = QueriesTable::new(Arc::clone(&query_log), Some(id1));
 
         let expected = vec![
-            "+----------------------+------------+-------------------+--------------------+---------+----------+",
-            "| issue_time           | query_type | query_text        | completed_duration | success | trace_id |",
-            "+----------------------+------------+-------------------+--------------------+---------+----------+",
-            "| 1996-12-19T16:39:57Z | sql        | select * from foo |                    | false   |          |",
-            "| 1996-12-20T16:39:57Z | sql        | select * from bar | 4s                 | false   |          |",
-            "+----------------------+------------+-------------------+--------------------+---------+----------+",
+            "+----------------------+------------+-------------------+--------------------+---------+----------+--------------------------------------+",
+            "| issue_time           | query_type | query_text        | completed_duration | success | trace_id | query_id                             |",
+            "+----------------------+------------+-------------------+--------------------+---------+----------+--------------------------------------+",
+            "| 1996-12-19T16:39:57Z | sql        | select * from foo |                    | false   |          |                                      |",
+            "| 1996-12-20T16:39:57Z | sql        | select * from bar | 4s                 | false   |          |                                      |",
+            "+----------------------+------------+-------------------+--------------------+---------+----------+--------------------------------------+",
         ];
 
-        let entries = table.scan(3).unwrap().collect::<Result<Vec<_>>>().unwrap();
+        let entries = table
+            .scan(3)
+            .await
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
         assert_eq!(entries.len(), 1);
         assert_batches_eq!(&expected, &entries);
     }
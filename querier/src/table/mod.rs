@@ -1,24 +1,32 @@
 use self::query_access::QuerierTableChunkPruner;
 use self::state_reconciler::Reconciler;
-use crate::chunk::util::create_basic_summary;
+use crate::chunk::util::{add_column_stats, column_stats_by_name, create_basic_summary};
 use crate::table::query_access::MetricPruningObserver;
 use crate::{
+    access_stats::TableAccessTracker,
+    admission::QueryAdmission,
     chunk::ChunkAdapter,
     ingester::{self, IngesterPartition},
     IngesterConnection,
 };
-use data_types::{ColumnId, PartitionId, ShardIndex, TableId, TimestampMinMax};
+use data_types::{
+    ColumnId, CompactionLevel, ParquetFile, PartitionId, PartitionSnapshot, ShardIndex, TableId,
+    TimestampMinMax,
+};
 use futures::{join, StreamExt};
 use iox_query::pruning::prune_summaries;
-use iox_query::{exec::Executor, provider, provider::ChunkPruner, QueryChunk};
-use observability_deps::tracing::{debug, trace};
+use iox_query::{
+    exec::Executor, provider, provider::ChunkPruner, QueryChunk, QueryCompleteness, QueryId,
+    QueryPruningStats, QuerySnapshot, SnapshotExpired,
+};
+use observability_deps::tracing::{debug, trace, warn};
 use predicate::Predicate;
 use schema::Schema;
 use sharder::JumpHash;
 use snafu::{ResultExt, Snafu};
 use std::collections::HashSet;
 use std::{
-    collections::{hash_map::Entry, HashMap},
+    collections::{hash_map::Entry, BTreeMap, HashMap},
     sync::Arc,
 };
 use trace::span::{Span, SpanRecorder};
@@ -56,6 +64,9 @@ pub enum Error {
 
     #[snafu(display("Chunk pruning failed: {}", source))]
     ChunkPruning { source: provider::Error },
+
+    #[snafu(display("Cannot read query snapshot: {}", source))]
+    Snapshot { source: SnapshotExpired },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -71,7 +82,10 @@ pub struct QuerierTableArgs {
     pub chunk_adapter: Arc<ChunkAdapter>,
     pub exec: Arc<Executor>,
     pub max_query_bytes: usize,
+    pub dedup_validation: bool,
     pub prune_metrics: Arc<PruneMetrics>,
+    pub query_admission: Option<Arc<QueryAdmission>>,
+    pub access_stats: Option<Arc<TableAccessTracker>>,
 }
 
 /// Table representation for the querier.
@@ -107,8 +121,17 @@ pub struct QuerierTable {
     /// Max combined chunk size for all chunks returned to the query subsystem.
     max_query_bytes: usize,
 
+    /// Check the output of table scans for primary key duplicates.
+    dedup_validation: bool,
+
     /// Metrics for chunk pruning.
     prune_metrics: Arc<PruneMetrics>,
+
+    /// Memory-based admission control for queries, if enabled.
+    query_admission: Option<Arc<QueryAdmission>>,
+
+    /// Tracker for table access statistics, if enabled.
+    access_stats: Option<Arc<TableAccessTracker>>,
 }
 
 impl QuerierTable {
@@ -124,7 +147,10 @@ impl QuerierTable {
             chunk_adapter,
             exec,
             max_query_bytes,
+            dedup_validation,
             prune_metrics,
+            query_admission,
+            access_stats,
         } = args;
 
         let reconciler = Reconciler::new(
@@ -144,7 +170,10 @@ impl QuerierTable {
             reconciler,
             exec,
             max_query_bytes,
+            dedup_validation,
             prune_metrics,
+            query_admission,
+            access_stats,
         }
     }
 
@@ -166,14 +195,36 @@ impl QuerierTable {
 
     /// Query all chunks within this table.
     ///
-    /// This currently contains all parquet files linked to their unprocessed tombstones.
+    /// This currently contains all parquet files linked to their unprocessed tombstones. The
+    /// chunks that are pruned using `predicate` are reported to `query_pruning_stats`.
+    ///
+    /// The partitions that were read are recorded in `query_snapshot`. If the query snapshot is
+    /// pinned, only the data of the pinned partition snapshots is returned.
     pub async fn chunks(
         &self,
         predicate: &Predicate,
+        query_id: Option<QueryId>,
+        query_completeness: Option<Arc<QueryCompleteness>>,
+        query_pruning_stats: Option<Arc<QueryPruningStats>>,
+        query_snapshot: Option<Arc<QuerySnapshot>>,
         span: Option<Span>,
     ) -> Result<Vec<Arc<dyn QueryChunk>>> {
+        if let Some(access_stats) = &self.access_stats {
+            access_stats.record(self.id);
+        }
+
         let mut span_recorder = SpanRecorder::new(span);
-        match self.chunks_inner(predicate, &span_recorder).await {
+        match self
+            .chunks_inner(
+                predicate,
+                query_id,
+                query_completeness,
+                query_pruning_stats.as_deref(),
+                query_snapshot.as_deref(),
+                &span_recorder,
+            )
+            .await
+        {
             Ok(chunks) => {
                 span_recorder.ok("got chunks");
                 Ok(chunks)
@@ -188,6 +239,10 @@ impl QuerierTable {
     async fn chunks_inner(
         &self,
         predicate: &Predicate,
+        query_id: Option<QueryId>,
+        query_completeness: Option<Arc<QueryCompleteness>>,
+        query_pruning_stats: Option<&QueryPruningStats>,
+        query_snapshot: Option<&QuerySnapshot>,
         span_recorder: &SpanRecorder,
     ) -> Result<Vec<Arc<dyn QueryChunk>>> {
         debug!(
@@ -198,11 +253,18 @@ impl QuerierTable {
         );
 
         let catalog_cache = self.chunk_adapter.catalog_cache();
+        let pinned_partitions = query_snapshot.and_then(|s| s.pinned_partitions());
 
         // ask ingesters for data, also optimistically fetching catalog
         // contents at the same time to pre-warm cache
         let (partitions, _parquet_files, _tombstones) = join!(
-            self.ingester_partitions(predicate, span_recorder.child_span("ingester partitions")),
+            self.ingester_partitions(
+                predicate,
+                query_id,
+                query_completeness,
+                pinned_partitions,
+                span_recorder.child_span("ingester partitions")
+            ),
             catalog_cache.parquet_file().get(
                 self.id(),
                 span_recorder.child_span("cache GET parquet_file (pre-warm")
@@ -214,7 +276,12 @@ impl QuerierTable {
         );
 
         // handle errors / cache refresh
-        let partitions = partitions?;
+        let partitions = match partitions {
+            Err(Error::GettingIngesterPartitions {
+                source: ingester::Error::SnapshotExpired { source, .. },
+            }) => return Err(Error::Snapshot { source }),
+            partitions => partitions?,
+        };
 
         // figure out if the ingester has created new parquet files or
         // tombstones the querier doens't yet know about
@@ -238,6 +305,10 @@ impl QuerierTable {
                 .get(self.id(), span_recorder.child_span("cache GET tombstone"))
         );
 
+        let snapshot_files =
+            snapshot_parquet_files(&parquet_files.files, &partitions, query_snapshot)
+                .context(SnapshotSnafu)?;
+
         let columns: HashSet<ColumnId> = parquet_files
             .files
             .iter()
@@ -260,8 +331,7 @@ impl QuerierTable {
         // create parquet files
         let parquet_files: Vec<_> = match cached_table {
             Some(cached_table) => {
-                let basic_summaries: Vec<_> = parquet_files
-                    .files
+                let basic_summaries: Vec<_> = snapshot_files
                     .iter()
                     .map(|p| {
                         Arc::new(create_basic_summary(
@@ -292,15 +362,63 @@ impl QuerierTable {
                     }
                 };
 
+                // Prune on the column statistics recorded in the catalog, so that files whose tag
+                // values cannot match an equality or range predicate are never fetched
+                let stats_keeps = if snapshot_files.iter().any(|p| !p.column_stats.is_empty()) {
+                    let stats_summaries: Vec<_> = snapshot_files
+                        .iter()
+                        .zip(&basic_summaries)
+                        .map(|(p, basic_summary)| {
+                            let basic_summary =
+                                basic_summary.as_ref().expect("basic summary exists");
+                            let mut summary = basic_summary.as_ref().clone();
+                            add_column_stats(
+                                &mut summary,
+                                &column_stats_by_name(p, &cached_table.column_id_map),
+                            );
+                            Some(Arc::new(summary))
+                        })
+                        .collect();
+
+                    match prune_summaries(
+                        Arc::clone(&cached_table.schema),
+                        &stats_summaries,
+                        predicate,
+                    ) {
+                        Ok(keeps) => keeps,
+                        Err(reason) => {
+                            debug!(?reason, "Could not prune with catalog column statistics");
+                            vec![true; stats_summaries.len()]
+                        }
+                    }
+                } else {
+                    vec![true; snapshot_files.len()]
+                };
+
                 let early_pruning_observer =
                     &MetricPruningObserver::new(Arc::clone(&self.prune_metrics));
-                futures::stream::iter(parquet_files.files.iter().zip(keeps))
-                    .filter_map(|(cached_parquet_file, keep)| async move {
+                let keeps: Vec<_> = keeps.into_iter().zip(stats_keeps).collect();
+                futures::stream::iter(snapshot_files.iter().zip(keeps))
+                    .filter_map(|(cached_parquet_file, (keep, stats_keep))| async move {
                         if !keep {
                             early_pruning_observer.was_pruned_early(
                                 cached_parquet_file.row_count as u64,
                                 cached_parquet_file.file_size_bytes as u64,
                             );
+                        } else if !stats_keep {
+                            early_pruning_observer.was_pruned_by_catalog_stats(
+                                cached_parquet_file.row_count as u64,
+                                cached_parquet_file.file_size_bytes as u64,
+                            );
+                        }
+                        if !(keep && stats_keep) {
+                            if let Some(query_pruning_stats) = query_pruning_stats {
+                                query_pruning_stats.record_pruned(
+                                    self.table_name(),
+                                    1,
+                                    cached_parquet_file.row_count as u64,
+                                );
+                            }
                             return None;
                         }
                         let chunk_adapter = Arc::clone(&self.chunk_adapter);
@@ -333,6 +451,7 @@ impl QuerierTable {
         trace!("Fetched chunks");
 
         let num_initial_chunks = chunks.len();
+        let initial_chunks = query_pruning_stats.map(|_| chunks.clone());
         let chunks = self
             .chunk_pruner()
             .prune_chunks(
@@ -343,6 +462,24 @@ impl QuerierTable {
             )
             .context(ChunkPruningSnafu)?;
         debug!(%predicate, num_initial_chunks, num_final_chunks=chunks.len(), "pruned with pushed down predicates");
+
+        if let (Some(query_pruning_stats), Some(initial_chunks)) =
+            (query_pruning_stats, initial_chunks)
+        {
+            let kept: HashSet<_> = chunks.iter().map(|chunk| chunk.id()).collect();
+            let pruned_rows = initial_chunks
+                .iter()
+                .filter(|chunk| !kept.contains(&chunk.id()))
+                .map(|chunk| query_access::chunk_rows(chunk.as_ref()) as u64)
+                .sum();
+            query_pruning_stats.record_pruned(
+                self.table_name(),
+                (num_initial_chunks - chunks.len()) as u64,
+                pruned_rows,
+            );
+            query_pruning_stats.record_kept(self.table_name(), chunks.len() as u64);
+        }
+
         Ok(chunks)
     }
 
@@ -355,9 +492,15 @@ impl QuerierTable {
     }
 
     /// Get partitions from ingesters.
+    ///
+    /// If the ingesters are unavailable (i.e. their circuit breaker is open), no partitions are
+    /// returned and the query is marked as incomplete instead.
     async fn ingester_partitions(
         &self,
         predicate: &Predicate,
+        query_id: Option<QueryId>,
+        query_completeness: Option<Arc<QueryCompleteness>>,
+        snapshot: Option<&BTreeMap<PartitionId, PartitionSnapshot>>,
         span: Option<Span>,
     ) -> Result<Vec<IngesterPartition>> {
         let mut span_recorder = SpanRecorder::new(span);
@@ -367,6 +510,8 @@ impl QuerierTable {
                 .ingester_partitions_inner(
                     Arc::clone(ingester_connection),
                     predicate,
+                    query_id,
+                    snapshot,
                     &span_recorder,
                 )
                 .await
@@ -375,6 +520,25 @@ impl QuerierTable {
                     span_recorder.ok("Got partitions");
                     Ok(partitions)
                 }
+                Err(Error::GettingIngesterPartitions {
+                    source: ingester::Error::IngesterUnavailable { ingester_address },
+                }) => {
+                    warn!(
+                        namespace=%self.namespace_name,
+                        table_name=%self.table_name(),
+                        ingester_address=ingester_address.as_str(),
+                        ?query_id,
+                        "Ingester unavailable, only using persisted data",
+                    );
+                    if let Some(query_completeness) = &query_completeness {
+                        query_completeness.mark_incomplete(format!(
+                            "ingester '{ingester_address}' unavailable, table '{}' only contains persisted data",
+                            self.table_name(),
+                        ));
+                    }
+                    span_recorder.ok("Ingester unavailable");
+                    Ok(vec![])
+                }
                 Err(e) => {
                     span_recorder.error("failed");
                     Err(e)
@@ -391,6 +555,8 @@ impl QuerierTable {
         &self,
         ingester_connection: Arc<dyn IngesterConnection>,
         predicate: &Predicate,
+        query_id: Option<QueryId>,
+        snapshot: Option<&BTreeMap<PartitionId, PartitionSnapshot>>,
         span_recorder: &SpanRecorder,
     ) -> Result<Vec<IngesterPartition>> {
         // For now, ask for *all* columns in the table from the ingester (need
@@ -422,6 +588,8 @@ impl QuerierTable {
                 columns,
                 predicate,
                 Arc::clone(&self.schema),
+                query_id,
+                snapshot,
                 span_recorder.child_span("IngesterConnection partitions"),
             )
             .await
@@ -505,6 +673,94 @@ impl QuerierTable {
     }
 }
 
+/// Record the partitions of the given parquet files and ingester partitions in `query_snapshot`.
+///
+/// If the query is pinned instead, only the parquet files of the pinned partition snapshots are
+/// returned. Files that contain data of both a snapshot and newer data (e.g. because they were
+/// compacted or persisted after the snapshot was taken) cannot be split and fail the query.
+fn snapshot_parquet_files(
+    parquet_files: &[Arc<ParquetFile>],
+    ingester_partitions: &[IngesterPartition],
+    query_snapshot: Option<&QuerySnapshot>,
+) -> Result<Vec<Arc<ParquetFile>>, SnapshotExpired> {
+    let query_snapshot = match query_snapshot {
+        Some(query_snapshot) => query_snapshot,
+        None => return Ok(parquet_files.to_vec()),
+    };
+
+    let pinned = match query_snapshot.pinned_partitions() {
+        Some(pinned) => pinned,
+        None => {
+            for p in ingester_partitions {
+                let persisted_max_sequence_number = p.parquet_max_sequence_number();
+                if let Some(max_sequence_number) =
+                    p.max_sequence_number().or(persisted_max_sequence_number)
+                {
+                    query_snapshot.record(
+                        p.partition_id(),
+                        PartitionSnapshot {
+                            persisted_max_sequence_number,
+                            max_sequence_number,
+                        },
+                    );
+                }
+            }
+
+            // The ingester knows which files it persisted, so only fall back to the files for
+            // partitions the ingester did not report.
+            let ingester_partition_ids: HashSet<_> = ingester_partitions
+                .iter()
+                .map(|p| p.partition_id())
+                .collect();
+            for file in parquet_files {
+                if !ingester_partition_ids.contains(&file.partition_id) {
+                    query_snapshot.record(
+                        file.partition_id,
+                        PartitionSnapshot {
+                            persisted_max_sequence_number: Some(file.max_sequence_number),
+                            max_sequence_number: file.max_sequence_number,
+                        },
+                    );
+                }
+            }
+
+            return Ok(parquet_files.to_vec());
+        }
+    };
+
+    // If a file ends exactly at the snapshot, all newer files persisted by the ingester only
+    // contain newer data.
+    let boundaries: HashSet<_> = parquet_files
+        .iter()
+        .map(|file| (file.partition_id, file.max_sequence_number))
+        .collect();
+
+    let mut result = Vec::with_capacity(parquet_files.len());
+    for file in parquet_files {
+        let snapshot = match pinned.get(&file.partition_id) {
+            Some(snapshot) => snapshot,
+            // partition had no data when the snapshot was taken
+            None => continue,
+        };
+
+        if file.max_sequence_number <= snapshot.max_sequence_number {
+            result.push(Arc::clone(file));
+        } else if file.compaction_level != CompactionLevel::Initial
+            || !boundaries.contains(&(file.partition_id, snapshot.max_sequence_number))
+        {
+            return Err(SnapshotExpired {
+                reason: format!(
+                    "parquet file {} of partition {} may contain data of the snapshot and newer data",
+                    file.id.get(),
+                    file.partition_id.get(),
+                ),
+            });
+        }
+    }
+
+    Ok(result)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -514,13 +770,17 @@ mod tests {
         QuerierChunkLoadSetting,
     };
     use assert_matches::assert_matches;
-    use data_types::{ChunkId, ColumnType, CompactionLevel, ParquetFileId, SequenceNumber};
+    use data_types::{
+        ChunkId, ColumnSet, ColumnStatsSet, ColumnType, CompactionLevel, NamespaceId,
+        ParquetFileId, SequenceNumber, ShardId, Timestamp,
+    };
     use iox_tests::util::{TestCatalog, TestParquetFileBuilder, TestTable};
     use predicate::Predicate;
     use schema::{builder::SchemaBuilder, InfluxFieldType};
     use std::sync::Arc;
     use test_helpers::maybe_start_logging;
     use trace::{span::SpanStatus, RingBufferTraceCollector};
+    use uuid::Uuid;
 
     #[tokio::test]
     async fn test_parquet_chunks() {
@@ -938,6 +1198,63 @@ mod tests {
         assert_matches!(err, Error::IngestersOverlap { .. });
     }
 
+    #[tokio::test]
+    async fn test_ingester_unavailable() {
+        maybe_start_logging();
+        let catalog = TestCatalog::new();
+
+        let ns = catalog.create_namespace("ns").await;
+        let table = ns.create_table("table1").await;
+        let shard = ns.create_shard(1).await;
+        let partition = table.with_shard(&shard).create_partition("k").await;
+        table.create_column("time", ColumnType::Time).await;
+        table.create_column("foo", ColumnType::F64).await;
+
+        let builder = TestParquetFileBuilder::default().with_line_protocol("table1 foo=1 11");
+        partition.create_parquet_file(builder).await;
+
+        let querier_table = TestQuerierTable::new(&catalog, &table).await;
+        let ingester_connection = querier_table
+            .querier_table
+            .ingester_connection
+            .as_ref()
+            .unwrap()
+            .as_any()
+            .downcast_ref::<MockIngesterConnection>()
+            .unwrap();
+
+        // other ingester errors still fail the query
+        ingester_connection.next_response(Err(ingester::Error::ShardNotMapped {
+            shard_index: ShardIndex::new(1),
+        }));
+        let err = querier_table
+            .querier_table
+            .chunks(&Predicate::default(), None, None, None, None, None)
+            .await
+            .unwrap_err();
+        assert_matches!(err, Error::GettingIngesterPartitions { .. });
+
+        // unavailable ingesters result in parquet-only, incomplete results
+        ingester_connection.next_response(Err(ingester::Error::IngesterUnavailable {
+            ingester_address: String::from("addr1"),
+        }));
+        let query_completeness = Arc::new(QueryCompleteness::new());
+        let chunks = querier_table
+            .querier_table
+            .chunks(
+                &Predicate::default(),
+                None,
+                Some(Arc::clone(&query_completeness)),
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert!(!query_completeness.is_complete());
+    }
+
     #[tokio::test]
     async fn test_parquet_cache_refresh() {
         maybe_start_logging();
@@ -1141,10 +1458,72 @@ mod tests {
                 .next_response(Ok(self.ingester_partitions.clone()));
 
             let span = Some(Span::root("root", Arc::clone(&self.traces) as _));
-            self.querier_table.chunks(pred, span).await
+            self.querier_table
+                .chunks(pred, None, None, None, None, span)
+                .await
         }
     }
 
+    #[test]
+    fn test_snapshot_parquet_files() {
+        let parquet_file = |id: i64, partition_id: i64, max: i64, level: CompactionLevel| {
+            Arc::new(ParquetFile {
+                id: ParquetFileId::new(id),
+                shard_id: ShardId::new(1),
+                namespace_id: NamespaceId::new(1),
+                table_id: TableId::new(1),
+                partition_id: PartitionId::new(partition_id),
+                object_store_id: Uuid::new_v4(),
+                max_sequence_number: SequenceNumber::new(max),
+                min_time: Timestamp::new(1),
+                max_time: Timestamp::new(1),
+                to_delete: None,
+                file_size_bytes: 1,
+                row_count: 1,
+                compaction_level: level,
+                created_at: Timestamp::new(1),
+                column_set: ColumnSet::new([ColumnId::new(1)]),
+                column_stats: ColumnStatsSet::default(),
+            })
+        };
+        let ids =
+            |files: Vec<Arc<ParquetFile>>| -> Vec<_> { files.iter().map(|f| f.id.get()).collect() };
+
+        let files = vec![
+            parquet_file(1, 1, 10, CompactionLevel::FileNonOverlapped),
+            parquet_file(2, 1, 20, CompactionLevel::Initial),
+            parquet_file(3, 2, 5, CompactionLevel::Initial),
+        ];
+
+        // unpinned queries record all partitions
+        let query_snapshot = QuerySnapshot::new();
+        let kept = snapshot_parquet_files(&files, &[], Some(&query_snapshot)).unwrap();
+        assert_eq!(ids(kept), vec![1, 2, 3]);
+        let token = query_snapshot.token();
+        assert_eq!(token.to_string(), "v1:1=20/20,2=5/5");
+
+        // new files of pinned partitions and files of new partitions are ignored
+        let mut new_files = files.clone();
+        new_files.push(parquet_file(4, 1, 30, CompactionLevel::Initial));
+        new_files.push(parquet_file(5, 3, 1, CompactionLevel::Initial));
+        let pinned = QuerySnapshot::pinned(token.clone());
+        let kept = snapshot_parquet_files(&new_files, &[], Some(&pinned)).unwrap();
+        assert_eq!(ids(kept), vec![1, 2, 3]);
+
+        // files that were compacted with newer data cannot be used
+        let compacted_files = vec![
+            parquet_file(6, 1, 30, CompactionLevel::FileNonOverlapped),
+            parquet_file(3, 2, 5, CompactionLevel::Initial),
+        ];
+        let pinned = QuerySnapshot::pinned(token);
+        let err = snapshot_parquet_files(&compacted_files, &[], Some(&pinned)).unwrap_err();
+        assert!(err.reason.contains("parquet file 6"), "{}", err);
+
+        // partitions with unpersisted data cannot be pinned once that data was persisted with newer data
+        let pinned = QuerySnapshot::pinned("v1:1=20/25".parse().unwrap());
+        snapshot_parquet_files(&new_files, &[], Some(&pinned)).unwrap_err();
+    }
+
     /// returns the number of deletes in each chunk
     fn num_deletes(mut chunks: Vec<Arc<dyn QueryChunk>>) -> Vec<usize> {
         chunks.sort_by_key(|c| c.id());
@@ -50,14 +50,33 @@ impl TableProvider for QuerierTable {
         let iox_ctx = self.exec.new_context_from_df(ExecutorType::Query, ctx);
 
         let mut builder =
-            ProviderBuilder::new(self.table_name(), Arc::clone(self.schema()), iox_ctx);
+            ProviderBuilder::new(self.table_name(), Arc::clone(self.schema()), iox_ctx)
+                .with_dedup_validation(self.dedup_validation);
 
         let pruning_predicate = Predicate::default().with_pushdown_exprs(filters);
         let chunks = self
-            .chunks(&pruning_predicate, ctx.child_span("querier table chunks"))
+            .chunks(
+                &pruning_predicate,
+                ctx.query_id(),
+                ctx.query_completeness(),
+                ctx.query_pruning_stats(),
+                ctx.query_snapshot(),
+                ctx.child_span("querier table chunks"),
+            )
             .await
             .map_err(|e| DataFusionError::External(Box::new(e)))?;
 
+        if let Some(query_admission) = &self.query_admission {
+            let estimated_bytes = chunks
+                .iter()
+                .map(|chunk| chunk_estimate_size(chunk.as_ref()))
+                .sum::<usize>();
+            query_admission
+                .admit(estimated_bytes, ctx.query_memory_reservation().as_deref())
+                .await
+                .map_err(|e| DataFusionError::External(Box::new(e)))?;
+        }
+
         for chunk in chunks {
             builder = builder.add_chunk(chunk);
         }
@@ -157,6 +176,16 @@ impl MetricPruningObserver {
         self.metrics.rows_pruned.inc(row_count);
         self.metrics.bytes_pruned.inc(size_estimate);
     }
+
+    /// Called when pruning a chunk using the column statistics of the catalog, before fully
+    /// creating the chunk structure
+    pub(crate) fn was_pruned_by_catalog_stats(&self, row_count: u64, file_size_bytes: u64) {
+        self.was_pruned_early(row_count, file_size_bytes);
+        self.metrics.files_pruned_by_catalog_stats.inc(1);
+        self.metrics
+            .bytes_pruned_by_catalog_stats
+            .inc(file_size_bytes);
+    }
 }
 
 impl PruningObserver for MetricPruningObserver {
@@ -223,6 +252,10 @@ pub struct PruneMetrics {
     bytes_could_not_prune_no_expression: U64Counter,
     bytes_could_not_prune_cannot_create_predicate: U64Counter,
     bytes_could_not_prune_df: U64Counter,
+
+    // parquet files pruned using the column statistics of the catalog
+    files_pruned_by_catalog_stats: U64Counter,
+    bytes_pruned_by_catalog_stats: U64Counter,
 }
 
 impl PruneMetrics {
@@ -293,6 +326,19 @@ impl PruneMetrics {
             ("reason", NotPrunedReason::DataFusionPruningFailed.name()),
         ]);
 
+        let files_pruned_by_catalog_stats = metric_registry
+            .register_metric::<U64Counter>(
+                "query_pruner_catalog_stats_files",
+                "Number of parquet files pruned using the column statistics of the catalog",
+            )
+            .recorder(&[]);
+        let bytes_pruned_by_catalog_stats = metric_registry
+            .register_metric::<U64Counter>(
+                "query_pruner_catalog_stats_bytes",
+                "Size (in bytes) of parquet files pruned using the catalog column statistics",
+            )
+            .recorder(&[]);
+
         Self {
             chunks_pruned,
             chunks_not_pruned,
@@ -309,6 +355,8 @@ impl PruneMetrics {
             bytes_could_not_prune_no_expression,
             bytes_could_not_prune_cannot_create_predicate,
             bytes_could_not_prune_df,
+            files_pruned_by_catalog_stats,
+            bytes_pruned_by_catalog_stats,
         }
     }
 }
@@ -325,7 +373,7 @@ fn chunk_estimate_size(chunk: &dyn QueryChunk) -> usize {
     }
 }
 
-fn chunk_rows(chunk: &dyn QueryChunk) -> usize {
+pub(crate) fn chunk_rows(chunk: &dyn QueryChunk) -> usize {
     let chunk = chunk.as_any();
 
     if let Some(chunk) = chunk.downcast_ref::<QuerierChunk>() {
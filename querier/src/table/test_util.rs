@@ -24,6 +24,7 @@ pub async fn querier_table(
         catalog.catalog(),
         catalog.time_provider(),
         catalog.metric_registry(),
+        catalog.object_store(),
         &Handle::current(),
     ));
     let chunk_adapter = Arc::new(ChunkAdapter::new(
@@ -52,7 +53,10 @@ pub async fn querier_table(
         chunk_adapter,
         exec: catalog.exec(),
         max_query_bytes: usize::MAX,
+        dedup_validation: false,
         prune_metrics: Arc::new(PruneMetrics::new(&catalog.metric_registry())),
+        query_admission: None,
+        access_stats: None,
     })
 }
 
@@ -0,0 +1,226 @@
+//! Cache warm-up on startup.
+//!
+//! A freshly started querier has empty caches, so the first queries of every namespace pay for
+//! the catalog round-trips. The table access statistics (see [`TableAccessStatsRepo`]) persist
+//! which tables were queried recently, so they are used to pre-load the namespace schemas and
+//! partition sort keys that are likely to be needed soon.
+//!
+//! [`TableAccessStatsRepo`]: iox_catalog::interface::TableAccessStatsRepo
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+
+use data_types::{NamespaceId, Timestamp};
+use observability_deps::tracing::{info, warn};
+use tokio_util::sync::CancellationToken;
+
+use crate::cache::CatalogCache;
+
+/// Maximum number of partitions per table whose sort keys are pre-loaded.
+const MAX_PARTITIONS_PER_TABLE: usize = 100;
+
+/// Configuration of the cache warm-up.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheWarmUpConfig {
+    /// Tables queried within this window before startup are warmed up.
+    pub window: Duration,
+
+    /// Maximum number of tables to warm up, most recently queried first.
+    pub max_tables: usize,
+}
+
+/// Pre-loads the catalog cache for recently queried tables.
+#[derive(Debug)]
+pub struct CacheWarmUp {
+    catalog_cache: Arc<CatalogCache>,
+    config: CacheWarmUpConfig,
+}
+
+impl CacheWarmUp {
+    /// Create new warm-up for the given cache.
+    pub fn new(catalog_cache: Arc<CatalogCache>, config: CacheWarmUpConfig) -> Self {
+        Self {
+            catalog_cache,
+            config,
+        }
+    }
+
+    /// Load the namespace schemas and the sort keys of the newest partitions of all tables that
+    /// were queried within the configured window.
+    ///
+    /// Catalog errors are logged and end the warm-up early, they never fail the querier.
+    pub async fn warm_up(&self) {
+        let now = self.catalog_cache.time_provider().now();
+        let since = now
+            .checked_sub(self.config.window)
+            .map(|t| Timestamp::new(t.timestamp_nanos()))
+            .unwrap_or_else(|| Timestamp::new(i64::MIN));
+
+        let catalog = self.catalog_cache.catalog();
+        let mut repos = catalog.repositories().await;
+
+        let stats = match repos
+            .table_access_stats()
+            .list_queried_since(since, self.config.max_tables)
+            .await
+        {
+            Ok(stats) => stats,
+            Err(e) => {
+                warn!(%e, "cannot list recently queried tables, skipping cache warm-up");
+                return;
+            }
+        };
+
+        let mut namespace_names: HashMap<NamespaceId, Option<Arc<str>>> = HashMap::new();
+        let mut n_tables = 0;
+        let mut n_partitions = 0;
+        for stat in stats {
+            let res = async {
+                let table = match repos.tables().get_by_id(stat.table_id).await? {
+                    Some(table) => table,
+                    // dropped since it was last queried
+                    None => return Ok(None),
+                };
+
+                let namespace_name = match namespace_names.entry(table.namespace_id) {
+                    Entry::Occupied(o) => o.get().clone(),
+                    Entry::Vacant(v) => {
+                        let namespace = repos.namespaces().get_by_id(table.namespace_id).await?;
+                        v.insert(namespace.map(|ns| Arc::from(ns.name))).clone()
+                    }
+                };
+                let namespace_name = match namespace_name {
+                    Some(name) => name,
+                    None => return Ok(None),
+                };
+
+                let mut partitions = repos.partitions().list_by_table_id(table.id).await?;
+                partitions.sort_by_key(|p| std::cmp::Reverse(p.id));
+                partitions.truncate(MAX_PARTITIONS_PER_TABLE);
+
+                Ok::<_, iox_catalog::interface::Error>(Some((namespace_name, partitions)))
+            }
+            .await;
+
+            let (namespace_name, partitions) = match res {
+                Ok(Some(x)) => x,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!(%e, "cannot read catalog, stopping cache warm-up early");
+                    break;
+                }
+            };
+
+            self.catalog_cache
+                .namespace()
+                .get(namespace_name, &[], None)
+                .await;
+            for partition in &partitions {
+                self.catalog_cache
+                    .partition()
+                    .sort_key(partition.id, &[], None)
+                    .await;
+            }
+
+            n_tables += 1;
+            n_partitions += partitions.len();
+        }
+
+        info!(
+            n_namespaces = namespace_names.values().filter(|n| n.is_some()).count(),
+            n_tables, n_partitions, "cache warm-up done"
+        );
+    }
+
+    /// Warm up the cache unless `shutdown` is triggered first, then wait for the shutdown.
+    pub async fn run(self: Arc<Self>, shutdown: CancellationToken) {
+        tokio::select! {
+            _ = shutdown.cancelled() => {}
+            _ = self.warm_up() => {}
+        }
+
+        shutdown.cancelled().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iox_tests::util::TestCatalog;
+    use tokio::runtime::Handle;
+
+    use crate::cache::test_util::assert_histogram_metric_count;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_warm_up() {
+        let catalog = TestCatalog::new();
+        let ns = catalog.create_namespace("ns").await;
+        let shard = ns.create_shard(1).await;
+        let table1 = ns.create_table("table1").await;
+        let table2 = ns.create_table("table2").await;
+        let p1 = table1
+            .with_shard(&shard)
+            .create_partition("k1")
+            .await
+            .partition
+            .clone();
+        let p2 = table2
+            .with_shard(&shard)
+            .create_partition("k2")
+            .await
+            .partition
+            .clone();
+
+        // table2 was queried before the warm-up window
+        record_access(&catalog, &table2.table, 1).await;
+        catalog.mock_time_provider().inc(Duration::from_secs(3_600));
+        record_access(&catalog, &table1.table, 2).await;
+
+        let catalog_cache = Arc::new(CatalogCache::new_testing(
+            catalog.catalog(),
+            catalog.time_provider(),
+            catalog.metric_registry(),
+            catalog.object_store(),
+            &Handle::current(),
+        ));
+        let warm_up = CacheWarmUp::new(
+            Arc::clone(&catalog_cache),
+            CacheWarmUpConfig {
+                window: Duration::from_secs(60),
+                max_tables: 10,
+            },
+        );
+        warm_up.warm_up().await;
+        assert_histogram_metric_count(&catalog.metric_registry, "namespace_get_by_name", 1);
+        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_ids", 1);
+
+        // warmed up entries are served from the cache
+        catalog_cache
+            .namespace()
+            .get(Arc::from("ns"), &[], None)
+            .await
+            .unwrap();
+        catalog_cache.partition().sort_key(p1.id, &[], None).await;
+        assert_histogram_metric_count(&catalog.metric_registry, "namespace_get_by_name", 1);
+        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_ids", 1);
+
+        // tables outside of the window are not
+        catalog_cache.partition().sort_key(p2.id, &[], None).await;
+        assert_histogram_metric_count(&catalog.metric_registry, "partition_get_by_ids", 2);
+    }
+
+    async fn record_access(catalog: &TestCatalog, table: &data_types::Table, query_count: i64) {
+        let now = catalog.time_provider().now();
+        catalog
+            .catalog()
+            .repositories()
+            .await
+            .table_access_stats()
+            .record(table.id, Timestamp::new(now.timestamp_nanos()), query_count)
+            .await
+            .unwrap();
+    }
+}
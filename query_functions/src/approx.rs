@@ -0,0 +1,420 @@
+//! Approximate aggregate functions
+//!
+//! Exact distinct counts and percentiles need memory proportional to the number of distinct
+//! values (or rows), which is prohibitive for high-cardinality tag data. The functions in this
+//! module use fixed-size sketches instead:
+//!
+//! * `approx_count_distinct(expr)`: estimated number of distinct non-null values, based on a
+//!   HyperLogLog sketch with a standard error of about 0.8%.
+//! * `approx_percentile(expr, percentile)`: estimated value at `percentile` (between 0 and 1) of
+//!   a numeric expression, based on a t-digest.
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
+
+use arrow::{
+    array::{
+        as_dictionary_array, as_string_array, Array, ArrayRef, BinaryArray, BooleanArray,
+        Float64Array, Int64Array, UInt64Array,
+    },
+    datatypes::{DataType, Int32Type},
+};
+use datafusion::{
+    error::{DataFusionError, Result as DataFusionResult},
+    logical_expr::{AggregateState, Signature, Volatility},
+    physical_plan::{udaf::AggregateUDF, Accumulator},
+    scalar::ScalarValue,
+};
+use once_cell::sync::Lazy;
+
+mod hyperloglog;
+mod tdigest;
+
+use hyperloglog::HyperLogLog;
+use tdigest::TDigest;
+
+/// The name of the approx_count_distinct UDAF given to DataFusion.
+pub const APPROX_COUNT_DISTINCT_UDAF_NAME: &str = "approx_count_distinct";
+
+/// The name of the approx_percentile UDAF given to DataFusion.
+pub const APPROX_PERCENTILE_UDAF_NAME: &str = "approx_percentile";
+
+type ReturnTypeFunction = Arc<dyn Fn(&[DataType]) -> DataFusionResult<Arc<DataType>> + Send + Sync>;
+type StateTypeFactory =
+    Arc<dyn Fn(&DataType) -> DataFusionResult<Arc<Vec<DataType>>> + Send + Sync>;
+type Factory = Arc<dyn Fn() -> DataFusionResult<Box<dyn Accumulator>> + Send + Sync>;
+
+/// Implementation of approx_count_distinct
+pub(crate) static APPROX_COUNT_DISTINCT_UDAF: Lazy<Arc<AggregateUDF>> = Lazy::new(|| {
+    let return_type: ReturnTypeFunction = Arc::new(|_| Ok(Arc::new(DataType::Int64)));
+    let state_type: StateTypeFactory = Arc::new(|_| Ok(Arc::new(vec![DataType::Binary])));
+    let factory: Factory = Arc::new(|| Ok(Box::new(ApproxCountDistinctAccumulator::default())));
+
+    Arc::new(AggregateUDF::new(
+        APPROX_COUNT_DISTINCT_UDAF_NAME,
+        // takes one argument of any type
+        &Signature::any(1, Volatility::Immutable),
+        &return_type,
+        &factory,
+        &state_type,
+    ))
+});
+
+/// Implementation of approx_percentile
+pub(crate) static APPROX_PERCENTILE_UDAF: Lazy<Arc<AggregateUDF>> = Lazy::new(|| {
+    let return_type: ReturnTypeFunction = Arc::new(|_| Ok(Arc::new(DataType::Float64)));
+    let state_type: StateTypeFactory =
+        Arc::new(|_| Ok(Arc::new(vec![DataType::Binary, DataType::Float64])));
+    let factory: Factory = Arc::new(|| Ok(Box::new(ApproxPercentileAccumulator::default())));
+
+    Arc::new(AggregateUDF::new(
+        APPROX_PERCENTILE_UDAF_NAME,
+        // takes two arguments: value, percentile
+        &Signature::exact(
+            vec![DataType::Float64, DataType::Float64],
+            Volatility::Immutable,
+        ),
+        &return_type,
+        &factory,
+        &state_type,
+    ))
+});
+
+/// Accumulates the hashes of all non-null input values into a [`HyperLogLog`] sketch.
+#[derive(Debug, Default)]
+struct ApproxCountDistinctAccumulator {
+    hll: HyperLogLog,
+}
+
+impl Accumulator for ApproxCountDistinctAccumulator {
+    fn state(&self) -> DataFusionResult<Vec<AggregateState>> {
+        Ok(vec![AggregateState::Scalar(ScalarValue::Binary(Some(
+            self.hll.registers().to_vec(),
+        )))])
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> DataFusionResult<()> {
+        match values {
+            [] => Ok(()),
+            [array] => hash_values(array, |hash| self.hll.add_hash(hash)),
+            _ => Err(DataFusionError::Internal(format!(
+                "Internal error: Expected 1 argument passed to approx_count_distinct but got {}",
+                values.len()
+            ))),
+        }
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> DataFusionResult<()> {
+        for registers in binary_array(states)?.iter().flatten() {
+            let other = HyperLogLog::from_registers(registers).ok_or_else(|| {
+                DataFusionError::Internal(format!(
+                    "Internal error: invalid HyperLogLog state of {} bytes",
+                    registers.len()
+                ))
+            })?;
+            self.hll.merge(&other);
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self) -> DataFusionResult<ScalarValue> {
+        Ok(ScalarValue::Int64(Some(self.hll.estimate() as i64)))
+    }
+}
+
+/// Accumulates all non-null input values into a [`TDigest`].
+///
+/// The percentile is passed as a (constant) argument, so it is only known once the first batch
+/// is seen and is carried along with the digest in the intermediate state.
+#[derive(Debug, Default)]
+struct ApproxPercentileAccumulator {
+    digest: TDigest,
+    percentile: Option<f64>,
+}
+
+impl ApproxPercentileAccumulator {
+    fn set_percentile(&mut self, percentiles: &Float64Array) -> DataFusionResult<()> {
+        if self.percentile.is_some() {
+            return Ok(());
+        }
+
+        if let Some(percentile) = percentiles.iter().flatten().next() {
+            if !(0.0..=1.0).contains(&percentile) {
+                return Err(DataFusionError::Plan(format!(
+                    "percentile of approx_percentile must be between 0 and 1 but got {}",
+                    percentile
+                )));
+            }
+            self.percentile = Some(percentile);
+        }
+        Ok(())
+    }
+}
+
+impl Accumulator for ApproxPercentileAccumulator {
+    fn state(&self) -> DataFusionResult<Vec<AggregateState>> {
+        Ok(vec![
+            AggregateState::Scalar(ScalarValue::Binary(Some(self.digest.to_bytes()))),
+            AggregateState::Scalar(ScalarValue::Float64(self.percentile)),
+        ])
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> DataFusionResult<()> {
+        let (values, percentiles) = match values {
+            [] => return Ok(()),
+            [values, percentiles] => (float64_array(values)?, float64_array(percentiles)?),
+            _ => {
+                return Err(DataFusionError::Internal(format!(
+                    "Internal error: Expected 2 arguments passed to approx_percentile but got {}",
+                    values.len()
+                )))
+            }
+        };
+
+        self.set_percentile(percentiles)?;
+        for v in values.iter().flatten() {
+            self.digest.add(v);
+        }
+        Ok(())
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> DataFusionResult<()> {
+        let percentiles = match states {
+            [_, percentiles] => float64_array(percentiles)?,
+            _ => {
+                return Err(DataFusionError::Internal(format!(
+                    "Internal error: Expected 2 state fields for approx_percentile but got {}",
+                    states.len()
+                )))
+            }
+        };
+        self.set_percentile(percentiles)?;
+
+        for bytes in binary_array(states)?.iter().flatten() {
+            let other = TDigest::from_bytes(bytes).ok_or_else(|| {
+                DataFusionError::Internal(format!(
+                    "Internal error: invalid t-digest state of {} bytes",
+                    bytes.len()
+                ))
+            })?;
+            self.digest.merge(&other);
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self) -> DataFusionResult<ScalarValue> {
+        let value = self
+            .percentile
+            .and_then(|percentile| self.digest.quantile(percentile));
+        Ok(ScalarValue::Float64(value))
+    }
+}
+
+/// The first state field, which holds the serialized sketches.
+fn binary_array(states: &[ArrayRef]) -> DataFusionResult<&BinaryArray> {
+    states
+        .first()
+        .and_then(|array| array.as_any().downcast_ref::<BinaryArray>())
+        .ok_or_else(|| {
+            DataFusionError::Internal("Internal error: expected binary sketch state".to_string())
+        })
+}
+
+fn float64_array(array: &ArrayRef) -> DataFusionResult<&Float64Array> {
+    array
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .ok_or_else(|| {
+            DataFusionError::Internal(format!(
+                "Internal error: expected Float64 argument but got {}",
+                array.data_type()
+            ))
+        })
+}
+
+fn hash_one<T: Hash + ?Sized>(v: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    v.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Call `f` with the hash of every non-null value of `array`.
+///
+/// Equal values must hash equally across batches, so all hashes use the (fixed key)
+/// [`DefaultHasher`].
+fn hash_values(array: &ArrayRef, mut f: impl FnMut(u64)) -> DataFusionResult<()> {
+    macro_rules! hash_primitive {
+        ($ARRAY_TYPE:ty, $TO_HASHABLE:expr) => {{
+            let array = array.as_any().downcast_ref::<$ARRAY_TYPE>().unwrap();
+            for v in array.iter().flatten() {
+                f(hash_one(&$TO_HASHABLE(v)));
+            }
+        }};
+    }
+
+    match array.data_type() {
+        DataType::Int64 => hash_primitive!(Int64Array, |v: i64| v),
+        DataType::UInt64 => hash_primitive!(UInt64Array, |v: u64| v),
+        DataType::Float64 => hash_primitive!(Float64Array, |v: f64| v.to_bits()),
+        DataType::Boolean => hash_primitive!(BooleanArray, |v: bool| v),
+        DataType::Utf8 => {
+            for v in as_string_array(array).iter().flatten() {
+                f(hash_one(v));
+            }
+        }
+        // tags: hash every dictionary value once
+        DataType::Dictionary(key, value)
+            if key.as_ref() == &DataType::Int32 && value.as_ref() == &DataType::Utf8 =>
+        {
+            let dictionary = as_dictionary_array::<Int32Type>(array);
+            let values = as_string_array(dictionary.values());
+            let hashes: Vec<_> = values.iter().map(|v| v.map(hash_one)).collect();
+            for key in dictionary.keys().iter().flatten() {
+                if let Some(hash) = hashes[key as usize] {
+                    f(hash);
+                }
+            }
+        }
+        _ => {
+            for i in 0..array.len() {
+                if array.is_valid(i) {
+                    f(hash_one(&ScalarValue::try_from_array(array, i)?));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use arrow::{
+        array::{DictionaryArray, StringArray, TimestampNanosecondArray},
+        datatypes::{Field, Schema},
+        record_batch::RecordBatch,
+    };
+    use datafusion::{assert_batches_eq, datasource::MemTable, logical_plan::Expr, prelude::*};
+    use schema::{TIME_DATA_TIMEZONE, TIME_DATA_TYPE};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_approx_count_distinct() {
+        let aggs = vec![
+            APPROX_COUNT_DISTINCT_UDAF
+                .call(vec![col("tag")])
+                .alias("tags"),
+            APPROX_COUNT_DISTINCT_UDAF
+                .call(vec![col("s")])
+                .alias("strings"),
+            APPROX_COUNT_DISTINCT_UDAF
+                .call(vec![col("f")])
+                .alias("floats"),
+            APPROX_COUNT_DISTINCT_UDAF
+                .call(vec![col("time")])
+                .alias("times"),
+        ];
+        let expected = [
+            "+------+---------+--------+-------+",
+            "| tags | strings | floats | times |",
+            "+------+---------+--------+-------+",
+            "| 3    | 3       | 3      | 6     |",
+            "+------+---------+--------+-------+",
+        ];
+        assert_batches_eq!(expected, &run(aggs).await);
+    }
+
+    #[tokio::test]
+    async fn test_approx_percentile() {
+        let aggs = vec![
+            APPROX_PERCENTILE_UDAF
+                .call(vec![col("f"), lit(0.0)])
+                .alias("p0"),
+            APPROX_PERCENTILE_UDAF
+                .call(vec![col("f"), lit(0.5)])
+                .alias("p50"),
+            APPROX_PERCENTILE_UDAF
+                .call(vec![col("f"), lit(1.0)])
+                .alias("p100"),
+        ];
+        let expected = [
+            "+----+-----+------+",
+            "| p0 | p50 | p100 |",
+            "+----+-----+------+",
+            "| 1  | 2   | 3    |",
+            "+----+-----+------+",
+        ];
+        assert_batches_eq!(expected, &run(aggs).await);
+
+        let err = run_err(vec![APPROX_PERCENTILE_UDAF.call(vec![col("f"), lit(50.0)])]).await;
+        assert!(err.contains("must be between 0 and 1"), "{}", err);
+    }
+
+    /// Aggregate over two partitions (so intermediate states get merged) of
+    ///
+    /// | tag | s    | f    | time |
+    /// |-----|------|------|------|
+    /// | a   | one  | 1    | 1    |
+    /// | b   | two  | 2    | 2    |
+    /// | a   | null | null | 3    |
+    /// | c   | one  | 3    | 4    |
+    /// | c   | two  | 2    | 5    |
+    /// | b   | tre  | null | 6    |
+    async fn run(aggs: Vec<Expr>) -> Vec<RecordBatch> {
+        frame(aggs).collect().await.unwrap()
+    }
+
+    async fn run_err(aggs: Vec<Expr>) -> String {
+        frame(aggs).collect().await.unwrap_err().to_string()
+    }
+
+    fn frame(aggs: Vec<Expr>) -> Arc<DataFrame> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                "tag",
+                DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+                true,
+            ),
+            Field::new("s", DataType::Utf8, true),
+            Field::new("f", DataType::Float64, true),
+            Field::new("time", TIME_DATA_TYPE(), false),
+        ]));
+        let batch =
+            |tags: Vec<&'static str>, s: Vec<Option<&str>>, f: Vec<Option<f64>>, time: Vec<i64>| {
+                RecordBatch::try_new(
+                    Arc::clone(&schema),
+                    vec![
+                        Arc::new(tags.into_iter().collect::<DictionaryArray<Int32Type>>()),
+                        Arc::new(StringArray::from(s)),
+                        Arc::new(Float64Array::from(f)),
+                        Arc::new(TimestampNanosecondArray::from_vec(
+                            time,
+                            TIME_DATA_TIMEZONE(),
+                        )),
+                    ],
+                )
+                .unwrap()
+            };
+        let partitions = vec![
+            vec![batch(
+                vec!["a", "b", "a"],
+                vec![Some("one"), Some("two"), None],
+                vec![Some(1.0), Some(2.0), None],
+                vec![1, 2, 3],
+            )],
+            vec![batch(
+                vec!["c", "c", "b"],
+                vec![Some("one"), Some("two"), Some("tre")],
+                vec![Some(3.0), Some(2.0), None],
+                vec![4, 5, 6],
+            )],
+        ];
+
+        let provider = MemTable::try_new(Arc::clone(&schema), partitions).unwrap();
+        let ctx = SessionContext::new();
+        ctx.register_table("t", Arc::new(provider)).unwrap();
+        ctx.table("t").unwrap().aggregate(vec![], aggs).unwrap()
+    }
+}
@@ -0,0 +1,128 @@
+//! A HyperLogLog sketch for estimating the number of distinct values.
+//!
+//! See "HyperLogLog: the analysis of a near-optimal cardinality estimation algorithm" by Flajolet
+//! et al. The sketch uses 64 bit hashes, so no large range correction is needed.
+
+/// Number of bits of the hash used to select a register.
+const PRECISION: u32 = 14;
+
+/// Number of registers, i.e. the size of the serialized sketch in bytes.
+pub(crate) const NUM_REGISTERS: usize = 1 << PRECISION;
+
+/// HyperLogLog sketch over 64 bit hashes with a standard error of about 0.8%.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HyperLogLog {
+    registers: Vec<u8>,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self {
+            registers: vec![0; NUM_REGISTERS],
+        }
+    }
+}
+
+impl HyperLogLog {
+    /// Restore a sketch from the output of [`registers`](Self::registers).
+    pub(crate) fn from_registers(registers: &[u8]) -> Option<Self> {
+        (registers.len() == NUM_REGISTERS).then(|| Self {
+            registers: registers.to_vec(),
+        })
+    }
+
+    /// The registers of this sketch.
+    pub(crate) fn registers(&self) -> &[u8] {
+        &self.registers
+    }
+
+    /// Add the hash of a value.
+    pub(crate) fn add_hash(&mut self, hash: u64) {
+        let index = (hash & (NUM_REGISTERS as u64 - 1)) as usize;
+        // position of the first set bit in the remaining bits, counting from 1
+        let rank = ((hash >> PRECISION).trailing_zeros() + 1).min(64 - PRECISION + 1) as u8;
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+    }
+
+    /// Merge `other` into this sketch, the result estimates the size of the union of both sets.
+    pub(crate) fn merge(&mut self, other: &Self) {
+        for (a, b) in self.registers.iter_mut().zip(&other.registers) {
+            *a = (*a).max(*b);
+        }
+    }
+
+    /// Estimated number of distinct values added to this sketch.
+    pub(crate) fn estimate(&self) -> u64 {
+        let m = NUM_REGISTERS as f64;
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let sum: f64 = self.registers.iter().map(|r| 2f64.powi(-(*r as i32))).sum();
+        let raw = alpha * m * m / sum;
+
+        // linear counting is more accurate for small cardinalities
+        let zeros = self.registers.iter().filter(|r| **r == 0).count();
+        let estimate = if raw <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        };
+
+        estimate.round() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+
+    use super::*;
+
+    fn hash(v: u64) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        v.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_estimate() {
+        assert_eq!(HyperLogLog::default().estimate(), 0);
+
+        for n in [1, 10, 1_000, 100_000, 1_000_000] {
+            let mut hll = HyperLogLog::default();
+            for v in 0..n {
+                // duplicates do not change the estimate
+                hll.add_hash(hash(v));
+                hll.add_hash(hash(v));
+            }
+
+            let error = (hll.estimate() as f64 - n as f64).abs() / n as f64;
+            assert!(error < 0.03, "n={} estimate={}", n, hll.estimate());
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut a = HyperLogLog::default();
+        let mut b = HyperLogLog::default();
+        let mut all = HyperLogLog::default();
+        for v in 0..10_000 {
+            if v % 3 == 0 {
+                a.add_hash(hash(v));
+            } else {
+                b.add_hash(hash(v));
+            }
+            all.add_hash(hash(v));
+        }
+
+        a.merge(&b);
+        assert_eq!(a, all);
+
+        let restored = HyperLogLog::from_registers(a.registers()).unwrap();
+        assert_eq!(restored, all);
+        assert!(HyperLogLog::from_registers(&[0; 3]).is_none());
+    }
+}
@@ -0,0 +1,240 @@
+//! A merging t-digest for estimating quantiles.
+//!
+//! See "Computing extremely accurate quantiles using t-digests" by Dunning and Ertl. Values are
+//! buffered and periodically merged into a sorted list of centroids whose maximum weight shrinks
+//! towards the tails of the distribution, so extreme quantiles stay accurate.
+
+/// Controls the number of centroids kept, and thereby accuracy vs size.
+const COMPRESSION: f64 = 100.0;
+
+/// Number of buffered values or centroids that triggers a merge.
+const BUFFER_SIZE: usize = 10 * COMPRESSION as usize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/// T-digest sketch of a distribution of `f64` values.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TDigest {
+    /// Merged centroids, sorted by mean.
+    centroids: Vec<Centroid>,
+
+    /// Values and centroids not merged yet.
+    buffer: Vec<Centroid>,
+
+    min: f64,
+    max: f64,
+}
+
+impl Default for TDigest {
+    fn default() -> Self {
+        Self {
+            centroids: vec![],
+            buffer: vec![],
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl TDigest {
+    /// Add a single value, NaNs are ignored.
+    pub(crate) fn add(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.push(Centroid {
+            mean: value,
+            weight: 1.0,
+        });
+    }
+
+    /// Merge `other` into this digest.
+    pub(crate) fn merge(&mut self, other: &Self) {
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        for centroid in other.centroids.iter().chain(&other.buffer) {
+            self.push(*centroid);
+        }
+    }
+
+    fn push(&mut self, centroid: Centroid) {
+        self.buffer.push(centroid);
+        if self.buffer.len() >= BUFFER_SIZE {
+            self.centroids = self.merged();
+            self.buffer.clear();
+        }
+    }
+
+    /// Merge the buffer into the centroids.
+    fn merged(&self) -> Vec<Centroid> {
+        if self.buffer.is_empty() {
+            return self.centroids.clone();
+        }
+
+        let mut all: Vec<_> = self.centroids.iter().chain(&self.buffer).copied().collect();
+        all.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+        let total: f64 = all.iter().map(|c| c.weight).sum();
+
+        let mut merged = Vec::with_capacity(all.len());
+        let mut current = all[0];
+        let mut weight_so_far = 0.0;
+        for centroid in all.into_iter().skip(1) {
+            let q = (weight_so_far + (current.weight + centroid.weight) / 2.0) / total;
+            let max_weight = 4.0 * total * q * (1.0 - q) / COMPRESSION;
+            if current.weight + centroid.weight <= max_weight {
+                let weight = current.weight + centroid.weight;
+                current.mean += (centroid.mean - current.mean) * centroid.weight / weight;
+                current.weight = weight;
+            } else {
+                weight_so_far += current.weight;
+                merged.push(current);
+                current = centroid;
+            }
+        }
+        merged.push(current);
+
+        merged
+    }
+
+    /// Estimate the value at quantile `q` (between 0 and 1), `None` if the digest is empty.
+    pub(crate) fn quantile(&self, q: f64) -> Option<f64> {
+        let centroids = self.merged();
+        let total: f64 = centroids.iter().map(|c| c.weight).sum();
+        let target = q.clamp(0.0, 1.0) * total;
+
+        // interpolate between the centers of the centroids, and the min and max at the ends
+        let mut prev = (0.0, self.min);
+        let mut weight_so_far = 0.0;
+        for centroid in &centroids {
+            let center = weight_so_far + centroid.weight / 2.0;
+            if target < center {
+                return Some(interpolate(prev, (center, centroid.mean), target));
+            }
+            prev = (center, centroid.mean);
+            weight_so_far += centroid.weight;
+        }
+
+        (!centroids.is_empty()).then(|| interpolate(prev, (total, self.max), target))
+    }
+
+    /// Serialize this digest as min, max and the (mean, weight) pairs of all centroids.
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let centroids = self.merged();
+        let mut bytes = Vec::with_capacity(16 * (centroids.len() + 1));
+        for v in [self.min, self.max]
+            .into_iter()
+            .chain(centroids.iter().flat_map(|c| [c.mean, c.weight]))
+        {
+            bytes.extend_from_slice(&v.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Restore a digest from the output of [`to_bytes`](Self::to_bytes).
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 16 || bytes.len() % 16 != 0 {
+            return None;
+        }
+
+        let mut values = bytes
+            .chunks_exact(8)
+            .map(|b| f64::from_le_bytes(b.try_into().expect("8 bytes")));
+        let min = values.next()?;
+        let max = values.next()?;
+        let mut centroids = vec![];
+        while let (Some(mean), Some(weight)) = (values.next(), values.next()) {
+            centroids.push(Centroid { mean, weight });
+        }
+
+        Some(Self {
+            centroids,
+            buffer: vec![],
+            min,
+            max,
+        })
+    }
+}
+
+fn interpolate((x0, y0): (f64, f64), (x1, y1): (f64, f64), x: f64) -> f64 {
+    if x1 <= x0 {
+        y1
+    } else {
+        y0 + (x - x0) / (x1 - x0) * (y1 - y0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantile_exact() {
+        let mut digest = TDigest::default();
+        assert_eq!(digest.quantile(0.5), None);
+
+        digest.add(3.0);
+        assert_eq!(digest.quantile(0.0), Some(3.0));
+        assert_eq!(digest.quantile(0.99), Some(3.0));
+
+        for v in [5.0, 1.0, 4.0, 2.0, f64::NAN] {
+            digest.add(v);
+        }
+        assert_eq!(digest.quantile(0.0), Some(1.0));
+        assert_eq!(digest.quantile(0.5), Some(3.0));
+        assert_eq!(digest.quantile(1.0), Some(5.0));
+    }
+
+    #[test]
+    fn test_quantile_approx() {
+        let n = 100_000;
+        let mut a = TDigest::default();
+        let mut b = TDigest::default();
+        // add the values in a scrambled order, split over two digests
+        for i in 0..n {
+            let v = ((i * 7_919) % n) as f64;
+            if i % 2 == 0 {
+                a.add(v);
+            } else {
+                b.add(v);
+            }
+        }
+        a.merge(&b);
+
+        for (q, tolerance) in [(0.001, 0.0005), (0.1, 0.005), (0.5, 0.01), (0.99, 0.001)] {
+            let expected = q * (n - 1) as f64;
+            let actual = a.quantile(q).unwrap();
+            assert!(
+                (actual - expected).abs() <= tolerance * n as f64,
+                "q={} expected={} actual={}",
+                q,
+                expected,
+                actual
+            );
+        }
+        assert_eq!(a.quantile(0.0), Some(0.0));
+        assert_eq!(a.quantile(1.0), Some((n - 1) as f64));
+    }
+
+    #[test]
+    fn test_serialization() {
+        let mut digest = TDigest::default();
+        for v in 0..10_000 {
+            digest.add(v as f64);
+        }
+
+        let restored = TDigest::from_bytes(&digest.to_bytes()).unwrap();
+        assert_eq!(restored.quantile(0.5), digest.quantile(0.5));
+        assert_eq!(restored.to_bytes(), digest.to_bytes());
+
+        let empty = TDigest::from_bytes(&TDigest::default().to_bytes()).unwrap();
+        assert_eq!(empty.quantile(0.5), None);
+        assert!(TDigest::from_bytes(&[0; 17]).is_none());
+    }
+}
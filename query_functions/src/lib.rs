@@ -16,6 +16,9 @@ use datafusion::{
 use group_by::WindowDuration;
 use window::EncodedWindowDuration;
 
+/// Approximate aggregates
+mod approx;
+
 /// Grouping by structs
 pub mod group_by;
 
@@ -31,6 +34,7 @@ mod window;
 /// Function registry
 mod registry;
 
+pub use crate::approx::{APPROX_COUNT_DISTINCT_UDAF_NAME, APPROX_PERCENTILE_UDAF_NAME};
 pub use crate::regex::REGEX_MATCH_UDF_NAME;
 pub use crate::regex::REGEX_NOT_MATCH_UDF_NAME;
 
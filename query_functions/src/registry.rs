@@ -7,7 +7,7 @@ use datafusion::{
 };
 use once_cell::sync::Lazy;
 
-use crate::{regex, window};
+use crate::{approx, regex, window};
 
 static REGISTRY: Lazy<IOxFunctionRegistry> = Lazy::new(IOxFunctionRegistry::new);
 
@@ -42,10 +42,16 @@ impl FunctionRegistry for IOxFunctionRegistry {
     }
 
     fn udaf(&self, name: &str) -> DataFusionResult<Arc<AggregateUDF>> {
-        Err(DataFusionError::Plan(format!(
-            "IOx FunctionRegistry does not contain user defined aggregate function '{}'",
-            name
-        )))
+        match name {
+            approx::APPROX_COUNT_DISTINCT_UDAF_NAME => {
+                Ok(approx::APPROX_COUNT_DISTINCT_UDAF.clone())
+            }
+            approx::APPROX_PERCENTILE_UDAF_NAME => Ok(approx::APPROX_PERCENTILE_UDAF.clone()),
+            _ => Err(DataFusionError::Plan(format!(
+                "IOx FunctionRegistry does not contain user defined aggregate function '{}'",
+                name
+            ))),
+        }
     }
 }
 
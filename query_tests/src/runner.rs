@@ -275,7 +275,7 @@ impl<W: Write> Runner<W> {
             let ctx = db.new_query_context(None);
 
             let physical_plan = planner
-                .query(sql, &ctx)
+                .query(sql, &[], &ctx)
                 .await
                 .expect("built plan successfully");
 
@@ -428,7 +428,7 @@ impl DbSetup for TwoMeasurementsManyFieldsTwoChunks {
             let planner = SqlQueryPlanner::default();
             let ctx = scenario.db.new_query_context(None);
             let physical_plan = planner
-                .query(query, &ctx)
+                .query(query, &[], &ctx)
                 .await
                 .expect("built plan successfully");
             let mut query_completed_token =
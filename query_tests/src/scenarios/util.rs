@@ -19,7 +19,10 @@ use ingester::{
     querier_handler::prepare_data_to_querier,
 };
 use iox_catalog::interface::get_schema_by_name;
-use iox_query::exec::{Executor, ExecutorConfig};
+use iox_query::{
+    exec::{Executor, ExecutorConfig},
+    QueryId,
+};
 use iox_tests::util::{TestCatalog, TestNamespace, TestShard};
 use itertools::Itertools;
 use mutable_batch_lp::LinesConverter;
@@ -679,6 +682,10 @@ static GLOBAL_EXEC: Lazy<Arc<Executor>> = Lazy::new(|| {
     Arc::new(Executor::new_with_config(ExecutorConfig {
         num_threads: 1,
         target_query_partitions: 4,
+        mem_pool_size: None,
+        spill_dir: None,
+        external_dedup_min_chunks: None,
+        aggregate_pushdown: false,
     }))
 });
 
@@ -924,6 +931,7 @@ impl MockIngester {
             self.catalog.catalog(),
             self.catalog.time_provider(),
             self.catalog.metric_registry(),
+            self.catalog.object_store(),
             &Handle::current(),
         ));
         let shard_to_ingesters = [(
@@ -985,6 +993,7 @@ impl IngesterFlightClient for MockIngester {
         &self,
         _ingester_address: Arc<str>,
         request: IngesterQueryRequest,
+        _query_id: Option<QueryId>,
     ) -> Result<Box<dyn IngesterFlightClientQueryData>, IngesterFlightClientError> {
         // NOTE: we MUST NOT unwrap errors here because some query tests assert error behavior
         // (e.g. passing predicates of wrong types)
@@ -1041,6 +1050,9 @@ impl QueryDataAdapter {
                                     tombstone_max_sequence_number: status
                                         .tombstone_max_sequence_number
                                         .map(|x| x.get()),
+                                    max_sequence_number: status
+                                        .max_sequence_number
+                                        .map(|x| x.get()),
                                 }),
                             },
                         ),
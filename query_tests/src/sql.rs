@@ -31,7 +31,7 @@ where
         let ctx = db.new_query_context(None);
 
         let physical_plan = planner
-            .query(&sql, &ctx)
+            .query(&sql, &[], &ctx)
             .await
             .expect("built plan successfully");
 
@@ -60,7 +60,7 @@ where
         let ctx = db.new_query_context(None);
 
         let result: Result<(), DataFusionError> = async {
-            let physical_plan = planner.query(&sql, &ctx).await?;
+            let physical_plan = planner.query(&sql, &[], &ctx).await?;
 
             ctx.collect(physical_plan).await?;
             Ok(())
@@ -15,6 +15,12 @@
 //!                      ║                        ║
 //!                      ║  ┌──────────────────┐  ║
 //!                      ║  │    Namespace     │  ║
+//!                      ║  │   Rate Limiter   │  ║
+//!                      ║  └──────────────────┘  ║
+//!                      ║            │           ║
+//!                      ║            ▼           ║
+//!                      ║  ┌──────────────────┐  ║
+//!                      ║  │    Namespace     │  ║
 //!                      ║  │   Autocreation   │─ ─ ─ ─ ─ ─ ─ ┐
 //!                      ║  └──────────────────┘  ║
 //!                      ║            │           ║           │
@@ -53,6 +59,9 @@
 //! resulting operation through the common [`DmlHandler`] composed of the layers
 //! described above.
 //!
+//! The [`NamespaceRateLimiter`] rejects requests to namespaces that exceed
+//! the write rate limit configured for them in the global catalog.
+//!
 //! The [`NamespaceAutocreation`] handler (for testing only) populates the
 //! global catalog with an entry for each namespace it observes, using the
 //! [`NamespaceCache`] as an optimisation, allowing the handler to skip sending
@@ -87,6 +96,9 @@ pub use sharded_write_buffer::*;
 mod ns_autocreation;
 pub use ns_autocreation::*;
 
+mod rate_limit;
+pub use rate_limit::*;
+
 mod partitioner;
 pub use partitioner::*;
 
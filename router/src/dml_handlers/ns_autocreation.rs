@@ -224,6 +224,9 @@ mod tests {
                 query_pool_id: QueryPoolId::new(42),
                 max_tables: 10000,
                 max_columns_per_table: 1000,
+                write_rate_limit: None,
+                query_rate_limit: None,
+                query_timeout_ms: None,
             }
         );
     }
@@ -0,0 +1,304 @@
+use super::DmlHandler;
+use async_trait::async_trait;
+use data_types::{DatabaseName, DeletePredicate};
+use hashbrown::HashMap;
+use iox_catalog::interface::Catalog;
+use iox_time::{Time, TimeProvider};
+use metric::U64Counter;
+use observability_deps::tracing::*;
+use parking_lot::Mutex;
+use std::{fmt::Debug, marker::PhantomData, num::NonZeroU32, sync::Arc, time::Duration};
+use thiserror::Error;
+use trace::ctx::SpanContext;
+use tracker::RateLimiter;
+
+/// An error rejecting a request that exceeds the rate limit of its namespace.
+#[derive(Debug, Error)]
+pub enum RateLimitError {
+    /// The namespace received more requests than its configured limit.
+    #[error(
+        "namespace {namespace} exceeded its limit of {limit} writes per second, \
+         retry after {retry_after:?}"
+    )]
+    Exceeded {
+        /// The rate-limited namespace.
+        namespace: String,
+        /// The configured limit, in requests per second.
+        limit: u32,
+        /// Duration after which the next request will be accepted.
+        retry_after: Duration,
+    },
+}
+
+impl RateLimitError {
+    /// Duration after which the client should retry the request.
+    pub fn retry_after(&self) -> Duration {
+        match self {
+            Self::Exceeded { retry_after, .. } => *retry_after,
+        }
+    }
+}
+
+/// Write rate limit of a namespace, as last read from the catalog.
+#[derive(Debug, Clone, Copy)]
+struct CachedLimit {
+    limit: Option<NonZeroU32>,
+    fetched_at: Time,
+}
+
+/// A layer that enforces the per-namespace write rate limit stored in the
+/// [`Catalog`].
+///
+/// Writes and deletes share a single token bucket per namespace. Namespaces
+/// without a limit (or that do not exist yet) are not limited.
+///
+/// The limits are cached and re-read from the catalog once they are older
+/// than the configured refresh interval. If the catalog cannot be reached,
+/// the previously observed limit is used.
+#[derive(Debug)]
+pub struct NamespaceRateLimiter<T> {
+    catalog: Arc<dyn Catalog>,
+    time_provider: Arc<dyn TimeProvider>,
+    refresh_interval: Duration,
+
+    limits: Mutex<HashMap<DatabaseName<'static>, CachedLimit>>,
+    rate_limiter: RateLimiter,
+
+    rejected: U64Counter,
+
+    _input: PhantomData<T>,
+}
+
+impl<T> NamespaceRateLimiter<T> {
+    /// Return a new [`NamespaceRateLimiter`] that reads the write rate limits
+    /// from `catalog`, refreshing them every `refresh_interval`.
+    pub fn new(
+        catalog: Arc<dyn Catalog>,
+        time_provider: Arc<dyn TimeProvider>,
+        refresh_interval: Duration,
+        metrics: &metric::Registry,
+    ) -> Self {
+        let rejected = metrics
+            .register_metric::<U64Counter>(
+                "dml_handler_rate_limited",
+                "number of DML requests rejected by the per-namespace rate limit",
+            )
+            .recorder(&[]);
+
+        Self {
+            catalog,
+            rate_limiter: RateLimiter::new(Arc::clone(&time_provider)),
+            time_provider,
+            refresh_interval,
+            limits: Default::default(),
+            rejected,
+            _input: Default::default(),
+        }
+    }
+
+    async fn limit(&self, namespace: &DatabaseName<'static>) -> Option<NonZeroU32> {
+        let now = self.time_provider.now();
+
+        let cached = self.limits.lock().get(namespace).copied();
+        if let Some(cached) = cached {
+            let age = now
+                .checked_duration_since(cached.fetched_at)
+                .unwrap_or_default();
+            if age < self.refresh_interval {
+                return cached.limit;
+            }
+        }
+
+        let mut repos = self.catalog.repositories().await;
+        let limit = match repos.namespaces().get_by_name(namespace.as_str()).await {
+            Ok(ns) => ns
+                .and_then(|ns| ns.write_rate_limit)
+                .and_then(|limit| u32::try_from(limit).ok())
+                .and_then(NonZeroU32::new),
+            Err(e) => {
+                warn!(error=%e, %namespace, "failed to read namespace rate limit");
+                cached.and_then(|cached| cached.limit)
+            }
+        };
+
+        self.limits.lock().insert(
+            namespace.clone(),
+            CachedLimit {
+                limit,
+                fetched_at: now,
+            },
+        );
+
+        limit
+    }
+
+    async fn check(&self, namespace: &DatabaseName<'static>) -> Result<(), RateLimitError> {
+        let limit = match self.limit(namespace).await {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+
+        self.rate_limiter
+            .try_acquire(namespace.as_str(), limit)
+            .map_err(|e| {
+                debug!(%namespace, retry_after=?e.retry_after, "namespace rate limited");
+                self.rejected.inc(1);
+                RateLimitError::Exceeded {
+                    namespace: namespace.to_string(),
+                    limit: limit.get(),
+                    retry_after: e.retry_after,
+                }
+            })
+    }
+}
+
+#[async_trait]
+impl<T> DmlHandler for NamespaceRateLimiter<T>
+where
+    T: Debug + Send + Sync,
+{
+    type WriteError = RateLimitError;
+    type DeleteError = RateLimitError;
+
+    // This handler accepts any write input type, returning it to the caller
+    // unmodified.
+    type WriteInput = T;
+    type WriteOutput = T;
+
+    /// Write `batches` to `namespace`.
+    async fn write(
+        &self,
+        namespace: &'_ DatabaseName<'static>,
+        batches: Self::WriteInput,
+        _span_ctx: Option<SpanContext>,
+    ) -> Result<Self::WriteOutput, Self::WriteError> {
+        self.check(namespace).await?;
+        Ok(batches)
+    }
+
+    /// Delete the data specified in `delete`.
+    async fn delete(
+        &self,
+        namespace: &DatabaseName<'static>,
+        _table_name: &str,
+        _predicate: &DeletePredicate,
+        _span_ctx: Option<SpanContext>,
+    ) -> Result<(), Self::DeleteError> {
+        self.check(namespace).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use data_types::{QueryPoolId, TimestampRange, TopicId};
+    use iox_catalog::mem::MemCatalog;
+    use iox_time::MockProvider;
+    use metric::{Attributes, Metric};
+
+    const NAMESPACE: &str = "bananas";
+
+    async fn setup() -> (
+        Arc<dyn Catalog>,
+        Arc<MockProvider>,
+        Arc<metric::Registry>,
+        NamespaceRateLimiter<()>,
+    ) {
+        let metrics = Arc::new(metric::Registry::new());
+        let catalog: Arc<dyn Catalog> = Arc::new(MemCatalog::new(Arc::clone(&metrics)));
+        catalog
+            .repositories()
+            .await
+            .namespaces()
+            .create(NAMESPACE, "inf", TopicId::new(42), QueryPoolId::new(42))
+            .await
+            .unwrap();
+
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let limiter = NamespaceRateLimiter::new(
+            Arc::clone(&catalog),
+            Arc::clone(&time_provider) as _,
+            Duration::from_secs(60),
+            &metrics,
+        );
+
+        (catalog, time_provider, metrics, limiter)
+    }
+
+    #[tokio::test]
+    async fn test_no_limit() {
+        let (_catalog, _time_provider, _metrics, limiter) = setup().await;
+        let ns = DatabaseName::try_from(NAMESPACE).unwrap();
+
+        for _ in 0..100 {
+            limiter.write(&ns, (), None).await.unwrap();
+        }
+
+        // unknown namespaces are not limited either
+        let ns = DatabaseName::try_from("platanos").unwrap();
+        limiter.write(&ns, (), None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_limit() {
+        let (catalog, time_provider, metrics, limiter) = setup().await;
+        let ns = DatabaseName::try_from(NAMESPACE).unwrap();
+
+        catalog
+            .repositories()
+            .await
+            .namespaces()
+            .update_write_rate_limit(NAMESPACE, Some(2))
+            .await
+            .unwrap();
+
+        limiter.write(&ns, (), None).await.unwrap();
+        let pred = DeletePredicate {
+            range: TimestampRange::new(1, 2),
+            exprs: vec![],
+        };
+        limiter.delete(&ns, "table", &pred, None).await.unwrap();
+        let err = limiter.write(&ns, (), None).await.unwrap_err();
+        assert_matches!(err, RateLimitError::Exceeded { limit: 2, .. });
+        assert_eq!(err.retry_after(), Duration::from_millis(500));
+
+        let rejected = metrics
+            .get_instrument::<Metric<U64Counter>>("dml_handler_rate_limited")
+            .unwrap()
+            .get_observer(&Attributes::from(&[]))
+            .unwrap()
+            .fetch();
+        assert_eq!(rejected, 1);
+
+        time_provider.inc(Duration::from_millis(500));
+        limiter.write(&ns, (), None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_limit_refresh() {
+        let (catalog, time_provider, _metrics, limiter) = setup().await;
+        let ns = DatabaseName::try_from(NAMESPACE).unwrap();
+
+        limiter.write(&ns, (), None).await.unwrap();
+
+        catalog
+            .repositories()
+            .await
+            .namespaces()
+            .update_write_rate_limit(NAMESPACE, Some(1))
+            .await
+            .unwrap();
+
+        // the cached (unlimited) value is used until it expires
+        limiter.write(&ns, (), None).await.unwrap();
+        limiter.write(&ns, (), None).await.unwrap();
+
+        time_provider.inc(Duration::from_secs(60));
+        limiter.write(&ns, (), None).await.unwrap();
+        assert_matches!(
+            limiter.write(&ns, (), None).await,
+            Err(RateLimitError::Exceeded { limit: 1, .. })
+        );
+    }
+}
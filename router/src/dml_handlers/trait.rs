@@ -1,4 +1,6 @@
-use super::{partitioner::PartitionError, NamespaceCreationError, SchemaError, ShardError};
+use super::{
+    partitioner::PartitionError, NamespaceCreationError, RateLimitError, SchemaError, ShardError,
+};
 use async_trait::async_trait;
 use data_types::{DatabaseName, DeletePredicate};
 use std::{error::Error, fmt::Debug, sync::Arc};
@@ -29,6 +31,10 @@ pub enum DmlError {
     #[error(transparent)]
     Partition(#[from] PartitionError),
 
+    /// The request exceeds the rate limit of its namespace.
+    #[error(transparent)]
+    RateLimited(#[from] RateLimitError),
+
     /// An unknown error occured while processing the DML request.
     #[error("internal dml handler error: {0}")]
     Internal(Box<dyn Error + Send + Sync>),
@@ -26,7 +26,7 @@ use service_grpc_catalog::CatalogService;
 use service_grpc_object_store::ObjectStoreService;
 use service_grpc_schema::SchemaService;
 use std::sync::Arc;
-use tonic::{metadata::AsciiMetadataValue, Request, Response, Status};
+use tonic::{codec::CompressionEncoding, metadata::AsciiMetadataValue, Request, Response, Status};
 use trace::ctx::SpanContext;
 use write_summary::WriteSummary;
 
@@ -34,6 +34,9 @@ use write_summary::WriteSummary;
 // investigate the cause if you dare.
 const WRITE_TOKEN_GRPC_HEADER: &str = "x-iox-write-token";
 
+// The number of seconds after which a rate-limited request may be retried.
+const RETRY_AFTER_GRPC_HEADER: &str = "retry-after";
+
 /// This type is responsible for managing all gRPC services exposed by `router`.
 #[derive(Debug)]
 pub struct GrpcDelegate<D, S> {
@@ -78,6 +81,7 @@ where
             Arc::clone(&self.dml_handler),
             &*self.metrics,
         ))
+        .accept_compressed(CompressionEncoding::Gzip)
     }
 
     /// Acquire a [`SchemaService`] gRPC service implementation.
@@ -220,6 +224,15 @@ where
             .map_err(|e| match e.into() {
                 e @ DmlError::DatabaseNotFound(_) => Status::not_found(e.to_string()),
                 e @ DmlError::Schema(_) => Status::aborted(e.to_string()),
+                DmlError::RateLimited(e) => {
+                    let mut status = Status::resource_exhausted(e.to_string());
+                    let retry_after = e.retry_after();
+                    let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+                    status
+                        .metadata_mut()
+                        .insert(RETRY_AFTER_GRPC_HEADER, AsciiMetadataValue::from(secs));
+                    status
+                }
 
                 e @ (DmlError::Internal(_)
                 | DmlError::WriteBuffer(_)
@@ -13,7 +13,7 @@ use mutable_batch_lp::LinesConverter;
 use observability_deps::tracing::*;
 use predicate::delete_predicate::{parse_delete_predicate, parse_http_delete_request};
 use serde::Deserialize;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use std::{str::Utf8Error, sync::Arc};
 use thiserror::Error;
 use tokio::sync::{Semaphore, TryAcquireError};
@@ -97,6 +97,15 @@ impl Error {
             Error::RequestLimit => StatusCode::SERVICE_UNAVAILABLE,
         }
     }
+
+    /// Duration after which the end user should retry the request, if the
+    /// request was rejected by a rate limit.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::DmlHandler(DmlError::RateLimited(e)) => Some(e.retry_after()),
+            _ => None,
+        }
+    }
 }
 
 impl From<&DmlError> for StatusCode {
@@ -123,6 +132,7 @@ impl From<&DmlError> for StatusCode {
                 StatusCode::INTERNAL_SERVER_ERROR
             }
             DmlError::Partition(PartitionError::BatchWrite(_)) => StatusCode::INTERNAL_SERVER_ERROR,
+            DmlError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
         }
     }
 }
@@ -522,7 +532,10 @@ mod tests {
     use test_helpers::timeout::FutureTimeout;
     use tokio_stream::wrappers::ReceiverStream;
 
-    use crate::dml_handlers::mock::{MockDmlHandler, MockDmlHandlerCall};
+    use crate::dml_handlers::{
+        mock::{MockDmlHandler, MockDmlHandlerCall},
+        RateLimitError,
+    };
 
     use super::*;
 
@@ -893,6 +906,34 @@ mod tests {
         }
     );
 
+    test_write_handler!(
+        rate_limited,
+        query_string = "?org=bananas&bucket=test",
+        body = "platanos,tag1=A,tag2=B val=42i 123456".as_bytes(),
+        dml_handler = [Err(DmlError::RateLimited(RateLimitError::Exceeded {
+            namespace: "bananas_test".to_string(),
+            limit: 1,
+            retry_after: Duration::from_millis(1500),
+        }))],
+        want_result = Err(Error::DmlHandler(DmlError::RateLimited(_))),
+        want_dml_calls = [MockDmlHandlerCall::Write{namespace, ..}] => {
+            assert_eq!(namespace, "bananas_test");
+        }
+    );
+
+    #[test]
+    fn test_rate_limited_error() {
+        let err = Error::DmlHandler(DmlError::RateLimited(RateLimitError::Exceeded {
+            namespace: "bananas_test".to_string(),
+            limit: 1,
+            retry_after: Duration::from_millis(1500),
+        }));
+        assert_eq!(err.as_status_code(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(err.retry_after(), Some(Duration::from_millis(1500)));
+
+        assert_eq!(Error::RequestLimit.retry_after(), None);
+    }
+
     test_write_handler!(
         field_upsert_within_batch,
         query_string = "?org=bananas&bucket=test",
@@ -466,6 +466,28 @@ pub enum InfluxFieldType {
     Boolean,
 }
 
+impl InfluxFieldType {
+    /// Returns true if a field of this type can be migrated to `target`.
+    ///
+    /// Only widening numeric conversions (integers to floats) are supported. Data written with
+    /// the old type is cast to the new type on read.
+    pub fn can_migrate_to(&self, target: Self) -> bool {
+        matches!(
+            (self, target),
+            (Self::Integer | Self::UInteger, Self::Float)
+        )
+    }
+
+    /// Returns true if an arrow array of type `from` can be cast to `to` as part of a field type
+    /// migration, see [`can_migrate_to`](Self::can_migrate_to).
+    pub fn is_migration(from: &ArrowDataType, to: &ArrowDataType) -> bool {
+        match (Self::try_from(from.clone()), Self::try_from(to.clone())) {
+            (Ok(from), Ok(to)) => from.can_migrate_to(to),
+            _ => false,
+        }
+    }
+}
+
 impl From<InfluxFieldType> for ArrowDataType {
     fn from(t: InfluxFieldType) -> Self {
         match t {
@@ -887,6 +909,30 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_field_type_migration() {
+        assert!(InfluxFieldType::Integer.can_migrate_to(InfluxFieldType::Float));
+        assert!(InfluxFieldType::UInteger.can_migrate_to(InfluxFieldType::Float));
+        assert!(!InfluxFieldType::Integer.can_migrate_to(InfluxFieldType::String));
+        assert!(!InfluxFieldType::Boolean.can_migrate_to(InfluxFieldType::String));
+        assert!(!InfluxFieldType::Float.can_migrate_to(InfluxFieldType::Integer));
+        assert!(!InfluxFieldType::String.can_migrate_to(InfluxFieldType::Boolean));
+        assert!(!InfluxFieldType::Float.can_migrate_to(InfluxFieldType::Float));
+
+        assert!(InfluxFieldType::is_migration(
+            &ArrowDataType::Int64,
+            &ArrowDataType::Float64
+        ));
+        assert!(!InfluxFieldType::is_migration(
+            &ArrowDataType::Float64,
+            &ArrowDataType::Int64
+        ));
+        assert!(!InfluxFieldType::is_migration(
+            &ArrowDataType::Int32,
+            &ArrowDataType::Float64
+        ));
+    }
+
     #[test]
     fn test_round_trip() {
         let schema1 = SchemaBuilder::new()
@@ -3,12 +3,12 @@
 pub mod planner;
 pub mod test_util;
 
-use std::sync::Arc;
+use std::{fmt::Display, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use iox_query::{exec::ExecutionContextProvider, QueryDatabase};
 use trace::span::Span;
-use tracker::InstrumentedAsyncOwnedSemaphorePermit;
+use tracker::{InstrumentedAsyncOwnedSemaphorePermit, RateLimited};
 
 /// Trait that allows the query engine (which includes flight and storage/InfluxRPC) to access a virtual set of
 /// databases.
@@ -24,4 +24,44 @@ pub trait QueryDatabaseProvider: std::fmt::Debug + Send + Sync + 'static {
 
     /// Acquire concurrency-limiting sempahore
     async fn acquire_semaphore(&self, span: Option<Span>) -> InstrumentedAsyncOwnedSemaphorePermit;
+
+    /// Timeout for a query against the database `name`, given the timeout requested by the client
+    /// (if any).
+    ///
+    /// Returns `None` if the query may run indefinitely.
+    async fn query_timeout(&self, _name: &str, requested: Option<Duration>) -> Option<Duration> {
+        requested
+    }
+
+    /// Check if another query against the database `name` is permitted by its rate limit.
+    async fn check_rate_limit(&self, _name: &str) -> Result<(), RateLimited> {
+        Ok(())
+    }
+
+    /// Acquire a query slot of the database `name`, which limits the concurrent queries of a
+    /// single database. This should be called BEFORE [`acquire_semaphore`](Self::acquire_semaphore).
+    ///
+    /// Returns `None` if the queries of a database are not limited.
+    async fn acquire_namespace_semaphore(
+        &self,
+        _name: &str,
+        _span: Option<Span>,
+    ) -> Result<Option<InstrumentedAsyncOwnedSemaphorePermit>, QueryQueueFull> {
+        Ok(None)
+    }
+}
+
+/// A query was rejected because too many queries of its database are already waiting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryQueueFull {
+    /// Maximum number of waiting queries per database.
+    pub max_queued: usize,
+}
+
+impl Display for QueryQueueFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "query queue full ({} queries waiting)", self.max_queued)
+    }
 }
+
+impl std::error::Error for QueryQueueFull {}
@@ -2,9 +2,14 @@
 use std::sync::Arc;
 
 use datafusion::physical_plan::ExecutionPlan;
+use influxdb_influxql_parser::statement::Statement;
 use iox_query::{
     exec::IOxSessionContext,
-    frontend::{influxrpc::InfluxRpcPlanner, sql::SqlQueryPlanner},
+    frontend::{
+        influxql::{InfluxQLQueryPlanner, InfluxQLStatementPlan},
+        influxrpc::InfluxRpcPlanner,
+        sql::{QueryParam, SqlQueryPlanner},
+    },
     plan::{fieldlist::FieldListPlan, seriesset::SeriesSetPlans, stringset::StringSetPlan},
     Aggregate, QueryDatabase, WindowDuration,
 };
@@ -31,18 +36,48 @@ impl Planner {
         }
     }
 
-    /// Plan a SQL query against the data in `database`, and return a
-    /// DataFusion physical execution plan.
-    pub async fn sql(&self, query: impl Into<String> + Send) -> Result<Arc<dyn ExecutionPlan>> {
+    /// Plan a SQL query with the bind parameters `params` against the data in `database`, and
+    /// return a DataFusion physical execution plan.
+    pub async fn sql(
+        &self,
+        query: impl Into<String> + Send,
+        params: Vec<QueryParam>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
         let planner = SqlQueryPlanner::new();
         let query = query.into();
         let ctx = self.ctx.child_ctx("planner sql");
 
+        self.ctx
+            .run(async move { planner.query(&query, &params, &ctx).await })
+            .await
+    }
+
+    /// Plan an InfluxQL query against the data in `database`, and return a DataFusion physical
+    /// execution plan.
+    pub async fn influxql(
+        &self,
+        query: impl Into<String> + Send,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let planner = InfluxQLQueryPlanner::new();
+        let query = query.into();
+        let ctx = self.ctx.child_ctx("planner influxql");
+
         self.ctx
             .run(async move { planner.query(&query, &ctx).await })
             .await
     }
 
+    /// Plan a parsed InfluxQL statement against the data in `database`, and return a DataFusion
+    /// physical execution plan along with how its result is split into series.
+    pub async fn influxql_statement(&self, statement: Statement) -> Result<InfluxQLStatementPlan> {
+        let planner = InfluxQLQueryPlanner::new();
+        let ctx = self.ctx.child_ctx("planner influxql statement");
+
+        self.ctx
+            .run(async move { planner.statement(&statement, &ctx).await })
+            .await
+    }
+
     /// Creates a plan as described on
     /// [`InfluxRpcPlanner::table_names`], on a separate threadpool
     pub async fn table_names<D>(
@@ -116,8 +116,8 @@ fn to_partition(p: data_types::Partition) -> Partition {
 mod tests {
     use super::*;
     use data_types::{
-        ColumnId, ColumnSet, CompactionLevel, ParquetFileParams, SequenceNumber, ShardIndex,
-        Timestamp,
+        ColumnId, ColumnSet, ColumnStatsSet, CompactionLevel, ParquetFileParams, SequenceNumber,
+        ShardIndex, Timestamp,
     };
     use generated_types::influxdata::iox::catalog::v1::catalog_service_server::CatalogService;
     use iox_catalog::mem::MemCatalog;
@@ -173,6 +173,7 @@ mod tests {
                 compaction_level: CompactionLevel::Initial,
                 created_at: Timestamp::new(2343),
                 column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+                column_stats: ColumnStatsSet::default(),
             };
             let p2params = ParquetFileParams {
                 object_store_id: Uuid::new_v4(),
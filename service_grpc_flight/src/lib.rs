@@ -2,33 +2,50 @@
 
 use arrow::error::ArrowError;
 use arrow_flight::{
+    flight_descriptor::DescriptorType,
     flight_service_server::{FlightService as Flight, FlightServiceServer as FlightServer},
-    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightInfo,
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint, FlightInfo,
     HandshakeRequest, HandshakeResponse, PutResult, SchemaAsIpc, SchemaResult, Ticket,
 };
 use arrow_util::optimize::{optimize_record_batch, optimize_schema};
 use bytes::{Bytes, BytesMut};
 use data_types::{DatabaseName, DatabaseNameError};
-use datafusion::physical_plan::ExecutionPlan;
-use futures::{SinkExt, Stream, StreamExt};
+use datafusion::{
+    error::DataFusionError,
+    physical_plan::{stream::RecordBatchStreamAdapter, ExecutionPlan, SendableRecordBatchStream},
+};
+use datafusion_util::deadline::{DeadlineExceeded, DeadlineStream};
+use futures::{Future, SinkExt, Stream, StreamExt, TryStreamExt};
 use generated_types::influxdata::iox::querier::v1 as proto;
 use iox_query::{
     exec::{ExecutionContextProvider, IOxSessionContext},
-    QueryCompletedToken, QueryDatabase,
+    frontend::sql::{ParamValue, QueryParam},
+    query_snapshot, QueryCompletedToken, QueryDatabase, QueryId, QuerySnapshot, SnapshotExpired,
+    QUERY_ID_HEADER,
 };
 use observability_deps::tracing::{info, warn};
+use parking_lot::Mutex;
 use pin_project::{pin_project, pinned_drop};
 use prost::Message;
 use serde::Deserialize;
 use service_common::{planner::Planner, QueryDatabaseProvider};
 use snafu::{ResultExt, Snafu};
-use std::{fmt::Debug, pin::Pin, sync::Arc, task::Poll};
-use tokio::task::JoinHandle;
-use tonic::{Request, Response, Streaming};
+use std::{collections::HashMap, fmt::Debug, pin::Pin, sync::Arc, task::Poll, time::Duration};
+use tokio::{sync::oneshot, task::JoinHandle, time::Instant};
+use tonic::{metadata::AsciiMetadataValue, Request, Response, Streaming};
 use trace::{ctx::SpanContext, span::SpanExt};
 use trace_http::ctx::{RequestLogContext, RequestLogContextExt};
 use tracker::InstrumentedAsyncOwnedSemaphorePermit;
 
+/// gRPC metadata key telling the client after how many seconds a rate-limited query may be
+/// retried.
+const RETRY_AFTER_HEADER: &str = "retry-after";
+
+/// Type of the Flight action that cancels a running query.
+///
+/// The body of the action is the ID of the query, as returned in the [`QUERY_ID_HEADER`].
+pub const CANCEL_QUERY_ACTION: &str = "CancelQuery";
+
 #[allow(clippy::enum_variant_names)]
 #[derive(Debug, Snafu)]
 pub enum Error {
@@ -70,6 +87,57 @@ pub enum Error {
 
     #[snafu(display("Error during protobuf serialization: {}", source))]
     Serialization { source: prost::EncodeError },
+
+    #[snafu(display("Query exceeded its timeout of {:?} and was cancelled", timeout))]
+    QueryTimeout { timeout: Duration },
+
+    #[snafu(display(
+        "Database {} exceeded its query rate limit, retry after {:?}",
+        database_name,
+        retry_after
+    ))]
+    RateLimited {
+        database_name: String,
+        retry_after: Duration,
+    },
+
+    #[snafu(display(
+        "Database {} has too many queued queries (at most {}), retry later",
+        database_name,
+        max_queued
+    ))]
+    QueueFull {
+        database_name: String,
+        max_queued: usize,
+    },
+
+    #[snafu(display("Query {} was cancelled", query_id))]
+    QueryCancelled { query_id: QueryId },
+
+    #[snafu(display("Unknown action type: {}", action_type))]
+    UnknownAction { action_type: String },
+
+    #[snafu(display("Invalid query ID: {}", query_id))]
+    InvalidQueryId { query_id: String },
+
+    #[snafu(display("Query {} is not running", query_id))]
+    QueryNotRunning { query_id: QueryId },
+
+    #[snafu(display("{}", source))]
+    InvalidSnapshotToken { source: query_snapshot::Error },
+
+    #[snafu(display(
+        "Invalid partition {} of a query split into {} partitions",
+        partition,
+        partition_count
+    ))]
+    InvalidPartition {
+        partition: u32,
+        partition_count: u32,
+    },
+
+    #[snafu(display("Invalid flight descriptor, expected a command containing a ReadInfo"))]
+    InvalidFlightDescriptor,
 }
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -85,9 +153,19 @@ impl From<Error> for tonic::Status {
             | Error::InvalidTicket { .. }
             | Error::InvalidTicketLegacy { .. }
             | Error::InvalidQuery { .. }
+            | Error::UnknownAction { .. }
+            | Error::InvalidQueryId { .. }
+            | Error::QueryNotRunning { .. }
+            | Error::InvalidSnapshotToken { .. }
+            | Error::InvalidPartition { .. }
+            | Error::InvalidFlightDescriptor
             // TODO(edd): this should be `debug`. Keeping at info whilst IOx still in early development
             | Error::InvalidDatabaseName { .. } => info!(?err, msg),
-            Error::Query { .. } => info!(?err, msg),
+            Error::Query { .. }
+            | Error::QueryTimeout { .. }
+            | Error::RateLimited { .. }
+            | Error::QueueFull { .. }
+            | Error::QueryCancelled { .. } => info!(?err, msg),
             Error::Optimize { .. }
             | Error::Planning { .. } | Error::Serialization { .. } => warn!(?err, msg),
         }
@@ -107,23 +185,97 @@ impl Error {
             Self::DatabaseNotFound { .. } => Status::not_found(self.to_string()),
             Self::Query { .. } => Status::internal(self.to_string()),
             Self::InvalidDatabaseName { .. } => Status::invalid_argument(self.to_string()),
+            Self::Planning {
+                source: DataFusionError::External(e),
+            } if SnapshotExpired::is_cause_of(e.as_ref()) => {
+                Status::failed_precondition(self.to_string())
+            }
             Self::Planning {
                 source: service_common::planner::Error::External(_),
             } => Status::internal(self.to_string()),
             Self::Planning { .. } => Status::invalid_argument(self.to_string()),
             Self::Optimize { .. } => Status::internal(self.to_string()),
             Self::Serialization { .. } => Status::internal(self.to_string()),
+            Self::QueryTimeout { .. } => Status::deadline_exceeded(self.to_string()),
+            Self::QueryCancelled { .. } => Status::cancelled(self.to_string()),
+            Self::UnknownAction { .. } => Status::invalid_argument(self.to_string()),
+            Self::InvalidQueryId { .. } => Status::invalid_argument(self.to_string()),
+            Self::QueryNotRunning { .. } => Status::not_found(self.to_string()),
+            Self::InvalidSnapshotToken { .. } => Status::invalid_argument(self.to_string()),
+            Self::InvalidPartition { .. } => Status::invalid_argument(self.to_string()),
+            Self::InvalidFlightDescriptor => Status::invalid_argument(self.to_string()),
+            Self::QueueFull { .. } => Status::resource_exhausted(self.to_string()),
+            Self::RateLimited { retry_after, .. } => {
+                let mut status = Status::resource_exhausted(self.to_string());
+                let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+                status
+                    .metadata_mut()
+                    .insert(RETRY_AFTER_HEADER, AsciiMetadataValue::from(secs));
+                status
+            }
         }
     }
 }
 
 type TonicStream<T> = Pin<Box<dyn Stream<Item = Result<T, tonic::Status>> + Send + Sync + 'static>>;
 
+/// Language of a query.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum QueryType {
+    Sql,
+    InfluxQL,
+}
+
+impl Default for QueryType {
+    fn default() -> Self {
+        Self::Sql
+    }
+}
+
+impl QueryType {
+    /// Name of the query type, as recorded in the query log.
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Sql => "sql",
+            Self::InfluxQL => "influxql",
+        }
+    }
+}
+
 #[derive(Deserialize, Debug)]
 /// Body of the `Ticket` serialized and sent to the do_get endpoint.
 struct ReadInfo {
     database_name: String,
+    /// Query text, in the language given by `query_type`.
     sql_query: String,
+    #[serde(default)]
+    query_type: QueryType,
+    #[serde(default)]
+    timeout_millis: u64,
+    /// Bind parameters, only supported by protobuf tickets.
+    #[serde(skip)]
+    params: Vec<QueryParam>,
+    /// Snapshot token of a previous query to pin this query to, empty if unset.
+    #[serde(default)]
+    snapshot_token: String,
+    /// Number of partitions the result is split into, zero if unpartitioned. Only supported by
+    /// protobuf tickets.
+    #[serde(skip)]
+    partition_count: u32,
+    /// Partition of the result to return.
+    #[serde(skip)]
+    partition: u32,
+}
+
+/// Output partitions of a query plan returned by a single partitioned read.
+///
+/// A query split into `count` partitions returns the output partitions `index`, `index + count`,
+/// `index + 2 * count`, ... of its plan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ReadPartition {
+    index: usize,
+    count: usize,
 }
 
 impl ReadInfo {
@@ -136,15 +288,69 @@ impl ReadInfo {
         Ok(read_info)
     }
 
+    /// Timeout requested by the client, if any.
+    fn timeout(&self) -> Option<Duration> {
+        Some(self.timeout_millis)
+            .filter(|millis| *millis > 0)
+            .map(Duration::from_millis)
+    }
+
     fn decode_protobuf(ticket: &[u8]) -> Result<Self> {
         let read_info =
             proto::ReadInfo::decode(Bytes::from(ticket.to_vec())).context(InvalidTicketSnafu {})?;
 
+        let query_type = match read_info.query_type() {
+            proto::read_info::QueryType::Unspecified | proto::read_info::QueryType::Sql => {
+                QueryType::Sql
+            }
+            proto::read_info::QueryType::Influxql => QueryType::InfluxQL,
+        };
+
         Ok(Self {
             database_name: read_info.namespace_name,
             sql_query: read_info.sql_query,
+            query_type,
+            timeout_millis: read_info.timeout_millis,
+            params: read_info.params.into_iter().map(query_param).collect(),
+            snapshot_token: read_info.snapshot_token,
+            partition_count: read_info.partition_count,
+            partition: read_info.partition,
         })
     }
+
+    /// Partition of the result requested by the client, `None` for the entire result.
+    fn partition(&self) -> Result<Option<ReadPartition>> {
+        match (self.partition, self.partition_count) {
+            (_, 0) => Ok(None),
+            (index, count) if index < count => Ok(Some(ReadPartition {
+                index: index as usize,
+                count: count as usize,
+            })),
+            (partition, partition_count) => InvalidPartitionSnafu {
+                partition,
+                partition_count,
+            }
+            .fail(),
+        }
+    }
+}
+
+fn query_param(param: proto::QueryParam) -> QueryParam {
+    use proto::query_param::Value;
+
+    let value = match param.value {
+        None => ParamValue::Null,
+        Some(Value::Bool(v)) => ParamValue::Boolean(v),
+        Some(Value::Int64(v)) => ParamValue::Integer(v),
+        Some(Value::Uint64(v)) => ParamValue::UInteger(v),
+        Some(Value::Float64(v)) => ParamValue::Float(v),
+        Some(Value::String(v)) => ParamValue::String(v),
+    };
+
+    QueryParam {
+        name: Some(param.name).filter(|name| !name.is_empty()),
+        value,
+    }
 }
 
 /// Concrete implementation of the gRPC Arrow Flight Service API
@@ -154,39 +360,32 @@ where
     S: QueryDatabaseProvider,
 {
     server: Arc<S>,
+    running_queries: Arc<RunningQueries>,
 }
 
 pub fn make_server<S>(server: Arc<S>) -> FlightServer<impl Flight>
 where
     S: QueryDatabaseProvider,
 {
-    FlightServer::new(FlightService { server })
+    FlightServer::new(FlightService::new(server))
 }
 
-#[tonic::async_trait]
-impl<S> Flight for FlightService<S>
+impl<S> FlightService<S>
 where
     S: QueryDatabaseProvider,
 {
-    type HandshakeStream = TonicStream<HandshakeResponse>;
-    type ListFlightsStream = TonicStream<FlightInfo>;
-    type DoGetStream = TonicStream<FlightData>;
-    type DoPutStream = TonicStream<PutResult>;
-    type DoActionStream = TonicStream<arrow_flight::Result>;
-    type ListActionsStream = TonicStream<ActionType>;
-    type DoExchangeStream = TonicStream<FlightData>;
-
-    async fn get_schema(
-        &self,
-        _request: Request<FlightDescriptor>,
-    ) -> Result<Response<SchemaResult>, tonic::Status> {
-        Err(tonic::Status::unimplemented("Not yet implemented"))
+    fn new(server: Arc<S>) -> Self {
+        Self {
+            server,
+            running_queries: Default::default(),
+        }
     }
 
-    async fn do_get(
+    async fn do_get_inner(
         &self,
         request: Request<Ticket>,
-    ) -> Result<Response<Self::DoGetStream>, tonic::Status> {
+        query_id: QueryId,
+    ) -> Result<GetStream, tonic::Status> {
         let external_span_ctx: Option<RequestLogContext> = request.extensions().get().cloned();
         let span_ctx: Option<SpanContext> = request.extensions().get().cloned();
         let ticket = request.into_inner();
@@ -199,7 +398,61 @@ where
                 ReadInfo::decode_json(&ticket.ticket)?
             }
         };
+        let partition = read_info.partition()?;
 
+        // reject queries exceeding the rate limit of their namespace before doing any work
+        if let Err(e) = self.server.check_rate_limit(&read_info.database_name).await {
+            return Err(Error::RateLimited {
+                database_name: read_info.database_name,
+                retry_after: e.retry_after,
+            }
+            .into());
+        }
+
+        // the timeout covers the entire query, including waiting for the semaphore and planning
+        let deadline = self
+            .server
+            .query_timeout(&read_info.database_name, read_info.timeout())
+            .await
+            .map(QueryDeadline::new);
+
+        with_deadline(
+            deadline,
+            self.do_get_planned(
+                read_info,
+                partition,
+                query_id,
+                deadline,
+                span_ctx,
+                external_span_ctx,
+            ),
+        )
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn do_get_planned(
+        &self,
+        read_info: ReadInfo,
+        partition: Option<ReadPartition>,
+        query_id: QueryId,
+        deadline: Option<QueryDeadline>,
+        span_ctx: Option<SpanContext>,
+        external_span_ctx: Option<RequestLogContext>,
+    ) -> Result<GetStream, tonic::Status> {
+        // wait for a slot of the namespace first, so that a namespace with many queued queries
+        // does not hold up the queries of other namespaces at the global semaphore
+        let namespace_permit = self
+            .server
+            .acquire_namespace_semaphore(
+                &read_info.database_name,
+                span_ctx.child_span("namespace query semaphore"),
+            )
+            .await
+            .map_err(|e| Error::QueueFull {
+                database_name: read_info.database_name.clone(),
+                max_queued: e.max_queued,
+            })?;
         let permit = self
             .server
             .acquire_semaphore(span_ctx.child_span("query rate limit semaphore"))
@@ -207,10 +460,51 @@ where
         info!(
             db_name=%read_info.database_name,
             sql_query=%read_info.sql_query,
+            query_type=%read_info.query_type.name(),
             trace=%external_span_ctx.format_jaeger(),
+            %query_id,
+            ?partition,
             "flight do_get",
         );
 
+        let (db, ctx) = self.query_context(&read_info, query_id, span_ctx).await?;
+        let query_completed_token = db.record_query(
+            &ctx,
+            read_info.query_type.name(),
+            Box::new(read_info.sql_query.clone()),
+        );
+
+        let physical_plan = plan_query(&ctx, &read_info).await?;
+        // the result cache only serves entire results
+        let physical_plan = match partition {
+            None => db.cached_plan(physical_plan),
+            Some(_) => physical_plan,
+        };
+
+        let output = GetStream::new(
+            ctx,
+            physical_plan,
+            partition,
+            read_info.database_name,
+            query_id,
+            query_completed_token,
+            permit,
+            namespace_permit,
+            deadline,
+            &self.running_queries,
+        )
+        .await?;
+
+        Ok(output)
+    }
+
+    /// Look up the namespace of `read_info` and create a context for its query.
+    async fn query_context(
+        &self,
+        read_info: &ReadInfo,
+        query_id: QueryId,
+        span_ctx: Option<SpanContext>,
+    ) -> Result<(Arc<S::Db>, IOxSessionContext), tonic::Status> {
         let database =
             DatabaseName::new(&read_info.database_name).context(InvalidDatabaseNameSnafu)?;
 
@@ -220,25 +514,273 @@ where
             .await
             .ok_or_else(|| tonic::Status::not_found(format!("Unknown namespace: {database}")))?;
 
-        let ctx = db.new_query_context(span_ctx);
-        let query_completed_token =
-            db.record_query(&ctx, "sql", Box::new(read_info.sql_query.clone()));
+        let mut ctx = db.new_query_context(span_ctx).with_query_id(query_id);
+        if !read_info.snapshot_token.is_empty() {
+            let token = read_info
+                .snapshot_token
+                .parse()
+                .context(InvalidSnapshotTokenSnafu)?;
+            ctx = ctx.with_query_snapshot(QuerySnapshot::pinned(token));
+        }
 
-        let physical_plan = Planner::new(&ctx)
-            .sql(&read_info.sql_query)
-            .await
-            .context(PlanningSnafu)?;
+        Ok((db, ctx))
+    }
 
-        let output = GetStream::new(
-            ctx,
-            physical_plan,
-            read_info.database_name,
-            query_completed_token,
-            permit,
-        )
-        .await?;
+    /// Split the query of the [`proto::ReadInfo`] in the command of `descriptor` into at most
+    /// `partition_count` partitions (unlimited if zero), one endpoint per partition.
+    ///
+    /// The tickets of all endpoints are pinned to the snapshot the query saw while planning, so
+    /// that the partitions add up to the result of the unpartitioned query.
+    async fn flight_info(
+        &self,
+        descriptor: FlightDescriptor,
+        span_ctx: Option<SpanContext>,
+    ) -> Result<FlightInfo, tonic::Status> {
+        if descriptor.r#type != DescriptorType::Cmd as i32 {
+            return Err(Error::InvalidFlightDescriptor.into());
+        }
+        let request =
+            proto::ReadInfo::decode(descriptor.cmd.as_slice()).context(InvalidTicketSnafu)?;
+        let read_info = ReadInfo::decode_protobuf(&descriptor.cmd)?;
+
+        if let Err(e) = self.server.check_rate_limit(&read_info.database_name).await {
+            return Err(Error::RateLimited {
+                database_name: read_info.database_name,
+                retry_after: e.retry_after,
+            }
+            .into());
+        }
+
+        let query_id = QueryId::new();
+        info!(
+            db_name=%read_info.database_name,
+            sql_query=%read_info.sql_query,
+            query_type=%read_info.query_type.name(),
+            %query_id,
+            "flight get_flight_info",
+        );
+
+        let (_db, ctx) = self.query_context(&read_info, query_id, span_ctx).await?;
+        let physical_plan = plan_query(&ctx, &read_info).await?;
+
+        let mut partition_count = physical_plan.output_partitioning().partition_count().max(1);
+        if read_info.partition_count > 0 {
+            partition_count = partition_count.min(read_info.partition_count as usize);
+        }
+        let snapshot_token = ctx
+            .query_snapshot()
+            .map(|query_snapshot| query_snapshot.token().to_string())
+            .unwrap_or_else(|| request.snapshot_token.clone());
+
+        let endpoint = (0..partition_count)
+            .map(|partition| {
+                let ticket = proto::ReadInfo {
+                    snapshot_token: snapshot_token.clone(),
+                    partition_count: partition_count as u32,
+                    partition: partition as u32,
+                    ..request.clone()
+                };
+                FlightEndpoint {
+                    ticket: Some(Ticket {
+                        ticket: ticket.encode_to_vec(),
+                    }),
+                    // an empty location means that the ticket can be redeemed at this service
+                    location: vec![],
+                }
+            })
+            .collect();
+
+        let schema = optimize_schema(&physical_plan.schema());
+        let options = arrow::ipc::writer::IpcWriteOptions::default();
+        let schema = SchemaResult::from(SchemaAsIpc::new(&schema, &options)).schema;
+
+        Ok(FlightInfo {
+            schema,
+            flight_descriptor: Some(descriptor),
+            endpoint,
+            total_records: -1,
+            total_bytes: -1,
+        })
+    }
+}
+
+/// Plan the query of `read_info`.
+async fn plan_query(
+    ctx: &IOxSessionContext,
+    read_info: &ReadInfo,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    let planner = Planner::new(ctx);
+    match read_info.query_type {
+        QueryType::Sql => {
+            planner
+                .sql(&read_info.sql_query, read_info.params.clone())
+                .await
+        }
+        QueryType::InfluxQL if !read_info.params.is_empty() => {
+            Err(service_common::planner::Error::Plan(
+                "bind parameters are not supported for InfluxQL queries".to_string(),
+            ))
+        }
+        QueryType::InfluxQL => planner.influxql(&read_info.sql_query).await,
+    }
+    .context(PlanningSnafu)
+}
+
+/// Execute the output partitions of `plan` that belong to `partition`, one after the other.
+fn execute_partition(
+    ctx: &IOxSessionContext,
+    plan: Arc<dyn ExecutionPlan>,
+    partition: ReadPartition,
+) -> SendableRecordBatchStream {
+    let schema = plan.schema();
+    let ctx = Arc::new(ctx.child_ctx("execute_partition"));
+    let partitions =
+        (partition.index..plan.output_partitioning().partition_count()).step_by(partition.count);
+
+    let batches = futures::stream::iter(partitions)
+        .then(move |partition| {
+            let ctx = Arc::clone(&ctx);
+            let plan = Arc::clone(&plan);
+            async move {
+                ctx.execute_stream_partitioned(plan, partition)
+                    .await
+                    .map_err(ArrowError::from)
+            }
+        })
+        .try_flatten();
+
+    Box::pin(RecordBatchStreamAdapter::new(schema, batches))
+}
+
+/// Queries whose results are currently streamed to clients.
+///
+/// Clients can cancel them via the [`CANCEL_QUERY_ACTION`].
+#[derive(Debug, Default)]
+struct RunningQueries {
+    queries: Mutex<HashMap<QueryId, oneshot::Sender<()>>>,
+}
+
+impl RunningQueries {
+    /// Register a running query.
+    ///
+    /// The returned receiver resolves once the query is cancelled. The query is unregistered when
+    /// the returned [`RunningQuery`] is dropped.
+    fn register(self: &Arc<Self>, query_id: QueryId) -> (RunningQuery, oneshot::Receiver<()>) {
+        let (tx, rx) = oneshot::channel();
+        self.queries.lock().insert(query_id, tx);
+
+        let running_query = RunningQuery {
+            queries: Arc::clone(self),
+            query_id,
+        };
+        (running_query, rx)
+    }
+
+    /// Cancel a running query. Returns false if the query is not (or no longer) running.
+    fn cancel(&self, query_id: QueryId) -> bool {
+        match self.queries.lock().remove(&query_id) {
+            // the query may finish concurrently, in which case there's nothing left to cancel
+            Some(tx) => tx.send(()).is_ok(),
+            None => false,
+        }
+    }
+}
+
+/// Registration of a query in [`RunningQueries`].
+#[derive(Debug)]
+struct RunningQuery {
+    queries: Arc<RunningQueries>,
+    query_id: QueryId,
+}
+
+impl Drop for RunningQuery {
+    fn drop(&mut self) {
+        self.queries.queries.lock().remove(&self.query_id);
+    }
+}
+
+/// Point in time at which a query is cancelled.
+#[derive(Debug, Clone, Copy)]
+struct QueryDeadline {
+    timeout: Duration,
+    at: Instant,
+}
+
+impl QueryDeadline {
+    fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            at: Instant::now() + timeout,
+        }
+    }
+}
+
+/// Run `fut` until the deadline is reached.
+///
+/// If the deadline passes, `fut` is dropped, which cancels all work (e.g. DataFusion execution and
+/// ingester requests) that it drives.
+async fn with_deadline<F, T, E>(deadline: Option<QueryDeadline>, fut: F) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>>,
+    E: From<Error>,
+{
+    match deadline {
+        Some(deadline) => match tokio::time::timeout_at(deadline.at, fut).await {
+            Ok(res) => res,
+            Err(_) => Err(Error::QueryTimeout {
+                timeout: deadline.timeout,
+            }
+            .into()),
+        },
+        None => fut.await,
+    }
+}
+
+#[tonic::async_trait]
+impl<S> Flight for FlightService<S>
+where
+    S: QueryDatabaseProvider,
+{
+    type HandshakeStream = TonicStream<HandshakeResponse>;
+    type ListFlightsStream = TonicStream<FlightInfo>;
+    type DoGetStream = TonicStream<FlightData>;
+    type DoPutStream = TonicStream<PutResult>;
+    type DoActionStream = TonicStream<arrow_flight::Result>;
+    type ListActionsStream = TonicStream<ActionType>;
+    type DoExchangeStream = TonicStream<FlightData>;
+
+    async fn get_schema(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<SchemaResult>, tonic::Status> {
+        Err(tonic::Status::unimplemented("Not yet implemented"))
+    }
 
-        Ok(Response::new(Box::pin(output) as Self::DoGetStream))
+    async fn do_get(
+        &self,
+        request: Request<Ticket>,
+    ) -> Result<Response<Self::DoGetStream>, tonic::Status> {
+        let query_id = QueryId::new();
+        let query_id_header: AsciiMetadataValue = query_id
+            .to_string()
+            .parse()
+            .expect("UUID is a valid header value");
+
+        match self.do_get_inner(request, query_id).await {
+            Ok(output) => {
+                let mut response = Response::new(Box::pin(output) as Self::DoGetStream);
+                response
+                    .metadata_mut()
+                    .insert(QUERY_ID_HEADER, query_id_header);
+                Ok(response)
+            }
+            Err(mut status) => {
+                status
+                    .metadata_mut()
+                    .insert(QUERY_ID_HEADER, query_id_header);
+                Err(status)
+            }
+        }
     }
 
     async fn handshake(
@@ -263,9 +805,11 @@ where
 
     async fn get_flight_info(
         &self,
-        _request: Request<FlightDescriptor>,
+        request: Request<FlightDescriptor>,
     ) -> Result<Response<FlightInfo>, tonic::Status> {
-        Err(tonic::Status::unimplemented("Not yet implemented"))
+        let span_ctx: Option<SpanContext> = request.extensions().get().cloned();
+        let flight_info = self.flight_info(request.into_inner(), span_ctx).await?;
+        Ok(Response::new(flight_info))
     }
 
     async fn do_put(
@@ -277,16 +821,43 @@ where
 
     async fn do_action(
         &self,
-        _request: Request<Action>,
+        request: Request<Action>,
     ) -> Result<Response<Self::DoActionStream>, tonic::Status> {
-        Err(tonic::Status::unimplemented("Not yet implemented"))
+        let action = request.into_inner();
+
+        match action.r#type.as_str() {
+            CANCEL_QUERY_ACTION => {
+                let query_id = String::from_utf8_lossy(&action.body);
+                let query_id: QueryId =
+                    query_id.trim().parse().map_err(|_| Error::InvalidQueryId {
+                        query_id: query_id.to_string(),
+                    })?;
+
+                if !self.running_queries.cancel(query_id) {
+                    return Err(Error::QueryNotRunning { query_id }.into());
+                }
+                info!(%query_id, "query cancelled by client");
+
+                let output = futures::stream::empty();
+                Ok(Response::new(Box::pin(output) as Self::DoActionStream))
+            }
+            _ => Err(Error::UnknownAction {
+                action_type: action.r#type,
+            }
+            .into()),
+        }
     }
 
     async fn list_actions(
         &self,
         _request: Request<Empty>,
     ) -> Result<Response<Self::ListActionsStream>, tonic::Status> {
-        Err(tonic::Status::unimplemented("Not yet implemented"))
+        let action_type = ActionType {
+            r#type: CANCEL_QUERY_ACTION.to_string(),
+            description: "Cancel a running query. The body is the ID of the query.".to_string(),
+        };
+        let output = futures::stream::iter(std::iter::once(Ok(action_type)));
+        Ok(Response::new(Box::pin(output) as Self::ListActionsStream))
     }
 
     async fn do_exchange(
@@ -297,6 +868,13 @@ where
     }
 }
 
+/// Streams the results of a query to the client.
+///
+/// The record batches are produced by a background task that drives the DataFusion execution. It is
+/// connected to the client via a channel of size 1, so the execution only progresses as fast as
+/// the client consumes the results, one batch at a time. Dropping the stream (e.g. because the
+/// client disconnected) or cancelling the query via the [`CANCEL_QUERY_ACTION`] aborts the
+/// execution.
 #[pin_project(PinnedDrop)]
 struct GetStream {
     #[pin]
@@ -305,15 +883,25 @@ struct GetStream {
     done: bool,
     #[allow(dead_code)]
     permit: InstrumentedAsyncOwnedSemaphorePermit,
+    #[allow(dead_code)]
+    namespace_permit: Option<InstrumentedAsyncOwnedSemaphorePermit>,
+    #[allow(dead_code)]
+    running_query: RunningQuery,
 }
 
 impl GetStream {
+    #[allow(clippy::too_many_arguments)]
     async fn new(
         ctx: IOxSessionContext,
         physical_plan: Arc<dyn ExecutionPlan>,
+        partition: Option<ReadPartition>,
         database_name: String,
+        query_id: QueryId,
         mut query_completed_token: QueryCompletedToken,
         permit: InstrumentedAsyncOwnedSemaphorePermit,
+        namespace_permit: Option<InstrumentedAsyncOwnedSemaphorePermit>,
+        deadline: Option<QueryDeadline>,
+        running_queries: &Arc<RunningQueries>,
     ) -> Result<Self, tonic::Status> {
         // setup channel
         let (mut tx, rx) = futures::channel::mpsc::channel::<Result<FlightData, tonic::Status>>(1);
@@ -327,75 +915,126 @@ impl GetStream {
 
         // Add response metadata
         let mut bytes = BytesMut::new();
-        let app_metadata = proto::AppMetadata {};
+        let incomplete_reasons = ctx
+            .query_completeness()
+            .map(|query_completeness| query_completeness.reasons())
+            .unwrap_or_default();
+        if !incomplete_reasons.is_empty() {
+            warn!(
+                %query_id,
+                ?incomplete_reasons,
+                "query results may be incomplete",
+            );
+        }
+        let app_metadata = proto::AppMetadata {
+            query_id: query_id.to_string(),
+            incomplete: !incomplete_reasons.is_empty(),
+            incomplete_reasons,
+            snapshot_token: ctx
+                .query_snapshot()
+                .map(|query_snapshot| query_snapshot.token().to_string())
+                .unwrap_or_default(),
+        };
         prost::Message::encode(&app_metadata, &mut bytes).context(SerializationSnafu)?;
         schema_flight_data.app_metadata = bytes.to_vec();
 
-        let mut stream_record_batches = ctx
-            .execute_stream(Arc::clone(&physical_plan))
-            .await
-            .map_err(|e| Box::new(e) as _)
-            .context(QuerySnafu {
-                database_name: &database_name,
-            })?;
+        let mut stream_record_batches = match partition {
+            None => ctx
+                .execute_stream(Arc::clone(&physical_plan))
+                .await
+                .map_err(|e| Box::new(e) as _)
+                .context(QuerySnafu {
+                    database_name: &database_name,
+                })?,
+            Some(partition) => execute_partition(&ctx, Arc::clone(&physical_plan), partition),
+        };
+        if let Some(deadline) = deadline {
+            // once the deadline passes, the stream fails and cancels the query execution
+            stream_record_batches = Box::pin(DeadlineStream::new_with_deadline(
+                stream_record_batches,
+                deadline.at,
+                deadline.timeout,
+            ));
+        }
+
+        let (running_query, cancelled) = running_queries.register(query_id);
 
         let join_handle = tokio::spawn(async move {
-            if tx.send(Ok(schema_flight_data)).await.is_err() {
-                // receiver gone
-                return;
-            }
+            let mut batches_tx = tx.clone();
+            let send_batches = async move {
+                if batches_tx.send(Ok(schema_flight_data)).await.is_err() {
+                    // receiver gone
+                    return;
+                }
+
+                while let Some(batch_or_err) = stream_record_batches.next().await {
+                    match batch_or_err {
+                        Ok(batch) => {
+                            match optimize_record_batch(&batch, Arc::clone(&schema)) {
+                                Ok(batch) => {
+                                    let (flight_dictionaries, flight_batch) =
+                                        arrow_flight::utils::flight_data_from_arrow_batch(
+                                            &batch, &options,
+                                        );
+
+                                    for dict in flight_dictionaries {
+                                        if batches_tx.send(Ok(dict)).await.is_err() {
+                                            // receiver is gone
+                                            return;
+                                        }
+                                    }
 
-            while let Some(batch_or_err) = stream_record_batches.next().await {
-                match batch_or_err {
-                    Ok(batch) => {
-                        match optimize_record_batch(&batch, Arc::clone(&schema)) {
-                            Ok(batch) => {
-                                let (flight_dictionaries, flight_batch) =
-                                    arrow_flight::utils::flight_data_from_arrow_batch(
-                                        &batch, &options,
-                                    );
-
-                                for dict in flight_dictionaries {
-                                    if tx.send(Ok(dict)).await.is_err() {
+                                    if batches_tx.send(Ok(flight_batch)).await.is_err() {
                                         // receiver is gone
                                         return;
                                     }
                                 }
-
-                                if tx.send(Ok(flight_batch)).await.is_err() {
-                                    // receiver is gone
+                                Err(e) => {
+                                    // failure sending here is OK because we're cutting the stream anyways
+                                    batches_tx
+                                        .send(Err(Error::Optimize { source: e }.into()))
+                                        .await
+                                        .ok();
+
+                                    // end stream
                                     return;
                                 }
                             }
-                            Err(e) => {
-                                // failure sending here is OK because we're cutting the stream anyways
-                                tx.send(Err(Error::Optimize { source: e }.into()))
-                                    .await
-                                    .ok();
-
-                                // end stream
-                                return;
-                            }
                         }
-                    }
-                    Err(e) => {
-                        // failure sending here is OK because we're cutting the stream anyways
-                        tx.send(Err(Error::Query {
-                            database_name: database_name.clone(),
-                            source: Box::new(e),
-                        }
-                        .into()))
-                            .await
-                            .ok();
+                        Err(e) => {
+                            let err = match DeadlineExceeded::from_error(&e) {
+                                Some(DeadlineExceeded { timeout }) => {
+                                    Error::QueryTimeout { timeout }
+                                }
+                                None => Error::Query {
+                                    database_name: database_name.clone(),
+                                    source: Box::new(e),
+                                },
+                            };
 
-                        // end stream
-                        return;
+                            // failure sending here is OK because we're cutting the stream anyways
+                            batches_tx.send(Err(err.into())).await.ok();
+
+                            // end stream
+                            return;
+                        }
                     }
                 }
-            }
 
-            // if we get here, all is good
-            query_completed_token.set_success()
+                // if we get here, all is good
+                query_completed_token.set_success()
+            };
+
+            // on cancellation, dropping `send_batches` (and with it the record batch stream)
+            // cancels the query execution
+            let res = tokio::select! {
+                _ = send_batches => Ok(()),
+                Ok(()) = cancelled => Err(Error::QueryCancelled { query_id }),
+            };
+            if let Err(e) = res {
+                // failure sending here is OK because we're cutting the stream anyways
+                tx.send(Err(e.into())).await.ok();
+            }
         });
 
         Ok(Self {
@@ -403,6 +1042,8 @@ impl GetStream {
             join_handle,
             done: false,
             permit,
+            namespace_permit,
+            running_query,
         })
     }
 }
@@ -473,9 +1114,7 @@ mod tests {
         // add some data
         test_storage.db_or_create("my_db").await;
 
-        let service = FlightService {
-            server: Arc::clone(&test_storage),
-        };
+        let service = FlightService::new(Arc::clone(&test_storage));
         let ticket = Ticket {
             ticket: br#"{"database_name": "my_db", "sql_query": "SELECT 1;"}"#.to_vec(),
         };
@@ -582,6 +1221,370 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_query_id() {
+        let test_storage = Arc::new(TestDatabaseStore::default());
+        test_storage.db_or_create("my_db").await;
+
+        let service = FlightService::new(Arc::clone(&test_storage));
+        let ticket = Ticket {
+            ticket: br#"{"database_name": "my_db", "sql_query": "SELECT 1;"}"#.to_vec(),
+        };
+        let response = service.do_get(tonic::Request::new(ticket)).await.unwrap();
+        let query_id: QueryId = response
+            .metadata()
+            .get(QUERY_ID_HEADER)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+
+        // the same ID is returned as part of the schema message
+        let schema_msg = response.into_inner().next().await.unwrap().unwrap();
+        let app_metadata = proto::AppMetadata::decode(schema_msg.app_metadata.as_slice()).unwrap();
+        assert_eq!(app_metadata.query_id, query_id.to_string());
+        assert!(!app_metadata.incomplete);
+        assert!(app_metadata.incomplete_reasons.is_empty());
+
+        // errors carry the ID as well
+        let ticket = Ticket {
+            ticket: br#"{"database_name": "unknown_db", "sql_query": "SELECT 1;"}"#.to_vec(),
+        };
+        let status = service
+            .do_get(tonic::Request::new(ticket))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+        let other_query_id: QueryId = status
+            .metadata()
+            .get(QUERY_ID_HEADER)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_ne!(other_query_id, query_id);
+    }
+
+    #[tokio::test]
+    async fn test_partitioned_query() {
+        let test_storage = Arc::new(TestDatabaseStore::default());
+        test_storage.db_or_create("my_db").await;
+        let service = FlightService::new(Arc::clone(&test_storage));
+
+        let read_info = |partition_count: u32, partition: u32| proto::ReadInfo {
+            namespace_name: String::from("my_db"),
+            sql_query: String::from("SELECT 1 AS x UNION ALL SELECT 2 AS x"),
+            timeout_millis: 0,
+            params: vec![],
+            query_type: proto::read_info::QueryType::Sql.into(),
+            snapshot_token: String::new(),
+            partition_count,
+            partition,
+        };
+        let flight_info = |partition_count: u32| {
+            service.get_flight_info(tonic::Request::new(FlightDescriptor {
+                r#type: DescriptorType::Cmd as i32,
+                cmd: read_info(partition_count, 0).encode_to_vec(),
+                path: vec![],
+            }))
+        };
+
+        // the partitions add up to the entire result
+        let info = flight_info(0).await.unwrap().into_inner();
+        assert!(!info.endpoint.is_empty());
+        assert!(!info.schema.is_empty());
+        let mut n_rows = 0;
+        for (i, endpoint) in info.endpoint.iter().enumerate() {
+            let ticket = endpoint.ticket.clone().unwrap();
+            let ticket_info = proto::ReadInfo::decode(ticket.ticket.as_slice()).unwrap();
+            assert_eq!(ticket_info.partition, i as u32);
+            assert_eq!(ticket_info.partition_count, info.endpoint.len() as u32);
+            n_rows += count_rows(&service, ticket).await;
+        }
+        assert_eq!(n_rows, 2);
+
+        // the number of partitions is capped by the client
+        let info = flight_info(1).await.unwrap().into_inner();
+        assert_eq!(info.endpoint.len(), 1);
+
+        // partitions that the plan does not have are empty
+        let ticket = Ticket {
+            ticket: read_info(100, 99).encode_to_vec(),
+        };
+        assert_eq!(count_rows(&service, ticket).await, 0);
+
+        let status = service
+            .do_get(tonic::Request::new(Ticket {
+                ticket: read_info(2, 2).encode_to_vec(),
+            }))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        let status = service
+            .get_flight_info(tonic::Request::new(FlightDescriptor {
+                r#type: DescriptorType::Path as i32,
+                cmd: vec![],
+                path: vec!["my_db".to_string()],
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    /// Number of rows returned by a `do_get` of `ticket`.
+    async fn count_rows<S: QueryDatabaseProvider>(
+        service: &FlightService<S>,
+        ticket: Ticket,
+    ) -> usize {
+        let mut stream = service
+            .do_get(tonic::Request::new(ticket))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let mut n_rows = 0;
+        while let Some(data) = stream.next().await {
+            let data = data.unwrap();
+            let message = arrow::ipc::root_as_message(&data.data_header).unwrap();
+            if let Some(batch) = message.header_as_record_batch() {
+                n_rows += batch.length() as usize;
+            }
+        }
+        n_rows
+    }
+
+    #[tokio::test]
+    async fn test_running_queries() {
+        let running_queries = Arc::new(RunningQueries::default());
+        let query_id = QueryId::new();
+
+        let (running_query, cancelled) = running_queries.register(query_id);
+        assert!(!running_queries.cancel(QueryId::new()));
+        assert!(running_queries.cancel(query_id));
+        cancelled.await.unwrap();
+
+        // cancelling twice is not possible
+        assert!(!running_queries.cancel(query_id));
+        drop(running_query);
+
+        // finished queries cannot be cancelled
+        let (running_query, _cancelled) = running_queries.register(query_id);
+        drop(running_query);
+        assert!(!running_queries.cancel(query_id));
+        assert!(running_queries.queries.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_query_action() {
+        let test_storage = Arc::new(TestDatabaseStore::default());
+        let service = FlightService::new(Arc::clone(&test_storage));
+
+        let action_types: Vec<_> = service
+            .list_actions(tonic::Request::new(Empty {}))
+            .await
+            .unwrap()
+            .into_inner()
+            .map(|action_type| action_type.unwrap().r#type)
+            .collect()
+            .await;
+        assert_eq!(action_types, vec![CANCEL_QUERY_ACTION.to_string()]);
+
+        let cancel = |body: &[u8]| {
+            service.do_action(tonic::Request::new(Action {
+                r#type: CANCEL_QUERY_ACTION.to_string(),
+                body: body.to_vec(),
+            }))
+        };
+
+        let status = cancel(b"foo").await.err().unwrap();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        let query_id = QueryId::new();
+        let status = cancel(query_id.to_string().as_bytes()).await.err().unwrap();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        let (_running_query, cancelled) = service.running_queries.register(query_id);
+        let results: Vec<_> = cancel(query_id.to_string().as_bytes())
+            .await
+            .unwrap()
+            .into_inner()
+            .collect()
+            .await;
+        assert!(results.is_empty());
+        cancelled.await.unwrap();
+
+        let status = service
+            .do_action(tonic::Request::new(Action {
+                r#type: String::from("Unknown"),
+                body: vec![],
+            }))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[test]
+    fn test_read_info_timeout() {
+        let read_info =
+            ReadInfo::decode_json(br#"{"database_name": "my_db", "sql_query": "SELECT 1;"}"#)
+                .unwrap();
+        assert_eq!(read_info.timeout(), None);
+
+        let read_info = ReadInfo::decode_json(
+            br#"{"database_name": "my_db", "sql_query": "SELECT 1;", "timeout_millis": 1500}"#,
+        )
+        .unwrap();
+        assert_eq!(read_info.timeout(), Some(Duration::from_millis(1500)));
+
+        let ticket = proto::ReadInfo {
+            namespace_name: String::from("my_db"),
+            sql_query: String::from("SELECT 1;"),
+            timeout_millis: 10,
+            params: vec![],
+            query_type: proto::read_info::QueryType::Sql.into(),
+            snapshot_token: String::new(),
+            partition_count: 0,
+            partition: 0,
+        }
+        .encode_to_vec();
+        let read_info = ReadInfo::decode_protobuf(&ticket).unwrap();
+        assert_eq!(read_info.timeout(), Some(Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn test_read_info_params() {
+        let ticket = proto::ReadInfo {
+            namespace_name: String::from("my_db"),
+            sql_query: String::from("SELECT $1, $host, $3;"),
+            timeout_millis: 0,
+            params: vec![
+                proto::QueryParam {
+                    name: String::new(),
+                    value: Some(proto::query_param::Value::Int64(1)),
+                },
+                proto::QueryParam {
+                    name: String::from("host"),
+                    value: Some(proto::query_param::Value::String(String::from("a"))),
+                },
+                proto::QueryParam {
+                    name: String::new(),
+                    value: None,
+                },
+            ],
+            query_type: proto::read_info::QueryType::Sql.into(),
+            snapshot_token: String::new(),
+            partition_count: 0,
+            partition: 0,
+        }
+        .encode_to_vec();
+        let read_info = ReadInfo::decode_protobuf(&ticket).unwrap();
+        assert_eq!(
+            read_info.params,
+            vec![
+                QueryParam::positional(ParamValue::Integer(1)),
+                QueryParam::named("host", ParamValue::String(String::from("a"))),
+                QueryParam::positional(ParamValue::Null),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_info_query_type() {
+        let read_info =
+            ReadInfo::decode_json(br#"{"database_name": "my_db", "sql_query": "SELECT 1;"}"#)
+                .unwrap();
+        assert_eq!(read_info.query_type, QueryType::Sql);
+
+        let read_info = ReadInfo::decode_json(
+            br#"{"database_name": "my_db", "sql_query": "SELECT 1;", "query_type": "influxql"}"#,
+        )
+        .unwrap();
+        assert_eq!(read_info.query_type, QueryType::InfluxQL);
+
+        let ticket = |query_type: proto::read_info::QueryType| {
+            proto::ReadInfo {
+                namespace_name: String::from("my_db"),
+                sql_query: String::from("SELECT f FROM m"),
+                timeout_millis: 0,
+                params: vec![proto::QueryParam {
+                    name: String::new(),
+                    value: Some(proto::query_param::Value::Int64(1)),
+                }],
+                query_type: query_type.into(),
+                snapshot_token: String::new(),
+                partition_count: 0,
+                partition: 0,
+            }
+            .encode_to_vec()
+        };
+        for (query_type, expected) in [
+            (proto::read_info::QueryType::Unspecified, QueryType::Sql),
+            (proto::read_info::QueryType::Sql, QueryType::Sql),
+            (proto::read_info::QueryType::Influxql, QueryType::InfluxQL),
+        ] {
+            let read_info = ReadInfo::decode_protobuf(&ticket(query_type)).unwrap();
+            assert_eq!(read_info.query_type, expected);
+        }
+
+        // InfluxQL queries don't support bind parameters
+        let test_storage = Arc::new(TestDatabaseStore::default());
+        test_storage.db_or_create("my_db").await;
+        let service = FlightService::new(Arc::clone(&test_storage));
+        let status = service
+            .do_get(tonic::Request::new(Ticket {
+                ticket: ticket(proto::read_info::QueryType::Influxql),
+            }))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert!(
+            status
+                .message()
+                .contains("bind parameters are not supported"),
+            "{}",
+            status.message()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_with_deadline() {
+        let res: Result<u8, tonic::Status> = with_deadline(None, async { Ok(1) }).await;
+        assert_eq!(res.unwrap(), 1);
+
+        let deadline = QueryDeadline::new(Duration::from_secs(10));
+        let res: Result<u8, tonic::Status> = with_deadline(Some(deadline), async { Ok(1) }).await;
+        assert_eq!(res.unwrap(), 1);
+
+        let deadline = QueryDeadline::new(Duration::from_millis(1));
+        let status = with_deadline(
+            Some(deadline),
+            futures::future::pending::<Result<(), tonic::Status>>(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::DeadlineExceeded);
+    }
+
+    #[test]
+    fn test_rate_limited_status() {
+        let status = tonic::Status::from(Error::RateLimited {
+            database_name: String::from("my_db"),
+            retry_after: Duration::from_millis(1500),
+        });
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+        assert_eq!(
+            status.metadata().get(RETRY_AFTER_HEADER).unwrap(),
+            &AsciiMetadataValue::from(2u64)
+        );
+    }
+
     /// Assert that given future is pending.
     ///
     /// This will try to poll the future a bit to ensure that it is not stuck in tokios task preemption.
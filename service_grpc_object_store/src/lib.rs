@@ -17,7 +17,7 @@ use generated_types::influxdata::iox::object_store::v1::*;
 use iox_catalog::interface::Catalog;
 use object_store::DynObjectStore;
 use observability_deps::tracing::*;
-use parquet_file::ParquetFilePath;
+use parquet_file::{ParquetFilePath, ParquetPathLayout};
 use std::sync::Arc;
 use tonic::{Request, Response, Status};
 use uuid::Uuid;
@@ -72,13 +72,21 @@ impl object_store_service_server::ObjectStoreService for ObjectStoreService {
             parquet_file.partition_id,
             parquet_file.object_store_id,
         );
-        let path = path.object_store_path();
 
-        let res = self
+        // the file may have been written with any path layout
+        let res = match self
             .object_store
-            .get(&path)
+            .get(&path.object_store_path_with_layout(ParquetPathLayout::V1))
             .await
-            .map_err(|e| Status::unknown(e.to_string()))?;
+        {
+            Err(object_store::Error::NotFound { .. }) => {
+                self.object_store
+                    .get(&path.object_store_path_with_layout(ParquetPathLayout::V2))
+                    .await
+            }
+            res => res,
+        }
+        .map_err(|e| Status::unknown(e.to_string()))?;
 
         let rx = Box::pin(res.into_stream().map(|next| match next {
             Ok(data) => Ok(GetParquetFileByObjectStoreIdResponse {
@@ -96,8 +104,8 @@ mod tests {
     use super::*;
     use bytes::Bytes;
     use data_types::{
-        ColumnId, ColumnSet, CompactionLevel, ParquetFileParams, SequenceNumber, ShardIndex,
-        Timestamp,
+        ColumnId, ColumnSet, ColumnStatsSet, CompactionLevel, ParquetFileParams, SequenceNumber,
+        ShardIndex, Timestamp,
     };
     use generated_types::influxdata::iox::object_store::v1::object_store_service_server::ObjectStoreService;
     use iox_catalog::mem::MemCatalog;
@@ -152,6 +160,7 @@ mod tests {
                 compaction_level: CompactionLevel::Initial,
                 created_at: Timestamp::new(2343),
                 column_set: ColumnSet::new([ColumnId::new(1), ColumnId::new(2)]),
+                column_stats: ColumnStatsSet::default(),
             };
 
             p1 = repos.parquet_files().create(p1params).await.unwrap();
@@ -167,7 +176,7 @@ mod tests {
             p1.partition_id,
             p1.object_store_id,
         );
-        let path = path.object_store_path();
+        let path = path.object_store_path_with_layout(ParquetPathLayout::V2);
 
         let data = Bytes::from_static(b"some data");
 
@@ -2,9 +2,11 @@
 
 use std::{ops::DerefMut, sync::Arc};
 
+use data_types::{ColumnType, Timestamp};
 use generated_types::influxdata::iox::schema::v1::*;
-use iox_catalog::interface::{get_schema_by_name, Catalog};
-use observability_deps::tracing::warn;
+use iox_catalog::interface::{get_schema_by_name, Catalog, Error as CatalogError, RepoCollection};
+use iox_time::TimeProvider;
+use observability_deps::tracing::{info, warn};
 use tonic::{Request, Response, Status};
 
 /// Implementation of the gRPC schema service
@@ -38,6 +40,162 @@ impl schema_service_server::SchemaService for SchemaService {
             .map(Arc::new)?;
         Ok(Response::new(schema_to_proto(schema)))
     }
+
+    async fn start_column_type_migration(
+        &self,
+        request: Request<StartColumnTypeMigrationRequest>,
+    ) -> Result<Response<StartColumnTypeMigrationResponse>, Status> {
+        let mut repos = self.catalog.repositories().await;
+
+        let req = request.into_inner();
+        let column_type = i16::try_from(req.column_type)
+            .ok()
+            .and_then(|t| ColumnType::try_from(t).ok())
+            .ok_or_else(|| {
+                Status::invalid_argument(format!("invalid column type: {}", req.column_type))
+            })?;
+
+        let table_id = get_table_id(repos.deref_mut(), &req.namespace, &req.table).await?;
+        let column = repos
+            .columns()
+            .list_by_table_id(table_id)
+            .await
+            .map_err(catalog_error_to_status)?
+            .into_iter()
+            .find(|c| c.name == req.column)
+            .ok_or_else(|| Status::not_found(format!("column {} not found", req.column)))?;
+
+        let created_at = Timestamp::new(self.catalog.time_provider().now().timestamp_nanos());
+        let migration = repos
+            .columns()
+            .start_type_migration(column.id, column_type, created_at)
+            .await
+            .map_err(catalog_error_to_status)?;
+        info!(
+            namespace=%req.namespace,
+            table=%req.table,
+            column=%req.column,
+            %column_type,
+            migration_id=%migration.id,
+            "started column type migration"
+        );
+
+        let files_remaining = files_remaining(repos.deref_mut(), &migration).await?;
+        Ok(Response::new(StartColumnTypeMigrationResponse {
+            migration: Some(migration_to_proto(&migration, column.name, files_remaining)),
+        }))
+    }
+
+    async fn get_column_type_migrations(
+        &self,
+        request: Request<GetColumnTypeMigrationsRequest>,
+    ) -> Result<Response<GetColumnTypeMigrationsResponse>, Status> {
+        let mut repos = self.catalog.repositories().await;
+
+        let req = request.into_inner();
+        let table_id = get_table_id(repos.deref_mut(), &req.namespace, &req.table).await?;
+        let columns = repos
+            .columns()
+            .list_by_table_id(table_id)
+            .await
+            .map_err(catalog_error_to_status)?;
+        let migrations = repos
+            .columns()
+            .list_type_migrations_by_table_id(table_id)
+            .await
+            .map_err(catalog_error_to_status)?;
+
+        let mut out = Vec::with_capacity(migrations.len());
+        for migration in migrations {
+            let column_name = columns
+                .iter()
+                .find(|c| c.id == migration.column_id)
+                .map(|c| c.name.clone())
+                .unwrap_or_default();
+            let files_remaining = files_remaining(repos.deref_mut(), &migration).await?;
+            out.push(migration_to_proto(&migration, column_name, files_remaining));
+        }
+
+        Ok(Response::new(GetColumnTypeMigrationsResponse {
+            migrations: out,
+        }))
+    }
+}
+
+async fn get_table_id(
+    repos: &mut dyn RepoCollection,
+    namespace: &str,
+    table: &str,
+) -> Result<data_types::TableId, Status> {
+    let namespace = repos
+        .namespaces()
+        .get_by_name(namespace)
+        .await
+        .map_err(catalog_error_to_status)?
+        .ok_or_else(|| Status::not_found(format!("namespace {} not found", namespace)))?;
+    let table = repos
+        .tables()
+        .get_by_namespace_and_name(namespace.id, table)
+        .await
+        .map_err(catalog_error_to_status)?
+        .ok_or_else(|| Status::not_found(format!("table {} not found", table)))?;
+    Ok(table.id)
+}
+
+/// Number of parquet files that were created before the migration started and therefore may
+/// still contain the old column type.
+async fn files_remaining(
+    repos: &mut dyn RepoCollection,
+    migration: &data_types::ColumnTypeMigration,
+) -> Result<i64, Status> {
+    if !migration.is_active() {
+        return Ok(0);
+    }
+
+    let files = repos
+        .parquet_files()
+        .list_by_table_not_to_delete(migration.table_id)
+        .await
+        .map_err(catalog_error_to_status)?;
+    Ok(files
+        .iter()
+        .filter(|f| f.created_at < migration.created_at)
+        .count() as i64)
+}
+
+fn catalog_error_to_status(e: CatalogError) -> Status {
+    match e {
+        CatalogError::ColumnNotFound { .. }
+        | CatalogError::TableNotFound { .. }
+        | CatalogError::NamespaceNotFoundByName { .. }
+        | CatalogError::ColumnTypeMigrationNotFound { .. } => Status::not_found(e.to_string()),
+        CatalogError::ColumnTypeMigrationUnsupported { .. } => {
+            Status::invalid_argument(e.to_string())
+        }
+        CatalogError::ColumnTypeMigrationActive { .. } => {
+            Status::failed_precondition(e.to_string())
+        }
+        e => {
+            warn!(error=%e, "catalog error during column type migration");
+            Status::internal(e.to_string())
+        }
+    }
+}
+
+fn migration_to_proto(
+    migration: &data_types::ColumnTypeMigration,
+    column: String,
+    files_remaining: i64,
+) -> ColumnTypeMigration {
+    ColumnTypeMigration {
+        id: migration.id.get(),
+        column,
+        from_type: migration.from_type as i32,
+        to_type: migration.to_type as i32,
+        created_at: migration.created_at.get(),
+        completed_at: migration.completed_at.map(|t| t.get()),
+        files_remaining,
+    }
 }
 
 fn schema_to_proto(schema: Arc<data_types::NamespaceSchema>) -> GetSchemaResponse {
@@ -79,7 +237,6 @@ fn schema_to_proto(schema: Arc<data_types::NamespaceSchema>) -> GetSchemaRespons
 #[cfg(test)]
 mod tests {
     use super::*;
-    use data_types::ColumnType;
     use generated_types::influxdata::iox::schema::v1::schema_service_server::SchemaService;
     use iox_catalog::mem::MemCatalog;
     use std::sync::Arc;
@@ -138,4 +295,91 @@ mod tests {
             vec![&"schema_test_column".to_string()]
         );
     }
+
+    #[tokio::test]
+    async fn test_column_type_migration() {
+        let catalog = {
+            let metrics = Arc::new(metric::Registry::default());
+            let catalog = Arc::new(MemCatalog::new(metrics));
+            let mut repos = catalog.repositories().await;
+            let topic = repos.topics().create_or_get("franz").await.unwrap();
+            let pool = repos.query_pools().create_or_get("franz").await.unwrap();
+            let namespace = repos
+                .namespaces()
+                .create("namespace_migration_test", "inf", topic.id, pool.id)
+                .await
+                .unwrap();
+            let table = repos
+                .tables()
+                .create_or_get("migration_test_table", namespace.id)
+                .await
+                .unwrap();
+            repos
+                .columns()
+                .create_or_get("field", table.id, ColumnType::I64)
+                .await
+                .unwrap();
+            repos
+                .columns()
+                .create_or_get("tag", table.id, ColumnType::Tag)
+                .await
+                .unwrap();
+            Arc::clone(&catalog)
+        };
+
+        let grpc = super::SchemaService::new(catalog);
+
+        // unsupported type change
+        let status = grpc
+            .start_column_type_migration(Request::new(StartColumnTypeMigrationRequest {
+                namespace: "namespace_migration_test".to_string(),
+                table: "migration_test_table".to_string(),
+                column: "tag".to_string(),
+                column_type: column_schema::ColumnType::F64 as i32,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+
+        // unknown column
+        let status = grpc
+            .start_column_type_migration(Request::new(StartColumnTypeMigrationRequest {
+                namespace: "namespace_migration_test".to_string(),
+                table: "migration_test_table".to_string(),
+                column: "unknown".to_string(),
+                column_type: column_schema::ColumnType::F64 as i32,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::NotFound);
+
+        let migration = grpc
+            .start_column_type_migration(Request::new(StartColumnTypeMigrationRequest {
+                namespace: "namespace_migration_test".to_string(),
+                table: "migration_test_table".to_string(),
+                column: "field".to_string(),
+                column_type: column_schema::ColumnType::F64 as i32,
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .migration
+            .unwrap();
+        assert_eq!(migration.column, "field");
+        assert_eq!(migration.from_type, column_schema::ColumnType::I64 as i32);
+        assert_eq!(migration.to_type, column_schema::ColumnType::F64 as i32);
+        assert_eq!(migration.completed_at, None);
+        assert_eq!(migration.files_remaining, 0);
+
+        let migrations = grpc
+            .get_column_type_migrations(Request::new(GetColumnTypeMigrationsRequest {
+                namespace: "namespace_migration_test".to_string(),
+                table: "migration_test_table".to_string(),
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+            .migrations;
+        assert_eq!(migrations, vec![migration]);
+    }
 }
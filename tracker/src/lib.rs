@@ -9,8 +9,10 @@
 
 mod async_semaphore;
 mod lock;
+mod rate_limit;
 mod task;
 
 pub use async_semaphore::*;
 pub use lock::*;
+pub use rate_limit::*;
 pub use task::*;
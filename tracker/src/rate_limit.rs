@@ -0,0 +1,128 @@
+//! Token-bucket rate limiting keyed by an arbitrary string (e.g. a namespace name).
+use std::{fmt::Display, num::NonZeroU32, sync::Arc, time::Duration};
+
+use hashbrown::HashMap;
+use iox_time::{Time, TimeProvider};
+use parking_lot::Mutex;
+
+/// A request was rejected because the rate limit of its key was exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimited {
+    /// Duration after which the next request will be accepted.
+    pub retry_after: Duration,
+}
+
+impl Display for RateLimited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "rate limit exceeded, retry after {:?}", self.retry_after)
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+/// State of a single token bucket.
+#[derive(Debug)]
+struct Bucket {
+    /// Available tokens, possibly fractional.
+    tokens: f64,
+
+    /// Time at which `tokens` was last refilled.
+    updated: Time,
+}
+
+/// Token-bucket rate limiter with one bucket per key.
+///
+/// Every bucket holds up to one second worth of requests (i.e. bursts up to the limit are
+/// permitted) and is refilled continuously at the configured rate. The limit is passed on every
+/// call so that it can change at runtime without resetting the bucket.
+#[derive(Debug)]
+pub struct RateLimiter {
+    time_provider: Arc<dyn TimeProvider>,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    /// Create new rate limiter with full buckets.
+    pub fn new(time_provider: Arc<dyn TimeProvider>) -> Self {
+        Self {
+            time_provider,
+            buckets: Default::default(),
+        }
+    }
+
+    /// Take a single token from the bucket of `key` that allows `limit` requests per second.
+    pub fn try_acquire(&self, key: &str, limit: NonZeroU32) -> Result<(), RateLimited> {
+        let now = self.time_provider.now();
+        let rate = f64::from(limit.get());
+
+        let mut buckets = self.buckets.lock();
+        if !buckets.contains_key(key) {
+            buckets.insert(
+                key.to_owned(),
+                Bucket {
+                    tokens: rate,
+                    updated: now,
+                },
+            );
+        }
+        let bucket = buckets.get_mut(key).expect("just inserted");
+
+        let elapsed = now
+            .checked_duration_since(bucket.updated)
+            .unwrap_or_default();
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * rate).min(rate);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(RateLimited {
+                retry_after: Duration::from_secs_f64((1.0 - bucket.tokens) / rate),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iox_time::MockProvider;
+
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter() {
+        let time_provider = Arc::new(MockProvider::new(Time::from_timestamp_nanos(0)));
+        let limiter = RateLimiter::new(Arc::clone(&time_provider) as _);
+        let limit = NonZeroU32::new(2).unwrap();
+
+        // burst up to the limit
+        limiter.try_acquire("a", limit).unwrap();
+        limiter.try_acquire("a", limit).unwrap();
+        let err = limiter.try_acquire("a", limit).unwrap_err();
+        assert_eq!(err.retry_after, Duration::from_millis(500));
+
+        // buckets are independent
+        limiter.try_acquire("b", limit).unwrap();
+
+        // refill
+        time_provider.inc(Duration::from_millis(250));
+        let err = limiter.try_acquire("a", limit).unwrap_err();
+        assert_eq!(err.retry_after, Duration::from_millis(250));
+        time_provider.inc(Duration::from_millis(250));
+        limiter.try_acquire("a", limit).unwrap();
+        limiter.try_acquire("a", limit).unwrap_err();
+
+        // refill is capped at the limit
+        time_provider.inc(Duration::from_secs(60));
+        limiter.try_acquire("a", limit).unwrap();
+        limiter.try_acquire("a", limit).unwrap();
+        limiter.try_acquire("a", limit).unwrap_err();
+
+        // the limit may change at runtime
+        let limit = NonZeroU32::new(10).unwrap();
+        time_provider.inc(Duration::from_millis(100));
+        limiter.try_acquire("a", limit).unwrap();
+        limiter.try_acquire("a", limit).unwrap_err();
+    }
+}